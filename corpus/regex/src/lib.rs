@@ -0,0 +1,31 @@
+//! Corpus module: the `regex` crate built for `wasm32-unknown-unknown`.
+//!
+//! The exports take no arguments so the corpus harness can smoke-run them
+//! without a host. Unicode classes are disabled to keep the module small;
+//! patterns use ASCII classes instead.
+
+use regex::Regex;
+
+/// Sum of the years of every date in a fixed text.
+#[no_mangle]
+pub extern "C" fn sum_years() -> i32 {
+    let re = Regex::new(r"([0-9]{4})-([0-9]{2})-([0-9]{2})").unwrap();
+    let text = "2024-01-15, 2025-12-31 and 1999-07-04";
+    re.captures_iter(text)
+        .map(|c| c[1].parse::<i32>().unwrap())
+        .sum()
+}
+
+/// Byte offset of the first word ending in "ing", or -1.
+#[no_mangle]
+pub extern "C" fn find_word() -> i32 {
+    let re = Regex::new(r"(?-u:\b)[a-z]+ing(?-u:\b)").unwrap();
+    re.find("the quick fox is jumping over")
+        .map_or(-1, |m| m.start() as i32)
+}
+
+/// Whether an invalid pattern is rejected (1) rather than accepted (0).
+#[no_mangle]
+pub extern "C" fn rejects_invalid() -> i32 {
+    Regex::new(r"(unclosed").is_err() as i32
+}
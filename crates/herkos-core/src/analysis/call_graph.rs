@@ -0,0 +1,377 @@
+//! Module call graph.
+//!
+//! Edges come from direct `call`s to local functions and imports, and from
+//! `call_indirect`: an indirect call of type `T` may reach every local
+//! function whose type is structurally equal to `T`, which is also the set of
+//! functions the generated dispatch names, and every import of type `T` that
+//! an element segment places in the table. The graph is therefore a sound
+//! over-approximation of what a call can execute, suitable for tree-shaking
+//! and for auditing which exports can reach which imports.
+
+use crate::ir::{ElementFunc, ImportIdx, IrInstr, LocalFuncIdx, ModuleInfo, TypeIdx};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Call graph over the local functions of a module.
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    /// Local functions called directly, per local function.
+    direct: Vec<Vec<usize>>,
+    /// Imports called, per local function.
+    imports: Vec<Vec<usize>>,
+    /// Canonical types called through `call_indirect`, per local function.
+    indirect: Vec<Vec<usize>>,
+    /// Local functions of each canonical type.
+    type_targets: BTreeMap<usize, Vec<usize>>,
+    /// Imports of each canonical type placed in the table.
+    type_imports: BTreeMap<usize, Vec<usize>>,
+    /// Canonical index of each type index.
+    canonical: Vec<usize>,
+}
+
+impl CallGraph {
+    /// Build the call graph of `info`.
+    pub fn build(info: &ModuleInfo) -> Self {
+        let canonical: Vec<usize> = (0..info.type_signatures.len())
+            .map(|idx| info.canonical_type.get(idx).copied().unwrap_or(idx))
+            .collect();
+        let canon = |idx: usize| canonical.get(idx).copied().unwrap_or(idx);
+
+        let mut graph = CallGraph::default();
+        for func in info.element_segments.iter().flat_map(|s| &s.func_indices) {
+            if let ElementFunc::Import { import, type_idx } = func {
+                let imports = graph.type_imports.entry(type_idx.as_usize()).or_default();
+                if !imports.contains(&import.as_usize()) {
+                    imports.push(import.as_usize());
+                }
+            }
+        }
+        for imports in graph.type_imports.values_mut() {
+            imports.sort_unstable();
+        }
+        for (idx, func) in info.ir_functions.iter().enumerate() {
+            graph
+                .type_targets
+                .entry(canon(func.type_idx.as_usize()))
+                .or_default()
+                .push(idx);
+
+            let (mut direct, mut imports, mut indirect) = (Vec::new(), Vec::new(), Vec::new());
+            for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+                match instr {
+                    IrInstr::Call { func_idx, .. } => direct.push(func_idx.as_usize()),
+                    IrInstr::CallImport { import_idx, .. } => imports.push(import_idx.as_usize()),
+                    IrInstr::CallIndirect { type_idx, .. } => {
+                        indirect.push(canon(type_idx.as_usize()))
+                    }
+                    _ => {}
+                }
+            }
+            for list in [&mut direct, &mut imports, &mut indirect] {
+                list.sort_unstable();
+                list.dedup();
+            }
+            graph.direct.push(direct);
+            graph.imports.push(imports);
+            graph.indirect.push(indirect);
+        }
+        graph.canonical = canonical;
+        graph
+    }
+
+    /// Number of local functions.
+    pub fn len(&self) -> usize {
+        self.direct.len()
+    }
+
+    /// Whether the module has no local functions.
+    pub fn is_empty(&self) -> bool {
+        self.direct.is_empty()
+    }
+
+    /// Local functions `func` calls directly, in index order.
+    pub fn direct_callees(&self, func: LocalFuncIdx) -> Vec<LocalFuncIdx> {
+        self.direct[func.as_usize()]
+            .iter()
+            .map(|&idx| LocalFuncIdx::new(idx))
+            .collect()
+    }
+
+    /// Imports `func` calls, directly or through `call_indirect`, in index
+    /// order.
+    pub fn imported_callees(&self, func: LocalFuncIdx) -> Vec<ImportIdx> {
+        let mut out = self.all_imports(func.as_usize());
+        out.sort_unstable();
+        out.dedup();
+        out.into_iter().map(ImportIdx::new).collect()
+    }
+
+    /// Canonical types `func` calls through `call_indirect`, in index order.
+    pub fn indirect_types(&self, func: LocalFuncIdx) -> Vec<TypeIdx> {
+        self.indirect[func.as_usize()]
+            .iter()
+            .map(|&idx| TypeIdx::new(idx))
+            .collect()
+    }
+
+    /// Local functions a `call_indirect` of `type_idx` may reach.
+    pub fn indirect_targets(&self, type_idx: TypeIdx) -> Vec<LocalFuncIdx> {
+        let idx = type_idx.as_usize();
+        let canon = self.canonical.get(idx).copied().unwrap_or(idx);
+        self.type_targets
+            .get(&canon)
+            .into_iter()
+            .flatten()
+            .map(|&idx| LocalFuncIdx::new(idx))
+            .collect()
+    }
+
+    /// Every local function `func` may call, directly or indirectly, in
+    /// index order.
+    pub fn callees(&self, func: LocalFuncIdx) -> Vec<LocalFuncIdx> {
+        let mut out = self.local_callees(func.as_usize());
+        out.sort_unstable();
+        out.dedup();
+        out.into_iter().map(LocalFuncIdx::new).collect()
+    }
+
+    /// Local functions reachable from `roots`, including the roots, in index
+    /// order.
+    pub fn reachable(&self, roots: &[LocalFuncIdx]) -> Vec<LocalFuncIdx> {
+        let seen = self.reach(roots);
+        (0..self.len())
+            .filter(|&idx| seen[idx])
+            .map(LocalFuncIdx::new)
+            .collect()
+    }
+
+    /// Imports reachable from `roots`, in index order.
+    pub fn reachable_imports(&self, roots: &[LocalFuncIdx]) -> Vec<ImportIdx> {
+        let seen = self.reach(roots);
+        let mut out: Vec<usize> = (0..self.len())
+            .filter(|&idx| seen[idx])
+            .flat_map(|idx| self.all_imports(idx))
+            .collect();
+        out.sort_unstable();
+        out.dedup();
+        out.into_iter().map(ImportIdx::new).collect()
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    ///
+    /// Functions are boxes labelled with their name-section name (or
+    /// `func_N`) and export names; imports are ellipses. Indirect calls are
+    /// dashed edges to every possible target.
+    pub fn to_dot(&self, info: &ModuleInfo) -> String {
+        let mut out = String::from("digraph callgraph {\n    node [shape=box];\n");
+        for (idx, import) in info.func_imports.iter().enumerate() {
+            let label = format!("{}.{}", import.module_name, import.func_name);
+            let _ = writeln!(
+                out,
+                "    i{idx} [label=\"{}\", shape=ellipse];",
+                dot_escape(&label)
+            );
+        }
+        for idx in 0..self.len() {
+            let mut label = info
+                .func_symbols
+                .get(idx)
+                .and_then(|s| s.name.clone())
+                .unwrap_or_else(|| format!("func_{idx}"));
+            let exports: Vec<&str> = info
+                .func_exports
+                .iter()
+                .filter(|e| e.func_index.as_usize() == idx)
+                .map(|e| e.name.as_str())
+                .collect();
+            if exports.is_empty() {
+                let _ = writeln!(out, "    f{idx} [label=\"{}\"];", dot_escape(&label));
+            } else {
+                label.push_str(&format!("\nexport {}", exports.join(", ")));
+                let _ = writeln!(
+                    out,
+                    "    f{idx} [label=\"{}\", style=bold];",
+                    dot_escape(&label)
+                );
+            }
+        }
+        for idx in 0..self.len() {
+            for callee in &self.direct[idx] {
+                let _ = writeln!(out, "    f{idx} -> f{callee};");
+            }
+            for import in &self.imports[idx] {
+                let _ = writeln!(out, "    f{idx} -> i{import};");
+            }
+            for ty in &self.indirect[idx] {
+                for callee in self.type_targets.get(ty).into_iter().flatten() {
+                    let _ = writeln!(out, "    f{idx} -> f{callee} [style=dashed];");
+                }
+                for import in self.type_imports.get(ty).into_iter().flatten() {
+                    let _ = writeln!(out, "    f{idx} -> i{import} [style=dashed];");
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Local callees of `idx`, possibly with duplicates.
+    fn local_callees(&self, idx: usize) -> Vec<usize> {
+        let mut out = self.direct[idx].clone();
+        for ty in &self.indirect[idx] {
+            out.extend(self.type_targets.get(ty).into_iter().flatten());
+        }
+        out
+    }
+
+    /// Imports `idx` calls directly or through tables, possibly with
+    /// duplicates.
+    fn all_imports(&self, idx: usize) -> Vec<usize> {
+        let mut out = self.imports[idx].clone();
+        for ty in &self.indirect[idx] {
+            out.extend(self.type_imports.get(ty).into_iter().flatten());
+        }
+        out
+    }
+
+    /// Which local functions are reachable from `roots`.
+    fn reach(&self, roots: &[LocalFuncIdx]) -> Vec<bool> {
+        let mut seen = vec![false; self.len()];
+        let mut stack: Vec<usize> = roots
+            .iter()
+            .map(|r| r.as_usize())
+            .filter(|&idx| idx < self.len())
+            .collect();
+        while let Some(idx) = stack.pop() {
+            if std::mem::replace(&mut seen[idx], true) {
+                continue;
+            }
+            stack.extend(self.local_callees(idx).into_iter().filter(|&c| !seen[c]));
+        }
+        seen
+    }
+}
+
+/// Escape a DOT string literal.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    const WAT: &str = r#"(module
+        (import "env" "log" (func $log (param i32)))
+        (import "env" "now" (func $now (result i32)))
+        (type $t (func (result i32)))
+        (table 1 funcref)
+        (elem (i32.const 0) $answer)
+        (func $answer (type $t) (call $now))
+        (func $other (type $t) (i32.const 1))
+        (func $logged (param i32) (call $log (local.get 0)))
+        (func (export "run") (result i32)
+            (call $logged (i32.const 1))
+            (call_indirect (type $t) (i32.const 0)))
+        (func (export "quiet") (result i32) (call $other)))"#;
+
+    fn graph() -> (CallGraph, ModuleInfo) {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &TranspileOptions::default()).unwrap();
+        (CallGraph::build(&info), info)
+    }
+
+    fn idxs(funcs: Vec<LocalFuncIdx>) -> Vec<usize> {
+        funcs.into_iter().map(|f| f.as_usize()).collect()
+    }
+
+    #[test]
+    fn records_direct_import_and_indirect_edges() {
+        let (graph, _) = graph();
+        let run = LocalFuncIdx::new(3);
+        assert_eq!(graph.len(), 5);
+        assert_eq!(idxs(graph.direct_callees(run)), vec![2]);
+        let types: Vec<usize> = graph
+            .indirect_types(run)
+            .iter()
+            .map(|t| t.as_usize())
+            .collect();
+        assert_eq!(types, vec![0]);
+        // `run` and `quiet` have the same type as `$t`.
+        assert_eq!(
+            idxs(graph.indirect_targets(TypeIdx::new(0))),
+            vec![0, 1, 3, 4]
+        );
+        assert_eq!(idxs(graph.callees(run)), vec![0, 1, 2, 3, 4]);
+        let imports: Vec<usize> = graph
+            .imported_callees(LocalFuncIdx::new(2))
+            .iter()
+            .map(|i| i.as_usize())
+            .collect();
+        assert_eq!(imports, vec![0]);
+    }
+
+    #[test]
+    fn reachability_follows_every_edge_kind() {
+        let (graph, _) = graph();
+        let quiet = LocalFuncIdx::new(4);
+        assert_eq!(idxs(graph.reachable(&[quiet])), vec![1, 4]);
+        assert!(graph.reachable_imports(&[quiet]).is_empty());
+        let run = LocalFuncIdx::new(3);
+        assert_eq!(idxs(graph.reachable(&[run])), vec![0, 1, 2, 3, 4]);
+        let imports: Vec<usize> = graph
+            .reachable_imports(&[run])
+            .iter()
+            .map(|i| i.as_usize())
+            .collect();
+        assert_eq!(imports, vec![0, 1]);
+    }
+
+    #[test]
+    fn indirect_calls_reach_imports_in_the_table() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "double" (func $double (param i32) (result i32)))
+                (import "env" "log" (func $log (param i32)))
+                (table 2 funcref)
+                (elem (i32.const 0) $double $log)
+                (func (export "apply") (param i32) (result i32)
+                    (call_indirect (param i32) (result i32) (i32.const 1) (local.get 0))))"#,
+        )
+        .unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &TranspileOptions::default()).unwrap();
+        let graph = CallGraph::build(&info);
+        let apply = LocalFuncIdx::new(0);
+        let imports: Vec<usize> = graph
+            .imported_callees(apply)
+            .iter()
+            .map(|i| i.as_usize())
+            .collect();
+        assert_eq!(imports, vec![0], "only the import of the called type");
+        assert_eq!(graph.reachable_imports(&[apply]).len(), 1);
+        assert!(graph
+            .to_dot(&info)
+            .contains("    f0 -> i0 [style=dashed];\n"));
+    }
+
+    #[test]
+    fn dot_lists_nodes_and_edges() {
+        let (graph, info) = graph();
+        let dot = graph.to_dot(&info);
+        assert!(dot.starts_with("digraph callgraph {\n"));
+        assert!(dot.contains("    i0 [label=\"env.log\", shape=ellipse];\n"));
+        assert!(dot.contains("    f0 [label=\"answer\"];\n"));
+        assert!(dot.contains("    f3 [label=\"func_3\\nexport run\", style=bold];\n"));
+        assert!(dot.contains("    f3 -> f2;\n"));
+        assert!(dot.contains("    f2 -> i0;\n"));
+        assert!(dot.contains("    f3 -> f0 [style=dashed];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,212 @@
+//! Constant-time functions and the branches left in them.
+//!
+//! Exports selected with `--constant-time`, and every function they reach,
+//! are generated in constant-time form (see
+//! [`ModuleInfo::constant_time`](crate::ir::ModuleInfo::constant_time)):
+//! the optimizer leaves them alone and `select` does not branch. What the
+//! transpiler cannot remove is control flow the source itself has, so
+//! [`ConstantTime`] lists every conditional branch in those functions whose
+//! condition is data-dependent.
+//!
+//! A value is data-dependent when it derives, through any chain of
+//! operations, from a parameter, a memory load, a global or a call result.
+//! Values computed only from constants (a loop counter running to a fixed
+//! bound) are not. The analysis tracks data flow, not control flow, and
+//! does not cover the bounds and trap checks generated for loads, stores
+//! and divisions.
+
+use super::CallGraph;
+use crate::ir::*;
+use crate::optimizer::utils::{for_each_def, for_each_use};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Which functions the `exports` reach, in local index order.
+///
+/// Empty when `exports` is.
+pub fn constant_time_functions(info: &ModuleInfo, exports: &[String]) -> Vec<bool> {
+    if exports.is_empty() {
+        return Vec::new();
+    }
+    let roots: Vec<LocalFuncIdx> = info
+        .func_exports
+        .iter()
+        .filter(|e| exports.contains(&e.name))
+        .map(|e| e.func_index)
+        .collect();
+    let mut selected = vec![false; info.ir_functions.len()];
+    for idx in CallGraph::build(info).reachable(&roots) {
+        selected[idx.as_usize()] = true;
+    }
+    selected
+}
+
+/// How a residual branch picks its successor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchKind {
+    /// Two-way branch (`br_if`, `if`).
+    If,
+    /// Multi-way branch (`br_table`).
+    Table,
+}
+
+/// A branch on a data-dependent condition in a constant-time function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResidualBranch {
+    /// Function containing the branch.
+    pub func: LocalFuncIdx,
+    /// Block the branch ends.
+    pub block: BlockId,
+    /// Two-way or multi-way.
+    pub kind: BranchKind,
+}
+
+impl fmt::Display for ResidualBranch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            BranchKind::If => "conditional branch",
+            BranchKind::Table => "br_table",
+        };
+        write!(
+            f,
+            "func_{} block_{}: {what} on a data-dependent value",
+            self.func.as_usize(),
+            self.block.0
+        )
+    }
+}
+
+/// Residual data-dependent branches of the constant-time functions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstantTime {
+    /// Branches in function, then block order.
+    pub branches: Vec<ResidualBranch>,
+}
+
+impl ConstantTime {
+    /// Find the residual branches of every function
+    /// [`ModuleInfo::is_constant_time`] selects.
+    pub fn compute(info: &ModuleInfo) -> Self {
+        let mut branches = Vec::new();
+        for (idx, func) in info.ir_functions.iter().enumerate() {
+            if !info.is_constant_time(idx) || info.is_merged(idx) {
+                continue;
+            }
+            let dependent = data_dependent_vars(func);
+            for block in &func.blocks {
+                let kind = match &block.terminator {
+                    IrTerminator::BranchIf { condition, .. } if dependent.contains(condition) => {
+                        BranchKind::If
+                    }
+                    IrTerminator::BranchTable { index, .. } if dependent.contains(index) => {
+                        BranchKind::Table
+                    }
+                    _ => continue,
+                };
+                branches.push(ResidualBranch {
+                    func: LocalFuncIdx::new(idx),
+                    block: block.id,
+                    kind,
+                });
+            }
+        }
+        Self { branches }
+    }
+
+    /// Whether the constant-time functions branch only on constants.
+    pub fn is_clean(&self) -> bool {
+        self.branches.is_empty()
+    }
+}
+
+/// Variables of `func` that derive from its inputs: parameters, loads,
+/// globals and call results, and whatever is computed from them.
+///
+/// Iterates to a fixed point, as lowered IR assigns variables in several
+/// blocks and loops carry values around back edges.
+fn data_dependent_vars(func: &IrFunction) -> HashSet<VarId> {
+    let mut dependent: HashSet<VarId> = func.params.iter().map(|(v, _)| *v).collect();
+    loop {
+        let mut changed = false;
+        for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+            let mut dests = Vec::new();
+            for_each_def(instr, |v| dests.push(v));
+            if dests.iter().all(|v| dependent.contains(v)) {
+                continue;
+            }
+            let source = matches!(
+                instr,
+                IrInstr::Load { .. }
+                    | IrInstr::GlobalGet { .. }
+                    | IrInstr::Call { .. }
+                    | IrInstr::CallImport { .. }
+                    | IrInstr::CallIndirect { .. }
+            );
+            let mut derived = false;
+            for_each_use(instr, |v| derived |= dependent.contains(&v));
+            if source || derived {
+                dependent.extend(dests);
+                changed = true;
+            }
+        }
+        if !changed {
+            return dependent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile_to_artifacts, TranspileOptions};
+
+    #[test]
+    fn branches_on_inputs_are_reported_and_counted_loops_are_not() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func $helper (param i32) (result i32)
+                    (if (result i32) (local.get 0)
+                        (then (i32.const 1))
+                        (else (i32.const 2))))
+                (func (export "check") (param i32 i32) (result i32)
+                    (local $i i32)
+                    (loop $l
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $l (i32.lt_u (local.get $i) (i32.const 16))))
+                    (call $helper
+                        (select (local.get 0) (local.get 1) (i32.eq (local.get 0) (local.get 1)))))
+                (func (export "other") (param i32) (result i32)
+                    (if (result i32) (local.get 0)
+                        (then (i32.const 1))
+                        (else (i32.const 2)))))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            constant_time: vec!["check".to_string()],
+            ..TranspileOptions::default()
+        };
+        let artifacts = transpile_to_artifacts(&wasm, &options).unwrap();
+
+        assert_eq!(artifacts.module_info.constant_time, [true, true, false]);
+        let report = &artifacts.reports.constant_time;
+        let funcs: Vec<usize> = report.branches.iter().map(|b| b.func.as_usize()).collect();
+        assert_eq!(funcs, [0], "{:?}", report.branches);
+        assert!(report.branches[0]
+            .to_string()
+            .starts_with("func_0 block_0: conditional branch"));
+
+        let code = &artifacts.rust_code;
+        assert!(code.contains("= ct_select(v"));
+        assert!(!code.contains("= if "), "selects must not branch");
+    }
+
+    #[test]
+    fn unknown_exports_are_rejected() {
+        let wasm = wat::parse_str(r#"(module (func (export "f")))"#).unwrap();
+        let options = TranspileOptions {
+            constant_time: vec!["g".to_string()],
+            ..TranspileOptions::default()
+        };
+        let err = transpile_to_artifacts(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("--constant-time: no function export named `g`"));
+    }
+}
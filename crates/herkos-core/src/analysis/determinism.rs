@@ -0,0 +1,266 @@
+//! Determinism audit — finds every construct whose observable result may
+//! differ between two executions of the same module with the same inputs.
+//!
+//! Lockstep simulations and consensus systems need bit-identical results on
+//! every machine. Wasm is deterministic except for a short, well-known list:
+//!
+//! - **NaN bit patterns**: arithmetic float ops that produce a NaN may return
+//!   any payload and sign (Wasm spec §4.3.3). `abs`, `neg`, `copysign` and the
+//!   reinterpretations are pure bit operations and are *not* flagged.
+//! - **`memory.grow` failure**: whether a grow succeeds depends on the chosen
+//!   `MAX_PAGES`, which is an embedding decision, not a property of the module.
+//! - **Imported clocks and entropy**: host functions that read time or random
+//!   state (WASI `clock_time_get`, `random_get`, and common `env` equivalents).
+//!
+//! The audit is conservative: a flagged float op only matters if the NaN is
+//! later observed through a reinterpretation or a store, but tracking that
+//! precisely is left to the user.
+
+use crate::ir::{BinOp, IrInstr, LocalFuncIdx, ModuleInfo, UnOp};
+use std::fmt;
+
+/// Substrings of import names that indicate a clock or entropy source.
+const NONDETERMINISTIC_IMPORT_PATTERNS: &[&str] =
+    &["clock", "time", "random", "rand", "entropy", "getrandom"];
+
+/// What makes a construct nondeterministic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NondeterminismKind {
+    /// Float operation whose NaN result has an unspecified bit pattern.
+    FloatNan { op: String },
+    /// `memory.grow`, whose failure depends on the embedding's page limit.
+    MemoryGrow,
+    /// Imported function that reads a clock or an entropy source.
+    Import {
+        module_name: String,
+        func_name: String,
+    },
+}
+
+/// A single nondeterminism finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NondeterminismSource {
+    /// Function containing the construct, or `None` for module-level findings
+    /// (imports are reported once, regardless of how many call sites exist).
+    pub func: Option<LocalFuncIdx>,
+    /// The kind of nondeterminism found.
+    pub kind: NondeterminismKind,
+}
+
+impl fmt::Display for NondeterminismSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(func) = self.func {
+            write!(f, "func_{}: ", func.as_usize())?;
+        }
+        match &self.kind {
+            NondeterminismKind::FloatNan { op } => {
+                write!(
+                    f,
+                    "{} may produce a NaN with an unspecified bit pattern",
+                    op
+                )
+            }
+            NondeterminismKind::MemoryGrow => {
+                write!(
+                    f,
+                    "memory.grow failure depends on the configured page limit"
+                )
+            }
+            NondeterminismKind::Import {
+                module_name,
+                func_name,
+            } => write!(
+                f,
+                "import {}.{} looks like a clock or entropy source",
+                module_name, func_name
+            ),
+        }
+    }
+}
+
+/// Collect every nondeterminism source in `info`.
+///
+/// Import findings come first (in import order), then per-function findings
+/// in function order. Within a function each distinct kind is reported once.
+pub fn audit_determinism(info: &ModuleInfo) -> Vec<NondeterminismSource> {
+    let mut sources = Vec::new();
+
+    for import in &info.func_imports {
+        if is_nondeterministic_import(&import.func_name) {
+            sources.push(NondeterminismSource {
+                func: None,
+                kind: NondeterminismKind::Import {
+                    module_name: import.module_name.to_string(),
+                    func_name: import.func_name.to_string(),
+                },
+            });
+        }
+    }
+
+    for (idx, func) in info.ir_functions.iter().enumerate() {
+        let mut kinds: Vec<NondeterminismKind> = Vec::new();
+        for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+            let kind = match instr {
+                IrInstr::BinOp { op, .. } if binop_may_produce_nan(*op) => {
+                    NondeterminismKind::FloatNan {
+                        op: format!("{:?}", op),
+                    }
+                }
+                IrInstr::UnOp { op, .. } if unop_may_produce_nan(*op) => {
+                    NondeterminismKind::FloatNan {
+                        op: format!("{:?}", op),
+                    }
+                }
+                IrInstr::MemoryGrow { .. } => NondeterminismKind::MemoryGrow,
+                _ => continue,
+            };
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        sources.extend(kinds.into_iter().map(|kind| NondeterminismSource {
+            func: Some(LocalFuncIdx::new(idx)),
+            kind,
+        }));
+    }
+
+    sources
+}
+
+/// Whether an import name matches a known clock/entropy pattern.
+fn is_nondeterministic_import(func_name: &str) -> bool {
+    let name = func_name.to_ascii_lowercase();
+    NONDETERMINISTIC_IMPORT_PATTERNS
+        .iter()
+        .any(|pattern| name.contains(pattern))
+}
+
+/// Float binary ops that may return a NaN with an unspecified payload.
+fn binop_may_produce_nan(op: BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::F32Add
+            | BinOp::F32Sub
+            | BinOp::F32Mul
+            | BinOp::F32Div
+            | BinOp::F32Min
+            | BinOp::F32Max
+            | BinOp::F64Add
+            | BinOp::F64Sub
+            | BinOp::F64Mul
+            | BinOp::F64Div
+            | BinOp::F64Min
+            | BinOp::F64Max
+    )
+}
+
+/// Float unary ops that may return a NaN with an unspecified payload.
+fn unop_may_produce_nan(op: UnOp) -> bool {
+    matches!(
+        op,
+        UnOp::F32Ceil
+            | UnOp::F32Floor
+            | UnOp::F32Trunc
+            | UnOp::F32Nearest
+            | UnOp::F32Sqrt
+            | UnOp::F64Ceil
+            | UnOp::F64Floor
+            | UnOp::F64Trunc
+            | UnOp::F64Nearest
+            | UnOp::F64Sqrt
+            | UnOp::F32DemoteF64
+            | UnOp::F64PromoteF32
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn audit(wat: &str) -> Vec<NondeterminismSource> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &TranspileOptions::default()).unwrap();
+        audit_determinism(&info)
+    }
+
+    #[test]
+    fn integer_module_is_deterministic() {
+        let sources = audit(
+            r#"(module
+                (func (param i32 i32) (result i32)
+                    local.get 0
+                    local.get 1
+                    i32.add))"#,
+        );
+        assert!(sources.is_empty());
+    }
+
+    #[test]
+    fn float_arithmetic_is_flagged_once_per_function() {
+        let sources = audit(
+            r#"(module
+                (func (param f32 f32) (result f32)
+                    local.get 0
+                    local.get 1
+                    f32.add
+                    local.get 1
+                    f32.add))"#,
+        );
+        assert_eq!(
+            sources,
+            vec![NondeterminismSource {
+                func: Some(LocalFuncIdx::new(0)),
+                kind: NondeterminismKind::FloatNan {
+                    op: "F32Add".to_string()
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn sign_bit_float_ops_are_not_flagged() {
+        let sources = audit(
+            r#"(module
+                (func (param f64 f64) (result f64)
+                    local.get 0
+                    f64.neg
+                    f64.abs
+                    local.get 1
+                    f64.copysign))"#,
+        );
+        assert!(sources.is_empty());
+    }
+
+    #[test]
+    fn memory_grow_is_flagged() {
+        let sources = audit(
+            r#"(module
+                (memory 1)
+                (func (param i32) (result i32)
+                    local.get 0
+                    memory.grow))"#,
+        );
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].kind, NondeterminismKind::MemoryGrow);
+    }
+
+    #[test]
+    fn clock_and_random_imports_are_flagged() {
+        let sources = audit(
+            r#"(module
+                (import "wasi_snapshot_preview1" "clock_time_get"
+                    (func (param i32 i64 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "random_get"
+                    (func (param i32 i32) (result i32)))
+                (import "env" "log" (func (param i32))))"#,
+        );
+        assert_eq!(sources.len(), 2);
+        assert!(sources.iter().all(|s| s.func.is_none()));
+        assert!(sources[0].to_string().contains("clock_time_get"));
+        assert!(sources[1].to_string().contains("random_get"));
+    }
+}
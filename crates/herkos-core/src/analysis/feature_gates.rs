@@ -0,0 +1,136 @@
+//! Per-function Cargo features for gated exports.
+//!
+//! A function must be compiled whenever some code that calls it is. Gated
+//! exports seed their feature into every function they reach, through direct
+//! calls or through `call_indirect` (whose generated dispatch names every
+//! function of the called type). Ungated exports and functions no export
+//! reaches stay callable through their public methods, so they and everything
+//! they reach are always compiled.
+
+use super::CallGraph;
+use crate::ir::{LocalFuncIdx, ModuleInfo};
+use std::collections::BTreeSet;
+
+/// Features that compile in each local function, in local index order.
+///
+/// An empty list means the function is always compiled. Every function is
+/// always compiled when `info.export_features` is empty.
+pub fn function_features(info: &ModuleInfo) -> Vec<Vec<String>> {
+    let count = info.ir_functions.len();
+    if info.export_features.is_empty() {
+        return vec![Vec::new(); count];
+    }
+    let graph = CallGraph::build(info);
+
+    // Features of the gated exports reaching each function.
+    let mut features: Vec<BTreeSet<&str>> = vec![BTreeSet::new(); count];
+    let mut reached = vec![false; count];
+    let mut always = vec![false; count];
+    let mut always_roots = Vec::new();
+    for export in &info.func_exports {
+        let root = export.func_index;
+        if root.as_usize() >= count {
+            continue;
+        }
+        match info.export_features.get(&export.name) {
+            Some(feature) => {
+                for idx in graph.reachable(&[root]) {
+                    features[idx.as_usize()].insert(feature);
+                    reached[idx.as_usize()] = true;
+                }
+            }
+            None => always_roots.push(root),
+        }
+    }
+    always_roots.extend(
+        (0..count)
+            .filter(|&idx| !reached[idx])
+            .map(LocalFuncIdx::new),
+    );
+    for idx in graph.reachable(&always_roots) {
+        always[idx.as_usize()] = true;
+    }
+
+    features
+        .into_iter()
+        .zip(always)
+        .map(|(set, always)| {
+            if always {
+                Vec::new()
+            } else {
+                set.into_iter().map(str::to_string).collect()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::features::FeatureConfig;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn gated(wat: &str, features: &str) -> Vec<Vec<String>> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        let options = TranspileOptions {
+            features: FeatureConfig::parse(features).unwrap(),
+            ..Default::default()
+        };
+        let info = build_module_info(&parsed, &options).unwrap();
+        info.func_features
+    }
+
+    #[test]
+    fn features_follow_direct_and_indirect_calls() {
+        let features = gated(
+            r#"(module
+                (type $t (func (result i64)))
+                (table 1 funcref)
+                (elem (i32.const 0) $seven)
+                (func $shared (param i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+                (func $seven (type $t) (i64.const 7))
+                (func (export "inc") (param i32) (result i32) (call $shared (local.get 0)))
+                (func (export "pick") (result i64)
+                    (drop (call $shared (i32.const 0)))
+                    (call_indirect (type $t) (i32.const 0)))
+                (func (export "always") (result i32) (i32.const 7)))"#,
+            "export inc math\nexport pick table",
+        );
+        let math = || "math".to_string();
+        let table = || "table".to_string();
+        assert_eq!(
+            features,
+            vec![
+                vec![math(), table()],
+                // A `call_indirect` target of `pick`'s dispatch.
+                vec![table()],
+                vec![math()],
+                vec![table()],
+                vec![],
+            ]
+        );
+    }
+
+    #[test]
+    fn ungated_and_unreached_functions_are_always_compiled() {
+        let features = gated(
+            r#"(module
+                (func $helper (result i32) (i32.const 1))
+                (func $orphan (result i32) (call $helper))
+                (func (export "a") (result i32) (call $helper))
+                (func (export "b") (result i32) (i32.const 2)))"#,
+            "export a fa\nexport b fb",
+        );
+        assert_eq!(
+            features,
+            vec![
+                vec![],
+                vec![],
+                vec!["fa".to_string()],
+                vec!["fb".to_string()]
+            ]
+        );
+    }
+}
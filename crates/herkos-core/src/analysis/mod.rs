@@ -0,0 +1,33 @@
+//! Whole-module analyses over the IR.
+//!
+//! Unlike the optimizer, analyses never rewrite the IR — they inspect a
+//! [`ModuleInfo`](crate::ir::ModuleInfo) and report facts about it. The
+//! transpiler uses some of them to enforce [`TranspileOptions`](crate::TranspileOptions)
+//! policies; library users can call them directly.
+
+mod call_graph;
+pub use call_graph::*;
+
+mod constant_time;
+pub use constant_time::*;
+
+mod determinism;
+pub use determinism::*;
+
+mod tinygo;
+pub use tinygo::*;
+
+mod feature_gates;
+pub use feature_gates::*;
+
+mod requirements;
+pub use requirements::*;
+
+mod side_effects;
+pub use side_effects::*;
+
+mod stack;
+pub use stack::*;
+
+mod wcet;
+pub use wcet::*;
@@ -0,0 +1,302 @@
+//! What each function needs from its caller.
+//!
+//! A function needs
+//!
+//! - the **host** if it calls an import, reads or writes an imported global,
+//!   or yields (with `--yield-points`);
+//! - **memory** if it loads, stores, or runs a `memory.*` operation;
+//! - the **globals** if it reads or writes a mutable module global;
+//! - the **table** if it runs `call_indirect`;
+//!
+//! either itself or in any function it may call, directly or through
+//! `call_indirect`. Requirements are solved as a fixpoint over the
+//! [`CallGraph`], so recursion and indirect cycles are handled.
+//!
+//! Generated functions all receive `Env<H>` and the module's memory and
+//! table, so their signatures agree with every call site whatever this
+//! reports; the analysis tells which functions, and thus which exports,
+//! really use each of them.
+
+use super::CallGraph;
+use crate::ir::{IrFunction, IrInstr, LocalFuncIdx, ModuleInfo, ResolvedGlobal};
+
+/// What a function uses from its caller's state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Needs {
+    /// Calls imports, touches imported globals, or yields.
+    pub host: bool,
+    /// Accesses linear memory.
+    pub memory: bool,
+    /// Accesses mutable module globals.
+    pub globals: bool,
+    /// Calls through the table.
+    pub table: bool,
+}
+
+impl Needs {
+    /// Everything `self` or `other` needs.
+    pub fn union(self, other: Needs) -> Needs {
+        Needs {
+            host: self.host || other.host,
+            memory: self.memory || other.memory,
+            globals: self.globals || other.globals,
+            table: self.table || other.table,
+        }
+    }
+
+    /// What `func`'s own body needs, not counting its callees.
+    fn own(info: &ModuleInfo, graph: &CallGraph, idx: usize, func: &IrFunction) -> Needs {
+        let mut needs = Needs {
+            host: !graph.imported_callees(LocalFuncIdx::new(idx)).is_empty()
+                || (info.yield_points && !crate::codegen::function::loop_headers(func).is_empty()),
+            ..Needs::default()
+        };
+        for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+            match instr {
+                IrInstr::GlobalGet { index, .. } | IrInstr::GlobalSet { index, .. } => {
+                    match info.resolve_global(*index) {
+                        ResolvedGlobal::Imported(..) => needs.host = true,
+                        ResolvedGlobal::Local(_, g) => needs.globals |= !g.is_const(),
+                    }
+                }
+                IrInstr::Load { .. }
+                | IrInstr::Store { .. }
+                | IrInstr::MemoryCopy { .. }
+                | IrInstr::MemoryFill { .. }
+                | IrInstr::MemoryInit { .. } => {
+                    needs.memory = true;
+                    needs.host |= info.observe_memory;
+                }
+                IrInstr::MemorySize { .. } | IrInstr::MemoryGrow { .. } => needs.memory = true,
+                IrInstr::CallIndirect { .. } => needs.table = true,
+                _ => {}
+            }
+        }
+        needs
+    }
+}
+
+/// Per-function requirements, including those of everything a function may
+/// call.
+#[derive(Debug, Clone, Default)]
+pub struct Requirements {
+    /// Needs of each local function.
+    funcs: Vec<Needs>,
+}
+
+impl Requirements {
+    /// Compute the requirements of every local function of `info`.
+    pub fn compute(info: &ModuleInfo) -> Self {
+        let graph = CallGraph::build(info);
+        let mut funcs: Vec<Needs> = info
+            .ir_functions
+            .iter()
+            .enumerate()
+            .map(|(idx, func)| Needs::own(info, &graph, idx, func))
+            .collect();
+
+        let callees: Vec<Vec<usize>> = (0..graph.len())
+            .map(|idx| {
+                graph
+                    .callees(LocalFuncIdx::new(idx))
+                    .iter()
+                    .map(|c| c.as_usize())
+                    .collect()
+            })
+            .collect();
+        propagate(&mut funcs, &callees);
+        Requirements { funcs }
+    }
+
+    /// Everything `func` may use, itself or through a callee.
+    pub fn needs(&self, func: LocalFuncIdx) -> Needs {
+        self.funcs.get(func.as_usize()).copied().unwrap_or_default()
+    }
+
+    /// Whether `func` may use the host, itself or through a callee.
+    pub fn needs_host(&self, func: LocalFuncIdx) -> bool {
+        self.needs(func).host
+    }
+
+    /// Whether `func` may access memory, itself or through a callee.
+    pub fn needs_memory(&self, func: LocalFuncIdx) -> bool {
+        self.needs(func).memory
+    }
+
+    /// Whether `func` may access mutable globals, itself or through a callee.
+    pub fn needs_globals(&self, func: LocalFuncIdx) -> bool {
+        self.needs(func).globals
+    }
+
+    /// Whether `func` may call through the table, itself or through a callee.
+    pub fn needs_table(&self, func: LocalFuncIdx) -> bool {
+        self.needs(func).table
+    }
+}
+
+/// Add the needs of each function's callees to its own, until nothing
+/// changes.
+fn propagate(needs: &mut [Needs], callees: &[Vec<usize>]) {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (idx, calls) in callees.iter().enumerate() {
+            let merged = calls.iter().fold(needs[idx], |acc, &c| acc.union(needs[c]));
+            if merged != needs[idx] {
+                needs[idx] = merged;
+                changed = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn requirements(wat: &str, options: &TranspileOptions) -> Requirements {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        Requirements::compute(&build_module_info(&parsed, options).unwrap())
+    }
+
+    fn host(reqs: &Requirements, count: usize) -> Vec<bool> {
+        (0..count)
+            .map(|idx| reqs.needs_host(LocalFuncIdx::new(idx)))
+            .collect()
+    }
+
+    #[test]
+    fn host_need_propagates_through_direct_and_indirect_calls() {
+        let reqs = requirements(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (type $t (func (param i32)))
+                (table 1 funcref)
+                (elem (i32.const 0) $writer)
+                (func $writer (type $t) (call $log (local.get 0)))
+                (func $dispatch (param i32) (call_indirect (type $t) (local.get 0) (i32.const 0)))
+                (func $outer (param i32) (call $dispatch (local.get 0)))
+                (func $pure (result i32) (i32.const 1)))"#,
+            &TranspileOptions::default(),
+        );
+        assert_eq!(host(&reqs, 4), vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn imported_globals_tables_and_recursion_need_the_host() {
+        let reqs = requirements(
+            r#"(module
+                (import "env" "g" (global $g i32))
+                (import "env" "double" (func $double (param i32) (result i32)))
+                (table 1 funcref)
+                (elem (i32.const 0) $double)
+                (func $read (result i32) (global.get $g))
+                (func $via_table (param i32) (result i32)
+                    (call_indirect (param i32) (result i32) (local.get 0) (i32.const 0)))
+                (func $even (param i32) (result i32)
+                    (if (result i32) (local.get 0)
+                        (then (call $odd (i32.sub (local.get 0) (i32.const 1))))
+                        (else (call $read))))
+                (func $odd (param i32) (result i32) (call $even (local.get 0)))
+                (func $spin (param i32) (result i32)
+                    (if (result i32) (local.get 0)
+                        (then (call $spin (i32.sub (local.get 0) (i32.const 1))))
+                        (else (i32.const 0)))))"#,
+            &TranspileOptions::default(),
+        );
+        assert_eq!(host(&reqs, 5), vec![true, true, true, true, false]);
+    }
+
+    #[test]
+    fn yield_points_make_loops_need_the_host() {
+        let wat = r#"(module
+            (func $count (param i32)
+                (loop $l (br_if $l (local.tee 0 (i32.sub (local.get 0) (i32.const 1))))))
+            (func $caller (call $count (i32.const 3)))
+            (func $straight (result i32) (i32.const 1)))"#;
+        let reqs = requirements(wat, &TranspileOptions::default());
+        assert_eq!(host(&reqs, 3), vec![false, false, false]);
+        let options = TranspileOptions {
+            yield_points: true,
+            ..Default::default()
+        };
+        let reqs = requirements(wat, &options);
+        assert_eq!(host(&reqs, 3), vec![true, true, false]);
+    }
+
+    #[test]
+    fn observed_memory_accesses_need_the_host() {
+        let wat = r#"(module
+            (memory 1)
+            (func $load (result i32) (i32.load (i32.const 0)))
+            (func $caller (result i32) (call $load))
+            (func $size (result i32) (memory.size)))"#;
+        let reqs = requirements(wat, &TranspileOptions::default());
+        assert_eq!(host(&reqs, 3), vec![false, false, false]);
+        let options = TranspileOptions {
+            observe_memory: true,
+            ..Default::default()
+        };
+        let reqs = requirements(wat, &options);
+        assert_eq!(host(&reqs, 3), vec![true, true, false]);
+    }
+
+    #[test]
+    fn memory_globals_and_table_needs_propagate() {
+        let reqs = requirements(
+            r#"(module
+                (memory 1)
+                (global $counter (mut i32) (i32.const 0))
+                (global $limit i32 (i32.const 10))
+                (type $t (func (result i32)))
+                (table 1 funcref)
+                (elem (i32.const 0) $load)
+                (func $load (type $t) (i32.load (i32.const 0)))
+                (func $bump (global.set $counter (i32.add (global.get $counter) (global.get $limit))))
+                (func $dispatch (result i32) (call_indirect (type $t) (i32.const 0)))
+                (func $all (call $bump) (drop (call $dispatch)))
+                (func $limit (result i32) (global.get $limit)))"#,
+            &TranspileOptions::default(),
+        );
+        let needs = |idx| reqs.needs(LocalFuncIdx::new(idx));
+        assert_eq!(
+            needs(0),
+            Needs {
+                memory: true,
+                ..Needs::default()
+            }
+        );
+        assert_eq!(
+            needs(1),
+            Needs {
+                globals: true,
+                ..Needs::default()
+            }
+        );
+        assert_eq!(
+            needs(2),
+            Needs {
+                memory: true,
+                table: true,
+                ..Needs::default()
+            }
+        );
+        assert_eq!(
+            needs(3),
+            Needs {
+                memory: true,
+                globals: true,
+                table: true,
+                host: false,
+            }
+        );
+        // Immutable globals are constants in the generated code.
+        assert_eq!(needs(4), Needs::default());
+        assert!(reqs.needs_memory(LocalFuncIdx::new(3)) && reqs.needs_table(LocalFuncIdx::new(3)));
+        assert!(!reqs.needs_globals(LocalFuncIdx::new(2)));
+    }
+}
@@ -0,0 +1,81 @@
+//! Which functions have side effects.
+//!
+//! Reports the [`Purity`] the IR records for each function (see
+//! [`herkos_ir::ir::purity`]): impure functions change memory, globals or
+//! call the host; pure ones only compute their results, and total ones
+//! also always return. Pure functions are generated `#[must_use]`, and
+//! unused calls to total ones are deleted by the optimizer.
+
+use crate::ir::*;
+
+/// The purity of every generated function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SideEffects {
+    /// Functions in local index order, without merged duplicates.
+    pub functions: Vec<(LocalFuncIdx, Purity)>,
+}
+
+impl SideEffects {
+    /// Read the purity of each function from [`ModuleInfo::purity`].
+    pub fn compute(info: &ModuleInfo) -> Self {
+        let functions = (0..info.ir_functions.len())
+            .filter(|&idx| !info.is_merged(idx))
+            .map(|idx| (LocalFuncIdx::new(idx), info.purity_of(idx)))
+            .collect();
+        Self { functions }
+    }
+
+    /// Functions that change no state (pure or total).
+    pub fn pure_functions(&self) -> impl Iterator<Item = LocalFuncIdx> + '_ {
+        self.functions
+            .iter()
+            .filter(|(_, purity)| purity.is_pure())
+            .map(|(func, _)| *func)
+    }
+
+    /// One `function<TAB>purity` line per function, named by its symbol
+    /// when the module has one.
+    pub fn to_text(&self, info: &ModuleInfo) -> String {
+        let mut text = String::from("# function\tpurity\n");
+        for (func, purity) in &self.functions {
+            let name = info
+                .func_symbols
+                .get(func.as_usize())
+                .and_then(|s| s.name.clone())
+                .unwrap_or_else(|| format!("func_{}", func.as_usize()));
+            text.push_str(&format!("{name}\t{}\n", purity.as_str()));
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transpile_to_artifacts, TranspileOptions};
+
+    #[test]
+    fn reports_each_function() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory 1)
+                (func $square (param i32) (result i32)
+                    (i32.mul (local.get 0) (local.get 0)))
+                (func $peek (param i32) (result i32) (i32.load (local.get 0)))
+                (func (export "poke") (param i32)
+                    (i32.store (local.get 0) (call $square (call $peek (local.get 0))))))"#,
+        )
+        .unwrap();
+        let artifacts = transpile_to_artifacts(&wasm, &TranspileOptions::default()).unwrap();
+        let report = &artifacts.reports.side_effects;
+        let purity: Vec<Purity> = report.functions.iter().map(|(_, p)| *p).collect();
+        assert_eq!(purity, [Purity::Total, Purity::Pure, Purity::Impure]);
+        assert_eq!(
+            report.to_text(&artifacts.module_info),
+            "# function\tpurity\nsquare\ttotal\npeek\tpure\nfunc_2\timpure\n"
+        );
+        // `square` and `peek` and their accessors; `poke` returns nothing
+        let must_use = "#[must_use = \"the function has no side effects\"]";
+        assert_eq!(artifacts.rust_code.matches(must_use).count(), 4);
+    }
+}
@@ -0,0 +1,407 @@
+//! Static native stack usage.
+//!
+//! Transpiled code keeps each Wasm parameter, local and operand stack slot
+//! of a function in a variable of its native frame, and every Wasm call is a
+//! native call. A [`FrameModel`] turns those counts into bytes; [`StackUsage`]
+//! adds the deepest chain of callees to bound the stack a call to each export
+//! needs, so embedded users can size the threads that run guest code.
+//!
+//! ## Algorithm
+//!
+//! Functions are grouped into the strongly connected components of the call
+//! graph (`call_indirect` reaching every function of its type) and bounded
+//! callees first. A function outside any cycle needs its frame plus its
+//! deepest callee. A cycle of recursive functions needs `depth × (sum of
+//! their frames)` plus the deepest callee outside the cycle, where `depth` is
+//! the annotated maximum nesting ([`StackConfig::recursion_depths`]); without
+//! one it is unbounded, and so is every caller.
+//!
+//! Imports add [`FrameModel::host_call`] below the caller's frame, as a
+//! reserve for the host function itself.
+//!
+//! The estimate is an upper bound on what the IR asks for, not a measurement:
+//! rustc keeps many of these values in registers, and may add spill slots and
+//! temporaries of its own. Calibrate the model against the target.
+
+use super::CallGraph;
+use crate::ir::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Native bytes taken by a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameModel {
+    /// Fixed part of every frame: return address, saved registers, and the
+    /// module and host references passed along.
+    pub frame_overhead: u64,
+    /// Each parameter, local and operand stack slot.
+    pub slot_bytes: u64,
+    /// Stack reserved for a host function called through an import.
+    pub host_call: u64,
+}
+
+impl Default for FrameModel {
+    fn default() -> Self {
+        Self {
+            frame_overhead: 32,
+            slot_bytes: 8,
+            host_call: 256,
+        }
+    }
+}
+
+impl FrameModel {
+    /// Frame of local function `func` of `info`, excluding callees.
+    pub fn frame_bytes(&self, info: &ModuleInfo, func: LocalFuncIdx) -> u64 {
+        let idx = func.as_usize();
+        let vars = info
+            .ir_functions
+            .get(idx)
+            .map_or(0, |f| f.params.len() + f.locals.len());
+        let operands = info.func_symbols.get(idx).map_or(0, |s| s.max_stack_depth);
+        self.frame_overhead + self.slot_bytes * (vars + operands) as u64
+    }
+}
+
+/// Frame model plus recursion depth annotations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackConfig {
+    pub frames: FrameModel,
+    /// Maximum nesting of the call cycle through the named function (export
+    /// or name-section name): how many times each function of the cycle can
+    /// be on the stack at once.
+    pub recursion_depths: BTreeMap<String, u64>,
+}
+
+/// Static upper bound on the stack a call needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackBound {
+    Bytes(u64),
+    /// A call cycle through `func` has no annotated depth; each level of
+    /// the cycle takes `per_level` bytes.
+    Recursion {
+        func: LocalFuncIdx,
+        per_level: u64,
+    },
+}
+
+impl StackBound {
+    /// The bytes, if bounded.
+    pub fn bytes(self) -> Option<u64> {
+        match self {
+            StackBound::Bytes(bytes) => Some(bytes),
+            StackBound::Recursion { .. } => None,
+        }
+    }
+}
+
+/// Stack usage of every local function and export of a module.
+#[derive(Debug, Clone, Default)]
+pub struct StackUsage {
+    frames: Vec<u64>,
+    funcs: Vec<StackBound>,
+    exports: Vec<(String, StackBound)>,
+}
+
+impl StackUsage {
+    /// Bound the stack usage of every function of `info` under `config`.
+    pub fn compute(info: &ModuleInfo, config: &StackConfig) -> Self {
+        let graph = CallGraph::build(info);
+
+        let mut names: HashMap<usize, Vec<&str>> = HashMap::new();
+        for export in &info.func_exports {
+            names
+                .entry(export.func_index.as_usize())
+                .or_default()
+                .push(&export.name);
+        }
+        for (idx, sym) in info.func_symbols.iter().enumerate() {
+            if let Some(name) = &sym.name {
+                names.entry(idx).or_default().push(name);
+            }
+        }
+
+        let frames: Vec<u64> = (0..graph.len())
+            .map(|idx| config.frames.frame_bytes(info, LocalFuncIdx::new(idx)))
+            .collect();
+        let mut funcs = vec![StackBound::Bytes(0); graph.len()];
+        for component in components(&graph) {
+            let members: HashSet<usize> = component.iter().copied().collect();
+            let mut recursive = component.len() > 1;
+            let mut deepest = 0;
+            let mut unbounded = None;
+            for &func in &component {
+                let local = LocalFuncIdx::new(func);
+                if !graph.imported_callees(local).is_empty() {
+                    deepest = deepest.max(config.frames.host_call);
+                }
+                for callee in graph.callees(local) {
+                    let callee = callee.as_usize();
+                    if members.contains(&callee) {
+                        recursive = true;
+                        continue;
+                    }
+                    match funcs[callee] {
+                        StackBound::Bytes(bytes) => deepest = deepest.max(bytes),
+                        bound => {
+                            unbounded.get_or_insert(bound);
+                        }
+                    }
+                }
+            }
+
+            let per_level: u64 = component.iter().map(|&f| frames[f]).sum();
+            let depth = component.iter().find_map(|f| {
+                names
+                    .get(f)
+                    .into_iter()
+                    .flatten()
+                    .find_map(|name| config.recursion_depths.get(*name).copied())
+            });
+            for &func in &component {
+                funcs[func] = match (unbounded, recursive, depth) {
+                    (Some(bound), _, _) => bound,
+                    (None, false, _) => StackBound::Bytes(frames[func] + deepest),
+                    (None, true, Some(depth)) => StackBound::Bytes(depth * per_level + deepest),
+                    (None, true, None) => StackBound::Recursion {
+                        func: LocalFuncIdx::new(component.iter().copied().min().unwrap_or(func)),
+                        per_level,
+                    },
+                };
+            }
+        }
+
+        let exports = info
+            .func_exports
+            .iter()
+            .filter_map(|e| Some((e.name.clone(), *funcs.get(e.func_index.as_usize())?)))
+            .collect();
+        StackUsage {
+            frames,
+            funcs,
+            exports,
+        }
+    }
+
+    /// Frame of local function `func` alone, in bytes.
+    pub fn frame(&self, func: LocalFuncIdx) -> u64 {
+        self.frames[func.as_usize()]
+    }
+
+    /// Bound of a call to local function `func`, callees included.
+    pub fn func(&self, func: LocalFuncIdx) -> StackBound {
+        self.funcs[func.as_usize()]
+    }
+
+    /// Bound of a call to export `name`, if it is a function export.
+    pub fn export(&self, name: &str) -> Option<StackBound> {
+        self.exports
+            .iter()
+            .find_map(|(n, bound)| (n == name).then_some(*bound))
+    }
+
+    /// Every function export with its bound, in export order.
+    pub fn exports(&self) -> &[(String, StackBound)] {
+        &self.exports
+    }
+
+    /// One `export<TAB>bytes` line per function export.
+    pub fn to_text(&self, info: &ModuleInfo) -> String {
+        let func_name = |func: LocalFuncIdx| {
+            info.func_symbols
+                .get(func.as_usize())
+                .and_then(|s| s.name.clone())
+                .unwrap_or_else(|| format!("func_{}", func.as_usize()))
+        };
+        let mut text = String::from("# export\tstack bytes\n");
+        for (name, bound) in &self.exports {
+            let bound = match bound {
+                StackBound::Bytes(bytes) => bytes.to_string(),
+                StackBound::Recursion { func, per_level } => format!(
+                    "unbounded (recursion through {}, {per_level} bytes per level)",
+                    func_name(*func)
+                ),
+            };
+            text.push_str(&format!("{name}\t{bound}\n"));
+        }
+        text
+    }
+}
+
+/// Strongly connected components of the call graph, each after the
+/// components it calls (Tarjan's algorithm).
+fn components(graph: &CallGraph) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let mut index = vec![UNVISITED; graph.len()];
+    let mut low = vec![0; graph.len()];
+    let mut on_stack = vec![false; graph.len()];
+    let mut stack = Vec::new();
+    let mut next = 0;
+    let mut components = Vec::new();
+
+    for root in 0..graph.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        index[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        let mut work: Vec<(usize, Vec<LocalFuncIdx>)> =
+            vec![(root, graph.callees(LocalFuncIdx::new(root)))];
+        while let Some((func, pending)) = work.last_mut() {
+            let func = *func;
+            match pending.pop().map(|f| f.as_usize()) {
+                Some(callee) if index[callee] == UNVISITED => {
+                    index[callee] = next;
+                    low[callee] = next;
+                    next += 1;
+                    stack.push(callee);
+                    on_stack[callee] = true;
+                    work.push((callee, graph.callees(LocalFuncIdx::new(callee))));
+                }
+                Some(callee) => {
+                    if on_stack[callee] {
+                        low[func] = low[func].min(index[callee]);
+                    }
+                }
+                None => {
+                    work.pop();
+                    if let Some((caller, _)) = work.last() {
+                        low[*caller] = low[*caller].min(low[func]);
+                    }
+                    if low[func] == index[func] {
+                        let mut component = Vec::new();
+                        while let Some(member) = stack.pop() {
+                            on_stack[member] = false;
+                            component.push(member);
+                            if member == func {
+                                break;
+                            }
+                        }
+                        components.push(component);
+                    }
+                }
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::TranspileOptions;
+
+    fn info(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        build_module_info(&parsed, &TranspileOptions::default()).unwrap()
+    }
+
+    const FRAMES: FrameModel = FrameModel {
+        frame_overhead: 100,
+        slot_bytes: 10,
+        host_call: 1000,
+    };
+
+    fn config(depths: &[(&str, u64)]) -> StackConfig {
+        StackConfig {
+            frames: FRAMES,
+            recursion_depths: depths.iter().map(|(n, d)| (n.to_string(), *d)).collect(),
+        }
+    }
+
+    #[test]
+    fn frames_count_params_locals_and_operands() {
+        let info = info(
+            r#"(module
+                (func (export "f") (param i32 i32) (result i32)
+                    (local i64)
+                    (i32.add (local.get 0) (i32.mul (local.get 1) (i32.const 3)))))"#,
+        );
+        // Two params, one local, three operands at once
+        assert_eq!(
+            FRAMES.frame_bytes(&info, LocalFuncIdx::new(0)),
+            100 + 10 * 6
+        );
+        let usage = StackUsage::compute(&info, &config(&[]));
+        assert_eq!(usage.export("f"), Some(StackBound::Bytes(160)));
+    }
+
+    #[test]
+    fn the_deepest_callee_chain_counts() {
+        let info = info(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (func $leaf (result i32) (i32.const 1))
+                (func $mid (result i32) (call $leaf))
+                (func $host (call $log (i32.const 0)))
+                (func (export "run") (result i32) (call $host) (call $mid)))"#,
+        );
+        let usage = StackUsage::compute(&info, &config(&[]));
+        let frame = |f| usage.frame(LocalFuncIdx::new(f));
+        let mid = usage.func(LocalFuncIdx::new(1)).bytes().unwrap();
+        assert_eq!(mid, frame(1) + frame(0));
+        let host = usage.func(LocalFuncIdx::new(2)).bytes().unwrap();
+        assert_eq!(host, frame(2) + 1000);
+        // The host call is deeper than `mid -> leaf`
+        assert_eq!(
+            usage.export("run"),
+            Some(StackBound::Bytes(frame(3) + host))
+        );
+    }
+
+    #[test]
+    fn recursion_needs_a_depth() {
+        let wat = r#"(module
+            (func $even (param i32) (result i32)
+                (if (result i32) (local.get 0)
+                    (then (call $odd (i32.sub (local.get 0) (i32.const 1))))
+                    (else (i32.const 1))))
+            (func $odd (param i32) (result i32)
+                (if (result i32) (local.get 0)
+                    (then (call $even (i32.sub (local.get 0) (i32.const 1))))
+                    (else (i32.const 0))))
+            (func (export "is_even") (param i32) (result i32) (call $even (local.get 0))))"#;
+        let info = info(wat);
+        let usage = StackUsage::compute(&info, &config(&[]));
+        let frame = |f| usage.frame(LocalFuncIdx::new(f));
+        let per_level = frame(0) + frame(1);
+        assert_eq!(
+            usage.export("is_even"),
+            Some(StackBound::Recursion {
+                func: LocalFuncIdx::new(0),
+                per_level
+            })
+        );
+        assert_eq!(
+            usage.to_text(&info),
+            format!(
+                "# export\tstack bytes\nis_even\tunbounded (recursion through even, {per_level} bytes per level)\n"
+            )
+        );
+
+        // Any function of the cycle can carry the annotation
+        let usage = StackUsage::compute(&info, &config(&[("odd", 50)]));
+        assert_eq!(
+            usage.export("is_even"),
+            Some(StackBound::Bytes(frame(2) + 50 * per_level))
+        );
+    }
+
+    #[test]
+    fn self_recursion_is_a_cycle() {
+        let info = info(
+            r#"(module
+                (func $fact (export "fact") (param i32) (result i32)
+                    (if (result i32) (local.get 0)
+                        (then (i32.mul (local.get 0) (call $fact (i32.sub (local.get 0) (i32.const 1)))))
+                        (else (i32.const 1)))))"#,
+        );
+        let usage = StackUsage::compute(&info, &config(&[("fact", 12)]));
+        let frame = usage.frame(LocalFuncIdx::new(0));
+        assert_eq!(usage.export("fact"), Some(StackBound::Bytes(12 * frame)));
+    }
+}
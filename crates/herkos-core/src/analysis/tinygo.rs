@@ -0,0 +1,191 @@
+//! TinyGo guest recognition.
+//!
+//! TinyGo's `wasm` target talks to its host through a fixed set of runtime
+//! imports (module `gojs`, or `env` before TinyGo 0.28) plus a few WASI
+//! calls, and expects the host to drive its scheduler:
+//!
+//! 1. call `_start` (or `_initialize` for reactor builds);
+//! 2. whenever the guest called `runtime.sleepTicks(ms)`, call
+//!    `go_scheduler` once the timeout has elapsed;
+//! 3. after the host invoked an exported Go callback, call `resume`.
+//!
+//! A module is recognized as TinyGo when its `producers` section names
+//! TinyGo, imports `runtime.ticks: () -> f64` or `runtime.sleepTicks: (f64)
+//! -> ()` from one of those modules, or exports both `go_scheduler` and
+//! `resume`. The signatures tell TinyGo apart from the gc toolchain's
+//! `GOOS=js` builds, whose `runtime.*` imports take a single stack-pointer
+//! `i32`. `wasip1` builds carry none of the import or export markers, so
+//! without a `producers` section they are treated as plain WASI modules.
+
+use crate::ir::{FuncImport, ModuleInfo, Toolchain, WasmType};
+
+/// Import modules TinyGo's runtime imports come from.
+const TINYGO_IMPORT_MODULES: &[&str] = &["gojs", "env"];
+
+/// WASI import module.
+const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+/// Scheduler entry points of a recognized TinyGo module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TinyGoModule {
+    /// Whether the module imports `runtime.ticks` or `runtime.sleepTicks`.
+    pub uses_clock: bool,
+    /// Entry point export: `_start`, else `_initialize`.
+    pub entry: Option<String>,
+    /// Whether `go_scheduler: () -> ()` is exported.
+    pub has_scheduler: bool,
+    /// Whether `resume: () -> ()` is exported.
+    pub has_resume: bool,
+}
+
+/// Default host behaviour for an import of a TinyGo module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TinyGoImport {
+    /// `runtime.ticks`: read the scheduler clock.
+    Ticks,
+    /// `runtime.sleepTicks`: schedule a `go_scheduler` wakeup.
+    SleepTicks,
+    /// `syscall/js.*`: JavaScript interop, which traps without a JS host.
+    JsInterop,
+    /// WASI `proc_exit`: traps, ending the run.
+    WasiExit,
+    /// Any other WASI call returning an errno: reports `ENOSYS`.
+    WasiUnsupported,
+}
+
+/// Recognize a TinyGo module and find its scheduler entry points.
+pub fn detect_tinygo(info: &ModuleInfo) -> Option<TinyGoModule> {
+    let uses_clock = info.func_imports.iter().any(|import| {
+        matches!(
+            tinygo_import(import),
+            Some(TinyGoImport::Ticks | TinyGoImport::SleepTicks)
+        )
+    });
+    let has_scheduler = exports_nullary(info, "go_scheduler");
+    let has_resume = exports_nullary(info, "resume");
+    let recognized =
+        info.built_with(Toolchain::TinyGo) || uses_clock || (has_scheduler && has_resume);
+    if !recognized {
+        return None;
+    }
+    let entry = ["_start", "_initialize"]
+        .into_iter()
+        .find(|name| exports_nullary(info, name))
+        .map(str::to_string);
+    Some(TinyGoModule {
+        uses_clock,
+        entry,
+        has_scheduler,
+        has_resume,
+    })
+}
+
+/// Classify an import of a TinyGo module, or `None` if it has no default.
+pub fn tinygo_import(import: &FuncImport) -> Option<TinyGoImport> {
+    let module = &*import.module_name;
+    let name = &*import.func_name;
+    if TINYGO_IMPORT_MODULES.contains(&module) {
+        return match name {
+            "runtime.ticks" if import.params.is_empty() && *import.results == [WasmType::F64] => {
+                Some(TinyGoImport::Ticks)
+            }
+            "runtime.sleepTicks"
+                if *import.params == [WasmType::F64] && import.results.is_empty() =>
+            {
+                Some(TinyGoImport::SleepTicks)
+            }
+            _ if name.starts_with("syscall/js.") => Some(TinyGoImport::JsInterop),
+            _ => None,
+        };
+    }
+    if module == WASI_MODULE {
+        return match name {
+            "proc_exit" => Some(TinyGoImport::WasiExit),
+            _ if *import.results == [WasmType::I32] => Some(TinyGoImport::WasiUnsupported),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// Whether `name` is exported as a `() -> ()` function.
+fn exports_nullary(info: &ModuleInfo, name: &str) -> bool {
+    info.func_exports
+        .iter()
+        .find(|e| e.name == name)
+        .and_then(|e| info.ir_function(e.func_index))
+        .is_some_and(|f| f.params.is_empty() && f.results.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn info(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        build_module_info(&parsed, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn recognizes_clock_imports_and_entry_points() {
+        let module = info(
+            r#"(module
+                (import "gojs" "runtime.ticks" (func (result f64)))
+                (import "gojs" "syscall/js.valueGet" (func (param i32 i32 i32 i32 i32)))
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                (func (export "_initialize"))
+                (func (export "go_scheduler")))"#,
+        );
+        let tinygo = detect_tinygo(&module).unwrap();
+        assert_eq!(
+            tinygo,
+            TinyGoModule {
+                uses_clock: true,
+                entry: Some("_initialize".to_string()),
+                has_scheduler: true,
+                has_resume: false,
+            }
+        );
+        let kinds: Vec<_> = module.func_imports.iter().map(tinygo_import).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(TinyGoImport::Ticks),
+                Some(TinyGoImport::JsInterop),
+                Some(TinyGoImport::WasiUnsupported),
+                Some(TinyGoImport::WasiExit),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_gc_go_and_plain_modules() {
+        // gc's GOOS=js runtime imports take a stack pointer.
+        let gc = info(
+            r#"(module
+                (import "gojs" "runtime.ticks" (func (param i32)))
+                (func (export "run")))"#,
+        );
+        assert_eq!(detect_tinygo(&gc), None);
+        let plain = info(r#"(module (func (export "resume")))"#);
+        assert_eq!(detect_tinygo(&plain), None);
+    }
+
+    #[test]
+    fn recognizes_wasip1_builds_by_their_producers() {
+        let wasip1 = info(
+            r#"(module
+                (@producers (language "Go" "1.22") (processed-by "TinyGo" "0.33.0"))
+                (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+                (func (export "_start")))"#,
+        );
+        let tinygo = detect_tinygo(&wasip1).unwrap();
+        assert_eq!(tinygo.entry.as_deref(), Some("_start"));
+        assert!(!tinygo.uses_clock);
+    }
+}
@@ -0,0 +1,1019 @@
+//! Static worst-case execution cost.
+//!
+//! Every IR instruction gets a cost from a [`CostModel`]; [`Wcet`] bounds the
+//! cost of a call to each function, and thus to each export, for
+//! schedulability analysis on real-time targets. Costs are abstract units:
+//! calibrate the model against the target before reading them as cycles.
+//!
+//! ## Algorithm
+//!
+//! Loops are the natural loops of the control-flow graph, processed from the
+//! innermost out. One iteration of a loop costs at most the longest path from
+//! its header to a back edge, with inner loops collapsed into single nodes
+//! weighted by their own bound; the loop as a whole costs
+//! `bound × iteration + longest path from the header to an exit`. The
+//! function costs the longest path from its entry to a `return` or trap.
+//! Callees contribute their own bound, so recursion, direct or through
+//! `call_indirect`, is unbounded.
+//!
+//! A loop's bound is the maximum number of times its back edges are taken
+//! per entry. It is detected for counted `i32` loops: a header phi starting
+//! at a constant, updated by arithmetic on itself and constants, and tested
+//! against constants at the header or the single latch. Other loops need an
+//! annotation ([`WcetConfig::loop_bounds`]) or are unbounded. Detection reads
+//! phis, so run the analysis before `lower_phis`.
+//!
+//! Host calls are assumed to cost [`CostModel::host_call`]; bulk memory
+//! operations and `memory.grow` cost per byte, up to the whole memory unless
+//! their length is constant.
+
+use super::CallGraph;
+use crate::ir::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Most loop iterations simulated when detecting a bound.
+const MAX_SIMULATED_ITERATIONS: u64 = 1 << 20;
+
+/// Cost of each class of instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostModel {
+    /// Constants, copies, `select`, integer ALU operations and comparisons,
+    /// global accesses, `memory.size`.
+    pub simple: u64,
+    /// Integer multiplication.
+    pub mul: u64,
+    /// Integer division and remainder.
+    pub div: u64,
+    /// Float arithmetic, comparisons and conversions.
+    pub float: u64,
+    /// Float division and square root.
+    pub float_div: u64,
+    /// A bounds-checked load or store, and the setup of bulk operations.
+    pub memory: u64,
+    /// Each byte a bulk memory operation or `memory.grow` touches.
+    pub bulk_byte: u64,
+    /// A branch or jump between blocks.
+    pub branch: u64,
+    /// Call overhead, excluding the callee.
+    pub call: u64,
+    /// `call_indirect` dispatch overhead, excluding the callee.
+    pub call_indirect: u64,
+    /// A whole host call: imports, yield points, memory observation.
+    pub host_call: u64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            simple: 1,
+            mul: 3,
+            div: 20,
+            float: 4,
+            float_div: 20,
+            memory: 3,
+            bulk_byte: 1,
+            branch: 2,
+            call: 5,
+            call_indirect: 10,
+            host_call: 100,
+        }
+    }
+}
+
+impl CostModel {
+    /// Fixed cost of `instr`, excluding callees and the per-byte cost of
+    /// bulk memory operations.
+    pub fn instr_cost(&self, instr: &IrInstr) -> u64 {
+        match instr {
+            IrInstr::BinOp { op, .. } => self.binop_cost(*op),
+            IrInstr::UnOp { op, .. } => self.unop_cost(*op),
+            IrInstr::Load { .. }
+            | IrInstr::Store { .. }
+            | IrInstr::MemoryGrow { .. }
+            | IrInstr::MemoryCopy { .. }
+            | IrInstr::MemoryFill { .. }
+            | IrInstr::MemoryInit { .. } => self.memory,
+            IrInstr::Call { .. } => self.call,
+            IrInstr::CallImport { .. } => self.host_call,
+            IrInstr::CallIndirect { .. } => self.call_indirect,
+            IrInstr::Const { .. }
+            | IrInstr::Assign { .. }
+            | IrInstr::Select { .. }
+            | IrInstr::GlobalGet { .. }
+            | IrInstr::GlobalSet { .. }
+            | IrInstr::MemorySize { .. }
+            | IrInstr::DataDrop { .. }
+            | IrInstr::Phi { .. } => self.simple,
+        }
+    }
+
+    fn binop_cost(&self, op: BinOp) -> u64 {
+        use BinOp::*;
+        match op {
+            I32Mul | I64Mul => self.mul,
+            I32DivS | I32DivU | I32RemS | I32RemU | I64DivS | I64DivU | I64RemS | I64RemU => {
+                self.div
+            }
+            F32Div | F64Div => self.float_div,
+            F32Add | F32Sub | F32Mul | F32Min | F32Max | F32Copysign | F32Eq | F32Ne | F32Lt
+            | F32Gt | F32Le | F32Ge | F64Add | F64Sub | F64Mul | F64Min | F64Max | F64Copysign
+            | F64Eq | F64Ne | F64Lt | F64Gt | F64Le | F64Ge => self.float,
+            _ => self.simple,
+        }
+    }
+
+    fn unop_cost(&self, op: UnOp) -> u64 {
+        use UnOp::*;
+        match op {
+            I32Clz | I32Ctz | I32Popcnt | I32Eqz | I64Clz | I64Ctz | I64Popcnt | I64Eqz
+            | I32WrapI64 | I64ExtendI32S | I64ExtendI32U | I32Extend8S | I32Extend16S
+            | I64Extend8S | I64Extend16S | I64Extend32S => self.simple,
+            F32Sqrt | F64Sqrt => self.float_div,
+            _ => self.float,
+        }
+    }
+}
+
+/// Cost model plus loop bound annotations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WcetConfig {
+    pub costs: CostModel,
+    /// Maximum back-edge traversals per entry for every loop of the named
+    /// function (export or name-section name) whose bound is not detected.
+    pub loop_bounds: BTreeMap<String, u64>,
+}
+
+/// Why a function has no static bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unbounded {
+    /// A loop in this function has no detected or annotated bound.
+    Loop(LocalFuncIdx),
+    /// This function is part of a recursive cycle.
+    Recursion(LocalFuncIdx),
+}
+
+/// Static upper bound on the cost of a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Cost(u64),
+    Unbounded(Unbounded),
+}
+
+impl Bound {
+    /// The cost, if bounded.
+    pub fn cost(self) -> Option<u64> {
+        match self {
+            Bound::Cost(cost) => Some(cost),
+            Bound::Unbounded(_) => None,
+        }
+    }
+}
+
+/// Worst-case cost of every local function and export of a module.
+#[derive(Debug, Clone, Default)]
+pub struct Wcet {
+    funcs: Vec<Bound>,
+    exports: Vec<(String, Bound)>,
+}
+
+impl Wcet {
+    /// Bound every function of `info` under `config`.
+    pub fn compute(info: &ModuleInfo, config: &WcetConfig) -> Self {
+        let graph = CallGraph::build(info);
+        let (order, recursive) = callee_first_order(&graph);
+
+        let mut names: HashMap<usize, Vec<&str>> = HashMap::new();
+        for export in &info.func_exports {
+            names
+                .entry(export.func_index.as_usize())
+                .or_default()
+                .push(&export.name);
+        }
+        for (idx, sym) in info.func_symbols.iter().enumerate() {
+            if let Some(name) = &sym.name {
+                names.entry(idx).or_default().push(name);
+            }
+        }
+
+        let mut funcs: Vec<Option<Bound>> = vec![None; graph.len()];
+        for idx in order {
+            let bound = if recursive.contains(&idx) {
+                Bound::Unbounded(Unbounded::Recursion(LocalFuncIdx::new(idx)))
+            } else {
+                let annotation = names
+                    .get(&idx)
+                    .into_iter()
+                    .flatten()
+                    .find_map(|name| config.loop_bounds.get(*name).copied());
+                let ctx = FuncContext {
+                    info,
+                    graph: &graph,
+                    costs: &config.costs,
+                    funcs: &funcs,
+                    idx,
+                    annotation,
+                };
+                ctx.bound()
+            };
+            funcs[idx] = Some(bound);
+        }
+        let funcs: Vec<Bound> = funcs
+            .into_iter()
+            .enumerate()
+            .map(|(idx, b)| {
+                b.unwrap_or(Bound::Unbounded(Unbounded::Recursion(LocalFuncIdx::new(
+                    idx,
+                ))))
+            })
+            .collect();
+
+        let exports = info
+            .func_exports
+            .iter()
+            .filter_map(|e| Some((e.name.clone(), *funcs.get(e.func_index.as_usize())?)))
+            .collect();
+        Wcet { funcs, exports }
+    }
+
+    /// Bound of a call to local function `func`.
+    pub fn func(&self, func: LocalFuncIdx) -> Bound {
+        self.funcs[func.as_usize()]
+    }
+
+    /// Bound of a call to export `name`, if it is a function export.
+    pub fn export(&self, name: &str) -> Option<Bound> {
+        self.exports
+            .iter()
+            .find_map(|(n, bound)| (n == name).then_some(*bound))
+    }
+
+    /// Every function export with its bound, in export order.
+    pub fn exports(&self) -> &[(String, Bound)] {
+        &self.exports
+    }
+
+    /// One `export<TAB>bound` line per function export.
+    pub fn to_text(&self, info: &ModuleInfo) -> String {
+        let func_name = |func: LocalFuncIdx| {
+            info.func_symbols
+                .get(func.as_usize())
+                .and_then(|s| s.name.clone())
+                .unwrap_or_else(|| format!("func_{}", func.as_usize()))
+        };
+        let mut text = String::from("# export\tworst-case cost\n");
+        for (name, bound) in &self.exports {
+            let bound = match bound {
+                Bound::Cost(cost) => cost.to_string(),
+                Bound::Unbounded(Unbounded::Loop(f)) => {
+                    format!("unbounded (loop in {})", func_name(*f))
+                }
+                Bound::Unbounded(Unbounded::Recursion(f)) => {
+                    format!("unbounded (recursion through {})", func_name(*f))
+                }
+            };
+            text.push_str(&format!("{name}\t{bound}\n"));
+        }
+        text
+    }
+}
+
+/// Local functions with every callee before its callers (except along
+/// cycles), and the functions on a call cycle.
+fn callee_first_order(graph: &CallGraph) -> (Vec<usize>, HashSet<usize>) {
+    let mut order = Vec::with_capacity(graph.len());
+    let mut recursive = HashSet::new();
+    let mut visited = vec![false; graph.len()];
+    let mut on_stack: Vec<usize> = Vec::new();
+    for root in 0..graph.len() {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        on_stack.push(root);
+        let mut stack: Vec<(usize, Vec<LocalFuncIdx>)> =
+            vec![(root, graph.callees(LocalFuncIdx::new(root)))];
+        while let Some((func, pending)) = stack.last_mut() {
+            let func = *func;
+            match pending.pop().map(|f| f.as_usize()) {
+                Some(callee) => {
+                    if let Some(pos) = on_stack.iter().position(|&f| f == callee) {
+                        recursive.extend(on_stack[pos..].iter().copied());
+                    } else if !visited[callee] {
+                        visited[callee] = true;
+                        on_stack.push(callee);
+                        stack.push((callee, graph.callees(LocalFuncIdx::new(callee))));
+                    }
+                }
+                None => {
+                    on_stack.pop();
+                    stack.pop();
+                    order.push(func);
+                }
+            }
+        }
+    }
+    (order, recursive)
+}
+
+/// Bounding one function, its callees already done.
+struct FuncContext<'a> {
+    info: &'a ModuleInfo,
+    graph: &'a CallGraph,
+    costs: &'a CostModel,
+    funcs: &'a [Option<Bound>],
+    idx: usize,
+    /// Bound of loops without a detected one.
+    annotation: Option<u64>,
+}
+
+/// Natural loop: header and body block indices (header included).
+struct Loop {
+    header: usize,
+    body: HashSet<usize>,
+    latches: Vec<usize>,
+}
+
+impl FuncContext<'_> {
+    fn unbounded_loop(&self) -> Bound {
+        Bound::Unbounded(Unbounded::Loop(LocalFuncIdx::new(self.idx)))
+    }
+
+    fn bound(&self) -> Bound {
+        let func = &self.info.ir_functions[self.idx];
+        let index: HashMap<BlockId, usize> = func
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.id, i))
+            .collect();
+        let succs: Vec<Vec<usize>> = func
+            .blocks
+            .iter()
+            .map(|b| {
+                crate::optimizer::utils::terminator_successors(&b.terminator)
+                    .iter()
+                    .filter_map(|s| index.get(s).copied())
+                    .collect()
+            })
+            .collect();
+
+        let loops = natural_loops(&succs);
+        let headers: HashSet<usize> = loops.iter().map(|l| l.header).collect();
+
+        let mut block_cost = Vec::with_capacity(func.blocks.len());
+        for (i, block) in func.blocks.iter().enumerate() {
+            match self.block_cost(func, block, headers.contains(&i)) {
+                Bound::Cost(cost) => block_cost.push(cost),
+                unbounded => return unbounded,
+            }
+        }
+
+        // Innermost loops first; `owner[b]` is the outermost loop processed
+        // so far that contains `b`.
+        let mut owner: Vec<Option<usize>> = vec![None; func.blocks.len()];
+        let mut loop_cost: HashMap<usize, u64> = HashMap::new();
+        let consts = crate::optimizer::utils::build_global_const_map(func);
+        for l in &loops {
+            let scope = Scope {
+                blocks: &l.body,
+                owner: &owner,
+                succs: &succs,
+                block_cost: &block_cost,
+                loop_cost: &loop_cost,
+                header: Some(l.header),
+            };
+            let Some((iteration, exit)) = scope.longest_paths(l.header) else {
+                return self.unbounded_loop();
+            };
+            let Some(trips) = detect_bound(func, l, &index, &consts).or(self.annotation) else {
+                return self.unbounded_loop();
+            };
+            let total = trips
+                .saturating_mul(iteration.unwrap_or(0))
+                .saturating_add(exit.unwrap_or(0));
+            loop_cost.insert(l.header, total);
+            for &b in &l.body {
+                owner[b] = Some(l.header);
+            }
+        }
+
+        let all: HashSet<usize> = reachable(&succs);
+        let scope = Scope {
+            blocks: &all,
+            owner: &owner,
+            succs: &succs,
+            block_cost: &block_cost,
+            loop_cost: &loop_cost,
+            header: None,
+        };
+        match scope.longest_paths(0) {
+            Some((_, exit)) => Bound::Cost(exit.unwrap_or(0)),
+            None => self.unbounded_loop(),
+        }
+    }
+
+    /// Cost of `block`'s instructions, callees and terminator.
+    fn block_cost(&self, func: &IrFunction, block: &IrBlock, is_header: bool) -> Bound {
+        let costs = self.costs;
+        let info = self.info;
+        let memory_bytes =
+            (info.memory_import_max_pages.unwrap_or(info.max_pages) as u64).saturating_mul(65536);
+        let consts = crate::optimizer::utils::build_global_const_map(func);
+        let bytes = |len: VarId, unit: u64| match consts.get(&len) {
+            Some(IrValue::I32(n)) => (u64::from(*n as u32).saturating_mul(unit)).min(memory_bytes),
+            _ => memory_bytes,
+        };
+        let callee = |idx: usize| self.funcs.get(idx).copied().flatten();
+
+        let mut total: u64 = 0;
+        if is_header && info.yield_points {
+            total = costs.host_call;
+        }
+        for instr in &block.instructions {
+            let mut cost = costs.instr_cost(instr);
+            match instr {
+                IrInstr::Call { func_idx, .. } => match callee(func_idx.as_usize()) {
+                    Some(Bound::Cost(c)) => cost = cost.saturating_add(c),
+                    Some(unbounded) => return unbounded,
+                    None => return Bound::Unbounded(Unbounded::Recursion(*func_idx)),
+                },
+                IrInstr::CallIndirect { type_idx, .. } => {
+                    let idx = type_idx.as_usize();
+                    let canonical =
+                        TypeIdx::new(info.canonical_type.get(idx).copied().unwrap_or(idx));
+                    let mut worst = if self
+                        .graph
+                        .imported_callees(LocalFuncIdx::new(self.idx))
+                        .is_empty()
+                    {
+                        0
+                    } else {
+                        costs.host_call
+                    };
+                    for target in self.graph.indirect_targets(canonical) {
+                        match callee(target.as_usize()) {
+                            Some(Bound::Cost(c)) => worst = worst.max(c),
+                            Some(unbounded) => return unbounded,
+                            None => return Bound::Unbounded(Unbounded::Recursion(target)),
+                        }
+                    }
+                    cost = cost.saturating_add(worst);
+                }
+                IrInstr::GlobalGet { index, .. } | IrInstr::GlobalSet { index, .. } => {
+                    if matches!(info.resolve_global(*index), ResolvedGlobal::Imported(..)) {
+                        cost = costs.call;
+                    }
+                }
+                IrInstr::MemoryFill { len, .. } | IrInstr::MemoryInit { len, .. } => {
+                    cost = cost.saturating_add(costs.bulk_byte.saturating_mul(bytes(*len, 1)));
+                }
+                IrInstr::MemoryCopy { len, .. } => {
+                    cost = cost.saturating_add(costs.bulk_byte.saturating_mul(bytes(*len, 1)));
+                }
+                IrInstr::MemoryGrow { delta, .. } => {
+                    cost =
+                        cost.saturating_add(costs.bulk_byte.saturating_mul(bytes(*delta, 65536)));
+                }
+                _ => {}
+            }
+            if info.observe_memory {
+                cost = cost.saturating_add(match instr {
+                    IrInstr::Load { .. }
+                    | IrInstr::Store { .. }
+                    | IrInstr::MemoryFill { .. }
+                    | IrInstr::MemoryInit { .. } => costs.host_call,
+                    IrInstr::MemoryCopy { .. } => 2 * costs.host_call,
+                    _ => 0,
+                });
+            }
+            total = total.saturating_add(cost);
+        }
+        if !matches!(
+            block.terminator,
+            IrTerminator::Return { .. } | IrTerminator::Unreachable
+        ) {
+            total = total.saturating_add(costs.branch);
+        }
+        Bound::Cost(total)
+    }
+}
+
+/// Blocks reachable from the entry.
+fn reachable(succs: &[Vec<usize>]) -> HashSet<usize> {
+    let mut seen = HashSet::new();
+    let mut work = vec![0];
+    while let Some(b) = work.pop() {
+        if b < succs.len() && seen.insert(b) {
+            work.extend(&succs[b]);
+        }
+    }
+    seen
+}
+
+/// Natural loops of the CFG, smallest (innermost) first. Back edges to the
+/// same header form one loop.
+fn natural_loops(succs: &[Vec<usize>]) -> Vec<Loop> {
+    let n = succs.len();
+    let mut latches: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    let mut visited = vec![false; n];
+    let mut on_stack = vec![false; n];
+    if n > 0 {
+        visited[0] = true;
+        on_stack[0] = true;
+        let mut stack: Vec<(usize, Vec<usize>)> = vec![(0, succs[0].clone())];
+        while let Some((block, pending)) = stack.last_mut() {
+            let block = *block;
+            match pending.pop() {
+                Some(succ) if on_stack[succ] => latches.entry(succ).or_default().push(block),
+                Some(succ) => {
+                    if !visited[succ] {
+                        visited[succ] = true;
+                        on_stack[succ] = true;
+                        stack.push((succ, succs[succ].clone()));
+                    }
+                }
+                None => {
+                    on_stack[block] = false;
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (b, ss) in succs.iter().enumerate() {
+        if visited[b] {
+            for &s in ss {
+                preds[s].push(b);
+            }
+        }
+    }
+    let mut loops: Vec<Loop> = latches
+        .into_iter()
+        .map(|(header, latches)| {
+            let mut body = HashSet::from([header]);
+            let mut work = latches.clone();
+            while let Some(b) = work.pop() {
+                if body.insert(b) {
+                    work.extend(&preds[b]);
+                }
+            }
+            Loop {
+                header,
+                body,
+                latches,
+            }
+        })
+        .collect();
+    loops.sort_by_key(|l| l.body.len());
+    loops
+}
+
+/// A loop body (or the whole function) with its inner loops collapsed.
+struct Scope<'a> {
+    blocks: &'a HashSet<usize>,
+    owner: &'a [Option<usize>],
+    succs: &'a [Vec<usize>],
+    block_cost: &'a [u64],
+    loop_cost: &'a HashMap<usize, u64>,
+    /// Loop header, whose incoming edges are back edges.
+    header: Option<usize>,
+}
+
+impl Scope<'_> {
+    /// Node standing for `b`: the inner loop containing it, or itself.
+    fn rep(&self, b: usize) -> usize {
+        match self.owner[b] {
+            Some(h) if Some(h) != self.header => h,
+            _ => b,
+        }
+    }
+
+    /// Longest paths from `start` to a back edge and to an exit (an edge
+    /// leaving the scope, `return` or a trap), or `None` if the collapsed
+    /// graph is cyclic (irreducible control flow).
+    #[allow(clippy::type_complexity)]
+    fn longest_paths(&self, start: usize) -> Option<(Option<u64>, Option<u64>)> {
+        // Nodes, their weights and successor nodes
+        let mut weight: HashMap<usize, u64> = HashMap::new();
+        let mut edges: HashMap<usize, HashSet<usize>> = HashMap::new();
+        let mut latch: HashSet<usize> = HashSet::new();
+        let mut exit: HashSet<usize> = HashSet::new();
+        for &b in self.blocks {
+            let r = self.rep(b);
+            let w = match self.owner[r] {
+                Some(h) if h == r && Some(h) != self.header => self.loop_cost[&r],
+                _ => self.block_cost[r],
+            };
+            weight.insert(r, w);
+            if self.succs[b].is_empty() {
+                exit.insert(r);
+            }
+            for &s in &self.succs[b] {
+                if !self.blocks.contains(&s) {
+                    exit.insert(r);
+                } else if Some(s) == self.header {
+                    latch.insert(r);
+                } else {
+                    let rs = self.rep(s);
+                    if rs != r {
+                        // Entering an inner loop anywhere but its header
+                        if rs != s {
+                            return None;
+                        }
+                        edges.entry(r).or_default().insert(rs);
+                    }
+                }
+            }
+        }
+
+        // Memoized longest paths in reverse topological order
+        let mut to_latch: HashMap<usize, Option<u64>> = HashMap::new();
+        let mut to_exit: HashMap<usize, Option<u64>> = HashMap::new();
+        let mut on_stack: HashSet<usize> = HashSet::new();
+        let empty = HashSet::new();
+        let mut stack: Vec<(usize, Vec<usize>)> = Vec::new();
+        let start = self.rep(start);
+        on_stack.insert(start);
+        stack.push((
+            start,
+            edges
+                .get(&start)
+                .unwrap_or(&empty)
+                .iter()
+                .copied()
+                .collect(),
+        ));
+        while let Some((node, pending)) = stack.last_mut() {
+            let node = *node;
+            match pending.pop() {
+                Some(next) if on_stack.contains(&next) => return None,
+                Some(next) if to_exit.contains_key(&next) => {}
+                Some(next) => {
+                    on_stack.insert(next);
+                    stack.push((
+                        next,
+                        edges.get(&next).unwrap_or(&empty).iter().copied().collect(),
+                    ));
+                }
+                None => {
+                    let w = weight[&node];
+                    let succ_nodes = edges.get(&node).unwrap_or(&empty);
+                    let best = |table: &HashMap<usize, Option<u64>>, here: bool| {
+                        let mut best = here.then_some(0u64);
+                        for s in succ_nodes {
+                            best = best.max(table[s]);
+                        }
+                        best.map(|b| b.saturating_add(w))
+                    };
+                    let l = best(&to_latch, latch.contains(&node));
+                    let e = best(&to_exit, exit.contains(&node));
+                    to_latch.insert(node, l);
+                    to_exit.insert(node, e);
+                    on_stack.remove(&node);
+                    stack.pop();
+                }
+            }
+        }
+        Some((to_latch[&start], to_exit[&start]))
+    }
+}
+
+/// Maximum back-edge traversals of counted loop `l`, if detected.
+fn detect_bound(
+    func: &IrFunction,
+    l: &Loop,
+    index: &HashMap<BlockId, usize>,
+    consts: &HashMap<VarId, IrValue>,
+) -> Option<u64> {
+    let mut defs: HashMap<VarId, &IrInstr> = HashMap::new();
+    for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+        let dest = match instr {
+            IrInstr::Phi { dest, .. } => Some(*dest),
+            _ => crate::optimizer::utils::instr_dest(instr),
+        };
+        if let Some(dest) = dest {
+            defs.insert(dest, instr);
+        }
+    }
+    let header = &func.blocks[l.header];
+    let mut tests = vec![l.header];
+    if let [latch] = l.latches[..] {
+        tests.push(latch);
+    }
+
+    let mut best: Option<u64> = None;
+    for instr in &header.instructions {
+        let IrInstr::Phi { dest: var, srcs } = instr else {
+            continue;
+        };
+        let eval = Eval {
+            defs: &defs,
+            consts,
+            var: *var,
+        };
+        // Constant on entry, one update expression from the back edges
+        let mut inits = HashSet::new();
+        let mut updates = HashSet::new();
+        for (pred, src) in srcs {
+            if index.get(pred).is_some_and(|p| l.body.contains(p)) {
+                updates.insert(*src);
+            } else if !defs.contains_key(src)
+                && !consts.contains_key(src)
+                && func
+                    .locals
+                    .iter()
+                    .any(|&(v, ty)| v == *src && ty == WasmType::I32)
+            {
+                // Wasm locals start at zero
+                inits.insert(Some(0));
+            } else {
+                inits.insert(eval.eval(*src, 0, 0));
+            }
+        }
+        let (Some(&Some(init)), Some(&update), 1, 1) = (
+            inits.iter().next(),
+            updates.iter().next(),
+            inits.len(),
+            updates.len(),
+        ) else {
+            continue;
+        };
+
+        for &t in &tests {
+            let IrTerminator::BranchIf {
+                condition,
+                if_true,
+                if_false,
+            } = func.blocks[t].terminator
+            else {
+                continue;
+            };
+            let leaves = |b: BlockId| index.get(&b).is_none_or(|i| !l.body.contains(i));
+            let exit_when = match (leaves(if_true), leaves(if_false)) {
+                (true, false) => true,
+                (false, true) => false,
+                _ => continue,
+            };
+            // At the latch, staying must take the back edge
+            if t != l.header {
+                let stay = if exit_when { if_false } else { if_true };
+                if index.get(&stay) != Some(&l.header) {
+                    continue;
+                }
+            }
+            let mut v = init;
+            let mut trips = 0;
+            let found = loop {
+                let Some(cond) = eval.eval(condition, v, 0) else {
+                    break None;
+                };
+                if (cond != 0) == exit_when {
+                    break Some(trips);
+                }
+                if trips == MAX_SIMULATED_ITERATIONS {
+                    break None;
+                }
+                let Some(next) = eval.eval(update, v, 0) else {
+                    break None;
+                };
+                v = next;
+                trips += 1;
+            };
+            if let Some(trips) = found {
+                best = Some(best.map_or(trips, |b| b.min(trips)));
+            }
+        }
+    }
+    best
+}
+
+/// Evaluates `i32` expressions over constants and one loop variable.
+struct Eval<'a> {
+    defs: &'a HashMap<VarId, &'a IrInstr>,
+    consts: &'a HashMap<VarId, IrValue>,
+    /// The header phi being simulated.
+    var: VarId,
+}
+
+impl Eval<'_> {
+    /// Value of `x` when the loop variable is `v`.
+    fn eval(&self, x: VarId, v: i32, depth: u32) -> Option<i32> {
+        if x == self.var {
+            return Some(v);
+        }
+        if let Some(IrValue::I32(c)) = self.consts.get(&x) {
+            return Some(*c);
+        }
+        if depth > 16 {
+            return None;
+        }
+        let depth = depth + 1;
+        match self.defs.get(&x)? {
+            IrInstr::Assign { src, .. } => self.eval(*src, v, depth),
+            // Inner loop phis that only carry one value through
+            IrInstr::Phi { dest, srcs } => {
+                let mut carried = srcs.iter().map(|(_, s)| *s).filter(|s| s != dest);
+                let first = carried.next()?;
+                if carried.all(|s| s == first) {
+                    self.eval(first, v, depth)
+                } else {
+                    None
+                }
+            }
+            IrInstr::UnOp {
+                op: UnOp::I32Eqz,
+                operand,
+                ..
+            } => Some((self.eval(*operand, v, depth)? == 0) as i32),
+            IrInstr::BinOp { op, lhs, rhs, .. } => {
+                let a = self.eval(*lhs, v, depth)?;
+                let b = self.eval(*rhs, v, depth)?;
+                let (ua, ub) = (a as u32, b as u32);
+                Some(match op {
+                    BinOp::I32Add => a.wrapping_add(b),
+                    BinOp::I32Sub => a.wrapping_sub(b),
+                    BinOp::I32Mul => a.wrapping_mul(b),
+                    BinOp::I32And => a & b,
+                    BinOp::I32Or => a | b,
+                    BinOp::I32Xor => a ^ b,
+                    BinOp::I32Shl => a.wrapping_shl(ub & 31),
+                    BinOp::I32ShrS => a.wrapping_shr(ub & 31),
+                    BinOp::I32ShrU => ua.wrapping_shr(ub & 31) as i32,
+                    BinOp::I32Eq => (a == b) as i32,
+                    BinOp::I32Ne => (a != b) as i32,
+                    BinOp::I32LtS => (a < b) as i32,
+                    BinOp::I32LtU => (ua < ub) as i32,
+                    BinOp::I32GtS => (a > b) as i32,
+                    BinOp::I32GtU => (ua > ub) as i32,
+                    BinOp::I32LeS => (a <= b) as i32,
+                    BinOp::I32LeU => (ua <= ub) as i32,
+                    BinOp::I32GeS => (a >= b) as i32,
+                    BinOp::I32GeU => (ua >= ub) as i32,
+                    _ => return None,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::TranspileOptions;
+
+    fn wcet(wat: &str, config: &WcetConfig) -> Wcet {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &TranspileOptions::default()).unwrap();
+        Wcet::compute(&info, config)
+    }
+
+    fn counted(n: i32) -> String {
+        format!(
+            r#"(module
+                (func (export "sum") (result i32)
+                    (local $i i32) (local $acc i32)
+                    (loop $next
+                        (local.set $acc (i32.add (local.get $acc) (i32.mul (local.get $i) (local.get $i))))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $next (i32.lt_s (local.get $i) (i32.const {n}))))
+                    (local.get $acc)))"#
+        )
+    }
+
+    #[test]
+    fn counted_loop_cost_grows_linearly() {
+        let config = WcetConfig::default();
+        let cost = |n| {
+            wcet(&counted(n), &config)
+                .export("sum")
+                .and_then(Bound::cost)
+                .unwrap()
+        };
+        let (c10, c20, c110) = (cost(10), cost(20), cost(110));
+        let per_iteration = (c20 - c10) / 10;
+        assert!(per_iteration > 0);
+        assert_eq!(c20 - c10, 10 * per_iteration);
+        assert_eq!(c110 - c10, 100 * per_iteration);
+    }
+
+    #[test]
+    fn nested_loops_multiply() {
+        let nested = |outer: i32, inner: i32| {
+            let wat = format!(
+                r#"(module
+                    (memory 1 1)
+                    (func (export "grid")
+                        (local $i i32) (local $j i32)
+                        (loop $rows
+                            (local.set $j (i32.const 0))
+                            (loop $cols
+                                (i32.store8 (i32.add (local.get $i) (local.get $j)) (i32.const 1))
+                                (br_if $cols (i32.ne (local.tee $j (i32.add (local.get $j) (i32.const 1))) (i32.const {inner}))))
+                            (br_if $rows (i32.lt_u (local.tee $i (i32.add (local.get $i) (i32.const 1))) (i32.const {outer}))))))"#
+            );
+            wcet(&wat, &WcetConfig::default())
+                .export("grid")
+                .and_then(Bound::cost)
+                .unwrap()
+        };
+        // Each extra inner iteration runs once per outer iteration
+        let d2 = nested(2, 16) - nested(2, 8);
+        let d4 = nested(4, 16) - nested(4, 8);
+        assert!(d2 > 0);
+        assert_eq!(d4, 2 * d2);
+    }
+
+    #[test]
+    fn unknown_trip_count_needs_an_annotation() {
+        let wat = r#"(module
+            (func $spin (export "spin") (param $n i32)
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.eqz (local.get $n)))
+                        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                        (br $next)))))"#;
+        let spin = LocalFuncIdx::new(0);
+        assert_eq!(
+            wcet(wat, &WcetConfig::default()).export("spin"),
+            Some(Bound::Unbounded(Unbounded::Loop(spin)))
+        );
+
+        let bounded = |n: u64| {
+            let config = WcetConfig {
+                loop_bounds: BTreeMap::from([("spin".to_string(), n)]),
+                ..Default::default()
+            };
+            wcet(wat, &config).func(spin).cost().unwrap()
+        };
+        assert!(bounded(100) > bounded(10));
+    }
+
+    #[test]
+    fn callees_and_longer_branches_count() {
+        let wat = r#"(module
+            (func $leaf (param i32) (result i32)
+                (if (result i32) (local.get 0)
+                    (then (i32.div_s (local.get 0) (i32.const 3)))
+                    (else (i32.const 0))))
+            (func (export "twice") (param i32) (result i32)
+                (i32.add (call $leaf (local.get 0)) (call $leaf (local.get 0)))))"#;
+        let costs = CostModel::default();
+        let report = wcet(wat, &WcetConfig::default());
+        let leaf = report.func(LocalFuncIdx::new(0)).cost().unwrap();
+        assert!(leaf >= costs.div);
+        let twice = report.export("twice").and_then(Bound::cost).unwrap();
+        assert!(twice >= 2 * (leaf + costs.call));
+
+        let expensive = WcetConfig {
+            costs: CostModel {
+                div: 1000,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let leaf_expensive = wcet(wat, &expensive).func(LocalFuncIdx::new(0)).cost();
+        assert_eq!(leaf_expensive, Some(leaf + 1000 - costs.div));
+    }
+
+    #[test]
+    fn recursion_is_unbounded() {
+        let wat = r#"(module
+            (func $fact (param i32) (result i32)
+                (if (result i32) (local.get 0)
+                    (then (i32.mul (local.get 0) (call $fact (i32.sub (local.get 0) (i32.const 1)))))
+                    (else (i32.const 1))))
+            (func (export "fact5") (result i32) (call $fact (i32.const 5))))"#;
+        let report = wcet(wat, &WcetConfig::default());
+        let fact = LocalFuncIdx::new(0);
+        assert_eq!(
+            report.export("fact5"),
+            Some(Bound::Unbounded(Unbounded::Recursion(fact)))
+        );
+        let info = {
+            let wasm = wat::parse_str(wat).unwrap();
+            let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+            build_module_info(&parsed, &TranspileOptions::default()).unwrap()
+        };
+        assert_eq!(
+            report.to_text(&info),
+            "# export\tworst-case cost\nfact5\tunbounded (recursion through fact)\n"
+        );
+    }
+
+    #[test]
+    fn bulk_memory_costs_per_byte() {
+        let wat = r#"(module
+            (memory 1 2)
+            (func (export "small") (memory.fill (i32.const 0) (i32.const 0) (i32.const 16)))
+            (func (export "any") (param i32) (memory.fill (i32.const 0) (i32.const 0) (local.get 0))))"#;
+        let report = wcet(wat, &WcetConfig::default());
+        let small = report.export("small").and_then(Bound::cost).unwrap();
+        let any = report.export("any").and_then(Bound::cost).unwrap();
+        assert!((16..100).contains(&small));
+        assert!(any >= 2 * 65536);
+    }
+}
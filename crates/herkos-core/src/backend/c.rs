@@ -0,0 +1,580 @@
+//! C backend — emits portable C99 with explicit bounds checks.
+//!
+//! Experimental. Statements target the module layout generated by
+//! [`codegen::c`](crate::codegen::c): every function takes the module as
+//! `m`, returns a `wasm_trap_t`, and writes its result through `ret`. Traps
+//! propagate with `WASM_TRY`; memory accesses, division and float
+//! truncation go through the `static` helpers in the generated preamble.
+//!
+//! Signed arithmetic is done on unsigned operands to avoid undefined
+//! behavior on overflow. Converting the result back relies on the
+//! two's-complement conversion every supported compiler implements.
+
+use crate::backend::Backend;
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use std::fmt::{self, Write};
+
+const INDENT: &str = "    ";
+
+/// Write a comma-separated argument list, each argument preceded by `, `.
+fn write_trailing_args(out: &mut dyn Write, args: &[VarId]) -> fmt::Result {
+    for arg in args {
+        write!(out, ", {arg}")?;
+    }
+    Ok(())
+}
+
+/// Write the `, &dest` result argument of a call, if it has a result.
+fn write_ret_arg(out: &mut dyn Write, dests: &[VarId]) -> fmt::Result {
+    for d in dests {
+        write!(out, ", &{d}")?;
+    }
+    Ok(())
+}
+
+/// C name of the import `module_name.func_name`, implemented by the host.
+pub fn import_symbol(module_name: &str, func_name: &str) -> String {
+    format!(
+        "wasm_import_{}_{}",
+        rust_ident(module_name),
+        rust_ident(func_name)
+    )
+}
+
+/// C type of a Wasm value type.
+pub fn c_type(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "int32_t",
+        WasmType::I64 => "int64_t",
+        WasmType::F32 => "float",
+        WasmType::F64 => "double",
+    }
+}
+
+/// C expression for the constant `value`. Non-finite floats are built from
+/// their bits, which also keeps NaN payloads.
+pub fn c_literal(value: &IrValue) -> String {
+    match *value {
+        IrValue::I32(i32::MIN) => "INT32_MIN".to_string(),
+        IrValue::I32(v) => v.to_string(),
+        IrValue::I64(i64::MIN) => "INT64_MIN".to_string(),
+        IrValue::I64(v) => format!("INT64_C({v})"),
+        IrValue::F32(v) if v.is_finite() => format!("{v:?}f"),
+        IrValue::F32(v) => format!("wasm_f32_from_bits(0x{:08x}u)", v.to_bits()),
+        IrValue::F64(v) if v.is_finite() => format!("{v:?}"),
+        IrValue::F64(v) => format!("wasm_f64_from_bits(UINT64_C(0x{:016x}))", v.to_bits()),
+    }
+}
+
+/// C code generation backend.
+pub struct CBackend;
+
+impl CBackend {
+    pub fn new() -> Self {
+        CBackend
+    }
+}
+
+impl Default for CBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for CBackend {
+    fn emit_const(&self, out: &mut dyn Write, dest: VarId, value: &IrValue) -> fmt::Result {
+        write!(out, "{INDENT}{dest} = {};", c_literal(value))
+    }
+
+    fn emit_binop(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        op: BinOp,
+        lhs: VarId,
+        rhs: VarId,
+    ) -> fmt::Result {
+        let expr = match op {
+            // Wrapping arithmetic on the unsigned representation
+            BinOp::I32Add => format!("(int32_t)((uint32_t){lhs} + (uint32_t){rhs})"),
+            BinOp::I32Sub => format!("(int32_t)((uint32_t){lhs} - (uint32_t){rhs})"),
+            BinOp::I32Mul => format!("(int32_t)((uint32_t){lhs} * (uint32_t){rhs})"),
+            BinOp::I64Add => format!("(int64_t)((uint64_t){lhs} + (uint64_t){rhs})"),
+            BinOp::I64Sub => format!("(int64_t)((uint64_t){lhs} - (uint64_t){rhs})"),
+            BinOp::I64Mul => format!("(int64_t)((uint64_t){lhs} * (uint64_t){rhs})"),
+
+            // Trapping division — helpers in the preamble
+            BinOp::I32DivS => return write_try(out, "wasm_i32_div_s", lhs, rhs, dest),
+            BinOp::I32DivU => return write_try(out, "wasm_i32_div_u", lhs, rhs, dest),
+            BinOp::I32RemS => return write_try(out, "wasm_i32_rem_s", lhs, rhs, dest),
+            BinOp::I32RemU => return write_try(out, "wasm_i32_rem_u", lhs, rhs, dest),
+            BinOp::I64DivS => return write_try(out, "wasm_i64_div_s", lhs, rhs, dest),
+            BinOp::I64DivU => return write_try(out, "wasm_i64_div_u", lhs, rhs, dest),
+            BinOp::I64RemS => return write_try(out, "wasm_i64_rem_s", lhs, rhs, dest),
+            BinOp::I64RemU => return write_try(out, "wasm_i64_rem_u", lhs, rhs, dest),
+
+            BinOp::I32And | BinOp::I64And => format!("{lhs} & {rhs}"),
+            BinOp::I32Or | BinOp::I64Or => format!("{lhs} | {rhs}"),
+            BinOp::I32Xor | BinOp::I64Xor => format!("{lhs} ^ {rhs}"),
+
+            BinOp::I32Shl => format!("(int32_t)((uint32_t){lhs} << ({rhs} & 31))"),
+            BinOp::I32ShrS => format!("wasm_i32_shr_s({lhs}, {rhs})"),
+            BinOp::I32ShrU => format!("(int32_t)((uint32_t){lhs} >> ({rhs} & 31))"),
+            BinOp::I32Rotl => format!("wasm_i32_rotl({lhs}, {rhs})"),
+            BinOp::I32Rotr => format!("wasm_i32_rotr({lhs}, {rhs})"),
+            BinOp::I64Shl => format!("(int64_t)((uint64_t){lhs} << ({rhs} & 63))"),
+            BinOp::I64ShrS => format!("wasm_i64_shr_s({lhs}, {rhs})"),
+            BinOp::I64ShrU => format!("(int64_t)((uint64_t){lhs} >> ({rhs} & 63))"),
+            BinOp::I64Rotl => format!("wasm_i64_rotl({lhs}, {rhs})"),
+            BinOp::I64Rotr => format!("wasm_i64_rotr({lhs}, {rhs})"),
+
+            // Comparisons yield int (0 or 1), which converts to int32_t
+            BinOp::I32Eq | BinOp::I64Eq | BinOp::F32Eq | BinOp::F64Eq => {
+                format!("{lhs} == {rhs}")
+            }
+            BinOp::I32Ne | BinOp::I64Ne | BinOp::F32Ne | BinOp::F64Ne => {
+                format!("{lhs} != {rhs}")
+            }
+            BinOp::I32LtS | BinOp::I64LtS | BinOp::F32Lt | BinOp::F64Lt => {
+                format!("{lhs} < {rhs}")
+            }
+            BinOp::I32GtS | BinOp::I64GtS | BinOp::F32Gt | BinOp::F64Gt => {
+                format!("{lhs} > {rhs}")
+            }
+            BinOp::I32LeS | BinOp::I64LeS | BinOp::F32Le | BinOp::F64Le => {
+                format!("{lhs} <= {rhs}")
+            }
+            BinOp::I32GeS | BinOp::I64GeS | BinOp::F32Ge | BinOp::F64Ge => {
+                format!("{lhs} >= {rhs}")
+            }
+            BinOp::I32LtU => format!("(uint32_t){lhs} < (uint32_t){rhs}"),
+            BinOp::I32GtU => format!("(uint32_t){lhs} > (uint32_t){rhs}"),
+            BinOp::I32LeU => format!("(uint32_t){lhs} <= (uint32_t){rhs}"),
+            BinOp::I32GeU => format!("(uint32_t){lhs} >= (uint32_t){rhs}"),
+            BinOp::I64LtU => format!("(uint64_t){lhs} < (uint64_t){rhs}"),
+            BinOp::I64GtU => format!("(uint64_t){lhs} > (uint64_t){rhs}"),
+            BinOp::I64LeU => format!("(uint64_t){lhs} <= (uint64_t){rhs}"),
+            BinOp::I64GeU => format!("(uint64_t){lhs} >= (uint64_t){rhs}"),
+
+            BinOp::F32Add | BinOp::F64Add => format!("{lhs} + {rhs}"),
+            BinOp::F32Sub | BinOp::F64Sub => format!("{lhs} - {rhs}"),
+            BinOp::F32Mul | BinOp::F64Mul => format!("{lhs} * {rhs}"),
+            BinOp::F32Div | BinOp::F64Div => format!("{lhs} / {rhs}"),
+            // fmin/fmax do not propagate NaN, nor order -0 below +0
+            BinOp::F32Min => format!("wasm_f32_min({lhs}, {rhs})"),
+            BinOp::F32Max => format!("wasm_f32_max({lhs}, {rhs})"),
+            BinOp::F64Min => format!("wasm_f64_min({lhs}, {rhs})"),
+            BinOp::F64Max => format!("wasm_f64_max({lhs}, {rhs})"),
+            BinOp::F32Copysign => format!("copysignf({lhs}, {rhs})"),
+            BinOp::F64Copysign => format!("copysign({lhs}, {rhs})"),
+        };
+        write!(out, "{INDENT}{dest} = {expr};")
+    }
+
+    fn emit_unop(&self, out: &mut dyn Write, dest: VarId, op: UnOp, operand: VarId) -> fmt::Result {
+        let expr = match op {
+            UnOp::I32Clz => format!("wasm_i32_clz({operand})"),
+            UnOp::I32Ctz => format!("wasm_i32_ctz({operand})"),
+            UnOp::I32Popcnt => format!("wasm_i32_popcnt({operand})"),
+            UnOp::I64Clz => format!("wasm_i64_clz({operand})"),
+            UnOp::I64Ctz => format!("wasm_i64_ctz({operand})"),
+            UnOp::I64Popcnt => format!("wasm_i64_popcnt({operand})"),
+            UnOp::I32Eqz | UnOp::I64Eqz => format!("{operand} == 0"),
+
+            UnOp::F32Abs => format!("fabsf({operand})"),
+            UnOp::F32Neg => format!("-{operand}"),
+            UnOp::F32Sqrt => format!("sqrtf({operand})"),
+            UnOp::F32Ceil => format!("ceilf({operand})"),
+            UnOp::F32Floor => format!("floorf({operand})"),
+            UnOp::F32Trunc => format!("truncf({operand})"),
+            // Ties to even in the default rounding mode
+            UnOp::F32Nearest => format!("rintf({operand})"),
+            UnOp::F64Abs => format!("fabs({operand})"),
+            UnOp::F64Neg => format!("-{operand}"),
+            UnOp::F64Sqrt => format!("sqrt({operand})"),
+            UnOp::F64Ceil => format!("ceil({operand})"),
+            UnOp::F64Floor => format!("floor({operand})"),
+            UnOp::F64Trunc => format!("trunc({operand})"),
+            UnOp::F64Nearest => format!("rint({operand})"),
+
+            UnOp::I32WrapI64 => format!("(int32_t)(uint32_t){operand}"),
+            UnOp::I64ExtendI32S => format!("(int64_t){operand}"),
+            UnOp::I64ExtendI32U => format!("(int64_t)(uint32_t){operand}"),
+            UnOp::I32Extend8S => format!("(int32_t)(int8_t){operand}"),
+            UnOp::I32Extend16S => format!("(int32_t)(int16_t){operand}"),
+            UnOp::I64Extend8S => format!("(int64_t)(int8_t){operand}"),
+            UnOp::I64Extend16S => format!("(int64_t)(int16_t){operand}"),
+            UnOp::I64Extend32S => format!("(int64_t)(int32_t){operand}"),
+
+            // Trapping float → int — helpers in the preamble
+            UnOp::I32TruncF32S => {
+                return write_try_unary(out, "wasm_i32_trunc_f32_s", operand, dest)
+            }
+            UnOp::I32TruncF32U => {
+                return write_try_unary(out, "wasm_i32_trunc_f32_u", operand, dest)
+            }
+            UnOp::I32TruncF64S => {
+                return write_try_unary(out, "wasm_i32_trunc_f64_s", operand, dest)
+            }
+            UnOp::I32TruncF64U => {
+                return write_try_unary(out, "wasm_i32_trunc_f64_u", operand, dest)
+            }
+            UnOp::I64TruncF32S => {
+                return write_try_unary(out, "wasm_i64_trunc_f32_s", operand, dest)
+            }
+            UnOp::I64TruncF32U => {
+                return write_try_unary(out, "wasm_i64_trunc_f32_u", operand, dest)
+            }
+            UnOp::I64TruncF64S => {
+                return write_try_unary(out, "wasm_i64_trunc_f64_s", operand, dest)
+            }
+            UnOp::I64TruncF64U => {
+                return write_try_unary(out, "wasm_i64_trunc_f64_u", operand, dest)
+            }
+
+            UnOp::F32ConvertI32S | UnOp::F32ConvertI64S | UnOp::F32DemoteF64 => {
+                format!("(float){operand}")
+            }
+            UnOp::F32ConvertI32U => format!("(float)(uint32_t){operand}"),
+            UnOp::F32ConvertI64U => format!("(float)(uint64_t){operand}"),
+            UnOp::F64ConvertI32S | UnOp::F64ConvertI64S | UnOp::F64PromoteF32 => {
+                format!("(double){operand}")
+            }
+            UnOp::F64ConvertI32U => format!("(double)(uint32_t){operand}"),
+            UnOp::F64ConvertI64U => format!("(double)(uint64_t){operand}"),
+
+            UnOp::I32ReinterpretF32 => format!("(int32_t)wasm_f32_to_bits({operand})"),
+            UnOp::I64ReinterpretF64 => format!("(int64_t)wasm_f64_to_bits({operand})"),
+            UnOp::F32ReinterpretI32 => format!("wasm_f32_from_bits((uint32_t){operand})"),
+            UnOp::F64ReinterpretI64 => format!("wasm_f64_from_bits((uint64_t){operand})"),
+        };
+        write!(out, "{INDENT}{dest} = {expr};")
+    }
+
+    fn emit_load(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        ty: WasmType,
+        addr: VarId,
+        offset: u32,
+        width: MemoryAccessWidth,
+        sign: Option<SignExtension>,
+    ) -> anyhow::Result<()> {
+        // (bytes read into `bits`, conversion of `bits` to the result)
+        let (size, value) = match (ty, width, sign) {
+            (WasmType::I32, MemoryAccessWidth::Full, _) => (4, "(int32_t)(uint32_t)bits"),
+            (WasmType::I64, MemoryAccessWidth::Full, _) => (8, "(int64_t)bits"),
+            (WasmType::F32, MemoryAccessWidth::Full, _) => {
+                (4, "wasm_f32_from_bits((uint32_t)bits)")
+            }
+            (WasmType::F64, MemoryAccessWidth::Full, _) => (8, "wasm_f64_from_bits(bits)"),
+            (WasmType::I32, MemoryAccessWidth::I8, Some(SignExtension::Signed)) => {
+                (1, "(int32_t)(int8_t)(uint8_t)bits")
+            }
+            (WasmType::I32, MemoryAccessWidth::I8, Some(SignExtension::Unsigned)) => {
+                (1, "(int32_t)bits")
+            }
+            (WasmType::I32, MemoryAccessWidth::I16, Some(SignExtension::Signed)) => {
+                (2, "(int32_t)(int16_t)(uint16_t)bits")
+            }
+            (WasmType::I32, MemoryAccessWidth::I16, Some(SignExtension::Unsigned)) => {
+                (2, "(int32_t)bits")
+            }
+            (WasmType::I64, MemoryAccessWidth::I8, Some(SignExtension::Signed)) => {
+                (1, "(int64_t)(int8_t)(uint8_t)bits")
+            }
+            (WasmType::I64, MemoryAccessWidth::I16, Some(SignExtension::Signed)) => {
+                (2, "(int64_t)(int16_t)(uint16_t)bits")
+            }
+            (WasmType::I64, MemoryAccessWidth::I32, Some(SignExtension::Signed)) => {
+                (4, "(int64_t)(int32_t)(uint32_t)bits")
+            }
+            (
+                WasmType::I64,
+                MemoryAccessWidth::I8 | MemoryAccessWidth::I16 | MemoryAccessWidth::I32,
+                Some(SignExtension::Unsigned),
+            ) => (access_size(width), "(int64_t)bits"),
+
+            // Invalid combinations (shouldn't occur in valid Wasm)
+            _ => anyhow::bail!("unsupported load: {ty:?} width={width:?} sign={sign:?}"),
+        };
+        write!(
+            out,
+            "{INDENT}WASM_TRY(wasm_load(m, {addr}, {offset}u, {size}, &bits));\n{INDENT}{dest} = {value};"
+        )?;
+        Ok(())
+    }
+
+    fn emit_store(
+        &self,
+        out: &mut dyn Write,
+        ty: WasmType,
+        addr: VarId,
+        value: VarId,
+        offset: u32,
+        width: MemoryAccessWidth,
+    ) -> anyhow::Result<()> {
+        let bits = match (ty, width) {
+            (WasmType::I32, _) => format!("(uint32_t){value}"),
+            (WasmType::I64, _) => format!("(uint64_t){value}"),
+            (WasmType::F32, MemoryAccessWidth::Full) => format!("wasm_f32_to_bits({value})"),
+            (WasmType::F64, MemoryAccessWidth::Full) => format!("wasm_f64_to_bits({value})"),
+            _ => anyhow::bail!("unsupported store: {ty:?} width={width:?}"),
+        };
+        let size = match width {
+            MemoryAccessWidth::Full => match ty {
+                WasmType::I32 | WasmType::F32 => 4,
+                WasmType::I64 | WasmType::F64 => 8,
+            },
+            _ => access_size(width),
+        };
+        write!(
+            out,
+            "{INDENT}WASM_TRY(wasm_store(m, {addr}, {offset}u, {size}, {bits}));"
+        )?;
+        Ok(())
+    }
+
+    fn emit_call(
+        &self,
+        out: &mut dyn Write,
+        dests: &[VarId],
+        func: &str,
+        args: &[VarId],
+        _has_memory: bool,
+        _has_table: bool,
+    ) -> fmt::Result {
+        // Memory and table live in the module struct, so every function
+        // takes just `m`.
+        write!(out, "{INDENT}WASM_TRY({func}(m")?;
+        write_trailing_args(out, args)?;
+        write_ret_arg(out, dests)?;
+        out.write_str("));")
+    }
+
+    fn emit_call_import(
+        &self,
+        out: &mut dyn Write,
+        dests: &[VarId],
+        module_name: &str,
+        func_name: &str,
+        args: &[VarId],
+    ) -> fmt::Result {
+        write!(
+            out,
+            "{INDENT}WASM_TRY({}(m",
+            import_symbol(module_name, func_name)
+        )?;
+        write_trailing_args(out, args)?;
+        write_ret_arg(out, dests)?;
+        out.write_str("));")
+    }
+
+    fn emit_global_get(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        index: usize,
+        _field: &str,
+        is_mutable: bool,
+    ) -> fmt::Result {
+        // Struct members stay positional: name-section names could clash
+        // with C keywords or the other `wasm_module_t` members.
+        if is_mutable {
+            write!(out, "{INDENT}{dest} = m->g{index};")
+        } else {
+            write!(out, "{INDENT}{dest} = G{index};")
+        }
+    }
+
+    fn emit_global_set(
+        &self,
+        out: &mut dyn Write,
+        index: usize,
+        _field: &str,
+        value: VarId,
+    ) -> fmt::Result {
+        write!(out, "{INDENT}m->g{index} = {value};")
+    }
+
+    fn emit_assign(&self, out: &mut dyn Write, dest: VarId, src: VarId) -> fmt::Result {
+        write!(out, "{INDENT}{dest} = {src};")
+    }
+
+    fn emit_select(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        val1: VarId,
+        val2: VarId,
+        condition: VarId,
+    ) -> fmt::Result {
+        write!(out, "{INDENT}{dest} = {condition} != 0 ? {val1} : {val2};")
+    }
+
+    fn emit_select_branchless(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        val1: VarId,
+        val2: VarId,
+        condition: VarId,
+        ty: WasmType,
+    ) -> fmt::Result {
+        let helper = match ty {
+            WasmType::I32 => "wasm_ct_select_i32",
+            WasmType::I64 => "wasm_ct_select_i64",
+            WasmType::F32 => "wasm_ct_select_f32",
+            WasmType::F64 => "wasm_ct_select_f64",
+        };
+        write!(
+            out,
+            "{INDENT}{dest} = {helper}({condition}, {val1}, {val2});"
+        )
+    }
+
+    fn emit_return(&self, out: &mut dyn Write, values: &[VarId]) -> fmt::Result {
+        // Multi-value results are rejected before codegen, so there is at
+        // most one `ret` out-parameter.
+        match values.first() {
+            Some(v) => write!(out, "{INDENT}*ret = {v};\n{INDENT}return WASM_OK;"),
+            None => write!(out, "{INDENT}return WASM_OK;"),
+        }
+    }
+
+    fn emit_memory_size(&self, out: &mut dyn Write, dest: VarId) -> fmt::Result {
+        write!(out, "{INDENT}{dest} = (int32_t)m->mem_pages;")
+    }
+
+    fn emit_memory_grow(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        delta: VarId,
+        max_pages: Option<usize>,
+    ) -> fmt::Result {
+        // Without an import limit, the module's own `mem_max_pages` bounds it.
+        let max = max_pages.map_or("UINT32_MAX".to_string(), |max| format!("{max}u"));
+        write!(
+            out,
+            "{INDENT}{dest} = wasm_memory_grow(m, (uint32_t){delta}, {max});"
+        )
+    }
+
+    fn emit_memory_copy(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        src: VarId,
+        len: VarId,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "{INDENT}WASM_TRY(wasm_memory_copy(m, {dst}, {src}, {len}));"
+        )
+    }
+
+    fn emit_memory_fill(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        val: VarId,
+        len: VarId,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "{INDENT}WASM_TRY(wasm_memory_fill(m, {dst}, {val}, {len}));"
+        )
+    }
+
+    fn emit_memory_init(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        src_offset: VarId,
+        len: VarId,
+        segment_const_name: &str,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "{INDENT}WASM_TRY(wasm_memory_init(m, {dst}, {segment_const_name}, {segment_const_name}_LEN, {src_offset}, {len}));"
+        )
+    }
+
+    fn emit_data_drop(&self, out: &mut dyn Write, segment: u32) -> fmt::Result {
+        write!(
+            out,
+            "{INDENT}/* data.drop segment {segment} (no-op: const array) */"
+        )
+    }
+
+    fn emit_unreachable(&self, out: &mut dyn Write) -> fmt::Result {
+        write!(out, "{INDENT}return WASM_TRAP_UNREACHABLE;")
+    }
+
+    fn emit_yield_point(&self, out: &mut dyn Write) -> fmt::Result {
+        write!(out, "{INDENT}WASM_TRY(wasm_host_maybe_yield(m));")
+    }
+
+    fn emit_jump_to_index(&self, out: &mut dyn Write, target_idx: usize) -> fmt::Result {
+        write!(out, "{INDENT}goto B{target_idx};")
+    }
+
+    fn emit_branch_if_to_index(
+        &self,
+        out: &mut dyn Write,
+        condition: VarId,
+        if_true_idx: usize,
+        if_false_idx: usize,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "{INDENT}if ({condition} != 0) goto B{if_true_idx};\n{INDENT}goto B{if_false_idx};"
+        )
+    }
+
+    fn emit_branch_table_to_index(
+        &self,
+        out: &mut dyn Write,
+        index: VarId,
+        target_indices: &[usize],
+        default_idx: usize,
+    ) -> fmt::Result {
+        if target_indices.is_empty() {
+            return self.emit_jump_to_index(out, default_idx);
+        }
+        writeln!(out, "{INDENT}switch ((uint32_t){index}) {{")?;
+        for (i, target_idx) in target_indices.iter().enumerate() {
+            writeln!(out, "{INDENT}case {i}u: goto B{target_idx};")?;
+        }
+        writeln!(out, "{INDENT}default: goto B{default_idx};")?;
+        write!(out, "{INDENT}}}")
+    }
+}
+
+/// Bytes accessed by a sub-width load or store.
+fn access_size(width: MemoryAccessWidth) -> u32 {
+    match width {
+        MemoryAccessWidth::I8 => 1,
+        MemoryAccessWidth::I16 => 2,
+        MemoryAccessWidth::I32 | MemoryAccessWidth::Full => 4,
+    }
+}
+
+/// `WASM_TRY(helper(lhs, rhs, &dest));`
+fn write_try(
+    out: &mut dyn Write,
+    helper: &str,
+    lhs: VarId,
+    rhs: VarId,
+    dest: VarId,
+) -> fmt::Result {
+    write!(out, "{INDENT}WASM_TRY({helper}({lhs}, {rhs}, &{dest}));")
+}
+
+/// `WASM_TRY(helper(operand, &dest));`
+fn write_try_unary(out: &mut dyn Write, helper: &str, operand: VarId, dest: VarId) -> fmt::Result {
+    write!(out, "{INDENT}WASM_TRY({helper}({operand}, &{dest}));")
+}
@@ -1,13 +1,18 @@
 //! Code generation backends.
 //!
 //! The Backend trait abstracts the difference between safe, verified, and hybrid
-//! code generation. Each backend emits different Rust code for the same IR.
+//! code generation. Each backend emits different Rust code for the same IR;
+//! the experimental C backend emits C99 statements instead.
 
 mod safe;
 pub use safe::SafeBackend;
 
+pub(crate) mod c;
+pub use c::CBackend;
+
 use crate::ir::*;
 use anyhow::Result;
+use std::fmt::{self, Write};
 
 /// Code generation backend trait.
 ///
@@ -15,118 +20,205 @@ use anyhow::Result;
 /// - SafeBackend: bounds-checked, returns Result
 /// - VerifiedBackend: unsafe + proof comments (Milestone 6)
 /// - HybridBackend: mix of safe and unsafe (Milestone 6)
+/// - CBackend: C99 for [`codegen::c`](crate::codegen::c), experimental
+///
+/// Every method appends its code to `out` (one statement, indented, without a
+/// trailing newline) instead of returning a fresh `String`, so codegen can
+/// stream a whole module into a single preallocated buffer.
 pub trait Backend {
     /// Emit Rust code for a constant value.
-    fn emit_const(&self, dest: VarId, value: &IrValue) -> String;
+    fn emit_const(&self, out: &mut dyn Write, dest: VarId, value: &IrValue) -> fmt::Result;
 
     /// Emit Rust code for a binary operation.
-    fn emit_binop(&self, dest: VarId, op: BinOp, lhs: VarId, rhs: VarId) -> String;
+    fn emit_binop(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        op: BinOp,
+        lhs: VarId,
+        rhs: VarId,
+    ) -> fmt::Result;
 
     /// Emit Rust code for a unary operation.
-    fn emit_unop(&self, dest: VarId, op: UnOp, operand: VarId) -> String;
+    fn emit_unop(&self, out: &mut dyn Write, dest: VarId, op: UnOp, operand: VarId) -> fmt::Result;
 
     /// Emit Rust code for a memory load (full or sub-width).
+    #[allow(clippy::too_many_arguments)]
     fn emit_load(
         &self,
+        out: &mut dyn Write,
         dest: VarId,
         ty: WasmType,
         addr: VarId,
         offset: u32,
         width: MemoryAccessWidth,
         sign: Option<SignExtension>,
-    ) -> Result<String>;
+    ) -> Result<()>;
 
     /// Emit Rust code for a memory store (full or sub-width).
     fn emit_store(
         &self,
+        out: &mut dyn Write,
         ty: WasmType,
         addr: VarId,
         value: VarId,
         offset: u32,
         width: MemoryAccessWidth,
-    ) -> Result<String>;
+    ) -> Result<()>;
 
-    /// Emit Rust code for a function call (local function).
+    /// Emit Rust code for a function call (local function `func`, the
+    /// callee's [`ModuleInfo::func_ident`](crate::ir::ModuleInfo::func_ident)).
     /// All functions uniformly take env, memory, and table parameters.
     fn emit_call(
         &self,
-        dest: Option<VarId>,
-        func_idx: usize,
+        out: &mut dyn Write,
+        dests: &[VarId],
+        func: &str,
         args: &[VarId],
         has_memory: bool,
         has_table: bool,
-    ) -> String;
+    ) -> fmt::Result;
 
     /// Emit Rust code for an imported function call.
     /// Generates `host.func_name(args)?`
     fn emit_call_import(
         &self,
-        dest: Option<VarId>,
+        out: &mut dyn Write,
+        dests: &[VarId],
         module_name: &str,
         func_name: &str,
         args: &[VarId],
-    ) -> String;
+    ) -> fmt::Result;
 
     /// Emit Rust code for reading a global variable.
-    /// Mutable globals: `globals.g{index}`, immutable: `G{index}` (const item).
-    fn emit_global_get(&self, dest: VarId, index: usize, is_mutable: bool) -> String;
-
-    /// Emit Rust code for writing a mutable global variable.
-    fn emit_global_set(&self, index: usize, value: VarId) -> String;
+    /// `Globals` fields (`is_mutable`, also set for globals initialized from
+    /// imports): `globals.{field}`, otherwise `G{index}` (const item).
+    fn emit_global_get(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        index: usize,
+        field: &str,
+        is_mutable: bool,
+    ) -> fmt::Result;
+
+    /// Emit Rust code for writing a mutable global variable (`Globals` field
+    /// `field`).
+    fn emit_global_set(
+        &self,
+        out: &mut dyn Write,
+        index: usize,
+        field: &str,
+        value: VarId,
+    ) -> fmt::Result;
 
     /// Emit Rust code for an assignment.
-    fn emit_assign(&self, dest: VarId, src: VarId) -> String;
+    fn emit_assign(&self, out: &mut dyn Write, dest: VarId, src: VarId) -> fmt::Result;
 
     /// Emit Rust code for select (conditional move).
-    fn emit_select(&self, dest: VarId, val1: VarId, val2: VarId, condition: VarId) -> String;
+    fn emit_select(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        val1: VarId,
+        val2: VarId,
+        condition: VarId,
+    ) -> fmt::Result;
+
+    /// Emit code for a select of `ty` values that does not branch on
+    /// `condition`, for constant-time functions.
+    fn emit_select_branchless(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        val1: VarId,
+        val2: VarId,
+        condition: VarId,
+        ty: WasmType,
+    ) -> fmt::Result;
 
-    /// Emit Rust code for a return statement.
-    fn emit_return(&self, value: Option<VarId>) -> String;
+    /// Emit Rust code for a return statement returning `values`.
+    fn emit_return(&self, out: &mut dyn Write, values: &[VarId]) -> fmt::Result;
 
     /// Emit Rust code for memory.size (returns current page count as i32).
-    fn emit_memory_size(&self, dest: VarId) -> String;
+    fn emit_memory_size(&self, out: &mut dyn Write, dest: VarId) -> fmt::Result;
 
     /// Emit Rust code for memory.grow (grows by delta pages, returns old size or -1).
-    fn emit_memory_grow(&self, dest: VarId, delta: VarId) -> String;
+    ///
+    /// `max_pages` is the limit declared by a memory import, checked on top of
+    /// the borrowed memory's own capacity.
+    fn emit_memory_grow(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        delta: VarId,
+        max_pages: Option<usize>,
+    ) -> fmt::Result;
 
     /// Emit Rust code for memory.copy (copies len bytes from src to dst).
-    fn emit_memory_copy(&self, dst: VarId, src: VarId, len: VarId) -> String;
+    fn emit_memory_copy(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        src: VarId,
+        len: VarId,
+    ) -> fmt::Result;
 
     /// Emit Rust code for memory.fill (fills len bytes at dst with byte val).
-    fn emit_memory_fill(&self, dst: VarId, val: VarId, len: VarId) -> String;
+    fn emit_memory_fill(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        val: VarId,
+        len: VarId,
+    ) -> fmt::Result;
 
     /// Emit Rust code for memory.init (copies len bytes from passive segment
     /// `segment_const_name` at src_offset into memory at dst).
     fn emit_memory_init(
         &self,
+        out: &mut dyn Write,
         dst: VarId,
         src_offset: VarId,
         len: VarId,
         segment_const_name: &str,
-    ) -> String;
+    ) -> fmt::Result;
 
     /// Emit Rust code for data.drop (no-op in the safe backend).
-    fn emit_data_drop(&self, segment: u32) -> String;
+    fn emit_data_drop(&self, out: &mut dyn Write, segment: u32) -> fmt::Result;
 
     /// Emit Rust code for unreachable.
-    fn emit_unreachable(&self) -> String;
+    fn emit_unreachable(&self, out: &mut dyn Write) -> fmt::Result;
+
+    /// Emit Rust code for a yield point (`env.host.maybe_yield()?`).
+    fn emit_yield_point(&self, out: &mut dyn Write) -> fmt::Result;
 
     /// Emit Rust code for an unconditional jump using block index.
-    fn emit_jump_to_index(&self, target_idx: usize) -> String;
+    fn emit_jump_to_index(&self, out: &mut dyn Write, target_idx: usize) -> fmt::Result;
 
     /// Emit Rust code for a conditional branch using block indices.
     fn emit_branch_if_to_index(
         &self,
+        out: &mut dyn Write,
         condition: VarId,
         if_true_idx: usize,
         if_false_idx: usize,
-    ) -> String;
+    ) -> fmt::Result;
 
     /// Emit Rust code for multi-way branch (br_table) using block indices.
     fn emit_branch_table_to_index(
         &self,
+        out: &mut dyn Write,
         index: VarId,
         target_indices: &[usize],
         default_idx: usize,
-    ) -> String;
+    ) -> fmt::Result;
+
+    /// Whether the code emitted for `instr` compiles inside a `const fn`.
+    ///
+    /// Terminators always do. Backends that never emit `const fn` keep the
+    /// default.
+    fn is_const_instr(&self, _instr: &IrInstr) -> bool {
+        false
+    }
 }
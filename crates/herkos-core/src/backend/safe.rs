@@ -4,54 +4,129 @@
 //! runtime bounds checks on memory accesses. All operations return `WasmResult<T>`.
 
 use crate::backend::Backend;
+use crate::codegen::types::{f32_literal, f64_literal};
+use crate::codegen::utils::rust_ident;
 use crate::ir::*;
+use std::fmt::{self, Write};
 
 const INDENT: &str = "                ";
 
-/// Format a function call result assignment.
-fn emit_call_result(dest: Option<VarId>, call_expr: &str) -> String {
-    match dest {
-        Some(d) => format!("{}{} = {};", INDENT, d, call_expr),
-        None => format!("{}{};", INDENT, call_expr),
+/// Write the `dest = ` prefix of a call statement: `(d0, d1) = ` for a call
+/// with several results, just the indent for void calls.
+fn write_call_prefix(out: &mut dyn Write, dests: &[VarId]) -> fmt::Result {
+    out.write_str(INDENT)?;
+    match crate::codegen::utils::format_vars(dests) {
+        Some(dests) => write!(out, "{dests} = "),
+        None => Ok(()),
     }
 }
 
-/// Emit a f32 const, handling NaN and infinity special values.
-fn emit_f32_const(dest: VarId, value: f32) -> String {
-    if value.is_nan() {
-        format!("{}{dest} = f32::NAN;", INDENT)
-    } else if value.is_infinite() {
-        if value.is_sign_positive() {
-            format!("{}{dest} = f32::INFINITY;", INDENT)
-        } else {
-            format!("{}{dest} = f32::NEG_INFINITY;", INDENT)
+/// Write a comma-separated list of variables, without a trailing separator.
+fn write_args(out: &mut dyn Write, args: &[VarId]) -> fmt::Result {
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            out.write_str(", ")?;
         }
-    } else {
-        format!("{}{dest} = {value}f32;", INDENT)
+        write!(out, "{arg}")?;
     }
+    Ok(())
+}
+
+/// Effective address of a memory access, formatted without allocating.
+struct AddrExpr {
+    addr: VarId,
+    offset: u32,
+    /// Through `effective_address`, correct for any `usize` width.
+    portable: bool,
 }
 
-/// Emit a f64 const, handling NaN and infinity special values.
-fn emit_f64_const(dest: VarId, value: f64) -> String {
-    if value.is_nan() {
-        format!("{}{dest} = f64::NAN;", INDENT)
-    } else if value.is_infinite() {
-        if value.is_sign_positive() {
-            format!("{}{dest} = f64::INFINITY;", INDENT)
+impl fmt::Display for AddrExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.portable {
+            write!(f, "effective_address({}, {})", self.addr, self.offset)
+        } else if self.offset > 0 {
+            // Unsigned first: a sign-extended address would wrap back into
+            // bounds past the offset
+            write!(
+                f,
+                "({} as u32 as usize).wrapping_add({}_usize)",
+                self.addr, self.offset
+            )
         } else {
-            format!("{}{dest} = f64::NEG_INFINITY;", INDENT)
+            write!(f, "{} as usize", self.addr)
         }
-    } else {
-        format!("{}{dest} = {value}f64;", INDENT)
+    }
+}
+
+/// Emit a shift or rotate. Wasm takes the count modulo the operand width
+/// (`i32.shl` by 33 shifts by 1, by -1 shifts by 31); the mask is explicit
+/// so nothing depends on how Rust treats larger counts.
+fn emit_shift(out: &mut dyn Write, dest: VarId, op: BinOp, lhs: VarId, rhs: VarId) -> fmt::Result {
+    let (ty, unsigned, method) = match op {
+        BinOp::I32Shl => ("i32", None, "wrapping_shl"),
+        BinOp::I32ShrS => ("i32", None, "wrapping_shr"),
+        BinOp::I32ShrU => ("i32", Some("u32"), "wrapping_shr"),
+        BinOp::I32Rotl => ("i32", None, "rotate_left"),
+        BinOp::I32Rotr => ("i32", None, "rotate_right"),
+        BinOp::I64Shl => ("i64", None, "wrapping_shl"),
+        BinOp::I64ShrS => ("i64", None, "wrapping_shr"),
+        BinOp::I64ShrU => ("i64", Some("u64"), "wrapping_shr"),
+        BinOp::I64Rotl => ("i64", None, "rotate_left"),
+        BinOp::I64Rotr => ("i64", None, "rotate_right"),
+        _ => unreachable!("{op:?} is not a shift"),
+    };
+    let mask = if ty == "i32" { 31 } else { 63 };
+    match unsigned {
+        Some(unsigned) => write!(
+            out,
+            "                {dest} = ({lhs} as {unsigned}).{method}(({rhs} & {mask}) as u32) as {ty};"
+        ),
+        None => write!(
+            out,
+            "                {dest} = {lhs}.{method}(({rhs} & {mask}) as u32);"
+        ),
     }
 }
 
 /// Safe code generation backend.
-pub struct SafeBackend;
+pub struct SafeBackend {
+    checked_arithmetic: bool,
+    portable: bool,
+}
 
 impl SafeBackend {
     pub fn new() -> Self {
-        SafeBackend
+        SafeBackend {
+            checked_arithmetic: false,
+            portable: false,
+        }
+    }
+
+    /// Emit integer `add`, `sub` and `mul` as signed `checked_*` operations
+    /// that trap with `WasmTrap::HostAssertedOverflow` instead of wrapping.
+    ///
+    /// Not spec-conformant: meant for finding unintended wraparound in ported
+    /// C code while testing.
+    pub fn with_checked_arithmetic(mut self, checked: bool) -> Self {
+        self.checked_arithmetic = checked;
+        self
+    }
+
+    /// Emit only code that compiles on every target: float rounding and
+    /// square roots through the runtime's `core`-only helpers, and addresses
+    /// through `effective_address` rather than `usize` arithmetic.
+    pub fn with_portable(mut self, portable: bool) -> Self {
+        self.portable = portable;
+        self
+    }
+
+    /// Cast of a Wasm `i32` address or length to `usize`.
+    fn usize_cast(&self) -> &'static str {
+        if self.portable {
+            "as u32 as usize"
+        } else {
+            "as usize"
+        }
     }
 }
 
@@ -62,121 +137,175 @@ impl Default for SafeBackend {
 }
 
 impl Backend for SafeBackend {
-    fn emit_const(&self, dest: VarId, value: &IrValue) -> String {
+    fn emit_const(&self, out: &mut dyn Write, dest: VarId, value: &IrValue) -> fmt::Result {
         match value {
-            IrValue::I32(v) => format!("                {dest} = {v}i32;"),
-            IrValue::I64(v) => format!("                {dest} = {v}i64;"),
-            IrValue::F32(v) => emit_f32_const(dest, *v),
-            IrValue::F64(v) => emit_f64_const(dest, *v),
+            IrValue::I32(v) => write!(out, "                {dest} = {v}i32;"),
+            IrValue::I64(v) => write!(out, "                {dest} = {v}i64;"),
+            IrValue::F32(v) => write!(out, "{INDENT}{dest} = {};", f32_literal(*v)),
+            IrValue::F64(v) => write!(out, "{INDENT}{dest} = {};", f64_literal(*v)),
         }
     }
 
-    fn emit_binop(&self, dest: VarId, op: BinOp, lhs: VarId, rhs: VarId) -> String {
+    fn emit_binop(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        op: BinOp,
+        lhs: VarId,
+        rhs: VarId,
+    ) -> fmt::Result {
+        if self.checked_arithmetic {
+            let method = match op {
+                BinOp::I32Add | BinOp::I64Add => Some("checked_add"),
+                BinOp::I32Sub | BinOp::I64Sub => Some("checked_sub"),
+                BinOp::I32Mul | BinOp::I64Mul => Some("checked_mul"),
+                _ => None,
+            };
+            if let Some(method) = method {
+                return write!(
+                    out,
+                    "{INDENT}{dest} = {lhs}.{method}({rhs}).ok_or(WasmTrap::HostAssertedOverflow)?;"
+                );
+            }
+        }
+
         let rust_op = match op {
             // i32 arithmetic - Wasm uses wrapping semantics
-            BinOp::I32Add => return format!("                {dest} = {lhs}.wrapping_add({rhs});"),
-            BinOp::I32Sub => return format!("                {dest} = {lhs}.wrapping_sub({rhs});"),
-            BinOp::I32Mul => return format!("                {dest} = {lhs}.wrapping_mul({rhs});"),
+            BinOp::I32Add => {
+                return write!(out, "                {dest} = {lhs}.wrapping_add({rhs});")
+            }
+            BinOp::I32Sub => {
+                return write!(out, "                {dest} = {lhs}.wrapping_sub({rhs});")
+            }
+            BinOp::I32Mul => {
+                return write!(out, "                {dest} = {lhs}.wrapping_mul({rhs});")
+            }
             BinOp::I32DivS => {
-                return format!("                {dest} = i32_div_s({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i32_div_s({lhs}, {rhs})?;");
             }
             BinOp::I32DivU => {
-                return format!("                {dest} = i32_div_u({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i32_div_u({lhs}, {rhs})?;");
             }
             BinOp::I32RemS => {
-                return format!("                {dest} = i32_rem_s({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i32_rem_s({lhs}, {rhs})?;");
             }
             BinOp::I32RemU => {
-                return format!("                {dest} = i32_rem_u({lhs}, {rhs})?;");
-            }
-            BinOp::I32And => return format!("                {dest} = {lhs} & {rhs};"),
-            BinOp::I32Or => return format!("                {dest} = {lhs} | {rhs};"),
-            BinOp::I32Xor => return format!("                {dest} = {lhs} ^ {rhs};"),
-            BinOp::I32Shl => return format!("                {dest} = {lhs}.wrapping_shl(({rhs} & 31) as u32);"),
-            BinOp::I32ShrS => return format!("                {dest} = {lhs}.wrapping_shr(({rhs} & 31) as u32);"),
-            BinOp::I32ShrU => {
-                return format!("                {dest} = ({lhs} as u32).wrapping_shr(({rhs} & 31) as u32) as i32;")
-            }
-            BinOp::I32Rotl => return format!("                {dest} = {lhs}.rotate_left(({rhs} & 31) as u32);"),
-            BinOp::I32Rotr => return format!("                {dest} = {lhs}.rotate_right(({rhs} & 31) as u32);"),
+                return write!(out, "                {dest} = i32_rem_u({lhs}, {rhs})?;");
+            }
+            BinOp::I32And => return write!(out, "                {dest} = {lhs} & {rhs};"),
+            BinOp::I32Or => return write!(out, "                {dest} = {lhs} | {rhs};"),
+            BinOp::I32Xor => return write!(out, "                {dest} = {lhs} ^ {rhs};"),
+            BinOp::I32Shl
+            | BinOp::I32ShrS
+            | BinOp::I32ShrU
+            | BinOp::I32Rotl
+            | BinOp::I32Rotr
+            | BinOp::I64Shl
+            | BinOp::I64ShrS
+            | BinOp::I64ShrU
+            | BinOp::I64Rotl
+            | BinOp::I64Rotr => return emit_shift(out, dest, op, lhs, rhs),
 
             // i32 comparisons
             BinOp::I32Eq => "==",
             BinOp::I32Ne => "!=",
             BinOp::I32LtS => "<",
             BinOp::I32LtU => {
-                return format!("                {dest} = if ({lhs} as u32) < ({rhs} as u32) {{ 1 }} else {{ 0 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u32) < ({rhs} as u32));"
+                )
             }
             BinOp::I32GtS => ">",
             BinOp::I32GtU => {
-                return format!("                {dest} = if ({lhs} as u32) > ({rhs} as u32) {{ 1 }} else {{ 0 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u32) > ({rhs} as u32));"
+                )
             }
             BinOp::I32LeS => "<=",
             BinOp::I32LeU => {
-                return format!("                {dest} = if ({lhs} as u32) <= ({rhs} as u32) {{ 1 }} else {{ 0 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u32) <= ({rhs} as u32));"
+                )
             }
             BinOp::I32GeS => ">=",
             BinOp::I32GeU => {
-                return format!("                {dest} = if ({lhs} as u32) >= ({rhs} as u32) {{ 1 }} else {{ 0 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u32) >= ({rhs} as u32));"
+                )
             }
 
             // i64 arithmetic (same pattern as i32)
-            BinOp::I64Add => return format!("                {dest} = {lhs}.wrapping_add({rhs});"),
-            BinOp::I64Sub => return format!("                {dest} = {lhs}.wrapping_sub({rhs});"),
-            BinOp::I64Mul => return format!("                {dest} = {lhs}.wrapping_mul({rhs});"),
+            BinOp::I64Add => {
+                return write!(out, "                {dest} = {lhs}.wrapping_add({rhs});")
+            }
+            BinOp::I64Sub => {
+                return write!(out, "                {dest} = {lhs}.wrapping_sub({rhs});")
+            }
+            BinOp::I64Mul => {
+                return write!(out, "                {dest} = {lhs}.wrapping_mul({rhs});")
+            }
             BinOp::I64DivS => {
-                return format!("                {dest} = i64_div_s({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i64_div_s({lhs}, {rhs})?;");
             }
             BinOp::I64DivU => {
-                return format!("                {dest} = i64_div_u({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i64_div_u({lhs}, {rhs})?;");
             }
             BinOp::I64RemS => {
-                return format!("                {dest} = i64_rem_s({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i64_rem_s({lhs}, {rhs})?;");
             }
             BinOp::I64RemU => {
-                return format!("                {dest} = i64_rem_u({lhs}, {rhs})?;");
+                return write!(out, "                {dest} = i64_rem_u({lhs}, {rhs})?;");
             }
-            BinOp::I64And => return format!("                {dest} = {lhs} & {rhs};"),
-            BinOp::I64Or => return format!("                {dest} = {lhs} | {rhs};"),
-            BinOp::I64Xor => return format!("                {dest} = {lhs} ^ {rhs};"),
-            BinOp::I64Shl => return format!("                {dest} = {lhs}.wrapping_shl(({rhs} & 63) as u32);"),
-            BinOp::I64ShrS => return format!("                {dest} = {lhs}.wrapping_shr(({rhs} & 63) as u32);"),
-            BinOp::I64ShrU => {
-                return format!("                {dest} = ({lhs} as u64).wrapping_shr(({rhs} & 63) as u32) as i64;")
-            }
-            BinOp::I64Rotl => return format!("                {dest} = {lhs}.rotate_left(({rhs} & 63) as u32);"),
-            BinOp::I64Rotr => return format!("                {dest} = {lhs}.rotate_right(({rhs} & 63) as u32);"),
+            BinOp::I64And => return write!(out, "                {dest} = {lhs} & {rhs};"),
+            BinOp::I64Or => return write!(out, "                {dest} = {lhs} | {rhs};"),
+            BinOp::I64Xor => return write!(out, "                {dest} = {lhs} ^ {rhs};"),
 
             // i64 comparisons
             BinOp::I64Eq => {
-                return format!("                {dest} = if {lhs} == {rhs} {{ 1i32 }} else {{ 0i32 }};")
+                return write!(out, "                {dest} = i32::from({lhs} == {rhs});")
             }
             BinOp::I64Ne => {
-                return format!("                {dest} = if {lhs} != {rhs} {{ 1i32 }} else {{ 0i32 }};")
+                return write!(out, "                {dest} = i32::from({lhs} != {rhs});")
             }
             BinOp::I64LtS => {
-                return format!("                {dest} = if {lhs} < {rhs} {{ 1i32 }} else {{ 0i32 }};")
+                return write!(out, "                {dest} = i32::from({lhs} < {rhs});")
             }
             BinOp::I64LtU => {
-                return format!("                {dest} = if ({lhs} as u64) < ({rhs} as u64) {{ 1i32 }} else {{ 0i32 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u64) < ({rhs} as u64));"
+                )
             }
             BinOp::I64GtS => {
-                return format!("                {dest} = if {lhs} > {rhs} {{ 1i32 }} else {{ 0i32 }};")
+                return write!(out, "                {dest} = i32::from({lhs} > {rhs});")
             }
             BinOp::I64GtU => {
-                return format!("                {dest} = if ({lhs} as u64) > ({rhs} as u64) {{ 1i32 }} else {{ 0i32 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u64) > ({rhs} as u64));"
+                )
             }
             BinOp::I64LeS => {
-                return format!("                {dest} = if {lhs} <= {rhs} {{ 1i32 }} else {{ 0i32 }};")
+                return write!(out, "                {dest} = i32::from({lhs} <= {rhs});")
             }
             BinOp::I64LeU => {
-                return format!("                {dest} = if ({lhs} as u64) <= ({rhs} as u64) {{ 1i32 }} else {{ 0i32 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u64) <= ({rhs} as u64));"
+                )
             }
             BinOp::I64GeS => {
-                return format!("                {dest} = if {lhs} >= {rhs} {{ 1i32 }} else {{ 0i32 }};")
+                return write!(out, "                {dest} = i32::from({lhs} >= {rhs});")
             }
             BinOp::I64GeU => {
-                return format!("                {dest} = if ({lhs} as u64) >= ({rhs} as u64) {{ 1i32 }} else {{ 0i32 }};")
+                return write!(
+                    out,
+                    "                {dest} = i32::from(({lhs} as u64) >= ({rhs} as u64));"
+                )
             }
 
             // f32/f64 arithmetic (no wrapping needed)
@@ -184,17 +313,21 @@ impl Backend for SafeBackend {
             BinOp::F32Sub => "-",
             BinOp::F32Mul => "*",
             BinOp::F32Div => "/",
-            BinOp::F32Min => return format!("                {dest} = {lhs}.min({rhs});"),
-            BinOp::F32Max => return format!("                {dest} = {lhs}.max({rhs});"),
-            BinOp::F32Copysign => return format!("                {dest} = {lhs}.copysign({rhs});"),
+            BinOp::F32Min => return write!(out, "{INDENT}{dest} = wasm_min_f32({lhs}, {rhs});"),
+            BinOp::F32Max => return write!(out, "{INDENT}{dest} = wasm_max_f32({lhs}, {rhs});"),
+            BinOp::F32Copysign => {
+                return write!(out, "                {dest} = {lhs}.copysign({rhs});")
+            }
 
             BinOp::F64Add => "+",
             BinOp::F64Sub => "-",
             BinOp::F64Mul => "*",
             BinOp::F64Div => "/",
-            BinOp::F64Min => return format!("                {dest} = {lhs}.min({rhs});"),
-            BinOp::F64Max => return format!("                {dest} = {lhs}.max({rhs});"),
-            BinOp::F64Copysign => return format!("                {dest} = {lhs}.copysign({rhs});"),
+            BinOp::F64Min => return write!(out, "{INDENT}{dest} = wasm_min_f64({lhs}, {rhs});"),
+            BinOp::F64Max => return write!(out, "{INDENT}{dest} = wasm_max_f64({lhs}, {rhs});"),
+            BinOp::F64Copysign => {
+                return write!(out, "                {dest} = {lhs}.copysign({rhs});")
+            }
 
             // Float comparisons
             BinOp::F32Eq => "==",
@@ -234,388 +367,587 @@ impl Backend for SafeBackend {
                 | BinOp::F64Le
                 | BinOp::F64Ge
         ) {
-            format!("                {dest} = if {lhs} {rust_op} {rhs} {{ 1i32 }} else {{ 0i32 }};")
+            write!(
+                out,
+                "                {dest} = i32::from({lhs} {rust_op} {rhs});"
+            )
         } else {
-            format!("                {dest} = {lhs} {rust_op} {rhs};")
+            write!(out, "                {dest} = {lhs} {rust_op} {rhs};")
         }
     }
 
-    fn emit_unop(&self, dest: VarId, op: UnOp, operand: VarId) -> String {
+    fn emit_unop(&self, out: &mut dyn Write, dest: VarId, op: UnOp, operand: VarId) -> fmt::Result {
         match op {
-            UnOp::I32Clz => format!("                {dest} = {operand}.leading_zeros() as i32;"),
-            UnOp::I32Ctz => format!("                {dest} = {operand}.trailing_zeros() as i32;"),
-            UnOp::I32Popcnt => format!("                {dest} = {operand}.count_ones() as i32;"),
+            UnOp::I32Clz => write!(
+                out,
+                "                {dest} = {operand}.leading_zeros() as i32;"
+            ),
+            UnOp::I32Ctz => write!(
+                out,
+                "                {dest} = {operand}.trailing_zeros() as i32;"
+            ),
+            UnOp::I32Popcnt => write!(
+                out,
+                "                {dest} = {operand}.count_ones() as i32;"
+            ),
             UnOp::I32Eqz => {
-                format!("                {dest} = if {operand} == 0 {{ 1 }} else {{ 0 }};")
+                write!(out, "                {dest} = i32::from({operand} == 0);")
             }
 
             UnOp::I64Eqz => {
-                format!("                {dest} = if {operand} == 0 {{ 1i32 }} else {{ 0i32 }};")
-            }
-            UnOp::I64Clz => format!("                {dest} = {operand}.leading_zeros() as i64;"),
-            UnOp::I64Ctz => format!("                {dest} = {operand}.trailing_zeros() as i64;"),
-            UnOp::I64Popcnt => format!("                {dest} = {operand}.count_ones() as i64;"),
-
-            UnOp::F32Abs => format!("                {dest} = {operand}.abs();"),
-            UnOp::F32Neg => format!("                {dest} = -{operand};"),
-            UnOp::F32Sqrt => format!("                {dest} = {operand}.sqrt();"),
-            UnOp::F32Ceil => format!("                {dest} = {operand}.ceil();"),
-            UnOp::F32Floor => format!("                {dest} = {operand}.floor();"),
-            UnOp::F32Trunc => format!("                {dest} = {operand}.trunc();"),
-            UnOp::F32Nearest => format!("                {dest} = {operand}.round_ties_even();"),
-
-            UnOp::F64Abs => format!("                {dest} = {operand}.abs();"),
-            UnOp::F64Neg => format!("                {dest} = -{operand};"),
-            UnOp::F64Sqrt => format!("                {dest} = {operand}.sqrt();"),
-            UnOp::F64Ceil => format!("                {dest} = {operand}.ceil();"),
-            UnOp::F64Floor => format!("                {dest} = {operand}.floor();"),
-            UnOp::F64Trunc => format!("                {dest} = {operand}.trunc();"),
-            UnOp::F64Nearest => format!("                {dest} = {operand}.round_ties_even();"),
+                write!(out, "                {dest} = i32::from({operand} == 0);")
+            }
+            UnOp::I64Clz => write!(
+                out,
+                "                {dest} = {operand}.leading_zeros() as i64;"
+            ),
+            UnOp::I64Ctz => write!(
+                out,
+                "                {dest} = {operand}.trailing_zeros() as i64;"
+            ),
+            UnOp::I64Popcnt => write!(
+                out,
+                "                {dest} = {operand}.count_ones() as i64;"
+            ),
+
+            UnOp::F32Abs => write!(out, "                {dest} = {operand}.abs();"),
+            UnOp::F32Neg => write!(out, "                {dest} = -{operand};"),
+            UnOp::F64Abs => write!(out, "                {dest} = {operand}.abs();"),
+            UnOp::F64Neg => write!(out, "                {dest} = -{operand};"),
+
+            // `std`-only methods, or the runtime's `core` versions
+            UnOp::F32Sqrt
+            | UnOp::F32Ceil
+            | UnOp::F32Floor
+            | UnOp::F32Trunc
+            | UnOp::F32Nearest
+            | UnOp::F64Sqrt
+            | UnOp::F64Ceil
+            | UnOp::F64Floor
+            | UnOp::F64Trunc
+            | UnOp::F64Nearest => {
+                let (method, ty) = match op {
+                    UnOp::F32Sqrt => ("sqrt", "f32"),
+                    UnOp::F32Ceil => ("ceil", "f32"),
+                    UnOp::F32Floor => ("floor", "f32"),
+                    UnOp::F32Trunc => ("trunc", "f32"),
+                    UnOp::F32Nearest => ("nearest", "f32"),
+                    UnOp::F64Sqrt => ("sqrt", "f64"),
+                    UnOp::F64Ceil => ("ceil", "f64"),
+                    UnOp::F64Floor => ("floor", "f64"),
+                    UnOp::F64Trunc => ("trunc", "f64"),
+                    _ => ("nearest", "f64"),
+                };
+                if self.portable {
+                    write!(out, "{INDENT}{dest} = wasm_{method}_{ty}({operand});")
+                } else if method == "nearest" {
+                    write!(out, "{INDENT}{dest} = {operand}.round_ties_even();")
+                } else {
+                    write!(out, "{INDENT}{dest} = {operand}.{method}();")
+                }
+            }
 
             // === Conversion operations ===
 
             // Integer truncation/extension
-            UnOp::I32WrapI64 => format!("                {dest} = {operand} as i32;"),
-            UnOp::I64ExtendI32S => format!("                {dest} = {operand} as i64;"),
-            UnOp::I64ExtendI32U => format!("                {dest} = ({operand} as u32) as i64;"),
+            UnOp::I32WrapI64 => write!(out, "                {dest} = {operand} as i32;"),
+            UnOp::I64ExtendI32S => write!(out, "                {dest} = {operand} as i64;"),
+            UnOp::I64ExtendI32U => {
+                write!(out, "                {dest} = ({operand} as u32) as i64;")
+            }
             // Sign-extension ops
-            UnOp::I32Extend8S => format!("                {dest} = ({operand} as i8) as i32;"),
-            UnOp::I32Extend16S => format!("                {dest} = ({operand} as i16) as i32;"),
-            UnOp::I64Extend8S => format!("                {dest} = ({operand} as i8) as i64;"),
-            UnOp::I64Extend16S => format!("                {dest} = ({operand} as i16) as i64;"),
-            UnOp::I64Extend32S => format!("                {dest} = ({operand} as i32) as i64;"),
+            UnOp::I32Extend8S => write!(out, "                {dest} = ({operand} as i8) as i32;"),
+            UnOp::I32Extend16S => {
+                write!(out, "                {dest} = ({operand} as i16) as i32;")
+            }
+            UnOp::I64Extend8S => write!(out, "                {dest} = ({operand} as i8) as i64;"),
+            UnOp::I64Extend16S => {
+                write!(out, "                {dest} = ({operand} as i16) as i64;")
+            }
+            UnOp::I64Extend32S => {
+                write!(out, "                {dest} = ({operand} as i32) as i64;")
+            }
 
             // Float → i32 (trapping on NaN/overflow) — logic lives in herkos_runtime::ops
             UnOp::I32TruncF32S => {
-                format!("                {dest} = i32_trunc_f32_s({operand})?;")
+                write!(out, "                {dest} = i32_trunc_f32_s({operand})?;")
             }
             UnOp::I32TruncF32U => {
-                format!("                {dest} = i32_trunc_f32_u({operand})?;")
+                write!(out, "                {dest} = i32_trunc_f32_u({operand})?;")
             }
             UnOp::I32TruncF64S => {
-                format!("                {dest} = i32_trunc_f64_s({operand})?;")
+                write!(out, "                {dest} = i32_trunc_f64_s({operand})?;")
             }
             UnOp::I32TruncF64U => {
-                format!("                {dest} = i32_trunc_f64_u({operand})?;")
+                write!(out, "                {dest} = i32_trunc_f64_u({operand})?;")
             }
 
             // Float → i64 (trapping on NaN/overflow) — logic lives in herkos_runtime::ops
             UnOp::I64TruncF32S => {
-                format!("                {dest} = i64_trunc_f32_s({operand})?;")
+                write!(out, "                {dest} = i64_trunc_f32_s({operand})?;")
             }
             UnOp::I64TruncF32U => {
-                format!("                {dest} = i64_trunc_f32_u({operand})?;")
+                write!(out, "                {dest} = i64_trunc_f32_u({operand})?;")
             }
             UnOp::I64TruncF64S => {
-                format!("                {dest} = i64_trunc_f64_s({operand})?;")
+                write!(out, "                {dest} = i64_trunc_f64_s({operand})?;")
             }
             UnOp::I64TruncF64U => {
-                format!("                {dest} = i64_trunc_f64_u({operand})?;")
+                write!(out, "                {dest} = i64_trunc_f64_u({operand})?;")
             }
 
             // Integer → float
-            UnOp::F32ConvertI32S => format!("                {dest} = {operand} as f32;"),
-            UnOp::F32ConvertI32U => format!("                {dest} = ({operand} as u32) as f32;"),
-            UnOp::F32ConvertI64S => format!("                {dest} = {operand} as f32;"),
-            UnOp::F32ConvertI64U => format!("                {dest} = ({operand} as u64) as f32;"),
-            UnOp::F64ConvertI32S => format!("                {dest} = {operand} as f64;"),
-            UnOp::F64ConvertI32U => format!("                {dest} = ({operand} as u32) as f64;"),
-            UnOp::F64ConvertI64S => format!("                {dest} = {operand} as f64;"),
-            UnOp::F64ConvertI64U => format!("                {dest} = ({operand} as u64) as f64;"),
+            UnOp::F32ConvertI32S => write!(out, "                {dest} = {operand} as f32;"),
+            UnOp::F32ConvertI32U => {
+                write!(out, "                {dest} = ({operand} as u32) as f32;")
+            }
+            UnOp::F32ConvertI64S => write!(out, "                {dest} = {operand} as f32;"),
+            UnOp::F32ConvertI64U => {
+                write!(out, "                {dest} = ({operand} as u64) as f32;")
+            }
+            UnOp::F64ConvertI32S => write!(out, "                {dest} = {operand} as f64;"),
+            UnOp::F64ConvertI32U => {
+                write!(out, "                {dest} = ({operand} as u32) as f64;")
+            }
+            UnOp::F64ConvertI64S => write!(out, "                {dest} = {operand} as f64;"),
+            UnOp::F64ConvertI64U => {
+                write!(out, "                {dest} = ({operand} as u64) as f64;")
+            }
 
             // Float precision
-            UnOp::F32DemoteF64 => format!("                {dest} = {operand} as f32;"),
-            UnOp::F64PromoteF32 => format!("                {dest} = {operand} as f64;"),
+            UnOp::F32DemoteF64 => write!(out, "                {dest} = {operand} as f32;"),
+            UnOp::F64PromoteF32 => write!(out, "                {dest} = {operand} as f64;"),
 
             // Reinterpretations (bitcast)
             UnOp::I32ReinterpretF32 => {
-                format!("                {dest} = {operand}.to_bits() as i32;")
+                write!(out, "                {dest} = {operand}.to_bits() as i32;")
             }
             UnOp::I64ReinterpretF64 => {
-                format!("                {dest} = {operand}.to_bits() as i64;")
+                write!(out, "                {dest} = {operand}.to_bits() as i64;")
             }
             UnOp::F32ReinterpretI32 => {
-                format!("                {dest} = f32::from_bits({operand} as u32);")
+                write!(
+                    out,
+                    "                {dest} = f32::from_bits({operand} as u32);"
+                )
             }
             UnOp::F64ReinterpretI64 => {
-                format!("                {dest} = f64::from_bits({operand} as u64);")
+                write!(
+                    out,
+                    "                {dest} = f64::from_bits({operand} as u64);"
+                )
             }
         }
     }
 
     fn emit_load(
         &self,
+        out: &mut dyn Write,
         dest: VarId,
         ty: WasmType,
         addr: VarId,
         offset: u32,
         width: MemoryAccessWidth,
         sign: Option<SignExtension>,
-    ) -> anyhow::Result<String> {
-        let addr_expr = if offset > 0 {
-            format!("({addr} as usize).wrapping_add({offset}_usize)")
-        } else {
-            format!("{addr} as usize")
+    ) -> anyhow::Result<()> {
+        let addr_expr = AddrExpr {
+            addr,
+            offset,
+            portable: self.portable,
         };
 
-        let load_expr = match (ty, width, sign) {
+        // (runtime method, cast applied to the loaded value)
+        let (method, cast) = match (ty, width, sign) {
             // Full-width loads
-            (WasmType::I32, MemoryAccessWidth::Full, _) => {
-                format!("memory.load_i32({addr_expr})?")
-            }
-            (WasmType::I64, MemoryAccessWidth::Full, _) => {
-                format!("memory.load_i64({addr_expr})?")
-            }
-            (WasmType::F32, MemoryAccessWidth::Full, _) => {
-                format!("memory.load_f32({addr_expr})?")
-            }
-            (WasmType::F64, MemoryAccessWidth::Full, _) => {
-                format!("memory.load_f64({addr_expr})?")
-            }
+            (WasmType::I32, MemoryAccessWidth::Full, _) => ("load_i32", ""),
+            (WasmType::I64, MemoryAccessWidth::Full, _) => ("load_i64", ""),
+            (WasmType::F32, MemoryAccessWidth::Full, _) => ("load_f32", ""),
+            (WasmType::F64, MemoryAccessWidth::Full, _) => ("load_f64", ""),
 
             // i32.load8_s / i32.load8_u
             (WasmType::I32, MemoryAccessWidth::I8, Some(SignExtension::Signed)) => {
-                format!("memory.load_u8({addr_expr})? as i8 as i32")
+                ("load_u8", " as i8 as i32")
             }
             (WasmType::I32, MemoryAccessWidth::I8, Some(SignExtension::Unsigned)) => {
-                format!("memory.load_u8({addr_expr})? as i32")
+                ("load_u8", " as i32")
             }
 
             // i32.load16_s / i32.load16_u
             (WasmType::I32, MemoryAccessWidth::I16, Some(SignExtension::Signed)) => {
-                format!("memory.load_u16({addr_expr})? as i16 as i32")
+                ("load_u16", " as i16 as i32")
             }
             (WasmType::I32, MemoryAccessWidth::I16, Some(SignExtension::Unsigned)) => {
-                format!("memory.load_u16({addr_expr})? as i32")
+                ("load_u16", " as i32")
             }
 
             // i64.load8_s / i64.load8_u
             (WasmType::I64, MemoryAccessWidth::I8, Some(SignExtension::Signed)) => {
-                format!("memory.load_u8({addr_expr})? as i8 as i64")
+                ("load_u8", " as i8 as i64")
             }
             (WasmType::I64, MemoryAccessWidth::I8, Some(SignExtension::Unsigned)) => {
-                format!("memory.load_u8({addr_expr})? as i64")
+                ("load_u8", " as i64")
             }
 
             // i64.load16_s / i64.load16_u
             (WasmType::I64, MemoryAccessWidth::I16, Some(SignExtension::Signed)) => {
-                format!("memory.load_u16({addr_expr})? as i16 as i64")
+                ("load_u16", " as i16 as i64")
             }
             (WasmType::I64, MemoryAccessWidth::I16, Some(SignExtension::Unsigned)) => {
-                format!("memory.load_u16({addr_expr})? as i64")
+                ("load_u16", " as i64")
             }
 
             // i64.load32_s / i64.load32_u
             (WasmType::I64, MemoryAccessWidth::I32, Some(SignExtension::Signed)) => {
-                format!("memory.load_i32({addr_expr})? as i64")
+                ("load_i32", " as i64")
             }
             (WasmType::I64, MemoryAccessWidth::I32, Some(SignExtension::Unsigned)) => {
-                format!("memory.load_i32({addr_expr})? as u32 as i64")
+                ("load_i32", " as u32 as i64")
             }
 
             // Invalid combinations (shouldn't occur in valid Wasm)
             _ => anyhow::bail!("unsupported load: {ty:?} width={width:?} sign={sign:?}"),
         };
 
-        Ok(format!("                {dest} = {load_expr};"))
+        write!(out, "{INDENT}{dest} = memory.{method}({addr_expr})?{cast};")?;
+        Ok(())
     }
 
     fn emit_store(
         &self,
+        out: &mut dyn Write,
         ty: WasmType,
         addr: VarId,
         value: VarId,
         offset: u32,
         width: MemoryAccessWidth,
-    ) -> anyhow::Result<String> {
-        let addr_expr = if offset > 0 {
-            format!("({addr} as usize).wrapping_add({offset}_usize)")
-        } else {
-            format!("{addr} as usize")
+    ) -> anyhow::Result<()> {
+        let addr_expr = AddrExpr {
+            addr,
+            offset,
+            portable: self.portable,
         };
 
-        let store_call = match (ty, width) {
+        // (runtime method, cast applied to the stored value)
+        let (method, cast) = match (ty, width) {
             // Full-width stores
-            (WasmType::I32, MemoryAccessWidth::Full) => {
-                format!("memory.store_i32({addr_expr}, {value})?")
-            }
-            (WasmType::I64, MemoryAccessWidth::Full) => {
-                format!("memory.store_i64({addr_expr}, {value})?")
-            }
-            (WasmType::F32, MemoryAccessWidth::Full) => {
-                format!("memory.store_f32({addr_expr}, {value})?")
-            }
-            (WasmType::F64, MemoryAccessWidth::Full) => {
-                format!("memory.store_f64({addr_expr}, {value})?")
-            }
+            (WasmType::I32, MemoryAccessWidth::Full) => ("store_i32", ""),
+            (WasmType::I64, MemoryAccessWidth::Full) => ("store_i64", ""),
+            (WasmType::F32, MemoryAccessWidth::Full) => ("store_f32", ""),
+            (WasmType::F64, MemoryAccessWidth::Full) => ("store_f64", ""),
 
             // i32.store8 / i64.store8
-            (WasmType::I32 | WasmType::I64, MemoryAccessWidth::I8) => {
-                format!("memory.store_u8({addr_expr}, {value} as u8)?")
-            }
+            (WasmType::I32 | WasmType::I64, MemoryAccessWidth::I8) => ("store_u8", " as u8"),
 
             // i32.store16 / i64.store16
-            (WasmType::I32 | WasmType::I64, MemoryAccessWidth::I16) => {
-                format!("memory.store_u16({addr_expr}, {value} as u16)?")
-            }
+            (WasmType::I32 | WasmType::I64, MemoryAccessWidth::I16) => ("store_u16", " as u16"),
 
             // i64.store32
-            (WasmType::I64, MemoryAccessWidth::I32) => {
-                format!("memory.store_i32({addr_expr}, {value} as i32)?")
-            }
+            (WasmType::I64, MemoryAccessWidth::I32) => ("store_i32", " as i32"),
 
             // Invalid combinations (shouldn't occur in valid Wasm)
             _ => anyhow::bail!("unsupported store: {ty:?} width={width:?}"),
         };
 
-        Ok(format!("                {store_call};"))
+        write!(out, "{INDENT}memory.{method}({addr_expr}, {value}{cast})?;")?;
+        Ok(())
     }
 
     fn emit_call(
         &self,
-        dest: Option<VarId>,
-        func_idx: usize,
+        out: &mut dyn Write,
+        dests: &[VarId],
+        func: &str,
         args: &[VarId],
         has_memory: bool,
         has_table: bool,
-    ) -> String {
-        let mut call_args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    ) -> fmt::Result {
+        write_call_prefix(out, dests)?;
+        write!(out, "{func}(")?;
+        write_args(out, args)?;
+        if !args.is_empty() {
+            out.write_str(", ")?;
+        }
         // All functions uniformly receive env
-        call_args.push("env".to_string());
+        out.write_str("env")?;
         if has_memory {
-            call_args.push("memory".to_string());
+            out.write_str(", memory")?;
         }
         if has_table {
-            call_args.push("table".to_string());
+            out.write_str(", table")?;
         }
-        let call_expr = format!("func_{}({})?", func_idx, call_args.join(", "));
-        emit_call_result(dest, &call_expr)
+        out.write_str(")?;")
     }
 
     fn emit_call_import(
         &self,
-        dest: Option<VarId>,
+        out: &mut dyn Write,
+        dests: &[VarId],
         _module_name: &str,
         func_name: &str,
         args: &[VarId],
-    ) -> String {
+    ) -> fmt::Result {
         // Generate: env.host.func_name(args)?
         // Note: module_name is ignored for now (Milestone 3 will use it for trait names)
-        let args_str: Vec<String> = args.iter().map(|a| a.to_string()).collect();
-        let call_expr = format!("env.host.{}({})?", func_name, args_str.join(", "));
-        emit_call_result(dest, &call_expr)
+        write_call_prefix(out, dests)?;
+        write!(out, "env.host.{}(", rust_ident(func_name))?;
+        write_args(out, args)?;
+        out.write_str(")?;")
     }
 
-    fn emit_global_get(&self, dest: VarId, index: usize, is_mutable: bool) -> String {
+    fn emit_global_get(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        index: usize,
+        field: &str,
+        is_mutable: bool,
+    ) -> fmt::Result {
         if is_mutable {
-            format!("                {dest} = env.globals.g{index};")
+            write!(out, "                {dest} = env.globals.{field};")
         } else {
-            format!("                {dest} = G{index};")
+            write!(out, "                {dest} = G{index};")
         }
     }
 
-    fn emit_global_set(&self, index: usize, value: VarId) -> String {
-        format!("                env.globals.g{index} = {value};")
+    fn emit_global_set(
+        &self,
+        out: &mut dyn Write,
+        _index: usize,
+        field: &str,
+        value: VarId,
+    ) -> fmt::Result {
+        write!(out, "                env.globals.{field} = {value};")
     }
 
-    fn emit_assign(&self, dest: VarId, src: VarId) -> String {
-        format!("                {dest} = {src};")
+    fn emit_assign(&self, out: &mut dyn Write, dest: VarId, src: VarId) -> fmt::Result {
+        write!(out, "                {dest} = {src};")
     }
 
-    fn emit_select(&self, dest: VarId, val1: VarId, val2: VarId, condition: VarId) -> String {
-        format!("                {dest} = if {condition} != 0 {{ {val1} }} else {{ {val2} }};")
+    fn emit_select(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        val1: VarId,
+        val2: VarId,
+        condition: VarId,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "                {dest} = if {condition} != 0 {{ {val1} }} else {{ {val2} }};"
+        )
     }
 
-    fn emit_return(&self, value: Option<VarId>) -> String {
-        match value {
-            Some(v) => format!("                return Ok({v});"),
-            None => "                return Ok(());".to_string(),
+    fn emit_select_branchless(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        val1: VarId,
+        val2: VarId,
+        condition: VarId,
+        _ty: WasmType,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "                {dest} = ct_select({condition}, {val1}, {val2});"
+        )
+    }
+
+    fn emit_return(&self, out: &mut dyn Write, values: &[VarId]) -> fmt::Result {
+        match crate::codegen::utils::format_vars(values) {
+            Some(values) => write!(out, "                return Ok({values});"),
+            None => out.write_str("                return Ok(());"),
         }
     }
 
-    fn emit_memory_size(&self, dest: VarId) -> String {
-        format!("                {dest} = memory.size();")
+    fn emit_memory_size(&self, out: &mut dyn Write, dest: VarId) -> fmt::Result {
+        write!(out, "                {dest} = memory.size();")
     }
 
-    fn emit_memory_grow(&self, dest: VarId, delta: VarId) -> String {
-        format!("                {dest} = memory.grow({delta} as u32);")
+    fn emit_memory_grow(
+        &self,
+        out: &mut dyn Write,
+        dest: VarId,
+        delta: VarId,
+        max_pages: Option<usize>,
+    ) -> fmt::Result {
+        match max_pages {
+            Some(max) => write!(
+                out,
+                "                {dest} = memory.grow_within({delta} as u32, {max});"
+            ),
+            None => write!(out, "                {dest} = memory.grow({delta} as u32);"),
+        }
     }
 
-    fn emit_memory_copy(&self, dst: VarId, src: VarId, len: VarId) -> String {
-        format!("                memory.memory_copy({dst} as u32, {src} as u32, {len} as u32)?;")
+    fn emit_memory_copy(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        src: VarId,
+        len: VarId,
+    ) -> fmt::Result {
+        write!(
+            out,
+            "                memory.memory_copy({dst} as u32, {src} as u32, {len} as u32)?;"
+        )
     }
 
-    fn emit_memory_fill(&self, dst: VarId, val: VarId, len: VarId) -> String {
-        format!("                memory.fill({dst} as usize, {val} as u8, {len} as usize)?;")
+    fn emit_memory_fill(
+        &self,
+        out: &mut dyn Write,
+        dst: VarId,
+        val: VarId,
+        len: VarId,
+    ) -> fmt::Result {
+        let cast = self.usize_cast();
+        write!(
+            out,
+            "{INDENT}memory.fill({dst} {cast}, {val} as u8, {len} {cast})?;"
+        )
     }
 
     fn emit_memory_init(
         &self,
+        out: &mut dyn Write,
         dst: VarId,
         src_offset: VarId,
         len: VarId,
         segment_const_name: &str,
-    ) -> String {
-        format!("                memory.init_data_partial({dst} as usize, {segment_const_name}, {src_offset} as usize, {len} as usize)?;")
+    ) -> fmt::Result {
+        let cast = self.usize_cast();
+        write!(out, "{INDENT}memory.init_data_partial({dst} {cast}, {segment_const_name}, {src_offset} {cast}, {len} {cast})?;")
     }
 
-    fn emit_data_drop(&self, segment: u32) -> String {
-        format!("                // data.drop segment {segment} (no-op: const slice)")
+    fn emit_data_drop(&self, out: &mut dyn Write, segment: u32) -> fmt::Result {
+        write!(
+            out,
+            "                // data.drop segment {segment} (no-op: const slice)"
+        )
     }
 
-    fn emit_unreachable(&self) -> String {
-        "    return Err(WasmTrap::Unreachable);".to_string()
+    fn emit_unreachable(&self, out: &mut dyn Write) -> fmt::Result {
+        out.write_str("    return Err(WasmTrap::Unreachable);")
     }
 
-    fn emit_jump_to_index(&self, target_idx: usize) -> String {
-        format!(
-            "                __current_block = Block::B{};\n                continue;",
-            target_idx
+    fn emit_yield_point(&self, out: &mut dyn Write) -> fmt::Result {
+        write!(
+            out,
+            "                env.host.{}()?;",
+            crate::ir::YIELD_METHOD
+        )
+    }
+
+    fn emit_jump_to_index(&self, out: &mut dyn Write, target_idx: usize) -> fmt::Result {
+        write!(
+            out,
+            "                __current_block = Block::B{target_idx};"
         )
     }
 
     fn emit_branch_if_to_index(
         &self,
+        out: &mut dyn Write,
         condition: VarId,
         if_true_idx: usize,
         if_false_idx: usize,
-    ) -> String {
-        format!(
-            "                if {condition} != 0 {{\n                    __current_block = Block::B{};\n                }} else {{\n                    __current_block = Block::B{};\n                }}\n                continue;",
-            if_true_idx, if_false_idx
+    ) -> fmt::Result {
+        write!(
+            out,
+            "                if {condition} != 0 {{\n                    __current_block = Block::B{if_true_idx};\n                }} else {{\n                    __current_block = Block::B{if_false_idx};\n                }}"
         )
     }
 
     fn emit_branch_table_to_index(
         &self,
+        out: &mut dyn Write,
         index: VarId,
         target_indices: &[usize],
         default_idx: usize,
-    ) -> String {
+    ) -> fmt::Result {
         if target_indices.is_empty() {
             // No targets, always jump to default
-            return format!(
-                "                __current_block = Block::B{};\n                continue;",
-                default_idx
-            );
+            return self.emit_jump_to_index(out, default_idx);
         }
 
-        let mut code = format!("                __current_block = match {index} as usize {{\n");
-
+        writeln!(
+            out,
+            "                __current_block = match {index} as usize {{"
+        )?;
         for (i, target_idx) in target_indices.iter().enumerate() {
-            code.push_str(&format!(
-                "                    {} => Block::B{},\n",
-                i, target_idx
-            ));
+            writeln!(out, "                    {i} => Block::B{target_idx},")?;
         }
+        writeln!(out, "                    _ => Block::B{default_idx},")?;
+        out.write_str("                };")
+    }
 
-        code.push_str(&format!(
-            "                    _ => Block::B{},\n",
-            default_idx
-        ));
-        code.push_str("                };\n");
-        code.push_str("                continue;");
-
-        code
+    fn is_const_instr(&self, instr: &IrInstr) -> bool {
+        match instr {
+            IrInstr::Const { .. } | IrInstr::Assign { .. } | IrInstr::Select { .. } => true,
+            IrInstr::BinOp { op, .. } => match op {
+                BinOp::I32Add
+                | BinOp::I32Sub
+                | BinOp::I32Mul
+                | BinOp::I64Add
+                | BinOp::I64Sub
+                | BinOp::I64Mul => !self.checked_arithmetic,
+                BinOp::I32And
+                | BinOp::I32Or
+                | BinOp::I32Xor
+                | BinOp::I32Shl
+                | BinOp::I32ShrS
+                | BinOp::I32ShrU
+                | BinOp::I32Rotl
+                | BinOp::I32Rotr
+                | BinOp::I64And
+                | BinOp::I64Or
+                | BinOp::I64Xor
+                | BinOp::I64Shl
+                | BinOp::I64ShrS
+                | BinOp::I64ShrU
+                | BinOp::I64Rotl
+                | BinOp::I64Rotr
+                | BinOp::F32Add
+                | BinOp::F32Sub
+                | BinOp::F32Mul
+                | BinOp::F32Div
+                | BinOp::F64Add
+                | BinOp::F64Sub
+                | BinOp::F64Mul
+                | BinOp::F64Div
+                | BinOp::F32Min
+                | BinOp::F32Max
+                | BinOp::F64Min
+                | BinOp::F64Max => true,
+                // Division traps through the runtime, comparisons go
+                // through `i32::from`, copysign is not const
+                _ => false,
+            },
+            IrInstr::UnOp { op, .. } => !matches!(
+                op,
+                UnOp::I32Eqz
+                    | UnOp::I64Eqz
+                    | UnOp::F32Abs
+                    | UnOp::F32Sqrt
+                    | UnOp::F32Ceil
+                    | UnOp::F32Floor
+                    | UnOp::F32Trunc
+                    | UnOp::F32Nearest
+                    | UnOp::F64Abs
+                    | UnOp::F64Sqrt
+                    | UnOp::F64Ceil
+                    | UnOp::F64Floor
+                    | UnOp::F64Trunc
+                    | UnOp::F64Nearest
+                    | UnOp::I32TruncF32S
+                    | UnOp::I32TruncF32U
+                    | UnOp::I32TruncF64S
+                    | UnOp::I32TruncF64U
+                    | UnOp::I64TruncF32S
+                    | UnOp::I64TruncF32U
+                    | UnOp::I64TruncF64S
+                    | UnOp::I64TruncF64U
+            ),
+            _ => false,
+        }
     }
 }
@@ -0,0 +1,845 @@
+//! High-level export bindings.
+//!
+//! Wasm exports only speak scalars, so a C `hash(const uint8_t *data, size_t
+//! len, uint8_t out[32])` surfaces as `fn hash(&mut self, v0: i32, v1: i32,
+//! v2: i32)`. A bindings file describes what those pointers mean; codegen then
+//! emits an additional wrapper that copies host data in and out of linear
+//! memory:
+//!
+//! ```text
+//! # Guest address of the region used to stage host data.
+//! scratch 0x10000
+//!
+//! struct Point { x: i32, y: i32 }
+//!
+//! export hash(data: &[u8], digest: out [u8; 32])
+//! export hash as hash_text(text: &str, digest: out [u8; 32])
+//! export translate(p: &Point, dx: i32, moved: out Point)
+//! ```
+//!
+//! The first `export` line yields
+//! `fn hash_bound(&mut self, data: &[u8]) -> WasmResult<[u8; 32]>` next to the
+//! raw `hash` method. Wrappers are named `<export>_bound` unless `as` gives an
+//! explicit name.
+//!
+//! | Binding type    | Wasm params | Wrapper behaviour                            |
+//! |-----------------|-------------|----------------------------------------------|
+//! | `i32` … `f64`   | same type   | passed through                               |
+//! | `&[u8]`, `&str` | ptr, len    | copied into guest memory before the call     |
+//! | `&mut [u8]`     | ptr, len    | copied in, and back out after the call       |
+//! | `&Name`         | ptr         | struct fields stored into guest memory       |
+//! | `out [u8; N]`   | ptr         | N bytes read back and returned               |
+//! | `out i32` …     | ptr         | scalar loaded back and returned              |
+//! | `out Name`      | ptr         | struct loaded back and returned              |
+//!
+//! Struct fields use C layout: each field is aligned to its own size and the
+//! struct size is rounded up to its largest alignment.
+//!
+//! Inputs and out-parameters are staged in the `scratch` region when one is
+//! declared. Without it, wrappers fall back to the module's own allocator
+//! (see [`GuestAllocator`]): each buffer is obtained through
+//! `alloc_in_guest` and released through `free_in_guest` after the call.
+//!
+//! `import` lines describe the pointers a host import receives, with the
+//! same types:
+//!
+//! ```text
+//! import env.write(fd: i32, buf: &[u8])
+//! import env.get_time(now: out i64)
+//! ```
+//!
+//! Every call to such an import is routed through a generated shim that
+//! checks each region — `(ptr, len)` for slices, `ptr` plus the size of the
+//! type otherwise — lies in linear memory, and traps with `OutOfBounds`
+//! before the host runs if not (see [`crate::codegen::import_checks`]).
+
+use crate::ir::{GuestRegion, ModuleInfo, RegionLen, Toolchain, WasmType};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/// Parsed and validated bindings file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BindingConfig {
+    /// Guest base address of the scratch region used by the wrappers.
+    pub scratch: Option<u32>,
+    /// Guest struct layouts, in declaration order.
+    pub structs: Vec<StructLayout>,
+    /// Export bindings, in declaration order.
+    pub exports: Vec<ExportBinding>,
+    /// Import bindings, in declaration order.
+    pub imports: Vec<ImportBinding>,
+}
+
+/// A guest struct with C layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructLayout {
+    /// Rust struct name emitted into the generated module.
+    pub name: String,
+    /// Fields with their byte offsets.
+    pub fields: Vec<StructField>,
+    /// Total size in bytes, including trailing padding.
+    pub size: u32,
+}
+
+/// A single struct field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructField {
+    /// Field name.
+    pub name: String,
+    /// Field type.
+    pub ty: WasmType,
+    /// Byte offset from the start of the struct.
+    pub offset: u32,
+}
+
+/// Wrapper definition for one export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportBinding {
+    /// Name of the wrapped export.
+    pub export: String,
+    /// Name of the generated wrapper method.
+    pub method: String,
+    /// Wrapper parameters, in Wasm parameter order.
+    pub params: Vec<BoundParam>,
+}
+
+/// Pointer arguments of one host import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportBinding {
+    /// Import module name.
+    pub module: String,
+    /// Import function name.
+    pub name: String,
+    /// Bound parameters, in Wasm parameter order.
+    pub params: Vec<BoundParam>,
+}
+
+impl ImportBinding {
+    /// The memory regions the arguments point to, by Wasm parameter index.
+    pub fn regions(&self, config: &BindingConfig) -> Vec<GuestRegion> {
+        let mut regions = Vec::new();
+        let mut index = 0;
+        for param in &self.params {
+            let len = match &param.kind {
+                BoundKind::Scalar(_) => None,
+                BoundKind::Bytes | BoundKind::BytesMut | BoundKind::Str => {
+                    Some(RegionLen::Param(index + 1))
+                }
+                BoundKind::OutBytes(len) => Some(RegionLen::Fixed(*len)),
+                BoundKind::OutScalar(ty) => Some(RegionLen::Fixed(scalar_size(*ty))),
+                BoundKind::Struct(name) | BoundKind::OutStruct(name) => config
+                    .struct_layout(name)
+                    .map(|layout| RegionLen::Fixed(layout.size)),
+            };
+            if let Some(len) = len {
+                regions.push(GuestRegion {
+                    name: param.name.clone(),
+                    ptr: index,
+                    len,
+                });
+            }
+            index += param.kind.wasm_params().len();
+        }
+        regions
+    }
+}
+
+/// A single wrapper parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundParam {
+    /// Parameter name (also used for the returned value of out-parameters).
+    pub name: String,
+    /// How the parameter maps onto Wasm parameters.
+    pub kind: BoundKind,
+}
+
+/// How a wrapper parameter maps onto Wasm parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoundKind {
+    /// Scalar passed through unchanged.
+    Scalar(WasmType),
+    /// `&[u8]` — copied in as `(ptr, len)`.
+    Bytes,
+    /// `&mut [u8]` — copied in as `(ptr, len)` and back out after the call.
+    BytesMut,
+    /// `&str` — UTF-8 bytes copied in as `(ptr, len)`.
+    Str,
+    /// `&Name` — struct stored in guest memory, passed as `ptr`.
+    Struct(String),
+    /// `out [u8; N]` — `ptr` to N bytes, returned as an array.
+    OutBytes(u32),
+    /// `out i32` etc. — `ptr` to a scalar, returned by value.
+    OutScalar(WasmType),
+    /// `out Name` — `ptr` to a struct, returned by value.
+    OutStruct(String),
+}
+
+impl BoundKind {
+    /// The Wasm parameters this binding consumes.
+    pub fn wasm_params(&self) -> Vec<WasmType> {
+        match self {
+            BoundKind::Scalar(ty) => vec![*ty],
+            BoundKind::Bytes | BoundKind::BytesMut | BoundKind::Str => {
+                vec![WasmType::I32, WasmType::I32]
+            }
+            BoundKind::Struct(_)
+            | BoundKind::OutBytes(_)
+            | BoundKind::OutScalar(_)
+            | BoundKind::OutStruct(_) => vec![WasmType::I32],
+        }
+    }
+
+    /// Whether the binding needs guest memory (everything except scalars).
+    pub fn uses_memory(&self) -> bool {
+        !matches!(self, BoundKind::Scalar(_))
+    }
+
+    /// Whether the binding produces a returned value.
+    pub fn is_output(&self) -> bool {
+        matches!(
+            self,
+            BoundKind::OutBytes(_) | BoundKind::OutScalar(_) | BoundKind::OutStruct(_)
+        )
+    }
+}
+
+impl BindingConfig {
+    /// Parse a bindings file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = BindingConfig::default();
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            config
+                .parse_line(line)
+                .with_context(|| format!("bindings line {}: `{}`", idx + 1, line))?;
+        }
+        Ok(config)
+    }
+
+    /// Whether the file declares no export bindings. Import bindings only
+    /// add checks to host calls, which every backend but C supports.
+    pub fn is_empty(&self) -> bool {
+        self.exports.is_empty()
+    }
+
+    /// Look up a struct layout by name.
+    pub fn struct_layout(&self, name: &str) -> Option<&StructLayout> {
+        self.structs.iter().find(|s| s.name == name)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match keyword {
+            "scratch" => {
+                if self.scratch.is_some() {
+                    bail!("duplicate `scratch` directive");
+                }
+                self.scratch = Some(parse_u32(rest)?);
+            }
+            "struct" => {
+                let layout = parse_struct(rest)?;
+                if self.struct_layout(&layout.name).is_some() {
+                    bail!("duplicate struct `{}`", layout.name);
+                }
+                self.structs.push(layout);
+            }
+            "export" => {
+                let binding = self.parse_export(rest)?;
+                if self.exports.iter().any(|e| e.method == binding.method) {
+                    bail!("duplicate wrapper method `{}`", binding.method);
+                }
+                self.exports.push(binding);
+            }
+            "import" => {
+                let binding = self.parse_import(rest)?;
+                if self
+                    .imports
+                    .iter()
+                    .any(|i| i.module == binding.module && i.name == binding.name)
+                {
+                    bail!("duplicate import `{}.{}`", binding.module, binding.name);
+                }
+                self.imports.push(binding);
+            }
+            other => bail!(
+                "unknown directive `{}` (expected `scratch`, `struct`, `export` or `import`)",
+                other
+            ),
+        }
+        Ok(())
+    }
+
+    fn parse_export(&self, rest: &str) -> Result<ExportBinding> {
+        let (head, params) = rest
+            .split_once('(')
+            .context("expected `export <name>(<params>)`")?;
+        let params = params
+            .trim_end()
+            .strip_suffix(')')
+            .context("missing closing `)`")?;
+
+        let mut words = head.split_whitespace();
+        let export = words.next().context("missing export name")?.to_string();
+        let method = match (words.next(), words.next(), words.next()) {
+            (None, _, _) => format!("{}_bound", export),
+            (Some("as"), Some(method), None) => method.to_string(),
+            _ => bail!("expected `export <name>` or `export <name> as <method>`"),
+        };
+        check_ident(&method)?;
+
+        Ok(ExportBinding {
+            export,
+            method,
+            params: self.parse_params(params)?,
+        })
+    }
+
+    fn parse_import(&self, rest: &str) -> Result<ImportBinding> {
+        let (head, params) = rest
+            .split_once('(')
+            .context("expected `import <module>.<name>(<params>)`")?;
+        let params = params
+            .trim_end()
+            .strip_suffix(')')
+            .context("missing closing `)`")?;
+        let (module, name) = head
+            .trim()
+            .split_once('.')
+            .filter(|(module, name)| !module.is_empty() && !name.is_empty())
+            .context("expected `import <module>.<name>(<params>)`")?;
+
+        Ok(ImportBinding {
+            module: module.to_string(),
+            name: name.to_string(),
+            params: self.parse_params(params)?,
+        })
+    }
+
+    fn parse_params(&self, params: &str) -> Result<Vec<BoundParam>> {
+        let mut bound = Vec::new();
+        for param in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, ty) = param
+                .split_once(':')
+                .with_context(|| format!("expected `name: type`, found `{}`", param))?;
+            let name = name.trim();
+            check_ident(name)?;
+            if name.starts_with("__") || matches!(name, "memory" | "host") {
+                bail!("parameter name `{}` is reserved", name);
+            }
+            if bound.iter().any(|p: &BoundParam| p.name == name) {
+                bail!("duplicate parameter `{}`", name);
+            }
+            bound.push(BoundParam {
+                name: name.to_string(),
+                kind: self.parse_kind(ty.trim())?,
+            });
+        }
+        Ok(bound)
+    }
+
+    fn parse_kind(&self, ty: &str) -> Result<BoundKind> {
+        if let Some(out) = ty.strip_prefix("out ") {
+            let out = out.trim();
+            if let Some(len) = out
+                .strip_prefix('[')
+                .and_then(|s| s.strip_suffix(']'))
+                .and_then(|s| s.split_once(';'))
+                .filter(|(elem, _)| elem.trim() == "u8")
+                .map(|(_, len)| len.trim())
+            {
+                return Ok(BoundKind::OutBytes(parse_u32(len)?));
+            }
+            if let Some(scalar) = parse_scalar(out) {
+                return Ok(BoundKind::OutScalar(scalar));
+            }
+            return Ok(BoundKind::OutStruct(self.known_struct(out)?));
+        }
+        if let Some(scalar) = parse_scalar(ty) {
+            return Ok(BoundKind::Scalar(scalar));
+        }
+        let compact: String = ty.split_whitespace().collect::<Vec<_>>().join(" ");
+        match compact.as_str() {
+            "&[u8]" => Ok(BoundKind::Bytes),
+            "&mut [u8]" => Ok(BoundKind::BytesMut),
+            "&str" => Ok(BoundKind::Str),
+            _ => match ty.strip_prefix('&') {
+                Some(name) => Ok(BoundKind::Struct(self.known_struct(name.trim())?)),
+                None => bail!("unsupported binding type `{}`", ty),
+            },
+        }
+    }
+
+    fn known_struct(&self, name: &str) -> Result<String> {
+        match self.struct_layout(name) {
+            Some(layout) => Ok(layout.name.clone()),
+            None => bail!("unknown struct `{}` (declare it before use)", name),
+        }
+    }
+
+    /// Check every binding against the module's exports and imports.
+    pub fn validate(&self, info: &ModuleInfo) -> Result<()> {
+        for binding in &self.exports {
+            self.validate_export(binding, info)
+                .with_context(|| format!("invalid binding for export `{}`", binding.export))?;
+        }
+        for binding in &self.imports {
+            validate_import(binding, info).with_context(|| {
+                format!(
+                    "invalid binding for import `{}.{}`",
+                    binding.module, binding.name
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The regions to check for each bound import of `info`, by index into
+    /// `func_imports`. Call after [`BindingConfig::validate`].
+    pub fn import_regions(&self, info: &ModuleInfo) -> BTreeMap<usize, Vec<GuestRegion>> {
+        let mut regions = BTreeMap::new();
+        for binding in &self.imports {
+            let Some(idx) = info
+                .func_imports
+                .iter()
+                .position(|i| *i.module_name == binding.module && *i.func_name == binding.name)
+            else {
+                continue;
+            };
+            let checked = binding.regions(self);
+            if !checked.is_empty() {
+                regions.insert(idx, checked);
+            }
+        }
+        regions
+    }
+
+    fn validate_export(&self, binding: &ExportBinding, info: &ModuleInfo) -> Result<()> {
+        let export = info
+            .func_exports
+            .iter()
+            .find(|e| e.name == binding.export)
+            .context("module has no such function export")?;
+        let func = info
+            .ir_function(export.func_index)
+            .context("export refers to a missing function")?;
+
+        if info.func_exports.iter().any(|e| e.name == binding.method) {
+            bail!(
+                "wrapper method `{}` collides with an export of the same name",
+                binding.method
+            );
+        }
+        if GuestAllocator::detect(info).is_some()
+            && [GuestAllocator::ALLOC_METHOD, GuestAllocator::FREE_METHOD]
+                .contains(&binding.method.as_str())
+        {
+            bail!(
+                "wrapper method `{}` collides with a generated guest allocator helper",
+                binding.method
+            );
+        }
+
+        let expected: Vec<WasmType> = binding
+            .params
+            .iter()
+            .flat_map(|p| p.kind.wasm_params())
+            .collect();
+        let actual: Vec<WasmType> = func.params.iter().map(|(_, ty)| *ty).collect();
+        if expected != actual {
+            bail!(
+                "bound parameters need Wasm signature ({}) but the export takes ({})",
+                format_types(&expected),
+                format_types(&actual)
+            );
+        }
+
+        if binding.params.iter().any(|p| p.kind.uses_memory()) {
+            if !info.has_memory && !info.has_memory_import {
+                bail!("pointer parameters require the module to have a linear memory");
+            }
+            if self.scratch.is_none() && GuestAllocator::detect(info).is_none() {
+                bail!(
+                    "pointer parameters require a `scratch <address>` directive or a guest \
+                     allocator export (`malloc`, `__wbindgen_malloc` or `cabi_realloc`)"
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_import(binding: &ImportBinding, info: &ModuleInfo) -> Result<()> {
+    let import = info
+        .func_imports
+        .iter()
+        .find(|i| *i.module_name == binding.module && *i.func_name == binding.name)
+        .context("module has no such function import")?;
+    let expected: Vec<WasmType> = binding
+        .params
+        .iter()
+        .flat_map(|p| p.kind.wasm_params())
+        .collect();
+    if expected != *import.params {
+        bail!(
+            "bound parameters need Wasm signature ({}) but the import takes ({})",
+            format_types(&expected),
+            format_types(&import.params)
+        );
+    }
+    if binding.params.iter().any(|p| p.kind.uses_memory())
+        && !info.has_memory
+        && !info.has_memory_import
+    {
+        bail!("pointer parameters require the module to have a linear memory");
+    }
+    Ok(())
+}
+
+/// Allocation entry point exported by the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuestAlloc {
+    /// `malloc(size) -> ptr` (C/C++ toolchains, `wasi-libc`).
+    Malloc,
+    /// `__wbindgen_malloc(size) -> ptr` (older `wasm-bindgen`).
+    WbindgenMalloc,
+    /// `__wbindgen_malloc(size, align) -> ptr`.
+    WbindgenMallocAligned,
+    /// `cabi_realloc(old_ptr, old_size, align, new_size) -> ptr` (canonical ABI).
+    CabiRealloc,
+}
+
+/// Deallocation entry point exported by the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuestFree {
+    /// `free(ptr)`.
+    Free,
+    /// `__wbindgen_free(ptr, size)` (older `wasm-bindgen`).
+    WbindgenFree,
+    /// `__wbindgen_free(ptr, size, align)`.
+    WbindgenFreeAligned,
+}
+
+impl GuestAlloc {
+    /// Name of the export.
+    pub fn export_name(self) -> &'static str {
+        match self {
+            GuestAlloc::Malloc => "malloc",
+            GuestAlloc::WbindgenMalloc | GuestAlloc::WbindgenMallocAligned => "__wbindgen_malloc",
+            GuestAlloc::CabiRealloc => "cabi_realloc",
+        }
+    }
+}
+
+impl GuestFree {
+    /// Name of the export.
+    pub fn export_name(self) -> &'static str {
+        match self {
+            GuestFree::Free => "free",
+            GuestFree::WbindgenFree | GuestFree::WbindgenFreeAligned => "__wbindgen_free",
+        }
+    }
+}
+
+/// The guest's own heap allocator, found among its exports.
+///
+/// Codegen emits `alloc_in_guest` (and `free_in_guest` when the guest also
+/// exports a matching deallocator) on top of it, and binding wrappers use
+/// them when no `scratch` region is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuestAllocator {
+    /// Allocation export.
+    pub alloc: GuestAlloc,
+    /// Matching deallocation export, if any. `cabi_realloc` has none.
+    pub free: Option<GuestFree>,
+}
+
+impl GuestAllocator {
+    /// Name of the generated allocation helper.
+    pub const ALLOC_METHOD: &'static str = "alloc_in_guest";
+    /// Name of the generated deallocation helper.
+    pub const FREE_METHOD: &'static str = "free_in_guest";
+
+    /// Find the guest allocator, preferring `malloc`, then `__wbindgen_malloc`,
+    /// then `cabi_realloc`. `__wbindgen_malloc` comes first in modules whose
+    /// `producers` section names wasm-bindgen.
+    ///
+    /// Exports only count when their signature matches the expected ABI.
+    /// Returns `None` for modules without a linear memory, without an
+    /// allocator export, or whose exports already use the helper names.
+    pub fn detect(info: &ModuleInfo) -> Option<Self> {
+        use WasmType::I32;
+        if !info.has_memory && !info.has_memory_import {
+            return None;
+        }
+        if info
+            .func_exports
+            .iter()
+            .any(|e| e.name == Self::ALLOC_METHOD || e.name == Self::FREE_METHOD)
+        {
+            return None;
+        }
+        let is = |name: &str, params: &[WasmType], result: Option<WasmType>| {
+            export_signature(info, name).is_some_and(|(p, r)| p == params && r == result.as_slice())
+        };
+        let malloc = is("malloc", &[I32], Some(I32)).then(|| GuestAllocator {
+            alloc: GuestAlloc::Malloc,
+            free: is("free", &[I32], None).then_some(GuestFree::Free),
+        });
+        let wbindgen = if is("__wbindgen_malloc", &[I32, I32], Some(I32)) {
+            Some(GuestAlloc::WbindgenMallocAligned)
+        } else if is("__wbindgen_malloc", &[I32], Some(I32)) {
+            Some(GuestAlloc::WbindgenMalloc)
+        } else {
+            None
+        };
+        let wbindgen = wbindgen.map(|alloc| {
+            let free = if is("__wbindgen_free", &[I32, I32, I32], None) {
+                Some(GuestFree::WbindgenFreeAligned)
+            } else if is("__wbindgen_free", &[I32, I32], None) {
+                Some(GuestFree::WbindgenFree)
+            } else {
+                None
+            };
+            GuestAllocator { alloc, free }
+        });
+        // wasm-bindgen's allocator owns the heap its glue expects, even when
+        // the Rust side also links a C `malloc`
+        let preferred = if info.built_with(Toolchain::WasmBindgen) {
+            wbindgen.or(malloc)
+        } else {
+            malloc.or(wbindgen)
+        };
+        if preferred.is_some() {
+            return preferred;
+        }
+        is("cabi_realloc", &[I32, I32, I32, I32], Some(I32)).then_some(GuestAllocator {
+            alloc: GuestAlloc::CabiRealloc,
+            free: None,
+        })
+    }
+}
+
+/// Parameter and result types of a function export.
+fn export_signature(info: &ModuleInfo, name: &str) -> Option<(Vec<WasmType>, Vec<WasmType>)> {
+    let export = info.func_exports.iter().find(|e| e.name == name)?;
+    let func = info.ir_function(export.func_index)?;
+    let params = func.params.iter().map(|(_, ty)| *ty).collect();
+    Some((params, func.results.clone()))
+}
+
+/// Parse a struct declaration: `Name { field: type, ... }`.
+fn parse_struct(rest: &str) -> Result<StructLayout> {
+    let (name, body) = rest
+        .split_once('{')
+        .context("expected `struct <Name> { <fields> }`")?;
+    let name = name.trim();
+    check_ident(name)?;
+    let body = body
+        .trim_end()
+        .strip_suffix('}')
+        .context("missing closing `}`")?;
+
+    let mut fields: Vec<StructField> = Vec::new();
+    let mut offset = 0u32;
+    let mut align = 1u32;
+    for field in body.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let (field_name, ty) = field
+            .split_once(':')
+            .with_context(|| format!("expected `name: type`, found `{}`", field))?;
+        let field_name = field_name.trim();
+        check_ident(field_name)?;
+        if fields.iter().any(|f| f.name == field_name) {
+            bail!("duplicate field `{}`", field_name);
+        }
+        let ty = parse_scalar(ty.trim())
+            .with_context(|| format!("field `{}` must be i32, i64, f32 or f64", field_name))?;
+        let size = scalar_size(ty);
+        offset = offset.next_multiple_of(size);
+        fields.push(StructField {
+            name: field_name.to_string(),
+            ty,
+            offset,
+        });
+        offset += size;
+        align = align.max(size);
+    }
+    if fields.is_empty() {
+        bail!("struct `{}` has no fields", name);
+    }
+
+    Ok(StructLayout {
+        name: name.to_string(),
+        fields,
+        size: offset.next_multiple_of(align),
+    })
+}
+
+/// Size (and alignment) of a Wasm scalar in linear memory.
+pub fn scalar_size(ty: WasmType) -> u32 {
+    match ty {
+        WasmType::I32 | WasmType::F32 => 4,
+        WasmType::I64 | WasmType::F64 => 8,
+    }
+}
+
+fn parse_scalar(ty: &str) -> Option<WasmType> {
+    match ty {
+        "i32" => Some(WasmType::I32),
+        "i64" => Some(WasmType::I64),
+        "f32" => Some(WasmType::F32),
+        "f64" => Some(WasmType::F64),
+        _ => None,
+    }
+}
+
+fn parse_u32(text: &str) -> Result<u32> {
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.with_context(|| format!("expected a 32-bit unsigned integer, found `{}`", text))
+}
+
+/// Rust keywords that cannot be used as generated identifiers.
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+fn check_ident(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || RUST_KEYWORDS.contains(&name) {
+        bail!("`{}` is not a valid identifier", name);
+    }
+    Ok(())
+}
+
+fn format_types(types: &[WasmType]) -> String {
+    types
+        .iter()
+        .map(|ty| crate::codegen::types::wasm_type_to_rust(ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+        # staging area
+        scratch 0x10000
+
+        struct Point { x: i32, y: i32 }
+        struct Mixed { a: i32, b: f64, c: f32 }
+
+        export hash(data: &[u8], digest: out [u8; 32])
+        export hash as hash_text(text: &str, digest: out [u8; 32])
+        export translate(p: &Point, dx: i32, moved: out Point)
+    "#;
+
+    #[test]
+    fn parses_example_file() {
+        let config = BindingConfig::parse(EXAMPLE).unwrap();
+        assert_eq!(config.scratch, Some(0x10000));
+        assert_eq!(config.structs.len(), 2);
+        assert_eq!(config.exports.len(), 3);
+
+        let hash = &config.exports[0];
+        assert_eq!(hash.export, "hash");
+        assert_eq!(hash.method, "hash_bound");
+        assert_eq!(hash.params[0].kind, BoundKind::Bytes);
+        assert_eq!(hash.params[1].kind, BoundKind::OutBytes(32));
+
+        assert_eq!(config.exports[1].method, "hash_text");
+        assert_eq!(config.exports[1].params[0].kind, BoundKind::Str);
+
+        let translate = &config.exports[2];
+        assert_eq!(
+            translate.params[0].kind,
+            BoundKind::Struct("Point".to_string())
+        );
+        assert_eq!(translate.params[1].kind, BoundKind::Scalar(WasmType::I32));
+        assert_eq!(
+            translate.params[2].kind,
+            BoundKind::OutStruct("Point".to_string())
+        );
+    }
+
+    #[test]
+    fn struct_layout_follows_c_rules() {
+        let config = BindingConfig::parse(EXAMPLE).unwrap();
+        let mixed = config.struct_layout("Mixed").unwrap();
+        let offsets: Vec<u32> = mixed.fields.iter().map(|f| f.offset).collect();
+        assert_eq!(offsets, vec![0, 8, 16]);
+        assert_eq!(mixed.size, 24);
+        assert_eq!(config.struct_layout("Point").unwrap().size, 8);
+    }
+
+    #[test]
+    fn wasm_params_expand_pointer_length_pairs() {
+        let config = BindingConfig::parse(EXAMPLE).unwrap();
+        let params: Vec<WasmType> = config.exports[0]
+            .params
+            .iter()
+            .flat_map(|p| p.kind.wasm_params())
+            .collect();
+        assert_eq!(params, vec![WasmType::I32; 3]);
+    }
+
+    #[test]
+    fn errors_report_line_numbers() {
+        let err = BindingConfig::parse("scratch 0\nexport f(p: &Missing)").unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("bindings line 2"), "{msg}");
+        assert!(msg.contains("unknown struct `Missing`"), "{msg}");
+    }
+
+    #[test]
+    fn import_regions_follow_the_bound_types() {
+        let config = BindingConfig::parse(
+            "struct Point { x: i32, y: i32 }\n\
+             import env.write(fd: i32, buf: &[u8], origin: &Point, done: out i64)",
+        )
+        .unwrap();
+        let write = &config.imports[0];
+        assert_eq!(
+            (write.module.as_str(), write.name.as_str()),
+            ("env", "write")
+        );
+        let regions = write.regions(&config);
+        let spans: Vec<(&str, usize, RegionLen)> = regions
+            .iter()
+            .map(|r| (r.name.as_str(), r.ptr, r.len))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("buf", 1, RegionLen::Param(2)),
+                ("origin", 3, RegionLen::Fixed(8)),
+                ("done", 4, RegionLen::Fixed(8)),
+            ]
+        );
+        assert!(BindingConfig::parse("import write(buf: &[u8])").is_err());
+        assert!(BindingConfig::parse("import env.f(a: i32)\nimport env.f(b: i32)").is_err());
+    }
+
+    #[test]
+    fn rejects_reserved_and_duplicate_names() {
+        assert!(BindingConfig::parse("export f(memory: i32)").is_err());
+        assert!(BindingConfig::parse("export f(__x: i32)").is_err());
+        assert!(BindingConfig::parse("export f(a: i32, a: i32)").is_err());
+        assert!(BindingConfig::parse("export f(a: i32)\nexport f(b: i32)").is_err());
+        assert!(BindingConfig::parse("struct P { x: u8 }").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! Host traits grouped by capability.
+//!
+//! Guest toolchains put most imports in one flat namespace (`env`), so the
+//! generated `ModuleHostTrait` mixes file access, graphics and logging in a
+//! single trait. A capabilities file regroups function imports into one
+//! trait per capability, to match how the host is organized:
+//!
+//! ```text
+//! # capability, then MODULE.NAME patterns; `*` matches any suffix
+//! capability FileSystem env.fd_*
+//! capability Graphics env.gl_* gpu.*
+//! capability Logging *.log
+//! ```
+//!
+//! Each capability becomes a `pub trait` holding the methods of the imports
+//! it matches, and `ModuleHostTrait` gets those traits as supertraits, so a
+//! host implements each capability separately and then
+//! `impl ModuleHostTrait for Host {}` with whatever stays ungrouped.
+//! Patterns are tried in file order and the first match wins. TinyGo
+//! runtime imports with default bodies stay in `ModuleHostTrait`, and
+//! imports routed to `GuestLog` (`--guest-log`) are not regrouped.
+
+use crate::analysis::detect_tinygo;
+use crate::codegen::tinygo::import_default;
+use crate::ir::{FuncImport, ModuleInfo};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/// Names the generated code already uses for its own items.
+const RESERVED: &[&str] = &[
+    "ModuleHostTrait",
+    "MemoryObserver",
+    "WasmModule",
+    "LibraryModule",
+    "Module",
+    "Globals",
+    "Env",
+    "GuestLog",
+];
+
+/// Parsed capabilities file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CapabilityConfig {
+    /// Import patterns in declaration order.
+    pub rules: Vec<CapabilityRule>,
+}
+
+/// One import pattern and the capability it assigns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapabilityRule {
+    /// Trait name of the capability.
+    pub capability: String,
+    /// Import module, or `*` for any.
+    pub module: String,
+    /// Import name; a trailing `*` matches any suffix.
+    pub name: String,
+}
+
+impl CapabilityRule {
+    /// Whether the rule matches `import`.
+    pub fn matches(&self, import: &FuncImport) -> bool {
+        (self.module == "*" || *self.module == *import.module_name)
+            && match self.name.strip_suffix('*') {
+                Some(prefix) => import.func_name.starts_with(prefix),
+                None => *self.name == *import.func_name,
+            }
+    }
+}
+
+impl CapabilityConfig {
+    /// Parse a capabilities file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = CapabilityConfig::default();
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            config
+                .parse_line(line)
+                .with_context(|| format!("capabilities line {}: `{}`", idx + 1, line))?;
+        }
+        Ok(config)
+    }
+
+    /// Whether no import is regrouped.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["capability", capability, patterns @ ..] if !patterns.is_empty() => {
+                check_capability(capability)?;
+                for pattern in patterns {
+                    let Some((module, name)) = pattern.split_once('.') else {
+                        bail!("expected a `MODULE.NAME` pattern, found `{}`", pattern);
+                    };
+                    if module.is_empty() || name.is_empty() {
+                        bail!("expected a `MODULE.NAME` pattern, found `{}`", pattern);
+                    }
+                    if module.contains('*') && module != "*" {
+                        bail!("module `{}` may only be `*` or a name", module);
+                    }
+                    if name.strip_suffix('*').unwrap_or(name).contains('*') {
+                        bail!("`*` may only end the import name in `{}`", pattern);
+                    }
+                    self.rules.push(CapabilityRule {
+                        capability: capability.to_string(),
+                        module: module.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+            }
+            ["capability", ..] => bail!("expected `capability <Name> <MODULE.NAME>...`"),
+            [other, ..] => bail!("unknown directive `{}` (expected `capability`)", other),
+            [] => {}
+        }
+        Ok(())
+    }
+
+    /// Map each regrouped function import of `info`, by index into
+    /// `func_imports`, to its capability.
+    ///
+    /// Patterns that match nothing are allowed, so one file can serve
+    /// several builds of a guest.
+    pub fn resolve(&self, info: &ModuleInfo) -> BTreeMap<usize, String> {
+        let tinygo = detect_tinygo(info).is_some();
+        let mut assigned = BTreeMap::new();
+        for (idx, import) in info.func_imports.iter().enumerate() {
+            if info.guest_log.contains_key(&idx) || tinygo && import_default(import).is_some() {
+                continue;
+            }
+            if let Some(rule) = self.rules.iter().find(|rule| rule.matches(import)) {
+                assigned.insert(idx, rule.capability.clone());
+            }
+        }
+        assigned
+    }
+}
+
+/// Capabilities name traits: an ASCII identifier starting with an uppercase
+/// letter, not already used by the generated code.
+fn check_capability(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!(
+            "`{}` is not a valid capability name (expected a type name such as `FileSystem`)",
+            name
+        );
+    }
+    if RESERVED.contains(&name) {
+        bail!("capability `{}` collides with a generated item", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn info(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        build_module_info(&parsed, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn parses_capabilities_with_several_patterns() {
+        let config =
+            CapabilityConfig::parse("# grouping\ncapability Graphics env.gl_* gpu.*  # all\n")
+                .unwrap();
+        assert_eq!(
+            config.rules,
+            vec![
+                CapabilityRule {
+                    capability: "Graphics".to_string(),
+                    module: "env".to_string(),
+                    name: "gl_*".to_string(),
+                },
+                CapabilityRule {
+                    capability: "Graphics".to_string(),
+                    module: "gpu".to_string(),
+                    name: "*".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_report_line_numbers() {
+        let err =
+            CapabilityConfig::parse("capability Fs env.fd_*\ncapability fs env.x\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("capabilities line 2"));
+        assert!(CapabilityConfig::parse("capability Fs").is_err());
+        assert!(CapabilityConfig::parse("capability Fs fd_write").is_err());
+        assert!(CapabilityConfig::parse("capability Fs env.*_write").is_err());
+        assert!(CapabilityConfig::parse("capability Fs e*.fd").is_err());
+        assert!(CapabilityConfig::parse("capability Env env.fd").is_err());
+        assert!(CapabilityConfig::parse("group Fs env.fd").is_err());
+    }
+
+    #[test]
+    fn resolve_assigns_the_first_matching_capability() {
+        let info = info(
+            r#"(module
+                (import "env" "fd_write" (func (param i32)))
+                (import "env" "fd_read" (func (param i32)))
+                (import "env" "gl_clear" (func))
+                (import "env" "log" (func (param i32)))
+                (import "gpu" "log" (func (param i32))))"#,
+        );
+        let config = CapabilityConfig::parse(
+            "capability FileSystem env.fd_*\ncapability Graphics env.gl_* gpu.*\ncapability Logging *.log\ncapability Unused net.*",
+        )
+        .unwrap();
+        let assigned = config.resolve(&info);
+        let assigned: Vec<(usize, &str)> = assigned.iter().map(|(i, c)| (*i, c.as_str())).collect();
+        assert_eq!(
+            assigned,
+            vec![
+                (0, "FileSystem"),
+                (1, "FileSystem"),
+                (2, "Graphics"),
+                (3, "Logging"),
+                (4, "Graphics"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Outline of a generated module's public API.
+//!
+//! `herkos bindings` shows what a module would expose before anyone commits
+//! to the generated code: the traits a host implements and the functions it
+//! calls, with their doc comments and feature gates, without bodies:
+//!
+//! ```text
+//! pub trait ModuleHostTrait {
+//!     fn log(&mut self, arg0: i32) -> WasmResult<()>;
+//! }
+//!
+//! pub fn new() -> Result<WasmModule, ConstructionError>;
+//!
+//! impl WasmModule {
+//!     pub fn run<H: ModuleHostTrait>(&mut self, v0: i32, host: &mut H) -> WasmResult<i32>;
+//! }
+//! ```
+//!
+//! The outline is read back from the generated source, so it covers every
+//! option that adds methods (bindings files, WIT worlds, export groups, ...).
+
+/// Outline the public traits, free functions and inherent impl methods of
+/// generated Rust `code`.
+pub fn outline(code: &str) -> String {
+    let mut out = String::new();
+    let mut lines = code.lines();
+    // Doc comments and attributes of the next item
+    let mut pending: Vec<&str> = Vec::new();
+    while let Some(line) = lines.next() {
+        if line.starts_with("///") || line.starts_with("#[") {
+            if keep_attr(line) {
+                pending.push(line);
+            }
+            continue;
+        }
+        if line.starts_with("pub trait ") {
+            let items = block_items(&mut lines, false);
+            push_block(&mut out, &pending, line, &items);
+        } else if is_inherent_impl(line) {
+            let items = block_items(&mut lines, true);
+            // Impls of generated internals expose nothing
+            if !items.is_empty() {
+                push_block(&mut out, &pending, line, &items);
+            }
+        } else if line.starts_with("pub fn ") {
+            let signature = signature(line, &mut lines);
+            for attr in &pending {
+                out.push_str(attr);
+                out.push('\n');
+            }
+            out.push_str(&signature);
+            out.push_str("\n\n");
+        }
+        pending.clear();
+    }
+    out
+}
+
+/// `impl Type {` or `impl<..> Type<..> {`, not a trait impl.
+fn is_inherent_impl(line: &str) -> bool {
+    (line.starts_with("impl ") || line.starts_with("impl<"))
+        && line.ends_with('{')
+        && !line.contains(" for ")
+}
+
+/// Lint settings are noise in an outline; docs and `cfg`s are not.
+fn keep_attr(line: &str) -> bool {
+    !line.trim_start().starts_with("#[allow(")
+}
+
+/// The items of the block opened on the previous line, up to its closing
+/// `}`: doc comments, attributes and body-less signatures of its functions
+/// (only the `pub` ones if `only_pub`).
+fn block_items<'a>(lines: &mut impl Iterator<Item = &'a str>, only_pub: bool) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut pending: Vec<&str> = Vec::new();
+    while let Some(line) = lines.next() {
+        if line == "}" {
+            break;
+        }
+        // Only lines at the block's own indentation start items
+        let Some(item) = line.strip_prefix("    ") else {
+            continue;
+        };
+        if item.starts_with(' ') {
+            continue;
+        }
+        if item.starts_with("///") || item.starts_with("#[") {
+            if keep_attr(item) {
+                pending.push(line);
+            }
+            continue;
+        }
+        let is_fn = item.starts_with("pub fn ") || (!only_pub && item.starts_with("fn "));
+        if is_fn {
+            let signature = signature(line, lines);
+            items.extend(pending.iter().map(|attr| attr.to_string()));
+            items.push(signature);
+        }
+        pending.clear();
+    }
+    items
+}
+
+/// The signature starting on `first`, joined onto one line if it spans
+/// several, ending in `;` instead of its body.
+fn signature<'a>(first: &str, lines: &mut impl Iterator<Item = &'a str>) -> String {
+    let mut signature = first.trim_end().to_string();
+    while !signature.ends_with(['{', '}', ';']) {
+        let Some(next) = lines.next() else { break };
+        let next = next.trim();
+        if !signature.ends_with('(') && !next.starts_with(')') {
+            signature.push(' ');
+        }
+        signature.push_str(next);
+    }
+    // A default method or a function with a body
+    if let Some(head) = signature.strip_suffix("{}") {
+        signature = format!("{};", head.trim_end());
+    } else if let Some(head) = signature.strip_suffix('{') {
+        signature = format!("{};", head.trim_end());
+        skip_body(lines, first);
+    }
+    signature.replace(",)", ")")
+}
+
+/// Skip to the `}` closing a body opened at `first`'s indentation.
+fn skip_body<'a>(lines: &mut impl Iterator<Item = &'a str>, first: &str) {
+    let indent = &first[..first.len() - first.trim_start().len()];
+    let close = format!("{indent}}}");
+    for line in lines.by_ref() {
+        if line == close {
+            break;
+        }
+    }
+}
+
+fn push_block(out: &mut String, attrs: &[&str], header: &str, items: &[String]) {
+    for attr in attrs {
+        out.push_str(attr);
+        out.push('\n');
+    }
+    out.push_str(header);
+    out.push('\n');
+    for item in items {
+        out.push_str(item);
+        out.push('\n');
+    }
+    out.push_str("}\n\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transpile, TranspileOptions};
+
+    #[test]
+    fn outline_keeps_signatures_and_drops_bodies() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (func (export "run") (param i32) (result i32)
+                  (call $log (local.get 0))
+                  (local.get 0))
+                (func $helper))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        let api = outline(&code);
+        assert!(api.contains(
+            "pub trait ModuleHostTrait {\n    fn log(&mut self, arg0: i32) -> WasmResult<()>;\n}\n"
+        ));
+        assert!(api.contains("pub fn new() -> Result<WasmModule, ConstructionError>;\n"));
+        assert!(api.contains(
+            "impl WasmModule {\n    pub fn run<H: ModuleHostTrait>(&mut self, v0: i32, host: &mut H) -> WasmResult<i32>;\n"
+        ));
+        // Internal functions and types are not part of the API
+        assert!(!api.contains("fn func_0<"));
+        assert!(!api.contains("struct Env"));
+        assert!(!api.contains("env.host"));
+    }
+
+    #[test]
+    fn default_methods_and_multiline_signatures_are_collapsed() {
+        let code = "\
+/// A trait.
+#[allow(clippy::too_many_arguments)]
+pub trait T {
+    /// Required.
+    fn a(&mut self) -> i32;
+    fn b(&mut self) -> i32 {
+        let x = 1;
+        x
+    }
+}
+
+impl Private {
+    fn hidden(&self) {}
+}
+
+impl W {
+    pub fn d(&self) {}
+    #[cfg(feature = \"x\")]
+    pub fn c(
+        &mut self,
+        v0: i32,
+    ) -> i32 {
+        v0
+    }
+}
+";
+        assert_eq!(
+            outline(code),
+            "/// A trait.\npub trait T {\n    /// Required.\n    fn a(&mut self) -> i32;\n    fn b(&mut self) -> i32;\n}\n\n\
+             impl W {\n    pub fn d(&self);\n    #[cfg(feature = \"x\")]\n    pub fn c(&mut self, v0: i32) -> i32;\n}\n\n"
+        );
+    }
+}
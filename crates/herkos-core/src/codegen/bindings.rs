@@ -0,0 +1,388 @@
+//! High-level binding wrapper generation.
+//!
+//! For every `export` line of the bindings file (see [`crate::bindings`]),
+//! emits a wrapper method that stages host slices and structs in the scratch
+//! region, calls the raw export method, and reads out-parameters back:
+//!
+//! ```text
+//! pub fn hash_bound(&mut self, data: &[u8]) -> WasmResult<[u8; 32]> {
+//!     let mut __scratch = Scratch::new(65536);
+//!     let __data_ptr = __scratch.alloc(data.len())?;
+//!     self.0.memory.write_bytes(__data_ptr, data)?;
+//!     let __digest_ptr = __scratch.alloc(32)?;
+//!     self.hash(__data_ptr as i32, data.len() as i32, __digest_ptr as i32)?;
+//!     let mut digest = [0u8; 32];
+//!     self.0.memory.read_bytes(__digest_ptr, &mut digest)?;
+//!     Ok(digest)
+//! }
+//! ```
+//!
+//! Without a `scratch` directive the buffers come from the guest allocator
+//! instead (`self.alloc_in_guest(data.len())?`) and are handed back through
+//! `free_in_guest` once the results have been read.
+
+use crate::bindings::{
+    scalar_size, BindingConfig, BoundKind, ExportBinding, GuestAllocator, StructLayout,
+};
+use crate::codegen::types::{format_results, wasm_type_to_rust};
+use crate::codegen::utils::{export_cfg_attr, rust_ident, ForwardedState};
+use crate::ir::*;
+
+/// Generate binding structs and the wrapper `impl WasmModule` block.
+///
+/// Returns an empty string when `config` declares no bindings.
+pub fn generate_bindings(config: &BindingConfig, info: &ModuleInfo) -> String {
+    if config.is_empty() {
+        return String::new();
+    }
+
+    let mut code = String::new();
+    for layout in &config.structs {
+        code.push_str(&generate_struct(layout));
+    }
+
+    code.push_str("impl WasmModule {\n");
+    for binding in &config.exports {
+        if let Some(wrapper) = generate_wrapper(config, binding, info) {
+            code.push_str(&export_cfg_attr("    ", info, &binding.export));
+            code.push_str(&wrapper);
+        }
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+/// Emit a `#[repr(C)]`-equivalent host mirror of a guest struct.
+fn generate_struct(layout: &StructLayout) -> String {
+    let mut code = format!(
+        "/// Guest struct `{}` ({} bytes in linear memory).\n",
+        layout.name, layout.size
+    );
+    code.push_str("#[derive(Debug, Clone, Copy, PartialEq, Default)]\n");
+    code.push_str(&format!("pub struct {} {{\n", layout.name));
+    for field in &layout.fields {
+        code.push_str(&format!(
+            "    pub {}: {},\n",
+            field.name,
+            wasm_type_to_rust(&field.ty)
+        ));
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+/// Guest address expression `ptr + offset`, omitting a zero offset.
+fn field_addr(ptr: &str, offset: u32) -> String {
+    if offset == 0 {
+        ptr.to_string()
+    } else {
+        format!("{ptr} + {offset}")
+    }
+}
+
+/// Emit one wrapper method. Returns `None` if the export is missing, which
+/// validation in `build_module_info` already rules out.
+fn generate_wrapper(
+    config: &BindingConfig,
+    binding: &ExportBinding,
+    info: &ModuleInfo,
+) -> Option<String> {
+    let export = info
+        .func_exports
+        .iter()
+        .find(|e| e.name == binding.export)?;
+    let ir_func = info.ir_function(export.func_index)?;
+    let mem = if info.has_memory {
+        "self.0.memory"
+    } else {
+        "memory"
+    };
+
+    // Generics, mirroring the raw export method.
+    let forwarded = ForwardedState::new(info);
+    let generic_part = forwarded.generic_part();
+
+    // Wrapper parameters and results.
+    let mut params = vec!["&mut self".to_string()];
+    let mut results: Vec<(String, String)> = Vec::new();
+    if !ir_func.results.is_empty() {
+        results.push(("__ret".to_string(), format_results(&ir_func.results)));
+    }
+    for param in &binding.params {
+        let name = &param.name;
+        match &param.kind {
+            BoundKind::Scalar(ty) => params.push(format!("{name}: {}", wasm_type_to_rust(ty))),
+            BoundKind::Bytes => params.push(format!("{name}: &[u8]")),
+            BoundKind::BytesMut => params.push(format!("{name}: &mut [u8]")),
+            BoundKind::Str => params.push(format!("{name}: &str")),
+            BoundKind::Struct(s) => params.push(format!("{name}: &{s}")),
+            BoundKind::OutBytes(len) => results.push((name.clone(), format!("[u8; {len}]"))),
+            BoundKind::OutScalar(ty) => {
+                results.push((name.clone(), wasm_type_to_rust(ty).to_string()))
+            }
+            BoundKind::OutStruct(s) => results.push((name.clone(), s.clone())),
+        }
+    }
+    params.extend(forwarded.params.iter().map(|p| p.to_string()));
+    let result_type = match results.as_slice() {
+        [] => "()".to_string(),
+        [(_, ty)] => ty.clone(),
+        many => format!(
+            "({})",
+            many.iter()
+                .map(|(_, ty)| ty.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let mut code = format!(
+        "    /// High-level wrapper for export `{}` (generated from the bindings file).\n",
+        binding.export
+    );
+    code.push_str(&format!(
+        "    pub fn {}{generic_part}({}) -> WasmResult<{result_type}> {{\n",
+        binding.method,
+        params.join(", ")
+    ));
+
+    // Stage inputs and reserve out-parameters in the scratch region, or in
+    // blocks from the guest allocator when no region is declared.
+    let helper_args: String = forwarded.args.iter().map(|a| format!(", {a}")).collect();
+    let guest_alloc = match config.scratch {
+        Some(_) => None,
+        None => GuestAllocator::detect(info),
+    };
+    let mut allocated: Vec<(String, String)> = Vec::new();
+    let mut alloc = |ptr: &str, len: String| match guest_alloc {
+        Some(_) => {
+            let expr = format!("self.{}({len}{helper_args})?", GuestAllocator::ALLOC_METHOD);
+            allocated.push((ptr.to_string(), len));
+            expr
+        }
+        None => format!("__scratch.alloc({len})?"),
+    };
+    if guest_alloc.is_none() && binding.params.iter().any(|p| p.kind.uses_memory()) {
+        code.push_str(&format!(
+            "        let mut __scratch = Scratch::new({});\n",
+            config.scratch.unwrap_or(0)
+        ));
+    }
+    let mut args: Vec<String> = Vec::new();
+    for param in &binding.params {
+        let name = &param.name;
+        let ptr = format!("__{name}_ptr");
+        match &param.kind {
+            BoundKind::Scalar(_) => args.push(name.clone()),
+            BoundKind::Bytes | BoundKind::BytesMut | BoundKind::Str => {
+                let bytes = if param.kind == BoundKind::Str {
+                    format!("{name}.as_bytes()")
+                } else {
+                    name.clone()
+                };
+                let at = alloc(&ptr, format!("{name}.len()"));
+                code.push_str(&format!("        let {ptr} = {at};\n"));
+                code.push_str(&format!("        {mem}.write_bytes({ptr}, {bytes})?;\n"));
+                args.push(format!("{ptr} as i32"));
+                args.push(format!("{name}.len() as i32"));
+            }
+            BoundKind::Struct(s) => {
+                let layout = config.struct_layout(s)?;
+                let at = alloc(&ptr, layout.size.to_string());
+                code.push_str(&format!("        let {ptr} = {at};\n"));
+                for field in &layout.fields {
+                    code.push_str(&format!(
+                        "        {mem}.store_{}({}, {name}.{})?;\n",
+                        wasm_type_to_rust(&field.ty),
+                        field_addr(&ptr, field.offset),
+                        field.name
+                    ));
+                }
+                args.push(format!("{ptr} as i32"));
+            }
+            BoundKind::OutBytes(len) => {
+                let at = alloc(&ptr, len.to_string());
+                code.push_str(&format!("        let {ptr} = {at};\n"));
+                args.push(format!("{ptr} as i32"));
+            }
+            BoundKind::OutScalar(ty) => {
+                let at = alloc(&ptr, scalar_size(*ty).to_string());
+                code.push_str(&format!("        let {ptr} = {at};\n"));
+                args.push(format!("{ptr} as i32"));
+            }
+            BoundKind::OutStruct(s) => {
+                let layout = config.struct_layout(s)?;
+                let at = alloc(&ptr, layout.size.to_string());
+                code.push_str(&format!("        let {ptr} = {at};\n"));
+                args.push(format!("{ptr} as i32"));
+            }
+        }
+    }
+
+    // Call the raw export method.
+    args.extend(forwarded.args.iter().map(|a| a.to_string()));
+    let let_ret = if !ir_func.results.is_empty() {
+        "let __ret = "
+    } else {
+        ""
+    };
+    code.push_str(&format!(
+        "        {let_ret}self.{}({})?;\n",
+        rust_ident(&binding.export),
+        args.join(", ")
+    ));
+
+    // Copy results back out of linear memory.
+    for param in &binding.params {
+        let name = &param.name;
+        let ptr = format!("__{name}_ptr");
+        match &param.kind {
+            BoundKind::BytesMut => {
+                code.push_str(&format!("        {mem}.read_bytes({ptr}, {name})?;\n"));
+            }
+            BoundKind::OutBytes(len) => {
+                code.push_str(&format!("        let mut {name} = [0u8; {len}];\n"));
+                code.push_str(&format!("        {mem}.read_bytes({ptr}, &mut {name})?;\n"));
+            }
+            BoundKind::OutScalar(ty) => {
+                code.push_str(&format!(
+                    "        let {name} = {mem}.load_{}({ptr})?;\n",
+                    wasm_type_to_rust(ty)
+                ));
+            }
+            BoundKind::OutStruct(s) => {
+                let layout = config.struct_layout(s)?;
+                let fields: Vec<String> = layout
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{}: {mem}.load_{}({})?",
+                            field.name,
+                            wasm_type_to_rust(&field.ty),
+                            field_addr(&ptr, field.offset)
+                        )
+                    })
+                    .collect();
+                code.push_str(&format!(
+                    "        let {name} = {s} {{ {} }};\n",
+                    fields.join(", ")
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    // Hand guest-allocated blocks back, most recent first.
+    if guest_alloc.is_some_and(|a| a.free.is_some()) {
+        for (ptr, len) in allocated.iter().rev() {
+            code.push_str(&format!(
+                "        self.{}({ptr}, {len}{helper_args})?;\n",
+                GuestAllocator::FREE_METHOD
+            ));
+        }
+    }
+
+    let value = match results.as_slice() {
+        [] => "()".to_string(),
+        [(name, _)] => name.clone(),
+        many => format!(
+            "({})",
+            many.iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    code.push_str(&format!("        Ok({value})\n"));
+    code.push_str("    }\n");
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bindings::BindingConfig;
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_with(wat: &str, bindings: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            bindings: BindingConfig::parse(bindings)?,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    const HASH_WAT: &str = r#"(module
+        (memory 2)
+        (func (export "hash") (param i32 i32 i32))
+        (func (export "area") (param i32) (result i32) i32.const 0))"#;
+
+    #[test]
+    fn emits_slice_and_out_array_wrapper() {
+        let code = transpile_with(
+            HASH_WAT,
+            "scratch 0x10000\nexport hash(data: &[u8], digest: out [u8; 32])",
+        )
+        .unwrap();
+        assert!(
+            code.contains("pub fn hash_bound(&mut self, data: &[u8]) -> WasmResult<[u8; 32]> {")
+        );
+        assert!(code.contains("let mut __scratch = Scratch::new(65536);"));
+        assert!(code.contains("self.0.memory.write_bytes(__data_ptr, data)?;"));
+        assert!(
+            code.contains("self.hash(__data_ptr as i32, data.len() as i32, __digest_ptr as i32)?;")
+        );
+        assert!(code.contains("self.0.memory.read_bytes(__digest_ptr, &mut digest)?;"));
+    }
+
+    #[test]
+    fn emits_struct_and_returns_tuple_with_raw_result() {
+        let code = transpile_with(
+            HASH_WAT,
+            "scratch 4096\nstruct Rect { w: i32, h: i32 }\nexport area as rect_area(r: &Rect)",
+        )
+        .unwrap();
+        assert!(code.contains("pub struct Rect {\n    pub w: i32,\n    pub h: i32,\n}"));
+        assert!(code.contains("pub fn rect_area(&mut self, r: &Rect) -> WasmResult<i32> {"));
+        assert!(code.contains("self.0.memory.store_i32(__r_ptr, r.w)?;"));
+        assert!(code.contains("self.0.memory.store_i32(__r_ptr + 4, r.h)?;"));
+        assert!(code.contains("let __ret = self.area(__r_ptr as i32)?;"));
+    }
+
+    #[test]
+    fn falls_back_to_guest_allocator_without_scratch() {
+        let wat = r#"(module
+            (memory 1)
+            (func (export "malloc") (param i32) (result i32) i32.const 8)
+            (func (export "free") (param i32))
+            (func (export "hash") (param i32 i32 i32)))"#;
+        let code = transpile_with(wat, "export hash(data: &[u8], digest: out [u8; 32])").unwrap();
+        assert!(!code.contains("Scratch::new"));
+        assert!(code.contains("let __data_ptr = self.alloc_in_guest(data.len())?;"));
+        assert!(code.contains("let __digest_ptr = self.alloc_in_guest(32)?;"));
+        let read = code
+            .find("self.0.memory.read_bytes(__digest_ptr, &mut digest)?;")
+            .unwrap();
+        let free_digest = code.find("self.free_in_guest(__digest_ptr, 32)?;").unwrap();
+        let free_data = code
+            .find("self.free_in_guest(__data_ptr, data.len())?;")
+            .unwrap();
+        assert!(read < free_digest && free_digest < free_data);
+    }
+
+    #[test]
+    fn rejects_signature_mismatch() {
+        let err = transpile_with(HASH_WAT, "scratch 0\nexport hash(data: &[u8])").unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("invalid binding for export `hash`"), "{msg}");
+        assert!(msg.contains("(i32, i32)"), "{msg}");
+    }
+
+    #[test]
+    fn rejects_unknown_export_and_missing_scratch() {
+        assert!(transpile_with(HASH_WAT, "export nope(x: i32)").is_err());
+        let err = transpile_with(HASH_WAT, "export area(r: out i32)").unwrap_err();
+        assert!(format!("{:#}", err).contains("scratch"));
+    }
+}
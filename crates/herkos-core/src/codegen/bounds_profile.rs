@@ -0,0 +1,270 @@
+//! Bounds-check heat map instrumentation (`--bounds-profile`).
+//!
+//! Every memory access left in the final IR — loads, stores and the bulk
+//! `memory.copy`, `memory.fill` and `memory.init` — gets a counter in
+//! `Globals`, bumped right before the access checks its bounds.
+//! `BOUNDS_SITES` maps each counter back to its function, Wasm byte offset
+//! and operator, and `bounds_report` lists the hottest sites through
+//! `herkos_runtime::write_bounds_report`:
+//!
+//! ```text
+//! pub const BOUNDS_SITES: &[BoundsSite] = &[
+//!     BoundsSite { func_index: 0, wasm_offset: 43, access: "i32.load" },
+//! ];
+//!
+//! fn func_0<H: ModuleHostTrait>(..) -> WasmResult<i32> {
+//!     ..
+//!     env.globals.bounds_checks[0] += 1;
+//!     v3 = memory.load_i32(v2 as usize)?;
+//! ```
+//!
+//! Offsets are those of the access's block (see
+//! [`crate::codegen::coverage`]); accesses in blocks the optimizer merged
+//! into their predecessor report that block's offset.
+
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use anyhow::{bail, Result};
+
+/// `Globals` field holding the counters.
+pub const FIELD: &str = "bounds_checks";
+
+/// Methods added to `WasmModule`.
+const METHODS: [&str; 3] = ["bounds_check_hits", "reset_bounds_checks", "bounds_report"];
+
+/// A counted memory access, in counter order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundsSite {
+    /// Local function index.
+    pub func: usize,
+    /// The block holding the access, in the function's final IR.
+    pub block: BlockId,
+    /// Index of the access among the block's instructions.
+    pub instr: usize,
+    /// Byte offset in the Wasm binary of the block, or of the function body.
+    pub offset: usize,
+    /// The Wasm operator, e.g. `i32.load offset=4`.
+    pub access: String,
+}
+
+/// Check that `info` can be instrumented for a bounds-check profile.
+///
+/// # Errors
+/// Fails when the module has no memory to check accesses against, or when
+/// the counters' field or the profile methods collide with a global or an
+/// export.
+pub fn check_bounds_profile(info: &ModuleInfo) -> Result<()> {
+    if !info.has_memory && !info.has_memory_import {
+        bail!("--bounds-profile: the module has no memory");
+    }
+    if info.globals.iter().any(|g| g.field == FIELD) {
+        bail!("--bounds-profile: a global already uses the `{FIELD}` field");
+    }
+    if let Some(export) = info
+        .func_exports
+        .iter()
+        .find(|e| METHODS.contains(&rust_ident(&e.name).as_str()))
+    {
+        bail!(
+            "--bounds-profile: export `{}` collides with a bounds profile method",
+            export.name
+        );
+    }
+    Ok(())
+}
+
+/// The Wasm operator of `instr`, if it checks memory bounds.
+fn access_name(instr: &IrInstr) -> Option<String> {
+    let (op, ty, width, sign, offset) = match instr {
+        IrInstr::Load {
+            ty,
+            offset,
+            width,
+            sign,
+            ..
+        } => ("load", *ty, *width, *sign, *offset),
+        IrInstr::Store {
+            ty, offset, width, ..
+        } => ("store", *ty, *width, None, *offset),
+        IrInstr::MemoryCopy { .. } => return Some("memory.copy".to_string()),
+        IrInstr::MemoryFill { .. } => return Some("memory.fill".to_string()),
+        IrInstr::MemoryInit { segment, .. } => return Some(format!("memory.init {segment}")),
+        _ => return None,
+    };
+    let bits = match width {
+        MemoryAccessWidth::Full => "",
+        MemoryAccessWidth::I8 => "8",
+        MemoryAccessWidth::I16 => "16",
+        MemoryAccessWidth::I32 => "32",
+    };
+    let sign = match sign {
+        Some(SignExtension::Signed) => "_s",
+        Some(SignExtension::Unsigned) => "_u",
+        None => "",
+    };
+    let mut name = format!("{ty}.{op}{bits}{sign}");
+    if offset != 0 {
+        name.push_str(&format!(" offset={offset}"));
+    }
+    Some(name)
+}
+
+/// The memory accesses of `info` that get a counter, by function, block
+/// and instruction order.
+///
+/// Empty unless a bounds profile was requested.
+pub fn bounds_sites(info: &ModuleInfo) -> Vec<BoundsSite> {
+    if !info.bounds_profile {
+        return Vec::new();
+    }
+    let mut sites = Vec::new();
+    for (func, ir_func) in info.ir_functions.iter().enumerate() {
+        if info.is_merged(func) {
+            continue;
+        }
+        let symbol = info.func_symbols.get(func);
+        for block in &ir_func.blocks {
+            let offset = symbol.map_or(0, |s| {
+                s.block_offsets
+                    .iter()
+                    .find(|(id, _)| *id == block.id)
+                    .map_or(s.code_range.start, |&(_, offset)| offset)
+            });
+            for (instr, ir_instr) in block.instructions.iter().enumerate() {
+                if let Some(access) = access_name(ir_instr) {
+                    sites.push(BoundsSite {
+                        func,
+                        block: block.id,
+                        instr,
+                        offset,
+                        access,
+                    });
+                }
+            }
+        }
+    }
+    sites
+}
+
+/// The `(block, instruction, counter)` triples of each of `num_functions`
+/// functions.
+pub fn site_counters(
+    sites: &[BoundsSite],
+    num_functions: usize,
+) -> Vec<Vec<(BlockId, usize, usize)>> {
+    let mut counters = vec![Vec::new(); num_functions];
+    for (counter, site) in sites.iter().enumerate() {
+        counters[site.func].push((site.block, site.instr, counter));
+    }
+    counters
+}
+
+/// Counter of instruction `instr` of `block`, if it is a counted access.
+pub fn counter_of(
+    counters: &[(BlockId, usize, usize)],
+    block: BlockId,
+    instr: usize,
+) -> Option<usize> {
+    counters
+        .iter()
+        .find(|&&(b, i, _)| b == block && i == instr)
+        .map(|&(_, _, counter)| counter)
+}
+
+/// Statement bumping `counter`, right before its access.
+pub fn count_statement(counter: usize) -> String {
+    format!("env.globals.{FIELD}[{counter}] += 1;")
+}
+
+/// Generate `BOUNDS_SITES` and the bounds profile accessors.
+///
+/// Returns an empty string unless a bounds profile was requested.
+pub fn generate_bounds_profile(info: &ModuleInfo, sites: &[BoundsSite]) -> String {
+    if !info.bounds_profile {
+        return String::new();
+    }
+    let mut code = String::from(
+        "/// Function, Wasm offset and operator of each bounds-check counter, in counter order.\n",
+    );
+    code.push_str("pub const BOUNDS_SITES: &[BoundsSite] = &[\n");
+    for site in sites {
+        code.push_str(&format!(
+            "    BoundsSite {{ func_index: {}, wasm_offset: {}, access: {:?} }},\n",
+            site.func, site.offset, site.access
+        ));
+    }
+    code.push_str("];\n\n");
+
+    code.push_str("impl WasmModule {\n");
+    code.push_str("    /// Bounds checks of each of [`BOUNDS_SITES`] so far.\n");
+    code.push_str("    pub fn bounds_check_hits(&self) -> &[u64] {\n");
+    code.push_str(&format!("        &self.0.globals.{FIELD}\n"));
+    code.push_str("    }\n\n");
+    code.push_str("    /// Forget the bounds checks so far.\n");
+    code.push_str("    pub fn reset_bounds_checks(&mut self) {\n");
+    code.push_str(&format!(
+        "        self.0.globals.{FIELD} = [0; {}];\n",
+        sites.len()
+    ));
+    code.push_str("    }\n\n");
+    code.push_str("    /// Write the `top` access sites with the most bounds checks so far,\n");
+    code.push_str("    /// hottest first.\n");
+    code.push_str(
+        "    pub fn bounds_report<W: core::fmt::Write>(&self, top: usize, out: &mut W) -> core::fmt::Result {\n",
+    );
+    code.push_str(&format!(
+        "        write_bounds_report(out, SYMBOLS, BOUNDS_SITES, &self.0.globals.{FIELD}, top)\n"
+    ));
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_profiled(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            bounds_profile: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn accesses_are_counted_with_their_operator() {
+        let code = transpile_profiled(
+            r#"(module
+                (memory 1 1)
+                (func (export "bump") (param i32)
+                    (i32.store offset=4 (local.get 0)
+                        (i32.add (i32.load8_u offset=4 (local.get 0)) (i32.const 1)))
+                    (memory.fill (local.get 0) (i32.const 0) (i32.const 4))))"#,
+        )
+        .unwrap();
+        for access in ["i32.load8_u offset=4", "i32.store offset=4", "memory.fill"] {
+            assert!(code.contains(&format!("access: \"{access}\" }},")));
+        }
+        assert!(code.contains("pub bounds_checks: [u64; 3],"));
+        assert!(code.contains("bounds_checks: [0; 3]"));
+        for counter in 0..3 {
+            assert!(code.contains(&format!("env.globals.bounds_checks[{counter}] += 1;")));
+        }
+        assert!(code.contains("pub fn bounds_report<W: core::fmt::Write>("));
+    }
+
+    #[test]
+    fn modules_without_memory_are_rejected() {
+        let err = transpile_profiled(r#"(module (func (export "f")))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("the module has no memory"));
+    }
+
+    #[test]
+    fn colliding_exports_are_rejected() {
+        let err = transpile_profiled(r#"(module (memory 1 1) (func (export "bounds_report")))"#)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("collides with a bounds profile method"));
+    }
+}
@@ -0,0 +1,1040 @@
+//! C99 module generation (experimental, `--mode c`).
+//!
+//! Emits one self-contained C translation unit from the same IR as the Rust
+//! codegen, with statements produced by [`CBackend`]. The file layout is:
+//!
+//! - Trap codes (`wasm_trap_t`), limits and the `wasm_module_t` struct
+//!   holding memory, mutable globals and the table
+//! - `static inline` helpers: bounds-checked loads and stores, trapping
+//!   division and float truncation, Wasm `min`/`max`, bit counting
+//! - Prototypes of the imports (`wasm_import_<module>_<name>`), which the
+//!   host implements, and of the exports (`wasm_export_<name>`)
+//! - Data segments, constant globals and one `static` function per IR
+//!   function. Blocks become labels and branches `goto`s
+//! - `wasm_init`, which sets up memory, globals and the table
+//!
+//! Every generated function returns `WASM_OK` or a trap code and writes its
+//! result, if any, through a trailing `ret` pointer. Imports follow the same
+//! convention and receive the module, whose `host` field is left to the
+//! embedder.
+//!
+//! Rust-only options (bindings, WIT, the wasmtime adapter, feature gates)
+//! and modules importing globals or their table are rejected.
+
+use crate::backend::c::{c_literal, c_type, import_symbol};
+use crate::backend::{Backend, CBackend};
+use crate::codegen::instruction::{write_instruction, write_terminator};
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Types and trap codes shared by every generated module.
+const TYPES: &str = r#"typedef enum {
+    WASM_OK = 0,
+    WASM_TRAP_OUT_OF_BOUNDS,
+    WASM_TRAP_DIVISION_BY_ZERO,
+    WASM_TRAP_INTEGER_OVERFLOW,
+    WASM_TRAP_UNREACHABLE,
+    WASM_TRAP_INDIRECT_CALL_TYPE_MISMATCH,
+    WASM_TRAP_TABLE_OUT_OF_BOUNDS,
+    WASM_TRAP_UNDEFINED_ELEMENT,
+    WASM_TRAP_INTERRUPTED
+} wasm_trap_t;
+
+enum { WASM_FUNC_NULL = 0, WASM_FUNC_LOCAL, WASM_FUNC_IMPORT };
+
+/* Table entry; `type_index` is the canonical type of the function. */
+typedef struct {
+    uint8_t kind;
+    uint32_t type_index;
+    uint32_t func_index;
+} wasm_funcref_t;
+
+#define WASM_PAGE_SIZE 65536u
+"#;
+
+/// Helpers over `wasm_module_t`. `static inline`, so unused ones cost nothing.
+const HELPERS: &str = r#"#define WASM_TRY(expr) \
+    do { \
+        wasm_trap_t wasm_trap_ = (expr); \
+        if (wasm_trap_ != WASM_OK) return wasm_trap_; \
+    } while (0)
+
+static inline uint32_t wasm_f32_to_bits(float f) { uint32_t b; memcpy(&b, &f, sizeof b); return b; }
+static inline float wasm_f32_from_bits(uint32_t b) { float f; memcpy(&f, &b, sizeof f); return f; }
+static inline uint64_t wasm_f64_to_bits(double f) { uint64_t b; memcpy(&b, &f, sizeof b); return b; }
+static inline double wasm_f64_from_bits(uint64_t b) { double f; memcpy(&f, &b, sizeof f); return f; }
+
+/* Branchless select for constant-time functions: the condition becomes an
+   all-ones or all-zeros mask, read through a volatile so the compiler cannot
+   turn the masking back into a branch. */
+static inline uint64_t wasm_ct_mask(int32_t c) {
+    volatile uint64_t mask = 0 - (uint64_t)(((uint32_t)c | (0u - (uint32_t)c)) >> 31);
+    return mask;
+}
+static inline int32_t wasm_ct_select_i32(int32_t c, int32_t a, int32_t b) {
+    uint32_t m = (uint32_t)wasm_ct_mask(c);
+    return (int32_t)(((uint32_t)a & m) | ((uint32_t)b & ~m));
+}
+static inline int64_t wasm_ct_select_i64(int32_t c, int64_t a, int64_t b) {
+    uint64_t m = wasm_ct_mask(c);
+    return (int64_t)(((uint64_t)a & m) | ((uint64_t)b & ~m));
+}
+static inline float wasm_ct_select_f32(int32_t c, float a, float b) {
+    uint32_t m = (uint32_t)wasm_ct_mask(c);
+    return wasm_f32_from_bits((wasm_f32_to_bits(a) & m) | (wasm_f32_to_bits(b) & ~m));
+}
+static inline double wasm_ct_select_f64(int32_t c, double a, double b) {
+    uint64_t m = wasm_ct_mask(c);
+    return wasm_f64_from_bits((wasm_f64_to_bits(a) & m) | (wasm_f64_to_bits(b) & ~m));
+}
+
+/* Memory: little-endian byte order regardless of the target's. */
+static inline int wasm_in_bounds(const wasm_module_t *m, uint32_t start, uint64_t len) {
+    return (uint64_t)start + len <= (uint64_t)m->mem_pages * WASM_PAGE_SIZE;
+}
+
+static inline wasm_trap_t wasm_load(const wasm_module_t *m, int32_t addr, uint32_t offset, uint32_t size, uint64_t *bits) {
+    uint64_t ea = (uint64_t)(uint32_t)addr + offset;
+    uint64_t v = 0;
+    uint32_t i;
+    if (ea + size > (uint64_t)m->mem_pages * WASM_PAGE_SIZE) return WASM_TRAP_OUT_OF_BOUNDS;
+    for (i = 0; i < size; i++) v |= (uint64_t)m->mem[ea + i] << (8 * i);
+    *bits = v;
+    return WASM_OK;
+}
+
+static inline wasm_trap_t wasm_store(wasm_module_t *m, int32_t addr, uint32_t offset, uint32_t size, uint64_t bits) {
+    uint64_t ea = (uint64_t)(uint32_t)addr + offset;
+    uint32_t i;
+    if (ea + size > (uint64_t)m->mem_pages * WASM_PAGE_SIZE) return WASM_TRAP_OUT_OF_BOUNDS;
+    for (i = 0; i < size; i++) m->mem[ea + i] = (uint8_t)(bits >> (8 * i));
+    return WASM_OK;
+}
+
+static inline int32_t wasm_memory_grow(wasm_module_t *m, uint32_t delta, uint32_t max_pages) {
+    uint32_t old = m->mem_pages;
+    uint64_t pages = (uint64_t)old + delta;
+    if (pages > m->mem_max_pages || pages > max_pages) return -1;
+    if (delta != 0) memset(m->mem + (size_t)old * WASM_PAGE_SIZE, 0, (size_t)delta * WASM_PAGE_SIZE);
+    m->mem_pages = (uint32_t)pages;
+    return (int32_t)old;
+}
+
+static inline wasm_trap_t wasm_memory_copy(wasm_module_t *m, int32_t dst, int32_t src, int32_t len) {
+    if (!wasm_in_bounds(m, (uint32_t)dst, (uint32_t)len) || !wasm_in_bounds(m, (uint32_t)src, (uint32_t)len)) return WASM_TRAP_OUT_OF_BOUNDS;
+    if (len != 0) memmove(m->mem + (uint32_t)dst, m->mem + (uint32_t)src, (uint32_t)len);
+    return WASM_OK;
+}
+
+static inline wasm_trap_t wasm_memory_fill(wasm_module_t *m, int32_t dst, int32_t val, int32_t len) {
+    if (!wasm_in_bounds(m, (uint32_t)dst, (uint32_t)len)) return WASM_TRAP_OUT_OF_BOUNDS;
+    if (len != 0) memset(m->mem + (uint32_t)dst, (uint8_t)val, (uint32_t)len);
+    return WASM_OK;
+}
+
+static inline wasm_trap_t wasm_memory_init(wasm_module_t *m, int32_t dst, const uint8_t *seg, uint32_t seg_len, int32_t src, int32_t len) {
+    if ((uint64_t)(uint32_t)src + (uint32_t)len > seg_len || !wasm_in_bounds(m, (uint32_t)dst, (uint32_t)len)) return WASM_TRAP_OUT_OF_BOUNDS;
+    if (len != 0) memcpy(m->mem + (uint32_t)dst, seg + (uint32_t)src, (uint32_t)len);
+    return WASM_OK;
+}
+
+static inline wasm_trap_t wasm_init_data(wasm_module_t *m, uint32_t offset, const uint8_t *data, uint32_t len) {
+    if (!wasm_in_bounds(m, offset, len)) return WASM_TRAP_OUT_OF_BOUNDS;
+    if (len != 0) memcpy(m->mem + offset, data, len);
+    return WASM_OK;
+}
+
+/* Integer arithmetic */
+static inline wasm_trap_t wasm_i32_div_s(int32_t a, int32_t b, int32_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    if (a == INT32_MIN && b == -1) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = a / b;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i32_div_u(int32_t a, int32_t b, int32_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    *r = (int32_t)((uint32_t)a / (uint32_t)b);
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i32_rem_s(int32_t a, int32_t b, int32_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    *r = b == -1 ? 0 : a % b;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i32_rem_u(int32_t a, int32_t b, int32_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    *r = (int32_t)((uint32_t)a % (uint32_t)b);
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_div_s(int64_t a, int64_t b, int64_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    if (a == INT64_MIN && b == -1) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = a / b;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_div_u(int64_t a, int64_t b, int64_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    *r = (int64_t)((uint64_t)a / (uint64_t)b);
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_rem_s(int64_t a, int64_t b, int64_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    *r = b == -1 ? 0 : a % b;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_rem_u(int64_t a, int64_t b, int64_t *r) {
+    if (b == 0) return WASM_TRAP_DIVISION_BY_ZERO;
+    *r = (int64_t)((uint64_t)a % (uint64_t)b);
+    return WASM_OK;
+}
+
+/* Right-shifting a negative value is implementation-defined in C. */
+static inline int32_t wasm_i32_shr_s(int32_t a, int32_t b) {
+    uint32_t s = (uint32_t)b & 31u, u = (uint32_t)a >> s;
+    if (a < 0 && s != 0) u |= ~(UINT32_MAX >> s);
+    return (int32_t)u;
+}
+static inline int64_t wasm_i64_shr_s(int64_t a, int64_t b) {
+    uint64_t s = (uint64_t)b & 63u, u = (uint64_t)a >> s;
+    if (a < 0 && s != 0) u |= ~(UINT64_MAX >> s);
+    return (int64_t)u;
+}
+static inline int32_t wasm_i32_rotl(int32_t a, int32_t b) {
+    uint32_t s = (uint32_t)b & 31u, u = (uint32_t)a;
+    return (int32_t)((u << s) | (u >> ((32u - s) & 31u)));
+}
+static inline int32_t wasm_i32_rotr(int32_t a, int32_t b) {
+    uint32_t s = (uint32_t)b & 31u, u = (uint32_t)a;
+    return (int32_t)((u >> s) | (u << ((32u - s) & 31u)));
+}
+static inline int64_t wasm_i64_rotl(int64_t a, int64_t b) {
+    uint64_t s = (uint64_t)b & 63u, u = (uint64_t)a;
+    return (int64_t)((u << s) | (u >> ((64u - s) & 63u)));
+}
+static inline int64_t wasm_i64_rotr(int64_t a, int64_t b) {
+    uint64_t s = (uint64_t)b & 63u, u = (uint64_t)a;
+    return (int64_t)((u >> s) | (u << ((64u - s) & 63u)));
+}
+
+static inline int32_t wasm_i32_clz(int32_t a) {
+    uint32_t u = (uint32_t)a;
+    int32_t n = 0;
+    if (u == 0) return 32;
+    while (!(u & 0x80000000u)) { u <<= 1; n++; }
+    return n;
+}
+static inline int32_t wasm_i32_ctz(int32_t a) {
+    uint32_t u = (uint32_t)a;
+    int32_t n = 0;
+    if (u == 0) return 32;
+    while (!(u & 1u)) { u >>= 1; n++; }
+    return n;
+}
+static inline int32_t wasm_i32_popcnt(int32_t a) {
+    uint32_t u = (uint32_t)a;
+    int32_t n = 0;
+    while (u) { u &= u - 1; n++; }
+    return n;
+}
+static inline int64_t wasm_i64_clz(int64_t a) {
+    uint64_t u = (uint64_t)a;
+    int64_t n = 0;
+    if (u == 0) return 64;
+    while (!(u & UINT64_C(0x8000000000000000))) { u <<= 1; n++; }
+    return n;
+}
+static inline int64_t wasm_i64_ctz(int64_t a) {
+    uint64_t u = (uint64_t)a;
+    int64_t n = 0;
+    if (u == 0) return 64;
+    while (!(u & 1u)) { u >>= 1; n++; }
+    return n;
+}
+static inline int64_t wasm_i64_popcnt(int64_t a) {
+    uint64_t u = (uint64_t)a;
+    int64_t n = 0;
+    while (u) { u &= u - 1; n++; }
+    return n;
+}
+
+/* Float min/max: NaN propagates and -0 orders below +0. */
+static inline float wasm_f32_min(float a, float b) {
+    if (isnan(a) || isnan(b)) return NAN;
+    if (a == 0 && b == 0) return signbit(a) ? a : b;
+    return a <= b ? a : b;
+}
+static inline float wasm_f32_max(float a, float b) {
+    if (isnan(a) || isnan(b)) return NAN;
+    if (a == 0 && b == 0) return signbit(a) ? b : a;
+    return a >= b ? a : b;
+}
+static inline double wasm_f64_min(double a, double b) {
+    if (isnan(a) || isnan(b)) return NAN;
+    if (a == 0 && b == 0) return signbit(a) ? a : b;
+    return a <= b ? a : b;
+}
+static inline double wasm_f64_max(double a, double b) {
+    if (isnan(a) || isnan(b)) return NAN;
+    if (a == 0 && b == 0) return signbit(a) ? b : a;
+    return a >= b ? a : b;
+}
+
+/* Float to integer truncation. NaN fails every range check, and traps like
+   an out-of-range value (as in herkos-runtime). */
+static inline wasm_trap_t wasm_i32_trunc_f32_s(float v, int32_t *r) {
+    if (!(v >= -2147483648.0f && v < 2147483648.0f)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int32_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i32_trunc_f32_u(float v, int32_t *r) {
+    if (!(v > -1.0f && v < 4294967296.0f)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int32_t)(uint32_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i32_trunc_f64_s(double v, int32_t *r) {
+    if (!(v > -2147483649.0 && v < 2147483648.0)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int32_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i32_trunc_f64_u(double v, int32_t *r) {
+    if (!(v > -1.0 && v < 4294967296.0)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int32_t)(uint32_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_trunc_f32_s(float v, int64_t *r) {
+    if (!(v >= -9223372036854775808.0f && v < 9223372036854775808.0f)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int64_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_trunc_f32_u(float v, int64_t *r) {
+    if (!(v > -1.0f && v < 18446744073709551616.0f)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int64_t)(uint64_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_trunc_f64_s(double v, int64_t *r) {
+    if (!(v >= -9223372036854775808.0 && v < 9223372036854775808.0)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int64_t)v;
+    return WASM_OK;
+}
+static inline wasm_trap_t wasm_i64_trunc_f64_u(double v, int64_t *r) {
+    if (!(v > -1.0 && v < 18446744073709551616.0)) return WASM_TRAP_INTEGER_OVERFLOW;
+    *r = (int64_t)(uint64_t)v;
+    return WASM_OK;
+}
+"#;
+
+/// Generate a C99 translation unit for `info`.
+pub fn generate_c_module(backend: &CBackend, info: &LoweredModuleInfo) -> Result<String> {
+    check_supported(info)?;
+
+    let estimated: usize = info
+        .ir_functions
+        .iter()
+        .map(crate::codegen::function::estimate_function_size)
+        .sum();
+    let mut code = String::with_capacity(HELPERS.len() + estimated);
+
+    writeln!(
+        code,
+        "/* Generated by herkos v{} (C backend, experimental) */",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(code, "/* Wasm binary version: {} */", info.wasm_version)?;
+    if let Some(record) = &info.pretransform {
+        writeln!(
+            code,
+            "/* Pre-transform: {} (input {:016x}, output {:016x}) */",
+            record.name.escape_debug().to_string().replace("*/", "* /"),
+            record.input_hash,
+            record.output_hash
+        )?;
+    }
+    code.push_str("/* DO NOT EDIT */\n\n");
+    code.push_str("#include <math.h>\n#include <stdint.h>\n#include <string.h>\n\n");
+    // Generated functions declare every IR variable up front and a label per
+    // block, whether used or not.
+    code.push_str("#if defined(__GNUC__)\n");
+    for warning in [
+        "-Wunused-variable",
+        "-Wunused-but-set-variable",
+        "-Wunused-parameter",
+        "-Wunused-label",
+        "-Wunused-function",
+    ] {
+        writeln!(code, "#pragma GCC diagnostic ignored \"{warning}\"")?;
+    }
+    code.push_str("#endif\n\n");
+
+    code.push_str(TYPES);
+    code.push('\n');
+    write_module_struct(&mut code, info)?;
+    code.push('\n');
+    code.push_str(HELPERS);
+    code.push('\n');
+    write_prototypes(&mut code, info)?;
+    write_segments_and_consts(&mut code, info)?;
+
+    for (idx, ir_func) in info.ir_functions.iter().enumerate() {
+        if info.is_merged(idx) {
+            continue;
+        }
+        write_function(&mut code, backend, ir_func, idx, info)?;
+        code.push('\n');
+    }
+
+    write_exports(&mut code, info)?;
+    write_init(&mut code, info)?;
+    Ok(code)
+}
+
+/// Reject modules and options the C backend cannot express.
+fn check_supported(info: &ModuleInfo) -> Result<()> {
+    if info.wasmtime_adapter {
+        bail!("the C backend does not support the wasmtime adapter");
+    }
+    // `--link` and `--hot-reload` imply dynamic invocation; name the option
+    // that was given
+    if info.link {
+        bail!("the C backend does not support runtime linking");
+    }
+    if info.hot_reload {
+        bail!("the C backend does not support hot reloading");
+    }
+    if info.dynamic_invoke {
+        bail!("the C backend does not support dynamic invocation");
+    }
+    if !info.export_groups.is_empty() {
+        bail!("the C backend does not support export groups");
+    }
+    if info.shared_host {
+        bail!("the C backend does not support shared hosts");
+    }
+    if info.heap_profile {
+        bail!("the C backend does not support heap profiling");
+    }
+    if !info.contracts.is_empty() {
+        bail!("the C backend does not support export contracts");
+    }
+    if !info.guest_log.is_empty() {
+        bail!("the C backend does not support guest logging");
+    }
+    if !info.import_capabilities.is_empty() {
+        bail!("the C backend does not support capability host traits");
+    }
+    if info.debug_memory {
+        bail!("the C backend does not support debug memory");
+    }
+    if info.coverage {
+        bail!("the C backend does not support coverage instrumentation");
+    }
+    if info.bounds_profile {
+        bail!("the C backend does not support bounds-check profiling");
+    }
+    if info.data_segments.iter().any(|seg| seg.protected) {
+        bail!("the C backend does not support write-protected data");
+    }
+    if !info.import_regions.is_empty() {
+        bail!("the C backend does not support checked import arguments");
+    }
+    if !info.export_features.is_empty() {
+        bail!("the C backend does not support export feature gates");
+    }
+    if !info.imported_globals.is_empty() {
+        bail!("the C backend does not support imported globals");
+    }
+    if info.has_table_import {
+        bail!("the C backend does not support imported tables");
+    }
+    if info.start.is_some() {
+        bail!("the C backend does not support start functions run at instantiation");
+    }
+    let multi_value = info.ir_functions.iter().any(|f| f.results.len() > 1)
+        || info.func_imports.iter().any(|imp| imp.results.len() > 1)
+        || info.type_signatures.iter().any(|sig| sig.results.len() > 1);
+    if multi_value {
+        bail!("the C backend does not support multi-value results");
+    }
+    Ok(())
+}
+
+/// Emit the limits and the `wasm_module_t` struct.
+fn write_module_struct(code: &mut String, info: &ModuleInfo) -> Result<()> {
+    let owns_storage = info.has_memory && info.max_pages > 0;
+    if owns_storage {
+        writeln!(code, "#define WASM_MAX_PAGES {}u", info.max_pages)?;
+    }
+    if info.owns_table() {
+        writeln!(code, "#define WASM_TABLE_MAX {}u", info.table_max)?;
+    }
+    code.push('\n');
+
+    code.push_str("typedef struct wasm_module {\n");
+    code.push_str("    /* Embedder context for imports; not used by generated code. */\n");
+    code.push_str("    void *host;\n");
+    if info.has_memory_import {
+        code.push_str("    /* Imported memory: set by the embedder before wasm_init. */\n");
+    }
+    code.push_str("    uint8_t *mem;\n    uint32_t mem_pages;\n    uint32_t mem_max_pages;\n");
+    if owns_storage {
+        code.push_str("    uint8_t mem_storage[(size_t)WASM_MAX_PAGES * WASM_PAGE_SIZE];\n");
+    }
+    for (idx, global) in info.globals.iter().enumerate() {
+        if !global.is_const() {
+            writeln!(code, "    {} g{idx};", c_type(global.init_value.ty()))?;
+        }
+    }
+    if info.owns_table() {
+        code.push_str("    wasm_funcref_t table[WASM_TABLE_MAX];\n    uint32_t table_size;\n");
+    }
+    code.push_str("} wasm_module_t;\n");
+    Ok(())
+}
+
+/// Parameter list `wasm_module_t *m, int32_t v0, ..., int32_t *ret`.
+///
+/// `results` has at most one type: `check_supported` rejects multi-value.
+fn param_list(params: &[(String, WasmType)], results: &[WasmType]) -> String {
+    let mut list = String::from("wasm_module_t *m");
+    for (name, ty) in params {
+        list.push_str(&format!(", {} {name}", c_type(*ty)));
+    }
+    if let Some(ty) = results.first().copied() {
+        list.push_str(&format!(", {} *ret", c_type(ty)));
+    }
+    list
+}
+
+fn function_params(ir_func: &IrFunction) -> Vec<(String, WasmType)> {
+    ir_func
+        .params
+        .iter()
+        .map(|(var, ty)| (var.to_string(), *ty))
+        .collect()
+}
+
+/// Emit the import, export and internal function prototypes.
+fn write_prototypes(code: &mut String, info: &ModuleInfo) -> Result<()> {
+    if !info.func_imports.is_empty() || info.yield_points {
+        code.push_str("/* Imports, implemented by the embedder. */\n");
+        let mut declared = std::collections::HashSet::new();
+        for import in &info.func_imports {
+            let symbol = import_symbol(&import.module_name, &import.func_name);
+            if !declared.insert(symbol.clone()) {
+                continue;
+            }
+            let params: Vec<(String, WasmType)> = import
+                .params
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (format!("arg{i}"), *ty))
+                .collect();
+            writeln!(
+                code,
+                "wasm_trap_t {symbol}({});",
+                param_list(&params, &import.results)
+            )?;
+        }
+        if info.yield_points {
+            code.push_str("wasm_trap_t wasm_host_maybe_yield(wasm_module_t *m);\n");
+        }
+        code.push('\n');
+    }
+
+    code.push_str("/* Exports */\n");
+    code.push_str("wasm_trap_t wasm_init(wasm_module_t *m);\n");
+    for export in &info.func_exports {
+        let ir_func = &info.ir_functions[export.func_index.as_usize()];
+        writeln!(
+            code,
+            "wasm_trap_t wasm_export_{}({});",
+            rust_ident(&export.name),
+            param_list(&function_params(ir_func), &ir_func.results)
+        )?;
+    }
+    code.push('\n');
+
+    for (idx, ir_func) in info.ir_functions.iter().enumerate() {
+        if info.is_merged(idx) {
+            continue;
+        }
+        writeln!(
+            code,
+            "static wasm_trap_t {}({});",
+            info.func_ident(idx),
+            param_list(&function_params(ir_func), &ir_func.results)
+        )?;
+    }
+    code.push('\n');
+    Ok(())
+}
+
+/// Append `data` as a C array initializer body.
+fn write_bytes(code: &mut String, data: &[u8]) -> Result<()> {
+    for (i, chunk) in data.chunks(16).enumerate() {
+        code.push_str(if i == 0 { "    " } else { ",\n    " });
+        let bytes: Vec<String> = chunk.iter().map(|b| format!("0x{b:02x}")).collect();
+        code.push_str(&bytes.join(", "));
+    }
+    code.push('\n');
+    Ok(())
+}
+
+/// Emit active and passive data segments and the constant globals.
+fn write_segments_and_consts(code: &mut String, info: &ModuleInfo) -> Result<()> {
+    if info.has_memory || info.has_memory_import {
+        for (idx, seg) in info.data_segments.iter().enumerate() {
+            if seg.data.is_empty() || !seg.resident {
+                continue;
+            }
+            writeln!(code, "static const uint8_t DATA_SEGMENT_{idx}[] = {{")?;
+            write_bytes(code, &seg.data)?;
+            code.push_str("};\n");
+        }
+    }
+    for seg in &info.passive_data_segments {
+        let name = format!("PASSIVE_SEGMENT_{}", seg.wasm_index);
+        writeln!(
+            code,
+            "static const uint32_t {name}_LEN = {}u;",
+            seg.data.len()
+        )?;
+        if seg.data.is_empty() {
+            // C has no zero-length arrays
+            writeln!(code, "static const uint8_t {name}[1] = {{ 0 }};")?;
+        } else {
+            writeln!(code, "static const uint8_t {name}[] = {{")?;
+            write_bytes(code, &seg.data)?;
+            code.push_str("};\n");
+        }
+    }
+    for (idx, global) in info.globals.iter().enumerate() {
+        if global.is_const() {
+            let value = global_init_value(&global.init_value)?;
+            writeln!(
+                code,
+                "static const {} G{idx} = {};",
+                c_type(global.init_value.ty()),
+                c_literal(&value)
+            )?;
+        }
+    }
+    code.push('\n');
+    Ok(())
+}
+
+fn global_init_value(init: &GlobalInit) -> Result<IrValue> {
+    Ok(match *init {
+        GlobalInit::I32(v) => IrValue::I32(v),
+        GlobalInit::I64(v) => IrValue::I64(v),
+        GlobalInit::F32(v) => IrValue::F32(v),
+        GlobalInit::F64(v) => IrValue::F64(v),
+        GlobalInit::Imported { .. } => bail!("the C backend does not support imported globals"),
+    })
+}
+
+/// Emit one IR function as a `static` C function.
+fn write_function(
+    code: &mut String,
+    backend: &CBackend,
+    ir_func: &IrFunction,
+    idx: usize,
+    info: &ModuleInfo,
+) -> Result<()> {
+    writeln!(
+        code,
+        "static wasm_trap_t {}({})\n{{",
+        info.func_ident(idx),
+        param_list(&function_params(ir_func), &ir_func.results)
+    )?;
+
+    let var_types = crate::codegen::function::infer_var_types(ir_func, info);
+    for (var, ty) in var_types
+        .iter()
+        .filter(|(var, _)| !ir_func.params.iter().any(|(p, _)| p == *var))
+    {
+        writeln!(code, "    {} {var} = 0;", c_type(*ty))?;
+    }
+    let has_loads = ir_func
+        .blocks
+        .iter()
+        .flat_map(|b| &b.instructions)
+        .any(|i| matches!(i, IrInstr::Load { .. }));
+    if has_loads {
+        code.push_str("    uint64_t bits = 0;\n");
+    }
+
+    let block_id_to_index: HashMap<BlockId, usize> = ir_func
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(idx, block)| (block.id, idx))
+        .collect();
+    let yield_blocks = if info.yield_points {
+        crate::codegen::function::loop_headers(ir_func)
+    } else {
+        Default::default()
+    };
+
+    for (block_idx, block) in ir_func.blocks.iter().enumerate() {
+        writeln!(code, "B{block_idx}:")?;
+        if yield_blocks.contains(&block.id) {
+            backend.emit_yield_point(code)?;
+            code.push('\n');
+        }
+        for instr in &block.instructions {
+            match instr {
+                IrInstr::Select {
+                    dest,
+                    val1,
+                    val2,
+                    condition,
+                } if info.is_constant_time(idx) => {
+                    let ty = var_types.get(val1).copied().unwrap_or(WasmType::I32);
+                    backend.emit_select_branchless(code, *dest, *val1, *val2, *condition, ty)?
+                }
+                IrInstr::CallIndirect {
+                    dests,
+                    type_idx,
+                    table_idx,
+                    args,
+                } => write_call_indirect(code, dests, type_idx.clone(), *table_idx, args, info)?,
+                _ => write_instruction(code, backend, instr, info)?,
+            }
+            code.push('\n');
+        }
+        write_terminator(
+            code,
+            backend,
+            &block.terminator,
+            &block_id_to_index,
+            &ir_func.results,
+        )?;
+        code.push('\n');
+    }
+    code.push_str("}\n");
+    Ok(())
+}
+
+/// Emit `call_indirect`: bounds and type checks, then a dispatch over the
+/// functions of the called type.
+fn write_call_indirect(
+    code: &mut String,
+    dests: &[VarId],
+    type_idx: TypeIdx,
+    table_idx: VarId,
+    args: &[VarId],
+    info: &ModuleInfo,
+) -> Result<()> {
+    if !info.owns_table() {
+        bail!("call_indirect without a table");
+    }
+    // Canonical type index, as stored in table entries (Wasm spec §4.4.9)
+    let canon_idx = info
+        .canonical_type
+        .get(type_idx.as_usize())
+        .copied()
+        .unwrap_or(type_idx.as_usize());
+
+    let mut dest_args = String::new();
+    for d in dests {
+        write!(dest_args, ", &{d}")?;
+    }
+    let mut call_args = String::new();
+    for arg in args {
+        write!(call_args, ", {arg}")?;
+    }
+    call_args.push_str(&dest_args);
+
+    code.push_str("    {\n");
+    code.push_str("        const wasm_funcref_t *entry;\n");
+    writeln!(
+        code,
+        "        if ((uint32_t){table_idx} >= m->table_size) return WASM_TRAP_TABLE_OUT_OF_BOUNDS;"
+    )?;
+    writeln!(code, "        entry = &m->table[(uint32_t){table_idx}];")?;
+    code.push_str(
+        "        if (entry->kind == WASM_FUNC_NULL) return WASM_TRAP_UNDEFINED_ELEMENT;\n",
+    );
+    writeln!(
+        code,
+        "        if (entry->type_index != {canon_idx}u) return WASM_TRAP_INDIRECT_CALL_TYPE_MISMATCH;"
+    )?;
+
+    code.push_str("        ");
+    for (func_idx, ir_func) in info.ir_functions.iter().enumerate() {
+        if ir_func.type_idx.as_usize() == canon_idx && !info.is_merged(func_idx) {
+            let func = info.func_ident(func_idx);
+            // Arguments dead-argument elimination removed are not passed on
+            let mut local_args = String::new();
+            for (arg, (_, live)) in args.iter().zip(info.wasm_params(func_idx)) {
+                if live {
+                    write!(local_args, ", {arg}")?;
+                }
+            }
+            writeln!(
+                code,
+                "if (entry->kind == WASM_FUNC_LOCAL && entry->func_index == {func_idx}u) WASM_TRY({func}(m{local_args}{dest_args}));"
+            )?;
+            code.push_str("        else ");
+        }
+    }
+    let mut imports: Vec<usize> = info
+        .element_segments
+        .iter()
+        .flat_map(|seg| &seg.func_indices)
+        .filter_map(|func| match func {
+            ElementFunc::Import { import, type_idx } if type_idx.as_usize() == canon_idx => {
+                Some(import.as_usize())
+            }
+            _ => None,
+        })
+        .collect();
+    imports.sort_unstable();
+    imports.dedup();
+    for import_idx in imports {
+        let import = &info.func_imports[import_idx];
+        writeln!(
+            code,
+            "if (entry->kind == WASM_FUNC_IMPORT && entry->func_index == {import_idx}u) WASM_TRY({}(m{call_args}));",
+            import_symbol(&import.module_name, &import.func_name)
+        )?;
+        code.push_str("        else ");
+    }
+    code.push_str("return WASM_TRAP_UNDEFINED_ELEMENT;\n");
+    code.push_str("    }");
+    Ok(())
+}
+
+/// Emit the exported functions, forwarding to the internal ones.
+fn write_exports(code: &mut String, info: &ModuleInfo) -> Result<()> {
+    for export in &info.func_exports {
+        let func_idx = export.func_index.as_usize();
+        let ir_func = &info.ir_functions[func_idx];
+        let params = function_params(ir_func);
+        writeln!(
+            code,
+            "wasm_trap_t wasm_export_{}({})\n{{",
+            rust_ident(&export.name),
+            param_list(&params, &ir_func.results)
+        )?;
+        write!(code, "    return {}(m", info.func_ident(func_idx))?;
+        for (name, _) in &params {
+            write!(code, ", {name}")?;
+        }
+        if !ir_func.results.is_empty() {
+            code.push_str(", ret");
+        }
+        code.push_str(");\n}\n\n");
+    }
+    Ok(())
+}
+
+/// Emit `wasm_init`: memory, mutable globals, data and element segments.
+fn write_init(code: &mut String, info: &ModuleInfo) -> Result<()> {
+    code.push_str("/* Initialize `m`. A module importing memory expects `mem`, `mem_pages`\n");
+    code.push_str("   and `mem_max_pages` to be set. */\n");
+    code.push_str("wasm_trap_t wasm_init(wasm_module_t *m)\n{\n");
+    if info.has_memory {
+        if info.max_pages > 0 {
+            code.push_str("    m->mem = m->mem_storage;\n");
+            code.push_str("    m->mem_max_pages = WASM_MAX_PAGES;\n");
+        } else {
+            code.push_str("    m->mem = NULL;\n    m->mem_max_pages = 0;\n");
+        }
+        writeln!(code, "    m->mem_pages = {}u;", info.initial_pages)?;
+        if info.initial_pages > 0 {
+            code.push_str("    memset(m->mem, 0, (size_t)m->mem_pages * WASM_PAGE_SIZE);\n");
+        }
+    } else if !info.has_memory_import {
+        code.push_str("    m->mem = NULL;\n    m->mem_pages = 0;\n    m->mem_max_pages = 0;\n");
+    }
+
+    for (idx, global) in info.globals.iter().enumerate() {
+        if !global.is_const() {
+            let value = global_init_value(&global.init_value)?;
+            writeln!(code, "    m->g{idx} = {};", c_literal(&value))?;
+        }
+    }
+
+    if info.has_memory || info.has_memory_import {
+        for (idx, seg) in info.data_segments.iter().enumerate() {
+            if !seg.resident {
+                continue;
+            }
+            if seg.data.is_empty() {
+                writeln!(
+                    code,
+                    "    WASM_TRY(wasm_init_data(m, {}u, NULL, 0));",
+                    seg.offset
+                )?;
+            } else {
+                writeln!(
+                    code,
+                    "    WASM_TRY(wasm_init_data(m, {}u, DATA_SEGMENT_{idx}, sizeof DATA_SEGMENT_{idx}));",
+                    seg.offset
+                )?;
+            }
+        }
+    }
+
+    if info.owns_table() {
+        writeln!(code, "    m->table_size = {}u;", info.table_initial)?;
+        code.push_str("    memset(m->table, 0, sizeof m->table);\n");
+        for seg in &info.element_segments {
+            if seg.func_indices.is_empty() {
+                continue;
+            }
+            writeln!(
+                code,
+                "    if ({}u > m->table_size) return WASM_TRAP_TABLE_OUT_OF_BOUNDS;",
+                seg.offset as u64 + seg.func_indices.len() as u64
+            )?;
+            for (i, func) in seg.func_indices.iter().enumerate() {
+                let (kind, type_idx, func_idx) = match func {
+                    ElementFunc::Local(local) => {
+                        let ir_func = info
+                            .ir_function(*local)
+                            .ok_or_else(|| anyhow::anyhow!("Invalid function index"))?;
+                        (
+                            "WASM_FUNC_LOCAL",
+                            ir_func.type_idx.as_usize(),
+                            local.as_usize(),
+                        )
+                    }
+                    ElementFunc::Import { import, type_idx } => {
+                        ("WASM_FUNC_IMPORT", type_idx.as_usize(), import.as_usize())
+                    }
+                };
+                let slot = seg.offset + i;
+                writeln!(
+                    code,
+                    "    m->table[{slot}].kind = {kind}; m->table[{slot}].type_index = {type_idx}u; m->table[{slot}].func_index = {func_idx}u;"
+                )?;
+            }
+        }
+    }
+    code.push_str("    return WASM_OK;\n}\n");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn c_options() -> TranspileOptions {
+        TranspileOptions {
+            mode: "c".to_string(),
+            ..TranspileOptions::default()
+        }
+    }
+
+    #[test]
+    fn blocks_become_labels_and_gotos() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "count") (param i32) (result i32)
+                  (local i32)
+                  (block
+                    (loop
+                      (br_if 1 (i32.eqz (local.get 0)))
+                      (local.set 1 (i32.add (local.get 1) (i32.const 1)))
+                      (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+                      (br 0)))
+                  (local.get 1)))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &c_options()).unwrap();
+        assert!(
+            code.contains("static wasm_trap_t func_0(wasm_module_t *m, int32_t v0, int32_t *ret)")
+        );
+        assert!(code
+            .contains("wasm_trap_t wasm_export_count(wasm_module_t *m, int32_t v0, int32_t *ret)"));
+        assert!(code.contains("goto B"));
+        assert!(code.contains("(int32_t)((uint32_t)"));
+        assert!(!code.contains("WasmResult"));
+    }
+
+    #[test]
+    fn imports_are_prototypes_for_the_embedder() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (func (export "run") (call $log (i32.const 7))))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &c_options()).unwrap();
+        assert!(code.contains("wasm_trap_t wasm_import_env_log(wasm_module_t *m, int32_t arg0);"));
+        assert!(code.contains("WASM_TRY(wasm_import_env_log(m, v"));
+    }
+
+    #[test]
+    fn constant_time_selects_do_not_branch() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "pick") (param i32 f64 f64) (result f64)
+                    (select (local.get 1) (local.get 2) (local.get 0))))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            constant_time: vec!["pick".to_string()],
+            ..c_options()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.contains("= wasm_ct_select_f64(v"));
+        assert!(!code.contains(" != 0 ? "));
+    }
+
+    #[test]
+    fn multi_value_is_rejected() {
+        let wasm = wat::parse_str(
+            r#"(module (func (export "f") (result i32 i32) (i32.const 1) (i32.const 2)))"#,
+        )
+        .unwrap();
+        let err = transpile(&wasm, &c_options()).unwrap_err();
+        assert!(format!("{err:#}").contains("multi-value"));
+    }
+
+    #[test]
+    fn rust_only_options_are_rejected() {
+        let wasm = wat::parse_str(r#"(module (func (export "f")))"#).unwrap();
+        let options = TranspileOptions {
+            wasmtime_adapter: true,
+            ..c_options()
+        };
+        let err = transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("wasmtime adapter"));
+
+        let options = TranspileOptions {
+            dynamic_invoke: true,
+            ..c_options()
+        };
+        let err = transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("dynamic invocation"));
+
+        let options = TranspileOptions {
+            link: true,
+            ..c_options()
+        };
+        let err = transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("runtime linking"));
+
+        let options = TranspileOptions {
+            hot_reload: true,
+            ..c_options()
+        };
+        let err = transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("hot reloading"));
+
+        let wasm =
+            wat::parse_str(r#"(module (import "env" "g" (func)) (func (export "f")))"#).unwrap();
+        let options = TranspileOptions {
+            shared_host: true,
+            ..c_options()
+        };
+        let err = transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("shared hosts"));
+    }
+}
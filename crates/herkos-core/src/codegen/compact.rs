@@ -0,0 +1,242 @@
+//! Compact emission for output only rustc reads (`--compact`).
+//!
+//! Rewrites generated Rust without changing its tokens:
+//!
+//! - comments and doc comments are dropped, and so are `#[doc(..)]`
+//!   attributes;
+//! - indentation and line breaks go, and other whitespace shrinks to a
+//!   single space, or to nothing next to delimiters and between an
+//!   identifier and an operator;
+//! - every top-level item ends its line, so the output stays usable with
+//!   line-based tools.
+//!
+//! Lint attributes stay: they keep the output warning-free. Spaces are only
+//! dropped where rejoining the neighbours cannot form another token (`< <`,
+//! `b "x"`, `r #x` keep theirs). String and character literals are copied
+//! as they are.
+
+/// Compact `code`, generated Rust source.
+pub fn compact(code: &str) -> String {
+    let src = code.as_bytes();
+    let mut out = String::with_capacity(code.len() / 2);
+    let mut pending_space = false;
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < src.len() {
+        let c = src[i];
+        let rest = &src[i..];
+        if c.is_ascii_whitespace() {
+            pending_space = true;
+            i += 1;
+            continue;
+        }
+        if rest.starts_with(b"//") {
+            i += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+            pending_space = true;
+            continue;
+        }
+        if rest.starts_with(b"/*") {
+            i += block_comment_len(rest);
+            pending_space = true;
+            continue;
+        }
+        if rest.starts_with(b"#[doc") && matches!(rest.get(5), Some(b'(' | b'=' | b' ')) {
+            i += attribute_len(rest);
+            pending_space = true;
+            continue;
+        }
+
+        // Next token: a literal copied whole, or a single character
+        let prev_is_ident = i > 0 && is_ident(src[i - 1]);
+        let len = literal_len(rest, prev_is_ident).unwrap_or_else(|| utf8_len(c));
+        if pending_space {
+            if let Some(last) = out.as_bytes().last() {
+                if keeps_space(*last, c) {
+                    out.push(' ');
+                }
+            }
+            pending_space = false;
+        }
+        out.push_str(&code[i..i + len]);
+        i += len;
+
+        match c {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 && matches!(c, b'}' | b';') {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Delimiters nothing glues to.
+const DELIMITERS: &[u8] = b"(){}[],;";
+
+/// Operator characters no identifier character forms a token with. `<`
+/// is missing so `x as i32 < y` does not turn into generic arguments.
+const OPERATORS: &[u8] = b"=+-*/%&|^>:!?@";
+
+fn is_ident(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80
+}
+
+/// Whether whitespace between `prev` and `next` must stay.
+fn keeps_space(prev: u8, next: u8) -> bool {
+    if prev == b'\n' || DELIMITERS.contains(&prev) || DELIMITERS.contains(&next) {
+        return false;
+    }
+    let glued = (is_ident(prev) && OPERATORS.contains(&next))
+        || (OPERATORS.contains(&prev) && is_ident(next));
+    !glued
+}
+
+/// Length of the UTF-8 sequence starting with `c`.
+fn utf8_len(c: u8) -> usize {
+    match c {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    }
+}
+
+/// Length of the (possibly nested) block comment `src` starts with.
+fn block_comment_len(src: &[u8]) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < src.len() {
+        if src[i..].starts_with(b"/*") {
+            depth += 1;
+            i += 2;
+        } else if src[i..].starts_with(b"*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    src.len()
+}
+
+/// Length of the attribute `src` starts with, up to its closing `]`.
+fn attribute_len(src: &[u8]) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < src.len() {
+        match src[i] {
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            b'"' => {
+                i += literal_len(&src[i..], false).unwrap_or(1);
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    src.len()
+}
+
+/// Length of the string, raw string or character literal `src` starts
+/// with, if any. A `b` or `r` prefix counts only at the start of a token,
+/// that is when `prev_is_ident` is false.
+fn literal_len(src: &[u8], prev_is_ident: bool) -> Option<usize> {
+    let mut start = 0;
+    if !prev_is_ident && src.first() == Some(&b'b') {
+        start = 1;
+    }
+    match src.get(start) {
+        Some(b'"') => Some(start + quoted_len(&src[start..], b'"')),
+        Some(b'\'') => char_literal_len(&src[start..]).map(|len| start + len),
+        Some(b'r') if !prev_is_ident || start == 1 => {
+            let hashes = src[start + 1..].iter().take_while(|&&b| b == b'#').count();
+            if src.get(start + 1 + hashes) != Some(&b'"') {
+                return None;
+            }
+            let body = start + 2 + hashes;
+            let close = format!("\"{}", "#".repeat(hashes));
+            let end = src[body..]
+                .windows(close.len())
+                .position(|w| w == close.as_bytes())
+                .map_or(src.len(), |at| body + at + close.len());
+            Some(end)
+        }
+        _ => None,
+    }
+}
+
+/// Length of the literal `src` starts with, delimited by `quote` and with
+/// backslash escapes.
+fn quoted_len(src: &[u8], quote: u8) -> usize {
+    let mut i = 1;
+    while i < src.len() {
+        match src[i] {
+            b'\\' => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    src.len()
+}
+
+/// Length of the character literal `src` starts with, or `None` for a
+/// lifetime or label (`'a`, `'_`).
+fn char_literal_len(src: &[u8]) -> Option<usize> {
+    match src.get(1)? {
+        b'\\' => Some(quoted_len(src, b'\'')),
+        &c => {
+            let end = 1 + utf8_len(c);
+            (src.get(end) == Some(&b'\'')).then_some(end + 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_comments_and_whitespace_between_delimiters() {
+        let code = "//! Module doc\n\
+                    /// Item doc\n\
+                    #[doc(hidden)]\n\
+                    pub fn add(a: i32, b: i32) -> i32 {\n    \
+                        // sum\n    \
+                        a + b /* inline */\n\
+                    }\n\n\
+                    const X: i32 = 1;\n";
+        assert_eq!(
+            compact(code),
+            "pub fn add(a:i32,b:i32)->i32{a+b}\nconst X:i32=1;\n"
+        );
+    }
+
+    #[test]
+    fn keeps_spaces_that_separate_tokens() {
+        assert_eq!(
+            compact("fn f() { x as i32 < y; a - -1; &'a mut b; r #x; }"),
+            "fn f(){x as i32 < y;a- -1;&'a mut b;r #x;}\n"
+        );
+    }
+
+    #[test]
+    fn copies_literals_unchanged() {
+        let code = r##"fn f() { g("a  //b", b"  ", r#" " "#, ' ', '\'', "\" ;"); }"##;
+        assert_eq!(
+            compact(code),
+            r##"fn f(){g("a  //b",b"  ",r#" " "#,' ','\'',"\" ;");}"##.to_string() + "\n"
+        );
+    }
+}
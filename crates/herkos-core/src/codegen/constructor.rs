@@ -1,25 +1,99 @@
 //! Constructor and initialization code generation.
 //!
 //! Handles generation of:
+//! - The file preamble, with the `MODULE_ABI` runtime version guard and the
+//!   `PRETRANSFORM` record
 //! - Module/LibraryModule constructors
 //! - Const items for immutable globals
 //! - Element segment initialization
 //! - Data segment initialization
+//! - `INSTANTIATION_COST` and `new_limited`, which checks it against the
+//!   host's `InstantiationLimits` before `new` copies anything
+//! - `DYLINK` layout constants for dynamic-linking side modules
 
 use crate::backend::Backend;
 use crate::ir::*;
+use crate::Preamble;
 use anyhow::Result;
 
-/// Emit preamble for generated Rust files.
-pub fn rust_code_preamble(info: &ModuleInfo) -> String {
+/// Emit preamble for generated Rust files, with the caller's module doc,
+/// inner attributes and `use` statements if any.
+pub fn rust_code_preamble(info: &ModuleInfo, preamble: Option<&Preamble>) -> String {
     let mut code = String::new();
     code.push_str(&format!(
         "// Generated by herkos v{}\n",
         env!("CARGO_PKG_VERSION")
     ));
     code.push_str(&format!("// Wasm binary version: {}\n", info.wasm_version));
+    if let Some(record) = &info.pretransform {
+        code.push_str(&format!(
+            "// Pre-transform: {}\n",
+            record.name.escape_debug()
+        ));
+    }
     code.push_str("// DO NOT EDIT\n\n");
-    code.push_str("use herkos_runtime::*;\n\n");
+    let empty = Preamble::default();
+    let preamble = preamble.unwrap_or(&empty);
+    if let Some(doc) = &preamble.module_doc {
+        for line in doc.lines() {
+            code.push_str(&format!(
+                "//!{}{}\n",
+                if line.is_empty() { "" } else { " " },
+                line
+            ));
+        }
+        code.push('\n');
+    }
+    for attr in &preamble.inner_attributes {
+        let attr = attr.trim();
+        let attr = attr
+            .strip_prefix("#![")
+            .and_then(|a| a.strip_suffix(']'))
+            .unwrap_or(attr);
+        code.push_str(&format!("#![{attr}]\n"));
+    }
+    if !preamble.inner_attributes.is_empty() {
+        code.push('\n');
+    }
+    if info.lowered_atomics {
+        code.push_str(
+            "// WARNING: atomic operations were lowered to plain loads and stores\n\
+             // (--lower-atomics). This code is only correct if a single thread\n\
+             // accesses the module's memory.\n\n",
+        );
+    }
+    code.push_str("use herkos_runtime::*;\n");
+    for path in &preamble.extra_use_statements {
+        let path = path.trim();
+        let path = path.strip_suffix(';').unwrap_or(path).trim_end();
+        if path.starts_with("use ") || path.starts_with("pub ") || path.starts_with("pub(") {
+            code.push_str(&format!("{path};\n"));
+        } else {
+            code.push_str(&format!("use {path};\n"));
+        }
+    }
+    code.push('\n');
+
+    // Fail to compile against a runtime without the interface used here
+    let (major, minor) = herkos_runtime::ABI_VERSION;
+    code.push_str("/// herkos-runtime ABI this code was generated against: `(major, minor)`.\n");
+    code.push_str(&format!(
+        "pub const MODULE_ABI: (u32, u32) = ({major}, {minor});\n"
+    ));
+    code.push_str(&format!(
+        "const _: () = assert!(\n    herkos_runtime::abi_compatible(MODULE_ABI),\n    \"generated by herkos v{} for herkos-runtime ABI {major}.{minor}, which the herkos-runtime in use does not provide; regenerate the module or use a matching herkos-runtime\"\n);\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    if let Some(record) = &info.pretransform {
+        code.push_str(
+            "\n/// Transform the Wasm binary went through before transpiling: name, and\n\
+             /// FNV-1a hashes of the binary it was given and of the one transpiled.\n",
+        );
+        code.push_str(&format!(
+            "pub const PRETRANSFORM: (&str, u64, u64) = ({:?}, {:#018x}, {:#018x});\n",
+            record.name, record.input_hash, record.output_hash
+        ));
+    }
     code
 }
 
@@ -27,107 +101,497 @@ pub fn rust_code_preamble(info: &ModuleInfo) -> String {
 pub fn emit_const_globals<B: Backend>(_backend: &B, info: &ModuleInfo) -> String {
     let mut code = String::new();
     for (idx, g) in info.globals.iter().enumerate() {
-        if !g.mutable {
-            let (rust_ty, value_str) = crate::codegen::types::global_init_to_rust(&g.init_value);
+        if g.is_const() {
+            let (rust_ty, value_str) =
+                crate::codegen::types::global_init_to_rust(&g.init_value, info);
             code.push_str(&format!("pub const G{idx}: {rust_ty} = {value_str};\n"));
         }
     }
-    if info.globals.iter().any(|g| !g.mutable) {
+    if info.globals.iter().any(GlobalDef::is_const) {
         code.push('\n');
     }
     code
 }
 
+/// Generate the `DYLINK` layout and `DYLINK_NEEDED` constants of a
+/// `dylink.0` side module, for placing it with `LinkedModules`.
+pub fn emit_dylink_consts(info: &ModuleInfo) -> String {
+    let Some(dylink) = &info.dylink else {
+        return String::new();
+    };
+    let mut code = String::new();
+    code.push_str("/// Memory and table space this side module needs (`dylink.0`).\n");
+    code.push_str(&format!(
+        "pub const DYLINK: DylinkLayout = DylinkLayout {{ memory_size: {}, memory_align: {}, table_size: {}, table_align: {} }};\n",
+        dylink.memory_size, dylink.memory_alignment, dylink.table_size, dylink.table_alignment
+    ));
+    code.push_str("/// Shared libraries this side module depends on.\n");
+    let needed: Vec<String> = dylink.needed.iter().map(|n| format!("{n:?}")).collect();
+    code.push_str(&format!(
+        "pub const DYLINK_NEEDED: &[&str] = &[{}];\n\n",
+        needed.join(", ")
+    ));
+    code
+}
+
+/// Generate `WasmModule::memory_limits()` and `required_memory()`, so hosts
+/// can size memory before instantiation. Empty for modules without memory.
+pub fn emit_memory_limits(info: &ModuleInfo) -> String {
+    let (initial_pages, max_pages) = if info.has_memory {
+        (info.initial_pages, "Some(MAX_PAGES as u32)".to_string())
+    } else if info.has_memory_import {
+        let max = match info.memory_import_max_pages {
+            Some(max) => format!("Some({max})"),
+            None => "None".to_string(),
+        };
+        (info.memory_import_initial_pages, max)
+    } else {
+        return String::new();
+    };
+    let data_end = info
+        .data_segments
+        .iter()
+        .filter(|s| s.base.is_none())
+        .map(|s| s.offset as u64 + s.data.len() as u64)
+        .max()
+        .unwrap_or(0)
+        .min(u32::MAX as u64);
+
+    let mut code = String::from(
+        "impl WasmModule {
+",
+    );
+    code.push_str(
+        "    /// Pages the memory declares and bytes the data segments occupy.
+",
+    );
+    code.push_str(&format!(
+        "    pub const fn memory_limits() -> MemoryLimits {{
+        MemoryLimits {{ initial_pages: {initial_pages}, max_pages: {max_pages}, data_end: {data_end} }}
+    }}
+"
+    ));
+    code.push_str(
+        "    /// Bytes of linear memory needed to instantiate the module.
+",
+    );
+    code.push_str(
+        "    pub const fn required_memory() -> usize {
+        Self::memory_limits().required_bytes()
+    }
+",
+    );
+    code.push_str(
+        "}
+
+",
+    );
+    code
+}
+
+/// Rust `u32` expression for a segment's start: the constant `offset`, or
+/// `offset` added to the imported base global, read from `host`.
+fn segment_start(info: &ModuleInfo, base: Option<ImportedGlobalIdx>, offset: u64) -> String {
+    match base.and_then(|idx| info.imported_globals.get(idx.as_usize())) {
+        Some(g) => format!("(host.get_{}() as u32).wrapping_add({offset})", g.name),
+        None => offset.to_string(),
+    }
+}
+
+/// Generate one `init_data` call per data segment of `memory_receiver`,
+/// then one `protect` call per write-protected segment, so that a later
+/// segment may still overwrite a protected one.
+fn emit_data_segments(info: &ModuleInfo, memory_receiver: &str) -> String {
+    let mut code = String::new();
+    let mut protect = String::new();
+    for (idx, seg) in info.data_segments.iter().enumerate() {
+        if !seg.resident {
+            continue;
+        }
+        let start = match seg.base {
+            Some(_) => format!(
+                "{} as usize",
+                segment_start(info, seg.base, seg.offset.into())
+            ),
+            None => seg.offset.to_string(),
+        };
+        if seg.protected {
+            protect.push_str(&format!(
+                "    {memory_receiver}.protect({start}, {})?;\n",
+                seg.data.len()
+            ));
+        }
+        if seg.read_only {
+            code.push_str(&format!(
+                "    {memory_receiver}.init_data({start}, &RODATA_{idx})?;\n"
+            ));
+            continue;
+        }
+        code.push_str(&format!("    {memory_receiver}.init_data({start}, &["));
+        crate::codegen::utils::write_byte_list(&mut code, &seg.data);
+        code.push_str("])?;\n");
+    }
+    code.push_str(&protect);
+    code
+}
+
+/// Element segments with more entries than this are emitted as `static`
+/// tables (see [`emit_element_statics`]) instead of slice literals.
+pub const LARGE_ELEMENT_SEGMENT: usize = 64;
+
 /// Generate element segment initialization code for a table.
 ///
 /// Element segments are declared in the Wasm binary's `element` section. Each
 /// segment specifies a base offset into the table and a list of function
 /// references to write into consecutive slots starting at that offset. This
 /// function emits one `table.init_elements(...)` call per segment, which is
-/// bounds-checked inside the runtime and propagates errors via `?`.
+/// bounds-checked inside the runtime and propagates errors via `?`. Segments
+/// holding imported functions use `table.init_refs(...)` instead, whose
+/// entries say which index space each function belongs to. Large segments
+/// pass their `ELEMENTS_{i}` static rather than a literal.
 pub fn emit_element_segments(info: &ModuleInfo, table_receiver: &str) -> Result<String> {
     let mut code = String::new();
 
-    for seg in &info.element_segments {
+    for (idx, seg) in info.element_segments.iter().enumerate() {
         if seg.func_indices.is_empty() {
             continue;
         }
 
-        // Build &[(type_index, func_index), ...] literal for init_elements.
-        // All indices are already in the local index space (imports subtracted).
-        let mut pairs: Vec<String> = Vec::new();
-        for local_func_idx in &seg.func_indices {
-            let type_idx = info
-                .ir_function(*local_func_idx)
-                .map(|f| f.type_idx.as_usize())
-                .ok_or(anyhow::anyhow!("Invalid function index"))?;
-            pairs.push(format!("({}, {})", type_idx, local_func_idx.as_usize()));
-        }
-
+        let (has_imports, entries) = element_entries(info, seg)?;
+        let entries = if entries.len() > LARGE_ELEMENT_SEGMENT {
+            format!("ELEMENTS_{idx}")
+        } else {
+            format!("[{}]", entries.join(", "))
+        };
         code.push_str(&format!(
-            "    {}.init_elements({}, &[{}])?;\n",
+            "    {}.{}({}, &{})?;\n",
             table_receiver,
-            seg.offset,
-            pairs.join(", ")
+            if has_imports {
+                "init_refs"
+            } else {
+                "init_elements"
+            },
+            segment_start(info, seg.base, seg.offset as u64),
+            entries
+        ));
+    }
+    Ok(code)
+}
+
+/// Generate a `static ELEMENTS_{i}` table for each element segment above
+/// [`LARGE_ELEMENT_SEGMENT`] entries.
+///
+/// Thousands of entries written inline into `new` (or the generic
+/// `init_table`) make rustc type-check and lower the literal inside the
+/// function, once per instantiation; a static is compiled once and the
+/// runtime copies it into the table in a loop.
+pub fn emit_element_statics(info: &ModuleInfo) -> Result<String> {
+    let mut code = String::new();
+    for (idx, seg) in info.element_segments.iter().enumerate() {
+        if seg.func_indices.len() <= LARGE_ELEMENT_SEGMENT {
+            continue;
+        }
+        let (has_imports, entries) = element_entries(info, seg)?;
+        let ty = if has_imports { "FuncRef" } else { "(u32, u32)" };
+        code.push_str(&format!(
+            "/// Element segment {idx}: {} table entries.\nstatic ELEMENTS_{idx}: [{ty}; {}] = [\n",
+            entries.len(),
+            entries.len()
         ));
+        for line in entries.chunks(8) {
+            code.push_str(&format!("    {},\n", line.join(", ")));
+        }
+        code.push_str("];\n\n");
     }
     Ok(code)
 }
 
+/// Entries of `seg`: `(type_index, func_index)` pairs for `init_elements`,
+/// or `FuncRef::local(..)` / `FuncRef::import(..)` for `init_refs` when the
+/// segment holds imported functions (the returned flag).
+fn element_entries(info: &ModuleInfo, seg: &ElementSegmentDef) -> Result<(bool, Vec<String>)> {
+    let has_imports = seg
+        .func_indices
+        .iter()
+        .any(|f| matches!(f, ElementFunc::Import { .. }));
+
+    let mut entries: Vec<String> = Vec::new();
+    for func in &seg.func_indices {
+        let entry = match func {
+            ElementFunc::Local(local_func_idx) => {
+                let type_idx = info
+                    .ir_function(*local_func_idx)
+                    .map(|f| f.type_idx.as_usize())
+                    .ok_or(anyhow::anyhow!("Invalid function index"))?;
+                if has_imports {
+                    format!(
+                        "FuncRef::local({}, {})",
+                        type_idx,
+                        local_func_idx.as_usize()
+                    )
+                } else {
+                    format!("({}, {})", type_idx, local_func_idx.as_usize())
+                }
+            }
+            ElementFunc::Import { import, type_idx } => format!(
+                "FuncRef::import({}, {})",
+                type_idx.as_usize(),
+                import.as_usize()
+            ),
+        };
+        entries.push(entry);
+    }
+    Ok((has_imports, entries))
+}
+
+/// Generate `new_table` and `init_table` for a module that imports its table.
+///
+/// The host owns an imported table and passes it to every export, so the
+/// module's element segments cannot be applied in `new()`. `init_table`
+/// writes them into a host table; `new_table` creates one of the imported
+/// size with them applied. Both take the host when segment offsets are
+/// relative to an imported global (dylink's `__table_base`).
+fn generate_table_init(info: &ModuleInfo) -> Result<String> {
+    let reads_host = info.element_segments.iter().any(|s| s.base.is_some());
+    let (host_generic, host_param, host_arg) = if reads_host {
+        (", H: ModuleHostTrait + ?Sized", ", host: &H", ", host")
+    } else {
+        ("", "", "")
+    };
+    let mut code = String::new();
+    code.push_str(&format!(
+        "/// Create a table of the imported size ({} entries) holding this module's element segments.\n",
+        info.table_initial
+    ));
+    code.push_str(&format!(
+        "pub fn new_table<const TP: usize{host_generic}>({}) -> WasmResult<Table<TP>> {{\n",
+        host_param.trim_start_matches(", ")
+    ));
+    code.push_str(&format!(
+        "    let mut table = Table::try_new({})?;\n",
+        info.table_initial
+    ));
+    code.push_str(&format!("    init_table(&mut table{host_arg})?;\n"));
+    code.push_str("    Ok(table)\n");
+    code.push_str("}\n\n");
+    code.push_str("/// Write this module's element segments into an imported table.\n");
+    let table_param = if info
+        .element_segments
+        .iter()
+        .any(|s| !s.func_indices.is_empty())
+    {
+        "table"
+    } else {
+        "_table"
+    };
+    code.push_str(&format!(
+        "pub fn init_table<const TP: usize{host_generic}>({table_param}: &mut Table<TP>{host_param}) -> WasmResult<()> {{\n"
+    ));
+    code.push_str(&emit_element_segments(info, "table")?);
+    code.push_str("    Ok(())\n");
+    code.push_str("}\n\n");
+    Ok(code)
+}
+
+/// Generate `INSTANTIATION_COST`, the bytes and table entries `new` copies,
+/// and `new_limited`, which checks it against the host's limits and then
+/// calls `new`, passing the host along if `new` takes it. Data copied by `init_memory` and entries
+/// written by `init_table` are not counted: the host calls those itself.
+fn emit_new_limited(info: &ModuleInfo, return_type: &str) -> String {
+    let data_bytes: usize = if info.has_memory {
+        info.data_segments
+            .iter()
+            .filter(|s| s.resident)
+            .map(|s| s.data.len())
+            .sum()
+    } else {
+        0
+    };
+    let table_entries: usize = if info.owns_table() {
+        info.element_segments
+            .iter()
+            .map(|s| s.func_indices.len())
+            .sum()
+    } else {
+        0
+    };
+    let (generics, host_param, new_args) = match new_host_param(info) {
+        Some((generics, param)) => (generics, format!("{param}, "), "(host)"),
+        None => ("", String::new(), "()"),
+    };
+    format!(
+        "
+/// Bytes the data segments copy and table entries the element segments
+/// write when `new` instantiates the module.
+pub const INSTANTIATION_COST: InstantiationCost = InstantiationCost {{ data_bytes: {data_bytes}, table_entries: {table_entries} }};
+
+/// `new`, unless instantiating would exceed `limits`.
+pub fn new_limited{generics}({host_param}limits: &InstantiationLimits) -> {return_type} {{
+    limits.check(&INSTANTIATION_COST)?;
+    new{new_args}
+}}
+"
+    )
+}
+
+/// Generate `init_memory` for a module that imports its memory.
+///
+/// An imported memory belongs to the host, so the module's data segments
+/// are written by `init_memory` rather than `new()`. It takes the host when
+/// segment offsets are relative to an imported global (dylink's
+/// `__memory_base`).
+fn generate_memory_init(info: &ModuleInfo) -> String {
+    let (host_generic, host_param) = if info.data_segments.iter().any(|s| s.base.is_some()) {
+        (", H: ModuleHostTrait + ?Sized", ", host: &H")
+    } else {
+        ("", "")
+    };
+    let mut code = String::new();
+    code.push_str("/// Write this module's data segments into an imported memory.\n");
+    code.push_str(&format!(
+        "pub fn init_memory<const MP: usize{host_generic}>(memory: &mut IsolatedMemory<MP>{host_param}) -> WasmResult<()> {{\n"
+    ));
+    code.push_str(&emit_data_segments(info, "memory"));
+    code.push_str("    Ok(())\n");
+    code.push_str("}\n\n");
+    code
+}
+
+/// Generics and host parameter of `new`, if it takes the host: mutably to
+/// call a start function that reaches it, shared to read imported globals.
+pub fn new_host_param(info: &ModuleInfo) -> Option<(&'static str, &'static str)> {
+    if info.start_uses_host() {
+        Some(("<H: ModuleHostTrait>", "host: &mut H"))
+    } else if info.init_reads_host() {
+        Some(("<H: ModuleHostTrait + ?Sized>", "host: &H"))
+    } else {
+        None
+    }
+}
+
+/// Statements returning `module`, a `WasmModule` expression, from `new`
+/// after running the start function (see [`ModuleInfo::start`]) on it.
+fn finish_new(info: &ModuleInfo, module: &str) -> String {
+    let Some(start) = info.start else {
+        return format!("    Ok({module})\n");
+    };
+    let start = info
+        .merged_into
+        .get(start.as_usize())
+        .copied()
+        .flatten()
+        .unwrap_or(start);
+    let call = crate::codegen::export::forward_call(info, start.as_usize(), "module");
+    let mut code = format!("    let mut module = {module};\n");
+    code.push_str("    // The start function\n");
+    code.push_str("    {\n");
+    for line in call.lines() {
+        if line.trim_start().starts_with("let ") {
+            code.push_str(&format!("{line}\n"));
+        } else {
+            code.push_str(&format!("{line}?;\n"));
+        }
+    }
+    code.push_str("    }\n");
+    code.push_str("    Ok(module)\n");
+    code
+}
+
 /// Generate the `pub fn new() -> WasmModule` or `pub fn new() -> WasmResult<WasmModule>` constructor.
+///
+/// Globals initialized from imported globals (dylink's `__memory_base`
+/// pattern) are read from the host, which `new` then takes as a parameter.
 pub fn generate_constructor<B: Backend>(
     _backend: &B,
     info: &ModuleInfo,
-    has_mut_globals: bool,
+    has_global_fields: bool,
 ) -> Result<String> {
     let mut code = String::new();
+    // Element segments of an imported table are applied by `init_table`.
+    let init_elements = info.owns_table() && !info.element_segments.is_empty();
+    if info.has_table_import {
+        code.push_str(&generate_table_init(info)?);
+    }
+    if info.has_memory_import && !info.data_segments.is_empty() {
+        code.push_str(&generate_memory_init(info));
+    }
 
     // Simple constructor for modules with no initialization
     if !info.has_memory
-        && !has_mut_globals
+        && !has_global_fields
+        && !info.heap_profile
+        && !info.coverage
+        && !info.bounds_profile
         && info.data_segments.is_empty()
-        && info.element_segments.is_empty()
+        && !init_elements
+        && info.start.is_none()
     {
         code.push_str("pub fn new() -> Result<WasmModule, ConstructionError> {\n");
         code.push_str("    Ok(WasmModule(LibraryModule::new(Globals {}, Table::try_new(0)?)))\n");
         code.push_str("}\n");
+        code.push_str(&emit_new_limited(
+            info,
+            "Result<WasmModule, ConstructionError>",
+        ));
         return Ok(code);
     }
 
-    code.push_str("pub fn new() -> WasmResult<WasmModule> {\n");
+    match new_host_param(info) {
+        Some((generics, param)) => code.push_str(&format!(
+            "pub fn new{generics}({param}) -> WasmResult<WasmModule> {{\n"
+        )),
+        None => code.push_str("pub fn new() -> WasmResult<WasmModule> {\n"),
+    }
 
-    // Build globals initializer (always generates a Globals struct, empty if no mutable globals)
-    let globals_init = if has_mut_globals {
-        let mut fields = String::from("Globals { ");
-        let mut first = true;
-        for (idx, g) in info.globals.iter().enumerate() {
-            if g.mutable {
-                if !first {
-                    fields.push_str(", ");
-                }
-                let (_, value_str) = crate::codegen::types::global_init_to_rust(&g.init_value);
-                fields.push_str(&format!("g{idx}: {value_str}"));
-                first = false;
-            }
-        }
-        fields.push_str(" }");
-        fields
-    } else {
+    // Build globals initializer (always generates a Globals struct, empty if
+    // every global is a const item)
+    let mut fields: Vec<String> = info
+        .globals
+        .iter()
+        .filter(|g| !g.is_const())
+        .map(|g| {
+            let (_, value_str) = crate::codegen::types::global_init_to_rust(&g.init_value, info);
+            format!("{}: {value_str}", g.field)
+        })
+        .collect();
+    if info.heap_profile {
+        fields.push(format!(
+            "{}: HeapProfile::new()",
+            crate::codegen::heap_profile::FIELD
+        ));
+    }
+    if info.coverage {
+        fields.push(format!(
+            "{}: [0; {}]",
+            crate::codegen::coverage::FIELD,
+            crate::codegen::coverage::coverage_sites(info).len()
+        ));
+    }
+    if info.bounds_profile {
+        fields.push(format!(
+            "{}: [0; {}]",
+            crate::codegen::bounds_profile::FIELD,
+            crate::codegen::bounds_profile::bounds_sites(info).len()
+        ));
+    }
+    let globals_init = if fields.is_empty() {
         "Globals {}".to_string()
+    } else {
+        format!("Globals {{ {} }}", fields.join(", "))
     };
 
     // Table initialization
-    let table_init = if info.has_table() {
+    let table_init = if info.owns_table() {
         format!("Table::try_new({})?", info.table_initial)
     } else {
         "Table::try_new(0)?".to_string()
     };
 
     if info.has_memory {
-        let needs_mut = !info.data_segments.is_empty() || !info.element_segments.is_empty();
+        let needs_mut = info.data_segments.iter().any(|s| s.resident) || init_elements;
         // Always use Globals type (may be empty struct)
         let globals_type = "Globals";
-        let table_size_str = if info.has_table() { "TABLE_MAX" } else { "0" };
+        let table_size_str = if info.owns_table() { "TABLE_MAX" } else { "0" };
         // Use try_init (in-place initialisation) instead of try_new to avoid
         // materialising a large Result<Module<…>, E> on the call stack. In
         // debug builds without NRVO, try_new would stack-allocate several
@@ -148,34 +612,30 @@ pub fn generate_constructor<B: Backend>(
         ));
 
         // Data segment initialization — one bulk call per segment
-        for seg in &info.data_segments {
-            let bytes: Vec<String> = seg.data.iter().map(|b| format!("{}u8", b)).collect();
-            code.push_str(&format!(
-                "    module.memory.init_data({}, &[{}])?;\n",
-                seg.offset,
-                bytes.join(", ")
-            ));
-        }
+        code.push_str(&emit_data_segments(info, "module.memory"));
 
         // Element segment initialization
-        code.push_str(&emit_element_segments(info, "module.table")?);
+        if init_elements {
+            code.push_str(&emit_element_segments(info, "module.table")?);
+        }
 
-        code.push_str("    Ok(WasmModule(module))\n");
-    } else if !info.element_segments.is_empty() {
+        code.push_str(&finish_new(info, "WasmModule(module)"));
+    } else if init_elements {
         // Need mutable table for element initialization
         code.push_str(&format!("    let mut table = {};\n", table_init));
         code.push_str(&emit_element_segments(info, "table")?);
-        code.push_str(&format!(
-            "    Ok(WasmModule(LibraryModule::new({}, table)))\n",
-            globals_init
+        code.push_str(&finish_new(
+            info,
+            &format!("WasmModule(LibraryModule::new({globals_init}, table))"),
         ));
     } else {
-        code.push_str(&format!(
-            "    Ok(WasmModule(LibraryModule::new({}, {})))\n",
-            globals_init, table_init
+        code.push_str(&finish_new(
+            info,
+            &format!("WasmModule(LibraryModule::new({globals_init}, {table_init}))"),
         ));
     }
 
     code.push_str("}\n");
+    code.push_str(&emit_new_limited(info, "WasmResult<WasmModule>"));
     Ok(code)
 }
@@ -0,0 +1,123 @@
+//! Documentation and debug assertions for export contracts (see
+//! [`crate::contracts`]).
+//!
+//! ```text
+//! /// # Contract
+//! ///
+//! /// - argument 0 in `0..=100`
+//! pub fn set_volume(&mut self, v0: i32) -> WasmResult<()> {
+//!     debug_assert!(v0 >= 0, "`set_volume`: argument 0 must be in 0..=100");
+//!     debug_assert!(v0 <= 100, "`set_volume`: argument 0 must be in 0..=100");
+//!     ...
+//! ```
+
+use crate::ir::*;
+
+/// Doc section listing the contracts of `export`, for a method indented
+/// by `indent`. Empty without contracts.
+pub fn contract_docs(indent: &str, info: &ModuleInfo, export: &str) -> String {
+    let Some(contracts) = info.contracts.get(export) else {
+        return String::new();
+    };
+    let mut docs = format!("{indent}/// # Contract\n{indent}///\n");
+    for contract in contracts {
+        let condition = match contract.kind {
+            ContractKind::Range { min, max } => format!("in `{min}..={max}`"),
+            ContractKind::NonNull => "not null".to_string(),
+        };
+        docs.push_str(&format!(
+            "{indent}/// - argument {} {condition}\n",
+            contract.param
+        ));
+    }
+    docs
+}
+
+/// `debug_assert!`s checking the contracts of `export` on arguments `v0`,
+/// `v1`, ... of `func`, in a body indented by `indent`. Bounds at the
+/// extremes of the parameter type hold anyway and are left out.
+pub fn contract_asserts(
+    indent: &str,
+    info: &ModuleInfo,
+    export: &str,
+    func: &IrFunction,
+) -> String {
+    let Some(contracts) = info.contracts.get(export) else {
+        return String::new();
+    };
+    let func_idx = info
+        .func_exports
+        .iter()
+        .find(|e| e.name == export)
+        .map(|e| e.func_index.as_usize());
+    let mut code = String::new();
+    for contract in contracts {
+        let arg = match func_idx {
+            Some(idx) => info.param_name(idx, contract.param),
+            None => format!("v{}", contract.param),
+        };
+        let (checks, condition) = match contract.kind {
+            ContractKind::Range { min, max } => {
+                let (lowest, highest) = match func.params.get(contract.param) {
+                    Some((_, WasmType::I32)) => (i32::MIN as i64, i32::MAX as i64),
+                    _ => (i64::MIN, i64::MAX),
+                };
+                let mut checks = Vec::new();
+                if min > lowest {
+                    checks.push(format!("{arg} >= {min}"));
+                }
+                if max < highest {
+                    checks.push(format!("{arg} <= {max}"));
+                }
+                (checks, format!("be in {min}..={max}"))
+            }
+            ContractKind::NonNull => (vec![format!("{arg} != 0")], "not be null".to_string()),
+        };
+        let message = format!("`{export}`: argument {} must {condition}", contract.param)
+            .replace('{', "{{")
+            .replace('}', "}}");
+        for check in checks {
+            code.push_str(&format!("{indent}debug_assert!({check}, {message:?});\n"));
+        }
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contracts::ContractConfig;
+    use crate::{transpile, TranspileOptions};
+
+    #[test]
+    fn exports_document_and_check_their_contracts() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory 1)
+                (func (export "read") (param i32 i32) (result i32) (local.get 1))
+                (func (export "wide") (param i64) (result i64) (local.get 0)))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            contracts: ContractConfig::parse(
+                "require read 0 nonnull\n\
+                 require read 1 range 1..=2147483647\n\
+                 require wide 0 range -9223372036854775808..=-1\n",
+            )
+            .unwrap(),
+            ..TranspileOptions::default()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.contains(
+            "    /// # Contract\n    ///\n    /// - argument 0 not null\n    /// - argument 1 in `1..=2147483647`\n    #[must_use = \"the function has no side effects\"]\n    pub fn read("
+        ));
+        assert!(code.contains(
+            "        debug_assert!(v0 != 0, \"`read`: argument 0 must not be null\");\n"
+        ));
+        assert!(code.contains(
+            "        debug_assert!(v1 >= 1, \"`read`: argument 1 must be in 1..=2147483647\");\n"
+        ));
+        assert!(!code.contains("v1 <= 2147483647"));
+        assert!(code.contains("debug_assert!(v0 <= -1, "));
+        assert!(!code.contains("v0 >= -9223372036854775808"));
+    }
+}
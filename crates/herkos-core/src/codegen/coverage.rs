@@ -0,0 +1,194 @@
+//! Block coverage instrumentation (`--coverage`).
+//!
+//! Every block the IR builder saw start at a Wasm operator gets a counter
+//! in `Globals`, bumped when the block runs. `COVERAGE_POINTS` maps each
+//! counter back to its function and byte offset in the original binary,
+//! and `coverage_report` writes an LCOV tracefile keyed by those offsets
+//! through `herkos_runtime::write_lcov`:
+//!
+//! ```text
+//! pub const COVERAGE_POINTS: &[CoveragePoint] = &[
+//!     CoveragePoint { func_index: 0, wasm_offset: 43 },
+//!     CoveragePoint { func_index: 0, wasm_offset: 51 },
+//! ];
+//!
+//! fn func_0<H: ModuleHostTrait>(..) -> WasmResult<i32> {
+//!     ..
+//!             Block::B1 => {
+//!                 env.globals.coverage[1] += 1;
+//! ```
+//!
+//! Blocks the optimizer merges into their predecessor are counted with it.
+
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use anyhow::{bail, Result};
+
+/// `Globals` field holding the counters.
+pub const FIELD: &str = "coverage";
+
+/// Methods added to `WasmModule`.
+const METHODS: [&str; 3] = ["coverage_hits", "reset_coverage", "coverage_report"];
+
+/// A counted block, in counter order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageSite {
+    /// Local function index.
+    pub func: usize,
+    /// The block, in the function's final IR.
+    pub block: BlockId,
+    /// Byte offset in the Wasm binary of the operator the block starts at.
+    pub offset: usize,
+}
+
+/// Check that `info` can be instrumented for coverage.
+///
+/// # Errors
+/// Fails when the counters' field or the coverage methods collide with a
+/// global or an export.
+pub fn check_coverage(info: &ModuleInfo) -> Result<()> {
+    if info.globals.iter().any(|g| g.field == FIELD) {
+        bail!("--coverage: a global already uses the `{FIELD}` field");
+    }
+    if let Some(export) = info
+        .func_exports
+        .iter()
+        .find(|e| METHODS.contains(&rust_ident(&e.name).as_str()))
+    {
+        bail!(
+            "--coverage: export `{}` collides with a coverage method",
+            export.name
+        );
+    }
+    Ok(())
+}
+
+/// The blocks of `info` that get a counter, by function and start order.
+///
+/// Empty unless coverage was requested.
+pub fn coverage_sites(info: &ModuleInfo) -> Vec<CoverageSite> {
+    if !info.coverage {
+        return Vec::new();
+    }
+    let mut sites = Vec::new();
+    for (func, ir_func) in info.ir_functions.iter().enumerate() {
+        if info.is_merged(func) {
+            continue;
+        }
+        let Some(symbol) = info.func_symbols.get(func) else {
+            continue;
+        };
+        for &(block, offset) in &symbol.block_offsets {
+            if ir_func.blocks.iter().any(|b| b.id == block) {
+                sites.push(CoverageSite {
+                    func,
+                    block,
+                    offset,
+                });
+            }
+        }
+    }
+    sites
+}
+
+/// The `(block, counter)` pairs of each of `num_functions` functions.
+pub fn block_counters(sites: &[CoverageSite], num_functions: usize) -> Vec<Vec<(BlockId, usize)>> {
+    let mut counters = vec![Vec::new(); num_functions];
+    for (counter, site) in sites.iter().enumerate() {
+        counters[site.func].push((site.block, counter));
+    }
+    counters
+}
+
+/// Statement bumping `counter`, at the start of its block.
+pub fn count_statement(counter: usize) -> String {
+    format!("env.globals.{FIELD}[{counter}] += 1;")
+}
+
+/// Generate `COVERAGE_POINTS` and the coverage accessors.
+///
+/// Returns an empty string unless coverage was requested.
+pub fn generate_coverage(info: &ModuleInfo, sites: &[CoverageSite]) -> String {
+    if !info.coverage {
+        return String::new();
+    }
+    let mut code =
+        String::from("/// Function and Wasm offset of each coverage counter, in counter order.\n");
+    code.push_str("pub const COVERAGE_POINTS: &[CoveragePoint] = &[\n");
+    for site in sites {
+        code.push_str(&format!(
+            "    CoveragePoint {{ func_index: {}, wasm_offset: {} }},\n",
+            site.func, site.offset
+        ));
+    }
+    code.push_str("];\n\n");
+
+    code.push_str("impl WasmModule {\n");
+    code.push_str("    /// Executions of each of [`COVERAGE_POINTS`] so far.\n");
+    code.push_str("    pub fn coverage_hits(&self) -> &[u64] {\n");
+    code.push_str(&format!("        &self.0.globals.{FIELD}\n"));
+    code.push_str("    }\n\n");
+    code.push_str("    /// Forget the executions so far.\n");
+    code.push_str("    pub fn reset_coverage(&mut self) {\n");
+    code.push_str(&format!(
+        "        self.0.globals.{FIELD} = [0; {}];\n",
+        sites.len()
+    ));
+    code.push_str("    }\n\n");
+    code.push_str("    /// Write the coverage so far as an LCOV tracefile for `source`,\n");
+    code.push_str("    /// with Wasm byte offsets as line numbers.\n");
+    code.push_str(
+        "    pub fn coverage_report<W: core::fmt::Write>(&self, source: &str, out: &mut W) -> core::fmt::Result {\n",
+    );
+    code.push_str(&format!(
+        "        write_lcov(out, source, SYMBOLS, COVERAGE_POINTS, &self.0.globals.{FIELD})\n"
+    ));
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_covered(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            coverage: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn blocks_are_counted_at_their_wasm_offsets() {
+        let code = transpile_covered(
+            r#"(module
+                (func (export "abs") (param i32) (result i32)
+                    (if (result i32) (i32.lt_s (local.get 0) (i32.const 0))
+                        (then (i32.sub (i32.const 0) (local.get 0)))
+                        (else (local.get 0)))))"#,
+        )
+        .unwrap();
+        // Entry, then, else and join blocks
+        assert_eq!(
+            code.matches("    CoveragePoint { func_index: 0,").count(),
+            4
+        );
+        assert!(code.contains("pub coverage: [u64; 4],"));
+        assert!(code.contains("coverage: [0; 4]"));
+        for counter in 0..4 {
+            assert!(code.contains(&format!("env.globals.coverage[{counter}] += 1;")));
+        }
+        assert!(code.contains("pub fn coverage_report<W: core::fmt::Write>("));
+        // Counters are state: no `pure` API
+        assert!(!code.contains("pub mod pure"));
+    }
+
+    #[test]
+    fn colliding_exports_are_rejected() {
+        let err = transpile_covered(r#"(module (func (export "reset_coverage")))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("collides with a coverage method"));
+    }
+}
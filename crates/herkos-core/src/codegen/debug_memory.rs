@@ -0,0 +1,114 @@
+//! Poisoned memory for debugging (`--debug-memory`).
+//!
+//! The module lists where its data segments land, and
+//! `arm_debug_memory` hands that list to a `herkos_runtime::DebugMemory`,
+//! which poisons the rest of the memory and places canaries after the
+//! segments. Loads and stores reach the host's `MemoryObserver` as with
+//! `--observe-memory`, which the option implies:
+//!
+//! ```text
+//! pub const DATA_SEGMENTS: &[(u32, u32)] = &[(1024, 12), (1040, 4)];
+//!
+//! impl WasmModule {
+//!     pub fn arm_debug_memory(&mut self, debug: &mut DebugMemory) {
+//!         debug.arm(&mut self.0.memory, DATA_SEGMENTS);
+//!     }
+//! }
+//! ```
+
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use anyhow::{bail, Result};
+
+/// Method added to `WasmModule`.
+const METHOD: &str = "arm_debug_memory";
+
+/// Check that `info` can run on debug memory.
+///
+/// # Errors
+/// Fails for a module without a memory of its own, whose contents are not
+/// its to poison, or with an export named like the generated method.
+pub fn check_debug_memory(info: &ModuleInfo) -> Result<()> {
+    if !info.has_memory {
+        bail!("--debug-memory needs a module that defines its own memory");
+    }
+    if let Some(export) = info
+        .func_exports
+        .iter()
+        .find(|e| rust_ident(&e.name) == METHOD)
+    {
+        bail!(
+            "--debug-memory: export `{}` collides with the `{METHOD}` method",
+            export.name
+        );
+    }
+    Ok(())
+}
+
+/// Generate `DATA_SEGMENTS` and `arm_debug_memory`.
+///
+/// Returns an empty string unless debug memory was requested.
+pub fn generate_debug_memory(info: &ModuleInfo) -> String {
+    if !info.debug_memory {
+        return String::new();
+    }
+    let segments: Vec<String> = info
+        .data_segments
+        .iter()
+        .filter(|s| s.base.is_none() && s.resident)
+        .map(|s| format!("({}, {})", s.offset, s.data.len()))
+        .collect();
+
+    let mut code = String::from("/// Where the active data segments land, as `(offset, len)`.\n");
+    code.push_str(&format!(
+        "pub const DATA_SEGMENTS: &[(u32, u32)] = &[{}];\n\n",
+        segments.join(", ")
+    ));
+    code.push_str("impl WasmModule {\n");
+    code.push_str("    /// Poison the memory outside the data segments and place canaries\n");
+    code.push_str("    /// after them. Call it before anything else runs.\n");
+    code.push_str(&format!(
+        "    pub fn {METHOD}(&mut self, debug: &mut DebugMemory) {{\n"
+    ));
+    code.push_str("        debug.arm(&mut self.0.memory, DATA_SEGMENTS);\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_debug(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            debug_memory: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn data_segments_are_listed_and_accesses_observed() {
+        let code = transpile_debug(
+            r#"(module
+                (memory 1)
+                (data (i32.const 16) "abcd")
+                (data (i32.const 64) "xy")
+                (func (export "get") (param i32) (result i32)
+                    (i32.load (local.get 0))))"#,
+        )
+        .unwrap();
+        assert!(code.contains("pub const DATA_SEGMENTS: &[(u32, u32)] = &[(16, 4), (64, 2)];"));
+        assert!(code.contains("pub fn arm_debug_memory(&mut self, debug: &mut DebugMemory) {"));
+        assert!(code.contains("pub trait ModuleHostTrait: MemoryObserver {"));
+        assert!(code.contains(".on_load("));
+    }
+
+    #[test]
+    fn borrowed_memory_is_rejected() {
+        let err = transpile_debug(r#"(module (import "env" "memory" (memory 1)))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("defines its own memory"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Table dispatch for `call_indirect`.
+//!
+//! Each local function has exactly one form per way it is reached:
+//! `func_N`, the internal form called directly; an arm of its type's
+//! dispatcher, reached through the table; and one export method (further
+//! export names of the same function forward to it, see
+//! [`crate::codegen::export`]). Every `call_indirect` of a type calls that
+//! type's dispatcher instead of repeating the lookup at each site:
+//!
+//! ```text
+//! fn dispatch_1<H: ModuleHostTrait>(index: i32, a0: i32, env: &mut Env<'_, H>, table: &Table<TABLE_MAX>) -> WasmResult<i32> {
+//!     let __entry = table.get(index as u32)?;
+//!     if __entry.type_index != 1 { return Err(WasmTrap::IndirectCallTypeMismatch); }
+//!     match __entry.func_index {
+//!         2 => func_2(a0, env, table),
+//!         4 => func_4(a0, env, table),
+//!         _ => Err(WasmTrap::UndefinedElement),
+//!     }
+//! }
+//! ```
+//!
+//! Instrumenting indirect calls thus touches one function per type rather
+//! than every call site.
+
+use crate::codegen::utils::cfg_attr;
+use crate::ir::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Canonical index of `type_idx` (Wasm spec §4.4.9: structurally equal
+/// types match).
+fn canonical(info: &ModuleInfo, type_idx: &TypeIdx) -> usize {
+    let idx = type_idx.as_usize();
+    info.canonical_type.get(idx).copied().unwrap_or(idx)
+}
+
+/// Append the call of the dispatcher for `call_indirect` of `type_idx`.
+pub fn write_dispatch_call(
+    out: &mut String,
+    dests: &[VarId],
+    type_idx: &TypeIdx,
+    table_idx: VarId,
+    args: &[VarId],
+    info: &ModuleInfo,
+) -> std::fmt::Result {
+    out.push_str("                ");
+    if let Some(dests) = crate::codegen::utils::format_vars(dests) {
+        write!(out, "{dests} = ")?;
+    }
+    write!(out, "dispatch_{}({table_idx}, ", canonical(info, type_idx))?;
+    for arg in args {
+        write!(out, "{arg}, ")?;
+    }
+    out.push_str("env");
+    if info.has_memory || info.has_memory_import {
+        out.push_str(", memory");
+    }
+    if info.has_table() {
+        out.push_str(", table");
+    }
+    out.push_str(")?;");
+    Ok(())
+}
+
+/// Generate the dispatcher of every type a function calls through the table.
+///
+/// A dispatcher is compiled whenever one of its callers is: it carries the
+/// union of their features, or none if any caller is always compiled.
+pub fn generate_dispatchers(info: &ModuleInfo) -> String {
+    // Callers of each canonical type
+    let mut callers: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (func_idx, ir_func) in info.ir_functions.iter().enumerate() {
+        if info.is_merged(func_idx) {
+            continue;
+        }
+        for instr in ir_func.blocks.iter().flat_map(|b| &b.instructions) {
+            if let IrInstr::CallIndirect { type_idx, .. } = instr {
+                callers
+                    .entry(canonical(info, type_idx))
+                    .or_default()
+                    .push(func_idx);
+            }
+        }
+    }
+
+    let mut code = String::new();
+    for (canon_idx, funcs) in callers {
+        let features: Vec<&String> = if funcs.iter().any(|&f| {
+            info.func_features
+                .get(f)
+                .is_none_or(|features| features.is_empty())
+        }) {
+            Vec::new()
+        } else {
+            funcs
+                .iter()
+                .flat_map(|&f| &info.func_features[f])
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+        code.push_str(&cfg_attr("", &features));
+        // Writing to a String cannot fail
+        let _ = write_dispatcher(&mut code, canon_idx, info);
+        code.push('\n');
+    }
+    code
+}
+
+/// Append `dispatch_{canon_idx}`: table lookup, type check, and a match on
+/// the entry's function.
+///
+/// Entries may name imports when the module's element segments place
+/// imported functions, or when the host supplies the table; such
+/// dispatchers also match on the entry's kind so that an import index is
+/// never mistaken for a local function.
+///
+/// A dylink side module shares its imported table with other modules, whose
+/// entries index their own functions. Only the module's slots, from
+/// `__table_base` on, are dispatched; other indices trap.
+fn write_dispatcher(out: &mut String, canon_idx: usize, info: &ModuleInfo) -> std::fmt::Result {
+    let Some(sig) = info.type_signatures.get(canon_idx) else {
+        return Ok(());
+    };
+    let has_memory = info.has_memory || info.has_memory_import;
+    let has_table = info.has_table();
+
+    out.push_str("#[allow(unused_variables, clippy::too_many_arguments)]\n");
+    write!(out, "fn dispatch_{canon_idx}<")?;
+    if info.has_memory_import {
+        out.push_str("const MP: usize, ");
+    }
+    if info.has_table_import {
+        out.push_str("const TP: usize, ");
+    }
+    out.push_str("H: ModuleHostTrait>(index: i32, ");
+    for (i, ty) in sig.params.iter().enumerate() {
+        write!(
+            out,
+            "a{i}: {}, ",
+            crate::codegen::types::wasm_type_to_rust(ty)
+        )?;
+    }
+    out.push_str("env: &mut Env<'_, H>");
+    if info.has_memory {
+        out.push_str(", memory: &mut IsolatedMemory<MAX_PAGES>");
+    } else if info.has_memory_import {
+        out.push_str(", memory: &mut IsolatedMemory<MP>");
+    }
+    if info.has_table_import {
+        out.push_str(", table: &Table<TP>");
+    } else if has_table {
+        out.push_str(", table: &Table<TABLE_MAX>");
+    }
+    writeln!(
+        out,
+        ") -> {} {{",
+        crate::codegen::types::format_return_type(&sig.results)
+    )?;
+    let args: Vec<String> = (0..sig.params.len()).map(|i| format!("a{i}")).collect();
+
+    if let Some(dylink) = info.dylink.as_ref().filter(|_| info.has_table_import) {
+        if let Some(base) = dylink
+            .table_base
+            .and_then(|idx| info.imported_globals.get(idx.as_usize()))
+        {
+            writeln!(
+                out,
+                "    if (index as u32).wrapping_sub(env.host.get_{}() as u32) >= {} {{ return Err(WasmTrap::UndefinedElement); }}",
+                base.name, dylink.table_size
+            )?;
+        }
+    }
+
+    out.push_str("    let __entry = table.get(index as u32)?;\n");
+    // FuncRef.type_index is stored canonical during element segment
+    // initialization
+    writeln!(
+        out,
+        "    if __entry.type_index != {canon_idx} {{ return Err(WasmTrap::IndirectCallTypeMismatch); }}"
+    )?;
+
+    let mut import_targets: Vec<(usize, &FuncImport)> = info
+        .element_segments
+        .iter()
+        .flat_map(|seg| &seg.func_indices)
+        .filter_map(|func| match func {
+            ElementFunc::Import { import, type_idx } if type_idx.as_usize() == canon_idx => info
+                .func_imports
+                .get(import.as_usize())
+                .map(|imp| (import.as_usize(), imp)),
+            _ => None,
+        })
+        .collect();
+    import_targets.sort_by_key(|(idx, _)| *idx);
+    import_targets.dedup_by_key(|(idx, _)| *idx);
+    let kinded = info.has_table_import
+        || info
+            .element_segments
+            .iter()
+            .flat_map(|seg| &seg.func_indices)
+            .any(|func| matches!(func, ElementFunc::Import { .. }));
+
+    if kinded {
+        out.push_str("    match (__entry.kind, __entry.func_index) {\n");
+    } else {
+        out.push_str("    match __entry.func_index {\n");
+    }
+    for (func_idx, ir_func) in info.ir_functions.iter().enumerate() {
+        if ir_func.type_idx.as_usize() != canon_idx || info.is_merged(func_idx) {
+            continue;
+        }
+        let func = info.func_ident(func_idx);
+        if kinded {
+            write!(out, "        (FuncKind::Local, {func_idx}) => {func}(")?;
+        } else {
+            write!(out, "        {func_idx} => {func}(")?;
+        }
+        // Arguments dead-argument elimination removed are not passed on
+        for (arg, (_, live)) in args.iter().zip(info.wasm_params(func_idx)) {
+            if live {
+                write!(out, "{arg}, ")?;
+            }
+        }
+        out.push_str("env");
+        if has_memory {
+            out.push_str(", memory");
+        }
+        if has_table {
+            out.push_str(", table");
+        }
+        out.push_str("),\n");
+    }
+    for (import_idx, import) in import_targets {
+        let call = super::import_checks::checked_call(info, import_idx, &args)
+            .or_else(|| super::guest_log::guest_log_call(info, import_idx, &args))
+            .unwrap_or_else(|| {
+                format!(
+                    "env.host.{}({})",
+                    super::utils::rust_ident(&import.func_name),
+                    args.join(", ")
+                )
+            });
+        writeln!(out, "        (FuncKind::Import, {import_idx}) => {call},")?;
+    }
+    out.push_str("        _ => Err(WasmTrap::UndefinedElement),\n");
+    out.push_str("    }\n}\n");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    #[test]
+    fn call_sites_of_a_type_share_one_dispatcher() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (type $t (func (param i32) (result i32)))
+                (table 2 funcref)
+                (elem (i32.const 0) $double $inc)
+                (func $double (type $t) (i32.mul (local.get 0) (i32.const 2)))
+                (func $inc (type $t) (i32.add (local.get 0) (i32.const 1)))
+                (func (export "twice") (param i32 i32) (result i32)
+                  (call_indirect (type $t)
+                    (call_indirect (type $t) (local.get 1) (local.get 0))
+                    (local.get 0))))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert_eq!(code.matches("fn dispatch_0<").count(), 1);
+        assert_eq!(code.matches("= dispatch_0(").count(), 2);
+        assert_eq!(code.matches("table.get(").count(), 1);
+        assert!(code.contains("        0 => func_double(a0, env, table),\n"));
+        assert!(code.contains("        1 => func_inc(a0, env, table),\n"));
+    }
+
+    #[test]
+    fn dead_arguments_are_not_dispatched() {
+        // `$keep` is not in the table, but the host could place it there
+        let wasm = wat::parse_str(
+            r#"(module
+                (type $t (func (param i32 i32)))
+                (global $g (mut i32) (i32.const 0))
+                (table 1 funcref)
+                (func $keep (type $t) (global.set $g (local.get 0)))
+                (func (export "run") (param i32)
+                  (call $keep (local.get 0) (i32.const 1))
+                  (call_indirect (type $t) (local.get 0) (i32.const 2) (local.get 0))))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            optimize: true,
+            ..TranspileOptions::default()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.contains("fn func_keep<H: ModuleHostTrait>(mut v0: i32, env"));
+        assert!(code.contains("        0 => func_keep(a0, env, table),\n"));
+    }
+}
@@ -3,6 +3,10 @@
 //! Generates the uniform `Env<H>` context struct that bundles host + globals,
 //! along with the `ModuleHostTrait` and `Globals` struct that it contains.
 
+use crate::analysis::detect_tinygo;
+use crate::codegen::tinygo::{import_default, ImportDefault};
+use crate::codegen::utils::too_many_args_attr;
+use crate::ir::YIELD_METHOD;
 use crate::ir::*;
 
 /// Generate the environment block: ModuleHostTrait, Globals struct, and Env<H> struct.
@@ -40,23 +44,77 @@ pub fn generate_env_block(info: &ModuleInfo) -> String {
 }
 
 /// Generate the unified ModuleHostTrait from both function and global imports.
+///
+/// Imports assigned to a capability (`--capabilities`) are declared in that
+/// capability's trait instead, which becomes a supertrait.
 fn generate_module_host_trait(info: &ModuleInfo) -> String {
-    let mut code = String::from("pub trait ModuleHostTrait {\n");
-
-    // Add all function import methods
-    for imp in &info.func_imports {
-        let mut params = vec!["&mut self".to_string()];
-        for (i, ty) in imp.params.iter().enumerate() {
-            let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
-            params.push(format!("arg{}: {}", i, rust_ty));
+    let mut code = String::new();
+
+    // Capability traits, in order of their first import
+    let mut capabilities: Vec<&str> = Vec::new();
+    for capability in info.import_capabilities.values() {
+        if !capabilities.contains(&capability.as_str()) {
+            capabilities.push(capability);
+        }
+    }
+    for capability in &capabilities {
+        code.push_str(&format!(
+            "/// Host capability `{capability}`: the imports grouped under it.\n"
+        ));
+        code.push_str(&format!("pub trait {capability} {{\n"));
+        for (idx, imp) in info.func_imports.iter().enumerate() {
+            if info.import_capabilities.get(&idx).map(String::as_str) == Some(*capability) {
+                code.push_str(&import_method(imp, None));
+            }
+        }
+        code.push_str("}\n\n");
+    }
+
+    // `--observe-memory`: loads and stores report to the host
+    let mut supertraits: Vec<&str> = Vec::new();
+    if info.observe_memory {
+        supertraits.push("MemoryObserver");
+    }
+    // `--guest-log`: logging imports reach the host as text
+    if !info.guest_log.is_empty() {
+        supertraits.push("GuestLog");
+    }
+    supertraits.extend(&capabilities);
+    if supertraits.is_empty() {
+        code.push_str("pub trait ModuleHostTrait {\n");
+    } else {
+        code.push_str(&format!(
+            "pub trait ModuleHostTrait: {} {{\n",
+            supertraits.join(" + ")
+        ));
+    }
+
+    // TinyGo guests get defaults for their runtime imports, backed by a clock
+    // the host provides.
+    let tinygo = detect_tinygo(info);
+    if tinygo.as_ref().is_some_and(|t| t.uses_clock) {
+        code.push_str(
+            "    /// Scheduler clock behind the default `runtime.ticks` / `runtime.sleepTicks`.\n",
+        );
+        code.push_str("    fn tinygo_clock(&mut self) -> &mut TinyGoClock;\n");
+    }
+
+    // Add all remaining function import methods
+    for (idx, imp) in info.func_imports.iter().enumerate() {
+        if info.import_capabilities.contains_key(&idx) || info.guest_log.contains_key(&idx) {
+            continue;
         }
+        let default = tinygo.as_ref().and_then(|_| import_default(imp));
+        code.push_str(&import_method(imp, default));
+    }
 
-        let return_ty = crate::codegen::types::format_return_type(imp.return_type.as_ref());
+    // Cooperative yield point at loop headers (`--yield-points`)
+    if info.yield_points {
+        code.push_str(
+            "    /// Called at every loop header. Return `Err` to abort the running call.\n",
+        );
         code.push_str(&format!(
-            "    fn {}({}) -> {};\n",
-            imp.func_name,
-            params.join(", "),
-            return_ty
+            "    fn {YIELD_METHOD}(&mut self) -> WasmResult<()> {{\n        Ok(())\n    }}\n"
         ));
     }
 
@@ -65,10 +123,19 @@ fn generate_module_host_trait(info: &ModuleInfo) -> String {
         let rust_ty = crate::codegen::types::wasm_type_to_rust(&g.wasm_type);
 
         // Getter (always)
-        code.push_str(&format!("    fn get_{}(&self) -> {};\n", g.name, rust_ty));
+        let allow = if is_snake_case(&format!("get_{}", g.name)) {
+            ""
+        } else {
+            "    #[allow(non_snake_case)]\n"
+        };
+        code.push_str(&format!(
+            "{allow}    fn get_{}(&self) -> {};\n",
+            g.name, rust_ty
+        ));
 
         // Setter (only if mutable)
         if g.mutable {
+            code.push_str(allow);
             code.push_str(&format!(
                 "    fn set_{}(&mut self, val: {});\n",
                 g.name, rust_ty
@@ -80,16 +147,85 @@ fn generate_module_host_trait(info: &ModuleInfo) -> String {
     code
 }
 
-/// Generate the Globals struct containing all mutable globals.
+/// Trait method for a function import, with its default body if it has one.
+fn import_method(imp: &FuncImport, default: Option<ImportDefault>) -> String {
+    let mut code = String::new();
+    let arg_prefix = match &default {
+        Some(d) if !d.uses_args => "_arg",
+        _ => "arg",
+    };
+    let mut params = vec!["&mut self".to_string()];
+    for (i, ty) in imp.params.iter().enumerate() {
+        let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
+        params.push(format!("{}{}: {}", arg_prefix, i, rust_ty));
+    }
+
+    let method = crate::codegen::utils::rust_ident(&imp.func_name);
+    let return_ty = crate::codegen::types::format_return_type(&imp.results);
+    let signature = format!("fn {}({}) -> {}", method, params.join(", "), return_ty);
+    if let Some(default) = &default {
+        code.push_str(&format!("    /// {}\n", default.doc));
+    }
+    if !is_snake_case(&method) {
+        code.push_str("    #[allow(non_snake_case)]\n");
+    }
+    code.push_str(&too_many_args_attr("    ", params.len()));
+    match default {
+        Some(default) => {
+            code.push_str(&format!("    {signature} {{\n"));
+            for stmt in &default.body {
+                code.push_str(&format!("        {stmt}\n"));
+            }
+            code.push_str("    }\n");
+        }
+        None => code.push_str(&format!("    {signature};\n")),
+    }
+    code
+}
+
+/// Whether rustc's `non_snake_case` lint accepts identifier `name`: no
+/// uppercase letters and no `__` after leading underscores (as in
+/// `get___memory_base`).
+fn is_snake_case(name: &str) -> bool {
+    !name.chars().any(|c| c.is_ascii_uppercase()) && !name.trim_start_matches('_').contains("__")
+}
+
+/// Generate the Globals struct containing all globals that are not const
+/// items (see [`GlobalDef::is_const`]).
 fn generate_globals_struct(info: &ModuleInfo) -> String {
     let mut code = String::from("pub struct Globals {\n");
 
-    for (idx, g) in info.globals.iter().enumerate() {
-        if g.mutable {
+    for g in &info.globals {
+        if !g.is_const() {
             let rust_ty = crate::codegen::types::wasm_type_to_rust(&g.init_value.ty());
-            code.push_str(&format!("    pub g{}: {},\n", idx, rust_ty));
+            code.push_str(&format!("    pub {}: {},\n", g.field, rust_ty));
         }
     }
+    // `--heap-profile`: blocks handed out by the guest allocator
+    if info.heap_profile {
+        code.push_str(&format!(
+            "    pub {}: HeapProfile,\n",
+            crate::codegen::heap_profile::FIELD
+        ));
+    }
+    // `--coverage`: executions of each counted block
+    if info.coverage {
+        let sites = crate::codegen::coverage::coverage_sites(info);
+        code.push_str(&format!(
+            "    pub {}: [u64; {}],\n",
+            crate::codegen::coverage::FIELD,
+            sites.len()
+        ));
+    }
+    // `--bounds-profile`: bounds checks of each counted access
+    if info.bounds_profile {
+        let sites = crate::codegen::bounds_profile::bounds_sites(info);
+        code.push_str(&format!(
+            "    pub {}: [u64; {}],\n",
+            crate::codegen::bounds_profile::FIELD,
+            sites.len()
+        ));
+    }
 
     code.push_str("}\n");
     code
@@ -2,110 +2,238 @@
 //!
 //! Generates the `impl WasmModule { ... }` block with methods for all functions.
 //! Exported functions are thin wrappers that construct an Env<H> and forward to internal functions.
+//! A function exported under several names gets one such wrapper; the other
+//! names call it.
 
 use crate::backend::Backend;
+use crate::codegen::contracts::{contract_asserts, contract_docs};
+use crate::codegen::table_introspection::generate_table_methods;
+use crate::codegen::utils::{
+    export_cfg_attr, func_cfg_attr, must_use_attr, too_many_args_attr, ForwardedState,
+};
 use crate::ir::*;
 
+/// Statements building an `Env` from `receiver` (a `WasmModule`) and
+/// calling local function `func_idx` with the Wasm arguments in scope, as
+/// the tail of a method body.
+///
+/// The host, an imported memory and an imported table are passed on from
+/// the `host`, `memory` and `table` variables.
+pub fn forward_call(info: &ModuleInfo, func_idx: usize, receiver: &str) -> String {
+    let mut code = String::new();
+    if info.uses_host() {
+        code.push_str(&format!(
+            "        let mut env = Env {{ host, globals: &mut {receiver}.0.globals }};\n"
+        ));
+    } else {
+        code.push_str("        let mut __host = herkos_runtime::NoHost;\n");
+        code.push_str(&format!(
+            "        let mut env = Env {{ host: &mut __host, globals: &mut {receiver}.0.globals }};\n"
+        ));
+    }
+
+    // Wasm params the function still takes + env + memory (if owned) + table
+    let mut call_args: Vec<String> = Vec::new();
+    for (i, (_, live)) in info.wasm_params(func_idx).into_iter().enumerate() {
+        if live {
+            call_args.push(info.param_name(func_idx, i));
+        } else {
+            code.push_str(&format!(
+                "        let _ = {};\n",
+                info.param_name(func_idx, i)
+            ));
+        }
+    }
+    call_args.push("&mut env".to_string());
+    if info.has_memory {
+        call_args.push(format!("&mut {receiver}.0.memory"));
+    } else if info.has_memory_import {
+        call_args.push("memory".to_string());
+    }
+    if info.has_table_import {
+        call_args.push("table".to_string());
+    } else if info.has_table() {
+        call_args.push(format!("&{receiver}.0.table"));
+    }
+    code.push_str(&format!(
+        "        {}({})\n",
+        info.func_ident(func_idx),
+        call_args.join(", ")
+    ));
+    code
+}
+
 /// Generate the `impl WasmModule { ... }` block with accessor methods for all functions.
 pub fn generate_export_impl<B: Backend>(_backend: &B, info: &ModuleInfo) -> String {
     let mut code = String::new();
-    let has_imports = !info.func_imports.is_empty() || !info.imported_globals.is_empty();
+    let forwarded = ForwardedState::new(info);
+    let generic_part = forwarded.generic_part();
 
     code.push_str("impl WasmModule {\n");
 
-    // Build a map of function index -> export name for quick lookup
-    let export_names: std::collections::HashMap<usize, &str> = info
-        .func_exports
-        .iter()
-        .map(|e| (e.func_index.as_usize(), e.name.as_str()))
-        .collect();
-
-    // Generate accessor methods for all functions
+    // Generate accessor methods for all functions: one per export name, or
+    // `func_N` for functions that are not exported. Merged duplicates are
+    // reached through their survivor's exports.
     for func_idx in 0..info.ir_functions.len() {
-        let ir_func = &info.ir_functions[func_idx];
-
-        // Use export name if available, otherwise use func_N
-        let method_name = if let Some(export_name) = export_names.get(&func_idx) {
-            (*export_name).to_string()
-        } else {
-            format!("func_{}", func_idx)
-        };
-
-        // Build generics
-        let mut generics: Vec<String> = Vec::new();
-        if info.has_memory_import {
-            generics.push("const MP: usize".to_string());
-        }
-        if has_imports {
-            generics.push("H: ModuleHostTrait".to_string());
-        }
-
-        // Method signature
-        let mut param_parts: Vec<String> = Vec::new();
-        param_parts.push("&mut self".to_string());
-        for (i, (_, ty)) in ir_func.params.iter().enumerate() {
-            let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
-            param_parts.push(format!("v{i}: {rust_ty}"));
+        if info.is_merged(func_idx) {
+            continue;
         }
-
-        // Add memory parameter if imported
-        if info.has_memory_import {
-            param_parts.push("memory: &mut IsolatedMemory<MP>".to_string());
-        }
-
-        // Add host parameter if module has imports
-        if has_imports {
-            param_parts.push("host: &mut H".to_string());
+        let ir_func = &info.ir_functions[func_idx];
+        let wasm_params = info.wasm_params(func_idx);
+
+        // Exported methods are gated on their export's feature, the others
+        // on the function's own
+        let mut methods: Vec<(String, String, Option<&str>)> = info
+            .func_exports
+            .iter()
+            .filter(|e| e.func_index.as_usize() == func_idx)
+            .map(|e| {
+                // Grouped exports are documented on their group's view
+                let mut gate = export_cfg_attr("    ", info, &e.name);
+                if crate::codegen::groups::group_of(&info.export_groups, &e.name).is_some() {
+                    gate.push_str("    #[doc(hidden)]\n");
+                }
+                (
+                    crate::codegen::utils::rust_ident(&e.name),
+                    gate,
+                    Some(e.name.as_str()),
+                )
+            })
+            .collect();
+        if methods.is_empty() {
+            methods.push((
+                info.func_ident(func_idx),
+                func_cfg_attr("    ", info, func_idx),
+                None,
+            ));
         }
 
-        let return_type = crate::codegen::types::format_return_type(ir_func.return_type.as_ref());
-
-        // Generate method signature (with generics if needed)
-        let generic_part = if generics.is_empty() {
-            String::new()
-        } else {
-            format!("<{}>", generics.join(", "))
-        };
-
-        code.push_str(&format!(
-            "    pub fn {}{generic_part}({}) -> {} {{\n",
-            method_name,
-            param_parts.join(", "),
-            return_type
-        ));
-
-        // Construct Env and forward call to internal function
-        if has_imports {
-            code.push_str("        let mut env = Env { host, globals: &mut self.0.globals };\n");
-        } else {
-            code.push_str("        let mut __host = herkos_runtime::NoHost;\n");
-            code.push_str(
-                "        let mut env = Env { host: &mut __host, globals: &mut self.0.globals };\n",
-            );
+        // Exactly one method constructs the Env; the function's other export
+        // names forward to it when compiled under the same feature. An
+        // ungated export is always compiled, so it is preferred.
+        let feature_of = |export: Option<&str>| export.and_then(|e| info.export_features.get(e));
+        let canonical = methods
+            .iter()
+            .position(|(_, _, export)| feature_of(*export).is_none())
+            .unwrap_or(0);
+        let canonical_name = methods[canonical].0.clone();
+        let canonical_feature = feature_of(methods[canonical].2);
+
+        for (i, (method_name, gate, export)) in methods.into_iter().enumerate() {
+            if let Some(export) = export {
+                code.push_str(&contract_docs("    ", info, export));
+            }
+            code.push_str(&gate);
+
+            // Method signature: the Wasm one, even without the parameters
+            // dead-argument elimination removed
+            let mut param_parts: Vec<String> = Vec::new();
+            param_parts.push("&mut self".to_string());
+            for (i, (ty, _)) in wasm_params.iter().enumerate() {
+                let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
+                param_parts.push(format!("{}: {rust_ty}", info.param_name(func_idx, i)));
+            }
+            // Imported memory, imported table, and host, if any
+            param_parts.extend(forwarded.params.iter().map(|p| p.to_string()));
+
+            let return_type = crate::codegen::types::format_return_type(&ir_func.results);
+
+            code.push_str(&too_many_args_attr("    ", param_parts.len()));
+            code.push_str(&must_use_attr("    ", info, func_idx));
+            code.push_str(&format!(
+                "    pub fn {}{generic_part}({}) -> {} {{\n",
+                method_name,
+                param_parts.join(", "),
+                return_type
+            ));
+
+            if let Some(export) = export {
+                code.push_str(&contract_asserts("        ", info, export, ir_func));
+            }
+
+            if i != canonical
+                && (canonical_feature.is_none() || canonical_feature == feature_of(export))
+            {
+                let mut args: Vec<String> = (0..wasm_params.len())
+                    .map(|i| info.param_name(func_idx, i))
+                    .collect();
+                args.extend(forwarded.args.iter().map(|a| a.to_string()));
+                code.push_str(&format!(
+                    "        self.{canonical_name}({})\n    }}\n",
+                    args.join(", ")
+                ));
+                continue;
+            }
+
+            code.push_str(&forward_call(info, func_idx, "self"));
+            code.push_str("    }\n");
         }
+    }
 
-        // Build call arguments: wasm params + env + memory (if owned) + table
-        let mut call_args: Vec<String> =
-            (0..ir_func.params.len()).map(|i| format!("v{i}")).collect();
-        call_args.push("&mut env".to_string());
-
-        if info.has_memory {
-            call_args.push("&mut self.0.memory".to_string());
-        } else if info.has_memory_import {
-            call_args.push("memory".to_string());
-        }
-        if info.has_table() {
-            call_args.push("&self.0.table".to_string());
+    code.push_str(&initialize_method(info, &forwarded));
+
+    // Host access to an exported table (LLVM's `__indirect_function_table`).
+    // A re-exported imported table is already in the host's hands.
+    if info.owns_table() {
+        for name in &info.table_exports {
+            let trimmed = name.trim_start_matches('_');
+            let method = crate::codegen::utils::rust_ident(if trimmed.is_empty() {
+                "table"
+            } else {
+                trimmed
+            });
+            code.push_str(&format!("    /// The table exported as `{name}`.\n"));
+            code.push_str(&format!(
+                "    pub fn {method}(&self) -> &Table<TABLE_MAX> {{\n        &self.0.table\n    }}\n"
+            ));
+            code.push_str(&format!(
+                "    /// The table exported as `{name}`, mutably.\n"
+            ));
+            code.push_str(&format!(
+                "    pub fn {method}_mut(&mut self) -> &mut Table<TABLE_MAX> {{\n        &mut self.0.table\n    }}\n"
+            ));
         }
-
-        code.push_str(&format!(
-            "        func_{}({})\n",
-            func_idx,
-            call_args.join(", ")
-        ));
-        code.push_str("    }\n");
+        code.push_str(&generate_table_methods(info));
     }
 
     code.push_str("}\n");
     code
 }
+
+/// `initialize()`, forwarding to the `__wasm_call_ctors` export of a module
+/// whose static constructors were not run at transpile time. Left out when
+/// an export already has the name.
+fn initialize_method(info: &ModuleInfo, forwarded: &ForwardedState) -> String {
+    let Some(func) = info.ctors() else {
+        return String::new();
+    };
+    let taken = info
+        .func_exports
+        .iter()
+        .any(|e| crate::codegen::utils::rust_ident(&e.name) == "initialize");
+    let ir_func = &info.ir_functions[func.as_usize()];
+    if info.ctors_evaluated || taken || !ir_func.params.is_empty() || !ir_func.results.is_empty() {
+        return String::new();
+    }
+    let mut params = vec!["&mut self".to_string()];
+    params.extend(forwarded.params.iter().map(|p| p.to_string()));
+    let args: Vec<String> = forwarded.args.iter().map(|a| a.to_string()).collect();
+    let mut code = String::new();
+    code.push_str("    /// Run the module's C/C++ static constructors (`__wasm_call_ctors`).\n");
+    code.push_str("    ///\n");
+    code.push_str("    /// Call this once, after `new()` and before any other export: until\n");
+    code.push_str("    /// then, globals with dynamic initializers are unset. Transpiling with\n");
+    code.push_str("    /// `--call-ctors` runs them at transpile time instead.\n");
+    code.push_str(&export_cfg_attr("    ", info, CTORS_EXPORT));
+    code.push_str(&format!(
+        "    pub fn initialize{}({}) -> WasmResult<()> {{\n",
+        forwarded.generic_part(),
+        params.join(", ")
+    ));
+    code.push_str(&format!(
+        "        self.{}({})\n    }}\n",
+        crate::codegen::utils::rust_ident(CTORS_EXPORT),
+        args.join(", ")
+    ));
+    code
+}
@@ -7,6 +7,28 @@
 use crate::backend::Backend;
 use crate::ir::*;
 use anyhow::Result;
+use std::fmt::Write;
+
+/// Fixed per-function overhead (attributes, signature, state machine scaffold).
+const FUNCTION_OVERHEAD_BYTES: usize = 512;
+/// Average emitted size of a block header, terminator and closing brace.
+const BYTES_PER_BLOCK: usize = 128;
+/// Average emitted size of one instruction (one indented statement).
+const BYTES_PER_INSTR: usize = 48;
+
+/// Estimate the size of the generated code for `ir_func`, in bytes.
+///
+/// Used to preallocate output buffers so that transpiling large modules does
+/// not repeatedly reallocate. Over- or under-estimating only costs memory or
+/// an extra reallocation — never correctness.
+pub fn estimate_function_size(ir_func: &IrFunction) -> usize {
+    let num_instrs: usize = ir_func.blocks.iter().map(|b| b.instructions.len()).sum();
+    let num_vars = ir_func.params.len() + ir_func.locals.len() + num_instrs;
+    FUNCTION_OVERHEAD_BYTES
+        + ir_func.blocks.len() * BYTES_PER_BLOCK
+        + num_instrs * BYTES_PER_INSTR
+        + num_vars * 32
+}
 
 /// Generate a complete Rust function from IR with module info.
 ///
@@ -18,26 +40,188 @@ pub fn generate_function_with_info<B: Backend>(
     info: &ModuleInfo,
     is_public: bool,
 ) -> Result<String> {
-    let mut output = String::new();
+    let mut output = String::with_capacity(estimate_function_size(ir_func));
+    write_function_with_info(
+        &mut output,
+        backend,
+        ir_func,
+        func_name,
+        info,
+        is_public,
+        false,
+        &[],
+        &[],
+    )?;
+    Ok(output)
+}
 
+/// Append a complete Rust function to `output`; see [`generate_function_with_info`].
+///
+/// `constant_time` emits `select` without branching on its condition (see
+/// [`ModuleInfo::constant_time`]). `coverage` pairs the blocks counted
+/// under `--coverage` with their counter (see [`crate::codegen::coverage`]),
+/// and `bounds_checks` the accesses counted under `--bounds-profile` (see
+/// [`crate::codegen::bounds_profile`]).
+#[allow(clippy::too_many_arguments)]
+pub fn write_function_with_info<B: Backend>(
+    output: &mut String,
+    backend: &B,
+    ir_func: &IrFunction,
+    func_name: &str,
+    info: &ModuleInfo,
+    is_public: bool,
+    constant_time: bool,
+    coverage: &[(BlockId, usize)],
+    bounds_checks: &[(BlockId, usize, usize)],
+) -> Result<()> {
     // Suppress warnings for generated code patterns that are hard to avoid
-    output.push_str("#[allow(unused_mut, unused_variables, unused_assignments, clippy::only_used_in_recursion, clippy::needless_return, clippy::manual_range_contains, clippy::never_loop)]\n");
+    output.push_str("#[allow(unused_mut, unused_variables, unused_assignments, clippy::only_used_in_recursion, clippy::never_loop, clippy::too_many_arguments)]\n");
 
     // Generate function signature
-    output.push_str(&generate_signature_with_info(
-        backend, ir_func, func_name, info, is_public,
-    ));
+    write_signature_with_info(
+        output,
+        backend,
+        ir_func,
+        func_name,
+        info,
+        is_public,
+        constant_time,
+    )?;
     output.push_str(" {\n");
 
+    let var_types = infer_var_types(ir_func, info);
+    let yield_blocks = if info.yield_points {
+        loop_headers(ir_func)
+    } else {
+        std::collections::HashSet::new()
+    };
+
+    // Structured control flow where the CFG allows it. Otherwise a state
+    // machine with a per-function Block enum, declared first so that no
+    // item follows a statement
+    let shape = crate::codegen::structured::plan(ir_func);
+    if shape.is_none() {
+        output.push_str("    #[derive(Clone, Copy)]\n    #[allow(dead_code)]\n");
+        output.push_str("    enum Block { ");
+        for idx in 0..ir_func.blocks.len() {
+            if idx > 0 {
+                output.push_str(", ");
+            }
+            write!(output, "B{idx}")?;
+        }
+        output.push_str(" }\n");
+    }
+
+    // Declare all SSA variables with their inferred types, in variable order
+    for (var, ty) in var_types
+        .iter()
+        .filter(|(var, _)| !ir_func.params.iter().any(|(p, _)| p == *var))
+    {
+        let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
+        let default = ty.default_value_literal();
+        writeln!(output, "    let mut {var}: {rust_ty} = {default};")?;
+    }
+
+    if let Some(shape) = shape {
+        let extras = crate::codegen::structured::BlockExtras {
+            yield_blocks: &yield_blocks,
+            coverage,
+            bounds_checks,
+            constant_time,
+            var_types: &var_types,
+        };
+        crate::codegen::structured::write_shape(
+            output, backend, ir_func, info, &extras, &shape, 1,
+        )?;
+        output.push_str("}\n");
+        return Ok(());
+    }
+
     // Create mapping from BlockId to vector index
     let mut block_id_to_index = std::collections::HashMap::new();
     for (idx, block) in ir_func.blocks.iter().enumerate() {
         block_id_to_index.insert(block.id, idx);
     }
 
-    // Collect all variables and their types from instructions.
-    let mut var_types: std::collections::HashMap<VarId, WasmType> =
-        std::collections::HashMap::new();
+    output.push_str("    let mut __current_block = Block::B0;\n");
+    output.push_str("    loop {\n");
+    output.push_str("        match __current_block {\n");
+
+    for (idx, block) in ir_func.blocks.iter().enumerate() {
+        writeln!(output, "            Block::B{idx} => {{")?;
+
+        if yield_blocks.contains(&block.id) {
+            backend.emit_yield_point(output)?;
+            output.push('\n');
+        }
+
+        if let Some(&(_, counter)) = coverage.iter().find(|(id, _)| *id == block.id) {
+            writeln!(
+                output,
+                "                {}",
+                crate::codegen::coverage::count_statement(counter)
+            )?;
+        }
+
+        for (instr_idx, instr) in block.instructions.iter().enumerate() {
+            if let Some(counter) =
+                crate::codegen::bounds_profile::counter_of(bounds_checks, block.id, instr_idx)
+            {
+                writeln!(
+                    output,
+                    "                {}",
+                    crate::codegen::bounds_profile::count_statement(counter)
+                )?;
+            }
+            let prev = instr_idx.checked_sub(1).map(|i| &block.instructions[i]);
+            if crate::codegen::instruction::is_write_back(prev, instr, info) {
+                continue;
+            }
+            match instr {
+                IrInstr::Select {
+                    dest,
+                    val1,
+                    val2,
+                    condition,
+                } if constant_time => {
+                    let ty = var_types.get(val1).copied().unwrap_or(WasmType::I32);
+                    backend.emit_select_branchless(output, *dest, *val1, *val2, *condition, ty)?
+                }
+                _ => crate::codegen::instruction::write_instruction(output, backend, instr, info)?,
+            }
+            output.push('\n');
+        }
+
+        crate::codegen::instruction::write_terminator(
+            output,
+            backend,
+            &block.terminator,
+            &block_id_to_index,
+            &ir_func.results,
+        )?;
+        output.push('\n');
+
+        output.push_str("            }\n");
+    }
+
+    // No catch-all needed — match is exhaustive over Block enum
+    output.push_str("        }\n");
+    output.push_str("    }\n");
+
+    output.push_str("}\n");
+    Ok(())
+}
+
+/// Type of every variable `ir_func` defines or reads, params and locals
+/// included, inferred from the instructions that define them.
+///
+/// Ordered by variable, so declarations come out the same on every run.
+pub(crate) fn infer_var_types(
+    ir_func: &IrFunction,
+    info: &ModuleInfo,
+) -> std::collections::BTreeMap<VarId, WasmType> {
+    let mut var_types: std::collections::BTreeMap<VarId, WasmType> =
+        std::collections::BTreeMap::new();
 
     // Seed with parameter types
     for (var, ty) in &ir_func.params {
@@ -49,6 +233,9 @@ pub fn generate_function_with_info<B: Backend>(
         var_types.insert(*var, *ty);
     }
 
+    // Copies whose source had no type yet when the scan reached them
+    let mut copies = Vec::new();
+
     // Infer types from instructions
     for block in &ir_func.blocks {
         for instr in &block.instructions {
@@ -66,36 +253,29 @@ pub fn generate_function_with_info<B: Backend>(
                     var_types.insert(*dest, *ty);
                 }
                 IrInstr::Call {
-                    dest: Some(dest),
-                    func_idx,
-                    ..
+                    dests, func_idx, ..
                 } => {
                     // func_idx is in local space (imports already excluded)
-                    let ty = info
-                        .ir_function(*func_idx)
-                        .and_then(|f| f.return_type)
-                        .unwrap_or(WasmType::I32);
-                    var_types.insert(*dest, ty);
+                    let results = info.ir_function(*func_idx).map(|f| &f.results[..]);
+                    insert_call_results(&mut var_types, dests, results);
                 }
                 IrInstr::CallImport {
-                    dest: Some(dest),
-                    import_idx,
-                    ..
+                    dests, import_idx, ..
                 } => {
                     // Look up import signature from func_imports
-                    let ty = info
+                    let results = info
                         .func_import(import_idx.clone())
-                        .and_then(|imp| imp.return_type)
-                        .unwrap_or(WasmType::I32);
-                    var_types.insert(*dest, ty);
+                        .map(|imp| &imp.results[..]);
+                    insert_call_results(&mut var_types, dests, results);
                 }
-                IrInstr::Assign { dest, src } => {
-                    if let Some(ty) = var_types.get(src) {
+                IrInstr::Assign { dest, src } => match var_types.get(src) {
+                    Some(ty) => {
                         var_types.insert(*dest, *ty);
-                    } else {
-                        var_types.insert(*dest, WasmType::I32);
                     }
-                }
+                    // The source may be defined by a block later in the scan,
+                    // or be another copy (phi lowering chains them).
+                    None => copies.push((*dest, *src)),
+                },
                 IrInstr::GlobalGet { dest, index } => {
                     let ty = match info.resolve_global(*index) {
                         ResolvedGlobal::Imported(_idx, g) => g.wasm_type,
@@ -104,22 +284,26 @@ pub fn generate_function_with_info<B: Backend>(
                     var_types.insert(*dest, ty);
                 }
                 IrInstr::CallIndirect {
-                    dest: Some(dest),
-                    type_idx,
-                    ..
+                    dests, type_idx, ..
                 } => {
-                    let ty = info
+                    let results = info
                         .type_signature(type_idx.clone())
-                        .and_then(|s| s.return_type)
-                        .unwrap_or(WasmType::I32);
-                    var_types.insert(*dest, ty);
+                        .map(|s| &s.results[..]);
+                    insert_call_results(&mut var_types, dests, results);
                 }
                 IrInstr::MemorySize { dest } | IrInstr::MemoryGrow { dest, .. } => {
                     var_types.insert(*dest, WasmType::I32);
                 }
-                IrInstr::Select { dest, val1, .. } => {
-                    // Result type matches the operand type
-                    let ty = var_types.get(val1).copied().unwrap_or(WasmType::I32);
+                IrInstr::Select {
+                    dest, val1, val2, ..
+                } => {
+                    // Result type matches the operand type; either operand may
+                    // be defined by a block later in the scan.
+                    let ty = var_types
+                        .get(val1)
+                        .or_else(|| var_types.get(val2))
+                        .copied()
+                        .unwrap_or(WasmType::I32);
                     var_types.insert(*dest, ty);
                 }
                 _ => {}
@@ -130,10 +314,11 @@ pub fn generate_function_with_info<B: Backend>(
         // dead-code blocks after `unreachable` where the variable
         // was never assigned by an instruction).
         match &block.terminator {
-            IrTerminator::Return { value: Some(var) } => {
-                var_types
-                    .entry(*var)
-                    .or_insert(ir_func.return_type.unwrap_or(WasmType::I32));
+            IrTerminator::Return { values } => {
+                for (i, var) in values.iter().enumerate() {
+                    let ty = ir_func.results.get(i).copied();
+                    var_types.entry(*var).or_insert(ty.unwrap_or(WasmType::I32));
+                }
             }
             IrTerminator::BranchIf { condition, .. } => {
                 var_types.entry(*condition).or_insert(WasmType::I32);
@@ -145,122 +330,138 @@ pub fn generate_function_with_info<B: Backend>(
         }
     }
 
-    // Declare all SSA variables with their inferred types
-    let mut sorted_vars: Vec<_> = var_types
-        .iter()
-        .filter(|(var, _)| !ir_func.params.iter().any(|(p, _)| p == *var))
-        .collect();
-    sorted_vars.sort_by_key(|(var, _)| var.0);
-
-    for (var, ty) in sorted_vars {
-        let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
-        let default = ty.default_value_literal();
-        output.push_str(&format!("    let mut {var}: {rust_ty} = {default};\n"));
-    }
-
-    // Multi-block: state machine with per-function Block enum
-    output.push_str("    #[derive(Clone, Copy)]\n    #[allow(dead_code)]\n");
-    output.push_str("    enum Block { ");
-    for idx in 0..ir_func.blocks.len() {
-        if idx > 0 {
-            output.push_str(", ");
+    // Each round types at least one more copy, or none is left to type.
+    for _ in 0..copies.len() {
+        let mut changed = false;
+        for (dest, src) in &copies {
+            if let Some(ty) = var_types.get(src).copied() {
+                changed |= var_types.insert(*dest, ty) != Some(ty);
+            }
         }
-        output.push_str(&format!("B{}", idx));
-    }
-    output.push_str(" }\n");
-    output.push_str("    let mut __current_block = Block::B0;\n");
-    output.push_str("    loop {\n");
-    output.push_str("        match __current_block {\n");
-
-    for (idx, block) in ir_func.blocks.iter().enumerate() {
-        output.push_str(&format!("            Block::B{} => {{\n", idx));
-
-        for instr in &block.instructions {
-            let code =
-                crate::codegen::instruction::generate_instruction_with_info(backend, instr, info)?;
-            output.push_str(&code);
-            output.push('\n');
+        if !changed {
+            break;
         }
+    }
+    for (dest, _) in copies {
+        var_types.entry(dest).or_insert(WasmType::I32);
+    }
 
-        let term_code = crate::codegen::instruction::generate_terminator_with_mapping(
-            backend,
-            &block.terminator,
-            &block_id_to_index,
-            ir_func.return_type,
-        );
-        output.push_str(&term_code);
-        output.push('\n');
+    var_types
+}
 
-        output.push_str("            }\n");
+/// Type the variables receiving a call's results from the callee's `results`
+/// (`i32` when the callee is unknown).
+fn insert_call_results(
+    var_types: &mut std::collections::BTreeMap<VarId, WasmType>,
+    dests: &[VarId],
+    results: Option<&[WasmType]>,
+) {
+    for (i, dest) in dests.iter().enumerate() {
+        let ty = results.and_then(|r| r.get(i)).copied();
+        var_types.insert(*dest, ty.unwrap_or(WasmType::I32));
     }
+}
 
-    // No catch-all needed — match is exhaustive over Block enum
-    output.push_str("        }\n");
-    output.push_str("    }\n");
+/// Targets of retreating edges in a depth-first walk from the entry block.
+///
+/// Every cycle in the CFG, reducible or not, passes through one of them, so a
+/// yield point in each bounds the work done between two yields.
+pub(crate) fn loop_headers(ir_func: &IrFunction) -> std::collections::HashSet<BlockId> {
+    use crate::optimizer::utils::terminator_successors;
+    use std::collections::{HashMap, HashSet};
 
-    output.push_str("}\n");
-    Ok(output)
+    let blocks: HashMap<BlockId, &IrBlock> = ir_func.blocks.iter().map(|b| (b.id, b)).collect();
+    let mut headers = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut on_stack = HashSet::new();
+    // (block, successors not yet visited)
+    let mut stack: Vec<(BlockId, Vec<BlockId>)> = Vec::new();
+    if let Some(entry) = ir_func.blocks.first() {
+        visited.insert(entry.id);
+        on_stack.insert(entry.id);
+        stack.push((entry.id, terminator_successors(&entry.terminator)));
+    }
+    while let Some((block, pending)) = stack.last_mut() {
+        let block = *block;
+        match pending.pop() {
+            Some(succ) if on_stack.contains(&succ) => {
+                headers.insert(succ);
+            }
+            Some(succ) => {
+                if visited.insert(succ) {
+                    if let Some(b) = blocks.get(&succ) {
+                        on_stack.insert(succ);
+                        stack.push((succ, terminator_successors(&b.terminator)));
+                    }
+                }
+            }
+            None => {
+                on_stack.remove(&block);
+                stack.pop();
+            }
+        }
+    }
+    headers
 }
 
-/// Generate function signature with module info.
-fn generate_signature_with_info<B: Backend>(
-    _backend: &B,
+/// Append the function signature (without the opening brace) to `out`.
+pub fn write_signature_with_info<B: Backend>(
+    out: &mut String,
+    backend: &B,
     ir_func: &IrFunction,
     func_name: &str,
     info: &ModuleInfo,
     is_public: bool,
-) -> String {
+    constant_time: bool,
+) -> std::fmt::Result {
     let visibility = if is_public { "pub " } else { "" };
+    // `ct_select` is not a `const fn`
+    let constness =
+        if !constant_time && crate::codegen::pure::is_const_function(backend, ir_func, info) {
+            "const "
+        } else {
+            ""
+        };
 
-    // Build generics: handle both H (host) and MP (imported memory size)
-    let mut generics: Vec<String> = Vec::new();
+    // Generics: MP (imported memory size) and TP (imported table size) if
+    // needed, then H (host). All internal functions have the
+    // H: ModuleHostTrait generic.
+    write!(out, "{visibility}{constness}fn {func_name}<")?;
     if info.has_memory_import {
-        generics.push("const MP: usize".to_string());
+        out.push_str("const MP: usize, ");
     }
-    // All internal functions have H: ModuleHostTrait generic
-    generics.push("H: ModuleHostTrait".to_string());
-
-    let generic_part = if generics.is_empty() {
-        String::new()
-    } else {
-        format!("<{}>", generics.join(", "))
-    };
-
-    let mut sig = format!("{visibility}fn {func_name}{generic_part}(");
+    if info.has_table_import {
+        out.push_str("const TP: usize, ");
+    }
+    out.push_str("H: ModuleHostTrait>(");
 
     // Parameters (mutable, as in WebAssembly all locals are mutable)
-    let mut param_parts: Vec<String> = ir_func
-        .params
-        .iter()
-        .map(|(var_id, ty)| {
-            let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
-            format!("mut {}: {}", var_id, rust_ty)
-        })
-        .collect();
+    for (var_id, ty) in &ir_func.params {
+        let rust_ty = crate::codegen::types::wasm_type_to_rust(ty);
+        write!(out, "mut {var_id}: {rust_ty}, ")?;
+    }
 
     // Always add env parameter
-    param_parts.push("env: &mut Env<'_, H>".to_string());
+    out.push_str("env: &mut Env<'_, H>");
 
     // Add memory parameter — either const MAX_PAGES or generic MP
     if info.has_memory {
-        param_parts.push("memory: &mut IsolatedMemory<MAX_PAGES>".to_string());
+        out.push_str(", memory: &mut IsolatedMemory<MAX_PAGES>");
     } else if info.has_memory_import {
-        param_parts.push("memory: &mut IsolatedMemory<MP>".to_string());
+        out.push_str(", memory: &mut IsolatedMemory<MP>");
     }
 
-    // Add table parameter if module has a table
-    if info.has_table() {
-        param_parts.push("table: &Table<TABLE_MAX>".to_string());
+    // Add table parameter — either const TABLE_MAX or generic TP
+    if info.has_table_import {
+        out.push_str(", table: &Table<TP>");
+    } else if info.has_table() {
+        out.push_str(", table: &Table<TABLE_MAX>");
     }
 
-    sig.push_str(&param_parts.join(", "));
-    sig.push(')');
-
     // Return type
-    sig.push_str(&format!(
-        " -> {}",
-        crate::codegen::types::format_return_type(ir_func.return_type.as_ref())
-    ));
-
-    sig
+    write!(
+        out,
+        ") -> {}",
+        crate::codegen::types::format_return_type(&ir_func.results)
+    )
 }
@@ -0,0 +1,220 @@
+//! Export groups: namespaced sub-APIs over exports sharing a name prefix.
+//!
+//! A module with hundreds of exports makes one flat `impl WasmModule` hard to
+//! navigate. Each group prefix (`--export-group image_`) gets an accessor
+//! returning a borrowed view whose methods are the matching exports with the
+//! prefix stripped:
+//!
+//! ```text
+//! pub struct ImageExports<'a>(&'a mut WasmModule);
+//!
+//! impl WasmModule {
+//!     pub fn image(&mut self) -> ImageExports<'_> { ImageExports(self) }
+//! }
+//!
+//! impl ImageExports<'_> {
+//!     pub fn resize(&mut self, v0: i32) -> WasmResult<i32> { self.0.image_resize(v0) }
+//! }
+//! ```
+//!
+//! The flat methods stay, hidden from the docs, because the other generated
+//! wrappers call them. An export matching several prefixes joins the group
+//! of the longest one.
+
+use crate::codegen::types::{format_return_type, wasm_type_to_rust};
+use crate::codegen::utils::{
+    export_cfg_attr, must_use_attr, rust_ident, too_many_args_attr, ForwardedState,
+};
+use crate::ir::*;
+use anyhow::{bail, Result};
+
+/// Check `prefixes` against the exports of `info`.
+///
+/// # Errors
+/// Fails if a prefix names no group, matches no export, or its accessor
+/// collides with an export method or another group.
+pub fn check_groups(prefixes: &[String], info: &ModuleInfo) -> Result<()> {
+    let methods: Vec<String> = info
+        .func_exports
+        .iter()
+        .map(|e| rust_ident(&e.name))
+        .collect();
+    let mut accessors: Vec<String> = Vec::new();
+    for prefix in prefixes {
+        let name = group_name(prefix);
+        if name.is_empty() {
+            bail!("export group prefix `{prefix}` has no name before its separator");
+        }
+        let accessor = rust_ident(name);
+        if methods.contains(&accessor) || accessors.contains(&accessor) {
+            bail!("export group `{prefix}` collides with the `{accessor}` method");
+        }
+        if !info
+            .func_exports
+            .iter()
+            .any(|e| group_of(prefixes, &e.name) == Some(prefix.as_str()))
+        {
+            bail!("export group prefix `{prefix}` matches no function export");
+        }
+        accessors.push(accessor);
+    }
+    Ok(())
+}
+
+/// The prefix whose group export `name` belongs to: the longest one that
+/// leaves a non-empty method name.
+pub fn group_of<'a>(prefixes: &'a [String], name: &str) -> Option<&'a str> {
+    prefixes
+        .iter()
+        .filter(|p| name.len() > p.len() && name.starts_with(p.as_str()))
+        .max_by_key(|p| p.len())
+        .map(String::as_str)
+}
+
+/// Generate the group views and their accessors.
+///
+/// Returns an empty string when no groups were requested.
+pub fn generate_export_groups(info: &ModuleInfo) -> String {
+    let forwarded = ForwardedState::new(info);
+    let generic_part = forwarded.generic_part();
+    let mut code = String::new();
+    for prefix in &info.export_groups {
+        let name = group_name(prefix);
+        let view = format!("{}Exports", type_ident(name));
+
+        code.push_str(&format!(
+            "/// Exports named `{prefix}*`, without the prefix.\npub struct {view}<'a>(&'a mut WasmModule);\n\n"
+        ));
+        code.push_str("impl WasmModule {\n");
+        code.push_str(&format!("    /// Exports named `{prefix}*`.\n"));
+        code.push_str(&format!(
+            "    pub fn {}(&mut self) -> {view}<'_> {{\n        {view}(self)\n    }}\n",
+            rust_ident(name)
+        ));
+        code.push_str("}\n\n");
+
+        code.push_str(&format!("impl {view}<'_> {{\n"));
+        for export in &info.func_exports {
+            if group_of(&info.export_groups, &export.name) != Some(prefix.as_str()) {
+                continue;
+            }
+            let Some(func) = info.ir_function(export.func_index) else {
+                continue;
+            };
+            let mut params = vec!["&mut self".to_string()];
+            let mut args = Vec::new();
+            for (i, (_, ty)) in func.params.iter().enumerate() {
+                let arg = info.param_name(export.func_index.as_usize(), i);
+                params.push(format!("{arg}: {}", wasm_type_to_rust(ty)));
+                args.push(arg);
+            }
+            params.extend(forwarded.params.iter().map(|p| p.to_string()));
+            args.extend(forwarded.args.iter().map(|a| a.to_string()));
+
+            code.push_str(&export_cfg_attr("    ", info, &export.name));
+            code.push_str(&too_many_args_attr("    ", params.len()));
+            code.push_str(&must_use_attr("    ", info, export.func_index.as_usize()));
+            code.push_str(&format!(
+                "    pub fn {}{generic_part}({}) -> {} {{\n",
+                rust_ident(&export.name[prefix.len()..]),
+                params.join(", "),
+                format_return_type(&func.results)
+            ));
+            code.push_str(&format!(
+                "        self.0.{}({})\n",
+                rust_ident(&export.name),
+                args.join(", ")
+            ));
+            code.push_str("    }\n");
+        }
+        code.push_str("}\n\n");
+    }
+    code
+}
+
+/// Group name: the prefix without trailing separators (`image_` -> `image`).
+fn group_name(prefix: &str) -> &str {
+    prefix.trim_end_matches(|c: char| !c.is_ascii_alphanumeric())
+}
+
+/// `UpperCamelCase` type name for group `name`.
+fn type_ident(name: &str) -> String {
+    let mut ident = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            ident.push(first.to_ascii_uppercase());
+            ident.extend(chars);
+        }
+    }
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        ident.insert(0, 'G');
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transpile, TranspileOptions};
+
+    fn options(prefixes: &[&str]) -> TranspileOptions {
+        TranspileOptions {
+            export_groups: prefixes.iter().map(|p| p.to_string()).collect(),
+            ..TranspileOptions::default()
+        }
+    }
+
+    #[test]
+    fn groups_strip_the_prefix_and_forward() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (func (export "image_resize") (param i32) (result i32) local.get 0)
+                (func (export "image_gpu_blit"))
+                (func (export "audio-play") (param i64))
+                (func (export "version") (result i32) i32.const 1))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &options(&["image_", "image_gpu_", "audio-"])).unwrap();
+
+        assert!(code.contains("pub struct ImageExports<'a>(&'a mut WasmModule);"));
+        assert!(code.contains("pub fn image(&mut self) -> ImageExports<'_> {"));
+        assert!(code.contains(
+            "pub fn resize<H: ModuleHostTrait>(&mut self, v0: i32, host: &mut H) -> WasmResult<i32> {"
+        ));
+        assert!(code.contains("self.0.image_resize(v0, host)"));
+        // The longest prefix wins
+        assert!(code.contains("pub struct ImageGpuExports<'a>"));
+        assert!(code.contains("pub fn image_gpu(&mut self) -> ImageGpuExports<'_> {"));
+        assert!(code.contains("pub fn blit<H: ModuleHostTrait>(&mut self, host: &mut H)"));
+        assert!(code.contains("pub fn play<H: ModuleHostTrait>(&mut self, v0: i64, host: &mut H)"));
+        assert!(code.contains(
+            "#[doc(hidden)]\n    #[must_use = \"the function has no side effects\"]\n    pub fn image_resize"
+        ));
+        assert!(!code.contains("#[doc(hidden)]\n    pub fn version"));
+    }
+
+    #[test]
+    fn bad_prefixes_are_rejected() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "image_resize"))
+                (func (export "image")))"#,
+        )
+        .unwrap();
+        let err = transpile(&wasm, &options(&["audio_"])).unwrap_err();
+        assert!(format!("{err:#}").contains("matches no function export"));
+        let err = transpile(&wasm, &options(&["image_"])).unwrap_err();
+        assert!(format!("{err:#}").contains("collides with the `image` method"));
+        let err = transpile(&wasm, &options(&["_"])).unwrap_err();
+        assert!(format!("{err:#}").contains("has no name"));
+    }
+
+    #[test]
+    fn type_names_are_upper_camel_case() {
+        assert_eq!(type_ident("image"), "Image");
+        assert_eq!(type_ident("image_gpu"), "ImageGpu");
+        assert_eq!(type_ident("3d"), "G3d");
+    }
+}
@@ -0,0 +1,157 @@
+//! Guest allocator helper generation.
+//!
+//! When the module exports its own allocator (see
+//! [`GuestAllocator`](crate::bindings::GuestAllocator)), emits methods that let
+//! the host obtain and release guest buffers without calling the allocator
+//! exports by hand:
+//!
+//! ```text
+//! pub fn alloc_in_guest(&mut self, len: usize) -> WasmResult<usize> {
+//!     let __ptr = self.malloc(guest_alloc_len(len)?)?;
+//!     checked_guest_alloc(self.0.memory.as_slice(), __ptr, len)
+//! }
+//! pub fn free_in_guest(&mut self, ptr: usize, _len: usize) -> WasmResult<()> {
+//!     self.free(ptr as i32)
+//! }
+//! ```
+
+use crate::bindings::{GuestAlloc, GuestAllocator, GuestFree};
+use crate::codegen::utils::ForwardedState;
+use crate::ir::*;
+
+/// Generate the `impl WasmModule` block with `alloc_in_guest` and
+/// `free_in_guest`.
+///
+/// Returns an empty string when the module exports no usable allocator.
+pub fn generate_guest_alloc(info: &ModuleInfo) -> String {
+    let Some(allocator) = GuestAllocator::detect(info) else {
+        return String::new();
+    };
+
+    // Generics and trailing parameters, mirroring the raw export methods.
+    let forwarded = ForwardedState::new(info);
+    let generic_part = forwarded.generic_part();
+    let extra_params: String = forwarded.params.iter().map(|p| format!(", {p}")).collect();
+    let extra_args: String = forwarded.args.iter().map(|a| format!(", {a}")).collect();
+    let mem = if info.has_memory {
+        "self.0.memory"
+    } else {
+        "memory"
+    };
+
+    let alloc_name = allocator.alloc.export_name();
+    let alloc_args = match allocator.alloc {
+        GuestAlloc::Malloc | GuestAlloc::WbindgenMalloc => "guest_alloc_len(len)?",
+        GuestAlloc::WbindgenMallocAligned => "guest_alloc_len(len)?, GUEST_ALLOC_ALIGN",
+        GuestAlloc::CabiRealloc => "0, 0, GUEST_ALLOC_ALIGN, guest_alloc_len(len)?",
+    };
+    let mut code = String::from("impl WasmModule {\n");
+    code.push_str(&format!(
+        "    /// Allocate `len` bytes of linear memory through the guest's `{alloc_name}` export.\n"
+    ));
+    code.push_str(&format!(
+        "    pub fn {}{generic_part}(&mut self, len: usize{extra_params}) -> WasmResult<usize> {{\n",
+        GuestAllocator::ALLOC_METHOD
+    ));
+    code.push_str(&format!(
+        "        let __ptr = self.{alloc_name}({alloc_args}{extra_args})?;\n"
+    ));
+    code.push_str(&format!(
+        "        checked_guest_alloc({mem}.as_slice(), __ptr, len)\n"
+    ));
+    code.push_str("    }\n");
+
+    if let Some(free) = allocator.free {
+        let free_name = free.export_name();
+        let (len_param, free_args) = match free {
+            GuestFree::Free => ("_len", "ptr as i32"),
+            GuestFree::WbindgenFree => ("len", "ptr as i32, len as i32"),
+            GuestFree::WbindgenFreeAligned => ("len", "ptr as i32, len as i32, GUEST_ALLOC_ALIGN"),
+        };
+        code.push_str(&format!(
+            "    /// Release a block from [`Self::{}`] through the guest's `{free_name}` export.\n",
+            GuestAllocator::ALLOC_METHOD
+        ));
+        code.push_str(&format!(
+            "    pub fn {}{generic_part}(&mut self, ptr: usize, {len_param}: usize{extra_params}) -> WasmResult<()> {{\n",
+            GuestAllocator::FREE_METHOD
+        ));
+        code.push_str(&format!(
+            "        self.{free_name}({free_args}{extra_args})\n"
+        ));
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_wat(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        transpile(&wasm, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn emits_malloc_and_free_helpers() {
+        let code = transpile_wat(
+            r#"(module
+                (memory 1)
+                (func (export "malloc") (param i32) (result i32) i32.const 8)
+                (func (export "free") (param i32)))"#,
+        );
+        assert!(
+            code.contains("pub fn alloc_in_guest(&mut self, len: usize) -> WasmResult<usize> {")
+        );
+        assert!(code.contains("let __ptr = self.malloc(guest_alloc_len(len)?)?;"));
+        assert!(code.contains("checked_guest_alloc(self.0.memory.as_slice(), __ptr, len)"));
+        assert!(code.contains(
+            "pub fn free_in_guest(&mut self, ptr: usize, _len: usize) -> WasmResult<()> {"
+        ));
+        assert!(code.contains("self.free(ptr as i32)"));
+    }
+
+    #[test]
+    fn cabi_realloc_gets_no_free_helper() {
+        let code = transpile_wat(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (memory 1)
+                (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32) i32.const 8))"#,
+        );
+        assert!(code.contains(
+            "pub fn alloc_in_guest<H: ModuleHostTrait>(&mut self, len: usize, host: &mut H)"
+        ));
+        assert!(code
+            .contains("self.cabi_realloc(0, 0, GUEST_ALLOC_ALIGN, guest_alloc_len(len)?, host)?;"));
+        assert!(!code.contains("free_in_guest"));
+    }
+
+    #[test]
+    fn wasm_bindgen_modules_prefer_their_own_allocator() {
+        let exports = r#"
+                (memory 1)
+                (func (export "malloc") (param i32) (result i32) i32.const 8)
+                (func (export "__wbindgen_malloc") (param i32 i32) (result i32) i32.const 8)"#;
+        let plain = transpile_wat(&format!("(module {exports})"));
+        assert!(plain.contains("self.malloc(guest_alloc_len(len)?)"));
+        let bindgen = transpile_wat(&format!(
+            r#"(module (@producers (processed-by "wasm-bindgen" "0.2.92")) {exports})"#
+        ));
+        assert!(
+            bindgen.contains("self.__wbindgen_malloc(guest_alloc_len(len)?, GUEST_ALLOC_ALIGN)")
+        );
+    }
+
+    #[test]
+    fn ignores_exports_with_the_wrong_signature() {
+        let code = transpile_wat(
+            r#"(module
+                (memory 1)
+                (func (export "malloc") (param i64) (result i32) i32.const 8))"#,
+        );
+        assert!(!code.contains("alloc_in_guest"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Logging imports routed to `herkos_runtime::GuestLog` (`--guest-log`).
+//!
+//! Most guests print through `log(ptr, len)` style imports (`env.log`,
+//! `console.log`) or WASI `fd_write`. With `--guest-log`, each such import
+//! leaves `ModuleHostTrait`, which gets `GuestLog` as a supertrait instead,
+//! and every call site — direct or through the table — goes through a shim
+//! that reads the text out of linear memory:
+//!
+//! ```text
+//! fn guest_log_0<const M: usize, H: ModuleHostTrait + ?Sized>(host: &mut H, memory: &IsolatedMemory<M>, arg0: i32, arg1: i32) -> WasmResult<()> {
+//!     herkos_runtime::log_str(host, memory.as_slice(), arg0, arg1)
+//! }
+//!
+//!                 guest_log_0(&mut *env.host, memory, v3, v4)?;
+//! ```
+//!
+//! `fd_write` to stdout or stderr logs each buffer and stores the byte
+//! count at `nwritten`; other descriptors get `EBADF`.
+
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+
+/// WASI modules whose `fd_write` is recognized.
+const WASI_MODULES: [&str; 2] = ["wasi_snapshot_preview1", "wasi_unstable"];
+
+/// Import names recognized as `(ptr, len)` loggers, in any module.
+const LOG_NAMES: [&str; 3] = ["log", "print", "console_log"];
+
+/// The logging imports of `info`, by index into `func_imports`.
+pub fn detect(info: &ModuleInfo) -> BTreeMap<usize, GuestLogImport> {
+    use WasmType::I32;
+    let mut found = BTreeMap::new();
+    for (idx, imp) in info.func_imports.iter().enumerate() {
+        let shape = if WASI_MODULES.contains(&&*imp.module_name)
+            && &*imp.func_name == "fd_write"
+            && *imp.params == [I32, I32, I32, I32]
+            && *imp.results == [I32]
+        {
+            GuestLogImport::FdWrite
+        } else if LOG_NAMES.contains(&&*imp.func_name)
+            && *imp.params == [I32, I32]
+            && imp.results.is_empty()
+        {
+            GuestLogImport::PtrLen
+        } else {
+            continue;
+        };
+        found.insert(idx, shape);
+    }
+    found
+}
+
+/// Check that `info` fits `--guest-log` and return its logging imports.
+///
+/// # Errors
+/// Fails if the module has no logging import or no memory, if a logging
+/// import also has binding checks, or if another import would be a second
+/// `log` method next to `GuestLog::log`.
+pub fn check_guest_log(info: &ModuleInfo) -> Result<BTreeMap<usize, GuestLogImport>> {
+    let found = detect(info);
+    if found.is_empty() {
+        bail!("--guest-log found no logging import (`log(ptr, len)` or WASI `fd_write`)");
+    }
+    if !info.has_memory && !info.has_memory_import {
+        bail!("--guest-log needs a module with a memory");
+    }
+    for (idx, imp) in info.func_imports.iter().enumerate() {
+        if found.contains_key(&idx) {
+            if info.import_regions.contains_key(&idx) {
+                bail!(
+                    "import `{}.{}` has binding checks and cannot be routed to `GuestLog`",
+                    imp.module_name,
+                    imp.func_name
+                );
+            }
+        } else if rust_ident(&imp.func_name) == "log" {
+            bail!(
+                "import `{}.{}` collides with `GuestLog::log`",
+                imp.module_name,
+                imp.func_name
+            );
+        }
+    }
+    Ok(found)
+}
+
+/// Name of the shim of import `import_idx`.
+fn shim_name(import_idx: usize) -> String {
+    format!("guest_log_{import_idx}")
+}
+
+/// Call of the shim of import `import_idx` with `args`, or `None` when the
+/// import is not routed to `GuestLog`.
+pub fn guest_log_call(info: &ModuleInfo, import_idx: usize, args: &[String]) -> Option<String> {
+    info.guest_log.get(&import_idx)?;
+    let mut call_args = vec!["&mut *env.host".to_string(), "memory".to_string()];
+    call_args.extend(args.iter().cloned());
+    Some(format!(
+        "{}({})",
+        shim_name(import_idx),
+        call_args.join(", ")
+    ))
+}
+
+/// Generate the shim of every logging import.
+pub fn generate_guest_log_shims(info: &ModuleInfo) -> String {
+    let mut code = String::new();
+    for (&idx, shape) in &info.guest_log {
+        let Some(import) = info.func_imports.get(idx) else {
+            continue;
+        };
+        code.push_str(&format!(
+            "/// `{}.{}`, passed to `GuestLog::log`.\n",
+            import.module_name, import.func_name
+        ));
+        let name = shim_name(idx);
+        match shape {
+            GuestLogImport::PtrLen => {
+                code.push_str(&format!(
+                    "fn {name}<const M: usize, H: ModuleHostTrait + ?Sized>(host: &mut H, memory: &IsolatedMemory<M>, arg0: i32, arg1: i32) -> WasmResult<()> {{\n"
+                ));
+                code.push_str("    herkos_runtime::log_str(host, memory.as_slice(), arg0, arg1)\n");
+            }
+            GuestLogImport::FdWrite => {
+                code.push_str(&format!(
+                    "fn {name}<const M: usize, H: ModuleHostTrait + ?Sized>(host: &mut H, memory: &mut IsolatedMemory<M>, arg0: i32, arg1: i32, arg2: i32, arg3: i32) -> WasmResult<i32> {{\n"
+                ));
+                code.push_str(
+                    "    match herkos_runtime::log_iovecs(host, memory.as_slice(), arg0, arg1, arg2)? {\n",
+                );
+                code.push_str("        Some(written) => {\n");
+                code.push_str("            memory.store_i32(arg3 as u32 as usize, written)?;\n");
+                code.push_str("            Ok(0)\n");
+                code.push_str("        }\n");
+                code.push_str("        None => Ok(herkos_runtime::WASI_ERRNO_BADF),\n");
+                code.push_str("    }\n");
+            }
+        }
+        code.push_str("}\n\n");
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn guest_log(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            guest_log: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn logging_imports_move_to_guest_log() {
+        let code = guest_log(
+            r#"(module
+                (import "console" "log" (func $log (param i32 i32)))
+                (import "wasi_snapshot_preview1" "fd_write" (func $w (param i32 i32 i32 i32) (result i32)))
+                (import "env" "now" (func $now (result i64)))
+                (memory 1)
+                (func (export "run") (result i32)
+                    (call $log (i32.const 0) (i32.const 4))
+                    (drop (call $now))
+                    (call $w (i32.const 1) (i32.const 8) (i32.const 1) (i32.const 16))))"#,
+        )
+        .unwrap();
+        assert!(code.contains("pub trait ModuleHostTrait: GuestLog {\n"));
+        assert!(code.contains("    fn now(&mut self) -> WasmResult<i64>;\n"));
+        assert!(!code.contains("    fn log(&mut self"));
+        assert!(!code.contains("    fn fd_write(&mut self"));
+        assert!(code.contains("    guest_log_0(&mut *env.host, memory, v"));
+        assert!(code.contains("= guest_log_1(&mut *env.host, memory, "));
+        assert!(code.contains("fn guest_log_1<const M: usize, H: ModuleHostTrait + ?Sized>(host: &mut H, memory: &mut IsolatedMemory<M>, "));
+    }
+
+    #[test]
+    fn guest_log_rejects_modules_it_cannot_serve() {
+        let none = guest_log(
+            r#"(module (import "env" "tick" (func)) (memory 1)
+                (func (export "run") (call 0)))"#,
+        );
+        assert!(format!("{:#}", none.unwrap_err()).contains("found no logging import"));
+
+        let collision = guest_log(
+            r#"(module
+                (import "env" "log" (func (param i32)))
+                (import "console" "log" (func (param i32 i32)))
+                (memory 1))"#,
+        );
+        assert!(format!("{:#}", collision.unwrap_err()).contains("collides with `GuestLog::log`"));
+
+        let no_memory = guest_log(r#"(module (import "env" "log" (func (param i32 i32))))"#);
+        assert!(format!("{:#}", no_memory.unwrap_err()).contains("needs a module with a memory"));
+    }
+}
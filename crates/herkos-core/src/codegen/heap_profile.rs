@@ -0,0 +1,257 @@
+//! Heap profiling of the guest allocator (`--heap-profile`).
+//!
+//! The functions behind the `malloc`, `free`, `calloc` and `realloc`
+//! exports are generated under a `_untracked` name, and a wrapper with the
+//! original name records each outermost call in a
+//! `herkos_runtime::HeapProfile` stored in `Globals`. Calls from the host
+//! and from inside the guest both go through the wrapper:
+//!
+//! ```text
+//! fn func_3<H: ModuleHostTrait>(mut v0: i32, env: &mut Env<'_, H>, memory: ..) -> WasmResult<i32> {
+//!     let outermost = env.globals.heap_profile.enter();
+//!     let result = func_3_untracked(v0, env, memory);
+//!     env.globals.heap_profile.exit();
+//!     if outermost {
+//!         if let Ok(ptr) = result {
+//!             env.globals.heap_profile.record_alloc(ptr, v0 as u32 as u64);
+//!         }
+//!     }
+//!     result
+//! }
+//! ```
+
+use crate::backend::Backend;
+use crate::codegen::function::write_signature_with_info;
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+use anyhow::{bail, Result};
+
+/// `Globals` field holding the profile.
+pub const FIELD: &str = "heap_profile";
+
+/// Methods added to `WasmModule`.
+const METHODS: [&str; 2] = ["heap_stats", "heap_profile"];
+
+/// An allocator entry point, by its C signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocFn {
+    /// `void *malloc(size_t len)`
+    Malloc,
+    /// `void free(void *ptr)`
+    Free,
+    /// `void *calloc(size_t n, size_t len)`
+    Calloc,
+    /// `void *realloc(void *ptr, size_t len)`
+    Realloc,
+}
+
+impl AllocFn {
+    const ALL: [AllocFn; 4] = [
+        AllocFn::Malloc,
+        AllocFn::Free,
+        AllocFn::Calloc,
+        AllocFn::Realloc,
+    ];
+
+    fn export_name(self) -> &'static str {
+        match self {
+            AllocFn::Malloc => "malloc",
+            AllocFn::Free => "free",
+            AllocFn::Calloc => "calloc",
+            AllocFn::Realloc => "realloc",
+        }
+    }
+
+    fn signature(self) -> (&'static [WasmType], Option<WasmType>) {
+        use WasmType::I32;
+        match self {
+            AllocFn::Malloc => (&[I32], Some(I32)),
+            AllocFn::Free => (&[I32], None),
+            AllocFn::Calloc | AllocFn::Realloc => (&[I32, I32], Some(I32)),
+        }
+    }
+
+    /// Statement recording a successful call, given its parameters.
+    fn record(self, params: &[String]) -> String {
+        let profile = format!("env.globals.{FIELD}");
+        match self {
+            AllocFn::Malloc => format!("{profile}.record_alloc(ptr, {} as u32 as u64);", params[0]),
+            AllocFn::Free => format!("{profile}.record_free({});", params[0]),
+            AllocFn::Calloc => format!(
+                "{profile}.record_alloc(ptr, ({} as u32 as u64) * ({} as u32 as u64));",
+                params[0], params[1]
+            ),
+            AllocFn::Realloc => format!(
+                "{profile}.record_realloc({}, ptr, {} as u32 as u64);",
+                params[0], params[1]
+            ),
+        }
+    }
+}
+
+/// The local functions behind the allocator exports of `info`, with the
+/// entry point each implements.
+pub fn tracked_functions(info: &ModuleInfo) -> Vec<(LocalFuncIdx, AllocFn)> {
+    AllocFn::ALL
+        .into_iter()
+        .filter_map(|kind| {
+            let export = info
+                .func_exports
+                .iter()
+                .find(|e| e.name == kind.export_name())?;
+            let func = info.ir_function(export.func_index)?;
+            let params: Vec<WasmType> = func.params.iter().map(|(_, ty)| *ty).collect();
+            let (expected, result) = kind.signature();
+            (params == expected && func.results == result.as_slice())
+                .then_some((export.func_index, kind))
+        })
+        .collect()
+}
+
+/// Check that `info` can be heap-profiled.
+///
+/// # Errors
+/// Fails without `malloc` and `free` exports of the C signatures, or when
+/// the profile's field or methods collide with a global or an export.
+pub fn check_heap_profile(info: &ModuleInfo) -> Result<()> {
+    let tracked = tracked_functions(info);
+    for kind in [AllocFn::Malloc, AllocFn::Free] {
+        if !tracked.iter().any(|(_, k)| *k == kind) {
+            bail!(
+                "--heap-profile needs a `{}` export of the C signature",
+                kind.export_name()
+            );
+        }
+    }
+    if info.globals.iter().any(|g| g.field == FIELD) {
+        bail!("--heap-profile: a global already uses the `{FIELD}` field");
+    }
+    if let Some(export) = info
+        .func_exports
+        .iter()
+        .find(|e| METHODS.contains(&rust_ident(&e.name).as_str()))
+    {
+        bail!(
+            "--heap-profile: export `{}` collides with a heap profile method",
+            export.name
+        );
+    }
+    Ok(())
+}
+
+/// Name the body of a tracked function is generated under.
+pub fn untracked_name(func_name: &str) -> String {
+    format!("{func_name}_untracked")
+}
+
+/// Append the wrapper recording calls to `ir_func`, an allocator entry
+/// point generated as [`untracked_name`]`(func_name)`.
+pub fn write_tracking_wrapper<B: Backend>(
+    out: &mut String,
+    backend: &B,
+    ir_func: &IrFunction,
+    func_name: &str,
+    kind: AllocFn,
+    info: &ModuleInfo,
+) -> Result<()> {
+    let params: Vec<String> = ir_func.params.iter().map(|(v, _)| v.to_string()).collect();
+    let mut args = params.clone();
+    args.push("env".to_string());
+    if info.has_memory || info.has_memory_import {
+        args.push("memory".to_string());
+    }
+    if info.has_table_import || info.has_table() {
+        args.push("table".to_string());
+    }
+
+    out.push_str("#[allow(unused_mut, clippy::too_many_arguments)]\n");
+    write_signature_with_info(out, backend, ir_func, func_name, info, false, false)?;
+    out.push_str(" {\n");
+    out.push_str(&format!(
+        "    let outermost = env.globals.{FIELD}.enter();\n"
+    ));
+    out.push_str(&format!(
+        "    let result = {}({});\n",
+        untracked_name(func_name),
+        args.join(", ")
+    ));
+    out.push_str(&format!("    env.globals.{FIELD}.exit();\n"));
+    if kind == AllocFn::Free {
+        out.push_str("    if outermost && result.is_ok() {\n");
+        out.push_str(&format!("        {}\n", kind.record(&params)));
+    } else {
+        out.push_str("    if outermost {\n");
+        out.push_str("        if let Ok(ptr) = result {\n");
+        out.push_str(&format!("            {}\n", kind.record(&params)));
+        out.push_str("        }\n");
+    }
+    out.push_str("    }\n");
+    out.push_str("    result\n");
+    out.push_str("}\n");
+    Ok(())
+}
+
+/// Generate the `heap_stats` and `heap_profile` accessors.
+///
+/// Returns an empty string unless heap profiling was requested.
+pub fn generate_heap_stats(info: &ModuleInfo) -> String {
+    if !info.heap_profile {
+        return String::new();
+    }
+    let mut code = String::from("impl WasmModule {\n");
+    code.push_str("    /// Live and peak bytes and call counts of the guest allocator.\n");
+    code.push_str("    pub fn heap_stats(&self) -> HeapStats {\n");
+    code.push_str(&format!("        self.0.globals.{FIELD}.stats()\n"));
+    code.push_str("    }\n\n");
+    code.push_str("    /// The blocks the guest allocator handed out and did not get back.\n");
+    code.push_str("    pub fn heap_profile(&self) -> &HeapProfile {\n");
+    code.push_str(&format!("        &self.0.globals.{FIELD}\n"));
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_profiled(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            heap_profile: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn allocator_exports_are_wrapped() {
+        let code = transpile_profiled(
+            r#"(module
+                (memory 1 1)
+                (func (export "malloc") (param i32) (result i32) i32.const 8)
+                (func (export "free") (param i32))
+                (func (export "realloc") (param i32 i32) (result i32) i32.const 16))"#,
+        )
+        .unwrap();
+        assert!(code.contains("pub heap_profile: HeapProfile,"));
+        assert!(code.contains("heap_profile: HeapProfile::new()"));
+        assert!(code.contains("fn func_0_untracked<H: ModuleHostTrait>("));
+        assert!(code.contains("let result = func_0_untracked(v0, env, memory);"));
+        assert!(code.contains("env.globals.heap_profile.record_alloc(ptr, v0 as u32 as u64);"));
+        assert!(code.contains("env.globals.heap_profile.record_free(v0);"));
+        assert!(
+            code.contains("env.globals.heap_profile.record_realloc(v0, ptr, v1 as u32 as u64);")
+        );
+        assert!(code.contains("pub fn heap_stats(&self) -> HeapStats {"));
+    }
+
+    #[test]
+    fn modules_without_an_allocator_are_rejected() {
+        let err = transpile_profiled(
+            r#"(module (memory 1) (func (export "malloc") (param i32) (result i32) i32.const 8))"#,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("needs a `free` export"));
+    }
+}
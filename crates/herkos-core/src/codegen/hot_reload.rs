@@ -0,0 +1,214 @@
+//! Hot-reloadable modules (`--hot-reload`).
+//!
+//! The module implements `herkos_runtime::HotPlugin` — created fresh,
+//! called by name through `invoke`, its memory and mutable globals read and
+//! restored as raw bits — and emits the C-ABI vtable a `cdylib` exports with
+//! `herkos_runtime::export_hot_vtable!`. Imports go back to the host through
+//! `herkos_runtime::HotHost`:
+//!
+//! ```text
+//! impl herkos_runtime::HotPlugin for WasmModule {
+//!     const GLOBALS: usize = 1;
+//!     ..
+//!     fn global(&self, index: usize) -> u64 {
+//!         match index {
+//!             0 => self.0.globals.count as u32 as u64,
+//!             _ => 0,
+//!         }
+//!     }
+//!     ..
+//! }
+//!
+//! pub static HOT_VTABLE: herkos_runtime::HotVTable = herkos_runtime::HotVTable::of::<WasmModule>();
+//! ```
+
+use crate::codegen::link::generate_dynamic_host;
+use crate::ir::*;
+use anyhow::{bail, Result};
+
+/// Check that `info` can be reloaded in place.
+///
+/// # Errors
+/// Fails for modules whose state is partly the host's (imported memory,
+/// table or globals), or that need a host beyond their imports.
+pub fn check_hot_reload(info: &ModuleInfo) -> Result<()> {
+    // Instances are created and called with nothing but their arguments
+    if info.has_memory_import || info.has_table_import {
+        bail!("--hot-reload needs a module that defines its own memory and table");
+    }
+    if let Some(g) = info.imported_globals.first() {
+        bail!(
+            "--hot-reload does not support imported globals (`{}.{}`)",
+            g.module_name,
+            g.name
+        );
+    }
+    if info.start_uses_host() {
+        bail!("--hot-reload does not support start functions that call the host");
+    }
+    if info.observe_memory {
+        bail!("--hot-reload cannot be combined with --observe-memory");
+    }
+    if crate::analysis::detect_tinygo(info).is_some_and(|t| t.uses_clock) {
+        bail!("--hot-reload does not support TinyGo modules that use the scheduler clock");
+    }
+    Ok(())
+}
+
+/// Generate the `HotPlugin` impl and `HOT_VTABLE`.
+///
+/// Returns an empty string unless hot reloading was requested.
+pub fn generate_hot_reload(info: &ModuleInfo) -> String {
+    if !info.hot_reload {
+        return String::new();
+    }
+    let globals: Vec<&GlobalDef> = info.globals.iter().filter(|g| g.mutable).collect();
+    let host_arg = if info.uses_host() { ", host" } else { "" };
+    let host_param = if info.uses_host() { "host" } else { "_host" };
+
+    let mut code = String::new();
+    if info.uses_host() {
+        code.push_str(&generate_dynamic_host(info, "herkos_runtime::HotHost<'_>"));
+    }
+    code.push_str("impl herkos_runtime::HotPlugin for WasmModule {\n");
+    code.push_str(&format!(
+        "    const GLOBALS: usize = {};\n\n",
+        globals.len()
+    ));
+    code.push_str("    fn create() -> WasmResult<Self> {\n");
+    code.push_str("        new()\n");
+    code.push_str("    }\n\n");
+    code.push_str(&format!(
+        "    fn call(&mut self, name: &str, args: &[herkos_runtime::Value], {host_param}: &mut herkos_runtime::HotHost<'_>) -> WasmResult<Option<herkos_runtime::Value>> {{\n"
+    ));
+    code.push_str(&format!("        self.invoke(name, args{host_arg})\n"));
+    code.push_str("    }\n\n");
+
+    let (memory, memory_mut, delta, grow) = if info.has_memory {
+        (
+            "self.0.memory.as_slice()",
+            "self.0.memory.as_mut_slice()",
+            "delta",
+            "self.0.memory.grow(delta)",
+        )
+    } else {
+        ("&[]", "&mut []", "_delta", "-1")
+    };
+    code.push_str("    fn memory(&self) -> &[u8] {\n");
+    code.push_str(&format!("        {memory}\n"));
+    code.push_str("    }\n\n");
+    code.push_str("    fn memory_mut(&mut self) -> &mut [u8] {\n");
+    code.push_str(&format!("        {memory_mut}\n"));
+    code.push_str("    }\n\n");
+    code.push_str(&format!(
+        "    fn grow_memory(&mut self, {delta}: u32) -> i32 {{\n"
+    ));
+    code.push_str(&format!("        {grow}\n"));
+    code.push_str("    }\n\n");
+
+    code.push_str("    fn global(&self, index: usize) -> u64 {\n");
+    code.push_str("        match index {\n");
+    for (index, global) in globals.iter().enumerate() {
+        let field = format!("self.0.globals.{}", global.field);
+        let bits = match global.init_value.ty() {
+            WasmType::I32 => format!("{field} as u32 as u64"),
+            WasmType::I64 => format!("{field} as u64"),
+            WasmType::F32 => format!("{field}.to_bits() as u64"),
+            WasmType::F64 => format!("{field}.to_bits()"),
+        };
+        code.push_str(&format!("            {index} => {bits},\n"));
+    }
+    code.push_str("            _ => 0,\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n\n");
+
+    code.push_str("    fn set_global(&mut self, index: usize, bits: u64) {\n");
+    code.push_str("        match index {\n");
+    for (index, global) in globals.iter().enumerate() {
+        let value = match global.init_value.ty() {
+            WasmType::I32 => "bits as u32 as i32",
+            WasmType::I64 => "bits as i64",
+            WasmType::F32 => "f32::from_bits(bits as u32)",
+            WasmType::F64 => "f64::from_bits(bits)",
+        };
+        code.push_str(&format!(
+            "            {index} => self.0.globals.{} = {value},\n",
+            global.field
+        ));
+    }
+    code.push_str("            _ => {}\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+
+    code.push_str(
+        "/// Entry points of this build, exported from a `cdylib` with\n\
+         /// `herkos_runtime::export_hot_vtable!` and loaded by a `herkos_runtime::HotModule`.\n",
+    );
+    code.push_str(
+        "pub static HOT_VTABLE: herkos_runtime::HotVTable = herkos_runtime::HotVTable::of::<WasmModule>();\n\n",
+    );
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_hot(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            hot_reload: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn state_and_imports_cross_the_vtable() {
+        let code = transpile_hot(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (memory 1)
+                (global $count (mut i32) (i32.const 0))
+                (global $scale (mut f64) (f64.const 1.5))
+                (global $k i32 (i32.const 7))
+                (func (export "bump") (result i32)
+                    (global.set $count (i32.add (global.get $count) (global.get $k)))
+                    (call $log (global.get $count))
+                    (global.get $count)))"#,
+        )
+        .unwrap();
+        assert!(code.contains("impl ModuleHostTrait for herkos_runtime::HotHost<'_> {"));
+        assert!(code.contains("const GLOBALS: usize = 2;"));
+        assert!(code.contains("self.invoke(name, args, host)"));
+        assert!(code.contains("0 => self.0.globals.count as u32 as u64,"));
+        assert!(code.contains("1 => self.0.globals.scale.to_bits(),"));
+        assert!(code.contains("0 => self.0.globals.count = bits as u32 as i32,"));
+        assert!(code.contains("1 => self.0.globals.scale = f64::from_bits(bits),"));
+        assert!(code.contains("self.0.memory.grow(delta)"));
+        assert!(code.contains("pub static HOT_VTABLE: herkos_runtime::HotVTable"));
+        // Called by name
+        assert!(code.contains("pub fn invoke<H: ModuleHostTrait>"));
+    }
+
+    #[test]
+    fn modules_without_memory_or_imports() {
+        let code = transpile_hot(r#"(module (func (export "seven") (result i32) (i32.const 7)))"#)
+            .unwrap();
+        assert!(code.contains("const GLOBALS: usize = 0;"));
+        assert!(code.contains("_host: &mut herkos_runtime::HotHost<'_>"));
+        assert!(code.contains("self.invoke(name, args)\n"));
+        assert!(code.contains("fn memory(&self) -> &[u8] {\n        &[]\n"));
+        assert!(!code.contains("for herkos_runtime::HotHost"));
+    }
+
+    #[test]
+    fn host_owned_state_is_rejected() {
+        let err = transpile_hot(r#"(module (import "env" "memory" (memory 1)))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("defines its own memory and table"));
+
+        let err = transpile_hot(r#"(module (import "env" "g" (global i32)))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("imported globals (`env.g`)"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Checked host calls for imports with pointer arguments.
+//!
+//! `import` lines of the bindings file (see [`crate::bindings`]) say which
+//! arguments of a host import point into linear memory. Each such import gets
+//! a shim that checks the regions before calling the host, and every call
+//! site — direct or through the table — goes through it, so a host never
+//! receives a pointer that reaches past the end of memory:
+//!
+//! ```text
+//! fn checked_write<const M: usize, H: ModuleHostTrait + ?Sized>(host: &mut H, memory: &IsolatedMemory<M>, arg0: i32, arg1: i32, arg2: i32) -> WasmResult<i32> {
+//!     cabi::guest_range(memory.as_slice(), arg1, arg2, 1, 1)?;
+//!     host.write(arg0, arg1, arg2)
+//! }
+//!
+//!                 v6 = checked_write(&mut *env.host, memory, v3, v4, v5)?;
+//! ```
+
+use crate::codegen::types::{format_return_type, wasm_type_to_rust};
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+
+/// Name of the shim of `func_name`.
+fn shim_name(func_name: &str) -> String {
+    format!("checked_{}", rust_ident(func_name))
+}
+
+/// Call of the shim of import `import_idx` with `args`, or `None` when the
+/// import has no checked arguments.
+pub fn checked_call(info: &ModuleInfo, import_idx: usize, args: &[String]) -> Option<String> {
+    info.import_regions.get(&import_idx)?;
+    let import = info.func_imports.get(import_idx)?;
+    let mut call_args = vec!["&mut *env.host".to_string(), "memory".to_string()];
+    call_args.extend(args.iter().cloned());
+    Some(format!(
+        "{}({})",
+        shim_name(&import.func_name),
+        call_args.join(", ")
+    ))
+}
+
+/// Generate the shims of every import with checked arguments.
+pub fn generate_import_checks(info: &ModuleInfo) -> String {
+    let mut code = String::new();
+    for (&idx, regions) in &info.import_regions {
+        let Some(import) = info.func_imports.get(idx) else {
+            continue;
+        };
+        let names: Vec<String> = regions.iter().map(|r| format!("`{}`", r.name)).collect();
+        code.push_str(&format!(
+            "/// `{}.{}` with the guest memory behind {} checked first.\n",
+            import.module_name,
+            import.func_name,
+            names.join(", ")
+        ));
+        let mut params = vec![
+            "host: &mut H".to_string(),
+            "memory: &IsolatedMemory<M>".to_string(),
+        ];
+        let mut args = Vec::new();
+        for (i, ty) in import.params.iter().enumerate() {
+            params.push(format!("arg{i}: {}", wasm_type_to_rust(ty)));
+            args.push(format!("arg{i}"));
+        }
+        code.push_str(&format!(
+            "fn {}<const M: usize, H: ModuleHostTrait + ?Sized>({}) -> {} {{\n",
+            shim_name(&import.func_name),
+            params.join(", "),
+            format_return_type(&import.results)
+        ));
+        for region in regions {
+            let len = match region.len {
+                RegionLen::Param(param) => format!("arg{param}"),
+                RegionLen::Fixed(len) => len.to_string(),
+            };
+            code.push_str(&format!(
+                "    cabi::guest_range(memory.as_slice(), arg{}, {len}, 1, 1)?;\n",
+                region.ptr
+            ));
+        }
+        code.push_str(&format!(
+            "    host.{}({})\n",
+            rust_ident(&import.func_name),
+            args.join(", ")
+        ));
+        code.push_str("}\n\n");
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bindings::BindingConfig;
+    use crate::{transpile, TranspileOptions};
+
+    const WAT: &str = r#"(module
+        (import "env" "write" (func $write (param i32 i32 i32) (result i32)))
+        (import "env" "now" (func $now (param i32)))
+        (memory 1)
+        (table 1 funcref)
+        (elem (i32.const 0) $write)
+        (func (export "run") (param i32 i32) (result i32)
+            (call $now (i32.const 0))
+            (drop (call_indirect (param i32 i32 i32) (result i32)
+                (i32.const 1) (local.get 0) (local.get 1) (i32.const 0)))
+            (call $write (i32.const 1) (local.get 0) (local.get 1))))"#;
+
+    fn transpile_checked(bindings: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let options = TranspileOptions {
+            bindings: BindingConfig::parse(bindings).unwrap(),
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn host_calls_go_through_the_shims() {
+        let code =
+            transpile_checked("import env.write(fd: i32, buf: &[u8])\nimport env.now(at: out i64)")
+                .unwrap();
+        assert!(code.contains("/// `env.write` with the guest memory behind `buf` checked first.\nfn checked_write<const M: usize, H: ModuleHostTrait + ?Sized>(host: &mut H, memory: &IsolatedMemory<M>, arg0: i32, arg1: i32, arg2: i32) -> WasmResult<i32> {\n    cabi::guest_range(memory.as_slice(), arg1, arg2, 1, 1)?;\n    host.write(arg0, arg1, arg2)\n}"));
+        assert!(code.contains("    cabi::guest_range(memory.as_slice(), arg0, 8, 1, 1)?;\n"));
+        assert!(code.contains("checked_now(&mut *env.host, memory, "));
+        // Direct and table calls alike
+        assert_eq!(
+            code.matches("checked_write(&mut *env.host, memory, ")
+                .count(),
+            2
+        );
+        assert!(!code.contains("env.host.write("));
+    }
+
+    #[test]
+    fn imports_without_pointers_are_called_directly() {
+        let code = transpile_checked("import env.write(fd: i32, len: i32, n: i32)").unwrap();
+        assert!(!code.contains("checked_write"));
+        assert!(code.contains("env.host.write("));
+    }
+
+    #[test]
+    fn bindings_must_match_the_import() {
+        for (bindings, expected) in [
+            ("import env.read(buf: &[u8])", "no such function import"),
+            ("import env.write(buf: &[u8])", "but the import takes"),
+        ] {
+            let err = transpile_checked(bindings).unwrap_err();
+            assert!(format!("{err:#}").contains(expected), "{bindings}: {err:#}");
+        }
+    }
+}
@@ -7,19 +7,49 @@ use crate::backend::Backend;
 use crate::ir::*;
 use anyhow::Result;
 use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Whether `instr` writes back the value `prev` just read from the same
+/// place (`v1 = g; g = v1;`, `v1 = v2; v2 = v1;`), which changes nothing and
+/// reads to clippy as a botched swap. Host setters of imported globals are
+/// calls the host observes, so they are never write-backs.
+pub fn is_write_back(prev: Option<&IrInstr>, instr: &IrInstr, info: &ModuleInfo) -> bool {
+    match (prev, instr) {
+        (
+            Some(IrInstr::GlobalGet { dest, index }),
+            IrInstr::GlobalSet {
+                index: set_index,
+                value,
+            },
+        ) => {
+            dest == value
+                && index == set_index
+                && matches!(info.resolve_global(*index), ResolvedGlobal::Local(..))
+        }
+        (
+            Some(IrInstr::Assign { dest, src }),
+            IrInstr::Assign {
+                dest: back,
+                src: from,
+            },
+        ) => back == src && from == dest,
+        _ => false,
+    }
+}
 
-/// Generate code for a single instruction with module info.
-pub fn generate_instruction_with_info<B: Backend>(
+/// Append the code for a single instruction to `out` (no trailing newline).
+pub fn write_instruction<B: Backend>(
+    out: &mut String,
     backend: &B,
     instr: &IrInstr,
     info: &ModuleInfo,
-) -> Result<String> {
-    let code = match instr {
-        IrInstr::Const { dest, value } => backend.emit_const(*dest, value),
+) -> Result<()> {
+    let result = match instr {
+        IrInstr::Const { dest, value } => backend.emit_const(out, *dest, value),
 
-        IrInstr::BinOp { dest, op, lhs, rhs } => backend.emit_binop(*dest, *op, *lhs, *rhs),
+        IrInstr::BinOp { dest, op, lhs, rhs } => backend.emit_binop(out, *dest, *op, *lhs, *rhs),
 
-        IrInstr::UnOp { dest, op, operand } => backend.emit_unop(*dest, *op, *operand),
+        IrInstr::UnOp { dest, op, operand } => backend.emit_unop(out, *dest, *op, *operand),
 
         IrInstr::Load {
             dest,
@@ -28,7 +58,12 @@ pub fn generate_instruction_with_info<B: Backend>(
             offset,
             width,
             sign,
-        } => return backend.emit_load(*dest, *ty, *addr, *offset, *width, *sign),
+        } => {
+            if info.observe_memory {
+                write_observe(out, "on_load", *addr, *offset, width.bytes(*ty))?;
+            }
+            return backend.emit_load(out, *dest, *ty, *addr, *offset, *width, *sign);
+        }
 
         IrInstr::Store {
             ty,
@@ -36,81 +71,129 @@ pub fn generate_instruction_with_info<B: Backend>(
             value,
             offset,
             width,
-        } => return backend.emit_store(*ty, *addr, *value, *offset, *width),
+        } => {
+            if info.observe_memory {
+                write_observe(out, "on_store", *addr, *offset, width.bytes(*ty))?;
+            }
+            return backend.emit_store(out, *ty, *addr, *value, *offset, *width);
+        }
 
         IrInstr::Call {
-            dest,
+            dests,
             func_idx,
             args,
         } => {
             // Call to local function (imports are handled by CallImport)
-            let has_memory = info.has_memory;
+            let has_memory = info.has_memory || info.has_memory_import;
             let has_table = info.has_table();
-            backend.emit_call(*dest, func_idx.as_usize(), args, has_memory, has_table)
+            let func = info.func_ident(func_idx.as_usize());
+            backend.emit_call(out, dests, &func, args, has_memory, has_table)
         }
 
         IrInstr::CallImport {
-            dest,
+            dests,
+            import_idx,
             module_name,
             func_name,
             args,
-            ..
-        } => backend.emit_call_import(*dest, module_name, func_name, args),
+        } => {
+            let arg_names: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            let call = super::import_checks::checked_call(info, import_idx.as_usize(), &arg_names)
+                .or_else(|| {
+                    super::guest_log::guest_log_call(info, import_idx.as_usize(), &arg_names)
+                });
+            match call {
+                Some(call) => match super::utils::format_vars(dests) {
+                    Some(dests) => write!(out, "                {dests} = {call}?;"),
+                    None => write!(out, "                {call}?;"),
+                },
+                None => backend.emit_call_import(out, dests, module_name, func_name, args),
+            }
+        }
 
         IrInstr::CallIndirect {
-            dest,
+            dests,
             type_idx,
             table_idx,
             args,
-        } => generate_call_indirect(*dest, type_idx.clone(), *table_idx, args, info),
+        } => super::dispatch::write_dispatch_call(out, dests, type_idx, *table_idx, args, info),
 
-        IrInstr::Assign { dest, src } => backend.emit_assign(*dest, *src),
+        IrInstr::Assign { dest, src } => backend.emit_assign(out, *dest, *src),
 
         IrInstr::GlobalGet { dest, index } => match info.resolve_global(*index) {
             ResolvedGlobal::Imported(_idx, g) => {
-                format!("                {} = env.host.get_{}();", dest, g.name)
+                write!(out, "                {} = env.host.get_{}();", dest, g.name)
             }
             ResolvedGlobal::Local(idx, g) => {
-                let is_mutable = g.mutable;
-                backend.emit_global_get(*dest, idx.as_usize(), is_mutable)
+                backend.emit_global_get(out, *dest, idx.as_usize(), &g.field, !g.is_const())
             }
         },
 
         IrInstr::GlobalSet { index, value } => match info.resolve_global(*index) {
             ResolvedGlobal::Imported(_idx, g) => {
-                format!("                env.host.set_{}({});", g.name, value)
+                write!(out, "                env.host.set_{}({});", g.name, value)
+            }
+            ResolvedGlobal::Local(idx, g) => {
+                backend.emit_global_set(out, idx.as_usize(), &g.field, *value)
             }
-            ResolvedGlobal::Local(idx, _g) => backend.emit_global_set(idx.as_usize(), *value),
         },
 
-        IrInstr::MemorySize { dest } => backend.emit_memory_size(*dest),
+        IrInstr::MemorySize { dest } => backend.emit_memory_size(out, *dest),
 
-        IrInstr::MemoryGrow { dest, delta } => backend.emit_memory_grow(*dest, *delta),
+        IrInstr::MemoryGrow { dest, delta } => {
+            let max_pages = info
+                .memory_import_max_pages
+                .filter(|_| info.has_memory_import);
+            backend.emit_memory_grow(out, *dest, *delta, max_pages)
+        }
 
-        IrInstr::MemoryCopy { dst, src, len } => backend.emit_memory_copy(*dst, *src, *len),
+        IrInstr::MemoryCopy { dst, src, len } => {
+            if info.observe_memory {
+                write_observe_bulk(out, "on_load", *src, *len)?;
+                write_observe_bulk(out, "on_store", *dst, *len)?;
+            }
+            backend.emit_memory_copy(out, *dst, *src, *len)
+        }
 
-        IrInstr::MemoryFill { dst, val, len } => backend.emit_memory_fill(*dst, *val, *len),
+        IrInstr::MemoryFill { dst, val, len } => {
+            if info.observe_memory {
+                write_observe_bulk(out, "on_store", *dst, *len)?;
+            }
+            backend.emit_memory_fill(out, *dst, *val, *len)
+        }
 
         IrInstr::MemoryInit {
             dst,
             src_offset,
             len,
             segment,
-        } => backend.emit_memory_init(
-            *dst,
-            *src_offset,
-            *len,
-            &format!("PASSIVE_SEGMENT_{segment}"),
-        ),
+        } => {
+            if info.observe_memory {
+                write_observe_bulk(out, "on_store", *dst, *len)?;
+            }
+            backend.emit_memory_init(
+                out,
+                *dst,
+                *src_offset,
+                *len,
+                &format!("PASSIVE_SEGMENT_{segment}"),
+            )
+        }
 
-        IrInstr::DataDrop { segment } => backend.emit_data_drop(*segment),
+        IrInstr::DataDrop { segment } => backend.emit_data_drop(out, *segment),
+
+        // CSE after phi lowering can leave both operands the same variable,
+        // which clippy flags as an `if` with identical blocks
+        IrInstr::Select {
+            dest, val1, val2, ..
+        } if val1 == val2 => backend.emit_assign(out, *dest, *val1),
 
         IrInstr::Select {
             dest,
             val1,
             val2,
             condition,
-        } => backend.emit_select(*dest, *val1, *val2, *condition),
+        } => backend.emit_select(out, *dest, *val1, *val2, *condition),
 
         // Phi nodes must be lowered to Assign instructions by the lower_phis pass
         // before codegen runs. Reaching this arm is a compiler bug.
@@ -120,30 +203,62 @@ pub fn generate_instruction_with_info<B: Backend>(
             )
         }
     };
-    Ok(code)
+    Ok(result?)
 }
 
-/// Generate code for a terminator with BlockId to index mapping.
-pub fn generate_terminator_with_mapping<B: Backend>(
+/// `env.host.{method}(addr, len);` line reporting a load or store at
+/// `addr + offset` to the host's `MemoryObserver` (`--observe-memory`).
+fn write_observe(
+    out: &mut String,
+    method: &str,
+    addr: VarId,
+    offset: u32,
+    len: u32,
+) -> std::fmt::Result {
+    if offset == 0 {
+        writeln!(
+            out,
+            "                env.host.{method}({addr} as u32 as usize, {len});"
+        )
+    } else {
+        writeln!(
+            out,
+            "                env.host.{method}(({addr} as u32 as usize).wrapping_add({offset}), {len});"
+        )
+    }
+}
+
+/// Like [`write_observe`], for a bulk memory operation of `len` bytes.
+fn write_observe_bulk(out: &mut String, method: &str, addr: VarId, len: VarId) -> std::fmt::Result {
+    writeln!(
+        out,
+        "                env.host.{method}({addr} as u32 as usize, {len} as u32 as usize);"
+    )
+}
+
+/// Append the code for a terminator to `out`, using the BlockId to index mapping.
+pub fn write_terminator<B: Backend>(
+    out: &mut String,
     backend: &B,
     term: &IrTerminator,
     block_id_to_index: &HashMap<BlockId, usize>,
-    func_return_type: Option<WasmType>,
-) -> String {
-    match term {
-        IrTerminator::Return { value } => {
-            // If the function has a return type but the return has no value,
-            // this is dead code after `unreachable` — emit a trap instead
-            // of `return Ok(())` which would be a type mismatch.
-            if value.is_none() && func_return_type.is_some() {
-                return backend.emit_unreachable();
+    func_results: &[WasmType],
+) -> Result<()> {
+    let result = match term {
+        IrTerminator::Return { values } => {
+            // If the function has results but the return carries fewer
+            // values, this is dead code after `unreachable` — emit a trap
+            // instead of a `return` which would be a type mismatch.
+            if values.len() != func_results.len() {
+                backend.emit_unreachable(out)
+            } else {
+                backend.emit_return(out, values)
             }
-            backend.emit_return(*value)
         }
 
         IrTerminator::Jump { target } => {
             let idx = block_id_to_index[target];
-            backend.emit_jump_to_index(idx)
+            backend.emit_jump_to_index(out, idx)
         }
 
         IrTerminator::BranchIf {
@@ -153,7 +268,7 @@ pub fn generate_terminator_with_mapping<B: Backend>(
         } => {
             let true_idx = block_id_to_index[if_true];
             let false_idx = block_id_to_index[if_false];
-            backend.emit_branch_if_to_index(*condition, true_idx, false_idx)
+            backend.emit_branch_if_to_index(out, *condition, true_idx, false_idx)
         }
 
         IrTerminator::BranchTable {
@@ -163,83 +278,10 @@ pub fn generate_terminator_with_mapping<B: Backend>(
         } => {
             let target_indices: Vec<usize> = targets.iter().map(|t| block_id_to_index[t]).collect();
             let default_idx = block_id_to_index[default];
-            backend.emit_branch_table_to_index(*index, &target_indices, default_idx)
+            backend.emit_branch_table_to_index(out, *index, &target_indices, default_idx)
         }
 
-        IrTerminator::Unreachable => backend.emit_unreachable(),
-    }
-}
-
-/// Generate inline dispatch code for `call_indirect`.
-///
-/// The generated code:
-/// 1. Looks up the table entry by index
-/// 2. Checks the type signature matches
-/// 3. Dispatches to the matching function via a match on func_index
-///
-/// All dispatch arms uniformly pass `env` to the target functions.
-fn generate_call_indirect(
-    dest: Option<VarId>,
-    type_idx: TypeIdx,
-    table_idx: VarId,
-    args: &[VarId],
-    info: &ModuleInfo,
-) -> String {
-    let has_memory = info.has_memory;
-    let has_table = info.has_table();
-
-    // Canonicalize the type index for structural equivalence (Wasm spec §4.4.9).
-    // Two different type indices with identical (params, results) must match.
-    let type_idx_usize = type_idx.as_usize();
-    let canon_idx = info
-        .canonical_type
-        .get(type_idx_usize)
-        .copied()
-        .unwrap_or(type_idx_usize);
-
-    let mut code = String::new();
-
-    // Look up the table entry
-    code.push_str(&format!(
-        "                let __entry = table.get({table_idx} as u32)?;\n"
-    ));
-
-    // Type check (compares canonical indices — FuncRef.type_index is
-    // also stored as canonical during element segment initialization)
-    code.push_str(&format!(
-        "                if __entry.type_index != {canon_idx} {{ return Err(WasmTrap::IndirectCallTypeMismatch); }}\n"
-    ));
-
-    // Build dispatch match — only dispatch to functions with matching
-    // canonical type (structural equivalence)
-    let dest_prefix = match dest {
-        Some(d) => format!("{d} = "),
-        None => String::new(),
+        IrTerminator::Unreachable => backend.emit_unreachable(out),
     };
-
-    code.push_str(&format!(
-        "                {dest_prefix}match __entry.func_index {{\n"
-    ));
-
-    for (func_idx, ir_func) in info.ir_functions.iter().enumerate() {
-        if ir_func.type_idx.as_usize() == canon_idx {
-            // All arms uniformly: wasm args + env + memory + table
-            let mut arm_base: Vec<String> = args.iter().map(|a| a.to_string()).collect();
-            arm_base.push("env".to_string());
-
-            let arm_call_args = crate::codegen::utils::build_inner_call_args(
-                &arm_base, has_memory, "memory", has_table, "table",
-            );
-            let arm_args_str = arm_call_args.join(", ");
-            code.push_str(&format!(
-                "                    {} => func_{}({})?,\n",
-                func_idx, func_idx, arm_args_str
-            ));
-        }
-    }
-
-    code.push_str("                    _ => return Err(WasmTrap::UndefinedElement),\n");
-    code.push_str("                };");
-
-    code
+    Ok(result?)
 }
@@ -0,0 +1,157 @@
+//! Dynamic dispatch over exports by name.
+//!
+//! Generates `WasmModule::invoke`, which matches the export name and the
+//! types of the `herkos_runtime::Value` arguments and forwards to the typed
+//! export method:
+//!
+//! ```text
+//! ("add", &[herkos_runtime::Value::I32(v0), herkos_runtime::Value::I32(v1)]) => {
+//!     self.add(v0, v1, host).map(|r| Some(herkos_runtime::Value::from(r)))
+//! }
+//! ("add", _) => Err(WasmTrap::InvokeTypeMismatch),
+//! ```
+
+use crate::codegen::utils::{export_cfg_attr, rust_ident, ForwardedState};
+use crate::ir::*;
+
+/// Generate the `invoke` method.
+///
+/// Returns an empty string unless dynamic invocation was requested.
+pub fn generate_invoke(info: &ModuleInfo) -> String {
+    if !info.dynamic_invoke {
+        return String::new();
+    }
+    let forwarded = ForwardedState::new(info);
+
+    let mut params = vec![
+        "&mut self".to_string(),
+        "name: &str".to_string(),
+        "args: &[herkos_runtime::Value]".to_string(),
+    ];
+    params.extend(forwarded.params.iter().map(|p| p.to_string()));
+
+    let mut code = String::from("impl WasmModule {\n");
+    code.push_str("    /// Call function export `name` with dynamically typed arguments.\n");
+    code.push_str("    ///\n");
+    code.push_str("    /// # Errors\n");
+    code.push_str("    /// Traps with `WasmTrap::UnknownExport` if there is no such export,\n");
+    code.push_str("    /// `WasmTrap::InvokeTypeMismatch` if `args` do not match its\n");
+    code.push_str("    /// parameters, or with whatever the export itself traps with.\n");
+    code.push_str(&format!(
+        "    pub fn invoke{}({}) -> WasmResult<Option<herkos_runtime::Value>> {{\n",
+        forwarded.generic_part(),
+        params.join(", ")
+    ));
+    code.push_str("        match (name, args) {\n");
+    for export in &info.func_exports {
+        let Some(func) = info.ir_function(export.func_index) else {
+            continue;
+        };
+        let pattern: Vec<String> = func
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, (_, ty))| format!("herkos_runtime::Value::{}(v{i})", value_variant(ty)))
+            .collect();
+        let mut args: Vec<String> = (0..pattern.len()).map(|i| format!("v{i}")).collect();
+        args.extend(forwarded.args.iter().map(|a| a.to_string()));
+        let result = if !func.results.is_empty() {
+            ".map(|r| Some(herkos_runtime::Value::from(r)))"
+        } else {
+            ".map(|()| None)"
+        };
+        let gate = export_cfg_attr("            ", info, &export.name);
+
+        code.push_str(&gate);
+        code.push_str(&format!(
+            "            ({:?}, &[{}]) => {{\n",
+            export.name,
+            pattern.join(", ")
+        ));
+        code.push_str(&format!(
+            "                self.{}({}){result}\n",
+            rust_ident(&export.name),
+            args.join(", ")
+        ));
+        code.push_str("            }\n");
+        code.push_str(&gate);
+        code.push_str(&format!(
+            "            ({:?}, _) => Err(WasmTrap::InvokeTypeMismatch),\n",
+            export.name
+        ));
+    }
+    code.push_str("            _ => Err(WasmTrap::UnknownExport),\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+    code
+}
+
+/// `herkos_runtime::Value` variant holding a `ty`.
+fn value_variant(ty: &WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "I32",
+        WasmType::I64 => "I64",
+        WasmType::F32 => "F32",
+        WasmType::F64 => "F64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_invoke(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            dynamic_invoke: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options).unwrap()
+    }
+
+    #[test]
+    fn matches_names_and_argument_types() {
+        let code = transpile_invoke(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (func (export "add") (param i32 f64) (result f64)
+                    local.get 1)
+                (func (export "tick")))"#,
+        );
+        assert!(code.contains(
+            "pub fn invoke<H: ModuleHostTrait>(&mut self, name: &str, args: &[herkos_runtime::Value], host: &mut H) -> WasmResult<Option<herkos_runtime::Value>> {"
+        ));
+        assert!(code.contains(
+            "(\"add\", &[herkos_runtime::Value::I32(v0), herkos_runtime::Value::F64(v1)]) => {"
+        ));
+        assert!(
+            code.contains("self.add(v0, v1, host).map(|r| Some(herkos_runtime::Value::from(r)))")
+        );
+        assert!(code.contains("(\"add\", _) => Err(WasmTrap::InvokeTypeMismatch),"));
+        assert!(code.contains("(\"tick\", &[]) => {"));
+        assert!(code.contains("self.tick(host).map(|()| None)"));
+        assert!(code.contains("_ => Err(WasmTrap::UnknownExport),"));
+    }
+
+    #[test]
+    fn multi_value_exports_are_rejected() {
+        let wasm = wat::parse_str(
+            r#"(module (func (export "pair") (result i32 i32) (i32.const 1) (i32.const 2)))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            dynamic_invoke: true,
+            ..TranspileOptions::default()
+        };
+        let err = transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("export `pair` with several results"));
+    }
+
+    #[test]
+    fn omitted_by_default() {
+        let wasm = wat::parse_str(r#"(module (func (export "f")))"#).unwrap();
+        let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert!(!code.contains("fn invoke"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Linear memory layout descriptors.
+//!
+//! Host code reading or writing guest structures needs to know where the
+//! linker placed them. The module lists the regions it knows of as byte
+//! offsets: where its data segments land, the end of static data and the
+//! heap base (LLVM's `__data_end` and `__heap_base`), and the shadow stack
+//! below `__stack_pointer`'s initial value:
+//!
+//! ```text
+//! pub mod layout {
+//!     pub const RODATA: core::ops::Range<u32> = 1024..1060;
+//!     pub const DATA: core::ops::Range<u32> = 1060..1200;
+//!     pub const DATA_END: u32 = 1200;
+//!     pub const STACK: core::ops::Range<u32> = 1200..66736;
+//!     pub const HEAP_BASE: u32 = 66736;
+//! }
+//! ```
+//!
+//! Segments take their name-section name (`.rodata` becomes `RODATA`), or
+//! `SEGMENT_{i}` after their index. Segments placed relative to an imported
+//! base, or left out of memory (see [`DataSegmentDef::resident`]), have no
+//! fixed region and are not listed.
+
+use crate::ir::*;
+use heck::ToShoutySnakeCase;
+use std::collections::HashSet;
+
+/// Names of the region constants that are not data segments.
+const RESERVED: [&str; 3] = ["DATA_END", "STACK", "HEAP_BASE"];
+
+/// Value of the linker global `idx`, as an address.
+fn address_of(info: &ModuleInfo, idx: Option<LocalGlobalIdx>) -> Option<u32> {
+    match info.local_global(idx?)?.init_value {
+        GlobalInit::I32(value) => Some(value as u32),
+        _ => None,
+    }
+}
+
+/// Constant name of data segment `idx`: its name-section name when that
+/// makes a fresh identifier, `SEGMENT_{idx}` otherwise.
+fn segment_name(segment: &DataSegmentDef, idx: usize, taken: &mut HashSet<String>) -> String {
+    let named = segment
+        .name
+        .as_deref()
+        .map(|name| name.trim_start_matches('.').to_shouty_snake_case())
+        .filter(|name| {
+            name.starts_with(|c: char| c.is_ascii_uppercase())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !RESERVED.contains(&name.as_str())
+                && !name.starts_with("SEGMENT_")
+                && !taken.contains(name)
+        });
+    let name = named.unwrap_or_else(|| format!("SEGMENT_{idx}"));
+    taken.insert(name.clone());
+    name
+}
+
+/// Generate the `layout` module.
+///
+/// Returns an empty string for a module without memory, or when no region
+/// is known.
+pub fn generate_layout(info: &ModuleInfo) -> String {
+    if !info.has_memory && !info.has_memory_import {
+        return String::new();
+    }
+    let mut taken = HashSet::new();
+    let mut items = Vec::new();
+    let mut static_ends = Vec::new();
+    for (idx, segment) in info.data_segments.iter().enumerate() {
+        if segment.base.is_some() || !segment.resident {
+            continue;
+        }
+        let name = segment_name(segment, idx, &mut taken);
+        let end = segment.offset.saturating_add(segment.data.len() as u32);
+        let what = match &segment.name {
+            Some(original) => format!("Data segment `{original}`"),
+            None => format!("Data segment {idx}"),
+        };
+        items.push(format!(
+            "    /// {what}.\n    pub const {name}: core::ops::Range<u32> = {}..{end};\n",
+            segment.offset
+        ));
+        static_ends.push(end);
+    }
+
+    let data_end = address_of(info, info.data_end);
+    if let Some(end) = data_end {
+        items.push(format!(
+            "    /// End of static data (`__data_end`).\n    pub const DATA_END: u32 = {end};\n"
+        ));
+        static_ends.push(end);
+    }
+    if let Some(top) = address_of(info, info.stack_pointer) {
+        // The stack grows down from its initial pointer until it meets the
+        // static data below it (nothing below with `--stack-first`)
+        let bottom = static_ends
+            .iter()
+            .copied()
+            .filter(|&end| end <= top)
+            .max()
+            .unwrap_or(0);
+        items.push(format!(
+            "    /// Shadow stack, growing down from the initial `__stack_pointer`.\n    pub const STACK: core::ops::Range<u32> = {bottom}..{top};\n"
+        ));
+    }
+    if let Some(base) = address_of(info, info.heap_base) {
+        items.push(format!(
+            "    /// Start of the heap (`__heap_base`).\n    pub const HEAP_BASE: u32 = {base};\n"
+        ));
+    }
+    if items.is_empty() {
+        return String::new();
+    }
+
+    let mut code = String::from(
+        "/// Known regions of linear memory, as byte offsets, for host code reading\n\
+         /// or writing guest structures.\n\
+         pub mod layout {\n",
+    );
+    code.push_str(&items.join("\n"));
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_wat(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        transpile(&wasm, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn linker_regions_are_listed() {
+        let code = transpile_wat(
+            r#"(module
+                (memory 2)
+                (global $__stack_pointer (mut i32) (i32.const 66576))
+                (global (export "__data_end") i32 (i32.const 1040))
+                (global (export "__heap_base") i32 (i32.const 66576))
+                (data $.rodata (i32.const 1024) "abcd")
+                (data $.data (i32.const 1032) "xy")
+                (data (i32.const 1036) "z"))"#,
+        );
+        for item in [
+            "    /// Data segment `.rodata`.\n    pub const RODATA: core::ops::Range<u32> = 1024..1028;\n",
+            "    pub const DATA: core::ops::Range<u32> = 1032..1034;\n",
+            "    /// Data segment 2.\n    pub const SEGMENT_2: core::ops::Range<u32> = 1036..1037;\n",
+            "    pub const DATA_END: u32 = 1040;\n",
+            "    pub const STACK: core::ops::Range<u32> = 1040..66576;\n",
+            "    pub const HEAP_BASE: u32 = 66576;\n",
+        ] {
+            assert!(code.contains(item), "missing {item:?}");
+        }
+    }
+
+    #[test]
+    fn stack_first_layout_starts_the_stack_at_zero() {
+        let code = transpile_wat(
+            r#"(module
+                (memory 2)
+                (global $__stack_pointer (mut i32) (i32.const 65536))
+                (data (i32.const 65536) "abcd"))"#,
+        );
+        assert!(code.contains("pub const STACK: core::ops::Range<u32> = 0..65536;"));
+    }
+
+    #[test]
+    fn clashing_segment_names_fall_back_to_the_index() {
+        let code = transpile_wat(
+            r#"(module
+                (memory 1)
+                (data $.data (i32.const 0) "a")
+                (data $data (i32.const 8) "b")
+                (data $stack (i32.const 16) "c"))"#,
+        );
+        assert!(code.contains("pub const DATA: core::ops::Range<u32> = 0..1;"));
+        assert!(code.contains("pub const SEGMENT_1: core::ops::Range<u32> = 8..9;"));
+        assert!(code.contains("pub const SEGMENT_2: core::ops::Range<u32> = 16..17;"));
+    }
+
+    #[test]
+    fn modules_without_known_regions_have_no_layout() {
+        let code = transpile_wat(r#"(module (memory 1) (func (export "f")))"#);
+        assert!(!code.contains("pub mod layout"));
+    }
+}
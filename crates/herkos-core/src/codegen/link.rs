@@ -0,0 +1,163 @@
+//! Glue for wiring modules together at runtime (`--link`).
+//!
+//! Both sides of an import between two transpiled modules are generated:
+//!
+//! - `LinkedWasmModule<H>` bundles the instance with its host and
+//!   implements `herkos_runtime::Linkable` through `invoke`, so a
+//!   `herkos_runtime::Linker` can call its exports by name.
+//! - `ModuleHostTrait` is implemented for `herkos_runtime::Linker`: each
+//!   import calls the export of the same name in the instance registered
+//!   under the import's module name.
+//!
+//! ```text
+//! impl ModuleHostTrait for herkos_runtime::Linker<'_> {
+//!     fn double(&mut self, arg0: i32) -> WasmResult<i32> {
+//!         match self.call("math", "double", &[herkos_runtime::Value::from(arg0)])? {
+//!             Some(result) => result.try_into(),
+//!             None => Err(WasmTrap::InvokeTypeMismatch),
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::codegen::types::{format_return_type, wasm_type_to_rust};
+use crate::codegen::utils::rust_ident;
+use crate::ir::*;
+
+/// Generate `LinkedWasmModule` and the `Linker` host impl.
+///
+/// Returns an empty string unless linking was requested.
+pub fn generate_link(info: &ModuleInfo) -> String {
+    if !info.link {
+        return String::new();
+    }
+    let host_arg = if info.uses_host() {
+        ", &mut self.host"
+    } else {
+        ""
+    };
+
+    let mut code = String::new();
+    code.push_str(
+        "/// The module with its host, serving other modules' imports through a\n\
+         /// [`herkos_runtime::Linker`].\n",
+    );
+    code.push_str("pub struct LinkedWasmModule<H> {\n");
+    code.push_str("    pub module: WasmModule,\n");
+    code.push_str("    pub host: H,\n");
+    code.push_str("}\n\n");
+    code.push_str("impl<H: ModuleHostTrait> LinkedWasmModule<H> {\n");
+    code.push_str("    pub fn new(module: WasmModule, host: H) -> Self {\n");
+    code.push_str("        Self { module, host }\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+    code.push_str("impl<H: ModuleHostTrait> herkos_runtime::Linkable for LinkedWasmModule<H> {\n");
+    code.push_str("    fn call(&mut self, name: &str, args: &[herkos_runtime::Value]) -> WasmResult<Option<herkos_runtime::Value>> {\n");
+    code.push_str(&format!(
+        "        self.module.invoke(name, args{host_arg})\n"
+    ));
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+
+    if info.func_imports.is_empty() {
+        return code;
+    }
+    code.push_str(&generate_dynamic_host(info, "herkos_runtime::Linker<'_>"));
+    code
+}
+
+/// `ModuleHostTrait` for `host`, a type whose `call(module, field, args)`
+/// serves each import with dynamically typed values.
+pub fn generate_dynamic_host(info: &ModuleInfo, host: &str) -> String {
+    let mut code = format!("impl ModuleHostTrait for {host} {{\n");
+    for imp in &info.func_imports {
+        let mut params = vec!["&mut self".to_string()];
+        let mut args = Vec::new();
+        for (i, ty) in imp.params.iter().enumerate() {
+            params.push(format!("arg{i}: {}", wasm_type_to_rust(ty)));
+            args.push(format!("herkos_runtime::Value::from(arg{i})"));
+        }
+        code.push_str(&format!(
+            "    fn {}({}) -> {} {{\n",
+            rust_ident(&imp.func_name),
+            params.join(", "),
+            format_return_type(&imp.results)
+        ));
+        code.push_str(&format!(
+            "        match self.call({:?}, {:?}, &[{}])? {{\n",
+            &*imp.module_name,
+            imp.func_name,
+            args.join(", ")
+        ));
+        if !imp.results.is_empty() {
+            code.push_str("            Some(result) => result.try_into(),\n");
+            code.push_str("            None => Err(WasmTrap::InvokeTypeMismatch),\n");
+        } else {
+            code.push_str("            None => Ok(()),\n");
+            code.push_str("            Some(_) => Err(WasmTrap::InvokeTypeMismatch),\n");
+        }
+        code.push_str("        }\n");
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_link(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            link: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn imports_are_routed_through_the_linker() {
+        let code = transpile_link(
+            r#"(module
+                (import "math" "double" (func (param i32) (result i32)))
+                (import "env" "log" (func (param i64 f32)))
+                (func (export "run") (param i32) (result i32)
+                    (call 0 (local.get 0))))"#,
+        )
+        .unwrap();
+        assert!(code.contains("impl ModuleHostTrait for herkos_runtime::Linker<'_> {"));
+        assert!(code.contains("fn double(&mut self, arg0: i32) -> WasmResult<i32> {"));
+        assert!(code.contains(
+            "match self.call(\"math\", \"double\", &[herkos_runtime::Value::from(arg0)])? {"
+        ));
+        assert!(code.contains("Some(result) => result.try_into(),"));
+        assert!(code.contains("fn log(&mut self, arg0: i64, arg1: f32) -> WasmResult<()> {"));
+        assert!(code.contains("None => Ok(()),"));
+        assert!(code.contains("self.module.invoke(name, args, &mut self.host)"));
+        assert!(code.contains("pub fn invoke<H: ModuleHostTrait>"));
+    }
+
+    #[test]
+    fn modules_without_imports_only_serve_others() {
+        let code = transpile_link(
+            r#"(module (func (export "double") (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))))"#,
+        )
+        .unwrap();
+        assert!(code.contains(
+            "impl<H: ModuleHostTrait> herkos_runtime::Linkable for LinkedWasmModule<H> {"
+        ));
+        assert!(code.contains("self.module.invoke(name, args)\n"));
+        assert!(!code.contains("for herkos_runtime::Linker"));
+    }
+
+    #[test]
+    fn imported_state_is_rejected() {
+        let err = transpile_link(r#"(module (import "env" "memory" (memory 1)))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("defines its own memory and table"));
+
+        let err = transpile_link(r#"(module (import "env" "g" (global i32)))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("imported globals (`env.g`)"));
+    }
+}
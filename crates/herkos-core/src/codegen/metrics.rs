@@ -0,0 +1,120 @@
+//! Module metrics generation.
+//!
+//! Emits `metrics()`, the size and instrumentation of the module as a
+//! `herkos_runtime::ModuleMetrics`, for hosts inventorying many modules:
+//!
+//! ```text
+//! /// Size and instrumentation of this module.
+//! pub const fn metrics() -> ModuleMetrics {
+//!     ModuleMetrics {
+//!         functions: 3,
+//!         ...
+//!         instrumentation: Instrumentation { coverage: true, ..Instrumentation::NONE },
+//!     }
+//! }
+//! ```
+
+use crate::ir::*;
+
+/// Generate the `metrics()` function.
+pub fn generate_metrics(info: &ModuleInfo) -> String {
+    let blocks: usize = info.ir_functions.iter().map(|f| f.blocks.len()).sum();
+    let memory_pages = if info.has_memory {
+        info.initial_pages
+    } else if info.has_memory_import {
+        info.memory_import_initial_pages
+    } else {
+        0
+    };
+    let data_bytes: usize = info
+        .data_segments
+        .iter()
+        .map(|s| s.data.len())
+        .chain(info.passive_data_segments.iter().map(|s| s.data.len()))
+        .sum();
+
+    let mut code = String::new();
+    code.push_str("\n/// Size and instrumentation of this module.\n");
+    code.push_str("pub const fn metrics() -> ModuleMetrics {\n");
+    code.push_str("    ModuleMetrics {\n");
+    for (field, value) in [
+        ("functions", info.ir_functions.len()),
+        ("imports", info.func_imports.len()),
+        ("exports", info.func_exports.len()),
+        ("blocks", blocks),
+        ("table_size", info.table_initial),
+        ("memory_pages", memory_pages),
+        ("data_bytes", data_bytes),
+    ] {
+        code.push_str(&format!("        {field}: {value},\n"));
+    }
+    // Only the enabled options, over `Instrumentation::NONE`
+    let enabled: Vec<String> = [
+        ("observe_memory", info.observe_memory),
+        ("record_replay", info.record_replay),
+        ("yield_points", info.yield_points),
+        ("heap_profile", info.heap_profile),
+        ("debug_memory", info.debug_memory),
+        ("coverage", info.coverage),
+        ("bounds_profile", info.bounds_profile),
+    ]
+    .iter()
+    .filter(|(_, on)| *on)
+    .map(|(name, _)| format!("{name}: true, "))
+    .collect();
+    if enabled.is_empty() {
+        code.push_str(
+            "        instrumentation: Instrumentation::NONE,
+",
+        );
+    } else {
+        code.push_str(&format!(
+            "        instrumentation: Instrumentation {{ {}..Instrumentation::NONE }},
+",
+            enabled.concat()
+        ));
+    }
+    code.push_str("    }\n");
+    code.push_str("}\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    #[test]
+    fn metrics_count_the_module() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (memory 2)
+                (table 3 funcref)
+                (data (i32.const 0) "hello")
+                (data "abc")
+                (func (export "a") (export "b") (param i32) (result i32)
+                  (if (result i32) (local.get 0)
+                    (then (i32.const 1))
+                    (else (i32.const 2))))
+                (func))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            coverage: true,
+            ..TranspileOptions::default()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.contains("pub const fn metrics() -> ModuleMetrics {"));
+        for field in [
+            "functions: 2,",
+            "imports: 1,",
+            "exports: 2,",
+            "table_size: 3,",
+            "memory_pages: 2,",
+            "data_bytes: 8,",
+            "instrumentation: Instrumentation { coverage: true, ..Instrumentation::NONE },",
+        ] {
+            assert!(code.contains(field), "{field}");
+        }
+    }
+}
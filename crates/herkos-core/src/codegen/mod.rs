@@ -36,6 +36,9 @@
 //!                      ├─ Constructor (new())                │
 //!                      ├─ Private functions                  │
 //!                      ├─ Export impl block                  │
+//!                      ├─ Guest allocator helpers (optional) │
+//!                      ├─ Binding wrappers (optional)        │
+//!                      ├─ Symbol table                       │
 //!                      └──────────────────────────────────────┘
 //!                                            │
 //!                                            ▼
@@ -49,12 +52,27 @@
 //!
 //! Each sub-module handles a specific aspect of code generation:
 //!
+//! - **`api`**: Body-less outline of the generated public API (`herkos bindings`)
 //! - **`module`**: Main generation orchestration (`generate_module_with_info`, standalone vs wrapper)
 //! - **`traits`**: Host trait definitions from imports (`EnvImports`, `WasiImports`, etc.)
 //! - **`constructor`**: Module initialization (`new()`, data/element segments, const globals)
 //! - **`function`**: IR function translation (signatures, blocks, variables, SSA)
 //! - **`instruction`**: Individual instruction code generation and terminators
+//! - **`structured`**: `loop` / `if` / labeled `break` reconstruction from the CFG
+//! - **`dispatch`**: One `call_indirect` dispatcher per called type
 //! - **`export`**: Export method generation (forwarding to internal functions)
+//! - **`guest_alloc`**: `alloc_in_guest` / `free_in_guest` over the guest's own allocator exports
+//! - **`c`**: C99 translation unit for `--mode c` (experimental, uses `CBackend`)
+//! - **`bindings`**: High-level wrappers for pointer/length exports (from a bindings file)
+//! - **`wit`**: Canonical-ABI wrappers and import traits (from a WIT world)
+//! - **`groups`**: Namespaced views over exports sharing a name prefix (optional)
+//! - **`wasmtime_adapter`**: `WasmtimeExports` impl for wasmtime-style hosts (optional)
+//! - **`invoke`**: `invoke` method calling exports by name with dynamic values (optional)
+//! - **`replay`**: `Recorder` / `Replayer` hosts over a `ReplayLog` (optional)
+//! - **`tinygo`**: TinyGo host-trait defaults and `tinygo_run` scheduler loop
+//! - **`symbols`**: Symbol table (`SYMBOLS`, `symbolicate`) for trap reports
+//! - **`metrics`**: `metrics()`, the size and instrumentation of the module
+//! - **`table_introspection`**: `table_len` / `table_entry` over an exported table
 //! - **`types`**: Type conversions (Wasm→Rust, WasmResult formatting)
 //! - **`utils`**: Utility functions (call arg building, grouping)
 //!
@@ -78,8 +96,8 @@
 //!    │   └─ Data segment init (byte-by-byte)
 //!    │
 //!    ├─→ [Function generation per func in IR]
-//!    │   └─→ generate_function_with_info("func_0", ...)
-//!    │       ├─→ generate_signature_with_info()
+//!    │   └─→ write_function_with_info("func_0", ...)
+//!    │       ├─→ write_signature_with_info()
 //!    │       │   ├─ Collect trait bounds if needs_host
 //!    │       │   ├─ Add globals/memory/table/host parameters
 //!    │       │   └─ Build generic param H (if multiple trait bounds)
@@ -87,14 +105,14 @@
 //!    │       ├─→ [Variable type inference from instructions]
 //!    │       │
 //!    │       └─→ [Block translation]
-//!    │           ├─ Single-block: flat code emission
-//!    │           └─ Multi-block: state machine with Block enum + loop/match
+//!    │           ├─ Reducible CFG: structured loops, ifs and labeled blocks
+//!    │           └─ Otherwise: state machine with Block enum + loop/match
 //!    │
 //!    ├─→ [Per instruction]
-//!    │   └─→ generate_instruction_with_info()
+//!    │   └─→ write_instruction()
 //!    │       ├─ Delegates to backend.emit_*() for most operations
 //!    │       ├─ CallImport → host.func_name()
-//!    │       ├─ CallIndirect → dispatch match on func_index
+//!    │       ├─ CallIndirect → dispatch_T(index, ...) of its type
 //!    │       └─ GlobalGet/Set → redirect to imported globals via host traits
 //!    │
 //!    └─→ [Export impl generation]
@@ -113,12 +131,13 @@
 //!    parameter. Each import module gets its own trait (e.g., `EnvImports`, `WasiImports`).
 //!    This ensures zero-cost dispatch and type safety.
 //!
-//! 3. **SSA Variable Inference**: Types are inferred from instructions using a HashMap,
-//!    ensuring correct Rust type declarations for all intermediate values.
+//! 3. **SSA Variable Inference**: Types are inferred from instructions into a map
+//!    ordered by variable, ensuring correct Rust type declarations for all
+//!    intermediate values, emitted in the same order on every run.
 //!
-//! 4. **State Machine for Multi-Block Functions**: Functions with multiple blocks emit
-//!    a local `Block` enum and a `loop { match }` structure. Single-block functions
-//!    optimize to flat code.
+//! 4. **Structured Control Flow**: Blocks become nested `loop`s, `if`s and labeled
+//!    blocks left with `break` / `continue` (see `structured`). Functions whose CFG
+//!    does not allow it emit a local `Block` enum and a `loop { match }` state machine.
 //!
 //! 5. **Const Generics Over Runtime Sizes**: `MAX_PAGES` and `TABLE_MAX` are const
 //!    generics, not runtime values. This enables monomorphization and zero-cost memory
@@ -130,18 +149,50 @@
 //! - **Output**: Formatted Rust source code (typically passed through `rustfmt`)
 //! - **Error Handling**: Uses `anyhow::Result` for context on generation failures
 
+pub mod api;
+pub mod bindings;
+pub mod bounds_profile;
+pub mod c;
+pub mod compact;
 pub mod constructor;
+pub mod contracts;
+pub mod coverage;
+pub mod debug_memory;
+pub mod dispatch;
 pub mod env;
 pub mod export;
 pub mod function;
+pub mod groups;
+pub mod guest_alloc;
+pub mod guest_log;
+pub mod heap_profile;
+pub mod hot_reload;
+pub mod import_checks;
 pub mod instruction;
+pub mod invoke;
+pub mod layout;
+pub mod link;
+pub mod metrics;
 pub mod module;
+pub mod pure;
+pub mod replay;
+pub mod shadow_stack;
+pub mod shared_host;
+pub mod structured;
+pub mod symbols;
+pub mod table_introspection;
+pub mod tinygo;
 pub mod traits;
 pub mod types;
 pub mod utils;
+pub mod wasmtime_adapter;
+pub mod wit;
 
 use crate::backend::Backend;
+use crate::bindings::BindingConfig;
 use crate::ir::*;
+use crate::wit::WitWorld;
+use crate::Preamble;
 use anyhow::Result;
 
 /// Main code generator struct that orchestrates emission of Rust code from IR.
@@ -155,19 +206,54 @@ use anyhow::Result;
 /// ```
 pub struct CodeGenerator<'a, B: Backend> {
     backend: &'a B,
+    bindings: Option<&'a BindingConfig>,
+    wit: Option<&'a WitWorld>,
+    preamble: Option<&'a Preamble>,
 }
 
 impl<'a, B: Backend> CodeGenerator<'a, B> {
     /// Create a new code generator with a given backend.
     pub fn new(backend: &'a B) -> Self {
-        CodeGenerator { backend }
+        CodeGenerator {
+            backend,
+            bindings: None,
+            wit: None,
+            preamble: None,
+        }
+    }
+
+    /// Also generate the wrappers declared in a bindings file, already
+    /// validated against the module.
+    pub fn with_bindings(mut self, bindings: &'a BindingConfig) -> Self {
+        self.bindings = Some(bindings);
+        self
+    }
+
+    /// Also generate canonical-ABI wrappers for a WIT world, already
+    /// validated against the module.
+    pub fn with_wit(mut self, world: &'a WitWorld) -> Self {
+        self.wit = Some(world);
+        self
+    }
+
+    /// Add a module doc, inner attributes and `use` statements to the top of
+    /// the generated file.
+    pub fn with_preamble(mut self, preamble: &'a Preamble) -> Self {
+        self.preamble = Some(preamble);
+        self
     }
 
     /// Generate a complete Rust module from IR with full module info.
     ///
     /// This is the main entry point. It generates a module wrapper structure.
     pub fn generate_module_with_info(&self, info: &LoweredModuleInfo) -> Result<String> {
-        module::generate_module_with_info(self.backend, info)
+        module::generate_module_with_info(
+            self.backend,
+            info,
+            self.bindings,
+            self.wit,
+            self.preamble,
+        )
     }
 }
 
@@ -191,11 +277,11 @@ mod tests {
                     rhs: VarId(1),
                 }],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(2)),
+                    values: vec![VarId(2)],
                 },
             }],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I32),
+            results: vec![WasmType::I32],
             type_idx: TypeIdx::new(0),
         };
 
@@ -204,12 +290,19 @@ mod tests {
         let info = ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: Vec::new(),
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: Vec::new(),
@@ -219,6 +312,40 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: Vec::new(),
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
         let code =
             function::generate_function_with_info(&backend, &ir_func, "add", &info, true).unwrap();
@@ -226,7 +353,7 @@ mod tests {
         println!("Generated code:\n{}", code);
 
         // Basic checks
-        assert!(code.contains("pub fn add"));
+        assert!(code.contains("pub const fn add"));
         assert!(code.contains("v0: i32") || code.contains("mut v0: i32"));
         assert!(code.contains("v1: i32") || code.contains("mut v1: i32"));
         assert!(code.contains("-> WasmResult<i32>"));
@@ -243,10 +370,10 @@ mod tests {
             blocks: vec![IrBlock {
                 id: BlockId(0),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             }],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
 
@@ -255,12 +382,19 @@ mod tests {
         let info = ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: Vec::new(),
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: Vec::new(),
@@ -270,11 +404,45 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: Vec::new(),
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
         let code =
             function::generate_function_with_info(&backend, &ir_func, "noop", &info, true).unwrap();
 
-        assert!(code.contains("pub fn noop"));
+        assert!(code.contains("pub const fn noop"));
         assert!(code.contains("-> WasmResult<()>"));
         assert!(code.contains("Ok(())"));
     }
@@ -348,11 +516,11 @@ mod tests {
                     rhs: VarId(1),
                 }],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(2)),
+                    values: vec![VarId(2)],
                 },
             }],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I64),
+            results: vec![WasmType::I64],
             type_idx: TypeIdx::new(0),
         };
 
@@ -361,12 +529,19 @@ mod tests {
         let info = ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: Vec::new(),
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: Vec::new(),
@@ -376,6 +551,40 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: Vec::new(),
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
         let code = function::generate_function_with_info(&backend, &ir_func, "add64", &info, true)
             .unwrap();
@@ -412,11 +621,11 @@ mod tests {
                     },
                 ],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(2)),
+                    values: vec![VarId(2)],
                 },
             }],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I32),
+            results: vec![WasmType::I32],
             type_idx: TypeIdx::new(0),
         };
 
@@ -425,12 +634,19 @@ mod tests {
         let info = ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: Vec::new(),
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: Vec::new(),
@@ -440,6 +656,40 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: Vec::new(),
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
         let code =
             function::generate_function_with_info(&backend, &ir_func, "eq64", &info, true).unwrap();
@@ -465,26 +715,34 @@ mod tests {
                     index: GlobalIdx::new(0),
                 }],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(0)),
+                    values: vec![VarId(0)],
                 },
             }],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I32),
+            results: vec![WasmType::I32],
             type_idx: TypeIdx::new(0),
         };
 
         let info = ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: vec![GlobalDef {
                 mutable: true,
                 init_value: GlobalInit::I32(0),
+                field: "g0".to_string(),
             }],
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: vec![FuncExport {
@@ -497,6 +755,40 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: vec![ir_func],
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
 
         let backend = SafeBackend::new();
@@ -532,26 +824,38 @@ mod tests {
                     sign: None,
                 }],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(1)),
+                    values: vec![VarId(1)],
                 },
             }],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I32),
+            results: vec![WasmType::I32],
             type_idx: TypeIdx::new(0),
         };
 
         let info = ModuleInfo {
             has_memory: true,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 1,
             initial_pages: 1,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: Vec::new(),
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: vec![DataSegmentDef {
                 offset: 0,
+                base: None,
                 data: vec![72, 101, 108, 108, 111], // "Hello"
+                read_only: false,
+                resident: true,
+                name: None,
+                protected: false,
             }],
             passive_data_segments: Vec::new(),
             func_exports: vec![FuncExport {
@@ -564,6 +868,40 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: vec![ir_func],
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
 
         let backend = SafeBackend::new();
@@ -600,26 +938,34 @@ mod tests {
                     index: GlobalIdx::new(0),
                 }],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(0)),
+                    values: vec![VarId(0)],
                 },
             }],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I32),
+            results: vec![WasmType::I32],
             type_idx: TypeIdx::new(0),
         };
 
         let info = ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: vec![GlobalDef {
                 mutable: false,
                 init_value: GlobalInit::I32(42),
+                field: "g0".to_string(),
             }],
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: vec![FuncExport {
@@ -632,6 +978,40 @@ mod tests {
             imported_globals: Vec::new(),
             ir_functions: vec![ir_func],
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         };
 
         let backend = SafeBackend::new();
@@ -649,4 +1029,520 @@ mod tests {
         // GlobalGet for immutable should use const name
         assert!(code.contains("G0"));
     }
+
+    #[test]
+    fn generate_imported_indirect_function_table() {
+        let wat = r#"(module
+            (type $t (func (result i32)))
+            (import "env" "__indirect_function_table" (table 2 funcref))
+            (func $one (type $t) (i32.const 1))
+            (elem (i32.const 1) $one)
+            (func (export "call") (param i32) (result i32)
+                (call_indirect (type $t) (local.get 0))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        assert!(!code.contains("TABLE_MAX"));
+        assert!(code.contains("pub struct WasmModule(pub LibraryModule<Globals, 0>);"));
+        assert!(code.contains("pub fn new_table<const TP: usize>() -> WasmResult<Table<TP>> {"));
+        assert!(code.contains(
+            "pub fn init_table<const TP: usize>(table: &mut Table<TP>) -> WasmResult<()> {\n    \
+             table.init_elements(1, &[(0, 0)])?;\n"
+        ));
+        assert!(code.contains(
+            "pub fn call<const TP: usize>(&mut self, v0: i32, table: &Table<TP>) -> WasmResult<i32> {"
+        ));
+        assert!(code.contains("func_1(v0, &mut env, table)"));
+    }
+
+    #[test]
+    fn generate_runtime_abi_guard() {
+        let wasm = wat::parse_str(r#"(module (func (export "f")))"#).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        let (major, minor) = herkos_runtime::ABI_VERSION;
+        assert!(code.contains(&format!(
+            "pub const MODULE_ABI: (u32, u32) = ({major}, {minor});"
+        )));
+        assert!(code.contains("herkos_runtime::abi_compatible(MODULE_ABI),"));
+        assert!(code.contains(&format!("for herkos-runtime ABI {major}.{minor}, which")));
+    }
+
+    #[test]
+    fn generate_exported_indirect_function_table_accessors() {
+        let wat = r#"(module
+            (table (export "__indirect_function_table") 1 funcref)
+            (func (export "f")))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        assert!(code.contains("const TABLE_MAX: usize = 1;"));
+        assert!(code.contains(
+            "    pub fn indirect_function_table(&self) -> &Table<TABLE_MAX> {\n        &self.0.table\n"
+        ));
+        assert!(code.contains(
+            "    pub fn indirect_function_table_mut(&mut self) -> &mut Table<TABLE_MAX> {"
+        ));
+    }
+
+    #[test]
+    fn generate_yield_points_at_loop_headers() {
+        let wat = r#"(module
+            (func (export "spin") (param i32) (result i32)
+                (loop $outer
+                    (loop $inner
+                        (br_if $inner (i32.eqz (local.get 0))))
+                    (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+                    (br_if $outer (local.get 0)))
+                (local.get 0)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            yield_points: true,
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+
+        assert!(code.contains(
+            "    fn maybe_yield(&mut self) -> WasmResult<()> {\n        Ok(())\n    }\n"
+        ));
+        assert_eq!(code.matches("env.host.maybe_yield()?;").count(), 2);
+        // Import-less modules take a host so the callback reaches it.
+        assert!(code.contains(
+            "pub fn spin<H: ModuleHostTrait>(&mut self, v0: i32, host: &mut H) -> WasmResult<i32> {"
+        ));
+
+        let plain = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(!plain.contains("maybe_yield"));
+    }
+
+    #[test]
+    fn yield_points_reject_colliding_import() {
+        let wat = r#"(module (import "env" "maybe_yield" (func)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            yield_points: true,
+            ..Default::default()
+        };
+        let err = crate::transpile(&wasm, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("collides with the `maybe_yield`"));
+    }
+
+    #[test]
+    fn observe_memory_reports_accesses_to_the_host() {
+        let wat = r#"(module
+            (import "env" "log" (func $log (param i32)))
+            (memory 1 1)
+            (func (export "peek") (param i32) (result i32)
+                (call $log (i32.load16_u offset=8 (local.get 0)))
+                (i32.const 0))
+            (func (export "size") (result i32) (memory.size)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            observe_memory: true,
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+
+        assert!(code.contains("pub trait ModuleHostTrait: MemoryObserver {\n"));
+        assert!(code.contains("env.host.on_load((v2 as u32 as usize).wrapping_add(8), 2);"));
+        assert_eq!(code.matches("env.host.on_").count(), 1);
+
+        let plain = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(!plain.contains("MemoryObserver"));
+
+        let colliding = wat::parse_str(r#"(module (import "env" "on_store" (func)))"#).unwrap();
+        let err = crate::transpile(&colliding, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("collides with a `MemoryObserver` method"));
+    }
+
+    #[test]
+    fn protect_rodata_protects_named_segments_after_init() {
+        let wat = r#"(module
+            (memory 1 1)
+            (data $.rodata.str (i32.const 16) "const")
+            (data $.data (i32.const 32) "state")
+            (data $.rodata (i32.const 18) "!"))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            protect_rodata: true,
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+        assert!(code.contains(
+            "    module.memory.init_data(18, &[33u8])?;\n    module.memory.protect(16, 5)?;\n    module.memory.protect(18, 1)?;\n"
+        ));
+        assert_eq!(code.matches(".protect(").count(), 2);
+
+        let plain = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(!plain.contains(".protect("));
+
+        let no_memory = wat::parse_str("(module)").unwrap();
+        let err = crate::transpile(&no_memory, &options).unwrap_err();
+        assert!(format!("{err:#}").contains("needs a module that defines its own memory"));
+    }
+
+    #[test]
+    fn large_element_segments_become_statics() {
+        let large = vec!["$f"; constructor::LARGE_ELEMENT_SEGMENT + 1].join(" ");
+        let wat = format!(
+            r#"(module
+                (import "env" "g" (func $g))
+                (table 100 funcref)
+                (func $f)
+                (elem (i32.const 0) $g {large})
+                (elem (i32.const 90) $f $f))"#
+        );
+        let wasm = wat::parse_str(&wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        let len = constructor::LARGE_ELEMENT_SEGMENT + 2;
+        assert!(code.contains(&format!(
+            "static ELEMENTS_0: [FuncRef; {len}] = [\n    FuncRef::import(0, 0), FuncRef::local(0, 0),"
+        )));
+        assert!(code.contains("    table.init_refs(0, &ELEMENTS_0)?;\n"));
+        assert!(code.contains("    table.init_elements(90, &[(0, 0), (0, 0)])?;\n"));
+        assert!(!code.contains("ELEMENTS_1"));
+    }
+
+    #[test]
+    fn generate_feature_gated_exports() {
+        let wat = r#"(module
+            (func $square (param i32) (result i32) (i32.mul (local.get 0) (local.get 0)))
+            (func $unused (result i32) (i32.const 0))
+            (func (export "area") (param i32) (result i32) (call $square (local.get 0)))
+            (func (export "volume") (param i32) (result i32)
+                (i32.mul (call $square (local.get 0)) (local.get 0)))
+            (func (export "version") (result i32) (i32.const 1)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            features: crate::features::FeatureConfig::parse("prefix export-\nexport version core")
+                .unwrap(),
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+
+        assert!(code.contains(
+            "#[cfg(any(feature = \"export-area\", feature = \"export-volume\"))]\n#[must_use = \"the function has no side effects\"]\n#[allow("
+        ));
+        assert!(code.contains(
+            "#[cfg(feature = \"export-area\")]\n#[must_use = \"the function has no side effects\"]\n#[allow("
+        ));
+        assert!(code.contains(
+            "    #[cfg(feature = \"export-volume\")]\n    #[must_use = \"the function has no side effects\"]\n    pub fn volume(&mut self, v0: i32)"
+        ));
+        assert!(code.contains(
+            "    #[cfg(feature = \"core\")]\n    #[must_use = \"the function has no side effects\"]\n    pub fn version(&mut self)"
+        ));
+        // Functions no export reaches keep their accessor.
+        let unused = code.find("fn func_unused(").unwrap();
+        let preceding: Vec<&str> = code[..unused].lines().rev().take(2).collect();
+        assert!(!preceding[1].starts_with("#[cfg"));
+        assert!(code.contains("\n    pub fn func_unused(&mut self)"));
+
+        let plain = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(!plain.contains("#[cfg("));
+    }
+
+    #[test]
+    fn name_section_names_functions_and_parameters() {
+        let wat = r#"(module
+            (func $fooBar (param $Count i32) (param $host i32) (result i32) (local.get 0))
+            (func $foo_bar (result i32) (i32.const 0))
+            (func (export "run") (param $count i32) (result i32)
+                (i32.add (call $fooBar (local.get 0) (local.get 0)) (call $foo_bar))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        assert!(code.contains("fn func_foo_bar<H: ModuleHostTrait>(mut v0: i32, mut v1: i32"));
+        // `foo_bar` is taken by the first function
+        assert!(code.contains("fn func_1<H: ModuleHostTrait>("));
+        assert!(code.contains(" = func_foo_bar(v"));
+        assert!(code.contains(" = func_1(env)"));
+        assert!(code.contains("pub fn run(&mut self, count: i32) -> WasmResult<i32> {"));
+        assert!(code.contains("        func_2(count, &mut env)\n"));
+        // `host` is taken by the wrappers that forward the host
+        assert!(code.contains("pub fn func_foo_bar(&mut self, count: i32, v1: i32)"));
+    }
+
+    #[test]
+    fn exports_of_one_function_share_one_wrapper() {
+        let wat = r#"(module
+            (func (export "a") (export "b") (export "c") (export "d") (param i32) (result i32)
+                (local.get 0)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            features: crate::features::FeatureConfig::parse(
+                "export a fa\nexport c fc\nexport d fc",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+
+        // `b` is always compiled, so it constructs the Env and `a`, `c` and
+        // `d` forward to it
+        assert_eq!(code.matches("let mut env = Env").count(), 1);
+        assert!(code.contains(
+            "    pub fn b(&mut self, v0: i32) -> WasmResult<i32> {\n        let mut __host"
+        ));
+        for method in ["a", "c", "d"] {
+            assert!(code.contains(&format!(
+                "    pub fn {method}(&mut self, v0: i32) -> WasmResult<i32> {{\n        self.b(v0)\n    }}\n"
+            )));
+        }
+
+        // Without an ungated export, exports forward only under the same feature
+        let options = crate::TranspileOptions {
+            features: crate::features::FeatureConfig::parse(
+                "export a fa\nexport b fb\nexport c fa\nexport d fa",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+        assert_eq!(code.matches("let mut env = Env").count(), 2);
+        assert_eq!(code.matches("        self.a(v0)\n").count(), 2);
+    }
+
+    #[test]
+    fn generate_globals_initialized_from_imports() {
+        let wat = r#"(module
+            (import "env" "__memory_base" (global $base i32))
+            (global $data i32 (global.get $base))
+            (global $cursor (mut i32) (global.get $base))
+            (global $limit i32 (i32.const 64))
+            (func (export "data") (result i32) (global.get $data)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        assert!(code.contains(
+            "pub fn new<H: ModuleHostTrait + ?Sized>(host: &H) -> WasmResult<WasmModule> {"
+        ));
+        assert!(code.contains(
+            "Globals { data: host.get___memory_base(), cursor: host.get___memory_base() }"
+        ));
+        assert!(code.contains("pub struct Globals {\n    pub data: i32,\n    pub cursor: i32,\n}"));
+        assert!(code.contains("pub const G2: i32 = 64i32;"));
+        assert!(code.contains("= env.globals.data;"));
+    }
+
+    #[test]
+    fn global_initializers_require_immutable_imports() {
+        let wat = r#"(module
+            (import "env" "base" (global $base (mut i32)))
+            (global i32 (global.get $base)))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let err = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap_err();
+        // Rejected by validation before the builder's own check.
+        assert!(format!("{err:#}").contains("global.get of mutable global"));
+    }
+
+    #[test]
+    fn generate_dylink_side_module() {
+        let wat = r#"(module
+            (@dylink.0 (mem-info (memory 8 2) (table 1 0)) (needed "libmain.so"))
+            (import "env" "memory" (memory 1))
+            (import "env" "__indirect_function_table" (table 1 funcref))
+            (import "env" "__memory_base" (global $mb i32))
+            (import "env" "__table_base" (global $tb i32))
+            (type $t (func (result i32)))
+            (func $seven (type $t) (i32.const 7))
+            (func (export "call") (param i32) (result i32)
+                (call_indirect (type $t) (local.get 0)))
+            (elem (global.get $tb) $seven)
+            (data (i32.add (global.get $mb) (i32.const 4)) "hi"))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+
+        assert!(code.contains(
+            "pub const DYLINK: DylinkLayout = DylinkLayout { memory_size: 8, memory_align: 2, table_size: 1, table_align: 0 };"
+        ));
+        assert!(code.contains(r#"pub const DYLINK_NEEDED: &[&str] = &["libmain.so"];"#));
+        assert!(code.contains(
+            "pub fn init_memory<const MP: usize, H: ModuleHostTrait + ?Sized>(memory: &mut IsolatedMemory<MP>, host: &H) -> WasmResult<()> {"
+        ));
+        assert!(code.contains(
+            "memory.init_data((host.get___memory_base() as u32).wrapping_add(4) as usize, &["
+        ));
+        assert!(code.contains(
+            "pub fn init_table<const TP: usize, H: ModuleHostTrait + ?Sized>(table: &mut Table<TP>, host: &H) -> WasmResult<()> {"
+        ));
+        assert!(code.contains(
+            "table.init_elements((host.get___table_base() as u32).wrapping_add(0), &[(0, 0)])?;"
+        ));
+        assert!(code.contains(
+            "wrapping_sub(env.host.get___table_base() as u32) >= 1 { return Err(WasmTrap::UndefinedElement); }"
+        ));
+        // Segments of the imported memory and table are not applied by `new`.
+        assert!(code.contains("pub fn new() -> WasmResult<WasmModule> {"));
+    }
+
+    #[test]
+    fn segment_offsets_require_immutable_imports() {
+        let wat = r#"(module
+            (import "env" "__memory_base" (global (mut i32)))
+            (memory 1)
+            (data (global.get 0) "x"))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let err = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap_err();
+        assert!(format!("{err:#}").contains("invalid module at offset 0x29: constant expression required: global.get of mutable global"));
+    }
+
+    #[test]
+    fn lower_atomics_emits_plain_accesses() {
+        let wat = r#"(module
+            (memory 1 1 shared)
+            (func (export "inc") (param i32) (result i32)
+                (atomic.fence)
+                (i32.atomic.rmw.add (local.get 0) (i32.const 1))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            lower_atomics: true,
+            ..Default::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+        assert!(
+            code.contains("// WARNING: atomic operations were lowered to plain loads and stores")
+        );
+        assert!(code.contains(".load_i32("));
+        assert!(code.contains(".store_i32("));
+    }
+
+    #[test]
+    fn atomics_require_lower_atomics() {
+        let wat = r#"(module
+            (memory 1 1 shared)
+            (func (export "load") (param i32) (result i32)
+                (i32.atomic.load (local.get 0))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let err = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap_err();
+        assert!(format!("{err:#}").contains("--lower-atomics"));
+    }
+
+    #[test]
+    fn checked_arithmetic_replaces_wrapping_ops() {
+        let wat = r#"(module
+            (func (export "f") (param i32 i64) (result i64)
+                (i64.mul
+                    (i64.extend_i32_s (i32.add (local.get 0) (i32.const 1)))
+                    (local.get 1))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let wrapping = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(wrapping.contains(".wrapping_add("));
+        assert!(!wrapping.contains("checked_"));
+
+        let options = crate::TranspileOptions {
+            checked_arithmetic: true,
+            ..Default::default()
+        };
+        let checked = crate::transpile(&wasm, &options).unwrap();
+        assert!(!checked.contains(".wrapping_add(") && !checked.contains(".wrapping_mul("));
+        assert!(checked.contains(".checked_add(v"));
+        assert!(checked.contains(".checked_mul(v"));
+        assert!(checked.contains(".ok_or(WasmTrap::HostAssertedOverflow)?;"));
+
+        let c_options = crate::TranspileOptions {
+            mode: "c".to_string(),
+            ..options
+        };
+        assert!(crate::transpile(&wasm, &c_options).is_err());
+    }
+
+    #[test]
+    fn start_functions_calling_the_host_run_in_new() {
+        let wat = r#"(module
+            (import "env" "tick" (func $tick))
+            (memory 1 1)
+            (func $init (call $tick))
+            (start $init))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(code
+            .contains("pub fn new<H: ModuleHostTrait>(host: &mut H) -> WasmResult<WasmModule> {"));
+        assert!(code.contains(
+            "pub fn new_limited<H: ModuleHostTrait>(host: &mut H, limits: &InstantiationLimits)"
+        ));
+        assert!(code.contains(
+            "        let mut env = Env { host, globals: &mut module.0.globals };\n        func_init(&mut env, &mut module.0.memory)?;\n    }\n    Ok(module)\n"
+        ));
+    }
+
+    #[test]
+    fn start_functions_need_their_memory_to_run_in_new() {
+        let wat = r#"(module
+            (import "env" "memory" (memory 1))
+            (func $init (i32.store (i32.const 0) (i32.const 1)))
+            (start $init))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let err = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap_err();
+        assert!(format!("{err:#}").contains("has no imported memory or table to call it with"));
+    }
+
+    #[test]
+    fn global_write_backs_are_dropped() {
+        let wat = r#"(module
+            (import "env" "counter" (global $ext (mut i32)))
+            (global $g (mut i32) (i32.const 0))
+            (func (export "touch")
+              (global.set $g (global.get $g))
+              (global.set $ext (global.get $ext))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(code.contains("v0 = env.globals.g;"));
+        assert!(!code.contains("env.globals.g = v0;"));
+        // The host sees the imported global's setter called
+        assert!(code.contains("env.host.set_counter(v1);"));
+    }
+
+    #[test]
+    fn empty_loops_spin() {
+        let wasm = wat::parse_str(r#"(module (func (export "hang") (loop (br 0))))"#).unwrap();
+        let code = crate::transpile(&wasm, &crate::TranspileOptions::default()).unwrap();
+        assert!(
+            code.contains("    loop {\n        core::hint::spin_loop();\n    }\n"),
+            "{code}"
+        );
+    }
+
+    #[test]
+    fn selects_between_one_value_become_assignments() {
+        let wat = r#"(module
+            (global $g (mut i32) (i32.const 0))
+            (func (export "pick") (result i32)
+              (select (i32.const 7) (i32.const 7) (global.get $g))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            optimize: true,
+            ..crate::TranspileOptions::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+        assert!(!code.contains(" = if "), "{code}");
+    }
+
+    #[test]
+    fn nested_ifs_and_guarded_arms_collapse() {
+        let wat = r#"(module
+            (global $g (mut i32) (i32.const 0))
+            (func (export "f") (param i32 i32)
+              (block $out
+                (block $two
+                  (block $one
+                    (block $zero
+                      (br_table $zero $one $two $out (local.get 0)))
+                    (global.set $g (i32.const 10))
+                    (br $out))
+                  (if (local.get 1) (then (global.set $g (i32.const 20)) (br $out)))
+                  (br $out))
+                (global.set $g (i32.const 30)))
+              (if (local.get 0)
+                (then (if (local.get 1) (then (global.set $g (i32.const 1))))))))"#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = crate::TranspileOptions {
+            optimize: true,
+            ..crate::TranspileOptions::default()
+        };
+        let code = crate::transpile(&wasm, &options).unwrap();
+        assert!(code.contains("        1 if v1 != 0 => {\n"), "{code}");
+        assert!(code.contains("    if v0 != 0 && v1 != 0 {\n"), "{code}");
+    }
 }
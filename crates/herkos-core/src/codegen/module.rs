@@ -4,60 +4,139 @@
 //! with constructor, internal functions, and exported methods.
 
 use crate::backend::Backend;
-use crate::codegen::constructor::{emit_const_globals, generate_constructor, rust_code_preamble};
+use crate::bindings::BindingConfig;
+use crate::codegen::bindings::generate_bindings;
+use crate::codegen::bounds_profile::{bounds_sites, generate_bounds_profile, site_counters};
+use crate::codegen::constructor::{
+    emit_const_globals, emit_dylink_consts, emit_element_statics, emit_memory_limits,
+    generate_constructor, rust_code_preamble,
+};
+use crate::codegen::coverage::{block_counters, coverage_sites, generate_coverage};
+use crate::codegen::debug_memory::generate_debug_memory;
+use crate::codegen::dispatch::generate_dispatchers;
 use crate::codegen::env::generate_env_block;
 use crate::codegen::export::generate_export_impl;
-use crate::codegen::function::generate_function_with_info;
+use crate::codegen::function::{estimate_function_size, write_function_with_info};
+use crate::codegen::groups::generate_export_groups;
+use crate::codegen::guest_alloc::generate_guest_alloc;
+use crate::codegen::heap_profile::{
+    generate_heap_stats, tracked_functions, untracked_name, write_tracking_wrapper,
+};
+use crate::codegen::hot_reload::generate_hot_reload;
+use crate::codegen::import_checks::generate_import_checks;
+use crate::codegen::invoke::generate_invoke;
+use crate::codegen::layout::generate_layout;
+use crate::codegen::link::generate_link;
+use crate::codegen::metrics::generate_metrics;
+use crate::codegen::pure::generate_pure_api;
+use crate::codegen::replay::generate_replay;
+use crate::codegen::shadow_stack::generate_shadow_stack;
+use crate::codegen::shared_host::generate_shared_host;
+use crate::codegen::symbols::generate_symbol_table;
+use crate::codegen::table_introspection::generate_table_types;
+use crate::codegen::tinygo::generate_tinygo_support;
+use crate::codegen::utils::{func_cfg_attr, must_use_attr, write_byte_list};
+use crate::codegen::wasmtime_adapter::generate_wasmtime_adapter;
+use crate::codegen::wit::generate_wit_bindings;
 use crate::ir::*;
+use crate::wit::WitWorld;
+use crate::Preamble;
 use anyhow::{Context, Result};
 
+/// Fixed module overhead (preamble, env block, constructor, export impl).
+const MODULE_OVERHEAD_BYTES: usize = 4096;
+
+/// Estimate the size of the generated module, in bytes.
+///
+/// Sums the per-function estimates and the size of every data segment
+/// literal (about 6 bytes per `NNNu8, ` entry) so the output buffer is
+/// allocated once up front.
+fn estimate_module_size(info: &ModuleInfo) -> usize {
+    let functions: usize = info.ir_functions.iter().map(estimate_function_size).sum();
+    let data: usize = info
+        .data_segments
+        .iter()
+        .map(|s| s.data.len())
+        .chain(info.passive_data_segments.iter().map(|s| s.data.len()))
+        .sum();
+    MODULE_OVERHEAD_BYTES + functions + data * 6
+}
+
 /// Generate a complete Rust module from IR functions with full module info.
 ///
 /// This is the main entry point. It generates a module wrapper structure.
+/// `bindings` and `wit` add the wrappers declared outside the module.
 pub fn generate_module_with_info<B: Backend>(
     backend: &B,
     info: &LoweredModuleInfo,
+    bindings: Option<&BindingConfig>,
+    wit: Option<&WitWorld>,
+    preamble: Option<&Preamble>,
 ) -> Result<String> {
-    generate_wrapper_module(backend, info)
+    generate_wrapper_module(backend, info, bindings, wit, preamble)
 }
 
 /// Generate a module wrapper with Globals struct, constructor, and export methods.
-fn generate_wrapper_module<B: Backend>(backend: &B, info: &ModuleInfo) -> Result<String> {
-    let mut rust_code = rust_code_preamble(info);
-    let has_mut_globals = info.has_mutable_globals();
+fn generate_wrapper_module<B: Backend>(
+    backend: &B,
+    info: &ModuleInfo,
+    bindings: Option<&BindingConfig>,
+    wit: Option<&WitWorld>,
+    preamble: Option<&Preamble>,
+) -> Result<String> {
+    let mut rust_code = String::with_capacity(estimate_module_size(info));
+    rust_code.push_str(&rust_code_preamble(info, preamble));
+    let has_global_fields = info.has_global_fields();
 
     if info.has_memory {
         rust_code.push_str(&format!("const MAX_PAGES: usize = {};\n", info.max_pages));
     }
 
-    if info.has_table() {
+    if info.owns_table() {
         rust_code.push_str(&format!("const TABLE_MAX: usize = {};\n", info.table_max));
     }
     rust_code.push('\n');
 
     // Passive data segment consts (bulk-memory proposal)
     for seg in &info.passive_data_segments {
-        let bytes: Vec<String> = seg.data.iter().map(|b| format!("{b}u8")).collect();
         rust_code.push_str(&format!(
-            "#[allow(dead_code)]\nconst PASSIVE_SEGMENT_{}: &[u8] = &[{}];\n",
-            seg.wasm_index,
-            bytes.join(", ")
+            "#[allow(dead_code)]\nconst PASSIVE_SEGMENT_{}: &[u8] = &[",
+            seg.wasm_index
         ));
+        write_byte_list(&mut rust_code, &seg.data);
+        rust_code.push_str("];\n");
     }
     if !info.passive_data_segments.is_empty() {
         rust_code.push('\n');
     }
 
+    // Data segments the module never writes
+    for (idx, seg) in info.data_segments.iter().enumerate() {
+        if seg.read_only {
+            rust_code.push_str(&format!(
+                "/// Read-only data at address {:#x}.\npub const RODATA_{idx}: [u8; {}] = [",
+                seg.offset,
+                seg.data.len()
+            ));
+            write_byte_list(&mut rust_code, &seg.data);
+            rust_code.push_str("];\n\n");
+        }
+    }
+
+    // Large element segments, copied into the table by the constructor
+    rust_code.push_str(&emit_element_statics(info)?);
+
     // Environment block: ModuleHostTrait, NoHost impl, Globals struct, Env<H> struct
     rust_code.push_str(&generate_env_block(info));
 
     // Const items for immutable globals
     rust_code.push_str(&emit_const_globals(backend, info));
+    rust_code.push_str(&emit_dylink_consts(info));
 
     // Newtype wrapper struct (required to allow `impl WasmModule` on a foreign type)
     // Always use Globals for the type (it may be empty but is always generated)
     let globals_type = "Globals";
-    let table_size_str = if info.has_table() { "TABLE_MAX" } else { "0" };
+    let table_size_str = if info.owns_table() { "TABLE_MAX" } else { "0" };
     if info.has_memory {
         rust_code.push_str(&format!(
             "pub struct WasmModule(pub Module<{globals_type}, MAX_PAGES, {table_size_str}>);\n\n"
@@ -67,25 +146,135 @@ fn generate_wrapper_module<B: Backend>(backend: &B, info: &ModuleInfo) -> Result
             "pub struct WasmModule(pub LibraryModule<{globals_type}, {table_size_str}>);\n\n"
         ));
     }
+    rust_code.push_str(&emit_memory_limits(info));
 
     // Constructor (standalone free function)
-    rust_code.push_str(&generate_constructor(backend, info, has_mut_globals)?);
+    rust_code.push_str(&generate_constructor(backend, info, has_global_fields)?);
     rust_code.push('\n');
 
-    // Internal functions (private)
+    // Shims checking the guest memory passed to host imports
+    rust_code.push_str(&generate_import_checks(info));
+    rust_code.push_str(&crate::codegen::guest_log::generate_guest_log_shims(info));
+
+    // Internal functions (private); merged duplicates have no code of their own
+    let tracked = if info.heap_profile {
+        tracked_functions(info)
+    } else {
+        Vec::new()
+    };
+    let coverage_sites = coverage_sites(info);
+    let counters = block_counters(&coverage_sites, info.ir_functions.len());
+    let bounds_sites = bounds_sites(info);
+    let bounds_counters = site_counters(&bounds_sites, info.ir_functions.len());
     for (idx, ir_func) in info.ir_functions.iter().enumerate() {
-        let func_name = format!("func_{}", idx);
-        let code = generate_function_with_info(backend, ir_func, &func_name, info, false)
-            .with_context(|| format!("failed to generate code for function {}", idx))?;
-        rust_code.push_str(&code);
+        if info.is_merged(idx) {
+            continue;
+        }
+        let func_name = info.func_ident(idx);
+        // Allocator entry points keep their name for the profiling wrapper
+        let kind = tracked
+            .iter()
+            .find(|(func, _)| func.as_usize() == idx)
+            .map(|(_, kind)| *kind);
+        let body_name = match kind {
+            Some(_) => untracked_name(&func_name),
+            None => func_name.clone(),
+        };
+        rust_code.push_str(&func_cfg_attr("", info, idx));
+        rust_code.push_str(&must_use_attr("", info, idx));
+        write_function_with_info(
+            &mut rust_code,
+            backend,
+            ir_func,
+            &body_name,
+            info,
+            false,
+            info.is_constant_time(idx),
+            &counters[idx],
+            &bounds_counters[idx],
+        )
+        .with_context(|| format!("failed to generate code for function {}", idx))?;
         rust_code.push('\n');
+        if let Some(kind) = kind {
+            rust_code.push_str(&func_cfg_attr("", info, idx));
+            write_tracking_wrapper(&mut rust_code, backend, ir_func, &func_name, kind, info)?;
+            rust_code.push('\n');
+        }
     }
 
+    // Table dispatch shared by the `call_indirect`s of each type
+    rust_code.push_str(&generate_dispatchers(info));
+
     // Impl block with accessor methods for all functions
     if !info.ir_functions.is_empty() {
         rust_code.push_str(&generate_export_impl(backend, info));
         rust_code.push('\n');
+
+        // Namespaced views over export groups
+        rust_code.push_str(&generate_export_groups(info));
+    }
+
+    // Instance-free exports of modules without state
+    rust_code.push_str(&generate_pure_api(backend, info));
+
+    // Accessors for LLVM's shadow stack pointer
+    rust_code.push_str(&generate_shadow_stack(info));
+
+    // Where the linker placed data, stack and heap
+    rust_code.push_str(&generate_layout(info));
+
+    // Instantiation sequence for TinyGo guests
+    rust_code.push_str(&generate_tinygo_support(info));
+
+    // Helpers over the guest's own allocator exports
+    rust_code.push_str(&generate_guest_alloc(info));
+
+    // Statistics of the profiled guest allocator
+    rust_code.push_str(&generate_heap_stats(info));
+
+    // Poisoning and canaries for debug memory
+    rust_code.push_str(&generate_debug_memory(info));
+
+    // Coverage counters and their LCOV report
+    rust_code.push_str(&generate_coverage(info, &coverage_sites));
+
+    // Bounds-check counters and their heat map
+    rust_code.push_str(&generate_bounds_profile(info, &bounds_sites));
+
+    // High-level wrappers declared in the bindings file
+    if let Some(bindings) = bindings {
+        rust_code.push_str(&generate_bindings(bindings, info));
     }
 
+    // Canonical-ABI wrappers and import traits from the WIT world
+    rust_code.push_str(&generate_wit_bindings(wit, info));
+
+    // Name-based lookup for wasmtime-style host code
+    rust_code.push_str(&generate_wasmtime_adapter(info));
+
+    // Dispatch on export names for hosts without compile-time knowledge
+    rust_code.push_str(&generate_invoke(info));
+
+    // Glue for serving and consuming imports through a runtime linker
+    rust_code.push_str(&generate_link(info));
+
+    // C-ABI vtable for swapping the code of a running instance
+    rust_code.push_str(&generate_hot_reload(info));
+
+    // Host wrappers that record and replay host results
+    rust_code.push_str(&generate_replay(info));
+
+    // Instance bundled with a host shared with other instances
+    rust_code.push_str(&generate_shared_host(info));
+
+    // Symbol table for host-side trap reporting
+    rust_code.push_str(&generate_symbol_table(info));
+
+    // Size and instrumentation, for hosts inventorying modules
+    rust_code.push_str(&generate_metrics(info));
+
+    // Signatures and import names describing exported table entries
+    rust_code.push_str(&generate_table_types(info));
+
     Ok(rust_code)
 }
@@ -0,0 +1,159 @@
+//! Instance-free API for pure modules.
+//!
+//! A module with no memory, table, mutable globals or imports has no state,
+//! so creating a `WasmModule` to call it is pure ceremony. Its exports are
+//! also generated as free functions and as methods on a zero-sized marker:
+//!
+//! ```text
+//! pub mod pure {
+//!     pub const fn add(v0: i64, v1: i64) -> WasmResult<i64> { .. }
+//! }
+//!
+//! #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+//! pub struct PureModule;
+//!
+//! impl PureModule {
+//!     pub const fn add(&self, v0: i64, v1: i64) -> WasmResult<i64> { pure::add(v0, v1) }
+//! }
+//! ```
+//!
+//! Functions are `const fn` when the backend emits every instruction they
+//! contain in const-evaluable form. Calls, runtime-checked traps and
+//! comparisons are not, so in practice this covers leaf arithmetic.
+
+use crate::backend::Backend;
+use crate::codegen::contracts::{contract_asserts, contract_docs};
+use crate::codegen::types::{format_return_type, wasm_type_to_rust};
+use crate::codegen::utils::{export_cfg_attr, must_use_attr, rust_ident, too_many_args_attr};
+use crate::ir::*;
+
+/// Whether `ir_func` of a pure module is generated as a `const fn`.
+pub fn is_const_function<B: Backend>(backend: &B, ir_func: &IrFunction, info: &ModuleInfo) -> bool {
+    info.is_pure()
+        && ir_func
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .all(|instr| backend.is_const_instr(instr))
+}
+
+/// Generate the `pure` free functions and the `PureModule` marker.
+///
+/// Returns an empty string unless the module is pure and exports functions.
+pub fn generate_pure_api<B: Backend>(backend: &B, info: &ModuleInfo) -> String {
+    if !info.is_pure() || info.func_exports.is_empty() {
+        return String::new();
+    }
+    let mut free = String::from(
+        "/// The exports as free functions: the module has no state to instantiate.\npub mod pure {\n    use super::*;\n",
+    );
+    let mut marker = String::from(
+        "\n/// Zero-sized handle to the exports in [`pure`].\n#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]\npub struct PureModule;\n\nimpl PureModule {\n",
+    );
+
+    for export in &info.func_exports {
+        let Some(func) = info.ir_function(export.func_index) else {
+            continue;
+        };
+        let constness = if !info.is_constant_time(export.func_index.as_usize())
+            && is_const_function(backend, func, info)
+        {
+            "const "
+        } else {
+            ""
+        };
+        let name = rust_ident(&export.name);
+        let arg = |i| info.param_name(export.func_index.as_usize(), i);
+        let params: Vec<String> = func
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, (_, ty))| format!("{}: {}", arg(i), wasm_type_to_rust(ty)))
+            .collect();
+        let args: Vec<String> = (0..func.params.len()).map(arg).collect();
+        let return_type = format_return_type(&func.results);
+        let gate = export_cfg_attr("    ", info, &export.name);
+        let must_use = must_use_attr("    ", info, export.func_index.as_usize());
+
+        free.push('\n');
+        free.push_str(&contract_docs("    ", info, &export.name));
+        free.push_str(&gate);
+        free.push_str(&too_many_args_attr("    ", params.len()));
+        free.push_str(&must_use);
+        free.push_str(&format!(
+            "    pub {constness}fn {name}({}) -> {return_type} {{\n",
+            params.join(", ")
+        ));
+        free.push_str(&contract_asserts("        ", info, &export.name, func));
+        free.push_str("        let mut host = herkos_runtime::NoHost;\n");
+        free.push_str("        let mut globals = super::Globals {};\n");
+        free.push_str(&format!(
+            "        super::{}({}&mut super::Env {{ host: &mut host, globals: &mut globals }})\n",
+            info.func_ident(export.func_index.as_usize()),
+            args.iter().map(|a| format!("{a}, ")).collect::<String>()
+        ));
+        free.push_str("    }\n");
+
+        marker.push_str(&gate);
+        marker.push_str(&too_many_args_attr("    ", params.len() + 1));
+        marker.push_str(&must_use);
+        marker.push_str(&format!(
+            "    pub {constness}fn {name}(&self{}) -> {return_type} {{\n",
+            params.iter().map(|p| format!(", {p}")).collect::<String>()
+        ));
+        marker.push_str(&format!("        pure::{name}({})\n", args.join(", ")));
+        marker.push_str("    }\n");
+    }
+
+    free.push_str("}\n");
+    marker.push_str("}\n\n");
+    free + &marker
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    #[test]
+    fn leaf_arithmetic_is_const() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (global i32 (i32.const 3))
+                (func $sq (param i32) (result i32) local.get 0 local.get 0 i32.mul)
+                (func (export "square") (param i32) (result i32) local.get 0 call $sq)
+                (func (export "add") (param i64 i64) (result i64)
+                    local.get 0 local.get 1 i64.add)
+                (func (export "div") (param i32 i32) (result i32)
+                    local.get 0 local.get 1 i32.div_s))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+
+        assert!(code.contains("pub mod pure {"));
+        assert!(code.contains("pub struct PureModule;"));
+        assert!(code.contains("const fn func_sq<H: ModuleHostTrait>"));
+        assert!(code.contains("pub const fn add(v0: i64, v1: i64) -> WasmResult<i64> {"));
+        assert!(code.contains("pub const fn add(&self, v0: i64, v1: i64) -> WasmResult<i64> {"));
+        // Calls and runtime-checked division are not const
+        assert!(code.contains("pub fn square(v0: i32) -> WasmResult<i32> {"));
+        assert!(code.contains("pub fn div(&self, v0: i32, v1: i32) -> WasmResult<i32> {"));
+        assert!(code.contains(
+            "super::func_2(v0, v1, &mut super::Env { host: &mut host, globals: &mut globals })"
+        ));
+    }
+
+    #[test]
+    fn stateful_modules_have_no_pure_api() {
+        for wat in [
+            r#"(module (memory 1) (func (export "f")))"#,
+            r#"(module (global (mut i32) (i32.const 0)) (func (export "f")))"#,
+            r#"(module (import "env" "g" (func)) (func (export "f")))"#,
+            r#"(module (table 1 funcref) (func (export "f")))"#,
+        ] {
+            let wasm = wat::parse_str(wat).unwrap();
+            let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+            assert!(!code.contains("PureModule"), "{wat}");
+            assert!(!code.contains("const fn func_"), "{wat}");
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Record/replay host wrapper generation (`--record-replay`).
+//!
+//! Emits two `ModuleHostTrait` impls around `herkos_runtime::ReplayLog`:
+//!
+//! - `Recorder<'_, H>` forwards every call to the real host `H` and appends
+//!   what it returned to the log.
+//! - `Replayer` answers every call from a recorded log, so the run repeats
+//!   exactly without the original host.
+//!
+//! Each host method has a fixed number in the log: function imports first
+//! (in import order), then imported global getters, then `maybe_yield`.
+//! Setters of mutable imported globals only write the host, so the replayer
+//! ignores them.
+//!
+//! ```text
+//! fn random(&mut self) -> WasmResult<i32> {
+//!     let result = self.host.random();
+//!     self.log.get_mut().record(0, result.map(ReplayValue::from));
+//!     result
+//! }
+//! ```
+
+use crate::codegen::types::{format_return_type, wasm_type_to_rust};
+use crate::codegen::utils::rust_ident;
+use crate::ir::YIELD_METHOD;
+use crate::ir::*;
+
+/// Generate the `Recorder` and `Replayer` host wrappers.
+///
+/// Returns an empty string unless record/replay was requested.
+pub fn generate_replay(info: &ModuleInfo) -> String {
+    if !info.record_replay {
+        return String::new();
+    }
+    let methods = host_methods(info);
+
+    let mut code = String::new();
+    code.push_str(
+        "/// Host that forwards every call to `host` and records its result in a\n\
+         /// `ReplayLog`, for replay with [`Replayer`].\n",
+    );
+    code.push_str("pub struct Recorder<'a, H: ModuleHostTrait + ?Sized> {\n");
+    code.push_str("    pub host: &'a mut H,\n");
+    code.push_str("    log: core::cell::RefCell<ReplayLog>,\n");
+    code.push_str("}\n\n");
+    code.push_str("impl<'a, H: ModuleHostTrait + ?Sized> Recorder<'a, H> {\n");
+    code.push_str("    pub fn new(host: &'a mut H) -> Self {\n");
+    code.push_str("        Self {\n");
+    code.push_str("            host,\n");
+    code.push_str("            log: core::cell::RefCell::new(ReplayLog::new()),\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n\n");
+    code.push_str("    /// Results recorded so far.\n");
+    code.push_str("    pub fn into_log(self) -> ReplayLog {\n");
+    code.push_str("        self.log.into_inner()\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+
+    code.push_str("impl<H: ModuleHostTrait + ?Sized> ModuleHostTrait for Recorder<'_, H> {\n");
+    for (id, method) in methods.iter().enumerate() {
+        code.push_str(&format!("    {} {{\n", method.signature("arg")));
+        let args = method.args().join(", ");
+        match method.kind {
+            MethodKind::Call => {
+                code.push_str(&format!(
+                    "        let result = self.host.{}({args});\n",
+                    method.name
+                ));
+                code.push_str(&format!(
+                    "        self.log.get_mut().record({id}, result.map(ReplayValue::from));\n"
+                ));
+                code.push_str("        result\n");
+            }
+            MethodKind::Get => {
+                code.push_str(&format!(
+                    "        let value = self.host.{}();\n",
+                    method.name
+                ));
+                code.push_str(&format!(
+                    "        self.log.borrow_mut().record({id}, Ok(ReplayValue::from(value)));\n"
+                ));
+                code.push_str("        value\n");
+            }
+            MethodKind::Set => {
+                code.push_str(&format!("        self.host.{}({args});\n", method.name));
+            }
+        }
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+
+    code.push_str(
+        "/// Host that answers every call from a log recorded by [`Recorder`].\n\
+         ///\n\
+         /// Calls that differ from the recording trap with\n\
+         /// `WasmTrap::ReplayDiverged`; global reads return 0 instead.\n",
+    );
+    code.push_str("pub struct Replayer {\n");
+    code.push_str("    log: core::cell::RefCell<ReplayLog>,\n");
+    code.push_str("}\n\n");
+    code.push_str("impl Replayer {\n");
+    code.push_str("    pub fn new(log: ReplayLog) -> Self {\n");
+    code.push_str("        Self {\n");
+    code.push_str("            log: core::cell::RefCell::new(log),\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n\n");
+    code.push_str("    /// The log, with its replay position.\n");
+    code.push_str("    pub fn into_log(self) -> ReplayLog {\n");
+    code.push_str("        self.log.into_inner()\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+
+    code.push_str("impl ModuleHostTrait for Replayer {\n");
+    for (id, method) in methods.iter().enumerate() {
+        code.push_str(&format!("    {} {{\n", method.signature("_arg")));
+        match method.kind {
+            MethodKind::Call => code.push_str(&format!(
+                "        self.log.get_mut().replay({id}).and_then(TryInto::try_into)\n"
+            )),
+            MethodKind::Get => code.push_str(&format!(
+                "        self.log\n            .borrow_mut()\n            .replay({id})\n            .and_then(TryInto::try_into)\n            .unwrap_or_default()\n"
+            )),
+            MethodKind::Set => {}
+        }
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+
+    // `--observe-memory` makes the host trait a `MemoryObserver`
+    if info.observe_memory {
+        code.push_str("impl<H: ModuleHostTrait + ?Sized> MemoryObserver for Recorder<'_, H> {\n");
+        code.push_str("    fn on_load(&mut self, addr: usize, len: usize) {\n");
+        code.push_str("        self.host.on_load(addr, len);\n");
+        code.push_str("    }\n\n");
+        code.push_str("    fn on_store(&mut self, addr: usize, len: usize) {\n");
+        code.push_str("        self.host.on_store(addr, len);\n");
+        code.push_str("    }\n");
+        code.push_str("}\n\n");
+        code.push_str("impl MemoryObserver for Replayer {}\n\n");
+    }
+
+    code
+}
+
+/// How a host method reaches the log.
+enum MethodKind {
+    /// Import or yield point: records its `WasmResult`.
+    Call,
+    /// Imported global getter: records the value.
+    Get,
+    /// Imported global setter: not recorded.
+    Set,
+}
+
+/// One `ModuleHostTrait` method the wrappers implement.
+struct HostMethod {
+    name: String,
+    params: Vec<&'static str>,
+    ret: String,
+    kind: MethodKind,
+}
+
+impl HostMethod {
+    /// `fn name(&mut self, {prefix}0: i32, ...) -> ...`
+    fn signature(&self, prefix: &str) -> String {
+        let receiver = match self.kind {
+            MethodKind::Get => "&self",
+            _ => "&mut self",
+        };
+        let mut params = vec![receiver.to_string()];
+        params.extend(
+            self.params
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| format!("{prefix}{i}: {ty}")),
+        );
+        match self.kind {
+            MethodKind::Set => format!("fn {}({})", self.name, params.join(", ")),
+            _ => format!("fn {}({}) -> {}", self.name, params.join(", "), self.ret),
+        }
+    }
+
+    /// Forwarded arguments: `arg0, arg1, ...`
+    fn args(&self) -> Vec<String> {
+        (0..self.params.len()).map(|i| format!("arg{i}")).collect()
+    }
+}
+
+/// Host methods in log order.
+fn host_methods(info: &ModuleInfo) -> Vec<HostMethod> {
+    let mut methods: Vec<HostMethod> = info
+        .func_imports
+        .iter()
+        .map(|imp| HostMethod {
+            name: rust_ident(&imp.func_name),
+            params: imp.params.iter().map(wasm_type_to_rust).collect(),
+            ret: format_return_type(&imp.results),
+            kind: MethodKind::Call,
+        })
+        .collect();
+    for g in &info.imported_globals {
+        let ty = wasm_type_to_rust(&g.wasm_type);
+        methods.push(HostMethod {
+            name: format!("get_{}", g.name),
+            params: Vec::new(),
+            ret: ty.to_string(),
+            kind: MethodKind::Get,
+        });
+    }
+    if info.yield_points {
+        methods.push(HostMethod {
+            name: YIELD_METHOD.to_string(),
+            params: Vec::new(),
+            ret: format_return_type(&[]),
+            kind: MethodKind::Call,
+        });
+    }
+    // Setters last: they take no log slot
+    for g in info.imported_globals.iter().filter(|g| g.mutable) {
+        methods.push(HostMethod {
+            name: format!("set_{}", g.name),
+            params: vec![wasm_type_to_rust(&g.wasm_type)],
+            ret: String::new(),
+            kind: MethodKind::Set,
+        });
+    }
+    methods
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TranspileOptions;
+
+    fn transpile(wat: &str, options: &TranspileOptions) -> anyhow::Result<String> {
+        crate::transpile(&wat::parse_str(wat).unwrap(), options)
+    }
+
+    #[test]
+    fn recorder_and_replayer_number_host_methods() {
+        let options = TranspileOptions {
+            record_replay: true,
+            yield_points: true,
+            ..Default::default()
+        };
+        let code = transpile(
+            r#"(module
+                (import "env" "random" (func $random (result i32)))
+                (import "env" "log" (func $log (param i32 f64)))
+                (import "env" "seed" (global $seed (mut i64)))
+                (func (export "run") (result i32)
+                    (global.set $seed (global.get $seed))
+                    (call $log (call $random) (f64.const 0))
+                    (call $random)))"#,
+            &options,
+        )
+        .unwrap();
+
+        assert!(
+            code.contains("        self.log.get_mut().record(0, result.map(ReplayValue::from));\n")
+        );
+        assert!(code.contains("    fn log(&mut self, arg0: i32, arg1: f64) -> WasmResult<()> {\n        let result = self.host.log(arg0, arg1);\n        self.log.get_mut().record(1, "));
+        assert!(code
+            .contains("        self.log.borrow_mut().record(2, Ok(ReplayValue::from(value)));\n"));
+        assert!(code.contains(
+            "        let result = self.host.maybe_yield();\n        self.log.get_mut().record(3, "
+        ));
+        assert!(code.contains(
+            "    fn set_seed(&mut self, arg0: i64) {\n        self.host.set_seed(arg0);\n    }\n"
+        ));
+
+        assert!(code.contains("    fn random(&mut self) -> WasmResult<i32> {\n        self.log.get_mut().replay(0).and_then(TryInto::try_into)\n    }\n"));
+        assert!(code.contains("    fn set_seed(&mut self, _arg0: i64) {\n    }\n"));
+
+        let plain = transpile(
+            r#"(module (import "env" "random" (func (result i32))))"#,
+            &TranspileOptions::default(),
+        )
+        .unwrap();
+        assert!(!plain.contains("Recorder"));
+    }
+
+    #[test]
+    fn c_backend_rejects_record_replay() {
+        let options = TranspileOptions {
+            record_replay: true,
+            mode: "c".to_string(),
+            ..Default::default()
+        };
+        let err = transpile("(module)", &options).unwrap_err();
+        assert!(format!("{err:#}").contains("record/replay is not supported"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Shadow stack helper generation.
+//!
+//! When the module defines LLVM's `__stack_pointer` global (see
+//! [`ModuleInfo::stack_pointer`]), emits accessors for it and a constructor
+//! that places the instance's stack at a host-chosen top:
+//!
+//! ```text
+//! pub const STACK_POINTER_INIT: i32 = 66560;
+//!
+//! impl WasmModule {
+//!     pub fn stack_pointer(&self) -> i32 { self.0.globals.stack_pointer }
+//!     pub fn set_stack_pointer(&mut self, sp: i32) { self.0.globals.stack_pointer = sp; }
+//!     pub fn with_stack<R>(&mut self, top: i32, f: impl FnOnce(&mut Self) -> R) -> WasmResult<R> { ... }
+//! }
+//!
+//! pub fn new_with_stack(top: i32) -> WasmResult<WasmModule> { ... }
+//! ```
+
+use crate::codegen::types::global_init_to_rust;
+use crate::ir::*;
+
+/// Generate the shadow stack helpers.
+///
+/// Returns an empty string when the module has no `__stack_pointer`.
+pub fn generate_shadow_stack(info: &ModuleInfo) -> String {
+    let Some(idx) = info.stack_pointer else {
+        return String::new();
+    };
+    let Some(global) = info.local_global(idx) else {
+        return String::new();
+    };
+    let field = format!("self.0.globals.{}", global.field);
+    let (_, init) = global_init_to_rust(&global.init_value, info);
+    let memory_size = if info.has_memory {
+        "Some(self.0.memory.as_slice().len())"
+    } else {
+        "None"
+    };
+
+    let mut code = String::from(
+        "/// Initial value of the shadow stack pointer (`__stack_pointer`): the top of\n\
+         /// the stack region reserved by the linker.\n",
+    );
+    code.push_str(&format!("pub const STACK_POINTER_INIT: i32 = {init};\n\n"));
+
+    code.push_str("impl WasmModule {\n");
+    code.push_str("    /// Current shadow stack pointer (`__stack_pointer`).\n");
+    code.push_str(&format!(
+        "    pub fn stack_pointer(&self) -> i32 {{\n        {field}\n    }}\n"
+    ));
+    code.push_str("    /// Move the shadow stack pointer.\n");
+    code.push_str(&format!(
+        "    pub fn set_stack_pointer(&mut self, sp: i32) {{\n        {field} = sp;\n    }}\n"
+    ));
+    code.push_str(
+        "    /// Run `f` on a separate shadow stack growing down from `top`, restoring the\n\
+         \x20   /// previous stack pointer afterwards (also when `f` traps).\n",
+    );
+    code.push_str(
+        "    pub fn with_stack<R>(&mut self, top: i32, f: impl FnOnce(&mut Self) -> R) -> WasmResult<R> {\n",
+    );
+    code.push_str(&format!(
+        "        let top = check_stack_top(top, {memory_size})?;\n"
+    ));
+    code.push_str(&format!(
+        "        let saved = core::mem::replace(&mut {field}, top);\n"
+    ));
+    code.push_str("        let result = f(self);\n");
+    code.push_str(&format!("        {field} = saved;\n"));
+    code.push_str("        Ok(result)\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+
+    code.push_str(
+        "/// Instantiate the module with its shadow stack growing down from `top`\n\
+         /// instead of [`STACK_POINTER_INIT`], so instances sharing a memory get\n\
+         /// disjoint stack regions.\n",
+    );
+    if let Some((generics, param)) = crate::codegen::constructor::new_host_param(info) {
+        code.push_str(&format!(
+            "pub fn new_with_stack{generics}(top: i32, {param}) -> WasmResult<WasmModule> {{\n"
+        ));
+        code.push_str("    let mut module = new(host)?;\n");
+    } else {
+        code.push_str("pub fn new_with_stack(top: i32) -> WasmResult<WasmModule> {\n");
+        code.push_str("    let mut module = new()?;\n");
+    }
+    code.push_str(&format!(
+        "    let top = check_stack_top(top, {})?;\n",
+        memory_size.replace("self.", "module.")
+    ));
+    code.push_str("    module.set_stack_pointer(top);\n");
+    code.push_str("    Ok(module)\n");
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_wat(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        transpile(&wasm, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn emits_helpers_for_named_stack_pointer() {
+        let code = transpile_wat(
+            r#"(module
+                (memory 2)
+                (global $counter (mut i32) (i32.const 0))
+                (global $__stack_pointer (mut i32) (i32.const 66560))
+                (func (export "f")))"#,
+        );
+        assert!(code.contains("pub const STACK_POINTER_INIT: i32 = 66560i32;"));
+        assert!(code.contains(
+            "    pub fn stack_pointer(&self) -> i32 {\n        self.0.globals.stack_pointer\n    }\n"
+        ));
+        assert!(code.contains("        self.0.globals.stack_pointer = sp;\n"));
+        assert!(code.contains(
+            "        let top = check_stack_top(top, Some(self.0.memory.as_slice().len()))?;\n"
+        ));
+        assert!(code.contains(
+            "    let top = check_stack_top(top, Some(module.0.memory.as_slice().len()))?;\n"
+        ));
+    }
+
+    #[test]
+    fn finds_exported_stack_pointer_in_imported_memory_modules() {
+        let code = transpile_wat(
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (global (export "__stack_pointer") (mut i32) (i32.const 4096)))"#,
+        );
+        assert!(code.contains("pub fn new_with_stack(top: i32) -> WasmResult<WasmModule> {"));
+        assert!(code.contains("check_stack_top(top, None)?"));
+    }
+
+    #[test]
+    fn ignores_immutable_or_unnamed_globals() {
+        let immutable =
+            transpile_wat(r#"(module (global (export "__stack_pointer") i32 (i32.const 4096)))"#);
+        assert!(!immutable.contains("stack_pointer("));
+        let unnamed = transpile_wat(r#"(module (global (mut i32) (i32.const 4096)))"#);
+        assert!(!unnamed.contains("stack_pointer("));
+    }
+}
@@ -0,0 +1,142 @@
+//! Wrapper for a host shared by several instances (`--shared-host`).
+//!
+//! Export methods borrow the host as `&mut H` per call, which leaves hosts
+//! serving several instances to juggle the borrows themselves. The
+//! `SharedWasmModule<S>` wrapper owns a `herkos_runtime::SharedHost`
+//! handle (`Rc<RefCell<H>>`, `Arc<Mutex<H>>`, ...) next to the instance and
+//! borrows the host only for the duration of each call:
+//!
+//! ```text
+//! pub fn add(&mut self, v0: i32) -> WasmResult<i32> {
+//!     let module = &mut self.module;
+//!     self.host.with_host(|host| module.add(v0, host))
+//! }
+//! ```
+
+use crate::codegen::types::{format_return_type, wasm_type_to_rust};
+use crate::codegen::utils::{
+    export_cfg_attr, must_use_attr, rust_ident, too_many_args_attr, ForwardedState,
+};
+use crate::ir::*;
+
+/// Generate `SharedWasmModule` and its forwarding methods.
+///
+/// Returns an empty string unless a shared host was requested.
+pub fn generate_shared_host(info: &ModuleInfo) -> String {
+    if !info.shared_host {
+        return String::new();
+    }
+    // Imported memory and table are still passed per call; only the host
+    // comes from the shared handle
+    let forwarded = ForwardedState::new(info);
+    let generics: Vec<&str> = forwarded
+        .generics
+        .iter()
+        .copied()
+        .filter(|g| !g.starts_with("H:"))
+        .collect();
+    let generic_part = if generics.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", generics.join(", "))
+    };
+    let state_params: Vec<&str> = forwarded
+        .params
+        .iter()
+        .copied()
+        .filter(|p| !p.starts_with("host:"))
+        .collect();
+
+    let mut code = String::new();
+    code.push_str(
+        "/// The module with a host it shares with other instances, borrowed\n\
+         /// through [`SharedHost`] for the duration of each call.\n",
+    );
+    code.push_str("pub struct SharedWasmModule<S> {\n");
+    code.push_str("    pub module: WasmModule,\n");
+    code.push_str("    pub host: S,\n");
+    code.push_str("}\n\n");
+    code.push_str("impl<S> SharedWasmModule<S>\nwhere\n    S: SharedHost,\n    S::Host: ModuleHostTrait,\n{\n");
+    code.push_str("    pub fn new(module: WasmModule, host: S) -> Self {\n");
+    code.push_str("        Self { module, host }\n");
+    code.push_str("    }\n");
+
+    for export in &info.func_exports {
+        let Some(func) = info.ir_function(export.func_index) else {
+            continue;
+        };
+        let name = rust_ident(&export.name);
+        let mut params = vec!["&mut self".to_string()];
+        let mut args = Vec::new();
+        for (i, (_, ty)) in func.params.iter().enumerate() {
+            let arg = info.param_name(export.func_index.as_usize(), i);
+            params.push(format!("{arg}: {}", wasm_type_to_rust(ty)));
+            args.push(arg);
+        }
+        params.extend(state_params.iter().map(|p| p.to_string()));
+        args.extend(
+            forwarded
+                .args
+                .iter()
+                .filter(|a| **a != "host")
+                .map(|a| a.to_string()),
+        );
+        args.push("host".to_string());
+
+        code.push('\n');
+        code.push_str(&export_cfg_attr("    ", info, &export.name));
+        code.push_str(&too_many_args_attr("    ", params.len()));
+        code.push_str(&must_use_attr("    ", info, export.func_index.as_usize()));
+        code.push_str(&format!(
+            "    pub fn {name}{generic_part}({}) -> {} {{\n",
+            params.join(", "),
+            format_return_type(&func.results)
+        ));
+        code.push_str("        let module = &mut self.module;\n");
+        code.push_str(&format!(
+            "        self.host.with_host(|host| module.{name}({}))\n",
+            args.join(", ")
+        ));
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn options() -> TranspileOptions {
+        TranspileOptions {
+            shared_host: true,
+            ..TranspileOptions::default()
+        }
+    }
+
+    #[test]
+    fn methods_borrow_the_shared_host_per_call() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (import "env" "tick" (func $tick (param i32) (result i32)))
+                (func (export "bump") (param i32) (result i32)
+                    (call $tick (local.get 0))))"#,
+        )
+        .unwrap();
+        let code = transpile(&wasm, &options()).unwrap();
+
+        assert!(code.contains("pub struct SharedWasmModule<S> {"));
+        assert!(code.contains(
+            "pub fn bump<const MP: usize>(&mut self, v0: i32, memory: &mut IsolatedMemory<MP>) -> WasmResult<i32> {"
+        ));
+        assert!(code.contains("self.host.with_host(|host| module.bump(v0, memory, host))"));
+    }
+
+    #[test]
+    fn modules_without_a_host_are_rejected() {
+        let wasm = wat::parse_str(r#"(module (func (export "f")))"#).unwrap();
+        let err = transpile(&wasm, &options()).unwrap_err();
+        assert!(format!("{err:#}").contains("--shared-host needs a module"));
+    }
+}
@@ -0,0 +1,685 @@
+//! Structured control flow for generated functions.
+//!
+//! A function's blocks can always be emitted as a state machine (a
+//! `loop { match __current_block { ... } }`, see [`crate::codegen::function`]),
+//! but rustc optimizes that poorly and nobody can read it. This module
+//! rebuilds `loop`, `if`/`else`, `match` and labeled `break` / `continue`
+//! from the CFG instead, following Ramsey's "Beyond Relooper" (ICFP 2022):
+//!
+//! - A block with a back edge (a loop header) becomes `'lN: loop { ... }`,
+//!   and the back edges `continue 'lN`.
+//! - A block reached by two or more forward edges (a merge node) is emitted
+//!   after a labeled block `'bN: { ... }` around its immediate dominator's
+//!   code, and those edges become `break 'bN`.
+//! - A block reached by one forward edge is emitted inline at its branch.
+//!
+//! Labels are named after the block index, as in the state machine's
+//! `Block::BN`. The translation needs a reducible CFG, which every function
+//! translated from Wasm has; [`plan`] returns `None` for others, and for
+//! functions that would nest deeper than [`MAX_NESTING`], so the caller can
+//! fall back to the state machine.
+
+use crate::backend::Backend;
+use crate::ir::*;
+use crate::optimizer::utils::{compute_idoms, compute_rpo, terminator_successors};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// Deepest nesting of loops, labeled blocks and branches emitted before
+/// falling back to the state machine; rustc's parser recurses on each.
+pub const MAX_NESTING: usize = 128;
+
+/// Structured code for part of a function.
+#[derive(Debug)]
+pub enum Shape {
+    /// Block `idx`'s instructions, then its terminator with one [`Branch`]
+    /// per successor (in [`terminator_successors`] order).
+    Code { idx: usize, branches: Vec<Branch> },
+    /// `'lN: loop { body }`, where block `header` (N) starts `body`.
+    Loop { header: usize, body: Box<Shape> },
+    /// `'bN: { body }` followed by `then`, the code of block `follow` (N).
+    Block {
+        follow: usize,
+        body: Box<Shape>,
+        then: Box<Shape>,
+    },
+}
+
+/// How a terminator reaches one of its successors.
+#[derive(Debug)]
+pub enum Branch {
+    /// `continue 'lN`: back to loop header N.
+    Continue(usize),
+    /// `break 'bN`: forward to merge node N.
+    Break(usize),
+    /// The successor's code, in place.
+    Inline(Box<Shape>),
+}
+
+/// CFG facts the translation looks up.
+struct Cfg<'a> {
+    func: &'a IrFunction,
+    index: HashMap<BlockId, usize>,
+    rpo: HashMap<BlockId, usize>,
+    idom: HashMap<BlockId, BlockId>,
+    /// Dominator tree children of each block, by reverse postorder.
+    children: HashMap<BlockId, Vec<BlockId>>,
+    loop_headers: HashSet<BlockId>,
+    merge_nodes: HashSet<BlockId>,
+}
+
+impl Cfg<'_> {
+    fn is_backward(&self, from: BlockId, to: BlockId) -> bool {
+        self.rpo[&to] <= self.rpo[&from]
+    }
+
+    fn dominates(&self, dom: BlockId, mut block: BlockId) -> bool {
+        loop {
+            if block == dom {
+                return true;
+            }
+            match self.idom.get(&block) {
+                Some(&up) if up != block => block = up,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Structured translation of `func`, or `None` when its CFG is irreducible
+/// or the result would nest deeper than [`MAX_NESTING`].
+pub fn plan(func: &IrFunction) -> Option<Shape> {
+    let order = compute_rpo(func);
+    let rpo: HashMap<BlockId, usize> = order.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+    let index: HashMap<BlockId, usize> = func
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (b.id, i))
+        .collect();
+    let idom = compute_idoms(func);
+
+    let mut children: HashMap<BlockId, Vec<BlockId>> = HashMap::new();
+    for &block in order.iter().skip(1) {
+        children.entry(idom[&block]).or_default().push(block);
+    }
+
+    let mut cfg = Cfg {
+        func,
+        index,
+        rpo,
+        idom,
+        children,
+        loop_headers: HashSet::new(),
+        merge_nodes: HashSet::new(),
+    };
+
+    let mut forward_edges: HashMap<BlockId, usize> = HashMap::new();
+    for &block in &order {
+        let term = &func.blocks[cfg.index[&block]].terminator;
+        for succ in terminator_successors(term) {
+            if !cfg.is_backward(block, succ) {
+                *forward_edges.entry(succ).or_default() += 1;
+            } else if cfg.dominates(succ, block) {
+                cfg.loop_headers.insert(succ);
+            } else {
+                // A retreating edge into a loop it does not dominate
+                return None;
+            }
+        }
+    }
+    cfg.merge_nodes = forward_edges
+        .into_iter()
+        .filter(|&(_, count)| count >= 2)
+        .map(|(block, _)| block)
+        .collect();
+
+    do_tree(&cfg, func.entry_block, 0)
+}
+
+/// The code for `block` and everything it dominates.
+fn do_tree(cfg: &Cfg, block: BlockId, depth: usize) -> Option<Shape> {
+    if depth > MAX_NESTING {
+        return None;
+    }
+    // Merge nodes follow the code, the latest one outermost
+    let mut merges: Vec<BlockId> = cfg
+        .children
+        .get(&block)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|c| cfg.merge_nodes.contains(c))
+        .collect();
+    merges.reverse();
+
+    if cfg.loop_headers.contains(&block) {
+        let body = node_within(cfg, block, &merges, depth + 1)?;
+        Some(Shape::Loop {
+            header: cfg.index[&block],
+            body: Box::new(body),
+        })
+    } else {
+        node_within(cfg, block, &merges, depth)
+    }
+}
+
+/// The code for `block`, nested in one labeled block per merge node in
+/// `merges` (outermost first), each followed by that node's code.
+fn node_within(cfg: &Cfg, block: BlockId, merges: &[BlockId], depth: usize) -> Option<Shape> {
+    if depth > MAX_NESTING {
+        return None;
+    }
+    match merges.split_first() {
+        Some((&follow, inner)) => Some(Shape::Block {
+            follow: cfg.index[&follow],
+            body: Box::new(node_within(cfg, block, inner, depth + 1)?),
+            then: Box::new(do_tree(cfg, follow, depth)?),
+        }),
+        None => {
+            // A jump's target follows in place; other branches nest
+            let term = &cfg.func.blocks[cfg.index[&block]].terminator;
+            let inner = match term {
+                IrTerminator::Jump { .. } => depth,
+                _ => depth + 1,
+            };
+            let branches = terminator_successors(term)
+                .into_iter()
+                .map(|succ| do_branch(cfg, block, succ, inner))
+                .collect::<Option<_>>()?;
+            Some(Shape::Code {
+                idx: cfg.index[&block],
+                branches,
+            })
+        }
+    }
+}
+
+fn do_branch(cfg: &Cfg, from: BlockId, to: BlockId, depth: usize) -> Option<Branch> {
+    Some(if cfg.is_backward(from, to) {
+        Branch::Continue(cfg.index[&to])
+    } else if cfg.merge_nodes.contains(&to) {
+        Branch::Break(cfg.index[&to])
+    } else {
+        Branch::Inline(Box::new(do_tree(cfg, to, depth)?))
+    })
+}
+
+/// What [`write_shape`] emits in each block besides its instructions.
+pub struct BlockExtras<'a> {
+    /// Blocks starting with a yield point.
+    pub yield_blocks: &'a HashSet<BlockId>,
+    /// Blocks counted under `--coverage`, with their counter.
+    pub coverage: &'a [(BlockId, usize)],
+    /// Accesses counted under `--bounds-profile`, by block and instruction
+    /// index, with their counter.
+    pub bounds_checks: &'a [(BlockId, usize, usize)],
+    /// Emit `select` without branching on its condition.
+    pub constant_time: bool,
+    /// Type of every variable, for branchless `select`.
+    pub var_types: &'a BTreeMap<VarId, WasmType>,
+}
+
+/// Append `shape`, the whole body of `func`, to `out`, indented by `depth`
+/// levels.
+pub fn write_shape<B: Backend>(
+    out: &mut String,
+    backend: &B,
+    func: &IrFunction,
+    info: &ModuleInfo,
+    extras: &BlockExtras,
+    shape: &Shape,
+    depth: usize,
+) -> Result<()> {
+    let mut writer = Writer {
+        out,
+        backend,
+        func,
+        info,
+        extras,
+    };
+    let tail = Tail {
+        function: true,
+        of_loop: None,
+        of_block: None,
+    };
+    writer.shape(shape, depth, tail)
+}
+
+/// Where the code being written ends: at the end of the function body,
+/// where a `return` is needless, or of a loop or labeled block body, where
+/// a `continue` to that loop or a `break` out of that block is.
+#[derive(Clone, Copy)]
+struct Tail {
+    function: bool,
+    of_loop: Option<usize>,
+    of_block: Option<usize>,
+}
+
+impl Tail {
+    /// Whether `branch` needs no code here.
+    fn is_silent(self, branch: &Branch) -> bool {
+        match branch {
+            Branch::Continue(header) => self.of_loop == Some(*header),
+            Branch::Break(follow) => self.of_block == Some(*follow),
+            Branch::Inline(_) => false,
+        }
+    }
+}
+
+struct Writer<'a, B: Backend> {
+    out: &'a mut String,
+    backend: &'a B,
+    func: &'a IrFunction,
+    info: &'a ModuleInfo,
+    extras: &'a BlockExtras<'a>,
+}
+
+impl<B: Backend> Writer<'_, B> {
+    fn shape(&mut self, shape: &Shape, depth: usize, tail: Tail) -> Result<()> {
+        let pad = "    ".repeat(depth);
+        match shape {
+            Shape::Loop { header, body } => {
+                let body_tail = Tail {
+                    function: false,
+                    of_loop: Some(*header),
+                    of_block: None,
+                };
+                let body = self.nested(body, depth + 1, body_tail)?;
+                // The label goes when only the fall-through continues
+                let label = format!("'l{header}");
+                if body.contains(&format!("{label};")) {
+                    writeln!(self.out, "{pad}{label}: loop {{")?;
+                } else {
+                    writeln!(self.out, "{pad}loop {{")?;
+                }
+                // A Wasm loop branching only to itself spins forever; an empty
+                // `loop {}` says the same but trips clippy's `empty_loop`
+                if body.trim().is_empty() {
+                    writeln!(self.out, "{pad}    core::hint::spin_loop();")?;
+                }
+                self.out.push_str(&body);
+                writeln!(self.out, "{pad}}}")?;
+            }
+            Shape::Block { follow, body, then } => {
+                let body_tail = Tail {
+                    function: false,
+                    of_loop: None,
+                    of_block: Some(*follow),
+                };
+                let body = self.nested(body, depth + 1, body_tail)?;
+                // Without a `break`, the body simply runs into `then`
+                let label = format!("'b{follow}");
+                if body.contains(&format!("{label};")) {
+                    writeln!(self.out, "{pad}{label}: {{")?;
+                    self.out.push_str(&body);
+                    writeln!(self.out, "{pad}}}")?;
+                } else {
+                    self.out.push_str(&dedent(&body));
+                }
+                self.shape(then, depth, tail)?;
+            }
+            Shape::Code { idx, branches } => {
+                let block = &self.func.blocks[*idx];
+                self.block_body(block, depth)?;
+                match &block.terminator {
+                    IrTerminator::Jump { .. } => self.branch(&branches[0], depth, tail)?,
+                    IrTerminator::BranchIf { condition, .. } => {
+                        let conds = (format!("{condition} != 0"), format!("{condition} == 0"));
+                        self.two_way(conds, &branches[0], &branches[1], depth, tail)?;
+                    }
+                    IrTerminator::BranchTable { index, .. } => {
+                        let (default, targets) = branches.split_last().expect("default target");
+                        match targets {
+                            [] => self.branch(default, depth, tail)?,
+                            [target] => {
+                                let conds = (format!("{index} == 0"), format!("{index} != 0"));
+                                self.two_way(conds, target, default, depth, tail)?;
+                            }
+                            _ => {
+                                // Arms that do what the default does are left to `_`.
+                                let otherwise = self.arm("_", default, depth + 1, tail)?;
+                                let mut arms = Vec::new();
+                                let arm_pad = format!("{pad}    ");
+                                for (i, target) in targets.iter().enumerate() {
+                                    let mut arm =
+                                        self.arm(&i.to_string(), target, depth + 1, tail)?;
+                                    if let Some(guarded) = guarded_arm(&arm, &otherwise, &arm_pad) {
+                                        arm = guarded;
+                                    }
+                                    if arm.split_once(" => ").map(|(_, b)| b)
+                                        != otherwise.split_once(" => ").map(|(_, b)| b)
+                                    {
+                                        arms.push((i, target, arm));
+                                    }
+                                }
+                                match arms.as_slice() {
+                                    [] => self.branch(default, depth, tail)?,
+                                    [(i, target, _)] => {
+                                        let conds =
+                                            (format!("{index} == {i}"), format!("{index} != {i}"));
+                                        self.two_way(conds, target, default, depth, tail)?;
+                                    }
+                                    _ => {
+                                        writeln!(self.out, "{pad}match {index} as usize {{")?;
+                                        for (_, _, arm) in &arms {
+                                            self.out.push_str(arm);
+                                        }
+                                        self.out.push_str(&otherwise);
+                                        writeln!(self.out, "{pad}}}")?;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    IrTerminator::Return { .. } | IrTerminator::Unreachable => {
+                        let mut code = String::new();
+                        crate::codegen::instruction::write_terminator(
+                            &mut code,
+                            self.backend,
+                            &block.terminator,
+                            &HashMap::new(),
+                            &self.func.results,
+                        )?;
+                        if tail.function {
+                            code = tail_expression(&code);
+                        }
+                        push_reindented(self.out, &code, &pad);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `shape` written on its own, for its enclosing construct to inspect.
+    fn nested(&mut self, shape: &Shape, depth: usize, tail: Tail) -> Result<String> {
+        let start = self.out.len();
+        self.shape(shape, depth, tail)?;
+        Ok(self.out.split_off(start))
+    }
+
+    /// Write `branch` on its own and return its text.
+    fn nested_branch(&mut self, branch: &Branch, depth: usize, tail: Tail) -> Result<String> {
+        let start = self.out.len();
+        self.branch(branch, depth, tail)?;
+        Ok(self.out.split_off(start))
+    }
+
+    /// `if` on `conds` (the condition for `if_true`, then its negation),
+    /// leaving out a branch that writes nothing and writing identical
+    /// branches only once.
+    fn two_way(
+        &mut self,
+        (cond, negated): (String, String),
+        if_true: &Branch,
+        if_false: &Branch,
+        depth: usize,
+        tail: Tail,
+    ) -> Result<()> {
+        let pad = "    ".repeat(depth);
+        let then = self.nested_branch(if_true, depth + 1, tail)?;
+        let other = self.nested_branch(if_false, depth + 1, tail)?;
+        match (then.trim().is_empty(), other.trim().is_empty()) {
+            (true, true) => {}
+            _ if then == other => self.out.push_str(&dedent(&then)),
+            (false, true) => self.write_if(&cond, &then, &pad)?,
+            (true, false) => self.write_if(&negated, &other, &pad)?,
+            (false, false) => {
+                writeln!(self.out, "{pad}if {cond} {{")?;
+                self.out.push_str(&then);
+                writeln!(self.out, "{pad}}} else {{")?;
+                self.out.push_str(&other);
+                writeln!(self.out, "{pad}}}")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `if cond { body }`, with a lone `if` without `else` in `body` folded
+    /// into the condition (clippy's `collapsible_if`).
+    fn write_if(&mut self, cond: &str, body: &str, pad: &str) -> Result<()> {
+        match lone_if(body, &format!("{pad}    ")) {
+            Some((inner, then, None)) => {
+                writeln!(self.out, "{pad}if {cond} && {inner} {{")?;
+                self.out.push_str(&dedent(&then));
+            }
+            _ => {
+                writeln!(self.out, "{pad}if {cond} {{")?;
+                self.out.push_str(body);
+            }
+        }
+        writeln!(self.out, "{pad}}}")?;
+        Ok(())
+    }
+
+    /// The `pattern => { .. }` arm for `branch`.
+    fn arm(&mut self, pattern: &str, branch: &Branch, depth: usize, tail: Tail) -> Result<String> {
+        let pad = "    ".repeat(depth);
+        let body = self.nested_branch(branch, depth + 1, tail)?;
+        Ok(if body.trim().is_empty() {
+            format!("{pad}{pattern} => {{}}\n")
+        } else {
+            format!("{pad}{pattern} => {{\n{body}{pad}}}\n")
+        })
+    }
+
+    fn branch(&mut self, branch: &Branch, depth: usize, tail: Tail) -> Result<()> {
+        let pad = "    ".repeat(depth);
+        match branch {
+            _ if tail.is_silent(branch) => {}
+            Branch::Continue(header) => writeln!(self.out, "{pad}continue 'l{header};")?,
+            Branch::Break(follow) => writeln!(self.out, "{pad}break 'b{follow};")?,
+            Branch::Inline(shape) => self.shape(shape, depth, tail)?,
+        }
+        Ok(())
+    }
+
+    /// The yield point, coverage counter and instructions of `block`, with
+    /// the bounds-check counters of its accesses.
+    fn block_body(&mut self, block: &IrBlock, depth: usize) -> Result<()> {
+        let extras = self.extras;
+        let mut code = String::new();
+        if extras.yield_blocks.contains(&block.id) {
+            self.backend.emit_yield_point(&mut code)?;
+            code.push('\n');
+        }
+        if let Some(&(_, counter)) = extras.coverage.iter().find(|(id, _)| *id == block.id) {
+            code.push_str(&crate::codegen::coverage::count_statement(counter));
+            code.push('\n');
+        }
+        for (instr_idx, instr) in block.instructions.iter().enumerate() {
+            if let Some(counter) = crate::codegen::bounds_profile::counter_of(
+                extras.bounds_checks,
+                block.id,
+                instr_idx,
+            ) {
+                code.push_str(&crate::codegen::bounds_profile::count_statement(counter));
+                code.push('\n');
+            }
+            let prev = instr_idx.checked_sub(1).map(|i| &block.instructions[i]);
+            if crate::codegen::instruction::is_write_back(prev, instr, self.info) {
+                continue;
+            }
+            match instr {
+                IrInstr::Select {
+                    dest,
+                    val1,
+                    val2,
+                    condition,
+                } if extras.constant_time => {
+                    let ty = extras.var_types.get(val1).copied().unwrap_or(WasmType::I32);
+                    self.backend
+                        .emit_select_branchless(&mut code, *dest, *val1, *val2, *condition, ty)?
+                }
+                _ => crate::codegen::instruction::write_instruction(
+                    &mut code,
+                    self.backend,
+                    instr,
+                    self.info,
+                )?,
+            }
+            code.push('\n');
+        }
+        push_reindented(self.out, &code, &"    ".repeat(depth));
+        Ok(())
+    }
+}
+
+/// `code`, written at `pad`, as a lone `if`: its condition, its body and
+/// its `else` body, if any.
+fn lone_if<'c>(code: &'c str, pad: &str) -> Option<(&'c str, String, Option<String>)> {
+    let mut lines = code.lines();
+    let cond = lines
+        .next()?
+        .strip_prefix(pad)?
+        .strip_prefix("if ")?
+        .strip_suffix(" {")?;
+    let (mut then, mut other) = (String::new(), None::<String>);
+    let mut closed = false;
+    for line in lines {
+        let inner =
+            line.trim().is_empty() || line.strip_prefix(pad).is_some_and(|l| l.starts_with(' '));
+        if closed {
+            return None;
+        } else if inner {
+            let body = other.as_mut().unwrap_or(&mut then);
+            body.push_str(line);
+            body.push('\n');
+        } else if other.is_none() && line.strip_prefix(pad) == Some("} else {") {
+            other = Some(String::new());
+        } else if line.strip_prefix(pad) == Some("}") {
+            closed = true;
+        } else {
+            return None;
+        }
+    }
+    closed.then_some((cond, then, other))
+}
+
+/// `i => { if c { .. } else { .. } }`, written at `pad`, as the guarded arm
+/// `i if c => { .. }` when its `else` does what the `otherwise` arm does
+/// (clippy's `collapsible_match`).
+fn guarded_arm(arm: &str, otherwise: &str, pad: &str) -> Option<String> {
+    let (pattern, body) = arm.strip_prefix(pad)?.split_once(" => {\n")?;
+    let body = body.strip_suffix(&format!("{pad}}}\n"))?;
+    let default = otherwise.split_once(" => ")?.1;
+    let (cond, then, other) = lone_if(body, &format!("{pad}    "))?;
+    let same = match other {
+        Some(other) => format!("{{\n{}{pad}}}\n", dedent(&other)) == default,
+        None => default == "{}\n",
+    };
+    same.then(|| format!("{pad}{pattern} if {cond} => {{\n{}{pad}}}\n", dedent(&then)))
+}
+
+/// `code` one level further out.
+fn dedent(code: &str) -> String {
+    let mut out = String::new();
+    for line in code.lines() {
+        out.push_str(line.strip_prefix("    ").unwrap_or(line));
+        out.push('\n');
+    }
+    out
+}
+
+/// `return x;` as the tail expression `x`, which clippy prefers.
+fn tail_expression(code: &str) -> String {
+    let code = code.trim();
+    match code
+        .strip_prefix("return ")
+        .and_then(|c| c.strip_suffix(';'))
+    {
+        Some(expr) => expr.to_string(),
+        None => code.to_string(),
+    }
+}
+
+/// Append the lines of `code`, which the backend indents for the state
+/// machine's match arms, at `pad` instead.
+fn push_reindented(out: &mut String, code: &str, pad: &str) {
+    const ARM_INDENT: &str = "                ";
+    for line in code.lines() {
+        let line = line
+            .strip_prefix(ARM_INDENT)
+            .unwrap_or_else(|| line.trim_start());
+        if !line.is_empty() {
+            out.push_str(pad);
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn func(terminators: Vec<IrTerminator>) -> IrFunction {
+        IrFunction {
+            params: vec![(VarId(0), WasmType::I32)],
+            locals: vec![],
+            blocks: terminators
+                .into_iter()
+                .enumerate()
+                .map(|(id, terminator)| IrBlock {
+                    id: BlockId(id as u32),
+                    instructions: vec![],
+                    terminator,
+                })
+                .collect(),
+            entry_block: BlockId(0),
+            results: vec![],
+            type_idx: TypeIdx::new(0),
+        }
+    }
+
+    fn branch_if(if_true: u32, if_false: u32) -> IrTerminator {
+        IrTerminator::BranchIf {
+            condition: VarId(0),
+            if_true: BlockId(if_true),
+            if_false: BlockId(if_false),
+        }
+    }
+
+    fn ret() -> IrTerminator {
+        IrTerminator::Return { values: vec![] }
+    }
+
+    #[test]
+    fn loop_with_exit() {
+        // B0 → B1 ⇄ B1, B1 → B2
+        let shape = plan(&func(vec![
+            IrTerminator::Jump { target: BlockId(1) },
+            branch_if(1, 2),
+            ret(),
+        ]))
+        .expect("reducible");
+        let Shape::Code { idx: 0, branches } = shape else {
+            panic!("expected B0 first, got {shape:?}");
+        };
+        let [Branch::Inline(looped)] = branches.as_slice() else {
+            panic!("expected B1 inline, got {branches:?}");
+        };
+        let Shape::Loop { header: 1, body } = looped.as_ref() else {
+            panic!("expected a loop at B1, got {looped:?}");
+        };
+        let Shape::Code { idx: 1, branches } = body.as_ref() else {
+            panic!("expected B1 in the loop, got {body:?}");
+        };
+        assert!(matches!(branches[0], Branch::Continue(1)));
+        assert!(matches!(branches[1], Branch::Inline(_)));
+    }
+
+    #[test]
+    fn irreducible_cfg_has_no_plan() {
+        // B0 enters the B1 ⇄ B2 cycle at both blocks
+        let shape = plan(&func(vec![
+            branch_if(1, 2),
+            branch_if(2, 3),
+            branch_if(1, 3),
+            ret(),
+        ]));
+        assert!(shape.is_none());
+    }
+}
@@ -0,0 +1,102 @@
+//! Symbol table generation.
+//!
+//! Emits `pub const SYMBOLS: &[FuncSymbol]` (one entry per local function, in
+//! local index order) and a `symbolicate(func_index)` lookup, so that host-side
+//! error reporting can map a generated `func_N` back to its original name,
+//! export names, and Wasm body offsets.
+
+use crate::ir::*;
+
+/// Generate the `SYMBOLS` table and the `symbolicate` helper.
+pub fn generate_symbol_table(info: &ModuleInfo) -> String {
+    let num_imported = info.num_imported_functions();
+    let mut exports: Vec<Vec<&str>> = vec![Vec::new(); info.ir_functions.len()];
+    for export in &info.func_exports {
+        if let Some(names) = exports.get_mut(export.func_index.as_usize()) {
+            names.push(export.name.as_str());
+        }
+    }
+
+    let mut code = String::new();
+    code.push_str("/// Symbol table for trap reports — one entry per local function.\n");
+    code.push_str("pub const SYMBOLS: &[FuncSymbol] = &[\n");
+    for (idx, names) in exports.iter().enumerate() {
+        let symbol = info.func_symbols.get(idx).cloned().unwrap_or_default();
+        let name = match &symbol.name {
+            Some(name) => format!("Some({:?})", name),
+            None => "None".to_string(),
+        };
+        let export_list: Vec<String> = names.iter().map(|n| format!("{:?}", n)).collect();
+        code.push_str(&format!(
+            "    FuncSymbol {{ func_index: {}, wasm_index: {}, name: {}, exports: &[{}], wasm_start: {}, wasm_end: {} }},\n",
+            idx,
+            num_imported + idx,
+            name,
+            export_list.join(", "),
+            symbol.code_range.start,
+            symbol.code_range.end,
+        ));
+    }
+    code.push_str("];\n\n");
+
+    code.push_str("/// Look up the symbol for local function `func_index`.\n");
+    code.push_str("pub fn symbolicate(func_index: u32) -> Option<&'static FuncSymbol> {\n");
+    code.push_str("    SYMBOLS.get(func_index as usize)\n");
+    code.push_str("}\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_table_lists_names_and_exports() {
+        let info = ModuleInfo {
+            func_imports: vec![FuncImport {
+                module_name: "env".into(),
+                func_name: "log".into(),
+                params: vec![].into(),
+                results: Vec::new(),
+            }],
+            func_exports: vec![
+                FuncExport {
+                    name: "run".to_string(),
+                    func_index: LocalFuncIdx::new(0),
+                },
+                FuncExport {
+                    name: "main".to_string(),
+                    func_index: LocalFuncIdx::new(0),
+                },
+            ],
+            ir_functions: vec![
+                IrFunction {
+                    params: vec![],
+                    locals: vec![],
+                    blocks: vec![],
+                    entry_block: BlockId(0),
+                    results: Vec::new(),
+                    type_idx: TypeIdx::new(0),
+                };
+                2
+            ],
+            func_symbols: vec![FuncSymbolDef {
+                name: Some("compute".to_string()),
+                code_range: 40..58,
+                block_offsets: Vec::new(),
+                max_stack_depth: 0,
+            }],
+            ..Default::default()
+        };
+
+        let code = generate_symbol_table(&info);
+        assert!(code.contains(
+            "FuncSymbol { func_index: 0, wasm_index: 1, name: Some(\"compute\"), exports: &[\"run\", \"main\"], wasm_start: 40, wasm_end: 58 }"
+        ));
+        // Missing metadata falls back to an anonymous, empty-range entry.
+        assert!(code.contains(
+            "FuncSymbol { func_index: 1, wasm_index: 2, name: None, exports: &[], wasm_start: 0, wasm_end: 0 }"
+        ));
+        assert!(code.contains("pub fn symbolicate(func_index: u32)"));
+    }
+}
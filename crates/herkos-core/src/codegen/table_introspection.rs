@@ -0,0 +1,137 @@
+//! Host view of an exported table.
+//!
+//! Plugin systems register callbacks by storing them in the table; a host
+//! enumerating them needs more than the raw `FuncRef`s. A module that
+//! exports its own table gets the signature of every type and the name of
+//! every function import, and two methods resolving entries against them and
+//! `SYMBOLS`:
+//!
+//! ```text
+//! /// Signature of each type, by type index.
+//! pub const FUNC_TYPES: &[FuncType] = &[
+//!     FuncType { params: &[ValueType::I32], results: &[] },
+//! ];
+//! /// Name of each function import, by import index.
+//! pub const FUNC_IMPORTS: &[&str] = &["log"];
+//!
+//!     pub fn table_len(&self) -> u32 {
+//!         self.0.table.size() as u32
+//!     }
+//!     pub fn table_entry(&self, index: u32) -> Option<FuncDescriptor> {
+//!         let func = self.0.table.get(index).ok()?;
+//!         FuncDescriptor::resolve(func, FUNC_TYPES, SYMBOLS, FUNC_IMPORTS)
+//!     }
+//! ```
+
+use crate::ir::*;
+
+/// Whether the host gets the view: the module owns its table and exports it.
+fn introspected(info: &ModuleInfo) -> bool {
+    info.owns_table() && !info.table_exports.is_empty()
+}
+
+fn value_type(ty: &WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "ValueType::I32",
+        WasmType::I64 => "ValueType::I64",
+        WasmType::F32 => "ValueType::F32",
+        WasmType::F64 => "ValueType::F64",
+    }
+}
+
+/// Generate `FUNC_TYPES` and `FUNC_IMPORTS`.
+///
+/// Returns an empty string unless the module exports its own table.
+pub fn generate_table_types(info: &ModuleInfo) -> String {
+    if !introspected(info) {
+        return String::new();
+    }
+    let mut code = String::new();
+    code.push_str("\n/// Signature of each type, by type index.\n");
+    code.push_str("pub const FUNC_TYPES: &[FuncType] = &[\n");
+    for sig in &info.type_signatures {
+        let params: Vec<&str> = sig.params.iter().map(value_type).collect();
+        let results: Vec<&str> = sig.results.iter().map(value_type).collect();
+        code.push_str(&format!(
+            "    FuncType {{ params: &[{}], results: &[{}] }},\n",
+            params.join(", "),
+            results.join(", ")
+        ));
+    }
+    code.push_str("];\n\n");
+
+    let imports: Vec<String> = info
+        .func_imports
+        .iter()
+        .map(|import| format!("{:?}", import.func_name))
+        .collect();
+    code.push_str("/// Name of each function import, by import index.\n");
+    code.push_str(&format!(
+        "pub const FUNC_IMPORTS: &[&str] = &[{}];\n",
+        imports.join(", ")
+    ));
+    code
+}
+
+/// Generate `table_len` and `table_entry`, for the export impl block.
+///
+/// Returns an empty string unless the module exports its own table.
+pub fn generate_table_methods(info: &ModuleInfo) -> String {
+    if !introspected(info) {
+        return String::new();
+    }
+    let mut code = String::new();
+    code.push_str("    /// Number of slots in the exported table.\n");
+    code.push_str("    pub fn table_len(&self) -> u32 {\n");
+    code.push_str("        self.0.table.size() as u32\n");
+    code.push_str("    }\n");
+    code.push_str(
+        "    /// The function in slot `index` of the exported table, with its signature and\n\
+         \x20   /// name; `None` for an empty slot or an index past the end.\n",
+    );
+    code.push_str("    pub fn table_entry(&self, index: u32) -> Option<FuncDescriptor> {\n");
+    code.push_str("        let func = self.0.table.get(index).ok()?;\n");
+    code.push_str("        FuncDescriptor::resolve(func, FUNC_TYPES, SYMBOLS, FUNC_IMPORTS)\n");
+    code.push_str("    }\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_wat(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        transpile(&wasm, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn exported_tables_are_described() {
+        let code = transpile_wat(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (type $cb (func (param i64 f32) (result f64)))
+                (table (export "callbacks") 2 funcref)
+                (func $f (type $cb) (f64.const 0))
+                (elem (i32.const 0) $log $f))"#,
+        );
+        assert!(code.contains(
+            "pub const FUNC_TYPES: &[FuncType] = &[\n    FuncType { params: &[ValueType::I64, ValueType::F32], results: &[ValueType::F64] },\n    FuncType { params: &[ValueType::I32], results: &[] },\n];"
+        ));
+        assert!(code.contains("pub const FUNC_IMPORTS: &[&str] = &[\"log\"];"));
+        assert!(code.contains("    pub fn table_len(&self) -> u32 {"));
+        assert!(code.contains("FuncDescriptor::resolve(func, FUNC_TYPES, SYMBOLS, FUNC_IMPORTS)"));
+    }
+
+    #[test]
+    fn private_and_imported_tables_are_not() {
+        for wat in [
+            r#"(module (table 1 funcref) (func (export "f")))"#,
+            r#"(module (import "env" "t" (table 1 funcref)) (export "t" (table 0)) (func (export "f")))"#,
+        ] {
+            let code = transpile_wat(wat);
+            assert!(!code.contains("FUNC_TYPES"), "{wat}");
+            assert!(!code.contains("fn table_entry"), "{wat}");
+        }
+    }
+}
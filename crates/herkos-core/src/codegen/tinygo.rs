@@ -0,0 +1,195 @@
+//! TinyGo support shim generation.
+//!
+//! For modules recognized by [`detect_tinygo`], the host trait gets default
+//! bodies for TinyGo's runtime imports (see [`import_default`]) and the
+//! module gets methods that perform the instantiation sequence TinyGo's
+//! `wasm_exec.js` implements in JavaScript:
+//!
+//! ```text
+//! pub fn tinygo_run<H: ModuleHostTrait>(&mut self, host: &mut H) -> WasmResult<()> {
+//!     self._start(host)?;
+//!     self.tinygo_run_timers(host)
+//! }
+//! pub fn tinygo_run_timers<H: ModuleHostTrait>(&mut self, host: &mut H) -> WasmResult<()> {
+//!     while host.tinygo_clock().take_wakeup().is_some() {
+//!         self.go_scheduler(host)?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::analysis::{detect_tinygo, tinygo_import, TinyGoImport};
+use crate::codegen::utils::{rust_ident, ForwardedState};
+use crate::ir::*;
+
+/// `WASI` errno returned by defaulted calls (`ENOSYS`).
+const WASI_ERRNO_NOSYS: i32 = 52;
+
+/// Default host-trait method for a TinyGo import.
+pub(crate) struct ImportDefault {
+    /// Doc comment line.
+    pub doc: &'static str,
+    /// Method body, one statement per entry.
+    pub body: Vec<String>,
+    /// Whether the body reads the method's arguments.
+    pub uses_args: bool,
+}
+
+/// Default method for `import` of a TinyGo guest.
+pub(crate) fn import_default(import: &FuncImport) -> Option<ImportDefault> {
+    let default = match tinygo_import(import)? {
+        TinyGoImport::Ticks => ImportDefault {
+            doc: "TinyGo `runtime.ticks`: reads [`Self::tinygo_clock`].",
+            body: vec!["Ok(self.tinygo_clock().ticks())".to_string()],
+            uses_args: false,
+        },
+        TinyGoImport::SleepTicks => ImportDefault {
+            doc: "TinyGo `runtime.sleepTicks`: schedules a `go_scheduler` wakeup on [`Self::tinygo_clock`].",
+            body: vec![
+                "self.tinygo_clock().sleep_ticks(arg0);".to_string(),
+                "Ok(())".to_string(),
+            ],
+            uses_args: true,
+        },
+        TinyGoImport::JsInterop => ImportDefault {
+            doc: "TinyGo JavaScript interop: traps, as there is no JavaScript host.",
+            body: vec!["Err(WasmTrap::Unreachable)".to_string()],
+            uses_args: false,
+        },
+        TinyGoImport::WasiExit => ImportDefault {
+            doc: "WASI `proc_exit`: traps, ending the run.",
+            body: vec!["Err(WasmTrap::Unreachable)".to_string()],
+            uses_args: false,
+        },
+        TinyGoImport::WasiUnsupported => ImportDefault {
+            doc: "WASI call without host support: returns `ENOSYS`.",
+            body: vec![format!("Ok({WASI_ERRNO_NOSYS})")],
+            uses_args: false,
+        },
+    };
+    Some(default)
+}
+
+/// Generate the `impl WasmModule` block with the TinyGo run sequence.
+///
+/// Returns an empty string for modules that are not TinyGo guests.
+pub fn generate_tinygo_support(info: &ModuleInfo) -> String {
+    let Some(tinygo) = detect_tinygo(info) else {
+        return String::new();
+    };
+    let uses_host = info.uses_host();
+
+    // Generics and trailing parameters, mirroring the raw export methods.
+    let forwarded = ForwardedState::new(info);
+    let generic_part = forwarded.generic_part();
+    let params = std::iter::once("&mut self")
+        .chain(forwarded.params.iter().copied())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let args = forwarded.args.join(", ");
+    let signature =
+        |name: &str| format!("    pub fn {name}{generic_part}({params}) -> WasmResult<()> {{\n");
+
+    let has_timers = tinygo.uses_clock && tinygo.has_scheduler && uses_host;
+    let mut code = String::from("impl WasmModule {\n");
+    if let Some(entry) = &tinygo.entry {
+        code.push_str(&format!(
+            "    /// Run the TinyGo guest: call `{entry}`{}.\n",
+            if has_timers {
+                ", then fire timers until it stops sleeping"
+            } else {
+                ""
+            }
+        ));
+        code.push_str(&signature("tinygo_run"));
+        if has_timers {
+            code.push_str(&format!("        self.{}({args})?;\n", rust_ident(entry)));
+            code.push_str(&format!("        self.tinygo_run_timers({args})\n"));
+        } else {
+            code.push_str(&format!("        self.{}({args})\n", rust_ident(entry)));
+        }
+        code.push_str("    }\n");
+    }
+    if has_timers {
+        code.push_str(
+            "    /// Call `go_scheduler` for each pending `runtime.sleepTicks` wakeup, moving the\n",
+        );
+        code.push_str(
+            "    /// host's [`TinyGoClock`] forward to it. Does not return while the guest keeps\n",
+        );
+        code.push_str("    /// sleeping (e.g. a `time.Ticker` loop).\n");
+        code.push_str(&signature("tinygo_run_timers"));
+        code.push_str("        while host.tinygo_clock().take_wakeup().is_some() {\n");
+        code.push_str(&format!("            self.go_scheduler({args})?;\n"));
+        code.push_str("        }\n");
+        code.push_str("        Ok(())\n");
+        code.push_str("    }\n");
+    }
+    if tinygo.has_resume {
+        code.push_str(
+            "    /// Call `resume` after the host invoked an exported Go callback, then fire\n",
+        );
+        code.push_str("    /// pending timers.\n");
+        code.push_str(&signature("tinygo_resume"));
+        if has_timers {
+            code.push_str(&format!("        self.resume({args})?;\n"));
+            code.push_str(&format!("        self.tinygo_run_timers({args})\n"));
+        } else {
+            code.push_str(&format!("        self.resume({args})\n"));
+        }
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_wat(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        transpile(&wasm, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn emits_host_defaults_and_run_sequence() {
+        let code = transpile_wat(
+            r#"(module
+                (import "gojs" "runtime.ticks" (func (result f64)))
+                (import "gojs" "runtime.sleepTicks" (func (param f64)))
+                (import "gojs" "syscall/js.valueGet" (func (param i32 i32 i32 i32 i32)))
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (func (export "_start"))
+                (func (export "go_scheduler"))
+                (func (export "resume")))"#,
+        );
+        assert!(code.contains("    fn tinygo_clock(&mut self) -> &mut TinyGoClock;\n"));
+        assert!(code.contains(
+            "    fn runtime_sleepTicks(&mut self, arg0: f64) -> WasmResult<()> {\n        \
+             self.tinygo_clock().sleep_ticks(arg0);\n"
+        ));
+        assert!(code.contains(
+            "    fn syscall_js_valueGet(&mut self, _arg0: i32, _arg1: i32, _arg2: i32, _arg3: i32, _arg4: i32) -> WasmResult<()> {\n        \
+             Err(WasmTrap::Unreachable)\n"
+        ));
+        assert!(code.contains("        Ok(52)\n"));
+        assert!(code.contains(
+            "    pub fn tinygo_run<H: ModuleHostTrait>(&mut self, host: &mut H) -> WasmResult<()> {\n        \
+             self._start(host)?;\n        self.tinygo_run_timers(host)\n"
+        ));
+        assert!(code.contains("            self.go_scheduler(host)?;\n"));
+        assert!(code.contains("        self.resume(host)?;\n"));
+    }
+
+    #[test]
+    fn other_modules_keep_required_imports() {
+        let code = transpile_wat(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+                (func (export "_start")))"#,
+        );
+        assert!(code.contains("-> WasmResult<i32>;\n"));
+        assert!(!code.contains("tinygo"));
+    }
+}
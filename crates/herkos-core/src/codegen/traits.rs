@@ -38,14 +38,14 @@ pub fn all_import_module_names(info: &ModuleInfo) -> Vec<String> {
     let mut modules: Vec<String> = Vec::new();
 
     for imp in &info.func_imports {
-        if !modules.contains(&imp.module_name) {
-            modules.push(imp.module_name.clone());
+        if !modules.iter().any(|m| **m == *imp.module_name) {
+            modules.push(imp.module_name.to_string());
         }
     }
 
     for glob in &info.imported_globals {
-        if !modules.contains(&glob.module_name) {
-            modules.push(glob.module_name.clone());
+        if !modules.iter().any(|m| **m == *glob.module_name) {
+            modules.push(glob.module_name.to_string());
         }
     }
 
@@ -88,7 +88,7 @@ pub fn generate_host_traits<B: Backend>(_backend: &B, info: &ModuleInfo) -> Stri
                     params.push(format!("arg{i}: {rust_ty}"));
                 }
 
-                let return_ty = crate::codegen::types::format_return_type(imp.return_type.as_ref());
+                let return_ty = crate::codegen::types::format_return_type(&imp.results);
 
                 code.push_str(&format!(
                     "    fn {}({}) -> {};\n",
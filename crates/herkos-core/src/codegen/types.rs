@@ -12,27 +12,108 @@ pub fn wasm_type_to_rust(ty: &WasmType) -> &'static str {
     }
 }
 
-/// Format a Wasm return type as a Rust WasmResult type.
+/// Format Wasm results as a Rust value type: nothing is `()`, one result is
+/// its own type and several are a tuple.
 ///
 /// Examples:
-/// - `Some(I32)` → `"WasmResult<i32>"`
-/// - `None` → `"WasmResult<()>"`
-pub fn format_return_type(ty: Option<&WasmType>) -> String {
-    match ty {
-        Some(t) => format!("WasmResult<{}>", wasm_type_to_rust(t)),
-        None => "WasmResult<()>".to_string(),
+/// - `[I32]` → `"i32"`
+/// - `[I32, I64]` → `"(i32, i64)"`
+/// - `[]` → `"()"`
+pub fn format_results(results: &[WasmType]) -> String {
+    match results {
+        [ty] => wasm_type_to_rust(ty).to_string(),
+        _ => {
+            let types: Vec<&str> = results.iter().map(wasm_type_to_rust).collect();
+            format!("({})", types.join(", "))
+        }
     }
 }
 
-/// Convert a GlobalInit to (Rust type string, value literal string).
-pub fn global_init_to_rust(init: &GlobalInit) -> (&'static str, String) {
+/// Format Wasm results as a Rust WasmResult type.
+///
+/// Examples:
+/// - `[I32]` → `"WasmResult<i32>"`
+/// - `[I32, I64]` → `"WasmResult<(i32, i64)>"`
+/// - `[]` → `"WasmResult<()>"`
+pub fn format_return_type(results: &[WasmType]) -> String {
+    format!("WasmResult<{}>", format_results(results))
+}
+
+/// Convert a GlobalInit to (Rust type string, value expression string).
+///
+/// Imported initializers read the `host` parameter of `new()`.
+pub fn global_init_to_rust(init: &GlobalInit, info: &ModuleInfo) -> (&'static str, String) {
     let ty = init.ty();
     let rust_ty = wasm_type_to_rust(&ty);
     let value = match init {
         GlobalInit::I32(v) => format!("{v}i32"),
         GlobalInit::I64(v) => format!("{v}i64"),
-        GlobalInit::F32(v) => format!("{v}f32"),
-        GlobalInit::F64(v) => format!("{v}f64"),
+        GlobalInit::F32(v) => f32_literal(*v),
+        GlobalInit::F64(v) => f64_literal(*v),
+        GlobalInit::Imported { index, .. } => match info.imported_global(*index) {
+            Some(g) => format!("host.get_{}()", g.name),
+            None => ty.default_value_literal().to_string(),
+        },
     };
     (rust_ty, value)
 }
+
+/// A Rust expression for the `f32` constant `v`, exact to the bit.
+///
+/// Values whose shortest decimal form reads back to the same bits are
+/// literals (`1.5f32`, `-0f32`). NaNs, whose sign and payload Wasm keeps,
+/// and infinities, which have no literal, are `f32::from_bits(0x7fc00000)`,
+/// which is also valid in `const` items.
+pub fn f32_literal(v: f32) -> String {
+    let text = v.to_string();
+    if v.is_finite() && text.parse::<f32>().map(f32::to_bits) == Ok(v.to_bits()) {
+        format!("{text}f32")
+    } else {
+        format!("f32::from_bits(0x{:08x})", v.to_bits())
+    }
+}
+
+/// A Rust expression for the `f64` constant `v`, exact to the bit; see
+/// [`f32_literal`].
+pub fn f64_literal(v: f64) -> String {
+    let text = v.to_string();
+    if v.is_finite() && text.parse::<f64>().map(f64::to_bits) == Ok(v.to_bits()) {
+        format!("{text}f64")
+    } else {
+        format!("f64::from_bits(0x{:016x})", v.to_bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finite_floats_are_literals() {
+        assert_eq!(f32_literal(1.5), "1.5f32");
+        assert_eq!(f32_literal(-0.0), "-0f32");
+        assert_eq!(f64_literal(0.1), "0.1f64");
+        // The smallest subnormal reads back exactly from its long decimal form
+        let tiny = f64::from_bits(1);
+        let literal = f64_literal(tiny);
+        let digits = literal.strip_suffix("f64").unwrap();
+        assert_eq!(digits.parse::<f64>().map(f64::to_bits), Ok(1));
+    }
+
+    #[test]
+    fn non_finite_floats_keep_their_bits() {
+        assert_eq!(f32_literal(f32::INFINITY), "f32::from_bits(0x7f800000)");
+        assert_eq!(
+            f32_literal(f32::from_bits(0xffa0_0001)),
+            "f32::from_bits(0xffa00001)"
+        );
+        assert_eq!(
+            f64_literal(f64::NEG_INFINITY),
+            "f64::from_bits(0xfff0000000000000)"
+        );
+        assert_eq!(
+            f64_literal(f64::NAN),
+            format!("f64::from_bits(0x{:016x})", f64::NAN.to_bits())
+        );
+    }
+}
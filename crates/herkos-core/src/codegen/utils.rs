@@ -1,5 +1,8 @@
 //! General-purpose utility functions for code generation.
 
+use crate::ir::{ModuleInfo, VarId};
+use std::fmt::Write;
+
 /// Build a call args vector by conditionally adding memory and table.
 ///
 /// Note: Globals are now part of the env parameter (always first after wasm args).
@@ -19,3 +22,201 @@ pub fn build_inner_call_args(
     }
     call_args
 }
+
+/// Caller-supplied state that every method forwarding to a raw export must
+/// thread through: the imported memory, the imported table, and the host.
+pub struct ForwardedState {
+    /// Generic parameters (`const MP: usize`, `const TP: usize`,
+    /// `H: ModuleHostTrait`).
+    pub generics: Vec<&'static str>,
+    /// Trailing method parameters, in the same order.
+    pub params: Vec<&'static str>,
+    /// Names of those parameters, for forwarding calls.
+    pub args: Vec<&'static str>,
+}
+
+impl ForwardedState {
+    /// Collect the forwarded state of `info`'s export methods.
+    pub fn new(info: &ModuleInfo) -> Self {
+        let mut state = ForwardedState {
+            generics: Vec::new(),
+            params: Vec::new(),
+            args: Vec::new(),
+        };
+        if info.has_memory_import {
+            state.generics.push("const MP: usize");
+            state.params.push("memory: &mut IsolatedMemory<MP>");
+            state.args.push("memory");
+        }
+        if info.has_table_import {
+            state.generics.push("const TP: usize");
+            state.params.push("table: &Table<TP>");
+            state.args.push("table");
+        }
+        if info.uses_host() {
+            state.generics.push("H: ModuleHostTrait");
+            state.params.push("host: &mut H");
+            state.args.push("host");
+        }
+        state
+    }
+
+    /// The `<...>` generic parameter list, or an empty string.
+    pub fn generic_part(&self) -> String {
+        if self.generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", self.generics.join(", "))
+        }
+    }
+}
+
+/// `#[cfg(...)]` line (indented by `indent`) that compiles the next item in
+/// when any of `features` is enabled. Empty when `features` is empty.
+pub fn cfg_attr<S: AsRef<str>>(indent: &str, features: &[S]) -> String {
+    let preds: Vec<String> = features
+        .iter()
+        .map(|f| format!("feature = {:?}", f.as_ref()))
+        .collect();
+    match preds.as_slice() {
+        [] => String::new(),
+        [pred] => format!("{indent}#[cfg({pred})]\n"),
+        _ => format!("{indent}#[cfg(any({}))]\n", preds.join(", ")),
+    }
+}
+
+/// [`cfg_attr`] for local function `func_idx` (see
+/// [`ModuleInfo::func_features`]).
+pub fn func_cfg_attr(indent: &str, info: &ModuleInfo, func_idx: usize) -> String {
+    cfg_attr(
+        indent,
+        info.func_features.get(func_idx).map_or(&[][..], |f| f),
+    )
+}
+
+/// [`cfg_attr`] for items generated for export `name` (see
+/// [`ModuleInfo::export_features`]).
+pub fn export_cfg_attr(indent: &str, info: &ModuleInfo, name: &str) -> String {
+    cfg_attr(indent, info.export_features.get(name).as_slice())
+}
+
+/// `#[allow(clippy::too_many_arguments)]` for a method with `count`
+/// parameters (including `self`) above clippy's default threshold.
+///
+/// Wasm signatures fix the parameter count, so generated code cannot avoid
+/// the lint.
+pub fn too_many_args_attr(indent: &str, count: usize) -> String {
+    if count > 7 {
+        format!("{indent}#[allow(clippy::too_many_arguments)]\n")
+    } else {
+        String::new()
+    }
+}
+
+/// `#[must_use]` for local function `idx` when it is pure (see
+/// [`ModuleInfo::purity`]) and returns something: discarding the result
+/// discards all the call does.
+pub fn must_use_attr(indent: &str, info: &ModuleInfo, idx: usize) -> String {
+    let returns = info
+        .ir_functions
+        .get(idx)
+        .is_some_and(|f| !f.results.is_empty());
+    if returns && info.purity_of(idx).is_pure() {
+        format!("{indent}#[must_use = \"the function has no side effects\"]\n")
+    } else {
+        String::new()
+    }
+}
+
+/// Append `data` as a comma-separated list of `u8` literals (`1u8, 2u8, ...`).
+///
+/// Data segments can be megabytes long, so this writes straight into `out`
+/// instead of building one `String` per byte.
+pub fn write_byte_list(out: &mut String, data: &[u8]) {
+    out.reserve(data.len() * 6);
+    for (i, b) in data.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        // Writing to a String is infallible.
+        let _ = write!(out, "{b}u8");
+    }
+}
+
+/// Variables holding the results of a call or return: one result is the
+/// variable itself, several are a tuple `(v0, v1)`. `None` when there are
+/// no results.
+pub fn format_vars(vars: &[VarId]) -> Option<String> {
+    match vars {
+        [] => None,
+        [v] => Some(v.to_string()),
+        _ => {
+            let names: Vec<String> = vars.iter().map(VarId::to_string).collect();
+            Some(format!("({})", names.join(", ")))
+        }
+    }
+}
+
+/// Turn a Wasm import/export name into a Rust identifier.
+///
+/// Names are arbitrary UTF-8 in Wasm; toolchains such as `wit-bindgen` emit
+/// kebab-case (`get-name`). Every character that cannot appear in an
+/// identifier becomes `_`, and a leading digit gets a `_` prefix.
+pub fn rust_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_byte_list_formats_literals() {
+        let mut out = String::from("[");
+        write_byte_list(&mut out, &[0, 7, 255]);
+        out.push(']');
+        assert_eq!(out, "[0u8, 7u8, 255u8]");
+    }
+
+    #[test]
+    fn write_byte_list_empty() {
+        let mut out = String::new();
+        write_byte_list(&mut out, &[]);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn rust_ident_replaces_invalid_characters() {
+        assert_eq!(rust_ident("add"), "add");
+        assert_eq!(rust_ident("get-name"), "get_name");
+        assert_eq!(rust_ident("ns:pkg/iface#run"), "ns_pkg_iface_run");
+        assert_eq!(rust_ident("3d"), "_3d");
+        assert_eq!(rust_ident(""), "_");
+    }
+
+    #[test]
+    fn cfg_attr_combines_features() {
+        assert_eq!(cfg_attr::<&str>("", &[]), "");
+        assert_eq!(
+            cfg_attr("    ", &["math"]),
+            "    #[cfg(feature = \"math\")]\n"
+        );
+        assert_eq!(
+            cfg_attr("", &["a", "b"]),
+            "#[cfg(any(feature = \"a\", feature = \"b\"))]\n"
+        );
+    }
+}
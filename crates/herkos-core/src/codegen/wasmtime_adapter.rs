@@ -0,0 +1,174 @@
+//! Wasmtime-style adapter generation.
+//!
+//! Implements `herkos_runtime::wasmtime_compat::WasmtimeExports` for the
+//! generated module, so host code written against wasmtime's
+//! `Instance::get_typed_func` / `Memory::data_mut` runs unchanged on top of a
+//! `Store` holding the module. Each export becomes a shim closure of type
+//! `Func<Self, T, Params, Results>`; the requested type is checked against it
+//! in `cast_func`:
+//!
+//! ```text
+//! "add" => wasmtime_compat::cast_func::<wasmtime_compat::Func<Self, T, (i32, i32), i32>, _>(
+//!     |store, (v0, v1)| {
+//!         let (module, host) = store.parts_mut();
+//!         module.add(v0, v1, host)
+//!     },
+//! ),
+//! ```
+
+use crate::codegen::types::{format_results, wasm_type_to_rust};
+use crate::codegen::utils::{export_cfg_attr, rust_ident};
+use crate::ir::*;
+
+/// Generate the `WasmtimeExports` impl.
+///
+/// Returns an empty string unless the adapter was requested.
+pub fn generate_wasmtime_adapter(info: &ModuleInfo) -> String {
+    if !info.wasmtime_adapter {
+        return String::new();
+    }
+    let uses_host = info.uses_host();
+    let host_bound = if uses_host {
+        "ModuleHostTrait + 'static"
+    } else {
+        "'static"
+    };
+
+    let mut code =
+        format!("impl<T: {host_bound}> wasmtime_compat::WasmtimeExports<T> for WasmModule {{\n");
+    code.push_str("    fn typed_func<P: 'static, R: 'static>(\n");
+    code.push_str("        name: &str,\n");
+    code.push_str(
+        "    ) -> Result<wasmtime_compat::Func<Self, T, P, R>, wasmtime_compat::ExportError> {\n",
+    );
+    code.push_str("        match name {\n");
+    for export in &info.func_exports {
+        let Some(func) = info.ir_function(export.func_index) else {
+            continue;
+        };
+        let types: Vec<&str> = func
+            .params
+            .iter()
+            .map(|(_, ty)| wasm_type_to_rust(ty))
+            .collect();
+        let names: Vec<String> = (0..types.len()).map(|i| format!("v{i}")).collect();
+        let (params_ty, pattern) = match types.as_slice() {
+            [] => ("()".to_string(), "()".to_string()),
+            [ty] => (ty.to_string(), names[0].clone()),
+            _ => (
+                format!("({})", types.join(", ")),
+                format!("({})", names.join(", ")),
+            ),
+        };
+        let result_ty = format_results(&func.results);
+        let mut args = names;
+        if uses_host {
+            args.push("host".to_string());
+        }
+        let call = format!("module.{}({})", rust_ident(&export.name), args.join(", "));
+        code.push_str(&export_cfg_attr("            ", info, &export.name));
+        code.push_str(&format!(
+            "            {:?} => wasmtime_compat::cast_func::<wasmtime_compat::Func<Self, T, {params_ty}, {result_ty}>, _>(\n",
+            export.name
+        ));
+        code.push_str(&format!("                |store, {pattern}| {{\n"));
+        if uses_host {
+            code.push_str("                    let (module, host) = store.parts_mut();\n");
+        } else {
+            code.push_str("                    let module = store.module_mut();\n");
+        }
+        code.push_str(&format!("                    {call}\n"));
+        code.push_str("                },\n");
+        code.push_str("            ),\n");
+    }
+    code.push_str("            _ => Err(wasmtime_compat::ExportError::NotFound),\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n\n");
+
+    // Memory accessors.
+    let names: Vec<String> = info
+        .memory_exports
+        .iter()
+        .map(|name| format!("{name:?}"))
+        .collect();
+    code.push_str("    fn has_memory(name: &str) -> bool {\n");
+    if names.is_empty() {
+        code.push_str("        let _ = name;\n");
+        code.push_str("        false\n");
+    } else {
+        code.push_str(&format!("        matches!(name, {})\n", names.join(" | ")));
+    }
+    code.push_str("    }\n\n");
+    if info.has_memory {
+        code.push_str("    fn memory_data(&self) -> &[u8] {\n");
+        code.push_str("        self.0.memory.as_slice()\n");
+        code.push_str("    }\n\n");
+        code.push_str("    fn memory_data_mut(&mut self) -> &mut [u8] {\n");
+        code.push_str("        self.0.memory.as_mut_slice()\n");
+        code.push_str("    }\n");
+    } else {
+        code.push_str("    fn memory_data(&self) -> &[u8] {\n");
+        code.push_str("        &[]\n");
+        code.push_str("    }\n\n");
+        code.push_str("    fn memory_data_mut(&mut self) -> &mut [u8] {\n");
+        code.push_str("        &mut []\n");
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_adapter(wat: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            wasmtime_adapter: true,
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn emits_typed_shims_and_memory_accessors() {
+        let code = transpile_adapter(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (memory (export "memory") 1)
+                (func (export "add") (param i32 i32) (result i32)
+                    local.get 0 local.get 1 i32.add)
+                (func (export "tick")))"#,
+        )
+        .unwrap();
+        assert!(code.contains(
+            "impl<T: ModuleHostTrait + 'static> wasmtime_compat::WasmtimeExports<T> for WasmModule {"
+        ));
+        assert!(code.contains(
+            "\"add\" => wasmtime_compat::cast_func::<wasmtime_compat::Func<Self, T, (i32, i32), i32>, _>("
+        ));
+        assert!(code.contains("|store, (v0, v1)| {"));
+        assert!(code.contains("module.add(v0, v1, host)"));
+        assert!(code.contains("Func<Self, T, (), ()>, _>("));
+        assert!(code.contains("|store, ()| {"));
+        assert!(code.contains("matches!(name, \"memory\")"));
+        assert!(code.contains("self.0.memory.as_mut_slice()"));
+    }
+
+    #[test]
+    fn omitted_by_default_and_rejects_imported_memory_and_table() {
+        let wasm = wat::parse_str(r#"(module (func (export "f")))"#).unwrap();
+        let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert!(!code.contains("WasmtimeExports"));
+
+        let err = transpile_adapter(r#"(module (import "env" "memory" (memory 1)))"#).unwrap_err();
+        assert!(format!("{err:#}").contains("import their memory"));
+
+        let err = transpile_adapter(
+            r#"(module (import "env" "__indirect_function_table" (table 1 funcref)))"#,
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").contains("import their table"));
+    }
+}
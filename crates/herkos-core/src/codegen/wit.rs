@@ -0,0 +1,1068 @@
+//! WIT wrapper generation: canonical ABI lifting and lowering.
+//!
+//! For a world given with `--wit` (see [`crate::wit`]) this emits:
+//!
+//! - one Rust type per WIT `record`, `enum` and `variant`;
+//! - a `call_<name>` method per export, which lowers its arguments into
+//!   linear memory through the guest's `cabi_realloc`, calls the core export,
+//!   lifts the result, and finally calls `cabi_post_<name>` if the guest has
+//!   one;
+//! - a `WitImports` trait with WIT-typed methods, plus a blanket
+//!   `impl<T: WitImports> ModuleHostTrait for T` that adapts core imports.
+//!
+//! ```text
+//! pub fn call_greet(&mut self, name: &str) -> WasmResult<cabi::String> {
+//!     let __s0 = name.as_bytes();
+//!     let __r1 = { /* cabi_realloc(0, 0, 1, len) */ };
+//!     let __p2 = cabi::guest_range(self.0.memory.as_slice(), __r1, __s0.len() as i32, 1, 1)?.0;
+//!     self.0.memory.write_bytes(__p2, __s0)?;
+//!     let __r3 = { /* greet(ptr, len) */ };
+//!     let __ret4 = cabi::guest_range(self.0.memory.as_slice(), __r3, 1, 8, 4)?.0;
+//!     let __out5 = cabi::lift_string(self.0.memory.as_slice(), ...)?;
+//!     /* cabi_post_greet(__r3) */
+//!     Ok(__out5)
+//! }
+//! ```
+//!
+//! Value expressions passed between the helpers below always have reference
+//! type (`&T`), so records, lists and variants are never moved.
+
+use crate::codegen::utils::{export_cfg_attr, rust_ident, ForwardedState};
+use crate::ir::*;
+use crate::wit::{
+    rust_field_name, rust_type_name, TypeDef, TypeDefKind, WitFunction, WitType, WitWorld,
+    MAX_FLAT_RESULTS,
+};
+
+/// Generate WIT types, export wrappers and the `WitImports` adapter.
+///
+/// Returns an empty string when no world was supplied.
+pub fn generate_wit_bindings(world: Option<&WitWorld>, info: &ModuleInfo) -> String {
+    let Some(world) = world else {
+        return String::new();
+    };
+
+    let mut code = String::new();
+    for def in &world.types {
+        code.push_str(&generate_typedef(def));
+    }
+
+    if !world.exports.is_empty() {
+        code.push_str("impl WasmModule {\n");
+        for func in &world.exports {
+            if let Some(wrapper) = generate_export(world, func, info) {
+                code.push_str(&export_cfg_attr("    ", info, &func.core_name));
+                code.push_str(&wrapper);
+            }
+        }
+        code.push_str("}\n\n");
+    }
+
+    if !world.imports.is_empty() {
+        code.push_str(&generate_imports(world, info));
+    }
+    code
+}
+
+/// Emit the Rust type for a WIT `record`, `enum` or `variant`.
+fn generate_typedef(def: &TypeDef) -> String {
+    let name = rust_type_name(&def.name);
+    let mut code = format!("/// WIT `{}`.\n", def.name);
+    match &def.kind {
+        TypeDefKind::Record(fields) => {
+            code.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+            code.push_str(&format!("pub struct {name} {{\n"));
+            for field in fields {
+                code.push_str(&format!(
+                    "    pub {}: {},\n",
+                    rust_field_name(&field.name),
+                    rust_type(&field.ty)
+                ));
+            }
+        }
+        TypeDefKind::Enum(cases) => {
+            code.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
+            code.push_str(&format!("pub enum {name} {{\n"));
+            for case in cases {
+                code.push_str(&format!("    {},\n", rust_type_name(case)));
+            }
+        }
+        TypeDefKind::Variant(cases) => {
+            code.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+            code.push_str(&format!("pub enum {name} {{\n"));
+            for case in cases {
+                match &case.ty {
+                    Some(ty) => code.push_str(&format!(
+                        "    {}({}),\n",
+                        rust_type_name(&case.name),
+                        rust_type(ty)
+                    )),
+                    None => code.push_str(&format!("    {},\n", rust_type_name(&case.name))),
+                }
+            }
+        }
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+/// Owned Rust type of a WIT value.
+fn rust_type(ty: &WitType) -> String {
+    match ty {
+        WitType::Bool => "bool".to_string(),
+        WitType::S8 => "i8".to_string(),
+        WitType::U8 => "u8".to_string(),
+        WitType::S16 => "i16".to_string(),
+        WitType::U16 => "u16".to_string(),
+        WitType::S32 => "i32".to_string(),
+        WitType::U32 => "u32".to_string(),
+        WitType::S64 => "i64".to_string(),
+        WitType::U64 => "u64".to_string(),
+        WitType::F32 => "f32".to_string(),
+        WitType::F64 => "f64".to_string(),
+        WitType::Char => "char".to_string(),
+        WitType::String => "cabi::String".to_string(),
+        WitType::List(elem) => format!("cabi::Vec<{}>", rust_type(elem)),
+        WitType::Option(inner) => format!("Option<{}>", rust_type(inner)),
+        WitType::Result { ok, err } => {
+            let side =
+                |t: &Option<Box<WitType>>| t.as_deref().map_or_else(|| "()".to_string(), rust_type);
+            format!("Result<{}, {}>", side(ok), side(err))
+        }
+        WitType::Tuple(items) => {
+            let items: Vec<String> = items.iter().map(rust_type).collect();
+            if items.len() == 1 {
+                format!("({},)", items[0])
+            } else {
+                format!("({})", items.join(", "))
+            }
+        }
+        WitType::Named(name) => rust_type_name(name),
+    }
+}
+
+/// Rust type of a top-level export parameter: strings and lists borrow.
+fn param_type(ty: &WitType) -> String {
+    match ty {
+        WitType::String => "&str".to_string(),
+        WitType::List(elem) => format!("&[{}]", rust_type(elem)),
+        _ => rust_type(ty),
+    }
+}
+
+/// Pattern path of each case of a variant-like type, and whether a
+/// payload-less case still wraps a unit (`Ok(())`).
+fn case_paths(world: &WitWorld, ty: &WitType) -> Vec<(String, bool)> {
+    match ty {
+        WitType::Option(_) => vec![("None".to_string(), false), ("Some".to_string(), false)],
+        WitType::Result { .. } => vec![("Ok".to_string(), true), ("Err".to_string(), true)],
+        WitType::Named(name) => {
+            let owner = rust_type_name(name);
+            let cases: Vec<&str> = match world.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Enum(cases)) => cases.iter().map(String::as_str).collect(),
+                Some(TypeDefKind::Variant(cases)) => {
+                    cases.iter().map(|c| c.name.as_str()).collect()
+                }
+                _ => Vec::new(),
+            };
+            cases
+                .into_iter()
+                .map(|case| (format!("{}::{}", owner, rust_type_name(case)), false))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Dereference a `&T` value expression of a `Copy` type.
+fn deref(val: &str) -> String {
+    match val.strip_prefix('&') {
+        Some(place) => place.to_string(),
+        None => format!("*{val}"),
+    }
+}
+
+/// Receiver form of a `&T` value expression (for field access and method
+/// calls, which auto-dereference).
+fn recv(val: &str) -> &str {
+    val.strip_prefix('&').unwrap_or(val)
+}
+
+/// Parenthesize `expr` unless it is a plain identifier.
+fn paren(expr: &str) -> String {
+    if expr.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        expr.to_string()
+    } else {
+        format!("({expr})")
+    }
+}
+
+/// `base + offset`, omitting a zero offset.
+fn offset_addr(base: &str, offset: u32) -> String {
+    if offset == 0 {
+        base.to_string()
+    } else {
+        format!("{base} + {offset}")
+    }
+}
+
+/// Zero value of a core type, used to fill unused variant slots.
+fn zero(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "0i32",
+        WasmType::I64 => "0i64",
+        WasmType::F32 => "0.0f32",
+        WasmType::F64 => "0.0f64",
+    }
+}
+
+/// Widen a payload value of core type `have` into a joined variant slot.
+fn coerce_lower(expr: &str, have: WasmType, want: WasmType) -> String {
+    match (have, want) {
+        _ if have == want => expr.to_string(),
+        (WasmType::I32, WasmType::I64) => format!("{expr} as u32 as i64"),
+        (WasmType::F32, WasmType::I32) => format!("{}.to_bits() as i32", paren(expr)),
+        (WasmType::F32, WasmType::I64) | (WasmType::F64, WasmType::I64) => {
+            format!("{}.to_bits() as i64", paren(expr))
+        }
+        _ => expr.to_string(),
+    }
+}
+
+/// Narrow a joined variant slot of core type `have` back to a payload value.
+fn coerce_lift(expr: &str, have: WasmType, want: WasmType) -> String {
+    match (have, want) {
+        _ if have == want => expr.to_string(),
+        (WasmType::I64, WasmType::I32) => format!("{expr} as i32"),
+        (WasmType::I32, WasmType::F32) | (WasmType::I64, WasmType::F32) => {
+            format!("f32::from_bits({expr} as u32)")
+        }
+        (WasmType::I64, WasmType::F64) => format!("f64::from_bits({expr} as u64)"),
+        _ => expr.to_string(),
+    }
+}
+
+/// Collects the statements of one generated function body.
+struct Emitter<'a> {
+    world: &'a WitWorld,
+    info: &'a ModuleInfo,
+    /// Expression for the guest memory (only used by exports).
+    mem: &'static str,
+    lines: Vec<String>,
+    next_tmp: usize,
+}
+
+impl<'a> Emitter<'a> {
+    fn new(world: &'a WitWorld, info: &'a ModuleInfo) -> Self {
+        let mem = if info.has_memory {
+            "self.0.memory"
+        } else {
+            "memory"
+        };
+        Self {
+            world,
+            info,
+            mem,
+            lines: Vec::new(),
+            next_tmp: 0,
+        }
+    }
+
+    fn tmp(&mut self, prefix: &str) -> String {
+        let name = format!("__{prefix}{}", self.next_tmp);
+        self.next_tmp += 1;
+        name
+    }
+
+    fn line(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+    }
+
+    /// Render the collected lines, indenting by brace depth.
+    fn finish(self, indent: usize) -> String {
+        let mut code = String::new();
+        let mut depth = indent;
+        for line in &self.lines {
+            if line.starts_with('}') {
+                depth = depth.saturating_sub(1);
+            }
+            code.push_str(&"    ".repeat(depth));
+            code.push_str(line);
+            code.push('\n');
+            if line.ends_with('{') {
+                depth += 1;
+            }
+        }
+        code
+    }
+
+    /// Call local function `func_idx` with core arguments. Returns the
+    /// variable holding the result, if the function returns one.
+    fn call_core(&mut self, func_idx: usize, args: &[String]) -> Option<String> {
+        let info = self.info;
+        let returns = !info.ir_functions[func_idx].results.is_empty();
+        let uses_host = info.uses_host();
+
+        let mut call_args = args.to_vec();
+        call_args.push("&mut __env".to_string());
+        if info.has_memory {
+            call_args.push("&mut self.0.memory".to_string());
+        } else if info.has_memory_import {
+            call_args.push("&mut *memory".to_string());
+        }
+        if info.has_table_import {
+            call_args.push("table".to_string());
+        } else if info.has_table() {
+            call_args.push("&self.0.table".to_string());
+        }
+
+        let result = returns.then(|| self.tmp("r"));
+        match &result {
+            Some(var) => self.line(format!("let {var} = {{")),
+            None => self.line("{"),
+        }
+        if uses_host {
+            self.line("let mut __env = Env { host: &mut *host, globals: &mut self.0.globals };");
+        } else {
+            self.line("let mut __host = herkos_runtime::NoHost;");
+            self.line("let mut __env = Env { host: &mut __host, globals: &mut self.0.globals };");
+        }
+        let call = format!("{}({})?", info.func_ident(func_idx), call_args.join(", "));
+        if returns {
+            self.line(call);
+            self.line("};");
+        } else {
+            self.line(format!("{call};"));
+            self.line("}");
+        }
+        result
+    }
+
+    /// Allocate `len_expr` elements of `size` bytes through `cabi_realloc`
+    /// and return the variable holding the checked guest address.
+    fn alloc_guest(&mut self, len_expr: &str, size: u32, align: u32) -> String {
+        let realloc = self
+            .info
+            .func_exports
+            .iter()
+            .find(|e| e.name == "cabi_realloc")
+            .map(|e| e.func_index.as_usize())
+            .unwrap_or_default();
+        let bytes = if size == 1 {
+            format!("{len_expr} as i32")
+        } else {
+            format!("({len_expr} * {size}) as i32")
+        };
+        let raw = self
+            .call_core(
+                realloc,
+                &["0".to_string(), "0".to_string(), align.to_string(), bytes],
+            )
+            .unwrap_or_default();
+        let ptr = self.tmp("p");
+        self.line(format!(
+            "let {ptr} = cabi::guest_range({}.as_slice(), {raw}, {len_expr} as i32, {size}, {align})?.0;",
+            self.mem
+        ));
+        ptr
+    }
+
+    /// Lower `val` (a `&T` expression) to flat core values.
+    fn lower_flat(&mut self, ty: &WitType, val: &str) -> Vec<String> {
+        let world = self.world;
+        match ty {
+            WitType::Bool
+            | WitType::S8
+            | WitType::U8
+            | WitType::S16
+            | WitType::U16
+            | WitType::U32
+            | WitType::Char => vec![format!("{} as i32", deref(val))],
+            WitType::U64 => vec![format!("{} as i64", deref(val))],
+            WitType::S32 | WitType::S64 | WitType::F32 | WitType::F64 => vec![deref(val)],
+            WitType::String => {
+                let bytes = self.tmp("s");
+                self.line(format!("let {bytes} = {}.as_bytes();", recv(val)));
+                let ptr = self.alloc_guest(&format!("{bytes}.len()"), 1, 1);
+                self.line(format!("{}.write_bytes({ptr}, {bytes})?;", self.mem));
+                vec![format!("{ptr} as i32"), format!("{bytes}.len() as i32")]
+            }
+            WitType::List(elem) => {
+                let (size, align) = world.size_align(elem);
+                let list = self.tmp("l");
+                self.line(format!("let {list} = {val};"));
+                let ptr = self.alloc_guest(&format!("{list}.len()"), size, align);
+                let (idx, item, addr) = (self.tmp("i"), self.tmp("e"), self.tmp("a"));
+                self.line(format!(
+                    "for ({idx}, {item}) in {list}.iter().enumerate() {{"
+                ));
+                self.line(format!("let {addr} = {ptr} + {idx} * {size};"));
+                self.store(elem, &item, &addr);
+                self.line("}");
+                vec![format!("{ptr} as i32"), format!("{list}.len() as i32")]
+            }
+            WitType::Tuple(items) => items
+                .iter()
+                .enumerate()
+                .flat_map(|(i, t)| self.lower_flat(t, &format!("&{}.{i}", recv(val))))
+                .collect(),
+            WitType::Named(name) => match world.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => fields
+                    .iter()
+                    .flat_map(|f| {
+                        let field = format!("&{}.{}", recv(val), rust_field_name(&f.name));
+                        self.lower_flat(&f.ty, &field)
+                    })
+                    .collect(),
+                _ => self.lower_variant_flat(ty, val),
+            },
+            WitType::Option(_) | WitType::Result { .. } => self.lower_variant_flat(ty, val),
+        }
+    }
+
+    /// Lower a variant-like value to its discriminant and joined payload
+    /// slots with one `match`.
+    fn lower_variant_flat(&mut self, ty: &WitType, val: &str) -> Vec<String> {
+        let world = self.world;
+        let flat = world.flatten(ty);
+        let payloads = world.variant_payloads(ty).unwrap_or_default();
+        let vars: Vec<String> = flat.iter().map(|_| self.tmp("v")).collect();
+        let binding = if vars.len() == 1 {
+            vars[0].clone()
+        } else {
+            format!("({})", vars.join(", "))
+        };
+        self.line(format!("let {binding} = match {val} {{"));
+        for (disc, ((path, wraps_unit), payload)) in
+            case_paths(world, ty).into_iter().zip(&payloads).enumerate()
+        {
+            let mut values = vec![format!("{disc}i32")];
+            match payload {
+                Some(payload) => {
+                    let bound = self.tmp("x");
+                    self.line(format!("{path}({bound}) => {{"));
+                    let have = world.flatten(payload);
+                    for (i, expr) in self.lower_flat(payload, &bound).iter().enumerate() {
+                        values.push(coerce_lower(expr, have[i], flat[i + 1]));
+                    }
+                }
+                None if wraps_unit => self.line(format!("{path}(_) => {{")),
+                None => self.line(format!("{path} => {{")),
+            }
+            values.extend(flat[values.len()..].iter().map(|t| zero(*t).to_string()));
+            if values.len() == 1 {
+                self.line(values.remove(0));
+            } else {
+                self.line(format!("({})", values.join(", ")));
+            }
+            self.line("}");
+        }
+        self.line("};");
+        vars
+    }
+
+    /// Lift flat core values (`vals`, with their core types) to a WIT value
+    /// expression.
+    fn lift_flat(&mut self, ty: &WitType, vals: &[(String, WasmType)]) -> String {
+        let world = self.world;
+        let v = || vals[0].0.clone();
+        match ty {
+            WitType::Bool => format!("{} != 0", v()),
+            WitType::S8 => format!("{} as i8", v()),
+            WitType::U8 => format!("{} as u8", v()),
+            WitType::S16 => format!("{} as i16", v()),
+            WitType::U16 => format!("{} as u16", v()),
+            WitType::U32 => format!("{} as u32", v()),
+            WitType::U64 => format!("{} as u64", v()),
+            WitType::S32 | WitType::S64 | WitType::F32 | WitType::F64 => v(),
+            WitType::Char => format!("cabi::lift_char({})?", v()),
+            WitType::String => format!(
+                "cabi::lift_string({}.as_slice(), {}, {})?",
+                self.mem, vals[0].0, vals[1].0
+            ),
+            WitType::List(elem) => self.lift_list(elem, &vals[0].0, &vals[1].0),
+            WitType::Tuple(items) => {
+                let parts = self.lift_fields(items.iter(), vals);
+                if parts.len() == 1 {
+                    format!("({},)", parts[0])
+                } else {
+                    format!("({})", parts.join(", "))
+                }
+            }
+            WitType::Named(name) => match world.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => {
+                    let parts = self.lift_fields(fields.iter().map(|f| &f.ty), vals);
+                    let inits: Vec<String> = fields
+                        .iter()
+                        .zip(parts)
+                        .map(|(f, part)| format!("{}: {}", rust_field_name(&f.name), part))
+                        .collect();
+                    format!("{} {{ {} }}", rust_type_name(name), inits.join(", "))
+                }
+                _ => self.lift_variant_flat(ty, vals),
+            },
+            WitType::Option(_) | WitType::Result { .. } => self.lift_variant_flat(ty, vals),
+        }
+    }
+
+    fn lift_fields<'t>(
+        &mut self,
+        fields: impl Iterator<Item = &'t WitType>,
+        vals: &[(String, WasmType)],
+    ) -> Vec<String> {
+        let mut rest = vals;
+        let mut parts = Vec::new();
+        for ty in fields {
+            let (head, tail) = rest.split_at(self.world.flatten(ty).len());
+            parts.push(self.lift_flat(ty, head));
+            rest = tail;
+        }
+        parts
+    }
+
+    fn lift_variant_flat(&mut self, ty: &WitType, vals: &[(String, WasmType)]) -> String {
+        let world = self.world;
+        let payloads = world.variant_payloads(ty).unwrap_or_default();
+        let out = self.tmp("v");
+        self.line(format!("let {out} = match {} {{", vals[0].0));
+        for (disc, ((path, wraps_unit), payload)) in
+            case_paths(world, ty).into_iter().zip(&payloads).enumerate()
+        {
+            self.line(format!("{disc} => {{"));
+            let value = match payload {
+                Some(payload) => {
+                    let want = world.flatten(payload);
+                    let slots: Vec<(String, WasmType)> = want
+                        .iter()
+                        .zip(&vals[1..])
+                        .map(|(want, (expr, have))| (coerce_lift(expr, *have, *want), *want))
+                        .collect();
+                    let inner = self.lift_flat(payload, &slots);
+                    format!("{path}({inner})")
+                }
+                None if wraps_unit => format!("{path}(())"),
+                None => path,
+            };
+            self.line(value);
+            self.line("}");
+        }
+        self.line("_ => return Err(WasmTrap::InvalidValue),");
+        self.line("};");
+        out
+    }
+
+    /// Lift a list of `elem` stored at guest `(ptr, len)`.
+    fn lift_list(&mut self, elem: &WitType, ptr: &str, len: &str) -> String {
+        let (size, align) = self.world.size_align(elem);
+        let (base, count, out) = (self.tmp("p"), self.tmp("n"), self.tmp("vec"));
+        self.line(format!(
+            "let ({base}, {count}) = cabi::guest_range({}.as_slice(), {ptr}, {len}, {size}, {align})?;",
+            self.mem
+        ));
+        self.line(format!(
+            "let mut {out} = cabi::Vec::with_capacity({count});"
+        ));
+        let (idx, addr) = (self.tmp("i"), self.tmp("a"));
+        self.line(format!("for {idx} in 0..{count} {{"));
+        self.line(format!("let {addr} = {base} + {idx} * {size};"));
+        let item = self.load(elem, &addr);
+        self.line(format!("{out}.push({item});"));
+        self.line("}");
+        out
+    }
+
+    /// Load a WIT value stored at guest address `addr` (a `usize`).
+    fn load(&mut self, ty: &WitType, addr: &str) -> String {
+        let world = self.world;
+        let mem = self.mem;
+        match ty {
+            WitType::Bool => format!("{mem}.load_u8({addr})? != 0"),
+            WitType::S8 => format!("{mem}.load_u8({addr})? as i8"),
+            WitType::U8 => format!("{mem}.load_u8({addr})?"),
+            WitType::S16 => format!("{mem}.load_u16({addr})? as i16"),
+            WitType::U16 => format!("{mem}.load_u16({addr})?"),
+            WitType::S32 => format!("{mem}.load_i32({addr})?"),
+            WitType::U32 => format!("{mem}.load_i32({addr})? as u32"),
+            WitType::S64 => format!("{mem}.load_i64({addr})?"),
+            WitType::U64 => format!("{mem}.load_i64({addr})? as u64"),
+            WitType::F32 => format!("{mem}.load_f32({addr})?"),
+            WitType::F64 => format!("{mem}.load_f64({addr})?"),
+            WitType::Char => format!("cabi::lift_char({mem}.load_i32({addr})?)?"),
+            WitType::String => format!(
+                "cabi::lift_string({mem}.as_slice(), {mem}.load_i32({addr})?, {mem}.load_i32({addr} + 4)?)?"
+            ),
+            WitType::List(elem) => self.lift_list(
+                elem,
+                &format!("{mem}.load_i32({addr})?"),
+                &format!("{mem}.load_i32({addr} + 4)?"),
+            ),
+            WitType::Tuple(items) => {
+                let parts = self.load_fields(items.iter(), addr);
+                if parts.len() == 1 {
+                    format!("({},)", parts[0])
+                } else {
+                    format!("({})", parts.join(", "))
+                }
+            }
+            WitType::Named(name) => match world.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => {
+                    let parts = self.load_fields(fields.iter().map(|f| &f.ty), addr);
+                    let inits: Vec<String> = fields
+                        .iter()
+                        .zip(parts)
+                        .map(|(f, part)| format!("{}: {}", rust_field_name(&f.name), part))
+                        .collect();
+                    format!("{} {{ {} }}", rust_type_name(name), inits.join(", "))
+                }
+                _ => self.load_variant(ty, addr),
+            },
+            WitType::Option(_) | WitType::Result { .. } => self.load_variant(ty, addr),
+        }
+    }
+
+    fn load_fields<'t>(
+        &mut self,
+        fields: impl Iterator<Item = &'t WitType> + Clone,
+        addr: &str,
+    ) -> Vec<String> {
+        let offsets = self.world.field_offsets(fields.clone());
+        fields
+            .zip(offsets)
+            .map(|(ty, offset)| self.load(ty, &offset_addr(addr, offset)))
+            .collect()
+    }
+
+    fn load_variant(&mut self, ty: &WitType, addr: &str) -> String {
+        let world = self.world;
+        let mem = self.mem;
+        let payloads = world.variant_payloads(ty).unwrap_or_default();
+        let layout = world.variant_layout(&payloads);
+        let disc = match layout.disc_size {
+            1 => format!("{mem}.load_u8({addr})?"),
+            2 => format!("{mem}.load_u16({addr})?"),
+            _ => format!("{mem}.load_i32({addr})?"),
+        };
+        let out = self.tmp("v");
+        self.line(format!("let {out} = match {disc} {{"));
+        let payload_addr = offset_addr(addr, layout.payload_offset);
+        for (disc, ((path, wraps_unit), payload)) in
+            case_paths(world, ty).into_iter().zip(&payloads).enumerate()
+        {
+            self.line(format!("{disc} => {{"));
+            let value = match payload {
+                Some(payload) => {
+                    let inner = self.load(payload, &payload_addr);
+                    format!("{path}({inner})")
+                }
+                None if wraps_unit => format!("{path}(())"),
+                None => path,
+            };
+            self.line(value);
+            self.line("}");
+        }
+        self.line("_ => return Err(WasmTrap::InvalidValue),");
+        self.line("};");
+        out
+    }
+
+    /// Store `val` (a `&T` expression) at guest address `addr`.
+    fn store(&mut self, ty: &WitType, val: &str, addr: &str) {
+        let world = self.world;
+        let mem = self.mem;
+        let stmt = match ty {
+            WitType::Bool | WitType::S8 => {
+                format!("{mem}.store_u8({addr}, {} as u8)?;", deref(val))
+            }
+            WitType::U8 => format!("{mem}.store_u8({addr}, {})?;", deref(val)),
+            WitType::S16 => format!("{mem}.store_u16({addr}, {} as u16)?;", deref(val)),
+            WitType::U16 => format!("{mem}.store_u16({addr}, {})?;", deref(val)),
+            WitType::S32 => format!("{mem}.store_i32({addr}, {})?;", deref(val)),
+            WitType::U32 | WitType::Char => {
+                format!("{mem}.store_i32({addr}, {} as i32)?;", deref(val))
+            }
+            WitType::S64 => format!("{mem}.store_i64({addr}, {})?;", deref(val)),
+            WitType::U64 => format!("{mem}.store_i64({addr}, {} as i64)?;", deref(val)),
+            WitType::F32 => format!("{mem}.store_f32({addr}, {})?;", deref(val)),
+            WitType::F64 => format!("{mem}.store_f64({addr}, {})?;", deref(val)),
+            WitType::String | WitType::List(_) => {
+                let flat = self.lower_flat(ty, val);
+                self.line(format!("{mem}.store_i32({addr}, {})?;", flat[0]));
+                format!("{mem}.store_i32({addr} + 4, {})?;", flat[1])
+            }
+            WitType::Tuple(items) => {
+                let offsets = world.field_offsets(items.iter());
+                for (i, (item, offset)) in items.iter().zip(offsets).enumerate() {
+                    self.store(
+                        item,
+                        &format!("&{}.{i}", recv(val)),
+                        &offset_addr(addr, offset),
+                    );
+                }
+                return;
+            }
+            WitType::Named(name) => match world.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => {
+                    let offsets = world.field_offsets(fields.iter().map(|f| &f.ty));
+                    for (field, offset) in fields.iter().zip(offsets) {
+                        let place = format!("&{}.{}", recv(val), rust_field_name(&field.name));
+                        self.store(&field.ty, &place, &offset_addr(addr, offset));
+                    }
+                    return;
+                }
+                _ => return self.store_variant(ty, val, addr),
+            },
+            WitType::Option(_) | WitType::Result { .. } => {
+                return self.store_variant(ty, val, addr)
+            }
+        };
+        self.line(stmt);
+    }
+
+    fn store_variant(&mut self, ty: &WitType, val: &str, addr: &str) {
+        let world = self.world;
+        let mem = self.mem;
+        let payloads = world.variant_payloads(ty).unwrap_or_default();
+        let layout = world.variant_layout(&payloads);
+        let payload_addr = offset_addr(addr, layout.payload_offset);
+        self.line(format!("match {val} {{"));
+        for (disc, ((path, wraps_unit), payload)) in
+            case_paths(world, ty).into_iter().zip(&payloads).enumerate()
+        {
+            let bound = self.tmp("x");
+            match payload {
+                Some(_) => self.line(format!("{path}({bound}) => {{")),
+                None if wraps_unit => self.line(format!("{path}(_) => {{")),
+                None => self.line(format!("{path} => {{")),
+            }
+            self.line(match layout.disc_size {
+                1 => format!("{mem}.store_u8({addr}, {disc})?;"),
+                2 => format!("{mem}.store_u16({addr}, {disc})?;"),
+                _ => format!("{mem}.store_i32({addr}, {disc})?;"),
+            });
+            if let Some(payload) = payload {
+                self.store(payload, &bound, &payload_addr);
+            }
+            self.line("}");
+        }
+        self.line("}");
+    }
+}
+
+/// Generics and trailing parameters shared with the raw export methods.
+fn export_generics(info: &ModuleInfo) -> (String, Vec<String>) {
+    let forwarded = ForwardedState::new(info);
+    let params = forwarded.params.iter().map(|p| p.to_string()).collect();
+    (forwarded.generic_part(), params)
+}
+
+/// Emit the `call_<name>` wrapper for one export. Returns `None` if the
+/// export is missing, which validation in `build_module_info` rules out.
+fn generate_export(world: &WitWorld, func: &WitFunction, info: &ModuleInfo) -> Option<String> {
+    let func_idx = info
+        .func_exports
+        .iter()
+        .find(|e| e.name == func.core_name)?
+        .func_index
+        .as_usize();
+    let (generic_part, extra_params) = export_generics(info);
+
+    let mut params = vec!["&mut self".to_string()];
+    for param in &func.params {
+        params.push(format!(
+            "{}: {}",
+            rust_field_name(&param.name),
+            param_type(&param.ty)
+        ));
+    }
+    params.extend(extra_params);
+    let result_type = func
+        .result
+        .as_ref()
+        .map_or_else(|| "()".to_string(), rust_type);
+
+    let mut em = Emitter::new(world, info);
+    let mut args = Vec::new();
+    for param in &func.params {
+        let name = rust_field_name(&param.name);
+        let val = match param.ty {
+            WitType::String | WitType::List(_) => name,
+            _ => format!("&{name}"),
+        };
+        args.extend(em.lower_flat(&param.ty, &val));
+    }
+
+    let ret = em.call_core(func_idx, &args);
+    let results = world.flatten_results(func);
+    let out = match (&func.result, &ret) {
+        (Some(ty), Some(ret)) => {
+            let value = if results.len() <= MAX_FLAT_RESULTS {
+                em.lift_flat(ty, &[(ret.clone(), results[0])])
+            } else {
+                let (size, align) = world.size_align(ty);
+                let base = em.tmp("ret");
+                em.line(format!(
+                    "let {base} = cabi::guest_range({}.as_slice(), {ret}, 1, {size}, {align})?.0;",
+                    em.mem
+                ));
+                em.load(ty, &base)
+            };
+            let out = em.tmp("out");
+            em.line(format!("let {out} = {value};"));
+            out
+        }
+        _ => "()".to_string(),
+    };
+
+    let post = format!("cabi_post_{}", func.core_name);
+    if let Some(post) = info.func_exports.iter().find(|e| e.name == post) {
+        em.call_core(post.func_index.as_usize(), ret.as_slice());
+    }
+    em.line(format!("Ok({out})"));
+
+    let signature = func
+        .params
+        .iter()
+        .map(|p| format!("{}: {}", p.name, describe_type(&p.ty)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let returns = func
+        .result
+        .as_ref()
+        .map(|ty| format!(" -> {}", describe_type(ty)))
+        .unwrap_or_default();
+    let mut code = format!(
+        "    /// WIT export `{}` (`func({signature}){returns}`).\n",
+        func.core_name
+    );
+    code.push_str(&format!(
+        "    pub fn {}{generic_part}({}) -> WasmResult<{result_type}> {{\n",
+        func.export_method(),
+        params.join(", ")
+    ));
+    code.push_str(&em.finish(2));
+    code.push_str("    }\n");
+    Some(code)
+}
+
+/// Emit the `WitImports` trait and its `ModuleHostTrait` adapter.
+fn generate_imports(world: &WitWorld, info: &ModuleInfo) -> String {
+    let mut code = String::from("/// WIT-typed host imports of this world.\n");
+    code.push_str("pub trait WitImports {\n");
+    for func in &world.imports {
+        let mut params = vec!["&mut self".to_string()];
+        for param in &func.params {
+            params.push(format!(
+                "{}: {}",
+                rust_field_name(&param.name),
+                rust_type(&param.ty)
+            ));
+        }
+        let result = func
+            .result
+            .as_ref()
+            .map_or_else(|| "()".to_string(), rust_type);
+        code.push_str(&format!(
+            "    fn {}({}) -> WasmResult<{result}>;\n",
+            func.rust_name(),
+            params.join(", ")
+        ));
+    }
+    code.push_str("}\n\n");
+
+    code.push_str("impl<T: WitImports + ?Sized> ModuleHostTrait for T {\n");
+    for imp in &info.func_imports {
+        let Some(func) = world.import_for(&imp.module_name, &imp.func_name) else {
+            continue;
+        };
+        let mut params = vec!["&mut self".to_string()];
+        let mut vals = Vec::new();
+        for (i, ty) in imp.params.iter().enumerate() {
+            params.push(format!(
+                "arg{i}: {}",
+                crate::codegen::types::wasm_type_to_rust(ty)
+            ));
+            vals.push((format!("arg{i}"), *ty));
+        }
+        let return_ty = crate::codegen::types::format_return_type(&imp.results);
+        code.push_str(&format!(
+            "    fn {}({}) -> {} {{\n",
+            rust_ident(&imp.func_name),
+            params.join(", "),
+            return_ty
+        ));
+
+        let mut em = Emitter::new(world, info);
+        let mut rest = vals.as_slice();
+        let mut args = Vec::new();
+        for param in &func.params {
+            let (head, tail) = rest.split_at(world.flatten(&param.ty).len());
+            rest = tail;
+            let value = em.lift_flat(&param.ty, head);
+            let name = rust_field_name(&param.name);
+            em.line(format!("let {name} = {value};"));
+            args.push(name);
+        }
+        args.insert(0, "self".to_string());
+        let call = format!("WitImports::{}({})?", func.rust_name(), args.join(", "));
+        match &func.result {
+            Some(ty) => {
+                em.line(format!("let __result = {call};"));
+                let flat = em.lower_flat(ty, "&__result");
+                em.line(format!("Ok({})", flat[0]));
+            }
+            None => {
+                em.line(format!("{call};"));
+                em.line("Ok(())");
+            }
+        }
+        code.push_str(&em.finish(2));
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n\n");
+    code
+}
+
+/// WIT spelling of a type, for doc comments.
+fn describe_type(ty: &WitType) -> String {
+    match ty {
+        WitType::Bool => "bool".to_string(),
+        WitType::S8 => "s8".to_string(),
+        WitType::U8 => "u8".to_string(),
+        WitType::S16 => "s16".to_string(),
+        WitType::U16 => "u16".to_string(),
+        WitType::S32 => "s32".to_string(),
+        WitType::U32 => "u32".to_string(),
+        WitType::S64 => "s64".to_string(),
+        WitType::U64 => "u64".to_string(),
+        WitType::F32 => "f32".to_string(),
+        WitType::F64 => "f64".to_string(),
+        WitType::Char => "char".to_string(),
+        WitType::String => "string".to_string(),
+        WitType::List(elem) => format!("list<{}>", describe_type(elem)),
+        WitType::Option(inner) => format!("option<{}>", describe_type(inner)),
+        WitType::Result { ok, err } => match (ok, err) {
+            (None, None) => "result".to_string(),
+            (Some(ok), None) => format!("result<{}>", describe_type(ok)),
+            (ok, Some(err)) => format!(
+                "result<{}, {}>",
+                ok.as_deref().map_or_else(|| "_".to_string(), describe_type),
+                describe_type(err)
+            ),
+        },
+        WitType::Tuple(items) => format!(
+            "tuple<{}>",
+            items
+                .iter()
+                .map(describe_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        WitType::Named(name) => name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wit::WitWorld;
+    use crate::{transpile, TranspileOptions};
+
+    fn transpile_with(wat: &str, wit: &str) -> anyhow::Result<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let options = TranspileOptions {
+            wit: Some(WitWorld::parse(wit)?),
+            ..TranspileOptions::default()
+        };
+        transpile(&wasm, &options)
+    }
+
+    #[test]
+    fn emits_types_for_records_enums_and_variants() {
+        let code = transpile_with(
+            r#"(module (func (export "tick")))"#,
+            r#"world w {
+                record http-request { url: string, retries: u8 }
+                enum level { low, high }
+                variant shape { circle(f32), empty }
+                export tick: func();
+            }"#,
+        )
+        .unwrap();
+        assert!(code.contains(
+            "pub struct HttpRequest {\n    pub url: cabi::String,\n    pub retries: u8,\n}"
+        ));
+        assert!(code.contains("pub enum Level {\n    Low,\n    High,\n}"));
+        assert!(code.contains("pub enum Shape {\n    Circle(f32),\n    Empty,\n}"));
+        assert!(code.contains("pub fn call_tick(&mut self) -> WasmResult<()> {"));
+    }
+
+    #[test]
+    fn string_params_go_through_realloc_and_results_through_retptr() {
+        let code = transpile_with(
+            r#"(module
+                (memory 1 1)
+                (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32) i32.const 8)
+                (func (export "upper") (param i32 i32) (result i32) i32.const 0)
+                (func (export "cabi_post_upper") (param i32)))"#,
+            "world w { export upper: func(s: string) -> string; }",
+        )
+        .unwrap();
+        assert!(
+            code.contains("pub fn call_upper(&mut self, s: &str) -> WasmResult<cabi::String> {")
+        );
+        assert!(
+            code.contains("func_0(0, 0, 1, __s0.len() as i32, &mut __env, &mut self.0.memory)?")
+        );
+        assert!(code.contains("self.0.memory.write_bytes(__p2, __s0)?;"));
+        assert!(code.contains("cabi::guest_range(self.0.memory.as_slice(), __r3, 1, 8, 4)?.0;"));
+        assert!(code.contains("cabi::lift_string(self.0.memory.as_slice(), self.0.memory.load_i32(__ret4)?, self.0.memory.load_i32(__ret4 + 4)?)?"));
+        // Post-return cleanup receives the return pointer.
+        assert!(code.contains("func_2(__r3, &mut __env, &mut self.0.memory)?;"));
+    }
+
+    #[test]
+    fn imports_get_a_wit_trait_and_blanket_adapter() {
+        let code = transpile_with(
+            r#"(module
+                (import "$root" "set-flag" (func (param i32 i32) (result i32)))
+                (func (export "run")))"#,
+            r#"world w {
+                enum flag { on, off }
+                import set-flag: func(f: flag, ok: bool) -> option<u8>;
+                export run: func();
+            }"#,
+        );
+        // option<u8> flattens to two values: more than a host import may return.
+        let msg = format!("{:#}", code.unwrap_err());
+        assert!(msg.contains("results returned through memory"), "{msg}");
+
+        let code = transpile_with(
+            r#"(module
+                (import "$root" "set-flag" (func (param i32 i32) (result i32)))
+                (func (export "run")
+                    (drop (call 0 (i32.const 0) (i32.const 1)))))"#,
+            r#"world w {
+                enum flag { on, off }
+                import set-flag: func(f: flag, ok: bool) -> char;
+                export run: func();
+            }"#,
+        )
+        .unwrap();
+        assert!(code.contains("fn set_flag(&mut self, f: Flag, ok: bool) -> WasmResult<char>;"));
+        assert!(code.contains("impl<T: WitImports + ?Sized> ModuleHostTrait for T {"));
+        assert!(code.contains("fn set_flag(&mut self, arg0: i32, arg1: i32) -> WasmResult<i32> {"));
+        assert!(code.contains("0 => {\n                Flag::On\n            }"));
+        assert!(code.contains("let ok = arg1 != 0;"));
+        assert!(code.contains("let __result = WitImports::set_flag(self, f, ok)?;"));
+        assert!(code.contains("Ok(__result as i32)"));
+        // The core import itself is reachable under its sanitized name.
+        assert!(code.contains("env.host.set_flag("));
+    }
+
+    #[test]
+    fn no_world_emits_nothing() {
+        let wasm = wat::parse_str(r#"(module (func (export "run")))"#).unwrap();
+        let code = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert!(!code.contains("WitImports"));
+        assert!(!code.contains("call_run"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Contracts on the arguments of exports.
+//!
+//! A guest API often has invariants its signature cannot express: a volume
+//! between 0 and 100, a buffer pointer that must not be null. A contracts
+//! file states them, one precondition per line:
+//!
+//! ```text
+//! # export, parameter index, condition
+//! require set_volume 0 range 0..=100
+//! require read 0 nonnull
+//! require read 1 range 1..=4096
+//! ```
+//!
+//! The guest can carry the same lines in a `herkos.contracts` custom section
+//! (see [`crate::parser::CONTRACTS_SECTION`]), which is read in addition to
+//! any file. Each export method states its contracts in its documentation
+//! and checks them with `debug_assert!` before calling into the guest.
+//!
+//! `range MIN..=MAX` applies to integer parameters and compares signed;
+//! `nonnull` applies to `i32` parameters, addresses in linear memory.
+
+use crate::ir::{ContractKind, ModuleInfo, ParamContract, WasmType};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/// Parsed contracts file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContractConfig {
+    /// Contracts in declaration order.
+    pub contracts: Vec<ExportContract>,
+}
+
+/// Contract on one argument of one export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportContract {
+    /// Export name.
+    pub export: String,
+    /// Parameter and condition.
+    pub contract: ParamContract,
+}
+
+impl ContractConfig {
+    /// Parse a contracts file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = ContractConfig::default();
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let contract = parse_line(line)
+                .with_context(|| format!("contracts line {}: `{}`", idx + 1, line))?;
+            config.contracts.push(contract);
+        }
+        Ok(config)
+    }
+
+    /// Whether no contract is declared.
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty()
+    }
+
+    /// Add the contracts of `other`, skipping ones already declared.
+    pub fn extend(&mut self, other: ContractConfig) {
+        for contract in other.contracts {
+            if !self.contracts.contains(&contract) {
+                self.contracts.push(contract);
+            }
+        }
+    }
+
+    /// Check every contract against the exports of `info` and group them by
+    /// export.
+    ///
+    /// # Errors
+    /// Fails if an export does not exist, lacks the parameter, or the
+    /// parameter's type does not fit the condition.
+    pub fn resolve(&self, info: &ModuleInfo) -> Result<BTreeMap<String, Vec<ParamContract>>> {
+        let mut resolved: BTreeMap<String, Vec<ParamContract>> = BTreeMap::new();
+        for entry in &self.contracts {
+            let func = info
+                .func_exports
+                .iter()
+                .find(|e| e.name == entry.export)
+                .and_then(|e| info.ir_function(e.func_index));
+            let Some(func) = func else {
+                bail!(
+                    "contract on `{}`, which is not a function export",
+                    entry.export
+                );
+            };
+            let param = entry.contract.param;
+            let Some(&(_, ty)) = func.params.get(param) else {
+                bail!(
+                    "contract on argument {param} of `{}`, which takes {}",
+                    entry.export,
+                    func.params.len()
+                );
+            };
+            check_type(&entry.contract.kind, ty)
+                .with_context(|| format!("contract on argument {param} of `{}`", entry.export))?;
+            resolved
+                .entry(entry.export.clone())
+                .or_default()
+                .push(entry.contract);
+        }
+        Ok(resolved)
+    }
+}
+
+fn parse_line(line: &str) -> Result<ExportContract> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (export, param, kind) = match words.as_slice() {
+        ["require", export, param, "range", range] => (export, param, parse_range(range)?),
+        ["require", export, param, "nonnull"] => (export, param, ContractKind::NonNull),
+        ["require", _, _, "range"] => bail!("expected `range MIN..=MAX`"),
+        ["require", ..] => {
+            bail!("expected `require <export> <param> range MIN..=MAX` or `... nonnull`")
+        }
+        [other, ..] => bail!("unknown directive `{}` (expected `require`)", other),
+        [] => unreachable!("blank lines are skipped"),
+    };
+    let param = param
+        .parse()
+        .with_context(|| format!("`{param}` is not a parameter index"))?;
+    Ok(ExportContract {
+        export: export.to_string(),
+        contract: ParamContract { param, kind },
+    })
+}
+
+fn parse_range(range: &str) -> Result<ContractKind> {
+    let Some((min, max)) = range.split_once("..=") else {
+        bail!("`{range}` is not a range `MIN..=MAX`");
+    };
+    let min: i64 = min
+        .parse()
+        .with_context(|| format!("`{min}` is not an integer"))?;
+    let max: i64 = max
+        .parse()
+        .with_context(|| format!("`{max}` is not an integer"))?;
+    if min > max {
+        bail!("empty range `{range}`");
+    }
+    Ok(ContractKind::Range { min, max })
+}
+
+fn check_type(kind: &ContractKind, ty: WasmType) -> Result<()> {
+    match (kind, ty) {
+        (ContractKind::NonNull, WasmType::I32) => Ok(()),
+        (ContractKind::NonNull, _) => bail!("`nonnull` needs an i32 address, not {ty:?}"),
+        (ContractKind::Range { min, max }, WasmType::I32) => {
+            let fits = |v: &i64| i32::try_from(*v).is_ok();
+            if !fits(min) || !fits(max) {
+                bail!("range {min}..={max} does not fit an i32");
+            }
+            Ok(())
+        }
+        (ContractKind::Range { .. }, WasmType::I64) => Ok(()),
+        (ContractKind::Range { .. }, _) => bail!("`range` needs an integer, not {ty:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    const WAT: &str = r#"(module
+        (func (export "set_volume") (param i32))
+        (func (export "scale") (param i64 f32)))"#;
+
+    fn info() -> ModuleInfo {
+        let wasm = wat::parse_str(WAT).unwrap();
+        build_module_info(&parse_wasm(&wasm).unwrap(), &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn parses_ranges_and_nonnull() {
+        let config = ContractConfig::parse(
+            "# volume\nrequire set_volume 0 range 0..=100  # percent\n\nrequire scale 0 range -8..=8\nrequire set_volume 0 nonnull\n",
+        )
+        .unwrap();
+        let resolved = config.resolve(&info()).unwrap();
+        assert_eq!(
+            resolved["set_volume"],
+            [
+                ParamContract {
+                    param: 0,
+                    kind: ContractKind::Range { min: 0, max: 100 }
+                },
+                ParamContract {
+                    param: 0,
+                    kind: ContractKind::NonNull
+                },
+            ]
+        );
+        assert_eq!(
+            resolved["scale"][0].kind,
+            ContractKind::Range { min: -8, max: 8 }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        for (text, expected) in [
+            ("ensure a 0 nonnull", "unknown directive"),
+            ("require a 0 range 0..100", "is not a range"),
+            ("require a 0 range 5..=1", "empty range"),
+            ("require a x nonnull", "not a parameter index"),
+            ("require a 0 positive", "expected `require"),
+        ] {
+            let err = ContractConfig::parse(text).unwrap_err();
+            assert!(format!("{err:#}").contains(expected), "{text}: {err:#}");
+        }
+    }
+
+    #[test]
+    fn rejects_contracts_that_do_not_fit_the_export() {
+        let info = info();
+        for (text, expected) in [
+            ("require missing 0 nonnull", "not a function export"),
+            ("require set_volume 1 nonnull", "which takes 1"),
+            ("require scale 1 range 0..=1", "needs an integer"),
+            ("require scale 0 nonnull", "needs an i32 address"),
+            (
+                "require set_volume 0 range 0..=4294967295",
+                "does not fit an i32",
+            ),
+        ] {
+            let err = ContractConfig::parse(text)
+                .unwrap()
+                .resolve(&info)
+                .unwrap_err();
+            assert!(format!("{err:#}").contains(expected), "{text}: {err:#}");
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Cargo feature gating for exports.
+//!
+//! The linker drops unused functions from the final binary, but every export
+//! of a transpiled module is still compiled, type-checked and kept by
+//! `cargo check`. A features file lets downstream crates compile unused
+//! exports out instead:
+//!
+//! ```text
+//! # Exports not listed below are gated on `export-<name>`.
+//! prefix export-
+//!
+//! export add math
+//! export mul math
+//! export hash crypto
+//! ```
+//!
+//! Each gated export method gets `#[cfg(feature = "...")]`, and every
+//! internal function gets `#[cfg(any(...))]` over the features of the exports
+//! that reach it, through direct calls or through `call_indirect` to a
+//! function of the same type. Functions that are also reachable from an
+//! ungated export, or from no export at all, are always compiled. Without a
+//! `prefix` directive, unlisted exports stay ungated.
+//!
+//! Exports the generated helpers call themselves — the guest allocator, the
+//! TinyGo scheduler entry points and `cabi_post_*` — are never gated.
+//!
+//! The features must be declared in the `[features]` table of the crate that
+//! includes the generated code.
+
+use crate::analysis::detect_tinygo;
+use crate::bindings::GuestAllocator;
+use crate::ir::ModuleInfo;
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/// Parsed features file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FeatureConfig {
+    /// Prefix of the feature derived for every unlisted export.
+    pub prefix: Option<String>,
+    /// Explicit export-to-feature assignments, in declaration order.
+    pub exports: Vec<ExportFeature>,
+}
+
+/// Feature assigned to one export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportFeature {
+    /// Export name.
+    pub export: String,
+    /// Cargo feature that compiles the export in.
+    pub feature: String,
+}
+
+impl FeatureConfig {
+    /// Parse a features file.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = FeatureConfig::default();
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            config
+                .parse_line(line)
+                .with_context(|| format!("features line {}: `{}`", idx + 1, line))?;
+        }
+        Ok(config)
+    }
+
+    /// Set the prefix of derived features, replacing any `prefix` directive.
+    pub fn set_prefix(&mut self, prefix: &str) -> Result<()> {
+        check_prefix(prefix)?;
+        self.prefix = Some(prefix.to_string());
+        Ok(())
+    }
+
+    /// Whether no export is gated.
+    pub fn is_empty(&self) -> bool {
+        self.prefix.is_none() && self.exports.is_empty()
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["prefix", prefix] => {
+                if self.prefix.is_some() {
+                    bail!("duplicate `prefix` directive");
+                }
+                self.set_prefix(prefix)?;
+            }
+            ["prefix", ..] => bail!("expected `prefix <feature-prefix>`"),
+            ["export", export, feature] => {
+                check_feature(feature)?;
+                if self.exports.iter().any(|e| e.export == *export) {
+                    bail!("duplicate export `{}`", export);
+                }
+                self.exports.push(ExportFeature {
+                    export: export.to_string(),
+                    feature: feature.to_string(),
+                });
+            }
+            ["export", ..] => bail!("expected `export <name> <feature>`"),
+            [other, ..] => bail!(
+                "unknown directive `{}` (expected `prefix` or `export`)",
+                other
+            ),
+            [] => {}
+        }
+        Ok(())
+    }
+
+    /// Map every gated export of `info` to its feature.
+    ///
+    /// # Errors
+    /// Fails if a listed export does not exist or is called by generated
+    /// helpers.
+    pub fn resolve(&self, info: &ModuleInfo) -> Result<BTreeMap<String, String>> {
+        let pinned = helper_exports(info);
+        let mut gates = BTreeMap::new();
+        for entry in &self.exports {
+            if !info.func_exports.iter().any(|e| e.name == entry.export) {
+                bail!(
+                    "features file gates `{}`, which is not a function export",
+                    entry.export
+                );
+            }
+            if pinned.contains(&entry.export) {
+                bail!(
+                    "export `{}` is called by generated helpers and cannot be gated",
+                    entry.export
+                );
+            }
+            gates.insert(entry.export.clone(), entry.feature.clone());
+        }
+        if let Some(prefix) = &self.prefix {
+            for export in &info.func_exports {
+                if pinned.contains(&export.name) || gates.contains_key(&export.name) {
+                    continue;
+                }
+                gates.insert(export.name.clone(), derive_feature(prefix, &export.name));
+            }
+        }
+        Ok(gates)
+    }
+}
+
+/// Feature derived for `export` under `prefix`: characters Cargo does not
+/// allow in feature names become `_`, and a leading `-` gets a `_` prefix.
+pub fn derive_feature(prefix: &str, export: &str) -> String {
+    let mut name: String = export
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if !is_feature_name(&format!("{prefix}{name}")) {
+        name.insert(0, '_');
+    }
+    format!("{prefix}{name}")
+}
+
+/// Exports that generated helpers call directly.
+fn helper_exports(info: &ModuleInfo) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Some(allocator) = GuestAllocator::detect(info) {
+        names.push(allocator.alloc.export_name().to_string());
+        names.extend(allocator.free.map(|f| f.export_name().to_string()));
+    }
+    if let Some(tinygo) = detect_tinygo(info) {
+        names.extend(tinygo.entry);
+        if tinygo.has_scheduler {
+            names.push("go_scheduler".to_string());
+        }
+        if tinygo.has_resume {
+            names.push("resume".to_string());
+        }
+    }
+    names.extend(
+        info.func_exports
+            .iter()
+            .filter(|e| e.name.starts_with("cabi_post_"))
+            .map(|e| e.name.clone()),
+    );
+    names
+}
+
+/// Cargo feature names start with a letter, digit or `_`, and continue with
+/// those, `-`, `+` or `.`.
+fn is_feature_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.'))
+}
+
+fn check_feature(feature: &str) -> Result<()> {
+    if !is_feature_name(feature) {
+        bail!("`{}` is not a valid Cargo feature name", feature);
+    }
+    Ok(())
+}
+
+fn check_prefix(prefix: &str) -> Result<()> {
+    // Derived names append at least one valid character.
+    if !is_feature_name(&format!("{prefix}x")) {
+        bail!("`{}` is not a valid Cargo feature prefix", prefix);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn info(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        build_module_info(&parsed, &TranspileOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn parses_prefix_and_explicit_exports() {
+        let config = FeatureConfig::parse(
+            "# gating\nprefix export-\n\nexport add math  # shared\nexport mul math\n",
+        )
+        .unwrap();
+        assert_eq!(config.prefix.as_deref(), Some("export-"));
+        assert_eq!(
+            config.exports,
+            vec![
+                ExportFeature {
+                    export: "add".to_string(),
+                    feature: "math".to_string(),
+                },
+                ExportFeature {
+                    export: "mul".to_string(),
+                    feature: "math".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_report_line_numbers() {
+        let err = FeatureConfig::parse("prefix a-\nexport add bad\"name\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("features line 2"));
+        assert!(FeatureConfig::parse("gate add math").is_err());
+        assert!(FeatureConfig::parse("export add").is_err());
+        assert!(FeatureConfig::parse("export add a\nexport add b").is_err());
+        assert!(FeatureConfig::parse("prefix -x").is_err());
+    }
+
+    #[test]
+    fn resolve_derives_unlisted_exports_and_skips_helpers() {
+        let info = info(
+            r#"(module
+                (func (export "add"))
+                (func (export "mul"))
+                (func (export "cabi_post_add"))
+                (func (export "to.string")))"#,
+        );
+        let config = FeatureConfig::parse("prefix export-\nexport add math").unwrap();
+        let gates = config.resolve(&info).unwrap();
+        let gates: Vec<(&str, &str)> = gates
+            .iter()
+            .map(|(e, f)| (e.as_str(), f.as_str()))
+            .collect();
+        assert_eq!(
+            gates,
+            vec![
+                ("add", "math"),
+                ("mul", "export-mul"),
+                ("to.string", "export-to_string"),
+            ]
+        );
+
+        let unknown = FeatureConfig::parse("export sub math").unwrap();
+        assert!(unknown.resolve(&info).is_err());
+        let helper = FeatureConfig::parse("export cabi_post_add math").unwrap();
+        assert!(helper.resolve(&info).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! The IR of the `herkos-ir` crate, built with transpiler options.
+//!
+//! Everything in [`herkos_ir::ir`] is re-exported. [`build_module_info`]
+//! wraps the IR builder: it also validates the options that only matter to
+//! code generation (bindings, WIT world, host wrappers, export groups,
+//! constant-time exports and feature gates) against the module and records
+//! them in the [`ModuleInfo`].
+
+pub use herkos_ir::ir::*;
+
+use crate::codegen::utils::rust_ident;
+use crate::contracts::ContractConfig;
+use crate::TranspileOptions;
+use anyhow::{bail, Context, Result};
+use herkos_ir::parser::{ParsedModule, CONTRACTS_SECTION};
+
+/// Host-trait method called at yield points.
+pub const YIELD_METHOD: &str = "maybe_yield";
+
+/// Methods of `herkos_runtime::MemoryObserver`, a supertrait of the host
+/// trait under `--observe-memory`.
+pub const OBSERVER_METHODS: [&str; 2] = ["on_load", "on_store"];
+
+/// Build the IR of a parsed module for transpilation with `options`.
+///
+/// # Errors
+/// Fails if the module cannot be translated, or if an option does not fit
+/// it (bindings or WIT world not matching the exports, import names that
+/// collide with generated host methods, ...).
+pub fn build_module_info(parsed: &ParsedModule, options: &TranspileOptions) -> Result<ModuleInfo> {
+    let build = BuildOptions {
+        max_pages: options.max_pages,
+        lower_atomics: options.lower_atomics,
+        call_ctors: options.call_ctors,
+    };
+    let mut info = herkos_ir::ir::build_module_info(parsed, &build)?;
+
+    // Check high-level bindings against the real export and import
+    // signatures, then record the import arguments to check
+    options.bindings.validate(&info)?;
+    info.import_regions = options.bindings.import_regions(&info);
+
+    // Check the WIT world against the core exports and imports it describes
+    if let Some(world) = &options.wit {
+        world.validate(&info)?;
+    }
+
+    if options.wasmtime_adapter {
+        if info.has_memory_import {
+            bail!("the wasmtime adapter does not support modules that import their memory");
+        }
+        if info.has_table_import {
+            bail!("the wasmtime adapter does not support modules that import their table");
+        }
+        info.wasmtime_adapter = true;
+    }
+
+    if options.yield_points {
+        if let Some(import) = info
+            .func_imports
+            .iter()
+            .find(|imp| rust_ident(&imp.func_name) == YIELD_METHOD)
+        {
+            bail!(
+                "import `{}.{}` collides with the `{YIELD_METHOD}` yield-point method",
+                import.module_name,
+                import.func_name
+            );
+        }
+        info.yield_points = true;
+    }
+
+    if options.debug_memory {
+        crate::codegen::debug_memory::check_debug_memory(&info)?;
+        info.debug_memory = true;
+    }
+
+    if options.protect_rodata {
+        if !info.has_memory {
+            bail!("--protect-rodata needs a module that defines its own memory");
+        }
+        for seg in &mut info.data_segments {
+            seg.protected = seg
+                .name
+                .as_deref()
+                .is_some_and(|name| name == ".rodata" || name.starts_with(".rodata."));
+        }
+    }
+
+    // Debug memory sees accesses through the observer
+    if options.observe_memory || options.debug_memory {
+        if let Some(import) = info
+            .func_imports
+            .iter()
+            .find(|imp| OBSERVER_METHODS.contains(&rust_ident(&imp.func_name).as_str()))
+        {
+            bail!(
+                "import `{}.{}` collides with a `MemoryObserver` method",
+                import.module_name,
+                import.func_name
+            );
+        }
+        info.observe_memory = info.has_memory || info.has_memory_import;
+    }
+
+    if options.record_replay {
+        // The TinyGo scheduler reads the host clock outside any import call,
+        // so the log would miss it.
+        if crate::analysis::detect_tinygo(&info).is_some_and(|t| t.uses_clock) {
+            bail!("record/replay does not support TinyGo modules that use the scheduler clock");
+        }
+        info.record_replay = true;
+    }
+
+    if options.link {
+        // Linked instances are called with nothing but their arguments, and
+        // a `Linker` only serves function imports
+        if info.has_memory_import || info.has_table_import {
+            bail!("--link needs a module that defines its own memory and table");
+        }
+        if let Some(g) = info.imported_globals.first() {
+            bail!(
+                "--link does not support imported globals (`{}.{}`)",
+                g.module_name,
+                g.name
+            );
+        }
+        if info.observe_memory {
+            bail!("--link cannot be combined with --observe-memory");
+        }
+        if crate::analysis::detect_tinygo(&info).is_some_and(|t| t.uses_clock) {
+            bail!("--link does not support TinyGo modules that use the scheduler clock");
+        }
+        // A `Linker` returns at most one `Value`
+        if let Some(imp) = info.func_imports.iter().find(|imp| imp.results.len() > 1) {
+            bail!(
+                "--link does not support import `{}.{}` with several results",
+                imp.module_name,
+                imp.func_name
+            );
+        }
+        info.link = true;
+    }
+
+    if options.heap_profile {
+        crate::codegen::heap_profile::check_heap_profile(&info)?;
+        info.heap_profile = true;
+    }
+
+    if options.coverage {
+        crate::codegen::coverage::check_coverage(&info)?;
+        info.coverage = true;
+    }
+
+    if options.bounds_profile {
+        crate::codegen::bounds_profile::check_bounds_profile(&info)?;
+        info.bounds_profile = true;
+    }
+
+    if options.hot_reload {
+        crate::codegen::hot_reload::check_hot_reload(&info)?;
+        info.hot_reload = true;
+    }
+
+    // `LinkedWasmModule` and `HotPlugin` dispatch through `invoke`
+    info.dynamic_invoke = options.dynamic_invoke || options.link || options.hot_reload;
+    if info.dynamic_invoke {
+        // `invoke` returns at most one `Value`
+        let multi_value = info.func_exports.iter().find(|e| {
+            info.ir_function(e.func_index)
+                .is_some_and(|f| f.results.len() > 1)
+        });
+        if let Some(export) = multi_value {
+            bail!(
+                "dynamic invocation does not support export `{}` with several results",
+                export.name
+            );
+        }
+    }
+
+    crate::codegen::groups::check_groups(&options.export_groups, &info)?;
+    info.export_groups = options.export_groups.clone();
+
+    if options.shared_host {
+        if !info.uses_host() {
+            bail!("--shared-host needs a module whose exports take a host");
+        }
+        info.shared_host = true;
+    }
+
+    if let Some(name) = options
+        .constant_time
+        .iter()
+        .find(|name| !info.func_exports.iter().any(|e| &e.name == *name))
+    {
+        bail!("--constant-time: no function export named `{name}`");
+    }
+    info.constant_time = crate::analysis::constant_time_functions(&info, &options.constant_time);
+
+    if !options.no_optimize.is_empty() {
+        let mut unoptimized = vec![false; info.ir_functions.len()];
+        for name in &options.no_optimize {
+            let idx = find_function(&info, name)
+                .with_context(|| format!("--no-optimize: no function named `{name}`"))?;
+            unoptimized[idx] = true;
+        }
+        info.unoptimized = unoptimized;
+    }
+
+    // Logging imports reach the host as text, through `GuestLog`
+    if options.guest_log {
+        if info.link || info.hot_reload || info.record_replay || options.wit.is_some() {
+            bail!("--guest-log cannot be combined with --link, --hot-reload, --record-replay or a WIT world");
+        }
+        info.guest_log = crate::codegen::guest_log::check_guest_log(&info)?;
+    }
+
+    // Host traits per capability. Generated hosts (`--link`, hot reload,
+    // record/replay, WIT adapters) implement `ModuleHostTrait` in full.
+    if !options.capabilities.is_empty() {
+        if info.link || info.hot_reload || info.record_replay || options.wit.is_some() {
+            bail!("--capabilities cannot be combined with --link, --hot-reload, --record-replay or a WIT world");
+        }
+        info.import_capabilities = options.capabilities.resolve(&info);
+    }
+
+    // Cargo features for gated exports and the functions they reach
+    info.export_features = options.features.resolve(&info)?;
+
+    // Contracts from the options and from the module itself
+    let mut contracts = options.contracts.clone();
+    if let Some(text) = &parsed.contracts {
+        let declared = ContractConfig::parse(text)
+            .with_context(|| format!("in the `{CONTRACTS_SECTION}` section"))?;
+        contracts.extend(declared);
+    }
+    info.contracts = contracts.resolve(&info)?;
+    info.func_features = crate::analysis::function_features(&info);
+
+    Ok(info)
+}
+
+/// Local index of the function `name` designates: an export name, a name
+/// section name, or a local index.
+fn find_function(info: &ModuleInfo, name: &str) -> Option<usize> {
+    if let Some(export) = info.func_exports.iter().find(|e| e.name == name) {
+        return Some(export.func_index.as_usize());
+    }
+    if let Some(idx) = info
+        .func_symbols
+        .iter()
+        .position(|sym| sym.name.as_deref() == Some(name))
+    {
+        return Some(idx);
+    }
+    name.parse()
+        .ok()
+        .filter(|&idx| idx < info.ir_functions.len())
+}
@@ -1,33 +1,192 @@
 //! herkos-core — WebAssembly to Rust transpiler.
 //!
 //! This crate provides the core transpilation pipeline that converts WebAssembly
-//! modules into memory-safe Rust source code.
+//! modules into memory-safe Rust source code. The parser, IR and optimizer
+//! live in the `herkos-ir` crate and are re-exported here.
 
+pub mod analysis;
 pub mod backend;
+pub mod bindings;
 pub mod c_ffi;
+pub mod capabilities;
 pub mod codegen;
+pub mod contracts;
+pub mod features;
 pub mod ir;
-pub mod optimizer;
-pub mod parser;
+pub mod pretransform;
+pub mod wit;
+
+pub use herkos_ir::{optimizer, parser};
 
 // Re-export key types for convenience
+use anyhow::bail;
 pub use anyhow::{Context, Result};
 use backend::SafeBackend;
 use codegen::CodeGenerator;
-use ir::builder::build_module_info;
+use ir::build_module_info;
 use ir::{lower_phis, LoweredModuleInfo};
 use optimizer::{optimize_ir, optimize_lowered_ir};
-use parser::parse_wasm;
+use parser::{parse_wasm_with_features, supported_features};
+pub use pretransform::PreTransform;
+use std::sync::Arc;
 
 /// Configuration options for transpilation
 #[derive(Debug, Clone)]
 pub struct TranspileOptions {
-    /// Code generation backend mode ("safe", "verified", "hybrid")
+    /// Code generation backend mode ("safe", "verified", "hybrid"), or "c"
+    /// for C99 source from the experimental C backend (see [`codegen::c`])
     pub mode: String,
     /// Maximum memory pages (used when Wasm module declares no maximum)
     pub max_pages: usize,
     /// Enable optimizations
     pub optimize: bool,
+    /// Largest function body, in IR instructions, inlined into its callers
+    /// when optimizing; 0 disables inlining
+    /// (see [`optimizer::inline_small_functions`])
+    pub inline_threshold: usize,
+    /// Reject modules containing any source of nondeterminism
+    /// (see [`analysis::audit_determinism`])
+    pub deterministic: bool,
+    /// High-level wrapper bindings for pointer/length exports
+    /// (see [`bindings`])
+    pub bindings: bindings::BindingConfig,
+    /// WIT world for canonical-ABI export wrappers and import traits
+    /// (see [`wit`])
+    pub wit: Option<wit::WitWorld>,
+    /// Emit a wasmtime-style adapter (`Instance::get_typed_func`,
+    /// `Memory::data_mut`) over the generated module
+    /// (see `herkos_runtime::wasmtime_compat`)
+    pub wasmtime_adapter: bool,
+    /// Call the host's `maybe_yield` at every loop header, so long-running
+    /// calls can be interrupted. An interrupted call is unwound, not
+    /// suspended: only guests written to be restarted can pick up where
+    /// they stopped (see `herkos_runtime::WasmTrap::Interrupted`)
+    pub yield_points: bool,
+    /// Cargo features gating exports and the functions they reach
+    /// (see [`features`])
+    pub features: features::FeatureConfig,
+    /// Preconditions on export arguments, checked with `debug_assert!` and
+    /// stated in the docs (see [`contracts`]). Contracts in the module's
+    /// `herkos.contracts` custom section are added to these
+    pub contracts: contracts::ContractConfig,
+    /// Regroup function imports into one host trait per capability
+    /// (see [`capabilities`])
+    pub capabilities: capabilities::CapabilityConfig,
+    /// Route logging imports (`log(ptr, len)`, `console.log`, WASI
+    /// `fd_write` to stdout or stderr) to `herkos_runtime::GuestLog`, a
+    /// supertrait of `ModuleHostTrait`, with the text read out of linear
+    /// memory by generated shims (see [`codegen::guest_log`])
+    pub guest_log: bool,
+    /// Lower atomic memory operations (threads proposal) to plain loads and
+    /// stores, with fences as no-ops. Only sound when a single thread runs
+    /// the module: nothing is atomic in the generated code.
+    pub lower_atomics: bool,
+    /// Run the C/C++ static constructors (the `__wasm_call_ctors` export)
+    /// at transpile time, like a start function, so `new()` returns a
+    /// module whose constructors have run and the export does nothing.
+    /// Without it, modules with the export get an `initialize()` method to
+    /// call after `new()`
+    pub call_ctors: bool,
+    /// Trap with `WasmTrap::HostAssertedOverflow` on signed overflow in
+    /// integer `add`, `sub` and `mul` instead of wrapping. A testing aid for
+    /// ported C code, not spec-conformant; constants folded by `optimize`
+    /// still wrap.
+    pub checked_arithmetic: bool,
+    /// Report every load and store (and bulk memory operation) to the host
+    /// through `herkos_runtime::MemoryObserver`, for data watchpoints while
+    /// debugging. The runtime's `observe` feature must be enabled.
+    pub observe_memory: bool,
+    /// Emit `Recorder` and `Replayer` host wrappers that log every host
+    /// result to a `herkos_runtime::ReplayLog` and play it back, to
+    /// reproduce a run exactly while debugging.
+    pub record_replay: bool,
+    /// Emit an `invoke` method calling exports by name with
+    /// `herkos_runtime::Value` arguments, for hosts that only know the
+    /// export and its arguments at runtime
+    pub dynamic_invoke: bool,
+    /// Name prefixes (`image_`, `audio_`) whose exports are also reachable
+    /// through a namespaced view with the prefix stripped
+    /// (`module.image().resize(..)`, see [`codegen::groups`])
+    pub export_groups: Vec<String>,
+    /// Emit a `SharedWasmModule<S>` wrapper holding a host shared with other
+    /// instances (`Rc<RefCell<H>>`, `Arc<Mutex<H>>`), borrowed for each call
+    /// through `herkos_runtime::SharedHost`
+    pub shared_host: bool,
+    /// Exports generated in constant-time form, with every function they
+    /// reach: the optimizer leaves them as translated, `select` is emitted
+    /// without a branch (`herkos_runtime::ct_select`), and
+    /// [`Reports::constant_time`] lists the data-dependent branches left
+    pub constant_time: Vec<String>,
+    /// Functions the optimizer leaves as translated, by export name, name
+    /// section name or local index (the `N` of `func_N`), for working
+    /// around a suspected miscompile or keeping a function bit-exact while
+    /// it is investigated. Every mode generates safe code for them
+    pub no_optimize: Vec<String>,
+    /// Emit the glue for wiring modules together at runtime: a
+    /// `LinkedWasmModule<H>` serving other modules' imports through
+    /// `herkos_runtime::Linkable`, and `ModuleHostTrait` for
+    /// `herkos_runtime::Linker`, routing imports to registered modules.
+    /// Implies [`dynamic_invoke`](Self::dynamic_invoke)
+    pub link: bool,
+    /// Route the guest's `malloc` / `free` (and `calloc` / `realloc`, when
+    /// exported) through a `herkos_runtime::HeapProfile` that tracks live
+    /// blocks, exposed through `heap_stats()` and reported at drop
+    pub heap_profile: bool,
+    /// Emit compact output for rustc alone: no comments, doc attributes,
+    /// indentation or line breaks inside items (see [`codegen::compact`])
+    pub compact: bool,
+    /// Emit `DATA_SEGMENTS` and `arm_debug_memory`, which poisons the memory
+    /// outside the data segments and guards them with canaries through a
+    /// `herkos_runtime::DebugMemory`. Implies
+    /// [`observe_memory`](Self::observe_memory), which reports reads of
+    /// never-written bytes and stores into canaries
+    pub debug_memory: bool,
+    /// Make the data segments wasm-ld names `.rodata` (or `.rodata.*`)
+    /// read-only once the module is initialized, through
+    /// `IsolatedMemory::protect`: a store into constant data traps with
+    /// `WasmTrap::WriteProtected` instead of corrupting it. Needs the `name`
+    /// section's data segment names and a module that defines its memory
+    pub protect_rodata: bool,
+    /// Count the executions of every block in `Globals`, exposed through
+    /// `coverage_hits()` and written as an LCOV report keyed by function and
+    /// Wasm byte offset by `coverage_report()` (see [`codegen::coverage`]).
+    /// Duplicate functions are not merged, so each keeps its own counts
+    pub coverage: bool,
+    /// Count the bounds checks of every memory access in `Globals`, exposed
+    /// through `bounds_check_hits()` and listed hottest first, by function,
+    /// Wasm byte offset and operator, by `bounds_report()` (see
+    /// [`codegen::bounds_profile`]). Needs a memory; duplicate functions are
+    /// not merged, so each keeps its own counts
+    pub bounds_profile: bool,
+    /// Implement `herkos_runtime::HotPlugin` and emit `HOT_VTABLE`, the
+    /// C-ABI entry points a `cdylib` exports so that a
+    /// `herkos_runtime::HotModule` can swap in a rebuilt module while
+    /// keeping its memory and globals (see [`codegen::hot_reload`]).
+    /// Implies [`dynamic_invoke`](Self::dynamic_invoke)
+    pub hot_reload: bool,
+    /// Emit only code that compiles for every target `herkos-runtime`
+    /// supports, 32- or 64-bit, `no_std` included: float rounding and square
+    /// roots call the runtime's `core`-only helpers (`wasm_sqrt_f32`, ...)
+    /// instead of `std`'s, and addresses are computed with
+    /// `herkos_runtime::effective_address`, which cannot wrap on 32-bit
+    /// `usize`. Checked by the CLI's `--target-audit`
+    pub portable: bool,
+    /// Rewrite the Wasm binary before parsing, with `wasm-opt` or any other
+    /// module-to-module transform. Results are cached, and the transform is
+    /// recorded in the generated `PRETRANSFORM` (see [`pretransform`])
+    pub pretransform: Option<PreTransform>,
+    /// Edit the generated source after code generation, to add attributes,
+    /// headers or lint configuration (applied after `compact`)
+    pub postprocess: Option<PostProcess>,
+    /// Module doc, inner attributes and `use` statements added to the top of
+    /// the generated Rust file (see [`Preamble`])
+    pub preamble: Preamble,
+    /// Instruction costs and loop bounds for the static worst-case cost
+    /// estimate in [`Reports::wcet`] (see [`analysis::Wcet`])
+    pub wcet: analysis::WcetConfig,
+    /// Frame sizes and recursion depths for the static stack usage estimate
+    /// in [`Reports::stack`] (see [`analysis::StackUsage`])
+    pub stack: analysis::StackConfig,
 }
 
 impl Default for TranspileOptions {
@@ -36,14 +195,137 @@ impl Default for TranspileOptions {
             mode: "safe".to_string(),
             max_pages: 256,
             optimize: false,
+            inline_threshold: optimizer::DEFAULT_INLINE_THRESHOLD,
+            deterministic: false,
+            bindings: bindings::BindingConfig::default(),
+            wit: None,
+            wasmtime_adapter: false,
+            yield_points: false,
+            features: features::FeatureConfig::default(),
+            contracts: contracts::ContractConfig::default(),
+            capabilities: capabilities::CapabilityConfig::default(),
+            guest_log: false,
+            lower_atomics: false,
+            call_ctors: false,
+            checked_arithmetic: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            no_optimize: Vec::new(),
+            link: false,
+            heap_profile: false,
+            compact: false,
+            debug_memory: false,
+            protect_rodata: false,
+            coverage: false,
+            bounds_profile: false,
+            hot_reload: false,
+            portable: false,
+            pretransform: None,
+            postprocess: None,
+            preamble: Preamble::default(),
+            wcet: analysis::WcetConfig::default(),
+            stack: analysis::StackConfig::default(),
         }
     }
 }
 
+impl TranspileOptions {
+    /// Whether code generated outside the function bodies reads or writes
+    /// linear memory on the host's behalf: binding and WIT wrappers, the
+    /// wasmtime adapter, guest-log shims, hot-reload state transfer. Data
+    /// segments must then stay in memory as the module initialized them, so
+    /// read-only data is not promoted to constants.
+    pub fn host_accesses_memory(&self) -> bool {
+        !self.bindings.is_empty()
+            || self.wit.is_some()
+            || self.wasmtime_adapter
+            || self.guest_log
+            || self.hot_reload
+    }
+}
+
+/// Items added to the top of the generated Rust file (see
+/// [`TranspileOptions::preamble`]), to fit the lint regime and conventions
+/// of the crate that includes it.
+///
+/// ```
+/// use herkos_core::{Preamble, TranspileOptions};
+///
+/// let options = TranspileOptions {
+///     preamble: Preamble {
+///         module_doc: Some("Guest codec, transpiled from codec.wasm.".to_string()),
+///         inner_attributes: vec!["allow(clippy::all)".to_string()],
+///         extra_use_statements: vec!["core::convert::TryFrom".to_string()],
+///     },
+///     ..TranspileOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preamble {
+    /// Inner attributes, emitted as `#![...]` before any item. Given with or
+    /// without the `#![` `]` around them.
+    pub inner_attributes: Vec<String>,
+    /// Paths imported after `use herkos_runtime::*;`. Given with or without
+    /// `use` and the final `;`.
+    pub extra_use_statements: Vec<String>,
+    /// Module documentation, emitted as `//!` lines ahead of the inner
+    /// attributes.
+    pub module_doc: Option<String>,
+}
+
+impl Preamble {
+    /// Whether the preamble adds nothing.
+    pub fn is_empty(&self) -> bool {
+        self.inner_attributes.is_empty()
+            && self.extra_use_statements.is_empty()
+            && self.module_doc.is_none()
+    }
+}
+
+/// A hook rewriting the generated source in place (see
+/// [`TranspileOptions::postprocess`]).
+///
+/// ```
+/// use herkos_core::{PostProcess, TranspileOptions};
+///
+/// let options = TranspileOptions {
+///     postprocess: Some(PostProcess::new(|code| {
+///         code.insert_str(0, "// SPDX-License-Identifier: Apache-2.0\n");
+///     })),
+///     ..TranspileOptions::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct PostProcess(Arc<dyn Fn(&mut String) + Send + Sync>);
+
+impl PostProcess {
+    /// Wrap `hook`.
+    pub fn new(hook: impl Fn(&mut String) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    /// Run the hook on `code`.
+    pub fn apply(&self, code: &mut String) {
+        (self.0)(code)
+    }
+}
+
+impl std::fmt::Debug for PostProcess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PostProcess(..)")
+    }
+}
+
 /// Transpile a WebAssembly module to Rust source code.
 ///
 /// This is the main entry point for the transpilation pipeline.
-/// It takes raw WASM bytes and returns generated Rust code as a String.
+/// It takes raw WASM bytes and returns generated Rust code as a String (C code
+/// when `options.mode` is "c"). The same bytes and options always produce
+/// byte-identical output.
 ///
 /// # Arguments
 /// * `wasm_bytes` - Raw WebAssembly binary data
@@ -62,15 +344,132 @@ impl Default for TranspileOptions {
 /// std::fs::write("output.rs", rust_code).unwrap();
 /// ```
 pub fn transpile(wasm_bytes: &[u8], options: &TranspileOptions) -> Result<String> {
+    Ok(transpile_to_artifacts(wasm_bytes, options)?.rust_code)
+}
+
+/// Everything the pipeline produced for one module.
+///
+/// Returned by [`transpile_to_artifacts`] for tools that consume the IR
+/// (visualizers, verifiers, alternative backends) rather than, or as well as,
+/// the generated Rust.
+#[derive(Debug)]
+pub struct Artifacts {
+    /// Generated Rust source code, identical to what [`transpile`] returns
+    /// (C source in mode "c")
+    pub rust_code: String,
+    /// The optimized, phi-lowered IR the Rust code was generated from
+    pub module_info: LoweredModuleInfo,
+    /// Analyses of the module
+    pub reports: Reports,
+}
+
+/// Analysis results gathered while transpiling (see [`analysis`]).
+#[derive(Debug, Clone)]
+pub struct Reports {
+    /// Sources of nondeterminism in the unoptimized IR. Empty when
+    /// [`TranspileOptions::deterministic`] is set, since transpilation fails
+    /// on any finding.
+    pub determinism: Vec<analysis::NondeterminismSource>,
+    /// Call graph of [`Artifacts::module_info`]
+    pub call_graph: analysis::CallGraph,
+    /// Host, memory, globals and table needs of each function in
+    /// [`Artifacts::module_info`]
+    pub requirements: analysis::Requirements,
+    /// Static worst-case cost of each export under
+    /// [`TranspileOptions::wcet`], computed on the SSA IR before phi lowering
+    pub wcet: analysis::Wcet,
+    /// Native stack each export needs under [`TranspileOptions::stack`]
+    pub stack: analysis::StackUsage,
+    /// Data-dependent branches left in the functions selected by
+    /// [`TranspileOptions::constant_time`]
+    pub constant_time: analysis::ConstantTime,
+    /// Which functions of [`Artifacts::module_info`] change no state
+    pub side_effects: analysis::SideEffects,
+}
+
+/// Transpile a WebAssembly module, returning the generated Rust code together
+/// with the IR it was generated from and the analysis reports.
+///
+/// Runs the same pipeline as [`transpile`], so the IR reflects
+/// `options.optimize`.
+///
+/// # Example
+/// ```no_run
+/// use herkos_core::{transpile_to_artifacts, TranspileOptions};
+///
+/// let wasm_bytes = std::fs::read("input.wasm").unwrap();
+/// let artifacts = transpile_to_artifacts(&wasm_bytes, &TranspileOptions::default()).unwrap();
+/// for func in &artifacts.module_info.ir_functions {
+///     println!("{} blocks", func.blocks.len());
+/// }
+/// ```
+pub fn transpile_to_artifacts(wasm_bytes: &[u8], options: &TranspileOptions) -> Result<Artifacts> {
+    // Binary-level cleanups come first
+    let transformed = match &options.pretransform {
+        Some(transform) => Some(transform.apply(wasm_bytes)?),
+        None => None,
+    };
+    let input = wasm_bytes;
+    let wasm_bytes = transformed.as_deref().unwrap_or(input);
+
     // Parse the WebAssembly binary
-    let parsed = parse_wasm(wasm_bytes).context("failed to parse WebAssembly module")?;
+    let parsed = parse_wasm_with_features(wasm_bytes, supported_features(options.lower_atomics))
+        .context("failed to parse WebAssembly module")?;
 
     // Build complete module metadata from parsed module
-    let module_info =
+    let mut module_info =
         build_module_info(&parsed, options).context("failed to build module metadata")?;
+    module_info.pretransform = options
+        .pretransform
+        .as_ref()
+        .map(|transform| transform.record(input, wasm_bytes));
+
+    // Determinism audit runs on the unoptimized IR so the verdict does not
+    // depend on which optimizations are enabled.
+    let determinism = analysis::audit_determinism(&module_info);
+    if options.deterministic && !determinism.is_empty() {
+        let report: Vec<String> = determinism.iter().map(|s| format!("  {}", s)).collect();
+        bail!(
+            "module is not deterministic ({} finding(s)):\n{}",
+            determinism.len(),
+            report.join("\n")
+        );
+    }
 
     // Optimize the pure SSA IR.
-    let module_info = optimize_ir(module_info, options.optimize)?;
+    let mut module_info = optimize_ir(module_info, options.optimize)?;
+
+    // Small straight-line callees are copied into their callers, unless
+    // counters attribute blocks or accesses to the function holding them.
+    if options.optimize
+        && options.inline_threshold > 0
+        && !options.coverage
+        && !options.bounds_profile
+    {
+        optimizer::inline_small_functions(&mut module_info, options.inline_threshold)?;
+    }
+
+    // Data segments nothing writes become consts. Generated code reading or
+    // writing guest memory on the host's behalf would see them missing or
+    // stale, and folded loads would never reach a memory observer.
+    let observed = options.observe_memory || options.debug_memory;
+    if options.optimize && !options.host_accesses_memory() && !observed {
+        optimizer::promote_read_only_data(&mut module_info);
+    }
+
+    // Interprocedural passes: drop parameters nothing reads, then generate
+    // identical bodies (template instantiations) once, unless each is
+    // counted on its own.
+    if options.optimize {
+        optimizer::eliminate_dead_arguments(&mut module_info);
+        if !options.coverage && !options.bounds_profile {
+            optimizer::merge_duplicate_functions(&mut module_info);
+        }
+    }
+
+    // Loop bound detection matches induction variables through phis, so the
+    // cost estimate runs before they are lowered.
+    let wcet = analysis::Wcet::compute(&module_info, &options.wcet);
 
     // SSA destruction: lower phi nodes to predecessor assignments.
     let lowered_module_info = lower_phis::lower(module_info);
@@ -78,18 +477,390 @@ pub fn transpile(wasm_bytes: &[u8], options: &TranspileOptions) -> Result<String
     // Optimize the lowered IR
     let lowered_module_info = optimize_lowered_ir(lowered_module_info, options.optimize)?;
 
-    // Generate Rust source code
-    let rust_code = generate_rust_code(&lowered_module_info)?;
+    // Generate Rust (or C) source code
+    let mut rust_code = match options.mode.as_str() {
+        "c" if options.checked_arithmetic => {
+            bail!("checked arithmetic is not supported by the C backend")
+        }
+        "c" if options.observe_memory => {
+            bail!("memory observation is not supported by the C backend")
+        }
+        "c" if options.record_replay => {
+            bail!("record/replay is not supported by the C backend")
+        }
+        "c" if !options.bindings.is_empty() => {
+            bail!("binding wrappers are not supported by the C backend")
+        }
+        "c" if options.wit.is_some() => {
+            bail!("WIT bindings are not supported by the C backend")
+        }
+        "c" if options.compact => {
+            bail!("compact output is not supported by the C backend")
+        }
+        "c" if !options.preamble.is_empty() => {
+            bail!("a preamble is not supported by the C backend")
+        }
+        "c" => generate_c_code(&lowered_module_info)?,
+        "safe" | "verified" | "hybrid" => generate_rust_code(&lowered_module_info, options)?,
+        other => bail!("unknown mode `{other}` (expected safe, verified, hybrid or c)"),
+    };
+
+    if let Some(hook) = &options.postprocess {
+        hook.apply(&mut rust_code);
+    }
+
+    let reports = Reports {
+        determinism,
+        call_graph: analysis::CallGraph::build(&lowered_module_info),
+        requirements: analysis::Requirements::compute(&lowered_module_info),
+        wcet,
+        stack: analysis::StackUsage::compute(&lowered_module_info, &options.stack),
+        constant_time: analysis::ConstantTime::compute(&lowered_module_info),
+        side_effects: analysis::SideEffects::compute(&lowered_module_info),
+    };
 
-    Ok(rust_code)
+    Ok(Artifacts {
+        rust_code,
+        module_info: lowered_module_info,
+        reports,
+    })
 }
 
 /// Generates Rust source code from IR and module metadata.
-fn generate_rust_code(module_info: &LoweredModuleInfo) -> Result<String> {
-    let backend = SafeBackend::new();
-    let codegen = CodeGenerator::new(&backend);
+fn generate_rust_code(
+    module_info: &LoweredModuleInfo,
+    options: &TranspileOptions,
+) -> Result<String> {
+    let backend = SafeBackend::new()
+        .with_checked_arithmetic(options.checked_arithmetic)
+        .with_portable(options.portable);
+    let mut codegen = CodeGenerator::new(&backend)
+        .with_bindings(&options.bindings)
+        .with_preamble(&options.preamble);
+    if let Some(world) = &options.wit {
+        codegen = codegen.with_wit(world);
+    }
 
-    codegen
+    let code = codegen
         .generate_module_with_info(module_info)
-        .context("failed to generate Rust code")
+        .context("failed to generate Rust code")?;
+    Ok(if options.compact {
+        codegen::compact::compact(&code)
+    } else {
+        code
+    })
+}
+
+/// Generates C source code from IR with the experimental C backend.
+fn generate_c_code(module_info: &LoweredModuleInfo) -> Result<String> {
+    codegen::c::generate_c_module(&backend::CBackend::new(), module_info)
+        .context("failed to generate C code")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAT: &str = r#"(module
+        (import "env" "time" (func $time (result i64)))
+        (func $helper (result i64) (call $time))
+        (func (export "run") (result i64) (call $helper)))"#;
+
+    #[test]
+    fn artifacts_match_transpile_and_expose_ir() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let options = TranspileOptions::default();
+        let artifacts = transpile_to_artifacts(&wasm, &options).unwrap();
+
+        assert_eq!(artifacts.rust_code, transpile(&wasm, &options).unwrap());
+        assert_eq!(artifacts.module_info.ir_functions.len(), 2);
+
+        let run = ir::LocalFuncIdx::new(1);
+        let helper = ir::LocalFuncIdx::new(0);
+        assert_eq!(artifacts.reports.call_graph.direct_callees(run), [helper]);
+        assert!(artifacts.reports.requirements.needs_host(run));
+        // `time` looks like a clock import.
+        assert!(!artifacts.reports.determinism.is_empty());
+        assert!(matches!(
+            artifacts.reports.wcet.export("run"),
+            Some(analysis::Bound::Cost(_))
+        ));
+        // `run` calls `helper`, which calls the host
+        let helper_stack = artifacts.reports.stack.func(helper).bytes().unwrap();
+        assert_eq!(
+            artifacts.reports.stack.export("run"),
+            Some(analysis::StackBound::Bytes(
+                artifacts.reports.stack.frame(run) + helper_stack
+            ))
+        );
+    }
+
+    #[test]
+    fn optimize_generates_duplicate_bodies_once() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "a") (param i32) (result i32) (i32.mul (local.get 0) (i32.const 3)))
+                (func (export "b") (param i32) (result i32) (i32.mul (local.get 0) (i32.const 3))))"#,
+        )
+        .unwrap();
+        let options = TranspileOptions {
+            optimize: true,
+            ..TranspileOptions::default()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.contains("fn func_0<"));
+        assert!(!code.contains("fn func_1<"));
+        assert!(code.contains("    pub fn b(&mut self, v0: i32) -> WasmResult<i32> {"));
+
+        let plain = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert!(plain.contains("fn func_1<"));
+    }
+
+    #[test]
+    fn optimize_inlines_small_callees() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func $scale (param i32) (result i32) (i32.mul (local.get 0) (i32.const 3)))
+                (func (export "f") (param i32) (result i32)
+                    (i32.add (call $scale (local.get 0)) (call $scale (i32.const 5)))))"#,
+        )
+        .unwrap();
+        let calls = |options: &TranspileOptions| {
+            let artifacts = transpile_to_artifacts(&wasm, options).unwrap();
+            artifacts.module_info.ir_functions[1]
+                .blocks
+                .iter()
+                .flat_map(|b| &b.instructions)
+                .filter(|i| matches!(i, ir::IrInstr::Call { .. }))
+                .count()
+        };
+        let optimized = TranspileOptions {
+            optimize: true,
+            ..TranspileOptions::default()
+        };
+        assert_eq!(calls(&optimized), 0);
+        assert_eq!(calls(&TranspileOptions::default()), 2);
+        let no_inlining = TranspileOptions {
+            inline_threshold: 0,
+            ..optimized
+        };
+        assert_eq!(calls(&no_inlining), 2);
+    }
+
+    #[test]
+    fn optimize_keeps_public_signatures_of_shrunk_functions() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func $f (param i32 i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+                (func (export "run") (param i32) (result i32)
+                    (call $f (local.get 0) (i32.const 9))))"#,
+        )
+        .unwrap();
+        let signature = "    pub fn func_f(&mut self, v0: i32, v1: i32) -> WasmResult<i32> {";
+        let plain = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert!(plain.contains(signature));
+
+        let options = TranspileOptions {
+            optimize: true,
+            ..TranspileOptions::default()
+        };
+        let artifacts = transpile_to_artifacts(&wasm, &options).unwrap();
+        assert_eq!(artifacts.module_info.ir_functions[0].params.len(), 1);
+        assert!(artifacts.rust_code.contains(signature));
+        assert!(artifacts.rust_code.contains("        let _ = v1;\n"));
+    }
+
+    #[test]
+    fn no_optimize_leaves_listed_functions_as_translated() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func $a (export "a") (result i32) (i32.add (i32.const 1) (i32.const 2)))
+                (func $b (result i32) (i32.add (i32.const 1) (i32.const 2)))
+                (func (export "b") (result i32) (call $b)))"#,
+        )
+        .unwrap();
+        let instrs = |artifacts: &Artifacts, idx: usize| -> usize {
+            artifacts.module_info.ir_functions[idx]
+                .blocks
+                .iter()
+                .map(|b| b.instructions.len())
+                .sum()
+        };
+        let plain = transpile_to_artifacts(&wasm, &TranspileOptions::default()).unwrap();
+        let options = TranspileOptions {
+            optimize: true,
+            no_optimize: vec!["b".to_string(), "0".to_string()],
+            ..TranspileOptions::default()
+        };
+        let artifacts = transpile_to_artifacts(&wasm, &options).unwrap();
+        // `b` names the export, func 2; `0` is func 0. Func 1 is optimized.
+        assert_eq!(artifacts.module_info.unoptimized, [true, false, true]);
+        assert_eq!(instrs(&artifacts, 0), instrs(&plain, 0));
+        assert!(instrs(&artifacts, 1) < instrs(&plain, 1));
+        assert!(!artifacts.module_info.is_merged(1));
+
+        let unknown = TranspileOptions {
+            no_optimize: vec!["c".to_string()],
+            ..TranspileOptions::default()
+        };
+        let err = transpile(&wasm, &unknown).unwrap_err();
+        assert!(format!("{err:#}").contains("--no-optimize: no function named `c`"));
+    }
+
+    #[test]
+    fn postprocess_edits_the_generated_source() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let options = TranspileOptions {
+            postprocess: Some(PostProcess::new(|code| {
+                code.insert_str(0, "// SPDX-License-Identifier: MIT\n");
+            })),
+            ..TranspileOptions::default()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.starts_with("// SPDX-License-Identifier: MIT\n// Generated by herkos"));
+    }
+
+    #[test]
+    fn preamble_adds_doc_attributes_and_uses() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let options = TranspileOptions {
+            preamble: Preamble {
+                inner_attributes: vec![
+                    "allow(clippy::all)".to_string(),
+                    "#![deny(unsafe_code)]".to_string(),
+                ],
+                extra_use_statements: vec![
+                    "core::fmt".to_string(),
+                    "use core::mem;".to_string(),
+                    "pub use herkos_runtime::WasmTrap".to_string(),
+                ],
+                module_doc: Some("Codec guest.\n\nDo not edit.".to_string()),
+            },
+            ..TranspileOptions::default()
+        };
+        let code = transpile(&wasm, &options).unwrap();
+        assert!(code.contains(
+            "// DO NOT EDIT\n\n//! Codec guest.\n//!\n//! Do not edit.\n\n\
+             #![allow(clippy::all)]\n#![deny(unsafe_code)]\n\n\
+             use herkos_runtime::*;\nuse core::fmt;\nuse core::mem;\npub use herkos_runtime::WasmTrap;\n\n"
+        ));
+
+        let plain = transpile(&wasm, &TranspileOptions::default()).unwrap();
+        assert!(plain.contains("// DO NOT EDIT\n\nuse herkos_runtime::*;\n\n"));
+    }
+
+    #[test]
+    fn pretransform_runs_before_parsing_and_is_recorded() {
+        // The "binary" is text until the transform assembles it
+        let options = TranspileOptions {
+            pretransform: Some(PreTransform::new("wat2wasm", |text| {
+                Ok(wat::parse_bytes(text)?.into_owned())
+            })),
+            ..TranspileOptions::default()
+        };
+        let artifacts = transpile_to_artifacts(WAT.as_bytes(), &options).unwrap();
+        let wasm = wat::parse_str(WAT).unwrap();
+        let record = artifacts.module_info.pretransform.as_ref().unwrap();
+        assert_eq!(record.input_hash, pretransform::fingerprint(WAT.as_bytes()));
+        assert_eq!(record.output_hash, pretransform::fingerprint(&wasm));
+        let code = artifacts.rust_code;
+        assert!(code.contains("// Pre-transform: wat2wasm\n"));
+        assert!(code.contains(&format!(
+            "pub const PRETRANSFORM: (&str, u64, u64) = (\"wat2wasm\", {:#018x}, {:#018x});",
+            record.input_hash, record.output_hash
+        )));
+        assert!(code.contains("pub fn run<"));
+
+        let err = transpile(b"(module", &options).unwrap_err();
+        assert!(format!("{err:#}").starts_with("pre-transform `wat2wasm` failed"));
+    }
+
+    #[test]
+    fn artifacts_honor_deterministic_option() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let options = TranspileOptions {
+            deterministic: true,
+            ..TranspileOptions::default()
+        };
+        assert!(transpile_to_artifacts(&wasm, &options).is_err());
+    }
+
+    #[test]
+    fn output_is_byte_identical_across_runs() {
+        // Sibling and nested loops, several import modules and variables
+        // defined out of order: each hash map built along the way is seeded
+        // differently, so any iteration order leaking into the output shows
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (import "wasi" "clock" (func $clock (result i64)))
+                (import "env" "base" (global $base i32))
+                (memory 1 1)
+                (func (export "run") (param i32 i32) (result i32) (local i32 i32 i64)
+                  (loop $a
+                    (local.set 2 (i32.add (local.get 2) (i32.mul (local.get 1) (i32.const 3))))
+                    (local.set 3 (i32.const 0))
+                    (loop $inner
+                      (local.set 3 (i32.add (local.get 3) (i32.xor (local.get 1) (global.get $base))))
+                      (br_if $inner (i32.lt_u (local.get 3) (i32.const 100))))
+                    (br_if $a (i32.lt_u (local.get 2) (local.get 0))))
+                  (loop $b
+                    (local.set 4 (i64.add (local.get 4) (call $clock)))
+                    (i32.store (local.get 2) (i32.wrap_i64 (local.get 4)))
+                    (br_if $b (i64.lt_u (local.get 4) (i64.const 1000))))
+                  (call $log (local.get 3))
+                  (i32.add (local.get 2) (local.get 3))))"#,
+        )
+        .unwrap();
+        for optimize in [false, true] {
+            let options = TranspileOptions {
+                optimize,
+                ..TranspileOptions::default()
+            };
+            let first = transpile(&wasm, &options).unwrap();
+            for _ in 0..8 {
+                assert_eq!(transpile(&wasm, &options).unwrap(), first);
+            }
+        }
+    }
+
+    #[test]
+    fn host_memory_access_keeps_data_segments_resident() {
+        // The only load folds, so nothing in the function bodies reads the
+        // segment any more; `log` is there for `--guest-log`
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "log" (func (param i32 i32)))
+                (memory 1 1)
+                (data (i32.const 16) "\2a\00\00\00")
+                (func (export "get") (result i32) (i32.load (i32.const 16))))"#,
+        )
+        .unwrap();
+        let resident = |options: TranspileOptions| {
+            let options = TranspileOptions {
+                optimize: true,
+                ..options
+            };
+            let artifacts = transpile_to_artifacts(&wasm, &options).unwrap();
+            artifacts.module_info.data_segments[0].resident
+        };
+        assert!(!resident(TranspileOptions::default()));
+        for options in [
+            TranspileOptions {
+                wasmtime_adapter: true,
+                ..TranspileOptions::default()
+            },
+            TranspileOptions {
+                hot_reload: true,
+                ..TranspileOptions::default()
+            },
+            TranspileOptions {
+                guest_log: true,
+                ..TranspileOptions::default()
+            },
+        ] {
+            assert!(options.host_accesses_memory());
+            assert!(resident(options));
+        }
+    }
 }
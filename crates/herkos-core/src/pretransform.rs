@@ -0,0 +1,333 @@
+//! Binary transforms run before parsing.
+//!
+//! Cleanups that work on the Wasm binary — `wasm-opt`, stripping custom
+//! sections, a rewriter written in Rust — can run as part of transpiling
+//! instead of as a separate build step. A [`PreTransform`] wraps such a
+//! module-to-module function under a name:
+//!
+//! ```
+//! use herkos_core::{PreTransform, TranspileOptions};
+//!
+//! let options = TranspileOptions {
+//!     pretransform: Some(PreTransform::new("identity", |wasm| Ok(wasm.to_vec()))),
+//!     ..TranspileOptions::default()
+//! };
+//! ```
+//!
+//! Results are cached by the name and the input bytes, in memory (the most
+//! recent [`MEMORY_CACHE_ENTRIES`]) and, with [`PreTransform::with_cache_dir`],
+//! on disk across runs. A cache file holds the name and input next to the
+//! output and only counts as a hit when both match, so a hash collision or a
+//! stray file is a miss rather than another module's output. The generated code
+//! records what ran in `PRETRANSFORM` (name, and the [`fingerprint`] of the
+//! input and output binaries; see [`crate::ir::PreTransformRecord`]).
+
+use crate::ir::PreTransformRecord;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+type Hook = dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync;
+
+/// Inputs whose outputs a [`PreTransform`] keeps in memory; the oldest is
+/// evicted first.
+pub const MEMORY_CACHE_ENTRIES: usize = 64;
+
+/// Outputs by input, with the inputs in insertion order for eviction.
+#[derive(Default)]
+struct Cache {
+    outputs: HashMap<Arc<[u8]>, Arc<[u8]>>,
+    order: VecDeque<Arc<[u8]>>,
+}
+
+impl Cache {
+    fn insert(&mut self, input: &[u8], output: Arc<[u8]>) {
+        if self.outputs.contains_key(input) {
+            return;
+        }
+        if self.order.len() == MEMORY_CACHE_ENTRIES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.outputs.remove(&oldest);
+            }
+        }
+        let input: Arc<[u8]> = input.into();
+        self.order.push_back(input.clone());
+        self.outputs.insert(input, output);
+    }
+}
+
+/// A named module-to-module transform applied to the Wasm binary before it
+/// is parsed (see [`crate::TranspileOptions::pretransform`]).
+///
+/// Clones share the cache.
+#[derive(Clone)]
+pub struct PreTransform {
+    name: String,
+    hook: Arc<Hook>,
+    cache: Arc<Mutex<Cache>>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl PreTransform {
+    /// Wrap `hook` under `name`, which identifies the transform in the cache
+    /// and in the generated code: include anything that changes its output,
+    /// such as command-line flags.
+    pub fn new(
+        name: impl Into<String>,
+        hook: impl Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            hook: Arc::new(hook),
+            cache: Arc::default(),
+            cache_dir: None,
+        }
+    }
+
+    /// Also cache results as files in `dir`, created if missing, so later
+    /// runs reuse them.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Name of the transform.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Transform `wasm`, or return the cached result for the same input.
+    ///
+    /// # Errors
+    /// Fails when the hook does, or when the cache directory cannot be
+    /// written.
+    pub fn apply(&self, wasm: &[u8]) -> Result<Arc<[u8]>> {
+        if let Some(cached) = self.memory().outputs.get(wasm) {
+            return Ok(cached.clone());
+        }
+        let path = self.cache_dir.as_ref().map(|dir| {
+            let key = self.cache_key(wasm);
+            dir.join(format!("{key:016x}-{}.wasm", wasm.len()))
+        });
+        let cached = path
+            .as_ref()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|file| self.cached_output(wasm, &file).map(Arc::from));
+        let output: Arc<[u8]> = match cached {
+            Some(cached) => cached,
+            None => {
+                let output = (self.hook)(wasm)
+                    .with_context(|| format!("pre-transform `{}` failed", self.name))?;
+                if let Some(path) = &path {
+                    let dir = path.parent().unwrap();
+                    self.write_cache_file(path, wasm, &output)
+                        .with_context(|| format!("failed to cache in {}", dir.display()))?;
+                }
+                output.into()
+            }
+        };
+        self.memory().insert(wasm, output.clone());
+        Ok(output)
+    }
+
+    /// What running on `input` and producing `output` is recorded as.
+    pub fn record(&self, input: &[u8], output: &[u8]) -> PreTransformRecord {
+        PreTransformRecord {
+            name: self.name.clone(),
+            input_hash: fingerprint(input),
+            output_hash: fingerprint(output),
+        }
+    }
+
+    /// Key of `wasm` in the cache directory: the name and the bytes.
+    fn cache_key(&self, wasm: &[u8]) -> u64 {
+        fnv1a(fnv1a(FNV_OFFSET, self.name.as_bytes()) ^ 0xff, wasm)
+    }
+
+    /// The in-memory cache. A panic while it was locked leaves it
+    /// consistent, as every update is a single insert or removal.
+    fn memory(&self) -> MutexGuard<'_, Cache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The output in cache file `file`, if it was written by this transform
+    /// for `wasm`. A file holds the name and the input, each after its
+    /// little-endian `u64` length, then the output.
+    fn cached_output<'f>(&self, wasm: &[u8], file: &'f [u8]) -> Option<&'f [u8]> {
+        let mut rest = file;
+        for expected in [self.name.as_bytes(), wasm] {
+            let (len, tail) = rest.split_first_chunk::<8>()?;
+            let len = usize::try_from(u64::from_le_bytes(*len)).ok()?;
+            if len != expected.len() || tail.get(..len)? != expected {
+                return None;
+            }
+            rest = &tail[len..];
+        }
+        Some(rest)
+    }
+
+    /// Write the cache file at `path` for `wasm` and `output` (see
+    /// [`Self::cached_output`]) to a temporary file first and rename it into
+    /// place, so a concurrent or interrupted run never leaves a partial file.
+    fn write_cache_file(&self, path: &Path, wasm: &[u8], output: &[u8]) -> std::io::Result<()> {
+        static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+        let dir = path.parent().unwrap();
+        std::fs::create_dir_all(dir)?;
+
+        let mut file = Vec::with_capacity(16 + self.name.len() + wasm.len() + output.len());
+        for part in [self.name.as_bytes(), wasm] {
+            file.extend_from_slice(&(part.len() as u64).to_le_bytes());
+            file.extend_from_slice(part);
+        }
+        file.extend_from_slice(output);
+
+        let temp = dir.join(format!(
+            ".{}.{}-{}.tmp",
+            path.file_name().unwrap().to_string_lossy(),
+            std::process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let written = std::fs::write(&temp, &file).and_then(|()| std::fs::rename(&temp, path));
+        if written.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        written
+    }
+}
+
+impl std::fmt::Debug for PreTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PreTransform")
+            .field("name", &self.name)
+            .field("cache_dir", &self.cache_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64-bit FNV-1a hash of `bytes`, identifying a binary in
+/// [`PreTransformRecord`] and the cache.
+pub fn fingerprint(bytes: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET, bytes)
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting(runs: &Arc<AtomicUsize>) -> PreTransform {
+        let runs = runs.clone();
+        PreTransform::new("reverse", move |wasm| {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Ok(wasm.iter().rev().copied().collect())
+        })
+    }
+
+    #[test]
+    fn results_are_cached_per_input() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let transform = counting(&runs);
+        assert_eq!(&*transform.apply(b"abc").unwrap(), b"cba");
+        assert_eq!(&*transform.clone().apply(b"abc").unwrap(), b"cba");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(&*transform.apply(b"ab").unwrap(), b"ba");
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn the_cache_dir_outlives_the_transform() {
+        let dir = std::env::temp_dir().join(format!("herkos-pretransform-{}", std::process::id()));
+        let runs = Arc::new(AtomicUsize::new(0));
+        counting(&runs).with_cache_dir(&dir).apply(b"abc").unwrap();
+        let cached = counting(&runs).with_cache_dir(&dir).apply(b"abc").unwrap();
+        assert_eq!(&*cached, b"cba");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cache_files_for_other_inputs_are_misses() {
+        let dir =
+            std::env::temp_dir().join(format!("herkos-pretransform-miss-{}", std::process::id()));
+        let runs = Arc::new(AtomicUsize::new(0));
+        let transform = counting(&runs).with_cache_dir(&dir);
+        // A file at the input's path written for another input (a colliding
+        // key) or truncated
+        let path = dir.join(format!("{:016x}-3.wasm", transform.cache_key(b"abc")));
+        transform.write_cache_file(&path, b"xyz", b"zyx").unwrap();
+        let foreign = std::fs::read(&path).unwrap();
+        for file in [&foreign[..], &foreign[..12]] {
+            std::fs::write(&path, file).unwrap();
+            let fresh = counting(&runs).with_cache_dir(&dir);
+            assert_eq!(&*fresh.apply(b"abc").unwrap(), b"cba");
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        // The miss replaced the file, and no temporary file is left over
+        assert_eq!(
+            &*counting(&runs).with_cache_dir(&dir).apply(b"abc").unwrap(),
+            b"cba"
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_memory_cache_evicts_the_oldest_input() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let transform = counting(&runs);
+        for i in 0..=MEMORY_CACHE_ENTRIES {
+            transform.apply(&i.to_le_bytes()).unwrap();
+        }
+        assert_eq!(transform.memory().outputs.len(), MEMORY_CACHE_ENTRIES);
+        transform
+            .apply(&MEMORY_CACHE_ENTRIES.to_le_bytes())
+            .unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), MEMORY_CACHE_ENTRIES + 1);
+        transform.apply(&0usize.to_le_bytes()).unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), MEMORY_CACHE_ENTRIES + 2);
+    }
+
+    #[test]
+    fn a_poisoned_cache_still_serves() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let transform = counting(&runs);
+        transform.apply(b"abc").unwrap();
+        let cache = transform.cache.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = cache.lock().unwrap();
+            panic!("poison the cache");
+        })
+        .join();
+        assert!(transform.cache.is_poisoned());
+        assert_eq!(&*transform.apply(b"abc").unwrap(), b"cba");
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn failures_name_the_transform() {
+        let transform = PreTransform::new("wasm-opt -Oz", |_| anyhow::bail!("not installed"));
+        let err = transform.apply(b"").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "pre-transform `wasm-opt -Oz` failed: not installed"
+        );
+    }
+
+    #[test]
+    fn fingerprint_is_fnv1a() {
+        assert_eq!(fingerprint(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fingerprint(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
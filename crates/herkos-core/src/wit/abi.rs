@@ -0,0 +1,282 @@
+//! Canonical ABI layout: how WIT types flatten to core values and how they
+//! are laid out in linear memory.
+
+use super::{TypeDefKind, WitFunction, WitType, WitWorld};
+use crate::ir::WasmType;
+
+/// Most core parameters a function may take before the canonical ABI spills
+/// them to memory.
+pub const MAX_FLAT_PARAMS: usize = 16;
+
+/// Most core results a function may return before the canonical ABI returns
+/// a pointer to them instead.
+pub const MAX_FLAT_RESULTS: usize = 1;
+
+/// Memory layout of a variant-like type (`option`, `result`, `enum`,
+/// `variant`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct VariantLayout {
+    /// Size of the discriminant in bytes (1, 2 or 4).
+    pub disc_size: u32,
+    /// Offset of the payload from the start of the value.
+    pub payload_offset: u32,
+    /// Total size, including trailing padding.
+    pub size: u32,
+    /// Alignment.
+    pub align: u32,
+}
+
+impl WitWorld {
+    /// Core value types `ty` flattens to.
+    pub fn flatten(&self, ty: &WitType) -> Vec<WasmType> {
+        match ty {
+            WitType::Bool
+            | WitType::S8
+            | WitType::U8
+            | WitType::S16
+            | WitType::U16
+            | WitType::S32
+            | WitType::U32
+            | WitType::Char => vec![WasmType::I32],
+            WitType::S64 | WitType::U64 => vec![WasmType::I64],
+            WitType::F32 => vec![WasmType::F32],
+            WitType::F64 => vec![WasmType::F64],
+            WitType::String | WitType::List(_) => vec![WasmType::I32, WasmType::I32],
+            WitType::Tuple(items) => items.iter().flat_map(|t| self.flatten(t)).collect(),
+            WitType::Named(name) => match self.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => {
+                    fields.iter().flat_map(|f| self.flatten(&f.ty)).collect()
+                }
+                _ => self.flatten_variant(ty),
+            },
+            WitType::Option(_) | WitType::Result { .. } => self.flatten_variant(ty),
+        }
+    }
+
+    /// Discriminant followed by the position-wise join of all payloads.
+    fn flatten_variant(&self, ty: &WitType) -> Vec<WasmType> {
+        let mut flat = vec![WasmType::I32];
+        let mut joined: Vec<WasmType> = Vec::new();
+        for payload in self
+            .variant_payloads(ty)
+            .unwrap_or_default()
+            .iter()
+            .flatten()
+        {
+            for (i, core) in self.flatten(payload).into_iter().enumerate() {
+                match joined.get_mut(i) {
+                    Some(slot) => *slot = join(*slot, core),
+                    None => joined.push(core),
+                }
+            }
+        }
+        flat.extend(joined);
+        flat
+    }
+
+    /// Flattened core parameters of a function.
+    pub(crate) fn flatten_params(&self, func: &WitFunction) -> Vec<WasmType> {
+        func.params
+            .iter()
+            .flat_map(|p| self.flatten(&p.ty))
+            .collect()
+    }
+
+    /// Flattened core results of a function (before the return-pointer rule).
+    pub(crate) fn flatten_results(&self, func: &WitFunction) -> Vec<WasmType> {
+        func.result
+            .as_ref()
+            .map(|ty| self.flatten(ty))
+            .unwrap_or_default()
+    }
+
+    /// Payload types of a variant-like type, one entry per case, or `None`
+    /// for types that are not variants.
+    pub(crate) fn variant_payloads(&self, ty: &WitType) -> Option<Vec<Option<WitType>>> {
+        match ty {
+            WitType::Option(some) => Some(vec![None, Some((**some).clone())]),
+            WitType::Result { ok, err } => {
+                Some(vec![ok.as_deref().cloned(), err.as_deref().cloned()])
+            }
+            WitType::Named(name) => match self.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Enum(cases)) => Some(vec![None; cases.len()]),
+                Some(TypeDefKind::Variant(cases)) => {
+                    Some(cases.iter().map(|c| c.ty.clone()).collect())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Memory layout of a variant-like type.
+    pub(crate) fn variant_layout(&self, payloads: &[Option<WitType>]) -> VariantLayout {
+        let disc_size = discriminant_size(payloads.len());
+        let (mut max_size, mut max_align) = (0, 1);
+        for payload in payloads.iter().flatten() {
+            let (size, align) = self.size_align(payload);
+            max_size = max_size.max(size);
+            max_align = max_align.max(align);
+        }
+        let payload_offset = align_to(disc_size, max_align);
+        let align = disc_size.max(max_align);
+        VariantLayout {
+            disc_size,
+            payload_offset,
+            size: align_to(payload_offset + max_size, align),
+            align,
+        }
+    }
+
+    /// Size and alignment of `ty` in linear memory.
+    pub fn size_align(&self, ty: &WitType) -> (u32, u32) {
+        match ty {
+            WitType::Bool | WitType::S8 | WitType::U8 => (1, 1),
+            WitType::S16 | WitType::U16 => (2, 2),
+            WitType::S32 | WitType::U32 | WitType::F32 | WitType::Char => (4, 4),
+            WitType::S64 | WitType::U64 | WitType::F64 => (8, 8),
+            WitType::String | WitType::List(_) => (8, 4),
+            WitType::Tuple(items) => self.record_size_align(items.iter()),
+            WitType::Named(name) => match self.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => {
+                    self.record_size_align(fields.iter().map(|f| &f.ty))
+                }
+                _ => {
+                    let layout =
+                        self.variant_layout(&self.variant_payloads(ty).unwrap_or_default());
+                    (layout.size, layout.align)
+                }
+            },
+            WitType::Option(_) | WitType::Result { .. } => {
+                let layout = self.variant_layout(&self.variant_payloads(ty).unwrap_or_default());
+                (layout.size, layout.align)
+            }
+        }
+    }
+
+    /// Byte offsets of the fields of a record or tuple.
+    pub(crate) fn field_offsets<'a>(&self, fields: impl Iterator<Item = &'a WitType>) -> Vec<u32> {
+        let mut offset = 0;
+        fields
+            .map(|ty| {
+                let (size, align) = self.size_align(ty);
+                offset = align_to(offset, align);
+                let at = offset;
+                offset += size;
+                at
+            })
+            .collect()
+    }
+
+    fn record_size_align<'a>(&self, fields: impl Iterator<Item = &'a WitType>) -> (u32, u32) {
+        let (mut size, mut max_align) = (0, 1);
+        for ty in fields {
+            let (field_size, align) = self.size_align(ty);
+            size = align_to(size, align) + field_size;
+            max_align = max_align.max(align);
+        }
+        (align_to(size, max_align), max_align)
+    }
+
+    /// Whether lifting or lowering `ty` touches linear memory.
+    pub(crate) fn uses_memory(&self, ty: &WitType) -> bool {
+        match ty {
+            WitType::String | WitType::List(_) => true,
+            WitType::Option(inner) => self.uses_memory(inner),
+            WitType::Result { ok, err } => ok.iter().chain(err.iter()).any(|t| self.uses_memory(t)),
+            WitType::Tuple(items) => items.iter().any(|t| self.uses_memory(t)),
+            WitType::Named(name) => match self.typedef(name).map(|d| &d.kind) {
+                Some(TypeDefKind::Record(fields)) => fields.iter().any(|f| self.uses_memory(&f.ty)),
+                Some(TypeDefKind::Variant(cases)) => cases
+                    .iter()
+                    .filter_map(|c| c.ty.as_ref())
+                    .any(|t| self.uses_memory(t)),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Core type that can carry both `a` and `b` in one flattened slot.
+pub(crate) fn join(a: WasmType, b: WasmType) -> WasmType {
+    match (a, b) {
+        _ if a == b => a,
+        (WasmType::I32, WasmType::F32) | (WasmType::F32, WasmType::I32) => WasmType::I32,
+        _ => WasmType::I64,
+    }
+}
+
+/// Size of a discriminant able to number `cases` cases.
+pub(crate) fn discriminant_size(cases: usize) -> u32 {
+    if cases <= 1 << 8 {
+        1
+    } else if cases <= 1 << 16 {
+        2
+    } else {
+        4
+    }
+}
+
+pub(crate) fn align_to(offset: u32, align: u32) -> u32 {
+    offset.div_ceil(align) * align
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world() -> WitWorld {
+        WitWorld::parse(
+            r#"world w {
+                record point { x: s32, y: s32 }
+                record mixed { a: u8, b: u64, c: u16 }
+                variant shape { circle(f32), rect(point), empty }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn flattening_joins_variant_payloads() {
+        let w = world();
+        let shape = WitType::Named("shape".to_string());
+        // circle: [f32], rect: [i32, i32] → slots [i32 (f32 ⊔ i32), i32].
+        assert_eq!(
+            w.flatten(&shape),
+            vec![WasmType::I32, WasmType::I32, WasmType::I32]
+        );
+        let opt = WitType::Option(Box::new(WitType::F64));
+        assert_eq!(w.flatten(&opt), vec![WasmType::I32, WasmType::F64]);
+        let res = WitType::Result {
+            ok: Some(Box::new(WitType::U32)),
+            err: Some(Box::new(WitType::F32)),
+        };
+        assert_eq!(w.flatten(&res), vec![WasmType::I32, WasmType::I32]);
+        let res = WitType::Result {
+            ok: Some(Box::new(WitType::F64)),
+            err: Some(Box::new(WitType::S32)),
+        };
+        assert_eq!(w.flatten(&res), vec![WasmType::I32, WasmType::I64]);
+    }
+
+    #[test]
+    fn sizes_follow_natural_alignment() {
+        let w = world();
+        assert_eq!(w.size_align(&WitType::Named("point".into())), (8, 4));
+        assert_eq!(w.size_align(&WitType::Named("mixed".into())), (24, 8));
+        assert_eq!(w.size_align(&WitType::String), (8, 4));
+        assert_eq!(w.size_align(&WitType::Named("shape".into())), (12, 4));
+        let opt = WitType::Option(Box::new(WitType::U8));
+        assert_eq!(w.size_align(&opt), (2, 1));
+        let res = WitType::Result {
+            ok: None,
+            err: None,
+        };
+        assert_eq!(w.size_align(&res), (1, 1));
+        assert_eq!(
+            w.field_offsets([WitType::U8, WitType::U64, WitType::U16].iter()),
+            vec![0, 8, 16]
+        );
+    }
+}
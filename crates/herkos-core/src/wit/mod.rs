@@ -0,0 +1,501 @@
+//! WIT-described exports and imports.
+//!
+//! Guests built with `wit-bindgen` speak the component model's canonical ABI:
+//! a WIT `func(name: string) -> list<u8>` becomes a core export taking
+//! `(ptr, len)` and returning a pointer to a `(ptr, len)` pair. Given the
+//! world the guest was built for, codegen emits wrappers that lift and lower
+//! WIT values over `IsolatedMemory`:
+//!
+//! ```text
+//! package example:app;
+//!
+//! world app {
+//!     record point { x: s32, y: s32 }
+//!     enum level { debug, info, error }
+//!
+//!     import log: func(level: level, code: u32);
+//!     export greet: func(name: string) -> string;
+//!     export centroid: func(points: list<point>) -> option<point>;
+//! }
+//! ```
+//!
+//! yields `fn call_greet(&mut self, name: &str) -> WasmResult<cabi::String>`,
+//! `fn call_centroid(&mut self, points: &[Point]) -> WasmResult<Option<Point>>`,
+//! and a `WitImports` trait with `fn log(&mut self, level: Level, code: u32)`
+//! that implements `ModuleHostTrait` for every type implementing it.
+//!
+//! Supported: the scalar types, `char`, `string`, `list`, `option`, `result`,
+//! `tuple`, `record`, `enum`, `variant` and `type` aliases, declared at world
+//! level or inside interfaces of the same file (`import iface;` /
+//! `export iface;`). `flags`, resources, `use` and multiple worlds are not.
+//!
+//! Limits follow from the host calling convention: host imports get no
+//! access to guest memory, so imported functions may only take and return
+//! values that flatten to core scalars (no `string` or `list`, at most one
+//! result value). Exports may flatten to at most 16 core parameters.
+
+mod abi;
+mod parser;
+
+pub use abi::{MAX_FLAT_PARAMS, MAX_FLAT_RESULTS};
+
+use crate::ir::{ModuleInfo, WasmType};
+use anyhow::{bail, Context, Result};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+
+/// A parsed WIT world.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WitWorld {
+    /// World name.
+    pub name: String,
+    /// Named types (records, enums, variants), from the world and all
+    /// interfaces it references, in declaration order.
+    pub types: Vec<TypeDef>,
+    /// Exported functions, in declaration order.
+    pub exports: Vec<WitFunction>,
+    /// Imported functions, in declaration order.
+    pub imports: Vec<WitFunction>,
+}
+
+/// A named WIT type definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDef {
+    /// WIT (kebab-case) name.
+    pub name: String,
+    /// The definition.
+    pub kind: TypeDefKind,
+}
+
+/// Body of a named WIT type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeDefKind {
+    /// `record name { field: type, ... }`
+    Record(Vec<WitField>),
+    /// `enum name { case, ... }`
+    Enum(Vec<String>),
+    /// `variant name { case(type), case, ... }`
+    Variant(Vec<WitCase>),
+}
+
+/// A record field or function parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitField {
+    /// WIT (kebab-case) name.
+    pub name: String,
+    /// Field type.
+    pub ty: WitType,
+}
+
+/// A variant case with an optional payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitCase {
+    /// WIT (kebab-case) name.
+    pub name: String,
+    /// Payload type, if any.
+    pub ty: Option<WitType>,
+}
+
+/// A WIT value type. Aliases are resolved during parsing, so `Named` always
+/// refers to an entry of [`WitWorld::types`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum WitType {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    F32,
+    F64,
+    Char,
+    String,
+    List(Box<WitType>),
+    Option(Box<WitType>),
+    Result {
+        ok: Option<Box<WitType>>,
+        err: Option<Box<WitType>>,
+    },
+    Tuple(Vec<WitType>),
+    Named(String),
+}
+
+/// A WIT function, together with the core import/export it maps to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitFunction {
+    /// WIT (kebab-case) name.
+    pub name: String,
+    /// Interface the function belongs to (`None` for world-level functions).
+    pub interface: Option<String>,
+    /// Parameters, in order.
+    pub params: Vec<WitField>,
+    /// Result type, if any.
+    pub result: Option<WitType>,
+    /// Core module name (imports only; `$root` for world-level functions).
+    pub core_module: String,
+    /// Core function name (`iface#name` for interface exports).
+    pub core_name: String,
+}
+
+impl WitFunction {
+    /// Rust name of the function: snake case, prefixed with the interface.
+    pub fn rust_name(&self) -> String {
+        rust_field_name(&self.qualified_name())
+    }
+
+    /// Name of the generated wrapper method for an export.
+    pub fn export_method(&self) -> String {
+        format!("call_{}", self.qualified_name().to_snake_case())
+    }
+
+    fn qualified_name(&self) -> String {
+        match &self.interface {
+            Some(iface) => format!("{}-{}", iface, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Item names emitted by codegen that WIT types must not shadow.
+const RESERVED_TYPE_NAMES: &[&str] = &[
+    "WasmModule",
+    "Globals",
+    "Env",
+    "ModuleHostTrait",
+    "WitImports",
+    "Module",
+    "LibraryModule",
+    "IsolatedMemory",
+    "Table",
+    "FuncRef",
+    "FuncKind",
+    "FuncSymbol",
+    "Scratch",
+    "NoHost",
+    "WasmResult",
+    "WasmTrap",
+    "Option",
+    "Result",
+];
+
+/// Rust keywords that must be escaped when used as identifiers.
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while",
+];
+
+/// Rust identifier for a WIT field, parameter or function name.
+pub fn rust_field_name(wit_name: &str) -> String {
+    let name = wit_name.to_snake_case();
+    if RUST_KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+/// Rust identifier for a WIT type or case name.
+pub fn rust_type_name(wit_name: &str) -> String {
+    wit_name.to_upper_camel_case()
+}
+
+impl WitWorld {
+    /// Parse a `.wit` file containing exactly one world.
+    pub fn parse(text: &str) -> Result<Self> {
+        parser::parse(text)
+    }
+
+    /// Look up a named type definition.
+    pub fn typedef(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|t| t.name == name)
+    }
+
+    /// Check the world against the module's real exports and imports.
+    pub fn validate(&self, info: &ModuleInfo) -> Result<()> {
+        let mut type_names: Vec<String> = Vec::new();
+        for def in &self.types {
+            let name = rust_type_name(&def.name);
+            if RESERVED_TYPE_NAMES.contains(&name.as_str()) {
+                bail!(
+                    "WIT type `{}` collides with generated item `{}`",
+                    def.name,
+                    name
+                );
+            }
+            if type_names.contains(&name) {
+                bail!("WIT types map to the same Rust name `{}`", name);
+            }
+            type_names.push(name);
+        }
+
+        let mut methods: Vec<String> = Vec::new();
+        for func in &self.exports {
+            self.validate_export(func, info)
+                .with_context(|| format!("invalid WIT export `{}`", func.core_name))?;
+            let method = func.export_method();
+            if methods.contains(&method) {
+                bail!("WIT exports map to the same method `{}`", method);
+            }
+            methods.push(method);
+        }
+
+        if self.imports.is_empty() {
+            return Ok(());
+        }
+        let mut names: Vec<String> = Vec::new();
+        for func in &self.imports {
+            self.validate_import(func, info).with_context(|| {
+                format!(
+                    "invalid WIT import `{}.{}`",
+                    func.core_module, func.core_name
+                )
+            })?;
+            let name = func.rust_name();
+            if names.contains(&name) {
+                bail!("WIT imports map to the same trait method `{}`", name);
+            }
+            names.push(name);
+        }
+        // `WitImports` implements `ModuleHostTrait` wholesale, so it must
+        // account for every core import.
+        for imp in &info.func_imports {
+            if self.import_for(&imp.module_name, &imp.func_name).is_none() {
+                bail!(
+                    "core import `{}.{}` is not described by the WIT world",
+                    imp.module_name,
+                    imp.func_name
+                );
+            }
+        }
+        if let Some(g) = info.imported_globals.first() {
+            bail!(
+                "WIT imports cannot describe imported global `{}.{}`",
+                g.module_name,
+                g.name
+            );
+        }
+        Ok(())
+    }
+
+    /// The WIT import bound to a core import, if any.
+    pub fn import_for(&self, module: &str, name: &str) -> Option<&WitFunction> {
+        self.imports
+            .iter()
+            .find(|f| f.core_module == module && f.core_name == name)
+    }
+
+    fn validate_export(&self, func: &WitFunction, info: &ModuleInfo) -> Result<()> {
+        let export = info
+            .func_exports
+            .iter()
+            .find(|e| e.name == func.core_name)
+            .context("module has no such function export")?;
+        let ir_func = info
+            .ir_function(export.func_index)
+            .context("export refers to a missing function")?;
+
+        let params = self.flatten_params(func);
+        if params.len() > MAX_FLAT_PARAMS {
+            bail!(
+                "parameters flatten to {} core values; more than {} is not supported",
+                params.len(),
+                MAX_FLAT_PARAMS
+            );
+        }
+        let results = self.flatten_results(func);
+        let retptr = results.len() > MAX_FLAT_RESULTS;
+        let expected_ret = if retptr {
+            Some(WasmType::I32)
+        } else {
+            results.first().copied()
+        };
+        let actual: Vec<WasmType> = ir_func.params.iter().map(|(_, ty)| *ty).collect();
+        if actual != params || ir_func.results[..] != *expected_ret.as_slice() {
+            bail!(
+                "WIT signature lowers to ({}) -> ({}), but the export is ({}) -> ({})",
+                format_types(&params),
+                format_types(expected_ret.as_slice()),
+                format_types(&actual),
+                format_types(&ir_func.results)
+            );
+        }
+
+        let has_memory = info.has_memory || info.has_memory_import;
+        let params_use_memory = func.params.iter().any(|p| self.uses_memory(&p.ty));
+        if (retptr || params_use_memory) && !has_memory {
+            bail!("values passed through memory require the module to have a linear memory");
+        }
+        if params_use_memory {
+            let realloc = self
+                .core_export(info, "cabi_realloc")
+                .context("`string` and `list` parameters require a `cabi_realloc` export")?;
+            let (params, ret) = realloc;
+            if params != [WasmType::I32; 4] || ret != [WasmType::I32] {
+                bail!("`cabi_realloc` must have type (i32, i32, i32, i32) -> i32");
+            }
+        }
+        if let Some((params, ret)) =
+            self.core_export(info, &format!("cabi_post_{}", func.core_name))
+        {
+            let expected = if retptr { vec![WasmType::I32] } else { results };
+            if params != expected || !ret.is_empty() {
+                bail!(
+                    "`cabi_post_{}` must have type ({}) -> ()",
+                    func.core_name,
+                    format_types(&expected)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_import(&self, func: &WitFunction, info: &ModuleInfo) -> Result<()> {
+        for ty in func.params.iter().map(|p| &p.ty).chain(&func.result) {
+            if self.uses_memory(ty) {
+                bail!("host imports cannot exchange `string` or `list` values (no guest memory access)");
+            }
+        }
+        let params = self.flatten_params(func);
+        if params.len() > MAX_FLAT_PARAMS {
+            bail!(
+                "parameters flatten to {} core values; more than {} is not supported",
+                params.len(),
+                MAX_FLAT_PARAMS
+            );
+        }
+        let results = self.flatten_results(func);
+        if results.len() > MAX_FLAT_RESULTS {
+            bail!("results returned through memory are not supported for host imports");
+        }
+        // Imports the guest never calls are dropped from the core module.
+        let Some(imp) = info
+            .func_imports
+            .iter()
+            .find(|i| *i.module_name == *func.core_module && *i.func_name == *func.core_name)
+        else {
+            return Ok(());
+        };
+        if *imp.params != *params || *imp.results != *results {
+            bail!(
+                "WIT signature lowers to ({}) -> ({}), but the import is ({}) -> ({})",
+                format_types(&params),
+                format_types(&results),
+                format_types(&imp.params),
+                format_types(&imp.results)
+            );
+        }
+        Ok(())
+    }
+
+    /// Core signature of a local function export, if present.
+    fn core_export(&self, info: &ModuleInfo, name: &str) -> Option<(Vec<WasmType>, Vec<WasmType>)> {
+        let export = info.func_exports.iter().find(|e| e.name == name)?;
+        let func = info.ir_function(export.func_index)?;
+        Some((
+            func.params.iter().map(|(_, ty)| *ty).collect(),
+            func.results.clone(),
+        ))
+    }
+}
+
+fn format_types(types: &[WasmType]) -> String {
+    types
+        .iter()
+        .map(|ty| crate::codegen::types::wasm_type_to_rust(ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::build_module_info;
+    use crate::parser::parse_wasm;
+    use crate::TranspileOptions;
+
+    fn validate(wat: &str, wit: &str) -> Result<()> {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &TranspileOptions::default()).unwrap();
+        WitWorld::parse(wit).unwrap().validate(&info)
+    }
+
+    #[test]
+    fn accepts_matching_exports_and_imports() {
+        validate(
+            r#"(module
+                (import "$root" "log" (func (param i32 i32)))
+                (memory 1 1)
+                (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32) i32.const 0)
+                (func (export "greet") (param i32 i32) (result i32) i32.const 0)
+                (func (export "cabi_post_greet") (param i32)))"#,
+            r#"world w {
+                enum level { info, error }
+                import log: func(level: level, code: u32);
+                export greet: func(name: string) -> string;
+            }"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn rejects_signature_mismatch() {
+        let err = validate(
+            r#"(module (func (export "add") (param i32) (result i32) local.get 0))"#,
+            "world w { export add: func(a: u32, b: u32) -> u32; }",
+        )
+        .unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("invalid WIT export `add`"), "{msg}");
+        assert!(msg.contains("lowers to (i32, i32) -> (i32)"), "{msg}");
+    }
+
+    #[test]
+    fn string_params_require_realloc() {
+        let err = validate(
+            r#"(module (memory 1 1) (func (export "len") (param i32 i32) (result i32) i32.const 0))"#,
+            "world w { export len: func(s: string) -> u32; }",
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("cabi_realloc"));
+    }
+
+    #[test]
+    fn imports_must_be_memory_free_and_complete() {
+        let err = validate(
+            r#"(module (import "$root" "print" (func (param i32 i32))))"#,
+            "world w { import print: func(msg: string); }",
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("no guest memory access"));
+
+        let err = validate(
+            r#"(module
+                (import "$root" "tick" (func))
+                (import "env" "other" (func)))"#,
+            "world w { import tick: func(); }",
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("`env.other` is not described"));
+    }
+
+    #[test]
+    fn rust_names_are_escaped() {
+        assert_eq!(rust_field_name("type"), "type_");
+        assert_eq!(rust_field_name("get-name"), "get_name");
+        assert_eq!(rust_type_name("http-request"), "HttpRequest");
+        let func = WitFunction {
+            name: "get-name".to_string(),
+            interface: Some("user-db".to_string()),
+            params: vec![],
+            result: None,
+            core_module: String::new(),
+            core_name: String::new(),
+        };
+        assert_eq!(func.export_method(), "call_user_db_get_name");
+    }
+}
@@ -0,0 +1,739 @@
+//! Parser for the supported WIT subset.
+
+use super::{TypeDef, TypeDefKind, WitCase, WitField, WitFunction, WitType, WitWorld};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+
+/// Core module name of world-level imports.
+const ROOT_MODULE: &str = "$root";
+
+/// Alias chains longer than this are rejected as cyclic.
+const MAX_ALIAS_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or other word (`get-name`, `u32`, `0.1.0`).
+    Word(String),
+    /// Single punctuation character.
+    Punct(char),
+    /// `->`
+    Arrow,
+}
+
+/// A token with the 1-based line it starts on.
+type Spanned = (Token, usize);
+
+fn tokenize(text: &str) -> Result<Vec<Spanned>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\n' => {
+                line += 1;
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '/' => {
+                chars.next();
+                match chars.next() {
+                    Some('/') => {
+                        while chars.peek().is_some_and(|&c| c != '\n') {
+                            chars.next();
+                        }
+                    }
+                    Some('*') => {
+                        let mut prev = ' ';
+                        loop {
+                            match chars.next() {
+                                Some('/') if prev == '*' => break,
+                                Some(c) => {
+                                    if c == '\n' {
+                                        line += 1;
+                                    }
+                                    prev = c;
+                                }
+                                None => bail!("line {}: unterminated block comment", line),
+                            }
+                        }
+                    }
+                    _ => tokens.push((Token::Punct('/'), line)),
+                }
+            }
+            '-' if text_peek_arrow(&chars) => {
+                chars.next();
+                chars.next();
+                tokens.push((Token::Arrow, line));
+            }
+            '{' | '}' | '(' | ')' | '<' | '>' | ',' | ':' | ';' | '=' | '@' | '.' => {
+                chars.next();
+                tokens.push((Token::Punct(c), line));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '-' || c == '_' || c == '%' {
+                        if c == '-' && text_peek_arrow(&chars) {
+                            break;
+                        }
+                        word.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if word.is_empty() {
+                    bail!("line {}: unexpected character `{}`", line, c);
+                }
+                tokens.push((Token::Word(word), line));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Whether the iterator is positioned at `->`.
+fn text_peek_arrow(chars: &std::iter::Peekable<std::str::Chars<'_>>) -> bool {
+    let mut ahead = chars.clone();
+    ahead.next() == Some('-') && ahead.next() == Some('>')
+}
+
+/// A function declared inside an interface or world.
+#[derive(Clone)]
+struct FuncDecl {
+    name: String,
+    params: Vec<WitField>,
+    result: Option<WitType>,
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// Named types, in declaration order.
+    types: Vec<TypeDef>,
+    aliases: HashMap<String, WitType>,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(t, _)| t.clone())
+            .context("unexpected end of file")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<()> {
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            other => bail!("expected `{}`, found {}", c, describe(&other)),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        match self.next()? {
+            Token::Word(w) if w == keyword => Ok(()),
+            other => bail!("expected `{}`, found {}", keyword, describe(&other)),
+        }
+    }
+
+    /// An identifier, with the `%` keyword escape removed.
+    fn ident(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Word(w) => {
+                let name = w.strip_prefix('%').unwrap_or(&w);
+                let valid = name.split('-').all(|part| {
+                    part.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                        && part.chars().all(|c| c.is_ascii_alphanumeric())
+                });
+                if !valid {
+                    bail!("`{}` is not a valid WIT identifier", w);
+                }
+                Ok(name.to_string())
+            }
+            other => bail!("expected an identifier, found {}", describe(&other)),
+        }
+    }
+
+    /// `package ns:name@version;`, returned as `ns:name` and `@version`.
+    fn package(&mut self) -> Result<(String, String)> {
+        let mut text = String::new();
+        loop {
+            match self.next()? {
+                Token::Punct(';') => break,
+                Token::Word(w) => text.push_str(&w),
+                Token::Punct(c) => text.push(c),
+                Token::Arrow => bail!("unexpected `->` in package name"),
+            }
+        }
+        let (name, version) = match text.split_once('@') {
+            Some((name, version)) => (name.to_string(), format!("@{}", version)),
+            None => (text, String::new()),
+        };
+        if !name.contains(':') {
+            bail!(
+                "package name `{}` must have the form `namespace:name`",
+                name
+            );
+        }
+        Ok((name, version))
+    }
+
+    /// Try to parse a type definition; returns `false` if the next item is
+    /// not one.
+    fn typedef(&mut self) -> Result<bool> {
+        let Some(Token::Word(keyword)) = self.peek().cloned() else {
+            return Ok(false);
+        };
+        let kind = match keyword.as_str() {
+            "record" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let fields = self.braced_list(|p| {
+                    let name = p.ident()?;
+                    p.expect_punct(':')?;
+                    Ok(WitField { name, ty: p.ty()? })
+                })?;
+                if fields.is_empty() {
+                    bail!("record `{}` has no fields", name);
+                }
+                check_unique(fields.iter().map(|f| &f.name), "field")?;
+                (name, TypeDefKind::Record(fields))
+            }
+            "enum" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let cases = self.braced_list(|p| p.ident())?;
+                if cases.is_empty() {
+                    bail!("enum `{}` has no cases", name);
+                }
+                check_unique(cases.iter(), "case")?;
+                (name, TypeDefKind::Enum(cases))
+            }
+            "variant" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let cases = self.braced_list(|p| {
+                    let name = p.ident()?;
+                    let ty = if p.eat_punct('(') {
+                        let ty = p.ty()?;
+                        p.expect_punct(')')?;
+                        Some(ty)
+                    } else {
+                        None
+                    };
+                    Ok(WitCase { name, ty })
+                })?;
+                if cases.is_empty() {
+                    bail!("variant `{}` has no cases", name);
+                }
+                check_unique(cases.iter().map(|c| &c.name), "case")?;
+                (name, TypeDefKind::Variant(cases))
+            }
+            "type" => {
+                self.pos += 1;
+                let name = self.ident()?;
+                self.expect_punct('=')?;
+                let ty = self.ty()?;
+                self.expect_punct(';')?;
+                self.define(&name)?;
+                self.aliases.insert(name, ty);
+                return Ok(true);
+            }
+            "flags" | "resource" | "use" => bail!("`{}` is not supported", keyword),
+            _ => return Ok(false),
+        };
+        self.define(&kind.0)?;
+        self.types.push(TypeDef {
+            name: kind.0,
+            kind: kind.1,
+        });
+        Ok(true)
+    }
+
+    fn define(&self, name: &str) -> Result<()> {
+        if self.aliases.contains_key(name) || self.types.iter().any(|t| t.name == name) {
+            bail!("duplicate type `{}`", name);
+        }
+        Ok(())
+    }
+
+    /// `{ item, item, ... }` with an optional trailing comma.
+    fn braced_list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        self.expect_punct('{')?;
+        let mut items = Vec::new();
+        while !self.eat_punct('}') {
+            items.push(item(self)?);
+            if !self.eat_punct(',') {
+                self.expect_punct('}')?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn ty(&mut self) -> Result<WitType> {
+        let name = self.ident()?;
+        let ty = match name.as_str() {
+            "bool" => WitType::Bool,
+            "s8" => WitType::S8,
+            "u8" => WitType::U8,
+            "s16" => WitType::S16,
+            "u16" => WitType::U16,
+            "s32" => WitType::S32,
+            "u32" => WitType::U32,
+            "s64" => WitType::S64,
+            "u64" => WitType::U64,
+            "f32" | "float32" => WitType::F32,
+            "f64" | "float64" => WitType::F64,
+            "char" => WitType::Char,
+            "string" => WitType::String,
+            "list" => {
+                self.expect_punct('<')?;
+                let inner = self.ty()?;
+                self.expect_punct('>')?;
+                WitType::List(Box::new(inner))
+            }
+            "option" => {
+                self.expect_punct('<')?;
+                let inner = self.ty()?;
+                self.expect_punct('>')?;
+                WitType::Option(Box::new(inner))
+            }
+            "result" => {
+                let (mut ok, mut err) = (None, None);
+                if self.eat_punct('<') {
+                    if self.peek() == Some(&Token::Word("_".to_string())) {
+                        self.pos += 1;
+                    } else {
+                        ok = Some(Box::new(self.ty()?));
+                    }
+                    if self.eat_punct(',') {
+                        err = Some(Box::new(self.ty()?));
+                    }
+                    self.expect_punct('>')?;
+                }
+                WitType::Result { ok, err }
+            }
+            "tuple" => {
+                self.expect_punct('<')?;
+                let mut items = vec![self.ty()?];
+                while self.eat_punct(',') {
+                    items.push(self.ty()?);
+                }
+                self.expect_punct('>')?;
+                WitType::Tuple(items)
+            }
+            "borrow" | "own" | "future" | "stream" => bail!("`{}` is not supported", name),
+            _ => WitType::Named(name),
+        };
+        Ok(ty)
+    }
+
+    /// `func(params) -> result` after the `name:` part.
+    fn func(&mut self, name: String) -> Result<FuncDecl> {
+        self.expect_keyword("func")?;
+        self.expect_punct('(')?;
+        let mut params = Vec::new();
+        while !self.eat_punct(')') {
+            let name = self.ident()?;
+            self.expect_punct(':')?;
+            params.push(WitField {
+                name,
+                ty: self.ty()?,
+            });
+            if !self.eat_punct(',') {
+                self.expect_punct(')')?;
+                break;
+            }
+        }
+        check_unique(params.iter().map(|p| &p.name), "parameter")?;
+        let result = if self.peek() == Some(&Token::Arrow) {
+            self.pos += 1;
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        self.expect_punct(';')?;
+        Ok(FuncDecl {
+            name,
+            params,
+            result,
+        })
+    }
+
+    /// `interface name { ... }` after the keyword.
+    fn interface(&mut self) -> Result<(String, Vec<FuncDecl>)> {
+        let name = self.ident()?;
+        self.expect_punct('{')?;
+        let mut funcs = Vec::new();
+        while !self.eat_punct('}') {
+            if self.typedef()? {
+                continue;
+            }
+            let func = self.ident()?;
+            self.expect_punct(':')?;
+            funcs.push(self.func(func)?);
+        }
+        Ok((name, funcs))
+    }
+}
+
+/// Resolve aliases in `ty` and check that every name refers to a type.
+fn resolve(
+    ty: &mut WitType,
+    aliases: &HashMap<String, WitType>,
+    type_names: &[String],
+    depth: usize,
+) -> Result<()> {
+    match ty {
+        WitType::List(inner) | WitType::Option(inner) => resolve(inner, aliases, type_names, depth),
+        WitType::Result { ok, err } => ok
+            .iter_mut()
+            .chain(err.iter_mut())
+            .try_for_each(|t| resolve(t, aliases, type_names, depth)),
+        WitType::Tuple(items) => items
+            .iter_mut()
+            .try_for_each(|t| resolve(t, aliases, type_names, depth)),
+        WitType::Named(name) => {
+            if let Some(target) = aliases.get(name.as_str()) {
+                if depth >= MAX_ALIAS_DEPTH {
+                    bail!("type alias `{}` is cyclic", name);
+                }
+                *ty = target.clone();
+                return resolve(ty, aliases, type_names, depth + 1);
+            }
+            if !type_names.contains(name) {
+                bail!("unknown type `{}`", name);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// World item collected before interfaces are resolved.
+enum WorldItem {
+    Func { export: bool, decl: FuncDecl },
+    Interface { export: bool, name: String },
+}
+
+pub(super) fn parse(text: &str) -> Result<WitWorld> {
+    let mut p = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        types: Vec::new(),
+        aliases: HashMap::new(),
+    };
+    let mut package: Option<(String, String)> = None;
+    let mut interfaces: HashMap<String, Vec<FuncDecl>> = HashMap::new();
+    let mut world: Option<(String, Vec<WorldItem>)> = None;
+
+    while p.peek().is_some() {
+        let line = p.line();
+        parse_top_level(&mut p, &mut package, &mut interfaces, &mut world)
+            .with_context(|| format!("WIT line {}", line))?;
+    }
+
+    let (name, items) = world.context("WIT file declares no world")?;
+    let mut out = WitWorld {
+        name,
+        ..Default::default()
+    };
+    for item in items {
+        let (export, iface, decls) = match item {
+            WorldItem::Func { export, decl } => (export, None, vec![decl]),
+            WorldItem::Interface { export, name } => {
+                let decls = interfaces
+                    .get(&name)
+                    .cloned()
+                    .with_context(|| format!("unknown interface `{}`", name))?;
+                (export, Some(name), decls)
+            }
+        };
+        let core_module = match (&iface, &package) {
+            (None, _) => ROOT_MODULE.to_string(),
+            (Some(iface), Some((pkg, version))) => format!("{}/{}{}", pkg, iface, version),
+            (Some(iface), None) => iface.clone(),
+        };
+        for decl in decls {
+            let (core_module, core_name) = match (&iface, export) {
+                (Some(_), true) => (String::new(), format!("{}#{}", core_module, decl.name)),
+                (None, true) => (String::new(), decl.name.clone()),
+                (_, false) => (core_module.clone(), decl.name.clone()),
+            };
+            let func = WitFunction {
+                name: decl.name,
+                interface: iface.clone(),
+                params: decl.params,
+                result: decl.result,
+                core_module,
+                core_name,
+            };
+            if export {
+                out.exports.push(func);
+            } else {
+                out.imports.push(func);
+            }
+        }
+    }
+
+    let type_names: Vec<String> = p.types.iter().map(|t| t.name.clone()).collect();
+    for func in out.exports.iter_mut().chain(out.imports.iter_mut()) {
+        let name = func.core_name.clone();
+        for ty in func
+            .params
+            .iter_mut()
+            .map(|p| &mut p.ty)
+            .chain(func.result.iter_mut())
+        {
+            resolve(ty, &p.aliases, &type_names, 0)
+                .with_context(|| format!("in function `{}`", name))?;
+        }
+    }
+    for def in &mut p.types {
+        let fields: Vec<&mut WitType> = match &mut def.kind {
+            TypeDefKind::Record(fields) => fields.iter_mut().map(|f| &mut f.ty).collect(),
+            TypeDefKind::Variant(cases) => cases.iter_mut().filter_map(|c| c.ty.as_mut()).collect(),
+            TypeDefKind::Enum(_) => Vec::new(),
+        };
+        for ty in fields {
+            resolve(ty, &p.aliases, &type_names, 0)
+                .with_context(|| format!("in type `{}`", def.name))?;
+        }
+    }
+    out.types = p.types;
+    check_acyclic(&out)?;
+    Ok(out)
+}
+
+fn parse_top_level(
+    p: &mut Parser,
+    package: &mut Option<(String, String)>,
+    interfaces: &mut HashMap<String, Vec<FuncDecl>>,
+    world: &mut Option<(String, Vec<WorldItem>)>,
+) -> Result<()> {
+    let keyword = match p.next()? {
+        Token::Word(w) => w,
+        other => bail!(
+            "expected `package`, `interface` or `world`, found {}",
+            describe(&other)
+        ),
+    };
+    match keyword.as_str() {
+        "package" => {
+            if package.is_some() {
+                bail!("duplicate `package` declaration");
+            }
+            *package = Some(p.package()?);
+        }
+        "interface" => {
+            let (name, funcs) = p.interface()?;
+            if interfaces.insert(name.clone(), funcs).is_some() {
+                bail!("duplicate interface `{}`", name);
+            }
+        }
+        "world" => {
+            if world.is_some() {
+                bail!("only one world per file is supported");
+            }
+            let name = p.ident()?;
+            p.expect_punct('{')?;
+            let mut items = Vec::new();
+            while !p.eat_punct('}') {
+                if p.typedef()? {
+                    continue;
+                }
+                let export = match p.ident()?.as_str() {
+                    "export" => true,
+                    "import" => false,
+                    other => bail!("expected `import`, `export` or a type, found `{}`", other),
+                };
+                let name = p.ident()?;
+                if p.eat_punct(';') {
+                    items.push(WorldItem::Interface { export, name });
+                } else {
+                    p.expect_punct(':')?;
+                    if p.peek() == Some(&Token::Word("interface".to_string())) {
+                        bail!("inline interfaces are not supported");
+                    }
+                    items.push(WorldItem::Func {
+                        export,
+                        decl: p.func(name)?,
+                    });
+                }
+            }
+            *world = Some((name, items));
+        }
+        other => bail!(
+            "expected `package`, `interface` or `world`, found `{}`",
+            other
+        ),
+    }
+    Ok(())
+}
+
+/// Records and variants must not contain themselves (the canonical ABI has
+/// no indirection, so such types would have infinite size).
+fn check_acyclic(world: &WitWorld) -> Result<()> {
+    fn visit<'a>(world: &'a WitWorld, ty: &'a WitType, stack: &mut Vec<&'a str>) -> Result<()> {
+        match ty {
+            WitType::List(inner) | WitType::Option(inner) => visit(world, inner, stack),
+            WitType::Result { ok, err } => ok
+                .iter()
+                .chain(err.iter())
+                .try_for_each(|t| visit(world, t, stack)),
+            WitType::Tuple(items) => items.iter().try_for_each(|t| visit(world, t, stack)),
+            WitType::Named(name) => {
+                if stack.contains(&name.as_str()) {
+                    bail!("type `{}` contains itself", name);
+                }
+                let Some(def) = world.typedef(name) else {
+                    return Ok(());
+                };
+                stack.push(name);
+                let result = match &def.kind {
+                    TypeDefKind::Record(fields) => {
+                        fields.iter().try_for_each(|f| visit(world, &f.ty, stack))
+                    }
+                    TypeDefKind::Variant(cases) => cases
+                        .iter()
+                        .filter_map(|c| c.ty.as_ref())
+                        .try_for_each(|t| visit(world, t, stack)),
+                    TypeDefKind::Enum(_) => Ok(()),
+                };
+                stack.pop();
+                result
+            }
+            _ => Ok(()),
+        }
+    }
+    for def in &world.types {
+        visit(world, &WitType::Named(def.name.clone()), &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+fn check_unique<'a>(names: impl Iterator<Item = &'a String>, what: &str) -> Result<()> {
+    let mut seen: Vec<&String> = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            bail!("duplicate {} `{}`", what, name);
+        }
+        seen.push(name);
+    }
+    Ok(())
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(w) => format!("`{}`", w),
+        Token::Punct(c) => format!("`{}`", c),
+        Token::Arrow => "`->`".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_world_with_interfaces() {
+        let world = parse(
+            r#"
+            // Package version ends up in interface core names.
+            package example:app@0.2.0;
+
+            interface store {
+                type key = string;
+                /// Look a value up.
+                get: func(k: key) -> option<list<u8>>;
+            }
+
+            world app {
+                record point { x: s32, y: s32, }
+                variant shape { circle(f32), rect(tuple<point, point>), none }
+                import log: func(code: u32);
+                import store;
+                export store;
+                export area: func(s: shape) -> f64;
+                export %type: func() -> result<_, string>;
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(world.name, "app");
+        assert_eq!(world.types.len(), 2);
+        assert_eq!(world.imports.len(), 2);
+        assert_eq!(world.imports[0].core_module, "$root");
+        assert_eq!(world.imports[0].core_name, "log");
+        assert_eq!(world.imports[1].core_module, "example:app/store@0.2.0");
+        assert_eq!(world.imports[1].core_name, "get");
+        // The `key` alias resolves to `string`.
+        assert_eq!(world.imports[1].params[0].ty, WitType::String);
+
+        let names: Vec<&str> = world.exports.iter().map(|f| f.core_name.as_str()).collect();
+        assert_eq!(names, ["example:app/store@0.2.0#get", "area", "type"]);
+        assert_eq!(
+            world.exports[2].result,
+            Some(WitType::Result {
+                ok: None,
+                err: Some(Box::new(WitType::String)),
+            })
+        );
+    }
+
+    #[test]
+    fn import_name_of_interface_without_package() {
+        let world = parse("interface io { read: func() -> u8; } world w { import io; }").unwrap();
+        assert_eq!(world.imports[0].core_module, "io");
+        assert_eq!(world.imports[0].rust_name(), "io_read");
+    }
+
+    #[test]
+    fn errors_report_line_numbers() {
+        let err = parse("world w {\n    export f: func(x: widget);\n}").unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("unknown type `widget`"), "{msg}");
+
+        let err = parse("world w {\n  flags perms { read }\n}").unwrap_err();
+        let msg = format!("{:#}", err);
+        assert!(msg.contains("WIT line 1"), "{msg}");
+        assert!(msg.contains("`flags` is not supported"), "{msg}");
+    }
+
+    #[test]
+    fn rejects_recursive_and_duplicate_types() {
+        let err = parse("world w { record a { b: option<a> } }").unwrap_err();
+        assert!(format!("{:#}", err).contains("contains itself"));
+        let err = parse("world w { enum e { x } type e = u8; }").unwrap_err();
+        assert!(format!("{:#}", err).contains("duplicate type `e`"));
+        let err = parse("world a {} world b {}").unwrap_err();
+        assert!(format!("{:#}", err).contains("only one world"));
+    }
+}
@@ -5,14 +5,18 @@
 //! for IR construction and code generation.
 
 use super::super::types::*;
+use super::BuildOptions;
 use crate::parser::{ImportKind, ParsedModule};
-use crate::TranspileOptions;
 use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 /// Memory information extracted from the module.
 pub(super) struct MemoryInfo {
     pub(super) has_memory: bool,
     pub(super) has_memory_import: bool,
+    pub(super) import_initial_pages: usize,
+    pub(super) import_max_pages: Option<usize>,
     pub(super) max_pages: usize,
     pub(super) initial_pages: usize,
 }
@@ -21,18 +25,25 @@ pub(super) struct MemoryInfo {
 pub(super) struct TableInfo {
     pub(super) initial: usize,
     pub(super) max: usize,
+    pub(super) imported: bool,
 }
 
 /// Extracts memory information from a parsed WASM module.
 pub(super) fn extract_memory_info(
     parsed: &ParsedModule,
-    options: &TranspileOptions,
+    options: &BuildOptions,
 ) -> Result<MemoryInfo> {
     let has_memory = parsed.memory.is_some();
-    let has_memory_import = parsed
-        .imports
-        .iter()
-        .any(|imp| matches!(imp.kind, ImportKind::Memory { .. }));
+    let memory_import = parsed.imports.iter().find_map(|imp| match imp.kind {
+        ImportKind::Memory {
+            initial_pages,
+            maximum_pages,
+        } => Some((initial_pages, maximum_pages)),
+        _ => None,
+    });
+    let has_memory_import = memory_import.is_some();
+    let import_initial_pages = memory_import.map_or(0, |(initial, _)| initial as usize);
+    let import_max_pages = memory_import.and_then(|(_, max)| max).map(|p| p as usize);
     let max_pages = if let Some(ref mem) = parsed.memory {
         mem.maximum_pages
             .map(|p| p as usize)
@@ -49,20 +60,43 @@ pub(super) fn extract_memory_info(
     Ok(MemoryInfo {
         has_memory,
         has_memory_import,
+        import_initial_pages,
+        import_max_pages,
         max_pages,
         initial_pages,
     })
 }
 
 /// Extracts table information from a parsed WASM module.
+///
+/// An imported table (LLVM's `-Wl,--import-table`) takes its sizes from the
+/// import type; the host supplies a table at least that large.
 pub(super) fn extract_table_info(parsed: &ParsedModule) -> TableInfo {
     if let Some(ref tbl) = parsed.table {
-        TableInfo {
+        return TableInfo {
             initial: tbl.initial_size as usize,
             max: (tbl.max_size.unwrap_or(tbl.initial_size) as usize),
-        }
-    } else {
-        TableInfo { initial: 0, max: 0 }
+            imported: false,
+        };
+    }
+    let imported = parsed.imports.iter().find_map(|imp| match imp.kind {
+        ImportKind::Table {
+            initial_size,
+            max_size,
+        } => Some((initial_size, max_size)),
+        _ => None,
+    });
+    match imported {
+        Some((initial, max)) => TableInfo {
+            initial: initial as usize,
+            max: max.unwrap_or(initial) as usize,
+            imported: true,
+        },
+        None => TableInfo {
+            initial: 0,
+            max: 0,
+            imported: false,
+        },
     }
 }
 
@@ -86,32 +120,49 @@ pub(super) fn build_canonical_type_mapping(parsed: &ParsedModule) -> Vec<usize>
     mapping
 }
 
-/// Builds the per-type-index signatures: `(param_count, return_type)`.
-pub(super) fn build_type_signatures(parsed: &ParsedModule) -> Vec<(usize, Option<WasmType>)> {
+/// Builds the per-type-index signatures: `(param_count, result_count)`.
+pub(super) fn build_type_signatures(parsed: &ParsedModule) -> Vec<(usize, usize)> {
     parsed
         .types
         .iter()
-        .map(|ty| {
-            let param_count = ty.params().len();
-            let ret = ty
-                .results()
-                .first()
-                .map(|vt| WasmType::from_wasmparser(*vt));
-            (param_count, ret)
+        .map(|ty| (ty.params().len(), ty.results().len()))
+        .collect()
+}
+
+/// Interns import names: `(module_name, name)` per import, in import order.
+///
+/// Each distinct module name is allocated once, and each import name once, so
+/// the builder context, every `CallImport` site, and the final `FuncImport`
+/// entries all share the same strings instead of cloning them.
+pub(super) fn intern_import_names(parsed: &ParsedModule) -> Vec<(Arc<str>, Arc<str>)> {
+    let mut modules: BTreeMap<&str, Arc<str>> = BTreeMap::new();
+    parsed
+        .imports
+        .iter()
+        .map(|imp| {
+            let module_name = modules
+                .entry(imp.module_name.as_str())
+                .or_insert_with(|| Arc::from(imp.module_name.as_str()))
+                .clone();
+            (module_name, Arc::from(imp.name.as_str()))
         })
         .collect()
 }
 
 /// Extracts imported globals from a parsed WASM module.
-pub(super) fn build_imported_globals(parsed: &ParsedModule) -> Vec<ImportedGlobalDef> {
+pub(super) fn build_imported_globals(
+    parsed: &ParsedModule,
+    import_names: &[(Arc<str>, Arc<str>)],
+) -> Vec<ImportedGlobalDef> {
     parsed
         .imports
         .iter()
-        .filter_map(|imp| {
+        .zip(import_names)
+        .filter_map(|(imp, (module_name, name))| {
             if let ImportKind::Global { val_type, mutable } = &imp.kind {
                 Some(ImportedGlobalDef {
-                    module_name: imp.module_name.clone(),
-                    name: imp.name.clone(),
+                    module_name: module_name.clone(),
+                    name: name.to_string(),
                     wasm_type: WasmType::from_wasmparser(*val_type),
                     mutable: *mutable,
                 })
@@ -123,81 +174,75 @@ pub(super) fn build_imported_globals(parsed: &ParsedModule) -> Vec<ImportedGloba
 }
 
 /// Builds the function signature list (imported functions followed by local functions).
-pub(super) fn build_function_signatures(parsed: &ParsedModule) -> Vec<(usize, Option<WasmType>)> {
-    let mut func_sigs: Vec<(usize, Option<WasmType>)> = Vec::new();
+///
+/// Each entry is `(param_count, result_count)`.
+pub(super) fn build_function_signatures(parsed: &ParsedModule) -> Vec<(usize, usize)> {
+    let mut func_sigs: Vec<(usize, usize)> = Vec::new();
 
     // Imported function signatures
     for import in &parsed.imports {
         if let ImportKind::Function(type_idx) = &import.kind {
             let func_type = &parsed.types[*type_idx as usize];
-            let param_count = func_type.params().len();
-            let ret = func_type
-                .results()
-                .first()
-                .map(|vt| WasmType::from_wasmparser(*vt));
-            func_sigs.push((param_count, ret));
+            func_sigs.push((func_type.params().len(), func_type.results().len()));
         }
     }
 
     // Local function signatures
     for func in &parsed.functions {
         let func_type = &parsed.types[func.type_idx as usize];
-        let param_count = func_type.params().len();
-        let ret = func_type
-            .results()
-            .first()
-            .map(|vt| WasmType::from_wasmparser(*vt));
-        func_sigs.push((param_count, ret));
+        func_sigs.push((func_type.params().len(), func_type.results().len()));
     }
 
     func_sigs
 }
 
-/// Parses Wasm operators from a function body.
-pub(super) fn parse_function_operators(body: &[u8]) -> Result<Vec<wasmparser::Operator<'_>>> {
-    let mut operators = Vec::new();
-    let mut binary_reader = wasmparser::BinaryReader::new(body, 0);
-
-    while !binary_reader.eof() {
-        let op = binary_reader
-            .read_operator()
-            .context("failed to read operator")?;
-        operators.push(op);
-    }
-
-    Ok(operators)
+/// What translating a function body tells about it besides its IR.
+pub(super) struct BodyLayout {
+    /// See [`FuncSymbolDef::block_offsets`].
+    pub block_offsets: Vec<(BlockId, usize)>,
+    /// See [`FuncSymbolDef::max_stack_depth`].
+    pub max_stack_depth: usize,
 }
 
 /// Translates all functions in the module to intermediate representation.
+///
+/// Also returns the [`BodyLayout`] of each function, and whether atomic
+/// operators were lowered to plain memory accesses (only with
+/// `lower_atomics`).
 pub(super) fn build_ir_functions(
     parsed: &ParsedModule,
-    type_sigs: &[(usize, Option<WasmType>)],
+    type_sigs: &[(usize, usize)],
+    import_names: &[(Arc<str>, Arc<str>)],
     num_imported_functions: u32,
-) -> Result<Vec<IrFunction>> {
+    lower_atomics: bool,
+) -> Result<(Vec<IrFunction>, Vec<BodyLayout>, bool)> {
     use super::core::{IrBuilder, ModuleContext};
     use crate::parser::ImportKind;
 
     let mut ir_builder = IrBuilder::new();
-    let mut ir_functions = Vec::new();
+    let mut ir_functions = Vec::with_capacity(parsed.functions.len());
+    let mut layouts = Vec::with_capacity(parsed.functions.len());
 
     // Build function signature list (imported + local)
     let func_sigs = build_function_signatures(parsed);
 
     // Build function import list for IR builder
-    let func_imports: Vec<(String, String)> = parsed
+    let func_imports: Arc<[(Arc<str>, Arc<str>)]> = parsed
         .imports
         .iter()
-        .filter_map(|imp| match &imp.kind {
-            ImportKind::Function(_) => Some((imp.module_name.clone(), imp.name.clone())),
+        .zip(import_names)
+        .filter_map(|(imp, names)| match &imp.kind {
+            ImportKind::Function(_) => Some(names.clone()),
             _ => None,
         })
         .collect();
 
     let module_ctx = ModuleContext {
-        func_signatures: func_sigs,
-        type_signatures: type_sigs.to_vec(),
+        func_signatures: func_sigs.into(),
+        type_signatures: type_sigs.into(),
         num_imported_functions: num_imported_functions as usize,
         func_imports,
+        lower_atomics,
     };
 
     for (func_idx, func) in parsed.functions.iter().enumerate() {
@@ -209,19 +254,29 @@ pub(super) fn build_ir_functions(
             .map(|vt| (*vt, WasmType::from_wasmparser(*vt)))
             .collect();
 
-        let return_type = func_type
+        let results: Vec<WasmType> = func_type
             .results()
-            .first()
-            .map(|vt| WasmType::from_wasmparser(*vt));
-
-        let operators = parse_function_operators(&func.body)?;
+            .iter()
+            .map(|vt| WasmType::from_wasmparser(*vt))
+            .collect();
 
         let ir_func = ir_builder
-            .translate_function(&params, &func.locals, return_type, &operators, &module_ctx)
+            .translate_function_body(&params, &func.locals, results, &func.body, &module_ctx)
             .with_context(|| format!("failed to build IR for function {}", func_idx))?;
 
         ir_functions.push(ir_func);
+
+        // The bytecode is the tail of the body, after the local declarations
+        let body_start = func.code_range.end - func.body.len();
+        layouts.push(BodyLayout {
+            block_offsets: ir_builder
+                .block_offsets()
+                .iter()
+                .map(|&(block, offset)| (block, body_start + offset))
+                .collect(),
+            max_stack_depth: ir_builder.max_stack_depth(),
+        });
     }
 
-    Ok(ir_functions)
+    Ok((ir_functions, layouts, ir_builder.lowered_atomics))
 }
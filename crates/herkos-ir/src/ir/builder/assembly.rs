@@ -0,0 +1,586 @@
+//! Metadata assembly - builds the final ModuleInfo from analyzed pieces.
+//!
+//! This module takes the results of module analysis (extracted memory, table,
+//! type, and function information) and assembles them into the final `ModuleInfo`
+//! structure that is passed to code generation.
+
+use super::super::types::*;
+use super::analysis::{MemoryInfo, TableInfo};
+use crate::parser::{ExportKind, ImportKind, ParsedModule};
+use anyhow::{bail, Result};
+use heck::ToSnakeCase;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Assembles module metadata for code generation.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn assemble_module_metadata(
+    parsed: &ParsedModule,
+    mem_info: &MemoryInfo,
+    table_info: &TableInfo,
+    canonical_type: Vec<usize>,
+    mut ir_functions: Vec<IrFunction>,
+    num_imported_functions: usize,
+    import_names: &[(Arc<str>, Arc<str>)],
+    imported_globals: Vec<ImportedGlobalDef>,
+) -> Result<ModuleInfo> {
+    let globals = build_globals(parsed, &imported_globals)?;
+    let stack_pointer = find_stack_pointer(parsed);
+    let heap_base = find_linker_global(parsed, "__heap_base");
+    let data_end = find_linker_global(parsed, "__data_end");
+    let data_segments = build_data_segments(parsed, &imported_globals)?;
+    let passive_data_segments = build_passive_data_segments(parsed);
+    let element_segments = build_element_segments(
+        parsed,
+        num_imported_functions,
+        &canonical_type,
+        &imported_globals,
+    )?;
+    let dylink = build_dylink(parsed, &imported_globals);
+    let toolchains = build_toolchains(parsed);
+    let func_exports = build_function_exports(parsed, num_imported_functions);
+    let memory_exports = build_memory_exports(parsed);
+    let table_exports = build_table_exports(parsed);
+    let type_signatures = build_call_indirect_signatures(parsed);
+    let func_imports = build_function_imports(parsed, import_names, &type_signatures);
+    let func_symbols = build_func_symbols(parsed, num_imported_functions);
+    let func_idents = func_idents(parsed, num_imported_functions);
+    let param_names = param_names(parsed, num_imported_functions);
+
+    // Set type_idx for all IR functions
+    for (func_idx, func) in parsed.functions.iter().enumerate() {
+        if let Some(ir_func) = ir_functions.get_mut(func_idx) {
+            ir_func.type_idx = TypeIdx::new(canonical_type[func.type_idx as usize]);
+        }
+    }
+
+    Ok(ModuleInfo {
+        has_memory: mem_info.has_memory,
+        has_memory_import: mem_info.has_memory_import,
+        memory_import_initial_pages: mem_info.import_initial_pages,
+        memory_import_max_pages: mem_info.import_max_pages,
+        max_pages: mem_info.max_pages,
+        initial_pages: mem_info.initial_pages,
+        table_initial: table_info.initial,
+        table_max: table_info.max,
+        has_table_import: table_info.imported,
+        table_exports,
+        element_segments,
+        globals,
+        stack_pointer,
+        heap_base,
+        data_end,
+        data_segments,
+        passive_data_segments,
+        func_exports,
+        memory_exports,
+        type_signatures,
+        canonical_type,
+        func_imports,
+        imported_globals,
+        ir_functions,
+        wasm_version: parsed.wasm_version,
+        func_symbols,
+        wasmtime_adapter: false,
+        yield_points: false,
+        export_features: Default::default(),
+        func_features: Vec::new(),
+        merged_into: Vec::new(),
+        dead_params: Vec::new(),
+        start: None,
+        dylink,
+        toolchains,
+        lowered_atomics: false,
+        observe_memory: false,
+        record_replay: false,
+        dynamic_invoke: false,
+        export_groups: Vec::new(),
+        shared_host: false,
+        constant_time: Vec::new(),
+        unoptimized: Vec::new(),
+        ctors_evaluated: false,
+        func_idents,
+        param_names,
+        purity: Vec::new(),
+        link: false,
+        heap_profile: false,
+        debug_memory: false,
+        contracts: Default::default(),
+        coverage: false,
+        bounds_profile: false,
+        import_regions: Default::default(),
+        import_capabilities: Default::default(),
+        guest_log: Default::default(),
+        hot_reload: false,
+        pretransform: None,
+    })
+}
+
+/// Builds per-function debug metadata (original names and body ranges).
+fn build_func_symbols(parsed: &ParsedModule, num_imported_functions: usize) -> Vec<FuncSymbolDef> {
+    parsed
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(local_idx, func)| {
+            let wasm_idx = (num_imported_functions + local_idx) as u32;
+            FuncSymbolDef {
+                name: parsed.func_names.get(&wasm_idx).cloned(),
+                code_range: func.code_range.clone(),
+                block_offsets: Vec::new(), // set by `build_module_info`
+                max_stack_depth: 0,        // likewise
+            }
+        })
+        .collect()
+}
+
+/// Identifiers for the local functions, from the name section: `$square`
+/// becomes `func_square`. The `func_` prefix keeps them apart from the
+/// items of `herkos_runtime` the generated code glob-imports and from the
+/// other items herkos generates.
+///
+/// A function keeps the positional `func_{idx}` (an empty entry) when it has
+/// no name, or when its name is not an identifier, is longer than 64
+/// characters, is all digits or is taken by an earlier function.
+fn func_idents(parsed: &ParsedModule, num_imported_functions: usize) -> Vec<String> {
+    let mut taken = HashSet::new();
+    (0..parsed.functions.len())
+        .map(|idx| {
+            let wasm_idx = (num_imported_functions + idx) as u32;
+            let named = parsed
+                .func_names
+                .get(&wasm_idx)
+                .map(|name| name.to_snake_case())
+                .filter(|name| {
+                    !name.is_empty()
+                        && name.len() <= 64
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        && !name.bytes().all(|b| b.is_ascii_digit())
+                })
+                .map(|name| format!("func_{name}"))
+                .filter(|ident| !taken.contains(ident));
+            match named {
+                Some(ident) => {
+                    taken.insert(ident.clone());
+                    ident
+                }
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Names used by the generated methods besides the parameters.
+const WRAPPER_NAMES: &[&str] = &["env", "globals", "host", "memory", "module", "table"];
+
+/// Parameter names for the local functions' generated methods, from the
+/// local names in the name section.
+///
+/// A parameter keeps the positional `v{i}` (an empty entry) when it has no
+/// name, or when its name is not an identifier, is a keyword, looks like a
+/// positional name, is used by the method itself or is taken by an earlier
+/// parameter.
+fn param_names(parsed: &ParsedModule, num_imported_functions: usize) -> Vec<Vec<String>> {
+    parsed
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(idx, func)| {
+            let wasm_idx = (num_imported_functions + idx) as u32;
+            let Some(locals) = parsed.local_names.get(&wasm_idx) else {
+                return Vec::new();
+            };
+            let count = parsed
+                .types
+                .get(func.type_idx as usize)
+                .map_or(0, |ty| ty.params().len());
+            let mut taken = HashSet::new();
+            (0..count as u32)
+                .map(|param| {
+                    let named =
+                        locals
+                            .get(&param)
+                            .map(|name| name.to_snake_case())
+                            .filter(|name| {
+                                name.starts_with(|c: char| c.is_ascii_lowercase())
+                                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                                    && !RUST_KEYWORDS.contains(&name.as_str())
+                                    && !WRAPPER_NAMES.contains(&name.as_str())
+                                    && !is_positional_param(name)
+                                    && !taken.contains(name)
+                            });
+                    match named {
+                        Some(name) => {
+                            taken.insert(name.clone());
+                            name
+                        }
+                        None => String::new(),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether `name` has the form `v<digits>` used for unnamed parameters.
+fn is_positional_param(name: &str) -> bool {
+    name.strip_prefix('v')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Builds global variable definitions.
+///
+/// `global.get` initializers must name an immutable imported global of the
+/// same type (Wasm MVP constant expressions).
+fn build_globals(
+    parsed: &ParsedModule,
+    imported_globals: &[ImportedGlobalDef],
+) -> Result<Vec<GlobalDef>> {
+    let fields = global_fields(parsed);
+    parsed
+        .globals
+        .iter()
+        .zip(fields)
+        .enumerate()
+        .map(|(idx, (g, field))| {
+            let init_value = match g.init_value {
+                crate::parser::InitValue::I32(v) => GlobalInit::I32(v),
+                crate::parser::InitValue::I64(v) => GlobalInit::I64(v),
+                crate::parser::InitValue::F32(v) => GlobalInit::F32(v),
+                crate::parser::InitValue::F64(v) => GlobalInit::F64(v),
+                crate::parser::InitValue::GlobalGet(src) => {
+                    let ty = WasmType::from_wasmparser(g.val_type);
+                    let Some(import) = imported_globals.get(src as usize) else {
+                        bail!(
+                            "global {} is initialized from global {}, which is not imported",
+                            parsed.num_imported_globals as usize + idx,
+                            src
+                        );
+                    };
+                    if import.mutable || import.wasm_type != ty {
+                        bail!(
+                            "global {} is initialized from imported global `{}`, which must be an immutable {}",
+                            parsed.num_imported_globals as usize + idx,
+                            import.name,
+                            ty
+                        );
+                    }
+                    GlobalInit::Imported {
+                        index: ImportedGlobalIdx::new(src as usize),
+                        ty,
+                    }
+                }
+            };
+            Ok(GlobalDef {
+                mutable: g.mutable,
+                init_value,
+                field,
+            })
+        })
+        .collect()
+}
+
+/// Rust keywords that cannot be used as generated identifiers.
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while",
+];
+
+/// `Globals` field names for the local globals, from the name section
+/// (`__stack_pointer` becomes `stack_pointer`).
+///
+/// A global keeps the positional `g{index}` when it has no name, or when its
+/// name is not an identifier, is a keyword, looks like a positional name or
+/// is taken by an earlier global.
+fn global_fields(parsed: &ParsedModule) -> Vec<String> {
+    let mut taken = HashSet::new();
+    (0..parsed.globals.len())
+        .map(|idx| {
+            let wasm_idx = parsed.num_imported_globals + idx as u32;
+            let named = parsed
+                .global_names
+                .get(&wasm_idx)
+                .map(|name| name.to_snake_case())
+                .filter(|name| {
+                    name.starts_with(|c: char| c.is_ascii_lowercase())
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        && !RUST_KEYWORDS.contains(&name.as_str())
+                        && !is_positional(name)
+                        && !taken.contains(name)
+                });
+            let field = named.unwrap_or_else(|| format!("g{idx}"));
+            taken.insert(field.clone());
+            field
+        })
+        .collect()
+}
+
+/// Whether `name` has the form `g<digits>` used for unnamed globals.
+fn is_positional(name: &str) -> bool {
+    name.strip_prefix('g')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Finds LLVM's shadow stack pointer: a mutable `__stack_pointer` global
+/// (see [`find_linker_global`]).
+fn find_stack_pointer(parsed: &ParsedModule) -> Option<LocalGlobalIdx> {
+    find_linker_global(parsed, "__stack_pointer")
+        .filter(|idx| parsed.globals[idx.as_usize()].mutable)
+}
+
+/// Finds a global the linker defines: a local `i32` global with a constant
+/// initializer, named `name` in the name section or exported under it.
+fn find_linker_global(parsed: &ParsedModule, name: &str) -> Option<LocalGlobalIdx> {
+    let named = parsed
+        .global_names
+        .iter()
+        .find(|(_, n)| n.as_str() == name)
+        .map(|(&idx, _)| idx);
+    let exported = || {
+        parsed
+            .exports
+            .iter()
+            .find(|e| e.kind == ExportKind::Global && e.name == name)
+            .map(|e| e.index)
+    };
+    let local = named
+        .or_else(exported)?
+        .checked_sub(parsed.num_imported_globals)? as usize;
+    let global = parsed.globals.get(local)?;
+    matches!(global.init_value, crate::parser::InitValue::I32(_))
+        .then(|| LocalGlobalIdx::new(local))
+}
+
+/// Builds passive data segment definitions.
+fn build_passive_data_segments(parsed: &ParsedModule) -> Vec<PassiveDataSegment> {
+    parsed
+        .passive_data_segments
+        .iter()
+        .map(|ps| PassiveDataSegment {
+            wasm_index: ps.wasm_index,
+            data: ps.data.clone(),
+        })
+        .collect()
+}
+
+/// Resolves the global a segment offset is relative to, which must be an
+/// immutable imported `i32` (dylink's `__memory_base` / `__table_base`).
+fn segment_base(
+    base: Option<u32>,
+    imported_globals: &[ImportedGlobalDef],
+    what: &str,
+) -> Result<Option<ImportedGlobalIdx>> {
+    let Some(src) = base else {
+        return Ok(None);
+    };
+    match imported_globals.get(src as usize) {
+        Some(g) if !g.mutable && g.wasm_type == WasmType::I32 => {
+            Ok(Some(ImportedGlobalIdx::new(src as usize)))
+        }
+        Some(g) => bail!(
+            "{} offset is relative to imported global `{}`, which must be an immutable i32",
+            what,
+            g.name
+        ),
+        None => bail!(
+            "{} offset is relative to global {}, which is not imported",
+            what,
+            src
+        ),
+    }
+}
+
+/// Builds data segment definitions.
+fn build_data_segments(
+    parsed: &ParsedModule,
+    imported_globals: &[ImportedGlobalDef],
+) -> Result<Vec<DataSegmentDef>> {
+    parsed
+        .data_segments
+        .iter()
+        .map(|ds| {
+            Ok(DataSegmentDef {
+                offset: ds.offset,
+                base: segment_base(ds.base, imported_globals, "data segment")?,
+                data: ds.data.clone(),
+                read_only: false,
+                resident: true,
+                name: parsed.data_names.get(&ds.wasm_index).cloned(),
+                protected: false,
+            })
+        })
+        .collect()
+}
+
+/// Builds element segment (table initialization) definitions.
+///
+/// Wasm function indices below `num_imported_functions` name imports, which
+/// keep their canonical type so `call_indirect` can check and dispatch them.
+fn build_element_segments(
+    parsed: &ParsedModule,
+    num_imported_functions: usize,
+    canonical_type: &[usize],
+    imported_globals: &[ImportedGlobalDef],
+) -> Result<Vec<ElementSegmentDef>> {
+    let import_types: Vec<usize> = parsed
+        .imports
+        .iter()
+        .filter_map(|imp| match imp.kind {
+            ImportKind::Function(type_idx) => Some(type_idx as usize),
+            _ => None,
+        })
+        .collect();
+    parsed
+        .element_segments
+        .iter()
+        .map(|es| {
+            Ok(ElementSegmentDef {
+                offset: es.offset as usize,
+                base: segment_base(es.base, imported_globals, "element segment")?,
+                func_indices: es
+                    .func_indices
+                    .iter()
+                    .map(|idx| {
+                        let global_idx = *idx as usize;
+                        match global_idx.checked_sub(num_imported_functions) {
+                            Some(local_idx) => ElementFunc::Local(LocalFuncIdx::new(local_idx)),
+                            None => {
+                                let type_idx = import_types[global_idx];
+                                ElementFunc::Import {
+                                    import: ImportIdx::new(global_idx),
+                                    type_idx: TypeIdx::new(
+                                        canonical_type.get(type_idx).copied().unwrap_or(type_idx),
+                                    ),
+                                }
+                            }
+                        }
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Builds the dynamic-linking metadata of a `dylink.0` side module.
+fn build_dylink(
+    parsed: &ParsedModule,
+    imported_globals: &[ImportedGlobalDef],
+) -> Option<DylinkDef> {
+    let dylink = parsed.dylink.as_ref()?;
+    let table_base = imported_globals
+        .iter()
+        .position(|g| &*g.module_name == "env" && g.name == "__table_base")
+        .map(ImportedGlobalIdx::new);
+    Some(DylinkDef {
+        memory_size: dylink.memory_size,
+        memory_alignment: dylink.memory_alignment,
+        table_size: dylink.table_size,
+        table_alignment: dylink.table_alignment,
+        needed: dylink.needed.clone(),
+        table_base,
+    })
+}
+
+/// Recognizes the toolchains named anywhere in the `producers` section.
+fn build_toolchains(parsed: &ParsedModule) -> Vec<Toolchain> {
+    Toolchain::ALL
+        .into_iter()
+        .filter(|t| {
+            parsed
+                .producers
+                .names()
+                .any(|name| name.eq_ignore_ascii_case(t.producer_name()))
+        })
+        .collect()
+}
+
+/// Builds exported function definitions.
+///
+/// Export indices use global numbering (imports + locals). We filter to local
+/// functions and offset to local function index space for codegen (func_0, func_1, ...).
+fn build_function_exports(parsed: &ParsedModule, num_imported_functions: usize) -> Vec<FuncExport> {
+    parsed
+        .exports
+        .iter()
+        .filter(|e| e.kind == ExportKind::Func && (e.index as usize) >= num_imported_functions)
+        .map(|e| FuncExport {
+            name: e.name.clone(),
+            func_index: LocalFuncIdx::new((e.index as usize) - num_imported_functions),
+        })
+        .collect()
+}
+
+/// Builds the list of names the linear memory is exported under.
+fn build_memory_exports(parsed: &ParsedModule) -> Vec<String> {
+    parsed
+        .exports
+        .iter()
+        .filter(|e| e.kind == ExportKind::Memory)
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+/// Builds the list of names the table is exported under.
+fn build_table_exports(parsed: &ParsedModule) -> Vec<String> {
+    parsed
+        .exports
+        .iter()
+        .filter(|e| e.kind == ExportKind::Table)
+        .map(|e| e.name.clone())
+        .collect()
+}
+
+/// Builds type signatures for call_indirect type checking.
+fn build_call_indirect_signatures(parsed: &ParsedModule) -> Vec<FuncSignature> {
+    parsed
+        .types
+        .iter()
+        .map(|ty| {
+            let params = ty
+                .params()
+                .iter()
+                .map(|vt| WasmType::from_wasmparser(*vt))
+                .collect();
+            let results = ty
+                .results()
+                .iter()
+                .map(|vt| WasmType::from_wasmparser(*vt))
+                .collect();
+            FuncSignature {
+                params,
+                results,
+                type_idx: TypeIdx::new(0),
+            }
+        })
+        .collect()
+}
+
+/// Builds function import trait definitions.
+///
+/// Names come from the interned `import_names` and parameter lists are shared
+/// with the type section `signatures`, so no per-import allocation is made.
+fn build_function_imports(
+    parsed: &ParsedModule,
+    import_names: &[(Arc<str>, Arc<str>)],
+    signatures: &[FuncSignature],
+) -> Vec<FuncImport> {
+    parsed
+        .imports
+        .iter()
+        .zip(import_names)
+        .filter_map(|(imp, (module_name, func_name))| match &imp.kind {
+            ImportKind::Function(type_idx) => {
+                let sig = &signatures[*type_idx as usize];
+                Some(FuncImport {
+                    module_name: module_name.clone(),
+                    func_name: func_name.clone(),
+                    params: sig.params.clone(),
+                    results: sig.results.clone(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,390 @@
+//! Single-threaded lowering of atomic memory operators (threads proposal).
+//!
+//! Modules built with `-pthread` use atomics even when the host only ever
+//! runs one thread. With
+//! [`BuildOptions::lower_atomics`](crate::ir::BuildOptions::lower_atomics)
+//! they are translated to the plain IR memory accesses:
+//!
+//! - `*.atomic.load*` / `*.atomic.store*` → `Load` / `Store`
+//! - `*.atomic.rmw*.{add,sub,and,or,xor,xchg}` → `Load`, `BinOp`, `Store`,
+//!   returning the old value
+//! - `*.atomic.rmw*.cmpxchg` → `Load`, compare, `Select`, `Store`
+//! - `atomic.fence` → nothing
+//! - `memory.atomic.notify` → `0` (there are no other waiters)
+//! - `memory.atomic.wait32/64` → `1` ("not-equal") if the value differs,
+//!   `2` ("timed-out") for a finite timeout, and a trap for an infinite wait,
+//!   which no other thread could ever end
+//!
+//! Alignment is not checked: misaligned accesses that would trap as atomics
+//! succeed as plain accesses.
+
+use super::super::types::*;
+use super::core::{IrBuilder, ModuleContext};
+use anyhow::{bail, Result};
+use wasmparser::Operator;
+
+/// Read-modify-write operation of an `atomic.rmw` operator.
+#[derive(Debug, Clone, Copy)]
+enum RmwOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Xchg,
+}
+
+/// What an atomic operator does.
+#[derive(Debug, Clone, Copy)]
+enum AtomicKind {
+    Load,
+    Store,
+    Rmw(RmwOp),
+    Cmpxchg,
+    Notify,
+    Wait,
+    Fence,
+}
+
+/// A decoded atomic operator.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AtomicOp {
+    kind: AtomicKind,
+    /// Value type (the type of `expected` for waits).
+    ty: WasmType,
+    width: MemoryAccessWidth,
+    offset: u64,
+}
+
+impl AtomicOp {
+    /// Decode `op`, or `None` if it is not an atomic memory operator.
+    pub(super) fn decode(op: &Operator) -> Option<Self> {
+        use AtomicKind::*;
+        use MemoryAccessWidth::{Full, I16, I32 as W32, I8};
+        use WasmType::{I32, I64};
+        let (kind, ty, width, memarg) = match op {
+            Operator::AtomicFence => {
+                return Some(AtomicOp {
+                    kind: Fence,
+                    ty: I32,
+                    width: Full,
+                    offset: 0,
+                })
+            }
+            Operator::MemoryAtomicNotify { memarg } => (Notify, I32, Full, memarg),
+            Operator::MemoryAtomicWait32 { memarg } => (Wait, I32, Full, memarg),
+            Operator::MemoryAtomicWait64 { memarg } => (Wait, I64, Full, memarg),
+
+            Operator::I32AtomicLoad { memarg } => (Load, I32, Full, memarg),
+            Operator::I64AtomicLoad { memarg } => (Load, I64, Full, memarg),
+            Operator::I32AtomicLoad8U { memarg } => (Load, I32, I8, memarg),
+            Operator::I32AtomicLoad16U { memarg } => (Load, I32, I16, memarg),
+            Operator::I64AtomicLoad8U { memarg } => (Load, I64, I8, memarg),
+            Operator::I64AtomicLoad16U { memarg } => (Load, I64, I16, memarg),
+            Operator::I64AtomicLoad32U { memarg } => (Load, I64, W32, memarg),
+
+            Operator::I32AtomicStore { memarg } => (Store, I32, Full, memarg),
+            Operator::I64AtomicStore { memarg } => (Store, I64, Full, memarg),
+            Operator::I32AtomicStore8 { memarg } => (Store, I32, I8, memarg),
+            Operator::I32AtomicStore16 { memarg } => (Store, I32, I16, memarg),
+            Operator::I64AtomicStore8 { memarg } => (Store, I64, I8, memarg),
+            Operator::I64AtomicStore16 { memarg } => (Store, I64, I16, memarg),
+            Operator::I64AtomicStore32 { memarg } => (Store, I64, W32, memarg),
+
+            Operator::I32AtomicRmwAdd { memarg } => (Rmw(RmwOp::Add), I32, Full, memarg),
+            Operator::I64AtomicRmwAdd { memarg } => (Rmw(RmwOp::Add), I64, Full, memarg),
+            Operator::I32AtomicRmw8AddU { memarg } => (Rmw(RmwOp::Add), I32, I8, memarg),
+            Operator::I32AtomicRmw16AddU { memarg } => (Rmw(RmwOp::Add), I32, I16, memarg),
+            Operator::I64AtomicRmw8AddU { memarg } => (Rmw(RmwOp::Add), I64, I8, memarg),
+            Operator::I64AtomicRmw16AddU { memarg } => (Rmw(RmwOp::Add), I64, I16, memarg),
+            Operator::I64AtomicRmw32AddU { memarg } => (Rmw(RmwOp::Add), I64, W32, memarg),
+
+            Operator::I32AtomicRmwSub { memarg } => (Rmw(RmwOp::Sub), I32, Full, memarg),
+            Operator::I64AtomicRmwSub { memarg } => (Rmw(RmwOp::Sub), I64, Full, memarg),
+            Operator::I32AtomicRmw8SubU { memarg } => (Rmw(RmwOp::Sub), I32, I8, memarg),
+            Operator::I32AtomicRmw16SubU { memarg } => (Rmw(RmwOp::Sub), I32, I16, memarg),
+            Operator::I64AtomicRmw8SubU { memarg } => (Rmw(RmwOp::Sub), I64, I8, memarg),
+            Operator::I64AtomicRmw16SubU { memarg } => (Rmw(RmwOp::Sub), I64, I16, memarg),
+            Operator::I64AtomicRmw32SubU { memarg } => (Rmw(RmwOp::Sub), I64, W32, memarg),
+
+            Operator::I32AtomicRmwAnd { memarg } => (Rmw(RmwOp::And), I32, Full, memarg),
+            Operator::I64AtomicRmwAnd { memarg } => (Rmw(RmwOp::And), I64, Full, memarg),
+            Operator::I32AtomicRmw8AndU { memarg } => (Rmw(RmwOp::And), I32, I8, memarg),
+            Operator::I32AtomicRmw16AndU { memarg } => (Rmw(RmwOp::And), I32, I16, memarg),
+            Operator::I64AtomicRmw8AndU { memarg } => (Rmw(RmwOp::And), I64, I8, memarg),
+            Operator::I64AtomicRmw16AndU { memarg } => (Rmw(RmwOp::And), I64, I16, memarg),
+            Operator::I64AtomicRmw32AndU { memarg } => (Rmw(RmwOp::And), I64, W32, memarg),
+
+            Operator::I32AtomicRmwOr { memarg } => (Rmw(RmwOp::Or), I32, Full, memarg),
+            Operator::I64AtomicRmwOr { memarg } => (Rmw(RmwOp::Or), I64, Full, memarg),
+            Operator::I32AtomicRmw8OrU { memarg } => (Rmw(RmwOp::Or), I32, I8, memarg),
+            Operator::I32AtomicRmw16OrU { memarg } => (Rmw(RmwOp::Or), I32, I16, memarg),
+            Operator::I64AtomicRmw8OrU { memarg } => (Rmw(RmwOp::Or), I64, I8, memarg),
+            Operator::I64AtomicRmw16OrU { memarg } => (Rmw(RmwOp::Or), I64, I16, memarg),
+            Operator::I64AtomicRmw32OrU { memarg } => (Rmw(RmwOp::Or), I64, W32, memarg),
+
+            Operator::I32AtomicRmwXor { memarg } => (Rmw(RmwOp::Xor), I32, Full, memarg),
+            Operator::I64AtomicRmwXor { memarg } => (Rmw(RmwOp::Xor), I64, Full, memarg),
+            Operator::I32AtomicRmw8XorU { memarg } => (Rmw(RmwOp::Xor), I32, I8, memarg),
+            Operator::I32AtomicRmw16XorU { memarg } => (Rmw(RmwOp::Xor), I32, I16, memarg),
+            Operator::I64AtomicRmw8XorU { memarg } => (Rmw(RmwOp::Xor), I64, I8, memarg),
+            Operator::I64AtomicRmw16XorU { memarg } => (Rmw(RmwOp::Xor), I64, I16, memarg),
+            Operator::I64AtomicRmw32XorU { memarg } => (Rmw(RmwOp::Xor), I64, W32, memarg),
+
+            Operator::I32AtomicRmwXchg { memarg } => (Rmw(RmwOp::Xchg), I32, Full, memarg),
+            Operator::I64AtomicRmwXchg { memarg } => (Rmw(RmwOp::Xchg), I64, Full, memarg),
+            Operator::I32AtomicRmw8XchgU { memarg } => (Rmw(RmwOp::Xchg), I32, I8, memarg),
+            Operator::I32AtomicRmw16XchgU { memarg } => (Rmw(RmwOp::Xchg), I32, I16, memarg),
+            Operator::I64AtomicRmw8XchgU { memarg } => (Rmw(RmwOp::Xchg), I64, I8, memarg),
+            Operator::I64AtomicRmw16XchgU { memarg } => (Rmw(RmwOp::Xchg), I64, I16, memarg),
+            Operator::I64AtomicRmw32XchgU { memarg } => (Rmw(RmwOp::Xchg), I64, W32, memarg),
+
+            Operator::I32AtomicRmwCmpxchg { memarg } => (Cmpxchg, I32, Full, memarg),
+            Operator::I64AtomicRmwCmpxchg { memarg } => (Cmpxchg, I64, Full, memarg),
+            Operator::I32AtomicRmw8CmpxchgU { memarg } => (Cmpxchg, I32, I8, memarg),
+            Operator::I32AtomicRmw16CmpxchgU { memarg } => (Cmpxchg, I32, I16, memarg),
+            Operator::I64AtomicRmw8CmpxchgU { memarg } => (Cmpxchg, I64, I8, memarg),
+            Operator::I64AtomicRmw16CmpxchgU { memarg } => (Cmpxchg, I64, I16, memarg),
+            Operator::I64AtomicRmw32CmpxchgU { memarg } => (Cmpxchg, I64, W32, memarg),
+
+            _ => return None,
+        };
+        Some(AtomicOp {
+            kind,
+            ty,
+            width,
+            offset: memarg.offset,
+        })
+    }
+}
+
+impl IrBuilder {
+    /// Translate an atomic operator to plain memory accesses, if
+    /// `ctx.lower_atomics` allows it.
+    pub(super) fn translate_atomic(&mut self, op: AtomicOp, ctx: &ModuleContext) -> Result<()> {
+        if !ctx.lower_atomics {
+            bail!(
+                "atomic operators need shared-memory threads, which are not supported; \
+                 transpile with --lower-atomics if the module runs on a single thread"
+            );
+        }
+        self.lowered_atomics = true;
+
+        match op.kind {
+            AtomicKind::Fence => {}
+            AtomicKind::Load => {
+                let sign = sub_width_sign(op.width);
+                self.emit_load_ext(op.ty, op.offset, op.width, sign)?;
+            }
+            AtomicKind::Store => self.emit_store_narrow(op.ty, op.offset, op.width)?,
+            AtomicKind::Rmw(rmw) => {
+                let value = self.pop_atomic_operand("rmw value")?;
+                let addr = self.pop_atomic_operand("rmw address")?;
+                let old = self.emit_atomic_load(op, addr);
+                let new = match rmw_binop(rmw, op.ty) {
+                    Some(binop) => self.emit_value(|dest| IrInstr::BinOp {
+                        dest,
+                        op: binop,
+                        lhs: old.var_id(),
+                        rhs: value.var_id(),
+                    }),
+                    // Exchanges store the operand itself.
+                    None => value,
+                };
+                self.emit_atomic_store(op, addr, new);
+                self.value_stack.push(old);
+            }
+            AtomicKind::Cmpxchg => {
+                let replacement = self.pop_atomic_operand("cmpxchg replacement")?;
+                let expected = self.pop_atomic_operand("cmpxchg expected value")?;
+                let addr = self.pop_atomic_operand("cmpxchg address")?;
+                let old = self.emit_atomic_load(op, addr);
+                // Sub-width operators compare against `expected` wrapped to
+                // the access width.
+                let expected = match width_mask(op.ty, op.width) {
+                    Some(mask) => {
+                        let mask = self.emit_value(|dest| IrInstr::Const { dest, value: mask });
+                        self.emit_value(|dest| IrInstr::BinOp {
+                            dest,
+                            op: if op.ty == WasmType::I64 {
+                                BinOp::I64And
+                            } else {
+                                BinOp::I32And
+                            },
+                            lhs: expected.var_id(),
+                            rhs: mask.var_id(),
+                        })
+                    }
+                    None => expected,
+                };
+                let equal = self.emit_value(|dest| IrInstr::BinOp {
+                    dest,
+                    op: eq_binop(op.ty),
+                    lhs: old.var_id(),
+                    rhs: expected.var_id(),
+                });
+                let new = self.emit_value(|dest| IrInstr::Select {
+                    dest,
+                    val1: replacement.var_id(),
+                    val2: old.var_id(),
+                    condition: equal.var_id(),
+                });
+                self.emit_atomic_store(op, addr, new);
+                self.value_stack.push(old);
+            }
+            AtomicKind::Notify => {
+                self.pop_atomic_operand("notify count")?;
+                self.pop_atomic_operand("notify address")?;
+                let woken = self.emit_value(|dest| IrInstr::Const {
+                    dest,
+                    value: IrValue::I32(0),
+                });
+                self.value_stack.push(woken);
+            }
+            AtomicKind::Wait => self.emit_atomic_wait(op)?,
+        }
+        Ok(())
+    }
+
+    /// `memory.atomic.wait32/64` with no other thread to notify the waiter.
+    /// Stack: [addr, expected, timeout] -> [result]
+    fn emit_atomic_wait(&mut self, op: AtomicOp) -> Result<()> {
+        let timeout = self.pop_atomic_operand("wait timeout")?;
+        let expected = self.pop_atomic_operand("wait expected value")?;
+        let addr = self.pop_atomic_operand("wait address")?;
+        let loaded = self.emit_atomic_load(op, addr);
+        let equal = self.emit_value(|dest| IrInstr::BinOp {
+            dest,
+            op: eq_binop(op.ty),
+            lhs: loaded.var_id(),
+            rhs: expected.var_id(),
+        });
+
+        // A negative timeout waits forever: trap instead of hanging.
+        let zero = self.emit_value(|dest| IrInstr::Const {
+            dest,
+            value: IrValue::I64(0),
+        });
+        let forever = self.emit_value(|dest| IrInstr::BinOp {
+            dest,
+            op: BinOp::I64LtS,
+            lhs: timeout.var_id(),
+            rhs: zero.var_id(),
+        });
+        let blocked = self.emit_value(|dest| IrInstr::BinOp {
+            dest,
+            op: BinOp::I32And,
+            lhs: equal.var_id(),
+            rhs: forever.var_id(),
+        });
+        let trap_block = self.new_block();
+        let continue_block = self.new_block();
+        self.terminate(IrTerminator::BranchIf {
+            condition: blocked.var_id(),
+            if_true: trap_block,
+            if_false: continue_block,
+        });
+        self.start_block(trap_block);
+        self.terminate(IrTerminator::Unreachable);
+        self.start_real_block(continue_block);
+
+        let timed_out = self.emit_value(|dest| IrInstr::Const {
+            dest,
+            value: IrValue::I32(2),
+        });
+        let not_equal = self.emit_value(|dest| IrInstr::Const {
+            dest,
+            value: IrValue::I32(1),
+        });
+        let result = self.emit_value(|dest| IrInstr::Select {
+            dest,
+            val1: timed_out.var_id(),
+            val2: not_equal.var_id(),
+            condition: equal.var_id(),
+        });
+        self.value_stack.push(result);
+        Ok(())
+    }
+
+    /// Emit a value-producing instruction and return its variable.
+    fn emit_value(&mut self, f: impl FnOnce(VarId) -> IrInstr) -> UseVar {
+        let def = self.new_var();
+        self.emit_def(def, f)
+    }
+
+    fn pop_atomic_operand(&mut self, what: &str) -> Result<UseVar> {
+        self.value_stack
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Stack underflow for atomic {}", what))
+    }
+
+    /// Load the (zero-extended) current value an atomic operator works on.
+    fn emit_atomic_load(&mut self, op: AtomicOp, addr: UseVar) -> UseVar {
+        self.emit_value(|dest| IrInstr::Load {
+            dest,
+            ty: op.ty,
+            addr: addr.var_id(),
+            offset: op.offset as u32,
+            width: op.width,
+            sign: sub_width_sign(op.width),
+        })
+    }
+
+    fn emit_atomic_store(&mut self, op: AtomicOp, addr: UseVar, value: UseVar) {
+        self.emit_void(IrInstr::Store {
+            ty: op.ty,
+            addr: addr.var_id(),
+            value: value.var_id(),
+            offset: op.offset as u32,
+            width: op.width,
+        });
+    }
+}
+
+/// Atomic sub-width loads zero-extend.
+fn sub_width_sign(width: MemoryAccessWidth) -> Option<SignExtension> {
+    match width {
+        MemoryAccessWidth::Full => None,
+        _ => Some(SignExtension::Unsigned),
+    }
+}
+
+/// Mask of the bits a sub-width access covers.
+fn width_mask(ty: WasmType, width: MemoryAccessWidth) -> Option<IrValue> {
+    let bits = match width {
+        MemoryAccessWidth::Full => return None,
+        MemoryAccessWidth::I8 => 8,
+        MemoryAccessWidth::I16 => 16,
+        MemoryAccessWidth::I32 => 32,
+    };
+    let mask = (1u64 << bits) - 1;
+    Some(match ty {
+        WasmType::I64 => IrValue::I64(mask as i64),
+        _ => IrValue::I32(mask as i32),
+    })
+}
+
+fn eq_binop(ty: WasmType) -> BinOp {
+    match ty {
+        WasmType::I64 => BinOp::I64Eq,
+        _ => BinOp::I32Eq,
+    }
+}
+
+/// Binary operation computing the stored value, or `None` for exchanges.
+fn rmw_binop(op: RmwOp, ty: WasmType) -> Option<BinOp> {
+    let is_i64 = ty == WasmType::I64;
+    Some(match (op, is_i64) {
+        (RmwOp::Add, false) => BinOp::I32Add,
+        (RmwOp::Add, true) => BinOp::I64Add,
+        (RmwOp::Sub, false) => BinOp::I32Sub,
+        (RmwOp::Sub, true) => BinOp::I64Sub,
+        (RmwOp::And, false) => BinOp::I32And,
+        (RmwOp::And, true) => BinOp::I64And,
+        (RmwOp::Or, false) => BinOp::I32Or,
+        (RmwOp::Or, true) => BinOp::I64Or,
+        (RmwOp::Xor, false) => BinOp::I32Xor,
+        (RmwOp::Xor, true) => BinOp::I64Xor,
+        (RmwOp::Xchg, _) => return None,
+    })
+}
@@ -28,7 +28,7 @@
 //! the fields relevant to it, making illegal states unrepresentable. Key fields:
 //! - `end_block`: join point for forward branches / block exit
 //! - `Loop::start_block`: backward-branch target (re-enter the loop)
-//! - `result_var`: phi convergence slot if the block has a result type
+//! - `result_vars`: phi convergence slots, one per result of the block type
 //! - `locals_at_entry`: snapshot of `local_vars` at frame push
 //! - `branch_incoming`: predecessor snapshots from forward `br`/`br_if`/`br_table`
 //! - `Loop::loop_phi_vars` / `phi_patches`: phi source collection for loop back-edges
@@ -54,10 +54,12 @@
 //!
 //! ## Flow
 //!
-//! 1. `translate_function()` initializes state: allocates VarIds for all locals (params + declared),
+//! 1. `translate_function_body()` (or `translate_function()` for pre-decoded operators)
+//!    initializes state: allocates VarIds for all locals (params + declared),
 //!    creates entry block (BlockId(0)), pushes function-level control frame.
 //!
-//! 2. For each Wasm operator: `translate_operator()` (in `translate.rs`) pops arguments from
+//! 2. For each Wasm operator, decoded on the fly from the body bytes:
+//!    `translate_operator()` (in `translate.rs`) reads the shared `ModuleContext` and pops arguments from
 //!    `value_stack`, allocates new variables, emits IR instructions, pushes results.
 //!
 //! 3. Control flow instructions (`block`, `loop`, `if`, `br`, etc.) manipulate the control stack
@@ -224,6 +226,7 @@
 
 use super::super::types::*;
 use anyhow::{Context, Result};
+use std::sync::Arc;
 use wasmparser::ValType;
 
 /// Control flow frame for tracking nested blocks/loops/if.
@@ -243,8 +246,13 @@ pub(super) enum ControlFrame {
     Block {
         /// Join point where all paths (fall-through + forward branches) converge.
         end_block: BlockId,
-        /// Phi convergence slot for the block's result value; `None` if no result.
-        result_var: Option<UseVar>,
+        /// Phi convergence slots for the block's results, in order.
+        result_vars: Vec<UseVar>,
+        /// Value stack height when the frame was entered, below its parameters.
+        stack_height: usize,
+        /// Result values passed along each edge into `end_block` (fall-through and
+        /// branches carrying values); merged by phis into `result_vars`.
+        result_incoming: Vec<(BlockId, Vec<VarId>)>,
         /// Forward branches (`br`/`br_if`/`br_table`) that target this frame's `end_block`.
         branch_incoming: Vec<(BlockId, Vec<UseVar>)>,
     },
@@ -258,14 +266,24 @@ pub(super) enum ControlFrame {
         pre_loop_block: BlockId,
         /// Exit join point (target of forward `br` that exits the loop).
         end_block: BlockId,
-        /// Phi convergence slot for the loop's result value; `None` if no result.
-        result_var: Option<UseVar>,
+        /// Phi convergence slots for the loop's results, in order.
+        result_vars: Vec<UseVar>,
+        /// Value stack height when the frame was entered, below its parameters.
+        stack_height: usize,
+        /// Result values passed along each edge into `end_block` (fall-through and
+        /// branches carrying values); merged by phis into `result_vars`.
+        result_incoming: Vec<(BlockId, Vec<VarId>)>,
         /// Pre-loop snapshot of `local_vars`; used as the entry predecessor for loop phis.
         locals_at_entry: Vec<UseVar>,
         /// Forward branches that exit the loop (depth > 0 past the loop frame).
         branch_incoming: Vec<(BlockId, Vec<UseVar>)>,
         /// Pre-allocated phi vars (one per Wasm local); substituted into `local_vars` at push.
         loop_phi_vars: Vec<UseVar>,
+        /// Values the loop's parameters had on entry; the entry sources of `param_phi_vars`.
+        params_at_entry: Vec<UseVar>,
+        /// Pre-allocated phi vars (one per loop parameter); substituted for the
+        /// parameters on the value stack at push. Backward branches carry new values.
+        param_phi_vars: Vec<UseVar>,
     },
 
     /// The then-branch of an `if ... end` or `if ... else ... end` construct.
@@ -275,10 +293,19 @@ pub(super) enum ControlFrame {
         else_block: BlockId,
         /// Join point where then and else branches converge.
         end_block: BlockId,
-        /// Phi convergence slot for the if's result value; `None` if no result.
-        result_var: Option<UseVar>,
+        /// Phi convergence slots for the if's results, in order.
+        result_vars: Vec<UseVar>,
+        /// Value stack height when the frame was entered (condition popped),
+        /// below its parameters.
+        stack_height: usize,
+        /// Result values passed along each edge into `end_block` (fall-through and
+        /// branches carrying values); merged by phis into `result_vars`.
+        result_incoming: Vec<(BlockId, Vec<VarId>)>,
         /// Pre-if snapshot of `local_vars`; restored at `Operator::Else`.
         locals_at_entry: Vec<UseVar>,
+        /// The if's parameters; pushed again for the else-branch, and passed
+        /// through as results by an implicit else.
+        params_at_entry: Vec<UseVar>,
         /// Forward branches from the then-body targeting `end_block`.
         branch_incoming: Vec<(BlockId, Vec<UseVar>)>,
     },
@@ -288,8 +315,13 @@ pub(super) enum ControlFrame {
     Else {
         /// Join point inherited from the If frame.
         end_block: BlockId,
-        /// Phi convergence slot inherited from the If frame.
-        result_var: Option<UseVar>,
+        /// Phi convergence slots inherited from the If frame.
+        result_vars: Vec<UseVar>,
+        /// Value stack height inherited from the If frame.
+        stack_height: usize,
+        /// Result values passed along each edge into `end_block` (fall-through and
+        /// branches carrying values); merged by phis into `result_vars`.
+        result_incoming: Vec<(BlockId, Vec<VarId>)>,
         /// Forward branches from *both* then-body and else-body targeting `end_block`.
         branch_incoming: Vec<(BlockId, Vec<UseVar>)>,
         /// Then-branch fall-through info; `None` if the then-branch ended in dead code.
@@ -334,13 +366,50 @@ impl ControlFrame {
         }
     }
 
-    /// Result var (the phi convergence slot), if any.
-    pub(super) fn result_var(&self) -> Option<UseVar> {
+    /// Loop parameter phi vars (one per loop parameter); empty slice for
+    /// non-Loop frames.
+    pub(super) fn param_phi_vars(&self) -> &[UseVar] {
         match self {
-            ControlFrame::Block { result_var, .. }
-            | ControlFrame::Loop { result_var, .. }
-            | ControlFrame::If { result_var, .. }
-            | ControlFrame::Else { result_var, .. } => *result_var,
+            ControlFrame::Loop { param_phi_vars, .. } => param_phi_vars,
+            _ => &[],
+        }
+    }
+
+    /// Result vars (the phi convergence slots), one per result.
+    pub(super) fn result_vars(&self) -> &[UseVar] {
+        match self {
+            ControlFrame::Block { result_vars, .. }
+            | ControlFrame::Loop { result_vars, .. }
+            | ControlFrame::If { result_vars, .. }
+            | ControlFrame::Else { result_vars, .. } => result_vars,
+        }
+    }
+
+    /// Mutable reference to the result values passed into `end_block`.
+    pub(super) fn result_incoming_mut(&mut self) -> &mut Vec<(BlockId, Vec<VarId>)> {
+        match self {
+            ControlFrame::Block {
+                result_incoming, ..
+            }
+            | ControlFrame::Loop {
+                result_incoming, ..
+            }
+            | ControlFrame::If {
+                result_incoming, ..
+            }
+            | ControlFrame::Else {
+                result_incoming, ..
+            } => result_incoming,
+        }
+    }
+
+    /// Value stack height when the frame was entered.
+    pub(super) fn stack_height(&self) -> usize {
+        match self {
+            ControlFrame::Block { stack_height, .. }
+            | ControlFrame::Loop { stack_height, .. }
+            | ControlFrame::If { stack_height, .. }
+            | ControlFrame::Else { stack_height, .. } => *stack_height,
         }
     }
 }
@@ -349,22 +418,31 @@ impl ControlFrame {
 ///
 /// Contains information about the module's functions, types, and imports that
 /// is needed during translation of individual functions.
-#[derive(Debug, Clone)]
+///
+/// The tables are reference-counted so that handing them to the builder for
+/// each function is a pointer copy, not a deep clone of module-sized vectors.
+#[derive(Debug, Clone, Default)]
 pub struct ModuleContext {
-    /// Callee function signatures: (param_count, return_type) per function index.
-    pub func_signatures: Vec<(usize, Option<WasmType>)>,
+    /// Callee function signatures: (param_count, result_count) per function index.
+    pub func_signatures: Arc<[(usize, usize)]>,
 
-    /// Type section signatures: (param_count, return_type) per type index.
-    /// Used for call_indirect to resolve the expected type signature.
-    pub type_signatures: Vec<(usize, Option<WasmType>)>,
+    /// Type section signatures: (param_count, result_count) per type index.
+    /// Used for call_indirect to resolve the expected type signature, and for
+    /// multi-value block types.
+    pub type_signatures: Arc<[(usize, usize)]>,
 
     /// Number of imported functions (these occupy indices 0..N-1 in the
     /// function index space, before local functions).
     pub num_imported_functions: usize,
 
     /// Function import details: (module_name, func_name) for each imported function.
-    /// Indexed by import_idx (0..num_imported_functions-1).
-    pub func_imports: Vec<(String, String)>,
+    /// Indexed by import_idx (0..num_imported_functions-1). Names are interned.
+    pub func_imports: Arc<[(Arc<str>, Arc<str>)]>,
+
+    /// Whether atomic operators are lowered to plain memory accesses (see
+    /// [`BuildOptions::lower_atomics`](crate::ir::BuildOptions::lower_atomics)).
+    /// Rejected otherwise.
+    pub lower_atomics: bool,
 }
 
 /// IR builder state.
@@ -392,31 +470,20 @@ pub struct IrBuilder {
     /// Indices 0..param_count-1 are parameters; param_count.. are declared locals.
     pub(super) local_vars: Vec<UseVar>,
 
-    /// Callee function signatures: (param_count, return_type) per function index.
-    /// Set at the start of each `translate_function` call.
-    pub(super) func_signatures: Vec<(usize, Option<WasmType>)>,
-
-    /// Type section signatures: (param_count, return_type) per type index.
-    /// Used for call_indirect to resolve the expected type signature.
-    pub(super) type_signatures: Vec<(usize, Option<WasmType>)>,
-
-    /// Number of imported functions (these occupy indices 0..N-1 in the
-    /// function index space, before local functions).
-    pub(super) num_imported_functions: usize,
-
-    /// Function import details: (module_name, func_name) for each imported function.
-    /// Indexed by import_idx (0..num_imported_functions-1).
-    pub(super) func_imports: Vec<(String, String)>,
-
     /// True when the current insertion point is unreachable code.
     ///
     /// Set to `true` by `Br`, `BrTable`, `Return`, and `Unreachable` instructions.
     /// Cleared by `start_real_block()`.
     ///
-    /// When `dead_code` is true, emitted instructions are discarded and branches
-    /// are NOT recorded as phi predecessors.
+    /// When `dead_code` is true, operators are skipped up to the `Else` or
+    /// `End` of the current frame (the spec's "unreachable" stack mode) and
+    /// branches are NOT recorded as phi predecessors.
     pub(super) dead_code: bool,
 
+    /// Number of `block`/`loop`/`if` opened inside dead code and not yet
+    /// closed. These open no control frame; their `Else`/`End` are skipped too.
+    pub(super) dead_depth: u32,
+
     /// Deferred loop phi sources from backward branches.
     ///
     /// Each entry is `(phi_dest, pred_block, src_var)`. When a `Br` targets a loop
@@ -424,6 +491,22 @@ pub struct IrBuilder {
     /// instead of directly mutating the phi instructions. Consumed at `End` of each
     /// Loop frame by `emit_loop_phis()`.
     pub(super) phi_patches: Vec<(UseVar, BlockId, UseVar)>,
+
+    /// Whether any function translated so far had atomic operators lowered.
+    /// Not reset between functions.
+    pub(super) lowered_atomics: bool,
+
+    /// Body offset of the operator being translated (0 for pre-decoded
+    /// operators).
+    pub(super) op_offset: usize,
+
+    /// Body offset at which each live block of the current function starts,
+    /// in start order (see [`IrBuilder::block_offsets`]).
+    pub(super) block_offsets: Vec<(BlockId, usize)>,
+
+    /// Deepest the value stack of the current function has been between
+    /// operators (see [`IrBuilder::max_stack_depth`]).
+    pub(super) max_stack_depth: usize,
 }
 
 impl IrBuilder {
@@ -441,15 +524,31 @@ impl IrBuilder {
             value_stack: Vec::new(),
             control_stack: Vec::new(),
             local_vars: Vec::new(),
-            func_signatures: Vec::new(),
-            type_signatures: Vec::new(),
-            num_imported_functions: 0,
-            func_imports: Vec::new(),
             dead_code: false,
+            dead_depth: 0,
             phi_patches: Vec::new(),
+            lowered_atomics: false,
+            op_offset: 0,
+            block_offsets: Vec::new(),
+            max_stack_depth: 0,
         }
     }
 
+    /// Where the live blocks of the function translated last start, as
+    /// offsets into its body bytecode.
+    ///
+    /// Blocks started in unreachable code, and blocks created without an
+    /// operator of their own (shared return blocks), have no entry.
+    pub fn block_offsets(&self) -> &[(BlockId, usize)] {
+        &self.block_offsets
+    }
+
+    /// Most values the Wasm operand stack of the function translated last
+    /// held at once.
+    pub fn max_stack_depth(&self) -> usize {
+        self.max_stack_depth
+    }
+
     /// Allocate a new SSA variable definition token.
     ///
     /// Returns a [`DefVar`] that must be consumed by exactly one call to
@@ -528,6 +627,73 @@ impl IrBuilder {
         }
     }
 
+    /// The top `count` values of the value stack, deepest first, left in place.
+    pub(super) fn peek_values(&self, count: usize, context: &str) -> Result<Vec<VarId>> {
+        let start = self
+            .value_stack
+            .len()
+            .checked_sub(count)
+            .ok_or_else(|| anyhow::anyhow!("Stack underflow for {}", context))?;
+        Ok(self.value_stack[start..]
+            .iter()
+            .map(|v| v.var_id())
+            .collect())
+    }
+
+    /// Pop the values a frame leaves on the stack at its `Else`/`End` and record
+    /// them in `incoming` as the results passed along the fall-through edge.
+    ///
+    /// In dead code the stack is polymorphic: anything above the frame's entry
+    /// height is discarded and nothing is recorded.
+    pub(super) fn record_fallthrough_result(
+        &mut self,
+        result_count: usize,
+        stack_height: usize,
+        incoming: &mut Vec<(BlockId, Vec<VarId>)>,
+    ) -> Result<()> {
+        if self.dead_code {
+            self.value_stack.truncate(stack_height);
+            return Ok(());
+        }
+        if result_count > 0 {
+            let values = self.peek_values(result_count, "block result")?;
+            self.value_stack
+                .truncate(self.value_stack.len() - result_count);
+            incoming.push((self.current_block, values));
+        }
+        Ok(())
+    }
+
+    /// Insert the phis merging a frame's result values at its join block, one
+    /// per result.
+    pub(super) fn insert_result_phi(
+        &mut self,
+        join_block: BlockId,
+        result_vars: &[UseVar],
+        incoming: &[(BlockId, Vec<VarId>)],
+    ) -> Result<()> {
+        if result_vars.is_empty() || incoming.is_empty() {
+            return Ok(());
+        }
+        let block = self
+            .blocks
+            .iter_mut()
+            .find(|b| b.id == join_block)
+            .ok_or_else(|| anyhow::anyhow!("join block {:?} not found in blocks", join_block))?;
+        let phis = result_vars
+            .iter()
+            .enumerate()
+            .map(|(i, result_var)| IrInstr::Phi {
+                dest: result_var.var_id(),
+                srcs: incoming
+                    .iter()
+                    .map(|(block, values)| (*block, values[i]))
+                    .collect(),
+            });
+        block.instructions.splice(0..0, phis);
+        Ok(())
+    }
+
     /// Record the current block and local state as a phi predecessor, only if code is reachable.
     ///
     /// This is used at join points (End of Block/Loop/If) to collect predecessor information
@@ -539,15 +705,69 @@ impl IrBuilder {
         }
     }
 
-    /// Translate a function from Wasm bytecode to IR.
+    /// Translate a function from already-decoded Wasm operators to IR.
     pub fn translate_function(
         &mut self,
         params: &[(ValType, WasmType)],
         locals: &[ValType],
-        return_type: Option<WasmType>,
+        results: Vec<WasmType>,
         operators: &[wasmparser::Operator],
         module_ctx: &ModuleContext,
     ) -> Result<IrFunction> {
+        let (param_vars, func_locals) = self.begin_function(params, locals, results.len())?;
+
+        // Translate each Wasm operator to IR
+        for op in operators {
+            self.translate_operator(op, module_ctx)
+                .with_context(|| format!("translating operator {:?}", op))?;
+            self.track_stack_depth();
+        }
+
+        Ok(self.finish_function(param_vars, func_locals, results))
+    }
+
+    /// Translate a function from its raw Wasm body bytecode to IR.
+    ///
+    /// Operators are decoded and translated one at a time, so the body is read
+    /// exactly once and no intermediate operator list is materialized.
+    pub fn translate_function_body(
+        &mut self,
+        params: &[(ValType, WasmType)],
+        locals: &[ValType],
+        results: Vec<WasmType>,
+        body: &[u8],
+        module_ctx: &ModuleContext,
+    ) -> Result<IrFunction> {
+        let (param_vars, func_locals) = self.begin_function(params, locals, results.len())?;
+
+        let mut reader = wasmparser::BinaryReader::new(body, 0);
+        while !reader.eof() {
+            self.op_offset = reader.original_position();
+            let op = reader.read_operator().context("failed to read operator")?;
+            self.translate_operator(&op, module_ctx)
+                .with_context(|| format!("translating operator {:?}", op))?;
+            self.track_stack_depth();
+        }
+
+        Ok(self.finish_function(param_vars, func_locals, results))
+    }
+
+    /// Record the value stack depth after an operator. Operators push their
+    /// results after popping their operands, so the depth between
+    /// operators is the deepest the stack gets.
+    fn track_stack_depth(&mut self) {
+        self.max_stack_depth = self.max_stack_depth.max(self.value_stack.len());
+    }
+
+    /// Reset per-function state, allocate params and locals, and open the
+    /// entry block. Returns the `(params, locals)` variable lists.
+    #[allow(clippy::type_complexity)]
+    fn begin_function(
+        &mut self,
+        params: &[(ValType, WasmType)],
+        locals: &[ValType],
+        result_count: usize,
+    ) -> Result<(Vec<(VarId, WasmType)>, Vec<(VarId, WasmType)>)> {
         // Reset per-function state so each function starts fresh
         self.blocks.clear();
         self.value_stack.clear();
@@ -557,11 +777,11 @@ impl IrBuilder {
         self.current_block = BlockId(0);
         self.local_vars.clear();
         self.dead_code = false;
+        self.dead_depth = 0;
         self.phi_patches.clear();
-        self.func_signatures = module_ctx.func_signatures.clone();
-        self.type_signatures = module_ctx.type_signatures.clone();
-        self.num_imported_functions = module_ctx.num_imported_functions;
-        self.func_imports = module_ctx.func_imports.clone();
+        self.op_offset = 0;
+        self.block_offsets.clear();
+        self.max_stack_depth = 0;
 
         // Allocate VarIds for all locals (params first, then declared locals).
         // This ensures local_index maps directly to the correct UseVar.
@@ -596,6 +816,7 @@ impl IrBuilder {
         // so we always begin with block 0 as the entry point.
         let entry = self.new_block(); // Returns BlockId(0)
         self.current_block = entry;
+        self.block_offsets.push((entry, 0));
         self.blocks.push(IrBlock {
             id: entry,
             instructions: Vec::new(),
@@ -603,43 +824,63 @@ impl IrBuilder {
         });
 
         // Push function-level control frame
-        self.push_block(entry, return_type);
+        self.push_block(entry, 0, result_count)?;
 
-        // Translate each Wasm operator to IR
-        for op in operators {
-            self.translate_operator(op)
-                .with_context(|| format!("translating operator {:?}", op))?;
-        }
+        Ok((param_vars, func_locals))
+    }
 
-        // Build final function
-        Ok(IrFunction {
-            params: param_vars,
-            locals: func_locals,
-            blocks: self.blocks.clone(),
-            entry_block: entry,
-            return_type,
+    /// Build the final function, moving the translated blocks out of the builder.
+    fn finish_function(
+        &mut self,
+        params: Vec<(VarId, WasmType)>,
+        locals: Vec<(VarId, WasmType)>,
+        results: Vec<WasmType>,
+    ) -> IrFunction {
+        IrFunction {
+            params,
+            locals,
+            blocks: std::mem::take(&mut self.blocks),
+            entry_block: BlockId(0),
+            results,
             type_idx: TypeIdx::new(0), // Set by enrich_ir_functions during assembly
-        })
+        }
     }
 
-    /// Allocate a result variable if the block has a result type.
-    fn alloc_result_var(&mut self, result_type: Option<WasmType>) -> Option<UseVar> {
-        if result_type.is_some() {
-            let (_, use_var) = self.new_pre_alloc_var();
-            Some(use_var)
-        } else {
-            None
-        }
+    /// Allocate one result variable per result of a block type.
+    fn alloc_result_vars(&mut self, result_count: usize) -> Vec<UseVar> {
+        (0..result_count)
+            .map(|_| self.new_pre_alloc_var().1)
+            .collect()
+    }
+
+    /// Value stack height below the top `param_count` values: the entry
+    /// height of a frame taking them as parameters.
+    fn height_below(&self, param_count: usize) -> Result<usize> {
+        self.value_stack
+            .len()
+            .checked_sub(param_count)
+            .ok_or_else(|| anyhow::anyhow!("Stack underflow for block parameters"))
     }
 
     /// Push a Block control frame onto the control stack.
-    pub(super) fn push_block(&mut self, end_block: BlockId, result_type: Option<WasmType>) {
-        let result_var = self.alloc_result_var(result_type);
+    ///
+    /// The block's `param_count` parameters stay on the value stack.
+    pub(super) fn push_block(
+        &mut self,
+        end_block: BlockId,
+        param_count: usize,
+        result_count: usize,
+    ) -> Result<()> {
+        let stack_height = self.height_below(param_count)?;
+        let result_vars = self.alloc_result_vars(result_count);
         self.control_stack.push(ControlFrame::Block {
             end_block,
-            result_var,
+            result_vars,
+            stack_height,
+            result_incoming: Vec::new(),
             branch_incoming: Vec::new(),
         });
+        Ok(())
     }
 
     /// Push a Loop control frame onto the control stack.
@@ -648,48 +889,74 @@ impl IrBuilder {
     /// to point to them. This ensures all code inside the loop body reads/writes through
     /// the phi vars, making backward-branch phi sources correct.
     ///
+    /// The loop's `param_count` parameters are replaced on the value stack the
+    /// same way, by phi vars merging their entry values with those carried by
+    /// backward branches.
+    ///
     /// Must be called while `self.current_block` still points to the pre-loop block
     /// (before switching to the loop header).
     pub(super) fn push_loop(
         &mut self,
         start_block: BlockId,
         end_block: BlockId,
-        result_type: Option<WasmType>,
-    ) {
-        let result_var = self.alloc_result_var(result_type);
+        param_count: usize,
+        result_count: usize,
+    ) -> Result<()> {
+        let stack_height = self.height_below(param_count)?;
+        let result_vars = self.alloc_result_vars(result_count);
         let locals_at_entry = self.local_vars.clone();
         let pre_loop_block = self.current_block;
         let loop_phi_vars: Vec<UseVar> = (0..self.local_vars.len())
             .map(|_| self.new_pre_alloc_var().1)
             .collect();
         self.local_vars.clone_from(&loop_phi_vars);
+        let params_at_entry = self.value_stack.split_off(stack_height);
+        let param_phi_vars: Vec<UseVar> = (0..param_count)
+            .map(|_| self.new_pre_alloc_var().1)
+            .collect();
+        self.value_stack.extend_from_slice(&param_phi_vars);
         self.control_stack.push(ControlFrame::Loop {
             start_block,
             pre_loop_block,
             end_block,
-            result_var,
+            result_vars,
+            stack_height,
+            result_incoming: Vec::new(),
             locals_at_entry,
             branch_incoming: Vec::new(),
             loop_phi_vars,
+            params_at_entry,
+            param_phi_vars,
         });
+        Ok(())
     }
 
     /// Push an If control frame onto the control stack.
+    ///
+    /// The if's `param_count` parameters stay on the value stack for the
+    /// then-branch.
     pub(super) fn push_if(
         &mut self,
         else_block: BlockId,
         end_block: BlockId,
-        result_type: Option<WasmType>,
-    ) {
-        let result_var = self.alloc_result_var(result_type);
+        param_count: usize,
+        result_count: usize,
+    ) -> Result<()> {
+        let stack_height = self.height_below(param_count)?;
+        let result_vars = self.alloc_result_vars(result_count);
         let locals_at_entry = self.local_vars.clone();
+        let params_at_entry = self.value_stack[stack_height..].to_vec();
         self.control_stack.push(ControlFrame::If {
             else_block,
             end_block,
-            result_var,
+            result_vars,
+            stack_height,
+            result_incoming: Vec::new(),
             locals_at_entry,
+            params_at_entry,
             branch_incoming: Vec::new(),
         });
+        Ok(())
     }
 
     /// Pop a control frame from the control stack.
@@ -754,9 +1021,42 @@ impl IrBuilder {
         Ok((target, is_loop, frame_idx))
     }
 
+    /// Whether relative `depth` names the function body's own label.
+    ///
+    /// Branching there leaves the function, so it is translated as a return
+    /// rather than as a jump to the frame's `end_block` (the entry block).
+    pub(super) fn targets_function(&self, depth: u32) -> bool {
+        depth as usize + 1 == self.control_stack.len()
+    }
+
+    /// Number of results of the function being translated.
+    pub(super) fn function_result_count(&self) -> usize {
+        self.control_stack
+            .first()
+            .map_or(0, |frame| frame.result_vars().len())
+    }
+
+    /// Add a block returning the values a branch to the function label carries
+    /// (the top of the value stack, one per function result).
+    ///
+    /// The current block is left unchanged.
+    pub(super) fn new_return_block(&mut self) -> Result<BlockId> {
+        let values = self.peek_values(self.function_result_count(), "branch value")?;
+        let id = self.new_block();
+        self.blocks.push(IrBlock {
+            id,
+            instructions: Vec::new(),
+            terminator: IrTerminator::Return { values },
+        });
+        Ok(id)
+    }
+
     /// Start a new block (create and switch to it).
     pub(super) fn start_block(&mut self, block_id: BlockId) {
         self.current_block = block_id;
+        if !self.dead_code {
+            self.block_offsets.push((block_id, self.op_offset));
+        }
         self.blocks.push(IrBlock {
             id: block_id,
             instructions: Vec::new(),
@@ -773,32 +1073,54 @@ impl IrBuilder {
         self.start_block(block_id);
     }
 
+    /// Mark the join block just started as unreachable: nothing live
+    /// branches to it, so it gets no entry in [`IrBuilder::block_offsets`].
+    pub(super) fn mark_dead_join(&mut self) {
+        self.dead_code = true;
+        if self
+            .block_offsets
+            .last()
+            .is_some_and(|(id, _)| *id == self.current_block)
+        {
+            self.block_offsets.pop();
+        }
+    }
+
     /// Record a forward branch to a non-loop frame.
     ///
-    /// Saves `(current_block, local_vars_snapshot)` in the target frame's `branch_incoming`.
+    /// Saves `(current_block, local_vars_snapshot)` in the target frame's `branch_incoming`
+    /// and, if the frame has results, the top of the value stack as the values the branch
+    /// carries. The values are only read: `br_if` leaves them for the fall-through path.
     /// No-op if `dead_code` is set (unreachable branches are not phi predecessors).
     ///
     /// `frame_idx` is the index into `self.control_stack`.
-    pub(super) fn record_forward_branch(&mut self, frame_idx: usize) {
+    pub(super) fn record_forward_branch(&mut self, frame_idx: usize) -> Result<()> {
         if self.dead_code {
-            return;
+            return Ok(());
         }
         let pred_block = self.current_block;
         let locals_snap = self.local_vars.clone();
-        self.control_stack[frame_idx]
-            .branch_incoming_mut()
-            .push((pred_block, locals_snap));
+        let result_count = self.control_stack[frame_idx].result_vars().len();
+        let values = self.peek_values(result_count, "branch value")?;
+        let frame = &mut self.control_stack[frame_idx];
+        if result_count > 0 {
+            frame.result_incoming_mut().push((pred_block, values));
+        }
+        frame.branch_incoming_mut().push((pred_block, locals_snap));
+        Ok(())
     }
 
     /// Record a backward branch to a loop frame (adds to `phi_patches`).
     ///
-    /// For each loop phi var, records `(phi_var, current_block, current_local_value)`.
+    /// For each loop phi var, records `(phi_var, current_block, current_local_value)`,
+    /// and for each loop parameter, `(param_phi_var, current_block, value)` with the
+    /// values the branch carries on top of the value stack.
     /// No-op if `dead_code` is set.
     ///
     /// `frame_idx` is the index into `self.control_stack` for the Loop frame.
-    pub(super) fn record_loop_back_branch(&mut self, frame_idx: usize) {
+    pub(super) fn record_loop_back_branch(&mut self, frame_idx: usize) -> Result<()> {
         if self.dead_code {
-            return;
+            return Ok(());
         }
         let pred_block = self.current_block;
         // Clone to avoid borrow conflict (local_vars is also in self)
@@ -807,6 +1129,13 @@ impl IrBuilder {
             let src_var = self.local_vars[local_idx];
             self.phi_patches.push((phi_var, pred_block, src_var));
         }
+        let param_phi_vars = self.control_stack[frame_idx].param_phi_vars().to_vec();
+        let start = self.height_below(param_phi_vars.len())?;
+        for (i, &phi_var) in param_phi_vars.iter().enumerate() {
+            let src_var = self.value_stack[start + i];
+            self.phi_patches.push((phi_var, pred_block, src_var));
+        }
+        Ok(())
     }
 
     /// Insert SSA phi nodes at a join block for locals with differing predecessor values.
@@ -877,8 +1206,10 @@ impl IrBuilder {
     /// Emit phi instructions for a loop frame into its header block.
     ///
     /// Called at `End` of a Loop frame (after `pop_control`). Inserts `IrInstr::Phi`
-    /// at the start of the loop header (`start_block`) for each local. Sources come from:
-    /// 1. The pre-loop predecessor (`pre_loop_block`, `locals_at_entry`).
+    /// at the start of the loop header (`start_block`) for each local, then for each
+    /// loop parameter. Sources come from:
+    /// 1. The pre-loop predecessor (`pre_loop_block`, `locals_at_entry` and
+    ///    `params_at_entry`).
     /// 2. All backward branches recorded in `self.phi_patches` for this loop's phi vars.
     ///
     /// Consumes the relevant entries from `self.phi_patches`.
@@ -891,12 +1222,15 @@ impl IrBuilder {
                 pre_loop_block,
                 loop_phi_vars,
                 locals_at_entry,
+                param_phi_vars,
+                params_at_entry,
                 ..
             } => (
                 *start_block,
                 *pre_loop_block,
-                loop_phi_vars,
-                locals_at_entry,
+                // Parameters are merged exactly like locals
+                [loop_phi_vars.as_slice(), param_phi_vars].concat(),
+                [locals_at_entry.as_slice(), params_at_entry].concat(),
             ),
             _ => return,
         };
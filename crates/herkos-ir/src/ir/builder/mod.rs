@@ -0,0 +1,757 @@
+//! # IR Builder
+//!
+//! Translates a `ParsedModule` (parsed WebAssembly) into a `ModuleInfo` (SSA IR).
+//!
+//! ## Pipeline overview
+//!
+//! ```text
+//! ParsedModule
+//!      │
+//!      ├─[analysis]──────────────────────────────────────────────┐
+//!      │  extract_memory_info()  ─► MemoryInfo                   │
+//!      │  extract_table_info()   ─► TableInfo                    │
+//!      │  build_type_mappings()  ─► canonical_type, type_sigs    │
+//!      │  intern_import_names()  ─► shared Arc<str> names        │
+//!      │  build_imported_globals() ─► Vec<ImportedGlobalDef>     │
+//!      │                                                         │
+//!      └─[translate]─────────────────────────────────────────┐   │
+//!         build_ir_functions()                               │   │
+//!           └── for each local function:                     │   │
+//!                 IrBuilder::translate_function_body()       │   │
+//!                   └── for each decoded Operator:           │   │
+//!                         translate_operator()               │   │
+//!                           ├── emit_binop/emit_unop         │   │
+//!                           ├── emit_load/emit_store         │   │
+//!                           └── control-flow ops             │   │
+//!                ─► Vec<IrFunction>                          │   │
+//!                                                            │   │
+//! ◄───────────────────────────────[assembly]─────────────────┘───┘
+//!   assemble_module_metadata()
+//!     ├── build_globals()
+//!     ├── build_data_segments()
+//!     ├── build_element_segments()
+//!     ├── build_function_exports()
+//!     ├── build_function_type_signatures()
+//!     ├── build_call_indirect_signatures()
+//!     └── build_function_imports()
+//!          ─► ModuleInfo  ──► codegen
+//! ```
+//!
+//! ## Architecture
+//!
+//! The builder is split into four sub-modules:
+//!
+//! | Module       | Responsibility                                               |
+//! |--------------|--------------------------------------------------------------|
+//! | [`core`]     | `IrBuilder` state machine, SSA allocation, control stack     |
+//! | [`translate`]| Wasm operator → IR instruction dispatch                      |
+//! | [`analysis`] | Extract per-section metadata from `ParsedModule`             |
+//! | [`assembly`] | Assemble extracted pieces into a final `ModuleInfo`          |
+//!
+//! ### Flow
+//!
+//! 1. **Analysis phase**: Module structure is examined to extract fixed information
+//!    (memory size, imported functions, type signatures, etc.)
+//! 2. **Translation phase**: For each function, an `IrBuilder` walks the Wasm bytecode,
+//!    maintaining a simulated Wasm value stack in SSA form, converting each operator
+//!    to equivalent IR instructions and control flow.
+//! 3. **Assembly phase**: All pieces are combined into the final `ModuleInfo`.
+//!
+//! The `IrBuilder` is a stack machine interpreter that:
+//! - Simulates the WebAssembly evaluation stack using SSA variables (`VarId`)
+//! - Maintains a control-flow stack tracking nested `block`/`loop`/`if` frames
+//! - Allocates new basic blocks as needed (loops, branches, control structures)
+//! - Emits IR instructions to the current block
+//! - Terminates blocks with control-flow terminators (`Jump`, `BranchIf`, `Return`, etc.)
+
+mod analysis;
+mod assembly;
+mod atomics;
+pub mod core;
+mod translate;
+
+pub use core::ModuleContext;
+
+use super::types::{BlockId, IrBlock, IrTerminator, LocalFuncIdx, ModuleInfo};
+use crate::parser::ParsedModule;
+use anyhow::{bail, Result};
+
+/// Options that change the IR built for a module.
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// Maximum memory pages, used when the module's memory declares no
+    /// maximum
+    pub max_pages: usize,
+    /// Lower atomic memory operations (threads proposal) to plain loads and
+    /// stores, with fences as no-ops. Only sound when a single thread runs
+    /// the module.
+    pub lower_atomics: bool,
+    /// Evaluate the [`CTORS_EXPORT`] export at transpile time, like a start
+    /// function, and make it a no-op, so the static constructors of C and
+    /// C++ modules have run when the module is constructed.
+    pub call_ctors: bool,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            max_pages: 256,
+            lower_atomics: false,
+            call_ctors: false,
+        }
+    }
+}
+
+/// Build complete module metadata from a parsed WebAssembly module.
+///
+/// This is the main entry point for IR construction, coordinating all
+/// the intermediate steps needed to produce a fully-formed `ModuleInfo`.
+/// Code-generation settings (`wasmtime_adapter`, `yield_points`, ...) are
+/// left at their defaults for the caller to fill in.
+pub fn build_module_info(parsed: &ParsedModule, options: &BuildOptions) -> Result<ModuleInfo> {
+    // Analyze module structure (memory, table, types)
+    let mem_info = analysis::extract_memory_info(parsed, options)?;
+    let table_info = analysis::extract_table_info(parsed);
+    let canonical_type = analysis::build_canonical_type_mapping(parsed);
+    let type_sigs = analysis::build_type_signatures(parsed);
+
+    // Analyze imports
+    let import_names = analysis::intern_import_names(parsed);
+    let imported_globals = analysis::build_imported_globals(parsed, &import_names);
+    let num_imported_functions = parsed.num_imported_functions;
+
+    // Translate WebAssembly to intermediate representation
+    let (ir_functions, layouts, lowered_atomics) = analysis::build_ir_functions(
+        parsed,
+        &type_sigs,
+        &import_names,
+        num_imported_functions,
+        options.lower_atomics,
+    )?;
+
+    // Assemble module metadata for code generation
+    let mut info = assembly::assemble_module_metadata(
+        parsed,
+        &mem_info,
+        &table_info,
+        canonical_type,
+        ir_functions,
+        num_imported_functions as usize,
+        &import_names,
+        imported_globals,
+    )?;
+    info.lowered_atomics = lowered_atomics;
+    for (symbol, layout) in info.func_symbols.iter_mut().zip(layouts) {
+        symbol.block_offsets = layout.block_offsets;
+        symbol.max_stack_depth = layout.max_stack_depth;
+    }
+
+    // The start function's effects become the initial state, or, when they
+    // cannot be computed here, the constructor calls it
+    if let Some(start) = parsed.start {
+        let Some(local) = start.checked_sub(num_imported_functions) else {
+            bail!("the start function is an import, which herkos cannot call during construction");
+        };
+        let local = LocalFuncIdx::new(local as usize);
+        if let Err(err) = super::eval::bake_start(&mut info, local) {
+            if info.has_memory_import || info.has_table_import {
+                bail!(
+                    "start function {start} cannot be evaluated at transpile time ({err}), \
+                     and `new()` has no imported memory or table to call it with"
+                );
+            }
+            info.start = Some(local);
+        }
+    }
+
+    if options.call_ctors {
+        bake_ctors(&mut info)?;
+    }
+
+    Ok(info)
+}
+
+/// The export wasm-ld gives a module's static constructors when the module
+/// has no `_start` or `_initialize` to call them.
+pub const CTORS_EXPORT: &str = "__wasm_call_ctors";
+
+/// Evaluate the [`CTORS_EXPORT`] function into the initial state and empty
+/// its body, so a host that still calls it does not run the constructors
+/// twice.
+fn bake_ctors(info: &mut ModuleInfo) -> Result<()> {
+    let Some(func) = info.ctors() else {
+        bail!("the module exports no `{CTORS_EXPORT}` function to call");
+    };
+    let ir_func = &info.ir_functions[func.as_usize()];
+    if !ir_func.params.is_empty() || !ir_func.results.is_empty() {
+        bail!("`{CTORS_EXPORT}` must take and return nothing");
+    }
+    if let Err(err) = super::eval::bake_start(info, func) {
+        bail!("`{CTORS_EXPORT}` cannot be evaluated at transpile time: {err}");
+    }
+    let ir_func = &mut info.ir_functions[func.as_usize()];
+    ir_func.locals.clear();
+    ir_func.blocks = vec![IrBlock {
+        id: BlockId(0),
+        instructions: Vec::new(),
+        terminator: IrTerminator::Return { values: Vec::new() },
+    }];
+    info.ctors_evaluated = true;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::types::IrInstr;
+    use crate::ir::types::Toolchain;
+    use crate::ir::types::WasmType;
+    use wasmparser::ValType;
+
+    /// Test the invariant: entry_block is always BlockId(0)
+    #[test]
+    fn entry_block_is_always_block_zero() {
+        let mut builder = core::IrBuilder::new();
+
+        // Simple function: fn add(a: i32, b: i32) -> i32 { a + b }
+        let params = vec![(ValType::I32, WasmType::I32), (ValType::I32, WasmType::I32)];
+        let operators = vec![
+            wasmparser::Operator::LocalGet { local_index: 0 },
+            wasmparser::Operator::LocalGet { local_index: 1 },
+            wasmparser::Operator::I32Add,
+            wasmparser::Operator::End,
+        ];
+
+        let module_ctx = ModuleContext::default();
+
+        let ir_func = builder
+            .translate_function(&params, &[], vec![WasmType::I32], &operators, &module_ctx)
+            .expect("translation should succeed");
+
+        // INVARIANT CHECK: entry_block must be BlockId(0)
+        assert_eq!(
+            ir_func.entry_block,
+            crate::ir::types::BlockId(0),
+            "entry_block must always be BlockId(0)"
+        );
+
+        // Additional sanity checks
+        assert!(
+            !ir_func.blocks.is_empty(),
+            "function must have at least one block"
+        );
+        assert_eq!(
+            ir_func.blocks[0].id,
+            crate::ir::types::BlockId(0),
+            "first block in the blocks vector must be BlockId(0)"
+        );
+    }
+
+    /// Test that entry_block == BlockId(0) even for void functions
+    #[test]
+    fn entry_block_is_zero_for_void_function() {
+        let mut builder = core::IrBuilder::new();
+
+        // Void function: fn noop() { }
+        let operators = vec![wasmparser::Operator::Nop, wasmparser::Operator::End];
+
+        let module_ctx = ModuleContext::default();
+
+        let ir_func = builder
+            .translate_function(&[], &[], Vec::new(), &operators, &module_ctx)
+            .expect("translation should succeed");
+
+        assert_eq!(
+            ir_func.entry_block,
+            crate::ir::types::BlockId(0),
+            "entry_block must be BlockId(0) even for void functions"
+        );
+    }
+
+    /// Test that entry_block == BlockId(0) for functions with locals
+    #[test]
+    fn entry_block_is_zero_with_locals() {
+        let mut builder = core::IrBuilder::new();
+
+        let params = vec![(ValType::I32, WasmType::I32)];
+        let locals = vec![ValType::I32, ValType::I32];
+        let operators = vec![
+            wasmparser::Operator::I32Const { value: 42 },
+            wasmparser::Operator::End,
+        ];
+
+        let module_ctx = ModuleContext::default();
+
+        let ir_func = builder
+            .translate_function(
+                &params,
+                &locals,
+                vec![WasmType::I32],
+                &operators,
+                &module_ctx,
+            )
+            .expect("translation should succeed");
+
+        assert_eq!(
+            ir_func.entry_block,
+            crate::ir::types::BlockId(0),
+            "entry_block must be BlockId(0) regardless of locals"
+        );
+    }
+
+    /// Test that local variables are properly tracked and distinguished from parameters
+    #[test]
+    fn local_variables_separate_from_params() {
+        let mut builder = core::IrBuilder::new();
+
+        // Function: (param i32) (result i32)
+        //   (local i32)
+        //   local.get 0      ;; param
+        //   local.set 1      ;; store to local (index 1)
+        //   local.get 1      ;; get local back
+        let params = vec![(ValType::I32, WasmType::I32)];
+        let locals = vec![ValType::I32];
+        let operators = vec![
+            wasmparser::Operator::LocalGet { local_index: 0 },
+            wasmparser::Operator::LocalSet { local_index: 1 },
+            wasmparser::Operator::LocalGet { local_index: 1 },
+            wasmparser::Operator::End,
+        ];
+
+        let module_ctx = ModuleContext::default();
+
+        let ir_func = builder
+            .translate_function(
+                &params,
+                &locals,
+                vec![WasmType::I32],
+                &operators,
+                &module_ctx,
+            )
+            .expect("translation should succeed");
+
+        // Parameter 0 and local 1 must be different VarIds
+        let param_var = ir_func.params[0].0;
+        let local_var = ir_func.locals[0].0;
+        assert_ne!(
+            param_var, local_var,
+            "param and local must have distinct VarIds"
+        );
+
+        // Verify local tracking
+        assert_eq!(
+            ir_func.locals.len(),
+            1,
+            "should have exactly 1 declared local"
+        );
+        assert_eq!(
+            ir_func.locals[0].1,
+            WasmType::I32,
+            "local should have type i32"
+        );
+
+        // Verify params are still tracked
+        assert_eq!(ir_func.params.len(), 1, "should have exactly 1 param");
+        assert_eq!(
+            ir_func.params[0].1,
+            WasmType::I32,
+            "param should have type i32"
+        );
+    }
+
+    /// Test with multiple locals of different types
+    #[test]
+    fn multiple_locals_different_types() {
+        let mut builder = core::IrBuilder::new();
+
+        // Function: (param i32) (result i32)
+        //   (local i32 i64 f32)
+        //   local.get 0
+        let params = vec![(ValType::I32, WasmType::I32)];
+        let locals = vec![ValType::I32, ValType::I64, ValType::F32];
+        let operators = vec![
+            wasmparser::Operator::LocalGet { local_index: 0 },
+            wasmparser::Operator::End,
+        ];
+
+        let module_ctx = ModuleContext::default();
+
+        let ir_func = builder
+            .translate_function(
+                &params,
+                &locals,
+                vec![WasmType::I32],
+                &operators,
+                &module_ctx,
+            )
+            .expect("translation should succeed");
+
+        // Verify all locals are tracked with correct types
+        assert_eq!(ir_func.locals.len(), 3);
+        assert_eq!(ir_func.locals[0].1, WasmType::I32);
+        assert_eq!(ir_func.locals[1].1, WasmType::I64);
+        assert_eq!(ir_func.locals[2].1, WasmType::F32);
+
+        // All locals should have different VarIds
+        let var_ids: Vec<crate::ir::types::VarId> =
+            ir_func.locals.iter().map(|(v, _)| *v).collect();
+        assert_eq!(var_ids[0], var_ids[0]);
+        assert_ne!(var_ids[0], var_ids[1]);
+        assert_ne!(var_ids[1], var_ids[2]);
+    }
+
+    /// Import names and signature parameter lists are interned: every
+    /// `FuncImport` and `CallImport` site points at the same allocation.
+    #[test]
+    fn import_names_and_params_are_interned() {
+        use crate::ir::types::IrInstr;
+        use std::sync::Arc;
+
+        let wasm = wat::parse_str(
+            r#"(module
+                (type $t (func (param i32)))
+                (import "env" "log" (func $log (type $t)))
+                (import "env" "warn" (func $warn (type $t)))
+                (func
+                    i32.const 1
+                    call $log
+                    i32.const 2
+                    call $log))"#,
+        )
+        .unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+
+        let [log, warn] = &info.func_imports[..] else {
+            panic!("expected two imports");
+        };
+        assert!(Arc::ptr_eq(&log.module_name, &warn.module_name));
+        assert!(Arc::ptr_eq(&log.params, &warn.params));
+        assert!(Arc::ptr_eq(&log.params, &info.type_signatures[0].params));
+
+        let call_names: Vec<&Arc<str>> = info.ir_functions[0]
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .filter_map(|instr| match instr {
+                IrInstr::CallImport { func_name, .. } => Some(func_name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(call_names.len(), 2);
+        assert!(call_names
+            .iter()
+            .all(|name| Arc::ptr_eq(name, &log.func_name)));
+    }
+
+    /// Streaming translation from body bytes matches translation from a
+    /// pre-decoded operator list.
+    #[test]
+    fn translate_function_body_matches_decoded_operators() {
+        let params = vec![(ValType::I32, WasmType::I32), (ValType::I32, WasmType::I32)];
+        let operators = vec![
+            wasmparser::Operator::LocalGet { local_index: 0 },
+            wasmparser::Operator::LocalGet { local_index: 1 },
+            wasmparser::Operator::I32Add,
+            wasmparser::Operator::End,
+        ];
+        // local.get 0, local.get 1, i32.add, end
+        let body = [0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b];
+        let module_ctx = ModuleContext::default();
+
+        let mut builder = core::IrBuilder::new();
+        let decoded = builder
+            .translate_function(&params, &[], vec![WasmType::I32], &operators, &module_ctx)
+            .unwrap();
+        let streamed = builder
+            .translate_function_body(&params, &[], vec![WasmType::I32], &body, &module_ctx)
+            .unwrap();
+
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", streamed));
+    }
+
+    /// Blocks are located at the operator they start at; code after a
+    /// branch and joins nothing reaches have no location.
+    #[test]
+    fn block_offsets_locate_live_blocks() {
+        // block, br 0, end, loop, br 0, end, end
+        let body = [
+            0x02, 0x40, 0x0c, 0x00, 0x0b, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b,
+        ];
+        let mut builder = core::IrBuilder::new();
+        builder
+            .translate_function_body(&[], &[], Vec::new(), &body, &ModuleContext::default())
+            .unwrap();
+        let offsets: Vec<usize> = builder.block_offsets().iter().map(|(_, o)| *o).collect();
+        // Entry, the join after the block, the loop header
+        assert_eq!(offsets, [0, 4, 5]);
+    }
+
+    #[test]
+    fn max_stack_depth_counts_operands_held_at_once() {
+        // i32.const 1, i32.const 2, i32.const 3, i32.add, i32.add, drop, end
+        let body = [0x41, 0x01, 0x41, 0x02, 0x41, 0x03, 0x6a, 0x6a, 0x1a, 0x0b];
+        let mut builder = core::IrBuilder::new();
+        builder
+            .translate_function_body(&[], &[], Vec::new(), &body, &ModuleContext::default())
+            .unwrap();
+        assert_eq!(builder.max_stack_depth(), 3);
+    }
+
+    #[test]
+    fn translate_function_body_rejects_truncated_bytecode() {
+        let mut builder = core::IrBuilder::new();
+        // i32.const with a missing LEB128 immediate
+        let err = builder
+            .translate_function_body(&[], &[], Vec::new(), &[0x41], &ModuleContext::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("failed to read operator"));
+    }
+
+    #[test]
+    fn dead_code_is_not_translated() {
+        use wasmparser::{BlockType, Operator};
+        let mut builder = core::IrBuilder::new();
+        // unreachable; i32.add; block (i32.const 1; drop) end; end
+        let operators = vec![
+            Operator::Unreachable,
+            Operator::I32Add,
+            Operator::Block {
+                blockty: BlockType::Empty,
+            },
+            Operator::I32Const { value: 1 },
+            Operator::Drop,
+            Operator::End,
+            Operator::End,
+        ];
+        let ir_func = builder
+            .translate_function(
+                &[],
+                &[],
+                vec![WasmType::I32],
+                &operators,
+                &ModuleContext::default(),
+            )
+            .expect("dead code uses a polymorphic stack");
+
+        assert!(ir_func.blocks.iter().all(|b| b.instructions.is_empty()));
+        assert!(ir_func
+            .blocks
+            .iter()
+            .all(|b| matches!(b.terminator, crate::ir::types::IrTerminator::Unreachable)));
+    }
+
+    #[test]
+    fn block_result_is_a_phi_over_each_incoming_edge() {
+        use wasmparser::{BlockType, Operator};
+        let mut builder = core::IrBuilder::new();
+        // block (result i32) i32.const 10; local.get 0; br_if 0; drop; i32.const 20 end; end
+        let operators = vec![
+            Operator::Block {
+                blockty: BlockType::Type(ValType::I32),
+            },
+            Operator::I32Const { value: 10 },
+            Operator::LocalGet { local_index: 0 },
+            Operator::BrIf { relative_depth: 0 },
+            Operator::Drop,
+            Operator::I32Const { value: 20 },
+            Operator::End,
+            Operator::End,
+        ];
+        let ir_func = builder
+            .translate_function(
+                &[(ValType::I32, WasmType::I32)],
+                &[],
+                vec![WasmType::I32],
+                &operators,
+                &ModuleContext::default(),
+            )
+            .unwrap();
+
+        let phis: Vec<_> = ir_func
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .filter_map(|i| match i {
+                IrInstr::Phi { srcs, .. } => Some(srcs.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(phis, vec![2], "br_if and fall-through both feed the result");
+    }
+
+    #[test]
+    fn toolchains_come_from_the_producers_section() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (@producers (language "C11" "") (processed-by "clang" "18") (sdk "emscripten" "3.1")))"#,
+        )
+        .unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+        assert_eq!(info.toolchains, [Toolchain::Emscripten]);
+        assert!(info.built_with(Toolchain::Emscripten));
+        assert!(!info.built_with(Toolchain::WasmBindgen));
+    }
+
+    #[test]
+    fn globals_take_field_names_from_the_name_section() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "base" (global $base i32))
+                (global $__stack_pointer (mut i32) (i32.const 0))
+                (global $HeapBase (mut i32) (i32.const 0))
+                (global (mut i32) (i32.const 0))
+                (global $type (mut i32) (i32.const 0))
+                (global $g0 (mut i32) (i32.const 0))
+                (global $stack.pointer (mut i32) (i32.const 0)))"#,
+        )
+        .unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+
+        let fields: Vec<&str> = info.globals.iter().map(|g| g.field.as_str()).collect();
+        // Unnamed, keyword, positional-looking and duplicate names fall back
+        // to the local index.
+        assert_eq!(
+            fields,
+            ["stack_pointer", "heap_base", "g2", "g3", "g4", "g5"]
+        );
+    }
+
+    fn build_wat(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        build_module_info(&parsed, &BuildOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn calls_and_returns_carry_every_result() {
+        use crate::ir::types::IrTerminator;
+        let info = build_wat(
+            r#"(module
+                (func $pair (result i32 i64) (i32.const 1) (i64.const 2))
+                (func (result i64 i32) (local i32 i64)
+                    (call $pair)
+                    (local.set 1)
+                    (local.set 0)
+                    (local.get 1)
+                    (local.get 0))
+                (func (param i32) (result i32 i32)
+                    (call_indirect (result i32 i32) (local.get 0)))
+                (table 1 funcref))"#,
+        );
+        assert_eq!(info.ir_functions[0].results, [WasmType::I32, WasmType::I64]);
+        assert_eq!(info.ir_functions[1].results, [WasmType::I64, WasmType::I32]);
+
+        let dests: Vec<usize> = info.ir_functions[1..]
+            .iter()
+            .flat_map(|f| f.blocks.iter().flat_map(|b| &b.instructions))
+            .filter_map(|instr| match instr {
+                IrInstr::Call { dests, .. } | IrInstr::CallIndirect { dests, .. } => {
+                    Some(dests.len())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dests, [2, 2]);
+
+        for func in &info.ir_functions {
+            let returns: Vec<usize> = func
+                .blocks
+                .iter()
+                .filter_map(|b| match &b.terminator {
+                    IrTerminator::Return { values } => Some(values.len()),
+                    _ => None,
+                })
+                .collect();
+            assert!(!returns.is_empty() && returns.iter().all(|n| *n == 2));
+        }
+    }
+
+    #[test]
+    fn multi_value_blocks_take_params_and_merge_each_result() {
+        let info = build_wat(
+            r#"(module
+                (func (param i32) (result i32 i32)
+                    (i32.const 1)
+                    (i32.const 2)
+                    (block (param i32 i32) (result i32 i32)
+                        (local.get 0)
+                        (br_if 0)
+                        (i32.add)
+                        (i32.const 3))))"#,
+        );
+        // One phi per result, each merging the br_if and the fall-through
+        let phis: Vec<usize> = info.ir_functions[0]
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .filter_map(|i| match i {
+                IrInstr::Phi { srcs, .. } => Some(srcs.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(phis, [2, 2]);
+    }
+
+    #[test]
+    fn loop_params_become_phis() {
+        // Counts its parameter down to zero, carried on the stack
+        let info = build_wat(
+            r#"(module
+                (func (param i32) (result i32)
+                    (local.get 0)
+                    (loop (param i32) (result i32)
+                        (i32.const 1)
+                        (i32.sub)
+                        (local.tee 0)
+                        (local.get 0)
+                        (br_if 0))))"#,
+        );
+        let phis: Vec<usize> = info.ir_functions[0]
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .filter_map(|i| match i {
+                IrInstr::Phi { srcs, .. } => Some(srcs.len()),
+                _ => None,
+            })
+            .collect();
+        // The local and the loop parameter merge the entry and the back edge
+        assert_eq!(phis.iter().filter(|n| **n == 2).count(), 2);
+    }
+
+    #[test]
+    fn call_ctors_needs_an_evaluable_export() {
+        let build = |wat: &str| {
+            let wasm = wat::parse_str(wat).unwrap();
+            let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+            let options = BuildOptions {
+                call_ctors: true,
+                ..Default::default()
+            };
+            build_module_info(&parsed, &options).map_err(|e| e.to_string())
+        };
+        let err = build("(module (func (export \"f\")))").unwrap_err();
+        assert!(err.contains("exports no `__wasm_call_ctors`"), "{err}");
+        let err = build(
+            r#"(module (import "env" "init" (func $init))
+                (func (export "__wasm_call_ctors") (call $init)))"#,
+        )
+        .unwrap_err();
+        assert!(err.contains("calls import `env.init`"), "{err}");
+
+        let info = build(
+            r#"(module (global $g (mut i32) (i32.const 0))
+                (func (export "__wasm_call_ctors") (global.set $g (i32.const 5))))"#,
+        )
+        .unwrap();
+        assert!(info.ctors_evaluated);
+        assert!(info.ir_functions[0].blocks[0].instructions.is_empty());
+        assert!(matches!(
+            info.globals[0].init_value,
+            crate::ir::GlobalInit::I32(5)
+        ));
+    }
+}
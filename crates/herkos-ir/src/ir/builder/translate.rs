@@ -41,7 +41,8 @@
 //! 2. Call the appropriate record method *before* terminating the block, so the
 //!    snapshot captures the local state at the branch point.
 //! 3. Terminate the block with the appropriate `IrTerminator`.
-//! 4. Mark subsequent code as dead (`dead_code = true`) for unconditional branches.
+//! 4. Mark subsequent code as dead (`dead_code = true`) for unconditional branches;
+//!    it is skipped up to the `Else`/`End` of the enclosing frame.
 //!
 //! ### Protocol for join points (`End` of Block / If / Else / Loop)
 //!
@@ -115,13 +116,17 @@
 //! ```
 
 use super::super::types::*;
-use super::core::IrBuilder;
+use super::atomics::AtomicOp;
+use super::core::{IrBuilder, ModuleContext};
 use anyhow::{bail, Context, Result};
 use wasmparser::Operator;
 
 impl IrBuilder {
     /// Translate a single Wasm operator to IR instructions.
-    pub(super) fn translate_operator(&mut self, op: &Operator) -> Result<()> {
+    pub(super) fn translate_operator(&mut self, op: &Operator, ctx: &ModuleContext) -> Result<()> {
+        if self.dead_code && self.skip_dead_operator(op) {
+            return Ok(());
+        }
         match op {
             // Constants
             Operator::I32Const { value } => {
@@ -405,20 +410,25 @@ impl IrBuilder {
                 // instructions (e.g. End for enclosing blocks) don't corrupt
                 // the already-terminated block's control flow.
                 let dead_block = self.new_block();
-                self.start_block(dead_block);
                 self.dead_code = true;
+                self.start_block(dead_block);
             }
 
             // End (end of function or block)
             Operator::End => {
                 if self.control_stack.len() <= 1 {
-                    // End of function - treat as implicit return
-                    self.emit_return()?;
+                    // End of function - treat as implicit return (unless the
+                    // body already left through `return`/`br`/`unreachable`)
+                    if !self.dead_code {
+                        self.emit_return()?;
+                    }
                 } else {
                     let frame = self.pop_control()?;
 
-                    // Extract result_var before consuming frame in the match below.
-                    let result_var = frame.result_var();
+                    // Extract result_vars before consuming frame in the match below.
+                    let result_vars = frame.result_vars().to_vec();
+                    let result_count = result_vars.len();
+                    let stack_height = frame.stack_height();
 
                     // Emit loop phi instructions if this is a Loop frame.
                     // Called here (before the match consumes `frame`) because emit_loop_phis
@@ -429,9 +439,10 @@ impl IrBuilder {
                         super::core::ControlFrame::If {
                             else_block,
                             end_block,
-                            result_var: rv,
+                            mut result_incoming,
                             mut branch_incoming,
                             locals_at_entry,
+                            params_at_entry,
                             ..
                         } => {
                             // === IF without ELSE ===
@@ -445,14 +456,11 @@ impl IrBuilder {
                             self.push_predecessor_if_live(&mut branch_incoming);
 
                             // Assign result if needed (then-branch fall-through)
-                            if let Some(result_var) = rv {
-                                if let Some(stack_value) = self.value_stack.pop() {
-                                    self.emit_void(IrInstr::Assign {
-                                        dest: result_var.var_id(),
-                                        src: stack_value.var_id(),
-                                    });
-                                }
-                            }
+                            self.record_fallthrough_result(
+                                result_count,
+                                stack_height,
+                                &mut result_incoming,
+                            )?;
 
                             // Terminate then-branch (if reachable)
                             self.terminate_if_live(IrTerminator::Jump { target: end_block });
@@ -463,8 +471,14 @@ impl IrBuilder {
                             // instructions; we just terminate it directly.
                             self.start_block(else_block);
                             self.terminate(IrTerminator::Jump { target: end_block });
-                            // The implicit else carries the pre-if local state.
+                            // The implicit else carries the pre-if local state, and
+                            // passes the if's parameters through as its results
+                            // (validation makes their types equal).
                             branch_incoming.push((else_block, locals_at_entry.clone()));
+                            if result_count > 0 {
+                                let params = params_at_entry.iter().map(|v| v.var_id()).collect();
+                                result_incoming.push((else_block, params));
+                            }
 
                             // Restore local_vars to pre-if state before computing phis
                             // (preds already captured the necessary snapshots above).
@@ -476,15 +490,16 @@ impl IrBuilder {
                             // Insert phi nodes for locals with differing predecessor values.
                             // If no live predecessors, mark as dead code.
                             if branch_incoming.is_empty() {
-                                self.dead_code = true;
+                                self.mark_dead_join();
                             } else {
                                 self.insert_phis_at_join(end_block, &branch_incoming)?;
+                                self.insert_result_phi(end_block, &result_vars, &result_incoming)?;
                             }
                         }
 
                         super::core::ControlFrame::Else {
                             end_block,
-                            result_var: rv,
+                            mut result_incoming,
                             branch_incoming,
                             then_pred_info,
                             ..
@@ -505,14 +520,11 @@ impl IrBuilder {
                             preds.extend(branch_incoming.clone());
 
                             // Assign result if needed (else-branch fall-through)
-                            if let Some(result_var) = rv {
-                                if let Some(stack_value) = self.value_stack.pop() {
-                                    self.emit_void(IrInstr::Assign {
-                                        dest: result_var.var_id(),
-                                        src: stack_value.var_id(),
-                                    });
-                                }
-                            }
+                            self.record_fallthrough_result(
+                                result_count,
+                                stack_height,
+                                &mut result_incoming,
+                            )?;
 
                             // Terminate else-branch (if reachable)
                             self.terminate_if_live(IrTerminator::Jump { target: end_block });
@@ -521,15 +533,16 @@ impl IrBuilder {
                             self.start_real_block(end_block);
 
                             if preds.is_empty() {
-                                self.dead_code = true;
+                                self.mark_dead_join();
                             } else {
                                 self.insert_phis_at_join(end_block, &preds)?;
+                                self.insert_result_phi(end_block, &result_vars, &result_incoming)?;
                             }
                         }
 
                         super::core::ControlFrame::Loop {
                             end_block,
-                            result_var: rv,
+                            mut result_incoming,
                             mut branch_incoming,
                             ..
                         } => {
@@ -541,14 +554,11 @@ impl IrBuilder {
                             self.push_predecessor_if_live(&mut branch_incoming);
 
                             // Assign result if needed (loop fall-through)
-                            if let Some(result_var) = rv {
-                                if let Some(stack_value) = self.value_stack.pop() {
-                                    self.emit_void(IrInstr::Assign {
-                                        dest: result_var.var_id(),
-                                        src: stack_value.var_id(),
-                                    });
-                                }
-                            }
+                            self.record_fallthrough_result(
+                                result_count,
+                                stack_height,
+                                &mut result_incoming,
+                            )?;
 
                             // Terminate loop body fall-through (if reachable)
                             self.terminate_if_live(IrTerminator::Jump { target: end_block });
@@ -558,15 +568,16 @@ impl IrBuilder {
 
                             // Insert phis at end_block for locals with differing exit values
                             if branch_incoming.is_empty() {
-                                self.dead_code = true;
+                                self.mark_dead_join();
                             } else {
                                 self.insert_phis_at_join(end_block, &branch_incoming)?;
+                                self.insert_result_phi(end_block, &result_vars, &result_incoming)?;
                             }
                         }
 
                         super::core::ControlFrame::Block {
                             end_block,
-                            result_var: rv,
+                            mut result_incoming,
                             mut branch_incoming,
                             ..
                         } => {
@@ -575,16 +586,11 @@ impl IrBuilder {
                             self.push_predecessor_if_live(&mut branch_incoming);
 
                             // Assign result if needed (block fall-through)
-                            if let Some(result_var) = rv {
-                                if let Some(stack_value) = self.value_stack.pop() {
-                                    // Normal case: block fell through with a result value
-                                    self.emit_void(IrInstr::Assign {
-                                        dest: result_var.var_id(),
-                                        src: stack_value.var_id(),
-                                    });
-                                }
-                                // Empty stack: block ended with br/return (dead code after)
-                            }
+                            self.record_fallthrough_result(
+                                result_count,
+                                stack_height,
+                                &mut result_incoming,
+                            )?;
 
                             // Terminate block fall-through (if reachable)
                             self.terminate_if_live(IrTerminator::Jump { target: end_block });
@@ -593,17 +599,16 @@ impl IrBuilder {
                             self.start_real_block(end_block);
 
                             if branch_incoming.is_empty() {
-                                self.dead_code = true;
+                                self.mark_dead_join();
                             } else {
                                 self.insert_phis_at_join(end_block, &branch_incoming)?;
+                                self.insert_result_phi(end_block, &result_vars, &result_incoming)?;
                             }
                         }
                     }
 
-                    // If the control structure produced a result, push it onto the value stack.
-                    if let Some(rv) = result_var {
-                        self.value_stack.push(rv);
-                    }
+                    // Push the control structure's results onto the value stack.
+                    self.value_stack.extend(result_vars);
                 }
             }
 
@@ -773,14 +778,11 @@ impl IrBuilder {
 
             // Control flow
             Operator::Block { blockty } => {
-                // === Parse the block's result type ===
-                // A block can optionally produce a value (e.g., "block i32 ... end").
-                // If no result type, the block just groups instructions without producing a value.
-                let result_type = match blockty {
-                    wasmparser::BlockType::Empty => None,
-                    wasmparser::BlockType::Type(vt) => Some(WasmType::from_wasmparser(*vt)),
-                    wasmparser::BlockType::FuncType(_) => bail!("Multi-value blocks not supported"),
-                };
+                // === Parse the block's type ===
+                // A block can produce values (e.g., "block i32 ... end"), and with
+                // multi-value take parameters from the stack. Without either, the
+                // block just groups instructions.
+                let (param_count, result_count) = block_arity(blockty, ctx)?;
 
                 // === Create the exit block ===
                 // When a "br" (branch) instruction inside this block executes,
@@ -790,15 +792,11 @@ impl IrBuilder {
 
                 // === STEP 1: Push control frame ===
                 // Blocks have no backward jumps — br targets end_block (forward exit).
-                self.push_block(end_block, result_type);
+                self.push_block(end_block, param_count, result_count)?;
             }
 
             Operator::Loop { blockty } => {
-                let result_type = match blockty {
-                    wasmparser::BlockType::Empty => None,
-                    wasmparser::BlockType::Type(vt) => Some(WasmType::from_wasmparser(*vt)),
-                    wasmparser::BlockType::FuncType(_) => bail!("Multi-value blocks not supported"),
-                };
+                let (param_count, result_count) = block_arity(blockty, ctx)?;
 
                 // === KEY DIFFERENCE: Loop vs Block ===
                 // Block:
@@ -829,8 +827,9 @@ impl IrBuilder {
                 //
                 // push_loop also pre-allocates phi vars for all locals and updates
                 // self.local_vars to point to them, so all code inside the loop body
-                // reads/writes through the phi vars from the start.
-                self.push_loop(loop_header, end_block, result_type);
+                // reads/writes through the phi vars from the start. The loop's
+                // parameters get phi vars too: backward branches carry new values.
+                self.push_loop(loop_header, end_block, param_count, result_count)?;
 
                 // === STEP 3: Begin codegen in the loop header block ===
                 // This block is the entry point to the loop and the target of backward
@@ -839,14 +838,10 @@ impl IrBuilder {
             }
 
             Operator::If { blockty } => {
-                // === Parse the if's result type ===
-                // An if can optionally produce a value (e.g., "if i32 ... else ... end").
-                // Both then and else branches must produce the same type.
-                let result_type = match blockty {
-                    wasmparser::BlockType::Empty => None,
-                    wasmparser::BlockType::Type(vt) => Some(WasmType::from_wasmparser(*vt)),
-                    wasmparser::BlockType::FuncType(_) => bail!("Multi-value blocks not supported"),
-                };
+                // === Parse the if's type ===
+                // An if can produce values (e.g., "if i32 ... else ... end").
+                // Both then and else branches must produce the same types.
+                let (param_count, result_count) = block_arity(blockty, ctx)?;
 
                 // === STEP 1: Pop the condition from the value stack ===
                 // The condition (i32, treated as bool: 0 = false, nonzero = true)
@@ -888,8 +883,8 @@ impl IrBuilder {
                 //   - start_block=then_block: where br 0 jumps (inside if context)
                 //   - end_block=end_block: where br 1 jumps (out of if/else)
                 //   - else_block=Some(else_block): deferred; we'll activate it when we see Else or End
-                //   - result_type: the if's result type (if any)
-                self.push_if(else_block, end_block, result_type);
+                //   - param_count/result_count: the if's block type
+                self.push_if(else_block, end_block, param_count, result_count)?;
 
                 // === NOTE: Deferred activation ===
                 // The else_block is NOT activated yet. It's stored in the control frame.
@@ -926,8 +921,11 @@ impl IrBuilder {
                 let super::core::ControlFrame::If {
                     else_block,
                     end_block: if_end_block,
-                    result_var,
+                    result_vars,
+                    stack_height,
+                    mut result_incoming,
                     locals_at_entry,
+                    params_at_entry,
                     branch_incoming,
                     ..
                 } = if_frame
@@ -944,24 +942,23 @@ impl IrBuilder {
                     None
                 };
 
-                // Step 1b: Assign the result variable from the then-branch value (if typed).
-                if let Some(result_var) = result_var {
-                    if let Some(stack_value) = self.value_stack.pop() {
-                        self.emit_void(IrInstr::Assign {
-                            dest: result_var.var_id(),
-                            src: stack_value.var_id(),
-                        });
-                    }
-                }
+                // Step 1b: Assign the result variables from the then-branch values (if typed).
+                self.record_fallthrough_result(
+                    result_vars.len(),
+                    stack_height,
+                    &mut result_incoming,
+                )?;
 
                 // Step 1c: Terminate the then-branch with a jump to end_block.
                 self.terminate_if_live(IrTerminator::Jump {
                     target: if_end_block,
                 });
 
-                // Step 3: Restore local_vars to the pre-if snapshot.
-                // The else-branch must see the same locals that entered the if.
+                // Step 3: Restore local_vars to the pre-if snapshot, and the if's
+                // parameters onto the value stack.
+                // The else-branch must see the same locals and operands that entered the if.
                 self.local_vars = locals_at_entry.clone();
+                self.value_stack.extend_from_slice(&params_at_entry);
 
                 // Step 4: Activate the else block (always reachable: false path of BranchIf).
                 self.start_real_block(else_block);
@@ -971,7 +968,9 @@ impl IrBuilder {
                 // of end_block when End is processed.
                 self.control_stack.push(super::core::ControlFrame::Else {
                     end_block: if_end_block,
-                    result_var,
+                    result_vars,
+                    stack_height,
+                    result_incoming, // then-body result values
                     branch_incoming, // then-body forward branches
                     then_pred_info,  // then fall-through predecessor
                 });
@@ -987,28 +986,33 @@ impl IrBuilder {
                 // Before terminating the block we snapshot `local_vars` and record it
                 // as a phi predecessor for the target frame.  This snapshot is used by
                 // `insert_phis_at_join` (or `emit_loop_phis`) to build the phi sources.
+                //
+                // A branch to the function body's label returns from the function.
                 let (target, is_loop, frame_idx) = self.resolve_branch_info(*relative_depth)?;
 
                 // Record snapshot *before* terminating so local_vars is still valid.
-                if is_loop {
-                    // Backward branch: store (phi_var, current_block, src_var) in phi_patches.
-                    // Consumed by emit_loop_phis when the loop's End is processed.
-                    self.record_loop_back_branch(frame_idx);
+                if self.targets_function(*relative_depth) {
+                    self.emit_return()?;
                 } else {
-                    // Forward branch: push (current_block, local_vars) into branch_incoming.
-                    // Consumed by insert_phis_at_join when the target frame's End is processed.
-                    self.record_forward_branch(frame_idx);
+                    if is_loop {
+                        // Backward branch: store (phi_var, current_block, src_var) in phi_patches.
+                        // Consumed by emit_loop_phis when the loop's End is processed.
+                        self.record_loop_back_branch(frame_idx)?;
+                    } else {
+                        // Forward branch: push (current_block, local_vars) into branch_incoming.
+                        // Consumed by insert_phis_at_join when the target frame's End is processed.
+                        self.record_forward_branch(frame_idx)?;
+                    }
+                    self.terminate(IrTerminator::Jump { target });
                 }
 
-                self.terminate(IrTerminator::Jump { target });
-
                 // Everything after an unconditional branch is unreachable.
                 // We still need a block to absorb any subsequent instructions
                 // (e.g. the End of the enclosing block) without corrupting the
                 // already-terminated block.
                 let dead_block = self.new_block();
-                self.start_block(dead_block);
                 self.dead_code = true;
+                self.start_block(dead_block);
             }
 
             Operator::BrIf { relative_depth } => {
@@ -1027,13 +1031,16 @@ impl IrBuilder {
                     .ok_or_else(|| anyhow::anyhow!("Stack underflow for br_if"))?
                     .var_id();
 
-                let (target, is_loop, frame_idx) = self.resolve_branch_info(*relative_depth)?;
+                let (mut target, is_loop, frame_idx) = self.resolve_branch_info(*relative_depth)?;
 
                 // Record the taken branch as a phi predecessor (snapshot before termination).
-                if is_loop {
-                    self.record_loop_back_branch(frame_idx);
+                // A branch to the function label takes a returning side exit instead.
+                if self.targets_function(*relative_depth) {
+                    target = self.new_return_block()?;
+                } else if is_loop {
+                    self.record_loop_back_branch(frame_idx)?;
                 } else {
-                    self.record_forward_branch(frame_idx);
+                    self.record_forward_branch(frame_idx)?;
                 }
 
                 // The fall-through block is always reachable (the false path of BranchIf).
@@ -1078,21 +1085,39 @@ impl IrBuilder {
                     .chain(std::iter::once(default_depth))
                 {
                     let (_, is_loop, frame_idx) = self.resolve_branch_info(depth)?;
+                    if self.targets_function(depth) {
+                        continue;
+                    }
                     if recorded.insert(frame_idx) {
                         if is_loop {
-                            self.record_loop_back_branch(frame_idx);
+                            self.record_loop_back_branch(frame_idx)?;
                         } else {
-                            self.record_forward_branch(frame_idx);
+                            self.record_forward_branch(frame_idx)?;
                         }
                     }
                 }
 
+                // Entries naming the function label share one returning block.
+                let mut return_block = None;
+                let mut target_block = |this: &mut Self, depth: u32| -> Result<BlockId> {
+                    if !this.targets_function(depth) {
+                        return this.get_branch_target(depth);
+                    }
+                    match return_block {
+                        Some(block) => Ok(block),
+                        None => {
+                            let block = this.new_return_block()?;
+                            return_block = Some(block);
+                            Ok(block)
+                        }
+                    }
+                };
                 let target_blocks: Vec<BlockId> = target_depths
                     .iter()
-                    .map(|depth| self.get_branch_target(*depth))
+                    .map(|depth| target_block(self, *depth))
                     .collect::<Result<Vec<_>>>()?;
 
-                let default = self.get_branch_target(default_depth)?;
+                let default = target_block(self, default_depth)?;
 
                 self.terminate(IrTerminator::BranchTable {
                     index,
@@ -1102,13 +1127,13 @@ impl IrBuilder {
 
                 // Everything after br_table is unreachable (same as Br).
                 let dead_block = self.new_block();
-                self.start_block(dead_block);
                 self.dead_code = true;
+                self.start_block(dead_block);
             }
 
             Operator::Call { function_index } => {
                 let func_idx = *function_index as usize;
-                let (param_count, callee_return_type) = *self
+                let (param_count, result_count) = *ctx
                     .func_signatures
                     .get(func_idx)
                     .ok_or_else(|| anyhow::anyhow!("Call to unknown function {}", func_idx))?;
@@ -1116,26 +1141,21 @@ impl IrBuilder {
                 let args =
                     self.pop_call_args(param_count, &format!("call to func_{}", func_idx))?;
 
-                // For optional-result calls we use new_pre_alloc_var: the dest is
-                // defined by the call instruction itself, not via emit_def.
-                let (dest_id, dest_use) = if callee_return_type.is_some() {
-                    let (id, u) = self.new_pre_alloc_var();
-                    (Some(id), Some(u))
-                } else {
-                    (None, None)
-                };
+                // Result dests use new_pre_alloc_var: they are defined by the
+                // call instruction itself, not via emit_def.
+                let (dests, dest_uses) = self.alloc_call_results(result_count);
 
                 // Check if this is a call to an imported function or a local function
-                if func_idx < self.num_imported_functions {
+                if func_idx < ctx.num_imported_functions {
                     // Call to imported function
                     let import_idx = func_idx;
                     let (module_name, func_name) =
-                        self.func_imports.get(import_idx).cloned().ok_or_else(|| {
+                        ctx.func_imports.get(import_idx).cloned().ok_or_else(|| {
                             anyhow::anyhow!("Call: import index {} out of range", import_idx)
                         })?;
 
                     self.emit_void(IrInstr::CallImport {
-                        dest: dest_id,
+                        dests,
                         import_idx: ImportIdx::new(import_idx),
                         module_name,
                         func_name,
@@ -1143,17 +1163,15 @@ impl IrBuilder {
                     });
                 } else {
                     // Call to local function - convert to local index
-                    let local_func_idx = func_idx - self.num_imported_functions;
+                    let local_func_idx = func_idx - ctx.num_imported_functions;
                     self.emit_void(IrInstr::Call {
-                        dest: dest_id,
+                        dests,
                         func_idx: LocalFuncIdx::new(local_func_idx),
                         args,
                     });
                 }
 
-                if let Some(u) = dest_use {
-                    self.value_stack.push(u);
-                }
+                self.value_stack.extend(dest_uses);
             }
 
             Operator::CallIndirect {
@@ -1164,8 +1182,8 @@ impl IrBuilder {
                     bail!("Multi-table not supported (table_index={})", table_index);
                 }
                 let type_idx_usize = *type_index as usize;
-                let (param_count, callee_return_type) =
-                    *self.type_signatures.get(type_idx_usize).ok_or_else(|| {
+                let (param_count, result_count) =
+                    *ctx.type_signatures.get(type_idx_usize).ok_or_else(|| {
                         anyhow::anyhow!("CallIndirect: unknown type index {}", type_idx_usize)
                     })?;
 
@@ -1184,33 +1202,41 @@ impl IrBuilder {
                     &format!("call_indirect type {}", type_idx_usize),
                 )?;
 
-                let (dest_id, dest_use) = if callee_return_type.is_some() {
-                    let (id, u) = self.new_pre_alloc_var();
-                    (Some(id), Some(u))
-                } else {
-                    (None, None)
-                };
+                let (dests, dest_uses) = self.alloc_call_results(result_count);
                 self.emit_void(IrInstr::CallIndirect {
-                    dest: dest_id,
+                    dests,
                     type_idx: TypeIdx::new(*type_index as usize),
                     table_idx: table_idx_var,
                     args,
                 });
 
-                if let Some(u) = dest_use {
-                    self.value_stack.push(u);
-                }
+                self.value_stack.extend(dest_uses);
             }
 
             Operator::Unreachable => {
                 self.terminate(IrTerminator::Unreachable);
                 // Create unreachable continuation block (dead code follows)
                 let unreachable_block = self.new_block();
-                self.start_block(unreachable_block);
                 self.dead_code = true;
+                self.start_block(unreachable_block);
             }
 
-            Operator::Select => {
+            // `select (result t)` behaves like the untyped form; only the
+            // reference-types proposal needs the annotation, and reference
+            // operands are not supported.
+            Operator::TypedSelect { ty }
+                if !matches!(
+                    ty,
+                    wasmparser::ValType::I32
+                        | wasmparser::ValType::I64
+                        | wasmparser::ValType::F32
+                        | wasmparser::ValType::F64
+                ) =>
+            {
+                bail!("Unsupported select of type {:?}", ty)
+            }
+
+            Operator::Select | Operator::TypedSelect { .. } => {
                 if self.value_stack.len() < 3 {
                     bail!("Stack underflow for select (need 3 values)");
                 }
@@ -1310,28 +1336,51 @@ impl IrBuilder {
                 });
             }
 
-            _ => bail!("Unsupported operator: {:?}", op),
+            _ => match AtomicOp::decode(op) {
+                Some(atomic) => self.translate_atomic(atomic, ctx)?,
+                None => bail!("Unsupported operator: {:?}", op),
+            },
         }
 
         Ok(())
     }
 
-    /// Pop the top-of-stack value (if any) and terminate the current block with a Return.
+    /// Whether `op` is skipped because it is dead code.
+    ///
+    /// Dead code runs from an unconditional transfer (`br`, `br_table`,
+    /// `return`, `unreachable`) to the `Else`/`End` of the enclosing frame.
+    /// It never executes and, per the spec, type-checks against a polymorphic
+    /// stack, so it is not translated at all. Nested constructs inside it
+    /// are tracked by depth only, so their `Else`/`End` are skipped too.
+    fn skip_dead_operator(&mut self, op: &Operator) -> bool {
+        match op {
+            Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                self.dead_depth += 1;
+                true
+            }
+            Operator::Else if self.dead_depth > 0 => true,
+            Operator::End if self.dead_depth > 0 => {
+                self.dead_depth -= 1;
+                true
+            }
+            Operator::Else | Operator::End => false,
+            _ => true,
+        }
+    }
+
+    /// Pop the function results (if the function has any) and terminate the
+    /// current block with a Return.
     fn emit_return(&mut self) -> Result<()> {
-        let value = if self.value_stack.is_empty() {
-            None
-        } else {
-            Some(
-                self.value_stack
-                    .pop()
-                    .ok_or_else(|| anyhow::anyhow!("stack underflow in return"))?
-                    .var_id(),
-            )
-        };
-        self.terminate(IrTerminator::Return { value });
+        let values = self.pop_call_args(self.function_result_count(), "return")?;
+        self.terminate(IrTerminator::Return { values });
         Ok(())
     }
 
+    /// Allocate the dests of a call with `result_count` results.
+    fn alloc_call_results(&mut self, result_count: usize) -> (Vec<VarId>, Vec<UseVar>) {
+        (0..result_count).map(|_| self.new_pre_alloc_var()).unzip()
+    }
+
     /// Emit a binary operation.
     pub(super) fn emit_binop(&mut self, op: BinOp) -> Result<()> {
         if self.value_stack.len() < 2 {
@@ -1475,3 +1524,17 @@ impl IrBuilder {
         Ok(())
     }
 }
+
+/// `(param_count, result_count)` of a block type: a type index for
+/// multi-value blocks, which take parameters or produce several results.
+fn block_arity(blockty: &wasmparser::BlockType, ctx: &ModuleContext) -> Result<(usize, usize)> {
+    Ok(match blockty {
+        wasmparser::BlockType::Empty => (0, 0),
+        wasmparser::BlockType::Type(_) => (0, 1),
+        wasmparser::BlockType::FuncType(type_idx) => {
+            *ctx.type_signatures
+                .get(*type_idx as usize)
+                .ok_or_else(|| anyhow::anyhow!("block type: unknown type index {}", type_idx))?
+        }
+    })
+}
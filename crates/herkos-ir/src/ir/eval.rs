@@ -0,0 +1,772 @@
+//! Transpile-time evaluation of the start function.
+//!
+//! A Wasm start function runs once at instantiation, and in modules built
+//! from C or C++ it is usually the static constructors: it fills tables in
+//! memory and sets globals, without calling the host. [`bake_start`] runs
+//! it on the IR, against the memory image the data segments describe, and
+//! writes the result back as the module's initial state:
+//!
+//! - the data segments become the non-zero runs of the final memory, and
+//!   `initial_pages` grows with any `memory.grow`;
+//! - mutable globals the function changed get its final value as their
+//!   initializer.
+//!
+//! The generated constructor then starts from that state, so instantiation
+//! costs no more than copying the data segments. Evaluation gives up
+//! ([`EvalError`]) on anything whose result depends on the host — calls to
+//! imports, imported memory or globals — on calls through the table, on
+//! traps, and after [`START_FUEL`] instructions. The constructor then calls
+//! the start function itself (see [`ModuleInfo::start`]).
+//!
+//! The same evaluator runs any local function through [`Interpreter`], a
+//! reference for the generated code to be checked against.
+
+use crate::ir::*;
+use crate::optimizer::{try_eval_binop, try_eval_unop};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// Instructions and branches the start function may execute before
+/// evaluation gives up.
+pub const START_FUEL: u64 = 50_000_000;
+
+/// Nesting of calls evaluation follows before giving up.
+const MAX_CALL_DEPTH: usize = 512;
+
+const PAGE_SIZE: usize = 65536;
+
+/// Why a function could not be evaluated at transpile time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// It calls host import `module.name`.
+    Import { module: String, name: String },
+    /// It calls through the table.
+    IndirectCall,
+    /// It depends on state the host provides.
+    HostState(&'static str),
+    /// It traps.
+    Trap(&'static str),
+    /// It ran for more than [`START_FUEL`] instructions, or nested calls too
+    /// deeply.
+    Exhausted,
+    /// The IR reads a variable nothing defined.
+    Undefined(VarId),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Import { module, name } => write!(f, "it calls import `{module}.{name}`"),
+            EvalError::IndirectCall => f.write_str("it calls through the table"),
+            EvalError::HostState(what) => write!(f, "it depends on {what}"),
+            EvalError::Trap(what) => write!(f, "it traps ({what})"),
+            EvalError::Exhausted => write!(
+                f,
+                "it runs for more than {START_FUEL} instructions or {MAX_CALL_DEPTH} nested calls"
+            ),
+            EvalError::Undefined(var) => write!(f, "it reads undefined variable {var}"),
+        }
+    }
+}
+
+/// Run local function `func` as the start function of `info`, and make the
+/// memory and globals it leaves the module's initial state.
+///
+/// `info` is unchanged when evaluation fails.
+///
+/// # Errors
+/// See [`EvalError`].
+pub fn bake_start(info: &mut ModuleInfo, func: LocalFuncIdx) -> Result<(), EvalError> {
+    bake_with_fuel(info, func, START_FUEL)
+}
+
+fn bake_with_fuel(info: &mut ModuleInfo, func: LocalFuncIdx, fuel: u64) -> Result<(), EvalError> {
+    let mut machine = Machine::new(info, fuel)?;
+    let initial_memory = machine.memory.clone();
+    machine.call(info, func, &[], 0)?;
+
+    for (global, value) in info.globals.iter_mut().zip(&machine.globals) {
+        if let Some(value) = value {
+            if global.mutable && !same_bits(value, &global.init_value) {
+                global.init_value = match *value {
+                    IrValue::I32(v) => GlobalInit::I32(v),
+                    IrValue::I64(v) => GlobalInit::I64(v),
+                    IrValue::F32(v) => GlobalInit::F32(v),
+                    IrValue::F64(v) => GlobalInit::F64(v),
+                };
+            }
+        }
+    }
+    if machine.memory != initial_memory {
+        let named: Vec<(Range<usize>, String)> = info
+            .data_segments
+            .iter()
+            .filter(|s| s.base.is_none())
+            .filter_map(|s| {
+                let start = s.offset as usize;
+                Some((start..start + s.data.len(), s.name.clone()?))
+            })
+            .collect();
+        info.initial_pages = machine.memory.len() / PAGE_SIZE;
+        info.data_segments = nonzero_runs(&machine.memory)
+            .flat_map(|(offset, data)| split_at_segments(offset, data, &named))
+            .map(|(offset, data, name)| DataSegmentDef {
+                offset: offset as u32,
+                base: None,
+                data: data.to_vec(),
+                read_only: false,
+                resident: true,
+                name,
+                protected: false,
+            })
+            .collect();
+    }
+    Ok(())
+}
+
+/// An instance of a module run by the IR evaluator, with the memory and
+/// globals its calls leave behind.
+///
+/// Calls fail like [`bake_start`] does: on the host, the table, traps, and
+/// once `fuel` instructions have run in all.
+pub struct Interpreter<'a> {
+    info: &'a ModuleInfo,
+    machine: Machine,
+}
+
+impl<'a> Interpreter<'a> {
+    /// `info` right after its data segments are copied.
+    ///
+    /// # Errors
+    /// Fails when a data segment is relocated or out of bounds.
+    pub fn new(info: &'a ModuleInfo, fuel: u64) -> Result<Self, EvalError> {
+        Ok(Self {
+            info,
+            machine: Machine::new(info, fuel)?,
+        })
+    }
+
+    /// Call local function `func` with `args`.
+    ///
+    /// Memory and globals keep what the function wrote before it trapped.
+    ///
+    /// # Errors
+    /// See [`EvalError`].
+    pub fn call(
+        &mut self,
+        func: LocalFuncIdx,
+        args: &[IrValue],
+    ) -> Result<Vec<IrValue>, EvalError> {
+        self.machine.call(self.info, func, args, 0)
+    }
+
+    /// The linear memory, all pages of it.
+    pub fn memory(&self) -> &[u8] {
+        &self.machine.memory
+    }
+
+    /// Value of local global `idx`, unless the host provides it.
+    pub fn global(&self, idx: LocalGlobalIdx) -> Option<IrValue> {
+        self.machine.globals.get(idx.as_usize()).copied().flatten()
+    }
+}
+
+/// Whether `value` has the bits `init` starts the global with.
+fn same_bits(value: &IrValue, init: &GlobalInit) -> bool {
+    match (*value, *init) {
+        (IrValue::I32(a), GlobalInit::I32(b)) => a == b,
+        (IrValue::I64(a), GlobalInit::I64(b)) => a == b,
+        (IrValue::F32(a), GlobalInit::F32(b)) => a.to_bits() == b.to_bits(),
+        (IrValue::F64(a), GlobalInit::F64(b)) => a.to_bits() == b.to_bits(),
+        _ => false,
+    }
+}
+
+/// The run of `data` at `offset`, cut wherever one of the `named` segment
+/// ranges begins or ends, each non-zero piece with the name of the segment
+/// it lies in.
+fn split_at_segments<'m>(
+    offset: usize,
+    data: &'m [u8],
+    named: &[(Range<usize>, String)],
+) -> Vec<(usize, &'m [u8], Option<String>)> {
+    let end = offset + data.len();
+    let mut cuts: Vec<usize> = named
+        .iter()
+        .flat_map(|(range, _)| [range.start, range.end])
+        .filter(|&cut| offset < cut && cut < end)
+        .collect();
+    cuts.sort_unstable();
+    cuts.dedup();
+    cuts.push(end);
+    let mut start = offset;
+    let mut pieces = Vec::new();
+    for cut in cuts {
+        let piece = &data[start - offset..cut - offset];
+        if piece.iter().any(|&b| b != 0) {
+            let name = named
+                .iter()
+                .find(|(range, _)| range.contains(&start))
+                .map(|(_, name)| name.clone());
+            pieces.push((start, piece, name));
+        }
+        start = cut;
+    }
+    pieces
+}
+
+/// Zero bytes between two runs below which they are kept as one segment.
+const MAX_GAP: usize = 16;
+
+/// `(offset, bytes)` of each stretch of `memory` holding non-zero bytes.
+fn nonzero_runs(memory: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let start = pos + memory[pos..].iter().position(|&b| b != 0)?;
+        let mut end = start;
+        let mut zeros = 0;
+        for (i, &b) in memory.iter().enumerate().skip(start) {
+            if b != 0 {
+                end = i + 1;
+                zeros = 0;
+            } else {
+                zeros += 1;
+                if zeros >= MAX_GAP {
+                    break;
+                }
+            }
+        }
+        pos = end;
+        Some((start, &memory[start..end]))
+    })
+}
+
+/// The state a function runs against.
+struct Machine {
+    memory: Vec<u8>,
+    max_pages: usize,
+    /// Value of each local global; `None` when it comes from the host.
+    globals: Vec<Option<IrValue>>,
+    fuel: u64,
+}
+
+impl Machine {
+    /// The state of `info` right after its data segments are copied.
+    fn new(info: &ModuleInfo, fuel: u64) -> Result<Self, EvalError> {
+        let mut memory = Vec::new();
+        if info.has_memory {
+            memory = vec![0; info.initial_pages * PAGE_SIZE];
+            for seg in &info.data_segments {
+                if seg.base.is_some() {
+                    return Err(EvalError::HostState("a relocated data segment"));
+                }
+                let start = seg.offset as usize;
+                memory
+                    .get_mut(start..start + seg.data.len())
+                    .ok_or(EvalError::Trap("data segment out of bounds"))?
+                    .copy_from_slice(&seg.data);
+            }
+        }
+        let globals = info
+            .globals
+            .iter()
+            .map(|g| match g.init_value {
+                GlobalInit::I32(v) => Some(IrValue::I32(v)),
+                GlobalInit::I64(v) => Some(IrValue::I64(v)),
+                GlobalInit::F32(v) => Some(IrValue::F32(v)),
+                GlobalInit::F64(v) => Some(IrValue::F64(v)),
+                GlobalInit::Imported { .. } => None,
+            })
+            .collect();
+        Ok(Self {
+            memory,
+            max_pages: info.max_pages,
+            globals,
+            fuel,
+        })
+    }
+
+    /// Run local function `func` with `args`.
+    fn call(
+        &mut self,
+        info: &ModuleInfo,
+        func: LocalFuncIdx,
+        args: &[IrValue],
+        depth: usize,
+    ) -> Result<Vec<IrValue>, EvalError> {
+        if depth >= MAX_CALL_DEPTH {
+            return Err(EvalError::Exhausted);
+        }
+        let func = info
+            .ir_function(func)
+            .ok_or(EvalError::Trap("call to a missing function"))?;
+        let blocks: HashMap<BlockId, &IrBlock> = func.blocks.iter().map(|b| (b.id, b)).collect();
+
+        let mut vars: HashMap<VarId, IrValue> = HashMap::new();
+        for ((var, _), arg) in func.params.iter().zip(args) {
+            vars.insert(*var, *arg);
+        }
+        for (var, ty) in &func.locals {
+            vars.insert(*var, zero(*ty));
+        }
+        let get = |vars: &HashMap<VarId, IrValue>, var: &VarId| {
+            vars.get(var).copied().ok_or(EvalError::Undefined(*var))
+        };
+
+        let mut prev = None;
+        let mut current = func.entry_block;
+        loop {
+            let block = blocks
+                .get(&current)
+                .ok_or(EvalError::Trap("branch to a missing block"))?;
+
+            // Each block costs at least one unit, so empty loops run out too
+            self.fuel = self.fuel.checked_sub(1).ok_or(EvalError::Exhausted)?;
+
+            // Phis read the values on entry, all at once
+            let mut phis = Vec::new();
+            for instr in &block.instructions {
+                if let IrInstr::Phi { dest, srcs } = instr {
+                    let src = srcs
+                        .iter()
+                        .find(|(pred, _)| Some(*pred) == prev)
+                        .map(|(_, src)| *src)
+                        .ok_or(EvalError::Undefined(*dest))?;
+                    phis.push((*dest, get(&vars, &src)?));
+                }
+            }
+            vars.extend(phis);
+
+            for instr in &block.instructions {
+                self.fuel = self.fuel.checked_sub(1).ok_or(EvalError::Exhausted)?;
+                match instr {
+                    IrInstr::Phi { .. } => {}
+                    IrInstr::Const { dest, value } => {
+                        vars.insert(*dest, *value);
+                    }
+                    IrInstr::BinOp { dest, op, lhs, rhs } => {
+                        let value = try_eval_binop(*op, get(&vars, lhs)?, get(&vars, rhs)?)
+                            .ok_or(EvalError::Trap("integer division by zero or overflow"))?;
+                        vars.insert(*dest, value);
+                    }
+                    IrInstr::UnOp { dest, op, operand } => {
+                        let value = eval_unop(*op, get(&vars, operand)?)?;
+                        vars.insert(*dest, value);
+                    }
+                    IrInstr::Load {
+                        dest,
+                        ty,
+                        addr,
+                        offset,
+                        width,
+                        sign,
+                    } => {
+                        let addr = effective_addr(get(&vars, addr)?, *offset);
+                        let value = self.load(*ty, addr, *width, *sign)?;
+                        vars.insert(*dest, value);
+                    }
+                    IrInstr::Store {
+                        ty,
+                        addr,
+                        value,
+                        offset,
+                        width,
+                    } => {
+                        let addr = effective_addr(get(&vars, addr)?, *offset);
+                        let bytes = width.bytes(*ty) as usize;
+                        let bits = to_bits(get(&vars, value)?);
+                        self.bytes_mut(info, addr, bytes)?
+                            .copy_from_slice(&bits.to_le_bytes()[..bytes]);
+                    }
+                    IrInstr::Call {
+                        dests,
+                        func_idx,
+                        args,
+                    } => {
+                        let args = args
+                            .iter()
+                            .map(|a| get(&vars, a))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let results = self.call(info, *func_idx, &args, depth + 1)?;
+                        vars.extend(dests.iter().copied().zip(results));
+                    }
+                    IrInstr::CallImport {
+                        module_name,
+                        func_name,
+                        ..
+                    } => {
+                        return Err(EvalError::Import {
+                            module: module_name.to_string(),
+                            name: func_name.to_string(),
+                        });
+                    }
+                    IrInstr::CallIndirect { .. } => return Err(EvalError::IndirectCall),
+                    IrInstr::Assign { dest, src } => {
+                        vars.insert(*dest, get(&vars, src)?);
+                    }
+                    IrInstr::GlobalGet { dest, index } => {
+                        let value = match info.resolve_global(*index) {
+                            ResolvedGlobal::Imported(..) => None,
+                            ResolvedGlobal::Local(idx, _) => self.globals[idx.as_usize()],
+                        };
+                        let value = value.ok_or(EvalError::HostState("an imported global"))?;
+                        vars.insert(*dest, value);
+                    }
+                    IrInstr::GlobalSet { index, value } => match info.resolve_global(*index) {
+                        ResolvedGlobal::Imported(..) => {
+                            return Err(EvalError::HostState("an imported global"));
+                        }
+                        ResolvedGlobal::Local(idx, _) => {
+                            self.globals[idx.as_usize()] = Some(get(&vars, value)?);
+                        }
+                    },
+                    IrInstr::MemorySize { dest } => {
+                        self.check_memory(info)?;
+                        let pages = (self.memory.len() / PAGE_SIZE) as i32;
+                        vars.insert(*dest, IrValue::I32(pages));
+                    }
+                    IrInstr::MemoryGrow { dest, delta } => {
+                        self.check_memory(info)?;
+                        let old = self.memory.len() / PAGE_SIZE;
+                        let new = old.saturating_add(as_u32(get(&vars, delta)?) as usize);
+                        let result = if new > self.max_pages {
+                            -1
+                        } else {
+                            self.memory.resize(new * PAGE_SIZE, 0);
+                            old as i32
+                        };
+                        vars.insert(*dest, IrValue::I32(result));
+                    }
+                    IrInstr::MemoryCopy { dst, src, len } => {
+                        let (dst, src) = (as_u32(get(&vars, dst)?), as_u32(get(&vars, src)?));
+                        let len = as_u32(get(&vars, len)?) as usize;
+                        self.bytes_mut(info, dst as u64, len)?;
+                        self.bytes_mut(info, src as u64, len)?;
+                        let (dst, src) = (dst as usize, src as usize);
+                        self.memory.copy_within(src..src + len, dst);
+                    }
+                    IrInstr::MemoryFill { dst, val, len } => {
+                        let dst = as_u32(get(&vars, dst)?) as u64;
+                        let val = as_u32(get(&vars, val)?) as u8;
+                        let len = as_u32(get(&vars, len)?) as usize;
+                        self.bytes_mut(info, dst, len)?.fill(val);
+                    }
+                    IrInstr::MemoryInit {
+                        dst,
+                        src_offset,
+                        len,
+                        segment,
+                    } => {
+                        let dst = as_u32(get(&vars, dst)?) as u64;
+                        let src = as_u32(get(&vars, src_offset)?) as usize;
+                        let len = as_u32(get(&vars, len)?) as usize;
+                        let data = info
+                            .passive_data_segments
+                            .iter()
+                            .find(|s| s.wasm_index == *segment)
+                            .and_then(|s| s.data.get(src..src.checked_add(len)?))
+                            .ok_or(EvalError::Trap("memory.init out of bounds"))?;
+                        self.bytes_mut(info, dst, len)?.copy_from_slice(data);
+                    }
+                    // Passive segments are constant in generated code
+                    IrInstr::DataDrop { .. } => {}
+                    IrInstr::Select {
+                        dest,
+                        val1,
+                        val2,
+                        condition,
+                    } => {
+                        let value = if as_u32(get(&vars, condition)?) != 0 {
+                            get(&vars, val1)?
+                        } else {
+                            get(&vars, val2)?
+                        };
+                        vars.insert(*dest, value);
+                    }
+                }
+            }
+
+            prev = Some(current);
+            current = match &block.terminator {
+                IrTerminator::Return { values } => {
+                    return values.iter().map(|v| get(&vars, v)).collect();
+                }
+                IrTerminator::Jump { target } => *target,
+                IrTerminator::BranchIf {
+                    condition,
+                    if_true,
+                    if_false,
+                } => {
+                    if as_u32(get(&vars, condition)?) != 0 {
+                        *if_true
+                    } else {
+                        *if_false
+                    }
+                }
+                IrTerminator::BranchTable {
+                    index,
+                    targets,
+                    default,
+                } => *targets
+                    .get(as_u32(get(&vars, index)?) as usize)
+                    .unwrap_or(default),
+                IrTerminator::Unreachable => return Err(EvalError::Trap("unreachable")),
+            };
+        }
+    }
+
+    /// Fail unless the module's memory is its own.
+    fn check_memory(&self, info: &ModuleInfo) -> Result<(), EvalError> {
+        if info.has_memory_import {
+            return Err(EvalError::HostState("an imported memory"));
+        }
+        Ok(())
+    }
+
+    /// The `len` bytes at `addr`, if in bounds.
+    fn bytes_mut(
+        &mut self,
+        info: &ModuleInfo,
+        addr: u64,
+        len: usize,
+    ) -> Result<&mut [u8], EvalError> {
+        self.check_memory(info)?;
+        let start = usize::try_from(addr).ok();
+        start
+            .and_then(|start| self.memory.get_mut(start..start.checked_add(len)?))
+            .ok_or(EvalError::Trap("out-of-bounds memory access"))
+    }
+
+    fn load(
+        &mut self,
+        ty: WasmType,
+        addr: u64,
+        width: MemoryAccessWidth,
+        sign: Option<SignExtension>,
+    ) -> Result<IrValue, EvalError> {
+        let len = width.bytes(ty) as usize;
+        let start = addr as usize;
+        let bytes = self
+            .memory
+            .get(start..start + len)
+            .ok_or(EvalError::Trap("out-of-bounds memory access"))?;
+        let mut buf = [0u8; 8];
+        buf[..len].copy_from_slice(bytes);
+        let mut bits = u64::from_le_bytes(buf);
+        if sign == Some(SignExtension::Signed) {
+            let shift = 64 - 8 * len as u32;
+            bits = ((bits << shift) as i64 >> shift) as u64;
+        }
+        Ok(match ty {
+            WasmType::I32 => IrValue::I32(bits as i32),
+            WasmType::I64 => IrValue::I64(bits as i64),
+            WasmType::F32 => IrValue::F32(f32::from_bits(bits as u32)),
+            WasmType::F64 => IrValue::F64(f64::from_bits(bits)),
+        })
+    }
+}
+
+/// A unary operation, trapping where Wasm does.
+fn eval_unop(op: UnOp, value: IrValue) -> Result<IrValue, EvalError> {
+    // Sign extensions are not folded by the optimizer
+    let extended = match (op, value) {
+        (UnOp::I32Extend8S, IrValue::I32(v)) => Some(IrValue::I32(v as i8 as i32)),
+        (UnOp::I32Extend16S, IrValue::I32(v)) => Some(IrValue::I32(v as i16 as i32)),
+        (UnOp::I64Extend8S, IrValue::I64(v)) => Some(IrValue::I64(v as i8 as i64)),
+        (UnOp::I64Extend16S, IrValue::I64(v)) => Some(IrValue::I64(v as i16 as i64)),
+        (UnOp::I64Extend32S, IrValue::I64(v)) => Some(IrValue::I64(v as i32 as i64)),
+        _ => try_eval_unop(op, value),
+    };
+    extended.ok_or(EvalError::Trap("invalid conversion to integer"))
+}
+
+/// Address of an access at `offset` from `addr`.
+fn effective_addr(addr: IrValue, offset: u32) -> u64 {
+    as_u32(addr) as u64 + offset as u64
+}
+
+fn as_u32(value: IrValue) -> u32 {
+    to_bits(value) as u32
+}
+
+fn to_bits(value: IrValue) -> u64 {
+    match value {
+        IrValue::I32(v) => v as u32 as u64,
+        IrValue::I64(v) => v as u64,
+        IrValue::F32(v) => v.to_bits() as u64,
+        IrValue::F64(v) => v.to_bits(),
+    }
+}
+
+fn zero(ty: WasmType) -> IrValue {
+    match ty {
+        WasmType::I32 => IrValue::I32(0),
+        WasmType::I64 => IrValue::I64(0),
+        WasmType::F32 => IrValue::F32(0.0),
+        WasmType::F64 => IrValue::F64(0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::builder::{build_module_info, BuildOptions};
+    use crate::parser::parse_wasm;
+
+    fn info_of(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = parse_wasm(&wasm).unwrap();
+        let options = BuildOptions {
+            max_pages: 4,
+            lower_atomics: false,
+            call_ctors: false,
+        };
+        build_module_info(&parsed, &options).unwrap()
+    }
+
+    fn bake(info: &mut ModuleInfo, func: usize) -> Result<(), EvalError> {
+        bake_start(info, LocalFuncIdx::new(func))
+    }
+
+    #[test]
+    fn loops_fill_memory_and_set_globals() {
+        let mut info = info_of(
+            r#"(module
+                (memory 1)
+                (global $n (mut i32) (i32.const 0))
+                (global $k i64 (i64.const 9))
+                (data (i32.const 100) "\01\02")
+                (func $init (local $i i32)
+                    (loop $next
+                        (i32.store8 offset=200 (local.get $i) (i32.add (local.get $i) (i32.const 1)))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $next (i32.lt_u (local.get $i) (i32.const 4))))
+                    (global.set $n (i32.load16_s (i32.const 100)))))"#,
+        );
+        bake(&mut info, 0).unwrap();
+        assert!(matches!(
+            info.globals[0].init_value,
+            GlobalInit::I32(0x0201)
+        ));
+        assert!(matches!(info.globals[1].init_value, GlobalInit::I64(9)));
+        let segments: Vec<(u32, &[u8])> = info
+            .data_segments
+            .iter()
+            .map(|s| (s.offset, s.data.as_slice()))
+            .collect();
+        assert_eq!(segments, [(100, &[1, 2][..]), (200, &[1, 2, 3, 4][..])]);
+    }
+
+    #[test]
+    fn baked_segments_keep_their_names() {
+        let mut info = info_of(
+            r#"(module
+                (memory 1)
+                (data $.rodata (i32.const 100) "\01\02")
+                (data $.data (i32.const 102) "\03\00")
+                (func $init
+                    (i32.store8 (i32.const 103) (i32.const 9))
+                    (i32.store8 (i32.const 300) (i32.const 7))))"#,
+        );
+        bake(&mut info, 0).unwrap();
+        let segments: Vec<(u32, &[u8], Option<&str>)> = info
+            .data_segments
+            .iter()
+            .map(|s| (s.offset, s.data.as_slice(), s.name.as_deref()))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                (100, &[1, 2][..], Some(".rodata")),
+                (102, &[3, 9][..], Some(".data")),
+                (300, &[7][..], None),
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_and_growth_are_followed() {
+        let mut info = info_of(
+            r#"(module
+                (memory 1 4)
+                (func $put (param i32 i32) (i32.store (local.get 0) (local.get 1)))
+                (func $init
+                    (drop (memory.grow (i32.const 1)))
+                    (call $put (i32.const 65536) (i32.const -1))))"#,
+        );
+        bake(&mut info, 1).unwrap();
+        assert_eq!(info.initial_pages, 2);
+        assert_eq!(info.data_segments[0].offset, 65536);
+        assert_eq!(info.data_segments[0].data, [0xff; 4]);
+    }
+
+    #[test]
+    fn host_dependent_code_is_not_evaluated() {
+        let mut info = info_of(
+            r#"(module
+                (import "env" "log" (func $log (param i32)))
+                (memory 1)
+                (func $init (i32.store (i32.const 0) (i32.const 1)) (call $log (i32.const 0))))"#,
+        );
+        assert_eq!(
+            bake(&mut info, 0),
+            Err(EvalError::Import {
+                module: "env".into(),
+                name: "log".into()
+            })
+        );
+        // Left as it was
+        assert!(info.data_segments.is_empty());
+
+        let mut info = info_of(r#"(module (func $init (loop $l (br $l))))"#);
+        assert_eq!(
+            bake_with_fuel(&mut info, LocalFuncIdx::new(0), 1000),
+            Err(EvalError::Exhausted)
+        );
+
+        let mut info = info_of(
+            r#"(module (memory 1) (func $init (i32.store (i32.const 65535) (i32.const 1))))"#,
+        );
+        assert_eq!(
+            bake(&mut info, 0),
+            Err(EvalError::Trap("out-of-bounds memory access"))
+        );
+    }
+
+    #[test]
+    fn runs_split_at_long_gaps() {
+        let mut memory = vec![0u8; 64];
+        memory[1] = 1;
+        memory[5] = 2;
+        memory[40] = 3;
+        let runs: Vec<(usize, &[u8])> = nonzero_runs(&memory).collect();
+        assert_eq!(runs, [(1, &[1, 0, 0, 0, 2][..]), (40, &[3][..])]);
+    }
+
+    #[test]
+    fn interpreter_keeps_state_across_calls() {
+        let info = info_of(
+            r#"(module
+                (memory 1)
+                (global $n (mut i32) (i32.const 0))
+                (func $bump (param i32) (result i32)
+                    (global.set $n (i32.add (global.get $n) (local.get 0)))
+                    (i32.store (i32.const 8) (global.get $n))
+                    (i32.div_u (i32.const 100) (local.get 0))))"#,
+        );
+        let mut interp = Interpreter::new(&info, START_FUEL).unwrap();
+        let bump = LocalFuncIdx::new(0);
+        assert_eq!(
+            interp.call(bump, &[IrValue::I32(4)]),
+            Ok(vec![IrValue::I32(25)])
+        );
+        assert_eq!(
+            interp.call(bump, &[IrValue::I32(0)]),
+            Err(EvalError::Trap("integer division by zero or overflow"))
+        );
+        // The trap came after the global and memory were written
+        assert_eq!(interp.global(LocalGlobalIdx::new(0)), Some(IrValue::I32(4)));
+        assert_eq!(interp.memory()[8..12], 4i32.to_le_bytes());
+    }
+}
@@ -77,7 +77,10 @@
 //! assigning there is equivalent to selecting based on the taken path.
 
 use crate::ir::{BlockId, IrFunction, IrInstr, ModuleInfo, VarId};
-use std::collections::HashSet;
+use crate::optimizer::utils::{
+    for_each_def, for_each_use, for_each_use_terminator, terminator_successors,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Lower all `IrInstr::Phi` nodes in `module_info`, returning a [`super::LoweredModuleInfo`].
 ///
@@ -214,44 +217,193 @@ fn lower_func(func: &mut IrFunction) {
 
     // Step 3: Lower non-trivial phis to predecessor-block assignments.
     //
-    // We collect all phi nodes first, then mutate blocks.
-    // `phi_assignments` maps `(dest, pred_block) -> src`.
-    // `phi_locations` maps `block_id -> [phi_dest]` so we know which phis to remove.
-    let mut phi_assignments: Vec<(BlockId, IrInstr)> = Vec::new();
-    let mut phi_block_dests: Vec<(BlockId, VarId)> = Vec::new();
-
+    // The copies a predecessor makes for its successor's phis all happen on
+    // the edge at once; placed at the end of the block, they need care:
+    //
+    // - A phi's dest may still be live on the predecessor's other edges, or
+    //   read by its terminator (after copy propagation, the exit of a loop
+    //   can read the header's phi rather than a copy of it). Such a phi is
+    //   isolated: predecessors assign a fresh variable, and the join block
+    //   copies it into the dest on entry.
+    // - A copy's source may be the dest of another copy in the same
+    //   predecessor (`a = phi(.., b)`, `b = phi(.., a)`). The copies are
+    //   ordered so each source is read before it is overwritten, and a
+    //   cycle is broken through a fresh variable.
+    let mut phis = Vec::new();
     for block in &func.blocks {
         for instr in &block.instructions {
             if let IrInstr::Phi { dest, srcs } = instr {
-                phi_block_dests.push((block.id, *dest));
-                for (pred_block, src) in srcs {
-                    phi_assignments.push((
-                        *pred_block,
-                        IrInstr::Assign {
-                            dest: *dest,
-                            src: *src,
-                        },
-                    ));
-                }
+                phis.push((block.id, *dest, srcs.clone()));
             }
         }
     }
+    if phis.is_empty() {
+        return;
+    }
+
+    let live_in = live_in(func);
+    let mut next_var = next_var(func);
+    let mut fresh = || {
+        next_var += 1;
+        VarId(next_var - 1)
+    };
+    let mut copies: BTreeMap<BlockId, Vec<(VarId, VarId)>> = BTreeMap::new();
+    let mut entries: BTreeMap<BlockId, Vec<IrInstr>> = BTreeMap::new();
+    for (join, dest, srcs) in &phis {
+        let clobbers = |pred: &BlockId| {
+            func.blocks.iter().find(|b| b.id == *pred).is_some_and(|b| {
+                let mut read = false;
+                for_each_use_terminator(&b.terminator, |v| read |= v == *dest);
+                read || terminator_successors(&b.terminator)
+                    .iter()
+                    .any(|s| s != join && live_in.get(s).is_some_and(|l| l.contains(dest)))
+            })
+        };
+        let target = if srcs.iter().any(|(pred, _)| clobbers(pred)) {
+            let isolated = fresh();
+            entries.entry(*join).or_default().push(IrInstr::Assign {
+                dest: *dest,
+                src: isolated,
+            });
+            isolated
+        } else {
+            *dest
+        };
+        for (pred, src) in srcs {
+            copies.entry(*pred).or_default().push((target, *src));
+        }
+    }
 
     // Insert assignments into predecessor blocks (before the terminator).
-    for (pred_id, assign) in phi_assignments {
+    for (pred_id, parallel) in copies {
         if let Some(block) = func.blocks.iter_mut().find(|b| b.id == pred_id) {
-            // Insert just before the terminator (i.e., at the end of the instruction list).
-            block.instructions.push(assign);
+            sequentialize(parallel, &mut fresh, &mut block.instructions);
         }
     }
 
-    // Remove phi instructions from their join blocks.
-    let phi_dests: HashSet<VarId> = phi_block_dests.iter().map(|(_, d)| *d).collect();
+    // Replace the phis with the copies out of their isolated variables.
     for block in &mut func.blocks {
         block
             .instructions
-            .retain(|i| !matches!(i, IrInstr::Phi { dest, .. } if phi_dests.contains(dest)));
+            .retain(|i| !matches!(i, IrInstr::Phi { .. }));
+        if let Some(entry) = entries.remove(&block.id) {
+            block.instructions.splice(0..0, entry);
+        }
+    }
+}
+
+/// Append the parallel copies `(dest, src)` to `out` as sequential
+/// assignments with the same effect, taking temporaries from `fresh`.
+fn sequentialize(
+    mut pending: Vec<(VarId, VarId)>,
+    fresh: &mut impl FnMut() -> VarId,
+    out: &mut Vec<IrInstr>,
+) {
+    pending.retain(|(dest, src)| dest != src);
+    while !pending.is_empty() {
+        // A copy whose dest no other pending copy reads can go now
+        let ready = (0..pending.len()).find(|&i| {
+            let dest = pending[i].0;
+            pending.iter().all(|&(_, src)| src != dest)
+        });
+        match ready {
+            Some(i) => {
+                let (dest, src) = pending.remove(i);
+                out.push(IrInstr::Assign { dest, src });
+            }
+            None => {
+                // Only cycles are left: save one dest, and read it from there
+                let (dest, _) = pending[0];
+                let saved = fresh();
+                out.push(IrInstr::Assign {
+                    dest: saved,
+                    src: dest,
+                });
+                for (_, src) in &mut pending {
+                    if *src == dest {
+                        *src = saved;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Variables live on entry to each block, phi dests excluded. A phi's
+/// source is live at the end of its predecessor.
+fn live_in(func: &IrFunction) -> HashMap<BlockId, HashSet<VarId>> {
+    let mut uses: HashMap<BlockId, HashSet<VarId>> = HashMap::new();
+    let mut defs: HashMap<BlockId, HashSet<VarId>> = HashMap::new();
+    let mut phi_uses: HashMap<BlockId, HashSet<VarId>> = HashMap::new();
+    for block in &func.blocks {
+        let used = uses.entry(block.id).or_default();
+        let defined = defs.entry(block.id).or_default();
+        for instr in &block.instructions {
+            if let IrInstr::Phi { dest, srcs } = instr {
+                defined.insert(*dest);
+                for (pred, src) in srcs {
+                    phi_uses.entry(*pred).or_default().insert(*src);
+                }
+                continue;
+            }
+            for_each_use(instr, |v| {
+                if !defined.contains(&v) {
+                    used.insert(v);
+                }
+            });
+            for_each_def(instr, |v| {
+                defined.insert(v);
+            });
+        }
+        for_each_use_terminator(&block.terminator, |v| {
+            if !defined.contains(&v) {
+                used.insert(v);
+            }
+        });
+    }
+
+    let mut live_in = uses.clone();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in func.blocks.iter().rev() {
+            let mut live_out = phi_uses.get(&block.id).cloned().unwrap_or_default();
+            for succ in terminator_successors(&block.terminator) {
+                if let Some(live) = live_in.get(&succ) {
+                    live_out.extend(live);
+                }
+            }
+            let defined = &defs[&block.id];
+            let live = live_in.get_mut(&block.id).unwrap();
+            for var in live_out {
+                if !defined.contains(&var) && live.insert(var) {
+                    changed = true;
+                }
+            }
+        }
     }
+    live_in
+}
+
+/// One past the largest variable `func` declares, defines or reads.
+fn next_var(func: &IrFunction) -> u32 {
+    let mut next = 0;
+    let mut see = |var: VarId| next = next.max(var.0 + 1);
+    for (var, _) in func.params.iter().chain(&func.locals) {
+        see(*var);
+    }
+    for block in &func.blocks {
+        for instr in &block.instructions {
+            if let IrInstr::Phi { dest, srcs } = instr {
+                see(*dest);
+                srcs.iter().for_each(|(_, v)| see(*v));
+            }
+            for_each_def(instr, &mut see);
+            for_each_use(instr, &mut see);
+        }
+        for_each_use_terminator(&block.terminator, &mut see);
+    }
+    next
 }
 
 /// Replace every read-occurrence of `old` with `new` in `instr`.
@@ -278,18 +430,25 @@ fn replace_phi_src(instr: &mut IrInstr, old: VarId, new: VarId) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ir::{BlockId, IrBlock, IrFunction, IrTerminator, IrValue, TypeIdx, VarId};
+    use crate::ir::{BinOp, BlockId, IrBlock, IrFunction, IrTerminator, IrValue, TypeIdx, VarId};
 
     fn make_module(blocks: Vec<IrBlock>) -> ModuleInfo {
         ModuleInfo {
             has_memory: false,
             has_memory_import: false,
+            memory_import_initial_pages: 0,
+            memory_import_max_pages: None,
             max_pages: 0,
             initial_pages: 0,
             table_initial: 0,
             table_max: 0,
+            has_table_import: false,
+            table_exports: Vec::new(),
             element_segments: Vec::new(),
             globals: Vec::new(),
+            stack_pointer: None,
+            heap_base: None,
+            data_end: None,
             data_segments: Vec::new(),
             passive_data_segments: Vec::new(),
             func_exports: Vec::new(),
@@ -302,10 +461,44 @@ mod tests {
                 locals: vec![],
                 blocks,
                 entry_block: BlockId(0),
-                return_type: None,
+                results: Vec::new(),
                 type_idx: TypeIdx::new(0),
             }],
             wasm_version: 1,
+            func_symbols: Vec::new(),
+            memory_exports: Vec::new(),
+            wasmtime_adapter: false,
+            yield_points: false,
+            export_features: Default::default(),
+            func_features: Vec::new(),
+            merged_into: Vec::new(),
+            dead_params: Vec::new(),
+            start: None,
+            dylink: None,
+            toolchains: Vec::new(),
+            lowered_atomics: false,
+            observe_memory: false,
+            record_replay: false,
+            dynamic_invoke: false,
+            export_groups: Vec::new(),
+            shared_host: false,
+            constant_time: Vec::new(),
+            unoptimized: Vec::new(),
+            ctors_evaluated: false,
+            func_idents: Vec::new(),
+            param_names: Vec::new(),
+            purity: Vec::new(),
+            link: false,
+            heap_profile: false,
+            debug_memory: false,
+            contracts: Default::default(),
+            coverage: false,
+            bounds_profile: false,
+            import_regions: Default::default(),
+            import_capabilities: Default::default(),
+            guest_log: Default::default(),
+            hot_reload: false,
+            pretransform: None,
         }
     }
 
@@ -329,7 +522,7 @@ mod tests {
                 srcs: vec![(BlockId(0), VarId(0)), (BlockId(0), VarId(0))],
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(1)),
+                values: vec![VarId(1)],
             },
         };
 
@@ -390,7 +583,7 @@ mod tests {
                 srcs: vec![(BlockId(1), VarId(1)), (BlockId(2), VarId(2))],
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(3)),
+                values: vec![VarId(3)],
             },
         };
 
@@ -422,4 +615,118 @@ mod tests {
             }
         )));
     }
+
+    /// Evaluate function 0 of `module` before and after lowering.
+    fn run_lowered(module: ModuleInfo) -> (Vec<IrValue>, Vec<IrValue>) {
+        use crate::ir::eval::Interpreter;
+        use crate::ir::LocalFuncIdx;
+        let run = |info: &ModuleInfo| {
+            Interpreter::new(info, 1 << 20)
+                .unwrap()
+                .call(LocalFuncIdx::new(0), &[])
+                .unwrap()
+        };
+        let built = run(&module);
+        (built, run(&lower(module)))
+    }
+
+    fn konst(dest: u32, value: i32) -> IrInstr {
+        IrInstr::Const {
+            dest: VarId(dest),
+            value: IrValue::I32(value),
+        }
+    }
+
+    fn binop(dest: u32, op: BinOp, lhs: u32, rhs: u32) -> IrInstr {
+        IrInstr::BinOp {
+            dest: VarId(dest),
+            op,
+            lhs: VarId(lhs),
+            rhs: VarId(rhs),
+        }
+    }
+
+    /// A loop whose header phis are `n`, `b` and `a`, with `a` taking the
+    /// previous `b`, and whose exit reads `b`.
+    fn fib_loop(exit_reads: u32) -> Vec<IrBlock> {
+        // block_0: v0=0; v1=1; v2=5; jump block_1
+        // block_1: v3=phi(n) v4=phi(b) v5=phi((block_1,v4))
+        //          v6=v5+v4; v7=v3-1; br_if v7 block_1 else block_2
+        // block_2: return exit_reads
+        let phi = |dest, init, next| IrInstr::Phi {
+            dest: VarId(dest),
+            srcs: vec![(BlockId(0), VarId(init)), (BlockId(1), VarId(next))],
+        };
+        vec![
+            IrBlock {
+                id: BlockId(0),
+                instructions: vec![konst(0, 0), konst(1, 1), konst(2, 5)],
+                terminator: IrTerminator::Jump { target: BlockId(1) },
+            },
+            IrBlock {
+                id: BlockId(1),
+                instructions: vec![
+                    phi(3, 2, 7),
+                    phi(4, 1, 6),
+                    phi(5, 0, 4),
+                    binop(6, BinOp::I32Add, 5, 4),
+                    konst(8, 1),
+                    binop(7, BinOp::I32Sub, 3, 8),
+                ],
+                terminator: IrTerminator::BranchIf {
+                    condition: VarId(7),
+                    if_true: BlockId(1),
+                    if_false: BlockId(2),
+                },
+            },
+            IrBlock {
+                id: BlockId(2),
+                instructions: vec![],
+                terminator: IrTerminator::Return {
+                    values: vec![VarId(exit_reads)],
+                },
+            },
+        ]
+    }
+
+    /// The back edge's copies must not clobber a phi the loop exit still
+    /// reads, nor a phi that another phi of the same block reads.
+    #[test]
+    fn phi_dests_live_past_the_back_edge_keep_their_value() {
+        for exit_reads in [4, 5, 6] {
+            let (built, lowered) = run_lowered(make_module(fib_loop(exit_reads)));
+            assert_eq!(built, lowered, "returning v{exit_reads}");
+        }
+    }
+
+    /// Phis that swap each other's values go through a temporary.
+    #[test]
+    fn swapping_phis_are_sequentialized() {
+        // block_1: v2=phi((block_1,v3)) v3=phi((block_1,v2)) v4=phi(n)
+        let mut blocks = fib_loop(0);
+        blocks[0].instructions = vec![konst(0, 1), konst(1, 2), konst(5, 3)];
+        blocks[1].instructions = vec![
+            IrInstr::Phi {
+                dest: VarId(2),
+                srcs: vec![(BlockId(0), VarId(0)), (BlockId(1), VarId(3))],
+            },
+            IrInstr::Phi {
+                dest: VarId(3),
+                srcs: vec![(BlockId(0), VarId(1)), (BlockId(1), VarId(2))],
+            },
+            IrInstr::Phi {
+                dest: VarId(4),
+                srcs: vec![(BlockId(0), VarId(5)), (BlockId(1), VarId(7))],
+            },
+            konst(8, 1),
+            binop(7, BinOp::I32Sub, 4, 8),
+            binop(6, BinOp::I32Sub, 2, 3),
+        ];
+        blocks[2].terminator = IrTerminator::Return {
+            values: vec![VarId(6)],
+        };
+        let (built, lowered) = run_lowered(make_module(blocks));
+        assert_eq!(built, vec![IrValue::I32(-1)]);
+        assert_eq!(built, lowered);
+    }
 }
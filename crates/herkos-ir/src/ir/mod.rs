@@ -13,16 +13,22 @@ mod types;
 pub use types::*;
 
 pub mod builder;
-pub use builder::{build_module_info, ModuleContext};
+pub use builder::{build_module_info, BuildOptions, ModuleContext, CTORS_EXPORT};
+
+pub mod eval;
 
 pub mod lower_phis;
 
+pub mod purity;
+pub use purity::{compute_purity, Purity};
+
 /// [`ModuleInfo`] with all `IrInstr::Phi` nodes lowered to `IrInstr::Assign`.
 ///
 /// Constructed exclusively by [`lower_phis::lower`]. Signals the phase
 /// boundary between SSA IR (with phi nodes) and post-SSA IR (without).
 /// After this point, no optimizer pass or codegen module will encounter
 /// `IrInstr::Phi` in any function body.
+#[derive(Debug)]
 pub struct LoweredModuleInfo(ModuleInfo);
 
 impl std::ops::Deref for LoweredModuleInfo {
@@ -0,0 +1,284 @@
+//! Side-effect-free functions.
+//!
+//! A function is **pure** when calling it changes nothing the caller or the
+//! host can observe: it stores to no memory, sets no global, does not grow
+//! memory or drop a data segment, and calls no import and no impure local
+//! function. Indirect calls count as impure, since the callee is only known
+//! at run time. Pure functions may still read memory and globals, so two
+//! calls with the same arguments can return different results.
+//!
+//! A pure function is **total** when it also always returns: it contains no
+//! instruction that can trap (loads, integer division, float truncation,
+//! `unreachable`), no loop, and calls only total functions, none of them
+//! recursively. A call to a total function whose results are unused can be
+//! deleted.
+//!
+//! Not to be confused with [`ModuleInfo::is_pure`], which asks whether the
+//! module as a whole has state.
+
+use super::{IrFunction, IrInstr, IrTerminator, ModuleInfo};
+use crate::optimizer::utils::{compute_rpo, is_side_effect_free, terminator_successors};
+use std::collections::HashMap;
+
+/// What calling a function can do besides returning its results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Purity {
+    /// May write memory or globals, or call the host.
+    #[default]
+    Impure,
+    /// Changes no state, but may trap or not terminate.
+    Pure,
+    /// Changes no state and always returns.
+    Total,
+}
+
+impl Purity {
+    /// Whether the function changes no state (pure or total).
+    pub fn is_pure(self) -> bool {
+        self != Purity::Impure
+    }
+
+    /// Lowercase name, as in the audit report.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Purity::Impure => "impure",
+            Purity::Pure => "pure",
+            Purity::Total => "total",
+        }
+    }
+}
+
+/// The purity of each local function, parallel to `info.ir_functions`.
+///
+/// Purity is a greatest fixpoint (mutually recursive functions that only
+/// call each other are pure), totality a least one (recursion is never
+/// total).
+pub fn compute_purity(info: &ModuleInfo) -> Vec<Purity> {
+    let funcs = &info.ir_functions;
+    let callees: Vec<Vec<usize>> = funcs.iter().map(direct_callees).collect();
+
+    let mut pure: Vec<bool> = funcs.iter().map(changes_no_state).collect();
+    loop {
+        let mut changed = false;
+        for idx in 0..funcs.len() {
+            if pure[idx]
+                && callees[idx]
+                    .iter()
+                    .any(|&c| !pure.get(c).copied().unwrap_or(false))
+            {
+                pure[idx] = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let terminates: Vec<bool> = funcs.iter().map(always_returns).collect();
+    let mut total = vec![false; funcs.len()];
+    loop {
+        let mut changed = false;
+        for idx in 0..funcs.len() {
+            if !total[idx]
+                && pure[idx]
+                && terminates[idx]
+                && callees[idx]
+                    .iter()
+                    .all(|&c| total.get(c).copied().unwrap_or(false))
+            {
+                total[idx] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    (0..funcs.len())
+        .map(|idx| match (pure[idx], total[idx]) {
+            (_, true) => Purity::Total,
+            (true, false) => Purity::Pure,
+            (false, false) => Purity::Impure,
+        })
+        .collect()
+}
+
+/// Local functions `func` calls directly.
+fn direct_callees(func: &IrFunction) -> Vec<usize> {
+    func.blocks
+        .iter()
+        .flat_map(|b| &b.instructions)
+        .filter_map(|instr| match instr {
+            IrInstr::Call { func_idx, .. } => Some(func_idx.as_usize()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `func`'s own instructions leave memory, globals and the host alone.
+fn changes_no_state(func: &IrFunction) -> bool {
+    func.blocks
+        .iter()
+        .flat_map(|b| &b.instructions)
+        .all(|instr| {
+            !matches!(
+                instr,
+                IrInstr::Store { .. }
+                    | IrInstr::GlobalSet { .. }
+                    | IrInstr::MemoryGrow { .. }
+                    | IrInstr::MemoryCopy { .. }
+                    | IrInstr::MemoryFill { .. }
+                    | IrInstr::MemoryInit { .. }
+                    | IrInstr::DataDrop { .. }
+                    | IrInstr::CallImport { .. }
+                    | IrInstr::CallIndirect { .. }
+            )
+        })
+}
+
+/// Whether `func` cannot trap or loop, apart from what its callees do.
+fn always_returns(func: &IrFunction) -> bool {
+    let no_traps = func.blocks.iter().all(|b| {
+        !matches!(b.terminator, IrTerminator::Unreachable)
+            && b.instructions.iter().all(|instr| {
+                matches!(instr, IrInstr::Call { .. } | IrInstr::Phi { .. })
+                    || is_side_effect_free(instr)
+            })
+    });
+    no_traps && !has_cycle(func)
+}
+
+/// Whether the blocks reachable from the entry form a cycle: some edge goes
+/// back to a block at or before its source in reverse postorder.
+fn has_cycle(func: &IrFunction) -> bool {
+    let order: HashMap<_, _> = compute_rpo(func)
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| (id, i))
+        .collect();
+    func.blocks.iter().any(|block| {
+        let Some(&from) = order.get(&block.id) else {
+            return false;
+        };
+        terminator_successors(&block.terminator)
+            .iter()
+            .any(|succ| order.get(succ).is_some_and(|&to| to <= from))
+    })
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BinOp, BlockId, GlobalIdx, IrBlock, LocalFuncIdx, TypeIdx, VarId, WasmType};
+
+    fn func(blocks: Vec<IrBlock>) -> IrFunction {
+        IrFunction {
+            params: vec![(VarId(0), WasmType::I32)],
+            locals: vec![],
+            blocks,
+            entry_block: BlockId(0),
+            results: vec![WasmType::I32],
+            type_idx: TypeIdx::new(0),
+        }
+    }
+
+    fn block(id: u32, instructions: Vec<IrInstr>, terminator: IrTerminator) -> IrBlock {
+        IrBlock {
+            id: BlockId(id),
+            instructions,
+            terminator,
+        }
+    }
+
+    fn ret(var: u32) -> IrTerminator {
+        IrTerminator::Return {
+            values: vec![VarId(var)],
+        }
+    }
+
+    fn binop(op: BinOp) -> IrInstr {
+        IrInstr::BinOp {
+            dest: VarId(1),
+            op,
+            lhs: VarId(0),
+            rhs: VarId(0),
+        }
+    }
+
+    fn call(callee: usize) -> IrInstr {
+        IrInstr::Call {
+            dests: vec![VarId(1)],
+            func_idx: LocalFuncIdx::new(callee),
+            args: vec![VarId(0)],
+        }
+    }
+
+    fn purity_of(funcs: Vec<IrFunction>) -> Vec<Purity> {
+        compute_purity(&ModuleInfo {
+            ir_functions: funcs,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn classifies_state_traps_and_calls() {
+        let purity = purity_of(vec![
+            // 0: arithmetic only
+            func(vec![block(0, vec![binop(BinOp::I32Add)], ret(1))]),
+            // 1: division may trap
+            func(vec![block(0, vec![binop(BinOp::I32DivU)], ret(1))]),
+            // 2: writes a global
+            func(vec![block(
+                0,
+                vec![IrInstr::GlobalSet {
+                    index: GlobalIdx::new(0),
+                    value: VarId(0),
+                }],
+                ret(0),
+            )]),
+            // 3..=5: call each of the above
+            func(vec![block(0, vec![call(0)], ret(1))]),
+            func(vec![block(0, vec![call(1)], ret(1))]),
+            func(vec![block(0, vec![call(2)], ret(1))]),
+        ]);
+        assert_eq!(
+            purity,
+            [
+                Purity::Total,
+                Purity::Pure,
+                Purity::Impure,
+                Purity::Total,
+                Purity::Pure,
+                Purity::Impure,
+            ]
+        );
+    }
+
+    #[test]
+    fn loops_and_recursion_are_pure_but_not_total() {
+        let purity = purity_of(vec![
+            // 0: B0 → B1 → B0
+            func(vec![
+                block(0, vec![], IrTerminator::Jump { target: BlockId(1) }),
+                block(
+                    1,
+                    vec![],
+                    IrTerminator::BranchIf {
+                        condition: VarId(0),
+                        if_true: BlockId(0),
+                        if_false: BlockId(2),
+                    },
+                ),
+                block(2, vec![], ret(0)),
+            ]),
+            // 1 and 2 call each other
+            func(vec![block(0, vec![call(2)], ret(1))]),
+            func(vec![block(0, vec![call(1)], ret(1))]),
+        ]);
+        assert_eq!(purity, [Purity::Pure, Purity::Pure, Purity::Pure]);
+    }
+}
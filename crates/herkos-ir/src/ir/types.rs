@@ -5,11 +5,13 @@
 //! more IR instructions, with explicit variable names (v0, v1, ...) instead
 //! of an implicit stack.
 
+use super::purity::Purity;
 use std::fmt;
+use std::sync::Arc;
 
 /// Unique identifier for a variable in SSA form.
 /// Variables are numbered sequentially: v0, v1, v2, ...
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct VarId(pub u32);
 
 /// One-time-use definition token for an SSA variable.
@@ -116,7 +118,7 @@ impl fmt::Display for VarId {
 }
 
 /// Unique identifier for a basic block.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BlockId(pub u32);
 
 impl fmt::Display for BlockId {
@@ -198,8 +200,9 @@ pub struct IrFunction {
     /// is the first block created during IR translation.
     pub entry_block: BlockId,
 
-    /// Return type (None for void functions)
-    pub return_type: Option<WasmType>,
+    /// Result types, in order (empty for void functions; more than one
+    /// with multi-value, returned as a tuple)
+    pub results: Vec<WasmType>,
 
     /// Index into the Wasm type section (needed for call_indirect dispatch).
     pub type_idx: TypeIdx,
@@ -234,6 +237,21 @@ pub enum MemoryAccessWidth {
     I32,
 }
 
+impl MemoryAccessWidth {
+    /// Bytes touched by a load or store of `ty` with this width.
+    pub fn bytes(self, ty: WasmType) -> u32 {
+        match self {
+            MemoryAccessWidth::I8 => 1,
+            MemoryAccessWidth::I16 => 2,
+            MemoryAccessWidth::I32 => 4,
+            MemoryAccessWidth::Full => match ty {
+                WasmType::I32 | WasmType::F32 => 4,
+                WasmType::I64 | WasmType::F64 => 8,
+            },
+        }
+    }
+}
+
 /// Sign extension for sub-width loads.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SignExtension {
@@ -299,23 +317,23 @@ pub enum IrInstr {
 
     /// Call direct function (local, not imported)
     Call {
-        dest: Option<VarId>,    // None for void functions
+        dests: Vec<VarId>,      // One per result; empty for void functions
         func_idx: LocalFuncIdx, // Local function index (imports are handled separately)
         args: Vec<VarId>,
     },
 
     /// Call imported function from host
     CallImport {
-        dest: Option<VarId>,   // None for void functions
+        dests: Vec<VarId>,     // One per result; empty for void functions
         import_idx: ImportIdx, // Index into the imports list
-        module_name: String,   // Import module name (e.g., "env")
-        func_name: String,     // Import field name (e.g., "log")
+        module_name: Arc<str>, // Import module name (e.g., "env"), interned
+        func_name: Arc<str>,   // Import field name (e.g., "log"), interned
         args: Vec<VarId>,
     },
 
     /// Call indirect (via table)
     CallIndirect {
-        dest: Option<VarId>,
+        dests: Vec<VarId>,
         type_idx: TypeIdx,
         table_idx: VarId,
         args: Vec<VarId>,
@@ -384,8 +402,8 @@ pub enum IrInstr {
 /// Block terminator — how control flow exits a basic block.
 #[derive(Debug, Clone)]
 pub enum IrTerminator {
-    /// Return from function
-    Return { value: Option<VarId> },
+    /// Return from function, with one value per function result
+    Return { values: Vec<VarId> },
 
     /// Unconditional jump to target block
     Jump { target: BlockId },
@@ -809,17 +827,34 @@ impl fmt::Display for BinOp {
 pub struct GlobalDef {
     /// Whether the global is mutable.
     pub mutable: bool,
-    /// The constant initializer value (also encodes the type).
+    /// The initializer value (also encodes the type).
     pub init_value: GlobalInit,
+    /// Name of the `Globals` field holding this global: its name-section
+    /// name in snake_case when that is a usable identifier, else `g{index}`.
+    pub field: String,
+}
+
+impl GlobalDef {
+    /// Whether the global is emitted as a `const` item: immutable, with a
+    /// constant initializer. All others are `Globals` fields.
+    pub fn is_const(&self) -> bool {
+        !self.mutable && !matches!(self.init_value, GlobalInit::Imported { .. })
+    }
 }
 
-/// Constant initializer value for a global.
+/// Initializer value for a global.
 #[derive(Debug, Clone, Copy)]
 pub enum GlobalInit {
     I32(i32),
     I64(i64),
     F32(f32),
     F64(f64),
+    /// Value of an immutable imported global, read from the host when the
+    /// module is instantiated.
+    Imported {
+        index: ImportedGlobalIdx,
+        ty: WasmType,
+    },
 }
 
 impl GlobalInit {
@@ -830,6 +865,7 @@ impl GlobalInit {
             GlobalInit::I64(_) => WasmType::I64,
             GlobalInit::F32(_) => WasmType::F32,
             GlobalInit::F64(_) => WasmType::F64,
+            GlobalInit::Imported { ty, .. } => *ty,
         }
     }
 }
@@ -839,8 +875,23 @@ impl GlobalInit {
 pub struct DataSegmentDef {
     /// Byte offset into memory.
     pub offset: u32,
+    /// Imported global `offset` is relative to (dylink's `__memory_base`).
+    pub base: Option<ImportedGlobalIdx>,
     /// Raw bytes to write.
     pub data: Vec<u8>,
+    /// Never written by the module: emitted as a `pub const RODATA_{i}`
+    /// array, with constant-address loads folded (see
+    /// [`crate::optimizer::promote_read_only_data`]).
+    pub read_only: bool,
+    /// Copied into linear memory at instantiation. Only a read-only segment
+    /// that no remaining load can observe is left out.
+    pub resident: bool,
+    /// Name from the `name` section (`.rodata` for wasm-ld's constant
+    /// data), if any.
+    pub name: Option<String>,
+    /// Made read-only in linear memory once the module is initialized
+    /// (`herkos_core::TranspileOptions::protect_rodata`).
+    pub protected: bool,
 }
 
 /// A passive data segment (bulk-memory proposal).
@@ -870,10 +921,10 @@ pub struct FuncExport {
 /// Signature of a function.
 #[derive(Debug, Clone)]
 pub struct FuncSignature {
-    /// Parameter types.
-    pub params: Vec<WasmType>,
-    /// Return type (None for void).
-    pub return_type: Option<WasmType>,
+    /// Parameter types, shared with every import of this type.
+    pub params: Arc<[WasmType]>,
+    /// Result types (empty for void).
+    pub results: Vec<WasmType>,
     /// Index into the Wasm type section (needed for call_indirect dispatch).
     /// Note: This field is currently always set to 0 and not used in codegen.
     pub type_idx: TypeIdx,
@@ -884,29 +935,99 @@ pub struct FuncSignature {
 pub struct ElementSegmentDef {
     /// Starting offset in the table.
     pub offset: usize,
-    /// Function indices to place into the table starting at `offset`.
-    /// These are in the local function index space (imports already subtracted).
-    pub func_indices: Vec<LocalFuncIdx>,
+    /// Imported global `offset` is relative to (dylink's `__table_base`).
+    pub base: Option<ImportedGlobalIdx>,
+    /// Functions to place into the table starting at `offset`.
+    pub func_indices: Vec<ElementFunc>,
+}
+
+/// A function placed into a table by an element segment.
+#[derive(Debug, Clone)]
+pub enum ElementFunc {
+    /// A local function (imports already subtracted from the Wasm index).
+    Local(LocalFuncIdx),
+    /// A function import, dispatched through the host trait.
+    Import {
+        /// Index into `ModuleInfo::func_imports`.
+        import: ImportIdx,
+        /// Canonical type index of the import's signature.
+        type_idx: TypeIdx,
+    },
+}
+
+/// Dynamic-linking metadata of a side module (`dylink.0` custom section).
+///
+/// The loader reserves `memory_size` bytes at `__memory_base` and
+/// `table_size` slots at `__table_base` for the module; its data and element
+/// segments are placed relative to those imported globals.
+#[derive(Debug, Clone, Default)]
+pub struct DylinkDef {
+    /// Bytes of memory the module's data needs.
+    pub memory_size: u32,
+    /// Required alignment of the memory area, as a power of two.
+    pub memory_alignment: u32,
+    /// Table slots the module's element segments need.
+    pub table_size: u32,
+    /// Required alignment of the table area, as a power of two.
+    pub table_alignment: u32,
+    /// Shared libraries this module depends on.
+    pub needed: Vec<String>,
+    /// The imported `env.__table_base` global, if any.
+    pub table_base: Option<ImportedGlobalIdx>,
+}
+
+/// A toolchain the `producers` custom section names, whose recognition
+/// layers can enable their shims without a flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Toolchain {
+    /// Emscripten (`sdk: Emscripten`).
+    Emscripten,
+    /// TinyGo (`processed-by: TinyGo`).
+    TinyGo,
+    /// AssemblyScript (`language: AssemblyScript`).
+    AssemblyScript,
+    /// wasm-bindgen (`processed-by: wasm-bindgen`).
+    WasmBindgen,
+}
+
+impl Toolchain {
+    /// Every toolchain, in recognition order.
+    pub const ALL: [Toolchain; 4] = [
+        Toolchain::Emscripten,
+        Toolchain::TinyGo,
+        Toolchain::AssemblyScript,
+        Toolchain::WasmBindgen,
+    ];
+
+    /// Name it goes by in the `producers` section, compared ignoring case.
+    pub fn producer_name(self) -> &'static str {
+        match self {
+            Toolchain::Emscripten => "Emscripten",
+            Toolchain::TinyGo => "TinyGo",
+            Toolchain::AssemblyScript => "AssemblyScript",
+            Toolchain::WasmBindgen => "wasm-bindgen",
+        }
+    }
 }
 
 /// An imported function for trait generation.
 #[derive(Debug, Clone)]
 pub struct FuncImport {
-    /// Import module name (e.g., "env").
-    pub module_name: String,
-    /// Import function name (e.g., "log").
-    pub func_name: String,
-    /// Parameter types.
-    pub params: Vec<WasmType>,
-    /// Return type (None for void).
-    pub return_type: Option<WasmType>,
+    /// Import module name (e.g., "env"), interned across all imports.
+    pub module_name: Arc<str>,
+    /// Import function name (e.g., "log"), shared with `CallImport` sites.
+    pub func_name: Arc<str>,
+    /// Parameter types, shared with the type section signature.
+    pub params: Arc<[WasmType]>,
+    /// Result types (empty for void).
+    pub results: Vec<WasmType>,
 }
 
 /// An imported global variable.
 #[derive(Debug, Clone)]
 pub struct ImportedGlobalDef {
-    /// Import module name.
-    pub module_name: String,
+    /// Import module name, interned across all imports.
+    pub module_name: Arc<str>,
     /// Import field name (used as method name in host trait).
     pub name: String,
     /// The Wasm value type.
@@ -915,6 +1036,81 @@ pub struct ImportedGlobalDef {
     pub mutable: bool,
 }
 
+/// Debug metadata for a local function (used for the generated symbol table).
+#[derive(Debug, Clone, Default)]
+pub struct FuncSymbolDef {
+    /// Original name from the Wasm `name` section, if present.
+    pub name: Option<String>,
+    /// Byte range of the function body in the original Wasm binary.
+    pub code_range: std::ops::Range<usize>,
+    /// Byte offset in the original Wasm binary of the operator each block
+    /// starts at, for the blocks the builder saw start in live code.
+    pub block_offsets: Vec<(BlockId, usize)>,
+    /// Most values the Wasm operand stack of the body holds at once.
+    pub max_stack_depth: usize,
+}
+
+/// A precondition on one argument of an export (see `herkos_core::contracts`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParamContract {
+    /// Index of the parameter.
+    pub param: usize,
+    /// What the argument must satisfy.
+    pub kind: ContractKind,
+}
+
+/// What a [`ParamContract`] requires of an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractKind {
+    /// The argument lies in `min..=max`.
+    Range { min: i64, max: i64 },
+    /// The argument, an address in linear memory, is not 0.
+    NonNull,
+}
+
+/// A region of linear memory an import argument points to, checked to be in
+/// bounds before the host is called (see `herkos_core::bindings`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuestRegion {
+    /// Name of the bound parameter.
+    pub name: String,
+    /// Index of the parameter holding the address.
+    pub ptr: usize,
+    /// Length of the region in bytes.
+    pub len: RegionLen,
+}
+
+/// Length of a [`GuestRegion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionLen {
+    /// Given by the argument at this parameter index.
+    Param(usize),
+    /// Known from the binding type.
+    Fixed(u32),
+}
+
+/// How a logging import recognized by `herkos_core::TranspileOptions::guest_log`
+/// passes its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuestLogImport {
+    /// `(ptr: i32, len: i32)`: UTF-8 bytes in linear memory.
+    PtrLen,
+    /// WASI `fd_write(fd, iovs, iovs_len, nwritten) -> errno`.
+    FdWrite,
+}
+
+/// A transform the Wasm binary went through before it was parsed (see
+/// `herkos_core::TranspileOptions::pretransform`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreTransformRecord {
+    /// Name of the transform, such as the command that ran.
+    pub name: String,
+    /// FNV-1a hash of the binary given to the transform.
+    pub input_hash: u64,
+    /// FNV-1a hash of the binary it returned, the one transpiled.
+    pub output_hash: u64,
+}
+
 /// Module-level information describing a WebAssembly module.
 /// Memory ownership model for a WebAssembly module.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -942,10 +1138,25 @@ pub struct ModuleInfo {
     pub table_initial: usize,
     /// Maximum table size (for const generic TABLE_MAX).
     pub table_max: usize,
+    /// Whether the table is imported rather than locally declared
+    /// (LLVM's `-Wl,--import-table`). Sizes then come from the import.
+    pub has_table_import: bool,
+    /// Names the table is exported under (LLVM's
+    /// `__indirect_function_table`).
+    pub table_exports: Vec<String>,
     /// Element segments for table initialization.
     pub element_segments: Vec<ElementSegmentDef>,
     /// Global variable definitions (mutable + immutable).
     pub globals: Vec<GlobalDef>,
+    /// LLVM's shadow stack pointer (`__stack_pointer`), if the module
+    /// defines it as a mutable `i32` global.
+    pub stack_pointer: Option<LocalGlobalIdx>,
+    /// LLVM's `__heap_base`, where the heap starts, if the module defines
+    /// it as an `i32` global with a constant initializer.
+    pub heap_base: Option<LocalGlobalIdx>,
+    /// LLVM's `__data_end`, the end of static data, if the module defines
+    /// it as an `i32` global with a constant initializer.
+    pub data_end: Option<LocalGlobalIdx>,
     /// Data segments for memory initialization.
     pub data_segments: Vec<DataSegmentDef>,
     /// Passive data segments (bulk-memory proposal).
@@ -953,6 +1164,8 @@ pub struct ModuleInfo {
     pub passive_data_segments: Vec<PassiveDataSegment>,
     /// Exported functions.
     pub func_exports: Vec<FuncExport>,
+    /// Names the linear memory is exported under.
+    pub memory_exports: Vec<String>,
     /// Type section signatures (for call_indirect dispatch).
     pub type_signatures: Vec<FuncSignature>,
     /// Canonical type index mapping: maps each Wasm type index to the
@@ -964,12 +1177,133 @@ pub struct ModuleInfo {
     pub func_imports: Vec<FuncImport>,
     /// Whether memory is imported rather than locally declared.
     pub has_memory_import: bool,
+    /// Initial pages the memory import declares: the borrowed memory must
+    /// be at least this large.
+    pub memory_import_initial_pages: usize,
+    /// Maximum pages the memory import declares, if any. `memory.grow` on the
+    /// borrowed memory fails beyond it even when the host's memory is larger.
+    pub memory_import_max_pages: Option<usize>,
     /// Imported global definitions, in import declaration order.
     pub imported_globals: Vec<ImportedGlobalDef>,
     /// All IR functions in the module.
     pub ir_functions: Vec<IrFunction>,
     /// Wasm binary version from the module header.
     pub wasm_version: u16,
+    /// Per-function debug metadata, parallel to `ir_functions`.
+    pub func_symbols: Vec<FuncSymbolDef>,
+    /// Rust (and C) identifier of each local function, parallel to
+    /// `ir_functions`: `func_{name}` from the name section, or empty for the
+    /// positional `func_{idx}` (see [`ModuleInfo::func_ident`]).
+    pub func_idents: Vec<String>,
+    /// Parameter names of each local function from the name section,
+    /// parallel to `ir_functions`; empty for the positional `v{i}` (see
+    /// [`ModuleInfo::param_name`]).
+    pub param_names: Vec<Vec<String>>,
+    /// Whether the static constructors were evaluated at transpile time and
+    /// their export emptied (see `BuildOptions::call_ctors`).
+    pub ctors_evaluated: bool,
+    /// Whether to emit the wasmtime-style adapter
+    /// (`herkos_runtime::wasmtime_compat`).
+    pub wasmtime_adapter: bool,
+    /// Whether loop headers call the host's `maybe_yield` (see
+    /// `herkos_core::TranspileOptions::yield_points`).
+    pub yield_points: bool,
+    /// Cargo feature each gated export is compiled under (see
+    /// `herkos_core::features`). Ungated exports are absent.
+    pub export_features: std::collections::BTreeMap<String, String>,
+    /// Cargo features that compile in each local function, parallel to
+    /// `ir_functions`. Empty for functions that are always compiled.
+    pub func_features: Vec<Vec<String>>,
+    /// For each local function whose body duplicates an earlier one, the
+    /// function that replaced it (see `optimizer::merge_duplicate_functions`),
+    /// parallel to `ir_functions`. Nothing references a merged function and
+    /// codegen skips it. Empty when the pass did not run.
+    pub merged_into: Vec<Option<LocalFuncIdx>>,
+    /// Parameters of each local function that
+    /// `optimizer::eliminate_dead_arguments` removed, as Wasm position and
+    /// type in ascending order, parallel to `ir_functions`. The function's
+    /// public method keeps the Wasm signature (see [`ModuleInfo::wasm_params`])
+    /// and does not pass these on. Empty when the pass did not run.
+    pub dead_params: Vec<Vec<(usize, WasmType)>>,
+    /// Start function the generated `new()` calls once the segments are
+    /// applied. `None` when the module has none, or when its effects were
+    /// evaluated at transpile time into the initial state.
+    pub start: Option<LocalFuncIdx>,
+    /// `dylink.0` metadata, if this is a dynamic-linking side module.
+    pub dylink: Option<DylinkDef>,
+    /// Toolchains named in the `producers` custom section (see
+    /// [`ModuleInfo::built_with`]).
+    pub toolchains: Vec<Toolchain>,
+    /// Whether atomic operators were lowered to plain memory accesses (see
+    /// [`BuildOptions::lower_atomics`](crate::ir::BuildOptions::lower_atomics)).
+    pub lowered_atomics: bool,
+    /// Whether loads and stores report to the host's `MemoryObserver` (see
+    /// `herkos_core::TranspileOptions::observe_memory`).
+    pub observe_memory: bool,
+    /// Whether to emit the `Recorder` / `Replayer` host wrappers (see
+    /// `herkos_core::TranspileOptions::record_replay`).
+    pub record_replay: bool,
+    /// Whether to emit the `invoke` method dispatching on export names (see
+    /// `herkos_core::TranspileOptions::dynamic_invoke`).
+    pub dynamic_invoke: bool,
+    /// Validated export group prefixes (see
+    /// `herkos_core::TranspileOptions::export_groups`).
+    pub export_groups: Vec<String>,
+    /// Whether to emit the `SharedWasmModule` wrapper (see
+    /// `herkos_core::TranspileOptions::shared_host`).
+    pub shared_host: bool,
+    /// Whether each local function is generated in constant-time form,
+    /// parallel to `ir_functions` (see
+    /// `herkos_core::TranspileOptions::constant_time`). The optimizer leaves
+    /// these functions as translated. Empty when no export was selected.
+    pub constant_time: Vec<bool>,
+    /// Whether each local function bypasses the optimizer, parallel to
+    /// `ir_functions` (see `herkos_core::TranspileOptions::no_optimize`).
+    /// Empty when no function was listed.
+    pub unoptimized: Vec<bool>,
+    /// What calling each local function can do besides returning, parallel
+    /// to `ir_functions` (see [`compute_purity`](super::compute_purity)).
+    /// Filled in by `optimizer::optimize_lowered_ir`; empty before.
+    pub purity: Vec<Purity>,
+    /// Whether to emit the `LinkedWasmModule` wrapper and the
+    /// `ModuleHostTrait` impl for `herkos_runtime::Linker` (see
+    /// `herkos_core::TranspileOptions::link`).
+    pub link: bool,
+    /// Whether calls to the guest allocator are tracked by a heap profile
+    /// in `Globals` (see `herkos_core::TranspileOptions::heap_profile`).
+    pub heap_profile: bool,
+    /// Whether to emit `DATA_SEGMENTS` and `arm_debug_memory` (see
+    /// `herkos_core::TranspileOptions::debug_memory`).
+    pub debug_memory: bool,
+    /// Preconditions on the arguments of each export, by export name, from
+    /// a contracts file or the `herkos.contracts` custom section (see
+    /// `herkos_core::contracts`). Exports without contracts are absent.
+    pub contracts: std::collections::BTreeMap<String, Vec<ParamContract>>,
+    /// Whether every block counts its executions in `Globals`, for a
+    /// coverage report keyed by Wasm offset (see
+    /// `herkos_core::TranspileOptions::coverage`).
+    pub coverage: bool,
+    /// Whether every memory access counts its bounds checks in `Globals`, for
+    /// a heat map of the hottest checks (see
+    /// `herkos_core::TranspileOptions::bounds_profile`).
+    pub bounds_profile: bool,
+    /// Memory regions the arguments of each function import point to, by
+    /// index into `func_imports`, checked before every call to the host.
+    /// Imports without checks are absent.
+    pub import_regions: std::collections::BTreeMap<usize, Vec<GuestRegion>>,
+    /// Capability trait of each regrouped function import, by index into
+    /// `func_imports` (see `herkos_core::capabilities`). Imports declared
+    /// directly in `ModuleHostTrait` are absent.
+    pub import_capabilities: std::collections::BTreeMap<usize, String>,
+    /// Logging imports routed to `herkos_runtime::GuestLog`, by index into
+    /// `func_imports` (see `herkos_core::TranspileOptions::guest_log`).
+    pub guest_log: std::collections::BTreeMap<usize, GuestLogImport>,
+    /// Whether to emit the `HotPlugin` impl and its C-ABI vtable (see
+    /// `herkos_core::TranspileOptions::hot_reload`).
+    pub hot_reload: bool,
+    /// The transform applied to the binary before parsing, recorded in the
+    /// generated code.
+    pub pretransform: Option<PreTransformRecord>,
 }
 
 impl ModuleInfo {
@@ -980,6 +1314,83 @@ impl ModuleInfo {
         self.func_imports.len()
     }
 
+    /// Whether local function `idx` was merged into an identical one and
+    /// generates no code (see [`ModuleInfo::merged_into`]).
+    pub fn is_merged(&self, idx: usize) -> bool {
+        self.merged_into.get(idx).is_some_and(Option::is_some)
+    }
+
+    /// The Wasm parameter types of local function `func`, each with whether
+    /// the function still takes it (see [`ModuleInfo::dead_params`]).
+    pub fn wasm_params(&self, func: usize) -> Vec<(WasmType, bool)> {
+        let dead = self.dead_params.get(func).map_or(&[][..], Vec::as_slice);
+        let mut live = self.ir_functions[func].params.iter();
+        let mut dead = dead.iter().peekable();
+        let mut params = Vec::new();
+        loop {
+            if let Some(&(_, ty)) = dead.next_if(|(pos, _)| *pos == params.len()) {
+                params.push((ty, false));
+            } else if let Some((_, ty)) = live.next() {
+                params.push((*ty, true));
+            } else {
+                return params;
+            }
+        }
+    }
+
+    /// Whether local function `idx` is generated in constant-time form (see
+    /// [`ModuleInfo::constant_time`]).
+    pub fn is_constant_time(&self, idx: usize) -> bool {
+        self.constant_time.get(idx).copied().unwrap_or(false)
+    }
+
+    /// Whether the `producers` section says `toolchain` built the module.
+    pub fn built_with(&self, toolchain: Toolchain) -> bool {
+        self.toolchains.contains(&toolchain)
+    }
+
+    /// Whether the optimizer leaves local function `idx` as translated:
+    /// listed in [`ModuleInfo::unoptimized`], or constant-time.
+    pub fn skips_optimizer(&self, idx: usize) -> bool {
+        self.is_constant_time(idx) || self.unoptimized.get(idx).copied().unwrap_or(false)
+    }
+
+    /// What calling local function `idx` can do besides returning (see
+    /// [`ModuleInfo::purity`]).
+    pub fn purity_of(&self, idx: usize) -> Purity {
+        self.purity.get(idx).copied().unwrap_or_default()
+    }
+
+    /// The identifier local function `idx` is generated under (see
+    /// [`ModuleInfo::func_idents`]).
+    pub fn func_ident(&self, idx: usize) -> String {
+        match self.func_idents.get(idx) {
+            Some(ident) if !ident.is_empty() => ident.clone(),
+            _ => format!("func_{idx}"),
+        }
+    }
+
+    /// The name of parameter `param` of local function `func` in the
+    /// generated methods (see [`ModuleInfo::param_names`]).
+    pub fn param_name(&self, func: usize, param: usize) -> String {
+        match self
+            .param_names
+            .get(func)
+            .and_then(|names| names.get(param))
+        {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("v{param}"),
+        }
+    }
+
+    /// The local function exported as `__wasm_call_ctors`, if any.
+    pub fn ctors(&self) -> Option<LocalFuncIdx> {
+        self.func_exports
+            .iter()
+            .find(|e| e.name == super::CTORS_EXPORT)
+            .map(|e| e.func_index)
+    }
+
     // ─── Typed accessors ───────────────────────────────────────────────────
 
     /// Get an IR function by local function index.
@@ -1062,9 +1473,56 @@ impl ModuleInfo {
         self.globals.iter().any(|g| g.mutable)
     }
 
+    /// Whether the `Globals` struct has fields (see [`GlobalDef::is_const`]).
+    pub fn has_global_fields(&self) -> bool {
+        self.globals.iter().any(|g| !g.is_const())
+    }
+
+    /// Whether `new()` takes the host mutably, to call a start function
+    /// that reaches it (see [`ModuleInfo::start`]).
+    pub fn start_uses_host(&self) -> bool {
+        self.start.is_some() && self.uses_host()
+    }
+
+    /// Whether `new()` takes the host, to read the imported globals some
+    /// global initializers, or the offsets of segments it applies, refer to.
+    pub fn init_reads_host(&self) -> bool {
+        self.globals
+            .iter()
+            .any(|g| matches!(g.init_value, GlobalInit::Imported { .. }))
+            || (self.has_memory && self.data_segments.iter().any(|s| s.base.is_some()))
+            || (self.owns_table() && self.element_segments.iter().any(|s| s.base.is_some()))
+    }
+
     /// Whether the module has a non-trivial table (for indirect calls).
     pub fn has_table(&self) -> bool {
-        self.table_max > 0
+        self.table_max > 0 || self.has_table_import
+    }
+
+    /// Whether the module's own `Module` struct holds the table, rather
+    /// than the caller passing an imported one in.
+    pub fn owns_table(&self) -> bool {
+        self.table_max > 0 && !self.has_table_import
+    }
+
+    /// Whether export methods take a `host` parameter: the module has
+    /// imports, or yield points or memory observation call back into the host.
+    pub fn uses_host(&self) -> bool {
+        !self.func_imports.is_empty()
+            || !self.imported_globals.is_empty()
+            || self.yield_points
+            || self.observe_memory
+    }
+
+    /// Whether the module has no state and no host: no memory, table or
+    /// mutable globals, and no imports. Its exports need no instance.
+    pub fn is_pure(&self) -> bool {
+        !self.has_memory
+            && !self.has_memory_import
+            && !self.has_table()
+            && !self.has_global_fields()
+            && !self.uses_host()
+            && !self.coverage
     }
 
     /// Determine the memory ownership model.
@@ -1097,8 +1555,12 @@ where
 pub fn all_import_module_names(info: &ModuleInfo) -> std::collections::BTreeSet<String> {
     info.func_imports
         .iter()
-        .map(|i| i.module_name.clone())
-        .chain(info.imported_globals.iter().map(|g| g.module_name.clone()))
+        .map(|i| i.module_name.to_string())
+        .chain(
+            info.imported_globals
+                .iter()
+                .map(|g| g.module_name.to_string()),
+        )
         .collect()
 }
 
@@ -1375,16 +1837,16 @@ mod tests {
         let info = ModuleInfo {
             func_imports: vec![
                 FuncImport {
-                    module_name: "env".to_string(),
-                    func_name: "log".to_string(),
-                    params: vec![WasmType::I32],
-                    return_type: None,
+                    module_name: "env".into(),
+                    func_name: "log".into(),
+                    params: vec![WasmType::I32].into(),
+                    results: Vec::new(),
                 },
                 FuncImport {
-                    module_name: "env".to_string(),
-                    func_name: "read".to_string(),
-                    params: vec![],
-                    return_type: Some(WasmType::I32),
+                    module_name: "env".into(),
+                    func_name: "read".into(),
+                    params: vec![].into(),
+                    results: vec![WasmType::I32],
                 },
             ],
             ..Default::default()
@@ -1400,12 +1862,14 @@ mod tests {
         info.globals.push(GlobalDef {
             mutable: false,
             init_value: GlobalInit::I32(0),
+            field: "g0".to_string(),
         });
         assert!(!info.has_mutable_globals());
 
         info.globals.push(GlobalDef {
             mutable: true,
             init_value: GlobalInit::I32(0),
+            field: "g1".to_string(),
         });
         assert!(info.has_mutable_globals());
     }
@@ -1417,28 +1881,33 @@ mod tests {
 
         info.table_max = 10;
         assert!(info.has_table());
+        assert!(info.owns_table());
+
+        info.has_table_import = true;
+        assert!(info.has_table());
+        assert!(!info.owns_table());
     }
 
     #[test]
     fn test_group_by_module() {
         let imports = vec![
             FuncImport {
-                module_name: "env".to_string(),
-                func_name: "log".to_string(),
-                params: vec![],
-                return_type: None,
+                module_name: "env".into(),
+                func_name: "log".into(),
+                params: vec![].into(),
+                results: Vec::new(),
             },
             FuncImport {
-                module_name: "wasi".to_string(),
-                func_name: "read".to_string(),
-                params: vec![],
-                return_type: Some(WasmType::I32),
+                module_name: "wasi".into(),
+                func_name: "read".into(),
+                params: vec![].into(),
+                results: vec![WasmType::I32],
             },
             FuncImport {
-                module_name: "env".to_string(),
-                func_name: "debug".to_string(),
-                params: vec![],
-                return_type: None,
+                module_name: "env".into(),
+                func_name: "debug".into(),
+                params: vec![].into(),
+                results: Vec::new(),
             },
         ];
 
@@ -1446,8 +1915,8 @@ mod tests {
         assert_eq!(grouped.len(), 2);
         assert_eq!(grouped["env"].len(), 2);
         assert_eq!(grouped["wasi"].len(), 1);
-        assert_eq!(grouped["env"][0].func_name, "log");
-        assert_eq!(grouped["env"][1].func_name, "debug");
+        assert_eq!(grouped["env"][0].func_name, "log".into());
+        assert_eq!(grouped["env"][1].func_name, "debug".into());
     }
 
     #[test]
@@ -1455,27 +1924,27 @@ mod tests {
         let info = ModuleInfo {
             func_imports: vec![
                 FuncImport {
-                    module_name: "env".to_string(),
-                    func_name: "log".to_string(),
-                    params: vec![],
-                    return_type: None,
+                    module_name: "env".into(),
+                    func_name: "log".into(),
+                    params: vec![].into(),
+                    results: Vec::new(),
                 },
                 FuncImport {
-                    module_name: "wasi".to_string(),
-                    func_name: "read".to_string(),
-                    params: vec![],
-                    return_type: Some(WasmType::I32),
+                    module_name: "wasi".into(),
+                    func_name: "read".into(),
+                    params: vec![].into(),
+                    results: vec![WasmType::I32],
                 },
             ],
             imported_globals: vec![
                 ImportedGlobalDef {
-                    module_name: "env".to_string(),
+                    module_name: "env".into(),
                     name: "mem_ptr".to_string(),
                     wasm_type: WasmType::I32,
                     mutable: false,
                 },
                 ImportedGlobalDef {
-                    module_name: "sys".to_string(),
+                    module_name: "sys".into(),
                     name: "errno".to_string(),
                     wasm_type: WasmType::I32,
                     mutable: true,
@@ -1503,10 +1972,10 @@ mod tests {
                     dest: VarId(0),
                     value: IrValue::I32(42),
                 }],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             }],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         assert!(!has_import_calls(&ir_func_no_imports));
@@ -1523,17 +1992,17 @@ mod tests {
                         value: IrValue::I32(42),
                     },
                     IrInstr::CallImport {
-                        dest: None,
+                        dests: Vec::new(),
                         import_idx: ImportIdx::new(0),
-                        module_name: "env".to_string(),
-                        func_name: "log".to_string(),
+                        module_name: "env".into(),
+                        func_name: "log".into(),
                         args: vec![VarId(0)],
                     },
                 ],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             }],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         assert!(has_import_calls(&ir_func_with_imports));
@@ -1550,10 +2019,10 @@ mod tests {
                     dest: VarId(0),
                     value: IrValue::I32(42),
                 }],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             }],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
 
@@ -1579,10 +2048,10 @@ mod tests {
                         index: GlobalIdx::new(0), // First imported global
                     },
                 ],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             }],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         assert!(has_global_import_access(&ir_func_with_global_get, 2));
@@ -1599,10 +2068,10 @@ mod tests {
                     index: GlobalIdx::new(1), // Second imported global
                     value: VarId(0),
                 }],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             }],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
 
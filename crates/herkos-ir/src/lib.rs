@@ -0,0 +1,22 @@
+//! herkos-ir — intermediate representation of the herkos transpiler.
+//!
+//! This crate holds everything between a WebAssembly binary and code
+//! generation: the [`parser`], the SSA [`ir`] with its builder and phi
+//! lowering, and the [`optimizer`] passes. It has no code generator, so
+//! verifiers, alternative backends and research tools can depend on it
+//! alone:
+//!
+//! ```ignore
+//! let parsed = herkos_ir::parser::parse_wasm(&wasm)?;
+//! let info = herkos_ir::ir::build_module_info(&parsed, &BuildOptions::default())?;
+//! let info = herkos_ir::optimizer::optimize_ir(info, true)?;
+//! ```
+//!
+//! The public types follow semantic versioning: a breaking change to
+//! [`ir::ModuleInfo`], the instruction set or the pass entry points bumps
+//! the major version (the minor version while below 1.0). `herkos-core`
+//! builds the transpiler on top of it.
+
+pub mod ir;
+pub mod optimizer;
+pub mod parser;
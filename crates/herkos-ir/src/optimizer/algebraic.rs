@@ -21,6 +21,7 @@
 //! | `x << 0`, `x >> 0` | `x`          |
 //! | `x == x`           | `1`          |
 //! | `x != x`           | `0`          |
+//! | `select(c, x, x)`  | `x`          |
 
 use crate::{
     ir::{BinOp, IrFunction, IrInstr, IrValue, VarId},
@@ -61,6 +62,21 @@ fn eliminate_once(func: &mut IrFunction) -> bool {
                 continue;
             }
 
+            // Both operands of a select are the same: the condition is moot.
+            if let IrInstr::Select {
+                dest, val1, val2, ..
+            } = instr
+            {
+                if val1 == val2 {
+                    *instr = IrInstr::Assign {
+                        dest: *dest,
+                        src: *val1,
+                    };
+                    changed = true;
+                }
+                continue;
+            }
+
             let (dest, op, lhs, rhs) = match instr {
                 IrInstr::BinOp { dest, op, lhs, rhs } => (*dest, *op, *lhs, *rhs),
                 _ => continue,
@@ -297,7 +313,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -306,10 +322,31 @@ mod tests {
         vec![IrBlock {
             id: BlockId(0),
             instructions: instrs,
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         }]
     }
 
+    // ── Select ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn select_of_equal_operands() {
+        // v2 = select(v0, v0, v1) → v2 = Assign(v0)
+        let mut func = make_func(single_block(vec![IrInstr::Select {
+            dest: VarId(2),
+            val1: VarId(0),
+            val2: VarId(0),
+            condition: VarId(1),
+        }]));
+        eliminate(&mut func);
+        assert!(matches!(
+            func.blocks[0].instructions[0],
+            IrInstr::Assign {
+                dest: VarId(2),
+                src: VarId(0)
+            }
+        ));
+    }
+
     // ── Additive identity ────────────────────────────────────────────────
 
     #[test]
@@ -683,7 +720,7 @@ mod tests {
                     rhs: VarId(1),
                 }],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(2)),
+                    values: vec![VarId(2)],
                 },
             },
         ]);
@@ -143,7 +143,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -276,13 +276,13 @@ mod tests {
                 id: BlockId(1),
                 instructions: vec![],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(1)), // second use of v1
+                    values: vec![VarId(1)], // second use of v1
                 },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func);
@@ -0,0 +1,328 @@
+//! Read-only data segment promotion.
+//!
+//! ## What it does
+//!
+//! An active data segment that no instruction can write keeps its initial
+//! bytes for the whole life of the instance. Such segments are marked
+//! [`DataSegmentDef::read_only`], which codegen emits as `pub const RODATA_{i}`
+//! byte arrays, and every load from a constant address inside one becomes a
+//! `Const`. When no load or `memory.copy` is left that could read a segment,
+//! it is not copied into linear memory at all ([`DataSegmentDef::resident`]).
+//!
+//! ## Algorithm
+//!
+//! 1. A write is known not to touch a segment only when its address (and, for
+//!    `memory.fill` / `memory.copy` / `memory.init`, its length) is a constant
+//!    from [`build_global_const_map`]. A single write through a computed
+//!    address keeps every segment writable.
+//! 2. Constant-address loads that fall entirely inside a read-only segment are
+//!    replaced by the little-endian value of the bytes they read, except in
+//!    functions that skip the optimizer ([`ModuleInfo::skips_optimizer`]).
+//! 3. A read-only segment stays resident if any remaining load or
+//!    `memory.copy` source may overlap it, or if it does not fit in the
+//!    initial memory (initialization must still trap).
+//!
+//! ## Safety
+//!
+//! Promotion is only valid when nothing outside the generated function
+//! bodies reads or writes linear memory: a write would make the folded bytes
+//! stale, and a read of a segment left out of memory sees zeroes. Only
+//! modules that own an unexported memory are considered, and the caller
+//! skips modules with generated code accessing memory on the host's behalf
+//! (`herkos_core::TranspileOptions::host_accesses_memory`). Host code must not
+//! access these regions through `module.0.memory` either.
+
+use super::utils::build_global_const_map;
+use crate::ir::*;
+use std::collections::HashMap;
+
+/// Byte range `[start, end)` in linear memory.
+type Range = (u64, u64);
+
+/// Promote never-written data segments of `info` to constants.
+pub fn promote(info: &mut ModuleInfo) {
+    if !info.has_memory || !info.memory_exports.is_empty() || info.data_segments.is_empty() {
+        return;
+    }
+    let segments: Vec<Range> = info
+        .data_segments
+        .iter()
+        .map(|s| {
+            (
+                u64::from(s.offset),
+                u64::from(s.offset) + s.data.len() as u64,
+            )
+        })
+        .collect();
+    let mut read_only: Vec<bool> = info
+        .data_segments
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            // A later segment overwriting this one at instantiation would make
+            // the folded bytes stale.
+            s.base.is_none()
+                && !s.data.is_empty()
+                && !segments
+                    .iter()
+                    .enumerate()
+                    .any(|(j, other)| j != i && overlaps(Some(*other), segments[i]))
+        })
+        .collect();
+
+    // 1. Writes
+    for func in &info.ir_functions {
+        let consts = build_global_const_map(func);
+        for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+            let written = match instr {
+                IrInstr::Store {
+                    ty,
+                    addr,
+                    offset,
+                    width,
+                    ..
+                } => access_range(&consts, *addr, *offset, access_size(*ty, *width)),
+                IrInstr::MemoryFill { dst, len, .. }
+                | IrInstr::MemoryCopy { dst, len, .. }
+                | IrInstr::MemoryInit { dst, len, .. } => bulk_range(&consts, *dst, *len),
+                _ => continue,
+            };
+            for (i, seg) in segments.iter().enumerate() {
+                if overlaps(written, *seg) {
+                    read_only[i] = false;
+                }
+            }
+        }
+    }
+    if !read_only.contains(&true) {
+        return;
+    }
+
+    // 2. Fold constant loads
+    let read_only_segments: Vec<&DataSegmentDef> = info
+        .data_segments
+        .iter()
+        .zip(&read_only)
+        .filter_map(|(s, &ro)| ro.then_some(s))
+        .collect();
+    let skipped: Vec<bool> = (0..info.ir_functions.len())
+        .map(|idx| info.skips_optimizer(idx))
+        .collect();
+    for (func, _) in info
+        .ir_functions
+        .iter_mut()
+        .zip(skipped)
+        .filter(|(_, skip)| !skip)
+    {
+        let consts = build_global_const_map(func);
+        for instr in func.blocks.iter_mut().flat_map(|b| &mut b.instructions) {
+            if let IrInstr::Load {
+                dest,
+                ty,
+                addr,
+                offset,
+                width,
+                sign,
+            } = *instr
+            {
+                let size = access_size(ty, width);
+                let Some((start, end)) = access_range(&consts, addr, offset, size) else {
+                    continue;
+                };
+                let bytes = read_only_segments.iter().find_map(|s| {
+                    let base = u64::from(s.offset);
+                    (start >= base && end <= base + s.data.len() as u64)
+                        .then(|| &s.data[(start - base) as usize..(end - base) as usize])
+                });
+                if let Some(bytes) = bytes {
+                    let value = decode(bytes, ty, sign);
+                    *instr = IrInstr::Const { dest, value };
+                }
+            }
+        }
+    }
+
+    // 3. Residency
+    let memory_bytes = info.initial_pages as u64 * 65536;
+    let mut resident: Vec<bool> = segments.iter().map(|s| s.1 > memory_bytes).collect();
+    for func in &info.ir_functions {
+        let consts = build_global_const_map(func);
+        for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+            let read = match instr {
+                IrInstr::Load {
+                    ty,
+                    addr,
+                    offset,
+                    width,
+                    ..
+                } => access_range(&consts, *addr, *offset, access_size(*ty, *width)),
+                IrInstr::MemoryCopy { src, len, .. } => bulk_range(&consts, *src, *len),
+                _ => continue,
+            };
+            for (i, seg) in segments.iter().enumerate() {
+                if overlaps(read, *seg) {
+                    resident[i] = true;
+                }
+            }
+        }
+    }
+
+    for ((seg, ro), res) in info.data_segments.iter_mut().zip(read_only).zip(resident) {
+        seg.read_only = ro;
+        seg.resident = !ro || res;
+    }
+}
+
+/// Whether an access to `range` (`None`: unknown address) may touch `seg`.
+fn overlaps(range: Option<Range>, seg: Range) -> bool {
+    match range {
+        Some((start, end)) => start < seg.1 && seg.0 < end,
+        None => true,
+    }
+}
+
+/// Bytes touched by a load or store of `ty` with `width`.
+fn access_size(ty: WasmType, width: MemoryAccessWidth) -> u64 {
+    u64::from(width.bytes(ty))
+}
+
+/// Range of a load or store at constant `addr` + `offset`.
+fn access_range(
+    consts: &HashMap<VarId, IrValue>,
+    addr: VarId,
+    offset: u32,
+    size: u64,
+) -> Option<Range> {
+    match consts.get(&addr)? {
+        IrValue::I32(a) => {
+            let start = u64::from(*a as u32) + u64::from(offset);
+            Some((start, start + size))
+        }
+        _ => None,
+    }
+}
+
+/// Range of a bulk memory operation at constant `addr` with constant `len`.
+fn bulk_range(consts: &HashMap<VarId, IrValue>, addr: VarId, len: VarId) -> Option<Range> {
+    match (consts.get(&addr)?, consts.get(&len)?) {
+        (IrValue::I32(a), IrValue::I32(n)) => {
+            let start = u64::from(*a as u32);
+            Some((start, start + u64::from(*n as u32)))
+        }
+        _ => None,
+    }
+}
+
+/// Value a load of `ty` reads from little-endian `bytes`.
+fn decode(bytes: &[u8], ty: WasmType, sign: Option<SignExtension>) -> IrValue {
+    let mut raw = [0u8; 8];
+    raw[..bytes.len()].copy_from_slice(bytes);
+    let mut bits = u64::from_le_bytes(raw);
+    if sign == Some(SignExtension::Signed) && bytes.len() < 8 {
+        let shift = 64 - 8 * bytes.len() as u32;
+        bits = (((bits << shift) as i64) >> shift) as u64;
+    }
+    match ty {
+        WasmType::I32 => IrValue::I32(bits as u32 as i32),
+        WasmType::I64 => IrValue::I64(bits as i64),
+        WasmType::F32 => IrValue::F32(f32::from_bits(bits as u32)),
+        WasmType::F64 => IrValue::F64(f64::from_bits(bits)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{build_module_info, BuildOptions};
+
+    fn module(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let mut info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+        promote(&mut info);
+        info
+    }
+
+    fn consts(info: &ModuleInfo) -> Vec<IrValue> {
+        info.ir_functions
+            .iter()
+            .flat_map(|f| f.blocks.iter().flat_map(|b| &b.instructions))
+            .filter_map(|i| match i {
+                IrInstr::Const { value, .. } => Some(*value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_loads_fold_and_segment_leaves_memory() {
+        let info = module(
+            r#"(module
+                (memory 1)
+                (data (i32.const 16) "\ff\fe\01\02")
+                (func (export "f") (result i32)
+                    (i32.add
+                        (i32.load8_s (i32.const 16))
+                        (i32.load16_u offset=2 (i32.const 16)))))"#,
+        );
+        let seg = &info.data_segments[0];
+        assert!(seg.read_only);
+        assert!(!seg.resident);
+        let values = consts(&info);
+        assert!(values.contains(&IrValue::I32(-1)));
+        assert!(values.contains(&IrValue::I32(0x0201)));
+    }
+
+    #[test]
+    fn dynamic_load_keeps_segment_resident() {
+        let info = module(
+            r#"(module
+                (memory 1)
+                (data (i32.const 0) "abcd")
+                (func (export "f") (param i32) (result i32)
+                    (i32.add
+                        (i32.load (i32.const 0))
+                        (i32.load8_u (local.get 0)))))"#,
+        );
+        let seg = &info.data_segments[0];
+        assert!(seg.read_only);
+        assert!(seg.resident);
+        assert!(consts(&info).contains(&IrValue::I32(i32::from_le_bytes(*b"abcd"))));
+    }
+
+    #[test]
+    fn writes_that_may_alias_keep_segment_writable() {
+        // A store through a computed address could hit any segment.
+        let dynamic = module(
+            r#"(module
+                (memory 1)
+                (data (i32.const 0) "abcd")
+                (func (export "f") (param i32) (i32.store8 (local.get 0) (i32.const 0))))"#,
+        );
+        assert!(!dynamic.data_segments[0].read_only);
+        assert!(dynamic.data_segments[0].resident);
+
+        // A constant-address store only rules out the segments it overlaps.
+        let constant = module(
+            r#"(module
+                (memory 1)
+                (data (i32.const 0) "abcd")
+                (data (i32.const 64) "efgh")
+                (func (export "f") (i32.store8 offset=3 (i32.const 0) (i32.const 0))))"#,
+        );
+        assert!(!constant.data_segments[0].read_only);
+        assert!(constant.data_segments[1].read_only);
+    }
+
+    #[test]
+    fn exported_memory_is_left_alone() {
+        let info = module(
+            r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "abcd")
+                (func (export "f") (result i32) (i32.load (i32.const 0))))"#,
+        );
+        assert!(!info.data_segments[0].read_only);
+        assert!(info.data_segments[0].resident);
+    }
+}
@@ -7,6 +7,7 @@
 //! - **Propagates** constants through `Assign` chains.
 //! - **Folds** `BinOp(Const, Const)` and `UnOp(Const)` into `Const` when the
 //!   result is statically computable.
+//! - **Resolves** `Select` with a constant condition to the chosen operand.
 //!
 //! ## Algorithm
 //!
@@ -23,6 +24,8 @@
 //!    - `Assign { dest, src }` where src is known → replace with `Const`, record
 //!    - `BinOp { dest, op, lhs, rhs }` where both known → fold via `try_eval_binop`
 //!    - `UnOp { dest, op, operand }` where operand known → fold via `try_eval_unop`
+//!    - `Select { dest, val1, val2, condition }` where condition known →
+//!      `Assign` of the chosen operand (`Const` if that one is known too)
 //!
 //! 3. Run to fixpoint.
 //!
@@ -122,6 +125,26 @@ pub fn eliminate(func: &mut IrFunction) -> Result<()> {
                             }
                         }
                     }
+                    IrInstr::Select {
+                        dest,
+                        val1,
+                        val2,
+                        condition,
+                    } => {
+                        if let Some(IrValue::I32(c)) = known.get(condition).copied() {
+                            let d = *dest;
+                            let src = if c != 0 { *val1 } else { *val2 };
+                            *instr = match known.get(&src).copied() {
+                                Some(value) => {
+                                    known.insert(d, value);
+                                    folded = true;
+                                    IrInstr::Const { dest: d, value }
+                                }
+                                None => IrInstr::Assign { dest: d, src },
+                            };
+                            changed = true;
+                        }
+                    }
                     _ => {}
                 }
 
@@ -155,7 +178,7 @@ pub fn eliminate(func: &mut IrFunction) -> Result<()> {
 /// - The value types don't match the expected operand types for the op.
 /// - The operation would trap at runtime (div/rem by zero, signed overflow,
 ///   etc.) — we must preserve the runtime trap rather than folding it away.
-fn try_eval_binop(op: BinOp, lhs: IrValue, rhs: IrValue) -> Option<IrValue> {
+pub(crate) fn try_eval_binop(op: BinOp, lhs: IrValue, rhs: IrValue) -> Option<IrValue> {
     match (op, lhs, rhs) {
         // ── i32 arithmetic ──────────────────────────────────────────────
         (BinOp::I32Add, IrValue::I32(a), IrValue::I32(b)) => Some(IrValue::I32(a.wrapping_add(b))),
@@ -382,7 +405,7 @@ fn try_eval_binop(op: BinOp, lhs: IrValue, rhs: IrValue) -> Option<IrValue> {
 /// Attempt to evaluate a unary operation on a constant value.
 ///
 /// Returns `None` for trapping conversions (`TruncF*` with NaN/out-of-range).
-fn try_eval_unop(op: UnOp, val: IrValue) -> Option<IrValue> {
+pub(crate) fn try_eval_unop(op: UnOp, val: IrValue) -> Option<IrValue> {
     match (op, val) {
         // ── i32 unary ───────────────────────────────────────────────────
         (UnOp::I32Clz, IrValue::I32(v)) => Some(IrValue::I32((v as u32).leading_zeros() as i32)),
@@ -467,7 +490,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -481,7 +504,7 @@ mod tests {
     }
 
     fn ret_none() -> IrTerminator {
-        IrTerminator::Return { value: None }
+        IrTerminator::Return { values: Vec::new() }
     }
 
     // ── Basic constant propagation through Assign ────────────────────────
@@ -512,6 +535,104 @@ mod tests {
         }
     }
 
+    // ── Select resolution ───────────────────────────────────────────────
+
+    #[test]
+    fn select_with_known_condition_picks_an_operand() {
+        // v0 = 1; v3 = select(v1, v2, v0) → v3 = Assign(v1)
+        // v4 = 0; v5 = select(v1, v2, v4) → v5 = Assign(v2)
+        let mut func = make_func(single_block(
+            vec![
+                IrInstr::Const {
+                    dest: VarId(0),
+                    value: IrValue::I32(1),
+                },
+                IrInstr::Select {
+                    dest: VarId(3),
+                    val1: VarId(1),
+                    val2: VarId(2),
+                    condition: VarId(0),
+                },
+                IrInstr::Const {
+                    dest: VarId(4),
+                    value: IrValue::I32(0),
+                },
+                IrInstr::Select {
+                    dest: VarId(5),
+                    val1: VarId(1),
+                    val2: VarId(2),
+                    condition: VarId(4),
+                },
+            ],
+            ret_none(),
+        ));
+        eliminate(&mut func).unwrap();
+        assert!(matches!(
+            func.blocks[0].instructions[1],
+            IrInstr::Assign {
+                dest: VarId(3),
+                src: VarId(1)
+            }
+        ));
+        assert!(matches!(
+            func.blocks[0].instructions[3],
+            IrInstr::Assign {
+                dest: VarId(5),
+                src: VarId(2)
+            }
+        ));
+    }
+
+    #[test]
+    fn select_of_known_operand_folds_to_const() {
+        // v0 = 7; v1 = -2 (any non-zero condition); v3 = select(v0, v2, v1) → v3 = 7
+        let mut func = make_func(single_block(
+            vec![
+                IrInstr::Const {
+                    dest: VarId(0),
+                    value: IrValue::I32(7),
+                },
+                IrInstr::Const {
+                    dest: VarId(1),
+                    value: IrValue::I32(-2),
+                },
+                IrInstr::Select {
+                    dest: VarId(3),
+                    val1: VarId(0),
+                    val2: VarId(2),
+                    condition: VarId(1),
+                },
+            ],
+            ret_none(),
+        ));
+        eliminate(&mut func).unwrap();
+        assert!(matches!(
+            func.blocks[0].instructions[2],
+            IrInstr::Const {
+                dest: VarId(3),
+                value: IrValue::I32(7)
+            }
+        ));
+    }
+
+    #[test]
+    fn select_with_unknown_condition_is_kept() {
+        let mut func = make_func(single_block(
+            vec![IrInstr::Select {
+                dest: VarId(3),
+                val1: VarId(1),
+                val2: VarId(2),
+                condition: VarId(0),
+            }],
+            ret_none(),
+        ));
+        eliminate(&mut func).unwrap();
+        assert!(matches!(
+            func.blocks[0].instructions[0],
+            IrInstr::Select { .. }
+        ));
+    }
+
     // ── BinOp folding ───────────────────────────────────────────────────
 
     #[test]
@@ -838,7 +959,7 @@ mod tests {
                 ret_none(),
             ),
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         eliminate(&mut func).unwrap();
@@ -1033,7 +1154,7 @@ mod tests {
                     dest: VarId(1),
                     src: VarId(0),
                 }],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -1082,7 +1203,7 @@ mod tests {
                     dest: VarId(1),
                     src: VarId(0),
                 }],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -1116,6 +1237,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn binop_shift_counts_wrap_at_the_width() {
+        let i32s = [
+            (BinOp::I32Shl, 1, 32, 1),
+            (BinOp::I32Shl, 1, 33, 2),
+            (BinOp::I32Shl, 1, -1, i32::MIN),
+            (BinOp::I32ShrS, i32::MIN, 31, -1),
+            (BinOp::I32ShrS, i32::MIN, 63, -1),
+            (BinOp::I32ShrU, i32::MIN, -1, 1),
+            (BinOp::I32ShrU, -1, 32, -1),
+            (BinOp::I32Rotl, 1, -1, i32::MIN),
+            (BinOp::I32Rotr, 1, 33, i32::MIN),
+        ];
+        for (op, a, b, expected) in i32s {
+            assert_eq!(
+                try_eval_binop(op, IrValue::I32(a), IrValue::I32(b)),
+                Some(IrValue::I32(expected)),
+                "{op:?} {a} {b}"
+            );
+        }
+        let i64s = [
+            (BinOp::I64Shl, 1, 64, 1),
+            (BinOp::I64Shl, 1, 63, i64::MIN),
+            (BinOp::I64Shl, 1, -1, i64::MIN),
+            (BinOp::I64ShrS, i64::MIN, 127, -1),
+            (BinOp::I64ShrU, i64::MIN, -1, 1),
+            (BinOp::I64Rotl, 1, 65, 2),
+            (BinOp::I64Rotr, 1, -63, i64::MIN),
+        ];
+        for (op, a, b, expected) in i64s {
+            assert_eq!(
+                try_eval_binop(op, IrValue::I64(a), IrValue::I64(b)),
+                Some(IrValue::I64(expected)),
+                "{op:?} {a} {b}"
+            );
+        }
+    }
+
     #[test]
     fn binop_i64_div_zero() {
         assert_eq!(
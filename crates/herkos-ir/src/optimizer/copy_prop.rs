@@ -42,8 +42,8 @@
 //! settle, dead variables are pruned from `IrFunction::locals`.
 
 use super::utils::{
-    build_global_use_count, count_uses_of, count_uses_of_terminator, for_each_use, instr_dest,
-    prune_dead_locals, replace_uses_of, replace_uses_of_terminator, set_instr_dest,
+    build_global_use_count, count_uses_of, count_uses_of_terminator, defines, for_each_use,
+    instr_dest, prune_dead_locals, replace_uses_of, replace_uses_of_terminator, set_instr_dest,
 };
 use crate::ir::{IrBlock, IrFunction, IrInstr, VarId};
 use std::collections::HashMap;
@@ -173,7 +173,7 @@ fn coalesce_one(block: &mut IrBlock, global_uses: &HashMap<VarId, usize>) -> boo
                     found = true;
                 }
             });
-            if instr_dest(i) == Some(v_dst) {
+            if defines(i, v_dst) {
                 found = true;
             }
             found
@@ -244,7 +244,7 @@ fn forward_propagate_one(block: &mut IrBlock, global_uses: &HashMap<VarId, usize
         // If it were, uses past the redefinition would read a different value.
         if block.instructions[assign_idx + 1..]
             .iter()
-            .any(|i| instr_dest(i) == Some(v_dst))
+            .any(|i| defines(i, v_dst))
         {
             continue;
         }
@@ -271,7 +271,7 @@ fn forward_propagate_one(block: &mut IrBlock, global_uses: &HashMap<VarId, usize
         // Check v_src is not written in [assign_idx+1, check_end).
         if block.instructions[assign_idx + 1..check_end]
             .iter()
-            .any(|i| instr_dest(i) == Some(v_src))
+            .any(|i| defines(i, v_src))
         {
             continue;
         }
@@ -302,7 +302,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -316,7 +316,7 @@ mod tests {
     }
 
     fn ret_none() -> IrTerminator {
-        IrTerminator::Return { value: None }
+        IrTerminator::Return { values: Vec::new() }
     }
 
     // ── Basic: Const → Assign ─────────────────────────────────────────────
@@ -386,7 +386,7 @@ mod tests {
                 ret_none(),
             ),
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         eliminate(&mut func);
@@ -459,7 +459,7 @@ mod tests {
                 },
             ],
             IrTerminator::Return {
-                value: Some(VarId(1)),
+                values: vec![VarId(1)],
             },
         ));
         eliminate(&mut func);
@@ -485,7 +485,7 @@ mod tests {
                 },
             ],
             IrTerminator::Return {
-                value: Some(VarId(11)),
+                values: vec![VarId(11)],
             },
         ));
         eliminate(&mut func);
@@ -542,7 +542,7 @@ mod tests {
                 src: VarId(0),
             }],
             IrTerminator::Return {
-                value: Some(VarId(10)),
+                values: vec![VarId(10)],
             },
         ));
         eliminate(&mut func);
@@ -552,7 +552,7 @@ mod tests {
             "Assign should be removed"
         );
         match &func.blocks[0].terminator {
-            IrTerminator::Return { value: Some(v) } => assert_eq!(*v, VarId(0)),
+            IrTerminator::Return { values } => assert_eq!(values, &[VarId(0)]),
             other => panic!("expected Return(v0), got {other:?}"),
         }
     }
@@ -588,7 +588,7 @@ mod tests {
                 ret_none(),
             ),
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         eliminate(&mut func);
@@ -858,7 +858,7 @@ mod tests {
                         src: VarId(8),
                     },
                 ],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func);
@@ -0,0 +1,191 @@
+//! Dead argument elimination.
+//!
+//! ## What it does
+//!
+//! Removes parameters a function never reads, together with the matching
+//! argument at every direct call site. Such parameters are common after
+//! tree-shaking and inlining in the guest toolchain; dropping them shrinks
+//! the generated signatures and calls.
+//!
+//! ## Algorithm
+//!
+//! 1. A function is a candidate unless the host or `call_indirect` can call
+//!    it: exported functions and functions placed in a table keep their Wasm
+//!    signature. So do functions that skip the optimizer. The public
+//!    `func_N` method of any other function keeps it too: the pass records
+//!    the removed positions in `ModuleInfo::dead_params` and the method drops
+//!    those arguments before calling the shrunk function.
+//! 2. A parameter is dead when no instruction or terminator of the callee
+//!    uses its variable.
+//! 3. Dropping an argument can leave the caller's own parameter unused (it
+//!    was only passed along), so steps 2-3 repeat until nothing changes.
+//!
+//! ## Safety
+//!
+//! Only direct calls can reach a candidate, and the pass rewrites all of
+//! them. The instructions computing a dropped argument stay in the caller,
+//! so their side effects and traps are unchanged; the post-lowering dead
+//! instruction pass removes the pure ones.
+
+use super::utils::{for_each_use, for_each_use_terminator};
+use crate::ir::*;
+use std::collections::{HashMap, HashSet};
+
+/// Remove unused parameters of functions only reachable by direct calls.
+pub fn eliminate(info: &mut ModuleInfo) {
+    let mut pinned = vec![false; info.ir_functions.len()];
+    for export in &info.func_exports {
+        pinned[export.func_index.as_usize()] = true;
+    }
+    for (idx, pin) in pinned.iter_mut().enumerate() {
+        *pin |= info.skips_optimizer(idx);
+    }
+    for func in info.element_segments.iter().flat_map(|s| &s.func_indices) {
+        if let ElementFunc::Local(idx) = func {
+            pinned[idx.as_usize()] = true;
+        }
+    }
+    info.dead_params = vec![Vec::new(); info.ir_functions.len()];
+
+    loop {
+        // Dead parameter positions per function, in this round
+        let mut dead: HashMap<LocalFuncIdx, Vec<usize>> = HashMap::new();
+        for (idx, func) in info.ir_functions.iter_mut().enumerate() {
+            if pinned[idx] || func.params.is_empty() {
+                continue;
+            }
+            let used = used_vars(func);
+            let positions: Vec<usize> = func
+                .params
+                .iter()
+                .enumerate()
+                .filter(|(_, (var, _))| !used.contains(var))
+                .map(|(i, _)| i)
+                .collect();
+            if !positions.is_empty() {
+                // Record the Wasm positions: earlier rounds shifted the rest
+                let wasm_dead = &mut info.dead_params[idx];
+                let live: Vec<usize> = (0..func.params.len() + wasm_dead.len())
+                    .filter(|i| !wasm_dead.iter().any(|(pos, _)| pos == i))
+                    .collect();
+                wasm_dead.extend(positions.iter().map(|&p| (live[p], func.params[p].1)));
+                wasm_dead.sort_unstable_by_key(|(pos, _)| *pos);
+                func.params.retain(|(var, _)| used.contains(var));
+                dead.insert(LocalFuncIdx::new(idx), positions);
+            }
+        }
+        if dead.is_empty() {
+            return;
+        }
+
+        for func in &mut info.ir_functions {
+            for instr in func.blocks.iter_mut().flat_map(|b| &mut b.instructions) {
+                if let IrInstr::Call { func_idx, args, .. } = instr {
+                    if let Some(positions) = dead.get(func_idx) {
+                        let mut i = 0;
+                        args.retain(|_| {
+                            let keep = !positions.contains(&i);
+                            i += 1;
+                            keep
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Every variable `func` reads.
+fn used_vars(func: &IrFunction) -> HashSet<VarId> {
+    let mut used = HashSet::new();
+    for block in &func.blocks {
+        for instr in &block.instructions {
+            for_each_use(instr, |v| {
+                used.insert(v);
+            });
+        }
+        for_each_use_terminator(&block.terminator, |v| {
+            used.insert(v);
+        });
+    }
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{build_module_info, BuildOptions};
+
+    fn eliminated(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+        let mut info = crate::optimizer::optimize_ir(info, true).unwrap();
+        eliminate(&mut info);
+        info
+    }
+
+    fn param_counts(info: &ModuleInfo) -> Vec<usize> {
+        info.ir_functions.iter().map(|f| f.params.len()).collect()
+    }
+
+    fn call_arg_counts(func: &IrFunction) -> Vec<usize> {
+        func.blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .filter_map(|i| match i {
+                IrInstr::Call { args, .. } => Some(args.len()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_parameters_and_their_arguments_go() {
+        let info = eliminated(
+            r#"(module
+                (func $pick (param i32 i64 i32) (result i32) (local.get 2))
+                (func (export "run") (param i32) (result i32)
+                    (call $pick (local.get 0) (i64.const 7) (i32.const 9))))"#,
+        );
+        assert_eq!(param_counts(&info), [1, 1]);
+        assert_eq!(call_arg_counts(&info.ir_functions[1]), [1]);
+        assert_eq!(
+            info.dead_params[0],
+            [(0, WasmType::I32), (1, WasmType::I64)]
+        );
+        assert_eq!(
+            info.wasm_params(0),
+            [
+                (WasmType::I32, false),
+                (WasmType::I64, false),
+                (WasmType::I32, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn parameters_only_passed_along_go_too() {
+        let info = eliminated(
+            r#"(module
+                (func $leaf (param i32) (result i32) (i32.const 1))
+                (func $mid (param i32) (result i32) (call $leaf (local.get 0)))
+                (func (export "run") (param i32) (result i32) (call $mid (local.get 0))))"#,
+        );
+        assert_eq!(param_counts(&info), [0, 0, 1]);
+        assert_eq!(call_arg_counts(&info.ir_functions[1]), [0]);
+        assert_eq!(call_arg_counts(&info.ir_functions[2]), [0]);
+    }
+
+    #[test]
+    fn exported_and_table_functions_keep_their_signature() {
+        let info = eliminated(
+            r#"(module
+                (table 1 funcref)
+                (elem (i32.const 0) $in_table)
+                (func $in_table (param i32) (result i32) (i32.const 1))
+                (func (export "exported") (param i32) (result i32) (i32.const 2)))"#,
+        );
+        assert_eq!(param_counts(&info), [1, 1]);
+    }
+}
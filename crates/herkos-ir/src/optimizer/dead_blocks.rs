@@ -54,7 +54,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -72,7 +72,7 @@ mod tests {
         let mut func = make_func(vec![IrBlock {
             id: BlockId(0),
             instructions: vec![],
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         }]);
         eliminate(&mut func).unwrap();
         assert_eq!(block_ids(&func), vec![0]);
@@ -90,7 +90,7 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -107,12 +107,12 @@ mod tests {
             IrBlock {
                 id: BlockId(0),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -132,12 +132,12 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -157,7 +157,7 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -180,7 +180,7 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
@@ -190,7 +190,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -220,12 +220,12 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -252,17 +252,17 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -291,22 +291,22 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(4),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -333,12 +333,12 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func).unwrap();
@@ -369,7 +369,7 @@ mod tests {
                     id: BlockId(1),
                     instructions: vec![],
                     terminator: IrTerminator::Return {
-                        value: Some(VarId(0)),
+                        values: vec![VarId(0)],
                     },
                 },
                 IrBlock {
@@ -384,12 +384,12 @@ mod tests {
                     id: BlockId(3),
                     instructions: vec![],
                     terminator: IrTerminator::Return {
-                        value: Some(VarId(1)),
+                        values: vec![VarId(1)],
                     },
                 },
             ],
             entry_block: BlockId(0),
-            return_type: Some(WasmType::I32),
+            results: vec![WasmType::I32],
             type_idx: TypeIdx::new(0),
         };
         eliminate(&mut func).unwrap();
@@ -1,31 +1,55 @@
 //! Dead instruction elimination.
 //!
 //! Removes instructions whose destination `VarId` has zero uses across the
-//! entire function and whose operation is side-effect-free.
+//! entire function and whose operation is side-effect-free, and calls to
+//! [`Purity::Total`] functions none of whose results are used.
+//!
+//! Runs in both phases. Before phi lowering it also removes phis nothing
+//! reads, each of which would otherwise become a variable and a copy in
+//! every predecessor.
 //!
 //! ## Algorithm
 //!
 //! 1. Build the global use-count map (`VarId → number of reads`).
 //! 2. For each instruction that produces a value (`instr_dest` returns `Some`):
 //!    if the use count is zero **and** the instruction is side-effect-free,
-//!    mark it for removal.
+//!    mark it for removal. Calls count when the callee is total and every
+//!    result is unused, including calls without results. Unused phis are
+//!    marked too.
 //! 3. Remove all marked instructions.
 //! 4. Repeat to fixpoint — removing an instruction may make its operands'
 //!    definitions unused.
 //! 5. Prune dead locals from `IrFunction::locals`.
 
 use super::utils::{build_global_use_count, instr_dest, is_side_effect_free, prune_dead_locals};
-use crate::ir::IrFunction;
+use crate::ir::{IrFunction, IrInstr, Purity};
 
 /// Run dead instruction elimination to fixpoint, then prune dead locals.
-pub fn eliminate(func: &mut IrFunction) {
+///
+/// `purity` gives each local function's [`Purity`]; functions past its end
+/// count as impure.
+pub fn eliminate(func: &mut IrFunction, purity: &[Purity]) {
     loop {
         let uses = build_global_use_count(func);
         let mut changed = false;
 
         for block in &mut func.blocks {
             block.instructions.retain(|instr| {
-                if let Some(dest) = instr_dest(instr) {
+                if let IrInstr::Call {
+                    dests, func_idx, ..
+                } = instr
+                {
+                    let total = purity.get(func_idx.as_usize()) == Some(&Purity::Total);
+                    if total && dests.iter().all(|d| !uses.contains_key(d)) {
+                        changed = true;
+                        return false; // remove
+                    }
+                } else if let IrInstr::Phi { dest, .. } = instr {
+                    if !uses.contains_key(dest) {
+                        changed = true;
+                        return false; // remove
+                    }
+                } else if let Some(dest) = instr_dest(instr) {
                     if uses.get(&dest).copied().unwrap_or(0) == 0 && is_side_effect_free(instr) {
                         changed = true;
                         return false; // remove
@@ -59,7 +83,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -70,7 +94,7 @@ mod tests {
             locals,
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -84,7 +108,7 @@ mod tests {
     }
 
     fn ret_none() -> IrTerminator {
-        IrTerminator::Return { value: None }
+        IrTerminator::Return { values: Vec::new() }
     }
 
     // ── Basic: unused side-effect-free instruction is removed ─────────────
@@ -98,7 +122,7 @@ mod tests {
             }],
             ret_none(),
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 0);
     }
 
@@ -123,7 +147,7 @@ mod tests {
             ],
             ret_none(),
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         // v2 unused → removed; then v0, v1 become unused → removed
         assert_eq!(func.blocks[0].instructions.len(), 0);
     }
@@ -138,10 +162,10 @@ mod tests {
                 value: IrValue::I32(42),
             }],
             IrTerminator::Return {
-                value: Some(VarId(0)),
+                values: vec![VarId(0)],
             },
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 1);
     }
 
@@ -166,7 +190,7 @@ mod tests {
             ],
             ret_none(),
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         // Load may trap → kept; v0 is used by Load → kept
         assert_eq!(func.blocks[0].instructions.len(), 2);
     }
@@ -193,7 +217,7 @@ mod tests {
             ],
             ret_none(),
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         // Store has side effects → kept; v0, v1 used by Store → kept
         assert_eq!(func.blocks[0].instructions.len(), 3);
     }
@@ -236,7 +260,7 @@ mod tests {
             ],
             ret_none(),
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 0);
     }
 
@@ -265,10 +289,10 @@ mod tests {
                 },
             ],
             IrTerminator::Return {
-                value: Some(VarId(0)),
+                values: vec![VarId(0)],
             },
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 1);
         match &func.blocks[0].instructions[0] {
             IrInstr::Const {
@@ -296,12 +320,12 @@ mod tests {
                     },
                 ],
                 IrTerminator::Return {
-                    value: Some(VarId(0)),
+                    values: vec![VarId(0)],
                 },
             ),
             vec![(VarId(0), WasmType::I32), (VarId(1), WasmType::I32)],
         );
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         // v1 is dead → removed from instructions and locals
         assert!(!func.locals.iter().any(|(v, _)| *v == VarId(1)));
         assert!(func.locals.iter().any(|(v, _)| *v == VarId(0)));
@@ -327,11 +351,11 @@ mod tests {
                 id: BlockId(1),
                 instructions: vec![],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(0)),
+                    values: vec![VarId(0)],
                 },
             },
         ]);
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 1);
     }
 
@@ -346,16 +370,96 @@ mod tests {
             }],
             ret_none(),
         ));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 0);
     }
 
+    // ── Calls: only unused calls to total functions are removed ─────────
+
+    #[test]
+    fn unused_call_to_total_function_removed() {
+        let call = |dest: u32, callee: usize| IrInstr::Call {
+            dests: vec![VarId(dest)],
+            func_idx: crate::ir::LocalFuncIdx::new(callee),
+            args: vec![],
+        };
+        let mut func = make_func(single_block(
+            vec![call(0, 0), call(1, 1), call(2, 0)],
+            IrTerminator::Return {
+                values: vec![VarId(2)],
+            },
+        ));
+        eliminate(&mut func, &[Purity::Total, Purity::Pure]);
+        // v0: total and unused → removed; v1: may trap → kept; v2: used → kept
+        let kept: Vec<_> = func.blocks[0]
+            .instructions
+            .iter()
+            .filter_map(instr_dest)
+            .collect();
+        assert_eq!(kept, [VarId(1), VarId(2)]);
+    }
+
+    // ── Phis (before lowering) ──────────────────────────────────────────
+
+    #[test]
+    fn unused_phi_removed() {
+        // B0: v0 = 1; br v9 → B1, B2
+        // B1: v1 = 2; jump B2
+        // B2: v2 = phi(B0: v0, B1: v1); v3 = phi(B0: v0, B1: v1); return v3
+        let mut func = make_func(vec![
+            IrBlock {
+                id: BlockId(0),
+                instructions: vec![IrInstr::Const {
+                    dest: VarId(0),
+                    value: IrValue::I32(1),
+                }],
+                terminator: IrTerminator::BranchIf {
+                    condition: VarId(9),
+                    if_true: BlockId(1),
+                    if_false: BlockId(2),
+                },
+            },
+            IrBlock {
+                id: BlockId(1),
+                instructions: vec![IrInstr::Const {
+                    dest: VarId(1),
+                    value: IrValue::I32(2),
+                }],
+                terminator: IrTerminator::Jump { target: BlockId(2) },
+            },
+            IrBlock {
+                id: BlockId(2),
+                instructions: vec![
+                    IrInstr::Phi {
+                        dest: VarId(2),
+                        srcs: vec![(BlockId(0), VarId(0)), (BlockId(1), VarId(1))],
+                    },
+                    IrInstr::Phi {
+                        dest: VarId(3),
+                        srcs: vec![(BlockId(0), VarId(0)), (BlockId(1), VarId(1))],
+                    },
+                ],
+                terminator: IrTerminator::Return {
+                    values: vec![VarId(3)],
+                },
+            },
+        ]);
+        eliminate(&mut func, &[]);
+        // v2 is unused; v3 keeps v0 and v1 alive
+        assert!(matches!(
+            func.blocks[2].instructions[..],
+            [IrInstr::Phi { dest: VarId(3), .. }]
+        ));
+        assert_eq!(func.blocks[0].instructions.len(), 1);
+        assert_eq!(func.blocks[1].instructions.len(), 1);
+    }
+
     // ── No-op: empty function ────────────────────────────────────────────
 
     #[test]
     fn empty_function_unchanged() {
         let mut func = make_func(single_block(vec![], ret_none()));
-        eliminate(&mut func);
+        eliminate(&mut func, &[]);
         assert_eq!(func.blocks[0].instructions.len(), 0);
     }
 }
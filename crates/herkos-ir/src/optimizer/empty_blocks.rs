@@ -95,7 +95,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -114,7 +114,7 @@ mod tests {
         IrBlock {
             id: BlockId(id),
             instructions: vec![],
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         }
     }
 
@@ -220,7 +220,7 @@ mod tests {
                     dest: VarId(1),
                     value: IrValue::I32(42),
                 }],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             jump(2, 3),
             ret(3),
@@ -211,7 +211,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -235,7 +235,7 @@ mod tests {
                 value: IrValue::I32(42),
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(1)),
+                values: vec![VarId(1)],
             },
         };
         let mut func = make_func(vec![b0, b1]);
@@ -284,7 +284,7 @@ mod tests {
                 rhs: VarId(1),
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(3)),
+                values: vec![VarId(3)],
             },
         };
         let mut func = make_func(vec![b0, b1]);
@@ -341,7 +341,7 @@ mod tests {
         let b3 = IrBlock {
             id: BlockId(3),
             instructions: vec![],
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         };
         let mut func = make_func(vec![b0, b1, b2, b3]);
         func.locals = vec![(VarId(1), WasmType::I32), (VarId(2), WasmType::I32)];
@@ -389,7 +389,7 @@ mod tests {
                 value: IrValue::I32(99),
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(1)),
+                values: vec![VarId(1)],
             },
         };
         let mut func = make_func(vec![b0, b1, b2]);
@@ -435,7 +435,7 @@ mod tests {
                 rhs: VarId(0),
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(3)),
+                values: vec![VarId(3)],
             },
         };
         let mut func = make_func(vec![b0, b1]);
@@ -470,7 +470,7 @@ mod tests {
                     value: IrValue::I32(1),
                 },
             ],
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         };
         let mut func = make_func(vec![b0]);
         func.locals = vec![(VarId(0), WasmType::I32), (VarId(1), WasmType::I32)];
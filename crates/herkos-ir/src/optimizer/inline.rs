@@ -0,0 +1,346 @@
+//! Inlining of small straight-line functions.
+//!
+//! ## What it does
+//!
+//! A direct call to a local function whose body is a single block ending in
+//! `return`, with at most `max_instrs` instructions, is replaced by a copy of
+//! that body, so that helpers like one-`BinOp` wrappers cost neither a Rust
+//! function call nor the `env` plumbing it passes along:
+//!
+//! ```text
+//! func 1 (add3):  v2 = v0 + v1; v3 = v2 + 3; return v3
+//! func 0:         v7 = call 1(v4, v5)
+//!   →
+//! func 0:         v10 = v4 + v5; v11 = v10 + 3; v7 = v11
+//! ```
+//!
+//! ## Algorithm
+//!
+//! 1. Snapshot every inlinable callee as it is now, so a pass inlines one
+//!    level deep and recursion cannot make it loop.
+//! 2. For each call to one of them from a different function: renumber the
+//!    callee's variables past every variable of both functions, then point
+//!    the parameters at the call's arguments. Declared locals get a zero
+//!    `Const` (Wasm zero-initializes them), and the call's destinations are
+//!    assigned the returned values.
+//! 3. Report the callers that changed, for the caller to re-optimize.
+//!
+//! Only straight-line callees are inlined: no block is added to the caller,
+//! and loops keep their yield points. Functions that skip the optimizer
+//! ([`ModuleInfo::skips_optimizer`]) are neither inlined nor inlined into.
+//! The callee itself is kept for its exports, table entries and any callers
+//! left. A trap in inlined code is reported in the caller.
+
+use super::utils::{
+    for_each_def, for_each_use, for_each_use_terminator, replace_uses_of, set_instr_dest,
+};
+use crate::ir::{IrFunction, IrInstr, IrTerminator, IrValue, ModuleInfo, VarId, WasmType};
+use std::collections::HashMap;
+
+/// Largest callee body, in instructions, inlined by default.
+pub const DEFAULT_INLINE_THRESHOLD: usize = 8;
+
+/// Body of a callee ready to be copied into callers.
+struct Inlinable {
+    params: Vec<VarId>,
+    locals: Vec<(VarId, WasmType)>,
+    body: Vec<IrInstr>,
+    results: Vec<VarId>,
+    /// One past the largest variable the callee uses.
+    next_var: u32,
+}
+
+/// Inline calls to small straight-line functions into their callers.
+///
+/// Returns the indices of the functions calls were inlined into.
+pub fn inline(module_info: &mut ModuleInfo, max_instrs: usize) -> Vec<usize> {
+    let callees: HashMap<usize, Inlinable> = module_info
+        .ir_functions
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !module_info.skips_optimizer(*idx))
+        .filter_map(|(idx, func)| Some((idx, inlinable(func, idx, max_instrs)?)))
+        .collect();
+    if callees.is_empty() {
+        return Vec::new();
+    }
+
+    let mut changed = Vec::new();
+    for idx in 0..module_info.ir_functions.len() {
+        if module_info.skips_optimizer(idx) {
+            continue;
+        }
+        let func = &mut module_info.ir_functions[idx];
+        let mut base = next_var(func);
+        let mut inlined = false;
+        for block_idx in 0..func.blocks.len() {
+            let instructions = std::mem::take(&mut func.blocks[block_idx].instructions);
+            let mut spliced = Vec::with_capacity(instructions.len());
+            for instr in instructions {
+                let callee = match &instr {
+                    IrInstr::Call { func_idx, .. } if func_idx.as_usize() != idx => {
+                        callees.get(&func_idx.as_usize())
+                    }
+                    _ => None,
+                };
+                let (Some(callee), IrInstr::Call { dests, args, .. }) = (callee, &instr) else {
+                    spliced.push(instr);
+                    continue;
+                };
+                let base_here = base.max(callee.next_var);
+                splice(&mut spliced, callee, dests, args, base_here);
+                base = base_here + callee.next_var;
+                inlined = true;
+            }
+            func.blocks[block_idx].instructions = spliced;
+        }
+        if inlined {
+            changed.push(idx);
+        }
+    }
+    changed
+}
+
+/// `func` as an [`Inlinable`], if it is small and straight-line enough.
+fn inlinable(func: &IrFunction, idx: usize, max_instrs: usize) -> Option<Inlinable> {
+    let [block] = func.blocks.as_slice() else {
+        return None;
+    };
+    let IrTerminator::Return { values } = &block.terminator else {
+        return None;
+    };
+    if block.instructions.len() > max_instrs {
+        return None;
+    }
+    let recursive = block
+        .instructions
+        .iter()
+        .any(|instr| matches!(instr, IrInstr::Call { func_idx, .. } if func_idx.as_usize() == idx));
+    if recursive
+        || block
+            .instructions
+            .iter()
+            .any(|instr| matches!(instr, IrInstr::Phi { .. }))
+    {
+        return None;
+    }
+    Some(Inlinable {
+        params: func.params.iter().map(|(var, _)| *var).collect(),
+        locals: func.locals.clone(),
+        body: block.instructions.clone(),
+        results: values.clone(),
+        next_var: next_var(func),
+    })
+}
+
+/// Append the body of `callee`, called with `args` into `dests`, to `out`,
+/// with its variables renumbered from `base`.
+fn splice(out: &mut Vec<IrInstr>, callee: &Inlinable, dests: &[VarId], args: &[VarId], base: u32) {
+    // Renumbering past both functions first keeps the replacements from
+    // colliding; parameters then read the arguments, all below `base`
+    let fresh = |var: VarId| VarId(base + var.0);
+    let params: HashMap<VarId, VarId> = callee
+        .params
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| (fresh(*param), *arg))
+        .collect();
+    let rename = |var: VarId| {
+        let var = fresh(var);
+        params.get(&var).copied().unwrap_or(var)
+    };
+
+    for (var, ty) in &callee.locals {
+        let value = match ty {
+            WasmType::I32 => IrValue::I32(0),
+            WasmType::I64 => IrValue::I64(0),
+            WasmType::F32 => IrValue::F32(0.0),
+            WasmType::F64 => IrValue::F64(0.0),
+        };
+        out.push(IrInstr::Const {
+            dest: fresh(*var),
+            value,
+        });
+    }
+    for instr in &callee.body {
+        let mut instr = instr.clone();
+        let mut uses = Vec::new();
+        for_each_use(&instr, |var| uses.push(var));
+        uses.sort_unstable_by_key(|var| var.0);
+        uses.dedup();
+        for var in uses {
+            replace_uses_of(&mut instr, var, fresh(var));
+        }
+        for (&param, &arg) in &params {
+            replace_uses_of(&mut instr, param, arg);
+        }
+        match &mut instr {
+            IrInstr::Call { dests, .. }
+            | IrInstr::CallImport { dests, .. }
+            | IrInstr::CallIndirect { dests, .. } => {
+                for dest in dests.iter_mut() {
+                    *dest = fresh(*dest);
+                }
+            }
+            _ => {
+                let mut dest = None;
+                for_each_def(&instr, |var| dest = Some(var));
+                if let Some(dest) = dest {
+                    set_instr_dest(&mut instr, fresh(dest));
+                }
+            }
+        }
+        out.push(instr);
+    }
+    for (dest, result) in dests.iter().zip(&callee.results) {
+        out.push(IrInstr::Assign {
+            dest: *dest,
+            src: rename(*result),
+        });
+    }
+}
+
+/// One past the largest variable `func` defines or reads.
+fn next_var(func: &IrFunction) -> u32 {
+    let mut next = 0;
+    let mut see = |var: VarId| next = next.max(var.0 + 1);
+    for (var, _) in func.params.iter().chain(&func.locals) {
+        see(*var);
+    }
+    for block in &func.blocks {
+        for instr in &block.instructions {
+            for_each_def(instr, &mut see);
+            for_each_use(instr, &mut see);
+        }
+        for_each_use_terminator(&block.terminator, &mut see);
+    }
+    next
+}
+
+// ── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BinOp, BlockId, IrBlock, LocalFuncIdx, TypeIdx};
+
+    fn single_block_func(
+        params: Vec<(VarId, WasmType)>,
+        instructions: Vec<IrInstr>,
+        values: Vec<VarId>,
+    ) -> IrFunction {
+        IrFunction {
+            params,
+            locals: vec![],
+            blocks: vec![IrBlock {
+                id: BlockId(0),
+                instructions,
+                terminator: IrTerminator::Return { values },
+            }],
+            entry_block: BlockId(0),
+            results: vec![WasmType::I32],
+            type_idx: TypeIdx::new(0),
+        }
+    }
+
+    fn call(dest: u32, callee: usize, args: Vec<VarId>) -> IrInstr {
+        IrInstr::Call {
+            dests: vec![VarId(dest)],
+            func_idx: LocalFuncIdx::new(callee),
+            args,
+        }
+    }
+
+    /// func 1: v2 = v0 + v1; return v2
+    fn add() -> IrFunction {
+        single_block_func(
+            vec![(VarId(0), WasmType::I32), (VarId(1), WasmType::I32)],
+            vec![IrInstr::BinOp {
+                dest: VarId(2),
+                op: BinOp::I32Add,
+                lhs: VarId(0),
+                rhs: VarId(1),
+            }],
+            vec![VarId(2)],
+        )
+    }
+
+    #[test]
+    fn call_is_replaced_by_the_callee_body() {
+        // func 0: v2 = call 1(v1, v0); return v2 — arguments swapped so that
+        // the callee's parameter numbers collide with the caller's
+        let caller = single_block_func(
+            vec![(VarId(0), WasmType::I32), (VarId(1), WasmType::I32)],
+            vec![call(2, 1, vec![VarId(1), VarId(0)])],
+            vec![VarId(2)],
+        );
+        let mut info = ModuleInfo {
+            ir_functions: vec![caller, add()],
+            ..Default::default()
+        };
+        assert_eq!(inline(&mut info, DEFAULT_INLINE_THRESHOLD), [0]);
+        let body = &info.ir_functions[0].blocks[0].instructions;
+        assert!(
+            matches!(
+                body[..],
+                [
+                    IrInstr::BinOp {
+                        dest: sum,
+                        op: BinOp::I32Add,
+                        lhs: VarId(1),
+                        rhs: VarId(0),
+                    },
+                    IrInstr::Assign {
+                        dest: VarId(2),
+                        src,
+                    },
+                ] if sum == src && sum.0 >= 3
+            ),
+            "{body:?}"
+        );
+    }
+
+    #[test]
+    fn large_recursive_and_branching_callees_are_kept() {
+        let recursive = single_block_func(
+            vec![(VarId(0), WasmType::I32)],
+            vec![call(1, 1, vec![VarId(0)])],
+            vec![VarId(1)],
+        );
+        let caller = single_block_func(
+            vec![(VarId(0), WasmType::I32)],
+            vec![
+                call(1, 1, vec![VarId(0)]),
+                call(2, 2, vec![VarId(0), VarId(1)]),
+            ],
+            vec![VarId(2)],
+        );
+        let mut info = ModuleInfo {
+            ir_functions: vec![caller, recursive, add()],
+            ..Default::default()
+        };
+        // `add` has one instruction: over a threshold of zero
+        assert!(inline(&mut info, 0).is_empty());
+        assert_eq!(inline(&mut info, 1), [0]);
+        let body = &info.ir_functions[0].blocks[0].instructions;
+        assert!(matches!(body[0], IrInstr::Call { .. }));
+        assert!(!matches!(body[1], IrInstr::Call { .. }));
+    }
+
+    #[test]
+    fn functions_skipping_the_optimizer_are_left_alone() {
+        let caller = single_block_func(
+            vec![(VarId(0), WasmType::I32)],
+            vec![call(1, 1, vec![VarId(0), VarId(0)])],
+            vec![VarId(1)],
+        );
+        let mut info = ModuleInfo {
+            ir_functions: vec![caller.clone(), add()],
+            unoptimized: vec![false, true],
+            ..Default::default()
+        };
+        assert!(inline(&mut info, DEFAULT_INLINE_THRESHOLD).is_empty());
+        info.unoptimized = vec![true, false];
+        assert!(inline(&mut info, DEFAULT_INLINE_THRESHOLD).is_empty());
+    }
+}
@@ -14,17 +14,21 @@
 //!    - `BinOp`, `UnOp`, `Select` — invariant if all operands are
 //!      defined outside the loop or by other invariant instructions
 //!    - Skip: `Assign`, `Load`, `Store`, `Call*`, `Global*`, `Memory*`
+//!    - Skip any instruction whose destination is defined again in the loop,
+//!      or read in the header before it: after phi lowering, that is a
+//!      variable carried across iterations, like a loop counter reset to a
+//!      constant by an enclosing loop
 //! 5. Create or reuse a preheader block and move invariant instructions there
 //!
 //! **V1 simplification:** only hoists from the loop header block (which
 //! dominates all loop blocks by definition).
 
 use super::utils::{
-    build_predecessors, compute_idoms, for_each_use, instr_dest, rewrite_terminator_target,
-    terminator_successors,
+    build_predecessors, compute_idoms, for_each_def, for_each_use, instr_dest,
+    rewrite_terminator_target, terminator_successors,
 };
 use crate::ir::{BlockId, IrBlock, IrFunction, IrInstr, IrTerminator, VarId};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Run loop-invariant code motion on `func`.
 pub fn eliminate(func: &mut IrFunction) {
@@ -84,13 +88,14 @@ fn find_back_edges(func: &IrFunction, idom: &HashMap<BlockId, BlockId>) -> Vec<(
 ///
 /// For each back edge (src → header), collects all blocks that can reach `src`
 /// without going through `header`. Multiple back edges with the same header
-/// are merged into one loop.
+/// are merged into one loop. Loops come in header order, so preheaders are
+/// numbered the same on every run.
 fn find_natural_loops(
     back_edges: &[(BlockId, BlockId)],
     preds: &HashMap<BlockId, HashSet<BlockId>>,
 ) -> Vec<(BlockId, HashSet<BlockId>)> {
     // Map from loop header → set of all blocks in that loop.
-    let mut loops: HashMap<BlockId, HashSet<BlockId>> = HashMap::new();
+    let mut loops: BTreeMap<BlockId, HashSet<BlockId>> = BTreeMap::new();
 
     for &(src, header) in back_edges {
         // Seed the loop with its header. Multiple back edges to the same header
@@ -145,18 +150,33 @@ fn hoist_invariants(func: &mut IrFunction, header: BlockId, loop_blocks: &HashSe
         None => return,
     };
 
-    // Collect all VarIds defined in any loop block.
-    let mut loop_defs: HashSet<VarId> = HashSet::new();
+    // Count the definitions of each VarId in the loop blocks.
+    let mut loop_defs: HashMap<VarId, usize> = HashMap::new();
     for block in &func.blocks {
         if loop_blocks.contains(&block.id) {
             for instr in &block.instructions {
-                if let Some(dest) = instr_dest(instr) {
-                    loop_defs.insert(dest);
-                }
+                for_each_def(instr, |dest| {
+                    *loop_defs.entry(dest).or_default() += 1;
+                });
             }
         }
     }
 
+    // Variables the header reads before defining them hold the previous
+    // iteration's value there.
+    let mut read_before_def: HashSet<VarId> = HashSet::new();
+    let mut defined: HashSet<VarId> = HashSet::new();
+    for instr in &func.blocks[header_idx].instructions {
+        for_each_use(instr, |v| {
+            if !defined.contains(&v) {
+                read_before_def.insert(v);
+            }
+        });
+        for_each_def(instr, |dest| {
+            defined.insert(dest);
+        });
+    }
+
     // Fixpoint: identify invariant instructions in the header.
     let mut invariant_dests: HashSet<VarId> = HashSet::new();
     loop {
@@ -169,13 +189,16 @@ fn hoist_invariants(func: &mut IrFunction, header: BlockId, loop_blocks: &HashSe
                 Some(d) => d,
                 None => continue,
             };
-            if invariant_dests.contains(&dest) {
+            if invariant_dests.contains(&dest)
+                || loop_defs.get(&dest) != Some(&1)
+                || read_before_def.contains(&dest)
+            {
                 continue;
             }
 
             let mut all_ops_invariant = true;
             for_each_use(instr, |v| {
-                if loop_defs.contains(&v) && !invariant_dests.contains(&v) {
+                if loop_defs.contains_key(&v) && !invariant_dests.contains(&v) {
                     all_ops_invariant = false;
                 }
             });
@@ -303,7 +326,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -325,7 +348,7 @@ mod tests {
                 id: BlockId(1),
                 instructions: vec![],
                 terminator: IrTerminator::Return {
-                    value: Some(VarId(0)),
+                    values: vec![VarId(0)],
                 },
             },
         ]);
@@ -374,7 +397,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -441,7 +464,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -508,7 +531,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -584,7 +607,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -645,7 +668,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -694,7 +717,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -770,7 +793,7 @@ mod tests {
             IrBlock {
                 id: BlockId(5),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -846,7 +869,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -886,7 +909,7 @@ mod tests {
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -949,7 +972,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -990,7 +1013,7 @@ mod tests {
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -1071,7 +1094,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -1110,7 +1133,7 @@ mod tests {
                 value: IrValue::I32(42),
             }],
             terminator: IrTerminator::Return {
-                value: Some(VarId(0)),
+                values: vec![VarId(0)],
             },
         }]);
 
@@ -1139,7 +1162,7 @@ mod tests {
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -1176,7 +1199,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -1257,6 +1280,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn natural_loops_come_in_header_order() {
+        // Self-loops found last-header-first
+        let back_edges: Vec<(BlockId, BlockId)> =
+            (1..9).rev().map(|b| (BlockId(b), BlockId(b))).collect();
+        let loops = find_natural_loops(&back_edges, &HashMap::new());
+
+        let headers: Vec<BlockId> = loops.iter().map(|(header, _)| *header).collect();
+        assert_eq!(headers, (1..9).map(BlockId).collect::<Vec<_>>());
+    }
+
     // ── Assign (counter reset) must NOT be hoisted from inner loop ────────
     //
     // Regression test for: `IrInstr::Assign` appearing in `is_licm_hoistable`
@@ -1345,7 +1379,7 @@ mod tests {
             IrBlock {
                 id: BlockId(5),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
 
@@ -1363,4 +1397,138 @@ mod tests {
             "j = Assign(v_init) must stay in the inner header B2, not be hoisted"
         );
     }
+
+    // ── Lowered counter reset folded to a Const → NOT hoisted ─────────────
+
+    #[test]
+    fn const_counter_reset_not_hoisted_from_outer_loop() {
+        // Copy propagation can turn the inner counter's reset into a Const in
+        // the outer header, which the inner loop then decrements:
+        //   B0: Jump(B1)
+        //   B1 (outer header): j = Const(4); Jump(B2)
+        //   B2 (inner loop): j = j - one; BranchIf(j, B2, B3)
+        //   B3: BranchIf(v0, B1, B4)   ← outer back edge
+        //   B4: Return
+        let mut func = make_func(vec![
+            IrBlock {
+                id: BlockId(0),
+                instructions: vec![],
+                terminator: IrTerminator::Jump { target: BlockId(1) },
+            },
+            IrBlock {
+                id: BlockId(1),
+                instructions: vec![IrInstr::Const {
+                    dest: VarId(1),
+                    value: IrValue::I32(4),
+                }],
+                terminator: IrTerminator::Jump { target: BlockId(2) },
+            },
+            IrBlock {
+                id: BlockId(2),
+                instructions: vec![IrInstr::BinOp {
+                    dest: VarId(1),
+                    op: BinOp::I32Sub,
+                    lhs: VarId(1),
+                    rhs: VarId(2),
+                }],
+                terminator: IrTerminator::BranchIf {
+                    condition: VarId(1),
+                    if_true: BlockId(2),
+                    if_false: BlockId(3),
+                },
+            },
+            IrBlock {
+                id: BlockId(3),
+                instructions: vec![],
+                terminator: IrTerminator::BranchIf {
+                    condition: VarId(0),
+                    if_true: BlockId(1),
+                    if_false: BlockId(4),
+                },
+            },
+            IrBlock {
+                id: BlockId(4),
+                instructions: vec![],
+                terminator: IrTerminator::Return { values: Vec::new() },
+            },
+        ]);
+
+        eliminate(&mut func);
+
+        let outer_header = func.blocks.iter().find(|b| b.id == BlockId(1)).unwrap();
+        assert!(
+            matches!(
+                outer_header.instructions[..],
+                [IrInstr::Const { dest: VarId(1), .. }]
+            ),
+            "j = Const(4) must reset the counter on every outer iteration"
+        );
+    }
+
+    // ── Value read in the header before its only definition → NOT hoisted ─
+
+    #[test]
+    fn def_read_earlier_in_header_not_hoisted() {
+        // After phi lowering, the header reads the previous iteration's v1
+        // before setting it; hoisting v1 = Const(5) would hide the entry
+        // value from the first iteration:
+        //   B0: v1 = Const(0); Jump(B1)
+        //   B1 (header): v3 = v2 + v1; v2 = v3; v1 = Const(5)
+        //                BranchIf(v0, B1, B2)   ← back edge
+        //   B2: Return(v3)
+        let mut func = make_func(vec![
+            IrBlock {
+                id: BlockId(0),
+                instructions: vec![IrInstr::Const {
+                    dest: VarId(1),
+                    value: IrValue::I32(0),
+                }],
+                terminator: IrTerminator::Jump { target: BlockId(1) },
+            },
+            IrBlock {
+                id: BlockId(1),
+                instructions: vec![
+                    IrInstr::BinOp {
+                        dest: VarId(3),
+                        op: BinOp::I32Add,
+                        lhs: VarId(2),
+                        rhs: VarId(1),
+                    },
+                    IrInstr::Assign {
+                        dest: VarId(2),
+                        src: VarId(3),
+                    },
+                    IrInstr::Const {
+                        dest: VarId(1),
+                        value: IrValue::I32(5),
+                    },
+                ],
+                terminator: IrTerminator::BranchIf {
+                    condition: VarId(0),
+                    if_true: BlockId(1),
+                    if_false: BlockId(2),
+                },
+            },
+            IrBlock {
+                id: BlockId(2),
+                instructions: vec![],
+                terminator: IrTerminator::Return {
+                    values: vec![VarId(3)],
+                },
+            },
+        ]);
+
+        eliminate(&mut func);
+
+        let header = func.blocks.iter().find(|b| b.id == BlockId(1)).unwrap();
+        assert!(
+            matches!(
+                header.instructions.last(),
+                Some(IrInstr::Const { dest: VarId(1), .. })
+            ),
+            "v1 = Const(5) must stay after the read of the entry value"
+        );
+        let entry = func.blocks.iter().find(|b| b.id == BlockId(0)).unwrap();
+        assert_eq!(entry.instructions.len(), 1);
+    }
 }
@@ -96,7 +96,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -106,7 +106,7 @@ mod tests {
         IrBlock {
             id: BlockId(id),
             instructions,
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         }
     }
 
@@ -353,7 +353,7 @@ mod tests {
                 lhs: VarId(0),
                 rhs: VarId(1),
             }],
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         };
 
         let mut func = make_func(vec![block0, block1]);
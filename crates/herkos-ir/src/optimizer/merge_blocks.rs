@@ -105,7 +105,7 @@ mod tests {
             locals: vec![],
             blocks,
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         }
     }
@@ -132,7 +132,7 @@ mod tests {
         let mut func = make_func(vec![IrBlock {
             id: BlockId(0),
             instructions: vec![],
-            terminator: IrTerminator::Return { value: None },
+            terminator: IrTerminator::Return { values: Vec::new() },
         }]);
         eliminate(&mut func);
         assert_eq!(block_ids(&func), vec![0]);
@@ -149,7 +149,7 @@ mod tests {
                 2,
                 3,
                 IrTerminator::Return {
-                    value: Some(VarId(2)),
+                    values: vec![VarId(2)],
                 },
             ),
         ]);
@@ -158,10 +158,8 @@ mod tests {
         assert_eq!(block_ids(&func), vec![0]);
         assert_eq!(func.blocks[0].instructions.len(), 3);
         assert!(matches!(
-            func.blocks[0].terminator,
-            IrTerminator::Return {
-                value: Some(VarId(2))
-            }
+            &func.blocks[0].terminator,
+            IrTerminator::Return { values } if values == &[VarId(2)]
         ));
     }
 
@@ -184,12 +182,12 @@ mod tests {
             IrBlock {
                 id: BlockId(1),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func);
@@ -214,7 +212,7 @@ mod tests {
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         // B1 is dead (no predecessor), but merge_blocks doesn't remove dead blocks.
@@ -245,7 +243,7 @@ mod tests {
                 IrBlock {
                     id: BlockId(0),
                     instructions: vec![],
-                    terminator: IrTerminator::Return { value: None },
+                    terminator: IrTerminator::Return { values: Vec::new() },
                 },
                 IrBlock {
                     id: BlockId(1),
@@ -254,7 +252,7 @@ mod tests {
                 },
             ],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: TypeIdx::new(0),
         };
         eliminate(&mut func);
@@ -287,14 +285,14 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func);
         assert_eq!(block_ids(&func), vec![0]);
         assert!(matches!(
-            func.blocks[0].terminator,
-            IrTerminator::Return { value: None }
+            &func.blocks[0].terminator,
+            IrTerminator::Return { values } if values.is_empty()
         ));
     }
 
@@ -322,12 +320,12 @@ mod tests {
             IrBlock {
                 id: BlockId(2),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func);
@@ -371,7 +369,7 @@ mod tests {
             IrBlock {
                 id: BlockId(3),
                 instructions: vec![],
-                terminator: IrTerminator::Return { value: None },
+                terminator: IrTerminator::Return { values: Vec::new() },
             },
         ]);
         eliminate(&mut func);
@@ -0,0 +1,288 @@
+//! Merging of duplicate function bodies.
+//!
+//! ## What it does
+//!
+//! Template-heavy C++ modules contain many functions with identical bodies
+//! (one instantiation per type that lowers to the same Wasm). Each duplicate
+//! is recorded in [`ModuleInfo::merged_into`] and every reference to it —
+//! direct calls, exports and table entries — is redirected to the first
+//! function with the same body, so codegen emits that body only once.
+//!
+//! ## Algorithm
+//!
+//! 1. Each function is reduced to a canonical key: its signature and blocks
+//!    with variables and blocks renumbered in order of first appearance, so
+//!    bodies built with different numbering still compare equal. Float
+//!    constants are keyed by their bits (`NaN` payloads and `-0.0` differ).
+//! 2. Functions with equal keys are merged into the lowest-indexed one, and
+//!    calls to merged functions are redirected. Constant-time functions are
+//!    never merged.
+//! 3. Redirected calls can make callers identical in turn, so steps 1-2
+//!    repeat until nothing changes.
+//!
+//! ## Safety
+//!
+//! Function indices stay stable: merged functions keep their IR, only their
+//! generated code is dropped. Wasm cannot compare function references, so a
+//! table entry pointing at the surviving copy is indistinguishable from one
+//! pointing at the duplicate.
+
+use crate::ir::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Merge functions of `info` with identical bodies.
+pub fn merge(info: &mut ModuleInfo) {
+    let n = info.ir_functions.len();
+    if info.merged_into.len() != n {
+        info.merged_into = vec![None; n];
+    }
+
+    loop {
+        let mut first: HashMap<String, usize> = HashMap::new();
+        let mut merged = false;
+        for idx in 0..n {
+            // Constant-time and unoptimized functions keep their own body
+            if info.merged_into[idx].is_some() || info.skips_optimizer(idx) {
+                continue;
+            }
+            let key = canonical_key(&info.ir_functions[idx]);
+            match first.get(&key) {
+                Some(&canon) => {
+                    info.merged_into[idx] = Some(LocalFuncIdx::new(canon));
+                    merged = true;
+                }
+                None => {
+                    first.insert(key, idx);
+                }
+            }
+        }
+        if !merged {
+            break;
+        }
+        let target = targets(&info.merged_into);
+        for func in &mut info.ir_functions {
+            for block in &mut func.blocks {
+                for instr in &mut block.instructions {
+                    if let IrInstr::Call { func_idx, .. } = instr {
+                        *func_idx = target(*func_idx);
+                    }
+                }
+            }
+        }
+    }
+
+    // A later round can merge a survivor in turn: point everything at the
+    // final one.
+    let resolved: Vec<Option<LocalFuncIdx>> = {
+        let target = targets(&info.merged_into);
+        info.merged_into.iter().map(|m| m.map(&target)).collect()
+    };
+    info.merged_into = resolved;
+    let target = targets(&info.merged_into);
+    for export in &mut info.func_exports {
+        export.func_index = target(export.func_index);
+    }
+    for seg in &mut info.element_segments {
+        for func in &mut seg.func_indices {
+            if let ElementFunc::Local(idx) = func {
+                *idx = target(*idx);
+            }
+        }
+    }
+
+    // The survivor is compiled whenever any of its copies was
+    for idx in 0..n.min(info.func_features.len()) {
+        let Some(canon) = info.merged_into[idx] else {
+            continue;
+        };
+        let canon = canon.as_usize();
+        if info.func_features[canon].is_empty() {
+            continue;
+        }
+        if info.func_features[idx].is_empty() {
+            info.func_features[canon].clear();
+        } else {
+            let features = info.func_features[idx].clone();
+            let merged = &mut info.func_features[canon];
+            merged.extend(features);
+            merged.sort();
+            merged.dedup();
+        }
+    }
+}
+
+/// Map from a function to the function that replaces it, following chains.
+fn targets(merged_into: &[Option<LocalFuncIdx>]) -> impl Fn(LocalFuncIdx) -> LocalFuncIdx + '_ {
+    move |mut idx| {
+        while let Some(next) = merged_into[idx.as_usize()] {
+            idx = next;
+        }
+        idx
+    }
+}
+
+/// Canonical text of `func`: equal for bodies that differ only in variable
+/// and block numbering.
+fn canonical_key(func: &IrFunction) -> String {
+    let text = format!(
+        "{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+        func.type_idx, func.results, func.params, func.locals, func.entry_block, func.blocks
+    );
+    let mut key = renumber(&text);
+
+    // `{:?}` prints every NaN alike
+    for instr in func.blocks.iter().flat_map(|b| &b.instructions) {
+        match instr {
+            IrInstr::Const {
+                value: IrValue::F32(v),
+                ..
+            } => write!(key, "|{:x}", v.to_bits()).unwrap(),
+            IrInstr::Const {
+                value: IrValue::F64(v),
+                ..
+            } => write!(key, "|{:x}", v.to_bits()).unwrap(),
+            _ => {}
+        }
+    }
+    key
+}
+
+/// Replace the numbers in `VarId(n)` and `BlockId(n)` by their order of
+/// first appearance.
+fn renumber(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut vars: HashMap<&str, usize> = HashMap::new();
+    let mut blocks: HashMap<&str, usize> = HashMap::new();
+    let mut rest = text;
+    loop {
+        let next = [("VarId(", 0), ("BlockId(", 1)]
+            .into_iter()
+            .filter_map(|(tag, kind)| rest.find(tag).map(|at| (at, tag, kind)))
+            .min();
+        let Some((at, tag, kind)) = next else {
+            out.push_str(rest);
+            return out;
+        };
+        let start = at + tag.len();
+        out.push_str(&rest[..start]);
+        let digits = rest[start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - start);
+        let number = &rest[start..start + digits];
+        let ids = if kind == 0 { &mut vars } else { &mut blocks };
+        let fresh = ids.len();
+        let id = *ids.entry(number).or_insert(fresh);
+        write!(out, "{id}").unwrap();
+        rest = &rest[start + digits..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{build_module_info, BuildOptions};
+
+    fn merged(wat: &str) -> ModuleInfo {
+        let wasm = wat::parse_str(wat).unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let mut info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+        merge(&mut info);
+        info
+    }
+
+    fn targets_of(info: &ModuleInfo) -> Vec<Option<usize>> {
+        info.merged_into
+            .iter()
+            .map(|m| m.map(|idx| idx.as_usize()))
+            .collect()
+    }
+
+    #[test]
+    fn identical_bodies_merge_into_the_first() {
+        let info = merged(
+            r#"(module
+                (table 2 funcref)
+                (elem (i32.const 0) $a $b)
+                (func $a (export "a") (param i32) (result i32)
+                    (i32.mul (local.get 0) (i32.const 3)))
+                (func $b (export "b") (param i32) (result i32)
+                    (i32.mul (local.get 0) (i32.const 3)))
+                (func $c (export "c") (param i32) (result i32)
+                    (i32.mul (local.get 0) (i32.const 4)))
+                (func (export "both") (param i32) (result i32)
+                    (i32.add (call $a (local.get 0)) (call $b (local.get 0)))))"#,
+        );
+        assert_eq!(targets_of(&info), [None, Some(0), None, None]);
+
+        let exports: Vec<(&str, usize)> = info
+            .func_exports
+            .iter()
+            .map(|e| (e.name.as_str(), e.func_index.as_usize()))
+            .collect();
+        assert_eq!(exports, [("a", 0), ("b", 0), ("c", 2), ("both", 3)]);
+
+        let table: Vec<usize> = info.element_segments[0]
+            .func_indices
+            .iter()
+            .map(|f| match f {
+                ElementFunc::Local(idx) => idx.as_usize(),
+                ElementFunc::Import { .. } => unreachable!(),
+            })
+            .collect();
+        assert_eq!(table, [0, 0]);
+
+        let calls: Vec<usize> = info.ir_functions[3]
+            .blocks
+            .iter()
+            .flat_map(|b| &b.instructions)
+            .filter_map(|i| match i {
+                IrInstr::Call { func_idx, .. } => Some(func_idx.as_usize()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, [0, 0]);
+    }
+
+    #[test]
+    fn callers_of_merged_functions_merge_too() {
+        let info = merged(
+            r#"(module
+                (func $a (result i32) (i32.const 7))
+                (func $b (result i32) (i32.const 7))
+                (func (export "x") (result i32) (i32.add (call $a) (i32.const 1)))
+                (func (export "y") (result i32) (i32.add (call $b) (i32.const 1))))"#,
+        );
+        assert_eq!(targets_of(&info), [None, Some(0), None, Some(2)]);
+        assert_eq!(info.func_exports[1].func_index.as_usize(), 2);
+    }
+
+    #[test]
+    fn signatures_and_float_bits_keep_functions_apart() {
+        let info = merged(
+            r#"(module
+                (func (export "nan1") (result f32) (f32.const nan:0x1))
+                (func (export "nan2") (result f32) (f32.const nan:0x2))
+                (func (export "zero") (result f32) (f32.const 0))
+                (func (export "neg") (result f32) (f32.const -0))
+                (func (export "i32") (param i32) (local.get 0) (drop))
+                (func (export "i64") (param i64) (local.get 0) (drop)))"#,
+        );
+        assert!(info.merged_into.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn constant_time_functions_are_not_merged() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "a") (param i32) (result i32) (i32.mul (local.get 0) (i32.const 3)))
+                (func (export "b") (param i32) (result i32) (i32.mul (local.get 0) (i32.const 3))))"#,
+        )
+        .unwrap();
+        let parsed = crate::parser::parse_wasm(&wasm).unwrap();
+        let mut info = build_module_info(&parsed, &BuildOptions::default()).unwrap();
+        info.constant_time = vec![false, true];
+        merge(&mut info);
+        assert_eq!(targets_of(&info), [None, None]);
+    }
+}
@@ -0,0 +1,275 @@
+//! IR optimization passes.
+//!
+//! This module implements optimizations on the intermediate representation (IR)
+//! to improve code generation quality and runtime performance.
+//!
+//! Passes are split into two phases:
+//! - **Pre-lowering** ([`optimize_ir`]): operates on SSA IR with phi nodes
+//! - **Post-lowering** ([`optimize_lowered_ir`]): operates on lowered IR after phi destruction
+
+use crate::ir::{compute_purity, IrFunction, LoweredModuleInfo, ModuleInfo};
+use anyhow::Result;
+
+// ── Shared utilities ─────────────────────────────────────────────────────────
+pub mod utils;
+
+// ── Passes run in both phases ────────────────────────────────────────────────
+mod dead_instrs;
+
+// ── Pre-lowering passes ──────────────────────────────────────────────────────
+mod algebraic;
+mod const_data;
+mod const_prop;
+mod copy_prop;
+mod dead_args;
+mod dead_blocks;
+mod inline;
+mod merge_functions;
+
+pub use inline::DEFAULT_INLINE_THRESHOLD;
+
+// Constant evaluation, shared with the start function evaluator
+pub(crate) use const_prop::{try_eval_binop, try_eval_unop};
+
+// ── Post-lowering passes ─────────────────────────────────────────────────────
+mod branch_fold;
+mod empty_blocks;
+mod gvn;
+mod licm;
+mod local_cse;
+mod merge_blocks;
+
+/// Optimizes the pure SSA IR before phi lowering.
+///
+/// Passes here operate on [`ModuleInfo`] with phi nodes still intact.
+/// Runs value optimizations (const_prop, algebraic), copy propagation and
+/// dead instruction elimination to simplify the IR before SSA destruction,
+/// so that phi lowering and code generation see fewer variables. Calls are
+/// kept: purity is only known after lowering. Constant-time functions
+/// ([`ModuleInfo::constant_time`]) and functions listed in
+/// [`ModuleInfo::unoptimized`] are left as translated.
+pub fn optimize_ir(module_info: ModuleInfo, do_opt: bool) -> Result<ModuleInfo> {
+    let mut module_info = module_info;
+    if do_opt {
+        let skipped: Vec<bool> = (0..module_info.ir_functions.len())
+            .map(|idx| module_info.skips_optimizer(idx))
+            .collect();
+        for (idx, func) in module_info.ir_functions.iter_mut().enumerate() {
+            if !skipped[idx] {
+                optimize_function(func)?;
+            }
+        }
+    }
+    Ok(module_info)
+}
+
+/// The [`optimize_ir`] passes over one function.
+fn optimize_function(func: &mut IrFunction) -> Result<()> {
+    for _ in 0..2 {
+        dead_blocks::eliminate(func)?;
+        const_prop::eliminate(func)?;
+        algebraic::eliminate(func);
+        copy_prop::eliminate(func);
+        dead_instrs::eliminate(func, &[]);
+    }
+    Ok(())
+}
+
+/// Inlines calls to straight-line functions of at most `max_instrs`
+/// instructions, then re-optimizes the callers (see `inline`).
+///
+/// Module-level, on SSA IR, after [`optimize_ir`] so callees are measured
+/// once simplified.
+pub fn inline_small_functions(module_info: &mut ModuleInfo, max_instrs: usize) -> Result<()> {
+    for idx in inline::inline(module_info, max_instrs) {
+        optimize_function(&mut module_info.ir_functions[idx])?;
+    }
+    Ok(())
+}
+
+/// Promotes data segments the module never writes to constants and folds the
+/// loads that read them at constant addresses (see `const_data`).
+///
+/// Module-level, on SSA IR. Only valid when nothing outside the generated
+/// function bodies reads or writes linear memory: the caller must skip
+/// modules whose generated wrappers access guest memory for the host.
+pub fn promote_read_only_data(module_info: &mut ModuleInfo) {
+    const_data::promote(module_info);
+}
+
+/// Removes parameters that functions only reachable by direct calls never
+/// read, and the matching call arguments (see `dead_args`).
+///
+/// Module-level, on SSA IR, after [`optimize_ir`].
+pub fn eliminate_dead_arguments(module_info: &mut ModuleInfo) {
+    dead_args::eliminate(module_info);
+}
+
+/// Merges functions with identical bodies, redirecting calls, exports and
+/// table entries to one survivor (see `merge_functions`).
+///
+/// Module-level, on SSA IR, after [`optimize_ir`] so bodies that optimize to
+/// the same code merge too.
+pub fn merge_duplicate_functions(module_info: &mut ModuleInfo) {
+    merge_functions::merge(module_info);
+}
+
+/// Optimizes the lowered IR after phi nodes have been eliminated.
+///
+/// Runs post-lowering structural passes, redundancy elimination (local CSE,
+/// GVN), branch condition folding, and loop invariant code motion. We repeat
+/// until reaching a fixed point (typically 2 iterations). Functions that
+/// skip the optimizer ([`ModuleInfo::skips_optimizer`]) are left as lowered.
+///
+/// Unused calls to total functions are deleted, unless every block counts
+/// its executions ([`ModuleInfo::coverage`]). Fills in
+/// [`ModuleInfo::purity`] for the result, whether or not `do_opt` is set.
+pub fn optimize_lowered_ir(
+    module_info: LoweredModuleInfo,
+    do_opt: bool,
+) -> Result<LoweredModuleInfo> {
+    let mut module_info = module_info;
+    if do_opt {
+        let skipped: Vec<bool> = (0..module_info.ir_functions.len())
+            .map(|idx| module_info.skips_optimizer(idx))
+            .collect();
+        let purity = if module_info.coverage || module_info.bounds_profile {
+            Vec::new()
+        } else {
+            compute_purity(&module_info)
+        };
+        for (idx, func) in module_info.ir_functions.iter_mut().enumerate() {
+            if skipped[idx] {
+                continue;
+            }
+            for _ in 0..2 {
+                empty_blocks::eliminate(func);
+                dead_blocks::eliminate(func)?;
+                merge_blocks::eliminate(func);
+                dead_blocks::eliminate(func)?;
+                copy_prop::eliminate(func);
+                local_cse::eliminate(func);
+                gvn::eliminate(func);
+                dead_instrs::eliminate(func, &purity);
+                branch_fold::eliminate(func);
+                dead_instrs::eliminate(func, &purity);
+                licm::eliminate(func);
+            }
+        }
+    }
+    module_info.purity = compute_purity(&module_info);
+    Ok(module_info)
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{BlockId, IrBlock, IrFunction, IrTerminator, ModuleInfo, TypeIdx};
+
+    #[test]
+    fn optimize_ir_eliminates_dead_blocks_across_functions() {
+        let make_ir_func = |blocks: Vec<IrBlock>| IrFunction {
+            params: vec![],
+            locals: vec![],
+            blocks,
+            entry_block: BlockId(0),
+            results: Vec::new(),
+            type_idx: TypeIdx::new(0),
+        };
+
+        let module = ModuleInfo {
+            ir_functions: vec![
+                // func 0: block_0 → Return, block_1 dead
+                make_ir_func(vec![
+                    IrBlock {
+                        id: BlockId(0),
+                        instructions: vec![],
+                        terminator: IrTerminator::Return { values: Vec::new() },
+                    },
+                    IrBlock {
+                        id: BlockId(1),
+                        instructions: vec![],
+                        terminator: IrTerminator::Return { values: Vec::new() },
+                    },
+                ]),
+                // func 1: block_0 → Jump → block_1 → Return (all live)
+                make_ir_func(vec![
+                    IrBlock {
+                        id: BlockId(0),
+                        instructions: vec![],
+                        terminator: IrTerminator::Jump { target: BlockId(1) },
+                    },
+                    IrBlock {
+                        id: BlockId(1),
+                        instructions: vec![],
+                        terminator: IrTerminator::Return { values: Vec::new() },
+                    },
+                ]),
+            ],
+            ..Default::default()
+        };
+
+        let result = super::optimize_ir(module, true).unwrap();
+        assert_eq!(
+            result.ir_functions[0].blocks.len(),
+            1,
+            "dead block in func 0 should be removed"
+        );
+        assert_eq!(
+            result.ir_functions[1].blocks.len(),
+            2,
+            "both blocks in func 1 should be kept"
+        );
+    }
+
+    #[test]
+    fn constant_time_functions_are_not_optimized() {
+        let block = |id| IrBlock {
+            id: BlockId(id),
+            instructions: vec![],
+            terminator: IrTerminator::Return { values: Vec::new() },
+        };
+        let module = ModuleInfo {
+            ir_functions: vec![IrFunction {
+                params: vec![],
+                locals: vec![],
+                blocks: vec![block(0), block(1)],
+                entry_block: BlockId(0),
+                results: Vec::new(),
+                type_idx: TypeIdx::new(0),
+            }],
+            constant_time: vec![true],
+            ..Default::default()
+        };
+
+        let result = super::optimize_ir(module, true).unwrap();
+        assert_eq!(result.ir_functions[0].blocks.len(), 2);
+    }
+
+    #[test]
+    fn unoptimized_functions_are_left_as_translated() {
+        let block = |id| IrBlock {
+            id: BlockId(id),
+            instructions: vec![],
+            terminator: IrTerminator::Return { values: Vec::new() },
+        };
+        let func = IrFunction {
+            params: vec![],
+            locals: vec![],
+            blocks: vec![block(0), block(1)],
+            entry_block: BlockId(0),
+            results: Vec::new(),
+            type_idx: TypeIdx::new(0),
+        };
+        let module = ModuleInfo {
+            ir_functions: vec![func.clone(), func],
+            unoptimized: vec![false, true],
+            ..Default::default()
+        };
+
+        let result = super::optimize_ir(module, true).unwrap();
+        assert_eq!(result.ir_functions[0].blocks.len(), 1);
+        assert_eq!(result.ir_functions[1].blocks.len(), 2);
+    }
+}
@@ -1,10 +1,9 @@
 //! Shared utility functions for IR optimization passes.
 //!
 //! Provides common operations on IR instructions, terminators, and control flow
-//! that are needed by multiple optimization passes.
-//!
-//! Some functions are not yet used by existing passes but are provided for
-//! upcoming optimization passes (const_prop, dead_instrs, local_cse, licm).
+//! that are needed by multiple optimization passes. Public so analyses and
+//! code generators built on the IR can share them.
+
 #![allow(dead_code)]
 
 use crate::ir::{BinOp, BlockId, IrFunction, IrInstr, IrTerminator, IrValue, UnOp, VarId};
@@ -133,12 +132,12 @@ pub fn for_each_use<F: FnMut(VarId)>(instr: &IrInstr, mut f: F) {
 /// Calls `f` with every variable read by a block terminator.
 pub fn for_each_use_terminator<F: FnMut(VarId)>(term: &IrTerminator, mut f: F) {
     match term {
-        IrTerminator::Return { value: Some(v) } => {
-            f(*v);
+        IrTerminator::Return { values } => {
+            for v in values {
+                f(*v);
+            }
         }
-        IrTerminator::Return { value: None }
-        | IrTerminator::Jump { .. }
-        | IrTerminator::Unreachable => {}
+        IrTerminator::Jump { .. } | IrTerminator::Unreachable => {}
         IrTerminator::BranchIf { condition, .. } => {
             f(*condition);
         }
@@ -151,6 +150,9 @@ pub fn for_each_use_terminator<F: FnMut(VarId)>(term: &IrTerminator, mut f: F) {
 // ── Instruction destination ──────────────────────────────────────────────────
 
 /// Returns the variable written by `instr`, or `None` for side-effect-only instructions.
+///
+/// A call with several results writes several variables and also gets `None`:
+/// use [`for_each_def`] to see every variable an instruction writes.
 pub fn instr_dest(instr: &IrInstr) -> Option<VarId> {
     match instr {
         IrInstr::Const { dest, .. }
@@ -163,9 +165,12 @@ pub fn instr_dest(instr: &IrInstr) -> Option<VarId> {
         | IrInstr::MemoryGrow { dest, .. }
         | IrInstr::Select { dest, .. } => Some(*dest),
 
-        IrInstr::Call { dest, .. }
-        | IrInstr::CallImport { dest, .. }
-        | IrInstr::CallIndirect { dest, .. } => *dest,
+        IrInstr::Call { dests, .. }
+        | IrInstr::CallImport { dests, .. }
+        | IrInstr::CallIndirect { dests, .. } => match dests.as_slice() {
+            [dest] => Some(*dest),
+            _ => None,
+        },
 
         IrInstr::Store { .. }
         | IrInstr::GlobalSet { .. }
@@ -194,10 +199,12 @@ pub fn set_instr_dest(instr: &mut IrInstr, new_dest: VarId) {
         | IrInstr::Select { dest, .. } => {
             *dest = new_dest;
         }
-        IrInstr::Call { dest, .. }
-        | IrInstr::CallImport { dest, .. }
-        | IrInstr::CallIndirect { dest, .. } => {
-            *dest = Some(new_dest);
+        IrInstr::Call { dests, .. }
+        | IrInstr::CallImport { dests, .. }
+        | IrInstr::CallIndirect { dests, .. } => {
+            if let [dest] = dests.as_mut_slice() {
+                *dest = new_dest;
+            }
         }
         // No dest — unreachable given precondition, but harmless to ignore.
         IrInstr::Store { .. }
@@ -210,6 +217,28 @@ pub fn set_instr_dest(instr: &mut IrInstr, new_dest: VarId) {
     }
 }
 
+/// Calls `f` with every variable written by `instr`: its [`instr_dest`], or
+/// each result of a call.
+pub fn for_each_def<F: FnMut(VarId)>(instr: &IrInstr, mut f: F) {
+    match instr {
+        IrInstr::Call { dests, .. }
+        | IrInstr::CallImport { dests, .. }
+        | IrInstr::CallIndirect { dests, .. } => dests.iter().copied().for_each(f),
+        _ => {
+            if let Some(dest) = instr_dest(instr) {
+                f(dest);
+            }
+        }
+    }
+}
+
+/// Whether `instr` writes `var` (see [`for_each_def`]).
+pub fn defines(instr: &IrInstr, var: VarId) -> bool {
+    let mut found = false;
+    for_each_def(instr, |v| found |= v == var);
+    found
+}
+
 // ── Instruction iteration ───────────────────────────────────────────────────
 
 /// Call `f` for each instruction across all blocks in the function.
@@ -342,12 +371,10 @@ pub fn replace_uses_of_terminator(term: &mut IrTerminator, old: VarId, new: VarI
         }
     };
     match term {
-        IrTerminator::Return { value: Some(v) } => {
-            sub(v);
+        IrTerminator::Return { values } => {
+            values.iter_mut().for_each(sub);
         }
-        IrTerminator::Return { value: None }
-        | IrTerminator::Jump { .. }
-        | IrTerminator::Unreachable => {}
+        IrTerminator::Jump { .. } | IrTerminator::Unreachable => {}
         IrTerminator::BranchIf { condition, .. } => {
             sub(condition);
         }
@@ -369,9 +396,9 @@ pub fn build_global_def_count(func: &IrFunction) -> HashMap<VarId, usize> {
     }
     // Each instruction that produces a value is a definition.
     for_each_instr(func, |instr| {
-        if let Some(dest) = instr_dest(instr) {
+        for_each_def(instr, |dest| {
             *counts.entry(dest).or_insert(0) += 1;
-        }
+        });
     });
     counts
 }
@@ -405,9 +432,9 @@ pub fn prune_dead_locals(func: &mut IrFunction) {
         for_each_use(instr, |v| {
             live.insert(v);
         });
-        if let Some(dest) = instr_dest(instr) {
+        for_each_def(instr, |dest| {
             live.insert(dest);
-        }
+        });
     });
 
     for block in &func.blocks {
@@ -812,11 +839,11 @@ pub fn build_global_const_map(func: &IrFunction) -> HashMap<VarId, IrValue> {
     let mut const_defs: HashMap<VarId, IrValue> = HashMap::new();
 
     for_each_instr(func, |instr| {
-        if let Some(dest) = instr_dest(instr) {
+        for_each_def(instr, |dest| {
             *total_defs.entry(dest).or_insert(0) += 1;
-            if let IrInstr::Const { dest, value } = instr {
-                const_defs.insert(*dest, *value);
-            }
+        });
+        if let IrInstr::Const { dest, value } = instr {
+            const_defs.insert(*dest, *value);
         }
     });
 
@@ -976,7 +1003,7 @@ mod tests {
     #[test]
     fn terminator_successors_coverage() {
         assert_eq!(
-            terminator_successors(&IrTerminator::Return { value: None }),
+            terminator_successors(&IrTerminator::Return { values: Vec::new() }),
             vec![]
         );
         assert_eq!(
@@ -1007,11 +1034,11 @@ mod tests {
                 IrBlock {
                     id: BlockId(1),
                     instructions: vec![],
-                    terminator: IrTerminator::Return { value: None },
+                    terminator: IrTerminator::Return { values: Vec::new() },
                 },
             ],
             entry_block: BlockId(0),
-            return_type: None,
+            results: Vec::new(),
             type_idx: crate::ir::TypeIdx::new(0),
         };
         let preds = build_predecessors(&func);
@@ -1,11 +1,21 @@
 //! WebAssembly module parser.
 //!
 //! This module wraps the `wasmparser` crate to extract structured information
-//! from `.wasm` binary files.
+//! from `.wasm` binary files. Modules are validated while they are parsed;
+//! invalid ones are rejected before any IR is built.
 
 use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::ops::Range;
 use wasmparser::{ExternalKind, FuncType, Parser, Payload, TypeRef, ValType};
 
+mod support;
+mod validate;
+pub use support::{unsupported_constructs, Unsupported, UnsupportedKind};
+pub use validate::supported_features;
+use validate::ModuleValidator;
+pub use wasmparser::WasmFeatures;
+
 /// Memory information from the Wasm module.
 #[derive(Debug, Clone)]
 pub struct MemoryInfo {
@@ -23,18 +33,22 @@ pub struct GlobalInfo {
     pub val_type: ValType,
     /// Whether the global is mutable.
     pub mutable: bool,
-    /// The constant initializer value.
+    /// The initializer value.
     pub init_value: InitValue,
 }
 
 /// Parsed constant initializer expression.
-/// Wasm MVP globals are initialized with a single constant instruction.
+/// Wasm MVP globals are initialized with a single constant instruction, or
+/// with `global.get` of an imported global (e.g. dylink's `__memory_base`).
 #[derive(Debug, Clone, Copy)]
 pub enum InitValue {
     I32(i32),
     I64(i64),
     F32(f32),
     F64(f64),
+    /// Value of the global at this index (in the combined import + local
+    /// index space).
+    GlobalGet(u32),
 }
 
 /// Table declaration from the Wasm module.
@@ -51,6 +65,8 @@ pub struct TableInfo {
 pub struct ElementSegment {
     /// Starting offset in the table (from the i32.const in the offset expression).
     pub offset: u32,
+    /// Global the offset is relative to (dylink's `__table_base`), if any.
+    pub base: Option<u32>,
     /// Function indices to place into the table starting at `offset`.
     pub func_indices: Vec<u32>,
 }
@@ -60,8 +76,13 @@ pub struct ElementSegment {
 pub struct DataSegment {
     /// Byte offset into memory 0 (from the i32.const in the offset expression).
     pub offset: u32,
+    /// Global the offset is relative to (dylink's `__memory_base`), if any.
+    pub base: Option<u32>,
     /// Raw data bytes to copy into memory at initialization.
     pub data: Vec<u8>,
+    /// Global Wasm data segment index (position in the data section), which
+    /// keys [`ParsedModule::data_names`].
+    pub wasm_index: u32,
 }
 
 /// A passive data segment (bulk-memory proposal).
@@ -173,6 +194,88 @@ pub struct ParsedModule {
 
     /// Wasm binary version from the module header.
     pub wasm_version: u16,
+
+    /// Function names from the `name` custom section, keyed by index in the
+    /// function index space (imports included). Empty if the section is absent.
+    pub func_names: BTreeMap<u32, String>,
+
+    /// Global names from the `name` custom section, keyed by index in the
+    /// global index space (imports included). Empty if the section is absent.
+    pub global_names: BTreeMap<u32, String>,
+
+    /// Local names from the `name` custom section: function index (imports
+    /// included) to local index (parameters first) to name. Empty if the
+    /// section or subsection is absent.
+    pub local_names: BTreeMap<u32, BTreeMap<u32, String>>,
+
+    /// Data segment names from the `name` custom section (`.rodata`,
+    /// `.data`, ... from wasm-ld), keyed by position in the data section.
+    /// Empty if the section or subsection is absent.
+    pub data_names: BTreeMap<u32, String>,
+
+    /// `dylink.0` metadata, if this is a dynamic-linking side module.
+    pub dylink: Option<DylinkInfo>,
+
+    /// Text of the [`CONTRACTS_SECTION`] custom section, if present.
+    pub contracts: Option<String>,
+
+    /// Toolchain metadata from the `producers` custom section. Empty if the
+    /// section is absent or malformed.
+    pub producers: Producers,
+
+    /// Start function, by index in the function index space (imports
+    /// included), if the module has a start section.
+    pub start: Option<u32>,
+}
+
+/// Custom section in which a guest declares contracts on its exports, in
+/// the format of a contracts file (see `herkos_core::contracts`).
+pub const CONTRACTS_SECTION: &str = "herkos.contracts";
+
+/// Dynamic-linking metadata from the `dylink.0` custom section.
+///
+/// Present on position-independent side modules (Emscripten `SIDE_MODULE`,
+/// `wasm-ld --experimental-pic -shared`). The loader reserves `memory_size`
+/// bytes and `table_size` slots for the module and passes their starts in the
+/// imported `env.__memory_base` and `env.__table_base` globals.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DylinkInfo {
+    /// Bytes of memory the module's data needs.
+    pub memory_size: u32,
+    /// Required alignment of the memory area, as a power of two.
+    pub memory_alignment: u32,
+    /// Table slots the module's element segments need.
+    pub table_size: u32,
+    /// Required alignment of the table area, as a power of two.
+    pub table_alignment: u32,
+    /// Shared libraries this module depends on.
+    pub needed: Vec<String>,
+}
+
+/// Toolchain metadata from the `producers` custom section.
+///
+/// Each entry is a `(name, version)` pair as the tool wrote it, e.g.
+/// `("rustc", "1.79.0")`. Several sections, e.g. one per linked object,
+/// add up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Producers {
+    /// Source languages (`Rust`, `C11`, `Go`).
+    pub language: Vec<(String, String)>,
+    /// Tools that processed the module (`clang`, `wasm-bindgen`, `TinyGo`).
+    pub processed_by: Vec<(String, String)>,
+    /// SDKs the module was built with (`Emscripten`).
+    pub sdk: Vec<(String, String)>,
+}
+
+impl Producers {
+    /// Names in all fields, languages first.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.language
+            .iter()
+            .chain(&self.processed_by)
+            .chain(&self.sdk)
+            .map(|(name, _)| name.as_str())
+    }
 }
 
 /// A single function in the module.
@@ -186,10 +289,14 @@ pub struct ParsedFunction {
 
     /// Function body (Wasm bytecode)
     pub body: Vec<u8>,
+
+    /// Byte range of the body (locals + code) in the original Wasm binary.
+    pub code_range: Range<usize>,
 }
 
 /// Evaluate a wasmparser ConstExpr into our InitValue.
-/// Wasm MVP globals use a single i32.const/i64.const/f32.const/f64.const instruction.
+/// Wasm MVP globals use a single i32.const/i64.const/f32.const/f64.const or
+/// global.get instruction.
 fn eval_const_expr(const_expr: wasmparser::ConstExpr) -> Result<InitValue> {
     let mut reader = const_expr.get_operators_reader();
     let op = reader.read().context("reading const expr operator")?;
@@ -202,10 +309,33 @@ fn eval_const_expr(const_expr: wasmparser::ConstExpr) -> Result<InitValue> {
         wasmparser::Operator::F64Const { value } => {
             Ok(InitValue::F64(f64::from_bits(value.bits())))
         }
+        wasmparser::Operator::GlobalGet { global_index } => Ok(InitValue::GlobalGet(global_index)),
         _ => anyhow::bail!("Unsupported const expression operator: {:?}", op),
     }
 }
 
+/// Evaluate an active segment offset: `i32.const c`, `global.get g`, or
+/// (extended-const) `global.get g; i32.const c; i32.add`, as produced for
+/// position-independent side modules.
+///
+/// Returns the constant part and the global it is relative to.
+fn eval_offset_expr(offset_expr: wasmparser::ConstExpr) -> Result<(u32, Option<u32>)> {
+    use wasmparser::Operator;
+    let mut reader = offset_expr.get_operators_reader();
+    let mut ops = Vec::new();
+    while !reader.eof() {
+        ops.push(reader.read().context("reading offset expr operator")?);
+    }
+    match ops.as_slice() {
+        [Operator::I32Const { value }, Operator::End] => Ok((*value as u32, None)),
+        [Operator::GlobalGet { global_index }, Operator::End] => Ok((0, Some(*global_index))),
+        [Operator::GlobalGet { global_index }, Operator::I32Const { value }, Operator::I32Add, Operator::End] => {
+            Ok((*value as u32, Some(*global_index)))
+        }
+        _ => anyhow::bail!("unsupported segment offset expression: {:?}", ops),
+    }
+}
+
 /// Parse an active element segment, or return None for passive/declared segments.
 fn parse_element_segment(element: wasmparser::Element) -> Result<Option<ElementSegment>> {
     match element.kind {
@@ -222,10 +352,7 @@ fn parse_element_segment(element: wasmparser::Element) -> Result<Option<ElementS
                 );
             }
 
-            let offset = match eval_const_expr(offset_expr)? {
-                InitValue::I32(v) => v as u32,
-                _ => anyhow::bail!("Element segment offset must be i32"),
-            };
+            let (offset, base) = eval_offset_expr(offset_expr).context("element segment offset")?;
 
             // Collect function indices from element items
             let mut func_indices = Vec::new();
@@ -243,6 +370,7 @@ fn parse_element_segment(element: wasmparser::Element) -> Result<Option<ElementS
 
             Ok(Some(ElementSegment {
                 offset,
+                base,
                 func_indices,
             }))
         }
@@ -258,6 +386,8 @@ fn parse_element_segment(element: wasmparser::Element) -> Result<Option<ElementS
 
 /// Parse a function code section entry, extracting locals and bytecode.
 fn parse_code_entry(body: wasmparser::FunctionBody, type_idx: u32) -> Result<ParsedFunction> {
+    let code_range = body.range();
+
     // Extract locals
     let mut locals = Vec::new();
     let locals_reader = body.get_locals_reader().context("getting locals reader")?;
@@ -282,11 +412,22 @@ fn parse_code_entry(body: wasmparser::FunctionBody, type_idx: u32) -> Result<Par
         type_idx,
         locals,
         body: body_bytes.to_vec(),
+        code_range,
     })
 }
 
 /// Parse a WebAssembly binary into a structured module.
+///
+/// Accepts every proposal the builder can translate, threads included; use
+/// [`parse_wasm_with_features`] to match the build options.
 pub fn parse_wasm(wasm_bytes: &[u8]) -> Result<ParsedModule> {
+    parse_wasm_with_features(wasm_bytes, supported_features(true))
+}
+
+/// Parse a WebAssembly binary, validating it against `features`.
+///
+/// See [`supported_features`] for the set matching the IR builder.
+pub fn parse_wasm_with_features(wasm_bytes: &[u8], features: WasmFeatures) -> Result<ParsedModule> {
     let parser = Parser::new(0);
 
     let mut types = Vec::new();
@@ -303,9 +444,19 @@ pub fn parse_wasm(wasm_bytes: &[u8]) -> Result<ParsedModule> {
     let mut num_imported_functions: u32 = 0;
     let mut num_imported_globals: u32 = 0;
     let mut wasm_version: u16 = 1;
+    let mut func_names = BTreeMap::new();
+    let mut global_names = BTreeMap::new();
+    let mut local_names = BTreeMap::new();
+    let mut data_names = BTreeMap::new();
+    let mut dylink = None;
+    let mut contracts: Option<String> = None;
+    let mut producers = Producers::default();
+    let mut start: Option<u32> = None;
+    let mut validator = ModuleValidator::new(features);
 
     for payload in parser.parse_all(wasm_bytes) {
         let payload = payload.context("parsing wasm payload")?;
+        validator.payload(&payload, num_imported_functions)?;
 
         match payload {
             Payload::Version { num, .. } => {
@@ -440,6 +591,10 @@ pub fn parse_wasm(wasm_bytes: &[u8]) -> Result<ParsedModule> {
                 }
             }
 
+            Payload::StartSection { func, .. } => {
+                start = Some(func);
+            }
+
             Payload::DataSection(reader) => {
                 for (segment_index, data) in (0_u32..).zip(reader) {
                     let data = data.context("reading data segment")?;
@@ -448,13 +603,13 @@ pub fn parse_wasm(wasm_bytes: &[u8]) -> Result<ParsedModule> {
                             memory_index: 0,
                             offset_expr,
                         } => {
-                            let offset = match eval_const_expr(offset_expr)? {
-                                InitValue::I32(v) => v as u32,
-                                _ => anyhow::bail!("Data segment offset must be i32"),
-                            };
+                            let (offset, base) =
+                                eval_offset_expr(offset_expr).context("data segment offset")?;
                             data_segments.push(DataSegment {
                                 offset,
+                                base,
                                 data: data.data.to_vec(),
+                                wasm_index: segment_index,
                             });
                         }
                         wasmparser::DataKind::Passive => {
@@ -473,6 +628,39 @@ pub fn parse_wasm(wasm_bytes: &[u8]) -> Result<ParsedModule> {
                 }
             }
 
+            Payload::CustomSection(reader) => match reader.as_known() {
+                wasmparser::KnownCustom::Name(names) => {
+                    parse_names(
+                        names,
+                        &mut func_names,
+                        &mut global_names,
+                        &mut local_names,
+                        &mut data_names,
+                    )?;
+                }
+                wasmparser::KnownCustom::Dylink0(subsections) => {
+                    dylink = Some(parse_dylink(subsections)?);
+                }
+                wasmparser::KnownCustom::Producers(fields) => {
+                    // Informational only: a malformed section is ignored
+                    let mut section = Producers::default();
+                    if parse_producers(fields, &mut section).is_ok() {
+                        producers.language.extend(section.language);
+                        producers.processed_by.extend(section.processed_by);
+                        producers.sdk.extend(section.sdk);
+                    }
+                }
+                _ if reader.name() == CONTRACTS_SECTION => {
+                    let text = std::str::from_utf8(reader.data())
+                        .with_context(|| format!("`{CONTRACTS_SECTION}` section is not UTF-8"))?;
+                    // Several sections, e.g. one per linked object, add up
+                    let all = contracts.get_or_insert_with(String::new);
+                    all.push_str(text);
+                    all.push('\n');
+                }
+                _ => {}
+            },
+
             _ => {}
         }
     }
@@ -491,9 +679,108 @@ pub fn parse_wasm(wasm_bytes: &[u8]) -> Result<ParsedModule> {
         num_imported_functions,
         num_imported_globals,
         wasm_version,
+        func_names,
+        global_names,
+        local_names,
+        data_names,
+        dylink,
+        contracts,
+        producers,
+        start,
     })
 }
 
+/// Collect the function-, global-, local- and data-name subsections of a
+/// `name` custom section.
+///
+/// Other subsections (labels, types, ...) are skipped.
+fn parse_names(
+    reader: wasmparser::NameSectionReader,
+    func_names: &mut BTreeMap<u32, String>,
+    global_names: &mut BTreeMap<u32, String>,
+    local_names: &mut BTreeMap<u32, BTreeMap<u32, String>>,
+    data_names: &mut BTreeMap<u32, String>,
+) -> Result<()> {
+    for subsection in reader {
+        let subsection = subsection.context("reading name subsection")?;
+        match subsection {
+            wasmparser::Name::Function(map) => {
+                for naming in map {
+                    let naming = naming.context("reading function name")?;
+                    func_names.insert(naming.index, naming.name.to_string());
+                }
+            }
+            wasmparser::Name::Global(map) => {
+                for naming in map {
+                    let naming = naming.context("reading global name")?;
+                    global_names.insert(naming.index, naming.name.to_string());
+                }
+            }
+            wasmparser::Name::Local(map) => {
+                for indirect in map {
+                    let indirect = indirect.context("reading local names")?;
+                    let locals: &mut BTreeMap<u32, String> =
+                        local_names.entry(indirect.index).or_default();
+                    for naming in indirect.names {
+                        let naming = naming.context("reading local name")?;
+                        locals.insert(naming.index, naming.name.to_string());
+                    }
+                }
+            }
+            wasmparser::Name::Data(map) => {
+                for naming in map {
+                    let naming = naming.context("reading data segment name")?;
+                    data_names.insert(naming.index, naming.name.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Collect the `dylink.0` subsections herkos uses (memory info and needed
+/// libraries); export/import symbol flags are skipped.
+fn parse_dylink(reader: wasmparser::Dylink0SectionReader) -> Result<DylinkInfo> {
+    let mut info = DylinkInfo::default();
+    for subsection in reader {
+        match subsection.context("reading dylink.0 subsection")? {
+            wasmparser::Dylink0Subsection::MemInfo(mem) => {
+                info.memory_size = mem.memory_size;
+                info.memory_alignment = mem.memory_alignment;
+                info.table_size = mem.table_size;
+                info.table_alignment = mem.table_alignment;
+            }
+            wasmparser::Dylink0Subsection::Needed(needed) => {
+                info.needed.extend(needed.iter().map(|s| s.to_string()));
+            }
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+/// Collect the fields of a `producers` custom section.
+fn parse_producers(
+    reader: wasmparser::ProducersSectionReader,
+    producers: &mut Producers,
+) -> wasmparser::Result<()> {
+    for field in reader {
+        let field = field?;
+        let entries = match field.name {
+            "language" => &mut producers.language,
+            "processed-by" => &mut producers.processed_by,
+            // `sdk`, the only other name wasmparser accepts
+            _ => &mut producers.sdk,
+        };
+        for value in field.values {
+            let value = value?;
+            entries.push((value.name.to_string(), value.version.to_string()));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -528,6 +815,22 @@ mod tests {
         assert!(module.memory.is_none());
     }
 
+    #[test]
+    fn parse_start_section() {
+        let wat = r#"
+            (module
+                (import "env" "f" (func))
+                (func $init)
+                (start $init)
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        assert_eq!(parse_wasm(&wasm).unwrap().start, Some(1));
+
+        let wasm = wat::parse_str("(module)").unwrap();
+        assert_eq!(parse_wasm(&wasm).unwrap().start, None);
+    }
+
     #[test]
     fn parse_memory_section() {
         let wat = r#"
@@ -778,4 +1081,174 @@ mod tests {
         assert_eq!(module.functions.len(), 2);
         assert_eq!(module.globals.len(), 1); // Only local globals, not imports
     }
+
+    #[test]
+    fn parse_function_names_and_code_ranges() {
+        let wat = r#"
+            (module
+                (import "env" "log" (func $log (param i32)))
+                (func $first (result i32) i32.const 1)
+                (func (result i32) i32.const 2)
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+
+        // Names are keyed by the full function index space (import first).
+        assert_eq!(module.func_names.get(&0).map(String::as_str), Some("log"));
+        assert_eq!(module.func_names.get(&1).map(String::as_str), Some("first"));
+        assert!(!module.func_names.contains_key(&2));
+        assert!(module.global_names.is_empty());
+
+        // Code ranges point into the original binary and do not overlap.
+        let r0 = module.functions[0].code_range.clone();
+        let r1 = module.functions[1].code_range.clone();
+        assert!(r0.start < r0.end && r0.end <= r1.start && r1.end <= wasm.len());
+    }
+
+    #[test]
+    fn parse_global_names() {
+        let wat = r#"
+            (module
+                (import "env" "base" (global $base i32))
+                (global $__stack_pointer (mut i32) (i32.const 1024))
+                (global (mut i32) (i32.const 0))
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+
+        // Names are keyed by the full global index space (import first).
+        assert_eq!(
+            module.global_names.get(&0).map(String::as_str),
+            Some("base")
+        );
+        assert_eq!(
+            module.global_names.get(&1).map(String::as_str),
+            Some("__stack_pointer")
+        );
+        assert!(!module.global_names.contains_key(&2));
+    }
+
+    #[test]
+    fn parse_local_names() {
+        let wat = r#"
+            (module
+                (import "env" "log" (func (param i32)))
+                (func (param $count i32) (param i32) (local $sum i64))
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+
+        // Keyed by function index (import first), then local index
+        let locals = &module.local_names[&1];
+        assert_eq!(locals.get(&0).map(String::as_str), Some("count"));
+        assert!(!locals.contains_key(&1));
+        assert_eq!(locals.get(&2).map(String::as_str), Some("sum"));
+    }
+
+    #[test]
+    fn parse_data_names() {
+        let wat = r#"
+            (module
+                (memory 1)
+                (data $.rodata (i32.const 16) "const")
+                (data "passive")
+                (data $.data (i32.const 32) "mut")
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+
+        // Keyed by position in the data section, passive segments included
+        let names: Vec<_> = module
+            .data_segments
+            .iter()
+            .map(|s| module.data_names.get(&s.wasm_index).map(String::as_str))
+            .collect();
+        assert_eq!(names, [Some(".rodata"), Some(".data")]);
+        assert_eq!(module.data_segments[1].wasm_index, 2);
+    }
+
+    #[test]
+    fn parse_global_get_initializer() {
+        let wat = r#"
+            (module
+                (import "env" "__memory_base" (global i32))
+                (global i32 (global.get 0))
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+        assert!(matches!(
+            module.globals[0].init_value,
+            InitValue::GlobalGet(0)
+        ));
+    }
+
+    #[test]
+    fn parse_producers_section() {
+        let wat = r#"
+            (module
+                (@producers
+                    (language "Rust" "")
+                    (processed-by "rustc" "1.79.0")
+                    (processed-by "wasm-bindgen" "0.2.92")
+                    (sdk "Emscripten" "3.1.61")))
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+        let pair = |name: &str, version: &str| (name.to_string(), version.to_string());
+        assert_eq!(
+            module.producers,
+            Producers {
+                language: vec![pair("Rust", "")],
+                processed_by: vec![pair("rustc", "1.79.0"), pair("wasm-bindgen", "0.2.92")],
+                sdk: vec![pair("Emscripten", "3.1.61")],
+            }
+        );
+        assert_eq!(
+            module.producers.names().collect::<Vec<_>>(),
+            ["Rust", "rustc", "wasm-bindgen", "Emscripten"]
+        );
+    }
+
+    #[test]
+    fn parse_dylink_section_and_relative_offsets() {
+        let wat = r#"
+            (module
+                (@dylink.0
+                    (mem-info (memory 16 2) (table 2 0))
+                    (needed "libmain.so"))
+                (import "env" "memory" (memory 1))
+                (import "env" "__indirect_function_table" (table 2 funcref))
+                (import "env" "__memory_base" (global i32))
+                (import "env" "__table_base" (global i32))
+                (func $f)
+                (elem (global.get 1) $f $f)
+                (data (global.get 0) "abc")
+                (data (i32.add (global.get 0) (i32.const 8)) "xy")
+            )
+        "#;
+        let wasm = wat::parse_str(wat).unwrap();
+        let module = parse_wasm(&wasm).unwrap();
+        assert_eq!(
+            module.dylink,
+            Some(DylinkInfo {
+                memory_size: 16,
+                memory_alignment: 2,
+                table_size: 2,
+                table_alignment: 0,
+                needed: vec!["libmain.so".to_string()],
+            })
+        );
+        assert_eq!(module.element_segments[0].base, Some(1));
+        let offsets: Vec<_> = module
+            .data_segments
+            .iter()
+            .map(|d| (d.base, d.offset))
+            .collect();
+        assert_eq!(offsets, vec![(Some(0), 0), (Some(0), 8)]);
+    }
 }
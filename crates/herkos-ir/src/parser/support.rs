@@ -0,0 +1,362 @@
+//! Every construct that keeps a module from being transpiled, counted.
+//!
+//! [`parse_wasm`](super::parse_wasm) and the IR builder stop at the first
+//! construct herkos has no translation for. [`unsupported_constructs`]
+//! scans the whole module instead and reports each such construct with how
+//! often the module uses it, most frequent first, so a feature request
+//! shows its real-world impact at once:
+//!
+//! ```text
+//! operator I32TruncSatF64S (saturating_float_to_int): 412 in 37 functions
+//! operator MemoryDiscard (memory_control): 2 in 1 functions
+//! section tag (exceptions): 1
+//! ```
+
+use super::supported_features;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use wasmparser::{CompositeInnerType, Encoding, MemoryType, Operator, Parser, Payload, TypeRef};
+
+/// What an [`Unsupported`] entry counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnsupportedKind {
+    /// An instruction in a function body.
+    Operator,
+    /// A module-level construct: a section, or entries of one.
+    Section,
+}
+
+impl fmt::Display for UnsupportedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnsupportedKind::Operator => "operator",
+            UnsupportedKind::Section => "section",
+        })
+    }
+}
+
+/// A construct herkos cannot transpile, and how often a module uses it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unsupported {
+    /// Operator or section.
+    pub kind: UnsupportedKind,
+    /// `Operator` variant name (`I32TruncSatF32S`), or the construct
+    /// (`tag`, `shared memory`, ...).
+    pub name: &'static str,
+    /// The Wasm proposal it belongs to, in `wasmparser`'s naming
+    /// (`saturating_float_to_int`, `multi_memory`, ...).
+    pub proposal: &'static str,
+    /// Occurrences in the module.
+    pub count: usize,
+    /// Functions with at least one occurrence (0 for sections).
+    pub functions: usize,
+}
+
+/// Formats as one line: `operator NAME (PROPOSAL): COUNT in N functions`.
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}): {}",
+            self.kind, self.name, self.proposal, self.count
+        )?;
+        if self.kind == UnsupportedKind::Operator {
+            write!(f, " in {} functions", self.functions)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scan `wasm_bytes` for every construct herkos cannot transpile.
+///
+/// `lower_atomics` is the transpile option of the same name: with it,
+/// atomic operators and shared memory are supported. The result is sorted
+/// by descending count, then kind and name, and is empty for a module
+/// herkos can transpile (barring invalid modules, which this does not
+/// validate). Operators in dead code, which the builder skips, are not
+/// counted.
+pub fn unsupported_constructs(wasm_bytes: &[u8], lower_atomics: bool) -> Result<Vec<Unsupported>> {
+    let mut tally = Tally::default();
+    let mut memories = 0;
+    let threads = supported_features(lower_atomics).threads();
+    let mut memory = |tally: &mut Tally, ty: &MemoryType| {
+        memories += 1;
+        if ty.memory64 {
+            tally.section("64-bit memory", "memory64", 1);
+        }
+        if ty.shared && !threads {
+            tally.section("shared memory", "threads", 1);
+        }
+    };
+
+    for payload in Parser::new(0).parse_all(wasm_bytes) {
+        match payload.context("parsing wasm payload")? {
+            Payload::Version {
+                encoding: Encoding::Component,
+                ..
+            } => {
+                // Nothing inside a component is read
+                tally.section("component", "component_model", 1);
+                break;
+            }
+            Payload::TypeSection(reader) => {
+                for rec_group in reader {
+                    let rec_group = rec_group.context("reading rec group")?;
+                    for sub_type in rec_group.types() {
+                        if !matches!(sub_type.composite_type.inner, CompositeInnerType::Func(_)) {
+                            tally.section("struct or array type", "gc", 1);
+                        }
+                    }
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    match import.context("reading import")?.ty {
+                        TypeRef::Memory(ty) => memory(&mut tally, &ty),
+                        TypeRef::Table(ty) if ty.table64 => {
+                            tally.section("64-bit table", "memory64", 1)
+                        }
+                        TypeRef::Tag(_) => tally.section("tag", "exceptions", 1),
+                        _ => {}
+                    }
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for ty in reader {
+                    memory(&mut tally, &ty.context("reading memory type")?);
+                }
+            }
+            Payload::TableSection(reader) => {
+                for table in reader {
+                    if table.context("reading table type")?.ty.table64 {
+                        tally.section("64-bit table", "memory64", 1);
+                    }
+                }
+            }
+            Payload::TagSection(reader) => {
+                tally.section("tag", "exceptions", reader.count() as usize);
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut reader = body
+                    .get_operators_reader()
+                    .context("reading function body")?;
+                let mut used = BTreeSet::new();
+                // Nesting depth inside dead code, if in dead code
+                let mut dead: Option<u32> = None;
+                while !reader.eof() {
+                    let op = reader.read().context("reading operator")?;
+                    if let Some(depth) = dead.as_mut() {
+                        match op {
+                            Operator::Block { .. }
+                            | Operator::Loop { .. }
+                            | Operator::If { .. } => *depth += 1,
+                            Operator::Else | Operator::End if *depth > 0 => {
+                                if matches!(op, Operator::End) {
+                                    *depth -= 1;
+                                }
+                            }
+                            Operator::Else | Operator::End => dead = None,
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    if let Some((name, proposal)) = blocker(&op, lower_atomics) {
+                        tally.operator(name, proposal);
+                        used.insert(name);
+                    }
+                    if matches!(
+                        op,
+                        Operator::Br { .. }
+                            | Operator::BrTable { .. }
+                            | Operator::Return
+                            | Operator::Unreachable
+                    ) {
+                        dead = Some(0);
+                    }
+                }
+                for name in used {
+                    if let Some(entry) = tally.0.get_mut(&(UnsupportedKind::Operator, name)) {
+                        entry.2 += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if memories > 1 {
+        tally.section("multiple memories", "multi_memory", memories);
+    }
+
+    let mut found: Vec<Unsupported> = tally
+        .0
+        .into_iter()
+        .map(|((kind, name), (proposal, count, functions))| Unsupported {
+            kind,
+            name,
+            proposal,
+            count,
+            functions,
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(a.kind.cmp(&b.kind))
+            .then(a.name.cmp(b.name))
+    });
+    Ok(found)
+}
+
+/// Proposal, occurrences and functions of each construct found.
+#[derive(Default)]
+struct Tally(BTreeMap<(UnsupportedKind, &'static str), (&'static str, usize, usize)>);
+
+impl Tally {
+    fn operator(&mut self, name: &'static str, proposal: &'static str) {
+        let entry = self
+            .0
+            .entry((UnsupportedKind::Operator, name))
+            .or_insert((proposal, 0, 0));
+        entry.1 += 1;
+    }
+
+    fn section(&mut self, name: &'static str, proposal: &'static str, count: usize) {
+        if count > 0 {
+            let entry = self
+                .0
+                .entry((UnsupportedKind::Section, name))
+                .or_insert((proposal, 0, 0));
+            entry.1 += count;
+        }
+    }
+}
+
+/// Name and proposal of `op` if the IR builder has no translation for it.
+///
+/// Mirrors `translate_operator`: the MVP and sign-extension operators, the
+/// bulk-memory ones on memory 0 and `data.drop`, numeric typed `select`,
+/// and, when lowered, the atomics.
+fn blocker(op: &Operator, lower_atomics: bool) -> Option<(&'static str, &'static str)> {
+    let (name, proposal) = describe(op);
+    let translated = match op {
+        Operator::MemorySize { mem, .. }
+        | Operator::MemoryGrow { mem, .. }
+        | Operator::MemoryFill { mem }
+        | Operator::MemoryInit { mem, .. } => {
+            if *mem != 0 {
+                return Some((name, "multi_memory"));
+            }
+            true
+        }
+        Operator::MemoryCopy { dst_mem, src_mem } => {
+            if *dst_mem != 0 || *src_mem != 0 {
+                return Some((name, "multi_memory"));
+            }
+            true
+        }
+        Operator::CallIndirect { table_index, .. } => {
+            if *table_index != 0 {
+                return Some((name, "reference_types"));
+            }
+            true
+        }
+        Operator::TypedSelect { ty } => matches!(
+            ty,
+            wasmparser::ValType::I32
+                | wasmparser::ValType::I64
+                | wasmparser::ValType::F32
+                | wasmparser::ValType::F64
+        ),
+        Operator::DataDrop { .. } => true,
+        _ => match proposal {
+            "mvp" | "sign_extension" => true,
+            "threads" => lower_atomics,
+            _ => false,
+        },
+    };
+    (!translated).then_some((name, proposal))
+}
+
+/// `Operator` variant name and proposal of `op`.
+fn describe(op: &Operator) -> (&'static str, &'static str) {
+    macro_rules! describe {
+        ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*))*) => {
+            match op {
+                $( Operator::$op { .. } => (stringify!($op), stringify!($proposal)), )*
+                _ => ("unknown", "unknown"),
+            }
+        };
+    }
+    wasmparser::for_each_operator!(describe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(wat: &str, lower_atomics: bool) -> Vec<String> {
+        let wasm = wat::parse_str(wat).unwrap();
+        unsupported_constructs(&wasm, lower_atomics)
+            .unwrap()
+            .iter()
+            .map(|u| u.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn counts_every_unsupported_operator_sorted_by_count() {
+        let wat = r#"(module
+            (table 1 funcref)
+            (func (param f64) (result i32)
+                (i32.add
+                    (i32.trunc_sat_f64_s (local.get 0))
+                    (i32.trunc_sat_f64_u (local.get 0))))
+            (func (param f64) (result i32)
+                (drop (table.size))
+                (i32.trunc_sat_f64_s (local.get 0)))
+            (func (result i32)
+                (return (i32.const 0))
+                (table.size)))"#;
+        assert_eq!(
+            report(wat, false),
+            [
+                "operator I32TruncSatF64S (saturating_float_to_int): 2 in 2 functions",
+                "operator I32TruncSatF64U (saturating_float_to_int): 1 in 1 functions",
+                // The second `table.size` is dead code
+                "operator TableSize (reference_types): 1 in 1 functions",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_sections_and_atomics_unless_lowered() {
+        let wat = r#"(module
+            (memory 1 1 shared)
+            (tag $e)
+            (func (param i32) (result i32)
+                (i32.atomic.load (local.get 0))))"#;
+        assert_eq!(
+            report(wat, false),
+            [
+                "operator I32AtomicLoad (threads): 1 in 1 functions",
+                "section shared memory (threads): 1",
+                "section tag (exceptions): 1",
+            ]
+        );
+        assert_eq!(report(wat, true), ["section tag (exceptions): 1"]);
+    }
+
+    #[test]
+    fn supported_modules_report_nothing() {
+        let wat = r#"(module
+            (memory 1)
+            (table 1 funcref)
+            (func (param i32 i32) (result i32)
+                (memory.fill (i32.const 0) (i32.const 0) (i32.const 4))
+                (select (result i32) (local.get 0) (local.get 1) (i32.const 1))
+                (call_indirect (param i32) (result i32) (local.get 0) (i32.const 0))
+                (i32.extend8_s)))"#;
+        assert!(report(wat, false).is_empty());
+    }
+}
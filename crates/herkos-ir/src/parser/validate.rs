@@ -0,0 +1,176 @@
+//! Module validation.
+//!
+//! The IR builder assumes a valid module: operand and control stacks never
+//! underflow, operand types match and every index is in range. Each payload
+//! is run through `wasmparser`'s validator as it is parsed, so malformed
+//! modules are rejected up front with the offending function and offset.
+//!
+//! The validator only enables the proposals the builder translates, so a
+//! module using SIMD, tail calls or memory64 fails here with the
+//! validator's message instead of midway through translation.
+
+use anyhow::{anyhow, Error, Result};
+use wasmparser::{
+    BinaryReaderError, FuncValidatorAllocations, Payload, ValidPayload, Validator, WasmFeatures,
+};
+
+/// The Wasm proposals the IR builder translates.
+///
+/// Threads are enabled only when atomics are lowered: without
+/// `lower_atomics` the builder has no translation for them.
+pub fn supported_features(lower_atomics: bool) -> WasmFeatures {
+    let mut features = WasmFeatures::WASM1
+        | WasmFeatures::SIGN_EXTENSION
+        | WasmFeatures::BULK_MEMORY
+        | WasmFeatures::REFERENCE_TYPES
+        | WasmFeatures::MULTI_VALUE
+        | WasmFeatures::EXTENDED_CONST;
+    if lower_atomics {
+        features |= WasmFeatures::THREADS;
+    }
+    features
+}
+
+/// Validates a module one payload at a time.
+pub(super) struct ModuleValidator {
+    validator: Validator,
+    allocations: FuncValidatorAllocations,
+    threads: bool,
+}
+
+impl ModuleValidator {
+    pub(super) fn new(features: WasmFeatures) -> Self {
+        ModuleValidator {
+            validator: Validator::new_with_features(features),
+            allocations: FuncValidatorAllocations::default(),
+            threads: features.threads(),
+        }
+    }
+
+    /// Validate `payload`, including the body of a code section entry.
+    ///
+    /// `num_imported_functions` maps the Wasm function index in errors to
+    /// the `func_N` name of the generated code.
+    pub(super) fn payload(&mut self, payload: &Payload, num_imported_functions: u32) -> Result<()> {
+        let valid = self
+            .validator
+            .payload(payload)
+            .map_err(|err| self.hint(invalid_module(&err), &err))?;
+        if let ValidPayload::Func(func, body) = valid {
+            let index = func.index;
+            let mut validator = func.into_validator(std::mem::take(&mut self.allocations));
+            validator.validate(&body).map_err(|err| {
+                let invalid = anyhow!(
+                    "invalid function {} (func_{}) at offset {:#x}: {}",
+                    index,
+                    index.wrapping_sub(num_imported_functions),
+                    err.offset(),
+                    err.message()
+                );
+                self.hint(invalid, &err)
+            })?;
+            self.allocations = validator.into_allocations();
+        }
+        Ok(())
+    }
+}
+
+impl ModuleValidator {
+    /// Point modules rejected for using threads at atomics lowering.
+    fn hint(&self, invalid: Error, err: &BinaryReaderError) -> Error {
+        if !self.threads && err.message().contains("threads") {
+            invalid.context(
+                "shared-memory threads are not supported; \
+                 transpile with --lower-atomics if the module runs on a single thread",
+            )
+        } else {
+            invalid
+        }
+    }
+}
+
+fn invalid_module(err: &BinaryReaderError) -> Error {
+    anyhow!(
+        "invalid module at offset {:#x}: {}",
+        err.offset(),
+        err.message()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::supported_features;
+    use crate::parser::{parse_wasm, parse_wasm_with_features};
+
+    fn error(wat: &str) -> String {
+        let wasm = wat::parse_str(wat).unwrap();
+        format!("{:#}", parse_wasm(&wasm).unwrap_err())
+    }
+
+    #[test]
+    fn stack_underflow_names_the_function() {
+        let err = error(
+            r#"(module
+                (import "env" "f" (func))
+                (func (result i32) (i32.const 1))
+                (func (result i32) (i32.const 1) (i32.add)))"#,
+        );
+        assert!(err.starts_with("invalid function 2 (func_1) at offset 0x"));
+        assert!(err.contains("type mismatch"));
+    }
+
+    #[test]
+    fn unbalanced_control_and_module_errors_are_rejected() {
+        let err = error("(module (func (block (br 1) (br 2))))");
+        assert!(err.contains("invalid function 0 (func_0)"));
+        assert!(err.contains("unknown label"));
+
+        let err = error("(module (func (call 5)))");
+        assert!(err.contains("unknown function 5"));
+
+        let err = error(r#"(module (export "m" (memory 0)))"#);
+        assert!(err.starts_with("invalid module at offset 0x"));
+    }
+
+    #[test]
+    fn valid_modules_are_accepted() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory 1 1 shared)
+                (func (export "f") (param i32) (result i32)
+                    (i32.atomic.load (local.get 0))))"#,
+        )
+        .unwrap();
+        assert!(parse_wasm(&wasm).is_ok());
+    }
+
+    #[test]
+    fn untranslated_proposals_are_rejected() {
+        let err = error("(module (func (param v128)))");
+        assert!(err.starts_with("invalid module at offset 0x"));
+        assert!(err.contains("SIMD support is not enabled"));
+
+        let err = error("(module (memory i64 1))");
+        assert!(err.contains("memory64"));
+
+        let err = error("(module (func (return_call 0)))");
+        assert!(err.contains("invalid function 0 (func_0)"));
+        assert!(err.contains("tail calls support is not enabled"));
+    }
+
+    #[test]
+    fn threads_need_lowered_atomics() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory 1 1 shared)
+                (func (export "f") (param i32) (result i32)
+                    (i32.atomic.load (local.get 0))))"#,
+        )
+        .unwrap();
+        let err = parse_wasm_with_features(&wasm, supported_features(false)).unwrap_err();
+        let err = format!("{err:#}");
+        assert!(err.contains("--lower-atomics"));
+        assert!(err.contains("threads must be enabled"));
+        assert!(parse_wasm_with_features(&wasm, supported_features(true)).is_ok());
+    }
+}
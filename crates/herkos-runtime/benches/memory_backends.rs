@@ -0,0 +1,145 @@
+//! Load/store/copy throughput of `IsolatedMemory` per backend and access width.
+//!
+//! Backends are where the memory lives: inline in the caller's frame
+//! (`static`, how generated `new()` returns it) or boxed on the heap (how
+//! hosts hold large memories). An mmap-backed memory will be added here as a
+//! third backend once it exists. Each operation is measured for i32 and i64
+//! accesses, with the bounds-checked and the unchecked (verified) accessors.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use herkos_runtime::{IsolatedMemory, PAGE_SIZE};
+use std::hint::black_box;
+
+const PAGES: usize = 4;
+/// Accesses per iteration, spread over the first page.
+const ACCESSES: usize = 4096;
+const COPY_LEN: u32 = 16 * 1024;
+
+/// Offsets visited by every load/store loop: a stride that is not a power of
+/// two, aligned to 8 bytes so i32 and i64 accesses stay in the first page.
+fn offsets() -> Vec<usize> {
+    (0..ACCESSES).map(|i| (i * 24) % (PAGE_SIZE - 8)).collect()
+}
+
+type Group<'a> = criterion::BenchmarkGroup<'a, criterion::measurement::WallTime>;
+
+/// Run `bench` once per backend, each with one active page.
+fn for_each_backend(
+    group: &mut Group<'_>,
+    bench: fn(&mut Group<'_>, &str, &mut IsolatedMemory<PAGES>),
+) {
+    // Held by value in this frame, as generated `new()` returns it.
+    let mut inline = IsolatedMemory::<PAGES>::try_new(1).unwrap();
+    bench(group, "static", &mut inline);
+    let mut boxed = Box::new(IsolatedMemory::<PAGES>::try_new(1).unwrap());
+    bench(group, "boxed", &mut boxed);
+}
+
+fn loads(group: &mut Group<'_>, backend: &str, mem: &mut IsolatedMemory<PAGES>) {
+    let offsets = offsets();
+    group.bench_function(format!("load i32 checked {backend}"), |b| {
+        b.iter(|| {
+            let mut sum = 0i32;
+            for &o in &offsets {
+                sum = sum.wrapping_add(mem.load_i32(black_box(o)).unwrap());
+            }
+            sum
+        })
+    });
+    group.bench_function(format!("load i32 unchecked {backend}"), |b| {
+        b.iter(|| {
+            let mut sum = 0i32;
+            for &o in &offsets {
+                // SAFETY: every offset is below PAGE_SIZE - 8 and one page is active.
+                sum = sum.wrapping_add(unsafe { mem.load_i32_unchecked(black_box(o)) });
+            }
+            sum
+        })
+    });
+    group.bench_function(format!("load i64 checked {backend}"), |b| {
+        b.iter(|| {
+            let mut sum = 0i64;
+            for &o in &offsets {
+                sum = sum.wrapping_add(mem.load_i64(black_box(o)).unwrap());
+            }
+            sum
+        })
+    });
+    group.bench_function(format!("load i64 unchecked {backend}"), |b| {
+        b.iter(|| {
+            let mut sum = 0i64;
+            for &o in &offsets {
+                // SAFETY: every offset is below PAGE_SIZE - 8 and one page is active.
+                sum = sum.wrapping_add(unsafe { mem.load_i64_unchecked(black_box(o)) });
+            }
+            sum
+        })
+    });
+}
+
+fn stores(group: &mut Group<'_>, backend: &str, mem: &mut IsolatedMemory<PAGES>) {
+    let offsets = offsets();
+    group.bench_function(format!("store i32 checked {backend}"), |b| {
+        b.iter(|| {
+            for (i, &o) in offsets.iter().enumerate() {
+                mem.store_i32(black_box(o), i as i32).unwrap();
+            }
+        })
+    });
+    group.bench_function(format!("store i32 unchecked {backend}"), |b| {
+        b.iter(|| {
+            for (i, &o) in offsets.iter().enumerate() {
+                // SAFETY: every offset is below PAGE_SIZE - 8 and one page is active.
+                unsafe { mem.store_i32_unchecked(black_box(o), i as i32) };
+            }
+        })
+    });
+    group.bench_function(format!("store i64 checked {backend}"), |b| {
+        b.iter(|| {
+            for (i, &o) in offsets.iter().enumerate() {
+                mem.store_i64(black_box(o), i as i64).unwrap();
+            }
+        })
+    });
+    group.bench_function(format!("store i64 unchecked {backend}"), |b| {
+        b.iter(|| {
+            for (i, &o) in offsets.iter().enumerate() {
+                // SAFETY: every offset is below PAGE_SIZE - 8 and one page is active.
+                unsafe { mem.store_i64_unchecked(black_box(o), i as i64) };
+            }
+        })
+    });
+}
+
+fn copies(group: &mut Group<'_>, backend: &str, mem: &mut IsolatedMemory<PAGES>) {
+    group.bench_function(format!("copy disjoint {backend}"), |b| {
+        b.iter(|| mem.memory_copy(black_box(COPY_LEN), black_box(0), COPY_LEN))
+    });
+    group.bench_function(format!("copy overlapping {backend}"), |b| {
+        b.iter(|| mem.memory_copy(black_box(8), black_box(0), COPY_LEN))
+    });
+}
+
+fn load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory load");
+    group.throughput(Throughput::Elements(ACCESSES as u64));
+    for_each_backend(&mut group, loads);
+    group.finish();
+}
+
+fn store(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory store");
+    group.throughput(Throughput::Elements(ACCESSES as u64));
+    for_each_backend(&mut group, stores);
+    group.finish();
+}
+
+fn copy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory copy");
+    group.throughput(Throughput::Bytes(COPY_LEN as u64));
+    for_each_backend(&mut group, copies);
+    group.finish();
+}
+
+criterion_group!(benches, load, store, copy);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+//! Version of the interface generated code relies on.
+//!
+//! Transpiled modules name runtime types and call runtime functions
+//! directly, so they only work with a runtime offering what they were
+//! generated against. Each module records that in a
+//! `MODULE_ABI: (u32, u32)` constant and checks it with [`abi_compatible`]
+//! in a const assertion, so a mismatched runtime fails to compile with a
+//! message naming the versions instead of misbehaving.
+//!
+//! The major version changes when something generated code uses changes or
+//! goes away; the minor version when something is added that newer
+//! generated code may use.
+
+/// ABI this runtime provides: `(major, minor)`.
+pub const ABI_VERSION: (u32, u32) = (1, 1);
+
+/// Whether code generated against ABI `required` runs on this runtime:
+/// same major version, and at least the minor version it needs.
+pub const fn abi_compatible(required: (u32, u32)) -> bool {
+    required.0 == ABI_VERSION.0 && required.1 <= ABI_VERSION.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_minors_are_compatible_and_other_majors_are_not() {
+        let (major, minor) = ABI_VERSION;
+        assert!(abi_compatible(ABI_VERSION));
+        assert!(abi_compatible((major, 0)));
+        assert!(!abi_compatible((major, minor + 1)));
+        assert!(!abi_compatible((major + 1, 0)));
+        if major > 0 {
+            assert!(!abi_compatible((major - 1, minor)));
+        }
+    }
+}
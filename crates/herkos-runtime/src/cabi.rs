@@ -0,0 +1,115 @@
+//! Canonical ABI helpers for WIT-generated wrappers.
+//!
+//! When the transpiler is given a `.wit` world, it emits wrappers that lift
+//! WIT values (strings, lists, records, variants) out of the guest's linear
+//! memory and lower them back in, following the component model's canonical
+//! ABI. The validation steps the ABI requires — UTF-8, `char` ranges, pointer
+//! alignment and bounds — live here so every wrapper shares one checked copy.
+//!
+//! All helpers take the active memory as a flat slice
+//! ([`IsolatedMemory::as_slice`](crate::IsolatedMemory::as_slice)) so they are
+//! not monomorphized per `MAX_PAGES`. Helpers returning owned values need the
+//! `alloc` feature.
+
+use crate::{WasmResult, WasmTrap};
+
+#[cfg(feature = "alloc")]
+pub use alloc::{string::String, vec::Vec};
+
+/// Lift a `char` from its core `i32` representation.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::InvalidValue)` for surrogates and values past
+/// `U+10FFFF`.
+#[inline]
+pub fn lift_char(value: i32) -> WasmResult<char> {
+    char::from_u32(value as u32).ok_or(WasmTrap::InvalidValue)
+}
+
+/// Validate a guest `(ptr, len)` pair describing `len` elements of
+/// `elem_size` bytes, and return the pair as host offsets.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::InvalidValue)` if `ptr` is not a multiple of
+/// `align`, and `Err(WasmTrap::OutOfBounds)` if the range does not fit in the
+/// active memory.
+#[inline(never)]
+pub fn guest_range(
+    memory: &[u8],
+    ptr: i32,
+    len: i32,
+    elem_size: usize,
+    align: usize,
+) -> WasmResult<(usize, usize)> {
+    let ptr = ptr as u32 as usize;
+    let len = len as u32 as usize;
+    if align > 1 && !ptr.is_multiple_of(align) {
+        return Err(WasmTrap::InvalidValue);
+    }
+    let end = len
+        .checked_mul(elem_size)
+        .and_then(|bytes| ptr.checked_add(bytes))
+        .ok_or(WasmTrap::OutOfBounds)?;
+    if end > memory.len() {
+        return Err(WasmTrap::OutOfBounds);
+    }
+    Ok((ptr, len))
+}
+
+/// Lift a `string` stored as UTF-8 at guest `(ptr, len)`.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::OutOfBounds)` if the bytes are outside the active
+/// memory and `Err(WasmTrap::InvalidValue)` if they are not valid UTF-8.
+#[cfg(feature = "alloc")]
+#[inline(never)]
+pub fn lift_string(memory: &[u8], ptr: i32, len: i32) -> WasmResult<String> {
+    let (start, len) = guest_range(memory, ptr, len, 1, 1)?;
+    let bytes = &memory[start..start + len];
+    core::str::from_utf8(bytes)
+        .map(String::from)
+        .map_err(|_| WasmTrap::InvalidValue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lift_char_rejects_surrogates_and_out_of_range() {
+        assert_eq!(lift_char('é' as i32), Ok('é'));
+        assert_eq!(lift_char(0xD800), Err(WasmTrap::InvalidValue));
+        assert_eq!(lift_char(0x11_0000), Err(WasmTrap::InvalidValue));
+        assert_eq!(lift_char(-1), Err(WasmTrap::InvalidValue));
+    }
+
+    #[test]
+    fn guest_range_checks_alignment_and_bounds() {
+        let memory = [0u8; 64];
+        assert_eq!(guest_range(&memory, 8, 4, 8, 4), Ok((8, 4)));
+        assert_eq!(guest_range(&memory, 0, 0, 8, 8), Ok((0, 0)));
+        assert_eq!(
+            guest_range(&memory, 2, 1, 4, 4),
+            Err(WasmTrap::InvalidValue)
+        );
+        assert_eq!(
+            guest_range(&memory, 60, 2, 4, 4),
+            Err(WasmTrap::OutOfBounds)
+        );
+        assert_eq!(
+            guest_range(&memory, 0, -1, 8, 1),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn lift_string_validates_utf8() {
+        let mut memory = [0u8; 16];
+        memory[4..9].copy_from_slice(b"hello");
+        assert_eq!(lift_string(&memory, 4, 5).as_deref(), Ok("hello"));
+        memory[4] = 0xFF;
+        assert_eq!(lift_string(&memory, 4, 5), Err(WasmTrap::InvalidValue));
+        assert_eq!(lift_string(&memory, 12, 5), Err(WasmTrap::OutOfBounds));
+    }
+}
@@ -0,0 +1,76 @@
+//! Branchless `select` for constant-time functions.
+//!
+//! Code transpiled with `--constant-time` emits Wasm `select` in the chosen
+//! functions as [`ct_select`] rather than `if`: both operands are combined
+//! through a mask derived from the condition, so no branch depends on it.
+//! The mask passes through [`core::hint::black_box`] so LLVM does not
+//! recognize the pattern and turn it back into a branch. `black_box` is a
+//! best-effort hint, so audit the machine code of anything that must hold
+//! up against timing attacks.
+
+use core::hint::black_box;
+
+/// Values [`ct_select`] can choose between.
+pub trait ConstantTimeSelect: Copy {
+    /// `self` where `mask` is all ones, `other` where it is all zeros.
+    fn select_masked(self, other: Self, mask: u64) -> Self;
+}
+
+impl ConstantTimeSelect for i32 {
+    #[inline(always)]
+    fn select_masked(self, other: Self, mask: u64) -> Self {
+        let mask = mask as u32 as i32;
+        (self & mask) | (other & !mask)
+    }
+}
+
+impl ConstantTimeSelect for i64 {
+    #[inline(always)]
+    fn select_masked(self, other: Self, mask: u64) -> Self {
+        let mask = mask as i64;
+        (self & mask) | (other & !mask)
+    }
+}
+
+impl ConstantTimeSelect for f32 {
+    #[inline(always)]
+    fn select_masked(self, other: Self, mask: u64) -> Self {
+        let bits = (self.to_bits() as i32).select_masked(other.to_bits() as i32, mask);
+        f32::from_bits(bits as u32)
+    }
+}
+
+impl ConstantTimeSelect for f64 {
+    #[inline(always)]
+    fn select_masked(self, other: Self, mask: u64) -> Self {
+        let bits = (self.to_bits() as i64).select_masked(other.to_bits() as i64, mask);
+        f64::from_bits(bits as u64)
+    }
+}
+
+/// Wasm `select` without a branch: `if_true` when `condition` is non-zero,
+/// `if_false` otherwise.
+#[inline(always)]
+pub fn ct_select<T: ConstantTimeSelect>(condition: i32, if_true: T, if_false: T) -> T {
+    // 0 or 1, then all zeros or all ones
+    let bit = ((condition as u32 | (condition as u32).wrapping_neg()) >> 31) as u64;
+    let mask = black_box(bit.wrapping_neg());
+    if_true.select_masked(if_false, mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_like_wasm_select() {
+        for condition in [1, -1, i32::MIN, 0x100] {
+            assert_eq!(ct_select(condition, 7i32, -3), 7);
+            assert_eq!(ct_select(condition, i64::MIN, 1), i64::MIN);
+        }
+        assert_eq!(ct_select(0, 7i32, -3), -3);
+        assert_eq!(ct_select(0, i64::MIN, 1), 1);
+        assert_eq!(ct_select(1, -0.0f32, 1.5).to_bits(), (-0.0f32).to_bits());
+        assert_eq!(ct_select(0, 2.5f64, f64::NAN).to_bits(), f64::NAN.to_bits());
+    }
+}
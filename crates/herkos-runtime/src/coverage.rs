@@ -0,0 +1,150 @@
+//! Block coverage of modules transpiled with `--coverage`.
+//!
+//! Every block of a covered module counts its executions in a counter, and
+//! the module lists, in counter order, the function and Wasm byte offset
+//! each counter belongs to:
+//!
+//! ```text
+//! pub const COVERAGE_POINTS: &[CoveragePoint] = &[
+//!     CoveragePoint { func_index: 0, wasm_offset: 0x2b },
+//!     CoveragePoint { func_index: 0, wasm_offset: 0x33 },
+//! ];
+//! ```
+//!
+//! [`write_lcov`] turns the counters into an LCOV tracefile in which the
+//! "lines" are byte offsets in the original `.wasm` file, so the usual LCOV
+//! tooling can summarize the coverage of a third-party module whose sources
+//! are not at hand.
+
+use crate::FuncSymbol;
+use core::fmt;
+
+/// Where a coverage counter sits in the original Wasm binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoveragePoint {
+    /// Local function index — the `N` in the generated `func_N`.
+    pub func_index: u32,
+    /// Byte offset of the operator the counted block starts at.
+    pub wasm_offset: u32,
+}
+
+/// Write the coverage recorded in `hits`, one count per entry of `points`,
+/// as an LCOV tracefile for `source`.
+///
+/// `points` lists each function's points together, entry block first, as
+/// modules emit them. Functions are named after `symbols` when they have a
+/// name, `func_N` otherwise, and are reported at the start of their body.
+/// Blocks starting at the same offset (a loop right at the start of a
+/// function) share a `DA` line with the highest count.
+pub fn write_lcov<W: fmt::Write>(
+    out: &mut W,
+    source: &str,
+    symbols: &[FuncSymbol],
+    points: &[CoveragePoint],
+    hits: &[u64],
+) -> fmt::Result {
+    let hits_at = |i: usize| hits.get(i).copied().unwrap_or(0);
+
+    writeln!(out, "TN:")?;
+    writeln!(out, "SF:{source}")?;
+
+    let (mut found, mut hit) = (0, 0);
+    for start in function_starts(points) {
+        let func_index = points[start].func_index;
+        let symbol = symbols.iter().find(|s| s.func_index == func_index);
+        let name = FuncName(func_index, symbol);
+        let line = symbol.map_or(points[start].wasm_offset, |s| s.wasm_start);
+        writeln!(out, "FN:{line},{name}")?;
+        writeln!(out, "FNDA:{},{name}", hits_at(start))?;
+        found += 1;
+        hit += usize::from(hits_at(start) > 0);
+    }
+    writeln!(out, "FNF:{found}")?;
+    writeln!(out, "FNH:{hit}")?;
+
+    let (mut found, mut hit) = (0, 0);
+    let mut i = 0;
+    while i < points.len() {
+        let point = points[i];
+        let mut count = hits_at(i);
+        i += 1;
+        while i < points.len() && points[i] == point {
+            count = count.max(hits_at(i));
+            i += 1;
+        }
+        writeln!(out, "DA:{},{count}", point.wasm_offset)?;
+        found += 1;
+        hit += usize::from(count > 0);
+    }
+    writeln!(out, "LF:{found}")?;
+    writeln!(out, "LH:{hit}")?;
+    writeln!(out, "end_of_record")
+}
+
+/// Index of the first point of each function in `points`.
+fn function_starts(points: &[CoveragePoint]) -> impl Iterator<Item = usize> + '_ {
+    (0..points.len()).filter(|&i| i == 0 || points[i - 1].func_index != points[i].func_index)
+}
+
+/// A function's original name, or `func_N`.
+struct FuncName<'a>(u32, Option<&'a FuncSymbol>);
+
+impl fmt::Display for FuncName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1.and_then(|s| s.name) {
+            Some(name) => f.write_str(name),
+            None => write!(f, "func_{}", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::string::String;
+
+    const SYMBOLS: &[FuncSymbol] = &[FuncSymbol {
+        func_index: 0,
+        wasm_index: 1,
+        name: Some("clamp"),
+        exports: &["clamp"],
+        wasm_start: 0x20,
+        wasm_end: 0x40,
+    }];
+
+    const fn point(func_index: u32, wasm_offset: u32) -> CoveragePoint {
+        CoveragePoint {
+            func_index,
+            wasm_offset,
+        }
+    }
+
+    #[test]
+    fn report_lists_functions_and_blocks_by_offset() {
+        let points = [
+            point(0, 0x22),
+            point(0, 0x2a),
+            point(0, 0x30),
+            point(1, 0x44),
+        ];
+        let mut out = String::new();
+        write_lcov(&mut out, "clamp.wasm", SYMBOLS, &points, &[3, 0, 3, 0]).unwrap();
+        assert_eq!(
+            out,
+            "TN:\nSF:clamp.wasm\n\
+             FN:32,clamp\nFNDA:3,clamp\nFN:68,func_1\nFNDA:0,func_1\nFNF:2\nFNH:1\n\
+             DA:34,3\nDA:42,0\nDA:48,3\nDA:68,0\nLF:4\nLH:2\nend_of_record\n"
+        );
+    }
+
+    #[test]
+    fn blocks_at_the_same_offset_share_a_line() {
+        let points = [point(0, 0x22), point(0, 0x22), point(0, 0x26)];
+        let mut out = String::new();
+        write_lcov(&mut out, "loop.wasm", SYMBOLS, &points, &[1, 10]).unwrap();
+        assert!(out.contains("FNDA:1,clamp\n"));
+        assert!(out.contains("DA:34,10\nDA:38,0\nLF:2\nLH:1\n"));
+    }
+}
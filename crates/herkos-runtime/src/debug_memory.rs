@@ -0,0 +1,308 @@
+//! Poisoned linear memory for catching uninitialized reads.
+//!
+//! Wasm memory starts zeroed, so ported C code that reads a variable it
+//! never wrote, or runs off the end of a static array, usually sees zeros
+//! and carries on. [`DebugMemory`] takes that safety net away while
+//! testing:
+//!
+//! - [`arm`](DebugMemory::arm) fills every byte outside the data segments
+//!   with [`POISON`], so code relying on zero-init gets garbage instead;
+//! - the [`CANARY_LEN`] bytes after each data segment hold a canary
+//!   pattern, and a store into them is an overflow of that segment;
+//! - with [`with_read_tracking`](DebugMemory::with_read_tracking), every
+//!   byte written is recorded, and loads of bytes never written are
+//!   reported.
+//!
+//! Stores and loads are seen through [`MemoryObserver`] (`observe`
+//! feature), which modules transpiled with `--debug-memory` call:
+//!
+//! ```text
+//! impl MemoryObserver for Host {
+//!     fn on_load(&mut self, addr: usize, len: usize) { self.debug.on_load(addr, len) }
+//!     fn on_store(&mut self, addr: usize, len: usize) { self.debug.on_store(addr, len) }
+//! }
+//!
+//! let mut module = module::new()?;
+//! module.arm_debug_memory(&mut host.debug);
+//! module.run(&mut host)?;
+//! assert!(host.debug.findings().is_empty());
+//! ```
+//!
+//! Pages added by `memory.grow` are zeroed as the spec requires; with read
+//! tracking, reads of them before a write are still reported. A
+//! `memory.copy` counts as a load of its source, so copying a partly
+//! initialized struct is reported too.
+
+use crate::IsolatedMemory;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Byte every unwritten address holds after [`DebugMemory::arm`].
+pub const POISON: u8 = 0xa5;
+
+/// Bytes guarded after each data segment, fewer if the next one starts
+/// sooner.
+pub const CANARY_LEN: usize = 8;
+
+/// Canary pattern, repeated by address.
+const CANARY: [u8; 4] = [0xca, 0xfe, 0xba, 0xbe];
+
+/// What a [`DebugFinding`] caught.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugFindingKind {
+    /// A load of bytes never written.
+    UninitRead,
+    /// A store into the canary after a data segment.
+    CanaryOverwrite,
+}
+
+/// A suspicious access, by effective address and length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugFinding {
+    /// What was caught.
+    pub kind: DebugFindingKind,
+    /// Effective address of the access.
+    pub addr: usize,
+    /// Bytes accessed.
+    pub len: usize,
+}
+
+/// Poison, canaries and, optionally, the record of written bytes of one
+/// linear memory.
+#[derive(Debug, Default)]
+pub struct DebugMemory {
+    /// One bit per byte written, when reads are tracked.
+    written: Option<Vec<u64>>,
+    /// Canary ranges, by address.
+    canaries: Vec<Range<usize>>,
+    findings: Vec<DebugFinding>,
+}
+
+impl DebugMemory {
+    /// Poison and canaries only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Poison and canaries, and reports of reads of bytes never written.
+    pub fn with_read_tracking() -> Self {
+        Self {
+            written: Some(Vec::new()),
+            canaries: Vec::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    /// Poison `memory` outside `segments`, given as `(offset, len)`, and
+    /// place a canary after each.
+    ///
+    /// Call it right after instantiation, with the module's
+    /// `DATA_SEGMENTS`: everything else in active memory is overwritten.
+    /// The segments count as written.
+    pub fn arm<const MAX_PAGES: usize>(
+        &mut self,
+        memory: &mut IsolatedMemory<MAX_PAGES>,
+        segments: &[(u32, u32)],
+    ) {
+        let bytes = memory.as_mut_slice();
+        let size = bytes.len();
+        let mut segments: Vec<Range<usize>> = segments
+            .iter()
+            .map(|&(offset, len)| {
+                let start = (offset as usize).min(size);
+                start..start.saturating_add(len as usize).min(size)
+            })
+            .filter(|s| !s.is_empty())
+            .collect();
+        segments.sort_by_key(|s| s.start);
+
+        let mut poisoned_from = 0;
+        self.canaries.clear();
+        for (i, segment) in segments.iter().enumerate() {
+            if poisoned_from < segment.start {
+                bytes[poisoned_from..segment.start].fill(POISON);
+            }
+            poisoned_from = poisoned_from.max(segment.end);
+            self.mark_written(segment.start, segment.len());
+
+            let next = segments.get(i + 1).map_or(size, |s| s.start);
+            let canary = segment.end..next.min(segment.end + CANARY_LEN);
+            if !canary.is_empty() && canary.start >= poisoned_from {
+                self.canaries.push(canary);
+            }
+        }
+        bytes[poisoned_from..].fill(POISON);
+        for canary in &self.canaries {
+            for addr in canary.clone() {
+                bytes[addr] = CANARY[addr % CANARY.len()];
+            }
+        }
+    }
+
+    /// Record `len` bytes at `addr` as written, for stores the observer
+    /// does not see, such as `IsolatedMemory::write_bytes` from the host.
+    pub fn mark_written(&mut self, addr: usize, len: usize) {
+        let Some(written) = &mut self.written else {
+            return;
+        };
+        let end = addr.saturating_add(len);
+        if end > written.len() * 64 {
+            written.resize(end.div_ceil(64), 0);
+        }
+        for byte in addr..end {
+            written[byte / 64] |= 1 << (byte % 64);
+        }
+    }
+
+    /// Whether the byte at `addr` was written. Always true without read
+    /// tracking.
+    pub fn is_written(&self, addr: usize) -> bool {
+        match &self.written {
+            Some(written) => written
+                .get(addr / 64)
+                .is_some_and(|word| word & (1 << (addr % 64)) != 0),
+            None => true,
+        }
+    }
+
+    /// First canary byte of `memory` that no longer holds its pattern,
+    /// including ones overwritten by stores the observer does not see.
+    pub fn damaged_canary<const MAX_PAGES: usize>(
+        &self,
+        memory: &IsolatedMemory<MAX_PAGES>,
+    ) -> Option<usize> {
+        let bytes = memory.as_slice();
+        self.canaries
+            .iter()
+            .flat_map(|canary| canary.clone())
+            .find(|&addr| bytes.get(addr) != Some(&CANARY[addr % CANARY.len()]))
+    }
+
+    /// Accesses caught so far, each reported once, in order.
+    pub fn findings(&self) -> &[DebugFinding] {
+        &self.findings
+    }
+
+    /// Forget the findings so far.
+    pub fn clear_findings(&mut self) {
+        self.findings.clear();
+    }
+
+    #[cfg(feature = "observe")]
+    fn report(&mut self, kind: DebugFindingKind, addr: usize, len: usize) {
+        let finding = DebugFinding { kind, addr, len };
+        if !self.findings.contains(&finding) {
+            self.findings.push(finding);
+        }
+    }
+
+    #[cfg(feature = "observe")]
+    fn hits_canary(&self, addr: usize, len: usize) -> bool {
+        let end = addr.saturating_add(len);
+        self.canaries
+            .iter()
+            .any(|canary| addr < canary.end && canary.start < end)
+    }
+}
+
+#[cfg(feature = "observe")]
+impl crate::MemoryObserver for DebugMemory {
+    fn on_load(&mut self, addr: usize, len: usize) {
+        if self.written.is_some() && (addr..addr.saturating_add(len)).any(|a| !self.is_written(a)) {
+            self.report(DebugFindingKind::UninitRead, addr, len);
+        }
+    }
+
+    fn on_store(&mut self, addr: usize, len: usize) {
+        if self.hits_canary(addr, len) {
+            self.report(DebugFindingKind::CanaryOverwrite, addr, len);
+        }
+        self.mark_written(addr, len);
+    }
+}
+
+/// Report of whatever was caught when the memory goes away.
+#[cfg(feature = "std")]
+impl Drop for DebugMemory {
+    fn drop(&mut self) {
+        for finding in &self.findings {
+            let what = match finding.kind {
+                DebugFindingKind::UninitRead => "read of never-written memory",
+                DebugFindingKind::CanaryOverwrite => "store past the end of a data segment",
+            };
+            std::eprintln!(
+                "herkos: debug memory: {what}: {} bytes at {:#x}",
+                finding.len,
+                finding.addr
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arm_poisons_all_but_the_segments_and_guards_them() {
+        let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+        memory.write_bytes(16, &[1, 2, 3, 4]).unwrap();
+        memory.write_bytes(24, &[5; 8]).unwrap();
+        let mut debug = DebugMemory::new();
+        debug.arm(&mut memory, &[(24, 8), (16, 4)]);
+
+        let bytes = memory.as_slice();
+        assert!(bytes[..16].iter().all(|&b| b == POISON));
+        assert_eq!(&bytes[16..20], &[1, 2, 3, 4]);
+        // The gap before the next segment is all canary
+        assert_eq!(&bytes[20..24], &CANARY);
+        assert_eq!(&bytes[24..32], &[5; 8]);
+        assert_eq!(&bytes[32..40], &[CANARY, CANARY].concat()[..]);
+        assert!(bytes[40..].iter().all(|&b| b == POISON));
+        assert_eq!(debug.damaged_canary(&memory), None);
+
+        memory.store_u8(21, 0).unwrap();
+        assert_eq!(debug.damaged_canary(&memory), Some(21));
+    }
+
+    #[test]
+    fn written_bytes_are_only_tracked_on_request() {
+        let debug = DebugMemory::new();
+        assert!(debug.is_written(100));
+
+        let mut debug = DebugMemory::with_read_tracking();
+        debug.mark_written(60, 8);
+        assert!(!debug.is_written(59));
+        assert!((60..68).all(|a| debug.is_written(a)));
+        assert!(!debug.is_written(68));
+        assert!(!debug.is_written(1 << 20));
+    }
+
+    #[cfg(feature = "observe")]
+    #[test]
+    fn observed_accesses_are_reported_once() {
+        use crate::MemoryObserver;
+
+        let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+        let mut debug = DebugMemory::with_read_tracking();
+        debug.arm(&mut memory, &[(0, 4)]);
+        debug.on_load(0, 4);
+        debug.on_store(16, 4);
+        debug.on_load(16, 4);
+        debug.on_load(14, 4);
+        debug.on_load(14, 4);
+        debug.on_store(2, 4);
+        let uninit = DebugFinding {
+            kind: DebugFindingKind::UninitRead,
+            addr: 14,
+            len: 4,
+        };
+        let overflow = DebugFinding {
+            kind: DebugFindingKind::CanaryOverwrite,
+            addr: 2,
+            len: 4,
+        };
+        assert_eq!(debug.findings(), &[uninit, overflow]);
+        debug.clear_findings();
+    }
+}
@@ -0,0 +1,179 @@
+//! Dynamic linking of `dylink.0` side modules.
+//!
+//! Position-independent side modules (Emscripten `SIDE_MODULE`,
+//! `wasm-ld --experimental-pic -shared`) import a shared memory and table
+//! and place their data and element segments relative to the imported
+//! `env.__memory_base` and `env.__table_base` globals. Each transpiled side
+//! module exports its `DYLINK` layout; [`LinkedModules`] hands out
+//! non-overlapping bases in one memory and table, growing them as needed.
+//!
+//! The host then returns the assigned [`Bases`] from the module's
+//! `get___memory_base` / `get___table_base` methods and calls `init_memory`
+//! / `init_table` to apply its segments.
+
+use crate::{IsolatedMemory, Table, WasmResult, WasmTrap, PAGE_SIZE};
+
+/// Memory and table space a side module needs, from its `dylink.0` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DylinkLayout {
+    /// Bytes of memory the module's data needs.
+    pub memory_size: u32,
+    /// Required alignment of the memory area, as a power of two.
+    pub memory_align: u32,
+    /// Table slots the module's element segments need.
+    pub table_size: u32,
+    /// Required alignment of the table area, as a power of two.
+    pub table_align: u32,
+}
+
+/// Start of the memory and table areas assigned to one side module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bases {
+    /// Value of the module's `__memory_base` global.
+    pub memory_base: u32,
+    /// Value of the module's `__table_base` global.
+    pub table_base: u32,
+}
+
+/// A memory and table shared by dynamically linked modules, with cursors to
+/// the next free byte and slot.
+pub struct LinkedModules<'a, const MAX_PAGES: usize, const TABLE_MAX: usize> {
+    memory: &'a mut IsolatedMemory<MAX_PAGES>,
+    table: &'a mut Table<TABLE_MAX>,
+    next_memory: u32,
+    next_table: u32,
+}
+
+impl<'a, const MAX_PAGES: usize, const TABLE_MAX: usize> LinkedModules<'a, MAX_PAGES, TABLE_MAX> {
+    /// Share `memory` and `table`, placing modules from `memory_start` and
+    /// `table_start` on (the end of the main module's data and slots).
+    pub fn new(
+        memory: &'a mut IsolatedMemory<MAX_PAGES>,
+        table: &'a mut Table<TABLE_MAX>,
+        memory_start: u32,
+        table_start: u32,
+    ) -> Self {
+        LinkedModules {
+            memory,
+            table,
+            next_memory: memory_start,
+            next_table: table_start,
+        }
+    }
+
+    /// Assign aligned, non-overlapping areas for a module with `layout`,
+    /// growing the memory and table to fit them.
+    ///
+    /// # Errors
+    /// Returns `Err(WasmTrap::OutOfBounds)` if the memory cannot hold the
+    /// area, or `Err(WasmTrap::TableOutOfBounds)` if the table cannot.
+    pub fn place(&mut self, layout: &DylinkLayout) -> WasmResult<Bases> {
+        let memory_base =
+            align_up(self.next_memory, layout.memory_align).ok_or(WasmTrap::OutOfBounds)?;
+        let memory_end = memory_base
+            .checked_add(layout.memory_size)
+            .ok_or(WasmTrap::OutOfBounds)?;
+        let table_base =
+            align_up(self.next_table, layout.table_align).ok_or(WasmTrap::TableOutOfBounds)?;
+        let table_end = table_base
+            .checked_add(layout.table_size)
+            .ok_or(WasmTrap::TableOutOfBounds)?;
+
+        let active = self.memory.active_size();
+        if memory_end as usize > active {
+            let pages = (memory_end as usize - active).div_ceil(PAGE_SIZE);
+            if self.memory.grow(pages as u32) < 0 {
+                return Err(WasmTrap::OutOfBounds);
+            }
+        }
+        let slots = self.table.size();
+        if table_end as usize > slots && self.table.grow(table_end - slots as u32, None) < 0 {
+            return Err(WasmTrap::TableOutOfBounds);
+        }
+
+        self.next_memory = memory_end;
+        self.next_table = table_end;
+        Ok(Bases {
+            memory_base,
+            table_base,
+        })
+    }
+
+    /// The shared memory.
+    pub fn memory(&mut self) -> &mut IsolatedMemory<MAX_PAGES> {
+        self.memory
+    }
+
+    /// The shared table.
+    pub fn table(&mut self) -> &mut Table<TABLE_MAX> {
+        self.table
+    }
+
+    /// The shared memory and table, to initialize a module's segments.
+    pub fn parts_mut(&mut self) -> (&mut IsolatedMemory<MAX_PAGES>, &mut Table<TABLE_MAX>) {
+        (self.memory, self.table)
+    }
+}
+
+/// Round `value` up to a multiple of `1 << align_log2`.
+fn align_up(value: u32, align_log2: u32) -> Option<u32> {
+    let mask = 1u32.checked_shl(align_log2)? - 1;
+    value.checked_add(mask).map(|v| v & !mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: DylinkLayout = DylinkLayout {
+        memory_size: 100,
+        memory_align: 4,
+        table_size: 3,
+        table_align: 0,
+    };
+
+    #[test]
+    fn places_modules_back_to_back_with_alignment() {
+        let mut memory = IsolatedMemory::<2>::try_new(1).unwrap();
+        let mut table = Table::<8>::try_new(1).unwrap();
+        let mut linked = LinkedModules::new(&mut memory, &mut table, 1000, 1);
+        assert_eq!(
+            linked.place(&LAYOUT),
+            Ok(Bases {
+                memory_base: 1008,
+                table_base: 1,
+            })
+        );
+        assert_eq!(
+            linked.place(&LAYOUT),
+            Ok(Bases {
+                memory_base: 1120,
+                table_base: 4,
+            })
+        );
+        assert_eq!(linked.table().size(), 7);
+    }
+
+    #[test]
+    fn grows_memory_and_table_to_fit() {
+        let mut memory = IsolatedMemory::<2>::try_new(1).unwrap();
+        let mut table = Table::<4>::try_new(0).unwrap();
+        let mut linked = LinkedModules::new(&mut memory, &mut table, PAGE_SIZE as u32 - 10, 0);
+        linked.place(&LAYOUT).unwrap();
+        assert_eq!(linked.memory().page_count(), 2);
+        assert_eq!(linked.table().size(), 3);
+    }
+
+    #[test]
+    fn fails_when_memory_or_table_is_full() {
+        let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+        let mut table = Table::<4>::try_new(0).unwrap();
+        let mut linked = LinkedModules::new(&mut memory, &mut table, PAGE_SIZE as u32 - 10, 0);
+        assert_eq!(linked.place(&LAYOUT), Err(WasmTrap::OutOfBounds));
+
+        let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+        let mut table = Table::<4>::try_new(0).unwrap();
+        let mut linked = LinkedModules::new(&mut memory, &mut table, 0, 2);
+        assert_eq!(linked.place(&LAYOUT), Err(WasmTrap::TableOutOfBounds));
+    }
+}
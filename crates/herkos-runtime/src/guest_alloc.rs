@@ -0,0 +1,75 @@
+//! Checks around guest allocator exports for generated wrappers.
+//!
+//! When a module exports its own allocator (`malloc`, `__wbindgen_malloc`,
+//! `cabi_realloc`), the transpiler emits `alloc_in_guest` / `free_in_guest`
+//! methods that call it. The returned pointer comes from untrusted guest code,
+//! so it is validated here before the host writes through it.
+
+use crate::{WasmResult, WasmTrap};
+
+/// Alignment requested from guest allocators that take one — enough for any
+/// Wasm scalar, matching [`Scratch`](crate::Scratch).
+pub const GUEST_ALLOC_ALIGN: i32 = 8;
+
+/// Convert a host allocation size to the guest's 32-bit size argument.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::OutOfBounds)` if `len` exceeds the 32-bit guest
+/// address space.
+#[inline]
+pub fn guest_alloc_len(len: usize) -> WasmResult<i32> {
+    u32::try_from(len)
+        .map(|len| len as i32)
+        .map_err(|_| WasmTrap::OutOfBounds)
+}
+
+/// Validate a pointer returned by a guest allocator for a `len`-byte block.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::GuestAllocFailed)` for a null pointer and
+/// `Err(WasmTrap::OutOfBounds)` if the block does not fit in `memory`.
+#[inline(never)]
+pub fn checked_guest_alloc(memory: &[u8], ptr: i32, len: usize) -> WasmResult<usize> {
+    if ptr == 0 {
+        return Err(WasmTrap::GuestAllocFailed);
+    }
+    let ptr = ptr as u32 as usize;
+    match ptr.checked_add(len) {
+        Some(end) if end <= memory.len() => Ok(ptr),
+        _ => Err(WasmTrap::OutOfBounds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_null_and_out_of_bounds_blocks() {
+        let memory = [0u8; 64];
+        assert_eq!(checked_guest_alloc(&memory, 16, 48), Ok(16));
+        assert_eq!(
+            checked_guest_alloc(&memory, 0, 4),
+            Err(WasmTrap::GuestAllocFailed)
+        );
+        assert_eq!(
+            checked_guest_alloc(&memory, 16, 49),
+            Err(WasmTrap::OutOfBounds)
+        );
+        assert_eq!(
+            checked_guest_alloc(&memory, -8, 4),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn sizes_past_guest_address_space_fail() {
+        assert_eq!(guest_alloc_len(32), Ok(32));
+        assert_eq!(guest_alloc_len(u32::MAX as usize), Ok(-1));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(
+            guest_alloc_len(u32::MAX as usize + 1),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Guest text output for the `GuestLog` convenience trait.
+//!
+//! Most guests print through one of a few imports: `log(ptr, len)` style
+//! functions (`env.log`, `console.log`) and WASI `fd_write` to stdout or
+//! stderr. With `--guest-log`, the transpiler routes the ones it recognizes
+//! to [`GuestLog::log`] through the helpers here, which read the text out of
+//! linear memory, so a host that only wants the guest's messages implements
+//! a single method.
+
+use crate::{WasmResult, WasmTrap};
+
+/// Receiver of the text a guest prints.
+pub trait GuestLog {
+    /// Called with each message, as the guest wrote it (including any
+    /// trailing newline).
+    fn log(&mut self, msg: &str);
+}
+
+/// WASI `errno` for a bad file descriptor: what `fd_write` returns for any
+/// descriptor but stdout (1) and stderr (2).
+pub const WASI_ERRNO_BADF: i32 = 8;
+
+/// Pass the UTF-8 text at guest `(ptr, len)` to `log`.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::OutOfBounds)` if the bytes are outside `memory`
+/// and `Err(WasmTrap::InvalidValue)` if they are not valid UTF-8.
+pub fn log_str<L: GuestLog + ?Sized>(
+    log: &mut L,
+    memory: &[u8],
+    ptr: i32,
+    len: i32,
+) -> WasmResult<()> {
+    log.log(guest_str(memory, ptr, len)?);
+    Ok(())
+}
+
+/// Pass each non-empty buffer of a WASI `fd_write` to `log`, one message
+/// per buffer.
+///
+/// Returns the number of bytes written, for the caller to store at the
+/// `nwritten` pointer, or `None` if `fd` is neither stdout nor stderr.
+///
+/// # Errors
+/// Returns `Err(WasmTrap::OutOfBounds)` if the `iovs_len` iovecs at `iovs`
+/// or a buffer they point to lie outside `memory`, `Err(WasmTrap::InvalidValue)`
+/// if `iovs` is misaligned or a buffer is not valid UTF-8.
+pub fn log_iovecs<L: GuestLog + ?Sized>(
+    log: &mut L,
+    memory: &[u8],
+    fd: i32,
+    iovs: i32,
+    iovs_len: i32,
+) -> WasmResult<Option<i32>> {
+    if fd != 1 && fd != 2 {
+        return Ok(None);
+    }
+    let (start, count) = crate::cabi::guest_range(memory, iovs, iovs_len, 8, 4)?;
+    let mut written: u32 = 0;
+    for iov in memory[start..start + count * 8].chunks_exact(8) {
+        let ptr = i32::from_le_bytes([iov[0], iov[1], iov[2], iov[3]]);
+        let len = i32::from_le_bytes([iov[4], iov[5], iov[6], iov[7]]);
+        let msg = guest_str(memory, ptr, len)?;
+        if !msg.is_empty() {
+            log.log(msg);
+        }
+        written = written
+            .checked_add(len as u32)
+            .ok_or(WasmTrap::OutOfBounds)?;
+    }
+    Ok(Some(written as i32))
+}
+
+fn guest_str(memory: &[u8], ptr: i32, len: i32) -> WasmResult<&str> {
+    let (start, len) = crate::cabi::guest_range(memory, ptr, len, 1, 1)?;
+    core::str::from_utf8(&memory[start..start + len]).map_err(|_| WasmTrap::InvalidValue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    extern crate std;
+    use std::{string::String, vec::Vec};
+
+    #[derive(Default)]
+    struct Lines(Vec<String>);
+
+    impl GuestLog for Lines {
+        fn log(&mut self, msg: &str) {
+            self.0.push(msg.into());
+        }
+    }
+
+    #[test]
+    fn log_str_decodes_utf8() {
+        let mut memory = [0u8; 16];
+        memory[4..9].copy_from_slice(b"hello");
+        let mut lines = Lines::default();
+        log_str(&mut lines, &memory, 4, 5).unwrap();
+        assert_eq!(lines.0, ["hello"]);
+
+        memory[4] = 0xFF;
+        assert_eq!(
+            log_str(&mut lines, &memory, 4, 5),
+            Err(WasmTrap::InvalidValue)
+        );
+        assert_eq!(
+            log_str(&mut lines, &memory, 12, 5),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn log_iovecs_logs_each_buffer_of_stdout_and_stderr() {
+        let mut memory = [0u8; 64];
+        memory[32..37].copy_from_slice(b"hello");
+        memory[40..46].copy_from_slice(b"world\n");
+        // Three iovecs at 8: "hello", an empty buffer, "world\n"
+        for (i, (ptr, len)) in [(32u32, 5u32), (0, 0), (40, 6)].into_iter().enumerate() {
+            let at = 8 + i * 8;
+            memory[at..at + 4].copy_from_slice(&ptr.to_le_bytes());
+            memory[at + 4..at + 8].copy_from_slice(&len.to_le_bytes());
+        }
+        let mut lines = Lines::default();
+        assert_eq!(log_iovecs(&mut lines, &memory, 1, 8, 3), Ok(Some(11)));
+        assert_eq!(lines.0, ["hello", "world\n"]);
+
+        assert_eq!(log_iovecs(&mut lines, &memory, 3, 8, 3), Ok(None));
+        assert_eq!(
+            log_iovecs(&mut lines, &memory, 2, 6, 1),
+            Err(WasmTrap::InvalidValue)
+        );
+        assert_eq!(
+            log_iovecs(&mut lines, &memory, 2, 60, 1),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Tracking of the blocks a guest allocator hands out.
+//!
+//! Modules transpiled with `--heap-profile` route every call to their
+//! `malloc`, `free`, `calloc` and `realloc` exports, from the host and from
+//! the guest itself, through a [`HeapProfile`] kept next to the globals.
+//! It records the live blocks, so leaks that would stay invisible inside
+//! linear memory show up in [`HeapProfile::leaks`], and with the `std`
+//! feature in a report on stderr when the instance is dropped.
+//!
+//! Allocators often call each other (`calloc` through `malloc`, `realloc`
+//! through `malloc` and `free`). Only the outermost tracked call is
+//! recorded, so each block is counted once.
+
+use alloc::collections::BTreeMap;
+
+/// Counters over the lifetime of a [`HeapProfile`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Bytes in blocks allocated and not freed.
+    pub live_bytes: u64,
+    /// Highest `live_bytes` seen.
+    pub peak_bytes: u64,
+    /// Blocks allocated and not freed.
+    pub live_blocks: u64,
+    /// Successful allocations, including the new block of a `realloc`.
+    pub allocations: u64,
+    /// Blocks freed, including the old block of a `realloc`.
+    pub frees: u64,
+    /// Non-zero requests the allocator answered with a null pointer.
+    pub failed_allocations: u64,
+    /// Frees of pointers that are not live blocks: double frees, or
+    /// pointers that never came from the allocator.
+    pub invalid_frees: u64,
+}
+
+/// Live blocks of a guest allocator and the [`HeapStats`] over them.
+#[derive(Debug, Default)]
+pub struct HeapProfile {
+    /// Size of each live block, by address.
+    blocks: BTreeMap<u32, u64>,
+    stats: HeapStats,
+    /// Tracked calls currently running.
+    depth: u32,
+}
+
+impl HeapProfile {
+    /// A profile with nothing allocated.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a tracked call. Returns whether it is the outermost one, the
+    /// only one whose result is recorded.
+    pub fn enter(&mut self) -> bool {
+        self.depth += 1;
+        self.depth == 1
+    }
+
+    /// End the tracked call started by the matching [`enter`](Self::enter).
+    pub fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// `malloc(len)` or `calloc` returned `ptr`.
+    pub fn record_alloc(&mut self, ptr: i32, len: u64) {
+        if ptr == 0 {
+            if len != 0 {
+                self.stats.failed_allocations += 1;
+            }
+            return;
+        }
+        // An address handed out twice means its free went unseen
+        if let Some(stale) = self.blocks.insert(ptr as u32, len) {
+            self.stats.live_bytes -= stale;
+            self.stats.live_blocks -= 1;
+        }
+        self.stats.allocations += 1;
+        self.stats.live_blocks += 1;
+        self.stats.live_bytes += len;
+        self.stats.peak_bytes = self.stats.peak_bytes.max(self.stats.live_bytes);
+    }
+
+    /// `free(ptr)` returned.
+    pub fn record_free(&mut self, ptr: i32) {
+        if ptr == 0 {
+            return;
+        }
+        match self.blocks.remove(&(ptr as u32)) {
+            Some(len) => {
+                self.stats.frees += 1;
+                self.stats.live_blocks -= 1;
+                self.stats.live_bytes -= len;
+            }
+            None => self.stats.invalid_frees += 1,
+        }
+    }
+
+    /// `realloc(old, len)` returned `new`.
+    pub fn record_realloc(&mut self, old: i32, new: i32, len: u64) {
+        if new == 0 && len != 0 {
+            // The old block is left as it was
+            self.stats.failed_allocations += 1;
+            return;
+        }
+        self.record_free(old);
+        self.record_alloc(new, len);
+    }
+
+    /// Counters so far.
+    pub fn stats(&self) -> HeapStats {
+        self.stats
+    }
+
+    /// Blocks allocated and not freed, as `(address, size)` by address.
+    pub fn leaks(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.blocks.iter().map(|(&ptr, &len)| (ptr, len))
+    }
+}
+
+/// Leak report: whatever is still allocated when the instance goes away.
+#[cfg(feature = "std")]
+impl Drop for HeapProfile {
+    fn drop(&mut self) {
+        if self.blocks.is_empty() {
+            return;
+        }
+        std::eprintln!(
+            "herkos: heap profile: {} blocks ({} bytes) never freed, peak {} bytes",
+            self.stats.live_blocks,
+            self.stats.live_bytes,
+            self.stats.peak_bytes
+        );
+        for (ptr, len) in self.leaks() {
+            std::eprintln!("herkos: heap profile:   {len} bytes at {ptr:#x}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_live_bytes_peak_and_leaks() {
+        let mut heap = HeapProfile::new();
+        heap.record_alloc(16, 32);
+        heap.record_alloc(64, 8);
+        heap.record_free(16);
+        heap.record_alloc(0, 4);
+        heap.record_alloc(0, 0);
+        heap.record_free(16);
+        heap.record_free(0);
+        assert_eq!(
+            heap.stats(),
+            HeapStats {
+                live_bytes: 8,
+                peak_bytes: 40,
+                live_blocks: 1,
+                allocations: 2,
+                frees: 1,
+                failed_allocations: 1,
+                invalid_frees: 1,
+            }
+        );
+        assert!(heap.leaks().eq([(64, 8)]));
+        heap.record_free(64);
+    }
+
+    #[test]
+    fn realloc_moves_the_block_unless_it_fails() {
+        let mut heap = HeapProfile::new();
+        heap.record_realloc(0, 16, 4);
+        heap.record_realloc(16, 32, 12);
+        heap.record_realloc(32, 0, 100);
+        assert!(heap.leaks().eq([(32, 12)]));
+        assert_eq!(heap.stats().failed_allocations, 1);
+        heap.record_realloc(32, 0, 0);
+        assert_eq!(heap.stats().live_blocks, 0);
+    }
+
+    #[test]
+    fn only_the_outermost_call_is_recorded() {
+        let mut heap = HeapProfile::new();
+        assert!(heap.enter());
+        assert!(!heap.enter());
+        heap.exit();
+        heap.exit();
+        assert!(heap.enter());
+        heap.exit();
+    }
+}
@@ -0,0 +1,682 @@
+//! Swapping a module's code while keeping its state, for development.
+//!
+//! A module transpiled with `--hot-reload` implements [`HotPlugin`] and
+//! emits `HOT_VTABLE`, a [`HotVTable`] of `extern "C"` functions. A plugin
+//! crate built as a `cdylib` exports it with [`export_hot_vtable!`]; the
+//! host loads the library (`libloading`, `dlopen`), looks up
+//! [`HOT_VTABLE_SYMBOL`] and drives the module through a [`HotModule`]:
+//!
+//! ```text
+//! let lib = unsafe { libloading::Library::new("libplugin.so")? };
+//! let entry: Symbol<extern "C" fn() -> &'static HotVTable> =
+//!     unsafe { lib.get(HOT_VTABLE_SYMBOL.as_bytes())? };
+//! let mut plugin = HotModule::new(entry())?;
+//! plugin.call("update", &[Value::I32(16)], &mut linker)?;
+//!
+//! // After rebuilding the plugin
+//! let next = unsafe { libloading::Library::new("libplugin.so")? };
+//! plugin.reload(/* `herkos_hot_vtable` of `next` */)?;
+//! drop(lib); // the old code is no longer referenced
+//! ```
+//!
+//! [`HotModule::reload`] saves the old instance's state — the active linear
+//! memory and every mutable global, as raw bits in declaration order — and
+//! creates the new instance from it, so the rebuilt code carries on where
+//! the old one stopped. The state is matched by position: a rebuild that
+//! moves data in memory or reorders its globals restores garbage, one that
+//! changes how many mutable globals there are is refused.
+//!
+//! Imports are served by a [`Linker`], which the module reaches through a
+//! [`HotHost`] callback. Everything crossing the library boundary is
+//! `#[repr(C)]`; both sides must use the same [`HOT_ABI_VERSION`].
+
+use crate::linker::Linker;
+use crate::replay::TRAPS;
+use crate::{Value, WasmResult, WasmTrap, PAGE_SIZE};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+/// Version of the vtable layout; libraries of another version are refused.
+pub const HOT_ABI_VERSION: u32 = 1;
+
+/// Symbol [`export_hot_vtable!`] defines in a plugin library.
+pub const HOT_VTABLE_SYMBOL: &str = "herkos_hot_vtable";
+
+/// A module whose code can be replaced while it runs, implemented by
+/// modules transpiled with `--hot-reload`.
+pub trait HotPlugin: Sized {
+    /// Number of mutable globals.
+    const GLOBALS: usize;
+
+    /// A fresh instance.
+    fn create() -> WasmResult<Self>;
+
+    /// Call function export `name` (see `--dynamic-invoke`).
+    fn call(
+        &mut self,
+        name: &str,
+        args: &[Value],
+        host: &mut HotHost<'_>,
+    ) -> WasmResult<Option<Value>>;
+
+    /// The active linear memory; empty without one.
+    fn memory(&self) -> &[u8];
+
+    /// The active linear memory, writable.
+    fn memory_mut(&mut self) -> &mut [u8];
+
+    /// Wasm `memory.grow`: the previous page count, or -1.
+    fn grow_memory(&mut self, delta: u32) -> i32;
+
+    /// Raw bits of mutable global `index`.
+    fn global(&self, index: usize) -> u64;
+
+    /// Set mutable global `index` from its raw bits.
+    fn set_global(&mut self, index: usize, bits: u64);
+}
+
+/// State carried from one build of a module to the next.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HotState {
+    /// The active linear memory.
+    pub memory: Vec<u8>,
+    /// Raw bits of each mutable global, in declaration order.
+    pub globals: Vec<u64>,
+}
+
+/// Why a build could not be loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotReloadError {
+    /// The library was built for another [`HOT_ABI_VERSION`].
+    AbiMismatch { found: u32 },
+    /// The build has a different number of mutable globals than the state.
+    GlobalsMismatch { saved: usize, found: usize },
+    /// Creating or restoring the instance trapped.
+    Trap(WasmTrap),
+}
+
+impl From<WasmTrap> for HotReloadError {
+    fn from(trap: WasmTrap) -> Self {
+        HotReloadError::Trap(trap)
+    }
+}
+
+/// An instance owned by the library that created it.
+#[repr(C)]
+pub struct HotInstance {
+    _private: [u8; 0],
+}
+
+/// A borrowed slice crossing the library boundary.
+#[repr(C)]
+pub struct HotSlice<T> {
+    ptr: *const T,
+    len: usize,
+}
+
+impl<T> HotSlice<T> {
+    fn new(slice: &[T]) -> Self {
+        Self {
+            ptr: slice.as_ptr(),
+            len: slice.len(),
+        }
+    }
+
+    /// # Safety
+    /// The slice this was made from must still be alive and unchanged.
+    unsafe fn get<'a>(&self) -> &'a [T] {
+        core::slice::from_raw_parts(self.ptr, self.len)
+    }
+}
+
+/// A [`Value`] or nothing, crossing the library boundary.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct HotValue {
+    /// 0 for nothing, then `I32`, `I64`, `F32`, `F64`.
+    kind: u32,
+    bits: u64,
+}
+
+impl From<Option<Value>> for HotValue {
+    fn from(value: Option<Value>) -> Self {
+        let (kind, bits) = match value {
+            None => (0, 0),
+            Some(Value::I32(v)) => (1, v as u32 as u64),
+            Some(Value::I64(v)) => (2, v as u64),
+            Some(Value::F32(v)) => (3, v.to_bits() as u64),
+            Some(Value::F64(v)) => (4, v.to_bits()),
+        };
+        Self { kind, bits }
+    }
+}
+
+impl HotValue {
+    fn get(self) -> WasmResult<Option<Value>> {
+        match self.kind {
+            0 => Ok(None),
+            1 => Ok(Some(Value::I32(self.bits as u32 as i32))),
+            2 => Ok(Some(Value::I64(self.bits as i64))),
+            3 => Ok(Some(Value::F32(f32::from_bits(self.bits as u32)))),
+            4 => Ok(Some(Value::F64(f64::from_bits(self.bits)))),
+            _ => Err(WasmTrap::InvalidValue),
+        }
+    }
+}
+
+fn to_hot_values(values: &[Value]) -> Vec<HotValue> {
+    values.iter().map(|&v| HotValue::from(Some(v))).collect()
+}
+
+fn from_hot_values(values: &[HotValue]) -> WasmResult<Vec<Value>> {
+    values
+        .iter()
+        .map(|v| v.get()?.ok_or(WasmTrap::InvalidValue))
+        .collect()
+}
+
+/// A call's outcome crossing the library boundary.
+#[repr(C)]
+pub struct HotReturn {
+    /// 0 on success, else one plus the trap's code.
+    trap: u32,
+    value: HotValue,
+}
+
+impl From<WasmResult<Option<Value>>> for HotReturn {
+    fn from(result: WasmResult<Option<Value>>) -> Self {
+        match result {
+            Ok(value) => Self {
+                trap: 0,
+                value: value.into(),
+            },
+            Err(trap) => Self {
+                trap: trap_code(trap),
+                value: None.into(),
+            },
+        }
+    }
+}
+
+impl HotReturn {
+    fn get(self) -> WasmResult<Option<Value>> {
+        check_trap(self.trap)?;
+        self.value.get()
+    }
+}
+
+fn trap_code(trap: WasmTrap) -> u32 {
+    // Every variant is listed in TRAPS
+    TRAPS.iter().position(|&t| t == trap).unwrap_or(0) as u32 + 1
+}
+
+fn check_trap(code: u32) -> WasmResult<()> {
+    match code {
+        0 => Ok(()),
+        code => Err(*TRAPS
+            .get(code as usize - 1)
+            .unwrap_or(&WasmTrap::InvalidValue)),
+    }
+}
+
+/// An import call, from the module to the host.
+#[repr(C)]
+pub struct HotImport {
+    module: HotSlice<u8>,
+    field: HotSlice<u8>,
+    args: HotSlice<HotValue>,
+}
+
+/// An export call, from the host to the module.
+#[repr(C)]
+pub struct HotCall {
+    name: HotSlice<u8>,
+    args: HotSlice<HotValue>,
+}
+
+/// The host as seen by a hot-reloadable module: its imports call back into
+/// the [`Linker`] the host passed to [`HotModule::call`].
+#[repr(C)]
+pub struct HotHost<'a> {
+    linker: *mut c_void,
+    import: extern "C" fn(linker: *mut c_void, import: &HotImport) -> HotReturn,
+    _linker: PhantomData<&'a mut ()>,
+}
+
+impl HotHost<'_> {
+    /// Call import `module.field` of the host.
+    ///
+    /// # Errors
+    /// Whatever the instance registered under `module` returns.
+    pub fn call(&mut self, module: &str, field: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+        let args = to_hot_values(args);
+        let import = HotImport {
+            module: HotSlice::new(module.as_bytes()),
+            field: HotSlice::new(field.as_bytes()),
+            args: HotSlice::new(&args),
+        };
+        (self.import)(self.linker, &import).get()
+    }
+}
+
+extern "C" fn serve_import(linker: *mut c_void, import: &HotImport) -> HotReturn {
+    // SAFETY: `HotModule::call` passes its `&mut Linker` for the duration of
+    // the call, and `HotHost::call` passes slices it keeps alive.
+    let (linker, module, field, args) = unsafe {
+        (
+            &mut *linker.cast::<Linker<'_>>(),
+            import.module.get(),
+            import.field.get(),
+            import.args.get(),
+        )
+    };
+    let result = (|| {
+        let module = core::str::from_utf8(module).map_err(|_| WasmTrap::InvalidValue)?;
+        let field = core::str::from_utf8(field).map_err(|_| WasmTrap::InvalidValue)?;
+        linker.call(module, field, &from_hot_values(args)?)
+    })();
+    result.into()
+}
+
+/// The C-ABI entry points of one build of a module, emitted as
+/// `HOT_VTABLE` by `--hot-reload`.
+#[repr(C)]
+pub struct HotVTable {
+    /// [`HOT_ABI_VERSION`] of the build.
+    pub abi_version: u32,
+    /// Number of mutable globals.
+    pub globals: u32,
+    create: extern "C" fn(state: Option<&HotStateRef>, out: &mut *mut HotInstance) -> u32,
+    destroy: extern "C" fn(instance: *mut HotInstance),
+    call: extern "C" fn(
+        instance: *mut HotInstance,
+        call: &HotCall,
+        host: &mut HotHost<'_>,
+    ) -> HotReturn,
+    save: extern "C" fn(instance: *const HotInstance, globals: *mut u64) -> HotSlice<u8>,
+}
+
+/// A [`HotState`] lent to a library.
+#[repr(C)]
+pub struct HotStateRef {
+    memory: HotSlice<u8>,
+    globals: HotSlice<u64>,
+}
+
+impl HotVTable {
+    /// The vtable of plugin `T`.
+    pub const fn of<T: HotPlugin>() -> Self {
+        Self {
+            abi_version: HOT_ABI_VERSION,
+            globals: T::GLOBALS as u32,
+            create: create::<T>,
+            destroy: destroy::<T>,
+            call: call::<T>,
+            save: save::<T>,
+        }
+    }
+}
+
+extern "C" fn create<T: HotPlugin>(state: Option<&HotStateRef>, out: &mut *mut HotInstance) -> u32 {
+    let result = T::create().and_then(|mut instance| {
+        if let Some(state) = state {
+            // SAFETY: `HotModule` lends a `HotState` it keeps alive.
+            let (memory, globals) = unsafe { (state.memory.get(), state.globals.get()) };
+            restore(&mut instance, memory, globals)?;
+        }
+        Ok(instance)
+    });
+    match result {
+        Ok(instance) => {
+            *out = Box::into_raw(Box::new(instance)).cast();
+            0
+        }
+        Err(trap) => trap_code(trap),
+    }
+}
+
+/// Overwrite the memory and globals of `instance`, growing its memory to
+/// hold `memory` if needed.
+fn restore<T: HotPlugin>(instance: &mut T, memory: &[u8], globals: &[u64]) -> WasmResult<()> {
+    let active = instance.memory().len();
+    if memory.len() > active {
+        let delta = (memory.len() - active).div_ceil(PAGE_SIZE);
+        if instance.grow_memory(delta as u32) < 0 {
+            return Err(WasmTrap::OutOfBounds);
+        }
+    }
+    instance.memory_mut()[..memory.len()].copy_from_slice(memory);
+    for (index, &bits) in globals.iter().enumerate() {
+        instance.set_global(index, bits);
+    }
+    Ok(())
+}
+
+extern "C" fn destroy<T: HotPlugin>(instance: *mut HotInstance) {
+    // SAFETY: `instance` was made by `create::<T>` and is destroyed once.
+    drop(unsafe { Box::from_raw(instance.cast::<T>()) });
+}
+
+extern "C" fn call<T: HotPlugin>(
+    instance: *mut HotInstance,
+    call: &HotCall,
+    host: &mut HotHost<'_>,
+) -> HotReturn {
+    // SAFETY: `instance` was made by `create::<T>`, and `HotModule::call`
+    // passes slices it keeps alive.
+    let (instance, name, args) =
+        unsafe { (&mut *instance.cast::<T>(), call.name.get(), call.args.get()) };
+    let result = (|| {
+        let name = core::str::from_utf8(name).map_err(|_| WasmTrap::InvalidValue)?;
+        instance.call(name, &from_hot_values(args)?, host)
+    })();
+    result.into()
+}
+
+extern "C" fn save<T: HotPlugin>(instance: *const HotInstance, globals: *mut u64) -> HotSlice<u8> {
+    // SAFETY: `instance` was made by `create::<T>`; `globals` has room for
+    // `T::GLOBALS` values.
+    let (instance, globals) = unsafe {
+        (
+            &*instance.cast::<T>(),
+            core::slice::from_raw_parts_mut(globals, T::GLOBALS),
+        )
+    };
+    for (index, bits) in globals.iter_mut().enumerate() {
+        *bits = instance.global(index);
+    }
+    HotSlice::new(instance.memory())
+}
+
+/// An instance of a hot-reloadable module, created through a [`HotVTable`].
+///
+/// The library the vtable comes from must stay loaded until the instance is
+/// dropped or reloaded from another vtable.
+pub struct HotModule {
+    vtable: &'static HotVTable,
+    instance: NonNull<HotInstance>,
+}
+
+impl HotModule {
+    /// A fresh instance of the build behind `vtable`.
+    ///
+    /// # Errors
+    /// [`HotReloadError::AbiMismatch`] for a library of another ABI version,
+    /// [`HotReloadError::Trap`] if the module's initialization traps.
+    pub fn new(vtable: &'static HotVTable) -> Result<Self, HotReloadError> {
+        Self::create(vtable, None)
+    }
+
+    /// An instance of the build behind `vtable`, carrying on from `state`.
+    ///
+    /// # Errors
+    /// As [`new`](Self::new), and [`HotReloadError::GlobalsMismatch`] if the
+    /// build has another number of mutable globals than `state`.
+    pub fn with_state(
+        vtable: &'static HotVTable,
+        state: &HotState,
+    ) -> Result<Self, HotReloadError> {
+        Self::create(vtable, Some(state))
+    }
+
+    fn create(
+        vtable: &'static HotVTable,
+        state: Option<&HotState>,
+    ) -> Result<Self, HotReloadError> {
+        if vtable.abi_version != HOT_ABI_VERSION {
+            return Err(HotReloadError::AbiMismatch {
+                found: vtable.abi_version,
+            });
+        }
+        if let Some(state) = state {
+            if state.globals.len() != vtable.globals as usize {
+                return Err(HotReloadError::GlobalsMismatch {
+                    saved: state.globals.len(),
+                    found: vtable.globals as usize,
+                });
+            }
+        }
+        let state = state.map(|state| HotStateRef {
+            memory: HotSlice::new(&state.memory),
+            globals: HotSlice::new(&state.globals),
+        });
+        let mut instance = core::ptr::null_mut();
+        check_trap((vtable.create)(state.as_ref(), &mut instance))?;
+        let instance = NonNull::new(instance).ok_or(WasmTrap::InvalidValue)?;
+        Ok(Self { vtable, instance })
+    }
+
+    /// The vtable of the build in use.
+    pub fn vtable(&self) -> &'static HotVTable {
+        self.vtable
+    }
+
+    /// Call function export `name`, serving imports with `linker`.
+    ///
+    /// # Errors
+    /// [`WasmTrap::UnknownExport`] if there is no such export,
+    /// [`WasmTrap::InvokeTypeMismatch`] if `args` do not match its
+    /// parameters, or whatever the export or the imports it calls trap with.
+    pub fn call(
+        &mut self,
+        name: &str,
+        args: &[Value],
+        linker: &mut Linker<'_>,
+    ) -> WasmResult<Option<Value>> {
+        let args = to_hot_values(args);
+        let call = HotCall {
+            name: HotSlice::new(name.as_bytes()),
+            args: HotSlice::new(&args),
+        };
+        let mut host = HotHost {
+            linker: (linker as *mut Linker<'_>).cast(),
+            import: serve_import,
+            _linker: PhantomData,
+        };
+        (self.vtable.call)(self.instance.as_ptr(), &call, &mut host).get()
+    }
+
+    /// A copy of the instance's memory and mutable globals.
+    pub fn save(&self) -> HotState {
+        let mut globals = alloc::vec![0; self.vtable.globals as usize];
+        let memory = (self.vtable.save)(self.instance.as_ptr(), globals.as_mut_ptr());
+        // SAFETY: the memory stays borrowed from the instance until its next
+        // call, and is copied right away.
+        let memory = unsafe { memory.get() }.to_vec();
+        HotState { memory, globals }
+    }
+
+    /// Replace the code with the build behind `vtable`, keeping the state.
+    ///
+    /// On error the old build stays in use.
+    ///
+    /// # Errors
+    /// As [`with_state`](Self::with_state).
+    pub fn reload(&mut self, vtable: &'static HotVTable) -> Result<(), HotReloadError> {
+        *self = Self::with_state(vtable, &self.save())?;
+        Ok(())
+    }
+}
+
+impl Drop for HotModule {
+    fn drop(&mut self) {
+        (self.vtable.destroy)(self.instance.as_ptr());
+    }
+}
+
+/// Export `HOT_VTABLE` of a module transpiled with `--hot-reload` from a
+/// `cdylib`, under [`HOT_VTABLE_SYMBOL`]:
+///
+/// ```text
+/// herkos_runtime::export_hot_vtable!(plugin::HOT_VTABLE);
+/// ```
+#[macro_export]
+macro_rules! export_hot_vtable {
+    ($vtable:path) => {
+        /// The hot-reload entry point of this library.
+        #[unsafe(no_mangle)]
+        pub extern "C" fn herkos_hot_vtable() -> &'static $crate::HotVTable {
+            &$vtable
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Linkable;
+
+    /// A counter whose step is the build's `STEP`, with one global and a
+    /// one-page memory holding the last total.
+    struct Counter<const STEP: i32> {
+        memory: Vec<u8>,
+        total: i32,
+    }
+
+    impl<const STEP: i32> HotPlugin for Counter<STEP> {
+        const GLOBALS: usize = 1;
+
+        fn create() -> WasmResult<Self> {
+            Ok(Self {
+                memory: alloc::vec![0; PAGE_SIZE],
+                total: 0,
+            })
+        }
+
+        fn call(
+            &mut self,
+            name: &str,
+            args: &[Value],
+            host: &mut HotHost<'_>,
+        ) -> WasmResult<Option<Value>> {
+            match (name, args) {
+                ("bump", []) => {
+                    self.total += STEP;
+                    self.memory[..4].copy_from_slice(&self.total.to_le_bytes());
+                    host.call("env", "log", &[Value::I32(self.total)])?;
+                    Ok(Some(Value::I32(self.total)))
+                }
+                ("bump", _) => Err(WasmTrap::InvokeTypeMismatch),
+                _ => Err(WasmTrap::UnknownExport),
+            }
+        }
+
+        fn memory(&self) -> &[u8] {
+            &self.memory
+        }
+
+        fn memory_mut(&mut self) -> &mut [u8] {
+            &mut self.memory
+        }
+
+        fn grow_memory(&mut self, delta: u32) -> i32 {
+            let old = self.memory.len() / PAGE_SIZE;
+            self.memory.resize((old + delta as usize) * PAGE_SIZE, 0);
+            old as i32
+        }
+
+        fn global(&self, _: usize) -> u64 {
+            self.total as u32 as u64
+        }
+
+        fn set_global(&mut self, _: usize, bits: u64) {
+            self.total = bits as u32 as i32;
+        }
+    }
+
+    static ONES: HotVTable = HotVTable::of::<Counter<1>>();
+    static TENS: HotVTable = HotVTable::of::<Counter<10>>();
+
+    /// Records what the module logs.
+    #[derive(Default)]
+    struct Log(Vec<i32>);
+
+    impl Linkable for Log {
+        fn call(&mut self, name: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+            match (name, args) {
+                ("log", &[Value::I32(v)]) => {
+                    self.0.push(v);
+                    Ok(None)
+                }
+                _ => Err(WasmTrap::UnknownExport),
+            }
+        }
+    }
+
+    fn bump(module: &mut HotModule, log: &mut Log) -> WasmResult<Option<Value>> {
+        let mut linker = Linker::new();
+        linker.register("env", log);
+        module.call("bump", &[], &mut linker)
+    }
+
+    #[test]
+    fn reload_keeps_memory_and_globals() {
+        let mut log = Log::default();
+        let mut module = HotModule::new(&ONES).unwrap();
+        assert_eq!(bump(&mut module, &mut log), Ok(Some(Value::I32(1))));
+        assert_eq!(bump(&mut module, &mut log), Ok(Some(Value::I32(2))));
+
+        module.reload(&TENS).unwrap();
+        assert!(core::ptr::eq(module.vtable(), &TENS));
+        assert_eq!(module.save().memory[..4], 2i32.to_le_bytes());
+        assert_eq!(bump(&mut module, &mut log), Ok(Some(Value::I32(12))));
+        assert_eq!(log.0, [1, 2, 12]);
+
+        let state = module.save();
+        assert_eq!(state.globals, [12]);
+        assert_eq!(state.memory.len(), PAGE_SIZE);
+    }
+
+    #[test]
+    fn traps_cross_the_boundary() {
+        let mut module = HotModule::new(&ONES).unwrap();
+        let mut linker = Linker::new();
+        assert_eq!(
+            module.call("bump", &[], &mut linker),
+            Err(WasmTrap::UnknownExport)
+        );
+        assert_eq!(
+            module.call("bump", &[Value::F64(1.0)], &mut linker),
+            Err(WasmTrap::InvokeTypeMismatch)
+        );
+        for &trap in TRAPS.iter() {
+            assert_eq!(HotReturn::from(Err(trap)).get(), Err(trap));
+        }
+    }
+
+    #[test]
+    fn incompatible_builds_are_refused() {
+        let mut module = HotModule::new(&ONES).unwrap();
+        let mut state = module.save();
+        state.globals.push(0);
+        assert_eq!(
+            HotModule::with_state(&TENS, &state).err(),
+            Some(HotReloadError::GlobalsMismatch { saved: 2, found: 1 })
+        );
+
+        static FUTURE: HotVTable = HotVTable {
+            abi_version: HOT_ABI_VERSION + 1,
+            ..HotVTable::of::<Counter<10>>()
+        };
+        assert_eq!(
+            module.reload(&FUTURE),
+            Err(HotReloadError::AbiMismatch {
+                found: HOT_ABI_VERSION + 1
+            })
+        );
+        assert!(core::ptr::eq(module.vtable(), &ONES));
+    }
+
+    #[test]
+    fn restored_memory_grows_to_the_saved_size() {
+        let state = HotState {
+            memory: alloc::vec![7; 2 * PAGE_SIZE],
+            globals: alloc::vec![5],
+        };
+        let module = HotModule::with_state(&ONES, &state).unwrap();
+        assert_eq!(module.save(), state);
+    }
+}
@@ -3,6 +3,7 @@
 //! This crate is `#![no_std]` by default. It provides:
 //! - `IsolatedMemory<const MAX_PAGES: usize>` for Wasm linear memory
 //! - `WasmTrap` / `WasmResult<T>` for Wasm trap handling
+//! - `Value`, a dynamically typed Wasm number
 //! - Trait definitions for capability-based host imports (Phase 3+)
 
 #![no_std]
@@ -10,27 +11,126 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
 /// WebAssembly page size: 64 KiB per the Wasm specification.
 pub const PAGE_SIZE: usize = 65536;
 
+mod abi;
+pub use abi::{abi_compatible, ABI_VERSION};
+
 mod memory;
-pub use memory::IsolatedMemory;
+pub use memory::{IsolatedMemory, MAX_PROTECTED_RANGES};
+
+mod limits;
+pub use limits::{InstantiationCost, InstantiationLimits, MemoryLimits};
 
 mod table;
-pub use table::{FuncRef, Table};
+pub use table::{FuncDescriptor, FuncKind, FuncRef, FuncType, Table};
 
 mod module;
-pub use module::{LibraryModule, Module};
+pub use module::{LibraryModule, Module, ModuleDump};
+
+mod symbols;
+pub use symbols::FuncSymbol;
+
+mod metrics;
+pub use metrics::{Instrumentation, ModuleMetrics};
+
+mod coverage;
+pub use coverage::{write_lcov, CoveragePoint};
+
+mod bounds_profile;
+pub use bounds_profile::{write_bounds_report, BoundsSite};
+
+mod scratch;
+pub use scratch::Scratch;
+
+mod guest_alloc;
+pub use guest_alloc::{checked_guest_alloc, guest_alloc_len, GUEST_ALLOC_ALIGN};
+
+mod shadow_stack;
+pub use shadow_stack::{check_stack_top, SHADOW_STACK_ALIGN};
+
+mod tinygo;
+pub use tinygo::TinyGoClock;
+
+mod guest_log;
+pub use guest_log::{log_iovecs, log_str, GuestLog, WASI_ERRNO_BADF};
+
+mod dylink;
+pub use dylink::{Bases, DylinkLayout, LinkedModules};
+
+#[cfg(feature = "observe")]
+mod observer;
+#[cfg(feature = "observe")]
+pub use observer::{MemoryObserver, Watchpoints};
+
+#[cfg(feature = "alloc")]
+mod replay;
+#[cfg(feature = "alloc")]
+pub use replay::{ReplayEvent, ReplayLog, ReplayValue};
+
+mod value;
+pub use value::{Value, ValueType};
+
+mod shared_host;
+pub use shared_host::SharedHost;
+
+#[cfg(feature = "alloc")]
+mod heap_profile;
+#[cfg(feature = "alloc")]
+pub use heap_profile::{HeapProfile, HeapStats};
+
+#[cfg(feature = "alloc")]
+mod debug_memory;
+#[cfg(feature = "alloc")]
+pub use debug_memory::{DebugFinding, DebugFindingKind, DebugMemory, CANARY_LEN, POISON};
+
+#[cfg(feature = "alloc")]
+mod linker;
+#[cfg(feature = "alloc")]
+pub use linker::{Linkable, Linker};
+
+#[cfg(feature = "alloc")]
+mod hot_reload;
+#[cfg(feature = "alloc")]
+pub use hot_reload::{
+    HotHost, HotModule, HotPlugin, HotReloadError, HotState, HotVTable, HOT_ABI_VERSION,
+    HOT_VTABLE_SYMBOL,
+};
+
+mod constant_time;
+pub use constant_time::{ct_select, ConstantTimeSelect};
+
+pub mod cabi;
+
+pub mod wasmtime_compat;
 
 mod ops;
 pub use ops::{
-    i32_div_s, i32_div_u, i32_rem_s, i32_rem_u, i32_trunc_f32_s, i32_trunc_f32_u, i32_trunc_f64_s,
-    i32_trunc_f64_u, i64_div_s, i64_div_u, i64_rem_s, i64_rem_u, i64_trunc_f32_s, i64_trunc_f32_u,
-    i64_trunc_f64_s, i64_trunc_f64_u, wasm_max_f32, wasm_max_f64, wasm_min_f32, wasm_min_f64,
-    wasm_nearest_f32, wasm_nearest_f64,
+    effective_address, i32_div_s, i32_div_u, i32_rem_s, i32_rem_u, i32_trunc_f32_s,
+    i32_trunc_f32_u, i32_trunc_f64_s, i32_trunc_f64_u, i64_div_s, i64_div_u, i64_rem_s, i64_rem_u,
+    i64_trunc_f32_s, i64_trunc_f32_u, i64_trunc_f64_s, i64_trunc_f64_u, wasm_ceil_f32,
+    wasm_ceil_f64, wasm_floor_f32, wasm_floor_f64, wasm_max_f32, wasm_max_f64, wasm_min_f32,
+    wasm_min_f64, wasm_nearest_f32, wasm_nearest_f64, wasm_sqrt_f32, wasm_sqrt_f64, wasm_trunc_f32,
+    wasm_trunc_f64,
 };
 
-/// Wasm execution errors — no panics, no unwinding.
+/// Errors returned by generated code and the runtime — no panics, no unwinding.
+///
+/// Not every variant is a Wasm trap. The first seven (`OutOfBounds` through
+/// `UndefinedElement`) are the traps the Wasm spec defines. `Interrupted`,
+/// `HostAssertedOverflow` and `WriteProtected` stop guest execution too, but
+/// only in modules the host opted into yield points, checked arithmetic or
+/// memory protection. The rest are errors at the host boundary, raised by
+/// generated wrappers and runtime helpers before or around guest code: ABI
+/// decoding (`InvalidValue`), guest allocation (`GuestAllocFailed`), replay
+/// (`ReplayDiverged`), dynamic invocation (`UnknownExport`,
+/// `InvokeTypeMismatch`), host sharing (`HostBusy`) and instantiation
+/// (`InstantiationLimitExceeded`). They share this type so that every export
+/// keeps a single `WasmResult` error channel.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WasmTrap {
     /// Memory access out of bounds.
@@ -47,6 +147,41 @@ pub enum WasmTrap {
     TableOutOfBounds,
     /// Undefined element in table.
     UndefinedElement,
+    /// Value crossing the canonical ABI boundary is malformed (invalid UTF-8,
+    /// `char`, variant discriminant, or misaligned pointer).
+    InvalidValue,
+    /// Guest allocator export (`malloc`, `cabi_realloc`, ...) returned a null
+    /// pointer.
+    GuestAllocFailed,
+    /// The host stopped a running call at a yield point (`maybe_yield`).
+    ///
+    /// The call is aborted, not suspended: like any trap it unwinds every
+    /// Wasm frame and drops their locals, so the only way to continue is to
+    /// call an export again. Work then resumes only if the guest was written
+    /// to be restarted, keeping its progress in globals or memory and
+    /// committing it before each yield point; any other guest starts over,
+    /// or finds memory in a half-updated state.
+    Interrupted,
+    /// Signed overflow in an integer `add`, `sub` or `mul`, in code
+    /// transpiled with checked arithmetic. Diagnostic only: Wasm itself wraps.
+    HostAssertedOverflow,
+    /// A replayed run called a different host method than the recording did,
+    /// or more of them (`ReplayLog::replay`).
+    ReplayDiverged,
+    /// `invoke` was given a name that is not a function export.
+    UnknownExport,
+    /// `invoke` was given arguments whose number or types differ from the
+    /// export's parameters, or a `Value` converted to another type.
+    InvokeTypeMismatch,
+    /// A host shared through `SharedHost` could not be lent to a call: an
+    /// outer call still borrows it, or its lock is poisoned.
+    HostBusy,
+    /// Store or bulk write into a range made read-only with
+    /// `IsolatedMemory::protect`.
+    WriteProtected,
+    /// Instantiation would copy more than the host's `InstantiationLimits`
+    /// allow.
+    InstantiationLimitExceeded,
 }
 
 /// Result type for Wasm operations — `Result<T, WasmTrap>`.
@@ -68,13 +203,24 @@ pub enum ConstructionError {
     MemoryInitialPagesExceedsMax { initial: usize, max: usize },
     /// Initial size exceeds MAX_SIZE for table.
     TableInitialSizeExceedsMax { initial: usize, max: usize },
+    /// Data segments copy more bytes than `InstantiationLimits` allow.
+    DataSegmentsExceedLimit { bytes: usize, limit: usize },
+    /// Element segments write more entries than `InstantiationLimits` allow.
+    ElementSegmentsExceedLimit { entries: usize, limit: usize },
 }
 
 impl From<ConstructionError> for WasmTrap {
-    fn from(_: ConstructionError) -> Self {
-        // Construction errors are programming errors, but we map them to
-        // OutOfBounds for compatibility with the error propagation chain.
-        WasmTrap::OutOfBounds
+    fn from(err: ConstructionError) -> Self {
+        match err {
+            ConstructionError::DataSegmentsExceedLimit { .. }
+            | ConstructionError::ElementSegmentsExceedLimit { .. } => {
+                WasmTrap::InstantiationLimitExceeded
+            }
+            // Other construction errors are programming errors, but we map
+            // them to OutOfBounds for compatibility with the error
+            // propagation chain.
+            _ => WasmTrap::OutOfBounds,
+        }
     }
 }
 
@@ -0,0 +1,171 @@
+//! Memory requirements of a transpiled module, known before instantiation.
+//!
+//! Generated modules with a linear memory report their [`MemoryLimits`]
+//! from `WasmModule::memory_limits()`, a `const fn`. Hosts use it to budget
+//! memory, or to size the `IsolatedMemory<MP>` they lend to a module that
+//! imports its memory no larger than needed.
+//!
+//! Every generated module also reports its [`InstantiationCost`], the bytes
+//! and table entries its `new()` copies. A host loading untrusted modules
+//! instantiates them with `new_limited(&InstantiationLimits)`, which refuses
+//! one whose segments exceed the limits before copying anything.
+
+use crate::{ConstructionError, PAGE_SIZE};
+
+/// Pages a module's memory declares and bytes its data segments occupy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimits {
+    /// Pages the memory starts with.
+    pub initial_pages: u32,
+    /// Pages the memory may grow to: `MAX_PAGES` for an owned memory, the
+    /// import's declared maximum for a borrowed one (`None` if unbounded).
+    pub max_pages: Option<u32>,
+    /// End of the highest active data segment at a constant address, in
+    /// bytes. Segments placed relative to an imported base are not counted.
+    pub data_end: u32,
+}
+
+impl MemoryLimits {
+    /// Fewest pages the module can be instantiated with: its initial pages,
+    /// or more if a data segment ends beyond them.
+    pub const fn required_pages(&self) -> u32 {
+        let data_pages = (self.data_end as usize).div_ceil(PAGE_SIZE) as u32;
+        if data_pages > self.initial_pages {
+            data_pages
+        } else {
+            self.initial_pages
+        }
+    }
+
+    /// [`required_pages`](Self::required_pages) in bytes.
+    pub const fn required_bytes(&self) -> usize {
+        self.required_pages() as usize * PAGE_SIZE
+    }
+
+    /// Whether a memory of `pages` pages can hold the module: at least the
+    /// required pages and, when declared, no more than the maximum.
+    pub const fn fits(&self, pages: u32) -> bool {
+        if pages < self.required_pages() {
+            return false;
+        }
+        match self.max_pages {
+            Some(max) => pages <= max,
+            None => true,
+        }
+    }
+}
+
+/// Work a module's `new()` does initializing its segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstantiationCost {
+    /// Bytes the data segments copy into linear memory.
+    pub data_bytes: usize,
+    /// Table entries the element segments write.
+    pub table_entries: usize,
+}
+
+/// Most work a host lets instantiation of a module do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstantiationLimits {
+    /// Most bytes the data segments may copy.
+    pub max_data_bytes: usize,
+    /// Most table entries the element segments may write.
+    pub max_table_entries: usize,
+}
+
+impl InstantiationLimits {
+    /// No limit: what `new()` allows.
+    pub const UNLIMITED: Self = Self {
+        max_data_bytes: usize::MAX,
+        max_table_entries: usize::MAX,
+    };
+
+    /// Check the `cost` of instantiating a module against the limits.
+    ///
+    /// # Errors
+    /// Returns `ConstructionError::DataSegmentsExceedLimit` or
+    /// `ConstructionError::ElementSegmentsExceedLimit` for the first limit
+    /// exceeded.
+    pub const fn check(&self, cost: &InstantiationCost) -> Result<(), ConstructionError> {
+        if cost.data_bytes > self.max_data_bytes {
+            return Err(ConstructionError::DataSegmentsExceedLimit {
+                bytes: cost.data_bytes,
+                limit: self.max_data_bytes,
+            });
+        }
+        if cost.table_entries > self.max_table_entries {
+            return Err(ConstructionError::ElementSegmentsExceedLimit {
+                entries: cost.table_entries,
+                limit: self.max_table_entries,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: MemoryLimits = MemoryLimits {
+        initial_pages: 1,
+        max_pages: Some(4),
+        data_end: 100,
+    };
+
+    #[test]
+    fn required_pages_cover_initial_pages_and_data() {
+        assert_eq!(LIMITS.required_pages(), 1);
+        assert_eq!(LIMITS.required_bytes(), PAGE_SIZE);
+
+        let past_initial = MemoryLimits {
+            data_end: PAGE_SIZE as u32 + 1,
+            ..LIMITS
+        };
+        assert_eq!(past_initial.required_pages(), 2);
+    }
+
+    #[test]
+    fn fits_checks_both_bounds() {
+        assert!(!LIMITS.fits(0));
+        assert!(LIMITS.fits(1));
+        assert!(LIMITS.fits(4));
+        assert!(!LIMITS.fits(5));
+        let unbounded = MemoryLimits {
+            max_pages: None,
+            ..LIMITS
+        };
+        assert!(unbounded.fits(65536));
+    }
+
+    #[test]
+    fn instantiation_limits_bound_data_and_elements() {
+        let cost = InstantiationCost {
+            data_bytes: 4096,
+            table_entries: 10,
+        };
+        assert_eq!(InstantiationLimits::UNLIMITED.check(&cost), Ok(()));
+        let limits = InstantiationLimits {
+            max_data_bytes: 4096,
+            max_table_entries: 9,
+        };
+        assert_eq!(
+            limits.check(&cost),
+            Err(ConstructionError::ElementSegmentsExceedLimit {
+                entries: 10,
+                limit: 9
+            })
+        );
+        let limits = InstantiationLimits {
+            max_data_bytes: 1024,
+            ..limits
+        };
+        assert_eq!(
+            limits.check(&cost),
+            Err(ConstructionError::DataSegmentsExceedLimit {
+                bytes: 4096,
+                limit: 1024
+            })
+        );
+    }
+}
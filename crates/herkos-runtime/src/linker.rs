@@ -0,0 +1,151 @@
+//! Wiring transpiled modules to each other at runtime.
+//!
+//! A module's imports are methods of its `ModuleHostTrait`, so another
+//! module can only serve them through a host written by hand. Modules
+//! transpiled with `--link` get the glue for both sides:
+//!
+//! - `LinkedWasmModule<H>` bundles an instance with its host and implements
+//!   [`Linkable`], so its exports can be called by name.
+//! - `ModuleHostTrait` is implemented for [`Linker`]: each import converts
+//!   its arguments to [`Value`]s and calls export `field` of the instance
+//!   registered under the import's module name.
+//!
+//! ```text
+//! let mut linker = Linker::new();
+//! linker.register("math", math::LinkedWasmModule::new(math, NoHost));
+//! app.run(&mut linker)?; // `math.*` imports call into `math`
+//! ```
+//!
+//! Calls to a module that is not registered, or to an export it lacks,
+//! trap with [`WasmTrap::UnknownExport`]; arguments or results of the wrong
+//! type trap with [`WasmTrap::InvokeTypeMismatch`].
+
+use crate::{Value, WasmResult, WasmTrap};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// An instance whose function exports can be called by name.
+pub trait Linkable {
+    /// Call function export `name` with `args`.
+    ///
+    /// # Errors
+    /// [`WasmTrap::UnknownExport`] if there is no such export,
+    /// [`WasmTrap::InvokeTypeMismatch`] if `args` do not match its
+    /// parameters, or whatever the export itself traps with.
+    fn call(&mut self, name: &str, args: &[Value]) -> WasmResult<Option<Value>>;
+}
+
+impl<L: Linkable + ?Sized> Linkable for &mut L {
+    fn call(&mut self, name: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+        (**self).call(name, args)
+    }
+}
+
+impl<L: Linkable + ?Sized> Linkable for Box<L> {
+    fn call(&mut self, name: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+        (**self).call(name, args)
+    }
+}
+
+/// Instances registered under the module names other modules import from.
+#[derive(Default)]
+pub struct Linker<'a> {
+    modules: Vec<(String, Box<dyn Linkable + 'a>)>,
+}
+
+impl<'a> Linker<'a> {
+    /// A linker with nothing registered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve imports from `module` with `instance`, replacing any instance
+    /// registered under that name before.
+    ///
+    /// Register `&mut instance` to keep using it once the linker is gone.
+    pub fn register(&mut self, module: &str, instance: impl Linkable + 'a) -> &mut Self {
+        let instance: Box<dyn Linkable + 'a> = Box::new(instance);
+        match self.modules.iter_mut().find(|(name, _)| name == module) {
+            Some((_, slot)) => *slot = instance,
+            None => self.modules.push((String::from(module), instance)),
+        }
+        self
+    }
+
+    /// Whether an instance is registered under `module`.
+    pub fn is_registered(&self, module: &str) -> bool {
+        self.modules.iter().any(|(name, _)| name == module)
+    }
+
+    /// Call export `name` of the instance registered under `module`.
+    ///
+    /// # Errors
+    /// [`WasmTrap::UnknownExport`] if no instance is registered under
+    /// `module`, otherwise whatever [`Linkable::call`] returns.
+    pub fn call(&mut self, module: &str, name: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+        let (_, instance) = self
+            .modules
+            .iter_mut()
+            .find(|(registered, _)| registered == module)
+            .ok_or(WasmTrap::UnknownExport)?;
+        instance.call(name, args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exports `add(i32, i32) -> i32` and counts its calls.
+    struct Adder {
+        calls: u32,
+    }
+
+    impl Linkable for Adder {
+        fn call(&mut self, name: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+            match (name, args) {
+                ("add", &[Value::I32(a), Value::I32(b)]) => {
+                    self.calls += 1;
+                    Ok(Some(Value::I32(a.wrapping_add(b))))
+                }
+                ("add", _) => Err(WasmTrap::InvokeTypeMismatch),
+                _ => Err(WasmTrap::UnknownExport),
+            }
+        }
+    }
+
+    #[test]
+    fn calls_reach_the_instance_registered_under_the_module_name() {
+        let mut adder = Adder { calls: 0 };
+        let mut linker = Linker::new();
+        linker.register("math", &mut adder);
+        assert!(linker.is_registered("math"));
+
+        let args = [Value::I32(2), Value::I32(3)];
+        assert_eq!(linker.call("math", "add", &args), Ok(Some(Value::I32(5))));
+        assert_eq!(
+            linker.call("math", "add", &[Value::I64(2)]),
+            Err(WasmTrap::InvokeTypeMismatch)
+        );
+        assert_eq!(
+            linker.call("math", "sub", &args),
+            Err(WasmTrap::UnknownExport)
+        );
+        assert_eq!(
+            linker.call("env", "add", &args),
+            Err(WasmTrap::UnknownExport)
+        );
+        drop(linker);
+        assert_eq!(adder.calls, 1);
+    }
+
+    #[test]
+    fn registering_again_replaces_the_instance() {
+        let mut linker = Linker::new();
+        linker
+            .register("math", Adder { calls: 0 })
+            .register("math", Adder { calls: 0 });
+        assert_eq!(linker.modules.len(), 1);
+    }
+}
@@ -11,9 +11,23 @@
 //! Load/store operations use the **outline pattern** (§13.3): the generic
 //! wrapper delegates to a non-generic inner function so that only one copy
 //! of the actual bounds-checking logic exists in the binary.
+//!
+//! Values are stored **little-endian** on every host, as the Wasm spec
+//! requires: loads and stores convert with `from_le_bytes`/`to_le_bytes`
+//! (`from_le`/`to_le` in the unchecked variants), never native order, so a
+//! module's memory image is the same on s390x or powerpc as on x86.
+//!
+//! Up to [`MAX_PROTECTED_RANGES`] byte ranges can be made read-only with
+//! [`IsolatedMemory::protect`], emulating page protection at byte
+//! granularity: every checked store and bulk write into one traps with
+//! `WriteProtected`. Loads, `as_mut_slice` and the unchecked stores are not
+//! affected.
 
 use crate::{WasmResult, WasmTrap, PAGE_SIZE};
 
+/// Number of disjoint read-only ranges an [`IsolatedMemory`] can hold.
+pub const MAX_PROTECTED_RANGES: usize = 8;
+
 /// Isolated linear memory for a single Wasm module.
 ///
 /// `MAX_PAGES` is the compile-time maximum (from the Wasm module's declared
@@ -26,6 +40,10 @@ pub struct IsolatedMemory<const MAX_PAGES: usize> {
     /// incremented by `grow`. Accesses beyond `active_pages * PAGE_SIZE`
     /// are out-of-bounds traps.
     active_pages: usize,
+    /// Read-only byte ranges `[start, end)`, disjoint and non-adjacent;
+    /// only the first `protected_count` are in use.
+    protected: [(usize, usize); MAX_PROTECTED_RANGES],
+    protected_count: usize,
 }
 
 impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
@@ -44,6 +62,8 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
         Ok(Self {
             pages: [[0u8; PAGE_SIZE]; MAX_PAGES],
             active_pages: initial_pages,
+            protected: [(0, 0); MAX_PROTECTED_RANGES],
+            protected_count: 0,
         })
     }
 
@@ -68,13 +88,15 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
         }
         let ptr = slot.as_mut_ptr();
         // SAFETY: ptr comes from MaybeUninit so it is valid for writes and
-        // correctly aligned. Both fields are written before the caller can
+        // correctly aligned. All fields are written before the caller can
         // call assume_init on the slot.
         unsafe {
             // Zero the entire pages array in-place — the compiler emits a
             // single memset; no large stack temporary is created.
             core::ptr::addr_of_mut!((*ptr).pages).write_bytes(0, 1);
             core::ptr::addr_of_mut!((*ptr).active_pages).write(initial_pages);
+            core::ptr::addr_of_mut!((*ptr).protected).write([(0, 0); MAX_PROTECTED_RANGES]);
+            core::ptr::addr_of_mut!((*ptr).protected_count).write(0);
         }
         Ok(())
     }
@@ -94,9 +116,17 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Wasm `memory.grow` — returns previous page count, or -1 on failure.
     /// No allocation occurs: the backing array is already sized to `MAX_PAGES`.
     pub fn grow(&mut self, delta: u32) -> i32 {
+        self.grow_within(delta, MAX_PAGES)
+    }
+
+    /// Wasm `memory.grow` for a memory whose type declares `max_pages`.
+    ///
+    /// A module importing this memory may declare a smaller maximum than the
+    /// host's `MAX_PAGES`; growing fails beyond whichever limit is lower.
+    pub fn grow_within(&mut self, delta: u32, max_pages: usize) -> i32 {
         let old = self.active_pages;
-        let new = old.wrapping_add(delta as usize);
-        if new > MAX_PAGES {
+        let new = old.saturating_add(delta as usize);
+        if new > MAX_PAGES.min(max_pages) {
             return -1;
         }
         // Zero-init the new pages (Wasm spec requires it).
@@ -125,6 +155,67 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
         self.pages.as_flattened_mut()
     }
 
+    // ── Write protection ──────────────────────────────────────────────
+
+    /// Make `len` bytes from `offset` read-only: later stores and bulk
+    /// writes touching them trap with `WriteProtected`.
+    ///
+    /// Ranges that overlap or touch an already protected one are merged
+    /// with it. Protection is meant to be applied once the memory is
+    /// initialized, e.g. to the constant data segments.
+    ///
+    /// # Errors
+    /// Returns `Err(WasmTrap::OutOfBounds)` if the range extends beyond the
+    /// active memory, or if it would be an additional range when
+    /// [`MAX_PROTECTED_RANGES`] are already in use.
+    pub fn protect(&mut self, offset: usize, len: usize) -> WasmResult<()> {
+        let end = offset.checked_add(len).ok_or(WasmTrap::OutOfBounds)?;
+        if end > self.active_size() {
+            return Err(WasmTrap::OutOfBounds);
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        let (mut start, mut end) = (offset, end);
+        let mut kept = 0;
+        for i in 0..self.protected_count {
+            let (s, e) = self.protected[i];
+            if s <= end && start <= e {
+                start = start.min(s);
+                end = end.max(e);
+            } else {
+                self.protected[kept] = (s, e);
+                kept += 1;
+            }
+        }
+        if kept == MAX_PROTECTED_RANGES {
+            return Err(WasmTrap::OutOfBounds);
+        }
+        self.protected[kept] = (start, end);
+        self.protected_count = kept + 1;
+        Ok(())
+    }
+
+    /// The read-only ranges, as `(start, end)` byte offsets, `end` excluded.
+    pub fn protected_ranges(&self) -> &[(usize, usize)] {
+        &self.protected[..self.protected_count]
+    }
+
+    /// Make the whole memory writable again.
+    pub fn unprotect_all(&mut self) {
+        self.protected_count = 0;
+    }
+
+    /// `Err(WriteProtected)` if `len` bytes from `offset` touch a read-only
+    /// range.
+    #[inline(always)]
+    fn check_writable(&self, offset: usize, len: usize) -> WasmResult<()> {
+        if self.protected_count == 0 {
+            return Ok(());
+        }
+        check_writable_inner(self.protected_ranges(), offset, len)
+    }
+
     // ── Bulk memory operations ────────────────────────────────────────
 
     /// Wasm `memory.copy` — copy `len` bytes from `src` to `dst`.
@@ -142,6 +233,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
         {
             return Err(WasmTrap::OutOfBounds);
         }
+        self.check_writable(dst, len)?;
         self.flat_mut().copy_within(src..src + len, dst);
         Ok(())
     }
@@ -151,6 +243,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Only the low 8 bits of `val` are used (Wasm spec). Traps (`OutOfBounds`)
     /// if the region extends beyond the current active memory.
     pub fn fill(&mut self, dst: usize, val: u8, len: usize) -> WasmResult<()> {
+        self.check_writable(dst, len)?;
         let active = self.active_size();
         fill_inner(self.flat_mut(), active, dst, val, len)
     }
@@ -169,6 +262,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
         src_offset: usize,
         len: usize,
     ) -> WasmResult<()> {
+        self.check_writable(dst, len)?;
         let active = self.active_size();
         init_data_partial_inner(self.flat_mut(), active, dst, data, src_offset, len)
     }
@@ -214,6 +308,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Store an i32 into linear memory with bounds checking.
     #[inline(always)]
     pub fn store_i32(&mut self, offset: usize, value: i32) -> WasmResult<()> {
+        self.check_writable(offset, 4)?;
         let active = self.active_size();
         store_i32_inner(self.flat_mut(), active, offset, value)
     }
@@ -221,6 +316,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Store an i64 into linear memory with bounds checking.
     #[inline(always)]
     pub fn store_i64(&mut self, offset: usize, value: i64) -> WasmResult<()> {
+        self.check_writable(offset, 8)?;
         let active = self.active_size();
         store_i64_inner(self.flat_mut(), active, offset, value)
     }
@@ -228,6 +324,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Store a u8 (i32.store8) into linear memory with bounds checking.
     #[inline(always)]
     pub fn store_u8(&mut self, offset: usize, value: u8) -> WasmResult<()> {
+        self.check_writable(offset, 1)?;
         let active = self.active_size();
         store_u8_inner(self.flat_mut(), active, offset, value)
     }
@@ -235,6 +332,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Store a u16 (i32.store16) into linear memory with bounds checking.
     #[inline(always)]
     pub fn store_u16(&mut self, offset: usize, value: u16) -> WasmResult<()> {
+        self.check_writable(offset, 2)?;
         let active = self.active_size();
         store_u16_inner(self.flat_mut(), active, offset, value)
     }
@@ -242,6 +340,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Store an f32 into linear memory with bounds checking.
     #[inline(always)]
     pub fn store_f32(&mut self, offset: usize, value: f32) -> WasmResult<()> {
+        self.check_writable(offset, 4)?;
         let active = self.active_size();
         store_f32_inner(self.flat_mut(), active, offset, value)
     }
@@ -249,6 +348,7 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// Store an f64 into linear memory with bounds checking.
     #[inline(always)]
     pub fn store_f64(&mut self, offset: usize, value: f64) -> WasmResult<()> {
+        self.check_writable(offset, 8)?;
         let active = self.active_size();
         store_f64_inner(self.flat_mut(), active, offset, value)
     }
@@ -262,12 +362,40 @@ impl<const MAX_PAGES: usize> IsolatedMemory<MAX_PAGES> {
     /// # Errors
     /// Returns `Err(WasmTrap::OutOfBounds)` if `offset + data.len()` exceeds
     /// `active_pages * PAGE_SIZE`.
+    /// Returns `Err(WasmTrap::WriteProtected)` if the region touches a
+    /// read-only range.
     #[inline(always)]
     pub fn init_data(&mut self, offset: usize, data: &[u8]) -> WasmResult<()> {
+        self.check_writable(offset, data.len())?;
         let active = self.active_size();
         init_data_inner(self.flat_mut(), active, offset, data)
     }
 
+    /// Copy a host byte slice into linear memory at `offset`.
+    ///
+    /// Used by generated binding wrappers to stage host data for the guest.
+    ///
+    /// # Errors
+    /// Returns `Err(WasmTrap::OutOfBounds)` if `offset + data.len()` exceeds
+    /// the active memory, or `Err(WasmTrap::WriteProtected)` if it touches a
+    /// read-only range.
+    #[inline(always)]
+    pub fn write_bytes(&mut self, offset: usize, data: &[u8]) -> WasmResult<()> {
+        self.check_writable(offset, data.len())?;
+        let active = self.active_size();
+        init_data_inner(self.flat_mut(), active, offset, data)
+    }
+
+    /// Copy `dst.len()` bytes out of linear memory starting at `offset`.
+    ///
+    /// # Errors
+    /// Returns `Err(WasmTrap::OutOfBounds)` if `offset + dst.len()` exceeds
+    /// the active memory.
+    #[inline(always)]
+    pub fn read_bytes(&self, offset: usize, dst: &mut [u8]) -> WasmResult<()> {
+        read_bytes_inner(self.flat(), self.active_size(), offset, dst)
+    }
+
     // ── Unchecked (verified) load/store ───────────────────────────────
     //
     // These skip bounds checking entirely. The caller MUST guarantee that
@@ -494,6 +622,18 @@ fn init_data_inner(
     Ok(())
 }
 
+#[inline(never)]
+fn read_bytes_inner(
+    memory: &[u8],
+    active_bytes: usize,
+    offset: usize,
+    dst: &mut [u8],
+) -> WasmResult<()> {
+    let src = checked_slice(memory, active_bytes, offset, dst.len())?;
+    dst.copy_from_slice(src);
+    Ok(())
+}
+
 #[inline(never)]
 fn fill_inner(
     memory: &mut [u8],
@@ -526,6 +666,15 @@ fn init_data_partial_inner(
     Ok(())
 }
 
+#[inline(never)]
+fn check_writable_inner(protected: &[(usize, usize)], offset: usize, len: usize) -> WasmResult<()> {
+    let end = offset.saturating_add(len);
+    if len > 0 && protected.iter().any(|&(s, e)| offset < e && s < end) {
+        return Err(WasmTrap::WriteProtected);
+    }
+    Ok(())
+}
+
 // ── Unchecked inner functions ─────────────────────────────────────────
 //
 // SAFETY: the caller (verified backend) guarantees the offset is in-bounds,
@@ -605,6 +754,18 @@ mod tests {
         assert_eq!(mem.page_count(), 1); // unchanged
     }
 
+    #[test]
+    fn grow_within_respects_declared_max() {
+        let mut mem = IsolatedMemory::<4>::try_new(1).unwrap();
+        assert_eq!(mem.grow_within(2, 2), -1);
+        assert_eq!(mem.grow_within(1, 2), 1);
+        assert_eq!(mem.grow_within(1, 2), -1);
+        assert_eq!(mem.page_count(), 2);
+        // A declared maximum above MAX_PAGES is capped by the backing array.
+        assert_eq!(mem.grow_within(3, 100), -1);
+        assert_eq!(mem.grow_within(2, 100), 2);
+    }
+
     #[test]
     fn grow_zero_is_noop() {
         let mut mem = Mem::try_new(1).unwrap();
@@ -739,6 +900,74 @@ mod tests {
         }
     }
 
+    // ── byte order ──
+    //
+    // Wasm memory is little-endian on every host. These pin the byte
+    // layout itself, so they fail on a big-endian host (s390x, powerpc)
+    // if a native-endian conversion slips in, where a roundtrip would not.
+
+    #[test]
+    fn stores_write_little_endian_bytes() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.store_u16(0, 0x0102).unwrap();
+        mem.store_i32(8, 0x01020304).unwrap();
+        mem.store_i64(16, 0x0102030405060708).unwrap();
+        mem.store_f32(32, 1.0).unwrap();
+        mem.store_f64(40, -2.0).unwrap();
+        let bytes = mem.as_slice();
+        assert_eq!(bytes[0..2], [0x02, 0x01]);
+        assert_eq!(bytes[8..12], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(
+            bytes[16..24],
+            [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+        assert_eq!(bytes[32..36], [0x00, 0x00, 0x80, 0x3F]);
+        assert_eq!(bytes[40..48], [0, 0, 0, 0, 0, 0, 0x00, 0xC0]);
+    }
+
+    #[test]
+    fn loads_read_little_endian_bytes() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.init_data(0, &[0x02, 0x01]).unwrap();
+        mem.init_data(8, &[0x04, 0x03, 0x02, 0x01]).unwrap();
+        mem.init_data(16, &[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x81])
+            .unwrap();
+        mem.init_data(32, &[0x00, 0x00, 0x80, 0x3F]).unwrap();
+        mem.init_data(40, &[0, 0, 0, 0, 0, 0, 0x00, 0xC0]).unwrap();
+        assert_eq!(mem.load_u16(0), Ok(0x0102));
+        assert_eq!(mem.load_i32(8), Ok(0x01020304));
+        assert_eq!(mem.load_i64(16), Ok(0x8102030405060708u64 as i64));
+        assert_eq!(mem.load_f32(32), Ok(1.0));
+        assert_eq!(mem.load_f64(40), Ok(-2.0));
+    }
+
+    #[test]
+    fn unaligned_access_is_little_endian() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.store_i32(1, 0x0A0B0C0D).unwrap();
+        assert_eq!(mem.as_slice()[0..6], [0, 0x0D, 0x0C, 0x0B, 0x0A, 0]);
+        // Straddles the stored word and the zero bytes after it
+        assert_eq!(mem.load_u16(4), Ok(0x000A));
+        assert_eq!(mem.load_i32(2), Ok(0x000A0B0C));
+    }
+
+    #[test]
+    fn unchecked_access_is_little_endian() {
+        let mut mem = Mem::try_new(1).unwrap();
+        unsafe {
+            mem.store_i32_unchecked(3, 0x01020304);
+            mem.store_i64_unchecked(9, 0x0102030405060708);
+            assert_eq!(mem.load_i32_unchecked(3), 0x01020304);
+            assert_eq!(mem.load_i64_unchecked(9), 0x0102030405060708);
+        }
+        let bytes = mem.as_slice();
+        assert_eq!(bytes[3..7], [0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(
+            bytes[9..17],
+            [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+        );
+    }
+
     // ── active_pages boundary ──
 
     #[test]
@@ -806,6 +1035,40 @@ mod tests {
         assert_eq!(mem.load_u8(5).unwrap(), 0xAB);
     }
 
+    // ── write_bytes / read_bytes ──
+
+    #[test]
+    fn write_then_read_bytes_round_trips() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.write_bytes(100, b"herkos").unwrap();
+        let mut buf = [0u8; 6];
+        mem.read_bytes(100, &mut buf).unwrap();
+        assert_eq!(&buf, b"herkos");
+    }
+
+    #[test]
+    fn read_bytes_out_of_bounds() {
+        let mem = Mem::try_new(1).unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            mem.read_bytes(PAGE_SIZE - 4, &mut buf),
+            Err(WasmTrap::OutOfBounds)
+        );
+        assert_eq!(
+            mem.read_bytes(usize::MAX, &mut buf),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn write_bytes_out_of_bounds() {
+        let mut mem = Mem::try_new(1).unwrap();
+        assert_eq!(
+            mem.write_bytes(PAGE_SIZE - 1, &[1, 2]),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+
     // ── fill ──
 
     #[test]
@@ -900,6 +1163,67 @@ mod tests {
         assert_eq!(mem.load_u8(2), Ok(0x03));
         assert_eq!(mem.load_u8(3), Ok(0x04));
     }
+
+    // ── write protection ──
+
+    #[test]
+    fn stores_into_protected_range_trap() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.init_data(16, b"const").unwrap();
+        mem.protect(16, 5).unwrap();
+        assert_eq!(mem.store_u8(16, 0), Err(WasmTrap::WriteProtected));
+        // Straddling either end
+        assert_eq!(mem.store_i32(13, 0), Err(WasmTrap::WriteProtected));
+        assert_eq!(mem.store_i64(20, 0), Err(WasmTrap::WriteProtected));
+        assert_eq!(mem.store_u16(21, 0), Ok(()));
+        assert_eq!(mem.store_i32(12, 0), Ok(()));
+        // Loads are unaffected, and the bytes unchanged
+        assert_eq!(mem.load_u8(16), Ok(b'c'));
+        let mut buf = [0u8; 5];
+        mem.read_bytes(16, &mut buf).unwrap();
+        assert_eq!(&buf, b"const");
+    }
+
+    #[test]
+    fn bulk_writes_into_protected_range_trap() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.protect(100, 10).unwrap();
+        assert_eq!(mem.fill(90, 1, 11), Err(WasmTrap::WriteProtected));
+        assert_eq!(mem.memory_copy(105, 0, 2), Err(WasmTrap::WriteProtected));
+        assert_eq!(mem.write_bytes(109, b"ab"), Err(WasmTrap::WriteProtected));
+        assert_eq!(
+            mem.init_data_partial(99, b"xy", 0, 2),
+            Err(WasmTrap::WriteProtected)
+        );
+        // Reading from the range and empty writes into it are fine
+        assert_eq!(mem.memory_copy(0, 100, 10), Ok(()));
+        assert_eq!(mem.fill(100, 1, 0), Ok(()));
+        mem.unprotect_all();
+        assert_eq!(mem.fill(90, 1, 11), Ok(()));
+    }
+
+    #[test]
+    fn protect_merges_touching_ranges() {
+        let mut mem = Mem::try_new(1).unwrap();
+        mem.protect(0, 4).unwrap();
+        mem.protect(8, 4).unwrap();
+        mem.protect(4, 4).unwrap();
+        mem.protect(100, 0).unwrap();
+        assert_eq!(mem.protected_ranges(), &[(0, 12)]);
+        assert_eq!(mem.protect(PAGE_SIZE - 1, 2), Err(WasmTrap::OutOfBounds));
+    }
+
+    #[test]
+    fn protect_fails_beyond_max_ranges() {
+        let mut mem = Mem::try_new(1).unwrap();
+        for i in 0..MAX_PROTECTED_RANGES {
+            mem.protect(i * 10, 1).unwrap();
+        }
+        assert_eq!(mem.protect(1000, 1), Err(WasmTrap::OutOfBounds));
+        // Growing an existing range still works
+        assert_eq!(mem.protect(1, 1), Ok(()));
+        assert_eq!(mem.protected_ranges().len(), MAX_PROTECTED_RANGES);
+    }
 }
 
 // ── Kani Formal Verification Proofs ──────────────────────────────────────
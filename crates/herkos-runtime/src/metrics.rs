@@ -0,0 +1,159 @@
+//! Size and instrumentation of a transpiled module.
+//!
+//! Every generated module has a `pub const fn metrics() -> ModuleMetrics`,
+//! so a host loading many transpiled plugins can inventory them — how big
+//! each is, which were built with coverage or memory observation — without
+//! parsing the original binaries.
+
+use core::fmt;
+
+/// Static facts about a transpiled module, fixed at transpile time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleMetrics {
+    /// Functions defined by the module (the entries of `SYMBOLS`).
+    pub functions: u32,
+    /// Function imports.
+    pub imports: u32,
+    /// Export names of functions.
+    pub exports: u32,
+    /// Basic blocks of the generated functions.
+    pub blocks: u32,
+    /// Initial size of the table, in entries.
+    pub table_size: u32,
+    /// Initial size of the linear memory, in pages.
+    pub memory_pages: u32,
+    /// Bytes of data segments, active and passive.
+    pub data_bytes: u32,
+    /// Instrumentation the module was generated with.
+    pub instrumentation: Instrumentation,
+}
+
+/// Instrumentation options a module was generated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Instrumentation {
+    /// `--observe-memory`: loads and stores report to a `MemoryObserver`.
+    pub observe_memory: bool,
+    /// `--record-replay`: host calls can be recorded and replayed.
+    pub record_replay: bool,
+    /// `--yield-points`: loop headers call the host's `maybe_yield`.
+    pub yield_points: bool,
+    /// `--heap-profile`: allocator calls are counted.
+    pub heap_profile: bool,
+    /// `--debug-memory`: freed memory is poisoned and allocations guarded.
+    pub debug_memory: bool,
+    /// `--coverage`: basic blocks count their executions.
+    pub coverage: bool,
+    /// `--bounds-profile`: memory accesses count their bounds checks.
+    pub bounds_profile: bool,
+}
+
+impl Instrumentation {
+    /// No instrumentation.
+    pub const NONE: Self = Self {
+        observe_memory: false,
+        record_replay: false,
+        yield_points: false,
+        heap_profile: false,
+        debug_memory: false,
+        coverage: false,
+        bounds_profile: false,
+    };
+
+    /// Whether any instrumentation is enabled.
+    pub const fn any(&self) -> bool {
+        self.observe_memory
+            || self.record_replay
+            || self.yield_points
+            || self.heap_profile
+            || self.debug_memory
+            || self.coverage
+            || self.bounds_profile
+    }
+}
+
+/// Formats as the names of the enabled options, comma separated, or `none`.
+impl fmt::Display for Instrumentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            (self.observe_memory, "observe-memory"),
+            (self.record_replay, "record-replay"),
+            (self.yield_points, "yield-points"),
+            (self.heap_profile, "heap-profile"),
+            (self.debug_memory, "debug-memory"),
+            (self.coverage, "coverage"),
+            (self.bounds_profile, "bounds-profile"),
+        ];
+        let mut first = true;
+        for (_, name) in flags.iter().filter(|(on, _)| *on) {
+            if !first {
+                f.write_str(", ")?;
+            }
+            f.write_str(name)?;
+            first = false;
+        }
+        if first {
+            f.write_str("none")?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats as one line: `N functions (M blocks), I imports, E exports,
+/// table T, P pages, D data bytes, instrumentation: ...`.
+impl fmt::Display for ModuleMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} functions ({} blocks), {} imports, {} exports, table {}, {} pages, \
+             {} data bytes, instrumentation: {}",
+            self.functions,
+            self.blocks,
+            self.imports,
+            self.exports,
+            self.table_size,
+            self.memory_pages,
+            self.data_bytes,
+            self.instrumentation
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+
+    const METRICS: ModuleMetrics = ModuleMetrics {
+        functions: 3,
+        imports: 1,
+        exports: 2,
+        blocks: 7,
+        table_size: 4,
+        memory_pages: 1,
+        data_bytes: 12,
+        instrumentation: Instrumentation::NONE,
+    };
+
+    #[test]
+    fn display_uninstrumented() {
+        assert!(!METRICS.instrumentation.any());
+        assert_eq!(
+            format!("{}", METRICS),
+            "3 functions (7 blocks), 1 imports, 2 exports, table 4, 1 pages, \
+             12 data bytes, instrumentation: none"
+        );
+    }
+
+    #[test]
+    fn display_lists_enabled_instrumentation() {
+        let instrumentation = Instrumentation {
+            yield_points: true,
+            coverage: true,
+            ..Instrumentation::NONE
+        };
+        assert!(instrumentation.any());
+        assert_eq!(format!("{}", instrumentation), "yield-points, coverage");
+    }
+}
@@ -8,7 +8,8 @@
 //! concrete exported/internal functions.
 
 use crate::memory::IsolatedMemory;
-use crate::table::Table;
+use crate::table::{FuncKind, Table};
+use core::fmt;
 
 /// A module that defines its own memory (§4.1).
 ///
@@ -82,6 +83,16 @@ impl<G, const MAX_PAGES: usize, const TABLE_SIZE: usize> Module<G, MAX_PAGES, TA
         }
         Ok(())
     }
+
+    /// Summary of the instance state for debugging: the globals, the memory
+    /// size and the occupied table slots. Formats with `Display`.
+    pub fn debug_dump(&self) -> ModuleDump<'_, G, TABLE_SIZE> {
+        ModuleDump {
+            globals: &self.globals,
+            memory_pages: Some((self.memory.page_count(), MAX_PAGES)),
+            table: &self.table,
+        }
+    }
 }
 
 /// A module that does NOT define its own memory (§4.1).
@@ -105,6 +116,62 @@ impl<G, const TABLE_SIZE: usize> LibraryModule<G, TABLE_SIZE> {
     pub const fn new(globals: G, table: Table<TABLE_SIZE>) -> Self {
         Self { globals, table }
     }
+
+    /// Summary of the instance state for debugging, as
+    /// [`Module::debug_dump`], without memory.
+    pub fn debug_dump(&self) -> ModuleDump<'_, G, TABLE_SIZE> {
+        ModuleDump {
+            globals: &self.globals,
+            memory_pages: None,
+            table: &self.table,
+        }
+    }
+}
+
+/// Instance state returned by [`Module::debug_dump`] and
+/// [`LibraryModule::debug_dump`].
+///
+/// Formats as one line per item, with the globals as `{:?}` formats them:
+///
+/// ```text
+/// globals: Globals { g0: 42, g1: -1 }
+/// memory: 1 of 2 pages
+/// table: 2 of 4 slots occupied (max 8)
+///   [0] func 3 (type 0)
+///   [2] import 1 (type 1)
+/// ```
+pub struct ModuleDump<'a, G, const TABLE_SIZE: usize> {
+    globals: &'a G,
+    /// Current and maximum pages, for a module owning its memory.
+    memory_pages: Option<(usize, usize)>,
+    table: &'a Table<TABLE_SIZE>,
+}
+
+impl<G: fmt::Debug, const TABLE_SIZE: usize> fmt::Display for ModuleDump<'_, G, TABLE_SIZE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "globals: {:?}", self.globals)?;
+        if let Some((pages, max)) = self.memory_pages {
+            writeln!(f, "memory: {pages} of {max} pages")?;
+        }
+        writeln!(
+            f,
+            "table: {} of {} slots occupied (max {TABLE_SIZE})",
+            self.table.occupied().count(),
+            self.table.size()
+        )?;
+        for (index, entry) in self.table.occupied() {
+            let kind = match entry.kind {
+                FuncKind::Local => "func",
+                FuncKind::Import => "import",
+            };
+            writeln!(
+                f,
+                "  [{index}] {kind} {} (type {})",
+                entry.func_index, entry.type_index
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +180,9 @@ mod tests {
     use crate::table::FuncRef;
     use crate::WasmTrap;
 
+    extern crate std;
+    use std::string::ToString;
+
     /// Example transpiler-generated globals struct.
     #[derive(Debug, Default, PartialEq)]
     struct TestGlobals {
@@ -180,15 +250,7 @@ mod tests {
     #[test]
     fn module_with_table() {
         let mut table = Table::<4>::try_new(2).unwrap();
-        table
-            .set(
-                0,
-                Some(FuncRef {
-                    type_index: 0,
-                    func_index: 3,
-                }),
-            )
-            .unwrap();
+        table.set(0, Some(FuncRef::local(0, 3))).unwrap();
 
         let module = Module::<(), 2, 4>::try_new(1, (), table).unwrap();
         let entry = module.table.get(0).unwrap();
@@ -202,4 +264,27 @@ mod tests {
         let lib = LibraryModule::<(), 0>::new((), Table::try_new(0).unwrap());
         assert_eq!(lib.globals, ());
     }
+
+    #[test]
+    fn debug_dump_summarizes_state() {
+        let mut table = Table::<8>::try_new(4).unwrap();
+        table.set(0, Some(FuncRef::local(0, 3))).unwrap();
+        table.set(2, Some(FuncRef::import(1, 1))).unwrap();
+        let module =
+            Module::<TestGlobals, 2, 8>::try_new(1, TestGlobals { g0: 42, g1: -1 }, table).unwrap();
+        assert_eq!(
+            module.debug_dump().to_string(),
+            "globals: TestGlobals { g0: 42, g1: -1 }\n\
+             memory: 1 of 2 pages\n\
+             table: 2 of 4 slots occupied (max 8)\n  \
+             [0] func 3 (type 0)\n  \
+             [2] import 1 (type 1)\n"
+        );
+
+        let lib = LibraryModule::<(), 0>::new((), Table::try_new(0).unwrap());
+        assert_eq!(
+            lib.debug_dump().to_string(),
+            "globals: ()\ntable: 0 of 0 slots occupied (max 0)\n"
+        );
+    }
 }
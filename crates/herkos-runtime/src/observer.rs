@@ -0,0 +1,201 @@
+//! Memory access observation for debugging (`observe` feature).
+//!
+//! Code transpiled with `--observe-memory` calls the host's
+//! [`MemoryObserver`] methods before every load and store, with the
+//! effective address and access size. [`Watchpoints`] filters those calls
+//! down to a fixed set of watched address ranges, so a host can set data
+//! watchpoints on guest memory:
+//!
+//! ```
+//! use herkos_runtime::{MemoryObserver, Watchpoints};
+//!
+//! #[derive(Default)]
+//! struct Log {
+//!     stores: usize,
+//! }
+//!
+//! impl MemoryObserver for Log {
+//!     fn on_store(&mut self, _addr: usize, _len: usize) {
+//!         self.stores += 1;
+//!     }
+//! }
+//!
+//! let mut watch: Watchpoints<Log, 4> = Watchpoints::new(Log::default());
+//! assert!(watch.watch(0x100..0x104));
+//! watch.on_store(0x102, 1);
+//! watch.on_store(0x200, 4);
+//! assert_eq!(watch.observer().stores, 1);
+//! ```
+
+use core::ops::Range;
+
+/// Receives the memory accesses of instrumented code.
+///
+/// `addr` is the effective address (base plus static offset) and `len` the
+/// number of bytes accessed. Calls happen before the bounds check, so
+/// accesses that trap are reported too. Both methods default to no-ops.
+pub trait MemoryObserver {
+    /// A load of `len` bytes at `addr`, or the source of a `memory.copy`.
+    fn on_load(&mut self, addr: usize, len: usize) {
+        let _ = (addr, len);
+    }
+
+    /// A store of `len` bytes at `addr`, or the destination of a bulk
+    /// memory operation.
+    fn on_store(&mut self, addr: usize, len: usize) {
+        let _ = (addr, len);
+    }
+}
+
+impl MemoryObserver for crate::NoHost {}
+
+/// Up to `N` watched address ranges in front of an observer.
+///
+/// Forwards an access to `O` only if it overlaps a watched range.
+pub struct Watchpoints<O, const N: usize> {
+    ranges: [(usize, usize); N],
+    len: usize,
+    observer: O,
+}
+
+impl<O: MemoryObserver, const N: usize> Watchpoints<O, N> {
+    /// No watched ranges yet.
+    pub const fn new(observer: O) -> Self {
+        Self {
+            ranges: [(0, 0); N],
+            len: 0,
+            observer,
+        }
+    }
+
+    /// Watch `range`. Returns `false`, watching nothing new, if all `N`
+    /// slots are in use or the range is empty.
+    pub fn watch(&mut self, range: Range<usize>) -> bool {
+        if self.len == N || range.is_empty() {
+            return false;
+        }
+        self.ranges[self.len] = (range.start, range.end);
+        self.len += 1;
+        true
+    }
+
+    /// Stop watching `range`, which must match a watched range exactly.
+    /// Returns whether it was watched.
+    pub fn unwatch(&mut self, range: Range<usize>) -> bool {
+        let Some(pos) = self.ranges[..self.len]
+            .iter()
+            .position(|&r| r == (range.start, range.end))
+        else {
+            return false;
+        };
+        self.ranges.copy_within(pos + 1..self.len, pos);
+        self.len -= 1;
+        true
+    }
+
+    /// Stop watching every range.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The observer accesses are forwarded to.
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// Mutable access to the observer.
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Whether `[addr, addr + len)` overlaps a watched range.
+    #[inline]
+    fn hits(&self, addr: usize, len: usize) -> bool {
+        let end = addr.saturating_add(len);
+        self.ranges[..self.len]
+            .iter()
+            .any(|&(start, stop)| addr < stop && start < end)
+    }
+}
+
+impl<O: MemoryObserver, const N: usize> MemoryObserver for Watchpoints<O, N> {
+    #[inline]
+    fn on_load(&mut self, addr: usize, len: usize) {
+        if self.hits(addr, len) {
+            self.observer.on_load(addr, len);
+        }
+    }
+
+    #[inline]
+    fn on_store(&mut self, addr: usize, len: usize) {
+        if self.hits(addr, len) {
+            self.observer.on_store(addr, len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Record {
+        loads: [(usize, usize); 4],
+        num_loads: usize,
+        stores: usize,
+    }
+
+    impl MemoryObserver for Record {
+        fn on_load(&mut self, addr: usize, len: usize) {
+            self.loads[self.num_loads] = (addr, len);
+            self.num_loads += 1;
+        }
+
+        fn on_store(&mut self, _addr: usize, _len: usize) {
+            self.stores += 1;
+        }
+    }
+
+    #[test]
+    fn only_overlapping_accesses_are_forwarded() {
+        let mut watch: Watchpoints<Record, 2> = Watchpoints::new(Record::default());
+        assert!(watch.watch(16..20));
+        watch.on_load(12, 4); // ends right before the range
+        watch.on_load(14, 4); // straddles its start
+        watch.on_load(19, 8); // straddles its end
+        watch.on_load(20, 1); // right after it
+        watch.on_store(16, 1);
+        watch.on_store(0, 1);
+        let record = watch.observer();
+        assert_eq!(&record.loads[..record.num_loads], &[(14, 4), (19, 8)]);
+        assert_eq!(record.stores, 1);
+    }
+
+    #[test]
+    fn capacity_and_unwatch() {
+        let mut watch: Watchpoints<Record, 2> = Watchpoints::new(Record::default());
+        assert!(!watch.watch(8..8));
+        assert!(watch.watch(0..4));
+        assert!(watch.watch(8..12));
+        assert!(!watch.watch(16..20));
+
+        assert!(!watch.unwatch(0..2));
+        assert!(watch.unwatch(0..4));
+        watch.on_store(0, 4);
+        watch.on_store(8, 4);
+        assert_eq!(watch.observer().stores, 1);
+
+        watch.clear();
+        watch.on_store(8, 4);
+        assert_eq!(watch.observer().stores, 1);
+        assert!(watch.watch(16..20));
+    }
+
+    #[test]
+    fn address_at_the_top_does_not_overflow() {
+        let mut watch: Watchpoints<Record, 1> = Watchpoints::new(Record::default());
+        assert!(watch.watch(usize::MAX - 4..usize::MAX));
+        watch.on_store(usize::MAX - 1, 8);
+        assert_eq!(watch.observer().stores, 1);
+    }
+}
@@ -9,10 +9,23 @@
 //!
 //! ## Integer division / remainder
 //!
-//! `i32::checked_div` / `checked_rem` return `None` for both divide-by-zero
-//! and signed overflow (`i32::MIN / -1`). Both cases trap in Wasm. Note that
-//! `checked_rem(i32::MIN, -1)` returns `Some(0)`, which is the correct Wasm
-//! result (the remainder after a would-be-overflowing division is 0).
+//! `i32::checked_div` returns `None` for both divide-by-zero and signed
+//! overflow (`i32::MIN / -1`). Both cases trap in Wasm, but with different
+//! traps: "integer divide by zero" and "integer overflow". The signed
+//! functions tell them apart before dividing. `i32::MIN rem_s -1` does not
+//! trap at all: the remainder after a would-be-overflowing division is 0.
+//!
+//! ## Rounding and square root
+//!
+//! `f32::floor`, `ceil`, `trunc`, `sqrt` and `round_ties_even` live in `std`
+//! (they call into `libm`). The `wasm_*` versions below need only `core`, for
+//! code generated with `--portable`. `sqrt` is correctly rounded, as the
+//! spec requires.
+//!
+//! ## Effective addresses
+//!
+//! [`effective_address`] adds a memory access's static offset to its `i32`
+//! address without overflowing on 32-bit targets.
 //!
 //! All functions are `#[inline(never)]` (outline pattern §13.3). There are no
 //! generics here, so the public function IS the inner function — no wrapper
@@ -47,9 +60,12 @@ pub fn i32_trunc_f32_u(v: f32) -> WasmResult<i32> {
 }
 
 /// Wasm `i32.trunc_f64_s`: truncate f64 toward zero to i32, trapping on NaN/overflow.
+///
+/// The lower bound is `<= -2147483649.0` (not `< -2147483648.0`): f64 can
+/// represent values like `-2147483648.5`, which truncate to `i32::MIN`.
 #[inline(never)]
 pub fn i32_trunc_f64_s(v: f64) -> WasmResult<i32> {
-    if v.is_nan() || !(-2147483648.0f64..2147483648.0f64).contains(&v) {
+    if v.is_nan() || v >= 2147483648.0f64 || v <= -2147483649.0f64 {
         return Err(WasmTrap::IntegerOverflow);
     }
     Ok(v as i32)
@@ -108,11 +124,17 @@ pub fn i64_trunc_f64_u(v: f64) -> WasmResult<i64> {
 // ── i32 division / remainder ──────────────────────────────────────────────────
 
 /// Wasm `i32.div_s`: signed integer division, trapping on divide-by-zero or
-/// signed overflow (`i32::MIN / -1`). Both cases produce `None` from
-/// `checked_div`, which maps to `DivisionByZero`.
+/// signed overflow (`i32::MIN / -1`), which trap with `DivisionByZero` and
+/// `IntegerOverflow` respectively.
 #[inline(never)]
-pub fn i32_div_s(lhs: i32, rhs: i32) -> WasmResult<i32> {
-    lhs.checked_div(rhs).ok_or(WasmTrap::DivisionByZero)
+pub const fn i32_div_s(lhs: i32, rhs: i32) -> WasmResult<i32> {
+    if rhs == 0 {
+        return Err(WasmTrap::DivisionByZero);
+    }
+    if lhs == i32::MIN && rhs == -1 {
+        return Err(WasmTrap::IntegerOverflow);
+    }
+    Ok(lhs / rhs)
 }
 
 /// Wasm `i32.div_u`: unsigned integer division, trapping on divide-by-zero.
@@ -155,10 +177,16 @@ pub fn i32_rem_u(lhs: i32, rhs: i32) -> WasmResult<i32> {
 // ── i64 division / remainder ──────────────────────────────────────────────────
 
 /// Wasm `i64.div_s`: signed integer division, trapping on divide-by-zero or
-/// signed overflow (`i64::MIN / -1`).
+/// signed overflow (`i64::MIN / -1`), like `i32_div_s`.
 #[inline(never)]
-pub fn i64_div_s(lhs: i64, rhs: i64) -> WasmResult<i64> {
-    lhs.checked_div(rhs).ok_or(WasmTrap::DivisionByZero)
+pub const fn i64_div_s(lhs: i64, rhs: i64) -> WasmResult<i64> {
+    if rhs == 0 {
+        return Err(WasmTrap::DivisionByZero);
+    }
+    if lhs == i64::MIN && rhs == -1 {
+        return Err(WasmTrap::IntegerOverflow);
+    }
+    Ok(lhs / rhs)
 }
 
 /// Wasm `i64.div_u`: unsigned integer division, trapping on divide-by-zero.
@@ -335,6 +363,142 @@ pub fn wasm_nearest_f64(v: f64) -> f64 {
     }
 }
 
+/// Wasm `f32.trunc` — round toward zero, keeping the sign of zero.
+pub fn wasm_trunc_f32(v: f32) -> f32 {
+    // Floats >= 2^23 (and NaN, infinities) have no fractional bits
+    if v.is_nan() || v.abs() >= 8_388_608.0 {
+        return v;
+    }
+    f32::copysign((v as i32) as f32, v)
+}
+
+/// Wasm `f32.floor` — round toward negative infinity.
+pub fn wasm_floor_f32(v: f32) -> f32 {
+    let t = wasm_trunc_f32(v);
+    if t > v {
+        t - 1.0
+    } else {
+        t
+    }
+}
+
+/// Wasm `f32.ceil` — round toward positive infinity, keeping `-0.0` for
+/// inputs in `(-1, 0]`.
+pub fn wasm_ceil_f32(v: f32) -> f32 {
+    let t = wasm_trunc_f32(v);
+    if t < v {
+        t + 1.0
+    } else {
+        t
+    }
+}
+
+/// Wasm `f64.trunc` — round toward zero, keeping the sign of zero.
+pub fn wasm_trunc_f64(v: f64) -> f64 {
+    // Floats >= 2^52 (and NaN, infinities) have no fractional bits
+    if v.is_nan() || v.abs() >= 4_503_599_627_370_496.0 {
+        return v;
+    }
+    f64::copysign((v as i64) as f64, v)
+}
+
+/// Wasm `f64.floor` — round toward negative infinity.
+pub fn wasm_floor_f64(v: f64) -> f64 {
+    let t = wasm_trunc_f64(v);
+    if t > v {
+        t - 1.0
+    } else {
+        t
+    }
+}
+
+/// Wasm `f64.ceil` — round toward positive infinity, keeping `-0.0` for
+/// inputs in `(-1, 0]`.
+pub fn wasm_ceil_f64(v: f64) -> f64 {
+    let t = wasm_trunc_f64(v);
+    if t < v {
+        t + 1.0
+    } else {
+        t
+    }
+}
+
+/// Wasm `f32.sqrt`, correctly rounded.
+///
+/// Rounding the correctly rounded `f64` root to `f32` is exact: `f64` carries
+/// more than twice the bits of `f32` plus two, so no double rounding occurs.
+pub fn wasm_sqrt_f32(v: f32) -> f32 {
+    wasm_sqrt_f64(v as f64) as f32
+}
+
+/// Wasm `f64.sqrt`, correctly rounded (round to nearest, ties to even).
+///
+/// Takes the integer square root of the significand, scaled to carry two
+/// extra bits, and rounds with the remainder as sticky bit.
+#[inline(never)]
+pub fn wasm_sqrt_f64(v: f64) -> f64 {
+    if v.is_nan() || v < 0.0 {
+        return f64::NAN;
+    }
+    if v == 0.0 || v.is_infinite() {
+        return v;
+    }
+    // v = m * 2^e with m in [2^52, 2^53)
+    let bits = v.to_bits();
+    let field = ((bits >> 52) & 0x7ff) as i64;
+    let (mut m, mut e) = if field == 0 {
+        (bits & ((1 << 52) - 1), -1074)
+    } else {
+        ((bits & ((1 << 52) - 1)) | (1 << 52), field - 1075)
+    };
+    while m < (1 << 52) {
+        m <<= 1;
+        e -= 1;
+    }
+    // Even exponent, m in [2^52, 2^54)
+    if e % 2 != 0 {
+        m <<= 1;
+        e -= 1;
+    }
+    // isqrt of m * 2^56, in [2^54, 2^55): 53 bits, a round bit and a guard bit
+    let mut rem = (m as u128) << 56;
+    let mut root: u128 = 0;
+    let mut bit: u128 = 1 << 108;
+    while bit != 0 {
+        if rem >= root + bit {
+            rem -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    let mut q = (root >> 2) as u64;
+    let round = (root >> 1) & 1 == 1;
+    let sticky = root & 1 == 1 || rem != 0;
+    if round && (sticky || q & 1 == 1) {
+        q += 1;
+    }
+    // sqrt(v) = q * 2^(e/2 - 26); a carry out of q bumps the exponent
+    let exponent = (e / 2 + 1049) as u64;
+    f64::from_bits((exponent << 52) + (q - (1 << 52)))
+}
+
+// ── Effective addresses ──────────────────────────────────────────────────────
+
+/// Effective address of a memory access: the `i32` address as unsigned plus
+/// the static `offset`.
+///
+/// Past 4 GiB the access is out of bounds for any 32-bit memory, so the sum
+/// saturates to `usize::MAX` instead of wrapping, on every target width.
+#[inline(always)]
+pub const fn effective_address(addr: i32, offset: u32) -> usize {
+    match (addr as u32).checked_add(offset) {
+        Some(address) => address as usize,
+        None => usize::MAX,
+    }
+}
+
 // ── Tests ─────────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -444,6 +608,11 @@ mod tests {
         assert_eq!(i32_trunc_f64_s(-2147483648.0f64).unwrap(), i32::MIN);
     }
 
+    #[test]
+    fn i32_trunc_f64_s_below_min_truncates_to_min() {
+        assert_eq!(i32_trunc_f64_s(-2147483648.9f64).unwrap(), i32::MIN);
+    }
+
     #[test]
     fn i32_trunc_f64_s_overflow() {
         assert_eq!(
@@ -602,7 +771,7 @@ mod tests {
     #[test]
     fn i32_div_s_min_over_neg_one() {
         // i32::MIN / -1 overflows → trap
-        assert_eq!(i32_div_s(i32::MIN, -1), Err(WasmTrap::DivisionByZero));
+        assert_eq!(i32_div_s(i32::MIN, -1), Err(WasmTrap::IntegerOverflow));
     }
 
     // ── i32_div_u ────────────────────────────────────────────────────────────
@@ -672,7 +841,7 @@ mod tests {
 
     #[test]
     fn i64_div_s_min_over_neg_one() {
-        assert_eq!(i64_div_s(i64::MIN, -1), Err(WasmTrap::DivisionByZero));
+        assert_eq!(i64_div_s(i64::MIN, -1), Err(WasmTrap::IntegerOverflow));
     }
 
     // ── i64_div_u ────────────────────────────────────────────────────────────
@@ -907,6 +1076,236 @@ mod tests {
         assert!(result.is_sign_positive());
     }
 
+    // ── min/max spec vectors ─────────────────────────────────────────────────
+
+    /// `(a, b, min, max)` from the Wasm testsuite (`f32.wast`/`f64.wast`),
+    /// compared bit for bit so the sign of zero counts.
+    #[test]
+    fn wasm_min_max_f32_spec_vectors() {
+        let denorm = f32::from_bits(1);
+        let cases = [
+            (-0.0, -0.0, -0.0, -0.0),
+            (-0.0, 0.0, -0.0, 0.0),
+            (0.0, -0.0, -0.0, 0.0),
+            (0.0, 0.0, 0.0, 0.0),
+            (-denorm, denorm, -denorm, denorm),
+            (denorm, -0.0, -0.0, denorm),
+            (-denorm, 0.0, -denorm, 0.0),
+            (
+                f32::MIN_POSITIVE,
+                -f32::MIN_POSITIVE,
+                -f32::MIN_POSITIVE,
+                f32::MIN_POSITIVE,
+            ),
+            (f32::MAX, f32::INFINITY, f32::MAX, f32::INFINITY),
+            (f32::NEG_INFINITY, f32::MIN, f32::NEG_INFINITY, f32::MIN),
+            (
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+            ),
+        ];
+        for (a, b, min, max) in cases {
+            assert_eq!(wasm_min_f32(a, b).to_bits(), min.to_bits(), "min({a}, {b})");
+            assert_eq!(wasm_max_f32(a, b).to_bits(), max.to_bits(), "max({a}, {b})");
+        }
+        for x in [
+            -0.0,
+            0.0,
+            denorm,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            -f32::NAN,
+        ] {
+            assert!(wasm_min_f32(x, f32::NAN).is_nan(), "min({x}, nan)");
+            assert!(wasm_min_f32(-f32::NAN, x).is_nan(), "min(-nan, {x})");
+            assert!(wasm_max_f32(x, f32::NAN).is_nan(), "max({x}, nan)");
+            assert!(wasm_max_f32(-f32::NAN, x).is_nan(), "max(-nan, {x})");
+        }
+    }
+
+    #[test]
+    fn wasm_min_max_f64_spec_vectors() {
+        let denorm = f64::from_bits(1);
+        let cases = [
+            (-0.0, -0.0, -0.0, -0.0),
+            (-0.0, 0.0, -0.0, 0.0),
+            (0.0, -0.0, -0.0, 0.0),
+            (0.0, 0.0, 0.0, 0.0),
+            (-denorm, denorm, -denorm, denorm),
+            (denorm, -0.0, -0.0, denorm),
+            (-denorm, 0.0, -denorm, 0.0),
+            (
+                f64::MIN_POSITIVE,
+                -f64::MIN_POSITIVE,
+                -f64::MIN_POSITIVE,
+                f64::MIN_POSITIVE,
+            ),
+            (f64::MAX, f64::INFINITY, f64::MAX, f64::INFINITY),
+            (f64::NEG_INFINITY, f64::MIN, f64::NEG_INFINITY, f64::MIN),
+            (
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+            ),
+        ];
+        for (a, b, min, max) in cases {
+            assert_eq!(wasm_min_f64(a, b).to_bits(), min.to_bits(), "min({a}, {b})");
+            assert_eq!(wasm_max_f64(a, b).to_bits(), max.to_bits(), "max({a}, {b})");
+        }
+        for x in [
+            -0.0,
+            0.0,
+            denorm,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            -f64::NAN,
+        ] {
+            assert!(wasm_min_f64(x, f64::NAN).is_nan(), "min({x}, nan)");
+            assert!(wasm_min_f64(-f64::NAN, x).is_nan(), "min(-nan, {x})");
+            assert!(wasm_max_f64(x, f64::NAN).is_nan(), "max({x}, nan)");
+            assert!(wasm_max_f64(-f64::NAN, x).is_nan(), "max(-nan, {x})");
+        }
+    }
+
+    // ── rounding and sqrt without std ────────────────────────────────────────
+
+    #[test]
+    fn wasm_rounding_f32_matches_std() {
+        extern crate std;
+        let values = [
+            0.0,
+            -0.0,
+            0.5,
+            -0.5,
+            1.5,
+            -1.5,
+            2.5,
+            -0.99,
+            123.456,
+            -123.456,
+            8_388_607.5,
+            8_388_608.0,
+            -8_388_609.0,
+            1e30,
+            -1e30,
+            f32::MIN_POSITIVE,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+        ];
+        for v in values {
+            assert_eq!(
+                wasm_trunc_f32(v).to_bits(),
+                v.trunc().to_bits(),
+                "trunc({v})"
+            );
+            assert_eq!(
+                wasm_floor_f32(v).to_bits(),
+                v.floor().to_bits(),
+                "floor({v})"
+            );
+            assert_eq!(wasm_ceil_f32(v).to_bits(), v.ceil().to_bits(), "ceil({v})");
+        }
+        assert!(wasm_trunc_f32(f32::NAN).is_nan());
+        assert!(wasm_floor_f32(f32::NAN).is_nan());
+        assert!(wasm_ceil_f32(f32::NAN).is_nan());
+    }
+
+    #[test]
+    fn wasm_rounding_f64_matches_std() {
+        extern crate std;
+        let values = [
+            0.0,
+            -0.0,
+            0.5,
+            -0.5,
+            1.5,
+            -1.5,
+            2.5,
+            -0.99,
+            123.456,
+            -123.456,
+            4_503_599_627_370_495.5,
+            4_503_599_627_370_496.0,
+            -1e300,
+            1e300,
+            f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        for v in values {
+            assert_eq!(
+                wasm_trunc_f64(v).to_bits(),
+                v.trunc().to_bits(),
+                "trunc({v})"
+            );
+            assert_eq!(
+                wasm_floor_f64(v).to_bits(),
+                v.floor().to_bits(),
+                "floor({v})"
+            );
+            assert_eq!(wasm_ceil_f64(v).to_bits(), v.ceil().to_bits(), "ceil({v})");
+        }
+        assert!(wasm_trunc_f64(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn wasm_sqrt_is_correctly_rounded() {
+        extern crate std;
+        let mut values = [
+            0.0,
+            -0.0,
+            1.0,
+            2.0,
+            3.0,
+            4.0,
+            0.25,
+            1e-300,
+            1e300,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            f64::from_bits(3),
+            f64::INFINITY,
+        ]
+        .to_vec();
+        // A spread of significands and exponents
+        let mut x: u64 = 0x9e37_79b9_7f4a_7c15;
+        for _ in 0..2000 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            values.push(f64::from_bits(x & !(1 << 63)));
+        }
+        for v in values.into_iter().filter(|v| !v.is_nan()) {
+            assert_eq!(
+                wasm_sqrt_f64(v).to_bits(),
+                v.sqrt().to_bits(),
+                "sqrt({v:e})"
+            );
+            let f = v as f32;
+            assert_eq!(
+                wasm_sqrt_f32(f).to_bits(),
+                f.sqrt().to_bits(),
+                "sqrt({f:e})"
+            );
+        }
+        assert!(wasm_sqrt_f64(-1.0).is_nan());
+        assert!(wasm_sqrt_f32(f32::NEG_INFINITY).is_nan());
+        assert!(wasm_sqrt_f64(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn effective_address_does_not_wrap() {
+        assert_eq!(effective_address(16, 4), 20);
+        assert_eq!(effective_address(-1, 0), u32::MAX as usize);
+        assert_eq!(effective_address(-16, 32), usize::MAX);
+        assert_eq!(effective_address(i32::MIN, u32::MAX), usize::MAX);
+    }
+
     // ── wasm_nearest_f32 ─────────────────────────────────────────────────────
 
     #[test]
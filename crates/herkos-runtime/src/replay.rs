@@ -0,0 +1,317 @@
+//! Record and replay of host results (`--record-replay`).
+//!
+//! The only nondeterministic inputs of a transpiled module are the values its
+//! host returns: import results, imported global reads and yield-point
+//! verdicts. Code transpiled with `--record-replay` gets a `Recorder` host
+//! wrapper that appends each of them to a [`ReplayLog`], and a `Replayer`
+//! host that answers every call from such a log. Replaying a recorded run
+//! repeats it exactly, without the original host.
+//!
+//! A log survives the process through [`ReplayLog::to_bytes`] /
+//! [`ReplayLog::from_bytes`].
+
+use crate::{WasmResult, WasmTrap};
+use alloc::vec::Vec;
+
+/// Value a host method returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayValue {
+    /// `()`, from imports without results and yield points.
+    Unit,
+    I32(i32),
+    I64(i64),
+    /// `f32` bits, so NaN payloads replay exactly.
+    F32(u32),
+    /// `f64` bits.
+    F64(u64),
+}
+
+impl From<()> for ReplayValue {
+    fn from((): ()) -> Self {
+        ReplayValue::Unit
+    }
+}
+
+impl From<i32> for ReplayValue {
+    fn from(v: i32) -> Self {
+        ReplayValue::I32(v)
+    }
+}
+
+impl From<i64> for ReplayValue {
+    fn from(v: i64) -> Self {
+        ReplayValue::I64(v)
+    }
+}
+
+impl From<f32> for ReplayValue {
+    fn from(v: f32) -> Self {
+        ReplayValue::F32(v.to_bits())
+    }
+}
+
+impl From<f64> for ReplayValue {
+    fn from(v: f64) -> Self {
+        ReplayValue::F64(v.to_bits())
+    }
+}
+
+/// Conversions back fail with `ReplayDiverged` on a type mismatch.
+macro_rules! try_from_replay_value {
+    ($($ty:ty => |$v:ident| $pat:pat => $value:expr),* $(,)?) => {$(
+        impl TryFrom<ReplayValue> for $ty {
+            type Error = WasmTrap;
+
+            fn try_from($v: ReplayValue) -> WasmResult<Self> {
+                match $v {
+                    $pat => Ok($value),
+                    _ => Err(WasmTrap::ReplayDiverged),
+                }
+            }
+        }
+    )*};
+}
+
+try_from_replay_value! {
+    () => |v| ReplayValue::Unit => (),
+    i32 => |v| ReplayValue::I32(x) => x,
+    i64 => |v| ReplayValue::I64(x) => x,
+    f32 => |v| ReplayValue::F32(bits) => f32::from_bits(bits),
+    f64 => |v| ReplayValue::F64(bits) => f64::from_bits(bits),
+}
+
+/// One host call: which method (numbered by the generated `Recorder`) and
+/// what it returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayEvent {
+    pub method: u32,
+    pub result: WasmResult<ReplayValue>,
+}
+
+/// Host results of one run, in call order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayLog {
+    events: Vec<ReplayEvent>,
+    /// Next event to replay.
+    cursor: usize,
+    diverged: bool,
+}
+
+/// Every trap, indexed by its serialized code.
+pub(crate) const TRAPS: [WasmTrap; 17] = [
+    WasmTrap::OutOfBounds,
+    WasmTrap::DivisionByZero,
+    WasmTrap::IntegerOverflow,
+    WasmTrap::Unreachable,
+    WasmTrap::IndirectCallTypeMismatch,
+    WasmTrap::TableOutOfBounds,
+    WasmTrap::UndefinedElement,
+    WasmTrap::InvalidValue,
+    WasmTrap::GuestAllocFailed,
+    WasmTrap::Interrupted,
+    WasmTrap::HostAssertedOverflow,
+    WasmTrap::ReplayDiverged,
+    WasmTrap::UnknownExport,
+    WasmTrap::InvokeTypeMismatch,
+    WasmTrap::HostBusy,
+    WasmTrap::WriteProtected,
+    WasmTrap::InstantiationLimitExceeded,
+];
+
+/// Serialized event tags (after the little-endian `method`).
+const TAG_UNIT: u8 = 0;
+const TAG_I32: u8 = 1;
+const TAG_I64: u8 = 2;
+const TAG_F32: u8 = 3;
+const TAG_F64: u8 = 4;
+/// Followed by one byte: the trap's index in [`TRAPS`].
+const TAG_TRAP: u8 = 5;
+
+impl ReplayLog {
+    /// An empty log.
+    pub const fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            cursor: 0,
+            diverged: false,
+        }
+    }
+
+    /// Recorded events, in call order.
+    pub fn events(&self) -> &[ReplayEvent] {
+        &self.events
+    }
+
+    /// Append the result of a call to `method`.
+    pub fn record(&mut self, method: u32, result: WasmResult<ReplayValue>) {
+        self.events.push(ReplayEvent { method, result });
+    }
+
+    /// Result of the next recorded call, which must be to `method`.
+    ///
+    /// # Errors
+    /// Returns the recorded trap, or `Err(WasmTrap::ReplayDiverged)` if the
+    /// run calls a different method than the recording did, or more of them.
+    /// A divergence is sticky: every later call fails the same way.
+    pub fn replay(&mut self, method: u32) -> WasmResult<ReplayValue> {
+        match self.events.get(self.cursor) {
+            Some(event) if !self.diverged && event.method == method => {
+                self.cursor += 1;
+                event.result
+            }
+            _ => {
+                self.diverged = true;
+                Err(WasmTrap::ReplayDiverged)
+            }
+        }
+    }
+
+    /// Whether replay has consumed every event without diverging.
+    pub fn is_finished(&self) -> bool {
+        !self.diverged && self.cursor == self.events.len()
+    }
+
+    /// Whether replay has diverged from the recording.
+    pub fn is_diverged(&self) -> bool {
+        self.diverged
+    }
+
+    /// Start replaying from the first event again.
+    pub fn rewind(&mut self) {
+        self.cursor = 0;
+        self.diverged = false;
+    }
+
+    /// Compact binary encoding of the events (not the replay position).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.events.len() * 9);
+        for event in &self.events {
+            out.extend_from_slice(&event.method.to_le_bytes());
+            match event.result {
+                Ok(ReplayValue::Unit) => out.push(TAG_UNIT),
+                Ok(ReplayValue::I32(v)) => {
+                    out.push(TAG_I32);
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+                Ok(ReplayValue::I64(v)) => {
+                    out.push(TAG_I64);
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+                Ok(ReplayValue::F32(bits)) => {
+                    out.push(TAG_F32);
+                    out.extend_from_slice(&bits.to_le_bytes());
+                }
+                Ok(ReplayValue::F64(bits)) => {
+                    out.push(TAG_F64);
+                    out.extend_from_slice(&bits.to_le_bytes());
+                }
+                Err(trap) => {
+                    out.push(TAG_TRAP);
+                    // Every variant is listed in TRAPS
+                    let code = TRAPS.iter().position(|&t| t == trap).unwrap_or(0);
+                    out.push(code as u8);
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode [`to_bytes`](Self::to_bytes) output. Returns `None` if `bytes`
+    /// is truncated or malformed.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let mut log = Self::new();
+        while !bytes.is_empty() {
+            let method = u32::from_le_bytes(take(&mut bytes)?);
+            let [tag] = take(&mut bytes)?;
+            let result = match tag {
+                TAG_UNIT => Ok(ReplayValue::Unit),
+                TAG_I32 => Ok(ReplayValue::I32(i32::from_le_bytes(take(&mut bytes)?))),
+                TAG_I64 => Ok(ReplayValue::I64(i64::from_le_bytes(take(&mut bytes)?))),
+                TAG_F32 => Ok(ReplayValue::F32(u32::from_le_bytes(take(&mut bytes)?))),
+                TAG_F64 => Ok(ReplayValue::F64(u64::from_le_bytes(take(&mut bytes)?))),
+                TAG_TRAP => {
+                    let [code] = take(&mut bytes)?;
+                    Err(*TRAPS.get(usize::from(code))?)
+                }
+                _ => return None,
+            };
+            log.record(method, result);
+        }
+        Some(log)
+    }
+}
+
+/// Split the first `N` bytes off `bytes`.
+fn take<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    let (head, rest) = bytes.split_first_chunk::<N>()?;
+    *bytes = rest;
+    Some(*head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ReplayLog {
+        let mut log = ReplayLog::new();
+        log.record(0, Ok(ReplayValue::from(-7i32)));
+        log.record(1, Ok(ReplayValue::from(())));
+        log.record(0, Err(WasmTrap::Interrupted));
+        log.record(2, Ok(ReplayValue::from(f32::from_bits(0x7fc0_0001))));
+        log.record(3, Ok(ReplayValue::from(i64::MIN)));
+        log.record(4, Ok(ReplayValue::from(2.5f64)));
+        log
+    }
+
+    #[test]
+    fn replay_returns_results_in_order() {
+        let mut log = sample();
+        assert_eq!(log.replay(0).and_then(i32::try_from), Ok(-7));
+        assert_eq!(log.replay(1).and_then(<()>::try_from), Ok(()));
+        assert_eq!(log.replay(0), Err(WasmTrap::Interrupted));
+        let nan = log.replay(2).and_then(f32::try_from).unwrap();
+        assert_eq!(nan.to_bits(), 0x7fc0_0001);
+        assert!(!log.is_finished());
+        assert_eq!(log.replay(3).and_then(i64::try_from), Ok(i64::MIN));
+        assert_eq!(log.replay(4).and_then(f64::try_from), Ok(2.5));
+        assert!(log.is_finished());
+    }
+
+    #[test]
+    fn divergence_is_sticky() {
+        let mut log = sample();
+        assert_eq!(log.replay(1), Err(WasmTrap::ReplayDiverged));
+        assert!(log.is_diverged());
+        assert_eq!(log.replay(0), Err(WasmTrap::ReplayDiverged));
+
+        log.rewind();
+        assert_eq!(
+            log.replay(0).and_then(i64::try_from),
+            Err(WasmTrap::ReplayDiverged)
+        );
+
+        let mut empty = ReplayLog::new();
+        assert!(empty.is_finished());
+        assert_eq!(empty.replay(0), Err(WasmTrap::ReplayDiverged));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let log = sample();
+        let bytes = log.to_bytes();
+        assert_eq!(ReplayLog::from_bytes(&bytes), Some(log));
+        assert_eq!(ReplayLog::from_bytes(&[]), Some(ReplayLog::new()));
+        assert_eq!(ReplayLog::from_bytes(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(ReplayLog::from_bytes(&[0, 0, 0, 0, 9]), None);
+        assert_eq!(ReplayLog::from_bytes(&[0, 0, 0, 0, TAG_TRAP, 200]), None);
+    }
+
+    #[test]
+    fn every_trap_has_a_code() {
+        for (code, trap) in TRAPS.iter().enumerate() {
+            let mut log = ReplayLog::new();
+            log.record(0, Err(*trap));
+            assert_eq!(log.to_bytes()[5], code as u8);
+        }
+    }
+}
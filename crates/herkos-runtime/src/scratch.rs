@@ -0,0 +1,70 @@
+//! Scratch-region bump allocator for generated binding wrappers.
+//!
+//! High-level wrappers (`fn hash_bound(&mut self, data: &[u8])`) need guest
+//! addresses to stage host slices and to receive out-parameters. The module
+//! reserves a fixed region of linear memory for this (the `scratch` address in
+//! the bindings file); each wrapper call walks it with a fresh [`Scratch`].
+//! Nothing is freed — the region is reused from its base on the next call.
+
+use crate::{WasmResult, WasmTrap};
+
+/// Alignment of every scratch allocation — enough for any Wasm scalar.
+const SCRATCH_ALIGN: usize = 8;
+
+/// Bump allocator over a guest scratch region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scratch {
+    next: usize,
+}
+
+impl Scratch {
+    /// Start allocating at guest address `base`.
+    #[inline(always)]
+    pub const fn new(base: usize) -> Self {
+        Self { next: base }
+    }
+
+    /// Reserve `len` bytes and return their guest address.
+    ///
+    /// Allocations are 8-byte aligned. This only computes addresses; the
+    /// subsequent memory access performs the real bounds check.
+    ///
+    /// # Errors
+    /// Returns `Err(WasmTrap::OutOfBounds)` if the region would extend past
+    /// the 32-bit guest address space.
+    #[inline]
+    pub fn alloc(&mut self, len: usize) -> WasmResult<usize> {
+        let start = self
+            .next
+            .checked_add(SCRATCH_ALIGN - 1)
+            .ok_or(WasmTrap::OutOfBounds)?
+            & !(SCRATCH_ALIGN - 1);
+        let end = start.checked_add(len).ok_or(WasmTrap::OutOfBounds)?;
+        if end > u32::MAX as usize {
+            return Err(WasmTrap::OutOfBounds);
+        }
+        self.next = end;
+        Ok(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_are_aligned_and_sequential() {
+        let mut scratch = Scratch::new(0x1001);
+        assert_eq!(scratch.alloc(3), Ok(0x1008));
+        assert_eq!(scratch.alloc(16), Ok(0x1010));
+        assert_eq!(scratch.alloc(0), Ok(0x1020));
+    }
+
+    #[test]
+    fn alloc_past_guest_address_space_fails() {
+        let mut scratch = Scratch::new(u32::MAX as usize - 8);
+        assert_eq!(scratch.alloc(16), Err(WasmTrap::OutOfBounds));
+        let mut scratch = Scratch::new(usize::MAX - 2);
+        assert_eq!(scratch.alloc(1), Err(WasmTrap::OutOfBounds));
+    }
+}
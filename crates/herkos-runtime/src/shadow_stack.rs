@@ -0,0 +1,53 @@
+//! LLVM shadow stack conventions.
+//!
+//! C (and Rust) compiled by LLVM keeps address-taken locals and large frames
+//! on a stack in linear memory, growing down from the address held in the
+//! mutable global `__stack_pointer`. Every instantiation starts at the same
+//! linker-chosen top, so instances sharing an imported memory would write
+//! over each other's frames. The transpiler emits `new_with_stack(top)` and
+//! `with_stack(top, ..)` for such modules so each instance or reentrant call
+//! can be given its own region; the top is validated here.
+
+use crate::{WasmResult, WasmTrap};
+
+/// Alignment of the shadow stack pointer required by the LLVM Wasm ABI.
+pub const SHADOW_STACK_ALIGN: u32 = 16;
+
+/// Validate `top` as a shadow stack pointer for a memory of `memory_size`
+/// bytes (`None` when the memory is imported and its size is not known yet).
+///
+/// # Errors
+/// Returns `Err(WasmTrap::OutOfBounds)` if `top` is not 16-byte aligned or
+/// lies beyond the end of the memory.
+#[inline]
+pub fn check_stack_top(top: i32, memory_size: Option<usize>) -> WasmResult<i32> {
+    let addr = top as u32;
+    let in_memory = memory_size.is_none_or(|size| addr as usize <= size);
+    if addr.is_multiple_of(SHADOW_STACK_ALIGN) && in_memory {
+        Ok(top)
+    } else {
+        Err(WasmTrap::OutOfBounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_aligned_tops_within_memory() {
+        assert_eq!(check_stack_top(65536, Some(65536)), Ok(65536));
+        assert_eq!(check_stack_top(1024, None), Ok(1024));
+        // Addresses are unsigned: the top half of the 32-bit space is valid.
+        assert_eq!(check_stack_top(i32::MIN, None), Ok(i32::MIN));
+    }
+
+    #[test]
+    fn rejects_misaligned_or_out_of_bounds_tops() {
+        assert_eq!(check_stack_top(1000, None), Err(WasmTrap::OutOfBounds));
+        assert_eq!(
+            check_stack_top(65552, Some(65536)),
+            Err(WasmTrap::OutOfBounds)
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Hosts shared by several module instances.
+//!
+//! Export methods take the host as `&mut H` for the duration of one call.
+//! A host serving several instances lives behind shared ownership instead,
+//! and [`SharedHost`] lends it out one call at a time. Modules transpiled
+//! with `--shared-host` get a `SharedWasmModule<S: SharedHost>` wrapper whose
+//! methods borrow the host through it.
+//!
+//! A call that re-enters the same host while it is lent out (a host import
+//! calling back into an instance sharing it) traps with
+//! [`WasmTrap::HostBusy`] rather than panicking.
+
+use crate::{WasmResult, WasmTrap};
+
+/// Shared ownership of a host that lends exclusive access for one call.
+pub trait SharedHost {
+    /// The host behind the shared handle.
+    type Host;
+
+    /// Run `f` with exclusive access to the host.
+    ///
+    /// # Errors
+    /// [`WasmTrap::HostBusy`] if the host cannot be lent out: it is already
+    /// borrowed, or its lock is poisoned. Otherwise whatever `f` returns.
+    fn with_host<R>(&self, f: impl FnOnce(&mut Self::Host) -> WasmResult<R>) -> WasmResult<R>;
+}
+
+/// Single-threaded sharing. A re-entrant call finds the host borrowed.
+impl<H> SharedHost for core::cell::RefCell<H> {
+    type Host = H;
+
+    fn with_host<R>(&self, f: impl FnOnce(&mut H) -> WasmResult<R>) -> WasmResult<R> {
+        let mut host = self.try_borrow_mut().map_err(|_| WasmTrap::HostBusy)?;
+        f(&mut host)
+    }
+}
+
+impl<S: SharedHost + ?Sized> SharedHost for &S {
+    type Host = S::Host;
+
+    fn with_host<R>(&self, f: impl FnOnce(&mut S::Host) -> WasmResult<R>) -> WasmResult<R> {
+        (**self).with_host(f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<S: SharedHost + ?Sized> SharedHost for alloc::rc::Rc<S> {
+    type Host = S::Host;
+
+    fn with_host<R>(&self, f: impl FnOnce(&mut S::Host) -> WasmResult<R>) -> WasmResult<R> {
+        (**self).with_host(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: SharedHost + ?Sized> SharedHost for alloc::sync::Arc<S> {
+    type Host = S::Host;
+
+    fn with_host<R>(&self, f: impl FnOnce(&mut S::Host) -> WasmResult<R>) -> WasmResult<R> {
+        (**self).with_host(f)
+    }
+}
+
+/// Sharing across threads. Calls from other threads wait for the lock; a
+/// re-entrant call on the same thread is not detected and deadlocks, as
+/// with any `Mutex`.
+#[cfg(feature = "std")]
+impl<H> SharedHost for std::sync::Mutex<H> {
+    type Host = H;
+
+    fn with_host<R>(&self, f: impl FnOnce(&mut H) -> WasmResult<R>) -> WasmResult<R> {
+        let mut host = self.lock().map_err(|_| WasmTrap::HostBusy)?;
+        f(&mut host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+
+    #[test]
+    fn refcell_lends_the_host_once_at_a_time() {
+        let host = RefCell::new(0u32);
+        let shared = &host;
+        assert_eq!(
+            shared.with_host(|h| {
+                *h += 1;
+                Ok(*h)
+            }),
+            Ok(1)
+        );
+        let nested = shared.with_host(|_| shared.with_host(|_| Ok(())));
+        assert_eq!(nested, Err(WasmTrap::HostBusy));
+        assert_eq!(*host.borrow(), 1);
+    }
+}
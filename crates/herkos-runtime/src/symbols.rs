@@ -0,0 +1,96 @@
+//! Symbol table entries for host-side trap reporting.
+//!
+//! The transpiler emits a `pub const SYMBOLS: &[FuncSymbol]` table with one
+//! entry per local function, in local function index order, plus a
+//! `symbolicate(func_index)` lookup. Hosts use it to turn a generated
+//! `func_217` into the original source name, its export name(s), and the
+//! byte range of its body in the original `.wasm` file.
+
+use core::fmt;
+
+/// Debug metadata for a single transpiled function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuncSymbol {
+    /// Local function index — the `N` in the generated `func_N`.
+    pub func_index: u32,
+    /// Index in the Wasm function index space (imports included).
+    pub wasm_index: u32,
+    /// Original name from the Wasm `name` section, if present.
+    pub name: Option<&'static str>,
+    /// Export names under which this function is reachable.
+    pub exports: &'static [&'static str],
+    /// Byte offset of the function body in the original Wasm binary.
+    pub wasm_start: u32,
+    /// Byte offset one past the end of the function body.
+    pub wasm_end: u32,
+}
+
+impl FuncSymbol {
+    /// Whether `wasm_offset` lies inside this function's body.
+    #[inline]
+    pub const fn contains_offset(&self, wasm_offset: u32) -> bool {
+        self.wasm_start <= wasm_offset && wasm_offset < self.wasm_end
+    }
+}
+
+/// Formats as `func_N <name> [export a, b] @0xSTART..0xEND`, omitting the
+/// name and export parts when they are absent.
+impl fmt::Display for FuncSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "func_{}", self.func_index)?;
+        if let Some(name) = self.name {
+            write!(f, " <{}>", name)?;
+        }
+        if let Some((first, rest)) = self.exports.split_first() {
+            write!(f, " [export {}", first)?;
+            for name in rest {
+                write!(f, ", {}", name)?;
+            }
+            f.write_str("]")?;
+        }
+        write!(f, " @{:#x}..{:#x}", self.wasm_start, self.wasm_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+
+    const SYM: FuncSymbol = FuncSymbol {
+        func_index: 3,
+        wasm_index: 5,
+        name: Some("compute"),
+        exports: &["run", "main"],
+        wasm_start: 0x40,
+        wasm_end: 0x58,
+    };
+
+    #[test]
+    fn contains_offset_is_half_open() {
+        assert!(!SYM.contains_offset(0x3f));
+        assert!(SYM.contains_offset(0x40));
+        assert!(SYM.contains_offset(0x57));
+        assert!(!SYM.contains_offset(0x58));
+    }
+
+    #[test]
+    fn display_full_symbol() {
+        assert_eq!(
+            format!("{}", SYM),
+            "func_3 <compute> [export run, main] @0x40..0x58"
+        );
+    }
+
+    #[test]
+    fn display_anonymous_symbol() {
+        let sym = FuncSymbol {
+            name: None,
+            exports: &[],
+            ..SYM
+        };
+        assert_eq!(format!("{}", sym), "func_3 @0x40..0x58");
+    }
+}
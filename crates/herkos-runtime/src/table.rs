@@ -13,7 +13,16 @@
 //! The table uses a fixed-size backing array (const generic `MAX_SIZE`)
 //! to stay `no_std` compatible.
 
-use crate::{WasmResult, WasmTrap};
+use crate::{FuncSymbol, ValueType, WasmResult, WasmTrap};
+
+/// Which index space a `FuncRef`'s `func_index` belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuncKind {
+    /// A function defined by the module (`func_N` in generated code).
+    Local,
+    /// A function import, dispatched through the host trait.
+    Import,
+}
 
 /// A single table entry: a typed function reference.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,10 +31,77 @@ pub struct FuncRef {
     /// verify the caller's expected signature matches the callee's actual
     /// signature. A mismatch is a trap (`IndirectCallTypeMismatch`).
     pub type_index: u32,
-    /// Index into the module's function index space. The transpiler
-    /// generates a match/dispatch over this value to call the right
-    /// concrete Rust function.
+    /// Index into the module's local functions or function imports, as
+    /// `kind` says. The transpiler generates a match/dispatch over this
+    /// value to call the right concrete Rust function.
     pub func_index: u32,
+    /// Whether `func_index` names a local function or an import.
+    pub kind: FuncKind,
+}
+
+impl FuncRef {
+    /// Reference to local function `func_index`.
+    pub const fn local(type_index: u32, func_index: u32) -> Self {
+        Self {
+            type_index,
+            func_index,
+            kind: FuncKind::Local,
+        }
+    }
+
+    /// Reference to function import `import_index`.
+    pub const fn import(type_index: u32, import_index: u32) -> Self {
+        Self {
+            type_index,
+            func_index: import_index,
+            kind: FuncKind::Import,
+        }
+    }
+}
+
+/// Parameter and result types of a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuncType {
+    pub params: &'static [ValueType],
+    /// Empty for a function without result.
+    pub results: &'static [ValueType],
+}
+
+/// A table entry resolved for a host enumerating the table, as returned by
+/// the `table_entry` method of a module exporting its table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuncDescriptor {
+    /// The entry itself.
+    pub func: FuncRef,
+    /// Its signature.
+    pub ty: &'static FuncType,
+    /// For a local function, its name in the `name` section, or else its
+    /// first export name; for an import, the imported name.
+    pub name: Option<&'static str>,
+}
+
+impl FuncDescriptor {
+    /// Describe `func` from a module's `types` (by type index), `symbols`
+    /// (by local function index) and the names of its function `imports`.
+    ///
+    /// Returns `None` when `func` names a type or function outside them.
+    pub fn resolve(
+        func: FuncRef,
+        types: &'static [FuncType],
+        symbols: &'static [FuncSymbol],
+        imports: &'static [&'static str],
+    ) -> Option<Self> {
+        let ty = types.get(func.type_index as usize)?;
+        let index = func.func_index as usize;
+        let name = match func.kind {
+            FuncKind::Local => {
+                let symbol = symbols.get(index)?;
+                symbol.name.or(symbol.exports.first().copied())
+            }
+            FuncKind::Import => Some(*imports.get(index)?),
+        };
+        Some(Self { func, ty, name })
+    }
 }
 
 /// Indirect call table with a compile-time maximum size.
@@ -112,6 +188,19 @@ impl<const MAX_SIZE: usize> Table<MAX_SIZE> {
         init_elements_inner(&mut self.entries, self.active_size, base, entries)
     }
 
+    /// Initialize table entries from an element segment holding imported
+    /// functions.
+    ///
+    /// Like [`init_elements`](Self::init_elements), but each entry carries
+    /// its own [`FuncKind`], so local functions and imports can be mixed.
+    ///
+    /// # Errors
+    /// Returns `Err(TableOutOfBounds)` if any slot index is out of range.
+    #[inline(always)]
+    pub fn init_refs(&mut self, base: u32, entries: &[FuncRef]) -> WasmResult<()> {
+        init_refs_inner(&mut self.entries, self.active_size, base, entries)
+    }
+
     /// Grow the table by `delta` slots, filling new slots with `init`.
     /// Returns the previous size, or -1 on failure.
     pub fn grow(&mut self, delta: u32, init: Option<FuncRef>) -> i32 {
@@ -126,6 +215,33 @@ impl<const MAX_SIZE: usize> Table<MAX_SIZE> {
         self.active_size = new;
         old as i32
     }
+
+    /// The occupied slots below the current size, as `(index, entry)` in
+    /// index order.
+    pub fn occupied(&self) -> impl Iterator<Item = (u32, FuncRef)> + '_ {
+        self.entries[..self.active_size]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.map(|entry| (i as u32, entry)))
+    }
+}
+
+/// Formats the size, the maximum size and the occupied slots:
+/// `Table { size: 4, max_size: 8, entries: {0: FuncRef { .. }} }`.
+impl<const MAX_SIZE: usize> core::fmt::Debug for Table<MAX_SIZE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        struct Entries<'a, const N: usize>(&'a Table<N>);
+        impl<const N: usize> core::fmt::Debug for Entries<'_, N> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_map().entries(self.0.occupied()).finish()
+            }
+        }
+        f.debug_struct("Table")
+            .field("size", &self.active_size)
+            .field("max_size", &MAX_SIZE)
+            .field("entries", &Entries(self))
+            .finish()
+    }
 }
 
 // ── Non-generic inner function (outline pattern, §13.3) ──────────────────────
@@ -145,29 +261,92 @@ fn init_elements_inner(
             return Err(WasmTrap::TableOutOfBounds);
         }
         match slots.get_mut(idx) {
-            Some(slot) => {
-                *slot = Some(FuncRef {
-                    type_index,
-                    func_index,
-                })
-            }
+            Some(slot) => *slot = Some(FuncRef::local(type_index, func_index)),
             None => return Err(WasmTrap::TableOutOfBounds),
         }
     }
     Ok(())
 }
 
+#[inline(never)]
+fn init_refs_inner(
+    slots: &mut [Option<FuncRef>],
+    active_size: usize,
+    base: u32,
+    entries: &[FuncRef],
+) -> WasmResult<()> {
+    let start = base as usize;
+    let end = start
+        .checked_add(entries.len())
+        .ok_or(WasmTrap::TableOutOfBounds)?;
+    if end > active_size {
+        return Err(WasmTrap::TableOutOfBounds);
+    }
+    let slots = slots
+        .get_mut(start..end)
+        .ok_or(WasmTrap::TableOutOfBounds)?;
+    for (slot, entry) in slots.iter_mut().zip(entries) {
+        *slot = Some(*entry);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    extern crate std;
+    use std::{format, vec::Vec};
+
     fn sample_ref(type_idx: u32, func_idx: u32) -> FuncRef {
-        FuncRef {
-            type_index: type_idx,
-            func_index: func_idx,
+        FuncRef::local(type_idx, func_idx)
+    }
+
+    const TYPES: &[FuncType] = &[
+        FuncType {
+            params: &[ValueType::I32],
+            results: &[],
+        },
+        FuncType {
+            params: &[],
+            results: &[ValueType::F64],
+        },
+    ];
+
+    const fn symbol(func_index: u32, name: Option<&'static str>) -> FuncSymbol {
+        FuncSymbol {
+            func_index,
+            wasm_index: func_index + 1,
+            name,
+            exports: &["on_tick"],
+            wasm_start: 0,
+            wasm_end: 0,
         }
     }
 
+    const SYMBOLS: &[FuncSymbol] = &[symbol(0, Some("tick")), symbol(1, None)];
+
+    #[test]
+    fn descriptors_resolve_signature_and_name() {
+        let named = FuncDescriptor::resolve(FuncRef::local(1, 0), TYPES, SYMBOLS, &["log"]);
+        assert_eq!(
+            named.map(|d| (d.ty, d.name)),
+            Some((&TYPES[1], Some("tick")))
+        );
+        // Unnamed functions go by their export
+        let exported = FuncDescriptor::resolve(FuncRef::local(0, 1), TYPES, SYMBOLS, &["log"]);
+        assert_eq!(exported.and_then(|d| d.name), Some("on_tick"));
+        let import = FuncDescriptor::resolve(FuncRef::import(0, 0), TYPES, SYMBOLS, &["log"]);
+        assert_eq!(
+            import.map(|d| (d.ty.params, d.name)),
+            Some((&[ValueType::I32][..], Some("log")))
+        );
+        assert_eq!(
+            FuncDescriptor::resolve(FuncRef::local(2, 0), TYPES, SYMBOLS, &[]),
+            None
+        );
+    }
+
     #[test]
     fn new_table_is_empty() {
         let table = Table::<8>::try_new(4).unwrap();
@@ -276,6 +455,33 @@ mod tests {
         assert_eq!(result, Err(WasmTrap::TableOutOfBounds));
     }
 
+    // ── init_refs ──
+
+    #[test]
+    fn init_refs_mixes_locals_and_imports() {
+        let mut table = Table::<4>::try_new(4).unwrap();
+        table
+            .init_refs(1, &[FuncRef::local(0, 2), FuncRef::import(0, 2)])
+            .unwrap();
+        assert_eq!(table.get(1).unwrap().kind, FuncKind::Local);
+        let import = table.get(2).unwrap();
+        assert_eq!(import.kind, FuncKind::Import);
+        assert_eq!(import.func_index, 2);
+        assert_eq!(table.get(0), Err(WasmTrap::UndefinedElement));
+    }
+
+    #[test]
+    fn init_refs_out_of_bounds_writes_nothing() {
+        let mut table = Table::<4>::try_new(4).unwrap();
+        let result = table.init_refs(3, &[FuncRef::import(0, 0), FuncRef::import(0, 1)]);
+        assert_eq!(result, Err(WasmTrap::TableOutOfBounds));
+        assert_eq!(table.get(3), Err(WasmTrap::UndefinedElement));
+        assert_eq!(
+            table.init_refs(u32::MAX, &[FuncRef::import(0, 0)]),
+            Err(WasmTrap::TableOutOfBounds)
+        );
+    }
+
     #[test]
     fn init_elements_exactly_fills_table() {
         let mut table = Table::<4>::try_new(4).unwrap();
@@ -285,6 +491,28 @@ mod tests {
         assert_eq!(table.get(3).unwrap().func_index, 3);
     }
 
+    #[test]
+    fn occupied_skips_empty_slots_and_slots_past_size() {
+        let mut table = Table::<8>::try_new(4).unwrap();
+        table.set(1, Some(sample_ref(0, 5))).unwrap();
+        table.set(3, Some(FuncRef::import(1, 0))).unwrap();
+        table.grow(2, None);
+        assert_eq!(
+            table.occupied().collect::<Vec<_>>(),
+            [(1, sample_ref(0, 5)), (3, FuncRef::import(1, 0))]
+        );
+    }
+
+    #[test]
+    fn debug_lists_occupied_entries() {
+        let mut table = Table::<4>::try_new(2).unwrap();
+        table.set(1, Some(sample_ref(0, 3))).unwrap();
+        assert_eq!(
+            format!("{table:?}"),
+            "Table { size: 2, max_size: 4, entries: {1: FuncRef { type_index: 0, func_index: 3, kind: Local }} }"
+        );
+    }
+
     #[test]
     fn try_new_fails_if_initial_exceeds_max() {
         let result = Table::<4>::try_new(5);
@@ -320,10 +548,7 @@ mod proofs {
         let index: u32 = kani::any();
         let type_index: u32 = kani::any();
         let func_index: u32 = kani::any();
-        let entry = Some(FuncRef {
-            type_index,
-            func_index,
-        });
+        let entry = Some(FuncRef::local(type_index, func_index));
         let _ = table.set(index, entry);
     }
 
@@ -379,10 +604,7 @@ mod proofs {
         let type_index: u32 = kani::any();
         let func_index: u32 = kani::any();
 
-        let entry = FuncRef {
-            type_index,
-            func_index,
-        };
+        let entry = FuncRef::local(type_index, func_index);
 
         // If set succeeds, get should return the same entry
         if table.set(index, Some(entry)).is_ok() {
@@ -418,10 +640,7 @@ mod proofs {
     #[kani::unwind(1)]
     fn set_out_of_bounds_returns_error() {
         let mut table = Table::<8>::new(4);
-        let entry = FuncRef {
-            type_index: 0,
-            func_index: 0,
-        };
+        let entry = FuncRef::local(0, 0);
 
         let result = table.set(4, Some(entry));
         kani::assert(
@@ -448,10 +667,7 @@ mod proofs {
     #[kani::unwind(1)]
     fn set_none_clears_slot() {
         let mut table = Table::<8>::new(4);
-        let entry = FuncRef {
-            type_index: 1,
-            func_index: 5,
-        };
+        let entry = FuncRef::local(1, 5);
 
         // Set to Some, then clear with None
         table.set(1, Some(entry)).unwrap();
@@ -470,10 +686,7 @@ mod proofs {
     #[kani::unwind(3)]
     fn grow_initializes_new_slots() {
         let mut table = Table::<8>::new(2);
-        let init = FuncRef {
-            type_index: 7,
-            func_index: 42,
-        };
+        let init = FuncRef::local(7, 42);
 
         let result = table.grow(2, Some(init));
 
@@ -505,10 +718,7 @@ mod proofs {
     fn get_success_implies_valid_index() {
         let mut table = Table::<8>::new(4);
         // Set a valid entry
-        let entry = FuncRef {
-            type_index: 0,
-            func_index: 1,
-        };
+        let entry = FuncRef::local(0, 1);
         table.set(0, Some(entry)).unwrap();
 
         let index: u32 = kani::any();
@@ -529,10 +739,7 @@ mod proofs {
     fn set_success_implies_valid_index() {
         let mut table = Table::<8>::new(4);
         let index: u32 = kani::any();
-        let entry = FuncRef {
-            type_index: 0,
-            func_index: 0,
-        };
+        let entry = FuncRef::local(0, 0);
 
         let result = table.set(index, Some(entry));
 
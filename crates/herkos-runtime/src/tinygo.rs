@@ -0,0 +1,89 @@
+//! Scheduler clock for TinyGo guests.
+//!
+//! TinyGo's Wasm runtime has no threads: goroutines run until they block,
+//! and a sleeping program asks the host to call the `go_scheduler` export
+//! after a timeout (`runtime.sleepTicks`). Time is read through
+//! `runtime.ticks`, in milliseconds as `f64`.
+//!
+//! [`TinyGoClock`] backs both imports with a virtual clock: a sleep records
+//! its deadline, and [`TinyGoClock::take_wakeup`] jumps straight to it when
+//! the generated `tinygo_run_timers` loop fires the timer. Runs are therefore
+//! deterministic and never block the host. Hosts that need wall-clock time
+//! call [`TinyGoClock::advance`] or override the import methods.
+
+/// Virtual millisecond clock with at most one pending wakeup.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TinyGoClock {
+    now: f64,
+    wakeup: Option<f64>,
+}
+
+impl TinyGoClock {
+    /// Clock at time zero with no pending wakeup.
+    pub const fn new() -> Self {
+        Self {
+            now: 0.0,
+            wakeup: None,
+        }
+    }
+
+    /// Current time in milliseconds (`runtime.ticks`).
+    #[inline]
+    pub fn ticks(&self) -> f64 {
+        self.now
+    }
+
+    /// Request a wakeup `timeout` milliseconds from now
+    /// (`runtime.sleepTicks`). An earlier pending wakeup is kept.
+    pub fn sleep_ticks(&mut self, timeout: f64) {
+        let deadline = self.now + timeout.max(0.0);
+        self.wakeup = Some(match self.wakeup {
+            Some(pending) if pending <= deadline => pending,
+            _ => deadline,
+        });
+    }
+
+    /// Consume the pending wakeup, moving the clock forward to its deadline.
+    ///
+    /// Returns the time skipped in milliseconds, or `None` if the guest is
+    /// not sleeping.
+    pub fn take_wakeup(&mut self) -> Option<f64> {
+        let deadline = self.wakeup.take()?;
+        let skipped = (deadline - self.now).max(0.0);
+        self.now = self.now.max(deadline);
+        Some(skipped)
+    }
+
+    /// Move the clock forward by `ms` milliseconds.
+    pub fn advance(&mut self, ms: f64) {
+        self.now += ms.max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wakeup_jumps_to_earliest_deadline() {
+        let mut clock = TinyGoClock::new();
+        assert_eq!(clock.take_wakeup(), None);
+        clock.sleep_ticks(10.0);
+        clock.sleep_ticks(4.0);
+        clock.sleep_ticks(7.0);
+        assert_eq!(clock.take_wakeup(), Some(4.0));
+        assert_eq!(clock.ticks(), 4.0);
+        assert_eq!(clock.take_wakeup(), None);
+    }
+
+    #[test]
+    fn advancing_past_a_deadline_skips_nothing() {
+        let mut clock = TinyGoClock::new();
+        clock.sleep_ticks(5.0);
+        clock.advance(8.0);
+        assert_eq!(clock.take_wakeup(), Some(0.0));
+        assert_eq!(clock.ticks(), 8.0);
+        clock.sleep_ticks(-3.0);
+        assert_eq!(clock.take_wakeup(), Some(0.0));
+    }
+}
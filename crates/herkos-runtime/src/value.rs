@@ -0,0 +1,152 @@
+//! Dynamically typed Wasm values.
+//!
+//! Code transpiled with `--dynamic-invoke` gets an `invoke` method that calls
+//! an export by name, taking and returning [`Value`]s, for hosts that only
+//! learn the export and its arguments at runtime (scripting engines, test
+//! harnesses driven by data files).
+//!
+//! Conversions into a [`Value`] use `From`; conversions out of one use
+//! `TryFrom` and trap with [`WasmTrap::InvokeTypeMismatch`] on the wrong
+//! type, so dynamic callers can propagate a mismatch with `?` like any other
+//! trap.
+
+use crate::{WasmResult, WasmTrap};
+
+/// A Wasm number of any type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+/// Type of a Wasm number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl Value {
+    /// Type of this value.
+    pub fn ty(self) -> ValueType {
+        match self {
+            Value::I32(_) => ValueType::I32,
+            Value::I64(_) => ValueType::I64,
+            Value::F32(_) => ValueType::F32,
+            Value::F64(_) => ValueType::F64,
+        }
+    }
+
+    /// The `i32`, if this is one.
+    pub fn as_i32(self) -> Option<i32> {
+        match self {
+            Value::I32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The `i64`, if this is one.
+    pub fn as_i64(self) -> Option<i64> {
+        match self {
+            Value::I64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The `f32`, if this is one.
+    pub fn as_f32(self) -> Option<f32> {
+        match self {
+            Value::F32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The `f64`, if this is one.
+    pub fn as_f64(self) -> Option<f64> {
+        match self {
+            Value::F64(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for Value {
+    fn from(v: i32) -> Self {
+        Value::I32(v)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::I64(v)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Self {
+        Value::F32(v)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::F64(v)
+    }
+}
+
+/// Conversions back trap with `InvokeTypeMismatch` on another type.
+macro_rules! try_from_value {
+    ($($ty:ty => $variant:ident),* $(,)?) => {$(
+        impl TryFrom<Value> for $ty {
+            type Error = WasmTrap;
+
+            fn try_from(v: Value) -> WasmResult<Self> {
+                match v {
+                    Value::$variant(x) => Ok(x),
+                    _ => Err(WasmTrap::InvokeTypeMismatch),
+                }
+            }
+        }
+    )*};
+}
+
+try_from_value! {
+    i32 => I32,
+    i64 => I64,
+    f32 => F32,
+    f64 => F64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_round_trip_and_check_the_type() {
+        assert_eq!(Value::from(7i32).as_i32(), Some(7));
+        assert_eq!(Value::from(-7i64).as_i64(), Some(-7));
+        assert_eq!(Value::from(1.5f32).as_f32(), Some(1.5));
+        assert_eq!(Value::from(2.5f64).as_f64(), Some(2.5));
+        assert_eq!(Value::I32(7).as_i64(), None);
+        assert_eq!(Value::F64(1.0).as_f32(), None);
+        assert_eq!(Value::from(1.5f32).ty(), ValueType::F32);
+    }
+
+    #[test]
+    fn try_from_traps_on_the_wrong_type() {
+        assert_eq!(i64::try_from(Value::I64(3)), Ok(3));
+        assert_eq!(f32::try_from(Value::F32(0.5)), Ok(0.5));
+        assert_eq!(
+            i32::try_from(Value::F32(1.0)),
+            Err(WasmTrap::InvokeTypeMismatch)
+        );
+        assert_eq!(
+            f64::try_from(Value::I64(1)),
+            Err(WasmTrap::InvokeTypeMismatch)
+        );
+    }
+}
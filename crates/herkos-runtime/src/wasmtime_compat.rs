@@ -0,0 +1,284 @@
+//! Wasmtime-style adapter over transpiled modules.
+//!
+//! Host code written against `wasmtime` looks up exports by name and calls
+//! them through typed handles:
+//!
+//! ```text
+//! let mut store = Store::new(&engine, host);
+//! let instance = Instance::new(&mut store, &module, &[])?;
+//! let add = instance.get_typed_func::<(i32, i32), i32>(&mut store, "add")?;
+//! let sum = add.call(&mut store, (1, 2))?;
+//! let memory = instance.get_memory(&mut store, "memory").unwrap();
+//! memory.data_mut(&mut store)[0] = 42;
+//! ```
+//!
+//! Modules transpiled with `--wasmtime-adapter` implement [`WasmtimeExports`],
+//! and the same code runs against them once the store is built around the
+//! transpiled module:
+//!
+//! ```text
+//! let mut store = Store::new(my_module::new()?, host);
+//! let instance = Instance::new(&mut store);
+//! ```
+//!
+//! Parameters use wasmtime's conventions: `()` for none, the bare type for
+//! one, a tuple for several. Results are `()` or the bare type. Signature
+//! mismatches are reported by [`Instance::get_typed_func`], never at call time.
+
+use crate::WasmResult;
+use core::any::Any;
+use core::fmt;
+use core::marker::PhantomData;
+
+/// Owns a transpiled module (`M`) and the host state (`T`) passed to its
+/// imports.
+pub struct Store<M, T> {
+    module: M,
+    data: T,
+}
+
+impl<M, T> Store<M, T> {
+    /// Wrap an instantiated module and its host state.
+    pub fn new(module: M, data: T) -> Self {
+        Self { module, data }
+    }
+
+    /// Host state.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Host state, mutably.
+    pub fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// The transpiled module.
+    pub fn module(&self) -> &M {
+        &self.module
+    }
+
+    /// The transpiled module, mutably (for calling its methods directly).
+    pub fn module_mut(&mut self) -> &mut M {
+        &mut self.module
+    }
+
+    /// Module and host state together, as generated export shims need them.
+    pub fn parts_mut(&mut self) -> (&mut M, &mut T) {
+        (&mut self.module, &mut self.data)
+    }
+
+    /// Take the module and host state back out.
+    pub fn into_parts(self) -> (M, T) {
+        (self.module, self.data)
+    }
+}
+
+/// Export shim taking `P` and returning `R`.
+pub type Func<M, T, P, R> = fn(&mut Store<M, T>, P) -> WasmResult<R>;
+
+/// Name-based export lookup, implemented by generated modules.
+pub trait WasmtimeExports<T: 'static>: Sized + 'static {
+    /// Look up function export `name` with parameters `P` and results `R`.
+    fn typed_func<P: 'static, R: 'static>(name: &str) -> Result<Func<Self, T, P, R>, ExportError>;
+
+    /// Whether the module exports its linear memory as `name`.
+    fn has_memory(name: &str) -> bool;
+
+    /// Active contents of the linear memory (empty if the module has none).
+    fn memory_data(&self) -> &[u8];
+
+    /// Active contents of the linear memory, mutably.
+    fn memory_data_mut(&mut self) -> &mut [u8];
+}
+
+/// Why an export lookup failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportError {
+    /// No function export with that name.
+    NotFound,
+    /// The export exists but its signature differs from the requested one.
+    TypeMismatch,
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::NotFound => f.write_str("no function export with that name"),
+            ExportError::TypeMismatch => f.write_str("export signature does not match"),
+        }
+    }
+}
+
+impl core::error::Error for ExportError {}
+
+/// Handle to the exports of the module in a [`Store`].
+pub struct Instance<M, T> {
+    _store: PhantomData<fn() -> Store<M, T>>,
+}
+
+impl<M, T> Clone for Instance<M, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T> Copy for Instance<M, T> {}
+
+impl<M: WasmtimeExports<T>, T: 'static> Instance<M, T> {
+    /// Handle for the module already instantiated in `store`.
+    pub fn new(_store: &mut Store<M, T>) -> Self {
+        Self {
+            _store: PhantomData,
+        }
+    }
+
+    /// Typed handle to function export `name`.
+    ///
+    /// # Errors
+    /// Returns [`ExportError::NotFound`] if there is no such export and
+    /// [`ExportError::TypeMismatch`] if its signature is not `P -> R`.
+    pub fn get_typed_func<P: 'static, R: 'static>(
+        &self,
+        _store: &mut Store<M, T>,
+        name: &str,
+    ) -> Result<TypedFunc<M, T, P, R>, ExportError> {
+        M::typed_func(name).map(|func| TypedFunc { func })
+    }
+
+    /// Handle to the memory exported as `name`, if any.
+    pub fn get_memory(&self, _store: &mut Store<M, T>, name: &str) -> Option<Memory> {
+        M::has_memory(name).then_some(Memory { _private: () })
+    }
+}
+
+/// Typed handle to a function export.
+pub struct TypedFunc<M, T, P, R> {
+    func: Func<M, T, P, R>,
+}
+
+impl<M, T, P, R> Clone for TypedFunc<M, T, P, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T, P, R> Copy for TypedFunc<M, T, P, R> {}
+
+impl<M, T, P, R> TypedFunc<M, T, P, R> {
+    /// Call the export.
+    #[inline]
+    pub fn call(&self, store: &mut Store<M, T>, params: P) -> WasmResult<R> {
+        (self.func)(store, params)
+    }
+}
+
+/// Handle to an exported linear memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Memory {
+    _private: (),
+}
+
+impl Memory {
+    /// Active memory contents.
+    pub fn data<'a, M: WasmtimeExports<T>, T: 'static>(&self, store: &'a Store<M, T>) -> &'a [u8] {
+        store.module.memory_data()
+    }
+
+    /// Active memory contents, mutably.
+    pub fn data_mut<'a, M: WasmtimeExports<T>, T: 'static>(
+        &self,
+        store: &'a mut Store<M, T>,
+    ) -> &'a mut [u8] {
+        store.module.memory_data_mut()
+    }
+
+    /// Active memory size in bytes.
+    pub fn data_size<M: WasmtimeExports<T>, T: 'static>(&self, store: &Store<M, T>) -> usize {
+        store.module.memory_data().len()
+    }
+}
+
+/// Convert the export shim `f` to the requested [`Func`] type, or report a
+/// signature mismatch. Used by generated [`WasmtimeExports`] impls.
+#[inline]
+pub fn cast_func<F: Any + Copy, G: Any + Copy>(f: F) -> Result<G, ExportError> {
+    (&f as &dyn Any)
+        .downcast_ref::<G>()
+        .copied()
+        .ok_or(ExportError::TypeMismatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hand-written equivalent of a generated module exporting
+    /// `add(i32, i32) -> i32` and a 4-byte memory.
+    struct Adder {
+        memory: [u8; 4],
+    }
+
+    impl Adder {
+        fn add(&mut self, a: i32, b: i32, calls: &mut u32) -> WasmResult<i32> {
+            *calls += 1;
+            Ok(a.wrapping_add(b))
+        }
+    }
+
+    impl WasmtimeExports<u32> for Adder {
+        fn typed_func<P: 'static, R: 'static>(
+            name: &str,
+        ) -> Result<Func<Self, u32, P, R>, ExportError> {
+            match name {
+                "add" => cast_func::<Func<Self, u32, (i32, i32), i32>, _>(|store, (a, b)| {
+                    let (module, calls) = store.parts_mut();
+                    module.add(a, b, calls)
+                }),
+                _ => Err(ExportError::NotFound),
+            }
+        }
+
+        fn has_memory(name: &str) -> bool {
+            name == "memory"
+        }
+
+        fn memory_data(&self) -> &[u8] {
+            &self.memory
+        }
+
+        fn memory_data_mut(&mut self) -> &mut [u8] {
+            &mut self.memory
+        }
+    }
+
+    #[test]
+    fn typed_func_checks_signature_and_calls() {
+        let mut store = Store::new(Adder { memory: [0; 4] }, 0u32);
+        let instance = Instance::new(&mut store);
+        let add = instance
+            .get_typed_func::<(i32, i32), i32>(&mut store, "add")
+            .unwrap();
+        assert_eq!(add.call(&mut store, (2, 3)), Ok(5));
+        assert_eq!(*store.data(), 1);
+        assert_eq!(
+            instance.get_typed_func::<i32, i32>(&mut store, "add").err(),
+            Some(ExportError::TypeMismatch)
+        );
+        assert_eq!(
+            instance.get_typed_func::<(), ()>(&mut store, "sub").err(),
+            Some(ExportError::NotFound)
+        );
+    }
+
+    #[test]
+    fn memory_handle_exposes_module_memory() {
+        let mut store = Store::new(Adder { memory: [0; 4] }, 0u32);
+        let instance = Instance::new(&mut store);
+        assert!(instance.get_memory(&mut store, "mem").is_none());
+        let memory = instance.get_memory(&mut store, "memory").unwrap();
+        memory.data_mut(&mut store)[1] = 7;
+        assert_eq!(memory.data(&store), &[0, 7, 0, 0]);
+        assert_eq!(memory.data_size(&store), 4);
+    }
+}
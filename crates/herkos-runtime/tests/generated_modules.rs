@@ -181,24 +181,8 @@ mod indirect_call {
     fn new() -> IndirectModule {
         let mut table = Table::<4>::try_new(2).unwrap();
         // Element segment: table[0] = func 0 (add), table[1] = func 1 (mul)
-        table
-            .set(
-                0,
-                Some(FuncRef {
-                    type_index: 0,
-                    func_index: 0,
-                }),
-            )
-            .unwrap();
-        table
-            .set(
-                1,
-                Some(FuncRef {
-                    type_index: 0,
-                    func_index: 1,
-                }),
-            )
-            .unwrap();
+        table.set(0, Some(FuncRef::local(0, 0))).unwrap();
+        table.set(1, Some(FuncRef::local(0, 1))).unwrap();
         LibraryModule::new((), table)
     }
 
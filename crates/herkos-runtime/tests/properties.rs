@@ -0,0 +1,390 @@
+//! Property tests for the safety core: `IsolatedMemory` and `Table`.
+//!
+//! Each case runs a random sequence of operations against both the real
+//! type and a plain `Vec` model, and checks after every step that:
+//!
+//! - an access succeeds exactly when it lies inside the active region, and a
+//!   failed access changes nothing,
+//! - loads return what the model holds, including sign extension of
+//!   sub-width loads as generated code performs it (`load_u8(..)? as i8`),
+//! - `grow` keeps existing contents, zeroes new pages and respects the
+//!   maximum,
+//! - table `get` / `set` / `grow` / `init_refs` respect the active size and
+//!   `MAX_SIZE`.
+//!
+//! Sequences come from proptest, which shrinks a failing one to a minimal
+//! sequence, prints it and records its seed under `proptest-regressions/`.
+//! `PROPTEST_CASES` raises the number of cases (default 256).
+
+use herkos_runtime::{FuncRef, IsolatedMemory, Table, WasmTrap, PAGE_SIZE};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+const MAX_PAGES: usize = 4;
+const MAX_SLOTS: usize = 16;
+const OPS_PER_CASE: usize = 64;
+
+fn in_bounds(offset: usize, len: usize, active: usize) -> bool {
+    offset.checked_add(len).is_some_and(|end| end <= active)
+}
+
+/// Where an access goes, biased towards the end of the active region and
+/// towards values that overflow `usize`; resolved against the active size
+/// when the operation runs.
+#[derive(Debug, Clone, Copy)]
+enum At {
+    /// Up to `width` bytes before or after the end of the active region.
+    Boundary(u8),
+    NearUsizeMax(u8),
+    NearU32Max(u8),
+    /// Up to 8 bytes before the end of one of the first `MAX_PAGES` pages.
+    PageEnd(u8, u8),
+    /// Anywhere in the active region or 16 bytes past it.
+    Anywhere(u32),
+}
+
+impl At {
+    fn offset(self, active: usize, width: usize) -> usize {
+        match self {
+            At::Boundary(k) => active.saturating_sub(width) + k as usize % (2 * width + 1),
+            At::NearUsizeMax(k) => usize::MAX - k as usize,
+            At::NearU32Max(k) => u32::MAX as usize - k as usize,
+            At::PageEnd(page, back) => PAGE_SIZE * (1 + page as usize) - back as usize,
+            At::Anywhere(x) => x as usize % (active + 16),
+        }
+    }
+}
+
+fn at() -> impl Strategy<Value = At> {
+    prop_oneof![
+        1 => (0u8..=16).prop_map(At::Boundary),
+        1 => (0u8..8).prop_map(At::NearUsizeMax),
+        1 => (0u8..8).prop_map(At::NearU32Max),
+        1 => (0..MAX_PAGES as u8, 0u8..8).prop_map(|(page, back)| At::PageEnd(page, back)),
+        4 => any::<u32>().prop_map(At::Anywhere),
+    ]
+}
+
+// ── IsolatedMemory ──────────────────────────────────────────────────────
+
+/// Model of linear memory: the active bytes and nothing else.
+struct MemoryModel(Vec<u8>);
+
+impl MemoryModel {
+    fn read<const N: usize>(&self, offset: usize) -> Result<[u8; N], WasmTrap> {
+        if !in_bounds(offset, N, self.0.len()) {
+            return Err(WasmTrap::OutOfBounds);
+        }
+        Ok(self.0[offset..offset + N].try_into().unwrap())
+    }
+
+    fn write(&mut self, offset: usize, bytes: &[u8]) -> Result<(), WasmTrap> {
+        if !in_bounds(offset, bytes.len(), self.0.len()) {
+            return Err(WasmTrap::OutOfBounds);
+        }
+        self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MemoryOp {
+    StoreU8(At, u8),
+    StoreU16(At, u16),
+    StoreI32(At, i32),
+    StoreI64(At, i64),
+    /// Stores the bit pattern as an `f64` and loads it back.
+    StoreF64(At, u64),
+    LoadU8(At),
+    LoadU16(At),
+    LoadI32(At),
+    LoadI64(At),
+    Grow(u32),
+    Fill(At, u8, usize),
+    Copy {
+        dst: At,
+        src: At,
+        len: u32,
+    },
+}
+
+fn memory_op() -> impl Strategy<Value = MemoryOp> {
+    prop_oneof![
+        (at(), any::<u8>()).prop_map(|(at, v)| MemoryOp::StoreU8(at, v)),
+        (at(), any::<u16>()).prop_map(|(at, v)| MemoryOp::StoreU16(at, v)),
+        (at(), any::<i32>()).prop_map(|(at, v)| MemoryOp::StoreI32(at, v)),
+        (at(), any::<i64>()).prop_map(|(at, v)| MemoryOp::StoreI64(at, v)),
+        (at(), any::<u64>()).prop_map(|(at, v)| MemoryOp::StoreF64(at, v)),
+        at().prop_map(MemoryOp::LoadU8),
+        at().prop_map(MemoryOp::LoadU16),
+        at().prop_map(MemoryOp::LoadI32),
+        at().prop_map(MemoryOp::LoadI64),
+        (0u32..3).prop_map(MemoryOp::Grow),
+        (at(), any::<u8>(), 0usize..48).prop_map(|(at, v, len)| MemoryOp::Fill(at, v, len)),
+        (at(), at(), 0u32..48).prop_map(|(dst, src, len)| MemoryOp::Copy { dst, src, len }),
+    ]
+}
+
+/// Apply `op` to `memory` and `model`, asserting they agree.
+fn memory_step(op: &MemoryOp, memory: &mut IsolatedMemory<MAX_PAGES>, model: &mut MemoryModel) {
+    let active = model.0.len();
+    match *op {
+        MemoryOp::StoreU8(at, v) => {
+            let off = at.offset(active, 1);
+            let expect = model.write(off, &[v]);
+            assert_eq!(memory.store_u8(off, v), expect, "store_u8 at {off}");
+        }
+        MemoryOp::StoreU16(at, v) => {
+            let off = at.offset(active, 2);
+            let expect = model.write(off, &v.to_le_bytes());
+            assert_eq!(memory.store_u16(off, v), expect, "store_u16 at {off}");
+        }
+        MemoryOp::StoreI32(at, v) => {
+            let off = at.offset(active, 4);
+            let expect = model.write(off, &v.to_le_bytes());
+            assert_eq!(memory.store_i32(off, v), expect, "store_i32 at {off}");
+        }
+        MemoryOp::StoreI64(at, v) => {
+            let off = at.offset(active, 8);
+            let expect = model.write(off, &v.to_le_bytes());
+            assert_eq!(memory.store_i64(off, v), expect, "store_i64 at {off}");
+        }
+        MemoryOp::StoreF64(at, bits) => {
+            // Through the bit pattern, so NaN payloads compare exactly.
+            let off = at.offset(active, 8);
+            let expect = model.write(off, &bits.to_le_bytes());
+            let stored = memory.store_f64(off, f64::from_bits(bits));
+            assert_eq!(stored, expect, "store_f64 at {off}");
+            let got = memory.load_f64(off).map(f64::to_bits);
+            assert_eq!(got, model.read::<8>(off).map(u64::from_le_bytes));
+        }
+        MemoryOp::LoadU8(at) => {
+            // i32.load8_s / i64.load8_s / load8_u
+            let off = at.offset(active, 1);
+            let expect = model.read::<1>(off).map(|b| b[0]);
+            let got = memory.load_u8(off);
+            assert_eq!(got, expect, "load_u8 at {off}");
+            if let Ok(byte) = got {
+                assert_eq!(byte as i8 as i32, ((byte as i32) << 24) >> 24);
+                assert_eq!(byte as i8 as i64, ((byte as i64) << 56) >> 56);
+                assert_eq!(byte as i32, (byte as i32) & 0xff);
+            }
+        }
+        MemoryOp::LoadU16(at) => {
+            // i32.load16_s / i64.load16_s / load16_u
+            let off = at.offset(active, 2);
+            let expect = model.read::<2>(off).map(u16::from_le_bytes);
+            let got = memory.load_u16(off);
+            assert_eq!(got, expect, "load_u16 at {off}");
+            if let Ok(half) = got {
+                assert_eq!(half as i16 as i32, ((half as i32) << 16) >> 16);
+                assert_eq!(half as i16 as i64, ((half as i64) << 48) >> 48);
+            }
+        }
+        MemoryOp::LoadI32(at) => {
+            // i32.load / i64.load32_s / i64.load32_u
+            let off = at.offset(active, 4);
+            let expect = model.read::<4>(off).map(i32::from_le_bytes);
+            let got = memory.load_i32(off);
+            assert_eq!(got, expect, "load_i32 at {off}");
+            if let Ok(word) = got {
+                assert_eq!(word as i64, ((word as i64) << 32) >> 32);
+                assert_eq!(word as u32 as i64, (word as i64) & 0xffff_ffff);
+            }
+        }
+        MemoryOp::LoadI64(at) => {
+            let off = at.offset(active, 8);
+            let expect = model.read::<8>(off).map(i64::from_le_bytes);
+            assert_eq!(memory.load_i64(off), expect, "load_i64 at {off}");
+        }
+        MemoryOp::Grow(delta) => {
+            let old_pages = active / PAGE_SIZE;
+            let expect = if old_pages + delta as usize <= MAX_PAGES {
+                model.0.resize(active + delta as usize * PAGE_SIZE, 0);
+                old_pages as i32
+            } else {
+                -1
+            };
+            assert_eq!(memory.grow(delta), expect, "grow by {delta}");
+            assert_eq!(memory.size() as usize * PAGE_SIZE, model.0.len());
+            // Growing never loses data and new pages start zeroed.
+            assert!(memory.as_slice() == &model.0[..], "grow by {delta}");
+        }
+        MemoryOp::Fill(at, byte, len) => {
+            let dst = at.offset(active, len.max(1));
+            let expect = model.write(dst, &vec![byte; len]);
+            assert_eq!(memory.fill(dst, byte, len), expect, "fill {len} at {dst}");
+        }
+        MemoryOp::Copy { dst, src, len } => {
+            let src = src.offset(active, len.max(1) as usize) as u32;
+            let dst = dst.offset(active, len.max(1) as usize) as u32;
+            let (s, d, n) = (src as usize, dst as usize, len as usize);
+            let expect = if in_bounds(s, n, active) && in_bounds(d, n, active) {
+                model.0.copy_within(s..s + n, d);
+                Ok(())
+            } else {
+                Err(WasmTrap::OutOfBounds)
+            };
+            assert_eq!(
+                memory.memory_copy(dst, src, len),
+                expect,
+                "copy {len} from {src} to {dst}"
+            );
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn memory_agrees_with_model(
+        initial in 0usize..3,
+        ops in vec(memory_op(), 0..=OPS_PER_CASE),
+    ) {
+        let mut memory = Box::new(IsolatedMemory::<MAX_PAGES>::try_new(initial).unwrap());
+        let mut model = MemoryModel(vec![0; initial * PAGE_SIZE]);
+        for (step, op) in ops.iter().enumerate() {
+            memory_step(op, &mut memory, &mut model);
+            if step % 16 == 15 {
+                prop_assert!(memory.as_slice() == &model.0[..], "step {}", step);
+            }
+        }
+        prop_assert!(memory.as_slice() == &model.0[..]);
+    }
+
+    #[test]
+    fn grow_beyond_max_fails_without_side_effects(
+        initial in 0..=MAX_PAGES,
+        excess in 1..=u32::MAX / 2,
+    ) {
+        let mut memory = Box::new(IsolatedMemory::<MAX_PAGES>::try_new(initial).unwrap());
+        memory.fill(0, 0xAB, memory.active_size()).unwrap();
+        let delta = (MAX_PAGES - initial) as u32 + excess;
+        prop_assert_eq!(memory.grow(delta), -1);
+        prop_assert_eq!(memory.page_count(), initial);
+        prop_assert!(memory.as_slice().iter().all(|&b| b == 0xAB));
+        prop_assert_eq!(memory.grow(u32::MAX), -1);
+    }
+}
+
+// ── Table ───────────────────────────────────────────────────────────────
+
+fn func_ref() -> impl Strategy<Value = Option<FuncRef>> {
+    prop_oneof![
+        Just(None),
+        (0u32..4, 0u32..8).prop_map(|(ty, idx)| Some(FuncRef::local(ty, idx))),
+        (0u32..4, 0u32..8).prop_map(|(ty, idx)| Some(FuncRef::import(ty, idx))),
+    ]
+}
+
+/// A table index: mostly near the active size, sometimes near `u32::MAX`.
+#[derive(Debug, Clone, Copy)]
+enum Index {
+    NearMax(u8),
+    Near(u8),
+}
+
+impl Index {
+    fn resolve(self, size: usize) -> u32 {
+        match self {
+            Index::NearMax(k) => u32::MAX - k as u32,
+            Index::Near(k) => k as u32 % (size as u32 + 4),
+        }
+    }
+}
+
+fn index() -> impl Strategy<Value = Index> {
+    prop_oneof![
+        1 => (0u8..4).prop_map(Index::NearMax),
+        3 => any::<u8>().prop_map(Index::Near),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum TableOp {
+    Get(Index),
+    Set(Index, Option<FuncRef>),
+    Grow(u32, Option<FuncRef>),
+    InitRefs(Index, Vec<Option<FuncRef>>),
+}
+
+fn table_op() -> impl Strategy<Value = TableOp> {
+    let delta = prop_oneof![1 => (0u32..4).prop_map(|k| u32::MAX - k), 3 => 0u32..6];
+    prop_oneof![
+        index().prop_map(TableOp::Get),
+        (index(), func_ref()).prop_map(|(i, f)| TableOp::Set(i, f)),
+        (delta, func_ref()).prop_map(|(d, f)| TableOp::Grow(d, f)),
+        (index(), vec(func_ref(), 0..5)).prop_map(|(i, fs)| TableOp::InitRefs(i, fs)),
+    ]
+}
+
+fn table_step(op: &TableOp, table: &mut Table<MAX_SLOTS>, model: &mut Vec<Option<FuncRef>>) {
+    let size = model.len();
+    match op {
+        TableOp::Get(index) => {
+            let index = index.resolve(size);
+            let expect = match model.get(index as usize) {
+                None => Err(WasmTrap::TableOutOfBounds),
+                Some(None) => Err(WasmTrap::UndefinedElement),
+                Some(Some(f)) => Ok(*f),
+            };
+            assert_eq!(table.get(index), expect, "get {index}");
+        }
+        TableOp::Set(index, entry) => {
+            let index = index.resolve(size);
+            let expect = match model.get_mut(index as usize) {
+                None => Err(WasmTrap::TableOutOfBounds),
+                Some(slot) => {
+                    *slot = *entry;
+                    Ok(())
+                }
+            };
+            assert_eq!(table.set(index, *entry), expect, "set {index}");
+        }
+        TableOp::Grow(delta, init) => {
+            let expect = if size + *delta as usize <= MAX_SLOTS {
+                model.resize(size + *delta as usize, *init);
+                size as i32
+            } else {
+                -1
+            };
+            assert_eq!(table.grow(*delta, *init), expect, "grow by {delta}");
+        }
+        TableOp::InitRefs(base, entries) => {
+            let base = base.resolve(size);
+            let entries: Vec<FuncRef> = entries.iter().flatten().copied().collect();
+            let b = base as usize;
+            let expect = if in_bounds(b, entries.len(), size) {
+                for (slot, entry) in model[b..b + entries.len()].iter_mut().zip(&entries) {
+                    *slot = Some(*entry);
+                }
+                Ok(())
+            } else {
+                Err(WasmTrap::TableOutOfBounds)
+            };
+            assert_eq!(
+                table.init_refs(base, &entries),
+                expect,
+                "init_refs of {} at {base}",
+                entries.len()
+            );
+        }
+    }
+    assert_eq!(table.size(), model.len());
+}
+
+proptest! {
+    #[test]
+    fn table_agrees_with_model(
+        initial in 0..MAX_SLOTS / 2,
+        ops in vec(table_op(), 0..=OPS_PER_CASE),
+    ) {
+        let mut table = Table::<MAX_SLOTS>::try_new(initial).unwrap();
+        let mut model = vec![None; initial];
+        for op in &ops {
+            table_step(op, &mut table, &mut model);
+        }
+        for index in 0..model.len() as u32 {
+            prop_assert_eq!(table.get(index).ok(), model[index as usize], "slot {}", index);
+        }
+    }
+}
@@ -1,14 +1,24 @@
 use anyhow::{Context, Result};
+use herkos_core::bindings::BindingConfig;
+use herkos_core::capabilities::CapabilityConfig;
+use herkos_core::features::FeatureConfig;
+use herkos_core::wit::WitWorld;
 use herkos_core::{transpile, TranspileOptions};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+#[path = "fuzz/differential.rs"]
+mod differential;
+
 fn main() -> Result<()> {
     // Rerun build script if this file, any data files, or the optimize flag changes
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=data");
+    println!("cargo:rerun-if-changed=fuzz");
     println!("cargo:rerun-if-env-changed=HERKOS_OPTIMIZE");
+    println!("cargo:rerun-if-env-changed=HERKOS_FUZZ_SEED");
+    println!("cargo:rerun-if-env-changed=HERKOS_FUZZ_CASES");
 
     // Use cargo's OUT_DIR for generated files (parallel-build safe)
     let out_dir = PathBuf::from(env::var("OUT_DIR").context("OUT_DIR not set")?);
@@ -39,6 +49,10 @@ fn main() -> Result<()> {
     let c_e2e_names = process_c_e2e_files(Path::new("data/c"), &out_dir, &options)?;
     module_names.extend(c_e2e_names);
 
+    // 4. Random modules checked against the IR interpreter
+    let differential_names = differential::process_differential_cases(&out_dir, &options)?;
+    module_names.extend(differential_names);
+
     // Generate module manifest
     let mut manifest = String::from("// Auto-generated module manifest\n");
     manifest.push_str("// DO NOT EDIT - generated by build.rs\n\n");
@@ -55,6 +69,39 @@ fn main() -> Result<()> {
 
 // ─── WAT processing ─────────────────────────────────────────────────────────
 
+/// Set the options the WAT test case `name` is transpiled with, on top of
+/// the shared ones. Sidecar files add bindings, WIT worlds, feature gates and
+/// capabilities (see [`process_wat_files`]).
+fn set_fixture_options(name: &str, options: &mut TranspileOptions) {
+    let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+    match name {
+        "wasmtime_adapter" => options.wasmtime_adapter = true,
+        "yield_points" => options.yield_points = true,
+        "atomics" => options.lower_atomics = true,
+        "call_ctors" => options.call_ctors = true,
+        "checked_arithmetic" => options.checked_arithmetic = true,
+        "observe_memory" => options.observe_memory = true,
+        "record_replay" => options.record_replay = true,
+        "dynamic_invoke" => options.dynamic_invoke = true,
+        "shared_host" => options.shared_host = true,
+        // Runtime linking glue
+        "link_math" | "link_app" => options.link = true,
+        "heap_profile" => options.heap_profile = true,
+        "compact" => options.compact = true,
+        "debug_memory" => options.debug_memory = true,
+        "protect_rodata" => options.protect_rodata = true,
+        "guest_log" => options.guest_log = true,
+        "coverage" => options.coverage = true,
+        "bounds_profile" => options.bounds_profile = true,
+        // Two builds of one plugin
+        "hot_reload_v1" | "hot_reload_v2" => options.hot_reload = true,
+        "portable" => options.portable = true,
+        "constant_time" => options.constant_time = strings(&["ct_eq", "ct_choose"]),
+        "export_groups" => options.export_groups = strings(&["image_", "audio_"]),
+        _ => {}
+    }
+}
+
 /// Scan `data/wat/*.wat`, parse each to Wasm, transpile to Rust.
 fn process_wat_files(
     wat_dir: &Path,
@@ -85,7 +132,43 @@ fn process_wat_files(
         fs::write(&wasm_path, &wasm_bytes)
             .with_context(|| format!("failed to write {}.wasm", name))?;
 
-        let rust_code = transpile(&wasm_bytes, options)
+        // Optional sidecars: `<name>.bindings` declares high-level wrappers,
+        // `<name>.wit` the WIT world the module implements, `<name>.features`
+        // the Cargo features gating its exports.
+        let mut module_options = options.clone();
+        set_fixture_options(&name, &mut module_options);
+        let bindings_path = path.with_extension("bindings");
+        if bindings_path.exists() {
+            let text = fs::read_to_string(&bindings_path)
+                .with_context(|| format!("failed to read {}", bindings_path.display()))?;
+            module_options.bindings = BindingConfig::parse(&text)
+                .with_context(|| format!("failed to parse {}", bindings_path.display()))?;
+        }
+        let wit_path = path.with_extension("wit");
+        if wit_path.exists() {
+            let text = fs::read_to_string(&wit_path)
+                .with_context(|| format!("failed to read {}", wit_path.display()))?;
+            module_options.wit = Some(
+                WitWorld::parse(&text)
+                    .with_context(|| format!("failed to parse {}", wit_path.display()))?,
+            );
+        }
+        let features_path = path.with_extension("features");
+        if features_path.exists() {
+            let text = fs::read_to_string(&features_path)
+                .with_context(|| format!("failed to read {}", features_path.display()))?;
+            module_options.features = FeatureConfig::parse(&text)
+                .with_context(|| format!("failed to parse {}", features_path.display()))?;
+        }
+        let capabilities_path = path.with_extension("capabilities");
+        if capabilities_path.exists() {
+            let text = fs::read_to_string(&capabilities_path)
+                .with_context(|| format!("failed to read {}", capabilities_path.display()))?;
+            module_options.capabilities = CapabilityConfig::parse(&text)
+                .with_context(|| format!("failed to parse {}", capabilities_path.display()))?;
+        }
+
+        let rust_code = transpile(&wasm_bytes, &module_options)
             .with_context(|| format!("failed to transpile {}", name))?;
 
         let module_path = out_dir.join(format!("{}.rs", name));
@@ -0,0 +1,26 @@
+;; Module built with `-pthread`: shared memory, atomic read-modify-write,
+;; compare-exchange, fences and futex-style wait/notify. Transpiled with
+;; `lower_atomics`, for a host that runs it on a single thread.
+(module
+  (memory 1 1 shared)
+  (func (export "fetch_add") (param $addr i32) (param $v i32) (result i32)
+    (atomic.fence)
+    (i32.atomic.rmw.add (local.get $addr) (local.get $v)))
+  (func (export "load") (param $addr i32) (result i32)
+    (i32.atomic.load (local.get $addr)))
+  (func (export "store64") (param $addr i32) (param $v i64)
+    (i64.atomic.store (local.get $addr) (local.get $v)))
+  (func (export "load64") (param $addr i32) (result i64)
+    (i64.atomic.load (local.get $addr)))
+  (func (export "swap8") (param $addr i32) (param $v i32) (result i32)
+    (i32.atomic.rmw8.xchg_u (local.get $addr) (local.get $v)))
+  (func (export "sub16") (param $addr i32) (param $v i32) (result i32)
+    (i32.atomic.rmw16.sub_u (local.get $addr) (local.get $v)))
+  (func (export "cmpxchg") (param $addr i32) (param $expected i32) (param $new i32) (result i32)
+    (i32.atomic.rmw.cmpxchg (local.get $addr) (local.get $expected) (local.get $new)))
+  (func (export "cmpxchg8") (param $addr i32) (param $expected i32) (param $new i32) (result i32)
+    (i32.atomic.rmw8.cmpxchg_u (local.get $addr) (local.get $expected) (local.get $new)))
+  (func (export "wait") (param $addr i32) (param $expected i32) (param $timeout i64) (result i32)
+    (memory.atomic.wait32 (local.get $addr) (local.get $expected) (local.get $timeout)))
+  (func (export "notify") (param $addr i32) (param $count i32) (result i32)
+    (memory.atomic.notify (local.get $addr) (local.get $count))))
@@ -0,0 +1,50 @@
+(module
+  (memory 1 1)
+
+  ;; checksum(ptr, len) -> sum of bytes
+  (func $checksum (param $ptr i32) (param $len i32) (result i32)
+    (local $i i32) (local $sum i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $sum
+          (i32.add (local.get $sum)
+            (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $sum))
+
+  ;; xor_in_place(ptr, len, key): buf[i] ^= key
+  (func $xor_in_place (param $ptr i32) (param $len i32) (param $key i32)
+    (local $i i32) (local $addr i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $addr (i32.add (local.get $ptr) (local.get $i)))
+        (i32.store8 (local.get $addr)
+          (i32.xor (i32.load8_u (local.get $addr)) (local.get $key)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next))))
+
+  ;; digest(ptr, len, out): out[0..4] = checksum (LE), out[4..8] = len (LE)
+  (func $digest (param $ptr i32) (param $len i32) (param $out i32)
+    (i32.store (local.get $out) (call $checksum (local.get $ptr) (local.get $len)))
+    (i32.store offset=4 (local.get $out) (local.get $len)))
+
+  ;; scale(point*, factor, out*): out = { x * factor, y * factor }
+  (func $scale (param $p i32) (param $factor i32) (param $out i32)
+    (i32.store (local.get $out)
+      (i32.mul (i32.load (local.get $p)) (local.get $factor)))
+    (i32.store offset=4 (local.get $out)
+      (i32.mul (i32.load offset=4 (local.get $p)) (local.get $factor))))
+
+  ;; divmod(a, b, rem*) -> a / b, *rem = a % b
+  (func $divmod (param $a i32) (param $b i32) (param $rem i32) (result i32)
+    (i32.store (local.get $rem) (i32.rem_u (local.get $a) (local.get $b)))
+    (i32.div_u (local.get $a) (local.get $b)))
+
+  (export "checksum" (func $checksum))
+  (export "xor_in_place" (func $xor_in_place))
+  (export "digest" (func $digest))
+  (export "scale" (func $scale))
+  (export "divmod" (func $divmod)))
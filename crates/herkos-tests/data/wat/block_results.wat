@@ -0,0 +1,83 @@
+;; Blocks and ifs whose result arrives along several edges: fall-through,
+;; `br` / `br_if` / `br_table` carrying a value, and branches out of nested
+;; frames.
+(module
+  ;; func_0: br_if leaves its value for the fall-through path
+  (func (export "br_if_value") (param i32) (result i32)
+    (block (result i32)
+      (i32.const 10)
+      (br_if 0 (local.get 0))
+      (drop)
+      (i32.const 20)))
+
+  ;; func_1: then-arm branches out of the enclosing block with a value
+  (func (export "if_branch_out") (param i32) (result i32)
+    (block $out (result i32)
+      (if (result i32) (local.get 0)
+        (then (br $out (i32.const 7)))
+        (else (i32.const 3)))
+      (i32.const 100)
+      (i32.add)))
+
+  ;; func_2: br_table picks one of three labeled blocks, each yielding a value
+  (func (export "classify") (param i32) (result i32)
+    (block $done (result i32)
+      (block $two (result i32)
+        (block $one (result i32)
+          (block $zero (result i32)
+            (i32.const 1000)
+            (local.get 0)
+            (br_table $zero $one $two))
+          (i32.const 1)
+          (i32.add)
+          (br $done))
+        (i32.const 2)
+        (i32.add)
+        (br $done))
+      (i32.const 3)
+      (i32.add)))
+
+  ;; func_3: loop exits through the enclosing block with the running sum
+  (func (export "sum_to") (param i32) (result i32)
+    (local $acc i32)
+    (block $exit (result i32)
+      (loop $again
+        (local.set $acc (i32.add (local.get $acc) (local.get 0)))
+        (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+        (br_if $exit (local.get $acc) (i32.eqz (local.get 0)))
+        (br $again))
+      (i32.const -1)))
+
+  ;; func_4: value-carrying branch from an if nested two blocks deep
+  (func (export "nested") (param i32 i32) (result i32)
+    (block $outer (result i32)
+      (block $inner (result i32)
+        (if (local.get 0)
+          (then (br $outer (i32.mul (local.get 1) (i32.const 2)))))
+        (if (local.get 1)
+          (then (br $inner (i32.const 5))))
+        (i32.const 9))
+      (i32.const 1)
+      (i32.add)))
+
+  ;; func_5: br_if to the function label returns its value early
+  (func (export "early_exit") (param i32) (result i32)
+    (block
+      (drop (br_if 1 (i32.const 42) (local.get 0))))
+    (i32.const 0))
+
+  ;; func_6: br_table mixing the function label with a block label
+  (func (export "table_exit") (param i32) (result i32)
+    (block $b (result i32)
+      (i32.const 5)
+      (local.get 0)
+      (br_table 1 $b 1))
+    (i32.const 10)
+    (i32.add))
+
+  ;; func_7: br 0 at function level returns, skipping the rest of the body
+  (func (export "br_return") (result i64)
+    (i64.const 3)
+    (br 0)
+    (drop)
+    (i64.const 4)))
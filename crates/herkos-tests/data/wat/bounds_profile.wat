@@ -0,0 +1,21 @@
+;; A module profiled with `--bounds-profile`: which of its memory accesses
+;; check their bounds most often?
+(module
+  (memory 1 1)
+  ;; Sum of the $len bytes at $ptr, stored after them.
+  (func (export "checksum") (param $ptr i32) (param $len i32) (result i32)
+    (local $i i32)
+    (local $sum i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $sum
+          (i32.add (local.get $sum)
+            (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.store (i32.add (local.get $ptr) (local.get $len)) (local.get $sum))
+    (local.get $sum))
+  ;; Never called by the tests.
+  (func (export "clear") (param $ptr i32) (param $len i32)
+    (memory.fill (local.get $ptr) (i32.const 0) (local.get $len))))
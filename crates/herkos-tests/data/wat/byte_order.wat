@@ -0,0 +1,47 @@
+(module
+  (memory 1 1)
+
+  ;; Known multi-byte values laid out byte by byte
+  (data (i32.const 64) "\08\07\06\05\04\03\02\81")
+  (data (i32.const 72) "\00\00\80\3f")
+
+  (func (export "store_i32") (param i32 i32)
+    local.get 0
+    local.get 1
+    i32.store)
+
+  (func (export "store_i64") (param i32 i64)
+    local.get 0
+    local.get 1
+    i64.store)
+
+  (func (export "store_f64") (param i32 f64)
+    local.get 0
+    local.get 1
+    f64.store)
+
+  (func (export "store_i16") (param i32 i32)
+    local.get 0
+    local.get 1
+    i32.store16)
+
+  (func (export "load_byte") (param i32) (result i32)
+    local.get 0
+    i32.load8_u)
+
+  (func (export "load_i64") (param i32) (result i64)
+    local.get 0
+    i64.load)
+
+  (func (export "load_f32") (param i32) (result f32)
+    local.get 0
+    f32.load)
+
+  (func (export "load_i16_s") (param i32) (result i32)
+    local.get 0
+    i32.load16_s)
+
+  ;; Reads a constant word of the data segment, which the optimizer folds
+  (func (export "data_word") (result i32)
+    i32.const 68
+    i32.load))
@@ -0,0 +1,17 @@
+;; Imports regrouped into host traits by capability_traits.capabilities:
+;; fd_* into FileSystem, gl_* into Graphics; `log` stays in ModuleHostTrait
+(module
+  (import "env" "fd_open" (func $fd_open (param i32) (result i32)))
+  (import "env" "gl_clear" (func $gl_clear (param i32)))
+  (import "env" "fd_write" (func $fd_write (param i32 i32) (result i32)))
+  (import "env" "log" (func $log (param i32)))
+
+  ;; Open file `path`, write `value` to it and clear the screen to `value`
+  (func (export "save_and_draw") (param $path i32) (param $value i32) (result i32)
+    (local $fd i32)
+    (local.set $fd (call $fd_open (local.get $path)))
+    (call $gl_clear (local.get $value))
+    (call $log (local.get $fd))
+    (call $fd_write (local.get $fd) (local.get $value))
+  )
+)
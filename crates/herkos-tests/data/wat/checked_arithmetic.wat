@@ -0,0 +1,16 @@
+;; Integer arithmetic transpiled with `--checked-arithmetic`: signed overflow
+;; in add, sub and mul traps with `HostAssertedOverflow` instead of wrapping.
+(module
+  (func (export "add32") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1)))
+  (func (export "sub32") (param i32 i32) (result i32)
+    (i32.sub (local.get 0) (local.get 1)))
+  (func (export "mul32") (param i32 i32) (result i32)
+    (i32.mul (local.get 0) (local.get 1)))
+  (func (export "add64") (param i64 i64) (result i64)
+    (i64.add (local.get 0) (local.get 1)))
+  (func (export "mul64") (param i64 i64) (result i64)
+    (i64.mul (local.get 0) (local.get 1)))
+  ;; Shifts are not arithmetic overflow and keep wrapping.
+  (func (export "shl32") (param i32 i32) (result i32)
+    (i32.shl (local.get 0) (local.get 1))))
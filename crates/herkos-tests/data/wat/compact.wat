@@ -0,0 +1,29 @@
+;; Transpiled with `--compact`: imports, globals, data, loops and a
+;; `br_table`, so most of the generated constructs go through compaction.
+(module
+  (import "env" "report" (func $report (param i32) (result i32)))
+  (memory 1 1)
+  (global $calls (mut i32) (i32.const 0))
+  (data (i32.const 16) "hello, world")
+
+  (func (export "sum_bytes") (param $ptr i32) (param $len i32) (result i32)
+    (local $acc i32)
+    (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $len)))
+        (local.set $acc (i32.add (local.get $acc) (i32.load8_u (local.get $ptr))))
+        (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+        (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+        (br $next)))
+    (call $report (local.get $acc)))
+
+  (func (export "classify") (param i32) (result i32)
+    (block $c (block $b (block $a
+      (br_table $a $b $c (local.get 0)))
+      (return (i32.const -1)))
+      (return (i32.lt_s (local.get 0) (i32.const -5))))
+    (i32.const 100))
+
+  (func (export "calls") (result i32)
+    (global.get $calls)))
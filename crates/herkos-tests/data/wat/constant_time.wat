@@ -0,0 +1,35 @@
+(module
+  (memory 1 1)
+
+  ;; 1 if the 16 bytes at $a and $b are equal, 0 otherwise. The loop runs
+  ;; all 16 iterations whatever the data.
+  (func (export "ct_eq") (param $a i32) (param $b i32) (result i32)
+    (local $i i32) (local $diff i32)
+    (loop $bytes
+      (local.set $diff
+        (i32.or (local.get $diff)
+          (i32.xor
+            (i32.load8_u (i32.add (local.get $a) (local.get $i)))
+            (i32.load8_u (i32.add (local.get $b) (local.get $i))))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $bytes (i32.lt_u (local.get $i) (i32.const 16))))
+    (i32.eqz (local.get $diff)))
+
+  ;; $x if $c is non-zero, $y otherwise, plus the same choice over floats
+  (func (export "ct_choose") (param $c i32) (param $x i64) (param $y i64) (result i64)
+    (i64.add
+      (select (local.get $x) (local.get $y) (local.get $c))
+      (i64.trunc_f64_s
+        (select (f64.const 0.5) (f64.const 2.5) (local.get $c)))))
+
+  ;; Not constant-time: exits early on the first difference
+  (func (export "leaky_eq") (param $a i32) (param $b i32) (result i32)
+    (local $i i32)
+    (loop $bytes
+      (if (i32.ne
+            (i32.load8_u (i32.add (local.get $a) (local.get $i)))
+            (i32.load8_u (i32.add (local.get $b) (local.get $i))))
+        (then (return (i32.const 0))))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $bytes (i32.lt_u (local.get $i) (i32.const 16))))
+    (i32.const 1)))
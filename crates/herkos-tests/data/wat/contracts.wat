@@ -0,0 +1,21 @@
+;; Export contracts declared by the guest in a `herkos.contracts` custom
+;; section: the generated methods check them with `debug_assert!`.
+(module
+  (memory 1 1)
+  (@custom "herkos.contracts" "require set_volume 0 range 0..=100\nrequire checksum 0 nonnull\nrequire checksum 1 range 0..=256\n")
+  (global $volume (mut i32) (i32.const 50))
+  (func (export "set_volume") (param $v i32)
+    (global.set $volume (local.get $v)))
+  (func (export "volume") (result i32)
+    (global.get $volume))
+  ;; Sum of the $len bytes at $ptr.
+  (func (export "checksum") (param $ptr i32) (param $len i32) (result i32)
+    (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $len)))
+        (local.set $acc (i32.add (local.get $acc) (i32.load8_u (local.get $ptr))))
+        (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+        (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+        (br $next)))
+    (local.get $acc)))
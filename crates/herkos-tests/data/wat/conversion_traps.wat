@@ -0,0 +1,36 @@
+(module
+  ;; Every trapping conversion and division, for the parity table in
+  ;; tests/conversion_traps.rs
+  (func (export "i32_trunc_f32_s") (param f32) (result i32) local.get 0 i32.trunc_f32_s)
+  (func (export "i32_trunc_f32_u") (param f32) (result i32) local.get 0 i32.trunc_f32_u)
+  (func (export "i32_trunc_f64_s") (param f64) (result i32) local.get 0 i32.trunc_f64_s)
+  (func (export "i32_trunc_f64_u") (param f64) (result i32) local.get 0 i32.trunc_f64_u)
+  (func (export "i64_trunc_f32_s") (param f32) (result i64) local.get 0 i64.trunc_f32_s)
+  (func (export "i64_trunc_f32_u") (param f32) (result i64) local.get 0 i64.trunc_f32_u)
+  (func (export "i64_trunc_f64_s") (param f64) (result i64) local.get 0 i64.trunc_f64_s)
+  (func (export "i64_trunc_f64_u") (param f64) (result i64) local.get 0 i64.trunc_f64_u)
+
+  (func (export "i32_div_s") (param i32 i32) (result i32) local.get 0 local.get 1 i32.div_s)
+  (func (export "i32_div_u") (param i32 i32) (result i32) local.get 0 local.get 1 i32.div_u)
+  (func (export "i32_rem_s") (param i32 i32) (result i32) local.get 0 local.get 1 i32.rem_s)
+  (func (export "i32_rem_u") (param i32 i32) (result i32) local.get 0 local.get 1 i32.rem_u)
+  (func (export "i64_div_s") (param i64 i64) (result i64) local.get 0 local.get 1 i64.div_s)
+  (func (export "i64_div_u") (param i64 i64) (result i64) local.get 0 local.get 1 i64.div_u)
+  (func (export "i64_rem_s") (param i64 i64) (result i64) local.get 0 local.get 1 i64.rem_s)
+  (func (export "i64_rem_u") (param i64 i64) (result i64) local.get 0 local.get 1 i64.rem_u)
+
+  ;; The same edge cases on constant operands, which the optimizer may fold
+  (func (export "const_i32_div_s_overflow") (result i32)
+    i32.const 0x80000000 i32.const -1 i32.div_s)
+  (func (export "const_i64_div_s_overflow") (result i64)
+    i64.const 0x8000000000000000 i64.const -1 i64.div_s)
+  (func (export "const_i32_rem_s_overflow") (result i32)
+    i32.const 0x80000000 i32.const -1 i32.rem_s)
+  (func (export "const_i64_rem_s_overflow") (result i64)
+    i64.const 0x8000000000000000 i64.const -1 i64.rem_s)
+  (func (export "const_i32_trunc_f64_s_below_min") (result i32)
+    f64.const -2147483648.9 i32.trunc_f64_s)
+  (func (export "const_i32_trunc_f64_s_nan") (result i32)
+    f64.const nan i32.trunc_f64_s)
+  (func (export "const_i64_trunc_f32_u_neg") (result i64)
+    f32.const -0.9 i64.trunc_f32_u))
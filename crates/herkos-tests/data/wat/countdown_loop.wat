@@ -1,6 +1,6 @@
 (module
   (func (param i32) (result i32)
-    block (result i32)
+    block
       loop
         local.get 0
         i32.const 0
@@ -13,6 +13,6 @@
         local.set 0
         br 0
       end
-      local.get 0
-    end)
+    end
+    local.get 0)
   (export "func_0" (func 0)))
@@ -0,0 +1,24 @@
+;; A third-party module under test, transpiled with `--coverage`: which of
+;; its branches do the tests below reach?
+(module
+  ;; -1, 0 or 1 by the sign of $x.
+  (func (export "sign") (param $x i32) (result i32)
+    (if (result i32) (i32.lt_s (local.get $x) (i32.const 0))
+      (then (i32.const -1))
+      (else
+        (if (result i32) (i32.eqz (local.get $x))
+          (then (i32.const 0))
+          (else (i32.const 1))))))
+  ;; 1 + 2 + ... + $n.
+  (func (export "triangle") (param $n i32) (result i32)
+    (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (local.get $n)))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $next)))
+    (local.get $acc))
+  ;; Never called by the tests.
+  (func (export "unused") (result i32)
+    (i32.const 7)))
@@ -0,0 +1,18 @@
+;; Static constructors in the wasm-ld convention: `__wasm_call_ctors` fills
+;; a table in memory and counts its runs in a global. Transpiled without
+;; `call_ctors`, so the host calls the generated `initialize()`.
+(module
+  (memory 1 1)
+  (global $runs (mut i32) (i32.const 0))
+
+  (func $ctors
+    (i32.store (i32.const 16) (i32.const 42))
+    (global.set $runs (i32.add (global.get $runs) (i32.const 1))))
+
+  (func (export "value") (result i32)
+    (i32.load (i32.const 16)))
+
+  (func (export "runs") (result i32)
+    (global.get $runs))
+
+  (export "__wasm_call_ctors" (func $ctors)))
@@ -0,0 +1,25 @@
+;; Internal helpers with parameters they never read. With optimizations the
+;; parameters and their call arguments are dropped; the instructions that
+;; computed the arguments (here a division that can trap, and a store) must
+;; still run.
+(module
+  (memory 1 1)
+
+  (func $second (param i32 i32 i32) (result i32)
+    (local.get 1))
+
+  (func $forward (param i32 i32) (result i32)
+    (call $second (local.get 0) (local.get 1) (local.get 0)))
+
+  (func (export "pick") (param $a i32) (param $b i32) (result i32)
+    (call $forward (i32.div_s (i32.const 100) (local.get $a)) (local.get $b)))
+
+  (func $bump (result i32)
+    (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+    (i32.load (i32.const 0)))
+
+  (func (export "counted") (param $b i32) (result i32)
+    (call $second (call $bump) (local.get $b) (call $bump)))
+
+  (func (export "count") (result i32)
+    (i32.load (i32.const 0))))
@@ -0,0 +1,29 @@
+;; Mistakes ported C code gets away with on zeroed memory, transpiled with
+;; `--debug-memory`: a static `int table[4] = {1, 2, 3, 4}` at 0x100 and
+;; memory nothing initialized.
+(module
+  (memory 1 1)
+  (data (i32.const 0x100) "\01\00\00\00\02\00\00\00\03\00\00\00\04\00\00\00")
+  ;; Sum of the first $n entries of the table; $n = 5 reads past its end.
+  (func (export "sum") (param $n i32) (result i32)
+    (local $i i32) (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (local.set $acc
+          (i32.add (local.get $acc)
+            (i32.load offset=0x100 (i32.shl (local.get $i) (i32.const 2)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $acc))
+  ;; table[$i] = $val, with no bounds check.
+  (func (export "set") (param $i i32) (param $val i32)
+    (i32.store offset=0x100 (i32.shl (local.get $i) (i32.const 2)) (local.get $val)))
+  ;; A counter at 0x200 that is never initialized: zero only by accident.
+  (func (export "count") (result i32)
+    (i32.store offset=0x200 (i32.const 0)
+      (i32.add (i32.load offset=0x200 (i32.const 0)) (i32.const 1)))
+    (i32.load offset=0x200 (i32.const 0)))
+  ;; Initialize the counter.
+  (func (export "reset")
+    (i32.store offset=0x200 (i32.const 0) (i32.const 0))))
@@ -0,0 +1,41 @@
+;; Two instantiations of the same "template", as C++ compilers emit them.
+;; With optimizations `scale_b` merges into `scale_a`, then `sum_b` (which
+;; now calls `scale_a` too) into `sum_a`; exports and table entries of the
+;; merged copies must keep working.
+(module
+  (type $unary (func (param i32) (result i32)))
+  (table 3 funcref)
+  (elem (i32.const 0) $scale_a $scale_b $negate)
+
+  (func $scale_a (export "scale_a") (param i32) (result i32)
+    (i32.add (i32.mul (local.get 0) (i32.const 3)) (i32.const 1)))
+
+  (func $scale_b (export "scale_b") (param i32) (result i32)
+    (i32.add (i32.mul (local.get 0) (i32.const 3)) (i32.const 1)))
+
+  (func $negate (param i32) (result i32)
+    (i32.sub (i32.const 0) (local.get 0)))
+
+  ;; sum of scale(i) for i in 0..n
+  (func $sum_a (export "sum_a") (param $n i32) (result i32)
+    (local $i i32) (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (call $scale_a (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $acc))
+
+  (func $sum_b (export "sum_b") (param $n i32) (result i32)
+    (local $i i32) (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (call $scale_b (local.get $i))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $acc))
+
+  (func (export "dispatch") (param $slot i32) (param $x i32) (result i32)
+    (call_indirect (type $unary) (local.get $x) (local.get $slot))))
@@ -0,0 +1,17 @@
+;; Emscripten-style side module (`-sSIDE_MODULE`): it imports the shared
+;; memory and table and places its data and element segments relative to
+;; `__memory_base` and `__table_base`, as announced in `dylink.0`.
+(module
+  (@dylink.0 (mem-info (memory 4 2) (table 1 0)) (needed "libmain.so"))
+  (import "env" "memory" (memory 1))
+  (import "env" "__indirect_function_table" (table 1 funcref))
+  (import "env" "__memory_base" (global $__memory_base i32))
+  (import "env" "__table_base" (global $__table_base i32))
+  (type $getter (func (result i32)))
+  (func $answer (type $getter) (i32.load (global.get $__memory_base)))
+  (elem (global.get $__table_base) $answer)
+  (data (global.get $__memory_base) "\2a\00\00\00")
+  (func (export "answer_ptr") (result i32)
+    (i32.add (global.get $__table_base) (i32.const 0)))
+  (func (export "call") (param $ptr i32) (result i32)
+    (call_indirect (type $getter) (local.get $ptr))))
@@ -0,0 +1,18 @@
+;; Second side module linked next to `dylink_side_a.wat` in the same memory
+;; and table. Its data starts 8 bytes into its area, and its table area holds
+;; two functions.
+(module
+  (@dylink.0 (mem-info (memory 16 3) (table 2 0)))
+  (import "env" "memory" (memory 1))
+  (import "env" "__indirect_function_table" (table 2 funcref))
+  (import "env" "__memory_base" (global $__memory_base i32))
+  (import "env" "__table_base" (global $__table_base i32))
+  (type $getter (func (result i32)))
+  (func $first (type $getter)
+    (i32.load (i32.add (global.get $__memory_base) (i32.const 8))))
+  (func $second (type $getter)
+    (i32.load (i32.add (global.get $__memory_base) (i32.const 12))))
+  (elem (global.get $__table_base) $first $second)
+  (data (i32.add (global.get $__memory_base) (i32.const 8)) "\07\00\00\00\08\00\00\00")
+  (func (export "call") (param $ptr i32) (result i32)
+    (call_indirect (type $getter) (local.get $ptr))))
@@ -0,0 +1,18 @@
+;; Exports of every value type, transpiled with `--dynamic-invoke` so tests
+;; can call them by name with `herkos_runtime::Value` arguments.
+(module
+  (global $count (mut i32) (i32.const 0))
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1)))
+  (func (export "widen") (param i32) (result i64)
+    (i64.extend_i32_s (local.get 0)))
+  (func (export "scale-f32") (param f32 f32) (result f32)
+    (f32.mul (local.get 0) (local.get 1)))
+  (func (export "half") (param f64) (result f64)
+    (f64.div (local.get 0) (f64.const 2)))
+  (func (export "bump")
+    (global.set $count (i32.add (global.get $count) (i32.const 1))))
+  (func (export "count") (result i32)
+    (global.get $count))
+  (func (export "div") (param i32 i32) (result i32)
+    (i32.div_s (local.get 0) (local.get 1))))
@@ -0,0 +1,15 @@
+;; Exports in two name-prefix groups plus one ungrouped, transpiled with
+;; `--export-group image_ --export-group audio_`.
+(module
+  (import "env" "beep" (func $beep (param i32)))
+  (global $volume (mut i32) (i32.const 5))
+  (func (export "image_area") (param i32 i32) (result i32)
+    (i32.mul (local.get 0) (local.get 1)))
+  (func (export "image_flip") (param i32) (result i32)
+    (i32.sub (i32.const 0) (local.get 0)))
+  (func (export "audio_set_volume") (param i32)
+    (global.set $volume (local.get 0)))
+  (func (export "audio_play")
+    (call $beep (global.get $volume)))
+  (func (export "version") (result i32)
+    (i32.const 3)))
@@ -0,0 +1,11 @@
+;; C module linked with `-Wl,--export-table`: the indirect function table is
+;; exported to the host as `__indirect_function_table`.
+(module
+  (type $unary (func (param i32) (result i32)))
+  (table (export "__indirect_function_table") 3 3 funcref)
+  (memory (export "memory") 1 1)
+  (func $double (type $unary) (i32.mul (local.get 0) (i32.const 2)))
+  (func $inc (type $unary) (i32.add (local.get 0) (i32.const 1)))
+  (elem (i32.const 1) $double $inc)
+  (func (export "apply") (param $index i32) (param $x i32) (result i32)
+    (call_indirect (type $unary) (local.get $x) (local.get $index))))
@@ -0,0 +1,14 @@
+;; Exports gated on Cargo features by `feature_gates.features`.
+;; `area` and `volume` share the internal `$square`.
+(module
+  (func $square (param $x i32) (result i32)
+    (i32.mul (local.get $x) (local.get $x)))
+
+  (func (export "area") (param $side i32) (result i32)
+    (call $square (local.get $side)))
+
+  (func (export "volume") (param $side i32) (result i32)
+    (i32.mul (call $square (local.get $side)) (local.get $side)))
+
+  (func (export "version") (result i32)
+    (i32.const 3)))
@@ -0,0 +1,18 @@
+;; Float constants emitted exactly: NaN payloads and signs, infinities,
+;; subnormals and negative zero, inline and as global initializers.
+(module
+  (global $quiet_nan f32 (f32.const nan:0x200001))
+  (global $floor (mut f64) (f64.const -inf))
+
+  (func (export "nan_const") (result f32)
+    (f32.const -nan:0x7f0001))
+  (func (export "nan_global") (result f32)
+    (global.get $quiet_nan))
+  (func (export "floor") (result f64)
+    (global.get $floor))
+  (func (export "infinity") (result f32)
+    (f32.const inf))
+  (func (export "tiny") (result f64)
+    (f64.const 0x1p-1074))
+  (func (export "neg_zero") (result f32)
+    (f32.const -0)))
@@ -0,0 +1,14 @@
+;; f32/f64 min and max. Wasm propagates NaN and orders -0 below +0, where
+;; Rust's `f32::min`/`max` return the other operand for NaN and either zero.
+(module
+  (func (export "f32_min") (param f32 f32) (result f32) (f32.min (local.get 0) (local.get 1)))
+  (func (export "f32_max") (param f32 f32) (result f32) (f32.max (local.get 0) (local.get 1)))
+  (func (export "f64_min") (param f64 f64) (result f64) (f64.min (local.get 0) (local.get 1)))
+  (func (export "f64_max") (param f64 f64) (result f64) (f64.max (local.get 0) (local.get 1)))
+
+  ;; The same on constants, folded by the optimizer
+  (func (export "const_min_zeros") (result f32) (f32.min (f32.const 0) (f32.const -0)))
+  (func (export "const_max_zeros") (result f64) (f64.max (f64.const -0) (f64.const 0)))
+  (func (export "const_min_nan") (result f64) (f64.min (f64.const 1) (f64.const nan)))
+  (func (export "const_max_nan") (result f32) (f32.max (f32.const nan) (f32.const 1)))
+)
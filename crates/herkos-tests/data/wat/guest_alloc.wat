@@ -0,0 +1,49 @@
+(module
+  (memory 1 1)
+
+  ;; Bump allocator: 8-byte aligned blocks from 0x1000 up to the end of the
+  ;; first page. $live counts blocks not yet passed to free.
+  (global $heap (mut i32) (i32.const 0x1000))
+  (global $live (mut i32) (i32.const 0))
+
+  (func $malloc (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+    (if (i64.gt_u (i64.add (i64.extend_i32_u (local.get $ptr))
+                           (i64.extend_i32_u (local.get $size)))
+                  (i64.const 0x10000))
+      (then (return (i32.const 0))))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+    (global.set $live (i32.add (global.get $live) (i32.const 1)))
+    (local.get $ptr))
+
+  (func $free (param $ptr i32)
+    (global.set $live (i32.sub (global.get $live) (i32.const 1))))
+
+  (func $live (result i32)
+    (global.get $live))
+
+  ;; checksum(ptr, len) -> sum of bytes
+  (func $checksum (param $ptr i32) (param $len i32) (result i32)
+    (local $i i32) (local $sum i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $sum
+          (i32.add (local.get $sum)
+            (i32.load8_u (i32.add (local.get $ptr) (local.get $i)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $sum))
+
+  ;; digest(ptr, len, out): out[0..4] = checksum (LE), out[4..8] = len (LE)
+  (func $digest (param $ptr i32) (param $len i32) (param $out i32)
+    (i32.store (local.get $out) (call $checksum (local.get $ptr) (local.get $len)))
+    (i32.store offset=4 (local.get $out) (local.get $len)))
+
+  (export "malloc" (func $malloc))
+  (export "free" (func $free))
+  (export "live" (func $live))
+  (export "checksum" (func $checksum))
+  (export "digest" (func $digest)))
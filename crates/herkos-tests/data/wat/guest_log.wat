@@ -0,0 +1,30 @@
+;; Logging imports for --guest-log: a `log(ptr, len)` import, also placed in
+;; the table, and WASI `fd_write`
+(module
+  (import "env" "log" (func $log (param i32 i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (type $log_t (func (param i32 i32)))
+  (memory 1 1)
+  (table 1 funcref)
+  (elem (i32.const 0) $log)
+
+  (data (i32.const 16) "hello")
+  (data (i32.const 32) "from wasi\n")
+  ;; Two iovecs at 64: "hello", "from wasi\n"
+  (data (i32.const 64) "\10\00\00\00\05\00\00\00\20\00\00\00\0a\00\00\00")
+
+  (func (export "greet")
+    (call $log (i32.const 16) (i32.const 5)))
+
+  (func (export "greet_indirect")
+    (call_indirect (type $log_t) (i32.const 16) (i32.const 5) (i32.const 0)))
+
+  ;; Write both iovecs to `fd`; returns the errno
+  (func (export "write") (param $fd i32) (result i32)
+    (call $fd_write (local.get $fd) (i32.const 64) (i32.const 2) (i32.const 96)))
+
+  ;; A message past the end of memory
+  (func (export "log_out_of_bounds")
+    (call $log (i32.const 65534) (i32.const 5)))
+)
@@ -0,0 +1,40 @@
+;; Bump allocator with the C allocator exports. `calloc` and `realloc` go
+;; through `malloc` and `free`; `make_buffer` allocates from inside the
+;; guest and never frees.
+(module
+  (memory 1 1)
+  (global $next (mut i32) (i32.const 1024))
+
+  (func $malloc (export "malloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (if (i32.gt_u (i32.add (global.get $next) (local.get $len)) (i32.const 65536))
+      (then (return (i32.const 0))))
+    (local.set $ptr (global.get $next))
+    ;; keep blocks 8-byte aligned
+    (global.set $next
+      (i32.and
+        (i32.add (i32.add (global.get $next) (local.get $len)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func $free (export "free") (param i32))
+
+  (func (export "calloc") (param $n i32) (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (call $malloc (i32.mul (local.get $n) (local.get $len))))
+    (if (local.get $ptr)
+      (then (memory.fill (local.get $ptr) (i32.const 0)
+              (i32.mul (local.get $n) (local.get $len)))))
+    (local.get $ptr))
+
+  (func (export "realloc") (param $old i32) (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (call $malloc (local.get $len)))
+    (if (i32.and (i32.ne (local.get $ptr) (i32.const 0)) (i32.ne (local.get $old) (i32.const 0)))
+      (then
+        (memory.copy (local.get $ptr) (local.get $old) (local.get $len))
+        (call $free (local.get $old))))
+    (local.get $ptr))
+
+  (func (export "make_buffer") (param $len i32) (result i32)
+    (call $malloc (local.get $len))))
@@ -0,0 +1,19 @@
+;; First build of a hot-reloadable plugin: `tick` counts by one.
+(module
+  (import "env" "log" (func $log (param i32)))
+  (memory 1 4)
+  (global $count (mut i32) (i32.const 0))
+  (global $scale (mut f64) (f64.const 1))
+
+  ;; Advance the counter and count the calls at address 8
+  (func (export "tick") (result i32)
+    (global.set $count (i32.add (global.get $count) (i32.const 1)))
+    (i32.store (i32.const 8) (i32.add (i32.load (i32.const 8)) (i32.const 1)))
+    (call $log (global.get $count))
+    (global.get $count))
+
+  (func (export "set_scale") (param f64)
+    (global.set $scale (local.get 0)))
+
+  (func (export "grow") (result i32)
+    (memory.grow (i32.const 1))))
@@ -0,0 +1,22 @@
+;; Second build of the plugin of `hot_reload_v1.wat`: `tick` counts by ten,
+;; and the state of the first build is read back.
+(module
+  (import "env" "log" (func $log (param i32)))
+  (memory 1 4)
+  (global $count (mut i32) (i32.const 0))
+  (global $scale (mut f64) (f64.const 1))
+
+  (func (export "tick") (result i32)
+    (global.set $count (i32.add (global.get $count) (i32.const 10)))
+    (i32.store (i32.const 8) (i32.add (i32.load (i32.const 8)) (i32.const 1)))
+    (call $log (global.get $count))
+    (global.get $count))
+
+  (func (export "calls") (result i32)
+    (i32.load (i32.const 8)))
+
+  (func (export "scaled") (result f64)
+    (f64.mul (f64.convert_i32_s (global.get $count)) (global.get $scale)))
+
+  (func (export "pages") (result i32)
+    (memory.size)))
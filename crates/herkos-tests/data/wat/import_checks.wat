@@ -0,0 +1,17 @@
+;; A guest that hands host imports pointers into its memory; the sidecar
+;; `import_checks.bindings` says which arguments are pointers, so every host
+;; call checks them first.
+(module
+  (import "env" "write" (func $write (param i32 i32) (result i32)))
+  (import "env" "now" (func $now (param i32)))
+  (memory 1 1)
+  (data (i32.const 16) "hello")
+
+  ;; write(buf, len) through a pointer/length pair
+  (func (export "write_at") (param i32 i32) (result i32)
+    (call $write (local.get 0) (local.get 1)))
+
+  ;; now(&out) through a pointer to an i64
+  (func (export "stamp") (param i32) (result i64)
+    (call $now (local.get 0))
+    (i64.load (local.get 0))))
@@ -0,0 +1,17 @@
+;; Imported memory whose type declares a maximum of 3 pages.
+;; `memory.grow` must fail beyond it even if the host lends a larger memory.
+(module
+  (import "env" "memory" (memory 1 3))
+
+  (func (export "size") (result i32)
+    memory.size)
+
+  (func (export "grow") (param i32) (result i32)
+    local.get 0
+    memory.grow)
+
+  ;; Store past the first page, only valid after growing
+  (func (export "store_last") (param i32)
+    (i32.store (i32.sub (i32.mul (memory.size) (i32.const 65536)) (i32.const 4))
+      (local.get 0)))
+)
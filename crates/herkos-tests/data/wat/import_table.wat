@@ -0,0 +1,11 @@
+;; C module linked with `-Wl,--import-table`: the indirect function table is
+;; imported from the host as `env.__indirect_function_table`.
+(module
+  (type $unary (func (param i32) (result i32)))
+  (import "env" "__indirect_function_table" (table 3 funcref))
+  (memory (export "memory") 1 1)
+  (func $double (type $unary) (i32.mul (local.get 0) (i32.const 2)))
+  (func $inc (type $unary) (i32.add (local.get 0) (i32.const 1)))
+  (elem (i32.const 1) $double $inc)
+  (func (export "apply") (param $index i32) (param $x i32) (result i32)
+    (call_indirect (type $unary) (local.get $x) (local.get $index))))
@@ -0,0 +1,23 @@
+;; Globals initialized from imported globals (the dylink `__memory_base`
+;; pattern): the host supplies the base at instantiation time.
+(module
+  (import "env" "__memory_base" (global $memory_base i32))
+  (memory 1 1)
+
+  ;; Read-only copy of the base, and a bump cursor starting at it.
+  (global $base i32 (global.get $memory_base))
+  (global $cursor (mut i32) (global.get $memory_base))
+
+  (func (export "base") (result i32)
+    (global.get $base))
+
+  ;; Store `value` at the cursor and advance it by 4 bytes.
+  (func (export "push") (param $value i32) (result i32)
+    (local $at i32)
+    (local.set $at (global.get $cursor))
+    (i32.store (local.get $at) (local.get $value))
+    (global.set $cursor (i32.add (local.get $at) (i32.const 4)))
+    (local.get $at))
+
+  (func (export "load") (param $addr i32) (result i32)
+    (i32.load (local.get $addr))))
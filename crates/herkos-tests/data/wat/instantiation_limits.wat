@@ -0,0 +1,16 @@
+;; 12 bytes of data segments and 3 table entries for `new()` to copy.
+(module
+  (memory 1 1)
+  (table 4 funcref)
+  (data (i32.const 0) "hello")
+  (data (i32.const 64) "world!!")
+  (elem (i32.const 0) $one $two $one)
+
+  (func $one (result i32) (i32.const 1))
+  (func $two (result i32) (i32.const 2))
+
+  (func (export "call") (param i32) (result i32)
+    (call_indirect (result i32) (local.get 0)))
+
+  (func (export "load8") (param i32) (result i32)
+    (i32.load8_u (local.get 0))))
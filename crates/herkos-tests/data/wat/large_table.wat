@@ -0,0 +1,16 @@
+;; A table filled by one element segment of 90 entries, above the
+;; threshold for emitting it as a `static ELEMENTS_0` table rather than a
+;; literal in `new`. Slot i holds $inc, $dec or $dbl for i % 3 = 0, 1, 2.
+(module
+  (type $unary (func (param i32) (result i32)))
+  (table 100 funcref)
+  (elem (i32.const 0)
+    $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl $inc $dec $dbl)
+  (elem (i32.const 95) $dbl)
+
+  (func $inc (param i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+  (func $dec (param i32) (result i32) (i32.sub (local.get 0) (i32.const 1)))
+  (func $dbl (param i32) (result i32) (i32.shl (local.get 0) (i32.const 1)))
+
+  (func (export "dispatch") (param $slot i32) (param $x i32) (result i32)
+    (call_indirect (type $unary) (local.get $x) (local.get $slot))))
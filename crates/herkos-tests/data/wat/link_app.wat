@@ -0,0 +1,5 @@
+;; Imports `double` from the module registered as "math"
+(module
+  (import "math" "double" (func $double (param i32) (result i32)))
+  (func (export "quadruple") (param i32) (result i32)
+    (call $double (call $double (local.get 0)))))
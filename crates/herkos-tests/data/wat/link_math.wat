@@ -0,0 +1,8 @@
+;; Served to `link_app` through a `Linker`: counts its calls
+(module
+  (global $calls (mut i32) (i32.const 0))
+  (func (export "double") (param i32) (result i32)
+    (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+    (i32.add (local.get 0) (local.get 0)))
+  (func (export "calls") (result i32)
+    (global.get $calls)))
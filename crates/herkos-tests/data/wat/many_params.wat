@@ -0,0 +1,23 @@
+;; Functions with more parameters than clippy's `too_many_arguments`
+;; threshold: an import, an internal helper and the export calling both.
+(module
+  (import "env" "weigh" (func $weigh (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+
+  ;; 1*a + 2*b + ... + 8*h
+  (func $dot (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+    (i32.add
+      (i32.add
+        (i32.add (local.get 0) (i32.mul (local.get 1) (i32.const 2)))
+        (i32.add (i32.mul (local.get 2) (i32.const 3)) (i32.mul (local.get 3) (i32.const 4))))
+      (i32.add
+        (i32.add (i32.mul (local.get 4) (i32.const 5)) (i32.mul (local.get 5) (i32.const 6)))
+        (i32.add (i32.mul (local.get 6) (i32.const 7)) (i32.mul (local.get 7) (i32.const 8))))))
+
+  ;; dot(args) + weigh(args)
+  (func (export "combine") (param i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+    (i32.add
+      (call $dot (local.get 0) (local.get 1) (local.get 2) (local.get 3)
+                 (local.get 4) (local.get 5) (local.get 6) (local.get 7))
+      (call $weigh (local.get 0) (local.get 1) (local.get 2) (local.get 3)
+                   (local.get 4) (local.get 5) (local.get 6) (local.get 7))))
+)
@@ -0,0 +1,26 @@
+;; Memory laid out the way wasm-ld does it: a greeting in `.rodata`, a
+;; counter in `.data`, the shadow stack above them and the heap at
+;; `__heap_base`. The host finds each region through `layout`.
+(module
+  (memory 2 2)
+  (global $__stack_pointer (mut i32) (i32.const 66576))
+  (global (export "__data_end") i32 (i32.const 1040))
+  (global (export "__heap_base") i32 (i32.const 66576))
+  (data $.rodata (i32.const 1024) "hello")
+  (data $.data (i32.const 1032) "\05\00\00\00")
+  ;; Bump the counter in `.data` and return it.
+  (func (export "bump") (result i32)
+    (i32.store (i32.const 1032) (i32.add (i32.load (i32.const 1032)) (i32.const 1)))
+    (i32.load (i32.const 1032)))
+  ;; Sum of the `n` words the host placed at the heap base.
+  (func (export "sum_heap") (param $n i32) (result i32)
+    (local $p i32) (local $acc i32)
+    (local.set $p (i32.const 66576))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (i32.load (local.get $p))))
+        (local.set $p (i32.add (local.get $p) (i32.const 4)))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $next)))
+    (local.get $acc)))
@@ -0,0 +1,10 @@
+;; Owned memory with data segments, one ending past the first page, and a
+;; passive segment that does not count towards the high-water mark.
+(module
+  (memory 2 4)
+  (data (i32.const 16) "low")
+  (data (i32.const 65530) "straddles")
+  (data "passive")
+
+  (func (export "load") (param i32) (result i32)
+    (i32.load8_u (local.get 0))))
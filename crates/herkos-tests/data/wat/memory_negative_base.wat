@@ -0,0 +1,14 @@
+(module
+  (memory 1 1)
+  ;; Loads and stores with a static offset: a negative base address plus the
+  ;; offset is past 4 GiB, never back in low memory
+  (func (export "store") (param $addr i32) (param $v i32)
+    (i32.store offset=32 (local.get $addr) (local.get $v)))
+  (func (export "load") (param $addr i32) (result i32)
+    (i32.load offset=32 (local.get $addr)))
+  (func (export "store8") (param $addr i32) (param $v i32)
+    (i32.store8 offset=16 (local.get $addr) (local.get $v)))
+  (func (export "load8_u") (param $addr i32) (result i32)
+    (i32.load8_u offset=16 (local.get $addr)))
+  (func (export "load64") (param $addr i32) (result i64)
+    (i64.load offset=65536 (local.get $addr))))
@@ -0,0 +1,91 @@
+;; Multi-value: functions returning several results, blocks taking
+;; parameters and producing several results, and loops carrying values on
+;; the stack.
+(module
+  (type $pair (func (param i32 i32) (result i32 i32)))
+  (type $to_i32 (func (result i32)))
+  (table 1 funcref)
+  (elem (i32.const 0) $divmod)
+
+  ;; func_0: quotient and remainder, returned as a tuple
+  (func $divmod (export "divmod") (param i32 i32) (result i32 i32)
+    (i32.div_u (local.get 0) (local.get 1))
+    (i32.rem_u (local.get 0) (local.get 1)))
+
+  ;; func_1: mixed result types in order
+  (func (export "widen") (param i32) (result i64 i32)
+    (i64.extend_i32_u (local.get 0))
+    (local.get 0))
+
+  ;; func_2: both results of a call, recombined
+  (func (export "sum_of_parts") (param i32 i32) (result i32)
+    (call $divmod (local.get 0) (local.get 1))
+    (i32.add))
+
+  ;; func_3: a block consuming two stack values and yielding two, with a
+  ;; br_if carrying both out early
+  (func (export "swap_unless") (param i32 i32 i32) (result i32 i32)
+    (local.get 0)
+    (local.get 1)
+    (block (param i32 i32) (result i32 i32)
+      (br_if 0 (local.get 2))
+      (local.set 0)
+      (local.set 1)
+      (local.get 0)
+      (local.get 1)))
+
+  ;; func_4: if with a parameter, each arm producing magnitude and sign
+  (func (export "split_sign") (param i32) (result i32 i32)
+    (local.get 0)
+    (if (param i32) (result i32 i32) (i32.lt_s (local.get 0) (i32.const 0))
+      (then (i32.mul (i32.const -1)) (i32.const -1))
+      (else (i32.const 1))))
+
+  ;; func_5: if without else passes its parameter through unchanged
+  (func (export "double_if") (param i32 i32) (result i32)
+    (local.get 0)
+    (if (param i32) (result i32) (local.get 1)
+      (then (i32.mul (i32.const 2)))))
+
+  ;; func_6: a loop carrying its accumulator and counter on the stack
+  (func (export "triangle") (param i32) (result i32)
+    (i32.const 0)
+    (local.get 0)
+    (loop $next (param i32 i32) (result i32 i32)
+      (local.set 0)
+      (i32.add (local.get 0))
+      (local.tee 0 (i32.sub (local.get 0) (i32.const 1)))
+      (br_if $next (i32.gt_s (local.get 0) (i32.const 0))))
+    (drop))
+
+  ;; func_7: call_indirect through a multi-value type
+  (func (export "divmod_indirect") (param i32 i32) (result i32 i32)
+    (call_indirect (type $pair) (local.get 0) (local.get 1) (i32.const 0)))
+  ;; func_8: br_table choosing between continuing a loop with its parameter
+  ;; and leaving the enclosing block with it as the result
+  (func (export "bit_length") (param i32) (result i32)
+    (local $steps i32)
+    (local.get 0)
+    (block $done (param i32) (result i32)
+      (loop $next (param i32) (result i32)
+        (local.set 0)
+        (local.set $steps (i32.add (local.get $steps) (i32.const 1)))
+        (i32.shr_u (local.get 0) (i32.const 1))
+        (br_table $next $done (i32.eqz (i32.shr_u (local.get 0) (i32.const 1))))))
+    (drop)
+    (local.get $steps))
+
+  ;; func_9: a single-result block typed by index, as multi-value
+  ;; toolchains emit
+  (func (export "pick") (param i32) (result i32)
+    (block (type $to_i32)
+      (br_if 0 (i32.const 10) (local.get 0))
+      (drop)
+      (i32.const 20)))
+
+  ;; func_10: a block whose parameter only leaves it through a branch
+  (func (export "nonzero_or_trap") (param i32) (result i32)
+    (local.get 0)
+    (block (param i32) (result i32)
+      (br_if 0 (local.get 0))
+      (unreachable))))
@@ -0,0 +1,17 @@
+;; Memory accesses transpiled with `--observe-memory`: every load, store and
+;; bulk operation is reported to the host's `MemoryObserver`.
+(module
+  (memory 1 1)
+  ;; Increment the i32 counter at 0x100 and return its new value.
+  (func (export "bump") (result i32)
+    (i32.store offset=0x100 (i32.const 0)
+      (i32.add (i32.load offset=0x100 (i32.const 0)) (i32.const 1)))
+    (i32.load offset=0x100 (i32.const 0)))
+  (func (export "store8") (param $addr i32) (param $val i32)
+    (i32.store8 (local.get $addr) (local.get $val)))
+  (func (export "load64") (param $addr i32) (result i64)
+    (i64.load (local.get $addr)))
+  (func (export "fill") (param $dst i32) (param $len i32)
+    (memory.fill (local.get $dst) (i32.const 0xaa) (local.get $len)))
+  (func (export "copy") (param $dst i32) (param $src i32) (param $len i32)
+    (memory.copy (local.get $dst) (local.get $src) (local.get $len))))
@@ -0,0 +1,22 @@
+;; Transpiled with `--portable`: float rounding and square roots go through
+;; the runtime's `core`-only helpers, addresses through `effective_address`.
+(module
+  (memory 1 1)
+  (func (export "f32_sqrt") (param f32) (result f32) (f32.sqrt (local.get 0)))
+  (func (export "f32_floor") (param f32) (result f32) (f32.floor (local.get 0)))
+  (func (export "f32_ceil") (param f32) (result f32) (f32.ceil (local.get 0)))
+  (func (export "f32_trunc") (param f32) (result f32) (f32.trunc (local.get 0)))
+  (func (export "f32_nearest") (param f32) (result f32) (f32.nearest (local.get 0)))
+  (func (export "f64_sqrt") (param f64) (result f64) (f64.sqrt (local.get 0)))
+  (func (export "f64_floor") (param f64) (result f64) (f64.floor (local.get 0)))
+  (func (export "f64_ceil") (param f64) (result f64) (f64.ceil (local.get 0)))
+  (func (export "f64_trunc") (param f64) (result f64) (f64.trunc (local.get 0)))
+  (func (export "f64_nearest") (param f64) (result f64) (f64.nearest (local.get 0)))
+
+  ;; Store then load at $addr + 32
+  (func (export "roundtrip") (param $addr i32) (param $v i32) (result i32)
+    (i32.store offset=32 (local.get $addr) (local.get $v))
+    (i32.load offset=32 (local.get $addr)))
+  (func (export "fill") (param $addr i32) (param $len i32)
+    (memory.fill (local.get $addr) (i32.const 0xff) (local.get $len)))
+)
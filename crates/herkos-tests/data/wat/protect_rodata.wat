@@ -0,0 +1,15 @@
+;; Constant data in a segment named `.rodata`, next to mutable `.data`,
+;; transpiled with --protect-rodata.
+(module
+  (memory 1 1)
+  (data $.rodata (i32.const 16) "const")
+  (data $.data (i32.const 32) "state")
+
+  (func (export "store8") (param i32 i32)
+    (i32.store8 (local.get 0) (local.get 1)))
+
+  (func (export "load8") (param i32) (result i32)
+    (i32.load8_u (local.get 0)))
+
+  (func (export "fill") (param i32 i32 i32)
+    (memory.fill (local.get 0) (local.get 1) (local.get 2))))
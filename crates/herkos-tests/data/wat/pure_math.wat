@@ -0,0 +1,16 @@
+;; A module with no memory, table, mutable globals or imports: its exports
+;; can be called without an instance, and its leaf arithmetic in const
+;; contexts.
+(module
+  (func $sq (param i32) (result i32)
+    (i32.mul (local.get 0) (local.get 0)))
+
+  (func (export "square") (param i32) (result i32)
+    (call $sq (local.get 0)))
+
+  (func (export "mix") (param i64 i64) (result i64)
+    (i64.xor (i64.add (local.get 0) (local.get 1))
+             (i64.shl (local.get 1) (i64.const 3))))
+
+  (func (export "div") (param i32 i32) (result i32)
+    (i32.div_s (local.get 0) (local.get 1))))
@@ -0,0 +1,18 @@
+;; Host-dependent computation transpiled with `--record-replay`: the result of
+;; `roll` depends on the host's `random` and the imported `bias`.
+(module
+  (import "env" "random" (func $random (result i32)))
+  (import "env" "report" (func $report (param i32)))
+  (import "env" "bias" (global $bias i32))
+  ;; Sum of `n` random numbers plus the bias, reporting each partial sum.
+  (func (export "roll") (param $n i32) (result i32)
+    (local $acc i32)
+    (local.set $acc (global.get $bias))
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (call $random)))
+        (call $report (local.get $acc))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $next)))
+    (local.get $acc)))
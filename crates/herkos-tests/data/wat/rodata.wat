@@ -0,0 +1,16 @@
+;; Constant data read only at constant addresses. With optimizations the
+;; segment becomes `RODATA_0`, its loads fold to constants and it is not
+;; copied into linear memory; the store to address 0 cannot alias it.
+(module
+  (memory 1 1)
+  (data (i32.const 1024) "\2a\00\00\00\ff\ff")
+
+  (func (export "version") (result i32)
+    (i32.load (i32.const 1024)))
+
+  (func (export "marker") (result i32)
+    (i32.load16_s offset=4 (i32.const 1024)))
+
+  (func (export "scratch") (param i32) (result i32)
+    (i32.store (i32.const 0) (local.get 0))
+    (i32.load (i32.const 0))))
@@ -50,8 +50,42 @@
     local.get 1           ;; condition (flag)
     select)
 
+  ;; func_5: typed select on i32, as newer LLVM emits
+  (func (param i32 i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    local.get 2
+    select (result i32))
+
+  ;; func_6: untyped select on f32
+  (func (param f32 f32 i32) (result f32)
+    local.get 0
+    local.get 1
+    local.get 2
+    select)
+
+  ;; func_7: typed select on f64: max(a, b)
+  (func (param f64 f64) (result f64)
+    local.get 0
+    local.get 1
+    local.get 0
+    local.get 1
+    f64.gt
+    select (result f64))
+
+  ;; func_8: typed select on i64
+  (func (param i64 i64 i32) (result i64)
+    local.get 0
+    local.get 1
+    local.get 2
+    select (result i64))
+
   (export "func_0" (func 0))
   (export "func_1" (func 1))
   (export "func_2" (func 2))
   (export "func_3" (func 3))
-  (export "func_4" (func 4)))
+  (export "func_4" (func 4))
+  (export "func_5" (func 5))
+  (export "func_6" (func 6))
+  (export "func_7" (func 7))
+  (export "func_8" (func 8)))
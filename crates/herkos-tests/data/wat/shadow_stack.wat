@@ -0,0 +1,19 @@
+;; C-style module with an LLVM shadow stack in an imported memory: `push`
+;; reserves a 16-byte frame below `__stack_pointer` and stores its argument
+;; there; `pop` reads it back and releases the frame.
+(module
+  (import "env" "memory" (memory 1 1))
+  (global $__stack_pointer (mut i32) (i32.const 4096))
+
+  (func (export "push") (param $value i32) (result i32)
+    (local $sp i32)
+    (global.set $__stack_pointer
+      (local.tee $sp (i32.sub (global.get $__stack_pointer) (i32.const 16))))
+    (i32.store (local.get $sp) (local.get $value))
+    (local.get $sp))
+
+  (func (export "pop") (result i32)
+    (local $sp i32)
+    (local.set $sp (global.get $__stack_pointer))
+    (global.set $__stack_pointer (i32.add (local.get $sp) (i32.const 16)))
+    (i32.load (local.get $sp))))
@@ -0,0 +1,6 @@
+;; Host-importing module for instances sharing one host (`--shared-host`).
+(module
+  (import "env" "tick" (func $tick (param i32) (result i32)))
+
+  (func (export "bump") (param i32) (result i32)
+    (call $tick (local.get 0))))
@@ -0,0 +1,21 @@
+;; Every shift and rotate, with the count from the caller. Wasm takes the
+;; count modulo 32 or 64, so 32, 64 and negative counts are all in range.
+(module
+  (func (export "i32_shl") (param i32 i32) (result i32) (i32.shl (local.get 0) (local.get 1)))
+  (func (export "i32_shr_s") (param i32 i32) (result i32) (i32.shr_s (local.get 0) (local.get 1)))
+  (func (export "i32_shr_u") (param i32 i32) (result i32) (i32.shr_u (local.get 0) (local.get 1)))
+  (func (export "i32_rotl") (param i32 i32) (result i32) (i32.rotl (local.get 0) (local.get 1)))
+  (func (export "i32_rotr") (param i32 i32) (result i32) (i32.rotr (local.get 0) (local.get 1)))
+  (func (export "i64_shl") (param i64 i64) (result i64) (i64.shl (local.get 0) (local.get 1)))
+  (func (export "i64_shr_s") (param i64 i64) (result i64) (i64.shr_s (local.get 0) (local.get 1)))
+  (func (export "i64_shr_u") (param i64 i64) (result i64) (i64.shr_u (local.get 0) (local.get 1)))
+  (func (export "i64_rotl") (param i64 i64) (result i64) (i64.rotl (local.get 0) (local.get 1)))
+  (func (export "i64_rotr") (param i64 i64) (result i64) (i64.rotr (local.get 0) (local.get 1)))
+
+  ;; The same at constant counts, folded by the optimizer
+  (func (export "const_counts") (result i32)
+    (i32.add
+      (i32.add
+        (i32.shl (i32.const 1) (i32.const 33))
+        (i32.shr_u (i32.const -1) (i32.const 32)))
+      (i32.wrap_i64 (i64.rotr (i64.const 1) (i64.const -63))))))
@@ -0,0 +1,35 @@
+;; A start function that only builds state: a table of squares in memory,
+;; a second page, and a count in a global. It is run while transpiling, so
+;; `new()` starts from the state it leaves.
+(module
+  (memory 1 4)
+  (global $entries (mut i32) (i32.const 0))
+  (data (i32.const 0) "\07")
+
+  (func $square (param i32) (result i32)
+    (i32.mul (local.get 0) (local.get 0)))
+
+  (func $init (local $i i32)
+    (drop (memory.grow (i32.const 1)))
+    (loop $next
+      (i32.store offset=16
+        (i32.shl (local.get $i) (i32.const 2))
+        (call $square (local.get $i)))
+      (local.set $i (i32.add (local.get $i) (i32.const 1)))
+      (br_if $next (i32.lt_u (local.get $i) (i32.const 32))))
+    (i32.store (i32.const 65536) (i32.load8_u (i32.const 0)))
+    (global.set $entries (local.get $i)))
+
+  (start $init)
+
+  (func (export "square") (param i32) (result i32)
+    (i32.load offset=16 (i32.shl (local.get 0) (i32.const 2))))
+
+  (func (export "entries") (result i32)
+    (global.get $entries))
+
+  (func (export "pages") (result i32)
+    (memory.size))
+
+  (func (export "second_page") (result i32)
+    (i32.load (i32.const 65536))))
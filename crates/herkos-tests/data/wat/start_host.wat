@@ -0,0 +1,21 @@
+;; A start function that asks the host for a seed, which cannot be evaluated
+;; while transpiling: `new()` takes the host and calls it once the data
+;; segments are in memory.
+(module
+  (import "env" "seed" (func $seed (result i32)))
+  (memory 1 1)
+  (global $calls (mut i32) (i32.const 0))
+  (data (i32.const 0) "\05")
+
+  (func $init
+    (i32.store (i32.const 4)
+      (i32.add (call $seed) (i32.load8_u (i32.const 0))))
+    (global.set $calls (i32.add (global.get $calls) (i32.const 1))))
+
+  (start $init)
+
+  (func (export "seeded") (result i32)
+    (i32.load (i32.const 4)))
+
+  (func (export "calls") (result i32)
+    (global.get $calls)))
@@ -0,0 +1,9 @@
+;; A start function that traps: instantiation fails, so `new()` returns the
+;; trap instead of a module.
+(module
+  (memory 1 1)
+  (func $init
+    (i32.store (i32.const 65536) (i32.const 1)))
+  (start $init)
+  (func (export "f") (result i32)
+    (i32.const 0)))
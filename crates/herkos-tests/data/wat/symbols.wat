@@ -0,0 +1,12 @@
+(module
+  (import "env" "log" (func $log (param i32)))
+  (func $double (param i32) (result i32)
+    local.get 0
+    i32.const 2
+    i32.mul)
+  (func $halve (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.shr_s)
+  (export "double" (func $double))
+  (export "twice" (func $double)))
@@ -0,0 +1,24 @@
+;; Element segments placing imported functions next to local ones.
+;; `call_indirect` on an import slot calls through the host trait.
+(module
+  (type $unary (func (param i32) (result i32)))
+  (type $sink (func (param i32)))
+
+  (import "env" "double" (func $double (type $unary)))
+  (import "env" "record" (func $record (type $sink)))
+  (import "env" "negate" (func $negate (type $unary)))
+
+  (table 5 funcref)
+  ;; slot 0: import, slot 1: local, slot 2: import of another type,
+  ;; slot 3: import again (second segment), slot 4: empty
+  (elem (i32.const 0) $double $inc $record)
+  (elem (i32.const 3) $negate)
+
+  (func $inc (type $unary)
+    (i32.add (local.get 0) (i32.const 1)))
+
+  (func (export "apply") (param i32 i32) (result i32)
+    (call_indirect (type $unary) (local.get 1) (local.get 0)))
+
+  (func (export "emit") (param i32 i32)
+    (call_indirect (type $sink) (local.get 1) (local.get 0))))
@@ -0,0 +1,13 @@
+;; A plugin registering callbacks in its exported table: a named handler,
+;; an unnamed one reachable only through its export, and a host import.
+;; Slot 1 stays empty.
+(module
+  (import "env" "notify" (func (param i32)))
+  (type $handler (func (param i32 i64) (result i32)))
+  (table (export "callbacks") 4 8 funcref)
+  (func $on_click (type $handler)
+    (i32.add (local.get 0) (i32.wrap_i64 (local.get 1))))
+  (func (export "on_key") (type $handler)
+    (local.get 0))
+  (elem (i32.const 0) $on_click)
+  (elem (i32.const 2) 2 0))
@@ -0,0 +1,33 @@
+;; Hand-written stand-in for a TinyGo `-target=wasm` build: a main goroutine
+;; that sleeps 5 ms three times, woken by the host through `go_scheduler`.
+(module
+  (import "gojs" "runtime.ticks" (func $ticks (result f64)))
+  (import "gojs" "runtime.sleepTicks" (func $sleep_ticks (param f64)))
+  (import "gojs" "syscall/js.valueGet" (func $value_get (param i32 i32 i32 i32 i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1 1)
+
+  (global $wakeups (mut i32) (i32.const 0))
+  (global $last_tick (mut f64) (f64.const -1))
+  (global $write_errno (mut i32) (i32.const -1))
+
+  (func (export "_start")
+    (global.set $write_errno
+      (call $fd_write (i32.const 1) (i32.const 0) (i32.const 0) (i32.const 0)))
+    (call $sleep_ticks (f64.const 5)))
+
+  (func (export "go_scheduler")
+    (global.set $wakeups (i32.add (global.get $wakeups) (i32.const 1)))
+    (global.set $last_tick (call $ticks))
+    (if (i32.lt_u (global.get $wakeups) (i32.const 3))
+      (then (call $sleep_ticks (f64.const 5)))))
+
+  (func (export "resume"))
+
+  (func (export "js_get")
+    (call $value_get (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0)))
+
+  (func (export "wakeups") (result i32) (global.get $wakeups))
+  (func (export "last_tick") (result f64) (global.get $last_tick))
+  (func (export "write_errno") (result i32) (global.get $write_errno)))
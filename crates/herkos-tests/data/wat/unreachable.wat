@@ -30,7 +30,32 @@
     return
     unreachable)
 
+;; func_4: dead code after `return` sees a polymorphic stack, so it may
+  ;; pop values that were never pushed; nested dead constructs are skipped
+  (func (param i32) (result i32)
+    local.get 0
+    i32.eqz
+    if (result i32)
+      i32.const -1
+      return
+      i32.add
+      (if (then nop) (else (br 0)))
+      i32.const 9
+    else
+      local.get 0
+    end)
+
+  ;; func_5: traps for nonzero n (dead i64 op after `unreachable`), else n + 1
+  (func (param i32) (result i32)
+    local.get 0
+    (if (then unreachable i64.eqz drop))
+    local.get 0
+    i32.const 1
+    i32.add)
+
   (export "func_0" (func 0))
   (export "func_1" (func 1))
   (export "func_2" (func 2))
-  (export "func_3" (func 3)))
+  (export "func_3" (func 3))
+  (export "func_4" (func 4))
+  (export "func_5" (func 5)))
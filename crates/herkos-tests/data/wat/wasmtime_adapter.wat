@@ -0,0 +1,22 @@
+(module
+  (import "env" "log" (func $log (param i32)))
+  (memory (export "memory") 1 1)
+
+  ;; add(a, b) -> a + b
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1)))
+
+  ;; negate(x) -> -x
+  (func (export "negate") (param i64) (result i64)
+    (i64.sub (i64.const 0) (local.get 0)))
+
+  ;; answer() -> 42
+  (func (export "answer") (result i32)
+    (i32.const 42))
+
+  ;; sum_bytes(): log(mem[0] + mem[1] + mem[2] + mem[3])
+  (func (export "sum_bytes")
+    (call $log
+      (i32.add
+        (i32.add (i32.load8_u (i32.const 0)) (i32.load8_u (i32.const 1)))
+        (i32.add (i32.load8_u (i32.const 2)) (i32.load8_u (i32.const 3)))))))
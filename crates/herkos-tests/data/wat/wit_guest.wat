@@ -0,0 +1,122 @@
+;; Hand-written canonical-ABI guest for wit_guest.wit: strings and lists are
+;; passed as (ptr, len), results wider than one core value via a return
+;; pointer, and host data is placed with a bump-allocating cabi_realloc.
+(module
+  (import "$root" "log" (func $log (param i32 i32)))
+  (import "test:guest/counter" "bump" (func $bump (param i32) (result i64)))
+  (memory 1 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (global $posted (mut i32) (i32.const 0))
+  (data (i32.const 600) "division by zero")
+
+  ;; cabi_realloc(old, old_size, align, size): bump allocator, never frees.
+  (func (export "cabi_realloc")
+    (param $old i32) (param $old_size i32) (param $align i32) (param $size i32)
+    (result i32)
+    (local $ptr i32)
+    (local.set $ptr
+      (i32.and
+        (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+        (i32.sub (i32.const 0) (local.get $align))))
+    (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+    (local.get $ptr))
+
+  (func (export "greet-len") (param $ptr i32) (param $len i32) (result i32)
+    (local.get $len))
+
+  ;; echo returns the argument string itself through the return area at 512.
+  (func (export "echo") (param $ptr i32) (param $len i32) (result i32)
+    (i32.store (i32.const 512) (local.get $ptr))
+    (i32.store (i32.const 516) (local.get $len))
+    (i32.const 512))
+
+  (func (export "cabi_post_echo") (param $ret i32)
+    (global.set $posted (i32.add (global.get $posted) (i32.const 1))))
+
+  (func (export "posted") (result i32)
+    (global.get $posted))
+
+  (func (export "sum") (param $ptr i32) (param $len i32) (result i64)
+    (local $i i32) (local $acc i64)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $acc
+          (i64.add (local.get $acc)
+            (i64.extend_i32_s
+              (i32.load (i32.add (local.get $ptr) (i32.shl (local.get $i) (i32.const 2)))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (local.get $acc))
+
+  (func (export "make-point") (param $x i32) (param $y i32) (result i32)
+    (i32.store (i32.const 520) (local.get $x))
+    (i32.store (i32.const 524) (local.get $y))
+    (i32.const 520))
+
+  ;; centroid: option<point> at 528 — u8 discriminant, point at offset 4.
+  (func (export "centroid") (param $ptr i32) (param $len i32) (result i32)
+    (local $i i32) (local $sx i32) (local $sy i32) (local $addr i32)
+    (if (i32.eqz (local.get $len))
+      (then
+        (i32.store8 (i32.const 528) (i32.const 0))
+        (return (i32.const 528))))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+        (local.set $addr (i32.add (local.get $ptr) (i32.shl (local.get $i) (i32.const 3))))
+        (local.set $sx (i32.add (local.get $sx) (i32.load (local.get $addr))))
+        (local.set $sy (i32.add (local.get $sy) (i32.load offset=4 (local.get $addr))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.store8 (i32.const 528) (i32.const 1))
+    (i32.store (i32.const 532) (i32.div_s (local.get $sx) (local.get $len)))
+    (i32.store (i32.const 536) (i32.div_s (local.get $sy) (local.get $len)))
+    (i32.const 528))
+
+  ;; classify: option<level> at 544 — none for 0, low for < 0, high for > 0.
+  (func (export "classify") (param $v i32) (result i32)
+    (if (i32.eqz (local.get $v))
+      (then (i32.store8 (i32.const 544) (i32.const 0)))
+      (else
+        (i32.store8 (i32.const 544) (i32.const 1))
+        (i32.store8 (i32.const 545) (i32.gt_s (local.get $v) (i32.const 0)))))
+    (i32.const 544))
+
+  ;; area(disc, a, b): circle(radius as f32 bits in a), rect(point{a, b}), empty.
+  (func (export "area") (param $disc i32) (param $a i32) (param $b i32) (result f32)
+    (if (i32.eqz (local.get $disc))
+      (then
+        (return
+          (f32.mul (f32.const 3)
+            (f32.mul (f32.reinterpret_i32 (local.get $a)) (f32.reinterpret_i32 (local.get $a)))))))
+    (if (i32.eq (local.get $disc) (i32.const 1))
+      (then (return (f32.convert_i32_s (i32.mul (local.get $a) (local.get $b))))))
+    (f32.const 0))
+
+  ;; divide: result<u32, string> at 548 — u8 discriminant, payload at 552.
+  (func (export "divide") (param $a i32) (param $b i32) (result i32)
+    (if (i32.eqz (local.get $b))
+      (then
+        (i32.store8 (i32.const 548) (i32.const 1))
+        (i32.store (i32.const 552) (i32.const 600))
+        (i32.store (i32.const 556) (i32.const 16)))
+      (else
+        (i32.store8 (i32.const 548) (i32.const 0))
+        (i32.store (i32.const 552) (i32.div_u (local.get $a) (local.get $b)))))
+    (i32.const 548))
+
+  (func (export "is-upper") (param $c i32) (result i32)
+    (i32.and
+      (i32.ge_u (local.get $c) (i32.const 65))
+      (i32.le_u (local.get $c) (i32.const 90))))
+
+  (func (export "notify") (param $level i32) (param $code i32)
+    (call $log (local.get $level) (local.get $code)))
+
+  (func (export "count") (param $by i32) (result i64)
+    (call $bump (local.get $by)))
+
+  ;; Returns a discriminant outside `level`, which the host must reject.
+  (func (export "bad-level") (result i32)
+    (i32.const 7)))
@@ -0,0 +1,13 @@
+;; Long-running loop transpiled with `--yield-points`: every iteration of
+;; `sum_to` passes through the host's `maybe_yield`.
+(module
+  (func (export "sum_to") (param $n i32) (result i64)
+    (local $i i32)
+    (local $acc i64)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (local.set $acc (i64.add (local.get $acc) (i64.extend_i32_u (local.get $i))))
+        (br $next)))
+    (local.get $acc)))
@@ -0,0 +1,537 @@
+//! Differential fuzzing of the IR interpreter against the generated code.
+//!
+//! Each case is a random module of integer arithmetic, control flow, memory
+//! accesses, globals and calls between its functions. The build script
+//! transpiles it like any other test module, then calls its exports on
+//! random arguments in the IR interpreter (`herkos_core::ir::eval::Interpreter`)
+//! and writes what each call returned, or that it trapped, followed by the
+//! globals and a checksum of memory, as assertions in `differential.rs`.
+//! `tests/differential.rs` runs them against the compiled module: a
+//! disagreement is a bug in the optimizer or code generation, or in the
+//! interpreter.
+//!
+//! `HERKOS_FUZZ_SEED` and `HERKOS_FUZZ_CASES` pick other modules, and more
+//! of them. Module `i` comes from seed `HERKOS_FUZZ_SEED + i`, which every
+//! assertion names, and its WAT is kept next to the generated Rust.
+
+use anyhow::{bail, Context, Result};
+use herkos_core::ir::eval::{EvalError, Interpreter, START_FUEL};
+use herkos_core::ir::{IrValue, LocalGlobalIdx};
+use herkos_core::{transpile_to_artifacts, TranspileOptions};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// Seed of the first module unless `HERKOS_FUZZ_SEED` says otherwise.
+const DEFAULT_SEED: u64 = 0x4845_524b_4f53;
+
+/// Modules generated unless `HERKOS_FUZZ_CASES` says otherwise.
+const DEFAULT_CASES: u64 = 12;
+
+/// Calls made to each export.
+const CALLS_PER_EXPORT: usize = 6;
+
+/// Nesting of generated expressions and statements.
+const MAX_DEPTH: usize = 3;
+
+/// Addresses are mostly masked into the first KiB, where the data segment is.
+const ADDR_MASK: i32 = 0x3ff;
+
+/// splitmix64: small, and the same on every host.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    /// An integer of `ty`, often one at an edge of some operation.
+    fn int(&mut self, ty: Ty) -> i64 {
+        let edges: [i64; 10] = [0, 1, -1, 2, 7, 31, 32, 63, 64, 0x8000];
+        match (self.below(4), ty) {
+            (0, Ty::I32) => *self.pick(&[i32::MIN as i64, i32::MAX as i64]),
+            (0, Ty::I64) => *self.pick(&[i64::MIN, i64::MAX]),
+            (1, Ty::I32) => self.next() as i32 as i64,
+            (1, Ty::I64) => self.next() as i64,
+            _ => *self.pick(&edges),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ty {
+    I32,
+    I64,
+}
+
+impl Ty {
+    fn name(self) -> &'static str {
+        match self {
+            Ty::I32 => "i32",
+            Ty::I64 => "i64",
+        }
+    }
+
+    fn random(rng: &mut Rng) -> Ty {
+        if rng.chance(50) {
+            Ty::I32
+        } else {
+            Ty::I64
+        }
+    }
+}
+
+const BINOPS: [&str; 15] = [
+    "add", "sub", "mul", "div_s", "div_u", "rem_s", "rem_u", "and", "or", "xor", "shl", "shr_s",
+    "shr_u", "rotl", "rotr",
+];
+const COMPARISONS: [&str; 10] = [
+    "eq", "ne", "lt_s", "lt_u", "gt_s", "gt_u", "le_s", "le_u", "ge_s", "ge_u",
+];
+const UNOPS: [&str; 5] = ["clz", "ctz", "popcnt", "extend8_s", "extend16_s"];
+
+/// Mutable globals of every module: one of each type.
+const GLOBALS: [Ty; 2] = [Ty::I32, Ty::I64];
+
+/// Signature of a generated function.
+struct Signature {
+    params: Vec<Ty>,
+    result: Ty,
+}
+
+/// Generator of one function body.
+struct Body<'a> {
+    rng: &'a mut Rng,
+    /// Functions it may call: those generated before it.
+    callees: &'a [Signature],
+    /// Parameters, then locals.
+    vars: Vec<Ty>,
+    num_params: usize,
+    /// Loop counters, locals after `vars`.
+    counters: usize,
+    labels: usize,
+}
+
+impl Body<'_> {
+    fn expr(&mut self, ty: Ty, depth: usize) -> String {
+        if depth == 0 || self.rng.chance(25) {
+            return self.leaf(ty);
+        }
+        let d = depth - 1;
+        let t = ty.name();
+        match self.rng.below(10) {
+            0 | 1 => {
+                let op = self.rng.pick(&BINOPS);
+                format!("({t}.{op} {} {})", self.expr(ty, d), self.expr(ty, d))
+            }
+            2 if ty == Ty::I32 => {
+                let operands = Ty::random(self.rng);
+                let op = self.rng.pick(&COMPARISONS);
+                format!(
+                    "({}.{op} {} {})",
+                    operands.name(),
+                    self.expr(operands, d),
+                    self.expr(operands, d)
+                )
+            }
+            3 => {
+                let op = if ty == Ty::I64 && self.rng.chance(20) {
+                    "extend32_s"
+                } else {
+                    self.rng.pick(&UNOPS)
+                };
+                format!("({t}.{op} {})", self.expr(ty, d))
+            }
+            4 => match ty {
+                Ty::I32 if self.rng.chance(50) => {
+                    format!("(i64.eqz {})", self.expr(Ty::I64, d))
+                }
+                Ty::I32 => format!("(i32.wrap_i64 {})", self.expr(Ty::I64, d)),
+                Ty::I64 => {
+                    let sign = if self.rng.chance(50) { "s" } else { "u" };
+                    format!("(i64.extend_i32_{sign} {})", self.expr(Ty::I32, d))
+                }
+            },
+            5 => format!(
+                "(select {} {} {})",
+                self.expr(ty, d),
+                self.expr(ty, d),
+                self.expr(Ty::I32, d)
+            ),
+            6 => format!(
+                "(if (result {t}) {} (then {}) (else {}))",
+                self.expr(Ty::I32, d),
+                self.expr(ty, d),
+                self.expr(ty, d)
+            ),
+            7 => {
+                let access = match ty {
+                    Ty::I32 => *self
+                        .rng
+                        .pick(&["load", "load8_s", "load8_u", "load16_s", "load16_u"]),
+                    Ty::I64 => *self.rng.pick(&[
+                        "load", "load8_s", "load8_u", "load16_s", "load16_u", "load32_s",
+                        "load32_u",
+                    ]),
+                };
+                let offset = self.offset();
+                format!("({t}.{access}{offset} {})", self.addr(d))
+            }
+            8 if !self.callees.is_empty() => self.call(ty, d),
+            9 if ty == Ty::I32 && self.rng.chance(20) => {
+                format!(
+                    "(memory.grow (i32.and {} (i32.const 1)))",
+                    self.expr(Ty::I32, d)
+                )
+            }
+            _ => self.leaf(ty),
+        }
+    }
+
+    fn leaf(&mut self, ty: Ty) -> String {
+        let vars: Vec<usize> = (0..self.vars.len())
+            .filter(|&v| self.vars[v] == ty)
+            .collect();
+        match self.rng.below(5) {
+            0 | 1 if !vars.is_empty() => format!("(local.get {})", self.rng.pick(&vars)),
+            2 => {
+                let global = GLOBALS.iter().position(|&g| g == ty).unwrap_or(0);
+                format!("(global.get {global})")
+            }
+            3 if ty == Ty::I32 && self.rng.chance(20) => "(memory.size)".to_string(),
+            _ => format!("({}.const {})", ty.name(), self.rng.int(ty)),
+        }
+    }
+
+    /// An address, mostly one into the data segment.
+    fn addr(&mut self, depth: usize) -> String {
+        let addr = self.expr(Ty::I32, depth);
+        if self.rng.chance(90) {
+            format!("(i32.and {addr} (i32.const {ADDR_MASK}))")
+        } else {
+            addr
+        }
+    }
+
+    fn offset(&mut self) -> String {
+        match self.rng.below(8) {
+            0 => " offset=4".to_string(),
+            1 => " offset=1021".to_string(),
+            2 if self.rng.chance(20) => " offset=65536".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// A call to an earlier function, converted to `ty`.
+    fn call(&mut self, ty: Ty, depth: usize) -> String {
+        let callee = self.rng.below(self.callees.len());
+        let params: Vec<Ty> = self.callees[callee].params.clone();
+        let result = self.callees[callee].result;
+        let args: Vec<String> = params.iter().map(|&p| self.expr(p, depth)).collect();
+        let call = format!("(call {callee} {})", args.join(" "));
+        match (result, ty) {
+            (Ty::I32, Ty::I64) => format!("(i64.extend_i32_u {call})"),
+            (Ty::I64, Ty::I32) => format!("(i32.wrap_i64 {call})"),
+            _ => call,
+        }
+    }
+
+    fn stmts(&mut self, depth: usize) -> String {
+        let count = 1 + self.rng.below(3);
+        (0..count)
+            .map(|_| self.stmt(depth))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn stmt(&mut self, depth: usize) -> String {
+        let d = depth.saturating_sub(1);
+        match self.rng.below(8) {
+            0 | 1 if self.vars.len() > self.num_params => {
+                let local = self.num_params + self.rng.below(self.vars.len() - self.num_params);
+                format!("(local.set {local} {})", self.expr(self.vars[local], d))
+            }
+            2 => {
+                let global = self.rng.below(GLOBALS.len());
+                format!("(global.set {global} {})", self.expr(GLOBALS[global], d))
+            }
+            3 if depth > 0 => format!(
+                "(if {} (then {}) (else {}))",
+                self.expr(Ty::I32, d),
+                self.stmts(d),
+                self.stmts(d)
+            ),
+            4 if depth > 0 => {
+                // Counted down from at most 4, in a local nothing else writes
+                let counter = self.vars.len() + self.counters;
+                self.counters += 1;
+                let label = self.label();
+                let count = 1 + self.rng.below(4);
+                format!(
+                    "(local.set {counter} (i32.const {count})) (loop ${label} {} \
+                     (br_if ${label} (local.tee {counter} (i32.sub (local.get {counter}) (i32.const 1)))))",
+                    self.stmts(d)
+                )
+            }
+            5 if depth > 0 => {
+                let label = self.label();
+                format!(
+                    "(block ${label} {} (br_if ${label} {}) {})",
+                    self.stmts(d),
+                    self.expr(Ty::I32, d),
+                    self.stmts(d)
+                )
+            }
+            6 if self.rng.chance(30) => {
+                let op = if self.rng.chance(50) { "fill" } else { "copy" };
+                let len = format!("(i32.and {} (i32.const 63))", self.expr(Ty::I32, d));
+                let value = match op {
+                    "fill" => self.expr(Ty::I32, d),
+                    _ => self.addr(d),
+                };
+                format!("(memory.{op} {} {value} {len})", self.addr(d))
+            }
+            6 if !self.callees.is_empty() => format!("(drop {})", self.call(Ty::I32, d)),
+            _ => {
+                let ty = Ty::random(self.rng);
+                let access = match ty {
+                    Ty::I32 => *self.rng.pick(&["store", "store8", "store16"]),
+                    Ty::I64 => *self.rng.pick(&["store", "store8", "store16", "store32"]),
+                };
+                let offset = self.offset();
+                format!(
+                    "({}.{access}{offset} {} {})",
+                    ty.name(),
+                    self.addr(d),
+                    self.expr(ty, d)
+                )
+            }
+        }
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!("l{}", self.labels)
+    }
+}
+
+/// A random module, and the signatures of its exports `f0`, `f1`, ...
+fn generate(rng: &mut Rng) -> (String, Vec<Signature>) {
+    let mut wat = String::from("(module\n  (memory 1 1)\n");
+    for ty in GLOBALS {
+        let _ = writeln!(
+            wat,
+            "  (global (mut {}) ({}.const {}))",
+            ty.name(),
+            ty.name(),
+            rng.int(ty)
+        );
+    }
+    wat.push_str("  (data (i32.const 0) \"");
+    for _ in 0..64 {
+        let _ = write!(wat, "\\{:02x}", rng.next() as u8);
+    }
+    wat.push_str("\")\n");
+
+    let mut funcs: Vec<Signature> = Vec::new();
+    for i in 0..2 + rng.below(3) {
+        let params: Vec<Ty> = (0..1 + rng.below(3)).map(|_| Ty::random(rng)).collect();
+        let locals: Vec<Ty> = (0..1 + rng.below(3)).map(|_| Ty::random(rng)).collect();
+        let result = Ty::random(rng);
+        let mut body = Body {
+            rng,
+            callees: &funcs,
+            vars: params.iter().chain(&locals).copied().collect(),
+            num_params: params.len(),
+            counters: 0,
+            labels: 0,
+        };
+        let stmts = body.stmts(MAX_DEPTH);
+        let value = body.expr(result, MAX_DEPTH);
+        let counters = body.counters;
+
+        let names = |tys: &[Ty]| tys.iter().map(|t| t.name()).collect::<Vec<_>>().join(" ");
+        let _ = writeln!(
+            wat,
+            "  (func (export \"f{i}\") (param {}) (result {}) (local {}{})\n    {stmts}\n    {value})",
+            names(&params),
+            result.name(),
+            names(&locals),
+            " i32".repeat(counters)
+        );
+        funcs.push(Signature { params, result });
+    }
+    for (i, ty) in GLOBALS.iter().enumerate() {
+        let _ = writeln!(
+            wat,
+            "  (func (export \"g{i}\") (result {}) (global.get {i}))",
+            ty.name()
+        );
+    }
+    wat.push_str(")\n");
+    (wat, funcs)
+}
+
+/// FNV-1a, as `tests/differential.rs` computes it over the compiled
+/// module's memory.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// `value` as a Rust literal.
+fn literal(value: IrValue) -> String {
+    match value {
+        IrValue::I32(v) => v.to_string(),
+        IrValue::I64(v) => format!("{v}i64"),
+        IrValue::F32(v) => format!("f32::from_bits({:#x})", v.to_bits()),
+        IrValue::F64(v) => format!("f64::from_bits({:#x})", v.to_bits()),
+    }
+}
+
+/// Generate, transpile and interpret the differential cases, writing each
+/// module to `OUT_DIR` and their assertions to `differential.rs`.
+///
+/// Returns the module names.
+pub fn process_differential_cases(
+    out_dir: &Path,
+    options: &TranspileOptions,
+) -> Result<Vec<String>> {
+    let seed = match std::env::var("HERKOS_FUZZ_SEED") {
+        Ok(text) => parse_u64(&text).context("HERKOS_FUZZ_SEED is not a number")?,
+        Err(_) => DEFAULT_SEED,
+    };
+    let cases = match std::env::var("HERKOS_FUZZ_CASES") {
+        Ok(text) => parse_u64(&text).context("HERKOS_FUZZ_CASES is not a number")?,
+        Err(_) => DEFAULT_CASES,
+    };
+    eprintln!("Generating {cases} differential cases from seed {seed:#x}...");
+
+    let mut names = Vec::new();
+    let mut tests = String::from("// Auto-generated by build.rs - DO NOT EDIT\n");
+    for case in 0..cases {
+        let module_seed = seed.wrapping_add(case);
+        let name = format!("differential_{case}");
+        let (wat, funcs) = generate(&mut Rng(module_seed));
+        fs::write(out_dir.join(format!("{name}.wat")), &wat)
+            .with_context(|| format!("failed to write {name}.wat"))?;
+        let wasm = wat::parse_str(&wat)
+            .with_context(|| format!("seed {module_seed:#x}: generated WAT is invalid"))?;
+        let artifacts = transpile_to_artifacts(&wasm, options)
+            .with_context(|| format!("seed {module_seed:#x}: failed to transpile {name}"))?;
+        fs::write(out_dir.join(format!("{name}.rs")), &artifacts.rust_code)
+            .with_context(|| format!("failed to write {name}.rs"))?;
+
+        let parsed = herkos_core::parser::parse_wasm(&wasm)?;
+        let info = herkos_core::ir::build_module_info(&parsed, options)?;
+        let mut interp = Interpreter::new(&info, START_FUEL)
+            .map_err(|err| anyhow::anyhow!("seed {module_seed:#x}: {err}"))?;
+        let mut rng = Rng(module_seed ^ 0xa5a5_a5a5_a5a5_a5a5);
+
+        let _ = writeln!(tests, "\n#[test]\nfn {name}() {{");
+        let _ = writeln!(
+            tests,
+            "    let mut module = herkos_tests::{name}::new().unwrap();"
+        );
+        'calls: for _ in 0..CALLS_PER_EXPORT {
+            for (i, func) in funcs.iter().enumerate() {
+                let args: Vec<IrValue> = func
+                    .params
+                    .iter()
+                    .map(|&ty| match ty {
+                        Ty::I32 => IrValue::I32(rng.int(ty) as i32),
+                        Ty::I64 => IrValue::I64(rng.int(ty)),
+                    })
+                    .collect();
+                let call = format!(
+                    "f{i}({})",
+                    args.iter()
+                        .map(|&a| literal(a))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                let func_idx = info
+                    .func_exports
+                    .iter()
+                    .find(|e| e.name == format!("f{i}"))
+                    .map(|e| e.func_index)
+                    .context("generated export missing")?;
+                match interp.call(func_idx, &args) {
+                    Ok(values) => {
+                        let [value] = values[..] else {
+                            bail!("seed {module_seed:#x}: {call} returned {values:?}");
+                        };
+                        let _ = writeln!(
+                            tests,
+                            "    assert_eq!(module.{call}, Ok({}), \"seed {module_seed:#x}: {call}\");",
+                            literal(value)
+                        );
+                    }
+                    Err(EvalError::Trap(what)) => {
+                        let _ = writeln!(
+                            tests,
+                            "    assert!(module.{call}.is_err(), \"seed {module_seed:#x}: {call} traps ({what})\");"
+                        );
+                    }
+                    // Fuel ran out: the state from here on is unknown
+                    Err(_) => break 'calls,
+                }
+            }
+        }
+        for (i, _) in GLOBALS.iter().enumerate() {
+            let value = interp
+                .global(LocalGlobalIdx::new(i))
+                .context("generated global missing")?;
+            let _ = writeln!(
+                tests,
+                "    assert_eq!(module.g{i}(), Ok({}), \"seed {module_seed:#x}: global {i}\");",
+                literal(value)
+            );
+        }
+        // Segments promoted to constants are never copied into the
+        // compiled module's memory; nothing writes them, so they still hold
+        // their data in the interpreter's
+        let mut memory = interp.memory().to_vec();
+        for segment in &artifacts.module_info.data_segments {
+            if !segment.resident {
+                let start = segment.offset as usize;
+                memory[start..start + segment.data.len()].fill(0);
+            }
+        }
+        let _ = writeln!(
+            tests,
+            "    assert_eq!(checksum(module.0.memory.as_slice()), {:#x}, \"seed {module_seed:#x}: memory\");",
+            checksum(&memory)
+        );
+        tests.push_str("}\n");
+        names.push(name);
+    }
+    fs::write(out_dir.join("differential.rs"), tests).context("failed to write differential.rs")?;
+    Ok(names)
+}
+
+/// A number in decimal or `0x` hexadecimal.
+fn parse_u64(text: &str) -> Result<u64> {
+    let text = text.trim();
+    Ok(match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16)?,
+        None => text.parse()?,
+    })
+}
@@ -1,3 +1,16 @@
+// Lints the generated modules must pass, on top of clippy's defaults. `forbid`
+// rather than `deny`, so that codegen cannot opt out with an `#[allow]` on the
+// functions it emits; fix the emitted code instead. `scripts/corpus.py` checks
+// the corpus with the same list.
+#![forbid(
+    clippy::needless_return,
+    clippy::manual_range_contains,
+    clippy::bool_to_int_with_if,
+    clippy::needless_continue,
+    clippy::items_after_statements,
+    clippy::unnecessary_semicolon
+)]
+
 // Include generated modules from build.rs (in OUT_DIR)
 include!(concat!(env!("OUT_DIR"), "/mod.rs"));
 
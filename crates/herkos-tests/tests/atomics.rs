@@ -0,0 +1,55 @@
+//! End-to-end tests for single-threaded lowering of atomics.
+//!
+//! `atomics.wat` is transpiled with `lower_atomics`, so every atomic
+//! operator runs as plain memory accesses on the module's memory.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::atomics;
+
+#[test]
+fn test_rmw_returns_the_old_value() {
+    let mut module = atomics::new().unwrap();
+    assert_eq!(module.fetch_add(16, 5), Ok(0));
+    assert_eq!(module.fetch_add(16, 7), Ok(5));
+    assert_eq!(module.load(16), Ok(12));
+
+    module.store64(32, -1).unwrap();
+    assert_eq!(module.load64(32), Ok(-1));
+}
+
+#[test]
+fn test_sub_width_rmw_touches_only_its_bytes() {
+    let mut module = atomics::new().unwrap();
+    module.store64(0, 0x0102_0304).unwrap();
+    assert_eq!(module.swap8(0, 0x1ff), Ok(0x04));
+    assert_eq!(module.load(0), Ok(0x0102_03ff));
+    // 0x03ff - 0x0400 wraps within 16 bits.
+    assert_eq!(module.sub16(0, 0x400), Ok(0x03ff));
+    assert_eq!(module.load(0), Ok(0x0102_ffff));
+}
+
+#[test]
+fn test_cmpxchg_stores_only_on_match() {
+    let mut module = atomics::new().unwrap();
+    assert_eq!(module.cmpxchg(8, 1, 9), Ok(0));
+    assert_eq!(module.load(8), Ok(0));
+    assert_eq!(module.cmpxchg(8, 0, 9), Ok(0));
+    assert_eq!(module.load(8), Ok(9));
+
+    // The expected value is wrapped to the access width.
+    assert_eq!(module.cmpxchg8(8, 0x109, 3), Ok(9));
+    assert_eq!(module.load(8), Ok(3));
+}
+
+#[test]
+fn test_wait_and_notify_without_other_threads() {
+    let mut module = atomics::new().unwrap();
+    module.fetch_add(4, 1).unwrap();
+    // Value differs: "not-equal".
+    assert_eq!(module.wait(4, 0, -1), Ok(1));
+    // Finite timeout with nobody to notify: "timed-out".
+    assert_eq!(module.wait(4, 1, 1000), Ok(2));
+    // An infinite wait could never end.
+    assert_eq!(module.wait(4, 1, -1), Err(WasmTrap::Unreachable));
+    assert_eq!(module.notify(4, 1), Ok(0));
+}
@@ -0,0 +1,66 @@
+//! End-to-end tests for high-level binding wrappers.
+//!
+//! `data/wat/bindings.bindings` maps the pointer/length exports of
+//! `bindings.wat` onto `&[u8]`, `&str`, `&mut [u8]`, a `Point` struct and
+//! out-parameters; these tests call the generated `*_bound` wrappers.
+
+use herkos_tests::bindings::{self, Point};
+
+#[test]
+fn test_slice_input() {
+    let mut module = bindings::new().unwrap();
+    assert_eq!(module.checksum_bound(&[1, 2, 3, 250]).unwrap(), 256);
+    assert_eq!(module.checksum_bound(&[]).unwrap(), 0);
+}
+
+#[test]
+fn test_str_input_with_renamed_wrapper() {
+    let mut module = bindings::new().unwrap();
+    assert_eq!(module.checksum_str("AB").unwrap(), 65 + 66);
+}
+
+#[test]
+fn test_mut_slice_is_copied_back() {
+    let mut module = bindings::new().unwrap();
+    let mut buf = *b"herkos";
+    module.xor_in_place_bound(&mut buf, 0x20).unwrap();
+    assert_eq!(&buf, b"HERKOS");
+}
+
+#[test]
+fn test_out_array() {
+    let mut module = bindings::new().unwrap();
+    let out = module.digest_bound(&[10, 20, 30]).unwrap();
+    assert_eq!(out, [60, 0, 0, 0, 3, 0, 0, 0]);
+}
+
+#[test]
+fn test_struct_in_and_out() {
+    let mut module = bindings::new().unwrap();
+    let scaled = module.scale_bound(&Point { x: 3, y: -4 }, 5).unwrap();
+    assert_eq!(scaled, Point { x: 15, y: -20 });
+}
+
+#[test]
+fn test_raw_result_and_out_scalar_form_a_tuple() {
+    let mut module = bindings::new().unwrap();
+    assert_eq!(module.divmod_bound(17, 5).unwrap(), (3, 2));
+}
+
+#[test]
+fn test_raw_export_is_still_available() {
+    let mut module = bindings::new().unwrap();
+    module.0.memory.write_bytes(0, &[7, 8]).unwrap();
+    assert_eq!(module.checksum(0, 2).unwrap(), 15);
+}
+
+#[test]
+fn test_oversized_input_traps() {
+    let mut module = bindings::new().unwrap();
+    // Scratch starts at 0x8000 in a single 64 KiB page.
+    let big = vec![0u8; 0x9000];
+    assert_eq!(
+        module.checksum_bound(&big),
+        Err(herkos_runtime::WasmTrap::OutOfBounds)
+    );
+}
@@ -0,0 +1,66 @@
+//! Tests for block and if/else results that arrive along several edges.
+//!
+//! Each predecessor of a frame's end passes its own value, whether it falls
+//! through or branches out with `br`, `br_if` or `br_table`.
+
+use herkos_tests::block_results;
+
+#[test]
+fn test_br_if_value_or_fall_through() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.br_if_value(1).unwrap(), 10);
+    assert_eq!(m.br_if_value(0).unwrap(), 20);
+}
+
+#[test]
+fn test_if_arm_branches_out_with_value() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.if_branch_out(1).unwrap(), 7);
+    assert_eq!(m.if_branch_out(0).unwrap(), 103);
+}
+
+#[test]
+fn test_br_table_values() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.classify(0).unwrap(), 1001);
+    assert_eq!(m.classify(1).unwrap(), 1002);
+    assert_eq!(m.classify(2).unwrap(), 1003);
+    // Out-of-range indices take the default label.
+    assert_eq!(m.classify(9).unwrap(), 1003);
+}
+
+#[test]
+fn test_loop_exits_with_value() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.sum_to(4).unwrap(), 10);
+    assert_eq!(m.sum_to(1).unwrap(), 1);
+}
+
+#[test]
+fn test_nested_value_branches() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.nested(1, 4).unwrap(), 8);
+    assert_eq!(m.nested(0, 4).unwrap(), 6);
+    assert_eq!(m.nested(0, 0).unwrap(), 10);
+}
+
+#[test]
+fn test_br_if_to_function_label_returns() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.early_exit(1).unwrap(), 42);
+    assert_eq!(m.early_exit(0).unwrap(), 0);
+}
+
+#[test]
+fn test_br_table_to_function_label_returns() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.table_exit(0).unwrap(), 5);
+    assert_eq!(m.table_exit(1).unwrap(), 15);
+    assert_eq!(m.table_exit(7).unwrap(), 5);
+}
+
+#[test]
+fn test_br_to_function_label_returns() {
+    let mut m = block_results::new().unwrap();
+    assert_eq!(m.br_return().unwrap(), 3);
+}
@@ -0,0 +1,73 @@
+//! Bounds-check heat map (`--bounds-profile`).
+//!
+//! `bounds_profile.wat` sums bytes in a loop: its byte load checks its
+//! bounds once per byte, the store of the sum once per call.
+
+use herkos_tests::bounds_profile;
+
+const WAT: &str = include_str!("../data/wat/bounds_profile.wat");
+
+/// Site of `bounds_profile::BOUNDS_SITES` doing `access`.
+fn site(access: &str) -> usize {
+    bounds_profile::BOUNDS_SITES
+        .iter()
+        .position(|s| s.access == access)
+        .unwrap()
+}
+
+#[test]
+fn test_sites_are_in_their_functions() {
+    let wasm = wat::parse_str(WAT).unwrap();
+    let sites = bounds_profile::BOUNDS_SITES;
+    let accesses: Vec<_> = sites.iter().map(|s| (s.func_index, s.access)).collect();
+    assert_eq!(
+        accesses,
+        [(0, "i32.load8_u"), (0, "i32.store"), (1, "memory.fill")]
+    );
+    for site in sites {
+        let symbol = bounds_profile::symbolicate(site.func_index).unwrap();
+        assert!(symbol.contains_offset(site.wasm_offset), "{site:?}");
+        assert!((site.wasm_offset as usize) < wasm.len());
+    }
+}
+
+#[test]
+fn test_checks_are_counted_per_site() {
+    let mut module = bounds_profile::new().unwrap();
+    assert_eq!(module.checksum(0, 10), Ok(0));
+    assert_eq!(module.checksum(0, 10), Ok(0));
+    let hits = module.bounds_check_hits();
+    assert_eq!(hits[site("i32.load8_u")], 20);
+    assert_eq!(hits[site("i32.store")], 2);
+    assert_eq!(hits[site("memory.fill")], 0);
+
+    module.reset_bounds_checks();
+    assert!(module.bounds_check_hits().iter().all(|&h| h == 0));
+}
+
+#[test]
+fn test_failed_checks_are_counted() {
+    let mut module = bounds_profile::new().unwrap();
+    assert!(module.clear(65_530, 10).is_err());
+    assert_eq!(module.bounds_check_hits()[site("memory.fill")], 1);
+}
+
+#[test]
+fn test_report_lists_the_hottest_sites_first() {
+    let mut module = bounds_profile::new().unwrap();
+    module.checksum(0, 5).unwrap();
+    let mut out = String::new();
+    module.bounds_report(10, &mut out).unwrap();
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines[0], "# 6 bounds checks at 3 sites");
+    assert_eq!(lines[1], "hits\tfunction\twasm_offset\taccess");
+    assert!(lines[2].starts_with("5\tfunc_0\t0x"), "{out}");
+    assert!(lines[2].ends_with("\ti32.load8_u"), "{out}");
+    assert!(lines[3].starts_with("1\tfunc_0\t0x"), "{out}");
+    // `clear` never ran
+    assert_eq!(lines.len(), 4);
+
+    let mut out = String::new();
+    module.bounds_report(1, &mut out).unwrap();
+    assert_eq!(out.lines().count(), 3);
+}
@@ -0,0 +1,54 @@
+//! Byte order of transpiled memory accesses.
+//!
+//! Wasm memory is little-endian regardless of the host. Each test checks
+//! individual bytes against literal expectations, so a native-endian
+//! conversion anywhere in codegen or the runtime fails here on a
+//! big-endian host instead of round-tripping unnoticed.
+
+use herkos_tests::byte_order;
+
+fn bytes(module: &mut byte_order::WasmModule, addr: i32, len: i32) -> Vec<i32> {
+    (addr..addr + len)
+        .map(|a| module.load_byte(a).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_i32_store_is_little_endian() {
+    let mut module = byte_order::new().unwrap();
+    module.store_i32(0, 0x01020304).unwrap();
+    assert_eq!(bytes(&mut module, 0, 4), [0x04, 0x03, 0x02, 0x01]);
+}
+
+#[test]
+fn test_i64_store_is_little_endian() {
+    let mut module = byte_order::new().unwrap();
+    module.store_i64(3, 0x0102030405060708).unwrap();
+    assert_eq!(
+        bytes(&mut module, 3, 8),
+        [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]
+    );
+}
+
+#[test]
+fn test_f64_store_is_little_endian() {
+    let mut module = byte_order::new().unwrap();
+    module.store_f64(16, -2.0).unwrap();
+    assert_eq!(bytes(&mut module, 16, 8), [0, 0, 0, 0, 0, 0, 0x00, 0xC0]);
+}
+
+#[test]
+fn test_i16_store_and_signed_load() {
+    let mut module = byte_order::new().unwrap();
+    module.store_i16(32, 0x8001).unwrap();
+    assert_eq!(bytes(&mut module, 32, 2), [0x01, 0x80]);
+    assert_eq!(module.load_i16_s(32).unwrap(), -0x7FFF);
+}
+
+#[test]
+fn test_loads_of_data_segments_are_little_endian() {
+    let mut module = byte_order::new().unwrap();
+    assert_eq!(module.load_i64(64).unwrap(), 0x8102030405060708u64 as i64);
+    assert_eq!(module.load_f32(72).unwrap(), 1.0);
+    assert_eq!(module.data_word().unwrap(), 0x81020304u32 as i32);
+}
@@ -0,0 +1,234 @@
+//! Experimental C backend (`mode: "c"`).
+//!
+//! Each case transpiles a WAT module to C, compiles it together with a small
+//! C driver using the system C compiler (`$CC`, default `cc`) with warnings
+//! as errors, runs the program and compares what the driver printed. Cases
+//! are skipped when no C compiler is available.
+
+use herkos_core::{transpile, TranspileOptions};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn compiler() -> String {
+    std::env::var("CC").unwrap_or_else(|_| "cc".to_string())
+}
+
+fn have_compiler() -> bool {
+    Command::new(compiler()).arg("--version").output().is_ok()
+}
+
+/// Transpile `wat` to C, build it with `driver`, run it and return stdout.
+/// `None` if there is no C compiler.
+fn run(case: &str, wat: &str, driver: &str) -> Option<String> {
+    if !have_compiler() {
+        eprintln!("skipping C backend case {case}: no C compiler");
+        return None;
+    }
+    let wasm = wat::parse_str(wat).unwrap_or_else(|e| panic!("invalid WAT for {case}: {e}"));
+    let options = TranspileOptions {
+        mode: "c".to_string(),
+        ..TranspileOptions::default()
+    };
+    let c_code =
+        transpile(&wasm, &options).unwrap_or_else(|e| panic!("failed to transpile {case}: {e:#}"));
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("c_backend");
+    std::fs::create_dir_all(&dir).unwrap();
+    let module = dir.join(format!("{case}.c"));
+    let main = dir.join(format!("{case}_main.c"));
+    let binary = dir.join(case);
+    std::fs::write(&module, c_code).unwrap();
+    std::fs::write(
+        &main,
+        format!(
+            "#include <stdio.h>\n#include \"{case}.c\"\n\nstatic wasm_module_t module;\n\n{driver}"
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(compiler())
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-O1", "-o"])
+        .arg(&binary)
+        .arg(&main)
+        .arg("-lm")
+        .current_dir(&dir)
+        .output()
+        .expect("failed to run the C compiler");
+    assert!(
+        output.status.success(),
+        "{case}: C compilation failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = Command::new(&binary).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{case}: driver exited with {}",
+        output.status
+    );
+    Some(String::from_utf8(output.stdout).unwrap())
+}
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("data/wat")
+        .join(format!("{name}.wat"));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
+
+#[test]
+fn test_c_loop_fibonacci() {
+    let driver = r#"
+int main(void) {
+    int32_t n, r;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    for (n = 0; n <= 10; n++) {
+        if (wasm_export_func_0(&module, n, &r) != WASM_OK) return 1;
+        printf("%d ", r);
+    }
+    return 0;
+}
+"#;
+    if let Some(out) = run("fibonacci", &fixture("fibonacci"), driver) {
+        assert_eq!(out, "0 1 1 2 3 5 8 13 21 34 55 ");
+    }
+}
+
+#[test]
+fn test_c_call_indirect_and_traps() {
+    let driver = r#"
+int main(void) {
+    int32_t r;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    wasm_export_dispatch_binop(&module, 6, 7, 2, &r); printf("%d\n", r);
+    wasm_export_dispatch_binop(&module, 6, 7, 1, &r); printf("%d\n", r);
+    wasm_export_dispatch_unop(&module, 9, 3, &r); printf("%d\n", r);
+    printf("%d\n", wasm_export_dispatch_binop(&module, 1, 2, 3, &r) == WASM_TRAP_INDIRECT_CALL_TYPE_MISMATCH);
+    printf("%d\n", wasm_export_dispatch_binop(&module, 1, 2, 4, &r) == WASM_TRAP_UNDEFINED_ELEMENT);
+    printf("%d\n", wasm_export_dispatch_binop(&module, 1, 2, 5, &r) == WASM_TRAP_TABLE_OUT_OF_BOUNDS);
+    return 0;
+}
+"#;
+    if let Some(out) = run("indirect_call", &fixture("indirect_call"), driver) {
+        assert_eq!(out, "42\n-1\n-9\n1\n1\n1\n");
+    }
+}
+
+#[test]
+fn test_c_imports_in_table() {
+    let driver = r#"
+static int32_t recorded;
+wasm_trap_t wasm_import_env_double(wasm_module_t *m, int32_t arg0, int32_t *ret) { (void)m; *ret = arg0 * 2; return WASM_OK; }
+wasm_trap_t wasm_import_env_negate(wasm_module_t *m, int32_t arg0, int32_t *ret) { (void)m; *ret = -arg0; return WASM_OK; }
+wasm_trap_t wasm_import_env_record(wasm_module_t *m, int32_t arg0) { (void)m; recorded = arg0; return WASM_OK; }
+
+int main(void) {
+    int32_t r;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    wasm_export_apply(&module, 0, 21, &r); printf("%d\n", r);
+    wasm_export_apply(&module, 1, 21, &r); printf("%d\n", r);
+    wasm_export_apply(&module, 3, 21, &r); printf("%d\n", r);
+    wasm_export_emit(&module, 2, 7); printf("%d\n", recorded);
+    printf("%d\n", wasm_export_apply(&module, 2, 1, &r) == WASM_TRAP_INDIRECT_CALL_TYPE_MISMATCH);
+    return 0;
+}
+"#;
+    if let Some(out) = run("table_imports", &fixture("table_imports"), driver) {
+        assert_eq!(out, "42\n22\n-21\n7\n1\n");
+    }
+}
+
+#[test]
+fn test_c_memory_segments_and_bounds() {
+    let driver = r#"
+int main(void) {
+    int32_t r;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    wasm_export_load_i32(&module, 0, &r); printf("%x\n", (unsigned)r);
+    wasm_export_load_byte(&module, 17, &r); printf("%d\n", r);
+    printf("%d\n", wasm_export_load_i32(&module, 65533, &r) == WASM_TRAP_OUT_OF_BOUNDS);
+    printf("%d\n", wasm_export_load_i32(&module, -1, &r) == WASM_TRAP_OUT_OF_BOUNDS);
+    return 0;
+}
+"#;
+    if let Some(out) = run("data_segments", &fixture("data_segments"), driver) {
+        assert_eq!(out, "4030201\n11\n1\n1\n");
+    }
+}
+
+#[test]
+fn test_c_memory_grow_and_init() {
+    let grow_driver = r#"
+int main(void) {
+    int32_t r;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    printf("%d\n", wasm_export_store_and_load(&module, 70000, 5, &r) == WASM_TRAP_OUT_OF_BOUNDS);
+    wasm_export_grow(&module, 1, &r); printf("%d\n", r);
+    wasm_export_grow(&module, 1, &r); printf("%d\n", r);
+    wasm_export_get_size(&module, &r); printf("%d\n", r);
+    wasm_export_store_and_load(&module, 70000, 5, &r); printf("%d\n", r);
+    return 0;
+}
+"#;
+    if let Some(out) = run("memory_grow", &fixture("memory_grow"), grow_driver) {
+        assert_eq!(out, "1\n1\n-1\n2\n5\n");
+    }
+
+    let init_driver = r#"
+int main(void) {
+    int32_t r;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    wasm_export_init_region(&module, 100, 1, 3);
+    wasm_export_load_byte(&module, 100, &r); printf("%c", (char)r);
+    wasm_export_load_byte(&module, 102, &r); printf("%c\n", (char)r);
+    printf("%d\n", wasm_export_init_region(&module, 0, 3, 3) == WASM_TRAP_OUT_OF_BOUNDS);
+    return 0;
+}
+"#;
+    if let Some(out) = run("memory_init", &fixture("memory_init"), init_driver) {
+        assert_eq!(out, "el\n1\n");
+    }
+}
+
+#[test]
+fn test_c_numeric_semantics() {
+    let wat = r#"(module
+        (func (export "div") (param i32 i32) (result i32) (i32.div_s (local.get 0) (local.get 1)))
+        (func (export "rem") (param i32 i32) (result i32) (i32.rem_s (local.get 0) (local.get 1)))
+        (func (export "shr") (param i32 i32) (result i32) (i32.shr_s (local.get 0) (local.get 1)))
+        (func (export "rotl") (param i64 i64) (result i64) (i64.rotl (local.get 0) (local.get 1)))
+        (func (export "clz") (param i32) (result i32) (i32.clz (local.get 0)))
+        (func (export "min") (param f32 f32) (result f32) (f32.min (local.get 0) (local.get 1)))
+        (func (export "nearest") (param f64) (result f64) (f64.nearest (local.get 0)))
+        (func (export "trunc") (param f64) (result i32) (i32.trunc_f64_s (local.get 0)))
+        (func (export "wrap") (result i32) (i32.add (i32.const 2147483647) (i32.const 1))))"#;
+    let driver = r#"
+int main(void) {
+    int32_t r;
+    int64_t l;
+    float f;
+    double d;
+    if (wasm_init(&module) != WASM_OK) return 1;
+    printf("%d\n", wasm_export_div(&module, 1, 0, &r) == WASM_TRAP_DIVISION_BY_ZERO);
+    printf("%d\n", wasm_export_div(&module, INT32_MIN, -1, &r) == WASM_TRAP_INTEGER_OVERFLOW);
+    wasm_export_rem(&module, INT32_MIN, -1, &r); printf("%d\n", r);
+    wasm_export_shr(&module, -16, 34, &r); printf("%d\n", r);
+    wasm_export_rotl(&module, INT64_MIN, 1, &l); printf("%lld\n", (long long)l);
+    wasm_export_clz(&module, 1, &r); printf("%d\n", r);
+    wasm_export_min(&module, 0.0f, -0.0f, &f); printf("%d\n", (int)signbit(f) != 0);
+    wasm_export_min(&module, NAN, 1.0f, &f); printf("%d\n", isnan(f) != 0);
+    wasm_export_nearest(&module, 2.5, &d); printf("%.1f\n", d);
+    printf("%d\n", wasm_export_trunc(&module, 3e9, &r) == WASM_TRAP_INTEGER_OVERFLOW);
+    printf("%d\n", wasm_export_trunc(&module, NAN, &r) == WASM_TRAP_INTEGER_OVERFLOW);
+    wasm_export_trunc(&module, -7.9, &r); printf("%d\n", r);
+    wasm_export_wrap(&module, &r); printf("%d\n", r);
+    return 0;
+}
+"#;
+    if let Some(out) = run("numeric", wat, driver) {
+        assert_eq!(
+            out,
+            "1\n1\n0\n-4\n1\n31\n1\n1\n2.0\n1\n1\n-7\n-2147483648\n"
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! End-to-end tests for the `__wasm_call_ctors` export.
+//!
+//! `ctors.wat` leaves the constructors to `initialize()`; `call_ctors.wat`
+//! is the same module transpiled with them run at transpile time.
+
+use herkos_tests::{call_ctors, ctors};
+
+#[test]
+fn test_initialize_runs_the_constructors() {
+    let mut module = ctors::new().unwrap();
+    assert_eq!(module.value(), Ok(0));
+    assert_eq!(module.runs(), Ok(0));
+    module.initialize().unwrap();
+    assert_eq!(module.value(), Ok(42));
+    assert_eq!(module.runs(), Ok(1));
+}
+
+#[test]
+fn test_constructors_run_at_transpile_time() {
+    let mut module = call_ctors::new().unwrap();
+    assert_eq!(module.value(), Ok(42));
+    assert_eq!(module.runs(), Ok(1));
+}
+
+#[test]
+fn test_evaluated_constructors_do_not_run_again() {
+    let mut module = call_ctors::new().unwrap();
+    module.__wasm_call_ctors().unwrap();
+    assert_eq!(module.runs(), Ok(1));
+}
@@ -0,0 +1,62 @@
+//! Host traits grouped by capability (`--capabilities`).
+//!
+//! `capability_traits.capabilities` puts `env.fd_*` into `FileSystem` and
+//! `env.gl_*` into `Graphics`; `env.log` stays in `ModuleHostTrait`, which
+//! has both capabilities as supertraits.
+
+use herkos_runtime::WasmResult;
+use herkos_tests::capability_traits::{self, FileSystem, Graphics, ModuleHostTrait};
+
+#[derive(Default)]
+struct Host {
+    files: Vec<(i32, i32)>,
+    screen: i32,
+    logged: Vec<i32>,
+}
+
+impl FileSystem for Host {
+    fn fd_open(&mut self, path: i32) -> WasmResult<i32> {
+        Ok(path + 100)
+    }
+
+    fn fd_write(&mut self, fd: i32, value: i32) -> WasmResult<i32> {
+        self.files.push((fd, value));
+        Ok(4)
+    }
+}
+
+impl Graphics for Host {
+    fn gl_clear(&mut self, color: i32) -> WasmResult<()> {
+        self.screen = color;
+        Ok(())
+    }
+}
+
+impl ModuleHostTrait for Host {
+    fn log(&mut self, value: i32) -> WasmResult<()> {
+        self.logged.push(value);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_imports_dispatch_through_capability_traits() {
+    let mut module = capability_traits::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.save_and_draw(7, 42, &mut host), Ok(4));
+    assert_eq!(host.files, vec![(107, 42)]);
+    assert_eq!(host.screen, 42);
+    assert_eq!(host.logged, vec![107]);
+}
+
+/// A capability can be implemented by a host that knows nothing else.
+fn clear_with<G: Graphics>(graphics: &mut G) -> WasmResult<()> {
+    graphics.gl_clear(1)
+}
+
+#[test]
+fn test_capabilities_are_independent_traits() {
+    let mut host = Host::default();
+    clear_with(&mut host).unwrap();
+    assert_eq!(host.screen, 1);
+}
@@ -0,0 +1,35 @@
+//! End-to-end tests for checked arithmetic.
+//!
+//! `checked_arithmetic.wat` is transpiled with `--checked-arithmetic`, so
+//! signed overflow in integer add, sub and mul traps instead of wrapping.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::checked_arithmetic;
+
+#[test]
+fn test_in_range_arithmetic_is_unchanged() {
+    let mut module = checked_arithmetic::new().unwrap();
+    assert_eq!(module.add32(2, 3), Ok(5));
+    assert_eq!(module.add32(-7, 3), Ok(-4));
+    assert_eq!(module.sub32(i32::MIN + 1, 1), Ok(i32::MIN));
+    assert_eq!(module.mul32(-46341, 46340), Ok(-2147441940));
+    assert_eq!(module.add64(i64::MAX - 1, 1), Ok(i64::MAX));
+}
+
+#[test]
+fn test_signed_overflow_traps() {
+    let mut module = checked_arithmetic::new().unwrap();
+    let overflow = WasmTrap::HostAssertedOverflow;
+    assert_eq!(module.add32(i32::MAX, 1), Err(overflow));
+    assert_eq!(module.sub32(i32::MIN, 1), Err(overflow));
+    assert_eq!(module.mul32(65536, 65536), Err(overflow));
+    assert_eq!(module.add64(i64::MAX, 1), Err(overflow));
+    assert_eq!(module.mul64(i64::MIN, -1), Err(overflow));
+}
+
+#[test]
+fn test_shifts_still_wrap() {
+    let mut module = checked_arithmetic::new().unwrap();
+    assert_eq!(module.shl32(1, 31), Ok(i32::MIN));
+    assert_eq!(module.shl32(1, 33), Ok(2));
+}
@@ -0,0 +1,49 @@
+//! Compact output (`--compact`).
+//!
+//! `compact.wat` is built in compact form; its exports must behave as in
+//! the regular output, which is about half as large again.
+
+use herkos_core::{transpile, TranspileOptions};
+use herkos_runtime::WasmResult;
+use herkos_tests::compact;
+
+const WAT: &str = include_str!("../data/wat/compact.wat");
+
+struct Doubler;
+
+impl compact::ModuleHostTrait for Doubler {
+    fn report(&mut self, value: i32) -> WasmResult<i32> {
+        Ok(value * 2)
+    }
+}
+
+#[test]
+fn test_compact_module_behaves_like_the_regular_one() {
+    let mut module = compact::new().unwrap();
+    let hello: i32 = b"hello".iter().map(|&b| b as i32).sum();
+    assert_eq!(module.sum_bytes(16, 5, &mut Doubler), Ok(hello * 2));
+    assert_eq!(module.classify(0, &mut Doubler), Ok(-1));
+    assert_eq!(module.classify(1, &mut Doubler), Ok(0));
+    assert_eq!(module.classify(7, &mut Doubler), Ok(100));
+    assert_eq!(module.calls(&mut Doubler), Ok(1));
+}
+
+#[test]
+fn test_compact_output_is_smaller_and_has_no_comments() {
+    let wasm = wat::parse_str(WAT).unwrap();
+    let regular = transpile(&wasm, &TranspileOptions::default()).unwrap();
+    let options = TranspileOptions {
+        compact: true,
+        ..TranspileOptions::default()
+    };
+    let compact = transpile(&wasm, &options).unwrap();
+
+    assert!(
+        compact.len() * 4 < regular.len() * 3,
+        "{} vs {} bytes",
+        compact.len(),
+        regular.len()
+    );
+    assert!(!compact.contains("//"));
+    assert!(!compact.contains("\n "));
+}
@@ -0,0 +1,58 @@
+//! Constant-time exports (`--constant-time`).
+//!
+//! `constant_time.wat` is transpiled with `ct_eq` and `ct_choose` selected:
+//! their `select`s go through `ct_select` and, since their loop runs a fixed
+//! number of times, no data-dependent branch is left. `leaky_eq` exits early
+//! on the first difference, which the report points out once selected.
+
+use herkos_core::{transpile_to_artifacts, TranspileOptions};
+use herkos_tests::constant_time;
+
+const WAT: &str = include_str!("../data/wat/constant_time.wat");
+
+fn artifacts(exports: &[&str]) -> herkos_core::Artifacts {
+    let wasm = wat::parse_str(WAT).unwrap();
+    let options = TranspileOptions {
+        optimize: true,
+        constant_time: exports.iter().map(|e| e.to_string()).collect(),
+        ..TranspileOptions::default()
+    };
+    transpile_to_artifacts(&wasm, &options).unwrap()
+}
+
+#[test]
+fn test_constant_time_exports_compute_the_same_results() {
+    let mut module = constant_time::new().unwrap();
+    module.0.memory.write_bytes(0, &[0x5a; 16]).unwrap();
+    module.0.memory.write_bytes(16, &[0x5a; 16]).unwrap();
+    module.0.memory.write_bytes(32, &[0x5a; 16]).unwrap();
+    module.0.memory.write_bytes(47, &[0x5b]).unwrap();
+
+    assert_eq!(module.ct_eq(0, 16), Ok(1));
+    assert_eq!(module.ct_eq(0, 32), Ok(0));
+    assert_eq!(module.leaky_eq(0, 16), Ok(1));
+    assert_eq!(module.leaky_eq(0, 32), Ok(0));
+
+    assert_eq!(module.ct_choose(1, 40, -7), Ok(40));
+    assert_eq!(module.ct_choose(i32::MIN, 40, -7), Ok(40));
+    assert_eq!(module.ct_choose(0, 40, -7), Ok(-7 + 2));
+}
+
+#[test]
+fn test_selected_exports_leave_no_data_dependent_branch() {
+    let artifacts = artifacts(&["ct_eq", "ct_choose"]);
+    assert!(
+        artifacts.reports.constant_time.is_clean(),
+        "{:?}",
+        artifacts.reports.constant_time.branches
+    );
+    assert_eq!(artifacts.rust_code.matches("= ct_select(").count(), 2);
+}
+
+#[test]
+fn test_early_exit_is_reported() {
+    let artifacts = artifacts(&["leaky_eq"]);
+    let branches = &artifacts.reports.constant_time.branches;
+    assert_eq!(branches.len(), 1, "{branches:?}");
+    assert_eq!(branches[0].func.as_usize(), 2);
+}
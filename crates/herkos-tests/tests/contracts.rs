@@ -0,0 +1,31 @@
+//! Export contracts (`herkos.contracts` custom section).
+//!
+//! `contracts.wat` declares a range on `set_volume` and a non-null pointer
+//! and bounded length on `checksum`. Tests build in debug, so breaking a
+//! contract panics before the guest runs.
+
+use herkos_tests::contracts;
+
+#[test]
+fn test_calls_within_the_contracts_run() {
+    let mut module = contracts::new().unwrap();
+    module.set_volume(100).unwrap();
+    assert_eq!(module.volume(), Ok(100));
+    module.0.memory.write_bytes(16, &[1, 2, 3]).unwrap();
+    assert_eq!(module.checksum(16, 3), Ok(6));
+    assert_eq!(module.checksum(16, 0), Ok(0));
+}
+
+#[test]
+#[should_panic(expected = "`set_volume`: argument 0 must be in 0..=100")]
+fn test_out_of_range_argument_panics() {
+    let mut module = contracts::new().unwrap();
+    let _ = module.set_volume(101);
+}
+
+#[test]
+#[should_panic(expected = "`checksum`: argument 0 must not be null")]
+fn test_null_pointer_panics() {
+    let mut module = contracts::new().unwrap();
+    let _ = module.checksum(0, 4);
+}
@@ -0,0 +1,353 @@
+//! Trap parity for the conversions and divisions transpilers get wrong.
+//!
+//! The expected values are those of the WebAssembly spec testsuite
+//! (`conversions.wast`, `i32.wast`, `i64.wast`), which wasmtime passes:
+//! truncation at and just past the integer bounds, NaN and infinities,
+//! `MIN / -1` and `MIN % -1`. Every case also runs on constant operands
+//! under `HERKOS_OPTIMIZE=1`, where the optimizer may fold it.
+
+use herkos_runtime::{WasmResult, WasmTrap};
+use herkos_tests::conversion_traps;
+
+const OVERFLOW: WasmTrap = WasmTrap::IntegerOverflow;
+const DIV_ZERO: WasmTrap = WasmTrap::DivisionByZero;
+
+/// NaNs with the sign bit set and with a non-canonical payload.
+const F32_NANS: [f32; 3] = [
+    f32::NAN,
+    f32::from_bits(0xffc0_0000),
+    f32::from_bits(0x7fa0_0000),
+];
+const F64_NANS: [f64; 3] = [
+    f64::NAN,
+    f64::from_bits(0xfff8_0000_0000_0000),
+    f64::from_bits(0x7ff4_0000_0000_0000),
+];
+
+fn check<T, R>(op: &str, cases: &[(T, WasmResult<R>)], mut f: impl FnMut(T) -> WasmResult<R>)
+where
+    T: Copy + core::fmt::Debug,
+    R: PartialEq + core::fmt::Debug,
+{
+    for (input, expected) in cases {
+        assert_eq!(&f(*input), expected, "{op}({input:?})");
+    }
+}
+
+#[test]
+fn test_i32_trunc_f32() {
+    let mut m = conversion_traps::new().unwrap();
+    check(
+        "i32.trunc_f32_s",
+        &[
+            (0.0, Ok(0)),
+            (-0.0, Ok(0)),
+            (f32::from_bits(1), Ok(0)),
+            (-f32::from_bits(1), Ok(0)),
+            (1.5, Ok(1)),
+            (-1.9, Ok(-1)),
+            (2147483520.0, Ok(2147483520)),
+            (-2147483648.0, Ok(i32::MIN)),
+            (2147483648.0, Err(OVERFLOW)),
+            (-2147483904.0, Err(OVERFLOW)),
+            (f32::INFINITY, Err(OVERFLOW)),
+            (f32::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i32_trunc_f32_s(v),
+    );
+    check(
+        "i32.trunc_f32_u",
+        &[
+            (-0.0, Ok(0)),
+            (1.9, Ok(1)),
+            (2147483648.0, Ok(i32::MIN)),
+            (4294967040.0, Ok(-256)),
+            (f32::from_bits(0xbf66_6666), Ok(0)), // -0.9
+            (f32::from_bits(0xbf7f_ffff), Ok(0)), // just above -1.0
+            (4294967296.0, Err(OVERFLOW)),
+            (-1.0, Err(OVERFLOW)),
+            (f32::INFINITY, Err(OVERFLOW)),
+            (f32::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i32_trunc_f32_u(v),
+    );
+    for nan in F32_NANS {
+        assert_eq!(
+            m.i32_trunc_f32_s(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+        assert_eq!(
+            m.i32_trunc_f32_u(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+    }
+}
+
+#[test]
+fn test_i32_trunc_f64() {
+    let mut m = conversion_traps::new().unwrap();
+    check(
+        "i32.trunc_f64_s",
+        &[
+            (-0.0, Ok(0)),
+            (f64::from_bits(1), Ok(0)),
+            (-1.5, Ok(-1)),
+            (2147483647.0, Ok(i32::MAX)),
+            (2147483647.9, Ok(i32::MAX)),
+            (-2147483648.0, Ok(i32::MIN)),
+            // Fractional values below i32::MIN still truncate to it
+            (-2147483648.5, Ok(i32::MIN)),
+            (f64::from_bits(0xc1e0_0000_001f_ffff), Ok(i32::MIN)),
+            (2147483648.0, Err(OVERFLOW)),
+            (-2147483649.0, Err(OVERFLOW)),
+            (f64::INFINITY, Err(OVERFLOW)),
+            (f64::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i32_trunc_f64_s(v),
+    );
+    check(
+        "i32.trunc_f64_u",
+        &[
+            (-0.9, Ok(0)),
+            (1e8, Ok(100_000_000)),
+            (2147483648.0, Ok(i32::MIN)),
+            (4294967295.0, Ok(-1)),
+            (4294967295.9, Ok(-1)),
+            (4294967296.0, Err(OVERFLOW)),
+            (-1.0, Err(OVERFLOW)),
+            (1e16, Err(OVERFLOW)),
+            (9223372036854775808.0, Err(OVERFLOW)),
+            (f64::INFINITY, Err(OVERFLOW)),
+            (f64::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i32_trunc_f64_u(v),
+    );
+    for nan in F64_NANS {
+        assert_eq!(
+            m.i32_trunc_f64_s(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+        assert_eq!(
+            m.i32_trunc_f64_u(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+    }
+}
+
+#[test]
+fn test_i64_trunc_f32() {
+    let mut m = conversion_traps::new().unwrap();
+    check(
+        "i64.trunc_f32_s",
+        &[
+            (-0.0, Ok(0)),
+            (-1.9, Ok(-1)),
+            (4294967296.0, Ok(4294967296)),
+            (9223371487098961920.0, Ok(9223371487098961920)),
+            (-9223372036854775808.0, Ok(i64::MIN)),
+            (9223372036854775808.0, Err(OVERFLOW)),
+            (-9223373136366403584.0, Err(OVERFLOW)),
+            (f32::INFINITY, Err(OVERFLOW)),
+            (f32::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i64_trunc_f32_s(v),
+    );
+    check(
+        "i64.trunc_f32_u",
+        &[
+            (f32::from_bits(0xbf66_6666), Ok(0)),
+            (4294967296.0, Ok(4294967296)),
+            (18446742974197923840.0, Ok(-1099511627776)),
+            (18446744073709551616.0, Err(OVERFLOW)),
+            (-1.0, Err(OVERFLOW)),
+            (f32::INFINITY, Err(OVERFLOW)),
+            (f32::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i64_trunc_f32_u(v),
+    );
+    for nan in F32_NANS {
+        assert_eq!(
+            m.i64_trunc_f32_s(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+        assert_eq!(
+            m.i64_trunc_f32_u(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+    }
+}
+
+#[test]
+fn test_i64_trunc_f64() {
+    let mut m = conversion_traps::new().unwrap();
+    check(
+        "i64.trunc_f64_s",
+        &[
+            (-0.0, Ok(0)),
+            (-1.5, Ok(-1)),
+            (9223372036854774784.0, Ok(9223372036854774784)),
+            (-9223372036854775808.0, Ok(i64::MIN)),
+            (9223372036854775808.0, Err(OVERFLOW)),
+            (-9223372036854777856.0, Err(OVERFLOW)),
+            (f64::INFINITY, Err(OVERFLOW)),
+            (f64::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i64_trunc_f64_s(v),
+    );
+    check(
+        "i64.trunc_f64_u",
+        &[
+            (-0.9, Ok(0)),
+            (4294967295.0, Ok(4294967295)),
+            (1e16, Ok(10_000_000_000_000_000)),
+            (9223372036854775808.0, Ok(i64::MIN)),
+            (18446744073709549568.0, Ok(-2048)),
+            (18446744073709551616.0, Err(OVERFLOW)),
+            (-1.0, Err(OVERFLOW)),
+            (f64::INFINITY, Err(OVERFLOW)),
+            (f64::NEG_INFINITY, Err(OVERFLOW)),
+        ],
+        |v| m.i64_trunc_f64_u(v),
+    );
+    for nan in F64_NANS {
+        assert_eq!(
+            m.i64_trunc_f64_s(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+        assert_eq!(
+            m.i64_trunc_f64_u(nan),
+            Err(OVERFLOW),
+            "{:#x}",
+            nan.to_bits()
+        );
+    }
+}
+
+#[test]
+fn test_i32_division() {
+    let mut m = conversion_traps::new().unwrap();
+    check(
+        "i32.div_s",
+        &[
+            ((i32::MIN, -1), Err(OVERFLOW)),
+            ((1, 0), Err(DIV_ZERO)),
+            ((0, 0), Err(DIV_ZERO)),
+            ((i32::MIN, 0), Err(DIV_ZERO)),
+            ((i32::MIN, 2), Ok(-0x4000_0000)),
+            ((i32::MIN + 1, 1000), Ok(-2147483)),
+            ((-5, 2), Ok(-2)),
+            ((5, -2), Ok(-2)),
+            ((-7, -3), Ok(2)),
+        ],
+        |(a, b)| m.i32_div_s(a, b),
+    );
+    check(
+        "i32.div_u",
+        &[
+            ((1, 0), Err(DIV_ZERO)),
+            ((i32::MIN, -1), Ok(0)),
+            ((-1, 2), Ok(i32::MAX)),
+            ((i32::MIN + 1, 1000), Ok(0x20c49b)),
+        ],
+        |(a, b)| m.i32_div_u(a, b),
+    );
+    check(
+        "i32.rem_s",
+        &[
+            ((i32::MIN, -1), Ok(0)),
+            ((i32::MAX, -1), Ok(0)),
+            ((1, 0), Err(DIV_ZERO)),
+            ((i32::MIN, 2), Ok(0)),
+            ((i32::MIN + 1, 1000), Ok(-647)),
+            ((-5, 2), Ok(-1)),
+            ((5, -2), Ok(1)),
+        ],
+        |(a, b)| m.i32_rem_s(a, b),
+    );
+    check(
+        "i32.rem_u",
+        &[
+            ((1, 0), Err(DIV_ZERO)),
+            ((i32::MIN, -1), Ok(i32::MIN)),
+            ((-1, 2), Ok(1)),
+            ((i32::MIN + 1, 1000), Ok(649)),
+        ],
+        |(a, b)| m.i32_rem_u(a, b),
+    );
+}
+
+#[test]
+fn test_i64_division() {
+    let mut m = conversion_traps::new().unwrap();
+    check(
+        "i64.div_s",
+        &[
+            ((i64::MIN, -1), Err(OVERFLOW)),
+            ((1, 0), Err(DIV_ZERO)),
+            ((i64::MIN, 0), Err(DIV_ZERO)),
+            ((i64::MIN, 2), Ok(-0x4000_0000_0000_0000)),
+            ((i64::MIN + 1, 1000), Ok(-9223372036854775)),
+            ((-5, 2), Ok(-2)),
+            ((5, -2), Ok(-2)),
+        ],
+        |(a, b)| m.i64_div_s(a, b),
+    );
+    check(
+        "i64.div_u",
+        &[
+            ((1, 0), Err(DIV_ZERO)),
+            ((i64::MIN, -1), Ok(0)),
+            ((-1, 2), Ok(i64::MAX)),
+            ((i64::MIN + 1, 1000), Ok(0x20c49ba5e353f7)),
+        ],
+        |(a, b)| m.i64_div_u(a, b),
+    );
+    check(
+        "i64.rem_s",
+        &[
+            ((i64::MIN, -1), Ok(0)),
+            ((i64::MAX, -1), Ok(0)),
+            ((1, 0), Err(DIV_ZERO)),
+            ((i64::MIN, 2), Ok(0)),
+            ((i64::MIN + 1, 1000), Ok(-807)),
+            ((-5, 2), Ok(-1)),
+        ],
+        |(a, b)| m.i64_rem_s(a, b),
+    );
+    check(
+        "i64.rem_u",
+        &[
+            ((1, 0), Err(DIV_ZERO)),
+            ((i64::MIN, -1), Ok(i64::MIN)),
+            ((-1, 2), Ok(1)),
+            ((i64::MIN + 1, 1000), Ok(809)),
+        ],
+        |(a, b)| m.i64_rem_u(a, b),
+    );
+}
+
+#[test]
+fn test_constant_operands_trap_like_runtime_ones() {
+    let mut m = conversion_traps::new().unwrap();
+    assert_eq!(m.const_i32_div_s_overflow(), Err(OVERFLOW));
+    assert_eq!(m.const_i64_div_s_overflow(), Err(OVERFLOW));
+    assert_eq!(m.const_i32_rem_s_overflow(), Ok(0));
+    assert_eq!(m.const_i64_rem_s_overflow(), Ok(0));
+    assert_eq!(m.const_i32_trunc_f64_s_below_min(), Ok(i32::MIN));
+    assert_eq!(m.const_i32_trunc_f64_s_nan(), Err(OVERFLOW));
+    assert_eq!(m.const_i64_trunc_f32_u_neg(), Ok(0));
+}
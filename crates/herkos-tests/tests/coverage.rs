@@ -0,0 +1,90 @@
+//! Block coverage (`--coverage`).
+//!
+//! `coverage.wat` stands in for a third-party module: the counters show
+//! which of its branches a test reached, at offsets in the `.wasm` file.
+
+use herkos_tests::coverage;
+
+const WAT: &str = include_str!("../data/wat/coverage.wat");
+
+/// Opcodes of the operators a block other than a function's entry starts
+/// at: `loop`, `if`, `else`, `end`, `br_if`.
+const BLOCK_STARTS: &[u8] = &[0x03, 0x04, 0x05, 0x0b, 0x0d];
+
+fn report(module: &coverage::WasmModule) -> String {
+    let mut out = String::new();
+    module.coverage_report("coverage.wasm", &mut out).unwrap();
+    out
+}
+
+/// Hits of each point of local function `func`.
+fn hits_of(module: &coverage::WasmModule, func: u32) -> Vec<u64> {
+    coverage::COVERAGE_POINTS
+        .iter()
+        .zip(module.coverage_hits())
+        .filter(|(point, _)| point.func_index == func)
+        .map(|(_, hits)| *hits)
+        .collect()
+}
+
+#[test]
+fn test_points_are_at_the_operators_starting_blocks() {
+    let wasm = wat::parse_str(WAT).unwrap();
+    let points = coverage::COVERAGE_POINTS;
+    for (i, point) in points.iter().enumerate() {
+        let symbol = coverage::symbolicate(point.func_index).unwrap();
+        assert!(symbol.contains_offset(point.wasm_offset), "{point:?}");
+        let entry = i == 0 || points[i - 1].func_index != point.func_index;
+        if !entry {
+            let opcode = wasm[point.wasm_offset as usize];
+            assert!(BLOCK_STARTS.contains(&opcode), "{point:?}: {opcode:#x}");
+        }
+    }
+    // The nested `if` of `sign` has four blocks more than straight-line code
+    assert_eq!(points.iter().filter(|p| p.func_index == 0).count(), 7);
+}
+
+#[test]
+fn test_report_shows_the_branches_reached() {
+    let mut module = coverage::new().unwrap();
+    assert_eq!(module.sign(5), Ok(1));
+    let sign = hits_of(&module, 0);
+    assert_eq!(sign[0], 1);
+    assert!(sign.contains(&0), "only one branch ran: {sign:?}");
+
+    let text = report(&module);
+    assert!(text.starts_with("TN:\nSF:coverage.wasm\n"));
+    assert!(text.contains("FNDA:1,func_0\n"));
+    assert!(text.contains("FNDA:0,func_1\n"));
+    assert!(text.contains("FNF:3\nFNH:1\n"));
+    assert!(text.ends_with("end_of_record\n"));
+
+    assert_eq!(module.sign(-5), Ok(-1));
+    assert_eq!(module.sign(0), Ok(0));
+    assert!(hits_of(&module, 0).iter().all(|&hits| hits > 0));
+    assert!(report(&module).contains("FNDA:3,func_0\n"));
+}
+
+#[test]
+fn test_loop_blocks_count_iterations() {
+    let mut module = coverage::new().unwrap();
+    assert_eq!(module.triangle(3), Ok(6));
+    // Entry, loop header (once per test of `$n`), body, exit
+    assert_eq!(hits_of(&module, 1), [1, 4, 3, 1]);
+
+    let text = report(&module);
+    let lines: Vec<&str> = text.lines().collect();
+    let found = lines.iter().find(|l| l.starts_with("LF:")).unwrap();
+    let hit = lines.iter().find(|l| l.starts_with("LH:")).unwrap();
+    // `sign`'s seven points and `unused` are not reached
+    assert_eq!((*found, *hit), ("LF:12", "LH:4"));
+}
+
+#[test]
+fn test_reset_forgets_the_hits() {
+    let mut module = coverage::new().unwrap();
+    assert_eq!(module.unused(), Ok(7));
+    assert_eq!(hits_of(&module, 2), [1]);
+    module.reset_coverage();
+    assert!(module.coverage_hits().iter().all(|&hits| hits == 0));
+}
@@ -0,0 +1,26 @@
+//! End-to-end tests for dead argument elimination.
+//!
+//! With `HERKOS_OPTIMIZE=1`, the unused parameters of `dead_arguments.wat`'s
+//! helpers are removed; traps and side effects of their arguments are not.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::dead_arguments;
+
+#[test]
+fn test_used_argument_passes_through() {
+    let mut module = dead_arguments::new().unwrap();
+    assert_eq!(module.pick(5, 42), Ok(42));
+}
+
+#[test]
+fn test_dropped_argument_still_traps() {
+    let mut module = dead_arguments::new().unwrap();
+    assert_eq!(module.pick(0, 42), Err(WasmTrap::DivisionByZero));
+}
+
+#[test]
+fn test_dropped_argument_side_effects_happen() {
+    let mut module = dead_arguments::new().unwrap();
+    assert_eq!(module.counted(7), Ok(7));
+    assert_eq!(module.count(), Ok(2));
+}
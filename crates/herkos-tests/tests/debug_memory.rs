@@ -0,0 +1,94 @@
+//! Poisoned debug memory (`--debug-memory`).
+//!
+//! `debug_memory.wat` holds a static table and a counter nothing
+//! initializes. On armed memory, the bugs zero-init hides show up: the
+//! counter starts from poison, and accesses past the table hit its canary.
+
+use herkos_runtime::{DebugFinding, DebugFindingKind, DebugMemory, MemoryObserver, POISON};
+use herkos_tests::debug_memory;
+
+/// Host forwarding every access to its debug memory.
+struct Host {
+    debug: DebugMemory,
+}
+
+impl MemoryObserver for Host {
+    fn on_load(&mut self, addr: usize, len: usize) {
+        self.debug.on_load(addr, len);
+    }
+
+    fn on_store(&mut self, addr: usize, len: usize) {
+        self.debug.on_store(addr, len);
+    }
+}
+
+impl debug_memory::ModuleHostTrait for Host {}
+
+fn armed(debug: DebugMemory) -> (debug_memory::WasmModule, Host) {
+    let mut module = debug_memory::new().unwrap();
+    let mut host = Host { debug };
+    module.arm_debug_memory(&mut host.debug);
+    (module, host)
+}
+
+fn finding(kind: DebugFindingKind, addr: usize, len: usize) -> DebugFinding {
+    DebugFinding { kind, addr, len }
+}
+
+#[test]
+fn test_data_segments_are_listed() {
+    assert_eq!(debug_memory::DATA_SEGMENTS, &[(0x100, 16)]);
+}
+
+#[test]
+fn test_uninitialized_counter_starts_from_poison() {
+    let mut module = debug_memory::new().unwrap();
+    let mut host = Host {
+        debug: DebugMemory::new(),
+    };
+    assert_eq!(module.count(&mut host), Ok(1));
+
+    let (mut module, mut host) = armed(DebugMemory::with_read_tracking());
+    let poison = i32::from_le_bytes([POISON; 4]);
+    assert_eq!(module.count(&mut host), Ok(poison.wrapping_add(1)));
+    assert_eq!(
+        host.debug.findings(),
+        &[finding(DebugFindingKind::UninitRead, 0x200, 4)]
+    );
+
+    host.debug.clear_findings();
+    module.reset(&mut host).unwrap();
+    assert_eq!(module.count(&mut host), Ok(1));
+    assert!(host.debug.findings().is_empty());
+}
+
+#[test]
+fn test_reads_past_the_table_are_reported() {
+    let (mut module, mut host) = armed(DebugMemory::with_read_tracking());
+    assert_eq!(module.sum(4, &mut host), Ok(10));
+    assert!(host.debug.findings().is_empty());
+
+    assert_ne!(module.sum(5, &mut host), Ok(10));
+    assert_eq!(
+        host.debug.findings(),
+        &[finding(DebugFindingKind::UninitRead, 0x110, 4)]
+    );
+    host.debug.clear_findings();
+}
+
+#[test]
+fn test_stores_past_the_table_hit_its_canary() {
+    let (mut module, mut host) = armed(DebugMemory::new());
+    module.set(3, 40, &mut host).unwrap();
+    assert_eq!(module.sum(4, &mut host), Ok(46));
+    assert!(host.debug.findings().is_empty());
+    assert_eq!(host.debug.damaged_canary(&module.0.memory), None);
+
+    module.set(4, 50, &mut host).unwrap();
+    assert_eq!(
+        host.debug.findings(),
+        &[finding(DebugFindingKind::CanaryOverwrite, 0x110, 4)]
+    );
+    assert_eq!(host.debug.damaged_canary(&module.0.memory), Some(0x110));
+    host.debug.clear_findings();
+}
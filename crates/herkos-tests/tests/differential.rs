@@ -0,0 +1,15 @@
+//! Differential tests of the generated code against the IR interpreter.
+//!
+//! The build script generates random modules and records what the IR
+//! interpreter computes for calls to their exports (see
+//! `fuzz/differential.rs`); the compiled modules must compute the same
+//! results, traps, globals and memory.
+
+/// FNV-1a, as the build script computes it over the interpreter's memory.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+include!(concat!(env!("OUT_DIR"), "/differential.rs"));
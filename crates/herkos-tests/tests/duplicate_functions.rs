@@ -0,0 +1,34 @@
+//! End-to-end tests for merged duplicate functions.
+//!
+//! `duplicate_functions.wat` contains two identical copies of a function and
+//! of its caller. With `HERKOS_OPTIMIZE=1` each pair is generated once and
+//! the exports and table entries of the copies are redirected; results must
+//! not depend on that.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::duplicate_functions;
+
+#[test]
+fn test_both_copies_stay_exported() {
+    let mut module = duplicate_functions::new().unwrap();
+    assert_eq!(module.scale_a(5), Ok(16));
+    assert_eq!(module.scale_b(5), Ok(16));
+}
+
+#[test]
+fn test_callers_of_merged_copies() {
+    let mut module = duplicate_functions::new().unwrap();
+    // 1 + 4 + 7 + 10
+    assert_eq!(module.sum_a(4), Ok(22));
+    assert_eq!(module.sum_b(4), Ok(22));
+    assert_eq!(module.sum_b(0), Ok(0));
+}
+
+#[test]
+fn test_table_entries_of_merged_copies() {
+    let mut module = duplicate_functions::new().unwrap();
+    assert_eq!(module.dispatch(0, 2), Ok(7));
+    assert_eq!(module.dispatch(1, 2), Ok(7));
+    assert_eq!(module.dispatch(2, 2), Ok(-2));
+    assert_eq!(module.dispatch(3, 2), Err(WasmTrap::TableOutOfBounds));
+}
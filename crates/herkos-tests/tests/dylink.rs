@@ -0,0 +1,106 @@
+//! End-to-end tests for `dylink.0` side modules.
+//!
+//! `dylink_side_a.wat` and `dylink_side_b.wat` import one memory and table
+//! and place their segments relative to `__memory_base` / `__table_base`.
+//! `LinkedModules` assigns each its own area of the shared memory and table.
+
+use herkos_runtime::{Bases, IsolatedMemory, LinkedModules, Table, WasmTrap};
+use herkos_tests::{dylink_side_a, dylink_side_b};
+
+/// Host of one side module, reporting the bases the linker assigned to it.
+struct Placed(Bases);
+
+impl dylink_side_a::ModuleHostTrait for Placed {
+    fn get___memory_base(&self) -> i32 {
+        self.0.memory_base as i32
+    }
+    fn get___table_base(&self) -> i32 {
+        self.0.table_base as i32
+    }
+}
+
+impl dylink_side_b::ModuleHostTrait for Placed {
+    fn get___memory_base(&self) -> i32 {
+        self.0.memory_base as i32
+    }
+    fn get___table_base(&self) -> i32 {
+        self.0.table_base as i32
+    }
+}
+
+/// Place and initialize both side modules after 1 KiB of main-module data
+/// and the reserved null slot 0.
+fn link(memory: &mut IsolatedMemory<2>, table: &mut Table<8>) -> (Placed, Placed) {
+    let mut linked = LinkedModules::new(memory, table, 1024, 1);
+    let a = Placed(linked.place(&dylink_side_a::DYLINK).unwrap());
+    let b = Placed(linked.place(&dylink_side_b::DYLINK).unwrap());
+    let (memory, table) = linked.parts_mut();
+    dylink_side_a::init_memory(memory, &a).unwrap();
+    dylink_side_a::init_table(table, &a).unwrap();
+    dylink_side_b::init_memory(memory, &b).unwrap();
+    dylink_side_b::init_table(table, &b).unwrap();
+    (a, b)
+}
+
+#[test]
+fn test_modules_get_disjoint_aligned_areas() {
+    let mut memory = Box::new(IsolatedMemory::<2>::try_new(1).unwrap());
+    let mut table = Table::<8>::try_new(1).unwrap();
+    let (a, b) = link(&mut memory, &mut table);
+    assert_eq!(
+        a.0,
+        Bases {
+            memory_base: 1024,
+            table_base: 1,
+        }
+    );
+    // `dylink_side_b` asks for 8-byte alignment.
+    assert_eq!(
+        b.0,
+        Bases {
+            memory_base: 1032,
+            table_base: 2,
+        }
+    );
+    assert_eq!(table.size(), 4);
+    assert_eq!(memory.load_i32(1024), Ok(42));
+    assert_eq!(memory.load_i32(1040), Ok(7));
+    assert_eq!(memory.load_i32(1044), Ok(8));
+}
+
+#[test]
+fn test_function_pointers_are_table_base_relative() {
+    let mut memory = Box::new(IsolatedMemory::<2>::try_new(1).unwrap());
+    let mut table = Table::<8>::try_new(1).unwrap();
+    let (mut host_a, mut host_b) = link(&mut memory, &mut table);
+    let mut a = dylink_side_a::new().unwrap();
+    let mut b = dylink_side_b::new().unwrap();
+
+    let ptr = a.answer_ptr(&mut memory, &table, &mut host_a).unwrap();
+    assert_eq!(ptr, 1);
+    assert_eq!(a.call(ptr, &mut memory, &table, &mut host_a), Ok(42));
+    assert_eq!(b.call(2, &mut memory, &table, &mut host_b), Ok(7));
+    assert_eq!(b.call(3, &mut memory, &table, &mut host_b), Ok(8));
+}
+
+#[test]
+fn test_slots_of_other_modules_trap() {
+    let mut memory = Box::new(IsolatedMemory::<2>::try_new(1).unwrap());
+    let mut table = Table::<8>::try_new(1).unwrap();
+    let (mut host_a, mut host_b) = link(&mut memory, &mut table);
+    let mut a = dylink_side_a::new().unwrap();
+    let mut b = dylink_side_b::new().unwrap();
+
+    assert_eq!(
+        a.call(2, &mut memory, &table, &mut host_a),
+        Err(WasmTrap::UndefinedElement)
+    );
+    assert_eq!(
+        b.call(1, &mut memory, &table, &mut host_b),
+        Err(WasmTrap::UndefinedElement)
+    );
+    assert_eq!(
+        a.call(0, &mut memory, &table, &mut host_a),
+        Err(WasmTrap::UndefinedElement)
+    );
+}
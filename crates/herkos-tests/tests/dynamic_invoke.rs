@@ -0,0 +1,64 @@
+//! End-to-end tests for dynamic invocation.
+//!
+//! `dynamic_invoke.wat` is transpiled with `--dynamic-invoke` (see
+//! `build.rs`); these tests call its exports by name, as a scripting host
+//! would.
+
+use herkos_runtime::{Value, WasmTrap};
+use herkos_tests::dynamic_invoke;
+
+#[test]
+fn test_invoke_passes_every_value_type() {
+    let mut module = dynamic_invoke::new().unwrap();
+    assert_eq!(
+        module.invoke("add", &[Value::I32(2), Value::I32(40)]),
+        Ok(Some(Value::I32(42)))
+    );
+    assert_eq!(
+        module.invoke("widen", &[Value::I32(-1)]),
+        Ok(Some(Value::I64(-1)))
+    );
+    assert_eq!(
+        module.invoke("scale-f32", &[Value::F32(1.5), Value::F32(2.0)]),
+        Ok(Some(Value::F32(3.0)))
+    );
+    assert_eq!(
+        module.invoke("half", &[Value::F64(5.0)]),
+        Ok(Some(Value::F64(2.5)))
+    );
+}
+
+#[test]
+fn test_invoke_without_result_returns_none() {
+    let mut module = dynamic_invoke::new().unwrap();
+    assert_eq!(module.invoke("bump", &[]), Ok(None));
+    assert_eq!(module.invoke("bump", &[]), Ok(None));
+    assert_eq!(module.count(), Ok(2));
+}
+
+#[test]
+fn test_invoke_reports_traps_of_the_export() {
+    let mut module = dynamic_invoke::new().unwrap();
+    assert_eq!(
+        module.invoke("div", &[Value::I32(1), Value::I32(0)]),
+        Err(WasmTrap::DivisionByZero)
+    );
+}
+
+#[test]
+fn test_invoke_rejects_unknown_names_and_mismatched_arguments() {
+    let mut module = dynamic_invoke::new().unwrap();
+    assert_eq!(module.invoke("sub", &[]), Err(WasmTrap::UnknownExport));
+    assert_eq!(
+        module.invoke("add", &[Value::I32(1)]),
+        Err(WasmTrap::InvokeTypeMismatch)
+    );
+    assert_eq!(
+        module.invoke("add", &[Value::I32(1), Value::I64(2)]),
+        Err(WasmTrap::InvokeTypeMismatch)
+    );
+    assert_eq!(
+        module.invoke("bump", &[Value::I32(1)]),
+        Err(WasmTrap::InvokeTypeMismatch)
+    );
+}
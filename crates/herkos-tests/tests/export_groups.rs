@@ -0,0 +1,41 @@
+//! End-to-end tests for export groups.
+//!
+//! `export_groups.wat` is transpiled with the `image_` and `audio_` groups
+//! (see `build.rs`): its exports are reached through `image()` and `audio()`
+//! with the prefix stripped, and the flat methods keep working.
+
+use herkos_runtime::WasmResult;
+use herkos_tests::export_groups::{self, ModuleHostTrait};
+
+#[derive(Default)]
+struct Speaker {
+    beeps: Vec<i32>,
+}
+
+impl ModuleHostTrait for Speaker {
+    fn beep(&mut self, volume: i32) -> WasmResult<()> {
+        self.beeps.push(volume);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_grouped_exports_drop_their_prefix() {
+    let mut module = export_groups::new().unwrap();
+    let mut host = Speaker::default();
+    assert_eq!(module.image().area(6, 7, &mut host), Ok(42));
+    assert_eq!(module.image().flip(5, &mut host), Ok(-5));
+
+    let mut audio = module.audio();
+    audio.set_volume(9, &mut host).unwrap();
+    audio.play(&mut host).unwrap();
+    assert_eq!(host.beeps, [9]);
+}
+
+#[test]
+fn test_flat_methods_remain() {
+    let mut module = export_groups::new().unwrap();
+    let mut host = Speaker::default();
+    assert_eq!(module.image_area(2, 3, &mut host), Ok(6));
+    assert_eq!(module.version(&mut host), Ok(3));
+}
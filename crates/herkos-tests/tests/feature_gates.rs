@@ -0,0 +1,42 @@
+//! End-to-end tests for Cargo feature gating of exports.
+//!
+//! `feature_gates.features` gates `area` on `gated-area` (a default feature
+//! of this crate) and `volume` on `gated-volume` (off by default).
+
+use herkos_tests::feature_gates;
+
+#[test]
+fn test_ungated_export_is_always_available() {
+    let mut module = feature_gates::new().unwrap();
+    assert_eq!(module.version(), Ok(3));
+}
+
+#[cfg(feature = "gated-area")]
+#[test]
+fn test_enabled_export_is_compiled_in() {
+    let mut module = feature_gates::new().unwrap();
+    assert_eq!(module.area(7), Ok(49));
+}
+
+#[cfg(feature = "gated-volume")]
+#[test]
+fn test_volume_export_is_compiled_in() {
+    let mut module = feature_gates::new().unwrap();
+    assert_eq!(module.volume(3), Ok(27));
+}
+
+#[cfg(not(feature = "gated-volume"))]
+#[test]
+fn test_disabled_export_is_compiled_out() {
+    // Inherent methods take precedence over trait methods, so this fallback
+    // is only called if the generated `volume` method does not exist.
+    trait Missing {
+        fn volume(&mut self, _side: i32) -> Option<i32> {
+            None
+        }
+    }
+    impl Missing for feature_gates::WasmModule {}
+
+    let mut module = feature_gates::new().unwrap();
+    assert_eq!(module.volume(3), None);
+}
@@ -0,0 +1,25 @@
+//! Float constants keep their exact bits: NaN payloads and signs,
+//! infinities, subnormals and negative zero.
+
+use herkos_tests::float_consts;
+
+#[test]
+fn test_nan_payloads_survive() {
+    let mut m = float_consts::new().unwrap();
+    assert_eq!(m.nan_const().unwrap().to_bits(), 0xffff_0001);
+    assert_eq!(m.nan_global().unwrap().to_bits(), 0x7fa0_0001);
+}
+
+#[test]
+fn test_infinities() {
+    let mut m = float_consts::new().unwrap();
+    assert_eq!(m.floor().unwrap(), f64::NEG_INFINITY);
+    assert_eq!(m.infinity().unwrap(), f32::INFINITY);
+}
+
+#[test]
+fn test_subnormal_and_negative_zero() {
+    let mut m = float_consts::new().unwrap();
+    assert_eq!(m.tiny().unwrap().to_bits(), 1);
+    assert_eq!(m.neg_zero().unwrap().to_bits(), 0x8000_0000);
+}
@@ -0,0 +1,51 @@
+//! `min`/`max` on NaN and signed zeros.
+//!
+//! Wasm returns NaN if either operand is NaN, and treats -0 as less than
+//! +0; Rust's `f32::min` would return the non-NaN operand and either zero.
+
+use herkos_tests::float_minmax;
+
+#[test]
+fn test_nan_propagates() {
+    let mut m = float_minmax::new().unwrap();
+    assert!(m.f32_min(f32::NAN, 1.0).unwrap().is_nan());
+    assert!(m.f32_min(1.0, f32::NAN).unwrap().is_nan());
+    assert!(m.f32_max(f32::NAN, 1.0).unwrap().is_nan());
+    assert!(m.f32_max(f32::NEG_INFINITY, f32::NAN).unwrap().is_nan());
+    assert!(m.f64_min(f64::NAN, 1.0).unwrap().is_nan());
+    assert!(m.f64_min(f64::INFINITY, f64::NAN).unwrap().is_nan());
+    assert!(m.f64_max(1.0, f64::NAN).unwrap().is_nan());
+    assert!(m.f64_max(f64::NAN, -0.0).unwrap().is_nan());
+}
+
+#[test]
+fn test_negative_zero_is_smaller() {
+    let mut m = float_minmax::new().unwrap();
+    for (a, b) in [(0.0, -0.0), (-0.0, 0.0)] {
+        assert!(m.f32_min(a, b).unwrap().is_sign_negative());
+        assert!(m.f32_max(a, b).unwrap().is_sign_positive());
+        assert!(m.f64_min(a.into(), b.into()).unwrap().is_sign_negative());
+        assert!(m.f64_max(a.into(), b.into()).unwrap().is_sign_positive());
+    }
+}
+
+#[test]
+fn test_ordinary_values() {
+    let mut m = float_minmax::new().unwrap();
+    assert_eq!(m.f32_min(-1.5, 2.0), Ok(-1.5));
+    assert_eq!(m.f32_max(-1.5, 2.0), Ok(2.0));
+    assert_eq!(m.f64_min(f64::NEG_INFINITY, -1e300), Ok(f64::NEG_INFINITY));
+    assert_eq!(m.f64_max(f64::INFINITY, 1e300), Ok(f64::INFINITY));
+    let denorm = f64::from_bits(1);
+    assert_eq!(m.f64_min(-denorm, 0.0), Ok(-denorm));
+    assert_eq!(m.f64_max(denorm, -0.0), Ok(denorm));
+}
+
+#[test]
+fn test_constants_fold_the_same_way() {
+    let mut m = float_minmax::new().unwrap();
+    assert!(m.const_min_zeros().unwrap().is_sign_negative());
+    assert!(m.const_max_zeros().unwrap().is_sign_positive());
+    assert!(m.const_min_nan().unwrap().is_nan());
+    assert!(m.const_max_nan().unwrap().is_nan());
+}
@@ -0,0 +1,44 @@
+//! End-to-end tests for the guest allocator helpers.
+//!
+//! `guest_alloc.wat` exports `malloc`/`free`, and its bindings file declares
+//! no scratch region, so the generated wrappers stage buffers through
+//! `alloc_in_guest` / `free_in_guest`.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::guest_alloc;
+
+#[test]
+fn test_alloc_and_free_round_trip() {
+    let mut module = guest_alloc::new().unwrap();
+    let a = module.alloc_in_guest(3).unwrap();
+    let b = module.alloc_in_guest(16).unwrap();
+    assert_eq!((a, b), (0x1000, 0x1008));
+    assert_eq!(module.live().unwrap(), 2);
+
+    module.0.memory.write_bytes(b, &[7; 16]).unwrap();
+    assert_eq!(module.checksum(b as i32, 16).unwrap(), 7 * 16);
+
+    module.free_in_guest(a, 3).unwrap();
+    module.free_in_guest(b, 16).unwrap();
+    assert_eq!(module.live().unwrap(), 0);
+}
+
+#[test]
+fn test_null_pointer_is_a_trap() {
+    let mut module = guest_alloc::new().unwrap();
+    assert_eq!(
+        module.alloc_in_guest(0x10000),
+        Err(WasmTrap::GuestAllocFailed)
+    );
+}
+
+#[test]
+fn test_wrappers_allocate_and_release_through_the_guest() {
+    let mut module = guest_alloc::new().unwrap();
+    assert_eq!(module.checksum_bound(&[1, 2, 3, 250]).unwrap(), 256);
+    assert_eq!(
+        module.digest_bound(b"ab").unwrap(),
+        [195, 0, 0, 0, 2, 0, 0, 0]
+    );
+    assert_eq!(module.live().unwrap(), 0);
+}
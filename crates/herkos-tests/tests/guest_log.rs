@@ -0,0 +1,58 @@
+//! Logging imports routed to `GuestLog` (`--guest-log`).
+//!
+//! `guest_log.wat` prints through `env.log(ptr, len)`, directly and through
+//! the table, and through WASI `fd_write`. The host only implements
+//! `GuestLog`.
+
+use herkos_runtime::{GuestLog, WasmTrap, WASI_ERRNO_BADF};
+use herkos_tests::guest_log::{self, ModuleHostTrait};
+
+#[derive(Default)]
+struct Host {
+    messages: Vec<String>,
+}
+
+impl GuestLog for Host {
+    fn log(&mut self, msg: &str) {
+        self.messages.push(msg.to_string());
+    }
+}
+
+impl ModuleHostTrait for Host {}
+
+#[test]
+fn test_log_import_passes_text() {
+    let mut module = guest_log::new().unwrap();
+    let mut host = Host::default();
+    module.greet(&mut host).unwrap();
+    module.greet_indirect(&mut host).unwrap();
+    assert_eq!(host.messages, ["hello", "hello"]);
+}
+
+#[test]
+fn test_fd_write_to_stdout_logs_each_buffer() {
+    let mut module = guest_log::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.write(1, &mut host), Ok(0));
+    assert_eq!(host.messages, ["hello", "from wasi\n"]);
+    assert_eq!(module.0.memory.load_i32(96), Ok(15));
+}
+
+#[test]
+fn test_fd_write_to_other_descriptors_fails() {
+    let mut module = guest_log::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.write(3, &mut host), Ok(WASI_ERRNO_BADF));
+    assert!(host.messages.is_empty());
+    assert_eq!(module.0.memory.load_i32(96), Ok(0));
+}
+
+#[test]
+fn test_text_outside_memory_traps() {
+    let mut module = guest_log::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(
+        module.log_out_of_bounds(&mut host),
+        Err(WasmTrap::OutOfBounds)
+    );
+}
@@ -0,0 +1,59 @@
+//! Guest allocator profiling (`--heap-profile`).
+//!
+//! `heap_profile.wat` is a bump allocator whose `calloc` and `realloc` call
+//! its own `malloc` and `free`, and whose `make_buffer` allocates from
+//! inside the guest. Every block is counted once, wherever the call
+//! came from.
+
+use herkos_runtime::HeapStats;
+use herkos_tests::heap_profile;
+
+#[test]
+fn test_host_allocations_are_tracked() {
+    let mut module = heap_profile::new().unwrap();
+    let a = module.malloc(100).unwrap();
+    let b = module.calloc(4, 8).unwrap();
+    module.free(a).unwrap();
+    module.free(a).unwrap();
+
+    assert_eq!(
+        module.heap_stats(),
+        HeapStats {
+            live_bytes: 32,
+            peak_bytes: 132,
+            live_blocks: 1,
+            allocations: 2,
+            frees: 1,
+            failed_allocations: 0,
+            invalid_frees: 1,
+        }
+    );
+    assert!(module.heap_profile().leaks().eq([(b as u32, 32)]));
+    module.free(b).unwrap();
+}
+
+#[test]
+fn test_realloc_moves_the_block() {
+    let mut module = heap_profile::new().unwrap();
+    let a = module.malloc(16).unwrap();
+    let b = module.realloc(a, 64).unwrap();
+    assert_ne!(a, b);
+
+    let stats = module.heap_stats();
+    assert_eq!((stats.live_blocks, stats.live_bytes), (1, 64));
+    assert_eq!((stats.allocations, stats.frees), (2, 1));
+    assert_eq!(stats.peak_bytes, 64);
+    module.free(b).unwrap();
+}
+
+#[test]
+fn test_guest_internal_allocations_show_up_as_leaks() {
+    let mut module = heap_profile::new().unwrap();
+    let buf = module.make_buffer(24).unwrap();
+    assert!(module.heap_profile().leaks().eq([(buf as u32, 24)]));
+
+    assert_eq!(module.malloc(1 << 20), Ok(0));
+    assert_eq!(module.heap_stats().failed_allocations, 1);
+    module.free(buf).unwrap();
+    assert_eq!(module.heap_stats().live_blocks, 0);
+}
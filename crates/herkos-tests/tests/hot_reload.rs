@@ -0,0 +1,129 @@
+//! Swapping a module's code while it runs (`--hot-reload`).
+//!
+//! `hot_reload_v1.wat` and `hot_reload_v2.wat` are two builds of one
+//! plugin. Both are linked in here, so the vtables are swapped directly
+//! instead of being loaded from rebuilt `cdylib`s.
+
+use herkos_runtime::{
+    HotModule, HotReloadError, HotVTable, Linkable, Linker, Value, WasmResult, WasmTrap,
+    HOT_VTABLE_SYMBOL, PAGE_SIZE,
+};
+use herkos_tests::{hot_reload_v1, hot_reload_v2};
+
+herkos_runtime::export_hot_vtable!(hot_reload_v1::HOT_VTABLE);
+
+/// Serves `env.log`, recording what the plugin logs.
+#[derive(Default)]
+struct Log(Vec<i32>);
+
+impl Linkable for Log {
+    fn call(&mut self, name: &str, args: &[Value]) -> WasmResult<Option<Value>> {
+        match (name, args) {
+            ("log", &[Value::I32(v)]) => {
+                self.0.push(v);
+                Ok(None)
+            }
+            ("log", _) => Err(WasmTrap::InvokeTypeMismatch),
+            _ => Err(WasmTrap::UnknownExport),
+        }
+    }
+}
+
+fn call(
+    plugin: &mut HotModule,
+    log: &mut Log,
+    name: &str,
+    args: &[Value],
+) -> WasmResult<Option<Value>> {
+    let mut linker = Linker::new();
+    linker.register("env", log);
+    plugin.call(name, args, &mut linker)
+}
+
+#[test]
+fn test_reload_carries_memory_and_globals_over() {
+    let mut log = Log::default();
+    let mut plugin = HotModule::new(&hot_reload_v1::HOT_VTABLE).unwrap();
+    for _ in 0..3 {
+        call(&mut plugin, &mut log, "tick", &[]).unwrap();
+    }
+    call(&mut plugin, &mut log, "set_scale", &[Value::F64(0.5)]).unwrap();
+    assert_eq!(
+        call(&mut plugin, &mut log, "grow", &[]),
+        Ok(Some(Value::I32(1)))
+    );
+
+    plugin.reload(&hot_reload_v2::HOT_VTABLE).unwrap();
+    assert_eq!(
+        call(&mut plugin, &mut log, "tick", &[]),
+        Ok(Some(Value::I32(13)))
+    );
+    assert_eq!(
+        call(&mut plugin, &mut log, "calls", &[]),
+        Ok(Some(Value::I32(4)))
+    );
+    assert_eq!(
+        call(&mut plugin, &mut log, "scaled", &[]),
+        Ok(Some(Value::F64(6.5)))
+    );
+    // The grown page came along
+    assert_eq!(
+        call(&mut plugin, &mut log, "pages", &[]),
+        Ok(Some(Value::I32(2)))
+    );
+    assert_eq!(log.0, [1, 2, 3, 13]);
+}
+
+#[test]
+fn test_state_survives_a_round_trip() {
+    let mut log = Log::default();
+    let mut plugin = HotModule::new(&hot_reload_v2::HOT_VTABLE).unwrap();
+    call(&mut plugin, &mut log, "tick", &[]).unwrap();
+    let state = plugin.save();
+    assert_eq!(state.memory.len(), PAGE_SIZE);
+    assert_eq!(state.globals, [10, 1f64.to_bits()]);
+
+    plugin.reload(&hot_reload_v1::HOT_VTABLE).unwrap();
+    plugin.reload(&hot_reload_v2::HOT_VTABLE).unwrap();
+    assert_eq!(plugin.save(), state);
+}
+
+#[test]
+fn test_exports_of_the_old_build_are_gone() {
+    let mut log = Log::default();
+    let mut plugin = HotModule::new(&hot_reload_v1::HOT_VTABLE).unwrap();
+    plugin.reload(&hot_reload_v2::HOT_VTABLE).unwrap();
+    assert_eq!(
+        call(&mut plugin, &mut log, "grow", &[]),
+        Err(WasmTrap::UnknownExport)
+    );
+    assert_eq!(
+        call(&mut plugin, &mut log, "tick", &[Value::I32(1)]),
+        Err(WasmTrap::InvokeTypeMismatch)
+    );
+}
+
+#[test]
+fn test_state_that_does_not_fit_is_refused() {
+    let mut plugin = HotModule::new(&hot_reload_v1::HOT_VTABLE).unwrap();
+    let mut state = plugin.save();
+    // Past the four pages both builds may grow to
+    state.memory.resize(5 * PAGE_SIZE, 0);
+    assert_eq!(
+        HotModule::with_state(&hot_reload_v2::HOT_VTABLE, &state).err(),
+        Some(HotReloadError::Trap(WasmTrap::OutOfBounds))
+    );
+    state.globals.pop();
+    assert!(matches!(
+        HotModule::with_state(&hot_reload_v2::HOT_VTABLE, &state),
+        Err(HotReloadError::GlobalsMismatch { saved: 1, found: 2 })
+    ));
+    assert!(plugin.reload(&hot_reload_v2::HOT_VTABLE).is_ok());
+}
+
+#[test]
+fn test_exported_entry_point_returns_the_vtable() {
+    let vtable: &'static HotVTable = herkos_hot_vtable();
+    assert!(std::ptr::eq(vtable, &hot_reload_v1::HOT_VTABLE));
+    assert_eq!(HOT_VTABLE_SYMBOL, "herkos_hot_vtable");
+}
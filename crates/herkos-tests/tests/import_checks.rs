@@ -0,0 +1,60 @@
+//! Checked host calls (`import` lines of a bindings file).
+//!
+//! `import_checks.bindings` declares the pointer arguments of the imports of
+//! `import_checks.wat`; a guest passing a region past the end of its memory
+//! traps before the host sees it.
+
+use herkos_runtime::{WasmResult, WasmTrap};
+use herkos_tests::import_checks;
+
+#[derive(Default)]
+struct Host {
+    writes: Vec<(i32, i32)>,
+    stamps: usize,
+}
+
+impl import_checks::ModuleHostTrait for Host {
+    fn write(&mut self, buf: i32, len: i32) -> WasmResult<i32> {
+        self.writes.push((buf, len));
+        Ok(len)
+    }
+
+    fn now(&mut self, _at: i32) -> WasmResult<()> {
+        self.stamps += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_regions_in_memory_reach_the_host() {
+    let mut module = import_checks::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.write_at(16, 5, &mut host), Ok(5));
+    // Ending exactly at the end of memory, and empty at the end
+    assert_eq!(module.write_at(65536 - 4, 4, &mut host), Ok(4));
+    assert_eq!(module.write_at(65536, 0, &mut host), Ok(0));
+    assert_eq!(host.writes, [(16, 5), (65532, 4), (65536, 0)]);
+    assert_eq!(module.stamp(65536 - 8, &mut host), Ok(0));
+    assert_eq!(host.stamps, 1);
+}
+
+#[test]
+fn test_regions_past_memory_trap_before_the_host() {
+    let mut module = import_checks::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(
+        module.write_at(65536 - 4, 5, &mut host),
+        Err(WasmTrap::OutOfBounds)
+    );
+    // A length that wraps the address space
+    assert_eq!(
+        module.write_at(16, -1, &mut host),
+        Err(WasmTrap::OutOfBounds)
+    );
+    assert_eq!(
+        module.stamp(65536 - 4, &mut host),
+        Err(WasmTrap::OutOfBounds)
+    );
+    assert!(host.writes.is_empty());
+    assert_eq!(host.stamps, 0);
+}
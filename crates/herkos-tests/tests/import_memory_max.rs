@@ -0,0 +1,48 @@
+//! `memory.grow` on imported memory respects the import's declared maximum.
+//!
+//! `import_memory_max.wat` imports `(memory 1 3)`; the host lends a memory
+//! with room for more pages than that.
+
+use herkos_runtime::IsolatedMemory;
+use herkos_tests::import_memory_max;
+
+#[test]
+fn test_grow_stops_at_declared_maximum() {
+    let mut memory = Box::new(IsolatedMemory::<8>::try_new(1).unwrap());
+    let mut module = import_memory_max::new().unwrap();
+
+    assert_eq!(module.grow(2, &mut memory).unwrap(), 1);
+    assert_eq!(module.size(&mut memory).unwrap(), 3);
+    assert_eq!(module.grow(1, &mut memory).unwrap(), -1);
+    assert_eq!(module.size(&mut memory).unwrap(), 3);
+    assert_eq!(memory.page_count(), 3);
+}
+
+#[test]
+fn test_grow_past_maximum_in_one_step_fails() {
+    let mut memory = Box::new(IsolatedMemory::<8>::try_new(1).unwrap());
+    let mut module = import_memory_max::new().unwrap();
+
+    assert_eq!(module.grow(5, &mut memory).unwrap(), -1);
+    assert_eq!(memory.page_count(), 1);
+}
+
+#[test]
+fn test_smaller_host_memory_limits_grow() {
+    let mut memory = Box::new(IsolatedMemory::<2>::try_new(1).unwrap());
+    let mut module = import_memory_max::new().unwrap();
+
+    assert_eq!(module.grow(2, &mut memory).unwrap(), -1);
+    assert_eq!(module.grow(1, &mut memory).unwrap(), 1);
+    assert_eq!(module.size(&mut memory).unwrap(), 2);
+}
+
+#[test]
+fn test_grown_pages_are_usable() {
+    let mut memory = Box::new(IsolatedMemory::<8>::try_new(1).unwrap());
+    let mut module = import_memory_max::new().unwrap();
+
+    module.grow(2, &mut memory).unwrap();
+    module.store_last(77, &mut memory).unwrap();
+    assert_eq!(memory.load_i32(3 * 65536 - 4), Ok(77));
+}
@@ -0,0 +1,42 @@
+//! End-to-end tests for globals initialized from imported globals.
+//!
+//! `imported_global_init.wat` initializes an immutable and a mutable global
+//! with `global.get $__memory_base`, so `new()` reads the base from the host.
+
+use herkos_tests::imported_global_init;
+
+struct Base(i32);
+
+impl imported_global_init::ModuleHostTrait for Base {
+    fn get___memory_base(&self) -> i32 {
+        self.0
+    }
+}
+
+#[test]
+fn test_initializers_read_host_at_instantiation() {
+    let mut host = Base(256);
+    let mut module = imported_global_init::new(&host).unwrap();
+    // Later host changes do not affect the instantiated values.
+    host.0 = 1024;
+    assert_eq!(module.base(&mut host), Ok(256));
+}
+
+#[test]
+fn test_mutable_global_starts_at_imported_value() {
+    let mut host = Base(64);
+    let mut module = imported_global_init::new(&host).unwrap();
+    assert_eq!(module.push(7, &mut host), Ok(64));
+    assert_eq!(module.push(9, &mut host), Ok(68));
+    assert_eq!(module.load(64, &mut host), Ok(7));
+    assert_eq!(module.load(68, &mut host), Ok(9));
+}
+
+#[test]
+fn test_instances_get_their_own_base() {
+    let mut low = imported_global_init::new(&Base(0)).unwrap();
+    let mut high = imported_global_init::new(&Base(4096)).unwrap();
+    let mut host = Base(0);
+    assert_eq!(low.push(1, &mut host), Ok(0));
+    assert_eq!(high.push(2, &mut host), Ok(4096));
+}
@@ -0,0 +1,42 @@
+//! End-to-end tests for LLVM's `__indirect_function_table` conventions.
+//!
+//! `import_table.wat` imports the table (`-Wl,--import-table`), so the host
+//! owns it and passes it to every export; `export_table.wat` exports it
+//! (`-Wl,--export-table`), so the host reaches it through accessors.
+
+use herkos_runtime::{Table, WasmTrap};
+use herkos_tests::{export_table, import_table};
+
+#[test]
+fn test_imported_table_is_passed_by_the_host() {
+    let mut module = import_table::new().unwrap();
+    let table = import_table::new_table::<8>().unwrap();
+    assert_eq!(table.size(), 3);
+    assert_eq!(module.apply(1, 20, &table), Ok(40));
+    assert_eq!(module.apply(2, 20, &table), Ok(21));
+    assert_eq!(module.apply(0, 20, &table), Err(WasmTrap::UndefinedElement));
+}
+
+#[test]
+fn test_init_table_fills_a_host_table() {
+    let mut module = import_table::new().unwrap();
+    let mut table = Table::<4>::try_new(4).unwrap();
+    import_table::init_table(&mut table).unwrap();
+    assert_eq!(module.apply(1, 5, &table), Ok(10));
+    assert_eq!(module.apply(3, 5, &table), Err(WasmTrap::UndefinedElement));
+}
+
+#[test]
+fn test_exported_table_is_reachable_from_the_host() {
+    let mut module = export_table::new().unwrap();
+    assert_eq!(module.indirect_function_table().size(), 3);
+    assert_eq!(module.apply(2, 7), Ok(8));
+
+    // Swap the two entries through the table export.
+    let table = module.indirect_function_table_mut();
+    let (double, inc) = (table.get(1).unwrap(), table.get(2).unwrap());
+    table.set(1, Some(inc)).unwrap();
+    table.set(2, Some(double)).unwrap();
+    assert_eq!(module.apply(1, 7), Ok(8));
+    assert_eq!(module.apply(2, 7), Ok(14));
+}
@@ -0,0 +1,48 @@
+//! Bounded instantiation (`new_limited`).
+//!
+//! `instantiation_limits.wat` copies 12 bytes of data and 3 table entries
+//! when instantiated.
+
+use herkos_runtime::{InstantiationCost, InstantiationLimits, WasmTrap};
+use herkos_tests::instantiation_limits;
+
+#[test]
+fn test_cost_counts_data_bytes_and_table_entries() {
+    assert_eq!(
+        instantiation_limits::INSTANTIATION_COST,
+        InstantiationCost {
+            data_bytes: 12,
+            table_entries: 3
+        }
+    );
+}
+
+#[test]
+fn test_within_limits_instantiates() {
+    let limits = InstantiationLimits {
+        max_data_bytes: 12,
+        max_table_entries: 3,
+    };
+    let mut module = instantiation_limits::new_limited(&limits).unwrap();
+    assert_eq!(module.call(1), Ok(2));
+    assert_eq!(module.load8(64), Ok(i32::from(b'w')));
+    assert!(instantiation_limits::new_limited(&InstantiationLimits::UNLIMITED).is_ok());
+}
+
+#[test]
+fn test_exceeding_limits_fails() {
+    let data = InstantiationLimits {
+        max_data_bytes: 11,
+        ..InstantiationLimits::UNLIMITED
+    };
+    let entries = InstantiationLimits {
+        max_table_entries: 2,
+        ..InstantiationLimits::UNLIMITED
+    };
+    for limits in [data, entries] {
+        assert!(matches!(
+            instantiation_limits::new_limited(&limits),
+            Err(WasmTrap::InstantiationLimitExceeded)
+        ));
+    }
+}
@@ -0,0 +1,28 @@
+//! End-to-end tests for a large element segment.
+//!
+//! `large_table.wat` fills 90 table slots from one segment, which is emitted
+//! as a `static` table and copied in by the constructor.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::large_table;
+
+#[test]
+fn test_every_slot_of_the_large_segment() {
+    let mut module = large_table::new().unwrap();
+    for slot in 0..90 {
+        let expected = match slot % 3 {
+            0 => 11,
+            1 => 9,
+            _ => 20,
+        };
+        assert_eq!(module.dispatch(slot, 10), Ok(expected), "slot {slot}");
+    }
+}
+
+#[test]
+fn test_small_segment_and_empty_slots() {
+    let mut module = large_table::new().unwrap();
+    assert_eq!(module.dispatch(95, 4), Ok(8));
+    assert_eq!(module.dispatch(90, 4), Err(WasmTrap::UndefinedElement));
+    assert_eq!(module.dispatch(100, 4), Err(WasmTrap::TableOutOfBounds));
+}
@@ -0,0 +1,76 @@
+//! Modules wired together at runtime through a `Linker` (`--link`).
+//!
+//! `link_app.wat` imports `math.double`; `link_math.wat` exports it and
+//! counts its calls. Registering a `link_math` instance under "math" serves
+//! the import without a hand-written host.
+
+use herkos_runtime::{Linkable, Linker, NoHost, Value, WasmResult, WasmTrap};
+use herkos_tests::{link_app, link_math};
+
+#[test]
+fn test_imports_are_served_by_the_registered_module() {
+    let mut math = link_math::LinkedWasmModule::new(link_math::new().unwrap(), NoHost);
+    let mut app = link_app::new().unwrap();
+    {
+        let mut linker = Linker::new();
+        linker.register("math", &mut math);
+        assert_eq!(app.quadruple(5, &mut linker), Ok(20));
+        assert_eq!(app.quadruple(-3, &mut linker), Ok(-12));
+    }
+    assert_eq!(math.module.calls(), Ok(4));
+}
+
+#[test]
+fn test_linked_modules_are_callable_by_name() {
+    let mut math = link_math::LinkedWasmModule::new(link_math::new().unwrap(), NoHost);
+    assert_eq!(
+        math.call("double", &[Value::I32(21)]),
+        Ok(Some(Value::I32(42)))
+    );
+    assert_eq!(
+        math.call("double", &[Value::I64(21)]),
+        Err(WasmTrap::InvokeTypeMismatch)
+    );
+    assert_eq!(math.call("triple", &[]), Err(WasmTrap::UnknownExport));
+}
+
+#[test]
+fn test_linked_modules_can_serve_in_turn() {
+    // `app` is served by `math` and serves `quadruple` to whoever links it
+    let mut inner = Linker::new();
+    inner.register(
+        "math",
+        link_math::LinkedWasmModule::new(link_math::new().unwrap(), NoHost),
+    );
+    let mut outer = Linker::new();
+    outer.register(
+        "app",
+        link_app::LinkedWasmModule::new(link_app::new().unwrap(), inner),
+    );
+    assert_eq!(
+        outer.call("app", "quadruple", &[Value::I32(7)]),
+        Ok(Some(Value::I32(28)))
+    );
+}
+
+/// Returns an `i64` where `link_app` expects an `i32`.
+struct WrongDouble;
+
+impl Linkable for WrongDouble {
+    fn call(&mut self, _name: &str, _args: &[Value]) -> WasmResult<Option<Value>> {
+        Ok(Some(Value::I64(0)))
+    }
+}
+
+#[test]
+fn test_missing_modules_and_mismatched_results_trap() {
+    let mut app = link_app::new().unwrap();
+    let mut linker = Linker::new();
+    assert_eq!(app.quadruple(1, &mut linker), Err(WasmTrap::UnknownExport));
+
+    linker.register("math", WrongDouble);
+    assert_eq!(
+        app.quadruple(1, &mut linker),
+        Err(WasmTrap::InvokeTypeMismatch)
+    );
+}
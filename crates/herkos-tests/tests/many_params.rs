@@ -0,0 +1,39 @@
+//! Functions with more than seven parameters.
+//!
+//! The export method, the import method and the internal function all exceed
+//! clippy's `too_many_arguments` threshold; the crate-level lints in
+//! `herkos-tests` check that the generated code still passes clippy.
+
+use herkos_runtime::WasmResult;
+use herkos_tests::many_params;
+
+/// Host whose `weigh` import sums its arguments.
+struct Summing;
+
+impl many_params::ModuleHostTrait for Summing {
+    fn weigh(
+        &mut self,
+        a: i32,
+        b: i32,
+        c: i32,
+        d: i32,
+        e: i32,
+        f: i32,
+        g: i32,
+        h: i32,
+    ) -> WasmResult<i32> {
+        Ok(a + b + c + d + e + f + g + h)
+    }
+}
+
+#[test]
+fn test_eight_arguments_reach_helper_and_import() {
+    let mut module = many_params::new().unwrap();
+    // dot = 1 + 2 + ... + 8 = 36, weigh = 8.
+    assert_eq!(module.combine(1, 1, 1, 1, 1, 1, 1, 1, &mut Summing), Ok(44));
+    // dot = 1*1 + 2*2 + ... + 8*8 = 204, weigh = 36.
+    assert_eq!(
+        module.combine(1, 2, 3, 4, 5, 6, 7, 8, &mut Summing),
+        Ok(240)
+    );
+}
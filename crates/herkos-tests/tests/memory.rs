@@ -3,7 +3,8 @@
 //! These tests verify that the generated code actually works correctly
 //! when executed with the herkos-runtime memory implementation.
 
-use herkos_tests::{memory_load, memory_roundtrip, memory_store, memory_sum};
+use herkos_runtime::WasmTrap;
+use herkos_tests::{memory_load, memory_negative_base, memory_roundtrip, memory_store, memory_sum};
 
 #[test]
 fn test_memory_store() {
@@ -138,3 +139,23 @@ fn test_memory_sum_as_static() {
     let sum = module.func_0(0, 3).unwrap();
     assert_eq!(sum, 60, "sum of [10, 20, 30] should be 60");
 }
+
+#[test]
+fn test_offsets_do_not_wrap_negative_base_addresses() {
+    let mut module = memory_negative_base::new().unwrap();
+
+    module.store(16, 7).unwrap();
+    assert_eq!(module.load(16), Ok(7));
+    module.store8(0, 9).unwrap();
+    assert_eq!(module.load8_u(0), Ok(9));
+
+    // 0xFFFF_FFF0 + 32 is past the memory, not address 16
+    assert_eq!(module.store(-16, 1), Err(WasmTrap::OutOfBounds));
+    assert_eq!(module.load(-16), Err(WasmTrap::OutOfBounds));
+    assert_eq!(module.store8(-16, 1), Err(WasmTrap::OutOfBounds));
+    assert_eq!(module.load8_u(-16), Err(WasmTrap::OutOfBounds));
+    assert_eq!(module.load64(-65536), Err(WasmTrap::OutOfBounds));
+    // Nothing landed in low memory
+    assert_eq!(module.load(16), Ok(7));
+    assert_eq!(module.load8_u(0), Ok(9));
+}
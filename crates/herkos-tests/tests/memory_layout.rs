@@ -0,0 +1,51 @@
+//! Memory layout descriptors.
+//!
+//! `memory_layout.wat` is laid out like a wasm-ld output; the host reads and
+//! writes guest data through the generated `layout` constants instead of
+//! hard-coded addresses.
+
+use herkos_tests::memory_layout;
+
+#[test]
+fn test_regions_follow_the_linker_globals() {
+    assert_eq!(memory_layout::layout::RODATA, 1024..1029);
+    assert_eq!(memory_layout::layout::DATA, 1032..1036);
+    assert_eq!(memory_layout::layout::DATA_END, 1040);
+    assert_eq!(memory_layout::layout::STACK, 1040..66576);
+    assert_eq!(
+        memory_layout::layout::HEAP_BASE,
+        memory_layout::layout::STACK.end
+    );
+}
+
+#[test]
+fn test_host_reads_static_data() {
+    let module = memory_layout::new().unwrap();
+    let rodata = memory_layout::layout::RODATA;
+    let mut greeting = [0; 5];
+    module
+        .0
+        .memory
+        .read_bytes(rodata.start as usize, &mut greeting)
+        .unwrap();
+    assert_eq!(&greeting, b"hello");
+
+    let mut module = module;
+    assert_eq!(module.bump(), Ok(6));
+    let counter = module
+        .0
+        .memory
+        .load_i32(memory_layout::layout::DATA.start as usize)
+        .unwrap();
+    assert_eq!(counter, 6);
+}
+
+#[test]
+fn test_host_writes_at_the_heap_base() {
+    let mut module = memory_layout::new().unwrap();
+    let base = memory_layout::layout::HEAP_BASE as usize;
+    for (i, word) in [10, 20, 12].into_iter().enumerate() {
+        module.0.memory.store_i32(base + 4 * i, word).unwrap();
+    }
+    assert_eq!(module.sum_heap(3), Ok(42));
+}
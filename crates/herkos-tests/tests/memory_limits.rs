@@ -0,0 +1,43 @@
+//! `memory_limits()` and `required_memory()` report a module's memory needs
+//! before instantiation.
+//!
+//! `memory_limits.wat` declares `(memory 2 4)` with a data segment ending at
+//! byte 65539; `import_memory_max.wat` imports `(memory 1 3)`.
+
+use herkos_runtime::{IsolatedMemory, MemoryLimits, PAGE_SIZE};
+use herkos_tests::{import_memory_max, memory_limits};
+
+const LIMITS: MemoryLimits = memory_limits::WasmModule::memory_limits();
+
+#[test]
+fn test_owned_memory_limits() {
+    assert_eq!(
+        LIMITS,
+        MemoryLimits {
+            initial_pages: 2,
+            max_pages: Some(4),
+            data_end: 65539,
+        }
+    );
+    assert_eq!(memory_limits::WasmModule::required_memory(), 2 * PAGE_SIZE);
+    let mut module = memory_limits::new().unwrap();
+    assert_eq!(module.load(65538), Ok(b's' as i32));
+}
+
+#[test]
+fn test_borrowed_memory_can_be_sized_from_limits() {
+    const LIMITS: MemoryLimits = import_memory_max::WasmModule::memory_limits();
+    assert_eq!(LIMITS.initial_pages, 1);
+    assert_eq!(LIMITS.max_pages, Some(3));
+    assert!(LIMITS.fits(3));
+    assert!(!LIMITS.fits(4));
+
+    const PAGES: usize = import_memory_max::WasmModule::memory_limits()
+        .max_pages
+        .unwrap() as usize;
+    let mut memory =
+        Box::new(IsolatedMemory::<PAGES>::try_new(LIMITS.required_pages() as usize).unwrap());
+    let mut module = import_memory_max::new().unwrap();
+    assert_eq!(module.grow(2, &mut memory).unwrap(), 1);
+    assert_eq!(module.grow(1, &mut memory).unwrap(), -1);
+}
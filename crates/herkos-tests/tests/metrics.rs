@@ -0,0 +1,40 @@
+//! End-to-end tests for the generated module metrics.
+//!
+//! Every transpiled module exposes `metrics()`, so a host can inventory the
+//! modules it links without the original binaries.
+
+use herkos_runtime::{Instrumentation, ModuleMetrics};
+use herkos_tests::{coverage, hello_data, symbols, yield_points};
+
+#[test]
+fn test_metrics_count_functions_imports_and_exports() {
+    let metrics = symbols::metrics();
+    assert_eq!(metrics.functions as usize, symbols::SYMBOLS.len());
+    assert_eq!(metrics.imports, 1);
+    assert_eq!(metrics.exports, 2, "one function exported twice");
+    assert!(metrics.blocks >= metrics.functions);
+    assert_eq!(metrics.table_size, 0);
+    assert_eq!(metrics.memory_pages, 0);
+    assert_eq!(metrics.instrumentation, Instrumentation::NONE);
+}
+
+#[test]
+fn test_metrics_count_memory_and_data() {
+    let metrics = hello_data::metrics();
+    assert_eq!(metrics.memory_pages, 1);
+    assert_eq!(metrics.data_bytes, "Hello".len() as u32);
+}
+
+#[test]
+fn test_metrics_name_the_instrumentation() {
+    assert!(coverage::metrics().instrumentation.coverage);
+    assert!(yield_points::metrics().instrumentation.yield_points);
+    assert_eq!(coverage::metrics().instrumentation.to_string(), "coverage");
+    assert!(!hello_data::metrics().instrumentation.any());
+}
+
+#[test]
+fn test_metrics_are_const() {
+    const METRICS: ModuleMetrics = symbols::metrics();
+    assert_eq!(METRICS, symbols::metrics());
+}
@@ -0,0 +1,16 @@
+//! Runtime ABI guard of generated modules.
+//!
+//! Every generated module carries `MODULE_ABI` and refuses to compile
+//! against a runtime whose `ABI_VERSION` is not compatible with it; that
+//! these modules compile at all is the positive half of the check.
+
+use herkos_runtime::{abi_compatible, ABI_VERSION};
+use herkos_tests::{add, import_memory, pure_math};
+
+#[test]
+fn test_generated_modules_record_the_runtime_abi() {
+    assert_eq!(add::MODULE_ABI, ABI_VERSION);
+    assert_eq!(import_memory::MODULE_ABI, ABI_VERSION);
+    assert_eq!(pure_math::MODULE_ABI, ABI_VERSION);
+    assert!(abi_compatible(add::MODULE_ABI));
+}
@@ -0,0 +1,70 @@
+//! Tests for multi-value: exports returning tuples, calls consuming every
+//! result, and blocks, ifs and loops taking parameters from the stack.
+
+use herkos_tests::multi_value;
+
+#[test]
+fn test_results_return_as_a_tuple() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.divmod(17, 5).unwrap(), (3, 2));
+    assert_eq!(m.widen(-1).unwrap(), (0xffff_ffff, -1));
+}
+
+#[test]
+fn test_call_receives_every_result() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.sum_of_parts(17, 5).unwrap(), 5);
+}
+
+#[test]
+fn test_block_params_and_results() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.swap_unless(1, 2, 0).unwrap(), (2, 1));
+    // br_if carries both values out unchanged
+    assert_eq!(m.swap_unless(1, 2, 1).unwrap(), (1, 2));
+}
+
+#[test]
+fn test_if_params() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.split_sign(-7).unwrap(), (7, -1));
+    assert_eq!(m.split_sign(7).unwrap(), (7, 1));
+    assert_eq!(m.double_if(21, 1).unwrap(), 42);
+    assert_eq!(m.double_if(21, 0).unwrap(), 21);
+}
+
+#[test]
+fn test_loop_params() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.triangle(4).unwrap(), 10);
+    assert_eq!(m.triangle(1).unwrap(), 1);
+}
+
+#[test]
+fn test_br_table_carries_loop_params() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.bit_length(8).unwrap(), 4);
+    assert_eq!(m.bit_length(1).unwrap(), 1);
+    assert_eq!(m.bit_length(-1).unwrap(), 32);
+}
+
+#[test]
+fn test_block_typed_by_index() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.pick(1).unwrap(), 10);
+    assert_eq!(m.pick(0).unwrap(), 20);
+}
+
+#[test]
+fn test_block_param_before_unreachable() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.nonzero_or_trap(5).unwrap(), 5);
+    assert!(m.nonzero_or_trap(0).is_err());
+}
+
+#[test]
+fn test_call_indirect_results() {
+    let mut m = multi_value::new().unwrap();
+    assert_eq!(m.divmod_indirect(17, 5).unwrap(), (3, 2));
+    assert!(m.divmod_indirect(1, 0).is_err());
+}
@@ -0,0 +1,122 @@
+//! End-to-end tests for memory observation.
+//!
+//! `observe_memory.wat` is transpiled with `--observe-memory`, so the host's
+//! `MemoryObserver` sees every access; `Watchpoints` narrows that down to the
+//! watched ranges.
+
+use herkos_runtime::{MemoryObserver, NoHost, WasmTrap, Watchpoints};
+use herkos_tests::observe_memory;
+
+/// Kind, address and length of a reported access.
+type Hit = (&'static str, usize, usize);
+
+/// Records every access it is told about.
+#[derive(Default)]
+struct Log {
+    hits: Vec<Hit>,
+}
+
+impl MemoryObserver for Log {
+    fn on_load(&mut self, addr: usize, len: usize) {
+        self.hits.push(("load", addr, len));
+    }
+
+    fn on_store(&mut self, addr: usize, len: usize) {
+        self.hits.push(("store", addr, len));
+    }
+}
+
+/// Host with data watchpoints in front of the log.
+struct Debugger {
+    watch: Watchpoints<Log, 4>,
+}
+
+impl Debugger {
+    fn new() -> Self {
+        Self {
+            watch: Watchpoints::new(Log::default()),
+        }
+    }
+
+    fn hits(&self) -> &[Hit] {
+        &self.watch.observer().hits
+    }
+}
+
+impl MemoryObserver for Debugger {
+    fn on_load(&mut self, addr: usize, len: usize) {
+        self.watch.on_load(addr, len);
+    }
+
+    fn on_store(&mut self, addr: usize, len: usize) {
+        self.watch.on_store(addr, len);
+    }
+}
+
+impl observe_memory::ModuleHostTrait for Log {}
+impl observe_memory::ModuleHostTrait for Debugger {}
+
+#[test]
+fn test_every_access_is_reported() {
+    let mut module = observe_memory::new().unwrap();
+    let mut host = Log::default();
+    assert_eq!(module.bump(&mut host), Ok(1));
+    assert_eq!(
+        host.hits,
+        [("load", 0x100, 4), ("store", 0x100, 4), ("load", 0x100, 4)]
+    );
+}
+
+#[test]
+fn test_watchpoint_sees_only_its_range() {
+    let mut module = observe_memory::new().unwrap();
+    let mut host = Debugger::new();
+    assert!(host.watch.watch(0x100..0x104));
+
+    assert_eq!(module.store8(0x200, 7, &mut host), Ok(()));
+    assert_eq!(module.load64(0x0f8, &mut host), Ok(0));
+    assert!(host.hits().is_empty());
+
+    assert_eq!(module.store8(0x103, 7, &mut host), Ok(()));
+    assert_eq!(module.load64(0x0fc, &mut host), Ok(0x0700_0000_0000_0000));
+    assert_eq!(host.hits(), [("store", 0x103, 1), ("load", 0x0fc, 8)]);
+}
+
+#[test]
+fn test_bulk_operations_report_their_ranges() {
+    let mut module = observe_memory::new().unwrap();
+    let mut host = Debugger::new();
+    assert!(host.watch.watch(0x100..0x101));
+    assert!(host.watch.watch(0x400..0x401));
+
+    assert_eq!(module.fill(0x0f0, 0x20, &mut host), Ok(()));
+    assert_eq!(module.copy(0x400, 0x0f0, 0x10, &mut host), Ok(()));
+    assert_eq!(module.copy(0x800, 0x100, 0x10, &mut host), Ok(()));
+    assert_eq!(
+        host.hits(),
+        [
+            ("store", 0x0f0, 0x20),
+            ("store", 0x400, 0x10),
+            ("load", 0x100, 0x10),
+        ]
+    );
+}
+
+#[test]
+fn test_trapping_access_is_reported_first() {
+    let mut module = observe_memory::new().unwrap();
+    let mut host = Debugger::new();
+    assert!(host.watch.watch(0x1_0000..usize::MAX));
+    assert_eq!(
+        module.store8(0x1_0000, 1, &mut host),
+        Err(WasmTrap::OutOfBounds)
+    );
+    assert_eq!(host.hits(), [("store", 0x1_0000, 1)]);
+}
+
+#[test]
+fn test_no_host_ignores_accesses() {
+    let mut module = observe_memory::new().unwrap();
+    assert_eq!(module.bump(&mut NoHost), Ok(1));
+    assert_eq!(module.bump(&mut NoHost), Ok(2));
+}
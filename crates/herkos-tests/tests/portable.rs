@@ -0,0 +1,107 @@
+//! Code generated with `--portable`.
+//!
+//! Float rounding and square roots run on the runtime's `core`-only helpers,
+//! which must agree with `std`'s, and an address plus its static offset past
+//! 4 GiB traps instead of wrapping around to low memory.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::portable;
+
+const F64_VALUES: &[f64] = &[
+    0.0,
+    -0.0,
+    0.5,
+    -0.5,
+    1.5,
+    2.5,
+    -2.5,
+    2.0,
+    1e-310,
+    123.456,
+    -987.654,
+    1e300,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+];
+
+#[test]
+fn test_f64_rounding_matches_std() {
+    let mut m = portable::new().unwrap();
+    for &v in F64_VALUES {
+        assert_eq!(
+            m.f64_floor(v).unwrap().to_bits(),
+            v.floor().to_bits(),
+            "floor({v})"
+        );
+        assert_eq!(
+            m.f64_ceil(v).unwrap().to_bits(),
+            v.ceil().to_bits(),
+            "ceil({v})"
+        );
+        assert_eq!(
+            m.f64_trunc(v).unwrap().to_bits(),
+            v.trunc().to_bits(),
+            "trunc({v})"
+        );
+        let nearest = v.round_ties_even();
+        assert_eq!(
+            m.f64_nearest(v).unwrap().to_bits(),
+            nearest.to_bits(),
+            "nearest({v})"
+        );
+        if v >= 0.0 {
+            assert_eq!(
+                m.f64_sqrt(v).unwrap().to_bits(),
+                v.sqrt().to_bits(),
+                "sqrt({v})"
+            );
+        }
+    }
+    assert!(m.f64_sqrt(-1.0).unwrap().is_nan());
+}
+
+#[test]
+fn test_f32_rounding_matches_std() {
+    let mut m = portable::new().unwrap();
+    for &v in F64_VALUES {
+        let v = v as f32;
+        assert_eq!(
+            m.f32_floor(v).unwrap().to_bits(),
+            v.floor().to_bits(),
+            "floor({v})"
+        );
+        assert_eq!(
+            m.f32_ceil(v).unwrap().to_bits(),
+            v.ceil().to_bits(),
+            "ceil({v})"
+        );
+        assert_eq!(
+            m.f32_trunc(v).unwrap().to_bits(),
+            v.trunc().to_bits(),
+            "trunc({v})"
+        );
+        let nearest = v.round_ties_even();
+        assert_eq!(
+            m.f32_nearest(v).unwrap().to_bits(),
+            nearest.to_bits(),
+            "nearest({v})"
+        );
+        if v >= 0.0 {
+            assert_eq!(
+                m.f32_sqrt(v).unwrap().to_bits(),
+                v.sqrt().to_bits(),
+                "sqrt({v})"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_offsets_past_4gib_trap() {
+    let mut m = portable::new().unwrap();
+    assert_eq!(m.roundtrip(16, 7), Ok(7));
+    // 0xFFFF_FFF0 + 32 is past any 32-bit memory, not address 16
+    assert_eq!(m.roundtrip(-16, 7), Err(WasmTrap::OutOfBounds));
+    assert_eq!(m.fill(-1, 1), Err(WasmTrap::OutOfBounds));
+    assert_eq!(m.fill(0, 16), Ok(()));
+}
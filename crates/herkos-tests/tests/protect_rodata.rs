@@ -0,0 +1,32 @@
+//! Write-protected constant data (`--protect-rodata`).
+//!
+//! `protect_rodata.wat` has a `.rodata` segment at 16 and a `.data` segment
+//! at 32. Only the first is read-only after `new()`.
+
+use herkos_runtime::WasmTrap;
+use herkos_tests::protect_rodata;
+
+#[test]
+fn test_rodata_range_is_protected() {
+    let module = protect_rodata::new().unwrap();
+    assert_eq!(module.0.memory.protected_ranges(), &[(16, 21)]);
+}
+
+#[test]
+fn test_stores_into_rodata_trap() {
+    let mut module = protect_rodata::new().unwrap();
+    assert_eq!(module.store8(16, 0), Err(WasmTrap::WriteProtected));
+    assert_eq!(module.store8(20, 0), Err(WasmTrap::WriteProtected));
+    assert_eq!(module.fill(0, 0, 17), Err(WasmTrap::WriteProtected));
+    assert_eq!(module.load8(16), Ok(i32::from(b'c')));
+}
+
+#[test]
+fn test_data_stays_writable() {
+    let mut module = protect_rodata::new().unwrap();
+    module.store8(32, i32::from(b'S')).unwrap();
+    assert_eq!(module.load8(32), Ok(i32::from(b'S')));
+    module.store8(21, 1).unwrap();
+    module.fill(0, 7, 16).unwrap();
+    assert_eq!(module.load8(15), Ok(7));
+}
@@ -0,0 +1,25 @@
+//! End-to-end tests for the instance-free API of pure modules.
+//!
+//! `pure_math.wat` has no state, so its exports are also free functions in
+//! `pure` and methods on the zero-sized `PureModule`.
+
+use herkos_runtime::{WasmResult, WasmTrap};
+use herkos_tests::pure_math::{self, PureModule};
+
+const MIX: WasmResult<i64> = pure_math::pure::mix(5, 2);
+
+#[test]
+fn test_leaf_arithmetic_evaluates_at_compile_time() {
+    assert_eq!(MIX, Ok(7 ^ 16));
+    const VIA_MARKER: WasmResult<i64> = PureModule.mix(5, 2);
+    assert_eq!(VIA_MARKER, MIX);
+}
+
+#[test]
+fn test_free_functions_match_the_instance() {
+    let mut module = pure_math::new().unwrap();
+    assert_eq!(pure_math::pure::square(-7), module.square(-7));
+    assert_eq!(PureModule.square(9), Ok(81));
+    assert_eq!(pure_math::pure::div(9, 0), Err(WasmTrap::DivisionByZero));
+    assert_eq!(core::mem::size_of::<PureModule>(), 0);
+}
@@ -0,0 +1,97 @@
+//! End-to-end tests for record/replay.
+//!
+//! `record_replay.wat` is transpiled with `--record-replay`: a run through
+//! `Recorder` logs every host result, and `Replayer` repeats the run from the
+//! log alone.
+
+use herkos_runtime::{ReplayLog, WasmResult, WasmTrap};
+use herkos_tests::record_replay::{self, ModuleHostTrait, Recorder, Replayer};
+
+/// Host with a pseudo-random generator that fails after `budget` draws.
+struct Dice {
+    state: u32,
+    budget: u32,
+    reports: Vec<i32>,
+}
+
+impl Dice {
+    fn new(state: u32, budget: u32) -> Self {
+        Self {
+            state,
+            budget,
+            reports: Vec::new(),
+        }
+    }
+}
+
+impl ModuleHostTrait for Dice {
+    fn random(&mut self) -> WasmResult<i32> {
+        if self.budget == 0 {
+            return Err(WasmTrap::Interrupted);
+        }
+        self.budget -= 1;
+        self.state = self.state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        Ok((self.state >> 16) as i32)
+    }
+
+    fn report(&mut self, arg0: i32) -> WasmResult<()> {
+        self.reports.push(arg0);
+        Ok(())
+    }
+
+    fn get_bias(&self) -> i32 {
+        1000
+    }
+}
+
+/// Run `roll(n)` against `host` through a recorder.
+fn record(host: &mut Dice, n: i32) -> (WasmResult<i32>, ReplayLog) {
+    let mut module = record_replay::new().unwrap();
+    let mut recorder = Recorder::new(host);
+    let result = module.roll(n, &mut recorder);
+    (result, recorder.into_log())
+}
+
+#[test]
+fn test_replay_reproduces_the_recorded_run() {
+    let mut host = Dice::new(42, u32::MAX);
+    let (result, log) = record(&mut host, 5);
+    assert!(result.is_ok());
+    // 5 draws, 5 reports and the bias read
+    assert_eq!(log.events().len(), 11);
+
+    // A different host would give a different result; the log alone doesn't.
+    let mut other = Dice::new(7, u32::MAX);
+    assert_ne!(record_replay::new().unwrap().roll(5, &mut other), result);
+
+    let mut module = record_replay::new().unwrap();
+    let mut replayer = Replayer::new(log);
+    assert_eq!(module.roll(5, &mut replayer), result);
+    assert!(replayer.into_log().is_finished());
+}
+
+#[test]
+fn test_recorded_trap_replays() {
+    let mut host = Dice::new(1, 3);
+    let (result, log) = record(&mut host, 10);
+    assert_eq!(result, Err(WasmTrap::Interrupted));
+    assert_eq!(host.reports.len(), 3);
+
+    let bytes = log.to_bytes();
+    let log = ReplayLog::from_bytes(&bytes).unwrap();
+    let mut module = record_replay::new().unwrap();
+    let mut replayer = Replayer::new(log);
+    assert_eq!(module.roll(10, &mut replayer), Err(WasmTrap::Interrupted));
+    assert!(replayer.into_log().is_finished());
+}
+
+#[test]
+fn test_divergent_replay_traps() {
+    let mut host = Dice::new(3, u32::MAX);
+    let (_, log) = record(&mut host, 2);
+
+    let mut module = record_replay::new().unwrap();
+    let mut replayer = Replayer::new(log);
+    assert_eq!(module.roll(3, &mut replayer), Err(WasmTrap::ReplayDiverged));
+    assert!(replayer.into_log().is_diverged());
+}
@@ -0,0 +1,21 @@
+//! End-to-end tests for read-only data segments.
+//!
+//! `rodata.wat` reads its data segment only at constant addresses, so with
+//! `HERKOS_OPTIMIZE=1` the reads fold to constants and the segment stays out
+//! of linear memory. Results must not depend on that.
+
+use herkos_tests::rodata;
+
+#[test]
+fn test_constant_reads() {
+    let mut module = rodata::new().unwrap();
+    assert_eq!(module.version(), Ok(42));
+    assert_eq!(module.marker(), Ok(-1));
+}
+
+#[test]
+fn test_writes_elsewhere_are_unaffected() {
+    let mut module = rodata::new().unwrap();
+    assert_eq!(module.scratch(7), Ok(7));
+    assert_eq!(module.version(), Ok(42));
+}
@@ -113,3 +113,39 @@ fn test_cond_inc_nonzero_flag() {
     let mut select_mod = select::new().unwrap();
     assert_eq!(select_mod.func_4(10, 99).unwrap(), 11);
 }
+
+// ── Typed select ──
+
+#[test]
+fn test_typed_select_i32() {
+    let mut select_mod = select::new().unwrap();
+    assert_eq!(select_mod.func_5(1, 2, 1).unwrap(), 1);
+    assert_eq!(select_mod.func_5(1, 2, 0).unwrap(), 2);
+}
+
+#[test]
+fn test_typed_select_i64() {
+    let mut select_mod = select::new().unwrap();
+    assert_eq!(select_mod.func_8(i64::MAX, -1, 7).unwrap(), i64::MAX);
+    assert_eq!(select_mod.func_8(i64::MAX, -1, 0).unwrap(), -1);
+}
+
+// ── Float select ──
+
+#[test]
+fn test_select_f32() {
+    let mut select_mod = select::new().unwrap();
+    assert_eq!(select_mod.func_6(1.5, -2.25, 1).unwrap(), 1.5);
+    assert_eq!(select_mod.func_6(1.5, -2.25, 0).unwrap(), -2.25);
+    let nan = select_mod.func_6(f32::NAN, 0.0, -1).unwrap();
+    assert!(nan.is_nan());
+}
+
+#[test]
+fn test_typed_select_f64_max() {
+    let mut select_mod = select::new().unwrap();
+    assert_eq!(select_mod.func_7(2.5, 1.0).unwrap(), 2.5);
+    assert_eq!(select_mod.func_7(-3.0, 0.5).unwrap(), 0.5);
+    // Comparisons with NaN are false, so the second operand is picked.
+    assert_eq!(select_mod.func_7(f64::NAN, 4.0).unwrap(), 4.0);
+}
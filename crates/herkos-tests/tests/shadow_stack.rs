@@ -0,0 +1,52 @@
+//! End-to-end tests for the shadow stack helpers.
+//!
+//! `shadow_stack.wat` keeps its frames in an imported memory below the LLVM
+//! `__stack_pointer` global, so two instances sharing one memory need
+//! disjoint stack regions.
+
+use herkos_runtime::{IsolatedMemory, WasmTrap};
+use herkos_tests::shadow_stack;
+
+#[test]
+fn test_stack_pointer_tracks_frames() {
+    let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+    let mut module = shadow_stack::new().unwrap();
+    assert_eq!(module.stack_pointer(), shadow_stack::STACK_POINTER_INIT);
+    assert_eq!(module.push(7, &mut memory), Ok(4080));
+    assert_eq!(module.stack_pointer(), 4080);
+    assert_eq!(module.pop(&mut memory), Ok(7));
+    assert_eq!(module.stack_pointer(), 4096);
+}
+
+#[test]
+fn test_instances_sharing_memory_get_disjoint_stacks() {
+    let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+    let mut a = shadow_stack::new_with_stack(4096).unwrap();
+    let mut b = shadow_stack::new_with_stack(8192).unwrap();
+    assert_eq!(a.push(1, &mut memory), Ok(4080));
+    assert_eq!(b.push(2, &mut memory), Ok(8176));
+    assert_eq!(a.pop(&mut memory), Ok(1));
+    assert_eq!(b.pop(&mut memory), Ok(2));
+
+    assert_eq!(
+        shadow_stack::new_with_stack(4100).err(),
+        Some(WasmTrap::OutOfBounds)
+    );
+}
+
+#[test]
+fn test_with_stack_restores_the_stack_pointer() {
+    let mut memory = IsolatedMemory::<1>::try_new(1).unwrap();
+    let mut module = shadow_stack::new().unwrap();
+    module.push(1, &mut memory).unwrap();
+    let inner = module
+        .with_stack(16384, |m| {
+            let frame = m.push(2, &mut memory)?;
+            m.pop(&mut memory)?;
+            Ok::<_, WasmTrap>(frame)
+        })
+        .unwrap();
+    assert_eq!(inner, Ok(16368));
+    assert_eq!(module.stack_pointer(), 4080);
+    assert_eq!(module.pop(&mut memory), Ok(1));
+}
@@ -0,0 +1,51 @@
+//! Instances sharing one host through `SharedWasmModule`.
+//!
+//! `shared_host.wat` forwards `bump` to the `tick` import; the host counts
+//! ticks from every instance.
+
+use herkos_runtime::WasmResult;
+use herkos_tests::shared_host::{self, SharedWasmModule};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Counter {
+    total: i32,
+}
+
+impl shared_host::ModuleHostTrait for Counter {
+    fn tick(&mut self, n: i32) -> WasmResult<i32> {
+        self.total += n;
+        Ok(self.total)
+    }
+}
+
+#[test]
+fn test_instances_share_an_rc_refcell_host() {
+    let host = Rc::new(RefCell::new(Counter::default()));
+    let mut a = SharedWasmModule::new(shared_host::new().unwrap(), Rc::clone(&host));
+    let mut b = SharedWasmModule::new(shared_host::new().unwrap(), Rc::clone(&host));
+
+    assert_eq!(a.bump(2), Ok(2));
+    assert_eq!(b.bump(3), Ok(5));
+    assert_eq!(a.bump(1), Ok(6));
+    assert_eq!(host.borrow().total, 6);
+}
+
+#[test]
+fn test_instances_share_an_arc_mutex_host_across_threads() {
+    let host = Arc::new(Mutex::new(Counter::default()));
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            let host = Arc::clone(&host);
+            scope.spawn(move || {
+                let mut module = SharedWasmModule::new(shared_host::new().unwrap(), host);
+                for _ in 0..10 {
+                    module.bump(1).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(host.lock().unwrap().total, 40);
+}
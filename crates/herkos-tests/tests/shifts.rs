@@ -0,0 +1,52 @@
+//! Shift and rotate counts at and past the operand width.
+//!
+//! Wasm takes shift counts modulo the width: a count of 32 on an `i32` is
+//! 0, 33 is 1, and -1 is 31. Debug builds must not panic on them either.
+
+use herkos_tests::shifts;
+
+#[test]
+fn test_i32_counts_wrap_at_32() {
+    let mut m = shifts::new().unwrap();
+    assert_eq!(m.i32_shl(1, 31), Ok(i32::MIN));
+    assert_eq!(m.i32_shl(1, 32), Ok(1));
+    assert_eq!(m.i32_shl(1, 33), Ok(2));
+    assert_eq!(m.i32_shl(1, -1), Ok(i32::MIN));
+    assert_eq!(m.i32_shl(1, i32::MIN), Ok(1));
+    assert_eq!(m.i32_shr_s(i32::MIN, 31), Ok(-1));
+    assert_eq!(m.i32_shr_s(i32::MIN, 32), Ok(i32::MIN));
+    assert_eq!(m.i32_shr_s(-8, -1), Ok(-1));
+    assert_eq!(m.i32_shr_u(i32::MIN, 31), Ok(1));
+    assert_eq!(m.i32_shr_u(-1, 32), Ok(-1));
+    assert_eq!(m.i32_shr_u(i32::MIN, -1), Ok(1));
+    assert_eq!(m.i32_rotl(1, 32), Ok(1));
+    assert_eq!(m.i32_rotl(1, -1), Ok(i32::MIN));
+    assert_eq!(m.i32_rotr(1, 33), Ok(i32::MIN));
+    assert_eq!(m.i32_rotr(i32::MIN, 63), Ok(1));
+}
+
+#[test]
+fn test_i64_counts_wrap_at_64() {
+    let mut m = shifts::new().unwrap();
+    assert_eq!(m.i64_shl(1, 63), Ok(i64::MIN));
+    assert_eq!(m.i64_shl(1, 64), Ok(1));
+    assert_eq!(m.i64_shl(1, -1), Ok(i64::MIN));
+    assert_eq!(m.i64_shl(1, i64::MIN), Ok(1));
+    // Only the low 6 bits count, not the low 32
+    assert_eq!(m.i64_shl(1, 1 << 32), Ok(1));
+    assert_eq!(m.i64_shr_s(i64::MIN, 63), Ok(-1));
+    assert_eq!(m.i64_shr_s(i64::MIN, 64), Ok(i64::MIN));
+    assert_eq!(m.i64_shr_u(i64::MIN, 63), Ok(1));
+    assert_eq!(m.i64_shr_u(-1, 64), Ok(-1));
+    assert_eq!(m.i64_shr_u(i64::MIN, -1), Ok(1));
+    assert_eq!(m.i64_rotl(1, 65), Ok(2));
+    assert_eq!(m.i64_rotl(1, -1), Ok(i64::MIN));
+    assert_eq!(m.i64_rotr(1, -63), Ok(i64::MIN));
+}
+
+#[test]
+fn test_constant_counts_fold_the_same() {
+    let mut m = shifts::new().unwrap();
+    // 2 + -1 + 0 (the low half of i64::MIN)
+    assert_eq!(m.const_counts(), Ok(1));
+}
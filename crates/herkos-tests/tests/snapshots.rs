@@ -0,0 +1,188 @@
+//! Snapshot tests for generated code.
+//!
+//! Each case transpiles a WAT fixture from `data/wat/` and compares the
+//! generated Rust with `tests/snapshots/<case>.snap`, so codegen changes show
+//! up as reviewable diffs next to the behavioral tests.
+//!
+//! After an intended codegen change, regenerate the snapshots and review the
+//! diff before committing:
+//!
+//! ```text
+//! HERKOS_UPDATE_SNAPSHOTS=1 cargo test -p herkos-tests --test snapshots
+//! ```
+
+use herkos_core::{transpile, TranspileOptions};
+use std::path::{Path, PathBuf};
+
+/// Environment variable that rewrites mismatching or missing snapshots.
+const UPDATE_VAR: &str = "HERKOS_UPDATE_SNAPSHOTS";
+
+/// Lines of context shown around each change of a mismatch report.
+const CONTEXT: usize = 3;
+
+fn crate_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Transpile `data/wat/<fixture>.wat` and check it against the `case`
+/// snapshot.
+fn check(case: &str, fixture: &str, options: TranspileOptions) {
+    let wat_path = crate_dir().join("data/wat").join(format!("{fixture}.wat"));
+    let wasm = wat::parse_file(&wat_path)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {e}", wat_path.display()));
+    let actual = transpile(&wasm, &options)
+        .unwrap_or_else(|e| panic!("failed to transpile {fixture}: {e:#}"));
+
+    let snap_path = snapshot_path(case);
+    let update = std::env::var_os(UPDATE_VAR).is_some();
+    // Checkouts with `core.autocrlf` may have rewritten line endings.
+    match std::fs::read_to_string(&snap_path).map(|s| s.replace("\r\n", "\n")) {
+        Ok(expected) if expected == actual => {}
+        Ok(_) | Err(_) if update => {
+            std::fs::write(&snap_path, &actual)
+                .unwrap_or_else(|e| panic!("failed to write {}: {e}", snap_path.display()));
+        }
+        Ok(expected) => panic!(
+            "generated code for `{case}` differs from {}:\n{}\nrerun with {UPDATE_VAR}=1 to accept the change",
+            snap_path.display(),
+            diff(&expected, &actual)
+        ),
+        Err(_) => panic!(
+            "missing snapshot {}; rerun with {UPDATE_VAR}=1 to create it",
+            snap_path.display()
+        ),
+    }
+}
+
+fn snapshot_path(case: &str) -> PathBuf {
+    crate_dir()
+        .join("tests/snapshots")
+        .join(format!("{case}.snap"))
+}
+
+/// Line diff of `expected` → `actual`, with `-`/`+` markers and a few lines
+/// of context around each change.
+fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, from the end of both inputs.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Edit script: (marker, line number in the old text, line).
+    let mut edits: Vec<(char, usize, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push((' ', i, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push(('-', i, old[i]));
+            i += 1;
+        } else {
+            edits.push(('+', i, new[j]));
+            j += 1;
+        }
+    }
+
+    // Print each run of changes with its context; runs whose context
+    // overlaps share one hunk.
+    let mut out = String::new();
+    let mut shown_until = None;
+    for k in (0..edits.len()).filter(|&k| edits[k].0 != ' ') {
+        let start = k.saturating_sub(CONTEXT);
+        let start = match shown_until {
+            Some(until) if start <= until => until,
+            _ => {
+                out.push_str(&format!("@@ line {} @@\n", edits[start].1 + 1));
+                start
+            }
+        };
+        let end = (k + CONTEXT + 1).min(edits.len());
+        for (marker, _, line) in edits.get(start..end).unwrap_or_default() {
+            out.push_str(&format!("{marker} {line}\n"));
+        }
+        shown_until = Some(end.max(start));
+    }
+    out
+}
+
+fn optimized() -> TranspileOptions {
+    TranspileOptions {
+        optimize: true,
+        ..TranspileOptions::default()
+    }
+}
+
+#[test]
+fn snapshot_add() {
+    check("add", "add", TranspileOptions::default());
+}
+
+#[test]
+fn snapshot_fibonacci() {
+    check("fibonacci", "fibonacci", TranspileOptions::default());
+}
+
+#[test]
+fn snapshot_fibonacci_optimized() {
+    check("fibonacci-O", "fibonacci", optimized());
+}
+
+#[test]
+fn snapshot_block_results() {
+    check(
+        "block_results",
+        "block_results",
+        TranspileOptions::default(),
+    );
+}
+
+#[test]
+fn snapshot_block_results_optimized() {
+    check("block_results-O", "block_results", optimized());
+}
+
+#[test]
+fn snapshot_rodata_optimized() {
+    check("rodata-O", "rodata", optimized());
+}
+
+#[test]
+fn snapshot_memory_store() {
+    check("memory_store", "memory_store", TranspileOptions::default());
+}
+
+#[test]
+fn snapshot_import_memory() {
+    check(
+        "import_memory",
+        "import_memory",
+        TranspileOptions::default(),
+    );
+}
+
+#[test]
+fn snapshot_table_imports() {
+    check(
+        "table_imports",
+        "table_imports",
+        TranspileOptions::default(),
+    );
+}
+
+#[test]
+fn diff_marks_changed_lines_with_context() {
+    let report = diff("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n");
+    assert_eq!(report, "@@ line 1 @@\n  a\n- b\n+ B\n  c\n  d\n+ e\n");
+}
@@ -0,0 +1,22 @@
+//! End-to-end tests for start functions evaluated at transpile time.
+//!
+//! `start_eval.wat` fills its memory and sets a global from its start
+//! function; the generated constructor must start from that state.
+
+use herkos_tests::start_eval;
+
+#[test]
+fn test_memory_is_initialized() {
+    let mut module = start_eval::new().unwrap();
+    assert_eq!(module.square(0), Ok(0));
+    assert_eq!(module.square(9), Ok(81));
+    assert_eq!(module.square(31), Ok(961));
+    assert_eq!(module.second_page(), Ok(7));
+}
+
+#[test]
+fn test_globals_and_growth_are_kept() {
+    let mut module = start_eval::new().unwrap();
+    assert_eq!(module.entries(), Ok(32));
+    assert_eq!(module.pages(), Ok(2));
+}
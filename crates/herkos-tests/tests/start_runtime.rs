@@ -0,0 +1,34 @@
+//! End-to-end tests for start functions called by the constructor.
+//!
+//! `start_host.wat` calls the host from its start function and
+//! `start_trap.wat` traps in it: neither can be evaluated at transpile time,
+//! so `new()` runs them.
+
+use herkos_runtime::{WasmResult, WasmTrap};
+use herkos_tests::{start_host, start_trap};
+
+struct SeedHost {
+    seeds: u32,
+}
+
+impl start_host::ModuleHostTrait for SeedHost {
+    fn seed(&mut self) -> WasmResult<i32> {
+        self.seeds += 1;
+        Ok(37)
+    }
+}
+
+#[test]
+fn test_start_calls_the_host_once() {
+    let mut host = SeedHost { seeds: 0 };
+    let mut module = start_host::new(&mut host).unwrap();
+    assert_eq!(host.seeds, 1);
+    assert_eq!(module.seeded(&mut host), Ok(42));
+    assert_eq!(module.calls(&mut host), Ok(1));
+    assert_eq!(host.seeds, 1);
+}
+
+#[test]
+fn test_trapping_start_fails_instantiation() {
+    assert!(matches!(start_trap::new(), Err(WasmTrap::OutOfBounds)));
+}
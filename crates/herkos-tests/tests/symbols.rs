@@ -0,0 +1,40 @@
+//! End-to-end tests for the generated symbol table.
+//!
+//! Every transpiled module exposes `SYMBOLS` and `symbolicate(func_index)` so
+//! that a host can turn `func_N` into a meaningful frame in error reports.
+
+use herkos_tests::symbols;
+
+#[test]
+fn test_symbolicate_named_exported_function() {
+    let sym = symbols::symbolicate(0).expect("func_0 has a symbol");
+    assert_eq!(sym.func_index, 0);
+    assert_eq!(sym.wasm_index, 1, "one imported function precedes it");
+    assert_eq!(sym.name, Some("double"));
+    assert_eq!(sym.exports, &["double", "twice"]);
+    assert!(sym.wasm_start < sym.wasm_end);
+}
+
+#[test]
+fn test_symbolicate_internal_function() {
+    let sym = symbols::symbolicate(1).expect("func_1 has a symbol");
+    assert_eq!(sym.name, Some("halve"));
+    assert!(sym.exports.is_empty());
+    assert_eq!(sym.to_string().split(' ').next(), Some("func_1"));
+}
+
+#[test]
+fn test_symbolicate_out_of_range() {
+    assert_eq!(symbols::SYMBOLS.len(), 2);
+    assert!(symbols::symbolicate(2).is_none());
+}
+
+#[test]
+fn test_symbols_cover_disjoint_offsets() {
+    let [a, b] = symbols::SYMBOLS else {
+        panic!("expected two symbols");
+    };
+    assert!(a.wasm_end <= b.wasm_start);
+    assert!(a.contains_offset(a.wasm_start));
+    assert!(!a.contains_offset(b.wasm_start));
+}
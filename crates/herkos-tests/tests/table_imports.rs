@@ -0,0 +1,68 @@
+//! Tables holding imported functions.
+//!
+//! `table_imports.wat` places `env.double`, `env.record` and `env.negate` in
+//! its table alongside a local function; `call_indirect` on those slots
+//! dispatches to the host.
+
+use herkos_runtime::{WasmResult, WasmTrap};
+use herkos_tests::table_imports;
+
+#[derive(Default)]
+struct Host {
+    recorded: Vec<i32>,
+}
+
+impl table_imports::ModuleHostTrait for Host {
+    fn double(&mut self, value: i32) -> WasmResult<i32> {
+        Ok(value * 2)
+    }
+    fn record(&mut self, value: i32) -> WasmResult<()> {
+        self.recorded.push(value);
+        Ok(())
+    }
+    fn negate(&mut self, value: i32) -> WasmResult<i32> {
+        Ok(-value)
+    }
+}
+
+#[test]
+fn test_import_and_local_slots_dispatch() {
+    let mut host = Host::default();
+    let mut module = table_imports::new().unwrap();
+    assert_eq!(module.apply(0, 21, &mut host), Ok(42));
+    assert_eq!(module.apply(1, 21, &mut host), Ok(22));
+    assert_eq!(module.apply(3, 21, &mut host), Ok(-21));
+}
+
+#[test]
+fn test_void_import_slot() {
+    let mut host = Host::default();
+    let mut module = table_imports::new().unwrap();
+    module.emit(2, 7, &mut host).unwrap();
+    assert_eq!(host.recorded, vec![7]);
+}
+
+#[test]
+fn test_import_slot_type_is_checked() {
+    let mut host = Host::default();
+    let mut module = table_imports::new().unwrap();
+    assert_eq!(
+        module.apply(2, 1, &mut host),
+        Err(WasmTrap::IndirectCallTypeMismatch)
+    );
+    assert_eq!(
+        module.emit(0, 1, &mut host),
+        Err(WasmTrap::IndirectCallTypeMismatch)
+    );
+    assert!(host.recorded.is_empty());
+}
+
+#[test]
+fn test_empty_slot_traps() {
+    let mut host = Host::default();
+    let mut module = table_imports::new().unwrap();
+    assert_eq!(
+        module.apply(4, 1, &mut host),
+        Err(WasmTrap::UndefinedElement)
+    );
+}
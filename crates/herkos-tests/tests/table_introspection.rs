@@ -0,0 +1,48 @@
+//! End-to-end tests for enumerating an exported table from the host.
+//!
+//! `table_introspection.wat` fills its exported `callbacks` table with a
+//! named function, an unnamed export and a host import.
+
+use herkos_runtime::{FuncDescriptor, FuncKind, ValueType};
+use herkos_tests::table_introspection;
+
+fn describe(entry: Option<FuncDescriptor>) -> Option<(FuncKind, Option<&'static str>)> {
+    entry.map(|d| (d.func.kind, d.name))
+}
+
+#[test]
+fn test_entries_are_resolved() {
+    let module = table_introspection::new().unwrap();
+    assert_eq!(module.table_len(), 4);
+
+    let click = module.table_entry(0).unwrap();
+    assert_eq!(click.name, Some("on_click"));
+    assert_eq!(click.ty.params, [ValueType::I32, ValueType::I64]);
+    assert_eq!(click.ty.results, [ValueType::I32]);
+
+    assert_eq!(module.table_entry(1), None);
+    assert_eq!(
+        describe(module.table_entry(2)),
+        Some((FuncKind::Local, Some("on_key")))
+    );
+    let notify = module.table_entry(3).unwrap();
+    assert_eq!(notify.func.kind, FuncKind::Import);
+    assert_eq!(notify.name, Some("notify"));
+    assert_eq!(notify.ty.params, [ValueType::I32]);
+    assert!(notify.ty.results.is_empty());
+
+    // Past the end
+    assert_eq!(module.table_entry(4), None);
+}
+
+#[test]
+fn test_entries_follow_table_writes() {
+    let mut module = table_introspection::new().unwrap();
+    let table = module.callbacks_mut();
+    let click = table.get(0).unwrap();
+    table.set(1, Some(click)).unwrap();
+    assert_eq!(
+        describe(module.table_entry(1)),
+        Some((FuncKind::Local, Some("on_click")))
+    );
+}
@@ -0,0 +1,42 @@
+//! End-to-end tests for TinyGo guest support.
+//!
+//! `tinygo_guest.wat` mimics the imports and scheduler exports of a TinyGo
+//! `-target=wasm` build. The host only provides the scheduler clock; every
+//! runtime import falls back to its generated default.
+
+use herkos_runtime::{TinyGoClock, WasmTrap};
+use herkos_tests::tinygo_guest::{self, ModuleHostTrait};
+
+#[derive(Default)]
+struct Host {
+    clock: TinyGoClock,
+}
+
+impl ModuleHostTrait for Host {
+    fn tinygo_clock(&mut self) -> &mut TinyGoClock {
+        &mut self.clock
+    }
+}
+
+#[test]
+fn test_run_fires_timers_until_the_guest_stops_sleeping() {
+    let mut module = tinygo_guest::new().unwrap();
+    let mut host = Host::default();
+    module.tinygo_run(&mut host).unwrap();
+    assert_eq!(module.wakeups(&mut host).unwrap(), 3);
+    assert_eq!(module.last_tick(&mut host).unwrap(), 15.0);
+    assert_eq!(host.clock.ticks(), 15.0);
+}
+
+#[test]
+fn test_resume_and_import_defaults() {
+    let mut module = tinygo_guest::new().unwrap();
+    let mut host = Host::default();
+    module.tinygo_resume(&mut host).unwrap();
+    assert_eq!(module.wakeups(&mut host).unwrap(), 0);
+
+    module.tinygo_run(&mut host).unwrap();
+    // WASI calls report ENOSYS; JavaScript interop traps.
+    assert_eq!(module.write_errno(&mut host).unwrap(), 52);
+    assert_eq!(module.js_get(&mut host), Err(WasmTrap::Unreachable));
+}
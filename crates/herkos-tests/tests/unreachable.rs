@@ -62,3 +62,19 @@ fn test_dead_unreachable() {
     assert_eq!(unreachable_mod.func_3(0).unwrap(), 0);
     assert_eq!(unreachable_mod.func_3(-7).unwrap(), -7);
 }
+
+// ── Dead code with a polymorphic stack ──
+
+#[test]
+fn test_dead_code_after_return_is_skipped() {
+    let mut unreachable_mod = unreachable::new().unwrap();
+    assert_eq!(unreachable_mod.func_4(0).unwrap(), -1);
+    assert_eq!(unreachable_mod.func_4(8).unwrap(), 8);
+}
+
+#[test]
+fn test_dead_code_after_unreachable_is_skipped() {
+    let mut unreachable_mod = unreachable::new().unwrap();
+    assert_eq!(unreachable_mod.func_5(0).unwrap(), 1);
+    assert!(unreachable_mod.func_5(3).is_err());
+}
@@ -0,0 +1,79 @@
+//! End-to-end tests for the wasmtime-style adapter.
+//!
+//! `wasmtime_adapter.wat` is transpiled with the adapter enabled (see
+//! `build.rs`); these tests drive it the way wasmtime host code would.
+
+use herkos_runtime::wasmtime_compat::{ExportError, Instance, Store};
+use herkos_runtime::WasmResult;
+use herkos_tests::wasmtime_adapter;
+
+#[derive(Default)]
+struct Logger {
+    logged: Vec<i32>,
+}
+
+impl wasmtime_adapter::ModuleHostTrait for Logger {
+    fn log(&mut self, value: i32) -> WasmResult<()> {
+        self.logged.push(value);
+        Ok(())
+    }
+}
+
+fn store() -> Store<wasmtime_adapter::WasmModule, Logger> {
+    Store::new(wasmtime_adapter::new().unwrap(), Logger::default())
+}
+
+#[test]
+fn test_typed_funcs_follow_wasmtime_conventions() {
+    let mut store = store();
+    let instance = Instance::new(&mut store);
+
+    let add = instance
+        .get_typed_func::<(i32, i32), i32>(&mut store, "add")
+        .unwrap();
+    assert_eq!(add.call(&mut store, (40, 2)), Ok(42));
+
+    let negate = instance
+        .get_typed_func::<i64, i64>(&mut store, "negate")
+        .unwrap();
+    assert_eq!(negate.call(&mut store, 7), Ok(-7));
+
+    let answer = instance
+        .get_typed_func::<(), i32>(&mut store, "answer")
+        .unwrap();
+    assert_eq!(answer.call(&mut store, ()), Ok(42));
+}
+
+#[test]
+fn test_lookup_errors() {
+    let mut store = store();
+    let instance = Instance::new(&mut store);
+    assert_eq!(
+        instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "add")
+            .err(),
+        Some(ExportError::TypeMismatch)
+    );
+    assert_eq!(
+        instance
+            .get_typed_func::<(), ()>(&mut store, "missing")
+            .err(),
+        Some(ExportError::NotFound)
+    );
+    assert!(instance.get_memory(&mut store, "mem").is_none());
+}
+
+#[test]
+fn test_memory_and_host_data() {
+    let mut store = store();
+    let instance = Instance::new(&mut store);
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.data_size(&store), 65536);
+    memory.data_mut(&mut store)[..4].copy_from_slice(&[1, 2, 3, 4]);
+
+    let sum_bytes = instance
+        .get_typed_func::<(), ()>(&mut store, "sum_bytes")
+        .unwrap();
+    sum_bytes.call(&mut store, ()).unwrap();
+    assert_eq!(store.data().logged, vec![10]);
+}
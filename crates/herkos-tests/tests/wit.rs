@@ -0,0 +1,146 @@
+//! End-to-end tests for WIT-driven canonical-ABI wrappers.
+//!
+//! `data/wat/wit_guest.wit` describes the exports and imports of the
+//! hand-written canonical-ABI guest `wit_guest.wat`; these tests call the
+//! generated `call_*` wrappers and implement the generated `WitImports`.
+
+use herkos_runtime::{WasmResult, WasmTrap};
+use herkos_tests::wit_guest::{self, Level, Point, Shape, WitImports};
+
+#[derive(Default)]
+struct Host {
+    logs: Vec<(Level, u32)>,
+    total: u64,
+}
+
+impl WitImports for Host {
+    fn log(&mut self, level: Level, code: u32) -> WasmResult<()> {
+        self.logs.push((level, code));
+        Ok(())
+    }
+
+    fn counter_bump(&mut self, by: u32) -> WasmResult<u64> {
+        self.total += u64::from(by);
+        Ok(self.total)
+    }
+}
+
+#[test]
+fn test_string_params_and_results() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.call_greet_len("héllo", &mut host).unwrap(), 6);
+    assert_eq!(module.call_echo("herkos ✓", &mut host).unwrap(), "herkos ✓");
+    assert_eq!(module.call_echo("", &mut host).unwrap(), "");
+}
+
+#[test]
+fn test_post_return_runs_after_lifting() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    module.call_echo("a", &mut host).unwrap();
+    module.call_echo("b", &mut host).unwrap();
+    assert_eq!(module.posted(&mut host).unwrap(), 2);
+}
+
+#[test]
+fn test_list_params() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(
+        module
+            .call_sum(&[1, -2, i32::MAX, i32::MAX], &mut host)
+            .unwrap(),
+        2 * i64::from(i32::MAX) - 1
+    );
+    assert_eq!(module.call_sum(&[], &mut host).unwrap(), 0);
+}
+
+#[test]
+fn test_records() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(
+        module.call_make_point(3, -4, &mut host).unwrap(),
+        Point { x: 3, y: -4 }
+    );
+    let points = [
+        Point { x: 0, y: 0 },
+        Point { x: 4, y: 8 },
+        Point { x: 2, y: 4 },
+    ];
+    assert_eq!(
+        module.call_centroid(&points, &mut host).unwrap(),
+        Some(Point { x: 2, y: 4 })
+    );
+    assert_eq!(module.call_centroid(&[], &mut host).unwrap(), None);
+}
+
+#[test]
+fn test_options_and_enums() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.call_classify(0, &mut host).unwrap(), None);
+    assert_eq!(
+        module.call_classify(-5, &mut host).unwrap(),
+        Some(Level::Low)
+    );
+    assert_eq!(
+        module.call_classify(5, &mut host).unwrap(),
+        Some(Level::High)
+    );
+}
+
+#[test]
+fn test_variant_params_share_joined_slots() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(
+        module.call_area(Shape::Circle(2.0), &mut host).unwrap(),
+        12.0
+    );
+    let rect = Shape::Rect(Point { x: 3, y: 5 });
+    assert_eq!(module.call_area(rect, &mut host).unwrap(), 15.0);
+    assert_eq!(module.call_area(Shape::Empty, &mut host).unwrap(), 0.0);
+}
+
+#[test]
+fn test_results() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(module.call_divide(17, 5, &mut host).unwrap(), Ok(3));
+    assert_eq!(
+        module.call_divide(1, 0, &mut host).unwrap(),
+        Err("division by zero".to_string())
+    );
+}
+
+#[test]
+fn test_char_and_bool() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert!(module.call_is_upper('Q', &mut host).unwrap());
+    assert!(!module.call_is_upper('q', &mut host).unwrap());
+    assert!(!module.call_is_upper('Ω', &mut host).unwrap());
+}
+
+#[test]
+fn test_wit_imports() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    module.call_notify(Level::High, 42, &mut host).unwrap();
+    module.call_notify(Level::Low, 7, &mut host).unwrap();
+    assert_eq!(host.logs, vec![(Level::High, 42), (Level::Low, 7)]);
+    assert_eq!(module.call_count(5, &mut host).unwrap(), 5);
+    assert_eq!(module.call_count(3, &mut host).unwrap(), 8);
+}
+
+#[test]
+fn test_invalid_discriminant_traps() {
+    let mut module = wit_guest::new().unwrap();
+    let mut host = Host::default();
+    assert_eq!(
+        module.call_bad_level(&mut host),
+        Err(WasmTrap::InvalidValue)
+    );
+}
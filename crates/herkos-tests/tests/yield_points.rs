@@ -0,0 +1,55 @@
+//! End-to-end tests for yield points.
+//!
+//! `yield_points.wat` is transpiled with `--yield-points`, so its loop calls
+//! `ModuleHostTrait::maybe_yield` once per iteration.
+
+use herkos_runtime::{NoHost, WasmResult, WasmTrap};
+use herkos_tests::yield_points;
+
+/// Host that counts yields and interrupts the call once its budget is spent.
+struct Budget {
+    yields: u32,
+    limit: u32,
+}
+
+impl yield_points::ModuleHostTrait for Budget {
+    fn maybe_yield(&mut self) -> WasmResult<()> {
+        self.yields += 1;
+        if self.yields > self.limit {
+            return Err(WasmTrap::Interrupted);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_loop_yields_once_per_iteration() {
+    let mut module = yield_points::new().unwrap();
+    let mut host = Budget {
+        yields: 0,
+        limit: u32::MAX,
+    };
+    assert_eq!(module.sum_to(100, &mut host), Ok(5050));
+    // One yield per iteration, plus the final check that exits the loop.
+    assert_eq!(host.yields, 101);
+}
+
+#[test]
+fn test_host_interrupts_a_long_call() {
+    let mut module = yield_points::new().unwrap();
+    let mut host = Budget {
+        yields: 0,
+        limit: 10,
+    };
+    assert_eq!(
+        module.sum_to(1_000_000, &mut host),
+        Err(WasmTrap::Interrupted)
+    );
+    assert_eq!(host.yields, 11);
+}
+
+#[test]
+fn test_no_host_never_yields() {
+    let mut module = yield_points::new().unwrap();
+    assert_eq!(module.sum_to(10, &mut NoHost), Ok(55));
+}
@@ -1,58 +1,776 @@
-use anyhow::{Context, Result};
-use clap::Parser;
-use herkos_core::{transpile, TranspileOptions};
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use herkos_core::analysis::{CallGraph, StackConfig, WcetConfig};
+use herkos_core::bindings::BindingConfig;
+use herkos_core::capabilities::CapabilityConfig;
+use herkos_core::codegen::api;
+use herkos_core::contracts::ContractConfig;
+use herkos_core::features::FeatureConfig;
+use herkos_core::ir::build_module_info;
+use herkos_core::optimizer::DEFAULT_INLINE_THRESHOLD;
+use herkos_core::parser::{parse_wasm_with_features, supported_features, unsupported_constructs};
+use herkos_core::wit::WitWorld;
+use herkos_core::{transpile, transpile_to_artifacts, PreTransform, TranspileOptions};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
 /// herkos — WebAssembly to Rust transpiler with compile-time isolation guarantees.
 #[derive(Parser, Debug)]
-#[command(version, about)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// Input WebAssembly binary (.wasm)
-    input: PathBuf,
+    #[arg(required = true)]
+    input: Option<PathBuf>,
 
-    /// Output Rust source file
+    /// Output source file (Rust, or C with `--mode c`). Written through a
+    /// temporary file, so it is never left partially written
     #[arg(long, short)]
     output: Option<PathBuf>,
 
+    /// Overwrite the output and `--emit` files if they exist
+    #[arg(long, short)]
+    force: bool,
+
+    /// Code generation mode: `safe` emits Rust, `c` emits portable C99 with
+    /// explicit bounds checks (experimental)
+    #[arg(long, default_value = "safe", value_parser = ["safe", "c"])]
+    mode: String,
+
     /// Enable IR optimizations
     #[arg(long, short = 'O')]
     optimize: bool,
+
+    /// Inline functions of at most N IR instructions into their callers
+    /// when optimizing (0 disables inlining)
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_INLINE_THRESHOLD)]
+    inline_threshold: usize,
+
+    /// Reject modules with nondeterministic behavior (float NaN bits,
+    /// memory.grow failure, imported clocks/random)
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Bindings file describing pointer/length export parameters; generates
+    /// additional wrapper methods taking `&[u8]`, `&str` or structs, and
+    /// bounds checks for the pointer arguments of host imports
+    #[arg(long, value_name = "FILE")]
+    bindings: Option<PathBuf>,
+
+    /// WIT world the guest was built for (e.g. with wit-bindgen); generates
+    /// `call_*` export wrappers and a `WitImports` trait over WIT types
+    #[arg(long, value_name = "FILE")]
+    wit: Option<PathBuf>,
+
+    /// Generate a wasmtime-style adapter (`Instance::get_typed_func`,
+    /// `Memory::data_mut`) over the transpiled module
+    #[arg(long)]
+    wasmtime_adapter: bool,
+
+    /// Call the host's `maybe_yield` at every loop header, so long-running
+    /// calls can cooperate with a scheduler or be interrupted
+    #[arg(long)]
+    yield_points: bool,
+
+    /// Features file assigning exports to Cargo features; generated exports
+    /// and the functions they reach are wrapped in `#[cfg(feature = ...)]`
+    #[arg(long, value_name = "FILE")]
+    export_features: Option<PathBuf>,
+
+    /// Gate every export not listed in the features file on the Cargo
+    /// feature `<PREFIX><export name>`
+    #[arg(long, value_name = "PREFIX")]
+    export_feature_prefix: Option<String>,
+
+    /// Contracts file with preconditions on export arguments (`require
+    /// <export> <param> range MIN..=MAX` or `nonnull`); exports document
+    /// them and check them with `debug_assert!`
+    #[arg(long, value_name = "FILE")]
+    contracts: Option<PathBuf>,
+
+    /// Capabilities file regrouping function imports into one host trait
+    /// per capability (`capability FileSystem env.fd_*`); `ModuleHostTrait`
+    /// gets those traits as supertraits
+    #[arg(long, value_name = "FILE")]
+    capabilities: Option<PathBuf>,
+
+    /// Route logging imports (`log(ptr, len)`, `console.log`, WASI
+    /// `fd_write` to stdout/stderr) to `herkos_runtime::GuestLog`, so the
+    /// host receives `&str` messages
+    #[arg(long)]
+    guest_log: bool,
+
+    /// Lower atomic operations to plain loads and stores (fences become
+    /// no-ops). Only sound if the module runs on a single thread
+    #[arg(long)]
+    lower_atomics: bool,
+
+    /// Run the C/C++ static constructors (`__wasm_call_ctors`) at transpile
+    /// time, so `new()` returns an initialized module. Without it, call the
+    /// generated `initialize()` after `new()`
+    #[arg(long)]
+    call_ctors: bool,
+
+    /// Trap on signed overflow in integer add, sub and mul instead of
+    /// wrapping, to find unintended wraparound in ported C code. Not
+    /// spec-conformant: for testing only
+    #[arg(long)]
+    checked_arithmetic: bool,
+
+    /// Report every guest memory access to the host's `MemoryObserver`, for
+    /// data watchpoints while debugging (needs the runtime's `observe` feature)
+    #[arg(long)]
+    observe_memory: bool,
+
+    /// Also emit `Recorder` / `Replayer` hosts that log every host result
+    /// and play the log back, to reproduce a run exactly
+    #[arg(long)]
+    record_replay: bool,
+
+    /// Emit an `invoke(name, args)` method that calls exports by name with
+    /// `herkos_runtime::Value` arguments
+    #[arg(long)]
+    dynamic_invoke: bool,
+
+    /// Also expose exports whose name starts with PREFIX through a
+    /// namespaced view with the prefix stripped (`--export-group image_`
+    /// gives `module.image().resize(..)`); repeatable
+    #[arg(long, value_name = "PREFIX")]
+    export_group: Vec<String>,
+
+    /// Emit a `SharedWasmModule` wrapper whose methods borrow a host shared
+    /// with other instances (`Rc<RefCell<H>>`, `Arc<Mutex<H>>`) for each call
+    #[arg(long)]
+    shared_host: bool,
+
+    /// Generate the export NAME, and every function it reaches, in
+    /// constant-time form: left unoptimized, `select` without branches, and
+    /// the data-dependent branches left reported on stderr; repeatable
+    #[arg(long, value_name = "NAME")]
+    constant_time: Vec<String>,
+
+    /// Leave the function FUNC (export name, name section name or the `N`
+    /// of `func_N`) as translated when optimizing, to work around a
+    /// suspected miscompile; repeatable
+    #[arg(long, value_name = "FUNC")]
+    no_optimize: Vec<String>,
+
+    /// Emit the glue for wiring transpiled modules together at runtime:
+    /// `LinkedWasmModule` lets this module serve others' imports, and its
+    /// own imports can be served by a `herkos_runtime::Linker`
+    #[arg(long)]
+    link: bool,
+
+    /// Track the guest allocator (`malloc`, `free`, `calloc`, `realloc`
+    /// exports): `heap_stats()` reports live and peak bytes, and blocks
+    /// still allocated are reported when the instance is dropped
+    #[arg(long)]
+    heap_profile: bool,
+
+    /// Emit compact output meant only for rustc: no comments, indentation or
+    /// line breaks inside items, for very large generated files
+    #[arg(long)]
+    compact: bool,
+
+    /// Emit `DATA_SEGMENTS` and `arm_debug_memory()`, which poisons memory
+    /// outside the data segments and places canaries after them, and report
+    /// loads and stores to the host as with --observe-memory
+    #[arg(long)]
+    debug_memory: bool,
+
+    /// Make the `.rodata` data segments read-only once the module is
+    /// initialized, so that stores into constant data trap with
+    /// `WriteProtected`
+    #[arg(long)]
+    protect_rodata: bool,
+
+    /// Count the executions of every block, and emit `coverage_report()`
+    /// writing them as an LCOV tracefile keyed by function and Wasm byte
+    /// offset
+    #[arg(long)]
+    coverage: bool,
+
+    /// Count the bounds checks of every memory access, and emit
+    /// `bounds_report()` listing the hottest access sites by function, Wasm
+    /// byte offset and operator
+    #[arg(long)]
+    bounds_profile: bool,
+
+    /// Implement `herkos_runtime::HotPlugin` and emit `HOT_VTABLE`, the
+    /// C-ABI entry points a `cdylib` exports so that a running host can swap
+    /// in a rebuilt module while keeping its memory and globals
+    #[arg(long)]
+    hot_reload: bool,
+
+    /// Emit only code that compiles for every target, `no_std` and 32-bit
+    /// included: no `std` float methods, no `usize`-width address math
+    #[arg(long)]
+    portable: bool,
+
+    /// Run `cargo check` on the output for each target in the
+    /// comma-separated list (by default wasm32-unknown-unknown,
+    /// thumbv7em-none-eabihf and x86_64-unknown-linux-gnu); implies
+    /// --portable
+    #[arg(
+        long,
+        value_name = "TARGETS",
+        value_delimiter = ',',
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = AUDIT_TARGETS
+    )]
+    target_audit: Option<Vec<String>>,
+
+    /// herkos-runtime checkout for --target-audit to build against, instead
+    /// of the crates.io release of this version
+    #[arg(long, value_name = "DIR", requires = "target_audit")]
+    audit_runtime: Option<PathBuf>,
+
+    /// Shell command the Wasm binary is piped through before it is parsed,
+    /// such as `wasm-opt -Oz -o - -`; recorded in the generated code
+    #[arg(long, value_name = "CMD")]
+    pre_cmd: Option<String>,
+
+    /// Directory caching the output of --pre-cmd across runs
+    #[arg(long, value_name = "DIR", requires = "pre_cmd")]
+    pre_cache: Option<PathBuf>,
+
+    /// Shell command the generated source is piped through before it is
+    /// written, to add headers, attributes or lint configuration
+    #[arg(long, value_name = "CMD")]
+    post_cmd: Option<String>,
+
+    /// Also write an analysis of the module; a `.dot` file (e.g.
+    /// `callgraph.dot`) gets the call graph in Graphviz format, a `.txt`
+    /// file (e.g. `wcet.txt`) the static worst-case cost of each export, a
+    /// `.su` file (e.g. `stack.su`) the native stack each export needs, a
+    /// `.purity` file which functions have no side effects
+    #[arg(long, value_name = "FILE")]
+    emit: Vec<PathBuf>,
+
+    /// Iteration bound for loops the worst-case cost analysis cannot bound
+    /// itself, for the function exported or named NAME
+    #[arg(long, value_name = "NAME=N", value_parser = parse_loop_bound)]
+    loop_bound: Vec<(String, u64)>,
+
+    /// Most nested calls of the recursive cycle through the function
+    /// exported or named NAME, for the stack usage analysis
+    #[arg(long, value_name = "NAME=N", value_parser = parse_recursion_depth)]
+    recursion_depth: Vec<(String, u64)>,
+}
+
+/// Targets `--target-audit` checks by default: WebAssembly, a `no_std`
+/// 32-bit microcontroller and a 64-bit host.
+const AUDIT_TARGETS: &str = "wasm32-unknown-unknown,thumbv7em-none-eabihf,x86_64-unknown-linux-gnu";
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Print the Rust API the module would get — host traits, constructor
+    /// and export methods, without bodies — instead of generating it
+    Bindings(BindingsArgs),
+    /// List every operator and section that keeps the module from being
+    /// transpiled, with how often each occurs, most frequent first
+    Unsupported(UnsupportedArgs),
+}
+
+#[derive(Args, Debug)]
+struct BindingsArgs {
+    /// Input WebAssembly binary (.wasm)
+    input: PathBuf,
+
+    /// Bindings file, whose wrapper methods are included
+    #[arg(long, value_name = "FILE")]
+    bindings: Option<PathBuf>,
+
+    /// WIT world, whose wrappers and import trait are included
+    #[arg(long, value_name = "FILE")]
+    wit: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct UnsupportedArgs {
+    /// Input WebAssembly binary (.wasm)
+    input: PathBuf,
+
+    /// Count atomics and shared memory as supported, as `--lower-atomics` does
+    #[arg(long)]
+    lower_atomics: bool,
+}
+
+/// Parse a `--loop-bound` value: `NAME=N`.
+fn parse_loop_bound(value: &str) -> Result<(String, u64)> {
+    let Some((name, bound)) = value.split_once('=') else {
+        bail!("expected NAME=N, got `{value}`");
+    };
+    let bound = bound
+        .parse()
+        .with_context(|| format!("invalid loop bound `{bound}`"))?;
+    Ok((name.to_string(), bound))
+}
+
+/// Parse a `--recursion-depth` value: `NAME=N`.
+fn parse_recursion_depth(value: &str) -> Result<(String, u64)> {
+    let Some((name, depth)) = value.split_once('=') else {
+        bail!("expected NAME=N, got `{value}`");
+    };
+    let depth = depth
+        .parse()
+        .with_context(|| format!("invalid recursion depth `{depth}`"))?;
+    Ok((name.to_string(), depth))
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    match &cli.command {
+        Some(Commands::Bindings(args)) => return print_bindings(args),
+        Some(Commands::Unsupported(args)) => return print_unsupported(args),
+        None => {}
+    }
+    let input = cli.input.clone().context("no input file")?;
+    check_overwrite(cli.output.iter().chain(&cli.emit), cli.force)?;
+
+    eprintln!("herkos: transpiling {}", input.display(),);
+    if cli.lower_atomics {
+        eprintln!(
+            "herkos: WARNING: --lower-atomics makes atomic operations plain loads and stores; \
+             the generated code is only correct if a single thread runs the module"
+        );
+    }
+    if cli.checked_arithmetic {
+        eprintln!(
+            "herkos: WARNING: --checked-arithmetic traps on signed integer overflow, which \
+             WebAssembly defines to wrap; use it for testing only"
+        );
+    }
 
-    eprintln!("herkos: transpiling {}", cli.input.display(),);
+    for path in &cli.emit {
+        check_emit_path(path)?;
+    }
 
     // Read WASM file
     let wasm_bytes =
-        fs::read(&cli.input).with_context(|| format!("failed to read {}", cli.input.display()))?;
+        fs::read(&input).with_context(|| format!("failed to read {}", input.display()))?;
+
+    let bindings = read_bindings(cli.bindings.as_deref())?;
+    let wit = read_wit(cli.wit.as_deref())?;
+
+    let mut features = match &cli.export_features {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            FeatureConfig::parse(&text)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+        }
+        None => FeatureConfig::default(),
+    };
+    if let Some(prefix) = &cli.export_feature_prefix {
+        features.set_prefix(prefix)?;
+    }
+
+    let contracts = match &cli.contracts {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            ContractConfig::parse(&text)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+        }
+        None => ContractConfig::default(),
+    };
+
+    let capabilities = match &cli.capabilities {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            CapabilityConfig::parse(&text)
+                .with_context(|| format!("failed to parse {}", path.display()))?
+        }
+        None => CapabilityConfig::default(),
+    };
 
     // Configure transpilation options
     let options = TranspileOptions {
-        mode: "safe".to_string(),
+        mode: cli.mode.clone(),
         max_pages: 256,
         optimize: cli.optimize,
+        inline_threshold: cli.inline_threshold,
+        deterministic: cli.deterministic,
+        bindings,
+        wit,
+        wasmtime_adapter: cli.wasmtime_adapter,
+        yield_points: cli.yield_points,
+        features,
+        contracts,
+        capabilities,
+        guest_log: cli.guest_log,
+        lower_atomics: cli.lower_atomics,
+        call_ctors: cli.call_ctors,
+        checked_arithmetic: cli.checked_arithmetic,
+        observe_memory: cli.observe_memory,
+        record_replay: cli.record_replay,
+        dynamic_invoke: cli.dynamic_invoke,
+        export_groups: cli.export_group.clone(),
+        shared_host: cli.shared_host,
+        constant_time: cli.constant_time.clone(),
+        no_optimize: cli.no_optimize.clone(),
+        link: cli.link,
+        heap_profile: cli.heap_profile,
+        compact: cli.compact,
+        debug_memory: cli.debug_memory,
+        protect_rodata: cli.protect_rodata,
+        coverage: cli.coverage,
+        bounds_profile: cli.bounds_profile,
+        hot_reload: cli.hot_reload,
+        portable: cli.portable || cli.target_audit.is_some(),
+        pretransform: cli.pre_cmd.as_ref().map(|cmd| {
+            let transform = PreTransform::new(cmd.clone(), {
+                let cmd = cmd.clone();
+                move |wasm| pipe("pre command", &cmd, wasm.to_vec())
+            });
+            match &cli.pre_cache {
+                Some(dir) => transform.with_cache_dir(dir),
+                None => transform,
+            }
+        }),
+        postprocess: None,
+        preamble: Default::default(),
+        wcet: WcetConfig {
+            loop_bounds: cli.loop_bound.iter().cloned().collect(),
+            ..WcetConfig::default()
+        },
+        stack: StackConfig {
+            recursion_depths: cli.recursion_depth.iter().cloned().collect(),
+            ..StackConfig::default()
+        },
     };
 
     // Transpile using library function
-    let rust_code = transpile(&wasm_bytes, &options).context("transpilation failed")?;
+    let artifacts = match transpile_to_artifacts(&wasm_bytes, &options) {
+        Ok(artifacts) => artifacts,
+        Err(err) => {
+            // The error names the first construct only
+            if let Ok(found) = unsupported_constructs(&wasm_bytes, cli.lower_atomics) {
+                if !found.is_empty() {
+                    eprintln!(
+                        "herkos: the module uses {} unsupported constructs; `herkos unsupported {}` lists them all",
+                        found.len(),
+                        input.display()
+                    );
+                }
+            }
+            return Err(err.context("transpilation failed"));
+        }
+    };
+    let mut rust_code = artifacts.rust_code;
+    if let Some(cmd) = &cli.post_cmd {
+        rust_code = run_post_cmd(cmd, &rust_code)?;
+    }
+    for branch in &artifacts.reports.constant_time.branches {
+        eprintln!("herkos: constant-time: {branch}");
+    }
 
     // Write output
     if let Some(output_path) = cli.output {
-        fs::write(&output_path, &rust_code)
-            .with_context(|| format!("failed to write {}", output_path.display()))?;
+        write_atomic(&output_path, rust_code.as_bytes())?;
         eprintln!("herkos: wrote {}", output_path.display());
     } else {
         // Print to stdout if no output file specified
         print!("{}", rust_code);
     }
 
+    for path in &cli.emit {
+        emit_artifact(path, &wasm_bytes, &options)?;
+        eprintln!("herkos: wrote {}", path.display());
+    }
+
+    if let Some(targets) = &cli.target_audit {
+        target_audit(&rust_code, targets, cli.audit_runtime.as_deref(), &options)?;
+    }
+
     eprintln!("herkos: transpilation complete");
     Ok(())
 }
 
+/// `herkos bindings`: print the outline of the generated API.
+fn print_bindings(args: &BindingsArgs) -> Result<()> {
+    let wasm_bytes = fs::read(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let options = TranspileOptions {
+        bindings: read_bindings(args.bindings.as_deref())?,
+        wit: read_wit(args.wit.as_deref())?,
+        ..TranspileOptions::default()
+    };
+    let code = transpile(&wasm_bytes, &options).context("transpilation failed")?;
+    print!("{}", api::outline(&code));
+    Ok(())
+}
+
+/// `herkos unsupported`: print what keeps the module from being transpiled.
+fn print_unsupported(args: &UnsupportedArgs) -> Result<()> {
+    let wasm_bytes = fs::read(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let found = unsupported_constructs(&wasm_bytes, args.lower_atomics)?;
+    if found.is_empty() {
+        eprintln!(
+            "herkos: {} uses no unsupported constructs",
+            args.input.display()
+        );
+    }
+    for construct in &found {
+        println!("{construct}");
+    }
+    Ok(())
+}
+
+/// The `--bindings` file, if any.
+fn read_bindings(path: Option<&Path>) -> Result<BindingConfig> {
+    let Some(path) = path else {
+        return Ok(BindingConfig::default());
+    };
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    BindingConfig::parse(&text).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// The `--wit` world, if any.
+fn read_wit(path: Option<&Path>) -> Result<Option<WitWorld>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    WitWorld::parse(&text)
+        .map(Some)
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Pipe `code` through the shell command `cmd` and return its output.
+fn run_post_cmd(cmd: &str, code: &str) -> Result<String> {
+    let output = pipe("post command", cmd, code.as_bytes().to_vec())?;
+    String::from_utf8(output).context("post command output is not UTF-8")
+}
+
+/// Pipe `input` through the shell command `cmd`, the `what` of the error
+/// messages, and return its output.
+fn pipe(what: &str, cmd: &str, input: Vec<u8>) -> Result<Vec<u8>> {
+    let mut child = shell(cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {what} `{cmd}`"))?;
+    // Feed stdin from another thread: the command may write before it has
+    // read everything
+    let mut stdin = child
+        .stdin
+        .take()
+        .with_context(|| format!("{what} has no stdin"))?;
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("failed to run {what} `{cmd}`"))?;
+    match writer.join() {
+        // A command may ignore its input
+        Ok(Err(err)) if err.kind() != ErrorKind::BrokenPipe => {
+            return Err(err).with_context(|| format!("failed to write to the {what}"));
+        }
+        Err(_) => bail!("failed to write to the {what}"),
+        _ => {}
+    }
+    if !output.status.success() {
+        bail!("{what} `{cmd}` failed ({})", output.status);
+    }
+    Ok(output.stdout)
+}
+
+/// `cmd` run by the platform's shell.
+fn shell(cmd: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut command = Command::new(shell);
+    command.args([flag, cmd]);
+    command
+}
+
+/// `cargo check` the generated `code` for each of `targets`, in a scratch
+/// `no_std` crate depending on herkos-runtime (from `runtime`, if given)
+/// with the features `options` need.
+fn target_audit(
+    code: &str,
+    targets: &[String],
+    runtime: Option<&Path>,
+    options: &TranspileOptions,
+) -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("herkos-audit-{}", std::process::id()));
+    fs::create_dir_all(dir.join("src"))
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let source = match runtime {
+        Some(path) => {
+            let path = path
+                .canonicalize()
+                .with_context(|| format!("no herkos-runtime at {}", path.display()))?;
+            format!("path = {:?}", path.display().to_string())
+        }
+        None => format!("version = \"{}\"", env!("CARGO_PKG_VERSION")),
+    };
+    let features: Vec<String> = runtime_features(options)
+        .iter()
+        .map(|f| format!("{f:?}"))
+        .collect();
+    let manifest = format!(
+        "[package]\nname = \"herkos-audit\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [dependencies]\nherkos-runtime = {{ {source}, default-features = false, features = [{}] }}\n\n\
+         [workspace]\n",
+        features.join(", ")
+    );
+    fs::write(dir.join("Cargo.toml"), manifest)?;
+    fs::write(
+        dir.join("src/lib.rs"),
+        "#![no_std]\n#![allow(warnings)]\nmod module;\n",
+    )?;
+    fs::write(dir.join("src/module.rs"), code)?;
+
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut failed = Vec::new();
+    for target in targets {
+        let output = Command::new(&cargo)
+            .args(["check", "--quiet", "--target", target])
+            .current_dir(&dir)
+            .output()
+            .context("failed to run cargo")?;
+        if output.status.success() {
+            eprintln!("herkos: target audit: {target}: ok");
+        } else {
+            eprintln!(
+                "herkos: target audit: {target}: failed\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            failed.push(target.as_str());
+        }
+    }
+    // Keep the crate of a failed audit for inspection
+    if failed.is_empty() {
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    } else {
+        bail!(
+            "the generated code does not build for {} (audit crate in {})",
+            failed.join(", "),
+            dir.display()
+        )
+    }
+}
+
+/// herkos-runtime features the code generated with `options` uses.
+fn runtime_features(options: &TranspileOptions) -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if options.record_replay
+        || options.heap_profile
+        || options.debug_memory
+        || options.link
+        || options.hot_reload
+        || options.wit.is_some()
+    {
+        features.push("alloc");
+    }
+    if options.observe_memory || options.debug_memory {
+        features.push("observe");
+    }
+    features
+}
+
+/// Reject `--emit` paths that select no known artifact.
+fn check_emit_path(path: &Path) -> Result<()> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("dot" | "txt" | "su" | "purity") => Ok(()),
+        _ => bail!(
+            "cannot emit {}: expected a .dot file for the call graph, a .txt file \
+             for the worst-case cost report, a .su file for the stack usage report \
+             or a .purity file for the side-effect report",
+            path.display()
+        ),
+    }
+}
+
+/// Write the analysis artifact selected by `path`'s extension.
+fn emit_artifact(path: &Path, wasm_bytes: &[u8], options: &TranspileOptions) -> Result<()> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    let text = if let Some("txt" | "su" | "purity") = extension {
+        let artifacts = transpile_to_artifacts(wasm_bytes, options)?;
+        let (reports, info) = (&artifacts.reports, &artifacts.module_info);
+        match extension {
+            Some("su") => reports.stack.to_text(info),
+            Some("purity") => reports.side_effects.to_text(info),
+            _ => reports.wcet.to_text(info),
+        }
+    } else {
+        // The transform's output is cached from transpiling
+        let transformed = match &options.pretransform {
+            Some(transform) => Some(transform.apply(wasm_bytes)?),
+            None => None,
+        };
+        let wasm_bytes = transformed.as_deref().unwrap_or(wasm_bytes);
+        let parsed =
+            parse_wasm_with_features(wasm_bytes, supported_features(options.lower_atomics))
+                .context("failed to parse WebAssembly module")?;
+        let info =
+            build_module_info(&parsed, options).context("failed to build module metadata")?;
+        CallGraph::build(&info).to_dot(&info)
+    };
+    write_atomic(path, text.as_bytes())
+}
+
+/// Refuse to replace any of `paths` that exists, unless `force` is set.
+fn check_overwrite<'a>(paths: impl IntoIterator<Item = &'a PathBuf>, force: bool) -> Result<()> {
+    if force {
+        return Ok(());
+    }
+    for path in paths {
+        if path.exists() {
+            bail!(
+                "{} already exists; pass --force to overwrite it",
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write `contents` to a temporary file next to `path`, then rename it over
+/// `path`: readers see the old file or the complete new one, never a
+/// truncated one.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file path", path.display()))?;
+    let tmp = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let written = fs::File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(err).with_context(|| format!("failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,7 +778,421 @@ mod tests {
     #[test]
     fn cli_parses_defaults() {
         let cli = Cli::parse_from(["herkos", "input.wasm"]);
-        assert_eq!(cli.input, PathBuf::from("input.wasm"));
+        assert!(cli.command.is_none());
+        assert_eq!(cli.input, Some(PathBuf::from("input.wasm")));
         assert!(cli.output.is_none());
+        assert!(!cli.force);
+        assert_eq!(cli.mode, "safe");
+        assert_eq!(cli.inline_threshold, DEFAULT_INLINE_THRESHOLD);
+        assert!(!cli.deterministic);
+        assert!(cli.bindings.is_none());
+        assert!(cli.wit.is_none());
+        assert!(!cli.wasmtime_adapter);
+        assert!(!cli.yield_points);
+        assert!(cli.export_features.is_none());
+        assert!(cli.contracts.is_none());
+        assert!(cli.capabilities.is_none());
+        assert!(!cli.guest_log);
+        assert!(cli.export_feature_prefix.is_none());
+        assert!(!cli.lower_atomics);
+        assert!(!cli.checked_arithmetic);
+        assert!(!cli.observe_memory);
+        assert!(!cli.record_replay);
+        assert!(!cli.dynamic_invoke);
+        assert!(!cli.shared_host);
+        assert!(!cli.link);
+        assert!(!cli.heap_profile);
+        assert!(!cli.compact);
+        assert!(!cli.debug_memory);
+        assert!(!cli.protect_rodata);
+        assert!(!cli.coverage);
+        assert!(!cli.bounds_profile);
+        assert!(!cli.hot_reload);
+        assert!(!cli.portable);
+        assert!(cli.target_audit.is_none());
+        assert!(cli.audit_runtime.is_none());
+        assert!(cli.pre_cmd.is_none());
+        assert!(cli.pre_cache.is_none());
+        assert!(cli.post_cmd.is_none());
+        assert!(cli.constant_time.is_empty());
+        assert!(cli.export_group.is_empty());
+        assert!(cli.emit.is_empty());
+        assert!(cli.loop_bound.is_empty());
+        assert!(cli.recursion_depth.is_empty());
+    }
+
+    #[test]
+    fn cli_parses_bindings_command() {
+        let cli = Cli::parse_from(["herkos", "bindings", "input.wasm", "--wit", "world.wit"]);
+        let Some(Commands::Bindings(args)) = cli.command else {
+            panic!("expected the bindings command");
+        };
+        assert_eq!(args.input, PathBuf::from("input.wasm"));
+        assert!(args.bindings.is_none());
+        assert_eq!(args.wit, Some(PathBuf::from("world.wit")));
+        // Transpiling options belong to transpiling
+        assert!(Cli::try_parse_from(["herkos", "bindings", "input.wasm", "-O"]).is_err());
+        assert!(Cli::try_parse_from(["herkos"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_unsupported_command() {
+        let cli = Cli::parse_from(["herkos", "unsupported", "input.wasm", "--lower-atomics"]);
+        let Some(Commands::Unsupported(args)) = cli.command else {
+            panic!("expected the unsupported command");
+        };
+        assert_eq!(args.input, PathBuf::from("input.wasm"));
+        assert!(args.lower_atomics);
+    }
+
+    #[test]
+    fn cli_parses_target_audit() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--target-audit"]);
+        let targets = cli.target_audit.unwrap();
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[0], "wasm32-unknown-unknown");
+
+        let cli = Cli::parse_from([
+            "herkos",
+            "--target-audit=riscv32imc-unknown-none-elf,aarch64-unknown-linux-gnu",
+            "input.wasm",
+            "--audit-runtime",
+            "crates/herkos-runtime",
+        ]);
+        assert_eq!(cli.input, Some(PathBuf::from("input.wasm")));
+        assert_eq!(
+            cli.target_audit.unwrap(),
+            ["riscv32imc-unknown-none-elf", "aarch64-unknown-linux-gnu"]
+        );
+        assert_eq!(
+            cli.audit_runtime,
+            Some(PathBuf::from("crates/herkos-runtime"))
+        );
+        assert!(Cli::try_parse_from(["herkos", "input.wasm", "--audit-runtime", "rt"]).is_err());
+        assert!(Cli::parse_from(["herkos", "input.wasm", "--portable"]).portable);
+    }
+
+    #[test]
+    fn cli_parses_deterministic() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--deterministic"]);
+        assert!(cli.deterministic);
+    }
+
+    #[test]
+    fn cli_parses_bindings_file() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--bindings", "api.bindings"]);
+        assert_eq!(cli.bindings, Some(PathBuf::from("api.bindings")));
+    }
+
+    #[test]
+    fn cli_parses_wit_file() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--wit", "world.wit"]);
+        assert_eq!(cli.wit, Some(PathBuf::from("world.wit")));
+    }
+
+    #[test]
+    fn cli_parses_wasmtime_adapter_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--wasmtime-adapter"]);
+        assert!(cli.wasmtime_adapter);
+    }
+
+    #[test]
+    fn cli_parses_yield_points_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--yield-points"]);
+        assert!(cli.yield_points);
+    }
+
+    #[test]
+    fn cli_parses_export_features() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--export-features",
+            "api.features",
+            "--export-feature-prefix",
+            "export-",
+        ]);
+        assert_eq!(cli.export_features, Some(PathBuf::from("api.features")));
+        assert_eq!(cli.export_feature_prefix.as_deref(), Some("export-"));
+    }
+
+    #[test]
+    fn cli_parses_mode() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--mode", "c"]);
+        assert_eq!(cli.mode, "c");
+        assert!(Cli::try_parse_from(["herkos", "input.wasm", "--mode", "cobol"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_lower_atomics_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--lower-atomics"]);
+        assert!(cli.lower_atomics);
+    }
+
+    #[test]
+    fn cli_parses_checked_arithmetic_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--checked-arithmetic"]);
+        assert!(cli.checked_arithmetic);
+    }
+
+    #[test]
+    fn cli_parses_observe_memory_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--observe-memory"]);
+        assert!(cli.observe_memory);
+    }
+
+    #[test]
+    fn cli_parses_record_replay_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--record-replay"]);
+        assert!(cli.record_replay);
+    }
+
+    #[test]
+    fn cli_parses_dynamic_invoke_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--dynamic-invoke"]);
+        assert!(cli.dynamic_invoke);
+    }
+
+    #[test]
+    fn cli_parses_export_groups() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--export-group",
+            "image_",
+            "--export-group",
+            "audio_",
+        ]);
+        assert_eq!(cli.export_group, ["image_", "audio_"]);
+    }
+
+    #[test]
+    fn cli_parses_shared_host_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--shared-host"]);
+        assert!(cli.shared_host);
+    }
+
+    #[test]
+    fn cli_parses_constant_time_exports() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--constant-time",
+            "verify",
+            "--constant-time",
+            "sign",
+        ]);
+        assert_eq!(cli.constant_time, ["verify", "sign"]);
+    }
+
+    #[test]
+    fn cli_parses_link_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--link"]);
+        assert!(cli.link);
+    }
+
+    #[test]
+    fn cli_parses_heap_profile_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--heap-profile"]);
+        assert!(cli.heap_profile);
+    }
+
+    #[test]
+    fn cli_parses_compact_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--compact"]);
+        assert!(cli.compact);
+    }
+
+    #[test]
+    fn cli_parses_debug_memory_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--debug-memory"]);
+        assert!(cli.debug_memory);
+    }
+
+    #[test]
+    fn cli_parses_protect_rodata_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--protect-rodata"]);
+        assert!(cli.protect_rodata);
+    }
+
+    #[test]
+    fn cli_parses_coverage_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--coverage"]);
+        assert!(cli.coverage);
+    }
+
+    #[test]
+    fn cli_parses_bounds_profile_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--bounds-profile"]);
+        assert!(cli.bounds_profile);
+    }
+
+    #[test]
+    fn cli_parses_hot_reload_flag() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--hot-reload"]);
+        assert!(cli.hot_reload);
+    }
+
+    #[test]
+    fn cli_parses_post_cmd() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--post-cmd", "cat header.rs -"]);
+        assert_eq!(cli.post_cmd.as_deref(), Some("cat header.rs -"));
+    }
+
+    #[test]
+    fn cli_parses_pre_cmd() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--pre-cmd",
+            "wasm-opt -Oz -o - -",
+            "--pre-cache",
+            "target/pre",
+        ]);
+        assert_eq!(cli.pre_cmd.as_deref(), Some("wasm-opt -Oz -o - -"));
+        assert_eq!(cli.pre_cache, Some(PathBuf::from("target/pre")));
+        // A cache without a command is a mistake
+        assert!(
+            Cli::try_parse_from(["herkos", "input.wasm", "--pre-cache", "target/pre"]).is_err()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pre_cmd_pipes_bytes() {
+        let output = pipe("pre command", "tr a b", vec![0, b'a', 0xff]).unwrap();
+        assert_eq!(output, [0, b'b', 0xff]);
+        let err = pipe("pre command", "exit 3", Vec::new()).unwrap_err();
+        assert!(format!("{err:#}").contains("pre command `exit 3` failed"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn post_cmd_rewrites_the_source() {
+        let code = run_post_cmd("sed 's/^fn /pub fn /'", "fn a() {}\n").unwrap();
+        assert_eq!(code, "pub fn a() {}\n");
+        let err = run_post_cmd("exit 3", "fn a() {}\n").unwrap_err();
+        assert!(format!("{err:#}").contains("post command `exit 3` failed"));
+    }
+
+    #[test]
+    fn cli_parses_contracts_file() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--contracts", "api.contracts"]);
+        assert_eq!(cli.contracts, Some(PathBuf::from("api.contracts")));
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_without_leftovers() {
+        let dir = std::env::temp_dir().join(format!("herkos-write-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.rs");
+        fs::write(&path, "old contents, longer than the new ones").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A failed write leaves the target alone
+        assert!(write_atomic(&dir.join("missing/out.rs"), b"x").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn existing_outputs_need_force() {
+        let existing = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let missing = existing.with_file_name("not-there.rs");
+        assert!(check_overwrite([&missing], false).is_ok());
+        let err = check_overwrite([&missing, &existing], false).unwrap_err();
+        assert!(err.to_string().contains("pass --force to overwrite it"));
+        assert!(check_overwrite([&existing], true).is_ok());
+
+        let cli = Cli::parse_from(["herkos", "input.wasm", "-o", "out.rs", "--force"]);
+        assert!(cli.force);
+    }
+
+    #[test]
+    fn cli_parses_capabilities_file() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--capabilities",
+            "host.capabilities",
+        ]);
+        assert_eq!(cli.capabilities, Some(PathBuf::from("host.capabilities")));
+    }
+
+    #[test]
+    fn cli_parses_inline_threshold() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "-O", "--inline-threshold", "0"]);
+        assert_eq!(cli.inline_threshold, 0);
+    }
+
+    #[test]
+    fn cli_parses_no_optimize_functions() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--optimize",
+            "--no-optimize",
+            "checksum",
+            "--no-optimize",
+            "3",
+        ]);
+        assert_eq!(cli.no_optimize, ["checksum", "3"]);
+    }
+
+    #[test]
+    fn cli_parses_loop_bounds() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--loop-bound",
+            "checksum=64",
+            "--loop-bound",
+            "fill=4096",
+        ]);
+        assert_eq!(
+            cli.loop_bound,
+            vec![("checksum".to_string(), 64), ("fill".to_string(), 4096)]
+        );
+        assert!(Cli::try_parse_from(["herkos", "input.wasm", "--loop-bound", "checksum"]).is_err());
+        assert!(Cli::try_parse_from(["herkos", "input.wasm", "--loop-bound", "f=-1"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_recursion_depths() {
+        let cli = Cli::parse_from(["herkos", "input.wasm", "--recursion-depth", "walk=32"]);
+        assert_eq!(cli.recursion_depth, vec![("walk".to_string(), 32)]);
+        assert!(
+            Cli::try_parse_from(["herkos", "input.wasm", "--recursion-depth", "walk=deep"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn emit_paths_must_name_a_known_artifact() {
+        assert!(check_emit_path(Path::new("out/callgraph.dot")).is_ok());
+        assert!(check_emit_path(Path::new("wcet.txt")).is_ok());
+        assert!(check_emit_path(Path::new("stack.su")).is_ok());
+        assert!(check_emit_path(Path::new("module.purity")).is_ok());
+        assert!(check_emit_path(Path::new("callgraph.json")).is_err());
+        assert!(check_emit_path(Path::new("callgraph")).is_err());
+    }
+
+    #[test]
+    fn cli_parses_emit_files() {
+        let cli = Cli::parse_from([
+            "herkos",
+            "input.wasm",
+            "--emit",
+            "callgraph.dot",
+            "--emit",
+            "other.dot",
+        ]);
+        assert_eq!(
+            cli.emit,
+            vec![PathBuf::from("callgraph.dot"), PathBuf::from("other.dot")]
+        );
     }
 }
@@ -34,7 +34,7 @@ fn test_simple_add() -> Result<()> {
     assert!(rust_code.contains("v1: i32"));
     assert!(rust_code.contains("-> WasmResult<i32>"));
     assert!(rust_code.contains("wrapping_add"));
-    assert!(rust_code.contains("return Ok("));
+    assert!(rust_code.contains("Ok(v"));
 
     Ok(())
 }
@@ -57,7 +57,7 @@ fn test_simple_sub() -> Result<()> {
 
     assert!(rust_code.contains("pub fn func_0"));
     assert!(rust_code.contains("wrapping_sub"));
-    assert!(rust_code.contains("return Ok("));
+    assert!(rust_code.contains("Ok(v"));
 
     Ok(())
 }
@@ -80,7 +80,7 @@ fn test_simple_mul() -> Result<()> {
 
     assert!(rust_code.contains("pub fn func_0"));
     assert!(rust_code.contains("wrapping_mul"));
-    assert!(rust_code.contains("return Ok("));
+    assert!(rust_code.contains("Ok(v"));
 
     Ok(())
 }
@@ -105,7 +105,7 @@ fn test_constant_arithmetic() -> Result<()> {
     assert!(rust_code.contains("10i32"));
     assert!(rust_code.contains("20i32"));
     assert!(rust_code.contains("wrapping_add"));
-    assert!(rust_code.contains("return Ok("));
+    assert!(rust_code.contains("Ok(v"));
 
     Ok(())
 }
@@ -155,7 +155,7 @@ fn test_void_return() -> Result<()> {
 
     assert!(rust_code.contains("pub fn func_0"));
     assert!(rust_code.contains("-> WasmResult<()>"));
-    assert!(rust_code.contains("return Ok(())"));
+    assert!(rust_code.contains("Ok(())"));
 
     Ok(())
 }
@@ -306,7 +306,8 @@ fn test_simple_if() -> Result<()> {
 
     assert!(rust_code.contains("42i32"));
     assert!(rust_code.contains("99i32"));
-    assert!(rust_code.contains("__current_block") || rust_code.contains("continue"));
+    assert!(rust_code.contains("} else {"));
+    assert!(!rust_code.contains("__current_block"));
 
     Ok(())
 }
@@ -317,7 +318,6 @@ fn test_simple_loop() -> Result<()> {
     let wat = r#"
         (module
             (func (param i32) (result i32)
-                local.get 0
                 loop (result i32)
                     local.get 0
                     i32.const 1
@@ -336,9 +336,9 @@ fn test_simple_loop() -> Result<()> {
 
     println!("Generated Rust code:\n{}", rust_code);
 
-    // Should have loop-related structures
-    assert!(rust_code.contains("__current_block"));
-    assert!(rust_code.contains("__current_block") || rust_code.contains("continue"));
+    // Should have a native loop
+    assert!(rust_code.contains("loop {"));
+    assert!(!rust_code.contains("__current_block"));
 
     Ok(())
 }
@@ -364,7 +364,8 @@ fn test_br_if() -> Result<()> {
 
     println!("Generated Rust code:\n{}", rust_code);
 
-    assert!(rust_code.contains("__current_block") || rust_code.contains("continue"));
+    assert!(rust_code.contains("if v5 != 0 {"));
+    assert!(!rust_code.contains("__current_block"));
 
     Ok(())
 }
@@ -374,18 +375,16 @@ fn test_nested_blocks() -> Result<()> {
     let wat = r#"
         (module
             (func (param i32) (result i32)
-                block (result i32)
-                    block (result i32)
+                block
+                    block
                         local.get 0
                         i32.const 0
                         i32.eq
                         br_if 0
-                        i32.const 1
                         br 1
                     end
-                    drop
-                    i32.const 2
                 end
+                i32.const 2
             )
         )
     "#;
@@ -394,9 +393,8 @@ fn test_nested_blocks() -> Result<()> {
 
     println!("Generated Rust code:\n{}", rust_code);
 
-    // Should have multiple labeled blocks
-    assert!(rust_code.contains("__current_block"));
-    assert!(rust_code.contains("__current_block") || rust_code.contains("continue"));
+    // Should have no state machine
+    assert!(!rust_code.contains("__current_block"));
 
     Ok(())
 }
@@ -406,20 +404,19 @@ fn test_br_table() -> Result<()> {
     let wat = r#"
         (module
             (func (param i32) (result i32)
-                block (result i32)
-                    block (result i32)
-                        block (result i32)
+                block
+                    block
+                        block
                             local.get 0
                             br_table 0 1 2 2
                         end
                         i32.const 10
-                        br 1
+                        return
                     end
                     i32.const 20
-                    br 0
+                    return
                 end
                 i32.const 30
-                i32.add
             )
         )
     "#;
@@ -429,7 +426,8 @@ fn test_br_table() -> Result<()> {
     println!("Generated Rust code:\n{}", rust_code);
 
     assert!(rust_code.contains("match"));
-    assert!(rust_code.contains("__current_block") || rust_code.contains("continue"));
+    assert!(rust_code.contains("0 => {"));
+    assert!(!rust_code.contains("__current_block"));
 
     Ok(())
 }
@@ -455,7 +453,8 @@ fn test_if_without_else() -> Result<()> {
 
     println!("Generated Rust code:\n{}", rust_code);
 
-    assert!(rust_code.contains("__current_block") || rust_code.contains("continue"));
+    assert!(rust_code.contains("if v5 != 0 {"));
+    assert!(!rust_code.contains("} else {"));
 
     Ok(())
 }
@@ -681,8 +680,8 @@ fn test_i64_comparisons() -> Result<()> {
     let rust_code = transpile_wat(wat)?;
     println!("Generated Rust code:\n{}", rust_code);
 
-    assert!(rust_code.contains("1i32"));
-    assert!(rust_code.contains("0i32"));
+    // Comparisons produce their i32 result with `i32::from(bool)`.
+    assert!(rust_code.contains("i32::from("));
     assert!(rust_code.contains("as u64"));
 
     Ok(())
@@ -878,8 +877,8 @@ fn test_direct_call_simple() -> Result<()> {
     let rust_code = transpile_wat(wat)?;
     println!("Generated Rust code:\n{}", rust_code);
 
-    // Should contain a call to func_0
-    assert!(rust_code.contains("func_0("));
+    // Should contain a call to func_helper
+    assert!(rust_code.contains("func_helper("));
     // The helper should have wrapping_add
     assert!(rust_code.contains("wrapping_add"));
 
@@ -903,8 +902,8 @@ fn test_recursive_fibonacci() -> Result<()> {
     let rust_code = transpile_wat(wat)?;
     println!("Generated Rust code:\n{}", rust_code);
 
-    // Should contain recursive calls to func_0
-    assert!(rust_code.contains("func_0("));
+    // Should contain recursive calls to func_fib
+    assert!(rust_code.contains("func_fib("));
     assert!(rust_code.contains("wrapping_add"));
     assert!(rust_code.contains("wrapping_sub"));
 
@@ -931,7 +930,7 @@ fn test_void_function_call() -> Result<()> {
     println!("Generated Rust code:\n{}", rust_code);
 
     // Should contain a call without dest assignment
-    assert!(rust_code.contains("func_0("));
+    assert!(rust_code.contains("func_set_value("));
     assert!(rust_code.contains("memory.store_i32"));
 
     Ok(())
@@ -955,10 +954,10 @@ fn test_call_i64_return_type() -> Result<()> {
     let rust_code = transpile_wat(wat)?;
     println!("Generated Rust code:\n{}", rust_code);
 
-    // Should contain a call to func_0
-    assert!(rust_code.contains("func_0("));
-    // The result variable in func_1 should be i64, not i32
-    // func_1 is the main function which calls func_0 returning i64
+    // Should contain a call to func_add64
+    assert!(rust_code.contains("func_add64("));
+    // The result variable in func_main should be i64, not i32
+    // func_main is the main function which calls func_add64 returning i64
     assert!(rust_code.contains("v0: i64"));
 
     Ok(())
@@ -1086,6 +1085,59 @@ fn test_mode_safe_produces_bounds_checks() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_deterministic_rejects_float_arithmetic() -> Result<()> {
+    let wat = r#"
+        (module
+            (func (export "mix") (param f32 i32) (result i32)
+                local.get 1
+                local.get 0
+                local.get 0
+                f32.mul
+                drop
+            )
+        )
+    "#;
+
+    let wasm_bytes = wat::parse_str(wat).context("failed to parse WAT")?;
+    let opts = TranspileOptions {
+        deterministic: true,
+        ..TranspileOptions::default()
+    };
+    let err = transpile(&wasm_bytes, &opts).expect_err("f32.mul must be rejected");
+    let msg = format!("{:#}", err);
+    assert!(msg.contains("not deterministic"), "unexpected error: {msg}");
+    assert!(msg.contains("F32Mul"), "unexpected error: {msg}");
+
+    // Without the option the same module transpiles fine.
+    transpile(&wasm_bytes, &TranspileOptions::default())?;
+
+    Ok(())
+}
+
+#[test]
+fn test_deterministic_accepts_integer_module() -> Result<()> {
+    let wat = r#"
+        (module
+            (func (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+            )
+        )
+    "#;
+
+    let wasm_bytes = wat::parse_str(wat).context("failed to parse WAT")?;
+    let opts = TranspileOptions {
+        deterministic: true,
+        ..TranspileOptions::default()
+    };
+    let code = transpile(&wasm_bytes, &opts)?;
+    assert!(code.contains("pub fn add("));
+
+    Ok(())
+}
+
 // ==================== Indirect Function Calls ====================
 
 #[test]
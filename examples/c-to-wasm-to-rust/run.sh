@@ -55,10 +55,10 @@ echo "==> Transpiling WebAssembly to Rust..."
 REPO_ROOT="$(cd "$SCRIPT_DIR/../.." && pwd)"
 
 if command -v herkos &>/dev/null; then
-    herkos "$WASM_FILE" --output "$GENERATED_RS"
+    herkos "$WASM_FILE" --force --output "$GENERATED_RS"
 else
     cargo run --manifest-path "$REPO_ROOT/Cargo.toml" -p herkos -- \
-        "$SCRIPT_DIR/$WASM_FILE" --output "$SCRIPT_DIR/$GENERATED_RS"
+        "$SCRIPT_DIR/$WASM_FILE" --force --output "$SCRIPT_DIR/$GENERATED_RS"
 fi
 
 echo "    Created $GENERATED_RS"
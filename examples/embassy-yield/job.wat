@@ -0,0 +1,23 @@
+;; Resumable summation job: `start(n)` resets the job, `run()` continues it
+;; and returns the sum of 1..=n once done. All progress lives in globals and
+;; is committed before each loop back-edge, so a call interrupted at a yield
+;; point resumes exactly where it stopped.
+(module
+  (global $i (mut i32) (i32.const 0))
+  (global $n (mut i32) (i32.const 0))
+  (global $acc (mut i64) (i64.const 0))
+
+  (func (export "start") (param $n i32)
+    (global.set $n (local.get $n))
+    (global.set $i (i32.const 0))
+    (global.set $acc (i64.const 0)))
+
+  (func (export "run") (result i64)
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (global.get $i) (global.get $n)))
+        (global.set $i (i32.add (global.get $i) (i32.const 1)))
+        (global.set $acc
+          (i64.add (global.get $acc) (i64.extend_i32_u (global.get $i))))
+        (br $next)))
+    (global.get $acc)))
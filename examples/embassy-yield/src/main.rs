@@ -0,0 +1,112 @@
+// Cooperative yielding example (embassy)
+//
+// This program runs a long transpiled computation on an embassy executor
+// without starving the other tasks:
+// - `job.wat` is transpiled with `--yield-points`, so every loop iteration
+//   calls `ModuleHostTrait::maybe_yield`
+// - the host's `maybe_yield` interrupts the call once its time slice is used
+//   up, returning `WasmTrap::Interrupted`
+// - the compute task then awaits `yield_now()`, letting the heartbeat task
+//   run, and calls `run()` again from the start. The interrupted call was
+//   unwound, not suspended: only because the job keeps its progress in
+//   globals does the new call continue where the old one stopped
+//
+// The executor uses `arch-std` so the example runs on a desktop; on a
+// microcontroller swap it for `arch-cortex-m` (or `arch-riscv32`) and the
+// matching embassy-time driver. The generated module is `no_std`.
+//
+// Run `./run.sh` to regenerate job_wasm.rs and execute this program.
+
+#[allow(dead_code)]
+mod job_wasm;
+
+use embassy_executor::Spawner;
+use embassy_futures::yield_now;
+use embassy_time::{Duration, Instant, Ticker};
+use herkos_runtime::{NoHost, WasmResult, WasmTrap};
+
+/// Number of terms summed by the job.
+const TERMS: i32 = 50_000_000;
+
+/// Longest stretch the job may run before giving the executor back.
+const SLICE: Duration = Duration::from_millis(2);
+
+/// Reading the clock is much slower than one loop iteration, so it is only
+/// checked every `CLOCK_STRIDE` yield points.
+const CLOCK_STRIDE: u32 = 4096;
+
+/// Host that interrupts the guest when its time slice runs out.
+struct SliceHost {
+    deadline: Instant,
+    countdown: u32,
+}
+
+impl SliceHost {
+    fn new() -> Self {
+        SliceHost {
+            deadline: Instant::now() + SLICE,
+            countdown: CLOCK_STRIDE,
+        }
+    }
+}
+
+impl job_wasm::ModuleHostTrait for SliceHost {
+    fn maybe_yield(&mut self) -> WasmResult<()> {
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return Ok(());
+        }
+        self.countdown = CLOCK_STRIDE;
+        if Instant::now() >= self.deadline {
+            return Err(WasmTrap::Interrupted);
+        }
+        Ok(())
+    }
+}
+
+#[embassy_executor::task]
+async fn heartbeat() {
+    let mut ticker = Ticker::every(Duration::from_millis(20));
+    let mut beats = 0u32;
+    loop {
+        beats += 1;
+        println!("  [heartbeat {}]", beats);
+        ticker.next().await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    println!("=== Cooperative Yielding Example ===\n");
+    spawner.spawn(heartbeat()).unwrap();
+
+    let mut module = job_wasm::new().expect("failed to instantiate module");
+    module
+        .start(TERMS, &mut NoHost)
+        .expect("failed to start the job");
+
+    let started = Instant::now();
+    let mut slices = 1u32;
+    let sum = loop {
+        match module.run(&mut SliceHost::new()) {
+            Ok(sum) => break sum,
+            Err(WasmTrap::Interrupted) => {
+                slices += 1;
+                yield_now().await;
+            }
+            Err(trap) => panic!("guest trapped: {:?}", trap),
+        }
+    };
+
+    let expected = TERMS as i64 * (TERMS as i64 + 1) / 2;
+    println!(
+        "\nsum(1..={}) = {} in {} slices, {} ms",
+        TERMS,
+        sum,
+        slices,
+        started.elapsed().as_millis()
+    );
+    assert_eq!(sum, expected);
+    println!("=== Done ===");
+    std::process::exit(0);
+}
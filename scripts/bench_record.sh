@@ -21,6 +21,7 @@ rm -f /tmp/new_bench_rows.csv
 # Run benchmarks
 echo "Running benchmarks (HERKOS_OPTIMIZE=1)..."
 HERKOS_OPTIMIZE=1 cargo bench -p herkos-tests
+cargo bench -p herkos-runtime
 
 echo
 echo "Collecting results..."
@@ -0,0 +1,382 @@
+#!/usr/bin/env python3
+"""Real-world corpus regression harness.
+
+Transpiles every module listed in corpus/corpus.json, compiles the generated
+Rust against herkos-runtime, lints it with clippy (warnings denied, plus
+CLIPPY_FORBID), smoke-runs the listed exports, and compares the
+generated size and the transpile/compile times with corpus/baseline.json.
+A listed module whose .wasm is missing, or that has no baseline, fails the run.
+"""
+
+import argparse
+import json
+import os
+import re
+import shutil
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+
+# ---------------------------------------------------------------------------
+# Paths
+# ---------------------------------------------------------------------------
+
+SCRIPT_DIR = Path(__file__).resolve().parent
+REPO_ROOT = SCRIPT_DIR.parent
+CORPUS_DIR = REPO_ROOT / "corpus"
+MANIFEST = CORPUS_DIR / "corpus.json"
+BASELINE = CORPUS_DIR / "baseline.json"
+WORK_DIR = REPO_ROOT / "target" / "corpus"
+HERKOS = REPO_ROOT / "target" / "release" / "herkos"
+RUNTIME = REPO_ROOT / "crates" / "herkos-runtime"
+
+# Large modules produce functions deep enough to overflow rustc's default stack.
+RUSTC_STACK = str(256 << 20)
+
+# Lints the generated code must pass on top of clippy's defaults; the same list
+# is forbidden in crates/herkos-tests/src/lib.rs.
+CLIPPY_FORBID = [
+    "clippy::needless_return",
+    "clippy::manual_range_contains",
+    "clippy::bool_to_int_with_if",
+    "clippy::needless_continue",
+    "clippy::items_after_statements",
+    "clippy::unnecessary_semicolon",
+]
+
+
+def check_repo_root() -> None:
+    if not (REPO_ROOT / "Cargo.toml").exists():
+        sys.exit("ERROR: Cargo.toml not found. Please run this script from the repo root.")
+
+
+class CorpusError(Exception):
+    """A module failed to build, transpile, compile or run."""
+
+
+def run(cmd: list[str], **kwargs) -> subprocess.CompletedProcess:
+    result = subprocess.run(cmd, capture_output=True, text=True, **kwargs)
+    if result.returncode != 0:
+        tail = "\n".join((result.stderr or result.stdout).splitlines()[-20:])
+        raise CorpusError(f"`{' '.join(map(str, cmd))}` failed:\n{tail}")
+    return result
+
+
+# ---------------------------------------------------------------------------
+# Steps
+# ---------------------------------------------------------------------------
+
+def build_herkos() -> None:
+    print("Building herkos CLI (release)...")
+    run(["cargo", "build", "--release", "-p", "herkos"], cwd=REPO_ROOT)
+
+
+def resolve_wasm(module: dict) -> Path:
+    """Build or locate the module's .wasm."""
+    if "cargo" in module:
+        crate = CORPUS_DIR / module["cargo"]
+        target_dir = WORK_DIR / "wasm"
+        run(
+            ["cargo", "build", "--release", "--target", "wasm32-unknown-unknown",
+             "--manifest-path", str(crate / "Cargo.toml")],
+            env={**os.environ, "CARGO_TARGET_DIR": str(target_dir)},
+        )
+        package = _package_name(crate / "Cargo.toml")
+        return target_dir / "wasm32-unknown-unknown" / "release" / f"{package.replace('-', '_')}.wasm"
+    path = CORPUS_DIR / module["wasm"]
+    if not path.exists():
+        raise CorpusError(f"{module['wasm']} is missing (see corpus/README.md)")
+    return path
+
+
+def _package_name(manifest: Path) -> str:
+    match = re.search(r'^name\s*=\s*"([^"]+)"', manifest.read_text(), re.MULTILINE)
+    if match is None:
+        raise CorpusError(f"no package name in {manifest}")
+    return match.group(1)
+
+
+def transpile(wasm: Path, flags: list[str], out: Path) -> float:
+    """Transpile `wasm` into `out`; returns the wall time in ms.
+
+    Runs shorter than a second are repeated and the best of three is kept.
+    """
+    best = float("inf")
+    for _ in range(3):
+        start = time.perf_counter()
+        run([str(HERKOS), *flags, str(wasm), "-o", str(out), "--force"])
+        best = min(best, (time.perf_counter() - start) * 1000)
+        if best >= 1000:
+            break
+    return best
+
+
+def write_harness(name: str, module: dict, generated: Path) -> Path:
+    """Write a binary crate that includes the generated module and calls its smoke exports."""
+    crate = WORK_DIR / "harness" / name
+    (crate / "src").mkdir(parents=True, exist_ok=True)
+    shutil.copyfile(generated, crate / "src" / "module.rs")
+
+    (crate / "Cargo.toml").write_text(
+        "[package]\n"
+        f'name = "corpus-{name}"\n'
+        'version = "0.0.0"\n'
+        'edition = "2021"\n'
+        "publish = false\n\n"
+        "[dependencies]\n"
+        f'herkos-runtime = {{ path = "{RUNTIME}" }}\n\n'
+        "[workspace]\n"
+    )
+
+    host = module.get("host")
+    lines = [
+        "// Generated by scripts/corpus.py; do not edit.",
+        "// Only some exports are called; everything else must be lint-clean.",
+        "#[allow(dead_code)]",
+        "mod module;",
+    ]
+    if host:
+        lines.append(f'include!("{CORPUS_DIR / host}");')
+    # One function per call, so each instance's frame is released before the next.
+    calls = module.get("smoke", [])
+    for i, call in enumerate(calls):
+        args = list(call.get("args", []))
+        lines += ["", "#[inline(never)]", f"fn call_{i}() {{"]
+        if host:
+            lines.append("    let mut host = host();")
+            args.append("&mut host")
+        lines += [
+            '    let mut m = module::new().expect("instantiate");',
+            f'    println!("{call["export"]} => {{:?}}", m.{call["export"]}({", ".join(args)}));',
+            "}",
+        ]
+    lines += [
+        "",
+        "fn main() {",
+        "    std::thread::Builder::new()",
+        f"        .stack_size({_stack_size(generated)})",
+        "        .spawn(|| {",
+        *(f"            call_{i}();" for i in range(len(calls))),
+        "        })",
+        "        .unwrap()",
+        "        .join()",
+        "        .unwrap();",
+        "}",
+        "",
+    ]
+    (crate / "src" / "main.rs").write_text("\n".join(lines))
+    return crate
+
+
+def _stack_size(generated: Path) -> int:
+    """A thread stack large enough to hold the module, which `new()` returns by value."""
+    match = re.search(r"const MAX_PAGES: usize = (\d+);", generated.read_text())
+    pages = int(match.group(1)) if match else 0
+    return (64 << 20) + 4 * pages * 65536
+
+
+def _cargo_env() -> dict[str, str]:
+    return {
+        **os.environ,
+        "CARGO_TARGET_DIR": str(WORK_DIR / "build"),
+        "RUST_MIN_STACK": RUSTC_STACK,
+        # Incremental reuse would hide how long the generated code takes to compile.
+        "CARGO_INCREMENTAL": "0",
+    }
+
+
+def compile_harness(crate: Path) -> tuple[float, Path]:
+    """Build the harness; returns the time spent on the generated code in ms and the binary."""
+    env = _cargo_env()
+    manifest = ["--manifest-path", str(crate / "Cargo.toml")]
+    # Build the runtime first so the timing covers only the generated module.
+    run(["cargo", "build", *manifest, "-p", "herkos-runtime"], env=env)
+    start = time.perf_counter()
+    run(["cargo", "build", *manifest], env=env)
+    elapsed = (time.perf_counter() - start) * 1000
+    return elapsed, WORK_DIR / "build" / "debug" / f"corpus-{crate.name}"
+
+
+def clippy(crate: Path) -> None:
+    """Lint the harness, and with it the generated code, with warnings denied."""
+    forbid = [arg for lint in CLIPPY_FORBID for arg in ("-F", lint)]
+    run(
+        ["cargo", "clippy", "--manifest-path", str(crate / "Cargo.toml"),
+         "--", "-D", "warnings", *forbid],
+        env=_cargo_env(),
+    )
+
+
+def smoke(binary: Path, module: dict) -> list[str]:
+    """Run the harness and return one message per export whose result differs."""
+    output = run([str(binary)]).stdout
+    results = dict(line.split(" => ", 1) for line in output.splitlines() if " => " in line)
+    failures = []
+    for call in module.get("smoke", []):
+        got = results.get(call["export"], "<no output>")
+        if got != call["expect"]:
+            failures.append(f"{call['export']}: expected {call['expect']}, got {got}")
+    return failures
+
+
+# ---------------------------------------------------------------------------
+# Baselines
+# ---------------------------------------------------------------------------
+
+def compare(name: str, measured: dict, baseline: dict, tolerance: dict,
+            check_times: bool) -> list[str]:
+    """Return one message per metric that regressed beyond the tolerance."""
+    base = baseline.get(name)
+    if base is None:
+        return [f"no baseline for {name}; record one with --update"]
+    if base["wasm_bytes"] != measured["wasm_bytes"]:
+        print(f"  input changed ({base['wasm_bytes']} -> {measured['wasm_bytes']} bytes); "
+              "not comparing, record a new baseline with --update")
+        return []
+
+    regressions = []
+    limit = base["rust_bytes"] * (1 + tolerance["size_percent"] / 100)
+    if measured["rust_bytes"] > limit:
+        regressions.append(
+            f"generated size {base['rust_bytes']} -> {measured['rust_bytes']} bytes "
+            f"(+{_percent(base['rust_bytes'], measured['rust_bytes'])}%)"
+        )
+    for metric in ("transpile_ms", "compile_ms") if check_times else ():
+        old, new = base[metric], measured[metric]
+        limit = old * (1 + tolerance["time_percent"] / 100)
+        if new > limit and new - old > tolerance["time_floor_ms"]:
+            regressions.append(f"{metric} {old:.0f} -> {new:.0f} (+{_percent(old, new)}%)")
+    return regressions
+
+
+def _percent(old: float, new: float) -> str:
+    return f"{(new - old) * 100 / old:.1f}" if old else "inf"
+
+
+# ---------------------------------------------------------------------------
+# Commands
+# ---------------------------------------------------------------------------
+
+def cmd_run(only: list[str], update: bool, check_times: bool) -> None:
+    manifest = json.loads(MANIFEST.read_text())
+    baseline = json.loads(BASELINE.read_text()) if BASELINE.exists() else {}
+    modules = [m for m in manifest["modules"] if not only or m["name"] in only]
+    unknown = set(only) - {m["name"] for m in manifest["modules"]}
+    if unknown:
+        sys.exit(f"ERROR: not in {MANIFEST.name}: {', '.join(sorted(unknown))}")
+
+    build_herkos()
+    WORK_DIR.mkdir(parents=True, exist_ok=True)
+
+    rows = []
+    failed = False
+    for module in modules:
+        name = module["name"]
+        print(f"\n=== {name} ===")
+        try:
+            wasm = resolve_wasm(module)
+            generated = WORK_DIR / f"{name}.rs"
+            transpile_ms = transpile(wasm, module.get("flags", []), generated)
+            source = generated.read_text()
+            measured = {
+                "wasm_bytes": wasm.stat().st_size,
+                "rust_bytes": len(source.encode()),
+                "rust_lines": source.count("\n"),
+                "transpile_ms": round(transpile_ms),
+            }
+            print(f"  transpiled {measured['wasm_bytes']} -> {measured['rust_bytes']} bytes "
+                  f"in {transpile_ms:.0f} ms")
+            harness = write_harness(name, module, generated)
+            compile_ms, binary = compile_harness(harness)
+            measured["compile_ms"] = round(compile_ms)
+            print(f"  compiled in {compile_ms:.0f} ms")
+            clippy(harness)
+            print("  clippy clean")
+            problems = smoke(binary, module)
+            print(f"  smoke-ran {len(module.get('smoke', []))} export(s)")
+        except CorpusError as e:
+            print(f"  FAILED: {e}")
+            rows.append((name, "failed", None))
+            failed = True
+            continue
+
+        if update:
+            baseline[name] = measured
+        else:
+            problems += compare(name, measured, baseline, manifest["tolerance"], check_times)
+        for problem in problems:
+            print(f"  REGRESSION: {problem}")
+        failed |= bool(problems)
+        rows.append((name, "regressed" if problems else "ok", measured))
+
+    print("\n" + f"{'MODULE':<16}  {'STATUS':<10}  {'RUST BYTES':>12}  {'TRANSPILE':>10}  {'COMPILE':>10}")
+    print("─" * 66)
+    for name, status, m in rows:
+        if m is None:
+            print(f"{name:<16}  {status:<10}")
+        else:
+            print(f"{name:<16}  {status:<10}  {m['rust_bytes']:>12}  "
+                  f"{m['transpile_ms']:>8} ms  {m['compile_ms']:>7} ms")
+
+    if update:
+        BASELINE.write_text(json.dumps(baseline, indent=2, sort_keys=True) + "\n")
+        print(f"\nBaseline written to {BASELINE.relative_to(REPO_ROOT)}")
+    if failed:
+        sys.exit(1)
+
+
+def cmd_list() -> None:
+    manifest = json.loads(MANIFEST.read_text())
+    for module in manifest["modules"]:
+        if "cargo" in module:
+            source = f"corpus/{module['cargo']} (built)"
+        else:
+            present = (CORPUS_DIR / module["wasm"]).exists()
+            source = f"corpus/{module['wasm']} ({'present' if present else 'absent'})"
+        print(f"{module['name']:<16}  {source:<40}  {module['description']}")
+
+
+# ---------------------------------------------------------------------------
+# CLI
+# ---------------------------------------------------------------------------
+
+def main() -> None:
+    check_repo_root()
+
+    parser = argparse.ArgumentParser(
+        prog="scripts/corpus.py",
+        description="Real-world corpus regression harness.",
+        formatter_class=argparse.RawDescriptionHelpFormatter,
+        epilog="""
+examples:
+  scripts/corpus.py list
+  scripts/corpus.py run
+  scripts/corpus.py run regex
+  scripts/corpus.py run --update
+  scripts/corpus.py run --no-timing
+""",
+    )
+    sub = parser.add_subparsers(dest="subcommand", metavar="subcommand")
+    sub.required = True
+
+    p_run = sub.add_parser("run", help="Transpile, compile and smoke-run the corpus.")
+    p_run.add_argument("only", nargs="*", metavar="MODULE",
+                       help="Only run these modules (default: all).")
+    p_run.add_argument("--update", action="store_true",
+                       help="Record the measurements as the new baseline instead of comparing.")
+    p_run.add_argument("--no-timing", action="store_true",
+                       help="Only compare generated sizes, for machines other than the baseline's.")
+
+    sub.add_parser("list", help="Show the corpus modules and whether they are present.")
+
+    args = parser.parse_args()
+    if args.subcommand == "run":
+        cmd_run(args.only, args.update, not args.no_timing)
+    elif args.subcommand == "list":
+        cmd_list()
+
+
+if __name__ == "__main__":
+    main()